@@ -0,0 +1,274 @@
+//! Correlation of alerts that share a probable root cause
+//!
+//! A machine that loses its disk raises a disk alert, then collector, session
+//! and offline alerts that are all the same problem. The daemon calls
+//! [`correlate`] every cycle: open alerts on one machine that fired within
+//! `window_secs` of the previous one form a group, and the group's probable
+//! root is its most severe alert, ties going to the earliest match in
+//! `causal_order` and then to the earliest fired.
+//!
+//! Groups are recomputed from scratch each cycle, so membership and root are
+//! revisable: a later, more severe alert takes over as root, and an alert
+//! left alone once the others resolve drops out of the group. A group keeps
+//! the id it was first given for as long as any member carries it.
+
+use crate::{AlertError, escalation::parse_severity};
+use serde::{Deserialize, Serialize};
+use vc_config::CorrelationConfig;
+use vc_store::{CorrelationCandidate, VcStore};
+
+/// Open alerts on one machine that share a probable root cause
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorrelationGroup {
+    pub correlation_id: String,
+    pub machine_id: String,
+    pub root_id: i64,
+    pub root_rule_id: String,
+    /// Members other than the root, in firing order
+    pub child_ids: Vec<i64>,
+}
+
+/// What one correlation pass changed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorrelationReport {
+    pub groups: Vec<CorrelationGroup>,
+    /// Alerts whose group or root flag changed
+    pub changed: usize,
+    /// Groups whose root is a different alert than before
+    pub new_roots: Vec<String>,
+}
+
+/// Regroup open alerts and store each one's correlation id and root flag
+///
+/// # Errors
+///
+/// Returns [`AlertError::StoreError`] if reading or updating alerts fails.
+pub fn correlate(
+    store: &VcStore,
+    config: &CorrelationConfig,
+) -> Result<CorrelationReport, AlertError> {
+    let mut report = CorrelationReport::default();
+    if !config.enabled {
+        return Ok(report);
+    }
+
+    let candidates = store.correlation_candidates()?;
+    let groups = group_alerts(&candidates, config);
+
+    let mut grouped = Vec::new();
+    for group in &groups {
+        let previous_root = candidates
+            .iter()
+            .find(|c| {
+                c.correlation_root && c.correlation_id.as_ref() == Some(&group.correlation_id)
+            })
+            .map(|c| c.id);
+        if previous_root.is_some_and(|id| id != group.root_id) {
+            report.new_roots.push(group.correlation_id.clone());
+        }
+
+        let mut ids = vec![group.root_id];
+        ids.extend(&group.child_ids);
+        report.changed +=
+            store.set_alert_correlation(&ids, Some(&group.correlation_id), Some(group.root_id))?;
+        grouped.extend(ids);
+    }
+
+    // Alerts that used to be grouped but are now on their own
+    let ungrouped: Vec<i64> = candidates
+        .iter()
+        .filter(|c| c.correlation_id.is_some() && !grouped.contains(&c.id))
+        .map(|c| c.id)
+        .collect();
+    report.changed += store.set_alert_correlation(&ungrouped, None, None)?;
+
+    report.groups = groups;
+    Ok(report)
+}
+
+/// Cluster candidates (ordered by machine, then firing time, as
+/// [`VcStore::correlation_candidates`] returns them) into groups of two or
+/// more and pick each group's root
+#[must_use]
+pub fn group_alerts(
+    candidates: &[CorrelationCandidate],
+    config: &CorrelationConfig,
+) -> Vec<CorrelationGroup> {
+    let window = i64::try_from(config.window_secs).unwrap_or(i64::MAX);
+    let mut clusters: Vec<Vec<&CorrelationCandidate>> = Vec::new();
+    for candidate in candidates {
+        match clusters.last_mut() {
+            Some(cluster)
+                if cluster.last().is_some_and(|prev| {
+                    prev.machine_id == candidate.machine_id
+                        && (candidate.fired_at - prev.fired_at).num_seconds() <= window
+                }) =>
+            {
+                cluster.push(candidate);
+            }
+            _ => clusters.push(vec![candidate]),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .filter(|cluster| cluster.len() > 1)
+        .map(|cluster| {
+            let root = cluster
+                .iter()
+                .copied()
+                .min_by_key(|c| {
+                    (
+                        std::cmp::Reverse(parse_severity(&c.severity)),
+                        causal_rank(&c.rule_id, &config.causal_order),
+                        c.fired_at,
+                        c.id,
+                    )
+                })
+                .unwrap_or(cluster[0]);
+            CorrelationGroup {
+                correlation_id: group_id(&cluster),
+                machine_id: root.machine_id.clone(),
+                root_id: root.id,
+                root_rule_id: root.rule_id.clone(),
+                child_ids: cluster
+                    .iter()
+                    .filter(|c| c.id != root.id)
+                    .map(|c| c.id)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Position of the first `causal_order` fragment in `rule_id`; rules that
+/// match none come last
+fn causal_rank(rule_id: &str, causal_order: &[String]) -> usize {
+    causal_order
+        .iter()
+        .position(|fragment| rule_id.contains(fragment.as_str()))
+        .unwrap_or(causal_order.len())
+}
+
+/// The id most members already carry (the lowest on a tie), so a group keeps
+/// its id as it grows or changes root; otherwise one from its first alert
+fn group_id(cluster: &[&CorrelationCandidate]) -> String {
+    let mut existing: Vec<&str> = cluster
+        .iter()
+        .filter_map(|c| c.correlation_id.as_deref())
+        .collect();
+    existing.sort_unstable();
+    existing
+        .chunk_by(|a, b| a == b)
+        .max_by(|a, b| a.len().cmp(&b.len()).then_with(|| b[0].cmp(a[0])))
+        .map_or_else(
+            || format!("corr-{}-{}", cluster[0].machine_id, cluster[0].id),
+            |ids| ids[0].to_string(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeDelta, Utc};
+    use vc_store::FiredAlert;
+
+    fn candidate(
+        id: i64,
+        rule: &str,
+        machine: &str,
+        severity: &str,
+        mins: i64,
+    ) -> CorrelationCandidate {
+        CorrelationCandidate {
+            id,
+            rule_id: rule.to_string(),
+            machine_id: machine.to_string(),
+            severity: severity.to_string(),
+            fired_at: DateTime::<Utc>::from_timestamp(1_790_000_000, 0).unwrap()
+                + TimeDelta::minutes(mins),
+            correlation_id: None,
+            correlation_root: false,
+        }
+    }
+
+    #[test]
+    fn test_group_alerts_by_machine_and_window() {
+        let config = CorrelationConfig::default();
+        let candidates = vec![
+            candidate(1, "collector-stale:sysmoni", "orko", "warning", 0),
+            candidate(2, "disk-full", "orko", "warning", 3),
+            candidate(3, "session-stuck", "orko", "warning", 9),
+            // More than window_secs after the last one: on its own
+            candidate(4, "agent-stuck", "orko", "warning", 30),
+            candidate(5, "disk-full", "sydney", "warning", 1),
+        ];
+        let groups = group_alerts(&candidates, &config);
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        // Equal severity: the causal order puts disk first
+        assert_eq!(group.root_id, 2);
+        assert_eq!(group.child_ids, vec![1, 3]);
+        assert_eq!(group.correlation_id, "corr-orko-1");
+    }
+
+    #[test]
+    fn test_more_severe_alert_becomes_root_and_id_is_kept() {
+        let config = CorrelationConfig::default();
+        let mut candidates = vec![
+            candidate(1, "disk-full", "orko", "warning", 0),
+            candidate(2, "collector-stale:sysmoni", "orko", "warning", 1),
+            candidate(3, "machine-offline", "orko", "critical", 2),
+        ];
+        for c in &mut candidates[..2] {
+            c.correlation_id = Some("corr-orko-1".to_string());
+        }
+        candidates[0].correlation_root = true;
+
+        let groups = group_alerts(&candidates, &config);
+        assert_eq!(groups[0].root_id, 3);
+        assert_eq!(groups[0].correlation_id, "corr-orko-1");
+    }
+
+    #[test]
+    fn test_correlate_writes_and_revises_groups() {
+        let store = VcStore::open_memory().unwrap();
+        let fire = |rule: &str, severity: &str, fired_at: &str| {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: fired_at.to_string(),
+                    severity: severity.to_string(),
+                    title: rule.to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: Some("orko".to_string()),
+                })
+                .unwrap();
+        };
+        fire("disk-full", "warning", "2026-10-01T00:00:00Z");
+        fire("collector-stale:sysmoni", "warning", "2026-10-01T00:02:00Z");
+
+        let config = CorrelationConfig::default();
+        let report = correlate(&store, &config).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.changed, 2);
+        assert_eq!(store.correlated_children(1).unwrap(), vec![2]);
+
+        // Nothing new: nothing changes
+        assert_eq!(correlate(&store, &config).unwrap().changed, 0);
+
+        fire("machine-offline", "critical", "2026-10-01T00:05:00Z");
+        let report = correlate(&store, &config).unwrap();
+        assert_eq!(report.groups[0].root_id, 3);
+        assert_eq!(report.new_roots, vec!["corr-orko-1"]);
+        assert_eq!(store.correlated_children(3).unwrap(), vec![1, 2]);
+        assert!(store.correlated_children(1).unwrap().is_empty());
+
+        let disabled = CorrelationConfig {
+            enabled: false,
+            ..CorrelationConfig::default()
+        };
+        assert!(correlate(&store, &disabled).unwrap().groups.is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+//! Digest delivery: batch low-severity alerts per channel
+//!
+//! A channel with a [`DigestPolicy`] holds alerts below `min_severity` and
+//! sends them as one grouped [`AlertDigest`] when the flush interval elapses
+//! or the batch fills up. Critical alerts never wait.
+
+use crate::{Alert, Severity};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// When a channel batches instead of delivering immediately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestPolicy {
+    /// Alerts below this severity are batched
+    pub min_severity: Severity,
+    /// How long the oldest pending alert may wait
+    pub flush_interval: Duration,
+    /// Flush as soon as this many alerts are pending
+    pub max_batch_size: usize,
+}
+
+impl DigestPolicy {
+    /// Whether an alert of `severity` goes into the digest
+    #[must_use]
+    pub fn should_batch(&self, severity: Severity) -> bool {
+        severity != Severity::Critical && severity < self.min_severity
+    }
+}
+
+/// Alerts of one rule on one machine within a digest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestGroup {
+    pub machine_id: Option<String>,
+    pub rule_id: String,
+    pub severity: Severity,
+    pub count: usize,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+    /// `alert_history` ids, for linking back
+    pub alert_ids: Vec<i64>,
+}
+
+/// Grouped summary of a batch of alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertDigest {
+    pub channel: String,
+    pub alert_count: usize,
+    pub max_severity: Severity,
+    pub first_at: DateTime<Utc>,
+    pub last_at: DateTime<Utc>,
+    pub groups: Vec<DigestGroup>,
+}
+
+impl AlertDigest {
+    /// Group `alerts` by machine and rule; `None` for an empty batch
+    #[must_use]
+    pub fn from_alerts(channel: &str, alerts: &[Alert]) -> Option<Self> {
+        let first = alerts.first()?;
+        let mut digest = Self {
+            channel: channel.to_string(),
+            alert_count: alerts.len(),
+            max_severity: first.severity,
+            first_at: first.fired_at,
+            last_at: first.fired_at,
+            groups: Vec::new(),
+        };
+
+        for alert in alerts {
+            digest.max_severity = digest.max_severity.max(alert.severity);
+            digest.first_at = digest.first_at.min(alert.fired_at);
+            digest.last_at = digest.last_at.max(alert.fired_at);
+
+            let index = if let Some(index) = digest
+                .groups
+                .iter()
+                .position(|g| g.machine_id == alert.machine_id && g.rule_id == alert.rule_id)
+            {
+                index
+            } else {
+                digest.groups.push(DigestGroup {
+                    machine_id: alert.machine_id.clone(),
+                    rule_id: alert.rule_id.clone(),
+                    severity: alert.severity,
+                    count: 0,
+                    first_at: alert.fired_at,
+                    last_at: alert.fired_at,
+                    alert_ids: Vec::new(),
+                });
+                digest.groups.len() - 1
+            };
+            let group = &mut digest.groups[index];
+            group.count += 1;
+            group.severity = group.severity.max(alert.severity);
+            group.first_at = group.first_at.min(alert.fired_at);
+            group.last_at = group.last_at.max(alert.fired_at);
+            group.alert_ids.extend(alert.id);
+        }
+
+        digest
+            .groups
+            .sort_by(|a, b| b.count.cmp(&a.count).then(a.rule_id.cmp(&b.rule_id)));
+        Some(digest)
+    }
+
+    /// Every `alert_history` id the digest covers
+    #[must_use]
+    pub fn alert_ids(&self) -> Vec<i64> {
+        self.groups
+            .iter()
+            .flat_map(|g| g.alert_ids.iter().copied())
+            .collect()
+    }
+
+    /// One alert summarizing the digest, for channels that only understand
+    /// single alerts
+    #[must_use]
+    pub fn to_summary_alert(&self) -> Alert {
+        let lines: Vec<String> = self
+            .groups
+            .iter()
+            .map(|g| {
+                format!(
+                    "{} x{} on {} ({} .. {})",
+                    g.rule_id,
+                    g.count,
+                    g.machine_id.as_deref().unwrap_or("fleet"),
+                    g.first_at.format("%H:%M"),
+                    g.last_at.format("%H:%M"),
+                )
+            })
+            .collect();
+
+        Alert {
+            id: None,
+            rule_id: "digest".to_string(),
+            fired_at: self.last_at,
+            severity: self.max_severity,
+            title: format!("Alert digest: {} alerts", self.alert_count),
+            message: lines.join("\n"),
+            machine_id: None,
+            context: serde_json::to_value(self).unwrap_or_default(),
+        }
+    }
+}
+
+/// Pending alerts for one digest channel
+#[derive(Debug)]
+pub struct DigestBuffer {
+    policy: DigestPolicy,
+    pending: Vec<Alert>,
+    opened_at: Option<Instant>,
+}
+
+impl DigestBuffer {
+    #[must_use]
+    pub fn new(policy: DigestPolicy) -> Self {
+        Self {
+            policy,
+            pending: Vec::new(),
+            opened_at: None,
+        }
+    }
+
+    #[must_use]
+    pub fn policy(&self) -> &DigestPolicy {
+        &self.policy
+    }
+
+    #[must_use]
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Queue an alert; returns the whole batch once it reaches the size cap
+    pub fn push(&mut self, alert: Alert, now: Instant) -> Option<Vec<Alert>> {
+        self.opened_at.get_or_insert(now);
+        self.pending.push(alert);
+        (self.pending.len() >= self.policy.max_batch_size).then(|| self.take())
+    }
+
+    /// Take the batch if its oldest alert has waited the flush interval
+    pub fn take_due(&mut self, now: Instant) -> Option<Vec<Alert>> {
+        let opened_at = self.opened_at?;
+        (now.duration_since(opened_at) >= self.policy.flush_interval).then(|| self.take())
+    }
+
+    /// Take whatever is pending (used on shutdown)
+    pub fn take(&mut self) -> Vec<Alert> {
+        self.opened_at = None;
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn policy() -> DigestPolicy {
+        DigestPolicy {
+            min_severity: Severity::Critical,
+            flush_interval: Duration::from_secs(1800),
+            max_batch_size: 3,
+        }
+    }
+
+    fn alert(id: i64, rule: &str, machine: &str, severity: Severity, minutes: i64) -> Alert {
+        Alert {
+            id: Some(id),
+            rule_id: rule.to_string(),
+            fired_at: DateTime::from_timestamp(1_800_000_000, 0).unwrap()
+                + TimeDelta::minutes(minutes),
+            severity,
+            title: rule.to_string(),
+            message: String::new(),
+            machine_id: Some(machine.to_string()),
+            context: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_critical_always_bypasses() {
+        let policy = policy();
+        assert!(policy.should_batch(Severity::Info));
+        assert!(policy.should_batch(Severity::Warning));
+        assert!(!policy.should_batch(Severity::Critical));
+
+        let warning_only = DigestPolicy {
+            min_severity: Severity::Warning,
+            ..policy
+        };
+        assert!(warning_only.should_batch(Severity::Info));
+        assert!(!warning_only.should_batch(Severity::Warning));
+    }
+
+    #[test]
+    fn test_buffer_flushes_on_size_and_interval() {
+        let mut buffer = DigestBuffer::new(policy());
+        let start = Instant::now();
+
+        assert!(
+            buffer
+                .push(alert(1, "r", "m", Severity::Info, 0), start)
+                .is_none()
+        );
+        assert!(buffer.take_due(start).is_none());
+        assert_eq!(
+            buffer
+                .take_due(start + Duration::from_secs(1800))
+                .map(|b| b.len()),
+            Some(1)
+        );
+        assert_eq!(buffer.pending_count(), 0);
+
+        buffer.push(alert(2, "r", "m", Severity::Info, 0), start);
+        buffer.push(alert(3, "r", "m", Severity::Info, 0), start);
+        let full = buffer.push(alert(4, "r", "m", Severity::Info, 0), start);
+        assert_eq!(full.map(|b| b.len()), Some(3));
+        assert!(buffer.take_due(start + Duration::from_secs(3600)).is_none());
+    }
+
+    #[test]
+    fn test_digest_groups_by_machine_and_rule() {
+        let alerts = vec![
+            alert(1, "disk", "a", Severity::Info, 0),
+            alert(2, "disk", "a", Severity::Warning, 10),
+            alert(3, "disk", "b", Severity::Info, 5),
+            alert(4, "cpu", "a", Severity::Info, 20),
+        ];
+        let digest = AlertDigest::from_alerts("slack", &alerts).unwrap();
+
+        assert_eq!(digest.alert_count, 4);
+        assert_eq!(digest.max_severity, Severity::Warning);
+        assert_eq!(digest.groups.len(), 3);
+        assert_eq!(digest.groups[0].rule_id, "disk");
+        assert_eq!(digest.groups[0].machine_id.as_deref(), Some("a"));
+        assert_eq!(digest.groups[0].count, 2);
+        assert_eq!(digest.groups[0].alert_ids, vec![1, 2]);
+        assert_eq!(digest.last_at - digest.first_at, TimeDelta::minutes(20));
+
+        let mut ids = digest.alert_ids();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+
+        let summary = digest.to_summary_alert();
+        assert_eq!(summary.severity, Severity::Warning);
+        assert!(summary.title.contains('4'));
+        assert!(summary.message.contains("disk x2 on a"));
+    }
+
+    #[test]
+    fn test_empty_batch_has_no_digest() {
+        assert!(AlertDigest::from_alerts("webhook", &[]).is_none());
+    }
+}
@@ -0,0 +1,528 @@
+//! Email delivery over SMTP
+//!
+//! [`EmailChannel`] renders alerts as compact plaintext mail and hands them to
+//! a [`MailTransport`]. The default [`CurlTransport`] drives `curl`, which
+//! speaks SMTP with STARTTLS or implicit TLS; credentials reach it through a
+//! private config file, never the command line. Failed sends are retried with
+//! exponential backoff before the channel reports an error.
+
+use crate::{Alert, AlertChannel, AlertError, Severity};
+use asupersync::Cx;
+use asupersync::process::{Command as ProcessCommand, Stdio};
+use asupersync::time::wall_now;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Transport security for the SMTP connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain SMTP
+    None,
+    /// Upgrade with STARTTLS; fail rather than send in the clear
+    StartTls,
+    /// Implicit TLS (`smtps`)
+    Tls,
+}
+
+impl SmtpTls {
+    /// Parse a config value (`none`, `starttls`, `tls`)
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "none" => Some(SmtpTls::None),
+            "starttls" => Some(SmtpTls::StartTls),
+            "tls" => Some(SmtpTls::Tls),
+            _ => None,
+        }
+    }
+}
+
+/// Sender and recipients of a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// Hands a rendered RFC 5322 message to an SMTP server
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, cx: &Cx, envelope: &Envelope, message: &str) -> Result<(), String>;
+}
+
+/// SMTP via the `curl` binary
+pub struct CurlTransport {
+    server: String,
+    port: u16,
+    tls: SmtpTls,
+    credentials: Option<(String, String)>,
+    timeout: Duration,
+}
+
+impl CurlTransport {
+    #[must_use]
+    pub fn new(server: impl Into<String>, port: u16, tls: SmtpTls, timeout: Duration) -> Self {
+        Self {
+            server: server.into(),
+            port,
+            tls,
+            credentials: None,
+            timeout,
+        }
+    }
+
+    #[must_use]
+    pub fn with_credentials(mut self, username: impl Into<String>, password: String) -> Self {
+        self.credentials = Some((username.into(), password));
+        self
+    }
+
+    /// curl config for one send; holds the password, so it only ever lives
+    /// in a private temp directory
+    fn curlrc(&self, envelope: &Envelope, message_path: &Path) -> String {
+        let scheme = if self.tls == SmtpTls::Tls {
+            "smtps"
+        } else {
+            "smtp"
+        };
+        let mut rc = String::new();
+        let _ = writeln!(
+            rc,
+            "url = {}",
+            curlrc_quote(&format!("{scheme}://{}:{}", self.server, self.port))
+        );
+        let _ = writeln!(rc, "mail-from = {}", curlrc_quote(&envelope.from));
+        for to in &envelope.to {
+            let _ = writeln!(rc, "mail-rcpt = {}", curlrc_quote(to));
+        }
+        let _ = writeln!(
+            rc,
+            "upload-file = {}",
+            curlrc_quote(&message_path.to_string_lossy())
+        );
+        if let Some((user, password)) = &self.credentials {
+            let _ = writeln!(rc, "user = {}", curlrc_quote(&format!("{user}:{password}")));
+        }
+        if self.tls == SmtpTls::StartTls {
+            rc.push_str("ssl-reqd\n");
+        }
+        let _ = writeln!(rc, "max-time = {}", self.timeout.as_secs().max(1));
+        rc.push_str("silent\nshow-error\n");
+        rc
+    }
+}
+
+#[async_trait]
+impl MailTransport for CurlTransport {
+    async fn send(&self, cx: &Cx, envelope: &Envelope, message: &str) -> Result<(), String> {
+        let dir = private_temp_dir().map_err(|e| format!("failed to stage message: {e}"))?;
+        let message_path = dir.join("message.eml");
+        let rc_path = dir.join("curlrc");
+        let staged = std::fs::write(&message_path, message)
+            .and_then(|()| std::fs::write(&rc_path, self.curlrc(envelope, &message_path)));
+
+        let result = match staged {
+            Ok(()) => self.run_curl(cx, &rc_path).await,
+            Err(e) => Err(format!("failed to stage message: {e}")),
+        };
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+}
+
+impl CurlTransport {
+    async fn run_curl(&self, cx: &Cx, rc_path: &Path) -> Result<(), String> {
+        let child = ProcessCommand::new("curl")
+            .arg("--config")
+            .arg(rc_path)
+            .stdout(Stdio::Pipe)
+            .stderr(Stdio::Pipe)
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("failed to run curl: {e}"))?;
+
+        // curl enforces max-time itself; this only guards against a hung child
+        let deadline = self.timeout + Duration::from_secs(5);
+        match asupersync::time::timeout(wall_now(), deadline, child.wait_with_output_async(cx))
+            .await
+        {
+            Ok(Ok(output)) if output.status.success() => Ok(()),
+            Ok(Ok(output)) => Err(format!(
+                "curl exited with {}: {}",
+                output.status.code().unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+            Ok(Err(e)) => Err(format!("curl failed: {e}")),
+            Err(_) => Err(format!("SMTP send timed out after {deadline:?}")),
+        }
+    }
+}
+
+/// Quote a value for a curl config file
+fn curlrc_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A fresh directory only the current user can read
+fn private_temp_dir() -> std::io::Result<PathBuf> {
+    let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+    let dir = std::env::temp_dir().join(format!("vc-smtp-{}-{nanos}", std::process::id()));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+/// Email sink for alerts, digests and reports
+pub struct EmailChannel {
+    transport: Box<dyn MailTransport>,
+    envelope: Envelope,
+    min_severity: Severity,
+    web_url: Option<String>,
+    max_attempts: u32,
+    retry_backoff: Duration,
+}
+
+impl EmailChannel {
+    #[must_use]
+    pub fn new(transport: Box<dyn MailTransport>, envelope: Envelope) -> Self {
+        Self {
+            transport,
+            envelope,
+            min_severity: Severity::Critical,
+            web_url: None,
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(5),
+        }
+    }
+
+    /// Build the channel from `[alerts.email]`, reading the password now so a
+    /// broken secret shows up before the first alert does
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlertError::InvalidConfig`] if the TLS mode is unknown or the
+    /// password cannot be read.
+    pub fn from_config(config: &vc_config::EmailConfig) -> Result<Self, AlertError> {
+        let tls = SmtpTls::parse(&config.tls).ok_or_else(|| {
+            AlertError::InvalidConfig(format!("unknown alerts.email.tls '{}'", config.tls))
+        })?;
+        let mut transport = CurlTransport::new(
+            config.server.clone(),
+            config.port(),
+            tls,
+            Duration::from_secs(config.timeout_secs),
+        );
+        if let Some(username) = &config.username {
+            let password = config
+                .resolve_password()
+                .map_err(|e| AlertError::InvalidConfig(e.to_string()))?
+                .unwrap_or_default();
+            transport = transport.with_credentials(username.clone(), password);
+        }
+
+        let mut channel = Self::new(
+            Box::new(transport),
+            Envelope {
+                from: config.from.clone(),
+                to: config.to.clone(),
+            },
+        );
+        channel.min_severity = parse_min_severity(&config.min_severity);
+        channel.web_url = config.web_url.clone();
+        channel.max_attempts = config.max_attempts.max(1);
+        channel.retry_backoff = Duration::from_secs(config.retry_backoff_secs);
+        Ok(channel)
+    }
+
+    #[must_use]
+    pub fn with_min_severity(mut self, min_severity: Severity) -> Self {
+        self.min_severity = min_severity;
+        self
+    }
+
+    #[must_use]
+    pub fn with_web_url(mut self, web_url: impl Into<String>) -> Self {
+        self.web_url = Some(web_url.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_retry(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Send a plaintext message to the configured recipients, retrying with
+    /// exponential backoff
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlertError::DeliveryFailed`] once every attempt has failed.
+    pub async fn send(&self, cx: &Cx, subject: &str, body: &str) -> Result<(), AlertError> {
+        let message = render_message(&self.envelope, subject, body, Utc::now());
+        let mut attempt = 1;
+        loop {
+            match self.transport.send(cx, &self.envelope, &message).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_attempts => {
+                    return Err(AlertError::DeliveryFailed(format!(
+                        "SMTP delivery failed after {attempt} attempt(s): {e}"
+                    )));
+                }
+                Err(e) => {
+                    let delay = self.retry_backoff * 2_u32.saturating_pow(attempt - 1);
+                    tracing::debug!(attempt, error = %e, "retrying email delivery");
+                    if !delay.is_zero() {
+                        asupersync::time::sleep(wall_now(), delay).await;
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AlertChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn deliver(&self, cx: &Cx, alert: &Alert) -> Result<(), AlertError> {
+        if alert.severity < self.min_severity {
+            return Ok(());
+        }
+        self.send(
+            cx,
+            &alert_subject(alert),
+            &alert_body(alert, self.web_url.as_deref()),
+        )
+        .await
+    }
+
+    async fn deliver_rendered(
+        &self,
+        cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        if alert.severity < self.min_severity {
+            return Ok(());
+        }
+        self.send(cx, &alert_subject(alert), message).await
+    }
+}
+
+fn parse_min_severity(value: &str) -> Severity {
+    match value.to_lowercase().as_str() {
+        "info" => Severity::Info,
+        "warning" => Severity::Warning,
+        _ => Severity::Critical,
+    }
+}
+
+fn severity_label(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "INFO",
+        Severity::Warning => "WARNING",
+        Severity::Critical => "CRITICAL",
+    }
+}
+
+/// Compact subject: severity, machine and rule
+#[must_use]
+pub fn alert_subject(alert: &Alert) -> String {
+    let machine = alert.machine_id.as_deref().unwrap_or("fleet");
+    format!(
+        "[vc] {} {machine}: {}",
+        severity_label(alert.severity),
+        alert.rule_id
+    )
+}
+
+/// Plaintext body, linking to the web UI when `web_url` is set
+#[must_use]
+pub fn alert_body(alert: &Alert, web_url: Option<&str>) -> String {
+    let mut body = format!("{}\n\n{}\n\n", alert.title, alert.message);
+    let _ = writeln!(body, "Severity: {}", severity_label(alert.severity));
+    let _ = writeln!(body, "Rule:     {}", alert.rule_id);
+    let _ = writeln!(
+        body,
+        "Machine:  {}",
+        alert.machine_id.as_deref().unwrap_or("fleet")
+    );
+    let _ = writeln!(
+        body,
+        "Fired:    {}",
+        alert.fired_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    if let Some(url) = web_url {
+        let _ = write!(body, "\n{}/alerts\n", url.trim_end_matches('/'));
+    }
+    body
+}
+
+/// Full message with headers; lines end in CRLF as SMTP requires
+#[must_use]
+pub fn render_message(
+    envelope: &Envelope,
+    subject: &str,
+    body: &str,
+    date: DateTime<Utc>,
+) -> String {
+    let header = |value: &str| value.replace(['\r', '\n'], " ");
+    let mut message = String::new();
+    let _ = write!(message, "From: {}\r\n", header(&envelope.from));
+    let _ = write!(message, "To: {}\r\n", header(&envelope.to.join(", ")));
+    let _ = write!(message, "Subject: {}\r\n", header(subject));
+    let _ = write!(message, "Date: {}\r\n", date.to_rfc2822());
+    message.push_str("MIME-Version: 1.0\r\n");
+    message.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+    message.push_str("Content-Transfer-Encoding: 8bit\r\n\r\n");
+    for line in body.lines() {
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails the first `failures` sends, then accepts
+    struct FlakyTransport {
+        failures: u32,
+        calls: Arc<AtomicU32>,
+    }
+
+    #[async_trait]
+    impl MailTransport for FlakyTransport {
+        async fn send(&self, _cx: &Cx, _envelope: &Envelope, _message: &str) -> Result<(), String> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                Err("421 try again later".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn channel(failures: u32) -> (EmailChannel, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let transport = FlakyTransport {
+            failures,
+            calls: Arc::clone(&calls),
+        };
+        let channel = EmailChannel::new(
+            Box::new(transport),
+            Envelope {
+                from: "vc@example.com".to_string(),
+                to: vec!["ops@example.com".to_string()],
+            },
+        )
+        .with_retry(3, Duration::ZERO);
+        (channel, calls)
+    }
+
+    fn alert(severity: Severity) -> Alert {
+        Alert {
+            id: Some(7),
+            rule_id: "disk-critical".to_string(),
+            fired_at: Utc::now(),
+            severity,
+            title: "Disk almost full".to_string(),
+            message: "/ is 97% full".to_string(),
+            machine_id: Some("orko".to_string()),
+            context: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_subject_and_body() {
+        let alert = alert(Severity::Critical);
+        assert_eq!(alert_subject(&alert), "[vc] CRITICAL orko: disk-critical");
+
+        let body = alert_body(&alert, Some("https://vc.example.com/"));
+        assert!(body.starts_with("Disk almost full\n\n/ is 97% full"));
+        assert!(body.contains("Machine:  orko"));
+        assert!(body.ends_with("https://vc.example.com/alerts\n"));
+        assert!(!alert_body(&alert, None).contains("http"));
+    }
+
+    #[test]
+    fn test_render_message_headers() {
+        let envelope = Envelope {
+            from: "vc@example.com".to_string(),
+            to: vec!["a@example.com".to_string(), "b@example.com".to_string()],
+        };
+        let message = render_message(&envelope, "hi\r\nBcc: x@evil", "one\ntwo", Utc::now());
+        assert!(message.contains("To: a@example.com, b@example.com\r\n"));
+        assert!(message.contains("Subject: hi  Bcc: x@evil\r\n"));
+        assert!(message.ends_with("\r\n\r\none\r\ntwo\r\n"));
+    }
+
+    #[test]
+    fn test_curlrc_keeps_secrets_quoted() {
+        let transport = CurlTransport::new(
+            "smtp.example.com",
+            587,
+            SmtpTls::StartTls,
+            Duration::from_secs(30),
+        )
+        .with_credentials("vc", "pa\"ss".to_string());
+        let envelope = Envelope {
+            from: "vc@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+        };
+        let rc = transport.curlrc(&envelope, Path::new("/tmp/m.eml"));
+        assert!(rc.contains("url = \"smtp://smtp.example.com:587\"\n"));
+        assert!(rc.contains("user = \"vc:pa\\\"ss\"\n"));
+        assert!(rc.contains("ssl-reqd\n"));
+        assert_eq!(SmtpTls::parse("TLS"), Some(SmtpTls::Tls));
+        assert_eq!(SmtpTls::parse("ssl"), None);
+    }
+
+    #[test]
+    fn test_retries_until_delivered() {
+        let (channel, calls) = channel(2);
+        let cx = Cx::for_testing();
+        futures::executor::block_on(async {
+            channel
+                .deliver(&cx, &alert(Severity::Critical))
+                .await
+                .unwrap();
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (channel, calls) = channel(5);
+        let cx = Cx::for_testing();
+        let err = futures::executor::block_on(channel.send(&cx, "s", "b")).unwrap_err();
+        assert!(err.to_string().contains("after 3 attempt(s)"));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_below_min_severity_is_skipped() {
+        let (channel, calls) = channel(0);
+        let cx = Cx::for_testing();
+        futures::executor::block_on(async {
+            channel
+                .deliver(&cx, &alert(Severity::Warning))
+                .await
+                .unwrap();
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
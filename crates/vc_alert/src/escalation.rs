@@ -0,0 +1,526 @@
+//! Time-based escalation of alerts left open and unacknowledged
+//!
+//! The daemon calls [`escalate`] every cycle with the `[[alerts.escalation]]`
+//! policies. Escalation works on alert groups (one rule on one machine), not
+//! on single occurrences: a group follows the first policy that matches it,
+//! keeps that policy once escalated, and reaches level N when its oldest open
+//! occurrence is as old as step N's `after_secs`.
+//!
+//! Each level applies once. Its level is stamped on the group's alerts with a
+//! conditional update before the step runs, so a repeated cycle (or a second
+//! process) finds nothing to do. Acknowledged and resolved alerts drop out of
+//! their group, which stops escalation for them.
+//!
+//! Severity bumps and incidents are written here. A `notify` step comes back
+//! as [`EscalationAction::Notify`] for the caller to deliver, since the sinks
+//! belong to the daemon.
+
+use crate::{Alert, AlertError, Severity};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use vc_config::{EscalationPolicy, EscalationStep};
+use vc_store::{AuditEvent, AuditEventType, AuditResult, OpenAlertGroup, VcStore};
+
+/// What one escalation level did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EscalationAction {
+    BumpSeverity { from: String, to: String },
+    Notify { sink: String },
+    OpenIncident { incident_id: String },
+}
+
+impl EscalationAction {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BumpSeverity { .. } => "bump_severity",
+            Self::Notify { .. } => "notify",
+            Self::OpenIncident { .. } => "open_incident",
+        }
+    }
+}
+
+/// An alert group reaching a new escalation level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Escalation {
+    pub group_key: String,
+    pub rule_id: String,
+    pub machine_id: Option<String>,
+    pub title: String,
+    /// Severity after this level
+    pub severity: String,
+    pub policy: String,
+    pub level: u32,
+    /// Open occurrences in the group, oldest first
+    pub alert_ids: Vec<i64>,
+    pub first_fired_at: DateTime<Utc>,
+    pub action: EscalationAction,
+}
+
+impl Escalation {
+    /// The alert a `notify` step delivers
+    #[must_use]
+    pub fn to_alert(&self) -> Alert {
+        Alert {
+            id: self.alert_ids.first().copied(),
+            rule_id: self.rule_id.clone(),
+            fired_at: self.first_fired_at,
+            severity: parse_severity(&self.severity),
+            title: format!("Escalated (level {}): {}", self.level, self.title),
+            message: format!(
+                "{} open alert(s) in {} unacknowledged since {}; escalated by policy {}",
+                self.alert_ids.len(),
+                self.group_key,
+                self.first_fired_at
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                self.policy
+            ),
+            machine_id: self.machine_id.clone(),
+            context: serde_json::json!({
+                "escalation_level": self.level,
+                "escalation_policy": self.policy,
+                "alert_ids": self.alert_ids,
+            }),
+        }
+    }
+
+    fn audit_event(&self, result: AuditResult, error: Option<&str>) -> AuditEvent {
+        let event = AuditEvent::new(
+            AuditEventType::AlertEscalation,
+            "daemon",
+            format!("alert_escalate_{}", self.action.as_str()),
+            result,
+            serde_json::json!({
+                "group": self.group_key,
+                "policy": self.policy,
+                "level": self.level,
+                "alert_ids": self.alert_ids,
+                "step": self.action,
+                "error": error,
+            }),
+        );
+        match &self.machine_id {
+            Some(machine) => event.with_machine_id(machine.clone()),
+            None => event,
+        }
+    }
+}
+
+/// First policy that applies to `group`: the one it already escalated
+/// under, or else the first whose filters all match
+#[must_use]
+pub fn policy_for<'a>(
+    policies: &'a [EscalationPolicy],
+    group: &OpenAlertGroup,
+) -> Option<&'a EscalationPolicy> {
+    if let Some(name) = &group.escalation_policy {
+        return policies.iter().find(|policy| &policy.name == name);
+    }
+    policies.iter().find(|policy| policy_matches(policy, group))
+}
+
+fn policy_matches(policy: &EscalationPolicy, group: &OpenAlertGroup) -> bool {
+    let type_matches = policy.alert_type.as_deref().is_none_or(|alert_type| {
+        group.rule_id == alert_type
+            || group
+                .rule_id
+                .split_once(':')
+                .is_some_and(|(prefix, _)| prefix == alert_type)
+    });
+    let group_matches = policy
+        .group
+        .as_deref()
+        .is_none_or(|key| key == group.group_key());
+    let severity_matches = policy
+        .severity
+        .as_deref()
+        .is_none_or(|severity| severity.eq_ignore_ascii_case(&group.severity));
+    type_matches && group_matches && severity_matches
+}
+
+/// Apply every escalation level that open, unacknowledged alert groups have
+/// reached by `now`, and return what was done.
+///
+/// Each level is recorded on the group's alerts and in the audit log. A step
+/// that fails (an incident that cannot be created) is audited as a failure
+/// and not retried; the level still counts as reached.
+///
+/// # Errors
+///
+/// Returns [`AlertError::StoreError`] if the groups cannot be read or a level
+/// cannot be recorded.
+pub fn escalate(
+    store: &VcStore,
+    policies: &[EscalationPolicy],
+    now: DateTime<Utc>,
+) -> Result<Vec<Escalation>, AlertError> {
+    if policies.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let stamp = now.to_rfc3339_opts(SecondsFormat::Micros, true);
+    let mut escalations = Vec::new();
+    for group in store.open_alert_groups()? {
+        // Occurrences that fired after the group escalated join at its level
+        if let Some(policy) = &group.escalation_policy
+            && !group.behind_ids.is_empty()
+        {
+            store.record_alert_escalation(
+                &group.behind_ids,
+                group.escalation_level,
+                policy,
+                group.escalated_at.as_deref().unwrap_or(&stamp),
+                None,
+            )?;
+        }
+
+        let Some(policy) = policy_for(policies, &group) else {
+            continue;
+        };
+        let age = u64::try_from((now - group.first_fired_at).num_seconds()).unwrap_or(0);
+        let mut severity = group.severity.clone();
+        for (index, step) in policy.steps.iter().enumerate() {
+            let level = u32::try_from(index + 1).unwrap_or(u32::MAX);
+            if step.after_secs > age {
+                break;
+            }
+            if level <= group.escalation_level {
+                continue;
+            }
+
+            let bumped = (step.action == "bump_severity").then(|| bump_severity(&severity));
+            let recorded = store.record_alert_escalation(
+                &group.alert_ids,
+                level,
+                &policy.name,
+                &stamp,
+                bumped.as_deref(),
+            )?;
+            if recorded == 0 {
+                // Another process got to this level first
+                break;
+            }
+
+            let from = severity.clone();
+            if let Some(bumped) = bumped {
+                severity = bumped;
+            }
+            let (action, error) = run_step(store, step, &group, &from, &severity, level);
+            let escalation = Escalation {
+                group_key: group.group_key(),
+                rule_id: group.rule_id.clone(),
+                machine_id: group.machine_id.clone(),
+                title: group.title.clone(),
+                severity: severity.clone(),
+                policy: policy.name.clone(),
+                level,
+                alert_ids: group.alert_ids.clone(),
+                first_fired_at: group.first_fired_at,
+                action,
+            };
+
+            let result = if error.is_some() {
+                AuditResult::Failure
+            } else {
+                AuditResult::Success
+            };
+            store.insert_audit_event(&escalation.audit_event(result, error.as_deref()))?;
+            match error {
+                Some(error) => tracing::warn!(
+                    group = %escalation.group_key,
+                    level,
+                    error = %error,
+                    "alert escalation step failed"
+                ),
+                None => {
+                    tracing::info!(
+                        group = %escalation.group_key,
+                        level,
+                        action = escalation.action.as_str(),
+                        "alert group escalated"
+                    );
+                    escalations.push(escalation);
+                }
+            }
+        }
+    }
+    Ok(escalations)
+}
+
+/// Carry out one step; the error, if any, is for the audit log
+fn run_step(
+    store: &VcStore,
+    step: &EscalationStep,
+    group: &OpenAlertGroup,
+    from: &str,
+    severity: &str,
+    level: u32,
+) -> (EscalationAction, Option<String>) {
+    match step.action.as_str() {
+        "bump_severity" => (
+            EscalationAction::BumpSeverity {
+                from: from.to_string(),
+                to: severity.to_string(),
+            },
+            None,
+        ),
+        "notify" => (
+            EscalationAction::Notify {
+                sink: step.sink.clone().unwrap_or_default(),
+            },
+            None,
+        ),
+        _ => {
+            let incident_id = incident_id(group);
+            let error = open_incident(store, &incident_id, group, severity, level)
+                .err()
+                .map(|e| e.to_string());
+            (EscalationAction::OpenIncident { incident_id }, error)
+        }
+    }
+}
+
+/// One incident per group, named after its oldest occurrence, so a repeated
+/// step reuses it
+fn incident_id(group: &OpenAlertGroup) -> String {
+    format!(
+        "inc-esc-{}",
+        group.alert_ids.first().copied().unwrap_or_default()
+    )
+}
+
+fn open_incident(
+    store: &VcStore,
+    incident_id: &str,
+    group: &OpenAlertGroup,
+    severity: &str,
+    level: u32,
+) -> Result<(), vc_store::StoreError> {
+    let description = format!(
+        "{} unacknowledged since {}",
+        group.group_key(),
+        group
+            .first_fired_at
+            .to_rfc3339_opts(SecondsFormat::Secs, true)
+    );
+    if store.get_incident(incident_id)?.is_none() {
+        store.create_incident(
+            incident_id,
+            &format!("Escalated: {}", group.title),
+            severity,
+            Some(&description),
+        )?;
+    }
+    store.add_incident_timeline_event(
+        incident_id,
+        "alert_escalated",
+        "escalation",
+        &format!("Escalation level {level}: {description}"),
+        Some(&serde_json::json!({ "alert_ids": group.alert_ids }).to_string()),
+    )?;
+    Ok(())
+}
+
+/// Stored severities are lowercase; anything unknown counts as a warning
+pub(crate) fn parse_severity(value: &str) -> Severity {
+    match value.to_lowercase().as_str() {
+        "info" => Severity::Info,
+        "critical" => Severity::Critical,
+        _ => Severity::Warning,
+    }
+}
+
+/// One severity up: info becomes warning, everything else critical
+fn bump_severity(value: &str) -> String {
+    match parse_severity(value) {
+        Severity::Info => "warning",
+        Severity::Warning | Severity::Critical => "critical",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use vc_store::FiredAlert;
+
+    fn step(after_secs: u64, action: &str, sink: Option<&str>) -> EscalationStep {
+        EscalationStep {
+            after_secs,
+            action: action.to_string(),
+            sink: sink.map(ToString::to_string),
+        }
+    }
+
+    fn policies() -> Vec<EscalationPolicy> {
+        vec![
+            EscalationPolicy {
+                name: "disk".to_string(),
+                alert_type: Some("disk-full".to_string()),
+                steps: vec![step(3600, "open_incident", None)],
+                ..EscalationPolicy::default()
+            },
+            EscalationPolicy {
+                name: "unacked-warnings".to_string(),
+                severity: Some("warning".to_string()),
+                steps: vec![
+                    step(6 * 3600, "bump_severity", None),
+                    step(6 * 3600, "notify", Some("email")),
+                    step(24 * 3600, "open_incident", None),
+                ],
+                ..EscalationPolicy::default()
+            },
+        ]
+    }
+
+    fn fire(store: &VcStore, rule: &str, machine: &str, severity: &str, at: DateTime<Utc>) {
+        store
+            .insert_alert(&FiredAlert {
+                rule_id: rule.to_string(),
+                fired_at: at.to_rfc3339(),
+                severity: severity.to_string(),
+                title: rule.to_string(),
+                message: String::new(),
+                context_json: None,
+                machine_id: Some(machine.to_string()),
+            })
+            .unwrap();
+    }
+
+    fn actions(escalations: &[Escalation]) -> Vec<(String, u32, &'static str)> {
+        escalations
+            .iter()
+            .map(|e| (e.group_key.clone(), e.level, e.action.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_policy_matching() {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now();
+        fire(&store, "disk-full", "orko", "warning", now);
+        fire(&store, "collector-stale:sysmoni", "orko", "info", now);
+        let groups = store.open_alert_groups().unwrap();
+        let policies = policies();
+
+        assert_eq!(policy_for(&policies, &groups[0]).unwrap().name, "disk");
+        assert!(policy_for(&policies, &groups[1]).is_none());
+
+        let stale = EscalationPolicy {
+            name: "stale".to_string(),
+            alert_type: Some("collector-stale".to_string()),
+            group: Some("orko/collector-stale:sysmoni".to_string()),
+            ..EscalationPolicy::default()
+        };
+        assert!(policy_matches(&stale, &groups[1]));
+        assert!(!policy_matches(&stale, &groups[0]));
+    }
+
+    #[test]
+    fn test_levels_apply_once_in_order() {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now();
+        for (machine, severity, hours) in [
+            ("orko", "warning", 7),
+            ("sydney", "warning", 1),
+            ("bender", "critical", 30),
+        ] {
+            let fired_at = now - Duration::hours(hours);
+            fire(&store, "machine-offline", machine, severity, fired_at);
+        }
+        let policies = policies();
+
+        let first = escalate(&store, &policies, now).unwrap();
+        assert_eq!(
+            actions(&first),
+            vec![
+                ("orko/machine-offline".to_string(), 1, "bump_severity"),
+                ("orko/machine-offline".to_string(), 2, "notify"),
+            ]
+        );
+        assert_eq!(
+            first[0].action,
+            EscalationAction::BumpSeverity {
+                from: "warning".to_string(),
+                to: "critical".to_string(),
+            }
+        );
+        let alert = first[1].to_alert();
+        assert_eq!(alert.severity, Severity::Critical);
+        assert!(alert.title.contains("level 2"));
+
+        // Once escalated, the group keeps its policy even though its
+        // severity no longer matches, and a repeated cycle does nothing
+        assert!(escalate(&store, &policies, now).unwrap().is_empty());
+        let later = escalate(&store, &policies, now + Duration::hours(18)).unwrap();
+        assert_eq!(
+            actions(&later),
+            vec![("orko/machine-offline".to_string(), 3, "open_incident")]
+        );
+        assert!(store.get_incident("inc-esc-1").unwrap().is_some());
+
+        let rows = store
+            .query_json(
+                "SELECT id, severity, escalation_level, escalation_policy FROM alert_history \
+                 ORDER BY id",
+            )
+            .unwrap();
+        assert_eq!(rows[0]["escalation_level"], 3);
+        assert_eq!(rows[0]["severity"], "critical");
+        assert_eq!(rows[0]["escalation_policy"], "unacked-warnings");
+        assert_eq!(rows[1]["escalation_level"], 0);
+
+        let audited = store
+            .query_json(
+                "SELECT action, result FROM audit_events \
+                 WHERE event_type = 'alert_escalation' ORDER BY id",
+            )
+            .unwrap();
+        assert_eq!(audited.len(), 3);
+        assert_eq!(audited[2]["action"], "alert_escalate_open_incident");
+        assert_eq!(audited[2]["result"], "success");
+    }
+
+    #[test]
+    fn test_acked_alerts_stop_and_new_occurrences_join_the_group() {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now();
+        fire(
+            &store,
+            "disk-full",
+            "orko",
+            "warning",
+            now - Duration::hours(2),
+        );
+        fire(
+            &store,
+            "disk-full",
+            "sydney",
+            "warning",
+            now - Duration::hours(2),
+        );
+        store
+            .execute_batch("UPDATE alert_history SET acknowledged = 1 WHERE id = 2")
+            .unwrap();
+
+        let escalated = escalate(&store, &policies(), now).unwrap();
+        assert_eq!(
+            actions(&escalated),
+            vec![("orko/disk-full".to_string(), 1, "open_incident")]
+        );
+
+        // A new occurrence in the escalated group takes the group's level
+        // rather than opening another incident
+        fire(&store, "disk-full", "orko", "warning", now);
+        assert!(escalate(&store, &policies(), now).unwrap().is_empty());
+        let groups = store.open_alert_groups().unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].alert_ids, vec![1, 3]);
+        assert!(groups[0].behind_ids.is_empty());
+
+        let incidents = store.list_incidents(None, 10).unwrap();
+        assert_eq!(incidents.len(), 1);
+    }
+}
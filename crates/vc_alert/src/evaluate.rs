@@ -0,0 +1,702 @@
+//! Rule evaluation shared by the daemon and `vc alert rules test`
+//!
+//! Rule queries are written against `current_timestamp`. [`RuleEvaluator`]
+//! evaluates them as of a given instant by substituting that instant, so the
+//! daemon (as of now) and the simulator (as of every poll step in a window)
+//! run the same SQL through the same code. Whatever fires goes to an
+//! [`AlertSink`]: [`StoreSink`] writes `alert_history`, [`SimulationSink`]
+//! only records a timeline.
+//!
+//! With [`RuleOverrides`], a breach is judged by the rule as it applies to
+//! the machine in the query's `machine_id` column.
+//!
+//! Only `Threshold` conditions are evaluated. `Pattern`, `Absence` and
+//! `RateOfChange` need per-condition query construction that does not exist
+//! yet; they come back as [`RuleOutcome::Unsupported`] rather than an
+//! invented result.
+
+use crate::overrides::RuleOverrides;
+use crate::{AlertCondition, AlertError, AlertRule, Severity, ThresholdOp};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+use vc_store::VcStore;
+
+/// A threshold breach found by evaluating a rule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Breach {
+    pub actual: f64,
+    pub threshold: f64,
+    /// Set when the rule query returns a `machine_id` column
+    pub machine_id: Option<String>,
+}
+
+/// Result of evaluating one rule at one instant
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleOutcome {
+    Breach(Breach),
+    Clear,
+    /// No rows, or a NULL: no telemetry to judge, which is not a breach
+    NoData,
+    /// The condition type is not evaluated yet
+    Unsupported,
+    /// The rule query failed
+    Failed(String),
+}
+
+/// Where evaluation results go
+pub trait AlertSink {
+    /// Whether an alert for this rule (and machine) is already open, in which
+    /// case the breach does not fire again
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlertError`] if the open-alert lookup fails.
+    fn is_open(&mut self, rule: &AlertRule, breach: &Breach) -> Result<bool, AlertError>;
+
+    /// Raise an alert for `breach`, observed at `at`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlertError`] if the alert cannot be recorded.
+    fn fire(
+        &mut self,
+        rule: &AlertRule,
+        at: DateTime<Utc>,
+        breach: &Breach,
+    ) -> Result<(), AlertError>;
+
+    /// See every outcome, including those that do not fire
+    fn observe(&mut self, _rule: &AlertRule, _at: DateTime<Utc>, _outcome: &RuleOutcome) {}
+}
+
+/// Evaluates alert rules against the store as of a given instant
+pub struct RuleEvaluator<'a> {
+    store: &'a VcStore,
+    overrides: Option<&'a RuleOverrides>,
+}
+
+impl<'a> RuleEvaluator<'a> {
+    #[must_use]
+    pub fn new(store: &'a VcStore) -> Self {
+        Self {
+            store,
+            overrides: None,
+        }
+    }
+
+    /// Judge each breach by the rule as overridden for its machine
+    #[must_use]
+    pub fn with_overrides(mut self, overrides: &'a RuleOverrides) -> Self {
+        self.overrides = Some(overrides);
+        self
+    }
+
+    /// Evaluate `rule` as if it were `as_of`
+    #[must_use]
+    pub fn evaluate_at(&self, rule: &AlertRule, as_of: DateTime<Utc>) -> RuleOutcome {
+        self.evaluate_resolved(rule, as_of).1
+    }
+
+    /// The outcome of `rule` as of `as_of`, next to the rule it was judged
+    /// by: overridden for the sampled machine when there is one
+    fn evaluate_resolved<'r>(
+        &self,
+        rule: &'r AlertRule,
+        as_of: DateTime<Utc>,
+    ) -> (Cow<'r, AlertRule>, RuleOutcome) {
+        let AlertCondition::Threshold { query, .. } = &rule.condition else {
+            return (Cow::Borrowed(rule), RuleOutcome::Unsupported);
+        };
+
+        let rows = match self.store.query_json(&query_as_of(query, as_of)) {
+            Ok(rows) => rows,
+            Err(e) => return (Cow::Borrowed(rule), RuleOutcome::Failed(e.to_string())),
+        };
+
+        // A threshold query yields a single scalar, optionally next to the
+        // machine it belongs to.
+        let Some(row) = rows.first().and_then(serde_json::Value::as_object) else {
+            return (Cow::Borrowed(rule), RuleOutcome::NoData);
+        };
+        let Some(actual) = row
+            .iter()
+            .filter(|(key, _)| key.as_str() != "machine_id")
+            .find_map(|(_, v)| v.as_f64())
+        else {
+            return (Cow::Borrowed(rule), RuleOutcome::NoData);
+        };
+        let machine_id = row
+            .get("machine_id")
+            .and_then(serde_json::Value::as_str)
+            .map(str::to_string);
+
+        let rule = match (self.overrides, machine_id.as_deref()) {
+            (Some(overrides), Some(machine)) => Cow::Owned(overrides.resolve(rule, machine).rule),
+            _ => Cow::Borrowed(rule),
+        };
+        // Overrides never change the condition type
+        let AlertCondition::Threshold {
+            operator, value, ..
+        } = &rule.condition
+        else {
+            return (rule, RuleOutcome::Unsupported);
+        };
+
+        let outcome = if operator.check(actual, *value) {
+            RuleOutcome::Breach(Breach {
+                actual,
+                threshold: *value,
+                machine_id,
+            })
+        } else {
+            RuleOutcome::Clear
+        };
+        (rule, outcome)
+    }
+
+    /// Evaluate `rules` at every `step` from `start` through `end` (once when
+    /// they are equal), sending breaches that are not already open to `sink`.
+    ///
+    /// Returns how many alerts fired.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AlertError`] if the sink fails.
+    pub fn run(
+        &self,
+        rules: &[AlertRule],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: Duration,
+        sink: &mut dyn AlertSink,
+    ) -> Result<usize, AlertError> {
+        let step = chrono::Duration::from_std(step.max(Duration::from_secs(1)))
+            .unwrap_or_else(|_| chrono::Duration::minutes(1));
+        let mut fired = 0_usize;
+        let mut at = start;
+
+        while at <= end {
+            for rule in rules {
+                let (rule, outcome) = self.evaluate_resolved(rule, at);
+                sink.observe(&rule, at, &outcome);
+                if let RuleOutcome::Breach(breach) = &outcome
+                    && !sink.is_open(&rule, breach)?
+                {
+                    sink.fire(&rule, at, breach)?;
+                    fired += 1;
+                }
+            }
+            at += step;
+        }
+
+        Ok(fired)
+    }
+}
+
+/// Whether a rule's query is anchored on `current_timestamp` and so can be
+/// evaluated as of a past instant. Queries that just take the latest row see
+/// the newest data at every step of a simulation.
+#[must_use]
+pub fn is_time_anchored(rule: &AlertRule) -> bool {
+    match &rule.condition {
+        AlertCondition::Threshold { query, .. } | AlertCondition::RateOfChange { query, .. } => {
+            query.to_ascii_lowercase().contains(NOW)
+        }
+        AlertCondition::Pattern { .. } | AlertCondition::Absence { .. } => false,
+    }
+}
+
+const NOW: &str = "current_timestamp";
+
+/// `query` with every `current_timestamp` replaced by `as_of`
+#[must_use]
+pub fn query_as_of(query: &str, as_of: DateTime<Utc>) -> String {
+    let literal = format!(
+        "CAST('{}' AS TIMESTAMP)",
+        as_of.format("%Y-%m-%d %H:%M:%S%.6f")
+    );
+    // ASCII lowercasing keeps byte offsets, so matches index the original.
+    let lower = query.to_ascii_lowercase();
+    let mut out = String::with_capacity(query.len());
+    let mut rest = 0;
+    for (idx, _) in lower.match_indices(NOW) {
+        out.push_str(&query[rest..idx]);
+        out.push_str(&literal);
+        rest = idx + NOW.len();
+    }
+    out.push_str(&query[rest..]);
+    out
+}
+
+// ============================================================================
+// Store sink (daemon)
+// ============================================================================
+
+/// Writes fired alerts to `alert_history`; an unresolved alert for the same
+/// rule and machine suppresses re-firing.
+pub struct StoreSink<'a> {
+    store: &'a VcStore,
+}
+
+impl<'a> StoreSink<'a> {
+    #[must_use]
+    pub fn new(store: &'a VcStore) -> Self {
+        Self { store }
+    }
+}
+
+impl AlertSink for StoreSink<'_> {
+    fn is_open(&mut self, rule: &AlertRule, breach: &Breach) -> Result<bool, AlertError> {
+        Ok(self
+            .store
+            .has_open_alert(&rule.rule_id, breach.machine_id.as_deref())?)
+    }
+
+    fn fire(
+        &mut self,
+        rule: &AlertRule,
+        at: DateTime<Utc>,
+        breach: &Breach,
+    ) -> Result<(), AlertError> {
+        let query = match &rule.condition {
+            AlertCondition::Threshold { query, .. } => Some(query.as_str()),
+            _ => None,
+        };
+        let context = serde_json::json!({
+            "actual": breach.actual,
+            "threshold": breach.threshold,
+            "query": query,
+        });
+
+        self.store.insert_alert(&vc_store::FiredAlert {
+            rule_id: rule.rule_id.clone(),
+            fired_at: at.to_rfc3339_opts(SecondsFormat::Micros, true),
+            severity: format!("{:?}", rule.severity).to_lowercase(),
+            title: rule.name.clone(),
+            message: format!(
+                "{} is {:.1}, which breaches the threshold of {:.1}",
+                rule.name, breach.actual, breach.threshold
+            ),
+            context_json: Some(context.to_string()),
+            machine_id: breach.machine_id.clone(),
+        })?;
+        Ok(())
+    }
+
+    fn observe(&mut self, rule: &AlertRule, _at: DateTime<Utc>, outcome: &RuleOutcome) {
+        match outcome {
+            RuleOutcome::Failed(error) => {
+                tracing::warn!(rule = %rule.rule_id, error = %error, "alert rule query failed");
+            }
+            RuleOutcome::Unsupported => {
+                tracing::debug!(rule = %rule.rule_id, "alert condition type not evaluated yet");
+            }
+            _ => {}
+        }
+    }
+}
+
+// ============================================================================
+// Simulation sink (`vc alert rules test`)
+// ============================================================================
+
+/// A stretch of consecutive breaching evaluations: one alert after dedup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FiringEpisode {
+    pub machine_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// Last breaching evaluation
+    pub ended_at: DateTime<Utc>,
+    /// From the first breach to one step past the last
+    pub duration_secs: i64,
+    pub evaluations: usize,
+    /// The breaching value furthest past the threshold
+    pub peak_actual: f64,
+    /// Threshold and severity of the rule as it applied to the machine
+    pub threshold: f64,
+    pub severity: Severity,
+}
+
+/// What a rule would have done over a window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub step_secs: u64,
+    pub evaluations: usize,
+    pub breaches: usize,
+    pub no_data: usize,
+    pub failures: usize,
+    /// Alerts that would have been raised after dedup
+    pub alert_count: usize,
+    pub firing_secs: i64,
+    pub machines: Vec<String>,
+    pub episodes: Vec<FiringEpisode>,
+    /// Scopes of the `[[alerts.overrides]]` configured for the rule
+    pub overrides: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Records what would have fired without writing anything.
+///
+/// An alert is assumed resolved as soon as its condition evaluates clear (or
+/// has no data); the next breach after that opens a new alert.
+#[derive(Debug)]
+pub struct SimulationSink {
+    step: Duration,
+    evaluations: usize,
+    breaches: usize,
+    no_data: usize,
+    unsupported: usize,
+    failures: Vec<String>,
+    episodes: Vec<FiringEpisode>,
+    /// Open episode index per machine
+    open: HashMap<Option<String>, usize>,
+}
+
+impl SimulationSink {
+    #[must_use]
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            evaluations: 0,
+            breaches: 0,
+            no_data: 0,
+            unsupported: 0,
+            failures: Vec::new(),
+            episodes: Vec::new(),
+            open: HashMap::new(),
+        }
+    }
+
+    /// Summarize the recorded timeline for `rule`
+    #[must_use]
+    pub fn into_report(
+        self,
+        rule: &AlertRule,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> SimulationReport {
+        let mut warnings = Vec::new();
+        if self.unsupported > 0 {
+            warnings.push(format!(
+                "{} conditions are not evaluated yet; nothing would fire",
+                condition_kind(&rule.condition)
+            ));
+        } else if !is_time_anchored(rule) {
+            warnings.push(
+                "query does not use current_timestamp; every step saw the latest data".to_string(),
+            );
+        }
+        if let Some(first) = self.failures.first() {
+            warnings.push(format!(
+                "query failed {} time(s): {first}",
+                self.failures.len()
+            ));
+        }
+
+        let machines: BTreeSet<String> = self
+            .episodes
+            .iter()
+            .filter_map(|episode| episode.machine_id.clone())
+            .collect();
+
+        SimulationReport {
+            rule_id: rule.rule_id.clone(),
+            rule_name: rule.name.clone(),
+            window_start,
+            window_end,
+            step_secs: self.step.as_secs(),
+            evaluations: self.evaluations,
+            breaches: self.breaches,
+            no_data: self.no_data,
+            failures: self.failures.len(),
+            alert_count: self.episodes.len(),
+            firing_secs: self.episodes.iter().map(|e| e.duration_secs).sum(),
+            machines: machines.into_iter().collect(),
+            episodes: self.episodes,
+            overrides: Vec::new(),
+            warnings,
+        }
+    }
+
+    fn step_secs(&self) -> i64 {
+        i64::try_from(self.step.as_secs()).unwrap_or(i64::MAX)
+    }
+}
+
+impl AlertSink for SimulationSink {
+    fn is_open(&mut self, _rule: &AlertRule, breach: &Breach) -> Result<bool, AlertError> {
+        Ok(self.open.contains_key(&breach.machine_id))
+    }
+
+    fn fire(
+        &mut self,
+        rule: &AlertRule,
+        at: DateTime<Utc>,
+        breach: &Breach,
+    ) -> Result<(), AlertError> {
+        self.open
+            .insert(breach.machine_id.clone(), self.episodes.len());
+        self.episodes.push(FiringEpisode {
+            machine_id: breach.machine_id.clone(),
+            started_at: at,
+            ended_at: at,
+            duration_secs: self.step_secs(),
+            evaluations: 1,
+            peak_actual: breach.actual,
+            threshold: breach.threshold,
+            severity: rule.severity,
+        });
+        Ok(())
+    }
+
+    fn observe(&mut self, rule: &AlertRule, at: DateTime<Utc>, outcome: &RuleOutcome) {
+        self.evaluations += 1;
+        match outcome {
+            RuleOutcome::Breach(breach) => {
+                self.breaches += 1;
+                let step_secs = self.step_secs();
+                if let Some(&idx) = self.open.get(&breach.machine_id) {
+                    let episode = &mut self.episodes[idx];
+                    episode.ended_at = at;
+                    episode.duration_secs = (at - episode.started_at).num_seconds() + step_secs;
+                    episode.evaluations += 1;
+                    if is_worse(&rule.condition, breach.actual, episode.peak_actual) {
+                        episode.peak_actual = breach.actual;
+                    }
+                }
+            }
+            RuleOutcome::Clear => self.open.clear(),
+            RuleOutcome::NoData => {
+                self.no_data += 1;
+                self.open.clear();
+            }
+            RuleOutcome::Unsupported => self.unsupported += 1,
+            RuleOutcome::Failed(error) => self.failures.push(error.clone()),
+        }
+    }
+}
+
+/// Whether `candidate` is further past the threshold than `current`
+fn is_worse(condition: &AlertCondition, candidate: f64, current: f64) -> bool {
+    match condition {
+        AlertCondition::Threshold {
+            operator: ThresholdOp::Lt | ThresholdOp::Lte,
+            ..
+        } => candidate < current,
+        _ => candidate > current,
+    }
+}
+
+pub(crate) fn condition_kind(condition: &AlertCondition) -> &'static str {
+    match condition {
+        AlertCondition::Threshold { .. } => "threshold",
+        AlertCondition::Pattern { .. } => "pattern",
+        AlertCondition::Absence { .. } => "absence",
+        AlertCondition::RateOfChange { .. } => "rate_of_change",
+    }
+}
+
+/// Replay `rule` over `[start, end]` at `step` without writing any alerts,
+/// judging each breach with `overrides` applied as the daemon would
+///
+/// # Errors
+///
+/// Returns [`AlertError`] if evaluation fails outside the rule query itself.
+pub fn simulate(
+    store: &VcStore,
+    rule: &AlertRule,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: Duration,
+    overrides: Option<&RuleOverrides>,
+) -> Result<SimulationReport, AlertError> {
+    let mut sink = SimulationSink::new(step);
+    let mut evaluator = RuleEvaluator::new(store);
+    if let Some(overrides) = overrides {
+        evaluator = evaluator.with_overrides(overrides);
+    }
+    evaluator.run(std::slice::from_ref(rule), start, end, step, &mut sink)?;
+    let mut report = sink.into_report(rule, start, end);
+    report.overrides = overrides.map_or_else(Vec::new, |o| o.scopes_for(&rule.rule_id));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold_rule(query: &str, operator: ThresholdOp, value: f64) -> AlertRule {
+        AlertRule {
+            rule_id: "test-rule".to_string(),
+            name: "Test Rule".to_string(),
+            description: None,
+            severity: Severity::Warning,
+            enabled: false,
+            condition: AlertCondition::Threshold {
+                query: query.to_string(),
+                operator,
+                value,
+            },
+            cooldown_secs: 0,
+            channels: vec![],
+        }
+    }
+
+    fn ts(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn store_with_samples() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "CREATE TABLE sim_samples (machine_id TEXT, collected_at TEXT, load DOUBLE)",
+            )
+            .unwrap();
+        // Load breaches 0:00-0:20 on m1, clears, then breaches again at 1:00.
+        store
+            .execute_simple(
+                "INSERT INTO sim_samples VALUES
+                 ('m1', '2026-10-01T00:00:00Z', 9.0),
+                 ('m1', '2026-10-01T00:10:00Z', 12.0),
+                 ('m1', '2026-10-01T00:20:00Z', 1.0),
+                 ('m1', '2026-10-01T00:30:00Z', 1.0),
+                 ('m1', '2026-10-01T00:40:00Z', 1.0),
+                 ('m1', '2026-10-01T00:50:00Z', 1.0),
+                 ('m1', '2026-10-01T01:00:00Z', 8.0)",
+            )
+            .unwrap();
+        store
+    }
+
+    const LOAD_QUERY: &str = "SELECT machine_id, load FROM sim_samples \
+        WHERE CAST(collected_at AS TIMESTAMP) <= current_timestamp \
+          AND CAST(collected_at AS TIMESTAMP) > current_timestamp - INTERVAL '10 minutes' \
+        ORDER BY collected_at DESC LIMIT 1";
+
+    #[test]
+    fn test_query_as_of_replaces_every_current_timestamp() {
+        let sql = query_as_of(
+            "SELECT 1 WHERE a > CURRENT_TIMESTAMP - x AND b < current_timestamp",
+            ts("2026-10-01T12:00:00Z"),
+        );
+        assert!(!sql.to_ascii_lowercase().contains(NOW));
+        assert_eq!(
+            sql.matches("CAST('2026-10-01 12:00:00.000000' AS TIMESTAMP)")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_simulation_reports_episodes_after_dedup() {
+        let store = store_with_samples();
+        let rule = threshold_rule(LOAD_QUERY, ThresholdOp::Gte, 5.0);
+
+        let report = simulate(
+            &store,
+            &rule,
+            ts("2026-10-01T00:00:00Z"),
+            ts("2026-10-01T01:00:00Z"),
+            Duration::from_secs(600),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(report.evaluations, 7);
+        assert_eq!(report.breaches, 3);
+        assert_eq!(report.alert_count, 2);
+        assert_eq!(report.machines, vec!["m1".to_string()]);
+        let first = &report.episodes[0];
+        assert_eq!(first.started_at, ts("2026-10-01T00:00:00Z"));
+        assert_eq!(first.ended_at, ts("2026-10-01T00:10:00Z"));
+        assert_eq!(first.duration_secs, 1200);
+        assert!((first.peak_actual - 12.0).abs() < f64::EPSILON);
+        assert_eq!(report.firing_secs, 1800);
+        assert!(report.warnings.is_empty());
+
+        // Nothing was written.
+        assert!(!store.has_open_alert("test-rule", None).unwrap());
+    }
+
+    #[test]
+    fn test_simulation_applies_machine_overrides() {
+        let store = store_with_samples();
+        // Nothing reaches 20, but m1's override lowers its threshold to 5.
+        let rule = threshold_rule(LOAD_QUERY, ThresholdOp::Gte, 20.0);
+        let overrides = RuleOverrides::new(
+            vec![vc_config::AlertRuleOverride {
+                rule: "test-rule".to_string(),
+                tag: Some("build".to_string()),
+                threshold: Some(5.0),
+                severity: Some("critical".to_string()),
+                ..vc_config::AlertRuleOverride::default()
+            }],
+            HashMap::from([("m1".to_string(), vec!["build".to_string()])]),
+        );
+        let window = (ts("2026-10-01T00:00:00Z"), ts("2026-10-01T01:00:00Z"));
+        let step = Duration::from_secs(600);
+
+        let base = simulate(&store, &rule, window.0, window.1, step, None).unwrap();
+        assert_eq!(base.alert_count, 0);
+
+        let report = simulate(&store, &rule, window.0, window.1, step, Some(&overrides)).unwrap();
+        assert_eq!(report.alert_count, 2);
+        assert_eq!(report.overrides, ["tag:build"]);
+        let first = &report.episodes[0];
+        assert!((first.threshold - 5.0).abs() < f64::EPSILON);
+        assert_eq!(first.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_store_sink_fires_once_while_open() {
+        let store = store_with_samples();
+        let rule = threshold_rule(LOAD_QUERY, ThresholdOp::Gte, 5.0);
+        let evaluator = RuleEvaluator::new(&store);
+        let at = ts("2026-10-01T00:10:00Z");
+
+        let mut sink = StoreSink::new(&store);
+        let step = Duration::from_secs(60);
+        assert_eq!(
+            evaluator
+                .run(&[rule.clone()], at, at, step, &mut sink)
+                .unwrap(),
+            1
+        );
+        assert_eq!(evaluator.run(&[rule], at, at, step, &mut sink).unwrap(), 0);
+        assert!(store.has_open_alert("test-rule", Some("m1")).unwrap());
+    }
+
+    #[test]
+    fn test_simulation_warns_on_unanchored_and_unsupported_rules() {
+        let store = store_with_samples();
+        let window = (ts("2026-10-01T00:00:00Z"), ts("2026-10-01T00:10:00Z"));
+        let step = Duration::from_secs(600);
+
+        let latest = threshold_rule(
+            "SELECT load FROM sim_samples ORDER BY collected_at DESC LIMIT 1",
+            ThresholdOp::Gte,
+            5.0,
+        );
+        let report = simulate(&store, &latest, window.0, window.1, step, None).unwrap();
+        assert!(report.warnings[0].contains("current_timestamp"));
+
+        let mut absence = latest;
+        absence.condition = AlertCondition::Absence {
+            table: "sim_samples".to_string(),
+            max_age_secs: 60,
+        };
+        let report = simulate(&store, &absence, window.0, window.1, step, None).unwrap();
+        assert_eq!(report.alert_count, 0);
+        assert!(report.warnings[0].contains("absence"));
+    }
+}
@@ -1,13 +1,26 @@
 //! `vc_alert` - Alerting system for Vibe Cockpit
 //!
 //! This crate provides:
-//! - Alert rule definitions
-//! - Condition evaluation
+//! - Alert rule definitions, with per-machine overrides
+//! - Condition evaluation, live or replayed over history
 //! - Alert history management
-//! - Delivery channels (TUI, webhook, desktop)
+//! - Delivery channels (TUI, webhook, desktop, email)
+//! - Digest batching of low-severity alerts per channel
+//! - Per-sink message templates filled in at delivery time
+//! - Time-based escalation of alert groups left unacknowledged
 //! - Alert routing, escalation, and suppression
 
+pub mod correlation;
+pub mod digest;
+pub mod email;
+pub mod escalation;
+pub mod evaluate;
+pub mod overrides;
 pub mod routing;
+pub mod template;
+
+pub use digest::{AlertDigest, DigestBuffer, DigestGroup, DigestPolicy};
+pub use email::EmailChannel;
 
 use asupersync::Cx;
 use asupersync::channel::mpsc;
@@ -16,8 +29,11 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use thiserror::Error;
+use vc_config::MessageTemplate;
 
 /// Alert errors
 #[derive(Error, Debug)]
@@ -31,6 +47,9 @@ pub enum AlertError {
     #[error("Delivery failed: {0}")]
     DeliveryFailed(String),
 
+    #[error("Invalid channel config: {0}")]
+    InvalidConfig(String),
+
     #[error("Store error: {0}")]
     StoreError(#[from] vc_store::StoreError),
 }
@@ -124,6 +143,24 @@ pub struct Alert {
 pub trait AlertChannel: Send + Sync {
     fn name(&self) -> &'static str;
     async fn deliver(&self, cx: &Cx, alert: &Alert) -> Result<(), AlertError>;
+
+    /// Deliver a batch as one message. Channels without a native digest
+    /// format send the digest's summary alert.
+    async fn deliver_digest(&self, cx: &Cx, digest: &AlertDigest) -> Result<(), AlertError> {
+        self.deliver(cx, &digest.to_summary_alert()).await
+    }
+
+    /// Deliver `message`, rendered from the sink's template, in place of the
+    /// default formatting. Channels that cannot carry free text ignore it.
+    async fn deliver_rendered(
+        &self,
+        cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        let _ = message;
+        self.deliver(cx, alert).await
+    }
 }
 
 /// Alert engine for rule evaluation
@@ -230,6 +267,92 @@ impl AlertEngine {
                 cooldown_secs: 120,
                 channels: vec!["tui".to_string(), "desktop".to_string()],
             },
+            AlertRule {
+                rule_id: "accounts-exhausted".to_string(),
+                name: "All Accounts Nearly Exhausted".to_string(),
+                description: Some(
+                    "Alert when every account has 10% or less of its quota remaining".to_string(),
+                ),
+                severity: Severity::Critical,
+                enabled: true,
+                condition: AlertCondition::Threshold {
+                    // Best remaining quota across each account's latest status
+                    query: "SELECT MAX(remaining_pct) FROM (SELECT remaining_pct, ROW_NUMBER() OVER (PARTITION BY provider, account_id ORDER BY CAST(collected_at AS TIMESTAMP) DESC) AS rn FROM account_status WHERE parse_error IS NULL AND remaining_pct IS NOT NULL AND CAST(collected_at AS TIMESTAMP) > current_timestamp - INTERVAL '1 hour') latest WHERE rn = 1".to_string(),
+                    operator: ThresholdOp::Lte,
+                    value: 10.0,
+                },
+                cooldown_secs: 900,
+                channels: vec!["tui".to_string(), "desktop".to_string()],
+            },
+            AlertRule {
+                rule_id: "http-check-failing".to_string(),
+                name: "HTTP Check Failing".to_string(),
+                description: Some(
+                    "Alert when an HTTP check failed its last 3 runs".to_string(),
+                ),
+                severity: Severity::Warning,
+                enabled: true,
+                condition: AlertCondition::Threshold {
+                    // Checks whose three most recent results in the last hour all failed
+                    query: "SELECT COUNT(*) FROM (SELECT machine_id, check_name FROM (SELECT machine_id, check_name, ok, ROW_NUMBER() OVER (PARTITION BY machine_id, check_name ORDER BY collected_at DESC) AS rn FROM http_check_results WHERE CAST(collected_at AS TIMESTAMP) > current_timestamp - INTERVAL '1 hour') recent WHERE rn <= 3 GROUP BY machine_id, check_name HAVING COUNT(*) = 3 AND MAX(ok) = 0) failing".to_string(),
+                    operator: ThresholdOp::Gte,
+                    value: 1.0,
+                },
+                cooldown_secs: 600,
+                channels: vec!["tui".to_string(), "desktop".to_string()],
+            },
+            AlertRule {
+                rule_id: "tls-cert-expiring".to_string(),
+                name: "TLS Certificate Expiring".to_string(),
+                description: Some(
+                    "Alert when an HTTP check's certificate is inside its warning window"
+                        .to_string(),
+                ),
+                severity: Severity::Warning,
+                enabled: true,
+                condition: AlertCondition::Threshold {
+                    query: "SELECT COUNT(DISTINCT machine_id || '/' || check_name) FROM http_check_results WHERE tls_warning IS NOT NULL AND CAST(collected_at AS TIMESTAMP) > current_timestamp - INTERVAL '1 hour'".to_string(),
+                    operator: ThresholdOp::Gte,
+                    value: 1.0,
+                },
+                cooldown_secs: 86400,
+                channels: vec!["tui".to_string()],
+            },
+            AlertRule {
+                rule_id: "oversized-writes".to_string(),
+                name: "Oversized Writes Detected".to_string(),
+                description: Some(
+                    "Alert when the store truncated or rejected values over its write limits"
+                        .to_string(),
+                ),
+                severity: Severity::Warning,
+                enabled: true,
+                condition: AlertCondition::Threshold {
+                    query: "SELECT COUNT(*) FROM oversized_writes WHERE CAST(occurred_at AS TIMESTAMP) > current_timestamp - INTERVAL '1 hour'".to_string(),
+                    operator: ThresholdOp::Gte,
+                    value: 1.0,
+                },
+                cooldown_secs: 3600,
+                channels: vec!["tui".to_string()],
+            },
+            AlertRule {
+                rule_id: "disk-forecast-full".to_string(),
+                name: "Disk Forecast To Fill".to_string(),
+                description: Some(
+                    "Alert when a mount is forecast to be full within 7 days".to_string(),
+                ),
+                severity: Severity::Warning,
+                enabled: true,
+                condition: AlertCondition::Threshold {
+                    // Soonest confident forecast the daemon kept in the last hour;
+                    // cyclic mounts have no date and never match
+                    query: "SELECT machine_id, days_until_full FROM disk_forecasts WHERE days_until_full IS NOT NULL AND confidence >= 0.5 AND CAST(computed_at AS TIMESTAMP) > current_timestamp - INTERVAL '1 hour' ORDER BY days_until_full LIMIT 1".to_string(),
+                    operator: ThresholdOp::Lte,
+                    value: 7.0,
+                },
+                cooldown_secs: 21600,
+                channels: vec!["tui".to_string(), "desktop".to_string()],
+            },
         ]
     }
 
@@ -340,6 +463,57 @@ impl AlertChannel for WebhookChannel {
             )))
         }
     }
+
+    async fn deliver_digest(&self, _cx: &Cx, digest: &AlertDigest) -> Result<(), AlertError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "type": "digest", "digest": digest }))
+            .send()
+            .await
+            .map_err(|e| AlertError::DeliveryFailed(format!("Webhook request failed: {e}")))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(AlertError::DeliveryFailed(format!(
+                "Webhook returned error status: {}",
+                response.status()
+            )))
+        }
+    }
+
+    /// The rendered template is the request body, sent as JSON when it
+    /// parses as JSON and as plain text otherwise
+    async fn deliver_rendered(
+        &self,
+        _cx: &Cx,
+        _alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        let content_type = if serde_json::from_str::<serde_json::Value>(message).is_ok() {
+            "application/json"
+        } else {
+            "text/plain; charset=utf-8"
+        };
+        let response = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(message.to_string())
+            .send()
+            .await
+            .map_err(|e| AlertError::DeliveryFailed(format!("Webhook request failed: {e}")))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(AlertError::DeliveryFailed(format!(
+                "Webhook returned error status: {}",
+                response.status()
+            )))
+        }
+    }
 }
 
 /// Log channel - writes alerts to tracing logs (useful for debugging/testing)
@@ -466,6 +640,19 @@ impl AlertChannel for MemoryChannel {
         self.alerts.lock().unwrap().push(alert.clone());
         Ok(())
     }
+
+    /// Stores the alert with the rendered text as its message
+    async fn deliver_rendered(
+        &self,
+        _cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        let mut alert = alert.clone();
+        alert.message = message.to_string();
+        self.alerts.lock().unwrap().push(alert);
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -547,10 +734,28 @@ impl AlertChannel for SlackChannel {
             }]
         });
 
+        self.post(&payload).await
+    }
+
+    async fn deliver_rendered(
+        &self,
+        _cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        if alert.severity < self.min_severity {
+            return Ok(());
+        }
+        self.post(&serde_json::json!({ "text": message })).await
+    }
+}
+
+impl SlackChannel {
+    async fn post(&self, payload: &serde_json::Value) -> Result<(), AlertError> {
         let response = self
             .client
             .post(&self.webhook_url)
-            .json(&payload)
+            .json(payload)
             .send()
             .await
             .map_err(|e| AlertError::DeliveryFailed(format!("Slack webhook failed: {e}")))?;
@@ -620,10 +825,28 @@ impl AlertChannel for DiscordChannel {
             }]
         });
 
+        self.post(&payload).await
+    }
+
+    async fn deliver_rendered(
+        &self,
+        _cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        if alert.severity < self.min_severity {
+            return Ok(());
+        }
+        self.post(&serde_json::json!({ "content": message })).await
+    }
+}
+
+impl DiscordChannel {
+    async fn post(&self, payload: &serde_json::Value) -> Result<(), AlertError> {
         let response = self
             .client
             .post(&self.webhook_url)
-            .json(&payload)
+            .json(payload)
             .send()
             .await
             .map_err(|e| AlertError::DeliveryFailed(format!("Discord webhook failed: {e}")))?;
@@ -662,12 +885,26 @@ impl AlertChannel for DesktopChannel {
     }
 
     async fn deliver(&self, cx: &Cx, alert: &Alert) -> Result<(), AlertError> {
+        self.notify(cx, alert, &alert.message).await
+    }
+
+    async fn deliver_rendered(
+        &self,
+        cx: &Cx,
+        alert: &Alert,
+        message: &str,
+    ) -> Result<(), AlertError> {
+        self.notify(cx, alert, message).await
+    }
+}
+
+impl DesktopChannel {
+    async fn notify(&self, cx: &Cx, alert: &Alert, body: &str) -> Result<(), AlertError> {
         if alert.severity < self.min_severity {
             return Ok(());
         }
 
         let title = format!("VC Alert: {}", alert.title);
-        let body = &alert.message;
 
         #[cfg(target_os = "macos")]
         {
@@ -701,7 +938,7 @@ impl AlertChannel for DesktopChannel {
                 .map_err(|e| AlertError::DeliveryFailed(format!("notify-send failed: {e}")))?;
         }
 
-        let _ = cx;
+        let _ = (cx, body);
         Ok(())
     }
 }
@@ -716,11 +953,18 @@ pub struct DeliveryResult {
     pub channel: String,
     pub success: bool,
     pub error: Option<String>,
+    /// `alert_history` ids covered: one for a direct delivery, the whole
+    /// batch for a digest
+    pub alert_ids: Vec<i64>,
+    /// Whether this was a digest delivery
+    pub digest: bool,
 }
 
 /// Manages multiple alert delivery channels and dispatches alerts
 pub struct ChannelManager {
     channels: Vec<Box<dyn AlertChannel>>,
+    digests: Mutex<HashMap<&'static str, DigestBuffer>>,
+    templates: HashMap<&'static str, MessageTemplate>,
 }
 
 impl ChannelManager {
@@ -728,6 +972,8 @@ impl ChannelManager {
     pub fn new() -> Self {
         Self {
             channels: Vec::new(),
+            digests: Mutex::new(HashMap::new()),
+            templates: HashMap::new(),
         }
     }
 
@@ -735,22 +981,211 @@ impl ChannelManager {
         self.channels.push(channel);
     }
 
+    /// Put a registered channel into digest mode
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest mutex is poisoned.
+    pub fn set_digest(&mut self, channel: &str, policy: DigestPolicy) {
+        if let Some(name) = self
+            .channels
+            .iter()
+            .map(|c| c.name())
+            .find(|name| *name == channel)
+        {
+            self.digests
+                .get_mut()
+                .unwrap()
+                .insert(name, DigestBuffer::new(policy));
+        }
+    }
+
+    /// Send a registered channel's single alerts through `template`
+    pub fn set_template(&mut self, channel: &str, template: MessageTemplate) {
+        if let Some(name) = self
+            .channels
+            .iter()
+            .map(|c| c.name())
+            .find(|name| *name == channel)
+        {
+            self.templates.insert(name, template);
+        }
+    }
+
     /// Deliver an alert to all registered channels
+    ///
+    /// Digest channels queue alerts their policy batches; those produce no
+    /// result until the digest is sent, which happens here if the batch just
+    /// filled up or later via [`Self::flush_digests`]. Templates see only
+    /// the alert's own variables; see [`Self::deliver_all_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest mutex is poisoned.
     pub async fn deliver_all(&self, cx: &Cx, alert: &Alert) -> Vec<DeliveryResult> {
+        let vars = template::alert_vars(alert, None, None);
+        self.deliver_all_with(cx, alert, &vars).await
+    }
+
+    /// [`Self::deliver_all`] with template variables resolved by the caller
+    /// (see [`template::resolve_vars`])
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest mutex is poisoned.
+    pub async fn deliver_all_with(
+        &self,
+        cx: &Cx,
+        alert: &Alert,
+        vars: &serde_json::Value,
+    ) -> Vec<DeliveryResult> {
         let mut results = Vec::with_capacity(self.channels.len());
 
         for channel in &self.channels {
-            let result = channel.deliver(cx, alert).await;
-            results.push(DeliveryResult {
-                channel: channel.name().to_string(),
-                success: result.is_ok(),
-                error: result.err().map(|e| e.to_string()),
-            });
+            let queued = {
+                let mut digests = self.digests.lock().unwrap();
+                match digests.get_mut(channel.name()) {
+                    Some(buffer) if buffer.policy().should_batch(alert.severity) => {
+                        Some(buffer.push(alert.clone(), Instant::now()))
+                    }
+                    _ => None,
+                }
+            };
+
+            match queued {
+                Some(Some(batch)) => {
+                    results.extend(Self::send_digest(cx, channel.as_ref(), &batch).await);
+                }
+                Some(None) => {}
+                None => {
+                    let result = self.deliver_one(cx, channel.as_ref(), alert, vars).await;
+                    results.push(DeliveryResult {
+                        channel: channel.name().to_string(),
+                        success: result.is_ok(),
+                        error: result.err().map(|e| e.to_string()),
+                        alert_ids: alert.id.into_iter().collect(),
+                        digest: false,
+                    });
+                }
+            }
         }
 
         results
     }
 
+    /// Deliver an alert to one channel right away, even if that channel is in
+    /// digest mode; `None` when no registered channel has that name
+    pub async fn deliver_to(
+        &self,
+        cx: &Cx,
+        channel: &str,
+        alert: &Alert,
+    ) -> Option<DeliveryResult> {
+        let vars = template::alert_vars(alert, None, None);
+        self.deliver_to_with(cx, channel, alert, &vars).await
+    }
+
+    /// [`Self::deliver_to`] with template variables resolved by the caller
+    pub async fn deliver_to_with(
+        &self,
+        cx: &Cx,
+        channel: &str,
+        alert: &Alert,
+        vars: &serde_json::Value,
+    ) -> Option<DeliveryResult> {
+        let channel = self.channels.iter().find(|c| c.name() == channel)?;
+        let result = self.deliver_one(cx, channel.as_ref(), alert, vars).await;
+        Some(DeliveryResult {
+            channel: channel.name().to_string(),
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            alert_ids: alert.id.into_iter().collect(),
+            digest: false,
+        })
+    }
+
+    /// Send digests whose flush interval has elapsed, or every pending digest
+    /// when `force` is set (shutdown must not drop queued alerts)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest mutex is poisoned.
+    pub async fn flush_digests(&self, cx: &Cx, force: bool) -> Vec<DeliveryResult> {
+        let now = Instant::now();
+        let mut results = Vec::new();
+
+        for channel in &self.channels {
+            let batch = {
+                let mut digests = self.digests.lock().unwrap();
+                match digests.get_mut(channel.name()) {
+                    Some(buffer) if force => Some(buffer.take()),
+                    Some(buffer) => buffer.take_due(now),
+                    None => None,
+                }
+            };
+            if let Some(batch) = batch {
+                results.extend(Self::send_digest(cx, channel.as_ref(), &batch).await);
+            }
+        }
+
+        results
+    }
+
+    /// Alerts waiting in digest buffers across all channels
+    ///
+    /// # Panics
+    ///
+    /// Panics if the digest mutex is poisoned.
+    #[must_use]
+    pub fn pending_digest_count(&self) -> usize {
+        self.digests
+            .lock()
+            .unwrap()
+            .values()
+            .map(DigestBuffer::pending_count)
+            .sum()
+    }
+
+    /// Through the channel's template when it has one; placeholders without
+    /// a value are sent as written and logged
+    async fn deliver_one(
+        &self,
+        cx: &Cx,
+        channel: &dyn AlertChannel,
+        alert: &Alert,
+        vars: &serde_json::Value,
+    ) -> Result<(), AlertError> {
+        let Some(message) = self.templates.get(channel.name()) else {
+            return channel.deliver(cx, alert).await;
+        };
+        let rendered = message.render(vars, template::escape_for(channel.name()));
+        if !rendered.unresolved.is_empty() {
+            tracing::warn!(
+                channel = channel.name(),
+                rule = %alert.rule_id,
+                unresolved = %rendered.unresolved.join(", "),
+                "template variables without a value were sent as written"
+            );
+        }
+        channel.deliver_rendered(cx, alert, &rendered.text).await
+    }
+
+    async fn send_digest(
+        cx: &Cx,
+        channel: &dyn AlertChannel,
+        batch: &[Alert],
+    ) -> Option<DeliveryResult> {
+        let digest = AlertDigest::from_alerts(channel.name(), batch)?;
+        let result = channel.deliver_digest(cx, &digest).await;
+        Some(DeliveryResult {
+            channel: channel.name().to_string(),
+            success: result.is_ok(),
+            error: result.err().map(|e| e.to_string()),
+            alert_ids: digest.alert_ids(),
+            digest: true,
+        })
+    }
+
     /// Number of registered channels
     #[must_use]
     pub fn channel_count(&self) -> usize {
@@ -1305,8 +1740,8 @@ mod tests {
     #[test]
     fn test_default_rules_count() {
         let engine = AlertEngine::new();
-        // We now have 6 default rules
-        assert_eq!(engine.rules().len(), 6);
+        // We now have 11 default rules
+        assert_eq!(engine.rules().len(), 11);
     }
 
     // ==========================================================================
@@ -1765,6 +2200,8 @@ mod tests {
             channel: "slack".to_string(),
             success: true,
             error: None,
+            alert_ids: vec![1],
+            digest: false,
         };
         assert!(result.success);
         assert!(result.error.is_none());
@@ -1776,6 +2213,8 @@ mod tests {
             channel: "webhook".to_string(),
             success: false,
             error: Some("Connection refused".to_string()),
+            alert_ids: vec![1],
+            digest: false,
         };
         assert!(!result.success);
         assert_eq!(result.error.as_deref(), Some("Connection refused"));
@@ -1787,6 +2226,8 @@ mod tests {
             channel: "slack".to_string(),
             success: true,
             error: None,
+            alert_ids: vec![1],
+            digest: false,
         };
         let json = serde_json::to_string(&result).unwrap();
         assert!(json.contains("\"channel\":\"slack\""));
@@ -1848,6 +2289,91 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_channel_manager_renders_templates() {
+        run_async(async {
+            let cx = test_cx();
+            let memory = MemoryChannel::new();
+            let delivered = std::sync::Arc::clone(&memory.alerts);
+            let mut manager = ChannelManager::new();
+            manager.add_channel(Box::new(memory));
+            manager.set_template(
+                "memory",
+                MessageTemplate::parse("{{alert.title}} on {{machine.hostname}}").unwrap(),
+            );
+            manager.set_template("pager", MessageTemplate::parse("ignored").unwrap());
+
+            let alert = Alert {
+                id: Some(1),
+                rule_id: "disk".to_string(),
+                fired_at: Utc::now(),
+                severity: Severity::Warning,
+                title: "Disk".to_string(),
+                message: "raw".to_string(),
+                machine_id: Some("m1".to_string()),
+                context: serde_json::json!({}),
+            };
+
+            // Unresolved variables do not fail delivery
+            let results = manager.deliver_all(&cx, &alert).await;
+            assert!(results[0].success);
+            let vars = serde_json::json!({
+                "alert": {"title": "Disk"},
+                "machine": {"hostname": "m1.lan"},
+            });
+            let result = manager
+                .deliver_to_with(&cx, "memory", &alert, &vars)
+                .await
+                .unwrap();
+            assert!(result.success);
+
+            let messages: Vec<_> = delivered
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|a| a.message.clone())
+                .collect();
+            assert_eq!(messages, ["Disk on {{machine.hostname}}", "Disk on m1.lan"]);
+        });
+    }
+
+    #[test]
+    fn test_channel_manager_deliver_to_one_channel() {
+        run_async(async {
+            let cx = test_cx();
+            let mut manager = ChannelManager::new();
+            manager.add_channel(Box::new(MemoryChannel::new()));
+            manager.add_channel(Box::new(LogChannel::info()));
+            manager.set_digest(
+                "memory",
+                DigestPolicy {
+                    min_severity: Severity::Critical,
+                    flush_interval: Duration::from_secs(3600),
+                    max_batch_size: 10,
+                },
+            );
+
+            let alert = Alert {
+                id: Some(4),
+                rule_id: "test".to_string(),
+                fired_at: Utc::now(),
+                severity: Severity::Info,
+                title: "Test".to_string(),
+                message: "Test message".to_string(),
+                machine_id: None,
+                context: serde_json::json!({}),
+            };
+
+            // Bypasses the digest the channel would otherwise queue this in
+            let result = manager.deliver_to(&cx, "memory", &alert).await.unwrap();
+            assert!(result.success);
+            assert_eq!(result.alert_ids, vec![4]);
+            assert!(!result.digest);
+            assert_eq!(manager.pending_digest_count(), 0);
+            assert!(manager.deliver_to(&cx, "pager", &alert).await.is_none());
+        });
+    }
+
     #[test]
     fn test_channel_manager_partial_failure() {
         run_async(async {
@@ -1903,4 +2429,54 @@ mod tests {
             assert!(results.is_empty());
         });
     }
+
+    #[test]
+    fn test_channel_manager_digest_batches_and_flushes() {
+        run_async(async {
+            let cx = test_cx();
+            let mut manager = ChannelManager::new();
+            manager.add_channel(Box::new(MemoryChannel::new()));
+            manager.set_digest(
+                "memory",
+                DigestPolicy {
+                    min_severity: Severity::Critical,
+                    flush_interval: Duration::from_secs(1800),
+                    max_batch_size: 10,
+                },
+            );
+
+            let mut alert = Alert {
+                id: Some(1),
+                rule_id: "disk".to_string(),
+                fired_at: Utc::now(),
+                severity: Severity::Warning,
+                title: "Disk".to_string(),
+                message: "Disk filling".to_string(),
+                machine_id: Some("m1".to_string()),
+                context: serde_json::json!({}),
+            };
+            assert!(manager.deliver_all(&cx, &alert).await.is_empty());
+            alert.id = Some(2);
+            assert!(manager.deliver_all(&cx, &alert).await.is_empty());
+            assert_eq!(manager.pending_digest_count(), 2);
+
+            // Critical bypasses the digest
+            alert.id = Some(3);
+            alert.severity = Severity::Critical;
+            let results = manager.deliver_all(&cx, &alert).await;
+            assert_eq!(results.len(), 1);
+            assert!(!results[0].digest);
+            assert_eq!(results[0].alert_ids, vec![3]);
+
+            // Interval not reached yet; shutdown forces the flush
+            assert!(manager.flush_digests(&cx, false).await.is_empty());
+            let flushed = manager.flush_digests(&cx, true).await;
+            assert_eq!(flushed.len(), 1);
+            assert!(flushed[0].digest);
+            assert!(flushed[0].success);
+            assert_eq!(flushed[0].alert_ids, vec![1, 2]);
+            assert_eq!(manager.pending_digest_count(), 0);
+            assert!(manager.flush_digests(&cx, true).await.is_empty());
+        });
+    }
 }
@@ -0,0 +1,310 @@
+//! Per-machine alert rule overrides
+//!
+//! `[[alerts.overrides]]` blocks change a rule's threshold, duration or
+//! severity on the machines they cover without redefining the rule. A rule
+//! resolves for a machine in three layers:
+//!
+//! - the rule as defined
+//! - the one tag override that matches: highest `priority`, equal priorities
+//!   going to the block listed first
+//! - the machine's own override, field by field on top
+//!
+//! The daemon and `vc alert rules test` resolve a rule for the machine a
+//! breach belongs to before judging it, so a rule whose query returns no
+//! `machine_id` is always judged as defined.
+
+use crate::escalation::parse_severity;
+use crate::evaluate::condition_kind;
+use crate::{AlertCondition, AlertRule};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use vc_config::AlertRuleOverride;
+
+/// How overrides combine, for command output
+pub const PRECEDENCE: &str = "machine id > tag > rule; among matching tags the highest \
+    priority wins, then the first listed";
+
+/// One field an override set
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppliedOverride {
+    pub field: &'static str,
+    /// `machine:<id>` or `tag:<tag>`
+    pub scope: String,
+    pub value: Value,
+}
+
+/// A rule as it applies to one machine
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedRule {
+    #[serde(flatten)]
+    pub rule: AlertRule,
+    pub overrides: Vec<AppliedOverride>,
+    /// Override fields this rule's condition has no use for
+    pub ignored: Vec<String>,
+}
+
+impl ResolvedRule {
+    fn apply(&mut self, entry: &AlertRuleOverride) {
+        let scope = entry.scope();
+        let kind = condition_kind(&self.rule.condition);
+
+        if let Some(threshold) = entry.threshold {
+            let applied = match &mut self.rule.condition {
+                AlertCondition::Threshold { value, .. }
+                | AlertCondition::RateOfChange {
+                    threshold_per_sec: value,
+                    ..
+                } => {
+                    *value = threshold;
+                    true
+                }
+                AlertCondition::Pattern { .. } | AlertCondition::Absence { .. } => false,
+            };
+            if applied {
+                self.record("threshold", &scope, json!(threshold));
+            } else {
+                self.ignored
+                    .push(format!("threshold from {scope} ({kind} rule)"));
+            }
+        }
+
+        if let Some(duration) = entry.duration_secs {
+            let applied = match &mut self.rule.condition {
+                AlertCondition::Absence {
+                    max_age_secs: secs, ..
+                }
+                | AlertCondition::RateOfChange {
+                    window_secs: secs, ..
+                } => {
+                    *secs = duration;
+                    true
+                }
+                AlertCondition::Threshold { .. } | AlertCondition::Pattern { .. } => false,
+            };
+            if applied {
+                self.record("duration_secs", &scope, json!(duration));
+            } else {
+                self.ignored
+                    .push(format!("duration_secs from {scope} ({kind} rule)"));
+            }
+        }
+
+        if let Some(severity) = &entry.severity {
+            self.rule.severity = parse_severity(severity);
+            self.record("severity", &scope, json!(self.rule.severity));
+        }
+    }
+
+    /// A later layer replaces what an earlier one set for the same field
+    fn record(&mut self, field: &'static str, scope: &str, value: Value) {
+        self.overrides.retain(|applied| applied.field != field);
+        self.overrides.push(AppliedOverride {
+            field,
+            scope: scope.to_string(),
+            value,
+        });
+    }
+}
+
+/// Configured overrides and the tags of each known machine
+#[derive(Debug, Clone, Default)]
+pub struct RuleOverrides {
+    overrides: Vec<AlertRuleOverride>,
+    machine_tags: HashMap<String, Vec<String>>,
+}
+
+impl RuleOverrides {
+    #[must_use]
+    pub fn new(
+        overrides: Vec<AlertRuleOverride>,
+        machine_tags: HashMap<String, Vec<String>>,
+    ) -> Self {
+        Self {
+            overrides,
+            machine_tags,
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// Tags of `machine_id`; empty for a machine not in the config
+    #[must_use]
+    pub fn tags(&self, machine_id: &str) -> &[String] {
+        self.machine_tags
+            .get(machine_id)
+            .map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Scopes of the overrides configured for `rule_id`, in config order
+    #[must_use]
+    pub fn scopes_for(&self, rule_id: &str) -> Vec<String> {
+        self.overrides
+            .iter()
+            .filter(|entry| entry.rule == rule_id)
+            .map(AlertRuleOverride::scope)
+            .collect()
+    }
+
+    /// Rule IDs overrides name that none of `rules` has
+    #[must_use]
+    pub fn unknown_rules(&self, rules: &[AlertRule]) -> Vec<String> {
+        let mut unknown: Vec<String> = self
+            .overrides
+            .iter()
+            .filter(|entry| !rules.iter().any(|rule| rule.rule_id == entry.rule))
+            .map(|entry| entry.rule.clone())
+            .collect();
+        unknown.sort();
+        unknown.dedup();
+        unknown
+    }
+
+    /// `rule` as it applies to `machine_id`
+    #[must_use]
+    pub fn resolve(&self, rule: &AlertRule, machine_id: &str) -> ResolvedRule {
+        let tags = self.tags(machine_id);
+        let matching = self
+            .overrides
+            .iter()
+            .filter(|entry| entry.rule == rule.rule_id && entry.matches(machine_id, tags));
+
+        let machine = matching.clone().find(|entry| entry.machine.is_some());
+        let tag = matching.filter(|entry| entry.machine.is_none()).fold(
+            None::<&AlertRuleOverride>,
+            |best, entry| match best {
+                Some(best) if best.priority >= entry.priority => Some(best),
+                _ => Some(entry),
+            },
+        );
+
+        let mut resolved = ResolvedRule {
+            rule: rule.clone(),
+            overrides: Vec::new(),
+            ignored: Vec::new(),
+        };
+        for entry in tag.into_iter().chain(machine) {
+            resolved.apply(entry);
+        }
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlertEngine, Severity};
+
+    fn entry(scope: &str, priority: i32, threshold: Option<f64>) -> AlertRuleOverride {
+        let (kind, name) = scope.split_once(':').unwrap();
+        AlertRuleOverride {
+            rule: "disk-critical".to_string(),
+            machine: (kind == "machine").then(|| name.to_string()),
+            tag: (kind == "tag").then(|| name.to_string()),
+            priority,
+            threshold,
+            ..AlertRuleOverride::default()
+        }
+    }
+
+    fn disk_rule() -> AlertRule {
+        AlertEngine::new()
+            .rules()
+            .iter()
+            .find(|rule| rule.rule_id == "disk-critical")
+            .cloned()
+            .unwrap()
+    }
+
+    fn threshold(rule: &AlertRule) -> f64 {
+        match rule.condition {
+            AlertCondition::Threshold { value, .. } => value,
+            _ => panic!("expected a threshold rule"),
+        }
+    }
+
+    #[test]
+    fn test_machine_beats_tag_beats_rule() {
+        let mut severity = entry("tag:build", 0, Some(95.0));
+        severity.severity = Some("warning".to_string());
+        let overrides = RuleOverrides::new(
+            vec![severity, entry("machine:orko", 0, Some(97.0))],
+            HashMap::from([
+                ("orko".to_string(), vec!["build".to_string()]),
+                ("mini".to_string(), vec!["build".to_string()]),
+            ]),
+        );
+        let rule = disk_rule();
+
+        let base = overrides.resolve(&rule, "other");
+        assert!((threshold(&base.rule) - 90.0).abs() < f64::EPSILON);
+        assert!(base.overrides.is_empty());
+
+        let tagged = overrides.resolve(&rule, "mini");
+        assert!((threshold(&tagged.rule) - 95.0).abs() < f64::EPSILON);
+        assert_eq!(tagged.rule.severity, Severity::Warning);
+
+        // The machine's threshold wins; the tag's severity still applies.
+        let machine = overrides.resolve(&rule, "orko");
+        assert!((threshold(&machine.rule) - 97.0).abs() < f64::EPSILON);
+        assert_eq!(machine.rule.severity, Severity::Warning);
+        let scopes: Vec<_> = machine
+            .overrides
+            .iter()
+            .map(|applied| (applied.field, applied.scope.as_str()))
+            .collect();
+        assert_eq!(
+            scopes,
+            [("severity", "tag:build"), ("threshold", "machine:orko")]
+        );
+    }
+
+    #[test]
+    fn test_multiple_tags_resolve_by_priority_then_order() {
+        let overrides = RuleOverrides::new(
+            vec![
+                entry("tag:gpu", 0, Some(80.0)),
+                entry("tag:build", 5, Some(95.0)),
+                entry("tag:ci", 5, Some(85.0)),
+            ],
+            HashMap::from([(
+                "orko".to_string(),
+                vec!["gpu".to_string(), "ci".to_string(), "build".to_string()],
+            )]),
+        );
+        let resolved = overrides.resolve(&disk_rule(), "orko");
+        assert!((threshold(&resolved.rule) - 95.0).abs() < f64::EPSILON);
+        assert_eq!(resolved.overrides[0].scope, "tag:build");
+    }
+
+    #[test]
+    fn test_fields_without_a_target_are_ignored() {
+        let mut duration = entry("machine:orko", 0, None);
+        duration.duration_secs = Some(120);
+        let overrides = RuleOverrides::new(vec![duration], HashMap::new());
+
+        let resolved = overrides.resolve(&disk_rule(), "orko");
+        assert!(resolved.overrides.is_empty());
+        assert_eq!(
+            resolved.ignored,
+            ["duration_secs from machine:orko (threshold rule)"]
+        );
+
+        let mut absence = disk_rule();
+        absence.condition = AlertCondition::Absence {
+            table: "t".to_string(),
+            max_age_secs: 600,
+        };
+        let resolved = overrides.resolve(&absence, "orko");
+        assert!(matches!(
+            resolved.rule.condition,
+            AlertCondition::Absence {
+                max_age_secs: 120,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,128 @@
+//! Template variables for notification messages
+//!
+//! Sinks with a template under `[alerts.templates.<sink>]` send it, rendered
+//! at delivery time, instead of their default formatting. The variables are
+//! the alert itself plus what the store knows about its machine:
+//!
+//! - `machine.id`, `machine.hostname`, `machine.health_score`
+//! - `alert.id`, `alert.rule_id`, `alert.severity`, `alert.title`,
+//!   `alert.message`, `alert.fired_at`, `alert.occurrences`
+//! - `links.web_ui`, `links.alert`
+//!
+//! A variable without a value renders as its literal placeholder and is
+//! logged; it never fails the delivery.
+
+use crate::{Alert, Severity};
+use serde_json::{Value, json};
+use vc_config::Escape;
+use vc_store::{AlertNotificationContext, VcStore};
+
+/// How a sink's payload wants values escaped: webhook templates are usually
+/// JSON bodies and Slack reads `&`, `<` and `>` as markup
+#[must_use]
+pub fn escape_for(sink: &str) -> Escape {
+    match sink {
+        "webhook" => Escape::Json,
+        "slack" => Escape::Html,
+        _ => Escape::None,
+    }
+}
+
+/// Variables for `alert`, with machine context from the store when given
+#[must_use]
+pub fn alert_vars(
+    alert: &Alert,
+    context: Option<&AlertNotificationContext>,
+    web_url: Option<&str>,
+) -> Value {
+    let severity = match alert.severity {
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    };
+    let web_url = web_url.map(|url| url.trim_end_matches('/'));
+    json!({
+        "machine": {
+            "id": alert.machine_id,
+            "hostname": context.and_then(|c| c.hostname.clone()),
+            "health_score": context.and_then(|c| c.health_score),
+        },
+        "alert": {
+            "id": alert.id,
+            "rule_id": alert.rule_id,
+            "severity": severity,
+            "title": alert.title,
+            "message": alert.message,
+            "fired_at": alert.fired_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            // Escalations and breach notices are not rows of their own
+            "occurrences": context.map(|c| c.occurrences.max(1)),
+        },
+        "links": {
+            "web_ui": web_url,
+            "alert": web_url.map(|url| format!("{url}/alerts")),
+        },
+    })
+}
+
+/// [`alert_vars`] with the machine context read from `store`; a store error
+/// is logged and leaves those variables unresolved
+#[must_use]
+pub fn resolve_vars(store: &VcStore, alert: &Alert, web_url: Option<&str>) -> Value {
+    match store.alert_notification_context(&alert.rule_id, alert.machine_id.as_deref()) {
+        Ok(context) => alert_vars(alert, Some(&context), web_url),
+        Err(e) => {
+            tracing::warn!(rule = %alert.rule_id, error = %e, "failed to load notification context");
+            alert_vars(alert, None, web_url)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use vc_config::MessageTemplate;
+
+    fn alert() -> Alert {
+        Alert {
+            id: Some(7),
+            rule_id: "disk".to_string(),
+            fired_at: Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+            severity: Severity::Critical,
+            title: "Disk <full>".to_string(),
+            message: "97% used".to_string(),
+            machine_id: Some("orko".to_string()),
+            context: json!({}),
+        }
+    }
+
+    #[test]
+    fn test_alert_vars_render_with_context() {
+        let context = AlertNotificationContext {
+            hostname: Some("orko.lan".to_string()),
+            health_score: Some(0.42),
+            occurrences: 3,
+        };
+        let template = MessageTemplate::parse(
+            "[{{alert.severity}}] {{alert.title}} on {{machine.hostname}} \
+             ({{machine.health_score}}, x{{alert.occurrences}}) {{links.alert}}",
+        )
+        .unwrap();
+        let vars = alert_vars(&alert(), Some(&context), Some("https://vc.example.com/"));
+        let rendered = template.render(&vars, escape_for("slack"));
+        assert_eq!(
+            rendered.text,
+            "[critical] Disk &lt;full&gt; on orko.lan (0.42, x3) https://vc.example.com/alerts"
+        );
+        assert!(rendered.unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_missing_context_leaves_placeholders() {
+        let template =
+            MessageTemplate::parse("{{machine.id}} {{machine.hostname}} {{links.web_ui}}").unwrap();
+        let rendered = template.render(&alert_vars(&alert(), None, None), Escape::None);
+        assert_eq!(rendered.text, "orko {{machine.hostname}} {{links.web_ui}}");
+        assert_eq!(rendered.unresolved, ["machine.hostname", "links.web_ui"]);
+    }
+}
@@ -0,0 +1,488 @@
+//! Daemon alert delivery
+//!
+//! Alerts are raised into `alert_history` by the collection tick. After each
+//! tick the daemon hands alerts it has not seen yet to the channels configured
+//! under `[alerts]`, and records every attempt in `alert_delivery_log`.
+//!
+//! Sinks listed under `[alerts.digest.<sink>]` batch alerts below their
+//! `min_severity` into one grouped message, sent when the flush interval
+//! elapses or the batch fills up. Critical alerts always go out immediately.
+//! On shutdown every pending digest is flushed rather than dropped.
+//!
+//! The email sink retries failed sends with backoff itself; only the final
+//! outcome (with the attempt count on failure) reaches the log.
+//!
+//! Sinks with a template under `[alerts.templates.<sink>]` send it instead of
+//! their default message, filled in from the store just before delivery.
+//!
+//! Escalation policies (`[[alerts.escalation]]`) run after delivery. Their
+//! `notify` steps go to the named sink at once, bypassing its digest.
+//! Before they run, open alerts are regrouped by probable root cause
+//! (`[alerts.correlation]`) so triage can present each group as one item.
+//!
+//! Incidents that miss an SLA target (`[incidents.sla]`) get an
+//! `sla_breached` timeline event, and `incidents.breach_sink` is told.
+
+use asupersync::Cx;
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+use vc_alert::escalation::EscalationAction;
+use vc_alert::{
+    Alert, AlertChannel, AlertError, ChannelManager, DeliveryResult, DesktopChannel, DigestPolicy,
+    DiscordChannel, EmailChannel, Severity, SlackChannel, WebhookChannel,
+};
+use vc_config::{AlertConfig, CorrelationConfig, DigestConfig, EscalationPolicy, IncidentConfig};
+use vc_query::sla::SlaBreach;
+use vc_store::{FiredAlert, VcStore};
+
+/// Most alerts handed to the channels per tick
+const BATCH_LIMIT: usize = 500;
+
+/// Parse a stored severity; unknown values are treated as warnings
+#[must_use]
+pub fn parse_severity(value: &str) -> Severity {
+    match value.to_lowercase().as_str() {
+        "info" => Severity::Info,
+        "critical" => Severity::Critical,
+        _ => Severity::Warning,
+    }
+}
+
+/// Digest policy for one sink
+#[must_use]
+pub fn digest_policy(config: &DigestConfig) -> DigestPolicy {
+    DigestPolicy {
+        min_severity: parse_severity(&config.min_severity),
+        flush_interval: Duration::from_secs(config.flush_interval_secs),
+        max_batch_size: config.max_batch_size,
+    }
+}
+
+/// Sink names accepted by [`build_channel`], in delivery order
+pub const SINKS: &[&str] = &["webhook", "slack", "discord", "desktop", "email"];
+
+/// Build one sink from `[alerts]`; `Ok(None)` when it is not configured
+///
+/// # Errors
+///
+/// Returns [`AlertError::InvalidConfig`] for an unknown sink or a configured
+/// sink that cannot be set up (e.g. an unreadable SMTP password or an
+/// unresolvable webhook URL reference).
+pub fn build_channel(
+    config: &AlertConfig,
+    sink: &str,
+) -> Result<Option<Box<dyn AlertChannel>>, AlertError> {
+    // Webhook URLs carry their credential, so they may be secret references
+    let url = |key: &str, value: Option<&str>| {
+        value
+            .map(|value| vc_config::resolve_secret(key, value))
+            .transpose()
+            .map_err(|e| AlertError::InvalidConfig(e.to_string()))
+    };
+    let channel: Option<Box<dyn AlertChannel>> = match sink {
+        "webhook" => url("alerts.webhook_url", config.webhook_url.as_deref())?
+            .map(|url| Box::new(WebhookChannel::new(url)) as Box<dyn AlertChannel>),
+        "slack" => url(
+            "alerts.slack_webhook_url",
+            config.slack_webhook_url.as_deref(),
+        )?
+        .map(|url| Box::new(SlackChannel::new(url, Severity::Info)) as Box<dyn AlertChannel>),
+        "discord" => url(
+            "alerts.discord_webhook_url",
+            config.discord_webhook_url.as_deref(),
+        )?
+        .map(|url| Box::new(DiscordChannel::new(url, Severity::Info)) as Box<dyn AlertChannel>),
+        "desktop" => config
+            .desktop_notifications
+            .then(|| Box::new(DesktopChannel::new(Severity::Info)) as Box<dyn AlertChannel>),
+        "email" => match &config.email {
+            Some(email) => Some(Box::new(EmailChannel::from_config(email)?)),
+            None => None,
+        },
+        other => {
+            return Err(AlertError::InvalidConfig(format!(
+                "unknown sink '{other}' (use one of: {})",
+                SINKS.join(", ")
+            )));
+        }
+    };
+    Ok(channel)
+}
+
+/// Build the channels configured under `[alerts]`, with digest policies applied
+///
+/// A sink that fails to build is logged and left out rather than taking the
+/// other sinks down with it.
+#[must_use]
+pub fn build_channel_manager(config: &AlertConfig) -> ChannelManager {
+    let mut manager = ChannelManager::new();
+    if !config.enabled {
+        return manager;
+    }
+
+    for sink in SINKS {
+        match build_channel(config, sink) {
+            Ok(Some(channel)) => manager.add_channel(channel),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(sink, error = %e, "alert sink disabled"),
+        }
+    }
+
+    for (sink, digest) in &config.digest {
+        manager.set_digest(sink, digest_policy(digest));
+    }
+    for (sink, template) in &config.templates {
+        match template.load(sink) {
+            Ok(template) => manager.set_template(sink, template),
+            Err(e) => tracing::warn!(sink, error = %e, "alert template ignored"),
+        }
+    }
+    manager
+}
+
+/// Base URL of the web UI for `{{links.web_ui}}`
+#[must_use]
+pub fn web_url(config: &AlertConfig) -> Option<String> {
+    config.web_url.clone().or_else(|| {
+        config
+            .email
+            .as_ref()
+            .and_then(|email| email.web_url.clone())
+    })
+}
+
+/// Synthetic critical alert for `vc alert test-notify`
+#[must_use]
+pub fn test_alert(machine_id: &str) -> Alert {
+    Alert {
+        id: None,
+        rule_id: "test-notify".to_string(),
+        fired_at: Utc::now(),
+        severity: Severity::Critical,
+        title: "Vibe Cockpit test notification".to_string(),
+        message: "If you can read this, the sink is configured correctly.".to_string(),
+        machine_id: Some(machine_id.to_string()),
+        context: serde_json::json!({ "test": true }),
+    }
+}
+
+/// Convert an `alert_history` row into a deliverable alert
+#[must_use]
+pub fn to_alert(id: i64, fired: &FiredAlert) -> Alert {
+    Alert {
+        id: Some(id),
+        rule_id: fired.rule_id.clone(),
+        fired_at: DateTime::parse_from_rfc3339(&fired.fired_at)
+            .map_or_else(|_| Utc::now(), |ts| ts.with_timezone(&Utc)),
+        severity: parse_severity(&fired.severity),
+        title: fired.title.clone(),
+        message: fired.message.clone(),
+        machine_id: fired.machine_id.clone(),
+        context: fired
+            .context_json
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_else(|| serde_json::json!({})),
+    }
+}
+
+/// Delivers newly fired alerts and pending digests for the daemon loop
+pub struct AlertDispatcher {
+    manager: ChannelManager,
+    watermark: i64,
+    web_url: Option<String>,
+}
+
+impl AlertDispatcher {
+    /// Start after the newest existing alert, so a restart does not resend
+    /// history
+    ///
+    /// # Errors
+    ///
+    /// Returns [`vc_store::StoreError`] if the watermark cannot be read.
+    pub fn new(config: &AlertConfig, store: &VcStore) -> Result<Self, vc_store::StoreError> {
+        Ok(Self {
+            manager: build_channel_manager(config),
+            watermark: store.max_alert_id()?,
+            web_url: web_url(config),
+        })
+    }
+
+    /// Deliver alerts fired since the last call, then any digest that is due
+    pub async fn dispatch(&mut self, store: &VcStore, cx: &Cx) {
+        if self.manager.channel_count() == 0 {
+            return;
+        }
+
+        let fired = match store.list_alerts_after(self.watermark, BATCH_LIMIT) {
+            Ok(fired) => fired,
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to load new alerts for delivery");
+                return;
+            }
+        };
+
+        for (id, row) in &fired {
+            self.watermark = *id;
+            let started = Instant::now();
+            let alert = to_alert(*id, row);
+            let vars = vc_alert::template::resolve_vars(store, &alert, self.web_url.as_deref());
+            let results = self.manager.deliver_all_with(cx, &alert, &vars).await;
+            log_results(store, &results, started);
+        }
+
+        let started = Instant::now();
+        let results = self.manager.flush_digests(cx, false).await;
+        log_results(store, &results, started);
+    }
+
+    /// Apply the escalation policies to open, unacknowledged alert groups and
+    /// deliver the `notify` steps they reach
+    pub async fn escalate(&self, store: &VcStore, policies: &[EscalationPolicy], cx: &Cx) {
+        let escalations = match vc_alert::escalation::escalate(store, policies, Utc::now()) {
+            Ok(escalations) => escalations,
+            Err(e) => {
+                tracing::warn!(error = %e, "alert escalation failed for this tick");
+                return;
+            }
+        };
+
+        for escalation in &escalations {
+            let EscalationAction::Notify { sink } = &escalation.action else {
+                continue;
+            };
+            let started = Instant::now();
+            let alert = escalation.to_alert();
+            let vars = vc_alert::template::resolve_vars(store, &alert, self.web_url.as_deref());
+            match self.manager.deliver_to_with(cx, sink, &alert, &vars).await {
+                Some(result) => log_results(store, &[result], started),
+                None => tracing::warn!(
+                    sink = %sink,
+                    group = %escalation.group_key,
+                    "escalation sink is not configured; notification skipped"
+                ),
+            }
+        }
+    }
+
+    /// Record missed incident SLA targets on the incidents' timelines and
+    /// tell `breach_sink` about each new one
+    pub async fn sla_breaches(&self, store: &VcStore, config: &IncidentConfig, cx: &Cx) {
+        let breaches = match vc_query::sla::record_breaches(store, config, Utc::now()) {
+            Ok(breaches) => breaches,
+            Err(e) => {
+                tracing::warn!(error = %e, "incident SLA check failed for this tick");
+                return;
+            }
+        };
+
+        for breach in &breaches {
+            tracing::info!(
+                incident = %breach.incident_id,
+                milestone = breach.milestone.as_str(),
+                "incident missed its SLA target"
+            );
+            let Some(sink) = &config.breach_sink else {
+                continue;
+            };
+            if self.manager.channel_count() == 0 {
+                continue;
+            }
+            let started = Instant::now();
+            let alert = breach_alert(breach);
+            let vars = vc_alert::template::resolve_vars(store, &alert, self.web_url.as_deref());
+            match self.manager.deliver_to_with(cx, sink, &alert, &vars).await {
+                Some(result) => log_results(store, &[result], started),
+                None => tracing::warn!(
+                    sink = %sink,
+                    incident = %breach.incident_id,
+                    "SLA breach sink is not configured; notification skipped"
+                ),
+            }
+        }
+    }
+
+    /// Flush every pending digest; called once on daemon shutdown
+    pub async fn shutdown(&self, store: &VcStore, cx: &Cx) {
+        let pending = self.manager.pending_digest_count();
+        if pending == 0 {
+            return;
+        }
+
+        let started = Instant::now();
+        let results = self.manager.flush_digests(cx, true).await;
+        log_results(store, &results, started);
+        tracing::info!(
+            pending,
+            digests = results.len(),
+            "flushed pending alert digests"
+        );
+    }
+}
+
+/// The alert sent to `incidents.breach_sink` for a missed SLA target
+#[must_use]
+pub fn breach_alert(breach: &SlaBreach) -> Alert {
+    Alert {
+        id: None,
+        rule_id: format!("incident-sla:{}", breach.milestone.as_str()),
+        fired_at: breach.due_at,
+        severity: parse_severity(&breach.severity),
+        title: format!("Incident SLA breached: {}", breach.title),
+        message: format!(
+            "{} ({}) missed its {} target of {}s, due {}",
+            breach.incident_id,
+            breach.severity,
+            breach.milestone.as_str(),
+            breach.target_secs,
+            breach
+                .due_at
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        ),
+        machine_id: None,
+        context: serde_json::json!({
+            "incident_id": breach.incident_id,
+            "milestone": breach.milestone,
+            "target_secs": breach.target_secs,
+        }),
+    }
+}
+
+/// Run one correlation pass over open alerts, logging rather than failing
+pub fn correlate(store: &VcStore, config: &CorrelationConfig) {
+    match vc_alert::correlation::correlate(store, config) {
+        Ok(report) => {
+            for group in &report.new_roots {
+                tracing::info!(correlation = %group, "correlated alert group has a new probable root");
+            }
+            if report.changed > 0 {
+                tracing::debug!(
+                    groups = report.groups.len(),
+                    changed = report.changed,
+                    "alert correlation updated"
+                );
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "alert correlation failed for this tick"),
+    }
+}
+
+fn log_results(store: &VcStore, results: &[DeliveryResult], started: Instant) {
+    let duration_ms = i64::try_from(started.elapsed().as_millis()).ok();
+    for result in results {
+        let status = if result.success { "success" } else { "failed" };
+        let logged = if result.digest {
+            store.insert_digest_delivery_log(
+                &result.channel,
+                &result.alert_ids,
+                status,
+                result.error.as_deref(),
+                duration_ms,
+            )
+        } else {
+            let alert_id = result
+                .alert_ids
+                .first()
+                .map_or_else(String::new, ToString::to_string);
+            store.insert_delivery_log(
+                &alert_id,
+                &result.channel,
+                status,
+                result.error.as_deref(),
+                duration_ms,
+            )
+        };
+        if let Err(e) = logged {
+            tracing::warn!(channel = %result.channel, error = %e, "failed to log alert delivery");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_channel_manager_from_config() {
+        let mut config = AlertConfig {
+            webhook_url: Some("http://localhost:9/hook".to_string()),
+            desktop_notifications: true,
+            ..AlertConfig::default()
+        };
+        config
+            .digest
+            .insert("webhook".to_string(), DigestConfig::default());
+        assert_eq!(build_channel_manager(&config).channel_count(), 2);
+
+        config.enabled = false;
+        assert_eq!(build_channel_manager(&config).channel_count(), 0);
+    }
+
+    #[test]
+    fn test_web_url_falls_back_to_email() {
+        let mut config = AlertConfig {
+            email: Some(vc_config::EmailConfig {
+                web_url: Some("https://mail.example.com".to_string()),
+                ..vc_config::EmailConfig::default()
+            }),
+            ..AlertConfig::default()
+        };
+        assert_eq!(
+            web_url(&config).as_deref(),
+            Some("https://mail.example.com")
+        );
+        config.web_url = Some("https://vc.example.com".to_string());
+        assert_eq!(web_url(&config).as_deref(), Some("https://vc.example.com"));
+    }
+
+    #[test]
+    fn test_build_channel_per_sink() {
+        let mut config = AlertConfig::default();
+        assert!(build_channel(&config, "email").unwrap().is_none());
+        assert!(build_channel(&config, "pager").is_err());
+
+        config.email = Some(vc_config::EmailConfig {
+            server: "smtp.example.com".to_string(),
+            from: "vc@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            ..vc_config::EmailConfig::default()
+        });
+        let email = build_channel(&config, "email").unwrap().unwrap();
+        assert_eq!(email.name(), "email");
+
+        // A missing password disables only the email sink
+        config.email.as_mut().unwrap().username = Some("vc".to_string());
+        config.email.as_mut().unwrap().password_file = Some("/nonexistent/vc-smtp-password".into());
+        config.desktop_notifications = true;
+        assert!(build_channel(&config, "email").is_err());
+        assert_eq!(build_channel_manager(&config).channel_count(), 1);
+
+        // Webhook URLs may be secret references, resolved when the sink is built
+        config.slack_webhook_url = Some("secret://cmd/echo http://localhost:9/slack".to_string());
+        assert!(build_channel(&config, "slack").unwrap().is_some());
+        config.slack_webhook_url = Some("secret://env/VC_TEST_SLACK_URL_UNSET".to_string());
+        let err = build_channel(&config, "slack").err().unwrap().to_string();
+        assert!(err.contains("alerts.slack_webhook_url"), "{err}");
+    }
+
+    #[test]
+    fn test_to_alert_parses_stored_row() {
+        let alert = to_alert(
+            9,
+            &FiredAlert {
+                rule_id: "disk".to_string(),
+                fired_at: "2026-10-16T12:00:00.000000Z".to_string(),
+                severity: "critical".to_string(),
+                title: "Disk".to_string(),
+                message: "full".to_string(),
+                context_json: Some(r#"{"actual": 97.0}"#.to_string()),
+                machine_id: Some("m1".to_string()),
+            },
+        );
+        assert_eq!(alert.id, Some(9));
+        assert_eq!(alert.severity, Severity::Critical);
+        assert_eq!(alert.fired_at.to_rfc3339(), "2026-10-16T12:00:00+00:00");
+        assert_eq!(alert.context["actual"], 97.0);
+        assert_eq!(parse_severity("bogus"), Severity::Warning);
+    }
+}
@@ -0,0 +1,159 @@
+//! CSV output format (`--format csv`)
+//!
+//! For pasting command output into a spreadsheet:
+//! - An array becomes one record per element; any other value is one record
+//! - Object keys become columns in first-seen order, after a header record;
+//!   `--csv-columns` picks and orders them instead
+//! - Nested objects and arrays are written as compact JSON in a single cell,
+//!   null as an empty cell, and elements that are not objects as a `value`
+//!   column
+//! - Fields are quoted per RFC 4180 when they contain a comma, quote, CR or
+//!   LF, with embedded quotes doubled; records end in CRLF
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+
+/// Column used for array elements and values that are not objects
+const VALUE_COLUMN: &str = "value";
+
+/// Serialize any value to CSV by way of its JSON form
+#[must_use]
+pub fn to_csv_via_json<T: Serialize>(value: &T, columns: Option<&[String]>) -> String {
+    match serde_json::to_value(value) {
+        Ok(json) => to_csv(&json, columns),
+        Err(e) => record(&[format!("serialization failed: {e}")]),
+    }
+}
+
+/// Render `value` as CSV with a header record. `columns` picks and orders
+/// the columns; a named column no record has is left empty.
+#[must_use]
+pub fn to_csv(value: &Value, columns: Option<&[String]>) -> String {
+    let records: Vec<Cow<'_, Map<String, Value>>> = match value {
+        Value::Array(items) => items.iter().map(as_record).collect(),
+        other => vec![as_record(other)],
+    };
+
+    let header: Vec<String> = match columns {
+        Some(columns) => columns.to_vec(),
+        None => {
+            let mut header: Vec<String> = Vec::new();
+            for record in &records {
+                for key in record.keys() {
+                    if !header.contains(key) {
+                        header.push(key.clone());
+                    }
+                }
+            }
+            header
+        }
+    };
+
+    let mut out = record(&header);
+    for fields in &records {
+        let cells: Vec<String> = header
+            .iter()
+            .map(|column| fields.get(column).map(cell).unwrap_or_default())
+            .collect();
+        out.push_str(&record(&cells));
+    }
+    out
+}
+
+fn as_record(value: &Value) -> Cow<'_, Map<String, Value>> {
+    match value {
+        Value::Object(map) => Cow::Borrowed(map),
+        other => {
+            let mut map = Map::new();
+            map.insert(VALUE_COLUMN.to_string(), other.clone());
+            Cow::Owned(map)
+        }
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(_) | Value::Object(_) => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// One CRLF-terminated record
+fn record(fields: &[String]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| escape(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_columns_in_first_seen_order() {
+        let rows = json!([
+            {"machine_id": "orko", "score": 0.9},
+            {"machine_id": "mac-mini", "status": "offline", "score": null},
+        ]);
+        assert_eq!(
+            to_csv(&rows, None),
+            "machine_id,score,status\r\norko,0.9,\r\nmac-mini,,offline\r\n"
+        );
+    }
+
+    #[test]
+    fn test_quoting_per_rfc_4180() {
+        let rows = json!([
+            {"message": "disk full, 98%", "title": "say \"hi\"", "body": "line one\nline two"},
+            {"message": "crlf\r\nhere", "title": "plain", "body": "ünïcødé ✓ 東京"},
+        ]);
+        assert_eq!(
+            to_csv(&rows, None),
+            "message,title,body\r\n\
+             \"disk full, 98%\",\"say \"\"hi\"\"\",\"line one\nline two\"\r\n\
+             \"crlf\r\nhere\",plain,ünïcødé ✓ 東京\r\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_values_are_compact_json() {
+        let rows = json!([{"id": 1, "tags": ["a", "b"], "meta": {"k": "v, w"}}]);
+        assert_eq!(
+            to_csv(&rows, None),
+            "id,tags,meta\r\n1,\"[\"\"a\"\",\"\"b\"\"]\",\"{\"\"k\"\":\"\"v, w\"\"}\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_selected_columns_pick_and_order() {
+        let rows = json!([{"a": 1, "b": 2, "c": 3}]);
+        let columns = vec!["c".to_string(), "a".to_string(), "missing".to_string()];
+        assert_eq!(to_csv(&rows, Some(&columns)), "c,a,missing\r\n3,1,\r\n");
+    }
+
+    #[test]
+    fn test_single_object_and_scalars() {
+        assert_eq!(
+            to_csv(&json!({"status": "ok", "rows": 3}), None),
+            "status,rows\r\nok,3\r\n"
+        );
+        assert_eq!(to_csv(&json!(["x", true]), None), "value\r\nx\r\ntrue\r\n");
+        assert_eq!(to_csv(&json!([]), None), "\r\n");
+    }
+}
@@ -0,0 +1,539 @@
+//! Daemon resource self-limits
+//!
+//! The daemon checks its own resident memory and the free space on the store
+//! volume once per cycle, against `[daemon.limits]`:
+//!
+//! - **Memory**: while RSS stays above `max_rss_mb` the guard escalates one
+//!   step per cycle — pause transcript collectors, then stretch the poll
+//!   interval, then refuse ingest. Once RSS falls below
+//!   `recovery_ratio * max_rss_mb` it steps back down one level per cycle.
+//! - **Disk**: while free space is below `min_free_disk_mb` every write except
+//!   alerts is paused. This clears as soon as space is available again.
+//!
+//! The latest state is persisted to `daemon_resource_state` so that
+//! `vc ingest`, `vc daemon check` and `vc robot health` — which run in other
+//! processes — can see it.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use vc_config::DaemonLimitsConfig;
+use vc_store::{DaemonResourceState, FiredAlert, VcStore};
+
+/// Collectors that ingest session transcripts; the first load to shed.
+pub const TRANSCRIPT_COLLECTORS: &[&str] = &["cass", "mcp_agent_mail"];
+
+/// Alert rule raised while the daemon is shedding load for memory
+pub const MEMORY_ALERT_RULE: &str = "daemon-memory-pressure";
+
+/// Alert rule raised while writes are paused for low disk
+pub const DISK_ALERT_RULE: &str = "daemon-disk-pressure";
+
+/// Persisted state older than this is ignored: the daemon that wrote it is
+/// probably no longer running.
+pub const STATE_MAX_AGE: Duration = Duration::from_secs(15 * 60);
+
+/// Load-shedding step, in the order steps are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradationLevel {
+    Normal,
+    TranscriptsPaused,
+    PollingStretched,
+    IngestPaused,
+}
+
+impl DegradationLevel {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::TranscriptsPaused => "transcripts_paused",
+            Self::PollingStretched => "polling_stretched",
+            Self::IngestPaused => "ingest_paused",
+        }
+    }
+
+    #[must_use]
+    fn escalate(self) -> Self {
+        match self {
+            Self::Normal => Self::TranscriptsPaused,
+            Self::TranscriptsPaused => Self::PollingStretched,
+            Self::PollingStretched | Self::IngestPaused => Self::IngestPaused,
+        }
+    }
+
+    #[must_use]
+    fn relax(self) -> Self {
+        match self {
+            Self::Normal | Self::TranscriptsPaused => Self::Normal,
+            Self::PollingStretched => Self::TranscriptsPaused,
+            Self::IngestPaused => Self::PollingStretched,
+        }
+    }
+}
+
+impl std::str::FromStr for DegradationLevel {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "normal" => Ok(Self::Normal),
+            "transcripts_paused" => Ok(Self::TranscriptsPaused),
+            "polling_stretched" => Ok(Self::PollingStretched),
+            "ingest_paused" => Ok(Self::IngestPaused),
+            other => Err(format!("unknown degradation level: {other}")),
+        }
+    }
+}
+
+/// One reading of the daemon's own resource use
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ResourceSample {
+    pub rss_mb: Option<u64>,
+    pub free_disk_mb: Option<u64>,
+}
+
+impl ResourceSample {
+    /// Read the current process RSS and the free space under `store_path`.
+    ///
+    /// Either value is `None` when it cannot be determined on this platform;
+    /// a missing reading never triggers load shedding.
+    #[must_use]
+    pub fn read(store_path: &Path) -> Self {
+        let rss_mb = std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| parse_vm_rss_mb(&status));
+
+        let dir = store_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let free_disk_mb = std::process::Command::new("df")
+            .arg("-Pk")
+            .arg(dir)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .and_then(|out| parse_df_available_mb(&String::from_utf8_lossy(&out.stdout)));
+
+        Self {
+            rss_mb,
+            free_disk_mb,
+        }
+    }
+}
+
+/// Parse `VmRSS` (kB) from `/proc/self/status` into MiB
+#[must_use]
+pub fn parse_vm_rss_mb(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+/// Parse the available column (kB) of `df -Pk <path>` into MiB
+#[must_use]
+pub fn parse_df_available_mb(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb / 1024)
+}
+
+/// What changed on the last [`ResourceGuard::evaluate`] call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardTransition {
+    pub previous_level: DegradationLevel,
+    pub level: DegradationLevel,
+    pub disk_pressure_started: bool,
+    pub disk_pressure_cleared: bool,
+}
+
+/// Tracks the daemon's degradation level across cycles
+#[derive(Debug, Clone)]
+pub struct ResourceGuard {
+    limits: DaemonLimitsConfig,
+    level: DegradationLevel,
+    disk_pressure: bool,
+    last_sample: ResourceSample,
+    watch_subscribers: Option<usize>,
+}
+
+impl ResourceGuard {
+    #[must_use]
+    pub fn new(limits: DaemonLimitsConfig) -> Self {
+        Self {
+            limits,
+            level: DegradationLevel::Normal,
+            disk_pressure: false,
+            last_sample: ResourceSample::default(),
+            watch_subscribers: None,
+        }
+    }
+
+    /// Subscriber count reported with the next persisted state; `None` when
+    /// the daemon is not serving a watch socket.
+    pub fn set_watch_subscribers(&mut self, count: Option<usize>) {
+        self.watch_subscribers = count;
+    }
+
+    #[must_use]
+    pub fn level(&self) -> DegradationLevel {
+        self.level
+    }
+
+    #[must_use]
+    pub fn disk_pressure(&self) -> bool {
+        self.disk_pressure
+    }
+
+    /// Fold a new sample into the guard state.
+    pub fn evaluate(&mut self, sample: ResourceSample) -> GuardTransition {
+        let previous_level = self.level;
+        let previous_disk = self.disk_pressure;
+        self.last_sample = sample;
+
+        if let (Some(max), Some(rss)) = (self.limits.max_rss_mb, sample.rss_mb) {
+            #[allow(
+                clippy::cast_precision_loss,
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss
+            )]
+            let recovery = (max as f64 * self.limits.recovery_ratio) as u64;
+            if rss > max {
+                self.level = self.level.escalate();
+            } else if rss < recovery {
+                self.level = self.level.relax();
+            }
+        } else {
+            self.level = DegradationLevel::Normal;
+        }
+
+        self.disk_pressure = match (self.limits.min_free_disk_mb, sample.free_disk_mb) {
+            (Some(min), Some(free)) => free < min,
+            _ => false,
+        };
+
+        GuardTransition {
+            previous_level,
+            level: self.level,
+            disk_pressure_started: self.disk_pressure && !previous_disk,
+            disk_pressure_cleared: !self.disk_pressure && previous_disk,
+        }
+    }
+
+    /// Whether a collector should be skipped this cycle
+    #[must_use]
+    pub fn skip_collector(&self, name: &str) -> bool {
+        self.disk_pressure
+            || (self.level >= DegradationLevel::TranscriptsPaused
+                && TRANSCRIPT_COLLECTORS.contains(&name))
+    }
+
+    /// Whether collected rows should be persisted this cycle
+    #[must_use]
+    pub fn writes_paused(&self) -> bool {
+        self.disk_pressure
+    }
+
+    /// Poll interval to use given the configured base interval
+    #[must_use]
+    pub fn poll_interval(&self, base: Duration) -> Duration {
+        if self.level >= DegradationLevel::PollingStretched {
+            base.saturating_mul(self.limits.poll_stretch_factor)
+        } else {
+            base
+        }
+    }
+
+    /// Snapshot of the guard for persistence
+    #[must_use]
+    pub fn state(&self) -> DaemonResourceState {
+        let mut reasons = Vec::new();
+        if self.level > DegradationLevel::Normal
+            && let (Some(rss), Some(max)) = (self.last_sample.rss_mb, self.limits.max_rss_mb)
+        {
+            reasons.push(format!("rss {rss} MiB (limit {max} MiB)"));
+        }
+        if self.disk_pressure
+            && let (Some(free), Some(min)) =
+                (self.last_sample.free_disk_mb, self.limits.min_free_disk_mb)
+        {
+            reasons.push(format!("free disk {free} MiB (minimum {min} MiB)"));
+        }
+
+        DaemonResourceState {
+            checked_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            level: self.level.as_str().to_string(),
+            disk_pressure: self.disk_pressure,
+            rss_mb: to_i64(self.last_sample.rss_mb),
+            free_disk_mb: to_i64(self.last_sample.free_disk_mb),
+            max_rss_mb: to_i64(self.limits.max_rss_mb),
+            min_free_disk_mb: to_i64(self.limits.min_free_disk_mb),
+            reason: (!reasons.is_empty()).then(|| reasons.join("; ")),
+            watch_subscribers: self
+                .watch_subscribers
+                .map(|count| i64::try_from(count).unwrap_or(i64::MAX)),
+        }
+    }
+}
+
+fn to_i64(value: Option<u64>) -> Option<i64> {
+    value.map(|v| i64::try_from(v).unwrap_or(i64::MAX))
+}
+
+/// Persist the guard state and raise or resolve the matching alerts.
+///
+/// Alerts are written even under disk pressure: they are the one write the
+/// guard never pauses.
+pub fn record_transition(store: &VcStore, guard: &ResourceGuard, transition: GuardTransition) {
+    let state = guard.state();
+    let reason = state.reason.clone().unwrap_or_default();
+
+    if transition.level != transition.previous_level {
+        if transition.level > transition.previous_level {
+            tracing::warn!(
+                from = transition.previous_level.as_str(),
+                to = transition.level.as_str(),
+                reason = %reason,
+                "daemon memory over limit; shedding load"
+            );
+        } else {
+            tracing::info!(
+                from = transition.previous_level.as_str(),
+                to = transition.level.as_str(),
+                "daemon memory pressure easing; restoring load"
+            );
+        }
+
+        if transition.level == DegradationLevel::Normal {
+            resolve(store, MEMORY_ALERT_RULE);
+        } else {
+            raise(
+                store,
+                MEMORY_ALERT_RULE,
+                "warning",
+                "Daemon shedding load (memory)",
+                &format!("Daemon degraded to {}: {reason}", transition.level.as_str()),
+            );
+        }
+    }
+
+    if transition.disk_pressure_started {
+        tracing::error!(reason = %reason, "store volume low on space; pausing writes");
+        raise(
+            store,
+            DISK_ALERT_RULE,
+            "critical",
+            "Daemon paused writes (disk)",
+            &format!("All writes except alerts are paused: {reason}"),
+        );
+    }
+    if transition.disk_pressure_cleared {
+        tracing::info!("store volume has space again; resuming writes");
+        resolve(store, DISK_ALERT_RULE);
+    }
+
+    if let Err(e) = store.upsert_daemon_resource_state(&state) {
+        tracing::warn!(error = %e, "daemon resource state persist failed");
+    }
+}
+
+/// Reason `vc ingest` should refuse a bundle, given the daemon's last
+/// recorded state, or `None` if ingest may proceed.
+#[must_use]
+pub fn ingest_refusal(state: &DaemonResourceState, now: DateTime<Utc>) -> Option<String> {
+    let checked_at = DateTime::parse_from_rfc3339(&state.checked_at).ok()?;
+    let age = now
+        .signed_duration_since(checked_at)
+        .to_std()
+        .unwrap_or_default();
+    if age > STATE_MAX_AGE {
+        return None;
+    }
+
+    let reason = state.reason.as_deref().unwrap_or("resource limit exceeded");
+    if state.disk_pressure {
+        Some(format!("daemon has paused writes for low disk: {reason}"))
+    } else if state.level == DegradationLevel::IngestPaused.as_str() {
+        Some(format!("daemon has paused ingest for memory: {reason}"))
+    } else {
+        None
+    }
+}
+
+fn raise(store: &VcStore, rule_id: &str, severity: &str, title: &str, message: &str) {
+    match store.has_open_alert(rule_id, None) {
+        Ok(true) => {}
+        Ok(false) => {
+            let alert = FiredAlert {
+                rule_id: rule_id.to_string(),
+                fired_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+                severity: severity.to_string(),
+                title: title.to_string(),
+                message: message.to_string(),
+                context_json: None,
+                machine_id: None,
+            };
+            if let Err(e) = store.insert_alert(&alert) {
+                tracing::warn!(rule = rule_id, error = %e, "daemon alert persist failed");
+            }
+        }
+        Err(e) => tracing::warn!(rule = rule_id, error = %e, "daemon alert lookup failed"),
+    }
+}
+
+fn resolve(store: &VcStore, rule_id: &str) {
+    if let Err(e) = store.resolve_open_alerts(rule_id) {
+        tracing::warn!(rule = rule_id, error = %e, "daemon alert resolve failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_rss_mb: Option<u64>, min_free_disk_mb: Option<u64>) -> DaemonLimitsConfig {
+        DaemonLimitsConfig {
+            max_rss_mb,
+            min_free_disk_mb,
+            ..DaemonLimitsConfig::default()
+        }
+    }
+
+    fn sample(rss_mb: u64, free_disk_mb: u64) -> ResourceSample {
+        ResourceSample {
+            rss_mb: Some(rss_mb),
+            free_disk_mb: Some(free_disk_mb),
+        }
+    }
+
+    #[test]
+    fn test_parse_vm_rss() {
+        let status = "Name:\tvc\nVmPeak:\t  900000 kB\nVmRSS:\t  524288 kB\nThreads:\t4\n";
+        assert_eq!(parse_vm_rss_mb(status), Some(512));
+        assert_eq!(parse_vm_rss_mb("Name:\tvc\n"), None);
+    }
+
+    #[test]
+    fn test_parse_df_available() {
+        let output = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                      /dev/sda1 41152736 20000000 2097152 50% /\n";
+        assert_eq!(parse_df_available_mb(output), Some(2048));
+        assert_eq!(parse_df_available_mb(""), None);
+    }
+
+    #[test]
+    fn test_memory_sheds_in_order_and_recovers() {
+        let mut guard = ResourceGuard::new(limits(Some(100), None));
+
+        guard.evaluate(sample(150, 0));
+        assert_eq!(guard.level(), DegradationLevel::TranscriptsPaused);
+        assert!(guard.skip_collector("cass"));
+        assert!(!guard.skip_collector("sysmoni"));
+        assert_eq!(
+            guard.poll_interval(Duration::from_secs(60)),
+            Duration::from_secs(60)
+        );
+
+        guard.evaluate(sample(150, 0));
+        assert_eq!(guard.level(), DegradationLevel::PollingStretched);
+        assert_eq!(
+            guard.poll_interval(Duration::from_secs(60)),
+            Duration::from_secs(240)
+        );
+
+        guard.evaluate(sample(150, 0));
+        assert_eq!(guard.level(), DegradationLevel::IngestPaused);
+        guard.evaluate(sample(150, 0));
+        assert_eq!(guard.level(), DegradationLevel::IngestPaused);
+
+        // Between the recovery threshold and the limit: hold steady
+        guard.evaluate(sample(95, 0));
+        assert_eq!(guard.level(), DegradationLevel::IngestPaused);
+
+        guard.evaluate(sample(50, 0));
+        assert_eq!(guard.level(), DegradationLevel::PollingStretched);
+        guard.evaluate(sample(50, 0));
+        guard.evaluate(sample(50, 0));
+        assert_eq!(guard.level(), DegradationLevel::Normal);
+    }
+
+    #[test]
+    fn test_disk_pressure_pauses_writes() {
+        let mut guard = ResourceGuard::new(limits(None, Some(1024)));
+
+        let transition = guard.evaluate(sample(10, 512));
+        assert!(transition.disk_pressure_started);
+        assert!(guard.writes_paused());
+        assert!(guard.skip_collector("sysmoni"));
+
+        let transition = guard.evaluate(sample(10, 4096));
+        assert!(transition.disk_pressure_cleared);
+        assert!(!guard.writes_paused());
+    }
+
+    #[test]
+    fn test_ingest_refusal_respects_level_and_age() {
+        let mut guard = ResourceGuard::new(limits(Some(100), None));
+        for _ in 0..3 {
+            guard.evaluate(sample(150, 0));
+        }
+        assert_eq!(guard.level(), DegradationLevel::IngestPaused);
+
+        let state = guard.state();
+        let now = Utc::now();
+        assert!(ingest_refusal(&state, now).is_some());
+
+        let later = now + chrono::Duration::hours(1);
+        assert!(ingest_refusal(&state, later).is_none());
+
+        let mut normal = state;
+        normal.level = DegradationLevel::PollingStretched.as_str().to_string();
+        assert!(ingest_refusal(&normal, now).is_none());
+    }
+
+    #[test]
+    fn test_no_limits_never_degrades() {
+        let mut guard = ResourceGuard::new(DaemonLimitsConfig::default());
+        guard.evaluate(sample(u64::MAX, 0));
+        assert_eq!(guard.level(), DegradationLevel::Normal);
+        assert!(!guard.disk_pressure());
+    }
+
+    #[test]
+    fn test_record_transition_raises_and_resolves_alerts() {
+        let store = VcStore::open_memory().unwrap();
+        let mut guard = ResourceGuard::new(limits(Some(100), Some(1024)));
+
+        let transition = guard.evaluate(sample(200, 100));
+        record_transition(&store, &guard, transition);
+        assert!(store.has_open_alert(MEMORY_ALERT_RULE, None).unwrap());
+        assert!(store.has_open_alert(DISK_ALERT_RULE, None).unwrap());
+
+        let state = store.get_daemon_resource_state().unwrap().unwrap();
+        assert_eq!(state.level, "transcripts_paused");
+        assert!(state.disk_pressure);
+
+        let transition = guard.evaluate(sample(10, 4096));
+        record_transition(&store, &guard, transition);
+        assert!(!store.has_open_alert(MEMORY_ALERT_RULE, None).unwrap());
+        assert!(!store.has_open_alert(DISK_ALERT_RULE, None).unwrap());
+    }
+
+    #[test]
+    fn test_degradation_level_from_str() {
+        assert_eq!(
+            "ingest_paused".parse::<DegradationLevel>().unwrap(),
+            DegradationLevel::IngestPaused
+        );
+        assert!("bogus".parse::<DegradationLevel>().is_err());
+    }
+}
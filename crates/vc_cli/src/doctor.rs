@@ -0,0 +1,782 @@
+//! `vc doctor`: diagnose common setup failures
+//!
+//! Most "vc shows nothing" reports come down to a handful of causes: an
+//! unwritable or wrong store path, an SSH agent that is not running, private
+//! keys with loose permissions, clock skew, or a daemon that has stopped.
+//! [`Doctor`] runs a battery of [`DoctorCheck`]s against the loaded config and
+//! reports each as pass, warn or fail with a one-line remediation hint.
+//!
+//! Checks are trait objects so features can add their own with
+//! [`Doctor::register`].
+
+use asupersync::Cx;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use vc_collect::executor::{Executor, SshConfig};
+use vc_config::{LintSeverity, MachineConfig, VcConfig};
+use vc_query::components::{self, ComponentHealth, ComponentStatus};
+use vc_store::{DaemonResourceState, VcStore};
+
+use crate::daemon_limits::ResourceSample;
+
+/// Free space below which the disk check fails when
+/// `daemon.limits.min_free_disk_mb` is unset
+pub const DEFAULT_MIN_FREE_DISK_MB: u64 = 1024;
+
+/// Clock skew between hub and machine beyond which the skew check fails
+pub const MAX_CLOCK_SKEW_SECS: i64 = 30;
+
+/// Timeout for the reachability probe of one machine
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    #[must_use]
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Pass => "✓",
+            Self::Warn => "⚠",
+            Self::Fail => "✗",
+        }
+    }
+}
+
+/// One line of the doctor report
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    /// Check name, suffixed with `:<machine>` for per-machine checks
+    pub check: String,
+    pub status: CheckStatus,
+    pub message: String,
+    /// How to fix a warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl CheckResult {
+    #[must_use]
+    pub fn pass(check: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            status: CheckStatus::Pass,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    #[must_use]
+    pub fn warn(
+        check: impl Into<String>,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.into(),
+            status: CheckStatus::Warn,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    #[must_use]
+    pub fn fail(
+        check: impl Into<String>,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+    ) -> Self {
+        Self {
+            check: check.into(),
+            status: CheckStatus::Fail,
+            message: message.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Everything a check may inspect
+pub struct DoctorContext<'a> {
+    pub config: &'a VcConfig,
+    /// Store opened from `global.db_path`, or the reason it would not open
+    pub store: Result<&'a VcStore, &'a str>,
+    /// Restrict machine checks to this machine ID
+    pub machine: Option<&'a str>,
+    pub now: DateTime<Utc>,
+}
+
+impl DoctorContext<'_> {
+    /// Enabled machines with an SSH host, honoring the `--machine` filter,
+    /// sorted by ID
+    #[must_use]
+    pub fn remote_machines(&self) -> Vec<(&str, &MachineConfig)> {
+        let mut machines: Vec<(&str, &MachineConfig)> = self
+            .config
+            .machines
+            .iter()
+            .filter(|(id, machine)| {
+                machine.enabled
+                    && machine.ssh_host.is_some()
+                    && self.machine.is_none_or(|wanted| wanted == id.as_str())
+            })
+            .map(|(id, machine)| (id.as_str(), machine))
+            .collect();
+        machines.sort_by_key(|(id, _)| *id);
+        machines
+    }
+}
+
+/// A diagnostic run by `vc doctor`
+#[async_trait]
+pub trait DoctorCheck: Send + Sync {
+    /// Short name shown in the report
+    fn name(&self) -> &'static str;
+
+    /// Run the check; per-machine checks return one result per machine
+    async fn run(&self, cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult>;
+}
+
+/// Results of a doctor run
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<CheckResult>,
+    pub passed: usize,
+    pub warned: usize,
+    pub failed: usize,
+}
+
+impl DoctorReport {
+    #[must_use]
+    pub fn new(checks: Vec<CheckResult>) -> Self {
+        let count = |status| checks.iter().filter(|c| c.status == status).count();
+        Self {
+            passed: count(CheckStatus::Pass),
+            warned: count(CheckStatus::Warn),
+            failed: count(CheckStatus::Fail),
+            checks,
+        }
+    }
+
+    /// Whether no check failed
+    #[must_use]
+    pub fn ok(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// Human-readable report, one line per result plus its hint
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let width = self.checks.iter().map(|c| c.check.len()).max().unwrap_or(0);
+        let mut out = String::new();
+        for result in &self.checks {
+            let _ = writeln!(
+                out,
+                "{} {:width$}  {}",
+                result.status.icon(),
+                result.check,
+                result.message
+            );
+            if let Some(hint) = &result.hint {
+                let _ = writeln!(out, "  {:width$}  → {hint}", "");
+            }
+        }
+        let _ = write!(
+            out,
+            "\n{} passed, {} warnings, {} failed",
+            self.passed, self.warned, self.failed
+        );
+        out
+    }
+}
+
+/// Runs registered checks in order
+#[derive(Default)]
+pub struct Doctor {
+    checks: Vec<Box<dyn DoctorCheck>>,
+}
+
+impl Doctor {
+    /// A doctor with no checks
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in battery: config, store, daemon, ingest, notifications,
+    /// replication, disk, web port, SSH credentials, then reachability and
+    /// clock skew per machine
+    #[must_use]
+    pub fn with_default_checks() -> Self {
+        let mut doctor = Self::new();
+        doctor
+            .register(Box::new(ConfigCheck))
+            .register(Box::new(StoreCheck))
+            .register(Box::new(DaemonHeartbeatCheck))
+            .register(Box::new(ComponentCheck("ingest")))
+            .register(Box::new(ComponentCheck("notifications")))
+            .register(Box::new(ComponentCheck("replication")))
+            .register(Box::new(DiskSpaceCheck))
+            .register(Box::new(WebPortCheck))
+            .register(Box::new(SshCredentialsCheck))
+            .register(Box::new(MachineProbeCheck));
+        doctor
+    }
+
+    /// Add a check to the end of the battery
+    pub fn register(&mut self, check: Box<dyn DoctorCheck>) -> &mut Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Names of the registered checks, in run order
+    #[must_use]
+    pub fn check_names(&self) -> Vec<&'static str> {
+        self.checks.iter().map(|check| check.name()).collect()
+    }
+
+    /// Run every check and collect the results
+    pub async fn run(&self, cx: &Cx, ctx: &DoctorContext<'_>) -> DoctorReport {
+        let mut results = Vec::new();
+        for check in &self.checks {
+            results.extend(check.run(cx, ctx).await);
+        }
+        DoctorReport::new(results)
+    }
+}
+
+// ============================================================================
+// Built-in checks
+// ============================================================================
+
+/// Config loads (the caller only builds a context once it has) and lints clean
+pub struct ConfigCheck;
+
+#[async_trait]
+impl DoctorCheck for ConfigCheck {
+    fn name(&self) -> &'static str {
+        "config"
+    }
+
+    async fn run(&self, _cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        let lint = ctx.config.lint();
+        let first = |severity| {
+            lint.issues
+                .iter()
+                .find(|issue| issue.severity == severity)
+                .map(|issue| format!("{}: {}", issue.path, issue.message))
+                .unwrap_or_default()
+        };
+        let result = if lint.error_count > 0 {
+            CheckResult::fail(
+                self.name(),
+                format!(
+                    "{} lint error(s); first: {}",
+                    lint.error_count,
+                    first(LintSeverity::Error)
+                ),
+                "run `vc config lint` and fix the reported errors",
+            )
+        } else if lint.warning_count > 0 {
+            CheckResult::warn(
+                self.name(),
+                format!(
+                    "{} lint warning(s); first: {}",
+                    lint.warning_count,
+                    first(LintSeverity::Warning)
+                ),
+                "run `vc config lint` for details and suggested fixes",
+            )
+        } else {
+            CheckResult::pass(self.name(), "config loads and lints clean")
+        };
+        vec![result]
+    }
+}
+
+impl From<ComponentHealth> for CheckResult {
+    fn from(health: ComponentHealth) -> Self {
+        let status = match health.effective_status() {
+            ComponentStatus::Ok => CheckStatus::Pass,
+            ComponentStatus::Degraded => CheckStatus::Warn,
+            ComponentStatus::Failed => CheckStatus::Fail,
+        };
+        let message = if health.ignored {
+            format!("{} (ignored)", health.message)
+        } else {
+            health.message
+        };
+        Self {
+            check: health.component.to_string(),
+            status,
+            message,
+            hint: health.hint.filter(|_| status != CheckStatus::Pass),
+        }
+    }
+}
+
+/// Store opens, its schema is current, and it holds collected data
+pub struct StoreCheck;
+
+#[async_trait]
+impl DoctorCheck for StoreCheck {
+    fn name(&self) -> &'static str {
+        "store"
+    }
+
+    async fn run(&self, cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        ComponentCheck(self.name()).run(cx, ctx).await
+    }
+}
+
+/// Daemon has recorded a heartbeat recently
+pub struct DaemonHeartbeatCheck;
+
+impl DaemonHeartbeatCheck {
+    /// Judge the daemon's last recorded state at `now`, as
+    /// `GET /api/health/components` does
+    #[must_use]
+    pub fn evaluate(
+        state: Option<&DaemonResourceState>,
+        config: &VcConfig,
+        now: DateTime<Utc>,
+    ) -> CheckResult {
+        components::daemon_health(state, config, now).into()
+    }
+}
+
+#[async_trait]
+impl DoctorCheck for DaemonHeartbeatCheck {
+    fn name(&self) -> &'static str {
+        "daemon"
+    }
+
+    async fn run(&self, cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        ComponentCheck(self.name()).run(cx, ctx).await
+    }
+}
+
+/// One of the components behind `GET /api/health/components` (ingest
+/// backlog, notification delivery, replication lag), judged the same way
+/// and with the same `[health_components]` weights and ignores
+pub struct ComponentCheck(pub &'static str);
+
+#[async_trait]
+impl DoctorCheck for ComponentCheck {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    async fn run(&self, _cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        components::evaluate_component(self.0, ctx.store, ctx.config, ctx.now)
+            .map(CheckResult::from)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Enough free space on the store volume
+pub struct DiskSpaceCheck;
+
+impl DiskSpaceCheck {
+    /// Fail below the minimum, warn below twice the minimum
+    #[must_use]
+    pub fn evaluate(free_mb: Option<u64>, min_free_mb: u64) -> CheckResult {
+        let name = "disk";
+        match free_mb {
+            None => CheckResult::warn(
+                name,
+                "could not determine free space on the store volume",
+                "check `df -h` on the directory of global.db_path",
+            ),
+            Some(free) if free < min_free_mb => CheckResult::fail(
+                name,
+                format!("{free} MiB free on the store volume (minimum {min_free_mb} MiB)"),
+                "free space or move global.db_path to a larger volume; `vc vacuum` drops expired rows",
+            ),
+            Some(free) if free < min_free_mb.saturating_mul(2) => CheckResult::warn(
+                name,
+                format!("{free} MiB free on the store volume (minimum {min_free_mb} MiB)"),
+                "free space soon; `vc vacuum` drops expired rows",
+            ),
+            Some(free) => CheckResult::pass(name, format!("{free} MiB free on the store volume")),
+        }
+    }
+}
+
+#[async_trait]
+impl DoctorCheck for DiskSpaceCheck {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    async fn run(&self, _cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        let sample = ResourceSample::read(&ctx.config.global.db_path);
+        let min_free = ctx
+            .config
+            .daemon
+            .limits
+            .min_free_disk_mb
+            .unwrap_or(DEFAULT_MIN_FREE_DISK_MB);
+        vec![Self::evaluate(sample.free_disk_mb, min_free)]
+    }
+}
+
+/// Web port can be bound when the web dashboard is enabled
+pub struct WebPortCheck;
+
+#[async_trait]
+impl DoctorCheck for WebPortCheck {
+    fn name(&self) -> &'static str {
+        "web"
+    }
+
+    async fn run(&self, _cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        let web = &ctx.config.web;
+        if !web.enabled {
+            return vec![CheckResult::pass(self.name(), "web dashboard disabled")];
+        }
+        let addr = format!("{}:{}", web.bind_address, web.port);
+        let result = match std::net::TcpListener::bind(&addr) {
+            Ok(_) => CheckResult::pass(self.name(), format!("{addr} is bindable")),
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => CheckResult::warn(
+                self.name(),
+                format!("{addr} is already in use"),
+                "fine if `vc web` is already running; otherwise change web.port",
+            ),
+            Err(e) => CheckResult::fail(
+                self.name(),
+                format!("cannot bind {addr}: {e}"),
+                "check web.bind_address is a local address and web.port is allowed",
+            ),
+        };
+        vec![result]
+    }
+}
+
+/// SSH keys exist with private permissions; an agent is running when keys
+/// are left to it
+pub struct SshCredentialsCheck;
+
+impl SshCredentialsCheck {
+    /// Judge a private key file's Unix permission bits
+    #[must_use]
+    pub fn evaluate_key_mode(machine_id: &str, key: &Path, mode: u32) -> CheckResult {
+        let name = format!("ssh_key:{machine_id}");
+        if mode & 0o077 == 0 {
+            CheckResult::pass(name, format!("{} is private", key.display()))
+        } else {
+            CheckResult::fail(
+                name,
+                format!(
+                    "{} is accessible by group or others (mode {:o})",
+                    key.display(),
+                    mode & 0o777
+                ),
+                format!("chmod 600 {}", key.display()),
+            )
+        }
+    }
+
+    fn check_key(machine_id: &str, key: &Path) -> CheckResult {
+        let key = vc_config::expand_path(key);
+        let metadata = match std::fs::metadata(&key) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                return CheckResult::fail(
+                    format!("ssh_key:{machine_id}"),
+                    format!("cannot read {}: {e}", key.display()),
+                    format!("fix machines.{machine_id}.ssh_key or create the key"),
+                );
+            }
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            Self::evaluate_key_mode(machine_id, &key, metadata.permissions().mode())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = metadata;
+            CheckResult::pass(
+                format!("ssh_key:{machine_id}"),
+                format!("{} exists", key.display()),
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl DoctorCheck for SshCredentialsCheck {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    async fn run(&self, _cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        let machines = ctx.remote_machines();
+        let mut results: Vec<CheckResult> = machines
+            .iter()
+            .filter_map(|(id, machine)| {
+                machine.ssh_key.as_ref().map(|key| Self::check_key(id, key))
+            })
+            .collect();
+
+        if machines
+            .iter()
+            .any(|(_, machine)| machine.ssh_key.is_none())
+        {
+            let agent = std::env::var_os("SSH_AUTH_SOCK");
+            results.push(match agent {
+                Some(sock) if Path::new(&sock).exists() => {
+                    CheckResult::pass("ssh_agent", "SSH agent is running")
+                }
+                Some(sock) => CheckResult::warn(
+                    "ssh_agent",
+                    format!(
+                        "SSH_AUTH_SOCK points at missing {}",
+                        Path::new(&sock).display()
+                    ),
+                    "restart the agent (`eval $(ssh-agent)` then `ssh-add`), or set ssh_key",
+                ),
+                None => CheckResult::warn(
+                    "ssh_agent",
+                    "no SSH agent; machines without ssh_key rely on default key files",
+                    "start one with `eval $(ssh-agent)` and `ssh-add`, or set ssh_key",
+                ),
+            });
+        }
+        results
+    }
+}
+
+/// Each remote machine answers a quick probe and its clock agrees with ours
+pub struct MachineProbeCheck;
+
+impl MachineProbeCheck {
+    /// Judge the clock skew of `machine_id` relative to the hub
+    #[must_use]
+    pub fn evaluate_skew(machine_id: &str, skew_secs: i64) -> CheckResult {
+        let name = format!("clock_skew:{machine_id}");
+        if skew_secs.abs() > MAX_CLOCK_SKEW_SECS {
+            CheckResult::fail(
+                name,
+                format!("clock is {skew_secs:+}s off the hub (limit {MAX_CLOCK_SKEW_SECS}s)"),
+                "enable NTP on both hosts (e.g. `timedatectl set-ntp true`)",
+            )
+        } else {
+            CheckResult::pass(name, format!("clock within {skew_secs:+}s of the hub"))
+        }
+    }
+}
+
+#[async_trait]
+impl DoctorCheck for MachineProbeCheck {
+    fn name(&self) -> &'static str {
+        "machine"
+    }
+
+    async fn run(&self, cx: &Cx, ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+        if let Some(wanted) = ctx.machine
+            && !ctx.config.machines.contains_key(wanted)
+        {
+            return vec![CheckResult::fail(
+                format!("machine:{wanted}"),
+                format!("machine '{wanted}' is not in the config"),
+                "check the ID against `vc machines list`",
+            )];
+        }
+        let machines = ctx.remote_machines();
+        if machines.is_empty() {
+            return vec![CheckResult::pass(
+                self.name(),
+                "no enabled remote machines to probe",
+            )];
+        }
+
+        let mut results = Vec::new();
+        for (id, machine) in machines {
+            let name = format!("machine:{id}");
+            let (Some(host), Some(user)) = (&machine.ssh_host, &machine.ssh_user) else {
+                results.push(CheckResult::fail(
+                    name,
+                    "ssh_host is set but ssh_user is missing",
+                    format!("set machines.{id}.ssh_user"),
+                ));
+                continue;
+            };
+            let mut ssh = SshConfig::new(user.clone(), host.clone()).with_port(machine.ssh_port);
+            if let Some(key) = &machine.ssh_key {
+                ssh = ssh.with_key(vc_config::expand_path(key).to_string_lossy());
+            }
+            let target = format!("ssh -p {} {user}@{host}", machine.ssh_port);
+
+            let started = Instant::now();
+            let output = Executor::remote(ssh)
+                .run(cx, "date -u +%s", PROBE_TIMEOUT)
+                .await;
+            let elapsed_ms = started.elapsed().as_millis();
+            match output {
+                Ok(output) if output.exit_code == 0 => {
+                    results.push(CheckResult::pass(
+                        name,
+                        format!("reachable in {elapsed_ms} ms"),
+                    ));
+                    if let Ok(remote) = output.stdout.trim().parse::<i64>() {
+                        results.push(Self::evaluate_skew(id, remote - Utc::now().timestamp()));
+                    }
+                }
+                Ok(output) => results.push(CheckResult::fail(
+                    name,
+                    format!(
+                        "probe exited {}: {}",
+                        output.exit_code,
+                        output.stderr.lines().next().unwrap_or("").trim()
+                    ),
+                    format!("try `{target}` by hand; see the ssh checks above"),
+                )),
+                Err(e) => results.push(CheckResult::fail(
+                    name,
+                    format!("unreachable: {e}"),
+                    format!("try `{target}` by hand; see the ssh checks above"),
+                )),
+            }
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(checked_at: DateTime<Utc>, level: &str) -> DaemonResourceState {
+        DaemonResourceState {
+            checked_at: checked_at.to_rfc3339(),
+            level: level.to_string(),
+            disk_pressure: false,
+            rss_mb: None,
+            free_disk_mb: None,
+            max_rss_mb: None,
+            min_free_disk_mb: None,
+            reason: None,
+            watch_subscribers: None,
+        }
+    }
+
+    #[test]
+    fn test_heartbeat_freshness() {
+        let config = VcConfig::default();
+        let now = Utc::now();
+        let interval = i64::try_from(config.global.poll_interval_secs).unwrap();
+
+        let missing = DaemonHeartbeatCheck::evaluate(None, &config, now);
+        assert_eq!(missing.status, CheckStatus::Warn);
+
+        let fresh = state(now - chrono::Duration::seconds(interval), "normal");
+        let result = DaemonHeartbeatCheck::evaluate(Some(&fresh), &config, now);
+        assert_eq!(result.status, CheckStatus::Pass);
+
+        let stale = state(now - chrono::Duration::seconds(interval * 4), "normal");
+        let result = DaemonHeartbeatCheck::evaluate(Some(&stale), &config, now);
+        assert_eq!(result.status, CheckStatus::Fail);
+
+        // A stretched poll interval is not mistaken for a dead daemon
+        let stretched = state(
+            now - chrono::Duration::seconds(interval * 4),
+            "polling_stretched",
+        );
+        let result = DaemonHeartbeatCheck::evaluate(Some(&stretched), &config, now);
+        assert_eq!(result.status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_disk_space_thresholds() {
+        assert_eq!(
+            DiskSpaceCheck::evaluate(Some(500), 1024).status,
+            CheckStatus::Fail
+        );
+        assert_eq!(
+            DiskSpaceCheck::evaluate(Some(1500), 1024).status,
+            CheckStatus::Warn
+        );
+        assert_eq!(
+            DiskSpaceCheck::evaluate(Some(4096), 1024).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            DiskSpaceCheck::evaluate(None, 1024).status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn test_key_mode_and_clock_skew() {
+        let key = Path::new("/home/me/.ssh/id_ed25519");
+        let loose = SshCredentialsCheck::evaluate_key_mode("orko", key, 0o100_644);
+        assert_eq!(loose.status, CheckStatus::Fail);
+        assert_eq!(
+            loose.hint.as_deref(),
+            Some("chmod 600 /home/me/.ssh/id_ed25519")
+        );
+        let private = SshCredentialsCheck::evaluate_key_mode("orko", key, 0o100_600);
+        assert_eq!(private.status, CheckStatus::Pass);
+
+        assert_eq!(
+            MachineProbeCheck::evaluate_skew("orko", 2).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            MachineProbeCheck::evaluate_skew("orko", -90).status,
+            CheckStatus::Fail
+        );
+    }
+
+    struct AlwaysFails;
+
+    #[async_trait]
+    impl DoctorCheck for AlwaysFails {
+        fn name(&self) -> &'static str {
+            "custom"
+        }
+
+        async fn run(&self, _cx: &Cx, _ctx: &DoctorContext<'_>) -> Vec<CheckResult> {
+            vec![CheckResult::fail(self.name(), "broken", "fix it")]
+        }
+    }
+
+    #[test]
+    fn test_registered_checks_feed_the_report() {
+        let config = VcConfig::default();
+        let store = VcStore::open_memory().unwrap();
+        let ctx = DoctorContext {
+            config: &config,
+            store: Ok(&store),
+            machine: None,
+            now: Utc::now(),
+        };
+        let mut doctor = Doctor::new();
+        doctor
+            .register(Box::new(ConfigCheck))
+            .register(Box::new(StoreCheck))
+            .register(Box::new(AlwaysFails));
+        assert_eq!(doctor.check_names(), vec!["config", "store", "custom"]);
+
+        let cx = Cx::for_testing();
+        let report = futures::executor::block_on(doctor.run(&cx, &ctx));
+        assert_eq!(report.checks.len(), 3);
+        assert_eq!(report.checks[2].status, CheckStatus::Fail);
+        assert!(!report.ok());
+        // An empty store opens fine but is flagged as possibly the wrong one
+        assert_eq!(report.checks[1].status, CheckStatus::Warn);
+        assert!(report.render_text().contains("→ fix it"));
+    }
+}
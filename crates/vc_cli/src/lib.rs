@@ -9,7 +9,7 @@
 use asupersync::signal::{ShutdownController, ShutdownReceiver};
 use asupersync::time::BudgetTimeExt;
 use asupersync::{Budget, CancelKind, Cx};
-use chrono::{DateTime, Duration as ChronoDuration, SecondsFormat, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, SecondsFormat, Timelike, Utc};
 use clap::{Parser, Subcommand, ValueEnum};
 use duckdb::{
     Connection as DuckConnection,
@@ -21,28 +21,48 @@ use serde::{Deserialize, Serialize};
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::{
-    Arc,
+    Arc, OnceLock,
     atomic::{AtomicBool, Ordering},
 };
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use vc_collect::executor::Executor;
-use vc_collect::machine::{Machine, MachineStatus};
-use vc_config::VcConfig;
+use vc_collect::machine::Machine;
+use vc_config::{ReplicationMode, VcConfig};
+#[cfg(feature = "guardian")]
+use vc_guardian::autogen::CapturedAction;
+#[cfg(feature = "guardian")]
+use vc_guardian::transcript::{CaptureProvenance, ExtractedCommand};
+#[cfg(feature = "knowledge")]
 use vc_knowledge::{
     EntryType, FeedbackType, KnowledgeEntry, KnowledgeFeedback, KnowledgeStore, SearchOptions,
 };
 use vc_store::{
-    AuditEventFilter, AuditEventType, VcStore, escape_sql_identifier, escape_sql_literal,
+    AuditEventFilter, AuditEventType, BackendKind, RetentionScope, TABLE_WIDE_SCOPE, VcStore,
+    escape_sql_identifier, escape_sql_literal,
 };
 
-pub mod robot;
+pub mod alert_delivery;
+pub mod csv;
+pub mod daemon_limits;
+pub mod doctor;
+pub mod multi_report;
+#[cfg(feature = "prometheus-import")]
+pub mod prometheus_import;
+pub mod replication;
 pub mod schema_registry;
-pub mod toon;
+pub mod staleness;
+pub mod store_open;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
 pub mod watch;
+pub mod watch_enrich;
+#[cfg(unix)]
+pub mod watch_socket;
 
-pub use robot::{HealthData, RobotEnvelope, StatusData, TriageData};
+pub use multi_report::{MultiMachineReport, TargetOutcome};
 pub use schema_registry::{SchemaEntry, SchemaIndex, SchemaRegistry};
+pub use vc_robot::{self as robot, HealthData, RobotEnvelope, StatusData, TriageData, toon};
 
 /// CLI errors
 #[derive(Error, Debug)]
@@ -65,17 +85,56 @@ pub enum CliError {
     #[error("Query error: {0}")]
     QueryError(#[from] vc_query::QueryError),
 
+    #[error(transparent)]
+    RobotError(#[from] vc_robot::RobotError),
+
     #[error("Validation error: {0}")]
     ValidationError(#[from] vc_query::ValidationError),
 
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[cfg(feature = "knowledge")]
     #[error("Knowledge error: {0}")]
     KnowledgeError(#[from] vc_knowledge::KnowledgeError),
 
+    #[cfg(feature = "tui")]
     #[error("TUI error: {0}")]
     TuiError(#[from] vc_tui::TuiError),
+
+    /// The command's part of `vc` was left out of this build
+    #[error("vc was built without the `{0}` feature; rebuild with `--features {0}`")]
+    FeatureDisabled(&'static str),
+
+    #[error("Report incomplete, data unavailable for: {}", .0.join(", "))]
+    PartialReport(Vec<String>),
+
+    #[error(transparent)]
+    StoreOpen(#[from] store_open::StoreOpenError),
+
+    #[error("{command}: {failed} of {total} target(s) failed")]
+    TargetsFailed {
+        command: String,
+        failed: usize,
+        total: usize,
+    },
+}
+
+/// Exit code for a report that was produced, but with sections whose data
+/// could not be read, so a scheduler can tell it apart from a hard failure
+pub const EXIT_PARTIAL_REPORT: i32 = 3;
+
+impl CliError {
+    /// Process exit code for this error
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::PartialReport(_) => EXIT_PARTIAL_REPORT,
+            CliError::StoreOpen(e) => e.failure.exit_code(),
+            CliError::TargetsFailed { .. } => TargetOutcome::Failed.exit_code(),
+            _ => 1,
+        }
+    }
 }
 
 /// Output format for robot mode
@@ -87,6 +146,8 @@ pub enum OutputFormat {
     Toon,
     /// Human-readable text
     Text,
+    /// Comma-separated values with a header row, for spreadsheets
+    Csv,
 }
 
 /// Main CLI application
@@ -102,6 +163,10 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub config: Option<std::path::PathBuf>,
 
+    /// Config profile to apply (overrides `VC_PROFILE`)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -110,6 +175,41 @@ pub struct Cli {
     #[arg(long, global = true, default_value = "text")]
     pub format: OutputFormat,
 
+    /// Timestamps in text output: relative, absolute or both (JSON and TOON
+    /// stay raw UTC)
+    #[arg(long, global = true, default_value = "relative")]
+    pub timestamps: vc_query::TimestampStyle,
+
+    /// Columns to write, in order, with `--format csv` (comma-separated;
+    /// default: every field, in first-seen order)
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub csv_columns: Option<Vec<String>>,
+
+    /// With `--format toon`, cut output to about N tokens by leaving out
+    /// whole records, least important first; a `CT:` section counts them
+    #[arg(long, global = true, value_name = "N")]
+    pub max_tokens: Option<usize>,
+
+    /// Bytes counted as one token by `--max-tokens`
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = toon::ToonBudget::DEFAULT_CHARS_PER_TOKEN
+    )]
+    pub chars_per_token: usize,
+
+    /// Who this invocation acts for, recorded on the audit events and rows
+    /// it writes (default: the OS user). With `vc audit list` it filters by
+    /// actor instead
+    #[arg(long, global = true)]
+    pub actor: Option<String>,
+
+    /// Keep the report of a command that acts on several machines in the
+    /// store, for `vc ops show <id>`
+    #[arg(long, global = true)]
+    pub save_report: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -129,6 +229,14 @@ pub enum Commands {
         /// Run in foreground
         #[arg(short, long)]
         foreground: bool,
+
+        /// Take the store lease from another daemon even though it has not
+        /// expired; for a daemon known to be dead
+        #[arg(long)]
+        takeover: bool,
+
+        #[command(subcommand)]
+        command: Option<DaemonCommands>,
     },
 
     /// Show current status
@@ -136,6 +244,11 @@ pub enum Commands {
         /// Machine to show status for
         #[arg(short, long)]
         machine: Option<String>,
+
+        /// Draw a 24h alert-count sparkline per machine (default: on for a
+        /// terminal, off when piped; `--sparkline=false` turns it off)
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        sparkline: Option<bool>,
     },
 
     /// Robot mode commands for agent consumption
@@ -169,6 +282,26 @@ pub enum Commands {
         /// Buffer up to N events before emitting (batch mode)
         #[arg(long)]
         buffer: Option<usize>,
+
+        /// Attach context to alert events: machine health, occurrence count
+        /// and group, matching playbook and top knowledge suggestion
+        /// (schema `vc.watch.alert_enriched.v1`)
+        #[arg(long)]
+        enrich: bool,
+
+        /// Serve one event stream to `vc watch --connect` subscribers on this
+        /// Unix socket instead of printing it
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["connect", "events", "changes_only", "machines", "min_severity", "buffer", "enrich"]
+        )]
+        serve: Option<PathBuf>,
+
+        /// Subscribe to a `vc watch --serve` or `vc daemon` socket instead of
+        /// polling the store
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["interval", "buffer"])]
+        connect: Option<PathBuf>,
     },
 
     /// Collector management
@@ -206,6 +339,20 @@ pub enum Commands {
         command: FleetCommands,
     },
 
+    /// Reports of commands that acted on several machines, saved with
+    /// `--save-report`
+    Ops {
+        #[command(subcommand)]
+        command: OpsCommands,
+    },
+
+    /// Diagnose common setup problems (exits nonzero if any check fails)
+    Doctor {
+        /// Only probe this machine
+        #[arg(short, long)]
+        machine: Option<String>,
+    },
+
     /// Run vacuum (retention policies)
     Vacuum {
         /// Dry run - show what would be deleted
@@ -264,20 +411,129 @@ pub enum Commands {
         command: HealthCommands,
     },
 
+    /// Estimated agent spend
+    Costs {
+        #[command(subcommand)]
+        command: CostCommands,
+    },
+
     /// Knowledge base management (solutions, patterns, prompts, debug logs)
     Knowledge {
         #[command(subcommand)]
         command: KnowledgeCommands,
     },
 
+    /// Agent session outcomes and success rates
+    Sessions {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+
+    /// Per-repo agent activity and stale work
+    Repos {
+        #[command(subcommand)]
+        command: RepoCommands,
+    },
+
+    /// Search knowledge, incidents, session transcripts and alerts at once
+    Search {
+        /// Words to look for; every one must match
+        query: String,
+
+        /// Kinds to search: knowledge, incidents, sessions, alerts
+        #[arg(long, value_delimiter = ',')]
+        kinds: Vec<vc_query::SearchKind>,
+
+        /// Window start: an age (e.g. 24h, 30d) or a timestamp
+        #[arg(long, default_value = "30d")]
+        since: String,
+
+        /// Maximum results, and results read per kind
+        #[arg(long, default_value_t = vc_query::search::DEFAULT_SEARCH_LIMIT)]
+        limit: usize,
+    },
+
+    /// Alerts, incidents, fleet commands, audit and drift events and
+    /// annotations merged into one time-ordered stream
+    Timeline {
+        #[command(subcommand)]
+        command: Option<TimelineCommands>,
+
+        /// Window start: an age (e.g. 24h, 7d) or a timestamp
+        #[arg(long, default_value = "24h")]
+        since: String,
+
+        /// Only events on this machine (incidents and fleet-wide commands are
+        /// always included)
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Kinds to include: alerts, incidents, fleet, audit, drift, annotations
+        #[arg(long, value_delimiter = ',')]
+        kinds: Vec<vc_query::TimelineKind>,
+
+        /// Events per page
+        #[arg(long, default_value = "200")]
+        limit: usize,
+
+        /// Continue after this cursor (printed at the end of a full page)
+        #[arg(long)]
+        after: Option<String>,
+    },
+
+    /// Record a deploy or other outside event. It shows next to the alerts
+    /// in the timeline, the digest, incident timelines and the web charts.
+    Annotate {
+        /// What happened, e.g. deploy, release, rollback, note
+        #[arg(long, default_value = "deploy")]
+        kind: String,
+
+        /// The machine it touched (default: the whole fleet)
+        #[arg(long, conflicts_with = "tag")]
+        machine: Option<String>,
+
+        /// The tag of the machines it touched (default: the whole fleet)
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// e.g. "release 1.42"
+        #[arg(long)]
+        message: String,
+
+        /// When it happened (default: now)
+        #[arg(long)]
+        ts: Option<String>,
+
+        /// What reported it
+        #[arg(long, default_value = "cli")]
+        source: String,
+    },
+
+    /// Annotations recorded by `vc annotate`, the web API and vc itself
+    Annotations {
+        #[command(subcommand)]
+        command: AnnotationsCommands,
+    },
+
     /// Incident management (tracking, timeline, notes)
     Incident {
         #[command(subcommand)]
         command: IncidentCommands,
     },
 
+    /// OpenTelemetry export state
+    Telemetry {
+        #[command(subcommand)]
+        command: TelemetryCommands,
+    },
+
     /// Start MCP server (JSON-RPC over stdio)
     Mcp {
+        /// Only offer the tools of this `[mcp.roles.<name>]` (default:
+        /// `[mcp] default_role`)
+        #[arg(long, global = true)]
+        role: Option<String>,
+
         #[command(subcommand)]
         command: McpCommands,
     },
@@ -343,9 +599,25 @@ pub enum Commands {
         /// Save to store for history
         #[arg(long)]
         save: bool,
+
+        /// Also send the report through the `[alerts.email]` sink
+        #[arg(long)]
+        email: bool,
+
+        /// Fail without output when any section's data cannot be read,
+        /// instead of reporting it and exiting with code 3
+        #[arg(long)]
+        strict: bool,
     },
 }
 
+/// Daemon subcommands
+#[derive(Subcommand, Debug)]
+pub enum DaemonCommands {
+    /// Show the daemon's last recorded resource state and degradation level
+    Check,
+}
+
 /// On-demand profiling subcommands
 #[derive(Subcommand, Debug)]
 pub enum ProfileCommands {
@@ -384,13 +656,30 @@ pub enum ProfileCommands {
         /// Maximum decisions to show
         #[arg(long, default_value = "20")]
         limit: usize,
+
+        /// Show each decision's rule, interval change, inputs and explanation
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Explain one machine's current poll intervals: the latest decisions
+    /// for each collector, newest first
+    Why {
+        /// Machine to explain
+        #[arg(long)]
+        machine: String,
+
+        /// Decisions to show per collector
+        #[arg(long, default_value = "5")]
+        depth: usize,
     },
 }
 
 /// Node push agent subcommands
 #[derive(Subcommand, Debug)]
 pub enum NodeCommands {
-    /// Show recent ingest history
+    /// Show recent ingest history: each bundle as complete or partial, then
+    /// the per-batch records
     History {
         /// Filter by machine
         #[arg(long)]
@@ -403,6 +692,22 @@ pub enum NodeCommands {
 
     /// Show spool configuration
     Config,
+
+    /// Print the signing headers for a request body, for pushing to a hub
+    /// with `[web.signing]` enabled
+    Sign {
+        /// File holding the shared secret (trailing newline ignored)
+        #[arg(long)]
+        secret_file: PathBuf,
+
+        /// Request body to sign; `-` reads stdin
+        #[arg(long)]
+        body_file: PathBuf,
+
+        /// Unix timestamp to sign at (default: now)
+        #[arg(long)]
+        timestamp: Option<i64>,
+    },
 }
 
 /// API token management subcommands
@@ -490,6 +795,11 @@ pub enum DbCommands {
         /// Specific tables to export (comma-separated). Default: all
         #[arg(long)]
         tables: Option<String>,
+
+        /// Write each table as `<table>.csv` instead of JSONL. CSV exports
+        /// are for spreadsheets; `vc db import` only reads JSONL
+        #[arg(long)]
+        csv: bool,
     },
 
     /// Import data from JSONL export bundle
@@ -499,8 +809,177 @@ pub enum DbCommands {
         from: String,
     },
 
+    /// Import history from a Prometheus server (one-shot migration; needs
+    /// the `prometheus-import` feature)
+    ImportPrometheus {
+        /// Prometheus base URL (e.g. http://prom:9090)
+        #[arg(long)]
+        url: String,
+
+        /// Metric name regex, or a PromQL series selector
+        #[arg(long = "match")]
+        matcher: String,
+
+        /// How far back to import (e.g. 30d, 12h)
+        #[arg(long, value_parser = parse_window, default_value = "30d")]
+        since: Duration,
+
+        /// Resolution to downsample to (e.g. 5m, 1h)
+        #[arg(long, value_parser = parse_window, default_value = "5m")]
+        step: Duration,
+
+        /// TOML file mapping metrics to tables/columns and labels to machines
+        #[arg(long)]
+        map_file: PathBuf,
+
+        /// Report what would be imported per machine without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Show database info (tables, row counts)
-    Info,
+    Info {
+        /// Also report transcripts offloaded to `[storage.artifacts]`
+        #[arg(long)]
+        detailed: bool,
+    },
+
+    /// Artifacts offloaded to `[storage.artifacts]`
+    Artifacts {
+        #[command(subcommand)]
+        command: ArtifactCommands,
+    },
+
+    /// Warm standby replication
+    Replication {
+        #[command(subcommand)]
+        command: ReplicationCommands,
+    },
+
+    /// Promote this standby to primary (manual failover)
+    Promote {
+        /// Who is promoting, for the audit log (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
+
+    /// Query shapes ranked by total time spent in them (from the query log)
+    SlowQueries {
+        /// Number of fingerprints to show
+        #[arg(long, default_value = "20")]
+        top: usize,
+
+        /// Only count queries from this far back (e.g. 1h, 30m, 7d)
+        #[arg(long, value_parser = parse_window)]
+        since: Option<Duration>,
+    },
+
+    /// Refresh planner statistics with ANALYZE. Default: the tables whose
+    /// changes since their last ANALYZE pass the `[analyze]` thresholds
+    Analyze {
+        /// Analyze only this table
+        #[arg(long, conflicts_with = "all")]
+        table: Option<String>,
+
+        /// Analyze every table
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Fill an empty store with a deterministic synthetic fleet for demos
+    /// and testing
+    Seed {
+        /// `demo`, `test` (small and fast) or `load` (millions of samples)
+        #[arg(long, default_value = "demo")]
+        profile: vc_store::SeedProfile,
+
+        /// Number of machines
+        #[arg(long, default_value = "5")]
+        machines: usize,
+
+        /// Days of history, ending now
+        #[arg(long, default_value = "7")]
+        days: u32,
+
+        /// Same seed, same fleet
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Seed even though the store already holds data
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Correct columns of historical rows a collector got wrong. The
+    /// original values are kept, so `--undo <id>` can put them back.
+    Correct {
+        /// Table to correct
+        #[arg(long, required_unless_present_any = ["undo", "list"])]
+        table: Option<String>,
+
+        /// Rows to correct, as a read-only SQL condition
+        #[arg(long = "where", required_unless_present_any = ["undo", "list"])]
+        filter: Option<String>,
+
+        /// Column to set, as COLUMN=VALUE (repeatable)
+        #[arg(long = "set", value_name = "COLUMN=VALUE")]
+        set: Vec<String>,
+
+        /// Only rows at or after this timestamp (on the table's timestamp column)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only rows before this timestamp
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Show the row count and a sample before/after without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Needed to change more than 100 rows
+        #[arg(long)]
+        confirm: bool,
+
+        /// Allow correcting primary/unique key and machine identity columns
+        #[arg(long)]
+        allow_key_columns: bool,
+
+        /// Rows updated per transaction
+        #[arg(long, default_value_t = vc_store::corrections::DEFAULT_CORRECTION_BATCH)]
+        batch_size: usize,
+
+        /// Put back the original values of this correction
+        #[arg(long, conflicts_with_all = ["table", "filter", "set", "list", "dry_run"])]
+        undo: Option<i64>,
+
+        /// List recorded corrections
+        #[arg(long, conflicts_with_all = ["table", "filter", "set"])]
+        list: bool,
+
+        /// Who is correcting, for the audit log (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
+}
+
+/// Offloaded artifact subcommands
+#[derive(Subcommand, Debug)]
+pub enum ArtifactCommands {
+    /// Check every pointer against the artifact store: the object exists
+    /// with the recorded size (exits non-zero on any problem)
+    Verify {
+        /// Download each object and compare its SHA-256 too
+        #[arg(long)]
+        download: bool,
+    },
+}
+
+/// Replication subcommands
+#[derive(Subcommand, Debug)]
+pub enum ReplicationCommands {
+    /// Show this node's role and per-table lag
+    Status,
 }
 
 /// Retention policy subcommands
@@ -522,6 +1001,26 @@ pub enum RetentionCommands {
         /// Disable the policy (default: enabled)
         #[arg(long)]
         disabled: bool,
+
+        /// Only cover some machines: `tag:<tag>` or `machines:<id>,...`
+        #[arg(long)]
+        scope: Option<RetentionScope>,
+
+        /// Cap on the table's total text bytes; vacuum deletes oldest rows past it (0 clears)
+        #[arg(long)]
+        max_total_bytes: Option<i64>,
+
+        /// Cap on one row's text bytes; longer values are cut at write time (0 clears)
+        #[arg(long)]
+        max_row_bytes: Option<i64>,
+
+        /// Bytes kept from the start of a cut value
+        #[arg(long)]
+        keep_head_bytes: Option<i64>,
+
+        /// Bytes kept from the end of a cut value
+        #[arg(long)]
+        keep_tail_bytes: Option<i64>,
     },
 
     /// Show vacuum operation history
@@ -530,6 +1029,84 @@ pub enum RetentionCommands {
         #[arg(long, default_value = "20")]
         limit: usize,
     },
+
+    /// Show what a different retention period would delete, without deleting
+    Simulate {
+        /// Table to simulate
+        #[arg(
+            long,
+            required_unless_present = "all_tables",
+            conflicts_with = "all_tables"
+        )]
+        table: Option<String>,
+
+        /// Simulate every table with a retention policy, ranked by bytes reclaimed
+        #[arg(long)]
+        all_tables: bool,
+
+        /// Retention period to simulate, in days
+        #[arg(long)]
+        days: i32,
+
+        /// Also count rows that would age out over this many days
+        #[arg(long, default_value = "30")]
+        horizon_days: i32,
+    },
+
+    /// Legal holds: rows vacuum must keep whatever their age
+    Hold {
+        #[command(subcommand)]
+        command: RetentionHoldCommands,
+    },
+}
+
+/// Legal hold subcommands
+#[derive(Subcommand, Debug)]
+pub enum RetentionHoldCommands {
+    /// Keep an incident's, a session's and/or a machine's rows out of vacuum
+    Create {
+        /// Why the rows must be kept
+        #[arg(long)]
+        reason: String,
+
+        /// Incident whose rows, and the alerts and sessions its timeline
+        /// names, are kept
+        #[arg(long, required_unless_present_any = ["session", "machine"])]
+        incident: Option<String>,
+
+        /// Session whose rows are kept
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Machine whose rows are kept
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// With --machine, only rows from this time on
+        #[arg(long, requires = "machine")]
+        from: Option<String>,
+
+        /// With --machine, only rows up to this time
+        #[arg(long, requires = "machine")]
+        to: Option<String>,
+    },
+
+    /// List holds in force
+    List {
+        /// Include released holds
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Release a hold; its rows age out again from the next vacuum
+    Release {
+        /// Hold ID
+        id: String,
+
+        /// Required: releasing a hold lets vacuum delete what it kept
+        #[arg(long)]
+        confirm: bool,
+    },
 }
 
 /// Data quality subcommands
@@ -559,6 +1136,19 @@ pub enum HealthCommands {
         /// Number of entries to show
         #[arg(long, default_value = "20")]
         limit: usize,
+
+        /// Count failures per machine and cause instead of listing runs
+        #[arg(long)]
+        by_cause: bool,
+
+        /// Count values cut or refused by the store write limits instead
+        /// of listing runs
+        #[arg(long, conflicts_with = "by_cause")]
+        oversized: bool,
+
+        /// With --by-cause or --oversized, how far back to count (e.g. 1h, 24h, 7d)
+        #[arg(long, default_value = "24h", value_parser = parse_window)]
+        window: Duration,
     },
 
     /// Show recent drift events
@@ -571,6 +1161,14 @@ pub enum HealthCommands {
         #[arg(long)]
         severity: Option<String>,
 
+        /// Filter by drifted metric/field (e.g. `kernel_version`)
+        #[arg(long)]
+        field: Option<String>,
+
+        /// One compact JSON object per event, oldest first (diff-friendly)
+        #[arg(long)]
+        json: bool,
+
         /// Number of entries to show
         #[arg(long, default_value = "50")]
         limit: usize,
@@ -588,6 +1186,66 @@ pub enum HealthCommands {
         /// Show score for a specific machine
         #[arg(long)]
         machine: Option<String>,
+
+        /// Compare against the machine's average over a window (e.g. 7d, 24h)
+        #[arg(long, value_parser = parse_window, requires = "machine")]
+        compare: Option<Duration>,
+
+        /// Show the machine's score history over a window (e.g. 24h, 7d)
+        #[arg(long, value_parser = parse_window, requires = "machine", conflicts_with = "compare")]
+        trend: Option<Duration>,
+
+        /// Draw the `--trend` history as a sparkline (default: on for a
+        /// terminal, off when piped; `--sparkline=false` turns it off)
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        sparkline: Option<bool>,
+    },
+
+    /// Recompute recent health history under the current scoring version,
+    /// so trends stay comparable after an upgrade
+    Rescore {
+        /// How far back to rescore (e.g. 24h, 7d)
+        #[arg(long, value_parser = parse_window, default_value = "7d")]
+        window: Duration,
+    },
+
+    /// Show the latest result of each configured HTTP check
+    Checks {
+        /// Filter by machine ID
+        #[arg(long)]
+        machine: Option<String>,
+    },
+
+    /// Forecast days until each mount is full, from its usage trend
+    Forecast {
+        /// Filter by machine ID
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Flag mounts forecast to fill within this (e.g. 7d, 30d; default
+        /// `forecast.horizon_days`)
+        #[arg(long, value_parser = parse_window)]
+        horizon: Option<Duration>,
+    },
+}
+
+/// Cost subcommands
+#[derive(Subcommand, Debug)]
+pub enum CostCommands {
+    /// Estimated spend over a window, bucketed over time
+    Trend {
+        /// How far back to look (e.g. 24h, 7d, 4w)
+        #[arg(long, value_parser = parse_window, default_value = "7d")]
+        window: Duration,
+
+        /// Only count spend attributed to this machine
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Draw the buckets as a sparkline (default: on for a terminal, off
+        /// when piped; `--sparkline=false` turns it off)
+        #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        sparkline: Option<bool>,
     },
 }
 
@@ -627,6 +1285,10 @@ pub enum KnowledgeCommands {
         /// Tags (comma-separated)
         #[arg(long)]
         tags: Option<String>,
+
+        /// Alert type this entry addresses, e.g. rate-limit (repeatable)
+        #[arg(long = "alert-type")]
+        alert_types: Vec<String>,
     },
 
     /// Search knowledge entries
@@ -645,6 +1307,15 @@ pub enum KnowledgeCommands {
         /// Maximum results to return
         #[arg(long, default_value = "20")]
         limit: usize,
+
+        /// Rank by embedding similarity blended with keyword score (needs
+        /// the embeddings feature and `[knowledge.embeddings]`)
+        #[arg(long)]
+        semantic: bool,
+
+        /// Also search archived entries
+        #[arg(long)]
+        include_archived: bool,
     },
 
     /// Show a specific knowledge entry
@@ -702,6 +1373,110 @@ pub enum KnowledgeCommands {
 
     /// Show mining statistics
     MineStats,
+
+    /// List entries most likely to have gone stale, with suggested actions
+    Review {
+        /// Maximum entries to return
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Hide an entry from default search, keeping it retrievable
+    Archive {
+        /// Entry ID
+        id: i64,
+    },
+
+    /// Link two entries: <SOURCE> <RELATION> <TARGET>
+    Link {
+        /// Source entry ID
+        source: i64,
+
+        /// Target entry ID
+        target: i64,
+
+        /// Relation: `relates_to`, supersedes, `derived_from`
+        #[arg(long, default_value = "relates_to")]
+        relation: String,
+    },
+
+    /// Embed entries that have no vector for the configured model
+    Reindex,
+
+    /// Remove links from one entry to another
+    Unlink {
+        /// Source entry ID
+        source: i64,
+
+        /// Target entry ID
+        target: i64,
+
+        /// Only remove this relation (default: all links between the two)
+        #[arg(long)]
+        relation: Option<String>,
+    },
+}
+
+/// Agent session subcommands
+#[derive(Subcommand, Debug)]
+pub enum SessionCommands {
+    /// Classify finished sessions as success, partial, failed or abandoned
+    Classify {
+        /// Reclassify sessions that already have an outcome (after changing
+        /// `[knowledge.outcomes]`)
+        #[arg(long)]
+        backfill: bool,
+
+        /// Maximum sessions to classify
+        #[arg(long, default_value = "1000")]
+        limit: usize,
+    },
+
+    /// Success rates per agent type and/or repo, against the window before
+    Stats {
+        /// Group by: agent, repo or agent-repo
+        #[arg(long, default_value = "agent-repo")]
+        group_by: vc_query::SessionGroupBy,
+
+        /// Window to rate (e.g. 24h, 7d)
+        #[arg(long, default_value = "7d", value_parser = parse_window)]
+        window: Duration,
+    },
+}
+
+/// Repo subcommands
+#[derive(Subcommand, Debug)]
+pub enum RepoCommands {
+    /// Sessions, commits and leftover changes per repo, with stale agent work
+    Activity {
+        /// Window to roll up (e.g. 24h, 7d)
+        #[arg(long, default_value = "7d", value_parser = parse_window)]
+        window: Duration,
+
+        /// Days without a session before uncommitted agent work is stale
+        #[arg(long, default_value_t = vc_query::repos::DEFAULT_STALE_DAYS)]
+        stale_days: i64,
+    },
+}
+
+/// Timeline subcommands
+#[derive(Subcommand, Debug)]
+pub enum TimelineCommands {
+    /// Show the full record behind a timeline event
+    Show {
+        /// Event kind: alerts, incidents, fleet, audit, drift or annotations
+        kind: vc_query::TimelineKind,
+
+        /// The event's ref id
+        id: String,
+    },
+}
+
+/// Telemetry export subcommands
+#[derive(Subcommand, Debug)]
+pub enum TelemetryCommands {
+    /// Show backlog and last successful export per stream
+    Status,
 }
 
 /// Incident management subcommands
@@ -716,9 +1491,13 @@ pub enum IncidentCommands {
         /// Maximum entries to return
         #[arg(long, default_value = "50")]
         limit: usize,
+
+        /// Only incidents that missed an SLA target (`[incidents.sla]`)
+        #[arg(long)]
+        breached: bool,
     },
 
-    /// Show incident details
+    /// Show incident details, with its SLA timers
     Show {
         /// Incident ID
         id: String,
@@ -752,6 +1531,36 @@ pub enum IncidentCommands {
         author: Option<String>,
     },
 
+    /// Acknowledge an incident, stopping its time-to-acknowledge timer
+    Ack {
+        /// Incident ID
+        id: String,
+
+        /// Who is acknowledging (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
+
+    /// Mark an incident mitigated, stopping its time-to-mitigate timer
+    Mitigate {
+        /// Incident ID
+        id: String,
+
+        /// What was done
+        #[arg(long)]
+        note: Option<String>,
+    },
+
+    /// Change an incident's severity; SLA targets of the new severity apply
+    /// from when the incident started
+    SetSeverity {
+        /// Incident ID
+        id: String,
+
+        /// Severity: info, warning, critical
+        severity: String,
+    },
+
     /// Close an incident
     Close {
         /// Incident ID
@@ -809,6 +1618,19 @@ pub enum ConfigCommands {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Also check things outside the config, such as secret files
+        /// existing with owner-only permissions
+        #[arg(long)]
+        deep: bool,
+    },
+
+    /// Resolve every secret:// reference in the config and report failures
+    /// (never the secrets themselves)
+    VerifySecrets {
+        /// Path to config file (uses auto-discovery if not specified)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
     },
 
     /// Generate a new configuration file interactively
@@ -835,10 +1657,51 @@ pub enum ConfigCommands {
         /// Output as JSON instead of TOML
         #[arg(long)]
         json: bool,
+
+        /// Show the config commands actually run with: the selected profile
+        /// merged in and environment overrides applied
+        #[arg(long)]
+        resolved: bool,
     },
 
     /// Show config file search paths
     Paths,
+
+    /// List recorded snapshots of the config file, newest first
+    History {
+        /// Most snapshots to list
+        #[arg(long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Line diff between two config snapshots, secrets masked
+    Diff {
+        /// Older snapshot hash (or a unique prefix)
+        #[arg(required_unless_present = "latest")]
+        hash_a: Option<String>,
+
+        /// Newer snapshot hash (or a unique prefix)
+        #[arg(required_unless_present = "latest")]
+        hash_b: Option<String>,
+
+        /// Diff the newest snapshot against the one before it
+        #[arg(long, conflicts_with_all = ["hash_a", "hash_b"])]
+        latest: bool,
+    },
+
+    /// Write a config snapshot back to the active config file
+    Rollback {
+        /// Snapshot hash (or a unique prefix)
+        hash: String,
+
+        /// Skip the confirmation prompt
+        #[arg(long)]
+        yes: bool,
+
+        /// Who is rolling back, for the audit log (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
 }
 
 /// Query subcommands
@@ -862,6 +1725,18 @@ pub enum QueryCommands {
         /// Parameters in key=value format
         #[arg(short, long)]
         param: Vec<String>,
+
+        /// Keep only these result columns (comma-separated)
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// Collapse results: count, sum:<column> or avg:<column>
+        #[arg(long)]
+        aggregate: Option<String>,
+
+        /// Aggregate per distinct value of this column
+        #[arg(long, requires = "aggregate")]
+        group_by: Option<String>,
     },
 
     /// List available templates
@@ -872,16 +1747,48 @@ pub enum QueryCommands {
         /// Natural language question (e.g., "Show critical alerts from today")
         question: String,
     },
+
+    /// Raw queries agents proposed over MCP, waiting for approval
+    Pending {
+        /// Show every proposal, not only pending ones
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Approve a proposed query; it runs now and the agent gets its rows
+    Approve {
+        /// Proposal ID
+        id: i64,
+    },
+
+    /// Deny a proposed query
+    Deny {
+        /// Proposal ID
+        id: i64,
+
+        /// Why, shown to the agent
+        #[arg(long)]
+        reason: Option<String>,
+    },
 }
 
 /// Robot mode subcommands
 #[derive(Subcommand, Debug)]
 pub enum RobotCommands {
     /// Get fleet health status
-    Health,
+    Health {
+        /// With `--format toon`, emit the lossless TOON encoding and fail if it
+        /// does not decode back to the exact health data
+        #[arg(long)]
+        verify: bool,
+    },
 
-    /// Get triage recommendations
-    Triage,
+    /// Get ranked triage actions
+    Triage {
+        /// Keep only the N best-ranked actions
+        #[arg(long, value_name = "N")]
+        max_items: Option<usize>,
+    },
 
     /// Get comprehensive fleet status (machines, repos, alerts)
     Status,
@@ -897,6 +1804,34 @@ pub enum RobotCommands {
 
     /// Get repository status
     Repos,
+
+    /// Generate and check JSON Schemas for robot envelopes
+    Schema {
+        #[command(subcommand)]
+        command: RobotSchemaCommands,
+    },
+}
+
+/// Robot schema subcommands
+#[derive(Subcommand, Debug)]
+pub enum RobotSchemaCommands {
+    /// Write one JSON Schema file per envelope version
+    Export {
+        /// Output directory
+        #[arg(long, default_value = schema_registry::GENERATED_SCHEMAS_DIR)]
+        out: PathBuf,
+
+        /// Overwrite schemas for existing versions (unreleased versions only)
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Diff generated schemas against a committed directory; exits nonzero on drift
+    Check {
+        /// Directory of committed schemas
+        #[arg(long, default_value = schema_registry::GENERATED_SCHEMAS_DIR)]
+        dir: PathBuf,
+    },
 }
 
 /// Alert subcommands
@@ -907,16 +1842,148 @@ pub enum AlertCommands {
         /// Show only unacknowledged
         #[arg(long)]
         unacked: bool,
+
+        /// Show only resolved alerts
+        #[arg(long, conflicts_with = "unacked")]
+        resolved: bool,
+
+        /// Only alerts resolved this way: condition, guardian, manual,
+        /// silence or unknown
+        #[arg(long, requires = "resolved")]
+        by: Option<vc_store::ResolutionKind>,
     },
 
-    /// Acknowledge an alert
+    /// Acknowledge an alert, or every alert matching a filter with --all
     Ack {
         /// Alert ID
-        id: i64,
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<i64>,
+
+        /// Acknowledge every open alert matching the filters below
+        #[arg(long)]
+        all: bool,
+
+        /// Only alerts on this machine
+        #[arg(long, requires = "all")]
+        machine: Option<String>,
+
+        /// Only alerts fired before this time (RFC 3339, YYYY-MM-DD, or an age like 2d)
+        #[arg(long, requires = "all", value_parser = parse_before)]
+        before: Option<String>,
+
+        /// Only alerts whose rule ID contains this text (e.g. offline)
+        #[arg(long = "type", requires = "all")]
+        alert_type: Option<String>,
+
+        /// Only alerts of this severity
+        #[arg(long, requires = "all")]
+        severity: Option<String>,
+
+        /// Show the matching count and a sample without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt for large matches
+        #[arg(long)]
+        yes: bool,
+
+        /// Who is acknowledging, for the audit log (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+
+        /// When the alert is the probable root of a correlated group,
+        /// acknowledge the alerts it likely caused too
+        #[arg(long, conflicts_with = "all")]
+        with_children: bool,
+    },
+
+    /// Resolve an alert, or every alert matching a filter with --all
+    Resolve {
+        /// Alert ID
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<i64>,
+
+        /// Resolve every open alert matching the filters below
+        #[arg(long)]
+        all: bool,
+
+        /// Only alerts on this machine
+        #[arg(long, requires = "all")]
+        machine: Option<String>,
+
+        /// Only alerts fired before this time (RFC 3339, YYYY-MM-DD, or an age like 2d)
+        #[arg(long, requires = "all", value_parser = parse_before)]
+        before: Option<String>,
+
+        /// Only alerts whose rule ID contains this text (e.g. offline)
+        #[arg(long = "type", requires = "all")]
+        alert_type: Option<String>,
+
+        /// Only alerts of this severity
+        #[arg(long, requires = "all")]
+        severity: Option<String>,
+
+        /// Show the matching count and a sample without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt for large matches
+        #[arg(long)]
+        yes: bool,
+
+        /// Who is resolving, for the audit log (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
+
+    /// Show the alerts linked to one probable root cause
+    Show {
+        /// Correlation ID, as shown by `vc alert list` and `vc robot triage`
+        #[arg(long)]
+        correlation: String,
     },
 
     /// Show alert rules
-    Rules,
+    Rules {
+        #[command(subcommand)]
+        command: Option<AlertRuleCommands>,
+
+        /// Show the rules as resolved for this machine, with its
+        /// `[[alerts.overrides]]` applied
+        #[arg(long)]
+        machine: Option<String>,
+    },
+
+    /// Send a test alert through one configured notification sink
+    TestNotify {
+        /// Sink to test: webhook, slack, discord, desktop or email
+        #[arg(long)]
+        sink: String,
+
+        /// Print the sink's `[alerts.templates]` message for the test alert
+        /// instead of sending it
+        #[arg(long)]
+        render_only: bool,
+    },
+}
+
+/// Alert rule subcommands
+#[derive(Subcommand, Debug)]
+pub enum AlertRuleCommands {
+    /// Replay a rule over historical samples without writing any alerts
+    Test {
+        /// Built-in rule id or name
+        #[arg(long, required_unless_present = "file", conflicts_with = "file")]
+        rule: Option<String>,
+
+        /// Rule definition file (JSON `AlertRule`)
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// How far back to replay (e.g. 7d, 12h, 30m)
+        #[arg(long, default_value = "7d", value_parser = parse_window)]
+        window: Duration,
+    },
 }
 
 /// Guardian subcommands
@@ -940,6 +2007,30 @@ pub enum GuardianCommands {
         run_id: i64,
     },
 
+    /// Walk a playbook against a machine's recorded state without executing
+    /// anything, and store the report as a sandbox run
+    Run {
+        /// Playbook ID (stored, built-in, or a draft at its current revision)
+        playbook_id: String,
+
+        /// Required: render each step and predict its effect instead of
+        /// running it
+        #[arg(long)]
+        sandbox: bool,
+
+        /// Machine whose recorded state the playbook is walked against
+        #[arg(long)]
+        machine: String,
+
+        /// Use the state recorded as of this time (default: now)
+        #[arg(long)]
+        at: Option<String>,
+
+        /// Parameter value (repeatable)
+        #[arg(long = "param", value_name = "NAME=VALUE")]
+        params: Vec<String>,
+    },
+
     /// Capture a resolution (actions that resolved an alert)
     Capture {
         /// Alert type that was resolved
@@ -947,8 +2038,12 @@ pub enum GuardianCommands {
         alert_type: String,
 
         /// Actions taken (JSON array of captured actions)
-        #[arg(long)]
-        actions: String,
+        #[arg(
+            long,
+            required_unless_present_any = ["from_session", "from_shell_history"],
+            conflicts_with_all = ["from_session", "from_shell_history"]
+        )]
+        actions: Option<String>,
 
         /// Resolution outcome: success, partial, failed
         #[arg(long, default_value = "success")]
@@ -961,6 +2056,27 @@ pub enum GuardianCommands {
         /// Operator who performed the resolution
         #[arg(long)]
         operator: Option<String>,
+
+        /// Extract the actions from this agent session's transcript
+        #[arg(long, conflicts_with = "from_shell_history")]
+        from_session: Option<String>,
+
+        /// Extract the actions from recent shell history on --machine
+        #[arg(long, requires = "machine")]
+        from_shell_history: bool,
+
+        /// How far back --from-shell-history looks (e.g. 30m, 2h)
+        #[arg(long, default_value = "30m", value_parser = parse_window)]
+        window: Duration,
+
+        /// Alert that was resolved; frames the --from-session window
+        /// (default: the latest alert of --alert-type)
+        #[arg(long)]
+        alert_id: Option<i64>,
+
+        /// Keep every extracted command without prompting
+        #[arg(long)]
+        yes: bool,
     },
 
     /// Run auto-generation pipeline to create playbook drafts from patterns
@@ -985,20 +2101,49 @@ pub enum GuardianCommands {
         limit: usize,
     },
 
+    /// Show a playbook draft with its revision history
+    ShowDraft {
+        /// Draft ID to show
+        draft_id: String,
+    },
+
     /// Validate a playbook draft
     ValidateDraft {
         /// Draft ID to validate
         draft_id: String,
     },
 
+    /// Edit a pending draft's steps, storing the result as a new revision
+    EditDraft {
+        /// Draft ID to edit
+        draft_id: String,
+
+        /// Read the edited steps (JSON array) from this file instead of opening $EDITOR
+        #[arg(long)]
+        steps_file: Option<PathBuf>,
+
+        /// Identity recorded as the author of the revision (default:
+        /// `--actor`, else the OS user)
+        #[arg(long)]
+        author: Option<String>,
+    },
+
     /// Approve a playbook draft
     ApproveDraft {
         /// Draft ID to approve
         draft_id: String,
 
-        /// Approver identity
-        #[arg(long, default_value = "operator")]
-        approver: String,
+        /// Revision being approved (must be the draft's current revision)
+        #[arg(long)]
+        revision: i64,
+
+        /// Approver identity (default: `--actor`, else the OS user)
+        #[arg(long)]
+        approver: Option<String>,
+
+        /// Sandbox run of this revision to attach to the approval
+        #[arg(long)]
+        sandbox_run: Option<String>,
     },
 
     /// Reject a playbook draft
@@ -1017,6 +2162,40 @@ pub enum GuardianCommands {
         draft_id: String,
     },
 
+    /// Write a playbook to a library file (YAML, or JSON for `.json`)
+    Export {
+        /// Playbook ID (stored or built-in)
+        #[arg(long)]
+        playbook: String,
+
+        /// File to write
+        #[arg(long)]
+        out: PathBuf,
+    },
+
+    /// Import a playbook from a library file
+    Import {
+        /// Library file to read
+        #[arg(long)]
+        from: PathBuf,
+
+        /// Enable the playbook right away (it is stored disabled otherwise)
+        #[arg(long)]
+        activate: bool,
+
+        /// Replace a playbook of the same name instead of storing a
+        /// versioned copy beside it
+        #[arg(long)]
+        overwrite: bool,
+    },
+
+    /// Validate every playbook file in a directory (exits nonzero on any issue)
+    Lint {
+        /// Directory of `.yaml`, `.yml` and `.json` playbook files
+        #[arg(long)]
+        dir: PathBuf,
+    },
+
     /// Show resolutions captured so far
     Resolutions {
         /// Filter by alert type
@@ -1050,24 +2229,69 @@ pub enum AutopilotCommands {
         limit: usize,
     },
 
-    /// Show decision summary statistics
+    /// Show decision summary statistics and what resolved alerts, per rule
     Summary,
 }
 
-/// Fleet subcommands
+/// Annotations subcommands
 #[derive(Subcommand, Debug)]
-pub enum FleetCommands {
-    /// Spawn new agents
-    Spawn {
-        /// Agent type
-        #[arg(long)]
-        agent_type: String,
+pub enum AnnotationsCommands {
+    /// List annotations, newest first
+    List {
+        /// Kinds to include, e.g. deploy,maintenance (default all)
+        #[arg(long = "kind", value_delimiter = ',')]
+        kinds: Vec<String>,
 
-        /// Count to spawn
-        #[arg(long, default_value = "1")]
-        count: u32,
+        /// Window start: an age (e.g. 24h, 7d) or a timestamp
+        #[arg(long, default_value = "7d")]
+        since: String,
 
-        /// Target machine
+        /// Window end: an age or a timestamp (default: now)
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only those that apply to this machine (its own, its tags' and
+        /// fleet-wide ones)
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Maximum annotations to list
+        #[arg(long, default_value = "100")]
+        limit: usize,
+    },
+}
+
+/// Ops subcommands
+#[derive(Subcommand, Debug)]
+pub enum OpsCommands {
+    /// Show a saved report
+    Show {
+        /// Operation ID printed when the report was saved
+        id: String,
+    },
+
+    /// List saved reports, newest first
+    List {
+        /// Maximum reports to list
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+/// Fleet subcommands
+#[derive(Subcommand, Debug)]
+pub enum FleetCommands {
+    /// Spawn new agents
+    Spawn {
+        /// Agent type
+        #[arg(long)]
+        agent_type: String,
+
+        /// Count to spawn
+        #[arg(long, default_value = "1")]
+        count: u32,
+
+        /// Target machine
         #[arg(long)]
         machine: String,
     },
@@ -1108,6 +2332,86 @@ pub enum FleetCommands {
         #[arg(long)]
         workload: Option<String>,
     },
+
+    /// Group the fleet by tool version and highlight outliers
+    Versions {
+        /// Only show this tool (e.g. sysmoni, vc-node)
+        #[arg(long)]
+        tool: Option<String>,
+    },
+
+    /// Show measured clock skew per machine, or one machine's history
+    Clock {
+        /// Show this machine's skew history instead of the fleet summary
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Maximum history entries
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Reconcile the fleet with a desired-state file
+    Apply {
+        /// Fleet file declaring agents per machine and tag
+        #[arg(long)]
+        file: PathBuf,
+
+        /// Print the plan without recording it
+        #[arg(long, conflicts_with = "execute")]
+        dry_run: bool,
+
+        /// Carry the plan out instead of only recording it
+        #[arg(long)]
+        execute: bool,
+    },
+
+    /// Run the pending and failed steps of an apply
+    Resume {
+        /// Apply ID printed by `vc fleet apply`
+        apply_id: String,
+    },
+
+    /// Compare running agents with the last applied fleet file
+    Drift,
+
+    /// Show running agents per machine from the agent inventory
+    Resources {
+        /// List this machine's agents individually
+        #[arg(long)]
+        machine: Option<String>,
+
+        /// Minutes without output before an agent in tmux counts as stalled
+        #[arg(long, default_value = "30")]
+        stalled_mins: i64,
+    },
+
+    /// Run one shell command on every selected machine and capture its output
+    Run {
+        /// Command to run, e.g. "df -h /"
+        #[arg(long)]
+        command: String,
+
+        /// Machines to run on: all, tag:<tag> or <id>,...
+        #[arg(long, default_value = "all")]
+        machines: vc_collect::fleet_run::MachineSelector,
+
+        /// Machines running at once
+        #[arg(long, default_value = "8")]
+        concurrency: usize,
+
+        /// Seconds each machine gets before it counts as failed
+        #[arg(long, default_value = "15")]
+        timeout: u64,
+
+        /// Start no more machines once one has failed
+        #[arg(long)]
+        fail_fast: bool,
+
+        /// Skip the confirmation asked before running on many machines
+        #[arg(long)]
+        yes: bool,
+    },
 }
 
 /// Audit trail subcommands
@@ -1185,6 +2489,27 @@ pub enum MachineCommands {
     Probe {
         /// Machine ID
         id: String,
+
+        /// Also run the machine's configured HTTP checks once
+        #[arg(long)]
+        http_checks: bool,
+    },
+
+    /// Fold a duplicate registry entry into another: its history moves to
+    /// the kept machine id and it leaves the registry
+    Merge {
+        /// Machine ID to fold away
+        #[arg(required_unless_present = "conflicts", requires = "into")]
+        from: Option<String>,
+
+        /// Machine ID that stays
+        #[arg(long)]
+        into: Option<String>,
+
+        /// Merge every UUID conflict probes found instead: each machine
+        /// re-added under a new ID is folded into that new ID
+        #[arg(long, conflicts_with_all = ["from", "into"])]
+        conflicts: bool,
     },
 
     /// Update machine status
@@ -1196,6 +2521,86 @@ pub enum MachineCommands {
         #[arg(long)]
         enabled: bool,
     },
+
+    /// Put a machine into or take it out of maintenance; shows its
+    /// maintenance state with neither --on nor --off
+    Maintenance {
+        /// Machine ID
+        id: String,
+
+        /// Enter maintenance: collectors skip the machine, its health score
+        /// stays as it was and the fleet aggregate leaves it out
+        #[arg(long, conflicts_with = "off")]
+        on: bool,
+
+        /// Leave maintenance
+        #[arg(long)]
+        off: bool,
+
+        /// Why the machine is in maintenance
+        #[arg(long, requires = "on")]
+        reason: Option<String>,
+
+        /// End maintenance automatically at this time (RFC3339)
+        #[arg(long, requires = "on")]
+        until: Option<String>,
+
+        /// Who is changing the maintenance state, for the audit log
+        /// (default: `--actor`, else the OS user)
+        #[arg(long)]
+        by: Option<String>,
+    },
+
+    /// Declare that a machine depends on another, so alerts on it are
+    /// traced to the other machine when that one has a critical alert
+    Depend {
+        /// Machine ID of the dependent machine
+        id: String,
+
+        /// Machine ID it depends on
+        #[arg(long)]
+        on: String,
+
+        /// What it depends on the other machine for
+        #[arg(
+            long,
+            value_parser = ["storage", "network", "service"],
+            required_unless_present = "remove"
+        )]
+        kind: Option<String>,
+
+        /// Drop the declaration instead
+        #[arg(long, conflicts_with = "kind")]
+        remove: bool,
+    },
+
+    /// Show what differs in a machine's environment (tool, agent and
+    /// toolchain versions, hardware, platform) between two times, or
+    /// against another machine
+    Diff {
+        /// Machine ID
+        id: String,
+
+        /// Earlier time (RFC3339); with --against, where drift marking
+        /// starts (default: a week before --to)
+        #[arg(long, required_unless_present = "against")]
+        from: Option<String>,
+
+        /// Later time (RFC3339, default: now)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Compare with this machine as of --to instead of over time
+        #[arg(long)]
+        against: Option<String>,
+    },
+}
+
+/// Where commands load their config from: `--config` and `--profile`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfigSource<'a> {
+    pub path: Option<&'a PathBuf>,
+    pub profile: Option<&'a str>,
 }
 
 impl Cli {
@@ -1225,7 +2630,39 @@ impl Cli {
     /// underlying operation reports an error, or the command is cancelled by a
     /// shutdown signal before it drains.
     pub async fn run_with_cx(self, cx: &Cx) -> Result<(), CliError> {
+        let result = self.dispatch(cx).await;
+        // Output is printed and the process exits next: write what any
+        // store still open buffers, whether the command succeeded or not
+        if let Err(err) = VcStore::flush_open_stores() {
+            tracing::warn!(error = %err, "failed to flush the store before exit");
+        }
+        result
+    }
+
+    async fn dispatch(self, cx: &Cx) -> Result<(), CliError> {
+        let config_source = ConfigSource {
+            path: self.config.as_ref(),
+            profile: self.profile.as_deref(),
+        };
+        if matches!(self.format, OutputFormat::Text) {
+            let zone = load_config(config_source)
+                .ok()
+                .and_then(|config| config.global.timezone.parse().ok())
+                .unwrap_or_default();
+            let _ = TIME_FORMAT.set(vc_query::TimeFormatter::new(self.timestamps, zone));
+        }
+        if let Some(columns) = &self.csv_columns {
+            let _ = CSV_COLUMNS.set(columns.clone());
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            let _ = TOON_BUDGET.set(toon::ToonBudget {
+                max_tokens,
+                chars_per_token: self.chars_per_token,
+            });
+        }
+
         match self.command {
+            #[cfg(feature = "tui")]
             Commands::Tui { inline } => {
                 if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
                     return Err(CliError::CommandFailed(
@@ -1233,18 +2670,18 @@ impl Cli {
                     ));
                 }
 
-                let config = load_config(self.config.as_ref())?;
+                let config = load_config(config_source)?;
                 // `resolve_tui_options` must run before `config` moves into the Arc.
                 let options = resolve_tui_options(&config, inline);
-                let store = Arc::new(VcStore::open(&config.global.db_path)?);
-                let config_source = self
+                let store = Arc::new(open_duckdb_store(&config)?);
+                let config_label = self
                     .config
                     .as_ref()
                     .map_or_else(|| "discovered".to_string(), |p| p.display().to_string());
                 let context = Some(vc_tui::AppContext::new(
                     store,
                     Arc::new(config),
-                    config_source,
+                    config_label,
                 ));
 
                 let controller = ShutdownController::new();
@@ -1257,22 +2694,56 @@ impl Cli {
                 )
                 .await?;
             }
-            Commands::Daemon { foreground } => {
+            #[cfg(not(feature = "tui"))]
+            Commands::Tui { .. } => return Err(CliError::FeatureDisabled("tui")),
+            Commands::Daemon {
+                command: Some(DaemonCommands::Check),
+                ..
+            } => {
+                let store = open_store(config_source)?;
+                let mut output = match store.get_daemon_resource_state()? {
+                    Some(state) => serde_json::to_value(&state)
+                        .map_err(|e| CliError::CommandFailed(e.to_string()))?,
+                    None => serde_json::json!({
+                        "level": "unknown",
+                        "message": "no resource state recorded; is `vc daemon` running?",
+                    }),
+                };
+                output["lease"] = match store.current_lease(vc_store::DAEMON_LEASE)? {
+                    Some(lease) => {
+                        let live = lease.is_live(Utc::now());
+                        let mut lease = serde_json::to_value(&lease)
+                            .map_err(|e| CliError::CommandFailed(e.to_string()))?;
+                        lease["live"] = serde_json::Value::Bool(live);
+                        lease
+                    }
+                    None => serde_json::Value::Null,
+                };
+                print_output(&output, self.format);
+            }
+            Commands::Daemon {
+                foreground,
+                takeover,
+                command: None,
+            } => {
                 let controller = ShutdownController::new();
                 let receiver = controller.subscribe();
                 run_with_shutdown_budget(
                     cx,
                     "daemon",
                     controller,
-                    run_daemon(self.config.as_ref(), foreground, cx, receiver),
+                    run_daemon(config_source, foreground, takeover, cx, receiver),
                 )
                 .await?;
             }
-            Commands::Status { machine } => {
+            Commands::Status { machine, sparkline } => {
                 // Same store-backed payload `vc robot status` returns, so the
                 // human and the agent can never disagree about the fleet.
-                let store = open_store(self.config.as_ref())?;
+                let config = load_config(config_source)?;
+                let store = open_store(config_source)?;
                 let mut envelope = robot::robot_status(&store)?;
+                let overdue = overdue_maintenance(&store, config.maintenance.max_hours)?;
+                envelope.warnings.extend(overdue.iter().cloned());
 
                 // `--machine` narrows the machine list; the fleet, repo and alert
                 // roll-ups stay fleet-wide, which is what they are.
@@ -1288,16 +2759,37 @@ impl Cli {
 
                 match self.format {
                     OutputFormat::Json => println!("{}", envelope.to_json_pretty()),
-                    OutputFormat::Toon => {
-                        use toon::ToToon;
-                        println!("{}", envelope.data.to_toon());
-                    }
+                    OutputFormat::Toon => println!("{}", robot_toon(&envelope.data)),
+                    OutputFormat::Csv => print_output(machines, self.format),
                     OutputFormat::Text => {
+                        let alert_trend = if sparklines_enabled(sparkline, self.format) {
+                            let since = Utc::now() - ChronoDuration::hours(24);
+                            let qb = vc_query::QueryBuilder::new(&store);
+                            Some((since, qb.alert_samples_by_machine(since)?))
+                        } else {
+                            None
+                        };
+                        for reminder in &overdue {
+                            println!("!!! {reminder}");
+                        }
                         let fleet = &envelope.data.fleet;
                         println!(
-                            "fleet: {} machines ({} online, {} offline)  health {:.2}",
-                            fleet.total_machines, fleet.online, fleet.offline, fleet.health_score
+                            "fleet: {} machines ({} online, {} offline, {} maintenance)  health {:.2}",
+                            fleet.total_machines,
+                            fleet.online,
+                            fleet.offline,
+                            fleet.maintenance,
+                            fleet.health_score
                         );
+                        // A fleet without tags has nothing to split
+                        if envelope
+                            .data
+                            .by_tag
+                            .iter()
+                            .any(|group| group.tag != vc_query::UNTAGGED_GROUP)
+                        {
+                            print_tag_breakdown(&envelope.data.by_tag);
+                        }
 
                         if machines.is_empty() {
                             println!("(no machines in the registry - run `vc machine add`)");
@@ -1306,9 +2798,10 @@ impl Cli {
                             let health = entry
                                 .health_score
                                 .map_or_else(|| "-".to_string(), |score| format!("{score:.2}"));
-                            let seen = entry
-                                .last_seen
-                                .map_or_else(|| "never".to_string(), |ts| ts.to_rfc3339());
+                            let seen = entry.last_seen.map_or_else(
+                                || "never".to_string(),
+                                |ts| time_format().timestamp(ts),
+                            );
                             let cpu = entry
                                 .metrics
                                 .as_ref()
@@ -1326,6 +2819,26 @@ impl Cli {
                             if let Some(issue) = &entry.top_issue {
                                 println!("      top_issue: {issue}");
                             }
+                            if let Some((since, alerts)) = &alert_trend {
+                                let samples = alerts.get(&entry.id).map_or(&[][..], Vec::as_slice);
+                                let line = sparkline(
+                                    samples,
+                                    *since,
+                                    vc_query::sparkline::Bucketing::Sum,
+                                    |v| format!("{v:.0}"),
+                                );
+                                println!("      alerts_24h: {line}");
+                            }
+                            if let Some(maintenance) = &entry.maintenance {
+                                let until = maintenance.until.map_or_else(
+                                    || "until turned off".to_string(),
+                                    |ts| format!("until {}", time_format().timestamp(ts)),
+                                );
+                                println!(
+                                    "      maintenance: {} ({until})",
+                                    maintenance.reason.as_deref().unwrap_or("no reason given")
+                                );
+                            }
                         }
 
                         let repos = &envelope.data.repos;
@@ -1344,87 +2857,111 @@ impl Cli {
                     }
                 }
             }
-            Commands::Robot { command } => {
-                use toon::ToToon;
-
-                match command {
-                    RobotCommands::Health => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_health(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => println!("{}", output.data.to_toon()),
-                            _ => println!("{}", output.to_json_pretty()),
-                        }
-                    }
-                    RobotCommands::Triage => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_triage(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => println!("{}", output.data.to_toon()),
-                            _ => println!("{}", output.to_json_pretty()),
-                        }
-                    }
-                    RobotCommands::Status => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_status(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => println!("{}", output.data.to_toon()),
-                            _ => println!("{}", output.to_json_pretty()),
-                        }
-                    }
-                    RobotCommands::Accounts => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_accounts(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => {
-                                println!("{}", toon::to_toon_via_json(&output.data));
-                            }
-                            _ => println!("{}", output.to_json_pretty()),
-                        }
+            Commands::Robot { command } => match command {
+                RobotCommands::Health { verify } => {
+                    if verify && !matches!(self.format, OutputFormat::Toon) {
+                        return Err(CliError::CommandFailed(
+                            "--verify checks the TOON encoding; use it with --format toon"
+                                .to_string(),
+                        ));
                     }
-                    RobotCommands::Oracle => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_oracle(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => {
-                                println!("{}", toon::to_toon_via_json(&output.data));
-                            }
-                            _ => println!("{}", output.to_json_pretty()),
-                        }
+                    if verify && self.max_tokens.is_some() {
+                        return Err(CliError::CommandFailed(
+                            "--verify emits the lossless encoding, which --max-tokens \
+                             cannot cut; use one or the other"
+                                .to_string(),
+                        ));
                     }
-                    RobotCommands::Repos => {
-                        let store = open_store(self.config.as_ref())?;
-                        let output = robot::robot_repos(&store)?;
-                        match self.format {
-                            OutputFormat::Toon => {
-                                println!("{}", toon::to_toon_via_json(&output.data));
-                            }
-                            _ => println!("{}", output.to_json_pretty()),
+                    let store = open_store(config_source)?;
+                    let output = robot::robot_health(&store)?;
+                    match self.format {
+                        OutputFormat::Toon if verify => {
+                            let value = serde_json::to_value(&output.data)
+                                .map_err(|e| CliError::CommandFailed(e.to_string()))?;
+                            let encoded = toon::encode_verified(&value).map_err(|e| {
+                                CliError::CommandFailed(format!("TOON round-trip failed: {e}"))
+                            })?;
+                            println!("{encoded}");
                         }
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
                     }
-                    RobotCommands::Machines => {
-                        let config = load_config(self.config.as_ref())?;
-                        let (machines, warning) =
-                            robot_machines_inventory(&config, self.config.as_ref());
-                        let mut data = serde_json::json!({
-                            "machines": machines,
-                            "total": machines.len(),
-                        });
-                        if let Some(warning) = warning {
-                            data["warning"] = serde_json::Value::String(warning);
-                        }
-                        let output = robot::RobotEnvelope::new("vc.robot.machines.v1", data);
-                        match self.format {
-                            OutputFormat::Toon => {
-                                println!("{}", toon::to_toon_via_json(&output.data));
-                            }
-                            _ => println!("{}", output.to_json_pretty()),
+                }
+                RobotCommands::Triage { max_items } => {
+                    let store = open_store(config_source)?;
+                    let config = load_config(config_source)?;
+                    let output =
+                        robot::robot_triage(&store, &config.collectors.min_versions, max_items)?;
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
+                }
+                RobotCommands::Status => {
+                    let store = open_store(config_source)?;
+                    let output = robot::robot_status(&store)?;
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
+                }
+                RobotCommands::Accounts => {
+                    let store = open_store(config_source)?;
+                    let output = robot::robot_accounts(&store)?;
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
+                }
+                RobotCommands::Oracle => {
+                    let store = open_store(config_source)?;
+                    let output = robot::robot_oracle(&store)?;
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
+                }
+                RobotCommands::Repos => {
+                    let store = open_store(config_source)?;
+                    let output = robot::robot_repos(&store)?;
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
+                }
+                RobotCommands::Schema { command } => match command {
+                    RobotSchemaCommands::Export { out, force } => {
+                        let report = schema_registry::export_schemas(&out, force).map_err(|e| {
+                            CliError::CommandFailed(format!("Schema export failed: {e}"))
+                        })?;
+                        print_output(&report, self.format);
+                    }
+                    RobotSchemaCommands::Check { dir } => {
+                        let report = schema_registry::check_schemas(&dir).map_err(|e| {
+                            CliError::CommandFailed(format!("Schema check failed: {e}"))
+                        })?;
+                        print_output(&report, self.format);
+                        if !report.is_clean() {
+                            return Err(CliError::CommandFailed(format!(
+                                "robot schemas in {} are out of date: export new versions, \
+                                     and bump the envelope version for changed ones",
+                                dir.display()
+                            )));
                         }
                     }
+                },
+                RobotCommands::Machines => {
+                    let config = load_config(config_source)?;
+                    let (machines, warning) = robot_machines_inventory(&config, config_source);
+                    let output = robot::machines_envelope(&machines, warning);
+                    match self.format {
+                        OutputFormat::Toon => println!("{}", robot_toon(&output.data)),
+                        _ => println!("{}", output.to_json_pretty()),
+                    }
                 }
-            }
+            },
             Commands::Audit { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
                 match command {
                     AuditCommands::List {
                         event_type,
@@ -1449,6 +2986,7 @@ impl Cli {
                         let filter = AuditEventFilter {
                             event_type,
                             machine_id: machine,
+                            actor: self.actor.clone(),
                             since,
                             limit,
                         };
@@ -1468,12 +3006,9 @@ impl Cli {
                 }
             }
             Commands::Machines { command } => {
-                let store = Arc::new(open_store(self.config.as_ref())?);
-                let config = match &self.config {
-                    Some(path) => VcConfig::load_with_env(path)?,
-                    None => VcConfig::discover_with_env()?,
-                };
-                let registry = vc_collect::machine::MachineRegistry::new(store);
+                let store = Arc::new(open_store(config_source)?);
+                let config = load_config(config_source)?;
+                let registry = vc_collect::machine::MachineRegistry::new(store.clone());
                 let _ = registry.load_from_config(&config);
 
                 match command {
@@ -1511,9 +3046,69 @@ impl Cli {
                         };
                         let machines = registry.list_machines(Some(filter)).unwrap_or_default();
                         print_output(&machines, self.format);
+                        if matches!(self.format, OutputFormat::Text) {
+                            let absent: Vec<&str> = machines
+                                .iter()
+                                .filter(|m| m.config_absent)
+                                .map(|m| m.machine_id.as_str())
+                                .collect();
+                            if !absent.is_empty() {
+                                eprintln!(
+                                    "No longer in the config (kept with their history): {}",
+                                    absent.join(", ")
+                                );
+                            }
+                        }
                     }
                     MachineCommands::Show { id } => match registry.get_machine(&id) {
-                        Ok(Some(machine)) => print_output(&machine, self.format),
+                        Ok(Some(machine)) => {
+                            let services = registry.list_services(&id).map_err(|e| {
+                                CliError::CommandFailed(format!("Error fetching services: {e}"))
+                            })?;
+                            let versions = registry.list_tool_versions(&id).map_err(|e| {
+                                CliError::CommandFailed(format!("Error fetching versions: {e}"))
+                            })?;
+                            let dependencies = store.machine_dependencies()?;
+                            let depends_on: Vec<_> = dependencies
+                                .iter()
+                                .filter(|dep| dep.machine_id == id)
+                                .map(|dep| {
+                                    serde_json::json!({
+                                        "machine_id": dep.depends_on,
+                                        "kind": dep.kind,
+                                    })
+                                })
+                                .collect();
+                            let depended_on_by: Vec<_> = dependencies
+                                .iter()
+                                .filter(|dep| dep.depends_on == id)
+                                .map(|dep| {
+                                    serde_json::json!({
+                                        "machine_id": dep.machine_id,
+                                        "kind": dep.kind,
+                                    })
+                                })
+                                .collect();
+                            // Most recently seen first: the first under this
+                            // id is the current identity
+                            let mut identities = match &machine.machine_uuid {
+                                Some(uuid) => store.machine_identities(uuid)?,
+                                None => Vec::new(),
+                            };
+                            if let Some(current) = identities
+                                .iter()
+                                .position(|identity| identity.machine_id == id)
+                            {
+                                identities.remove(current);
+                            }
+                            let mut payload = serde_json::to_value(&machine).unwrap_or_default();
+                            payload["prior_identities"] = serde_json::json!(identities);
+                            payload["services"] = serde_json::json!(services);
+                            payload["versions"] = serde_json::json!(versions);
+                            payload["depends_on"] = serde_json::json!(depends_on);
+                            payload["depended_on_by"] = serde_json::json!(depended_on_by);
+                            print_output(&payload, self.format);
+                        }
                         Ok(None) => {
                             return Err(CliError::CommandFailed(format!(
                                 "Machine not found: {id}"
@@ -1555,33 +3150,30 @@ impl Cli {
                                     .collect::<Vec<_>>()
                             })
                             .unwrap_or_default();
-                        let is_local = ssh_host.is_none();
-
-                        let machine = vc_collect::machine::Machine {
-                            machine_id: id.clone(),
-                            hostname: ssh_host.clone().unwrap_or_else(|| id.clone()),
-                            display_name: Some(id.clone()),
-                            ssh_host,
-                            ssh_user,
-                            ssh_key_path: None,
-                            ssh_port: port,
-                            is_local,
-                            os_type: None,
-                            arch: None,
-                            added_at: Some(chrono::Utc::now().to_rfc3339()),
-                            last_seen_at: None,
-                            last_probe_at: None,
-                            status: vc_collect::machine::MachineStatus::Unknown,
-                            tags: tags_vec,
-                            metadata: None,
-                            enabled: true,
-                        };
+                        let mut builder =
+                            Machine::builder(&id, ssh_host.clone().unwrap_or_else(|| id.clone()))
+                                .with_display_name(&id)
+                                .with_ssh_port(port)
+                                .with_local(ssh_host.is_none())
+                                .with_added_at(chrono::Utc::now().to_rfc3339())
+                                .with_tags(tags_vec);
+                        if let (Some(host), Some(user)) = (ssh_host, ssh_user) {
+                            builder = builder.with_ssh_host(host).with_ssh_user(user);
+                        }
+                        let machine = builder.build();
                         registry.upsert_machine(&machine).map_err(|e| {
                             CliError::CommandFailed(format!("Failed to add machine: {e}"))
                         })?;
+                        // As registered, with its UUID
+                        let machine = registry
+                            .get_machine(&id)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Error fetching machine: {e}"))
+                            })?
+                            .unwrap_or(machine);
                         print_output(&machine, self.format);
                     }
-                    MachineCommands::Probe { id } => {
+                    MachineCommands::Probe { id, http_checks } => {
                         let machine = match registry.get_machine(&id) {
                             Ok(Some(machine)) => machine,
                             Ok(None) => {
@@ -1596,16 +3188,18 @@ impl Cli {
                             }
                         };
 
-                        let executor = match machine.ssh_config() {
+                        let mut executor = match machine.ssh_config() {
                             Some(cfg) => Executor::remote(cfg),
                             None => Executor::local(),
                         };
 
-                        // First, check connectivity with uname
-                        let connectivity =
-                            executor.run(cx, "uname -s", Duration::from_secs(5)).await;
-                        let (status, os_detail) = match connectivity {
-                            Ok(output) if output.exit_code == 0 => {
+                        // First, check connectivity while finding out what
+                        // the machine runs: uname, then ver, then PowerShell
+                        let detected =
+                            vc_collect::detect_platform(cx, &executor, Duration::from_secs(5))
+                                .await;
+                        let (status, os_detail, platform) = match detected {
+                            Ok(detected) => {
                                 registry
                                     .update_status(&id, vc_collect::machine::MachineStatus::Online)
                                     .map_err(|e| {
@@ -1613,22 +3207,20 @@ impl Cli {
                                             "Status update failed: {e}"
                                         ))
                                     })?;
-                                (
-                                    vc_collect::machine::MachineStatus::Online,
-                                    Some(output.stdout.trim().to_string()),
-                                )
-                            }
-                            Ok(output) => {
-                                registry
-                                    .update_status(&id, vc_collect::machine::MachineStatus::Offline)
-                                    .map_err(|e| {
+                                if detected.platform != vc_collect::Platform::Unknown {
+                                    registry.set_platform(&id, detected.platform).map_err(|e| {
                                         CliError::CommandFailed(format!(
-                                            "Status update failed: {e}"
+                                            "Platform update failed: {e}"
                                         ))
                                     })?;
+                                }
+                                executor = executor.with_shell(
+                                    vc_collect::executor::Shell::for_platform(detected.platform),
+                                );
                                 (
-                                    vc_collect::machine::MachineStatus::Offline,
-                                    Some(output.stderr),
+                                    vc_collect::machine::MachineStatus::Online,
+                                    Some(detected.detail),
+                                    detected.platform,
                                 )
                             }
                             Err(err) => {
@@ -1642,14 +3234,96 @@ impl Cli {
                                 (
                                     vc_collect::machine::MachineStatus::Offline,
                                     Some(err.to_string()),
+                                    machine.platform(),
                                 )
                             }
                         };
 
-                        // If online, probe for tools
-                        let tools_result = if status == vc_collect::machine::MachineStatus::Online {
+                        // If online, measure its clock and probe for tools and
+                        // configured services
+                        let (tools_result, services, clock_skew, identity) = if status
+                            == vc_collect::machine::MachineStatus::Online
+                        {
+                            let clock_skew = match vc_collect::clock::measure_clock_skew(
+                                cx,
+                                &id,
+                                &executor,
+                                Duration::from_secs(5),
+                            )
+                            .await
+                            {
+                                Ok(sample) => {
+                                    registry
+                                        .record_clock_skew(
+                                            &sample,
+                                            config.ingest.clock_skew_warn_secs,
+                                        )
+                                        .map_err(|e| {
+                                            CliError::CommandFailed(format!(
+                                                "Clock skew update failed: {e}"
+                                            ))
+                                        })?;
+                                    Some(sample)
+                                }
+                                Err(e) => {
+                                    tracing::warn!(
+                                        machine = %id,
+                                        error = %e,
+                                        "clock skew probe failed"
+                                    );
+                                    None
+                                }
+                            };
+                            let identity = probe_identity(
+                                cx,
+                                &store,
+                                &id,
+                                &executor,
+                                config.identity.auto_rename,
+                                &vc_store::ActorContext::cli(self.actor.as_deref()),
+                            )
+                            .await?;
                             let prober = vc_collect::ToolProber::new();
-                            Some(prober.probe_machine(cx, &id, &executor, &registry).await)
+                            let tools = prober.probe_machine(cx, &id, &executor, &registry).await;
+                            let specs: Vec<_> = config
+                                .service_checks_for(&machine.tags)
+                                .into_iter()
+                                .filter_map(|(service, required)| {
+                                    vc_collect::ServiceSpec::from_config(service, required)
+                                })
+                                .collect();
+                            let services = vc_collect::ServiceProber::new()
+                                .check_services(cx, &id, &executor, &specs)
+                                .await;
+                            registry.record_services(&id, &services).map_err(|e| {
+                                CliError::CommandFailed(format!(
+                                    "Service status update failed: {e}"
+                                ))
+                            })?;
+                            (Some(tools), Some(services), clock_skew, identity)
+                        } else {
+                            (None, None, None, None)
+                        };
+
+                        // Hub-side checks still mean something when the
+                        // machine itself is unreachable, so run them anyway
+                        let http_results = if http_checks {
+                            let checks = config
+                                .machines
+                                .get(&id)
+                                .map(|m| m.http_checks.as_slice())
+                                .unwrap_or_default();
+                            let results = vc_collect::collectors::http_check::run_http_checks(
+                                cx, &executor, checks,
+                            )
+                            .await;
+                            let collected_at = Utc::now().to_rfc3339();
+                            let rows: Vec<_> = results
+                                .iter()
+                                .map(|r| r.to_row(&id, &collected_at))
+                                .collect();
+                            store.insert_json_batch("http_check_results", &rows)?;
+                            Some(results)
                         } else {
                             None
                         };
@@ -1658,6 +3332,7 @@ impl Cli {
                             "machine_id": id,
                             "status": status.as_str(),
                             "os": os_detail,
+                            "platform": platform,
                             "tools": tools_result.as_ref().map(|r| {
                                 r.found_tools.iter().map(|t| serde_json::json!({
                                     "name": t.tool_name,
@@ -1668,8 +3343,59 @@ impl Cli {
                             }),
                             "tools_found": tools_result.as_ref().map_or(0, vc_collect::ProbeResult::tool_count),
                             "probe_errors": tools_result.as_ref().map(|r| &r.errors),
+                            "services": services,
+                            "clock_skew": clock_skew,
+                            "identity": identity,
+                            "http_checks": http_results,
                         });
                         print_output(&payload, self.format);
+                        if matches!(self.format, OutputFormat::Text)
+                            && let Some(vc_collect::identity::IdentityCheck {
+                                outcome:
+                                    vc_collect::identity::IdentityOutcome::Conflict {
+                                        registered_as,
+                                        ..
+                                    },
+                                ..
+                            }) = &identity
+                        {
+                            eprintln!(
+                                "{id} is the machine registered as {registered_as}; \
+                                 `vc machines merge {registered_as} --into {id}` keeps one history"
+                            );
+                        }
+                    }
+                    MachineCommands::Merge {
+                        from,
+                        into,
+                        conflicts,
+                    } => {
+                        let actor = vc_store::ActorContext::cli(self.actor.as_deref());
+                        let pairs = if conflicts {
+                            store
+                                .machine_uuid_conflicts()?
+                                .into_iter()
+                                .map(|conflict| (conflict.registered_as, conflict.seen_as))
+                                .collect()
+                        } else {
+                            vec![(
+                                from.expect("clap requires <FROM> without --conflicts"),
+                                into.expect("clap requires --into with <FROM>"),
+                            )]
+                        };
+                        let mut merged = Vec::new();
+                        for (from, into) in pairs {
+                            // An earlier merge of this run may have folded
+                            // either one away already
+                            if conflicts
+                                && (registry.get_machine(&from).ok().flatten().is_none()
+                                    || registry.get_machine(&into).ok().flatten().is_none())
+                            {
+                                continue;
+                            }
+                            merged.push(store.merge_machines(&from, &into, &actor)?);
+                        }
+                        print_output(&serde_json::json!({ "merged": merged }), self.format);
                     }
                     MachineCommands::Enable { id, enabled } => {
                         let existing = registry.get_machine(&id).map_err(|e| {
@@ -1693,25 +3419,117 @@ impl Cli {
                             })?;
                         print_output(&updated, self.format);
                     }
-                }
-            }
-            Commands::Query { command } => {
-                let store = open_store(self.config.as_ref())?;
-                let validator = vc_query::QueryValidator::new(vc_query::GuardrailConfig::default());
-
-                match command {
-                    QueryCommands::Raw { sql, limit } => {
-                        // Validate the query is read-only
-                        validator.validate_raw(&sql)?;
-
-                        // Add LIMIT if not present
-                        let query = if sql.to_uppercase().contains("LIMIT") {
-                            sql
+                    MachineCommands::Maintenance {
+                        id,
+                        on,
+                        off,
+                        reason,
+                        until,
+                        by,
+                    } => {
+                        let by = command_actor(by.as_deref(), self.actor.as_deref());
+                        store.expire_maintenance(Utc::now())?;
+                        let payload = if on {
+                            let until = until.as_deref().map(parse_rfc3339).transpose()?;
+                            if until.is_some_and(|until| until <= Utc::now()) {
+                                return Err(CliError::CommandFailed(
+                                    "--until must be in the future".to_string(),
+                                ));
+                            }
+                            let window =
+                                store.start_maintenance(&id, reason.as_deref(), until, &by)?;
+                            serde_json::json!({ "machine_id": id, "maintenance": window })
+                        } else if off {
+                            let ended = store.end_maintenance(&id, &by)?;
+                            serde_json::json!({
+                                "machine_id": id,
+                                "was_in_maintenance": ended.is_some(),
+                                "ended": ended,
+                            })
+                        } else {
+                            serde_json::json!({
+                                "machine_id": id,
+                                "maintenance": store.maintenance_window(&id)?,
+                            })
+                        };
+                        print_output(&payload, self.format);
+                    }
+                    MachineCommands::Depend {
+                        id,
+                        on,
+                        kind,
+                        remove,
+                    } => {
+                        let payload = if remove {
+                            serde_json::json!({
+                                "machine_id": id,
+                                "depends_on": on,
+                                "removed": store.remove_machine_dependency(&id, &on)?,
+                            })
+                        } else {
+                            let kind = kind.expect("clap requires --kind without --remove");
+                            serde_json::json!(store.add_machine_dependency(&id, &on, &kind)?)
+                        };
+                        print_output(&payload, self.format);
+                    }
+                    MachineCommands::Diff {
+                        id,
+                        from,
+                        to,
+                        against,
+                    } => {
+                        let to = to
+                            .as_deref()
+                            .map_or_else(|| Ok(Utc::now()), parse_rfc3339)?;
+                        let from = from.as_deref().map(parse_rfc3339).transpose()?;
+                        if from.is_some_and(|from| from >= to) {
+                            return Err(CliError::CommandFailed(
+                                "--from must be before --to".to_string(),
+                            ));
+                        }
+                        let qb = vc_query::QueryBuilder::new(&store);
+                        let diff = match (&against, from) {
+                            (Some(other), from) => qb.environment_diff(
+                                &id,
+                                to,
+                                other,
+                                to,
+                                from.unwrap_or(to - ChronoDuration::days(7)),
+                            ),
+                            (None, Some(from)) => qb.environment_diff(&id, from, &id, to, from),
+                            (None, None) => unreachable!("clap requires --from without --against"),
+                        }
+                        .map_err(|e| CliError::CommandFailed(format!("Diff failed: {e}")))?;
+                        if matches!(self.format, OutputFormat::Text) {
+                            print!("{}", diff.render_text());
+                        } else {
+                            print_output(&diff, self.format);
+                        }
+                    }
+                }
+            }
+            Commands::Query { command } => {
+                let config = load_config(config_source)?;
+                let store = open_store(config_source)?;
+                let validator = vc_query::QueryValidator::new(
+                    vc_query::GuardrailConfig::from_config(&config.query),
+                );
+
+                match command {
+                    QueryCommands::Raw { sql, limit } => {
+                        // Validate the query is read-only
+                        validator.validate_raw(&sql)?;
+
+                        // Add LIMIT if not present
+                        let query = if sql.to_uppercase().contains("LIMIT") {
+                            sql
                         } else {
                             format!("{} LIMIT {}", sql.trim_end_matches(';'), limit)
                         };
 
-                        let rows = store.query_json(&query)?;
+                        let rows = validator
+                            .execute(&store, &query, vc_query::QueryOrigin::Operator)
+                            .map_err(with_timeout_hint)?;
 
                         if rows.len() >= limit {
                             eprintln!("Warning: Results may be truncated at {limit} rows");
@@ -1719,7 +3537,19 @@ impl Cli {
 
                         print_output(&rows, self.format);
                     }
-                    QueryCommands::Template { name, param } => {
+                    QueryCommands::Template {
+                        name,
+                        param,
+                        columns,
+                        aggregate,
+                        group_by,
+                    } => {
+                        let reshape = vc_query::Reshape::parse(
+                            columns.as_deref(),
+                            aggregate.as_deref(),
+                            group_by.as_deref(),
+                        )?;
+
                         // Parse parameters
                         let mut params = std::collections::HashMap::new();
                         for p in param {
@@ -1735,36 +3565,51 @@ impl Cli {
                         // Expand template
                         let sql = validator.expand_template(&name, &params)?;
 
-                        // Execute query
-                        let rows = store.query_json(&sql)?;
+                        // Execute query, then select/aggregate
+                        let rows = validator
+                            .execute(&store, &sql, vc_query::QueryOrigin::Operator)
+                            .map_err(with_timeout_hint)?;
+                        let rows = reshape.apply(rows)?;
                         print_output(&rows, self.format);
                     }
                     QueryCommands::Templates => {
-                        let templates: Vec<_> = validator
-                            .templates()
-                            .iter()
-                            .map(|(name, t)| {
-                                serde_json::json!({
-                                    "name": name,
-                                    "description": t.description,
-                                    "params": t.params.iter().map(|p| serde_json::json!({
-                                        "name": p.name,
-                                        "description": p.description,
-                                        "default": p.default,
-                                    })).collect::<Vec<_>>(),
-                                    "agent_safe": t.agent_safe,
-                                })
-                            })
-                            .collect();
-                        print_output(&templates, self.format);
+                        print_output(&validator.template_catalog(), self.format);
                     }
                     QueryCommands::Ask { question } => {
                         let engine = vc_query::NlEngine::new(Arc::new(store));
-                        let result = engine.ask(&question).map_err(|e| {
-                            CliError::CommandFailed(format!("NL query failed: {e}"))
+                        let result = engine.ask(&question).map_err(|e| match e {
+                            vc_query::QueryError::Timeout { .. } => with_timeout_hint(e),
+                            e => CliError::CommandFailed(format!("NL query failed: {e}")),
                         })?;
                         print_output(&result, self.format);
                     }
+                    QueryCommands::Pending { all } => {
+                        let proposals =
+                            store.list_query_proposals((!all).then_some("pending"), 100)?;
+                        print_output(&proposals, self.format);
+                    }
+                    QueryCommands::Approve { id } => {
+                        let operator = vc_store::ActorContext::cli(self.actor.as_deref());
+                        let proposal = validator
+                            .approve_proposal(&store, id, &operator)?
+                            .ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "Query proposal {id} is not pending"
+                                ))
+                            })?;
+                        print_output(&proposal, self.format);
+                    }
+                    QueryCommands::Deny { id, reason } => {
+                        let operator = vc_store::ActorContext::cli(self.actor.as_deref());
+                        let proposal = store
+                            .decide_query_proposal(id, false, reason.as_deref(), &operator)?
+                            .ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "Query proposal {id} is not pending"
+                                ))
+                            })?;
+                        print_output(&proposal, self.format);
+                    }
                 }
             }
             Commands::Config { command } => {
@@ -1775,6 +3620,7 @@ impl Cli {
                         file,
                         errors_only,
                         json,
+                        deep,
                     } => {
                         // Load config from specified file or discover
                         let config = match file {
@@ -1783,7 +3629,11 @@ impl Cli {
                         };
 
                         // Run lint
-                        let result = config.lint();
+                        let result = if deep {
+                            config.lint_deep()
+                        } else {
+                            config.lint()
+                        };
 
                         if json {
                             // JSON output
@@ -1832,6 +3682,32 @@ impl Cli {
                             ));
                         }
                     }
+                    ConfigCommands::VerifySecrets { file } => {
+                        let config = match file {
+                            Some(path) => VcConfig::load(&path)?,
+                            None => VcConfig::discover()?,
+                        };
+                        let checks = config.verify_secrets();
+                        let failed = checks.iter().filter(|check| !check.ok).count();
+                        if matches!(self.format, OutputFormat::Text) {
+                            if checks.is_empty() {
+                                println!("No secret:// references in the config");
+                            }
+                            for check in &checks {
+                                match &check.error {
+                                    None => println!("✓ {} ({})", check.key, check.reference),
+                                    Some(error) => println!("✗ {}: {error}", check.key),
+                                }
+                            }
+                        } else {
+                            print_output(&checks, self.format);
+                        }
+                        if failed > 0 {
+                            return Err(CliError::CommandFailed(format!(
+                                "{failed} secret reference(s) did not resolve"
+                            )));
+                        }
+                    }
                     ConfigCommands::Wizard {
                         output,
                         overwrite,
@@ -1862,10 +3738,21 @@ impl Cli {
                         println!("  2. Run 'vc config lint' to validate");
                         println!("  3. Run 'vc daemon' to start monitoring");
                     }
-                    ConfigCommands::Show { file, json } => {
-                        let config = match file {
-                            Some(path) => VcConfig::load(&path)?,
-                            None => VcConfig::discover()?,
+                    ConfigCommands::Show {
+                        file,
+                        json,
+                        resolved,
+                    } => {
+                        let config = if resolved {
+                            load_config(ConfigSource {
+                                path: file.as_ref().or(config_source.path),
+                                ..config_source
+                            })?
+                        } else {
+                            match file {
+                                Some(path) => VcConfig::load(&path)?,
+                                None => VcConfig::discover()?,
+                            }
                         };
 
                         if json {
@@ -1893,34 +3780,232 @@ impl Cli {
                             }
                         }
                     }
+                    ConfigCommands::History { limit } => {
+                        let store = open_store(config_source)?;
+                        let history: Vec<serde_json::Value> = store
+                            .config_snapshots(limit)?
+                            .into_iter()
+                            .map(|snapshot| {
+                                serde_json::json!({
+                                    "hash": &snapshot.content_hash[..12],
+                                    "path": snapshot.config_path,
+                                    "recorded_at": snapshot.recorded_at,
+                                    "file_mtime": snapshot.file_mtime,
+                                    "loaded_by": snapshot.loaded_by,
+                                    "bytes": snapshot.content.len(),
+                                })
+                            })
+                            .collect();
+                        print_output(&history, self.format);
+                    }
+                    ConfigCommands::Diff {
+                        hash_a,
+                        hash_b,
+                        latest,
+                    } => {
+                        let store = open_store(config_source)?;
+                        let (from, to) = if latest {
+                            let mut newest = store.config_snapshots(2)?;
+                            if newest.len() < 2 {
+                                return Err(CliError::CommandFailed(
+                                    "Fewer than two config snapshots recorded; nothing to diff"
+                                        .to_string(),
+                                ));
+                            }
+                            let to = newest.remove(0);
+                            (newest.remove(0), to)
+                        } else {
+                            (
+                                find_config_snapshot(
+                                    &store,
+                                    hash_a.as_deref().unwrap_or_default(),
+                                )?,
+                                find_config_snapshot(
+                                    &store,
+                                    hash_b.as_deref().unwrap_or_default(),
+                                )?,
+                            )
+                        };
+                        let diff = vc_config::history::line_diff(
+                            &vc_config::history::mask_secrets(&from.content),
+                            &vc_config::history::mask_secrets(&to.content),
+                        );
+                        if matches!(self.format, OutputFormat::Text) {
+                            println!("--- {} {}", &from.content_hash[..12], from.recorded_at);
+                            println!("+++ {} {}", &to.content_hash[..12], to.recorded_at);
+                            print!("{diff}");
+                        } else {
+                            print_output(
+                                &serde_json::json!({
+                                    "from": &from.content_hash[..12],
+                                    "to": &to.content_hash[..12],
+                                    "diff": diff,
+                                }),
+                                self.format,
+                            );
+                        }
+                    }
+                    ConfigCommands::Rollback { hash, yes, by } => {
+                        let by = command_actor(by.as_deref(), self.actor.as_deref());
+                        // Opening the store snapshots the current file first,
+                        // so the rollback itself can be undone
+                        let store = open_store(config_source)?;
+                        let snapshot = find_config_snapshot(&store, &hash)?;
+                        let path = active_config_path(config_source).ok_or_else(|| {
+                            CliError::CommandFailed(
+                                "No config file in use; pass --config to choose one".to_string(),
+                            )
+                        })?;
+                        let current = std::fs::read_to_string(&path)?;
+                        let current_hash = vc_config::history::content_hash(&current);
+                        if current_hash == snapshot.content_hash {
+                            println!(
+                                "{} already matches {}",
+                                path.display(),
+                                &snapshot.content_hash[..12]
+                            );
+                            return Ok(());
+                        }
+
+                        if !yes {
+                            if !std::io::stdin().is_terminal() {
+                                return Err(CliError::CommandFailed(format!(
+                                    "Re-run with --yes to overwrite {}",
+                                    path.display()
+                                )));
+                            }
+                            print!(
+                                "{}",
+                                vc_config::history::line_diff(
+                                    &vc_config::history::mask_secrets(&current),
+                                    &vc_config::history::mask_secrets(&snapshot.content),
+                                )
+                            );
+                            eprint!(
+                                "Overwrite {} with snapshot {}? [y/N] ",
+                                path.display(),
+                                &snapshot.content_hash[..12]
+                            );
+                            let mut answer = String::new();
+                            std::io::stdin().read_line(&mut answer).map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to read answer: {e}"))
+                            })?;
+                            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                                println!("Aborted; config unchanged");
+                                return Ok(());
+                            }
+                        }
+
+                        std::fs::write(&path, &snapshot.content)?;
+                        let path_text = path.display().to_string();
+                        store.insert_audit_event(&by.audit_event(
+                            vc_store::AuditEventType::UserCommand,
+                            "config_rollback",
+                            vc_store::AuditResult::Success,
+                            serde_json::json!({
+                                "path": path_text,
+                                "from_hash": current_hash,
+                                "to_hash": snapshot.content_hash,
+                            }),
+                        ))?;
+                        let config = load_config(config_source)?;
+                        record_config_snapshot(&store, &config, config_source);
+                        print_output(
+                            &serde_json::json!({
+                                "path": path_text,
+                                "previous": &current_hash[..12],
+                                "restored": &snapshot.content_hash[..12],
+                            }),
+                            self.format,
+                        );
+                    }
+                }
+            }
+            Commands::Doctor { machine } => {
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config).map_err(|e| e.to_string());
+                let ctx = doctor::DoctorContext {
+                    config: &config,
+                    store: store.as_ref().map_err(String::as_str),
+                    machine: machine.as_deref(),
+                    now: Utc::now(),
+                };
+                let report = doctor::Doctor::with_default_checks().run(cx, &ctx).await;
+
+                match self.format {
+                    OutputFormat::Text => println!("{}", report.render_text()),
+                    format => print_output(&report, format),
+                }
+                if !report.ok() {
+                    return Err(CliError::CommandFailed(format!(
+                        "{} doctor check(s) failed",
+                        report.failed
+                    )));
                 }
             }
             Commands::Vacuum { dry_run, table } => {
-                let store = open_store(self.config.as_ref())?;
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config)?.with_artifacts(&config.storage.artifacts);
 
                 let results = store
                     .run_vacuum(dry_run, table.as_deref())
                     .map_err(|e| CliError::CommandFailed(format!("Vacuum failed: {e}")))?;
 
+                // Ingest dedup hashes outside the lookback are never consulted again
+                let hashes_pruned = if table.is_none() {
+                    let before = vc_collect::node::dedup_since(&config.ingest, Utc::now())
+                        .unwrap_or_else(Utc::now);
+                    store
+                        .prune_ingest_row_hashes(before, dry_run)
+                        .map_err(|e| CliError::CommandFailed(format!("Vacuum failed: {e}")))?
+                } else {
+                    0
+                };
+
+                // Artifacts of sessions retention removed go with them
+                let (artifacts_pruned, artifact_bytes) =
+                    if table.as_deref().is_none_or(|t| t == "agent_sessions") {
+                        store
+                            .prune_orphan_artifacts(dry_run)
+                            .map_err(|e| CliError::CommandFailed(format!("Vacuum failed: {e}")))?
+                    } else {
+                        (0, 0)
+                    };
+
                 if results.is_empty() {
                     if table.is_some() {
                         println!("No retention policy found for specified table");
                     } else {
                         println!("No enabled retention policies found");
                     }
+                    let verb = if dry_run { "Would prune" } else { "Pruned" };
+                    if hashes_pruned > 0 {
+                        println!("{verb} {hashes_pruned} expired ingest dedup hashes");
+                    }
+                    if artifacts_pruned > 0 {
+                        println!(
+                            "{verb} {artifacts_pruned} offloaded transcripts of deleted sessions \
+                             ({artifact_bytes} bytes)"
+                        );
+                    }
                 } else {
                     let summary = serde_json::json!({
                         "dry_run": dry_run,
                         "tables_processed": results.len(),
+                        "ingest_hashes_pruned": hashes_pruned,
+                        "artifacts_pruned": artifacts_pruned,
+                        "artifact_bytes_pruned": artifact_bytes,
                         "total_rows_deleted": results.iter().map(|r| r.rows_deleted).sum::<i64>(),
                         "total_rows_would_delete": results.iter().map(|r| r.rows_would_delete).sum::<i64>(),
+                        "total_bytes_reclaimed": results.iter().map(|r| r.bytes_reclaimed).sum::<i64>(),
+                        "total_bytes_would_reclaim": results.iter().map(|r| r.bytes_would_reclaim).sum::<i64>(),
                         "results": results,
                     });
                     print_output(&summary, self.format);
                 }
             }
             Commands::Retention { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
 
                 match command {
                     RetentionCommands::List => {
@@ -1934,6 +4019,18 @@ impl Cli {
                             println!("To add a policy, use:");
                             println!("  vc retention set --table <table_name> --days <days>");
                         } else {
+                            // Name the table-wide scope instead of leaving it null
+                            let policies: Vec<serde_json::Value> = policies
+                                .iter()
+                                .map(|policy| {
+                                    let mut value = serde_json::to_value(policy)
+                                        .unwrap_or(serde_json::Value::Null);
+                                    if policy.scope.is_none() {
+                                        value["scope"] = TABLE_WIDE_SCOPE.into();
+                                    }
+                                    value
+                                })
+                                .collect();
                             print_output(&policies, self.format);
                         }
                     }
@@ -1941,18 +4038,82 @@ impl Cli {
                         table,
                         days,
                         disabled,
+                        scope,
+                        max_total_bytes,
+                        max_row_bytes,
+                        keep_head_bytes,
+                        keep_tail_bytes,
                     } => {
                         let enabled = !disabled;
+                        if let Some(scope) = scope {
+                            if max_total_bytes.is_some()
+                                || max_row_bytes.is_some()
+                                || keep_head_bytes.is_some()
+                                || keep_tail_bytes.is_some()
+                            {
+                                return Err(CliError::CommandFailed(
+                                    "Size limits belong to the table-wide policy; drop --scope"
+                                        .to_string(),
+                                ));
+                            }
+                            store
+                                .set_scoped_retention_policy(&table, &scope, days, enabled)
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!("Failed to set policy: {e}"))
+                                })?;
+                            let scope = scope.to_string();
+                            let policy = store
+                                .list_retention_policies()
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!("Failed to fetch policy: {e}"))
+                                })?
+                                .into_iter()
+                                .find(|p| {
+                                    p.table_name == table && p.scope.as_deref() == Some(&scope)
+                                });
+                            if let Some(policy) = policy {
+                                print_output(&policy, self.format);
+                            }
+                            return Ok(());
+                        }
+
                         store
                             .set_retention_policy(&table, days, None, enabled)
                             .map_err(|e| {
                                 CliError::CommandFailed(format!("Failed to set policy: {e}"))
                             })?;
 
-                        let policy = store.get_retention_policy(&table).map_err(|e| {
+                        let mut policy = store.get_retention_policy(&table).map_err(|e| {
                             CliError::CommandFailed(format!("Failed to fetch policy: {e}"))
                         })?;
 
+                        // Unset flags keep the stored value; 0 turns a cap off.
+                        if let Some(current) = policy.as_ref().filter(|_| {
+                            max_total_bytes.is_some()
+                                || max_row_bytes.is_some()
+                                || keep_head_bytes.is_some()
+                                || keep_tail_bytes.is_some()
+                        }) {
+                            store
+                                .set_retention_size_limits(
+                                    &table,
+                                    max_total_bytes
+                                        .map_or(current.max_total_bytes, |b| (b > 0).then_some(b)),
+                                    max_row_bytes
+                                        .map_or(current.max_row_bytes, |b| (b > 0).then_some(b)),
+                                    keep_head_bytes.unwrap_or(current.keep_head_bytes),
+                                    keep_tail_bytes.unwrap_or(current.keep_tail_bytes),
+                                )
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!(
+                                        "Failed to set size limits: {e}"
+                                    ))
+                                })?;
+                            policy = store.get_retention_policy(&table).map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to fetch policy: {e}"))
+                            })?;
+                        }
+
                         if let Some(policy) = policy {
                             print_output(&policy, self.format);
                         }
@@ -1968,10 +4129,54 @@ impl Cli {
                             print_output(&history, self.format);
                         }
                     }
+                    RetentionCommands::Simulate {
+                        table,
+                        all_tables,
+                        days,
+                        horizon_days,
+                    } => {
+                        if days <= 0 || horizon_days < 0 {
+                            return Err(CliError::CommandFailed(
+                                "--days must be positive and --horizon-days not negative"
+                                    .to_string(),
+                            ));
+                        }
+                        let simulations = if all_tables {
+                            store.simulate_retention_all(days, horizon_days)
+                        } else {
+                            let table = table.unwrap_or_default();
+                            store
+                                .simulate_retention(&table, days, horizon_days)
+                                .map(|simulation| vec![simulation])
+                        }
+                        .map_err(|e| CliError::CommandFailed(format!("Simulation failed: {e}")))?;
+
+                        if simulations.is_empty() {
+                            println!("No retention policies configured; pass --table instead");
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            print_retention_simulations(&simulations);
+                        } else if all_tables {
+                            let summary = serde_json::json!({
+                                "retention_days": days,
+                                "horizon_days": horizon_days,
+                                "total_rows_eligible_now": simulations.iter().map(|s| s.rows_eligible_now).sum::<i64>(),
+                                "total_bytes_eligible_now": simulations.iter().map(|s| s.bytes_eligible_now).sum::<i64>(),
+                                "total_bytes_eligible_horizon": simulations.iter().map(|s| s.bytes_eligible_horizon).sum::<i64>(),
+                                "tables": simulations,
+                            });
+                            print_output(&summary, self.format);
+                        } else {
+                            print_output(&simulations[0], self.format);
+                        }
+                    }
+                    RetentionCommands::Hold { command } => {
+                        let by = command_actor(None, self.actor.as_deref());
+                        run_retention_hold(&store, command, &by, self.format)?;
+                    }
                 }
             }
             Commands::Health { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
 
                 match command {
                     HealthCommands::Freshness {
@@ -1990,10 +4195,68 @@ impl Cli {
                             print_output(&summaries, self.format);
                         }
                     }
+                    HealthCommands::Collectors {
+                        machine,
+                        collector,
+                        oversized: true,
+                        window,
+                        ..
+                    } => {
+                        let since = Utc::now()
+                            - ChronoDuration::from_std(window).map_err(|e| {
+                                CliError::CommandFailed(format!("Window too large: {e}"))
+                            })?;
+                        let counts = store.oversized_write_counts(
+                            machine.as_deref(),
+                            collector.as_deref(),
+                            Some(since),
+                        )?;
+                        if counts.is_empty() {
+                            println!("No oversized writes recorded");
+                        } else {
+                            print_output(&counts, self.format);
+                        }
+                    }
+                    HealthCommands::Collectors {
+                        machine,
+                        collector,
+                        by_cause: true,
+                        window,
+                        ..
+                    } => {
+                        let since = Utc::now()
+                            - ChronoDuration::from_std(window).map_err(|e| {
+                                CliError::CommandFailed(format!("Window too large: {e}"))
+                            })?;
+                        let causes = store.collector_failure_causes(
+                            machine.as_deref(),
+                            collector.as_deref(),
+                            since,
+                        )?;
+                        let machines: Vec<serde_json::Value> = causes
+                            .chunk_by(|a, b| a.machine_id == b.machine_id)
+                            .map(|group| {
+                                serde_json::json!({
+                                    "machine_id": group[0].machine_id,
+                                    "failures": group.iter().map(|count| count.failures).sum::<i64>(),
+                                    "causes": group,
+                                })
+                            })
+                            .collect();
+                        print_output(
+                            &serde_json::json!({
+                                "since": since.to_rfc3339_opts(SecondsFormat::Secs, true),
+                                "machines": machines,
+                            }),
+                            self.format,
+                        );
+                    }
                     HealthCommands::Collectors {
                         machine,
                         collector,
                         limit,
+                        by_cause: false,
+                        ..
                     } => {
                         let entries = store
                             .list_collector_health(machine.as_deref(), collector.as_deref(), limit)
@@ -2012,15 +4275,26 @@ impl Cli {
                     HealthCommands::Drift {
                         machine,
                         severity,
+                        field,
+                        json,
                         limit,
                     } => {
                         let events = store
-                            .list_drift_events(machine.as_deref(), severity.as_deref(), limit)
+                            .list_drift_events(
+                                machine.as_deref(),
+                                severity.as_deref(),
+                                field.as_deref(),
+                                limit,
+                            )
                             .map_err(|e| {
                                 CliError::CommandFailed(format!("Failed to list drift events: {e}"))
                             })?;
 
-                        if events.is_empty() {
+                        if json {
+                            for line in drift_json_lines(&events) {
+                                println!("{line}");
+                            }
+                        } else if events.is_empty() {
                             println!("No drift events detected");
                         } else {
                             print_output(&events, self.format);
@@ -2042,14 +4316,83 @@ impl Cli {
                             print_output(&baselines, self.format);
                         }
                     }
-                    HealthCommands::Score { machine } => {
+                    HealthCommands::Score {
+                        machine,
+                        compare,
+                        trend,
+                        sparkline: draw,
+                    } => {
                         let qb = vc_query::QueryBuilder::new(&store);
 
-                        if let Some(machine_id) = &machine {
+                        if let (Some(machine_id), Some(window)) = (&machine, trend) {
+                            let since = Utc::now()
+                                - ChronoDuration::from_std(window).map_err(|e| {
+                                    CliError::CommandFailed(format!("Window too large: {e}"))
+                                })?;
+                            let history = qb.health_history(machine_id, since)?;
+                            let report = serde_json::json!({
+                                "machine_id": machine_id,
+                                "window_secs": window.as_secs(),
+                                "history": history
+                                    .iter()
+                                    .map(|(at, score)| serde_json::json!({
+                                        "collected_at": at.to_rfc3339(),
+                                        "overall_score": score,
+                                    }))
+                                    .collect::<Vec<_>>(),
+                            });
+                            if !matches!(self.format, OutputFormat::Text) {
+                                print_output(&report, self.format);
+                            } else if let Some((_, current)) = history.last() {
+                                let over = vc_query::timefmt::duration_secs(window.as_secs());
+                                if sparklines_enabled(draw, self.format) {
+                                    let line = sparkline(
+                                        &history,
+                                        since,
+                                        vc_query::sparkline::Bucketing::Mean,
+                                        |v| format!("{v:.2}"),
+                                    );
+                                    println!(
+                                        "{machine_id}: {current:.2} now  {line}  (last {over})"
+                                    );
+                                } else {
+                                    let (min, max) = history.iter().fold(
+                                        (f64::INFINITY, f64::NEG_INFINITY),
+                                        |(lo, hi), (_, score)| (lo.min(*score), hi.max(*score)),
+                                    );
+                                    println!(
+                                        "{machine_id}: {current:.2} now, min {min:.2} max {max:.2} \
+                                         over the last {over} ({} samples)",
+                                        history.len()
+                                    );
+                                }
+                            } else {
+                                println!("No health scores for {machine_id} in that window");
+                            }
+                        } else if let (Some(machine_id), Some(window)) = (&machine, compare) {
+                            let window = chrono::Duration::from_std(window).map_err(|e| {
+                                CliError::CommandFailed(format!("Window too large: {e}"))
+                            })?;
+                            let comparison =
+                                qb.health_comparison(machine_id, window).map_err(|e| {
+                                    CliError::CommandFailed(format!(
+                                        "Failed to compare health score: {e}"
+                                    ))
+                                })?;
+                            if matches!(self.format, OutputFormat::Text) {
+                                print_health_comparison(&comparison);
+                            } else {
+                                print_output(&comparison, self.format);
+                            }
+                        } else if let Some(machine_id) = &machine {
                             let health = qb.machine_health(machine_id).map_err(|e| {
                                 CliError::CommandFailed(format!("Failed to get health score: {e}"))
                             })?;
                             print_output(&health, self.format);
+                            print_scoring_version_changes(
+                                &qb.scoring_version_changes(Some(machine_id))?,
+                                self.format,
+                            );
                         } else {
                             let summaries = qb.list_health_summaries().map_err(|e| {
                                 CliError::CommandFailed(format!(
@@ -2061,22 +4404,85 @@ impl Cli {
                                 println!("No health scores computed yet");
                             } else {
                                 print_output(&summaries, self.format);
+                                print_scoring_version_changes(
+                                    &qb.scoring_version_changes(None)?,
+                                    self.format,
+                                );
+                            }
+                        }
+                    }
+                    HealthCommands::Rescore { window } => {
+                        let since = Utc::now()
+                            - ChronoDuration::from_std(window).map_err(|e| {
+                                CliError::CommandFailed(format!("Window too large: {e}"))
+                            })?;
+                        let rescore =
+                            vc_query::QueryBuilder::new(&store).rescore_health_since(since)?;
+                        if matches!(self.format, OutputFormat::Text) {
+                            println!(
+                                "Rescored {} health summaries under scoring version {}",
+                                rescore.rescored, rescore.scoring_version
+                            );
+                            if rescore.without_factors > 0 {
+                                println!(
+                                    "{} older summaries have no factor rows left and were kept as \
+                                     they were",
+                                    rescore.without_factors
+                                );
                             }
+                        } else {
+                            print_output(&rescore, self.format);
+                        }
+                    }
+                    HealthCommands::Checks { machine } => {
+                        let checks = store.latest_http_checks(machine.as_deref())?;
+
+                        if checks.is_empty() {
+                            println!("No HTTP checks recorded yet");
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            print_http_checks(&checks);
+                        } else {
+                            print_output(&checks, self.format);
+                        }
+                    }
+                    HealthCommands::Forecast { machine, horizon } => {
+                        let config = load_config(config_source)?;
+                        let mut options =
+                            vc_query::DiskForecastOptions::from_config(&config.forecast);
+                        options.machine = machine;
+                        if let Some(horizon) = horizon {
+                            options.horizon = ChronoDuration::from_std(horizon).map_err(|e| {
+                                CliError::CommandFailed(format!("Horizon too large: {e}"))
+                            })?;
+                        }
+                        let forecast = vc_query::QueryBuilder::new(&store)
+                            .disk_forecast(&options)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to forecast disks: {e}"))
+                            })?;
+
+                        if forecast.mounts.is_empty() {
+                            println!(
+                                "No filesystem samples in the last {}d",
+                                forecast.window_days
+                            );
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            print_disk_forecast(&forecast);
+                        } else {
+                            print_output(&forecast, self.format);
                         }
                     }
                 }
             }
             Commands::Autopilot { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
 
                 match command {
+                    #[cfg(feature = "guardian")]
                     AutopilotCommands::Status => {
                         use vc_guardian::autopilot::AutopilotStatus;
 
-                        let config = match &self.config {
-                            Some(path) => VcConfig::load_with_env(path)?,
-                            None => VcConfig::discover_with_env()?,
-                        };
+                        let config = load_config(config_source)?;
 
                         let mode = if config.autopilot.enabled {
                             vc_guardian::autopilot::AutopilotMode::Suggest
@@ -2105,15 +4511,29 @@ impl Cli {
                             .filter_map(|s| s["total"].as_u64())
                             .sum::<u64>();
 
+                        let pending_switches = if config.autopilot.auto_switch_accounts {
+                            vc_guardian::autopilot::evaluate_account_switches(
+                                &load_account_samples(&store)?,
+                                &config.autopilot,
+                            )
+                        } else {
+                            Vec::new()
+                        };
+
                         let status = AutopilotStatus {
                             mode,
                             decisions_today,
                             last_decision_at,
                             account_switches,
                             cost_alerts,
+                            pending_switches,
                         };
                         print_output(&status, self.format);
                     }
+                    #[cfg(not(feature = "guardian"))]
+                    AutopilotCommands::Status => {
+                        return Err(CliError::FeatureDisabled("guardian"));
+                    }
                     AutopilotCommands::Decisions {
                         decision_type,
                         limit,
@@ -2134,18 +4554,91 @@ impl Cli {
                         let summary = store.autopilot_decision_summary().map_err(|e| {
                             CliError::CommandFailed(format!("Failed to get decision summary: {e}"))
                         })?;
+                        // How much of the resolving automation actually did
+                        let sources = store.alert_resolution_sources(None).map_err(|e| {
+                            CliError::CommandFailed(format!(
+                                "Failed to get alert resolution sources: {e}"
+                            ))
+                        })?;
 
-                        if summary.is_empty() {
+                        if summary.is_empty() && sources.is_empty() {
                             println!("No autopilot decisions recorded yet");
                         } else {
-                            print_output(&summary, self.format);
+                            print_output(
+                                &serde_json::json!({
+                                    "decisions": summary,
+                                    "resolution_sources": sources,
+                                }),
+                                self.format,
+                            );
+                        }
+                    }
+                }
+            }
+            Commands::Costs { command } => {
+                let store = open_store(config_source)?;
+
+                match command {
+                    CostCommands::Trend {
+                        window,
+                        machine,
+                        sparkline: draw,
+                    } => {
+                        let until = Utc::now();
+                        let since = until
+                            - ChronoDuration::from_std(window).map_err(|e| {
+                                CliError::CommandFailed(format!("Window too large: {e}"))
+                            })?;
+                        let samples = vc_query::CostQueryBuilder::new(&store).spend_samples(
+                            since,
+                            until,
+                            machine.as_deref(),
+                        )?;
+                        let total: f64 = samples.iter().map(|(_, usd)| usd).sum();
+                        let buckets = vc_query::sparkline::bucketize(
+                            &samples,
+                            since,
+                            until,
+                            SPARKLINE_BUCKETS,
+                            vc_query::sparkline::Bucketing::Sum,
+                        );
+
+                        if matches!(self.format, OutputFormat::Text) {
+                            let over = vc_query::timefmt::duration_secs(window.as_secs());
+                            let scope = machine.as_deref().unwrap_or("fleet");
+                            println!(
+                                "{scope} spend over the last {over}: ${total:.2} ({} snapshots)",
+                                samples.len()
+                            );
+                            if sparklines_enabled(draw, self.format) {
+                                println!(
+                                    "  {}",
+                                    vc_query::sparkline::render_labeled(
+                                        &buckets,
+                                        vc_query::sparkline::SparkStyle::detect(),
+                                        |v| format!("${v:.2}"),
+                                    )
+                                );
+                            }
+                        } else {
+                            let report = serde_json::json!({
+                                "machine_id": machine,
+                                "since": since.to_rfc3339(),
+                                "until": until.to_rfc3339(),
+                                "total_cost_usd": total,
+                                "bucket_secs": window.as_secs() / SPARKLINE_BUCKETS as u64,
+                                "buckets": buckets,
+                            });
+                            print_output(&report, self.format);
                         }
                     }
                 }
             }
+            #[cfg(feature = "knowledge")]
             Commands::Knowledge { command } => {
-                let store = Arc::new(open_store(self.config.as_ref())?);
-                let kb = KnowledgeStore::new(store.clone());
+                let config = load_config(config_source)?;
+                let store = Arc::new(open_duckdb_store(&config)?);
+                let kb = knowledge_store(store.clone(), &config);
 
                 match command {
                     KnowledgeCommands::Add {
@@ -2157,6 +4650,7 @@ impl Cli {
                         file,
                         lines,
                         tags,
+                        alert_types,
                     } => {
                         let et: EntryType =
                             entry_type
@@ -2196,10 +4690,12 @@ impl Cli {
                         }
 
                         let id = kb.insert(&entry)?;
+                        kb.add_alert_types(id, &alert_types)?;
                         let result = serde_json::json!({
                             "id": id,
                             "title": title,
                             "entry_type": et.as_str(),
+                            "alert_types": alert_types,
                             "message": "Knowledge entry created successfully",
                         });
                         print_output(&result, self.format);
@@ -2209,8 +4705,13 @@ impl Cli {
                         entry_type,
                         tags,
                         limit,
+                        semantic,
+                        include_archived,
                     } => {
-                        let mut opts = SearchOptions::new().with_limit(limit);
+                        let mut opts = SearchOptions::new()
+                            .with_limit(limit)
+                            .with_semantic(semantic)
+                            .with_include_archived(include_archived);
 
                         if let Some(et_str) = entry_type {
                             let et: EntryType =
@@ -2241,7 +4742,23 @@ impl Cli {
                     KnowledgeCommands::Show { id } => {
                         let entry = kb.get(id)?;
                         kb.record_view(id).ok(); // best-effort view count
-                        print_output(&entry, self.format);
+                        let relations = kb.relations(id)?;
+                        let superseded_by = relations.superseded_by();
+                        if !superseded_by.is_empty() {
+                            let ids: Vec<String> =
+                                superseded_by.iter().map(ToString::to_string).collect();
+                            eprintln!(
+                                "Warning: entry {id} is superseded by entry {}; prefer the newer entry",
+                                ids.join(", ")
+                            );
+                        }
+                        let result = serde_json::json!({
+                            "entry": entry,
+                            "alert_types": kb.alert_types(id)?,
+                            "relations": relations,
+                            "superseded_by": superseded_by,
+                        });
+                        print_output(&result, self.format);
                     }
                     KnowledgeCommands::List { limit, entry_type } => {
                         if let Some(et_str) = entry_type {
@@ -2303,14 +4820,44 @@ impl Cli {
 
                         let total_solutions: usize =
                             results.iter().map(|r| r.solutions_extracted).sum();
+                        let suppressed: usize =
+                            results.iter().map(|r| r.suppressed_duplicates).sum();
                         let output = serde_json::json!({
                             "sessions_processed": results.len(),
                             "total_solutions_extracted": total_solutions,
+                            "suppressed_duplicates": suppressed,
                             "results": results,
                             "message": format!("Mined {} sessions, extracted {} solutions", results.len(), total_solutions),
                         });
                         print_output(&output, self.format);
                     }
+                    KnowledgeCommands::Reindex => {
+                        #[cfg(feature = "embeddings")]
+                        {
+                            if config.knowledge.embeddings.enabled {
+                                let report = kb.reindex_missing(|done, total| {
+                                    eprint!("\rEmbedding entries: {done}/{total}");
+                                })?;
+                                if report.missing > 0 {
+                                    eprintln!();
+                                }
+                                if let Some(reason) = &report.aborted {
+                                    eprintln!(
+                                        "Warning: stopped early, embedding backend unavailable: {reason}"
+                                    );
+                                }
+                                print_output(&report, self.format);
+                            } else {
+                                return Err(CliError::CommandFailed(
+                                    "knowledge.embeddings is not enabled in the config".to_string(),
+                                ));
+                            }
+                        }
+                        #[cfg(not(feature = "embeddings"))]
+                        return Err(CliError::CommandFailed(
+                            "vc was built without the embeddings feature".to_string(),
+                        ));
+                    }
                     KnowledgeCommands::MineStats => {
                         let miner = vc_knowledge::mining::SolutionMiner::new(store.clone());
                         let stats = miner.stats().map_err(|e| {
@@ -2321,24 +4868,405 @@ impl Cli {
                             "total_solutions": stats.total_solutions,
                             "total_patterns": stats.total_patterns,
                             "avg_quality": stats.avg_quality,
+                            "suppressed_duplicates": stats.suppressed_duplicates,
                         });
                         print_output(&output, self.format);
                     }
+                    KnowledgeCommands::Review { limit } => {
+                        let queue = kb.review_queue(limit, Utc::now())?;
+                        if queue.is_empty() {
+                            println!("No knowledge entries to review");
+                        } else {
+                            print_output(&queue, self.format);
+                        }
+                    }
+                    KnowledgeCommands::Archive { id } => {
+                        let entry = kb.archive(id)?;
+                        let result = serde_json::json!({
+                            "id": id,
+                            "title": entry.title,
+                            "archived_at": entry.archived_at,
+                            "message": "Knowledge entry archived",
+                        });
+                        print_output(&result, self.format);
+                    }
+                    KnowledgeCommands::Link {
+                        source,
+                        target,
+                        relation,
+                    } => {
+                        let relation: vc_knowledge::RelationType = relation.parse()?;
+                        kb.link(source, target, relation)?;
+                        let result = serde_json::json!({
+                            "source_id": source,
+                            "target_id": target,
+                            "relation": relation.as_str(),
+                            "message": format!("Linked entry {source} {relation} entry {target}"),
+                        });
+                        print_output(&result, self.format);
+                    }
+                    KnowledgeCommands::Unlink {
+                        source,
+                        target,
+                        relation,
+                    } => {
+                        let relation: Option<vc_knowledge::RelationType> =
+                            relation.map(|r| r.parse()).transpose()?;
+                        let removed = kb.unlink(source, target, relation)?;
+                        let result = serde_json::json!({
+                            "source_id": source,
+                            "target_id": target,
+                            "removed": removed,
+                            "message": format!("Removed {removed} link(s)"),
+                        });
+                        print_output(&result, self.format);
+                    }
+                }
+            }
+            #[cfg(not(feature = "knowledge"))]
+            Commands::Knowledge { .. } => return Err(CliError::FeatureDisabled("knowledge")),
+            Commands::Telemetry { command } => {
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config)?;
+
+                match command {
+                    TelemetryCommands::Status => {
+                        let streams = store.telemetry_status()?;
+                        let status = serde_json::json!({
+                            "enabled": config.telemetry.enabled,
+                            "exporter_built": cfg!(feature = "telemetry"),
+                            "endpoint": config.telemetry.otlp_endpoint,
+                            "streams": streams,
+                        });
+                        print_output(&status, self.format);
+                    }
+                }
+            }
+            Commands::Sessions { command } => {
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config)?;
+
+                match command {
+                    #[cfg(feature = "knowledge")]
+                    SessionCommands::Classify { backfill, limit } => {
+                        let report = vc_knowledge::outcome::classify_sessions(
+                            &store,
+                            &config.knowledge.outcomes,
+                            backfill,
+                            limit,
+                        )
+                        .map_err(|e| {
+                            CliError::CommandFailed(format!("Session classification failed: {e}"))
+                        })?;
+                        print_output(&report, self.format);
+                    }
+                    #[cfg(not(feature = "knowledge"))]
+                    SessionCommands::Classify { .. } => {
+                        return Err(CliError::FeatureDisabled("knowledge"));
+                    }
+                    SessionCommands::Stats { group_by, window } => {
+                        let window = ChronoDuration::from_std(window).map_err(|e| {
+                            CliError::CommandFailed(format!("Window too large: {e}"))
+                        })?;
+                        let rates = vc_query::QueryBuilder::new(&store)
+                            .session_success_rates(group_by, window)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!(
+                                    "Failed to compute session success rates: {e}"
+                                ))
+                            })?;
+                        if matches!(self.format, OutputFormat::Text) {
+                            if rates.is_empty() {
+                                println!("No classified sessions in this window");
+                            }
+                            for rate in &rates {
+                                println!("{}", rate.summary());
+                            }
+                        } else {
+                            print_output(
+                                &serde_json::json!({
+                                    "group_by": group_by.as_str(),
+                                    "window_secs": window.num_seconds(),
+                                    "groups": rates,
+                                }),
+                                self.format,
+                            );
+                        }
+                    }
+                }
+            }
+            Commands::Repos { command } => {
+                let store = open_store(config_source)?;
+                match command {
+                    RepoCommands::Activity { window, stale_days } => {
+                        let window = ChronoDuration::from_std(window).map_err(|e| {
+                            CliError::CommandFailed(format!("Window too large: {e}"))
+                        })?;
+                        let stale_after =
+                            ChronoDuration::try_days(stale_days).ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "--stale-days out of range: {stale_days}"
+                                ))
+                            })?;
+                        let activity =
+                            vc_query::repos::repo_activity(&store, window, stale_after, Utc::now())
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!(
+                                        "Failed to compute repo activity: {e}"
+                                    ))
+                                })?;
+                        if matches!(self.format, OutputFormat::Text) {
+                            if activity.is_empty() {
+                                println!("No repo activity in this window");
+                            }
+                            for repo in &activity {
+                                println!("{}", repo.summary());
+                            }
+                            for line in activity
+                                .iter()
+                                .filter_map(vc_query::RepoActivity::stale_summary)
+                            {
+                                println!("stale: {line}");
+                            }
+                        } else {
+                            print_output(
+                                &serde_json::json!({
+                                    "window_secs": window.num_seconds(),
+                                    "stale_days": stale_days,
+                                    "repos": activity,
+                                }),
+                                self.format,
+                            );
+                        }
+                    }
+                }
+            }
+            Commands::Search {
+                query,
+                kinds,
+                since,
+                limit,
+            } => {
+                let store = open_store(config_source)?;
+                let since =
+                    vc_query::timefmt::parse_since(&since, Utc::now()).ok_or_else(|| {
+                        CliError::CommandFailed(format!(
+                            "Invalid --since '{since}' (expected e.g. 30d or a timestamp)"
+                        ))
+                    })?;
+                let filter = vc_query::SearchFilter {
+                    query,
+                    kinds,
+                    since,
+                    limit,
+                };
+                let results = vc_query::QueryBuilder::new(&store).search(&filter)?;
+                if matches!(self.format, OutputFormat::Text) {
+                    print_search(&results);
+                } else {
+                    print_output(&results, self.format);
+                }
+            }
+            Commands::Timeline {
+                command,
+                since,
+                machine,
+                kinds,
+                limit,
+                after,
+            } => {
+                use vc_query::TimelineKind;
+
+                if let Some(TimelineCommands::Show { kind, id }) = command {
+                    // Kinds with a show command of their own go through it
+                    let command = match kind {
+                        TimelineKind::Incidents => Commands::Incident {
+                            command: IncidentCommands::Show { id },
+                        },
+                        TimelineKind::Audit => Commands::Audit {
+                            command: AuditCommands::Show {
+                                id: id.parse().map_err(|_| {
+                                    CliError::CommandFailed(format!("Invalid audit event id: {id}"))
+                                })?,
+                            },
+                        },
+                        TimelineKind::Alerts
+                        | TimelineKind::Fleet
+                        | TimelineKind::Drift
+                        | TimelineKind::Annotations => {
+                            let store = open_store(config_source)?;
+                            let row = vc_query::QueryBuilder::new(&store)
+                                .timeline_event(kind, &id)?
+                                .ok_or_else(|| {
+                                    CliError::CommandFailed(format!("No {kind} event {id}"))
+                                })?;
+                            print_output(&row, self.format);
+                            return Ok(());
+                        }
+                    };
+                    let show = Cli {
+                        config: self.config.clone(),
+                        profile: self.profile.clone(),
+                        verbose: self.verbose,
+                        format: self.format,
+                        timestamps: self.timestamps,
+                        csv_columns: self.csv_columns.clone(),
+                        max_tokens: self.max_tokens,
+                        chars_per_token: self.chars_per_token,
+                        actor: self.actor.clone(),
+                        save_report: self.save_report,
+                        command,
+                    };
+                    return Box::pin(show.dispatch(cx)).await;
+                }
+
+                let store = open_store(config_source)?;
+                let since =
+                    vc_query::timefmt::parse_since(&since, Utc::now()).ok_or_else(|| {
+                        CliError::CommandFailed(format!(
+                            "Invalid --since '{since}' (expected e.g. 24h, 7d or a timestamp)"
+                        ))
+                    })?;
+                let filter = vc_query::TimelineFilter {
+                    since,
+                    machine,
+                    kinds,
+                    limit,
+                    after,
+                };
+                let page = vc_query::QueryBuilder::new(&store).timeline(&filter)?;
+                if matches!(self.format, OutputFormat::Text) {
+                    print_timeline(&page);
+                } else {
+                    print_output(&page, self.format);
+                }
+            }
+            Commands::Annotate {
+                kind,
+                machine,
+                tag,
+                message,
+                ts,
+                source,
+            } => {
+                let store = open_store(config_source)?;
+                let ts = ts
+                    .map(|raw| {
+                        vc_query::timefmt::parse_timestamp(&raw).ok_or_else(|| {
+                            CliError::CommandFailed(format!(
+                                "Invalid --ts '{raw}' (expected a timestamp)"
+                            ))
+                        })
+                    })
+                    .transpose()?;
+                let scope = match (machine, tag) {
+                    (Some(machine), _) => vc_store::AnnotationScope::Machine(machine),
+                    (None, Some(tag)) => vc_store::AnnotationScope::Tag(tag),
+                    (None, None) => vc_store::AnnotationScope::Fleet,
+                };
+                let annotation = store.record_annotation(
+                    &vc_store::NewAnnotation {
+                        kind,
+                        scope,
+                        ts,
+                        message,
+                        source,
+                    },
+                    &command_actor(None, self.actor.as_deref()),
+                )?;
+                if matches!(self.format, OutputFormat::Text) {
+                    println!(
+                        "Annotation {} recorded: {}",
+                        annotation.id,
+                        annotation.summary()
+                    );
+                } else {
+                    print_output(&annotation, self.format);
+                }
+            }
+            Commands::Annotations {
+                command:
+                    AnnotationsCommands::List {
+                        kinds,
+                        since,
+                        until,
+                        machine,
+                        limit,
+                    },
+            } => {
+                let store = open_store(config_source)?;
+                let now = Utc::now();
+                let window = |flag: &str, raw: &str| {
+                    vc_query::timefmt::parse_since(raw, now).ok_or_else(|| {
+                        CliError::CommandFailed(format!(
+                            "Invalid --{flag} '{raw}' (expected e.g. 24h, 7d or a timestamp)"
+                        ))
+                    })
+                };
+                let filter = vc_store::AnnotationFilter {
+                    kinds,
+                    since: Some(window("since", &since)?),
+                    until: until
+                        .as_deref()
+                        .map(|raw| window("until", raw))
+                        .transpose()?,
+                    machine,
+                    limit,
+                };
+                let annotations = store.list_annotations(&filter)?;
+                if !matches!(self.format, OutputFormat::Text) {
+                    print_output(&annotations, self.format);
+                } else if annotations.is_empty() {
+                    println!("No annotations in this window");
+                } else {
+                    for annotation in &annotations {
+                        println!(
+                            "{}  {}",
+                            time_format().timestamp_str(&annotation.ts),
+                            annotation.summary()
+                        );
+                    }
                 }
             }
             Commands::Incident { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
 
                 match command {
-                    IncidentCommands::List { status, limit } => {
-                        let incidents =
+                    IncidentCommands::List {
+                        status,
+                        limit,
+                        breached,
+                    } => {
+                        let incidents = if breached {
+                            let config = load_config(config_source)?;
+                            vc_query::sla::breached_incidents(
+                                &store,
+                                status.as_deref(),
+                                &config.incidents,
+                                chrono::Utc::now(),
+                                limit,
+                            )
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to list incidents: {e}"))
+                            })?
+                            .into_iter()
+                            .map(|(mut incident, sla)| {
+                                incident["sla_breaches"] = sla
+                                    .breaches()
+                                    .map(|timer| timer.milestone.as_str())
+                                    .collect();
+                                incident
+                            })
+                            .collect()
+                        } else {
                             store
                                 .list_incidents(status.as_deref(), limit)
                                 .map_err(|e| {
                                     CliError::CommandFailed(format!(
                                         "Failed to list incidents: {e}"
                                     ))
-                                })?;
+                                })?
+                        };
 
                         if incidents.is_empty() {
                             println!("No incidents found");
@@ -2353,12 +5281,21 @@ impl Cli {
 
                         match incident {
                             Some(inc) => {
+                                let config = load_config(config_source)?;
                                 let notes = store.get_incident_notes(&id).unwrap_or_default();
                                 let timeline = store.get_incident_timeline(&id).unwrap_or_default();
+                                let sla = vc_query::sla::evaluate(
+                                    &inc,
+                                    &notes,
+                                    &timeline,
+                                    &config.incidents,
+                                    chrono::Utc::now(),
+                                );
                                 let result = serde_json::json!({
                                     "incident": inc,
                                     "notes": notes,
                                     "timeline": timeline,
+                                    "sla": sla,
                                 });
                                 print_output(&result, self.format);
                             }
@@ -2413,6 +5350,102 @@ impl Cli {
                         });
                         print_output(&result, self.format);
                     }
+                    IncidentCommands::Ack { id, by } => {
+                        let by = command_actor(by.as_deref(), self.actor.as_deref());
+                        if store
+                            .get_incident(&id)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to get incident: {e}"))
+                            })?
+                            .is_none()
+                        {
+                            return Err(CliError::CommandFailed(format!(
+                                "Incident not found: {id}"
+                            )));
+                        }
+                        store
+                            .add_incident_timeline_event(
+                                &id,
+                                "acknowledged",
+                                &by.name,
+                                &format!("Acknowledged by {by}"),
+                                None,
+                            )
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!(
+                                    "Failed to acknowledge incident: {e}"
+                                ))
+                            })?;
+
+                        let result = serde_json::json!({
+                            "incident_id": id,
+                            "acknowledged_by": by.name,
+                            "message": "Incident acknowledged",
+                        });
+                        print_output(&result, self.format);
+                    }
+                    IncidentCommands::Mitigate { id, note } => {
+                        let affected = store
+                            .update_incident_status(&id, "mitigated", None, None)
+                            .map_err(|e| {
+                            CliError::CommandFailed(format!("Failed to mitigate incident: {e}"))
+                        })?;
+
+                        if affected == 0 {
+                            return Err(CliError::CommandFailed(format!(
+                                "Incident not found: {id}"
+                            )));
+                        }
+                        if let Some(note) = note {
+                            store.add_incident_note(&id, None, &note).map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to add note: {e}"))
+                            })?;
+                        }
+
+                        let result = serde_json::json!({
+                            "incident_id": id,
+                            "status": "mitigated",
+                            "message": "Incident marked mitigated",
+                        });
+                        print_output(&result, self.format);
+                    }
+                    IncidentCommands::SetSeverity { id, severity } => {
+                        let severity = severity.to_lowercase();
+                        if !["info", "warning", "critical"].contains(&severity.as_str()) {
+                            return Err(CliError::CommandFailed(format!(
+                                "Invalid severity '{severity}'. Must be one of: info, warning, critical"
+                            )));
+                        }
+                        let previous = store
+                            .set_incident_severity(&id, &severity)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to change severity: {e}"))
+                            })?
+                            .ok_or_else(|| {
+                                CliError::CommandFailed(format!("Incident not found: {id}"))
+                            })?;
+
+                        // Re-time against the new severity's targets
+                        let config = load_config(config_source)?;
+                        let sla = vc_query::sla::incident_sla(
+                            &store,
+                            &id,
+                            &config.incidents,
+                            chrono::Utc::now(),
+                        )
+                        .map_err(|e| {
+                            CliError::CommandFailed(format!("Failed to time incident: {e}"))
+                        })?;
+
+                        let result = serde_json::json!({
+                            "incident_id": id,
+                            "previous_severity": previous,
+                            "severity": severity,
+                            "sla": sla,
+                            "message": "Incident severity changed",
+                        });
+                        print_output(&result, self.format);
+                    }
                     IncidentCommands::Close {
                         id,
                         reason,
@@ -2484,7 +5517,10 @@ impl Cli {
                                     println!("## Timeline");
                                     println!();
                                     for event in timeline {
-                                        let ts = event["ts"].as_str().unwrap_or("?");
+                                        let ts = event["ts"].as_str().map_or_else(
+                                            || "?".to_string(),
+                                            |ts| time_format().timestamp_str(ts),
+                                        );
                                         let desc = event["description"].as_str().unwrap_or("?");
                                         let etype = event["event_type"].as_str().unwrap_or("event");
                                         println!("- **{ts}** [{etype}]: {desc}");
@@ -2512,8 +5548,56 @@ impl Cli {
                     }
                 }
             }
+            Commands::Ops { command } => {
+                let store = open_store(config_source)?;
+                match command {
+                    OpsCommands::Show { id } => {
+                        let record = store.operation_report(&id)?.ok_or_else(|| {
+                            CliError::CommandFailed(format!("No saved report {id}"))
+                        })?;
+                        let report = MultiMachineReport::from_record(&record).map_err(|e| {
+                            CliError::CommandFailed(format!("Saved report {id} is unreadable: {e}"))
+                        })?;
+                        print_multi_report(&report, self.format);
+                    }
+                    OpsCommands::List { limit } => {
+                        let reports = store.list_operation_reports(limit)?;
+                        if !matches!(self.format, OutputFormat::Text) {
+                            let rows: Vec<serde_json::Value> = reports
+                                .iter()
+                                .map(|r| {
+                                    serde_json::json!({
+                                        "operation_id": r.operation_id,
+                                        "command": r.command,
+                                        "worst_outcome": r.worst_outcome,
+                                        "targets": r.targets,
+                                        "failed": r.failed,
+                                        "created_by": r.created_by,
+                                        "created_at": r.created_at,
+                                    })
+                                })
+                                .collect();
+                            print_output(&rows, self.format);
+                        } else if reports.is_empty() {
+                            println!("No saved reports; add --save-report to a fleet-wide command");
+                        } else {
+                            for r in &reports {
+                                println!(
+                                    "{}  {:<18}  {:<7}  {}/{} failed  {}",
+                                    r.operation_id,
+                                    r.command,
+                                    r.worst_outcome.as_deref().unwrap_or("-"),
+                                    r.failed,
+                                    r.targets,
+                                    time_format().timestamp_str(&r.created_at)
+                                );
+                            }
+                        }
+                    }
+                }
+            }
             Commands::Fleet { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = Arc::new(open_store(config_source)?);
 
                 match command {
                     FleetCommands::Spawn {
@@ -2676,24 +5760,256 @@ impl Cli {
                         });
                         print_output(&output, self.format);
                     }
-                }
-            }
-            Commands::Watch {
-                events,
-                changes_only,
-                interval,
-                machines,
-                min_severity,
-                buffer,
-            } => {
-                let controller = ShutdownController::new();
-                let receiver = controller.subscribe();
+                    FleetCommands::Versions { tool } => {
+                        let config = load_config(config_source)?;
+                        let spreads = vc_query::QueryBuilder::new(&store)
+                            .fleet_versions(tool.as_deref(), &config.collectors.min_versions)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to load versions: {e}"))
+                            })?;
+                        if spreads.is_empty() {
+                            println!("No tool versions recorded yet; run `vc machine probe`");
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            print_fleet_versions(&spreads);
+                        } else {
+                            print_output(&spreads, self.format);
+                        }
+                    }
+                    FleetCommands::Clock { machine, limit } => {
+                        let samples = match &machine {
+                            Some(id) => store.clock_skew_history(id, limit),
+                            None => store.list_clock_skew(None),
+                        }
+                        .map_err(|e| {
+                            CliError::CommandFailed(format!("Failed to load clock skew: {e}"))
+                        })?;
+                        if samples.is_empty() {
+                            println!("No clock skew measured yet; run `vc machines probe`");
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            print_clock_skew(&samples);
+                        } else {
+                            print_output(&samples, self.format);
+                        }
+                    }
+                    FleetCommands::Apply {
+                        file,
+                        dry_run,
+                        execute,
+                    } => {
+                        let text = std::fs::read_to_string(&file)?;
+                        let desired = vc_query::FleetSpec::parse(&text)?
+                            .resolve(&vc_query::fleet_state::fleet_machines(&store)?)?;
+                        let observed = vc_query::fleet_state::observed_agents(&store)?;
+                        let steps: Vec<vc_store::FleetApplyStep> =
+                            vc_query::fleet_state::plan(&desired, &observed)
+                                .into_iter()
+                                .zip(0..)
+                                .map(|(step, index)| vc_store::FleetApplyStep {
+                                    apply_id: String::new(),
+                                    step: index,
+                                    machine_id: step.machine_id,
+                                    agent_type: step.agent_type,
+                                    action: step.action.as_str().to_string(),
+                                    count: i64::from(step.count),
+                                    config_json: step.config.map(|config| config.to_string()),
+                                    status: "pending".to_string(),
+                                    command_id: None,
+                                    error: None,
+                                    completed_at: None,
+                                })
+                                .collect();
+
+                        if dry_run {
+                            print_fleet_apply(None, "dry-run", &steps, self.format);
+                            return Ok(());
+                        }
+                        let apply_id = format!("fa-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+                        let desired_json = serde_json::to_string(&desired).map_err(|e| {
+                            CliError::CommandFailed(format!("Failed to encode fleet state: {e}"))
+                        })?;
+                        store.create_fleet_apply(
+                            &apply_id,
+                            Some(&file.display().to_string()),
+                            &desired_json,
+                            &steps,
+                        )?;
+                        if execute {
+                            run_fleet_apply(&store, &apply_id)?;
+                            emit_multi_report(
+                                &store,
+                                &fleet_apply_report(&store, "vc fleet apply", &apply_id)?,
+                                self.format,
+                                self.save_report,
+                                self.actor.as_deref(),
+                            )?;
+                        } else {
+                            print_recorded_fleet_apply(&store, &apply_id, self.format)?;
+                        }
+                    }
+                    FleetCommands::Resume { apply_id } => {
+                        if store.fleet_apply(&apply_id)?.is_none() {
+                            return Err(CliError::CommandFailed(format!(
+                                "Unknown apply: {apply_id}"
+                            )));
+                        }
+                        run_fleet_apply(&store, &apply_id)?;
+                        emit_multi_report(
+                            &store,
+                            &fleet_apply_report(&store, "vc fleet resume", &apply_id)?,
+                            self.format,
+                            self.save_report,
+                            self.actor.as_deref(),
+                        )?;
+                    }
+                    FleetCommands::Drift => match vc_query::fleet_state::drift(&store)? {
+                        None => println!("No fleet file applied yet; run `vc fleet apply`"),
+                        Some(drift) if matches!(self.format, OutputFormat::Text) => {
+                            println!(
+                                "Since {} ({}):",
+                                drift.apply_id,
+                                drift.source.as_deref().unwrap_or("-")
+                            );
+                            if drift.drift.is_empty() {
+                                println!("  no drift");
+                            }
+                            for diff in &drift.drift {
+                                println!(
+                                    "  {:<16} {:<10} desired {:>3}  running {:>3}",
+                                    diff.machine_id, diff.agent_type, diff.desired, diff.observed
+                                );
+                            }
+                        }
+                        Some(drift) => print_output(&drift, self.format),
+                    },
+                    FleetCommands::Run {
+                        command,
+                        machines,
+                        concurrency,
+                        timeout,
+                        fail_fast,
+                        yes,
+                    } => {
+                        let config = load_config(config_source)?;
+                        let options = vc_collect::fleet_run::FleetRunOptions {
+                            timeout: Duration::from_secs(timeout),
+                            concurrency,
+                            fail_fast,
+                            max_output_bytes: config.fleet_run.max_output_bytes,
+                        };
+                        let actor = command_actor(None, self.actor.as_deref());
+                        run_fleet_command(
+                            cx,
+                            &store,
+                            &command,
+                            &machines,
+                            &options,
+                            (!yes).then_some(config.fleet_run.confirm_above),
+                            &actor,
+                            self.format,
+                            self.save_report,
+                        )
+                        .await?;
+                    }
+                    FleetCommands::Resources {
+                        machine,
+                        stalled_mins,
+                    } => {
+                        let stalled_after = ChronoDuration::minutes(stalled_mins);
+                        let machines = vc_query::agents::fleet_resources(
+                            &store,
+                            machine.as_deref(),
+                            stalled_after,
+                            Utc::now(),
+                        )?;
+                        let agents = match machine.as_deref() {
+                            Some(id) => store.list_agents_inventory(Some(id), true)?,
+                            None => Vec::new(),
+                        };
+                        if !matches!(self.format, OutputFormat::Text) {
+                            print_output(
+                                &serde_json::json!({ "machines": machines, "agents": agents }),
+                                self.format,
+                            );
+                        } else if machines.is_empty() {
+                            println!(
+                                "No agents inventoried yet; run `vc collect --collector agent_inventory`"
+                            );
+                        } else {
+                            print_fleet_resources(&machines, &agents, stalled_after, Utc::now());
+                        }
+                    }
+                }
+            }
+            #[cfg(unix)]
+            Commands::Watch {
+                serve: Some(path),
+                interval,
+                ..
+            } => {
+                let controller = ShutdownController::new();
+                let receiver = controller.subscribe();
+                run_with_shutdown_budget(
+                    cx,
+                    "watch",
+                    controller,
+                    run_watch_serve(config_source, cx, receiver, &path, interval),
+                )
+                .await?;
+            }
+            #[cfg(unix)]
+            Commands::Watch {
+                connect: Some(path),
+                events,
+                changes_only,
+                machines,
+                min_severity,
+                enrich,
+                ..
+            } => {
+                let request = watch_socket::SubscribeRequest {
+                    events,
+                    machines,
+                    min_severity,
+                    changes_only,
+                    enrich,
+                };
+                let controller = ShutdownController::new();
+                let receiver = controller.subscribe();
+                run_with_shutdown_budget(
+                    cx,
+                    "watch",
+                    controller,
+                    run_watch_connect(path, request, self.format, receiver),
+                )
+                .await?;
+            }
+            #[cfg(not(unix))]
+            Commands::Watch { serve: Some(_), .. }
+            | Commands::Watch {
+                connect: Some(_), ..
+            } => {
+                return Err(CliError::CommandFailed(
+                    "watch sockets need a Unix platform".to_string(),
+                ));
+            }
+            Commands::Watch {
+                events,
+                changes_only,
+                interval,
+                machines,
+                min_severity,
+                buffer,
+                enrich,
+                ..
+            } => {
+                let controller = ShutdownController::new();
+                let receiver = controller.subscribe();
                 run_with_shutdown_budget(
                     cx,
                     "watch",
                     controller,
                     run_watch(
-                        self.config.as_ref(),
+                        config_source,
                         self.format,
                         cx,
                         receiver,
@@ -2703,12 +6019,14 @@ impl Cli {
                         machines,
                         min_severity,
                         buffer,
+                        enrich,
                     ),
                 )
                 .await?;
             }
+            #[cfg(feature = "guardian")]
             Commands::Guardian { command } => {
-                let store = Arc::new(open_store(self.config.as_ref())?);
+                let store = Arc::new(open_store(config_source)?);
 
                 match command {
                     GuardianCommands::Playbooks => {
@@ -2766,6 +6084,74 @@ impl Cli {
                             }
                         }
                     }
+                    GuardianCommands::Run {
+                        playbook_id,
+                        sandbox,
+                        machine,
+                        at,
+                        params,
+                    } => {
+                        if !sandbox {
+                            return Err(CliError::CommandFailed(
+                                "Only sandbox runs are available; pass --sandbox".to_string(),
+                            ));
+                        }
+                        let at = match at {
+                            Some(raw) => {
+                                vc_query::timefmt::parse_timestamp(&raw).ok_or_else(|| {
+                                    CliError::CommandFailed(format!("Invalid timestamp: {raw}"))
+                                })?
+                            }
+                            None => chrono::Utc::now(),
+                        };
+                        let mut supplied = std::collections::BTreeMap::new();
+                        for param in params {
+                            let (name, value) = param.split_once('=').ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "Invalid --param {param}; expected NAME=VALUE"
+                                ))
+                            })?;
+                            supplied.insert(name.trim().to_string(), value.to_string());
+                        }
+
+                        let sandbox_error =
+                            |e: vc_guardian::GuardianError| CliError::CommandFailed(e.to_string());
+                        let target = vc_guardian::sandbox::SandboxTarget::resolve(
+                            &store,
+                            &vc_guardian::Guardian::new(),
+                            &playbook_id,
+                        )
+                        .map_err(sandbox_error)?;
+                        let report = vc_guardian::sandbox::run_sandbox(
+                            &store, &target, &supplied, &machine, at,
+                        )
+                        .map_err(sandbox_error)?;
+
+                        let by = command_actor(None, self.actor.as_deref());
+                        let record = vc_store::SandboxRunRecord {
+                            sandbox_id: format!("sbx-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+                            playbook_id: report.playbook_id.clone(),
+                            draft_id: report.draft_id.clone(),
+                            draft_revision: report.draft_revision,
+                            machine_id: report.machine_id.clone(),
+                            as_of: report.at.clone(),
+                            report: serde_json::to_value(&report)
+                                .unwrap_or(serde_json::Value::Null),
+                            created_by: Some(by.name.clone()),
+                            created_at: chrono::Utc::now()
+                                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                        };
+                        store.record_sandbox_run(&record)?;
+
+                        if matches!(self.format, OutputFormat::Text) {
+                            print_sandbox_report(&record.sandbox_id, &report);
+                        } else {
+                            let mut value =
+                                serde_json::to_value(&report).unwrap_or(serde_json::Value::Null);
+                            value["sandbox_id"] = record.sandbox_id.into();
+                            print_output(&value, self.format);
+                        }
+                    }
                     GuardianCommands::Approve { run_id } => {
                         let result = serde_json::json!({
                             "run_id": run_id,
@@ -2780,15 +6166,56 @@ impl Cli {
                         outcome,
                         machine,
                         operator,
+                        from_session,
+                        from_shell_history,
+                        window,
+                        alert_id,
+                        yes,
                     } => {
-                        use vc_guardian::autogen::{
-                            ActionCapture, CapturedAction, ResolutionOutcome,
+                        use vc_guardian::autogen::{ActionCapture, ResolutionOutcome};
+
+                        let mut machine = machine;
+                        let extracted = if let Some(session_id) = &from_session {
+                            let (commands, provenance) = session_capture_commands(
+                                &store,
+                                session_id,
+                                &alert_type,
+                                alert_id,
+                                machine.as_deref(),
+                            )?;
+                            machine = machine.or_else(|| provenance.machine_id.clone());
+                            Some((commands, provenance))
+                        } else if from_shell_history {
+                            let machine_id = machine.as_deref().unwrap_or_default();
+                            Some(
+                                shell_history_capture_commands(cx, &store, machine_id, window)
+                                    .await?,
+                            )
+                        } else {
+                            None
                         };
 
-                        let parsed_actions: Vec<CapturedAction> = serde_json::from_str(&actions)
-                            .map_err(|e| {
-                                CliError::CommandFailed(format!("Invalid actions JSON: {e}"))
-                            })?;
+                        let (parsed_actions, provenance) = match extracted {
+                            Some((commands, provenance)) => {
+                                match confirm_captured_commands(commands, provenance, yes)? {
+                                    Some((actions, provenance)) => (actions, Some(provenance)),
+                                    None => {
+                                        println!("Aborted; no resolution captured");
+                                        return Ok(());
+                                    }
+                                }
+                            }
+                            None => {
+                                let actions: Vec<CapturedAction> =
+                                    serde_json::from_str(actions.as_deref().unwrap_or_default())
+                                        .map_err(|e| {
+                                            CliError::CommandFailed(format!(
+                                                "Invalid actions JSON: {e}"
+                                            ))
+                                        })?;
+                                (actions, None)
+                            }
+                        };
 
                         let parsed_outcome = match outcome.to_lowercase().as_str() {
                             "success" => ResolutionOutcome::Success,
@@ -2797,13 +6224,16 @@ impl Cli {
                             _ => ResolutionOutcome::Unknown,
                         };
 
-                        let capture = ActionCapture::new(store);
+                        let mut capture = ActionCapture::new(store);
+                        if let Some(provenance) = provenance.clone() {
+                            capture = capture.with_provenance(provenance);
+                        }
                         let id = capture
                             .capture(
                                 &alert_type,
                                 &parsed_actions,
                                 parsed_outcome,
-                                None,
+                                provenance.as_ref().and_then(|p| p.alert_id),
                                 machine.as_deref(),
                                 operator.as_deref(),
                             )
@@ -2814,6 +6244,7 @@ impl Cli {
                             "alert_type": alert_type,
                             "actions_count": parsed_actions.len(),
                             "outcome": outcome,
+                            "provenance": provenance,
                             "message": "Resolution captured successfully",
                         });
                         print_output(&result, self.format);
@@ -2824,21 +6255,41 @@ impl Cli {
                     } => {
                         use vc_guardian::autogen;
 
+                        #[cfg(feature = "knowledge")]
+                        let kb = KnowledgeStore::new(store.clone());
                         let drafts = autogen::run_pipeline(store, min_samples, min_confidence)
                             .map_err(|e| {
                                 CliError::CommandFailed(format!("Generation failed: {e}"))
                             })?;
 
-                        let result = serde_json::json!({
-                            "drafts_created": drafts.len(),
-                            "drafts": drafts.iter().map(|d| serde_json::json!({
+                        // Knowledge tagged with the draft's alert type, for
+                        // the reviewer to check the generated steps against
+                        let mut draft_rows = Vec::with_capacity(drafts.len());
+                        for d in &drafts {
+                            #[cfg(feature = "knowledge")]
+                            let knowledge: Vec<serde_json::Value> = kb
+                                .by_alert_type(&d.alert_type, 3)?
+                                .into_iter()
+                                .map(|entry| {
+                                    serde_json::json!({"id": entry.id, "title": entry.title})
+                                })
+                                .collect();
+                            #[cfg(not(feature = "knowledge"))]
+                            let knowledge: Vec<serde_json::Value> = Vec::new();
+                            draft_rows.push(serde_json::json!({
                                 "draft_id": d.draft_id,
                                 "name": d.name,
                                 "alert_type": d.alert_type,
                                 "confidence": d.confidence,
                                 "sample_count": d.sample_count,
                                 "steps": d.steps.len(),
-                            })).collect::<Vec<_>>(),
+                                "knowledge": knowledge,
+                            }));
+                        }
+
+                        let result = serde_json::json!({
+                            "drafts_created": drafts.len(),
+                            "drafts": draft_rows,
                             "message": format!("Generated {} playbook drafts", drafts.len()),
                         });
                         print_output(&result, self.format);
@@ -2856,6 +6307,34 @@ impl Cli {
                             print_output(&drafts, self.format);
                         }
                     }
+                    GuardianCommands::ShowDraft { draft_id } => {
+                        let draft_row = store
+                            .get_playbook_draft(&draft_id)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to get draft: {e}"))
+                            })?
+                            .ok_or_else(|| {
+                                CliError::CommandFailed(format!("Draft not found: {draft_id}"))
+                            })?;
+                        let revisions =
+                            store
+                                .list_playbook_draft_revisions(&draft_id)
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!(
+                                        "Failed to list revisions: {e}"
+                                    ))
+                                })?;
+
+                        let sandbox_runs = store.list_sandbox_runs(&draft_id, 20)?;
+
+                        let result = serde_json::json!({
+                            "draft": draft_row,
+                            "revision_count": revisions.len(),
+                            "revisions": revisions,
+                            "sandbox_runs": sandbox_runs,
+                        });
+                        print_output(&result, self.format);
+                    }
                     GuardianCommands::ValidateDraft { draft_id } => {
                         use vc_guardian::autogen;
 
@@ -2868,67 +6347,114 @@ impl Cli {
                                 CliError::CommandFailed(format!("Draft not found: {draft_id}"))
                             })?;
 
-                        // Reconstruct minimal draft for validation
-                        let steps_json = draft_row["steps_json"].as_str().unwrap_or("[]");
-                        let steps: Vec<vc_guardian::PlaybookStep> =
-                            serde_json::from_str(steps_json).unwrap_or_default();
-
-                        let trigger_json = draft_row["trigger_json"]
-                            .as_str()
-                            .unwrap_or(r#"{"type":"manual"}"#);
-                        let trigger: vc_guardian::PlaybookTrigger =
-                            serde_json::from_str(trigger_json)
-                                .unwrap_or(vc_guardian::PlaybookTrigger::Manual);
-
-                        let confidence = draft_row["confidence"].as_f64().unwrap_or(0.0);
-                        let sample_count =
-                            usize::try_from(draft_row["sample_count"].as_u64().unwrap_or(0))
-                                .unwrap_or(usize::MAX);
-
-                        let pattern = autogen::ResolutionPattern {
-                            alert_type: draft_row["alert_type"].as_str().unwrap_or("").to_string(),
-                            description: String::new(),
-                            common_steps: vec![],
-                            confidence,
-                            sample_count,
-                        };
+                        let draft = autogen::PlaybookDraft::from_row(&draft_row);
+                        let validation = autogen::validate_draft(&draft);
+                        print_output(&validation, self.format);
+                    }
+                    GuardianCommands::EditDraft {
+                        draft_id,
+                        steps_file,
+                        author,
+                    } => {
+                        use vc_guardian::autogen;
 
-                        let draft = autogen::PlaybookDraft {
-                            draft_id: draft_id.clone(),
-                            name: draft_row["name"].as_str().unwrap_or("").to_string(),
-                            description: draft_row["description"]
-                                .as_str()
-                                .unwrap_or("")
-                                .to_string(),
-                            alert_type: pattern.alert_type.clone(),
-                            trigger,
-                            steps,
-                            confidence,
-                            sample_count,
-                            status: autogen::DraftStatus::PendingReview,
-                            source_pattern: pattern,
+                        let author = command_actor(author.as_deref(), self.actor.as_deref());
+
+                        let raw_steps = match steps_file {
+                            Some(path) => std::fs::read_to_string(&path)?,
+                            None => {
+                                let draft_row = store
+                                    .get_playbook_draft(&draft_id)
+                                    .map_err(|e| {
+                                        CliError::CommandFailed(format!("Failed to get draft: {e}"))
+                                    })?
+                                    .ok_or_else(|| {
+                                        CliError::CommandFailed(format!(
+                                            "Draft not found: {draft_id}"
+                                        ))
+                                    })?;
+                                let current = autogen::PlaybookDraft::from_row(&draft_row);
+                                edit_steps_in_editor(&draft_id, &current.steps)?
+                            }
                         };
 
-                        let validation = autogen::validate_draft(&draft);
-                        print_output(&validation, self.format);
+                        let steps: Vec<vc_guardian::PlaybookStep> =
+                            serde_json::from_str(&raw_steps).map_err(|e| {
+                                CliError::CommandFailed(format!("Invalid steps JSON: {e}"))
+                            })?;
+
+                        let revision = autogen::edit_draft(&store, &draft_id, steps, &author.name)
+                            .map_err(|e| CliError::CommandFailed(format!("Edit failed: {e}")))?;
+
+                        let result = serde_json::json!({
+                            "draft_id": revision.draft_id,
+                            "revision": revision.revision,
+                            "editor": revision.editor,
+                            "diff_summary": revision.diff_summary,
+                            "validation": revision.validation,
+                            "message": format!(
+                                "Stored revision {}. Approve with 'guardian approve-draft {} --revision {}'.",
+                                revision.revision, draft_id, revision.revision
+                            ),
+                        });
+                        print_output(&result, self.format);
                     }
-                    GuardianCommands::ApproveDraft { draft_id, approver } => {
-                        let affected =
-                            store
-                                .approve_playbook_draft(&draft_id, &approver)
-                                .map_err(|e| {
-                                    CliError::CommandFailed(format!("Approval failed: {e}"))
-                                })?;
+                    GuardianCommands::ApproveDraft {
+                        draft_id,
+                        revision,
+                        approver,
+                        sandbox_run,
+                    } => {
+                        let approver = command_actor(approver.as_deref(), self.actor.as_deref());
+                        if let Some(sandbox_id) = &sandbox_run {
+                            let run = store.sandbox_run(sandbox_id)?.ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "Sandbox run not found: {sandbox_id}"
+                                ))
+                            })?;
+                            if run.draft_id.as_deref() != Some(draft_id.as_str())
+                                || run.draft_revision != Some(revision)
+                            {
+                                return Err(CliError::CommandFailed(format!(
+                                    "Sandbox run {sandbox_id} was not made for revision \
+                                     {revision} of {draft_id}"
+                                )));
+                            }
+                        }
+                        let affected = store
+                            .approve_playbook_draft(&draft_id, &approver, revision)
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Approval failed: {e}"))
+                            })?;
 
                         if affected == 0 {
                             return Err(CliError::CommandFailed(format!(
-                                "Draft not found or not in pending_review status: {draft_id}"
+                                "Draft not found, not in pending_review status, or revision {revision} \
+                                 is not the current revision: {draft_id}"
                             )));
                         }
 
+                        if let Some(sandbox_id) = &sandbox_run {
+                            store.attach_sandbox_run_to_draft(&draft_id, sandbox_id)?;
+                        }
+
+                        // Approvals are always audited before we report success.
+                        store.insert_audit_event(&approver.audit_event(
+                            vc_store::AuditEventType::GuardianAction,
+                            "approve_draft",
+                            vc_store::AuditResult::Success,
+                            serde_json::json!({
+                                "draft_id": draft_id,
+                                "revision": revision,
+                                "sandbox_run": sandbox_run,
+                            }),
+                        ))?;
+
                         let result = serde_json::json!({
                             "draft_id": draft_id,
-                            "approved_by": approver,
+                            "approved_by": approver.name,
+                            "revision": revision,
+                            "sandbox_run": sandbox_run,
                             "status": "approved",
                             "message": "Draft approved. Use 'guardian activate-draft' to make it live.",
                         });
@@ -2970,6 +6496,56 @@ impl Cli {
                             }
                         }
                     }
+                    GuardianCommands::Export { playbook, out } => {
+                        let exported = vc_guardian::library::export_playbook(
+                            &store,
+                            &vc_guardian::Guardian::new(),
+                            &playbook,
+                            &out,
+                        )
+                        .map_err(|e| CliError::CommandFailed(format!("Export failed: {e}")))?;
+                        if exported.diverged == Some(true)
+                            && matches!(self.format, OutputFormat::Text)
+                        {
+                            eprintln!(
+                                "note: {} has changed since it was imported from {}",
+                                exported.playbook_id,
+                                exported.source_path.as_deref().unwrap_or("a file")
+                            );
+                        }
+                        print_output(&exported, self.format);
+                    }
+                    GuardianCommands::Import {
+                        from,
+                        activate,
+                        overwrite,
+                    } => {
+                        let imported = vc_guardian::library::import_playbook(
+                            &store,
+                            &vc_guardian::Guardian::new(),
+                            &from,
+                            activate,
+                            overwrite,
+                        )
+                        .map_err(|e| CliError::CommandFailed(format!("Import failed: {e}")))?;
+                        print_output(&imported, self.format);
+                    }
+                    GuardianCommands::Lint { dir } => {
+                        let results = vc_guardian::library::lint_dir(&dir)
+                            .map_err(|e| CliError::CommandFailed(format!("Lint failed: {e}")))?;
+                        let failed = results.iter().filter(|r| !r.valid).count();
+                        let result = serde_json::json!({
+                            "files": results.len(),
+                            "failed": failed,
+                            "results": results,
+                        });
+                        print_output(&result, self.format);
+                        if failed > 0 {
+                            return Err(CliError::CommandFailed(format!(
+                                "{failed} playbook file(s) failed lint"
+                            )));
+                        }
+                    }
                     GuardianCommands::Resolutions {
                         alert_type,
                         outcome,
@@ -2989,6 +6565,9 @@ impl Cli {
                     }
                 }
             }
+            #[cfg(not(feature = "guardian"))]
+            Commands::Guardian { .. } => return Err(CliError::FeatureDisabled("guardian")),
+            #[cfg(feature = "web")]
             Commands::Web { port, bind } => {
                 let controller = ShutdownController::new();
                 let receiver = controller.subscribe();
@@ -2996,14 +6575,32 @@ impl Cli {
                     cx,
                     "web",
                     controller,
-                    run_web_server(self.config.as_ref(), port, bind, receiver),
+                    run_web_server(config_source, port, bind, receiver),
                 )
                 .await?;
             }
-            Commands::Mcp { command } => {
-                let store = open_store(self.config.as_ref())?;
+            #[cfg(not(feature = "web"))]
+            Commands::Web { .. } => return Err(CliError::FeatureDisabled("web")),
+            #[cfg(feature = "mcp")]
+            Commands::Mcp { role, command } => {
+                let config = load_config(config_source)?;
+                let allowed_tools = config.mcp.allowed_tools(role.as_deref())?;
+                let store = open_duckdb_store(&config)?
+                    .with_query_log(&config.query_log, vc_store::QueryCaller::Mcp)
+                    .with_write_limits(&config.write_limits)
+                    .with_artifacts(&config.storage.artifacts);
                 let store = std::sync::Arc::new(store);
-                let server = vc_mcp::McpServer::new(store);
+                let server = vc_mcp::McpServer::new(store)
+                    .with_actor(self.actor.as_deref())
+                    .with_query_validator(vc_query::QueryValidator::new(
+                        vc_query::GuardrailConfig::from_config(&config.query),
+                    ))
+                    .with_proposal_ttl(Duration::from_secs(config.query_approval.ttl_secs))
+                    .with_max_response_bytes(config.mcp.max_response_bytes)
+                    .with_allowed_tools(
+                        role.as_deref().or(config.mcp.default_role.as_deref()),
+                        allowed_tools.as_deref(),
+                    );
 
                 match command {
                     McpCommands::Serve => {
@@ -3032,16 +6629,18 @@ impl Cli {
                     }
                 }
             }
+            #[cfg(not(feature = "mcp"))]
+            Commands::Mcp { .. } => return Err(CliError::FeatureDisabled("mcp")),
             Commands::Db { command } => {
-                let store = open_store(self.config.as_ref())?;
-
                 match command {
                     DbCommands::Export {
                         out,
                         since,
                         until,
                         tables,
+                        csv,
                     } => {
+                        let store = open_store(config_source)?;
                         // Get tables to export
                         let all_tables = store.list_tables().map_err(|e| {
                             CliError::CommandFailed(format!("Failed to list tables: {e}"))
@@ -3059,7 +6658,7 @@ impl Cli {
                         })?;
 
                         // Build manifest
-                        let manifest = store
+                        let mut manifest = store
                             .build_export_manifest(
                                 &export_tables,
                                 since.as_deref(),
@@ -3077,14 +6676,28 @@ impl Cli {
                                 .unwrap_or_default();
 
                             if !lines.is_empty() {
-                                let path = format!("{out}/{table}.jsonl");
-                                std::fs::write(&path, lines.join("\n") + "\n").map_err(|e| {
+                                let (path, contents) = if csv {
+                                    let rows: Vec<serde_json::Value> = lines
+                                        .iter()
+                                        .filter_map(|line| serde_json::from_str(line).ok())
+                                        .collect();
+                                    (
+                                        format!("{out}/{table}.csv"),
+                                        csv::to_csv(&serde_json::Value::Array(rows), None),
+                                    )
+                                } else {
+                                    (format!("{out}/{table}.jsonl"), lines.join("\n") + "\n")
+                                };
+                                std::fs::write(&path, contents).map_err(|e| {
                                     CliError::CommandFailed(format!("Failed to write {path}: {e}"))
                                 })?;
                                 total_rows += lines.len();
                             }
                         }
 
+                        manifest["format"] =
+                            serde_json::Value::from(if csv { "csv" } else { "jsonl" });
+
                         // Write manifest
                         let manifest_path = format!("{out}/manifest.json");
                         std::fs::write(
@@ -3095,8 +6708,26 @@ impl Cli {
                             CliError::CommandFailed(format!("Failed to write manifest: {e}"))
                         })?;
 
+                        // Register the directory so `/api/exports` can serve it
+                        let output_dir = std::fs::canonicalize(&out).map_or_else(
+                            |_| out.clone(),
+                            |path| path.to_string_lossy().into_owned(),
+                        );
+                        let bundle = store
+                            .record_export_bundle(
+                                &format!("ex-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+                                &output_dir,
+                                if csv { "csv" } else { "jsonl" },
+                                export_tables.len(),
+                                total_rows,
+                            )
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to record export: {e}"))
+                            })?;
+
                         let result = serde_json::json!({
                             "status": "ok",
+                            "bundle_id": bundle.bundle_id,
                             "output_dir": out,
                             "tables_exported": export_tables.len(),
                             "total_rows": total_rows,
@@ -3105,6 +6736,7 @@ impl Cli {
                         print_output(&result, self.format);
                     }
                     DbCommands::Import { from } => {
+                        let store = open_store(config_source)?;
                         // Read manifest
                         let manifest_path = format!("{from}/manifest.json");
                         let manifest_str =
@@ -3115,6 +6747,11 @@ impl Cli {
                             .map_err(|e| {
                                 CliError::CommandFailed(format!("Invalid manifest JSON: {e}"))
                             })?;
+                        if manifest["format"] == "csv" {
+                            return Err(CliError::CommandFailed(format!(
+                                "{from} is a CSV export; re-export without --csv to import it"
+                            )));
+                        }
 
                         let tables = manifest["tables"].as_array().ok_or_else(|| {
                             CliError::CommandFailed("Manifest missing tables array".to_string())
@@ -3147,7 +6784,75 @@ impl Cli {
                         });
                         print_output(&result, self.format);
                     }
-                    DbCommands::Info => {
+                    #[cfg(feature = "prometheus-import")]
+                    DbCommands::ImportPrometheus {
+                        url,
+                        matcher,
+                        since,
+                        step,
+                        map_file,
+                        dry_run,
+                    } => {
+                        use prometheus_import::{ImportMapping, ImportOptions, PrometheusClient};
+
+                        let mapping = ImportMapping::load(&map_file)
+                            .map_err(|e| CliError::CommandFailed(e.to_string()))?;
+                        let store = open_store(config_source)?;
+                        let client = PrometheusClient::new(&url, Duration::from_secs(60));
+                        let options = ImportOptions {
+                            url,
+                            matcher,
+                            since,
+                            step,
+                            dry_run,
+                        };
+                        let report = prometheus_import::run_import(
+                            &client,
+                            &store,
+                            &mapping,
+                            &options,
+                            |progress| {
+                                eprint!(
+                                    "\rChunk {}/{} through {}: {} rows",
+                                    progress.chunk,
+                                    progress.chunks,
+                                    progress.through.format("%Y-%m-%d %H:%M"),
+                                    progress.rows
+                                );
+                            },
+                        )
+                        .await;
+                        eprintln!();
+                        let report = report
+                            .map_err(|e| CliError::CommandFailed(format!("Import failed: {e}")))?;
+                        if !report.unmapped_metrics.is_empty() {
+                            eprintln!(
+                                "Warning: {} matched metrics have no [[metric]] mapping and were not imported",
+                                report.unmapped_metrics.len()
+                            );
+                        }
+                        if !report.unmapped_instances.is_empty() {
+                            eprintln!(
+                                "Warning: series from {} were skipped; add them to [machines]",
+                                report.unmapped_instances.join(", ")
+                            );
+                        }
+                        print_output(&report, self.format);
+                    }
+                    #[cfg(not(feature = "prometheus-import"))]
+                    DbCommands::ImportPrometheus { .. } => {
+                        return Err(CliError::CommandFailed(
+                            "vc was built without the prometheus-import feature".to_string(),
+                        ));
+                    }
+                    DbCommands::Info { detailed } => {
+                        // Goes through the backend trait so it works on either
+                        // engine; export and import still need `VcStore`.
+                        let config = load_config(config_source)?;
+                        let store = vc_store::open_backend(
+                            &config.global.db_path,
+                            config.global.store_backend.as_deref(),
+                        )?;
                         let tables = store.list_tables().map_err(|e| {
                             CliError::CommandFailed(format!("Failed to list tables: {e}"))
                         })?;
@@ -3161,11 +6866,289 @@ impl Cli {
                             }));
                         }
 
-                        let result = serde_json::json!({
+                        let mut result = serde_json::json!({
+                            "backend": store.kind().as_str(),
+                            "db_path": store.db_path(),
                             "total_tables": tables.len(),
                             "tables": table_info,
                         });
-                        print_output(&result, self.format);
+                        if detailed {
+                            let offloaded = store
+                                .query_json(
+                                    "SELECT COUNT(*) AS pointers, \
+                                     CAST(COALESCE(SUM(size_bytes), 0) AS BIGINT) AS offloaded_bytes \
+                                     FROM artifact_pointers",
+                                )
+                                .map_err(|e| {
+                                    CliError::CommandFailed(format!(
+                                        "Failed to read artifact pointers: {e}"
+                                    ))
+                                })?;
+                            let artifacts = &config.storage.artifacts;
+                            let mut summary = offloaded.into_iter().next().unwrap_or_default();
+                            summary["store"] = serde_json::json!(
+                                artifacts
+                                    .dir
+                                    .as_ref()
+                                    .map(|dir| dir.display().to_string())
+                                    .or_else(|| artifacts.s3_endpoint.clone())
+                            );
+                            summary["offload_enabled"] = serde_json::json!(artifacts.enabled);
+                            result["artifacts"] = summary;
+                        }
+                        print_output(&result, self.format);
+                    }
+                    DbCommands::Artifacts {
+                        command: ArtifactCommands::Verify { download },
+                    } => {
+                        let store = open_store(config_source)?;
+                        let checks = store.verify_artifacts(download)?;
+                        let problems: Vec<_> = checks
+                            .iter()
+                            .filter(|check| check.status != vc_store::ArtifactStatus::Ok)
+                            .collect();
+                        let result = serde_json::json!({
+                            "checked": checks.len(),
+                            "ok": checks.len() - problems.len(),
+                            "downloaded": download,
+                            "problems": problems,
+                        });
+                        print_output(&result, self.format);
+                        if !problems.is_empty() {
+                            return Err(CliError::CommandFailed(format!(
+                                "{} of {} artifact pointers failed verification",
+                                problems.len(),
+                                checks.len()
+                            )));
+                        }
+                    }
+                    DbCommands::Replication {
+                        command: ReplicationCommands::Status,
+                    } => {
+                        let config = load_config(config_source)?;
+                        let store = open_duckdb_store(&config)?;
+                        let role = store.replication_role(config.replication.mode)?;
+                        let tables = store.replication_status(
+                            vc_store::WatermarkSide::for_mode(role),
+                            &config.replication.tables,
+                        )?;
+                        let result = serde_json::json!({
+                            "role": role.as_str(),
+                            "configured_mode": config.replication.mode.as_str(),
+                            "promotion": store.last_promotion()?,
+                            "standby_url": config.replication.standby_url,
+                            "max_lag_secs": tables.iter().filter_map(|t| t.lag_secs).max(),
+                            "rows_pending": tables.iter().map(|t| t.rows_pending).sum::<i64>(),
+                            "tables": tables,
+                        });
+                        print_output(&result, self.format);
+                    }
+                    DbCommands::Promote { by } => {
+                        let config = load_config(config_source)?;
+                        let store = open_duckdb_store(&config)?;
+                        if store.replication_role(config.replication.mode)?
+                            != ReplicationMode::Standby
+                        {
+                            return Err(CliError::CommandFailed(
+                                "this cockpit is already a primary".to_string(),
+                            ));
+                        }
+                        let by = command_actor(by.as_deref(), self.actor.as_deref());
+                        let promotion = store.promote_to_primary(&by)?;
+                        let result = serde_json::json!({
+                            "status": "promoted",
+                            "role": ReplicationMode::Primary.as_str(),
+                            "promoted_at": promotion.promoted_at,
+                            "promoted_by": promotion.promoted_by,
+                            "message": "Promoted to primary; point clients here and set \
+                                        replication.mode = \"primary\" in the config",
+                        });
+                        print_output(&result, self.format);
+                    }
+                    DbCommands::SlowQueries { top, since } => {
+                        let config = load_config(config_source)?;
+                        let since = since
+                            .map(|window| {
+                                ChronoDuration::from_std(window)
+                                    .map(|window| {
+                                        (Utc::now() - window)
+                                            .to_rfc3339_opts(SecondsFormat::Micros, true)
+                                    })
+                                    .map_err(|e| {
+                                        CliError::CommandFailed(format!("Window too large: {e}"))
+                                    })
+                            })
+                            .transpose()?;
+                        let store = open_duckdb_store(&config)?
+                            .with_query_log(&config.query_log, vc_store::QueryCaller::Cli);
+                        let queries = store.slow_queries(since.as_deref(), top)?;
+                        let result = serde_json::json!({
+                            "query_log_enabled": config.query_log.enabled,
+                            "since": since,
+                            "total_ms": queries.iter().map(|q| q.total_ms).sum::<f64>(),
+                            "plans_changed": queries
+                                .iter()
+                                .filter(|q| q.plan_changed_at.is_some())
+                                .count(),
+                            "queries": queries,
+                        });
+                        print_output(&result, self.format);
+                    }
+                    DbCommands::Analyze { table, all } => {
+                        let config = load_config(config_source)?;
+                        let store = open_duckdb_store(&config)?
+                            .with_query_log(&config.query_log, vc_store::QueryCaller::Cli);
+                        let tables: Vec<String> = match table {
+                            Some(table) => vec![table],
+                            None if all => store
+                                .table_stats()?
+                                .into_iter()
+                                .map(|stats| stats.table_name)
+                                .collect(),
+                            None => store
+                                .tables_due_for_analyze(
+                                    config.analyze.change_ratio,
+                                    config.analyze.min_changes,
+                                )?
+                                .into_iter()
+                                .map(|stats| stats.table_name)
+                                .collect(),
+                        };
+                        let mut analyzed = Vec::new();
+                        for table in &tables {
+                            analyzed.push(store.analyze_table(table)?);
+                        }
+                        let result = serde_json::json!({
+                            "tables": analyzed.len(),
+                            "total_ms": analyzed.iter().map(|a| a.duration_ms).sum::<f64>(),
+                            "plans_changed": analyzed
+                                .iter()
+                                .map(|a| a.plans_changed.len())
+                                .sum::<usize>(),
+                            "analyzed": analyzed,
+                        });
+                        print_output(&result, self.format);
+                    }
+                    DbCommands::Seed {
+                        profile,
+                        machines,
+                        days,
+                        seed,
+                        force,
+                    } => {
+                        let store = open_store(config_source)?;
+                        let existing = vc_store::seed::seeded_rows(&store)?;
+                        if existing > 0 && !force {
+                            return Err(CliError::CommandFailed(format!(
+                                "Store already holds {existing} rows; pass --force to seed anyway"
+                            )));
+                        }
+                        let options = vc_store::SeedOptions {
+                            profile,
+                            machines,
+                            days,
+                            seed,
+                            force,
+                            ..vc_store::SeedOptions::default()
+                        };
+                        let report = vc_store::seed_store(&store, &options)
+                            .map_err(|e| CliError::CommandFailed(format!("Failed to seed: {e}")))?;
+                        print_output(&report, self.format);
+                    }
+                    DbCommands::Correct {
+                        table,
+                        filter,
+                        set,
+                        since,
+                        until,
+                        dry_run,
+                        confirm,
+                        allow_key_columns,
+                        batch_size,
+                        undo,
+                        list,
+                        by,
+                    } => {
+                        let store = open_store(config_source)?;
+                        if list {
+                            print_output(&store.list_corrections(50)?, self.format);
+                            return Ok(());
+                        }
+                        let actor = command_actor(by.as_deref(), self.actor.as_deref());
+                        if let Some(id) = undo {
+                            print_output(&store.undo_correction(id, &actor)?, self.format);
+                            return Ok(());
+                        }
+
+                        let table = table.unwrap_or_default();
+                        let filter = filter.unwrap_or_default();
+                        let mut assignments = std::collections::BTreeMap::new();
+                        for pair in set {
+                            let (column, value) = pair.split_once('=').ok_or_else(|| {
+                                CliError::CommandFailed(format!(
+                                    "Invalid --set {pair}; expected COLUMN=VALUE"
+                                ))
+                            })?;
+                            assignments.insert(column.trim().to_string(), value.to_string());
+                        }
+                        if assignments.is_empty() {
+                            return Err(CliError::CommandFailed(
+                                "Nothing to correct; pass --set COLUMN=VALUE".to_string(),
+                            ));
+                        }
+                        for raw in since.iter().chain(until.iter()) {
+                            if vc_query::timefmt::parse_timestamp(raw).is_none() {
+                                return Err(CliError::CommandFailed(format!(
+                                    "Invalid timestamp: {raw}"
+                                )));
+                            }
+                        }
+                        // The condition must hold up as part of a read-only query
+                        vc_query::QueryValidator::new(vc_query::GuardrailConfig::default())
+                            .validate_readonly(&format!(
+                                "SELECT * FROM \"{}\" WHERE {filter}",
+                                vc_store::escape_sql_identifier(&table)
+                            ))?;
+
+                        let spec = vc_store::CorrectionSpec {
+                            table,
+                            where_clause: filter,
+                            assignments,
+                            since,
+                            until,
+                        };
+                        let preview = store
+                            .preview_correction(&spec, vc_store::corrections::PREVIEW_SAMPLE)?;
+                        if dry_run || preview.rows == 0 {
+                            print_output(&preview, self.format);
+                            return Ok(());
+                        }
+                        if !preview.key_columns.is_empty() && !allow_key_columns {
+                            return Err(CliError::CommandFailed(format!(
+                                "{} of {} identify rows or machines; pass --allow-key-columns \
+                                 to correct them anyway",
+                                preview.key_columns.join(", "),
+                                preview.table
+                            )));
+                        }
+                        if preview.rows > vc_store::corrections::CONFIRM_ROWS && !confirm {
+                            return Err(CliError::CommandFailed(format!(
+                                "{} rows match; check them with --dry-run and pass --confirm \
+                                 to correct more than {}",
+                                preview.rows,
+                                vc_store::corrections::CONFIRM_ROWS
+                            )));
+                        }
+                        let correction =
+                            store.apply_correction(&spec, allow_key_columns, batch_size, &actor)?;
+                        print_output(
+                            &serde_json::json!({
+                                "correction": correction,
+                                "sample": preview.sample,
+                                "undo": format!("vc db correct --undo {}", correction.id),
+                            }),
+                            self.format,
+                        );
                     }
                 }
             }
@@ -3173,7 +7156,7 @@ impl Cli {
                 run_duckdb_migration(Path::new(&from), Path::new(&to), self.format)?;
             }
             Commands::Profile { command } => {
-                let store = open_store(self.config.as_ref())?;
+                let store = open_store(config_source)?;
                 let store = Arc::new(store);
 
                 match command {
@@ -3218,21 +7201,99 @@ impl Cli {
                             self.format,
                         );
                     }
-                    ProfileCommands::Decisions { machine, limit } => {
+                    ProfileCommands::Decisions {
+                        machine,
+                        limit,
+                        explain,
+                    } => {
                         let decisions = store
                             .list_poll_decisions(machine.as_deref(), limit)
                             .map_err(|e| {
                                 CliError::CommandFailed(format!("Failed to list decisions: {e}"))
                             })?;
-                        print_output(
-                            &serde_json::json!({"decisions": decisions, "count": decisions.len()}),
-                            self.format,
-                        );
+                        if !explain {
+                            print_output(
+                                &serde_json::json!({"decisions": decisions, "count": decisions.len()}),
+                                self.format,
+                            );
+                        } else if matches!(self.format, OutputFormat::Text) {
+                            if decisions.is_empty() {
+                                println!("No poll decisions recorded yet");
+                            }
+                            for row in &decisions {
+                                print_poll_decision(row);
+                            }
+                        } else {
+                            let explained: Vec<_> =
+                                decisions.iter().map(explain_poll_decision).collect();
+                            print_output(
+                                &serde_json::json!({
+                                    "decisions": explained,
+                                    "count": explained.len(),
+                                }),
+                                self.format,
+                            );
+                        }
+                    }
+                    ProfileCommands::Why { machine, depth } => {
+                        // Enough history for `depth` decisions on each of a
+                        // machine's collectors
+                        let decisions = store
+                            .list_poll_decisions(Some(&machine), depth.saturating_mul(64).max(64))
+                            .map_err(|e| {
+                                CliError::CommandFailed(format!("Failed to list decisions: {e}"))
+                            })?;
+                        let mut chains: std::collections::BTreeMap<String, Vec<serde_json::Value>> =
+                            std::collections::BTreeMap::new();
+                        for row in &decisions {
+                            let collector = row["collector"].as_str().unwrap_or("-").to_string();
+                            let chain = chains.entry(collector).or_default();
+                            if chain.len() < depth {
+                                chain.push(explain_poll_decision(row));
+                            }
+                        }
+
+                        if matches!(self.format, OutputFormat::Text) {
+                            if chains.is_empty() {
+                                println!("No poll decisions recorded for {machine}");
+                            }
+                            for (collector, chain) in &chains {
+                                let latest = &chain[0];
+                                println!(
+                                    "{machine}/{collector}: every {}s ({})",
+                                    latest["interval_secs"],
+                                    latest["rule"].as_str().unwrap_or("-")
+                                );
+                                for step in chain {
+                                    println!(
+                                        "  {}  {}",
+                                        step["decided_at"].as_str().unwrap_or("-"),
+                                        step["explanation"].as_str().unwrap_or("-")
+                                    );
+                                }
+                            }
+                        } else {
+                            print_output(
+                                &serde_json::json!({
+                                    "machine": machine,
+                                    "collectors": chains,
+                                }),
+                                self.format,
+                            );
+                        }
                     }
                 }
             }
             Commands::Ingest { from } => {
-                let store = open_store(self.config.as_ref())?;
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config)?;
+                refuse_on_standby(&config, &store)?;
+
+                if let Some(state) = store.get_daemon_resource_state()?
+                    && let Some(reason) = daemon_limits::ingest_refusal(&state, Utc::now())
+                {
+                    return Err(CliError::CommandFailed(format!("ingest refused: {reason}")));
+                }
 
                 // Read manifest
                 let manifest_path = format!("{from}/manifest.json");
@@ -3243,16 +7304,29 @@ impl Cli {
                     serde_json::from_str(&manifest_str)
                         .map_err(|e| CliError::CommandFailed(format!("Invalid manifest: {e}")))?;
 
-                let result = vc_collect::node::ingest_bundle(&store, &manifest)
-                    .map_err(|e| CliError::CommandFailed(format!("Ingest failed: {e}")))?;
-
+                let result = vc_collect::node::ingest_bundle(
+                    &store,
+                    &manifest,
+                    &config.ingest,
+                    config.machines.get(&manifest.machine_id),
+                )
+                .map_err(|e| CliError::CommandFailed(format!("Ingest failed: {e}")))?;
+
+                let failed = result
+                    .batches
+                    .iter()
+                    .filter(|batch| {
+                        matches!(batch.outcome, vc_collect::node::BatchOutcome::Failed { .. })
+                    })
+                    .count();
                 print_output(
                     &serde_json::json!({
-                        "status": "ok",
+                        "status": result.status,
                         "bundle_id": result.bundle_id,
                         "batches_processed": result.batches_processed,
                         "rows_ingested": result.rows_ingested,
                         "rows_deduplicated": result.rows_deduplicated,
+                        "batches": result.batches,
                         "message": format!(
                             "Ingested {} rows ({} deduped) from {}",
                             result.rows_ingested, result.rows_deduplicated, result.bundle_id
@@ -3260,30 +7334,86 @@ impl Cli {
                     }),
                     self.format,
                 );
+                if failed > 0 {
+                    return Err(CliError::CommandFailed(format!(
+                        "Ingest partial: {failed} of {} batches failed; ingest the bundle again \
+                         to retry them",
+                        result.batches_processed
+                    )));
+                }
             }
-            Commands::Node { command } => {
-                let store = open_store(self.config.as_ref())?;
+            Commands::Node { command } => match command {
+                NodeCommands::History { machine, limit } => {
+                    let store = open_store(config_source)?;
+                    let list_failed = |e: vc_store::StoreError| {
+                        CliError::CommandFailed(format!("Failed to list ingest records: {e}"))
+                    };
+                    let bundles = store
+                        .list_ingest_bundles(machine.as_deref(), limit)
+                        .map_err(list_failed)?;
+                    let records = store
+                        .list_ingest_records(machine.as_deref(), limit)
+                        .map_err(list_failed)?;
+                    let partial = bundles
+                        .iter()
+                        .filter(|bundle| bundle["status"] == "partial")
+                        .count();
+                    print_output(
+                        &serde_json::json!({
+                            "bundles": bundles,
+                            "partial_bundles": partial,
+                            "records": records,
+                            "count": records.len(),
+                        }),
+                        self.format,
+                    );
+                }
+                NodeCommands::Config => {
+                    let config = vc_collect::node::SpoolConfig::default();
+                    print_output(&config, self.format);
+                }
+                NodeCommands::Sign {
+                    secret_file,
+                    body_file,
+                    timestamp,
+                } => {
+                    let secret = std::fs::read_to_string(&secret_file)?;
+                    let secret = secret.trim_end_matches(['\r', '\n']);
+                    if secret.is_empty() {
+                        return Err(CliError::CommandFailed(format!(
+                            "Secret file {} is empty",
+                            secret_file.display()
+                        )));
+                    }
+                    let body = if body_file.as_os_str() == "-" {
+                        let mut body = Vec::new();
+                        std::io::Read::read_to_end(&mut std::io::stdin(), &mut body)?;
+                        body
+                    } else {
+                        std::fs::read(&body_file)?
+                    };
+                    let timestamp = timestamp.unwrap_or_else(|| Utc::now().timestamp());
+                    let signature = vc_types::signing::sign(secret.as_bytes(), timestamp, &body);
 
-                match command {
-                    NodeCommands::History { machine, limit } => {
-                        let records = store
-                            .list_ingest_records(machine.as_deref(), limit)
-                            .map_err(|e| {
-                                CliError::CommandFailed(format!(
-                                    "Failed to list ingest records: {e}"
-                                ))
-                            })?;
+                    if matches!(self.format, OutputFormat::Text) {
+                        println!("X-VC-Timestamp: {timestamp}");
+                        println!("X-VC-Signature: {signature}");
+                    } else {
                         print_output(
-                            &serde_json::json!({"records": records, "count": records.len()}),
+                            &serde_json::json!({
+                                "timestamp": timestamp,
+                                "signature": signature,
+                                "headers": {
+                                    "X-VC-Timestamp": timestamp.to_string(),
+                                    "X-VC-Signature": signature,
+                                },
+                            }),
                             self.format,
                         );
                     }
-                    NodeCommands::Config => {
-                        let config = vc_collect::node::SpoolConfig::default();
-                        print_output(&config, self.format);
-                    }
                 }
-            }
+            },
+            #[cfg(feature = "web")]
             Commands::Token { command } => {
                 match command {
                     TokenCommands::List => {
@@ -3369,24 +7499,35 @@ impl Cli {
                     }
                 }
             }
+            #[cfg(not(feature = "web"))]
+            Commands::Token { .. } => return Err(CliError::FeatureDisabled("web")),
             Commands::Report {
                 window,
                 output,
                 save,
+                email,
+                strict,
             } => {
-                let store = open_store(self.config.as_ref())?;
-                let report = vc_query::digest::generate_digest(&store, window);
+                let store = open_store(config_source)?;
+                let config = load_config(config_source)?;
+                let report = vc_query::digest::generate_digest(&store, window, &config.incidents);
+                if strict && report.is_partial() {
+                    return Err(CliError::CommandFailed(format!(
+                        "Report sections failed: {}",
+                        report.sections_failed.join(", ")
+                    )));
+                }
 
                 if output == "json" {
                     print_output(&report, self.format);
                 } else {
-                    let md = vc_query::digest::render_markdown(&report);
+                    let md = vc_query::digest::render_markdown(&report, time_format());
                     println!("{md}");
                 }
 
                 if save {
                     let json = serde_json::to_string(&report.summary).unwrap_or_default();
-                    let md = vc_query::digest::render_markdown(&report);
+                    let md = vc_query::digest::render_markdown(&report, time_format());
                     store
                         .insert_digest_report(
                             &report.report_id,
@@ -3399,6 +7540,36 @@ impl Cli {
                         })?;
                     eprintln!("Report saved: {}", report.report_id);
                 }
+
+                if email {
+                    let email_config = config.alerts.email.as_ref().ok_or_else(|| {
+                        CliError::CommandFailed("No [alerts.email] sink configured".to_string())
+                    })?;
+                    let channel = vc_alert::EmailChannel::from_config(email_config)
+                        .map_err(|e| CliError::CommandFailed(e.to_string()))?;
+                    let subject = if report.is_partial() {
+                        format!("[vc] Fleet digest ({window}h, incomplete)")
+                    } else {
+                        format!("[vc] Fleet digest ({window}h)")
+                    };
+                    let md = vc_query::digest::render_markdown(&report, time_format());
+
+                    let started = Instant::now();
+                    let sent = channel.send(cx, &subject, &md).await;
+                    log_notification(
+                        &store,
+                        &format!("report:{}", report.report_id),
+                        "email",
+                        sent.as_ref().err(),
+                        started,
+                    );
+                    sent.map_err(|e| CliError::CommandFailed(e.to_string()))?;
+                    eprintln!("Report emailed to {}", email_config.to.join(", "));
+                }
+
+                if report.is_partial() {
+                    return Err(CliError::PartialReport(report.sections_failed));
+                }
             }
             Commands::Redact { command } => match command {
                 RedactCommands::Rules => {
@@ -3420,7 +7591,7 @@ impl Cli {
                     );
                 }
                 RedactCommands::History { machine, limit } => {
-                    let store = open_store(self.config.as_ref())?;
+                    let store = open_store(config_source)?;
                     let events = store
                         .list_redaction_events(machine.as_deref(), limit)
                         .map_err(|e| {
@@ -3432,7 +7603,7 @@ impl Cli {
                     );
                 }
                 RedactCommands::Summary => {
-                    let store = open_store(self.config.as_ref())?;
+                    let store = open_store(config_source)?;
                     let summary = store.redaction_summary().map_err(|e| {
                         CliError::CommandFailed(format!("Failed to get summary: {e}"))
                     })?;
@@ -3454,10 +7625,12 @@ impl Cli {
                 }
             },
             Commands::Collect { collector, machine } => {
-                let config = load_config(self.config.as_ref())?;
-                let store = VcStore::open(&config.global.db_path)?;
-                let registry = vc_collect::CollectorRegistry::with_builtins();
+                let config = load_config(config_source)?;
+                let store = open_duckdb_store(&config)?;
+                refuse_on_standby(&config, &store)?;
+                let registry = vc_collect::CollectorRegistry::from_config(&config);
                 let timeout = config.collector_timeout();
+                let redaction = vc_collect::redact::RedactionEngine::new();
 
                 // Validate `--collector NAME` upfront against the registry, so a
                 // typo errors out immediately instead of silently iterating zero
@@ -3488,17 +7661,34 @@ impl Cli {
                 if targets.is_empty() {
                     targets.push("local".to_string());
                 }
+                let requested = targets.clone();
+                retain_outside_maintenance(&store, &mut targets)?;
+                let mut report = MultiMachineReport::new("vc collect")
+                    .with_context(serde_json::json!({ "collector": collector }));
+                for skipped in requested.iter().filter(|id| !targets.contains(id)) {
+                    report.push(
+                        skipped.as_str(),
+                        TargetOutcome::Skipped,
+                        Some("in maintenance".to_string()),
+                        None,
+                    );
+                }
+                // Per-collector lines are progress for people; the report is
+                // what scripts read
+                let progress = matches!(self.format, OutputFormat::Text);
 
-                let mut runs: usize = 0;
-                let mut failures: usize = 0;
                 let mut cancelled_early = false;
+                let mut attempted: usize = 0;
 
-                'outer: for machine_id in &targets {
+                for machine_id in &targets {
                     let ctx = vc_collect::CollectContext::local(machine_id.clone(), timeout);
+                    let machine_started = Instant::now();
+                    let mut runs: usize = 0;
+                    let mut failed: Vec<String> = Vec::new();
                     for (name, c) in registry.iter() {
                         if cx.checkpoint().is_err() {
                             cancelled_early = true;
-                            break 'outer;
+                            break;
                         }
                         if let Some(filter) = collector.as_deref()
                             && filter != name
@@ -3514,7 +7704,7 @@ impl Cli {
                         // so SIGINT/SIGTERM during `vc collect` actually cancels
                         // in-flight collectors (a fresh Cx::for_testing() here
                         // wouldn't be wired to the signal-driven shutdown path).
-                        let outcome = c.collect(cx, &ctx).await;
+                        let outcome = c.collect_on_platform(cx, &ctx).await;
                         let elapsed =
                             i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
                         runs += 1;
@@ -3538,14 +7728,24 @@ impl Cli {
                                 let mut total_bytes: i64 = 0;
                                 // Only count rows the store actually
                                 // persisted, using the count returned by
-                                // `insert_json_batch`. Surfacing storage
+                                // `write_collected_batch`. Surfacing storage
                                 // errors on stderr keeps the operator
                                 // informed when the DB is broken (disk
                                 // full, schema drift, etc.) — the daemon
                                 // path uses tracing::warn for the same
                                 // signal.
                                 for batch in &result.rows {
-                                    match store.insert_json_batch(&batch.table, &batch.rows) {
+                                    // Oversized transcripts are cut to
+                                    // head + tail per the table's
+                                    // retention policy before they land.
+                                    let limit = store.row_size_limit(&batch.table).ok().flatten();
+                                    let rows = redaction.truncate_rows(&batch.rows, limit.as_ref());
+                                    match store.write_collected_batch(
+                                        machine_id,
+                                        name,
+                                        &batch.table,
+                                        &rows,
+                                    ) {
                                         Ok(count) => {
                                             total_rows = total_rows.saturating_add(
                                                 i64::try_from(count).unwrap_or(i64::MAX),
@@ -3574,7 +7774,6 @@ impl Cli {
                                 let soft_err = if result.success {
                                     None
                                 } else {
-                                    failures += 1;
                                     Some(
                                         result
                                             .error
@@ -3593,7 +7792,6 @@ impl Cli {
                                 )
                             }
                             asupersync::Outcome::Err(e) => {
-                                failures += 1;
                                 (false, 0_i64, 0_i64, Some(e.to_string()), None, "fail")
                             }
                             asupersync::Outcome::Cancelled(reason) => {
@@ -3610,71 +7808,1830 @@ impl Cli {
                                     "canc",
                                 )
                             }
-                            asupersync::Outcome::Panicked(payload) => {
-                                failures += 1;
-                                (
-                                    false,
-                                    0_i64,
-                                    0_i64,
-                                    Some(format!("panicked: {}", payload.message())),
-                                    None,
-                                    "fail",
-                                )
-                            }
+                            asupersync::Outcome::Panicked(payload) => (
+                                false,
+                                0_i64,
+                                0_i64,
+                                Some(format!("panicked: {}", payload.message())),
+                                None,
+                                "fail",
+                            ),
+                        };
+
+                        // Cancellation is not the collector's failure
+                        if !success && !cancelled_early {
+                            failed.push(format!(
+                                "{name}: {}",
+                                error_class.as_deref().unwrap_or("failed")
+                            ));
+                        }
+
+                        let error_cause = collector_error_cause(&outcome, error_class.as_deref());
+                        let health = vc_store::CollectorHealth {
+                            machine_id: machine_id.clone(),
+                            collector: name.to_string(),
+                            collected_at: collected_at_ts,
+                            success,
+                            duration_ms: Some(elapsed),
+                            rows_inserted,
+                            bytes_parsed,
+                            error_class: error_class.clone(),
+                            error_cause: error_cause.map(|cause| cause.as_str().to_string()),
+                            freshness_seconds: None,
+                            payload_hash: None,
+                            collector_version: collector_version(&store, machine_id, c.as_ref()),
+                            schema_version: Some(c.schema_version().to_string()),
+                            cursor_json,
                         };
+                        if let Err(e) = store.insert_collector_health(&health) {
+                            eprintln!(
+                                "warn: collector_health persist failed collector={name} error={e}"
+                            );
+                        }
+
+                        match error_class {
+                            Some(err) if progress => println!(
+                                "{status} machine={machine_id} collector={name} duration_ms={elapsed} cause={} error={err}",
+                                error_cause.map_or("-", |cause| cause.as_str())
+                            ),
+                            None if progress => println!(
+                                "{status} machine={machine_id} collector={name} duration_ms={elapsed}"
+                            ),
+                            _ => {}
+                        }
+
+                        // Stop immediately on cancellation so we don't iterate
+                        // every remaining collector returning the same error.
+                        if cancelled_early {
+                            break;
+                        }
+                    }
+
+                    let (outcome, message) = if !failed.is_empty() {
+                        (
+                            TargetOutcome::Failed,
+                            format!(
+                                "{} of {runs} collector(s) failed: {}",
+                                failed.len(),
+                                failed.join("; ")
+                            ),
+                        )
+                    } else if cancelled_early {
+                        (
+                            TargetOutcome::Skipped,
+                            format!("cancelled after {runs} collector(s)"),
+                        )
+                    } else {
+                        (TargetOutcome::Success, format!("{runs} collector(s)"))
+                    };
+                    report.push(
+                        machine_id.as_str(),
+                        outcome,
+                        Some(message),
+                        Some(machine_started.elapsed()),
+                    );
+                    attempted += 1;
+                    if cancelled_early {
+                        break;
+                    }
+                }
+                for machine_id in &targets[attempted..] {
+                    report.push(
+                        machine_id.as_str(),
+                        TargetOutcome::Skipped,
+                        Some("cancelled".to_string()),
+                        None,
+                    );
+                }
+                report.finish();
+                emit_multi_report(
+                    &store,
+                    &report,
+                    self.format,
+                    self.save_report,
+                    self.actor.as_deref(),
+                )?;
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::Rules {
+                        command: Some(AlertRuleCommands::Test { rule, file, window }),
+                        ..
+                    },
+            } => {
+                let config = load_config(config_source)?;
+                let store = open_store(config_source)?;
+                let rule = resolve_alert_rule(rule.as_deref(), file.as_deref())?;
+                let overrides = rule_overrides(&config);
+
+                let end = Utc::now();
+                let start = end
+                    - chrono::Duration::from_std(window)
+                        .map_err(|e| CliError::CommandFailed(format!("Window too large: {e}")))?;
+                let report = vc_alert::evaluate::simulate(
+                    &store,
+                    &rule,
+                    start,
+                    end,
+                    config.poll_interval(),
+                    Some(&overrides),
+                )
+                .map_err(|e| CliError::CommandFailed(format!("Simulation failed: {e}")))?;
+
+                if matches!(self.format, OutputFormat::Text) {
+                    print_simulation_timeline(&report);
+                } else {
+                    print_output(&report, self.format);
+                }
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::Rules {
+                        command: None,
+                        machine,
+                    },
+            } => {
+                let config = load_config(config_source)?;
+                let overrides = rule_overrides(&config);
+                let engine = vc_alert::AlertEngine::new();
+                let unknown = overrides.unknown_rules(engine.rules());
+
+                let output = if let Some(machine) = &machine {
+                    let rules: Vec<_> = engine
+                        .rules()
+                        .iter()
+                        .map(|rule| overrides.resolve(rule, machine))
+                        .collect();
+                    if matches!(self.format, OutputFormat::Text) {
+                        print_resolved_rules(
+                            machine,
+                            config.machines.contains_key(machine),
+                            overrides.tags(machine),
+                            &rules,
+                        );
+                    }
+                    serde_json::json!({
+                        "machine": machine,
+                        "known_machine": config.machines.contains_key(machine),
+                        "tags": overrides.tags(machine),
+                        "precedence": vc_alert::overrides::PRECEDENCE,
+                        "rules": rules,
+                        "unknown_override_rules": unknown,
+                    })
+                } else {
+                    let rules: Vec<_> = engine
+                        .rules()
+                        .iter()
+                        .map(|rule| {
+                            let mut value = serde_json::to_value(rule).unwrap_or_default();
+                            value["overrides"] =
+                                serde_json::json!(overrides.scopes_for(&rule.rule_id));
+                            value
+                        })
+                        .collect();
+                    if matches!(self.format, OutputFormat::Text) {
+                        println!(
+                            "{:<24}  {:<8}  {:<7}  {:<40}  OVERRIDES",
+                            "RULE", "SEVERITY", "ENABLED", "CONDITION"
+                        );
+                        for rule in engine.rules() {
+                            let scopes = overrides.scopes_for(&rule.rule_id);
+                            println!(
+                                "{:<24}  {:<8}  {:<7}  {:<40}  {}",
+                                rule.rule_id,
+                                severity_label(rule.severity),
+                                rule.enabled,
+                                describe_condition(&rule.condition),
+                                if scopes.is_empty() {
+                                    "-".to_string()
+                                } else {
+                                    scopes.join(", ")
+                                },
+                            );
+                        }
+                    }
+                    serde_json::json!({
+                        "precedence": vc_alert::overrides::PRECEDENCE,
+                        "rules": rules,
+                        "unknown_override_rules": unknown,
+                    })
+                };
+
+                if matches!(self.format, OutputFormat::Text) {
+                    for rule in &unknown {
+                        eprintln!("warning: alerts.overrides names unknown rule '{rule}'");
+                    }
+                } else {
+                    print_output(&output, self.format);
+                }
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::List {
+                        unacked,
+                        resolved,
+                        by,
+                    },
+            } => {
+                let store = open_store(config_source)?;
+                let pending = if unacked {
+                    "WHERE resolved_at IS NULL AND COALESCE(acknowledged, 0) = 0".to_string()
+                } else if resolved {
+                    by.map_or_else(
+                        || "WHERE resolved_at IS NOT NULL".to_string(),
+                        |kind| {
+                            format!(
+                                "WHERE resolved_at IS NOT NULL \
+                                 AND COALESCE(resolution_kind, 'unknown') = '{kind}'"
+                            )
+                        },
+                    )
+                } else {
+                    String::new()
+                };
+                // Escalation state belongs to the group (rule on machine);
+                // every open occurrence in it carries the group's level
+                let alerts = store
+                    .query_json(&format!(
+                        "SELECT id, COALESCE(machine_id, 'unknown') || '/' || rule_id AS \"group\", \
+                         rule_id, machine_id, severity, title, \
+                         CAST(fired_at AS TEXT) AS fired_at, \
+                         COALESCE(acknowledged, 0) = 1 AS acknowledged, \
+                         CAST(resolved_at AS TEXT) AS resolved_at, \
+                         CASE WHEN resolved_at IS NOT NULL \
+                         THEN COALESCE(resolution_kind, 'unknown') END AS resolution_kind, \
+                         resolved_by, guardian_run_id, \
+                         COALESCE(escalation_level, 0) AS escalation_level, \
+                         escalation_policy, escalated_at, correlation_id \
+                         FROM alert_history {pending} \
+                         ORDER BY CAST(fired_at AS TIMESTAMP) DESC, id DESC LIMIT 200"
+                    ))
+                    .map_err(|e| CliError::CommandFailed(format!("Failed to list alerts: {e}")))?;
+                print_output(&alerts, self.format);
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::Ack {
+                        id,
+                        machine,
+                        before,
+                        alert_type,
+                        severity,
+                        dry_run,
+                        yes,
+                        by,
+                        with_children,
+                        ..
+                    },
+            } => {
+                let store = open_store(config_source)?;
+                let mut ids: Vec<i64> = id.into_iter().collect();
+                if let Some(id) = id {
+                    ids.extend(correlated_children_to_ack(
+                        &store,
+                        id,
+                        with_children,
+                        dry_run,
+                    )?);
+                }
+                let filter = vc_store::AlertFilter {
+                    ids,
+                    machine_id: machine,
+                    rule_type: alert_type,
+                    severity,
+                    before,
+                };
+                run_bulk_alert_action(
+                    &store,
+                    vc_store::BulkAlertAction::Acknowledge,
+                    &filter,
+                    dry_run,
+                    yes,
+                    &command_actor(by.as_deref(), self.actor.as_deref()),
+                    self.format,
+                    self.save_report,
+                )?;
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::Resolve {
+                        id,
+                        machine,
+                        before,
+                        alert_type,
+                        severity,
+                        dry_run,
+                        yes,
+                        by,
+                        ..
+                    },
+            } => {
+                let store = open_store(config_source)?;
+                let filter = vc_store::AlertFilter {
+                    ids: id.into_iter().collect(),
+                    machine_id: machine,
+                    rule_type: alert_type,
+                    severity,
+                    before,
+                };
+                run_bulk_alert_action(
+                    &store,
+                    vc_store::BulkAlertAction::Resolve,
+                    &filter,
+                    dry_run,
+                    yes,
+                    &command_actor(by.as_deref(), self.actor.as_deref()),
+                    self.format,
+                    self.save_report,
+                )?;
+            }
+            Commands::Alert {
+                command: AlertCommands::Show { correlation },
+            } => {
+                let store = open_store(config_source)?;
+                let alerts = store.correlated_alerts(&correlation)?;
+                if alerts.is_empty() {
+                    return Err(CliError::CommandFailed(format!(
+                        "No alerts carry correlation {correlation}"
+                    )));
+                }
+                print_output(&alerts, self.format);
+            }
+            Commands::Alert {
+                command:
+                    AlertCommands::TestNotify {
+                        sink,
+                        render_only: true,
+                    },
+            } => {
+                let config = load_config(config_source)?;
+                let template = config
+                    .alerts
+                    .templates
+                    .get(&sink)
+                    .ok_or_else(|| {
+                        CliError::CommandFailed(format!(
+                            "Sink '{sink}' has no template under [alerts.templates]"
+                        ))
+                    })?
+                    .load(&sink)
+                    .map_err(|e| CliError::CommandFailed(e.to_string()))?;
+
+                let alert = alert_delivery::test_alert("local");
+                let web_url = alert_delivery::web_url(&config.alerts);
+                let vars = match open_store(config_source) {
+                    Ok(store) => {
+                        vc_alert::template::resolve_vars(&store, &alert, web_url.as_deref())
+                    }
+                    Err(_) => vc_alert::template::alert_vars(&alert, None, web_url.as_deref()),
+                };
+                let rendered = template.render(&vars, vc_alert::template::escape_for(&sink));
+                if matches!(self.format, OutputFormat::Text) {
+                    println!("{}", rendered.text);
+                    for path in &rendered.unresolved {
+                        eprintln!("warning: {{{{{path}}}}} has no value and is sent as written");
+                    }
+                } else {
+                    print_output(
+                        &serde_json::json!({
+                            "sink": sink,
+                            "rendered": rendered.text,
+                            "unresolved": rendered.unresolved,
+                        }),
+                        self.format,
+                    );
+                }
+            }
+            Commands::Alert {
+                command: AlertCommands::TestNotify { sink, .. },
+            } => {
+                let config = load_config(config_source)?;
+                let channel = alert_delivery::build_channel(&config.alerts, &sink)
+                    .map_err(|e| CliError::CommandFailed(e.to_string()))?
+                    .ok_or_else(|| {
+                        CliError::CommandFailed(format!(
+                            "Sink '{sink}' is not configured under [alerts]"
+                        ))
+                    })?;
+
+                let started = Instant::now();
+                let result = channel
+                    .deliver(cx, &alert_delivery::test_alert("local"))
+                    .await;
+                if let Ok(store) = open_store(config_source) {
+                    log_notification(&store, "test-notify", &sink, result.as_ref().err(), started);
+                }
+
+                print_output(
+                    &serde_json::json!({
+                        "sink": sink,
+                        "delivered": result.is_ok(),
+                        "error": result.as_ref().err().map(ToString::to_string),
+                        "duration_ms": started.elapsed().as_millis(),
+                    }),
+                    self.format,
+                );
+                result.map_err(|e| CliError::CommandFailed(e.to_string()))?;
+            }
+            command => {
+                println!("Command not yet implemented: {:?}", command);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Matches above this many alerts need `--yes` or an interactive confirmation
+const BULK_ALERT_CONFIRM_THRESHOLD: usize = 20;
+
+/// `--by` when given, else the global `--actor`, else the OS user
+fn command_actor(by: Option<&str>, actor: Option<&str>) -> vc_store::ActorContext {
+    vc_store::ActorContext::cli(by.or(actor))
+}
+
+/// Read a probed machine's identity, reconcile it with the registry and
+/// leave its UUID in the machine's marker file. A machine that cannot be
+/// read or marked is logged, not failed: the rest of the probe still counts.
+async fn probe_identity(
+    cx: &Cx,
+    store: &VcStore,
+    machine_id: &str,
+    executor: &Executor,
+    auto_rename: bool,
+    actor: &vc_store::ActorContext,
+) -> Result<Option<vc_collect::identity::IdentityCheck>, CliError> {
+    let timeout = Duration::from_secs(5);
+    let remote = match vc_collect::identity::read_identity(cx, executor, timeout).await {
+        Ok(remote) => remote,
+        Err(e) => {
+            tracing::warn!(machine = %machine_id, error = %e, "identity probe failed");
+            return Ok(None);
+        }
+    };
+    let check =
+        vc_collect::identity::reconcile_identity(store, machine_id, &remote, auto_rename, actor)?;
+    if let Some(uuid) = &check.write_marker
+        && let Err(e) = vc_collect::identity::write_marker(cx, executor, uuid, timeout).await
+    {
+        tracing::warn!(
+            machine = %machine_id,
+            error = %e,
+            "could not write the machine UUID marker"
+        );
+    }
+    Ok(Some(check))
+}
+
+/// `vc retention hold create|list|release`; creating and releasing a hold
+/// are audited
+fn run_retention_hold(
+    store: &VcStore,
+    command: RetentionHoldCommands,
+    by: &vc_store::ActorContext,
+    format: OutputFormat,
+) -> Result<(), CliError> {
+    match command {
+        RetentionHoldCommands::Create {
+            reason,
+            incident,
+            session,
+            machine,
+            from,
+            to,
+        } => {
+            let bound = |raw: Option<String>| {
+                raw.map(|raw| {
+                    vc_query::timefmt::parse_timestamp(&raw)
+                        .ok_or_else(|| CliError::CommandFailed(format!("Invalid timestamp: {raw}")))
+                })
+                .transpose()
+            };
+            let scope = vc_store::HoldScope {
+                incident_id: incident,
+                session_id: session,
+                machine_id: machine,
+                from: bound(from)?,
+                to: bound(to)?,
+            };
+            let hold_id = format!("hold-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+            let hold = store
+                .create_legal_hold(&hold_id, &reason, &scope, &by.name)
+                .map_err(|e| CliError::CommandFailed(format!("Failed to create hold: {e}")))?;
+            store.insert_audit_event(&by.audit_event(
+                vc_store::AuditEventType::UserCommand,
+                "legal_hold_create",
+                vc_store::AuditResult::Success,
+                serde_json::to_value(&hold).unwrap_or(serde_json::Value::Null),
+            ))?;
+            print_output(&hold, format);
+        }
+        RetentionHoldCommands::List { all } => {
+            let holds = store.list_legal_holds(all)?;
+            if holds.is_empty() {
+                println!("No legal holds in force");
+            } else {
+                print_output(&holds, format);
+            }
+        }
+        RetentionHoldCommands::Release { id, confirm } => {
+            if !confirm {
+                return Err(CliError::CommandFailed(format!(
+                    "Releasing {id} lets vacuum delete the rows it keeps; pass --confirm"
+                )));
+            }
+            let hold = store
+                .release_legal_hold(&id, &by.name)
+                .map_err(|e| CliError::CommandFailed(format!("Failed to release hold: {e}")))?
+                .ok_or_else(|| CliError::CommandFailed(format!("Legal hold {id} not found")))?;
+            store.insert_audit_event(&by.audit_event(
+                vc_store::AuditEventType::UserCommand,
+                "legal_hold_release",
+                vc_store::AuditResult::Success,
+                serde_json::to_value(&hold).unwrap_or(serde_json::Value::Null),
+            ))?;
+            print_output(&hold, format);
+        }
+    }
+    Ok(())
+}
+
+/// Alerts shown by `--dry-run` and before a confirmation prompt
+const BULK_ALERT_SAMPLE: usize = 10;
+
+/// `vc alert ack|resolve`: preview the match, confirm large ones, then apply
+/// it as one store update with one summarizing audit event
+fn run_bulk_alert_action(
+    store: &VcStore,
+    action: vc_store::BulkAlertAction,
+    filter: &vc_store::AlertFilter,
+    dry_run: bool,
+    yes: bool,
+    actor: &vc_store::ActorContext,
+    format: OutputFormat,
+    save_report: bool,
+) -> Result<(), CliError> {
+    let preview = store.match_alerts(action, filter, BULK_ALERT_SAMPLE)?;
+    if dry_run {
+        print_output(
+            &serde_json::json!({
+                "action": action.as_str(),
+                "dry_run": true,
+                "filter": filter,
+                "matched": preview.matched,
+                "sample": preview.sample,
+            }),
+            format,
+        );
+        return Ok(());
+    }
+    if preview.matched == 0 {
+        if !filter.ids.is_empty() {
+            return Err(CliError::CommandFailed(format!(
+                "Alert {} not found or nothing to {}",
+                filter.ids[0],
+                action.as_str()
+            )));
+        }
+        println!("No alerts match; nothing to {}", action.as_str());
+        return Ok(());
+    }
+    if preview.matched > BULK_ALERT_CONFIRM_THRESHOLD && !yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::CommandFailed(format!(
+                "{} alerts match; re-run with --yes to {} them",
+                preview.matched,
+                action.as_str()
+            )));
+        }
+        eprint!("{} {} alerts? [y/N] ", action.as_str(), preview.matched);
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| CliError::CommandFailed(format!("Failed to read answer: {e}")))?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("Aborted; no alerts changed");
+            return Ok(());
+        }
+    }
+
+    let command = match action {
+        vc_store::BulkAlertAction::Acknowledge => "vc alert ack",
+        vc_store::BulkAlertAction::Resolve => "vc alert resolve",
+    };
+    let mut report = MultiMachineReport::new(command);
+    let started = Instant::now();
+    let changed = store.bulk_update_alerts(action, filter, actor)?;
+    store.insert_audit_event(&vc_store::bulk_alert_audit_event(
+        action, actor, filter, changed,
+    ))?;
+    // One update covers every machine, so each shares its duration
+    let elapsed = started.elapsed();
+    let done = match action {
+        vc_store::BulkAlertAction::Acknowledge => "acknowledged",
+        vc_store::BulkAlertAction::Resolve => "resolved",
+    };
+    for (machine_id, matched) in &preview.by_machine {
+        report.push(
+            machine_id.as_str(),
+            TargetOutcome::Success,
+            Some(format!("{matched} alert(s) {done}")),
+            Some(elapsed),
+        );
+    }
+    report = report.with_context(serde_json::json!({
+        "action": action.as_str(),
+        "filter": filter,
+        "matched": preview.matched,
+        "changed": changed,
+    }));
+    report.finish();
+    emit_multi_report(store, &report, format, save_report, Some(&actor.name))
+}
+
+/// `vc alert ack <id>` on the probable root of a correlated group: the
+/// children to acknowledge with it. `--with-children` takes them all; on a
+/// terminal the operator is asked; otherwise only the root is acknowledged.
+fn correlated_children_to_ack(
+    store: &VcStore,
+    root_id: i64,
+    with_children: bool,
+    dry_run: bool,
+) -> Result<Vec<i64>, CliError> {
+    let children = store.correlated_children(root_id)?;
+    if children.is_empty() || with_children {
+        return Ok(children);
+    }
+    if dry_run || !std::io::stdin().is_terminal() {
+        eprintln!(
+            "Alert {root_id} is the probable root of {} correlated alert(s); \
+             add --with-children to acknowledge them too",
+            children.len()
+        );
+        return Ok(Vec::new());
+    }
+    eprint!(
+        "Alert {root_id} is the probable root of {} correlated alert(s); \
+         acknowledge them too? [y/N] ",
+        children.len()
+    );
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| CliError::CommandFailed(format!("Failed to read answer: {e}")))?;
+    if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        Ok(children)
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+/// Minutes of transcript kept either side of the resolved alert's lifetime
+#[cfg(feature = "guardian")]
+const CAPTURE_WINDOW_MARGIN_MINS: i64 = 5;
+
+/// How long reading a machine's shell history may take
+#[cfg(feature = "guardian")]
+const SHELL_HISTORY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// `vc guardian capture --from-session`: the commands run in a recorded
+/// session, windowed to the alert they resolved (`--alert-id`, else the
+/// latest alert of the type fired before the session ended). Without such
+/// an alert the whole transcript is used.
+#[cfg(feature = "guardian")]
+fn session_capture_commands(
+    store: &VcStore,
+    session_id: &str,
+    alert_type: &str,
+    alert_id: Option<i64>,
+    machine: Option<&str>,
+) -> Result<(Vec<ExtractedCommand>, CaptureProvenance), CliError> {
+    use vc_guardian::transcript::{extract_session_commands, parse_transcript};
+    use vc_query::timefmt::parse_timestamp;
+
+    let session = store
+        .get_agent_session(session_id)?
+        .ok_or_else(|| CliError::CommandFailed(format!("Agent session not found: {session_id}")))?;
+    let field = |name: &str| session.get(name).and_then(serde_json::Value::as_str);
+    let machine_id = machine.or(field("machine_id")).map(ToString::to_string);
+    let session_end = field("ended_at").and_then(parse_timestamp);
+
+    let span = match alert_id {
+        Some(id) => Some(
+            store
+                .alert_span(id)?
+                .ok_or_else(|| CliError::CommandFailed(format!("Alert {id} not found")))?,
+        ),
+        None => {
+            let before = session_end
+                .unwrap_or_else(Utc::now)
+                .to_rfc3339_opts(SecondsFormat::Micros, true);
+            store.latest_alert_span(alert_type, machine_id.as_deref(), &before)?
+        }
+    };
+    let margin = ChronoDuration::minutes(CAPTURE_WINDOW_MARGIN_MINS);
+    let window = span.as_ref().and_then(|span| {
+        let fired = parse_timestamp(&span.fired_at)?;
+        let resolved = span
+            .resolved_at
+            .as_deref()
+            .and_then(parse_timestamp)
+            .or(session_end)
+            .unwrap_or_else(Utc::now);
+        Some((fired - margin, resolved + margin))
+    });
+
+    let transcript = parse_transcript(field("raw_json").unwrap_or_default());
+    let commands = extract_session_commands(&transcript, window);
+    let provenance = CaptureProvenance {
+        source: "session".to_string(),
+        session_id: Some(session_id.to_string()),
+        machine_id,
+        alert_id: span.map(|span| span.alert_id),
+        window_start: window.map(|(start, _)| start),
+        window_end: window.map(|(_, end)| end),
+        extracted: commands.len(),
+        ..CaptureProvenance::default()
+    };
+    Ok((commands, provenance))
+}
+
+/// `vc guardian capture --from-shell-history`: the shell history of the last
+/// `window` on `machine_id`, read through its executor
+#[cfg(feature = "guardian")]
+async fn shell_history_capture_commands(
+    cx: &Cx,
+    store: &Arc<VcStore>,
+    machine_id: &str,
+    window: Duration,
+) -> Result<(Vec<ExtractedCommand>, CaptureProvenance), CliError> {
+    use vc_guardian::transcript::{SHELL_HISTORY_COMMAND, parse_shell_history};
+
+    let registry = vc_collect::machine::MachineRegistry::new(store.clone());
+    let machine = registry
+        .get_machine(machine_id)
+        .map_err(|e| CliError::CommandFailed(format!("Error fetching machine: {e}")))?
+        .ok_or_else(|| CliError::CommandFailed(format!("Machine not found: {machine_id}")))?;
+    let executor = match machine.ssh_config() {
+        Some(cfg) => Executor::remote(cfg),
+        None => Executor::local(),
+    };
+    let output = executor
+        .run(cx, SHELL_HISTORY_COMMAND, SHELL_HISTORY_TIMEOUT)
+        .await
+        .map_err(|e| {
+            CliError::CommandFailed(format!("Reading shell history on {machine_id} failed: {e}"))
+        })?;
+
+    let end = Utc::now();
+    let start = end
+        - ChronoDuration::from_std(window)
+            .map_err(|e| CliError::CommandFailed(format!("Window too large: {e}")))?;
+    let commands = parse_shell_history(&output.stdout, start);
+    let timed = commands.iter().any(|command| command.at.is_some());
+    let provenance = CaptureProvenance {
+        source: "shell_history".to_string(),
+        machine_id: Some(machine_id.to_string()),
+        window_start: timed.then_some(start),
+        window_end: timed.then_some(end),
+        extracted: commands.len(),
+        ..CaptureProvenance::default()
+    };
+    Ok((commands, provenance))
+}
+
+/// Redact secrets from extracted commands, then let the operator trim the
+/// list (or keep all of it with `--yes`). `None` when nothing is kept.
+#[cfg(feature = "guardian")]
+fn confirm_captured_commands(
+    commands: Vec<ExtractedCommand>,
+    mut provenance: CaptureProvenance,
+    yes: bool,
+) -> Result<Option<(Vec<CapturedAction>, CaptureProvenance)>, CliError> {
+    if commands.is_empty() {
+        return Err(CliError::CommandFailed(format!(
+            "No commands found in the {} to capture",
+            provenance.source.replace('_', " ")
+        )));
+    }
+    let engine = vc_collect::redact::RedactionEngine::new();
+    let commands: Vec<(ExtractedCommand, usize)> = commands
+        .into_iter()
+        .map(|mut command| {
+            let (redacted, stats) = engine.redact_text(&command.command);
+            command.command = redacted;
+            (command, stats.fields_redacted)
+        })
+        .collect();
+
+    let keep = if yes {
+        (0..commands.len()).collect()
+    } else {
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::CommandFailed(format!(
+                "{} commands extracted; re-run with --yes to capture them all",
+                commands.len()
+            )));
+        }
+        for (n, (command, _)) in commands.iter().enumerate() {
+            let at = command
+                .at
+                .map(|at| at.format("%H:%M:%S ").to_string())
+                .unwrap_or_default();
+            eprintln!("{:>3}. {at}{}", n + 1, command.command);
+        }
+        eprint!("Keep which commands? [all/none/1-3,5] ");
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| CliError::CommandFailed(format!("Failed to read answer: {e}")))?;
+        vc_guardian::transcript::select_commands(&answer, commands.len())
+            .map_err(|e| CliError::CommandFailed(format!("Invalid selection: {e}")))?
+    };
+    if keep.is_empty() {
+        return Ok(None);
+    }
+
+    provenance.kept = keep.len();
+    provenance.redactions = keep.iter().map(|&n| commands[n].1).sum();
+    let actions = keep.iter().map(|&n| commands[n].0.to_action()).collect();
+    Ok(Some((actions, provenance)))
+}
+
+/// `--before`: a timestamp, a date (midnight UTC), or an age such as `2d`,
+/// normalized to the RFC 3339 form alerts are stored with
+fn parse_before(raw: &str) -> Result<String, String> {
+    let ts = vc_query::timefmt::parse_timestamp(raw)
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|naive| naive.and_utc())
+        })
+        .map_or_else(
+            || {
+                let age = parse_window(raw).map_err(|_| {
+                    format!("invalid --before '{raw}' (expected RFC 3339, YYYY-MM-DD or e.g. 2d)")
+                })?;
+                ChronoDuration::from_std(age)
+                    .map(|age| Utc::now() - age)
+                    .map_err(|e| format!("--before age too large: {e}"))
+            },
+            Ok,
+        )?;
+    Ok(ts.to_rfc3339_opts(SecondsFormat::Micros, true))
+}
+
+/// Record a one-off notification (report or test) in `alert_delivery_log`
+fn log_notification(
+    store: &VcStore,
+    alert_id: &str,
+    sink: &str,
+    error: Option<&vc_alert::AlertError>,
+    started: Instant,
+) {
+    let status = if error.is_some() { "failed" } else { "success" };
+    let error = error.map(ToString::to_string);
+    let logged = store.insert_delivery_log(
+        alert_id,
+        sink,
+        status,
+        error.as_deref(),
+        i64::try_from(started.elapsed().as_millis()).ok(),
+    );
+    if let Err(e) = logged {
+        tracing::warn!(sink, error = %e, "failed to log notification delivery");
+    }
+}
+
+/// Point a cancelled query at cheaper alternatives before reporting it
+fn with_timeout_hint(err: vc_query::QueryError) -> CliError {
+    if matches!(err, vc_query::QueryError::Timeout { .. }) {
+        eprintln!(
+            "Hint: narrow the query with filters (machine_id, a time range, LIMIT) \
+             or use a template (vc query templates)"
+        );
+    }
+    CliError::QueryError(err)
+}
+
+/// Parse a window such as `7d`, `12h`, `30m` or `90s`
+fn parse_window(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (amount, unit) = raw.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid window '{raw}' (expected e.g. 7d, 12h, 30m)"))?;
+    let unit_secs = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" | "" => 86_400,
+        "w" => 604_800,
+        other => {
+            return Err(format!(
+                "unknown window unit '{other}' (use s, m, h, d or w)"
+            ));
+        }
+    };
+    Ok(Duration::from_secs(amount.saturating_mul(unit_secs)))
+}
+
+/// One line per timeline event, then how to fetch the next page
+fn print_timeline(page: &vc_query::TimelinePage) {
+    if page.events.is_empty() {
+        println!("No events in this window");
+    }
+    for event in &page.events {
+        println!(
+            "{:<16} {:<9} {:<8} {:<14} {}  [{}]",
+            time_format().timestamp_str(&event.ts),
+            event.kind,
+            event.severity.as_deref().unwrap_or("-"),
+            event.machine_id.as_deref().unwrap_or("-"),
+            event.summary,
+            event.ref_id
+        );
+    }
+    if let Some(next) = &page.next {
+        println!();
+        println!("More events: vc timeline --after '{next}' (with the same filters)");
+    }
+}
+
+fn print_search(results: &vc_query::SearchResults) {
+    for warning in &results.warnings {
+        println!("warning: {warning}");
+    }
+    if results.hits.is_empty() {
+        println!("No matches");
+    }
+    for hit in &results.hits {
+        println!(
+            "{:<16} {:<9} {:<14} {}",
+            time_format().timestamp_str(&hit.ts),
+            hit.kind,
+            hit.machine_id.as_deref().unwrap_or("-"),
+            hit.title
+        );
+        if !hit.snippet.is_empty() {
+            println!("    {}", hit.snippet);
+        }
+        println!("    -> {}", hit.open);
+    }
+}
+
+/// Note machines whose latest score came from a different scoring version
+/// than the one before it; text output only, JSON carries `scoring_version`
+fn print_scoring_version_changes(changes: &[vc_query::ScoringVersionChange], format: OutputFormat) {
+    if !matches!(format, OutputFormat::Text) {
+        return;
+    }
+    for change in changes {
+        println!(
+            "note: {} was last scored under scoring version {} (previously {}); a jump may \
+             come from the upgrade. Run `vc health rescore --window 7d` to compare like with \
+             like.",
+            change.machine_id, change.scoring_version, change.previous_version
+        );
+    }
+}
+
+/// Current vs. window-average health, biggest movers first
+fn print_health_comparison(comparison: &vc_query::HealthComparison) {
+    use vc_query::timefmt::duration_secs;
+
+    let baseline =
+        |score: Option<f64>| score.map_or_else(|| "no baseline".to_string(), |b| format!("{b:.2}"));
+    let trend = |trend: Option<vc_query::HealthTrend>| trend.map_or("-", |t| t.as_str());
+
+    println!(
+        "{}: {:.2} now vs {} over the last {} ({})",
+        comparison.machine_id,
+        comparison.current_score,
+        baseline(comparison.baseline_score),
+        duration_secs(comparison.window_secs.unsigned_abs()),
+        trend(comparison.trend),
+    );
+    if comparison.factors.is_empty() {
+        return;
+    }
+
+    println!();
+    println!(
+        "{:<20}  {:>7}  {:>11}  {:>7}  {:<9}",
+        "FACTOR", "CURRENT", "BASELINE", "DELTA", "TREND"
+    );
+    for factor in &comparison.factors {
+        println!(
+            "{:<20}  {:>7.2}  {:>11}  {:>7}  {:<9}",
+            factor.factor_id,
+            factor.score,
+            baseline(factor.baseline_score),
+            factor
+                .delta()
+                .map_or_else(|| "-".to_string(), |d| format!("{d:+.2}")),
+            trend(factor.trend),
+        );
+    }
+}
+
+/// Buckets drawn in a text-output sparkline
+const SPARKLINE_BUCKETS: usize = 24;
+
+/// Whether text output draws sparklines: `--sparkline` decides when given,
+/// otherwise only a terminal gets them
+fn sparklines_enabled(flag: Option<bool>, format: OutputFormat) -> bool {
+    matches!(format, OutputFormat::Text) && flag.unwrap_or_else(|| std::io::stdout().is_terminal())
+}
+
+/// Labeled sparkline of `samples` from `since` until now
+fn sparkline(
+    samples: &[(DateTime<Utc>, f64)],
+    since: DateTime<Utc>,
+    bucketing: vc_query::sparkline::Bucketing,
+    label: impl Fn(f64) -> String,
+) -> String {
+    let buckets =
+        vc_query::sparkline::bucketize(samples, since, Utc::now(), SPARKLINE_BUCKETS, bucketing);
+    vc_query::sparkline::render_labeled(&buckets, vc_query::sparkline::SparkStyle::detect(), label)
+}
+
+/// Text rendering for `vc health checks`: one line per check, with the
+/// failure streak and any certificate warning
+fn print_http_checks(checks: &[vc_store::HttpCheckRecord]) {
+    println!(
+        "{:<16}  {:<20}  {:<4}  {:>6}  {:>9}  {}",
+        "MACHINE", "CHECK", "OK", "STATUS", "LATENCY", "DETAIL"
+    );
+    for check in checks {
+        let detail = match (&check.error, &check.tls_warning) {
+            (Some(error), _) if check.consecutive_failures > 1 => {
+                format!("{error} ({} in a row)", check.consecutive_failures)
+            }
+            (Some(error), _) => error.clone(),
+            (None, Some(warning)) => warning.clone(),
+            (None, None) => check.url.clone(),
+        };
+        println!(
+            "{:<16}  {:<20}  {:<4}  {:>6}  {:>9}  {}",
+            check.machine_id,
+            check.check_name,
+            if check.ok { "ok" } else { "FAIL" },
+            check
+                .status_code
+                .map_or_else(|| "-".to_string(), |code| code.to_string()),
+            check
+                .latency_ms
+                .map_or_else(|| "-".to_string(), |ms| format!("{ms:.0}ms")),
+            detail,
+        );
+    }
+}
+
+fn print_disk_forecast(forecast: &vc_query::DiskForecast) {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    // Display only; disk sizes are far inside f64's exact range
+    #[allow(clippy::cast_precision_loss)]
+    let gib = |bytes: i64| bytes as f64 / GIB;
+    println!(
+        "{:<16}  {:<20}  {:>6}  {:>10}  {:>10}  {:>11}  {:>4}  TREND",
+        "MACHINE", "MOUNT", "USED", "SIZE", "GROWTH/DAY", "DAYS LEFT", "CONF"
+    );
+    for mount in &forecast.mounts {
+        let days = match (mount.days_until_full, mount.within_horizon) {
+            (Some(days), true) => format!("{days:.1} !"),
+            (Some(days), false) => format!("{days:.1}"),
+            (None, _) => "-".to_string(),
+        };
+        println!(
+            "{:<16}  {:<20}  {:>5.1}%  {:>6.1} GiB  {:>10}  {:>11}  {:>4.2}  {}",
+            mount.machine_id,
+            mount.mount,
+            mount.usage_pct,
+            gib(mount.total_bytes),
+            mount.growth_bytes_per_day.map_or_else(
+                || "-".to_string(),
+                |bytes| format!("{:.2} GiB", bytes / GIB)
+            ),
+            days,
+            mount.confidence,
+            mount.trend.as_str(),
+        );
+    }
+    println!(
+        "\nFleet: {:.1} of {:.1} GiB used, growing {:.2} GiB/day; {} mount(s) fill within {}d (!)",
+        gib(forecast.used_bytes),
+        gib(forecast.total_bytes),
+        forecast.growth_bytes_per_day / GIB,
+        forecast.filling_within_horizon,
+        forecast.horizon_days,
+    );
+}
+
+/// Text rendering of the per-tag rows under `vc status`'s fleet line
+fn print_tag_breakdown(groups: &[vc_query::TagBreakdown]) {
+    println!(
+        "  {:<16} {:>5} {:>4} {:>4} {:>5} {:>6} {:>6}  WORST",
+        "TAG", "TOTAL", "ON", "OFF", "MAINT", "HEALTH", "ALERTS"
+    );
+    for group in groups {
+        println!(
+            "  {:<16} {:>5} {:>4} {:>4} {:>5} {:>6.2} {:>6}  {}",
+            group.tag,
+            group.total_machines,
+            group.online_machines,
+            group.offline_machines,
+            group.maintenance_machines,
+            group.health_score,
+            group.active_alerts,
+            group.worst_machine.as_deref().unwrap_or("-"),
+        );
+    }
+}
+
+/// Text rendering for `vc fleet versions`: one block per tool, outliers and
+/// versions below the configured minimum marked
+fn print_fleet_versions(spreads: &[vc_query::ToolVersionSpread]) {
+    for (i, spread) in spreads.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match &spread.minimum_version {
+            Some(min) => println!("{} (minimum {min})", spread.tool),
+            None => println!("{}", spread.tool),
+        }
+        for group in &spread.versions {
+            let marker = if group.below_minimum {
+                "  below minimum"
+            } else if group.outlier {
+                "  outlier"
+            } else {
+                ""
+            };
+            println!(
+                "  {:<12}  {:>3}  {}{marker}",
+                group.version,
+                group.machines.len(),
+                group.machines.join(", ")
+            );
+        }
+    }
+}
+
+/// Run an apply's steps that have not completed, in plan order. Each step
+/// becomes a fleet command tied to the apply. The first step that fails is
+/// recorded as failed and stops the run, leaving it and the rest for
+/// `vc fleet resume`.
+fn run_fleet_apply(store: &Arc<VcStore>, apply_id: &str) -> Result<(), CliError> {
+    let registry = vc_collect::machine::MachineRegistry::new(store.clone());
+    for step in store.fleet_apply_steps(apply_id)? {
+        if step.status == "completed" {
+            continue;
+        }
+        let blocked = match registry.get_machine(&step.machine_id) {
+            Ok(Some(machine)) if !machine.enabled => Some("machine is disabled".to_string()),
+            Ok(Some(machine))
+                if machine.status == vc_collect::machine::MachineStatus::Maintenance =>
+            {
+                Some("machine is in maintenance".to_string())
+            }
+            Ok(Some(_)) => None,
+            Ok(None) => Some("machine is not registered".to_string()),
+            Err(e) => Some(format!("machine lookup failed: {e}")),
+        };
+        if let Some(error) = blocked {
+            store.finish_fleet_apply_step(apply_id, step.step, Err(error.as_str()))?;
+            break;
+        }
+
+        let command_id = format!("fc-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+        let config = step
+            .config_json
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok());
+        let params = serde_json::json!({
+            "agent_type": step.agent_type,
+            "count": step.count,
+            "machine": step.machine_id,
+            "config": config,
+            "apply_id": apply_id,
+            "step": step.step,
+        });
+        store.record_fleet_command(
+            &command_id,
+            &step.action,
+            &params.to_string(),
+            Some("fleet-apply"),
+        )?;
+        // Like `vc fleet spawn`, the request is recorded for ntm to carry out
+        let result = serde_json::json!({
+            "message": format!(
+                "{} request recorded: {} x {} on {}",
+                step.action, step.count, step.agent_type, step.machine_id
+            ),
+        });
+        store.update_fleet_command(&command_id, "completed", Some(&result.to_string()), None)?;
+        store.finish_fleet_apply_step(apply_id, step.step, Ok(&command_id))?;
+    }
+    Ok(())
+}
+
+/// The outcome of each step of an apply that has run: completed steps
+/// succeeded, failed ones failed, and the rest were not reached
+fn fleet_apply_report(
+    store: &VcStore,
+    command: &str,
+    apply_id: &str,
+) -> Result<MultiMachineReport, CliError> {
+    let apply = store
+        .fleet_apply(apply_id)?
+        .ok_or_else(|| CliError::CommandFailed(format!("Unknown apply: {apply_id}")))?;
+    let mut report = MultiMachineReport::new(command).with_context(serde_json::json!({
+        "apply_id": apply_id,
+        "status": apply.status,
+    }));
+    for step in store.fleet_apply_steps(apply_id)? {
+        let what = format!("{} {} x {}", step.action, step.count, step.agent_type);
+        let (outcome, message) = match (step.status.as_str(), &step.command_id, &step.error) {
+            ("completed", Some(command_id), _) => {
+                (TargetOutcome::Success, format!("{what} [{command_id}]"))
+            }
+            ("completed", None, _) => (TargetOutcome::Success, what),
+            ("failed", _, error) => (
+                TargetOutcome::Failed,
+                format!("{what}: {}", error.as_deref().unwrap_or("failed")),
+            ),
+            _ => (
+                TargetOutcome::Skipped,
+                format!("{what}: not run after an earlier step failed"),
+            ),
+        };
+        report.push(step.machine_id, outcome, Some(message), None);
+    }
+    report.finish();
+    Ok(report)
+}
+
+fn print_recorded_fleet_apply(
+    store: &VcStore,
+    apply_id: &str,
+    format: OutputFormat,
+) -> Result<(), CliError> {
+    let apply = store
+        .fleet_apply(apply_id)?
+        .ok_or_else(|| CliError::CommandFailed(format!("Unknown apply: {apply_id}")))?;
+    let steps = store.fleet_apply_steps(apply_id)?;
+    print_fleet_apply(Some(apply_id), &apply.status, &steps, format);
+    Ok(())
+}
 
-                        let health = vc_store::CollectorHealth {
-                            machine_id: machine_id.clone(),
-                            collector: name.to_string(),
-                            collected_at: collected_at_ts,
-                            success,
-                            duration_ms: Some(elapsed),
-                            rows_inserted,
-                            bytes_parsed,
-                            error_class: error_class.clone(),
-                            freshness_seconds: None,
-                            payload_hash: None,
-                            collector_version: None,
-                            schema_version: None,
-                            cursor_json,
-                        };
-                        if let Err(e) = store.insert_collector_health(&health) {
-                            eprintln!(
-                                "warn: collector_health persist failed collector={name} error={e}"
-                            );
-                        }
+fn print_fleet_apply(
+    apply_id: Option<&str>,
+    status: &str,
+    steps: &[vc_store::FleetApplyStep],
+    format: OutputFormat,
+) {
+    if !matches!(format, OutputFormat::Text) {
+        let output = serde_json::json!({
+            "apply_id": apply_id,
+            "status": status,
+            "plan": steps,
+        });
+        print_output(&output, format);
+        return;
+    }
+    println!("{} ({status})", apply_id.unwrap_or("plan"));
+    if steps.is_empty() {
+        println!("  nothing to do: the fleet matches the file");
+    }
+    for step in steps {
+        let outcome = match (&step.command_id, &step.error) {
+            (Some(command_id), _) => format!("  [{command_id}]"),
+            (None, Some(error)) => format!("  [failed: {error}]"),
+            (None, None) => String::new(),
+        };
+        println!(
+            "  {} {} {} on {}{outcome}",
+            step.action, step.count, step.agent_type, step.machine_id
+        );
+    }
+    if let Some(apply_id) = apply_id
+        && status == "planned"
+    {
+        println!("Run `vc fleet resume {apply_id}` to carry it out");
+    }
+}
 
-                        match error_class {
-                            Some(err) => println!(
-                                "{status} machine={machine_id} collector={name} duration_ms={elapsed} error={err}"
-                            ),
-                            None => println!(
-                                "{status} machine={machine_id} collector={name} duration_ms={elapsed}"
-                            ),
-                        }
+/// `vc fleet run`: resolve the targets, leave machines in maintenance out,
+/// ask before running on more than `confirm_above` of them, then run the
+/// command everywhere and record it
+#[allow(clippy::too_many_arguments)]
+async fn run_fleet_command(
+    cx: &Cx,
+    store: &VcStore,
+    command: &str,
+    selector: &vc_collect::fleet_run::MachineSelector,
+    options: &vc_collect::fleet_run::FleetRunOptions,
+    confirm_above: Option<usize>,
+    actor: &vc_store::ActorContext,
+    format: OutputFormat,
+    save_report: bool,
+) -> Result<(), CliError> {
+    let machines = vc_collect::machine::load_machines(store)
+        .map_err(|e| CliError::CommandFailed(format!("Error listing machines: {e}")))?;
+    let mut machines = selector
+        .resolve(machines)
+        .map_err(CliError::CommandFailed)?;
+    if machines.is_empty() {
+        return Err(CliError::CommandFailed(format!(
+            "No enabled machines match {selector}"
+        )));
+    }
+    let requested: Vec<String> = machines.iter().map(|m| m.machine_id.clone()).collect();
+    let mut targets = requested.clone();
+    retain_outside_maintenance(store, &mut targets)?;
+    machines.retain(|m| targets.contains(&m.machine_id));
 
-                        // Stop immediately on cancellation so we don't iterate
-                        // every remaining collector returning the same error.
-                        if cancelled_early {
-                            break 'outer;
-                        }
-                    }
-                }
+    if let Some(limit) = confirm_above
+        && machines.len() > limit
+    {
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::CommandFailed(format!(
+                "{} machines match; re-run with --yes to run on all of them",
+                machines.len()
+            )));
+        }
+        eprint!("Run `{command}` on {} machines? [y/N] ", machines.len());
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| CliError::CommandFailed(format!("Failed to read answer: {e}")))?;
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            println!("Aborted; nothing run");
+            return Ok(());
+        }
+    }
 
-                if cancelled_early {
-                    println!("collected runs={runs} failures={failures} (cancelled)");
-                } else {
-                    println!("collected runs={runs} failures={failures}");
-                }
+    let redaction = vc_collect::redact::RedactionEngine::new();
+    let (command_id, outputs) = vc_collect::fleet_run::run_recorded(
+        cx, store, command, selector, machines, options, &redaction, actor,
+    )
+    .await?;
+
+    let mut report = MultiMachineReport::new("vc fleet run");
+    for skipped in requested.iter().filter(|id| !targets.contains(id)) {
+        report.push(
+            skipped.as_str(),
+            TargetOutcome::Skipped,
+            Some("in maintenance".to_string()),
+            None,
+        );
+    }
+    for output in &outputs {
+        let outcome = if output.skipped {
+            TargetOutcome::Skipped
+        } else if output.succeeded() {
+            TargetOutcome::Success
+        } else {
+            TargetOutcome::Failed
+        };
+        let summary = output.summary();
+        report.push(
+            output.machine_id.as_str(),
+            outcome,
+            (!summary.is_empty()).then_some(summary),
+            (!output.skipped).then(|| Duration::from_millis(output.duration_ms)),
+        );
+    }
+    report = report.with_context(serde_json::json!({
+        "command_id": command_id,
+        "command": command,
+        "machines": selector.to_string(),
+        "outputs": outputs,
+    }));
+    report.finish();
+    if matches!(format, OutputFormat::Text) {
+        print_fleet_run_outputs(&outputs);
+    }
+    emit_multi_report(store, &report, format, save_report, Some(&actor.name))
+}
+
+/// Each machine's captured output, printed ahead of the `vc fleet run` report
+fn print_fleet_run_outputs(outputs: &[vc_collect::fleet_run::MachineRunOutput]) {
+    for output in outputs.iter().filter(|o| !o.skipped) {
+        let status = match (output.exit_code, &output.error) {
+            (_, Some(error)) => error.clone(),
+            (Some(code), None) => format!("exit {code}"),
+            (None, None) => String::new(),
+        };
+        println!("== {} ({status}) ==", output.machine_id);
+        for line in output.stdout.lines() {
+            println!("{line}");
+        }
+        for line in output.stderr.lines() {
+            println!("stderr: {line}");
+        }
+        if output.truncated {
+            println!("[output cut at fleet_run.max_output_bytes]");
+        }
+        println!();
+    }
+}
+
+fn print_fleet_resources(
+    machines: &[vc_query::agents::MachineAgents],
+    agents: &[vc_store::AgentInventoryRecord],
+    stalled_after: ChronoDuration,
+    now: DateTime<Utc>,
+) {
+    for m in machines {
+        let types = m
+            .by_type
+            .iter()
+            .map(|(agent_type, n)| format!("{agent_type}={n}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        println!(
+            "{:<16}  {:>3} active  {:<24}  tmux {:>3}  stalled {:>3}  oldest {}",
+            m.machine_id,
+            m.active,
+            types,
+            m.in_tmux,
+            m.stalled,
+            m.oldest_started_at.as_deref().unwrap_or("-")
+        );
+    }
+    for agent in agents {
+        let location = match (&agent.session_name, &agent.pane) {
+            (Some(session), Some(pane)) => format!("{session}:{pane}"),
+            _ => agent
+                .pid
+                .map_or_else(|| "-".to_string(), |pid| format!("pid {pid}")),
+        };
+        let silent = agent
+            .last_activity_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| now - at.with_timezone(&Utc));
+        let activity = match silent {
+            Some(d) if d >= stalled_after => format!("stalled {}m", d.num_minutes()),
+            Some(d) => format!("active {}m ago", d.num_minutes()),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {:<8}  {:<16}  {:<18}  {}",
+            agent.agent_type,
+            location,
+            activity,
+            agent.working_dir.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+fn print_clock_skew(samples: &[vc_store::ClockSkewSample]) {
+    for sample in samples {
+        let uncertainty = sample.rtt_ms.map_or_else(
+            || "lower bound".to_string(),
+            |rtt| format!("±{}ms", rtt / 2),
+        );
+        println!(
+            "{:<16}  {:>+10}ms  {:<12}  {:<7}  {}",
+            sample.machine_id, sample.skew_ms, uncertainty, sample.source, sample.measured_at
+        );
+    }
+}
+
+fn print_retention_simulations(simulations: &[vc_store::RetentionSimulation]) {
+    for (i, sim) in simulations.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let current = sim.current_retention_days.map_or_else(
+            || "no policy".to_string(),
+            |days| format!("currently {days}d"),
+        );
+        println!(
+            "{}: keep {}d ({current}), {} rows / {} bytes total",
+            sim.table_name, sim.retention_days, sim.rows_total, sim.bytes_total
+        );
+        println!(
+            "  eligible now:        {:>10} rows  {:>14} bytes",
+            sim.rows_eligible_now, sim.bytes_eligible_now
+        );
+        println!(
+            "  within {:>3} days:    {:>10} rows  {:>14} bytes  ({:.0} rows/day inserted)",
+            sim.horizon_days,
+            sim.rows_eligible_horizon,
+            sim.bytes_eligible_horizon,
+            sim.daily_insert_rows
+        );
+        for bucket in &sim.breakdown {
+            println!(
+                "    {:<16} {}  {:>10} rows  {:>14} bytes",
+                bucket.machine_id.as_deref().unwrap_or("-"),
+                bucket.month,
+                bucket.rows,
+                bucket.bytes
+            );
+        }
+        for incident in &sim.open_incidents {
+            println!(
+                "  ! open incident {} ({}, {}) covers {} eligible rows: {}",
+                incident.incident_id,
+                incident.severity,
+                incident.status,
+                incident.rows,
+                incident.title
+            );
+        }
+    }
+}
+
+#[cfg(feature = "guardian")]
+fn print_sandbox_report(sandbox_id: &str, report: &vc_guardian::sandbox::SandboxReport) {
+    use vc_guardian::sandbox::EffectKind;
+
+    println!(
+        "Sandbox run {sandbox_id}: {} on {} as of {} (nothing was executed)",
+        report.playbook_id, report.machine_id, report.at
+    );
+    let met = match report.trigger.met {
+        Some(true) => "met",
+        Some(false) => "not met",
+        None => "unknown",
+    };
+    println!(
+        "Trigger {}: {met} ({})",
+        report.trigger.condition, report.trigger.detail
+    );
+    for (title, steps) in [("Steps", &report.steps), ("Rollback", &report.rollback)] {
+        if steps.is_empty() {
+            continue;
+        }
+        println!("{title}:");
+        for step in steps {
+            println!("  {}. [{}] {}", step.index, step.step_type, step.condition);
+            if let Some(line) = &step.command_line {
+                println!("     $ {line}");
             }
-            command => {
-                println!("Command not yet implemented: {:?}", command);
+            let marker = match step.effect.kind {
+                EffectKind::NoChange => "no change",
+                EffectKind::Predicted => "predicted",
+                EffectKind::Unknown => "unknown effect",
+            };
+            println!("     {marker}: {}", step.effect.summary);
+            for target in &step.effect.targets {
+                println!("       - {target}");
             }
         }
-        Ok(())
     }
 }
 
+/// The rule to simulate: a definition file, or a built-in by id or name
+fn resolve_alert_rule(
+    rule: Option<&str>,
+    file: Option<&std::path::Path>,
+) -> Result<vc_alert::AlertRule, CliError> {
+    if let Some(path) = file {
+        let raw = std::fs::read_to_string(path).map_err(|e| {
+            CliError::CommandFailed(format!("Failed to read {}: {e}", path.display()))
+        })?;
+        return serde_json::from_str(&raw).map_err(|e| {
+            CliError::CommandFailed(format!("Invalid rule in {}: {e}", path.display()))
+        });
+    }
+
+    let wanted = rule.unwrap_or_default();
+    let engine = vc_alert::AlertEngine::new();
+    engine
+        .rules()
+        .iter()
+        .find(|r| r.rule_id == wanted || r.name.eq_ignore_ascii_case(wanted))
+        .cloned()
+        .ok_or_else(|| {
+            let known: Vec<&str> = engine.rules().iter().map(|r| r.rule_id.as_str()).collect();
+            CliError::CommandFailed(format!(
+                "unknown alert rule '{wanted}'. Known: {}",
+                known.join(", ")
+            ))
+        })
+}
+
+/// A `poll_schedule_decisions` row with its recorded reasoning unpacked.
+/// Rows logged before reasoning was recorded have only a rule.
+fn explain_poll_decision(row: &serde_json::Value) -> serde_json::Value {
+    let reasoning: serde_json::Value = row["reason_json"]
+        .as_str()
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default();
+    let inputs: vc_collect::scheduler::DecisionInputs =
+        serde_json::from_value(reasoning["inputs"].clone()).unwrap_or_default();
+    let explanation = reasoning["explanation"]
+        .as_str()
+        .filter(|text| !text.is_empty())
+        .map_or_else(
+            || format!("{}s: no reasoning recorded", row["next_interval_seconds"]),
+            str::to_string,
+        );
+    serde_json::json!({
+        "decided_at": row["decided_at"],
+        "machine_id": row["machine_id"],
+        "collector": row["collector"],
+        "rule": reasoning["reason"].as_str().unwrap_or("unknown"),
+        "previous_interval_secs": reasoning["previous_interval_secs"],
+        "interval_secs": row["next_interval_seconds"],
+        "inputs": reasoning["inputs"],
+        "inputs_summary": if reasoning["inputs"].is_object() {
+            inputs.summary()
+        } else {
+            Vec::new()
+        },
+        "explanation": explanation,
+    })
+}
+
+/// Text rendering of one decision for `vc profile decisions --explain`
+fn print_poll_decision(row: &serde_json::Value) {
+    let decision = explain_poll_decision(row);
+    println!(
+        "{}  {}/{}  [{}]",
+        decision["decided_at"].as_str().unwrap_or("-"),
+        decision["machine_id"].as_str().unwrap_or("-"),
+        decision["collector"].as_str().unwrap_or("-"),
+        decision["rule"].as_str().unwrap_or("-"),
+    );
+    println!("    {}", decision["explanation"].as_str().unwrap_or("-"));
+    if let Some(inputs) = decision["inputs_summary"].as_array()
+        && !inputs.is_empty()
+    {
+        let inputs: Vec<&str> = inputs
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .collect();
+        println!("    inputs: {}", inputs.join(", "));
+    }
+}
+
+/// `[[alerts.overrides]]` with the configured tags of every machine
+fn rule_overrides(config: &VcConfig) -> vc_alert::overrides::RuleOverrides {
+    let tags = config
+        .machines
+        .iter()
+        .map(|(id, machine)| (id.clone(), machine.tags.clone()))
+        .collect();
+    vc_alert::overrides::RuleOverrides::new(config.alerts.overrides.clone(), tags)
+}
+
+fn severity_label(severity: vc_alert::Severity) -> &'static str {
+    match severity {
+        vc_alert::Severity::Info => "info",
+        vc_alert::Severity::Warning => "warning",
+        vc_alert::Severity::Critical => "critical",
+    }
+}
+
+/// One-line summary of a rule condition, e.g. `value >= 90`
+fn describe_condition(condition: &vc_alert::AlertCondition) -> String {
+    use vc_alert::{AlertCondition, ThresholdOp};
+
+    match condition {
+        AlertCondition::Threshold {
+            operator, value, ..
+        } => {
+            let op = match operator {
+                ThresholdOp::Gt => ">",
+                ThresholdOp::Gte => ">=",
+                ThresholdOp::Lt => "<",
+                ThresholdOp::Lte => "<=",
+                ThresholdOp::Eq => "==",
+            };
+            format!("value {op} {value}")
+        }
+        AlertCondition::Pattern {
+            table,
+            column,
+            regex,
+        } => format!("{table}.{column} ~ /{regex}/"),
+        AlertCondition::Absence {
+            table,
+            max_age_secs,
+        } => format!("no {table} rows for {max_age_secs}s"),
+        AlertCondition::RateOfChange {
+            window_secs,
+            threshold_per_sec,
+            ..
+        } => format!("rate > {threshold_per_sec}/s over {window_secs}s"),
+    }
+}
+
+/// Text rendering of `vc alert rules --machine`
+fn print_resolved_rules(
+    machine: &str,
+    known: bool,
+    tags: &[String],
+    rules: &[vc_alert::overrides::ResolvedRule],
+) {
+    println!(
+        "Rules for {machine} (tags: {})",
+        if tags.is_empty() {
+            "none".to_string()
+        } else {
+            tags.join(", ")
+        }
+    );
+    if !known {
+        println!("warning: {machine} is not in [machines]; only machine overrides apply");
+    }
+    println!("Precedence: {}", vc_alert::overrides::PRECEDENCE);
+    println!();
+    println!(
+        "{:<24}  {:<8}  {:<7}  {:<40}  OVERRIDDEN BY",
+        "RULE", "SEVERITY", "ENABLED", "CONDITION"
+    );
+    for resolved in rules {
+        let applied: Vec<String> = resolved
+            .overrides
+            .iter()
+            .map(|applied| format!("{} ({})", applied.field, applied.scope))
+            .collect();
+        println!(
+            "{:<24}  {:<8}  {:<7}  {:<40}  {}",
+            resolved.rule.rule_id,
+            severity_label(resolved.rule.severity),
+            resolved.rule.enabled,
+            describe_condition(&resolved.rule.condition),
+            if applied.is_empty() {
+                "-".to_string()
+            } else {
+                applied.join(", ")
+            },
+        );
+        for ignored in &resolved.ignored {
+            println!("  ignored: {ignored}");
+        }
+    }
+}
+
+/// Text rendering of a simulation: summary, then one timeline row per alert
+fn print_simulation_timeline(report: &vc_alert::evaluate::SimulationReport) {
+    use vc_query::timefmt::duration_ms;
+
+    let fmt = time_format();
+    println!(
+        "Rule {} ({}) over {} → {}, every {}",
+        report.rule_id,
+        report.rule_name,
+        fmt.absolute(report.window_start),
+        fmt.absolute(report.window_end),
+        duration_ms(millis(report.step_secs)),
+    );
+    println!(
+        "{} evaluations, {} breaching, {} without data, {} failed",
+        report.evaluations, report.breaches, report.no_data, report.failures
+    );
+    if !report.overrides.is_empty() {
+        println!("Overrides applied: {}", report.overrides.join(", "));
+    }
+    for warning in &report.warnings {
+        println!("warning: {warning}");
+    }
+    println!();
+
+    if report.episodes.is_empty() {
+        println!("Would not have fired.");
+        return;
+    }
+
+    println!(
+        "{:<24}  {:<24}  {:>10}  {:<16}  {:>10}  {:>10}  {:<8}",
+        "FIRED", "LAST BREACH", "DURATION", "MACHINE", "PEAK", "THRESHOLD", "SEVERITY"
+    );
+    for episode in &report.episodes {
+        println!(
+            "{:<24}  {:<24}  {:>10}  {:<16}  {:>10.1}  {:>10.1}  {:<8}",
+            fmt.absolute(episode.started_at),
+            fmt.absolute(episode.ended_at),
+            duration_ms(millis(episode.duration_secs.unsigned_abs())),
+            episode.machine_id.as_deref().unwrap_or("-"),
+            episode.peak_actual,
+            episode.threshold,
+            severity_label(episode.severity),
+        );
+    }
+    println!();
+    println!(
+        "{} alert(s) after dedup, firing for {} in total",
+        report.alert_count,
+        duration_ms(millis(report.firing_secs.unsigned_abs())),
+    );
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn millis(secs: u64) -> f64 {
+    secs as f64 * 1000.0
+}
+
 const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 30;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -3860,6 +9817,36 @@ async fn wait_for_interval_or_shutdown(tick: Duration, shutdown: &mut ShutdownRe
     )
 }
 
+/// Classified cause of a failed collector run, from the typed error when
+/// there is one and the reported message otherwise. Successful and
+/// cancelled runs have none.
+fn collector_error_cause(
+    outcome: &vc_collect::CollectOutcome,
+    message: Option<&str>,
+) -> Option<vc_collect::ErrorCause> {
+    match outcome {
+        asupersync::Outcome::Err(err) => Some(vc_collect::ErrorCause::of(err)),
+        asupersync::Outcome::Cancelled(_) => None,
+        asupersync::Outcome::Panicked(_) => Some(vc_collect::ErrorCause::Unknown),
+        asupersync::Outcome::Ok(_) => message.map(vc_collect::ErrorCause::classify),
+    }
+}
+
+/// Version of the tool a collector drives on a machine, as last probed, so
+/// each `collector_health` row records which binary produced its data.
+fn collector_version(
+    store: &VcStore,
+    machine_id: &str,
+    collector: &dyn vc_collect::Collector,
+) -> Option<String> {
+    let tool = collector.required_tool()?;
+    store
+        .latest_tool_version(machine_id, tool)
+        .ok()
+        .flatten()
+        .map(|version| version.tool_version)
+}
+
 /// Run one tick of collection: invoke every enabled collector against every
 /// enabled local machine and persist a `collector_health` row for each result.
 ///
@@ -3867,14 +9854,74 @@ async fn wait_for_interval_or_shutdown(tick: Duration, shutdown: &mut ShutdownRe
 /// do not abort the tick — the daemon keeps running so other collectors get
 /// a chance to report on every machine.
 #[allow(clippy::too_many_lines)]
+/// One reminder per machine that has been in maintenance for longer than
+/// `max_hours` (never when it is 0): a machine left there is silently out
+/// of monitoring.
+fn overdue_maintenance(store: &VcStore, max_hours: u64) -> Result<Vec<String>, CliError> {
+    if max_hours == 0 {
+        return Ok(Vec::new());
+    }
+    let now = Utc::now();
+    let max = i64::try_from(max_hours)
+        .ok()
+        .and_then(ChronoDuration::try_hours)
+        .unwrap_or(ChronoDuration::MAX);
+    Ok(store
+        .active_maintenance()?
+        .into_iter()
+        .filter(|window| window.age(now) > max)
+        .map(|window| {
+            format!(
+                "{} has been in maintenance for {}h (max {max_hours}h, reason: {}) - \
+                 `vc machines maintenance {} --off` when it is back",
+                window.machine_id,
+                window.age(now).num_hours(),
+                window.reason.as_deref().unwrap_or("none given"),
+                window.machine_id
+            )
+        })
+        .collect())
+}
+
+/// End maintenance windows that have run out, then drop machines still in
+/// maintenance from `targets`: nothing connects to them, so they raise no
+/// offline alerts and leave no failed-collector rows.
+fn retain_outside_maintenance(store: &VcStore, targets: &mut Vec<String>) -> Result<(), CliError> {
+    for machine_id in store.expire_maintenance(Utc::now())? {
+        tracing::info!(machine = %machine_id, "maintenance window expired");
+    }
+    let in_maintenance: Vec<String> = store
+        .active_maintenance()?
+        .into_iter()
+        .map(|window| window.machine_id)
+        .collect();
+    targets.retain(|machine_id| {
+        let skip = in_maintenance.contains(machine_id);
+        if skip {
+            tracing::debug!(machine = %machine_id, "in maintenance; not collecting");
+        }
+        !skip
+    });
+    Ok(())
+}
+
 async fn run_collection_tick(
     config: &VcConfig,
     registry: &vc_collect::CollectorRegistry,
     store: &VcStore,
+    guard: &daemon_limits::ResourceGuard,
+    audit: &mut vc_store::AuditWriter,
     cx: &Cx,
 ) -> Result<(usize, usize), CliError> {
     use vc_collect::CollectContext;
 
+    // Low disk pauses every write but alerts, so there is nothing to collect
+    // into and nothing new to score.
+    if guard.writes_paused() {
+        tracing::warn!("store volume low on space; skipping collection tick");
+        return Ok((0, 0));
+    }
+
     // Resolve the set of machines to collect against. If the user hasn't
     // configured any machines, fall back to a single "local" entry so the
     // daemon still produces health rows on a fresh DB.
@@ -3886,8 +9933,10 @@ async fn run_collection_tick(
     if targets.is_empty() {
         targets.push("local".to_string());
     }
+    retain_outside_maintenance(store, &mut targets)?;
 
     let timeout = config.collector_timeout();
+    let redaction = vc_collect::redact::RedactionEngine::new();
     let mut runs: usize = 0;
     let mut failures: usize = 0;
 
@@ -3905,10 +9954,14 @@ async fn run_collection_tick(
             if !config.is_collector_enabled(machine_id, name) {
                 continue;
             }
+            if guard.skip_collector(name) {
+                tracing::debug!(machine = %machine_id, collector = %name, "paused under memory pressure");
+                continue;
+            }
 
             let started = Instant::now();
             tracing::debug!(machine = %machine_id, collector = %name, "collecting");
-            let outcome = collector.collect(cx, &ctx).await;
+            let outcome = collector.collect_on_platform(cx, &ctx).await;
             let elapsed = i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX);
             runs += 1;
 
@@ -3920,12 +9973,14 @@ async fn run_collection_tick(
                     // errors are logged but don't fail the tick — the most
                     // important signal is that we actually ran the collector.
                     // Only count rows the store confirms it persisted (via
-                    // the `usize` returned by `insert_json_batch`); a failed
+                    // the `usize` returned by `write_collected_batch`); a failed
                     // batch must not inflate `rows_inserted`.
                     let mut total_rows: i64 = 0;
                     let mut total_bytes: i64 = 0;
                     for batch in &result.rows {
-                        match store.insert_json_batch(&batch.table, &batch.rows) {
+                        let limit = store.row_size_limit(&batch.table).ok().flatten();
+                        let rows = redaction.truncate_rows(&batch.rows, limit.as_ref());
+                        match store.write_collected_batch(machine_id, name, &batch.table, &rows) {
                             Ok(count) => {
                                 total_rows = total_rows
                                     .saturating_add(i64::try_from(count).unwrap_or(i64::MAX));
@@ -4006,11 +10061,13 @@ async fn run_collection_tick(
                 duration_ms: Some(elapsed),
                 rows_inserted,
                 bytes_parsed,
+                error_cause: collector_error_cause(&outcome, error_class.as_deref())
+                    .map(|cause| cause.as_str().to_string()),
                 error_class,
                 freshness_seconds: None,
                 payload_hash: None,
-                collector_version: None,
-                schema_version: None,
+                collector_version: collector_version(store, machine_id, collector.as_ref()),
+                schema_version: Some(collector.schema_version().to_string()),
                 cursor_json,
             };
 
@@ -4025,6 +10082,30 @@ async fn run_collection_tick(
                 );
             }
 
+            let audit_result = if was_cancelled {
+                vc_store::AuditResult::Skipped
+            } else if health.success {
+                vc_store::AuditResult::Success
+            } else {
+                vc_store::AuditResult::Failure
+            };
+            let event = vc_store::AuditEvent::new(
+                vc_store::AuditEventType::CollectorRun,
+                "daemon",
+                name.to_string(),
+                audit_result,
+                serde_json::json!({
+                    "duration_ms": health.duration_ms,
+                    "rows_inserted": health.rows_inserted,
+                    "error": health.error_class,
+                    "cause": health.error_cause,
+                }),
+            )
+            .with_machine_id(machine_id.clone());
+            if let Err(e) = audit.record(store, event, vc_store::AuditDurability::BestEffort) {
+                tracing::warn!(error = %e, "audit batch write failed");
+            }
+
             // If the collector returned `Outcome::Cancelled` we know the cx
             // is in a cancelled state — skip straight to returning instead of
             // iterating the rest of the registry just to have every remaining
@@ -4034,6 +10115,31 @@ async fn run_collection_tick(
                 return Ok((runs, failures));
             }
         }
+
+        let tags = config
+            .machines
+            .get(machine_id)
+            .map(|machine| machine.tags.clone())
+            .unwrap_or_default();
+        let specs: Vec<_> = config
+            .service_checks_for(&tags)
+            .into_iter()
+            .filter_map(|(service, required)| {
+                vc_collect::ServiceSpec::from_config(service, required)
+            })
+            .collect();
+        if !specs.is_empty() {
+            let services = vc_collect::ServiceProber::new()
+                .check_services(cx, machine_id, &ctx.executor, &specs)
+                .await;
+            if let Err(e) = store.replace_machine_services(machine_id, &services) {
+                tracing::warn!(machine = %machine_id, error = %e, "service status persist failed");
+            }
+        }
+    }
+
+    if let Err(e) = audit.flush(store, Utc::now()) {
+        tracing::warn!(error = %e, "audit batch write failed");
     }
 
     // Collection only fills the raw telemetry tables. Scoring and alerting are
@@ -4041,16 +10147,43 @@ async fn run_collection_tick(
     // the same tick — otherwise `health_summary` stays empty forever and every
     // downstream surface (fleet overview, TUI, `vc robot health`) reports
     // nothing while the underlying data is sitting right there.
-    score_and_alert(store, cx)?;
+    score_and_alert(store, config, cx)?;
+    #[cfg(feature = "knowledge")]
+    classify_finished_sessions(store, config);
 
     Ok((runs, failures))
 }
 
+/// Sessions classified per daemon tick; the rest wait for the next one
+#[cfg(feature = "knowledge")]
+const SESSIONS_CLASSIFIED_PER_TICK: usize = 200;
+
+/// Classify agent sessions that have ended since the last tick. Failures are
+/// logged: `vc sessions classify` catches up on whatever is missed.
+#[cfg(feature = "knowledge")]
+fn classify_finished_sessions(store: &VcStore, config: &VcConfig) {
+    match vc_knowledge::outcome::classify_sessions(
+        store,
+        &config.knowledge.outcomes,
+        false,
+        SESSIONS_CLASSIFIED_PER_TICK,
+    ) {
+        Ok(report) if report.classified > 0 => {
+            tracing::info!(
+                classified = report.classified,
+                "session outcomes classified"
+            );
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "session outcome classification failed"),
+    }
+}
+
 /// Score the freshly collected telemetry and raise alerts from it.
 ///
 /// Failures here are logged rather than propagated: a bad scoring pass must not
 /// discard a tick's worth of successfully collected data.
-fn score_and_alert(store: &VcStore, cx: &Cx) -> Result<(), CliError> {
+fn score_and_alert(store: &VcStore, config: &VcConfig, cx: &Cx) -> Result<(), CliError> {
     if cx.checkpoint().is_err() {
         return Ok(());
     }
@@ -4066,11 +10199,21 @@ fn score_and_alert(store: &VcStore, cx: &Cx) -> Result<(), CliError> {
     };
     tracing::debug!(machines = scores.len(), "health scores persisted");
 
+    // The `disk-forecast-full` rule reads the forecasts kept here
+    let options = vc_query::DiskForecastOptions::from_config(&config.forecast);
+    match query
+        .disk_forecast(&options)
+        .and_then(|forecast| vc_query::forecast::record_forecasts(store, &forecast))
+    {
+        Ok(mounts) => tracing::debug!(mounts, "disk forecasts recorded"),
+        Err(e) => tracing::warn!(error = %e, "disk forecasting failed for this tick"),
+    }
+
     if cx.checkpoint().is_err() {
         return Ok(());
     }
 
-    match evaluate_alert_rules(store, &scores) {
+    match evaluate_alert_rules(store, config, &scores) {
         Ok(raised) if raised > 0 => tracing::info!(raised, "alerts raised"),
         Ok(_) => {}
         Err(e) => tracing::warn!(error = %e, "alert evaluation failed for this tick"),
@@ -4081,108 +10224,148 @@ fn score_and_alert(store: &VcStore, cx: &Cx) -> Result<(), CliError> {
 
 /// Evaluate the built-in alert rules against the store and record what fires.
 ///
-/// Only `Threshold` rules are evaluated: they carry a SQL query that can be run
-/// directly. `Pattern`, `Absence` and `RateOfChange` are skipped and counted,
-/// because honouring them properly needs per-condition query construction that
-/// does not exist yet — raising nothing is correct; inventing a result is not.
-///
-/// A rule with an already-open (unresolved) alert does not re-fire, so a
-/// persistently unhealthy machine produces one alert rather than one per tick.
+/// Goes through the same [`vc_alert::evaluate::RuleEvaluator`] that
+/// `vc alert rules test` replays history with, as of now. Only `Threshold`
+/// rules are evaluated; a rule with an already-open (unresolved) alert does
+/// not re-fire, so a persistently unhealthy machine produces one alert rather
+/// than one per tick. Breaches are judged by the rule as overridden for their
+/// machine under `[[alerts.overrides]]`.
 fn evaluate_alert_rules(
     store: &VcStore,
-    scores: &[vc_query::HealthScore],
-) -> Result<usize, CliError> {
-    use vc_alert::{AlertCondition, AlertEngine};
-
-    let engine = AlertEngine::new();
-    let mut raised = 0_usize;
-    let mut skipped = 0_usize;
-
-    for rule in engine.rules() {
-        if !rule.enabled {
-            continue;
-        }
-
-        let AlertCondition::Threshold {
-            query,
-            operator,
-            value,
-        } = &rule.condition
-        else {
-            skipped += 1;
-            continue;
-        };
-
-        let rows = match store.query_json(query) {
-            Ok(rows) => rows,
-            Err(e) => {
-                tracing::warn!(rule = %rule.rule_id, error = %e, "alert rule query failed");
-                continue;
-            }
-        };
-
-        // A threshold query yields a single scalar. No rows, or a NULL, means
-        // there is simply no telemetry to judge — not a breach.
-        let Some(actual) = rows
-            .first()
-            .and_then(|row| row.as_object())
-            .and_then(|obj| obj.values().next())
-            .and_then(serde_json::Value::as_f64)
-        else {
-            continue;
-        };
-
-        if !operator.check(actual, *value) {
-            continue;
-        }
+    config: &VcConfig,
+    scores: &[vc_query::HealthScore],
+) -> Result<usize, CliError> {
+    use vc_alert::AlertEngine;
+    use vc_alert::evaluate::{RuleEvaluator, StoreSink};
 
-        if store.has_open_alert(&rule.rule_id, None)? {
-            continue;
-        }
+    let engine = AlertEngine::new();
+    let rules: Vec<_> = engine
+        .rules()
+        .iter()
+        .filter(|rule| rule.enabled)
+        .cloned()
+        .collect();
+    let now = Utc::now();
+    let overrides = rule_overrides(config);
+
+    let raised = RuleEvaluator::new(store)
+        .with_overrides(&overrides)
+        .run(
+            &rules,
+            now,
+            now,
+            Duration::from_secs(60),
+            &mut StoreSink::new(store),
+        )
+        .map_err(|e| CliError::CommandFailed(format!("Alert evaluation failed: {e}")))?;
+    let _ = scores;
 
-        let context = serde_json::json!({
-            "actual": actual,
-            "threshold": value,
-            "query": query,
-        });
+    Ok(raised)
+}
 
-        store.insert_alert(&vc_store::FiredAlert {
-            rule_id: rule.rule_id.clone(),
-            fired_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
-            severity: format!("{:?}", rule.severity).to_lowercase(),
-            title: rule.name.clone(),
-            message: format!(
-                "{} is {actual:.1}, which breaches the threshold of {value:.1}",
-                rule.name
-            ),
-            context_json: Some(context.to_string()),
-            machine_id: None,
-        })?;
-        raised += 1;
+/// Parsed `account_status` rows from the last two hours, for autopilot
+#[cfg(feature = "guardian")]
+fn load_account_samples(
+    store: &VcStore,
+) -> Result<Vec<vc_guardian::autopilot::AccountSample>, CliError> {
+    if !store.has_table("account_status")? {
+        return Ok(Vec::new());
     }
+    let rows = store
+        .query_json(
+            "SELECT COALESCE(provider, 'unknown') AS provider, account_id, used_pct, \
+                    is_active, CAST(collected_at AS TEXT) AS collected_at \
+             FROM account_status \
+             WHERE parse_error IS NULL AND account_id IS NOT NULL AND used_pct IS NOT NULL \
+               AND CAST(collected_at AS TIMESTAMP) > current_timestamp - INTERVAL '2 hours'",
+        )
+        .map_err(|e| CliError::CommandFailed(format!("Error reading account status: {e}")))?;
 
-    if skipped > 0 {
-        tracing::debug!(
-            skipped,
-            "non-threshold alert conditions are not evaluated yet"
-        );
-    }
-    let _ = scores;
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(vc_guardian::autopilot::AccountSample {
+                provider: row["provider"].as_str()?.to_string(),
+                account_id: row["account_id"].as_str()?.to_string(),
+                used_pct: row["used_pct"].as_f64()?,
+                is_active: row["is_active"]
+                    .as_bool()
+                    .or_else(|| row["is_active"].as_i64().map(|v| v != 0))
+                    .unwrap_or(false),
+                collected_at: DateTime::parse_from_rfc3339(row["collected_at"].as_str()?)
+                    .ok()?
+                    .with_timezone(&Utc),
+            })
+        })
+        .collect())
+}
 
-    Ok(raised)
+/// Drift events as compact JSON lines, oldest first, with a fixed key set so
+/// two runs diff line-by-line. Legacy rows keep their nulls.
+fn drift_json_lines(events: &[serde_json::Value]) -> Vec<String> {
+    events
+        .iter()
+        .rev()
+        .map(|event| {
+            serde_json::json!({
+                "id": event["id"],
+                "machine_id": event["machine_id"],
+                "detected_at": event["detected_at"],
+                "field": event["metric"],
+                "previous_value": event["previous_value"],
+                "new_value": event["new_value"],
+                "delta": event["delta"],
+                "severity": event["severity"],
+                "detection_method": event["detection_method"],
+                "baseline_ref": event["baseline_ref"],
+                "description": event["description"],
+            })
+            .to_string()
+        })
+        .collect()
 }
 
 async fn run_daemon(
-    config_path: Option<&PathBuf>,
+    source: ConfigSource<'_>,
     foreground: bool,
+    takeover: bool,
     cx: &Cx,
     mut shutdown: ShutdownReceiver,
 ) -> Result<(), CliError> {
-    let config = load_config(config_path)?;
-    let store = VcStore::open(&config.global.db_path)?;
-    let registry = vc_collect::CollectorRegistry::with_builtins();
+    let config = load_config(source)?;
     let tick = config.poll_interval();
+    let holder_id = daemon_lease_holder();
+    let store = acquire_daemon_lease(&config, &holder_id, tick, takeover)?;
+    record_config_snapshot(&store, &config, source);
+    let registry = vc_collect::CollectorRegistry::from_config(&config);
     let mut ticks = 0_u64;
+    let mut guard = daemon_limits::ResourceGuard::new(config.daemon.limits.clone());
+    let mut alerts = alert_delivery::AlertDispatcher::new(&config.alerts, &store)?;
+    let mut staleness = staleness::StalenessMonitor::new(config.alerts.staleness.clone());
+    let mut audit = vc_store::AuditWriter::new(&config.audit);
+    #[cfg(feature = "telemetry")]
+    let telemetry = telemetry::TelemetryExporter::from_config(&config.telemetry);
+    #[cfg(not(feature = "telemetry"))]
+    if config.telemetry.enabled {
+        tracing::warn!(
+            "telemetry export is enabled but vc was built without the telemetry feature"
+        );
+    }
+    let mut replication = replication::ReplicationShipper::from_config(&config.replication);
+    let mut last_collection = Instant::now();
+    #[cfg(unix)]
+    let watch_server = config
+        .daemon
+        .watch_socket
+        .as_deref()
+        .map(|path| watch_socket::WatchBroadcaster::bind(path, config.daemon.watch_queue_size))
+        .transpose()?;
+    #[cfg(unix)]
+    let mut watch_since = Utc::now();
+    #[cfg(not(unix))]
+    if config.daemon.watch_socket.is_some() {
+        tracing::warn!("daemon.watch_socket needs a Unix platform; not serving watch events");
+    }
 
     if !foreground {
         tracing::warn!("Background daemonization is not implemented yet; running in foreground");
@@ -4198,21 +10381,75 @@ async fn run_daemon(
     // Run an initial tick before the first sleep so the DB has fresh data
     // immediately after `vc daemon` starts (rather than after the first
     // poll_interval has elapsed).
-    if cx.checkpoint().is_ok() {
-        match run_collection_tick(&config, &registry, &store, cx).await {
+    if cx.checkpoint().is_ok() && !daemon_on_standby(&config, &store) {
+        #[cfg(unix)]
+        guard.set_watch_subscribers(
+            watch_server
+                .as_ref()
+                .map(watch_socket::WatchBroadcaster::subscriber_count),
+        );
+        let transition =
+            guard.evaluate(daemon_limits::ResourceSample::read(&config.global.db_path));
+        daemon_limits::record_transition(&store, &guard, transition);
+        match run_collection_tick(&config, &registry, &store, &guard, &mut audit, cx).await {
             Ok((runs, failures)) => {
                 tracing::info!(ticks, runs, failures, "collection tick complete");
             }
             Err(e) => tracing::warn!(error = %e, "collection tick failed"),
         }
+        if config.alerts.enabled {
+            staleness.check(&store);
+        }
+        alerts.dispatch(&store, cx).await;
+        if config.alerts.enabled {
+            alert_delivery::correlate(&store, &config.alerts.correlation);
+            alerts.escalate(&store, &config.alerts.escalation, cx).await;
+        }
+        alerts.sla_breaches(&store, &config.incidents, cx).await;
+        #[cfg(unix)]
+        if let Some(server) = &watch_server {
+            publish_watch_tick(server, &store, &mut watch_since);
+        }
+        #[cfg(feature = "telemetry")]
+        if let Some(exporter) = &telemetry {
+            exporter.export(&store).await;
+        }
+        if let Some(shipper) = &mut replication {
+            shipper.run_if_due(&store).await;
+        }
     }
 
+    let mut lease_lost = false;
     loop {
         if cx.checkpoint().is_err() {
             break;
         }
 
-        if wait_for_interval_or_shutdown(tick, &mut shutdown).await {
+        // Replication ships on its own, usually shorter, interval
+        let collect_every = guard.poll_interval(tick);
+        let wait = replication.as_ref().map_or(collect_every, |shipper| {
+            shipper.interval().min(collect_every)
+        });
+
+        // Heartbeat before sleeping, for long enough to cover the sleep and
+        // the tick after it
+        match store.renew_lease(
+            vc_store::DAEMON_LEASE,
+            &holder_id,
+            daemon_lease_ttl(&config, wait),
+        ) {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::error!(
+                    holder = %holder_id,
+                    "another daemon took over the store lease; stopping the poll loop"
+                );
+                lease_lost = true;
+                break;
+            }
+            Err(e) => tracing::warn!(error = %e, "could not heartbeat the daemon lease"),
+        }
+        if wait_for_interval_or_shutdown(wait, &mut shutdown).await {
             tracing::info!(ticks, "Daemon shutdown requested");
             break;
         }
@@ -4221,14 +10458,66 @@ async fn run_daemon(
             break;
         }
 
+        if daemon_on_standby(&config, &store) {
+            continue;
+        }
+
+        if last_collection.elapsed() < collect_every {
+            if let Some(shipper) = &mut replication {
+                shipper.run_if_due(&store).await;
+            }
+            continue;
+        }
+        last_collection = Instant::now();
         ticks += 1;
 
-        match run_collection_tick(&config, &registry, &store, cx).await {
+        #[cfg(unix)]
+        guard.set_watch_subscribers(
+            watch_server
+                .as_ref()
+                .map(watch_socket::WatchBroadcaster::subscriber_count),
+        );
+        let transition =
+            guard.evaluate(daemon_limits::ResourceSample::read(&config.global.db_path));
+        daemon_limits::record_transition(&store, &guard, transition);
+        match run_collection_tick(&config, &registry, &store, &guard, &mut audit, cx).await {
             Ok((runs, failures)) => {
                 tracing::info!(ticks, runs, failures, "collection tick complete");
             }
             Err(e) => tracing::warn!(ticks, error = %e, "collection tick failed"),
         }
+        if config.alerts.enabled {
+            staleness.check(&store);
+        }
+        alerts.dispatch(&store, cx).await;
+        if config.alerts.enabled {
+            alert_delivery::correlate(&store, &config.alerts.correlation);
+            alerts.escalate(&store, &config.alerts.escalation, cx).await;
+        }
+        alerts.sla_breaches(&store, &config.incidents, cx).await;
+        #[cfg(unix)]
+        if let Some(server) = &watch_server {
+            publish_watch_tick(server, &store, &mut watch_since);
+        }
+        #[cfg(feature = "telemetry")]
+        if let Some(exporter) = &telemetry {
+            exporter.export(&store).await;
+        }
+        if let Some(shipper) = &mut replication {
+            shipper.run_if_due(&store).await;
+        }
+        analyze_due_tables(&config, &store, &guard);
+        offload_old_transcripts(&config, &store, &guard);
+    }
+
+    // Queued digests must go out rather than die with the process
+    alerts.shutdown(&store, cx).await;
+    if let Err(e) = audit.shutdown(&store) {
+        tracing::warn!(error = %e, "final audit batch write failed");
+    }
+
+    if let Err(e) = store.release_lease(vc_store::DAEMON_LEASE, &holder_id) {
+        tracing::warn!(error = %e, "could not release the daemon lease");
     }
 
     tracing::info!(
@@ -4237,9 +10526,140 @@ async fn run_daemon(
         drained_children = 1_u32,
         "Daemon drained"
     );
+    if lease_lost {
+        return Err(CliError::CommandFailed(
+            "another daemon took over the store lease".to_string(),
+        ));
+    }
     Ok(())
 }
 
+/// Identifies this daemon process in the store lease
+fn daemon_lease_holder() -> String {
+    let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+    format!(
+        "{hostname}:{}:{}",
+        std::process::id(),
+        &uuid::Uuid::new_v4().to_string()[..8]
+    )
+}
+
+/// How long one heartbeat keeps the lease: the configured TTL, stretched so
+/// a long sleep between cycles cannot let it lapse
+fn daemon_lease_ttl(config: &VcConfig, wait: Duration) -> Duration {
+    Duration::from_secs(config.daemon.lease_ttl_secs).max(wait * 2)
+}
+
+/// Open the store and take the daemon lease, refusing to start while another
+/// daemon holds a live one
+fn acquire_daemon_lease(
+    config: &VcConfig,
+    holder_id: &str,
+    tick: Duration,
+    takeover: bool,
+) -> Result<VcStore, CliError> {
+    let store = open_duckdb_store(config)?
+        .with_query_log(&config.query_log, vc_store::QueryCaller::Daemon)
+        .with_write_limits(&config.write_limits)
+        .with_artifacts(&config.storage.artifacts)
+        .with_lease(vc_store::DAEMON_LEASE, holder_id);
+    let hostname = std::env::var("HOSTNAME").ok();
+    match store.acquire_lease(
+        vc_store::DAEMON_LEASE,
+        holder_id,
+        hostname.as_deref(),
+        daemon_lease_ttl(config, tick),
+        takeover,
+    )? {
+        vc_store::LeaseOutcome::Acquired(lease) => {
+            tracing::info!(holder = %lease.holder_id, takeover, "acquired the daemon lease");
+            Ok(store)
+        }
+        vc_store::LeaseOutcome::Held(lease) => Err(CliError::CommandFailed(format!(
+            "another daemon holds this store: {} on {}, last heartbeat {}, lease expires {}; \
+             stop it, or pass --takeover if it is gone",
+            lease.holder_id,
+            lease.hostname.as_deref().unwrap_or("an unknown host"),
+            lease.heartbeat_at,
+            lease.expires_at
+        ))),
+    }
+}
+
+/// `ANALYZE` the tables that changed most since their last one, when the
+/// quiet window is open and the daemon is neither shedding load nor short on
+/// disk. Each table holds the store only for its own statement.
+fn analyze_due_tables(config: &VcConfig, store: &VcStore, guard: &daemon_limits::ResourceGuard) {
+    if !config.analyze.enabled
+        || guard.level() != daemon_limits::DegradationLevel::Normal
+        || guard.disk_pressure()
+        || !config.analyze.in_quiet_window(Utc::now().hour())
+    {
+        return;
+    }
+    let due = match store
+        .tables_due_for_analyze(config.analyze.change_ratio, config.analyze.min_changes)
+    {
+        Ok(due) => due,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not read table statistics");
+            return;
+        }
+    };
+    for stats in due.into_iter().take(config.analyze.max_tables_per_tick) {
+        match store.analyze_table(&stats.table_name) {
+            Ok(result) => tracing::info!(
+                table = %result.table_name,
+                duration_ms = result.duration_ms,
+                changes = result.changes_reset,
+                plans_changed = result.plans_changed.len(),
+                "analyzed table"
+            ),
+            Err(e) => tracing::warn!(table = %stats.table_name, error = %e, "ANALYZE failed"),
+        }
+    }
+}
+
+/// Move transcripts past `[storage.artifacts] offload_after_days` to the
+/// artifact store, a batch per tick, unless the daemon is shedding load
+fn offload_old_transcripts(
+    config: &VcConfig,
+    store: &VcStore,
+    guard: &daemon_limits::ResourceGuard,
+) {
+    let artifacts = &config.storage.artifacts;
+    if !artifacts.enabled || guard.level() != daemon_limits::DegradationLevel::Normal {
+        return;
+    }
+    let ended_before = Utc::now() - ChronoDuration::days(i64::from(artifacts.offload_after_days));
+    match store.offload_transcripts(ended_before, artifacts.batch_size) {
+        Ok(summary) if summary.offloaded > 0 => tracing::info!(
+            transcripts = summary.offloaded,
+            bytes = summary.bytes,
+            "offloaded transcripts to the artifact store"
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "transcript offload failed"),
+    }
+}
+
+/// Whether the daemon should sit out this tick because the store is an
+/// unpromoted replication standby
+fn daemon_on_standby(config: &VcConfig, store: &VcStore) -> bool {
+    match store.replication_role(config.replication.mode) {
+        Ok(ReplicationMode::Standby) => {
+            tracing::debug!("replication standby; skipping collection until promoted");
+            true
+        }
+        Ok(ReplicationMode::Primary) => false,
+        Err(e) => {
+            tracing::warn!(error = %e, "could not read replication role; skipping collection");
+            true
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
 async fn run_tui(
     options: vc_tui::RunOptions,
     context: Option<vc_tui::AppContext>,
@@ -4271,7 +10691,7 @@ async fn run_tui(
 
 #[allow(clippy::too_many_arguments)]
 async fn run_watch(
-    config_path: Option<&PathBuf>,
+    source: ConfigSource<'_>,
     format: OutputFormat,
     cx: &Cx,
     mut shutdown: ShutdownReceiver,
@@ -4281,6 +10701,7 @@ async fn run_watch(
     machines: Option<Vec<String>>,
     min_severity: Option<String>,
     buffer: Option<usize>,
+    enrich: bool,
 ) -> Result<(), CliError> {
     let filter = watch::WatchFilter {
         event_types: events
@@ -4303,6 +10724,7 @@ async fn run_watch(
         "interval_secs": interval_secs,
         "changes_only": changes_only,
         "buffer_size": buffer_size,
+        "enrich": enrich,
         "filters": {
             "events": events,
             "machines": machines,
@@ -4318,7 +10740,8 @@ async fn run_watch(
         );
     }
 
-    let store = open_store(config_path)?;
+    let store = open_store(source)?;
+    let enricher = enrich.then(watch_enrich::AlertEnricher::default);
     let mut event_buffer: Vec<watch::WatchEvent> = Vec::new();
     let mut last_check = Utc::now();
     let tick = Duration::from_secs(interval_secs);
@@ -4345,30 +10768,15 @@ async fn run_watch(
         ticks += 1;
         let now = Utc::now();
 
-        let ts = escape_sql_literal(&last_check.to_rfc3339());
-        let sql = format!(
-            "SELECT id, severity, machine_id, message FROM alert_history WHERE fired_at > '{ts}' ORDER BY fired_at"
+        event_buffer.extend(
+            poll_watch_events(&store, last_check)
+                .into_iter()
+                .filter(|event| filter.matches(event))
+                .map(|event| match &enricher {
+                    Some(enricher) => enricher.enrich(&store, event),
+                    None => event,
+                }),
         );
-        if let Ok(rows) = store.query_json(&sql) {
-            for row in rows {
-                let severity = row
-                    .get("severity")
-                    .and_then(|v| v.as_str())
-                    .and_then(watch::WatchSeverity::from_str_loose)
-                    .unwrap_or(watch::WatchSeverity::Medium);
-                let event = watch::WatchEvent::alert(
-                    row.get("machine_id")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("unknown"),
-                    severity,
-                    row.get("id").and_then(|v| v.as_str()).unwrap_or(""),
-                    row.get("message").and_then(|v| v.as_str()).unwrap_or(""),
-                );
-                if filter.matches(&event) {
-                    event_buffer.push(event);
-                }
-            }
-        }
 
         if event_buffer.is_empty() && !changes_only {
             event_buffer.push(watch::WatchEvent::heartbeat());
@@ -4394,6 +10802,172 @@ async fn run_watch(
     Ok(())
 }
 
+/// Watch events recorded since `since`, unfiltered
+fn poll_watch_events(store: &VcStore, since: DateTime<Utc>) -> Vec<watch::WatchEvent> {
+    let ts = escape_sql_literal(&since.to_rfc3339());
+    let sql = format!(
+        "SELECT CAST(id AS TEXT) AS id, severity, machine_id, message, \
+         CAST(fired_at AS TEXT) AS fired_at \
+         FROM alert_history WHERE fired_at > '{ts}' ORDER BY fired_at"
+    );
+    let Ok(rows) = store.query_json(&sql) else {
+        return Vec::new();
+    };
+    rows.iter()
+        .map(|row| {
+            let severity = row
+                .get("severity")
+                .and_then(|v| v.as_str())
+                .and_then(watch::WatchSeverity::from_str_loose)
+                .unwrap_or(watch::WatchSeverity::Medium);
+            let mut event = watch::WatchEvent::alert(
+                row.get("machine_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown"),
+                severity,
+                row.get("id").and_then(|v| v.as_str()).unwrap_or(""),
+                row.get("message").and_then(|v| v.as_str()).unwrap_or(""),
+            );
+            // Stamp events with when they were recorded (hub time) rather
+            // than when this poll saw them, so merged streams order correctly
+            if let Some(fired_at) = row
+                .get("fired_at")
+                .and_then(|v| v.as_str())
+                .and_then(vc_collect::clock::parse_row_timestamp)
+            {
+                event.ts = fired_at;
+            }
+            event
+        })
+        .collect()
+}
+
+/// Publish everything recorded since `since` to the watch socket (a heartbeat
+/// when nothing was), then advance `since`. Alerts are enriched only while
+/// some subscriber asked for it.
+#[cfg(unix)]
+fn publish_watch_tick(
+    server: &watch_socket::WatchBroadcaster,
+    store: &VcStore,
+    since: &mut DateTime<Utc>,
+) {
+    let now = Utc::now();
+    let events = poll_watch_events(store, *since);
+    if events.is_empty() {
+        server.publish(&watch::WatchEvent::heartbeat());
+    }
+    let enricher = server
+        .wants_enrichment()
+        .then(watch_enrich::AlertEnricher::default);
+    for event in &events {
+        match &enricher {
+            Some(enricher) if event.event_type == watch::WatchEventType::Alert => {
+                server.publish_enriched(event, &enricher.enrich(store, event.clone()));
+            }
+            _ => {
+                server.publish(event);
+            }
+        }
+    }
+    *since = now;
+}
+
+/// `vc watch --serve`: one polling loop fanned out to every subscriber on
+/// the socket, each applying its own filter.
+#[cfg(unix)]
+async fn run_watch_serve(
+    source: ConfigSource<'_>,
+    cx: &Cx,
+    mut shutdown: ShutdownReceiver,
+    path: &Path,
+    interval: Option<u64>,
+) -> Result<(), CliError> {
+    let config = load_config(source)?;
+    let store = open_duckdb_store(&config)?;
+    let server = watch_socket::WatchBroadcaster::bind(path, config.daemon.watch_queue_size)?;
+    let interval_secs = interval.unwrap_or(30);
+    println!(
+        "{}",
+        serde_json::json!({
+            "type": "watch_serve_start",
+            "ts": Utc::now().to_rfc3339(),
+            "socket": path,
+            "interval_secs": interval_secs,
+        })
+    );
+
+    let tick = Duration::from_secs(interval_secs);
+    let mut since = Utc::now();
+    let mut ticks = 0_u64;
+    loop {
+        if cx.checkpoint().is_err() {
+            break;
+        }
+        if wait_for_interval_or_shutdown(tick, &mut shutdown).await {
+            tracing::info!(ticks, "Watch server shutdown requested");
+            break;
+        }
+        if cx.checkpoint().is_err() {
+            break;
+        }
+        ticks += 1;
+        publish_watch_tick(&server, &store, &mut since);
+    }
+
+    tracing::info!(
+        ticks,
+        subscribers = server.subscriber_count(),
+        "Watch server drained"
+    );
+    Ok(())
+}
+
+/// `vc watch --connect`: print what the socket sends until it closes or
+/// shutdown is requested.
+#[cfg(unix)]
+async fn run_watch_connect(
+    path: PathBuf,
+    request: watch_socket::SubscribeRequest,
+    format: OutputFormat,
+    mut shutdown: ShutdownReceiver,
+) -> Result<(), CliError> {
+    let use_toon = matches!(format, OutputFormat::Toon);
+    let mut client = watch_socket::WatchClient::connect(&path, &request).map_err(|e| {
+        CliError::CommandFailed(format!("cannot connect to {}: {e}", path.display()))
+    })?;
+    // Wake up regularly so a shutdown request is noticed between frames
+    client.set_read_timeout(Some(Duration::from_millis(250)))?;
+
+    let shutdown_requested = Arc::new(AtomicBool::new(false));
+    let worker_shutdown = Arc::clone(&shutdown_requested);
+    let join_handle = tokio::task::spawn_blocking(move || -> Result<(), CliError> {
+        while !worker_shutdown.load(Ordering::Acquire) {
+            match client.next_frame() {
+                Ok(Some(line)) => println!("{}", watch_socket::render_frame(&line, use_toon)),
+                Ok(None) => return Ok(()),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    });
+    let join_handle = Box::pin(join_handle);
+    let shutdown_wait = Box::pin(shutdown.wait());
+
+    let join_result = match future::select(join_handle, shutdown_wait).await {
+        Either::Left((join_result, _)) => join_result,
+        Either::Right(((), join_handle)) => {
+            shutdown_requested.store(true, Ordering::Release);
+            join_handle.await
+        }
+    };
+    join_result.map_err(|err| CliError::CommandFailed(format!("watch client failed: {err}")))?
+}
+
 fn flush_watch_events(event_buffer: &mut Vec<watch::WatchEvent>, use_toon: bool) {
     for event in event_buffer.drain(..) {
         if use_toon {
@@ -4404,19 +10978,64 @@ fn flush_watch_events(event_buffer: &mut Vec<watch::WatchEvent>, use_toon: bool)
     }
 }
 
+#[cfg(feature = "web")]
 async fn run_web_server(
-    config_path: Option<&PathBuf>,
+    source: ConfigSource<'_>,
     port: u16,
     bind: String,
     mut shutdown: ShutdownReceiver,
 ) -> Result<(), CliError> {
-    let config = load_config(config_path)?;
-    let store = VcStore::open(&config.global.db_path)?;
+    let config = load_config(source)?;
+    let store = open_duckdb_store(&config)?
+        .with_query_log(&config.query_log, vc_store::QueryCaller::Web)
+        .with_write_limits(&config.write_limits)
+        .with_artifacts(&config.storage.artifacts);
+    record_config_snapshot(&store, &config, source);
+    let health_config = config.clone();
     let mut web_config = config.web;
     web_config.port = port;
     web_config.bind_address = bind;
 
-    let server = vc_web::WebServer::new(store, web_config);
+    let signing = web_config.signing.clone();
+    let oidc = web_config.oidc.clone();
+    // Refuse to serve a login that cannot work, as `vc config lint` would
+    if let Some((key, message)) = oidc.problems().into_iter().next() {
+        return Err(CliError::CommandFailed(format!(
+            "{key}: {message} (see `vc config lint`)"
+        )));
+    }
+    let mut server = vc_web::WebServer::new(store, web_config)
+        .with_replication_mode(config.replication.mode)
+        .with_min_versions(config.collectors.min_versions)
+        .with_health_config(health_config)
+        .with_fleet_run(config.fleet_run)
+        .with_query_guardrails(vc_query::GuardrailConfig::from_config(&config.query));
+    if signing.enabled {
+        let secret = vc_config::resolve_secret(
+            "web.signing.secret",
+            signing.secret.as_deref().unwrap_or_default(),
+        )?;
+        server = server.with_request_signing(vc_web::signature::SignatureVerifier::new(
+            secret,
+            signing.max_skew_secs,
+            signing.replay_cache_size,
+        ));
+    }
+    if oidc.enabled {
+        let client_secret = vc_config::resolve_secret(
+            "web.oidc.client_secret",
+            oidc.client_secret.as_deref().unwrap_or_default(),
+        )?;
+        let session_key = oidc
+            .session_secret
+            .as_deref()
+            .map(|secret| vc_config::resolve_secret("web.oidc.session_secret", secret))
+            .transpose()?;
+        let login =
+            vc_web::oidc::OidcLogin::new(&oidc, client_secret, session_key.map(String::into_bytes))
+                .map_err(|err| CliError::CommandFailed(err.to_string()))?;
+        server = server.with_oidc(login);
+    }
     server
         .run_with_shutdown(async move {
             shutdown.wait().await;
@@ -4426,6 +11045,7 @@ async fn run_web_server(
     Ok(())
 }
 
+#[cfg(feature = "mcp")]
 async fn run_mcp_server(
     server: vc_mcp::McpServer,
     mut shutdown: ShutdownReceiver,
@@ -4455,13 +11075,14 @@ async fn run_mcp_server(
     }
 }
 
-fn load_config(config_path: Option<&std::path::PathBuf>) -> Result<VcConfig, CliError> {
-    match config_path {
-        Some(path) => VcConfig::load_with_env(path).map_err(CliError::from),
-        None => VcConfig::discover_with_env().map_err(CliError::from),
+fn load_config(source: ConfigSource<'_>) -> Result<VcConfig, CliError> {
+    match source.path {
+        Some(path) => VcConfig::load_with_env(path, source.profile).map_err(CliError::from),
+        None => VcConfig::discover_with_env(source.profile).map_err(CliError::from),
     }
 }
 
+#[cfg(feature = "tui")]
 fn resolve_tui_options(config: &VcConfig, inline_flag: bool) -> vc_tui::RunOptions {
     vc_tui::RunOptions {
         inline_mode: inline_flag || config.tui.inline_mode,
@@ -4472,12 +11093,12 @@ fn resolve_tui_options(config: &VcConfig, inline_flag: bool) -> vc_tui::RunOptio
 
 fn robot_machines_inventory(
     config: &VcConfig,
-    config_path: Option<&PathBuf>,
+    source: ConfigSource<'_>,
 ) -> (Vec<Machine>, Option<String>) {
     let fallback_warning =
         "machine registry unavailable; returning config-derived inventory".to_string();
 
-    let store = match open_store(config_path) {
+    let store = match open_store(source) {
         Ok(store) => Arc::new(store),
         Err(err) => {
             tracing::warn!(error = %err, "robot machines falling back to config-only inventory");
@@ -4539,48 +11160,35 @@ fn machine_from_config_entry(
         }))
     };
 
-    Machine {
-        machine_id: id.to_string(),
-        hostname,
-        display_name: Some(machine.name.clone()),
-        ssh_host: machine.ssh_host.clone(),
-        ssh_user: machine.ssh_user.clone(),
-        ssh_key_path,
-        ssh_port: machine.ssh_port,
-        is_local: machine.ssh_host.is_none(),
-        os_type: None,
-        arch: None,
-        added_at: Some(collected_at.to_string()),
-        last_seen_at: None,
-        last_probe_at: None,
-        status: MachineStatus::Unknown,
-        tags: machine.tags.clone(),
-        metadata,
-        enabled: machine.enabled,
+    let mut builder = Machine::builder(id, hostname)
+        .with_display_name(&machine.name)
+        .with_ssh_port(machine.ssh_port)
+        .with_local(machine.ssh_host.is_none())
+        .with_added_at(collected_at)
+        .with_tags(machine.tags.clone())
+        .with_enabled(machine.enabled);
+    if let Some(host) = &machine.ssh_host {
+        builder = builder.with_ssh_host(host);
+    }
+    if let Some(user) = &machine.ssh_user {
+        builder = builder.with_ssh_user(user);
+    }
+    if let Some(path) = ssh_key_path {
+        builder = builder.with_ssh_key_path(path);
+    }
+    if let Some(metadata) = metadata {
+        builder = builder.with_metadata(metadata);
     }
+    builder.build()
 }
 
 fn default_local_machine(collected_at: &str) -> Machine {
     let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
-    Machine {
-        machine_id: "local".to_string(),
-        hostname,
-        display_name: Some("Local Machine".to_string()),
-        ssh_host: None,
-        ssh_user: None,
-        ssh_key_path: None,
-        ssh_port: 22,
-        is_local: true,
-        os_type: None,
-        arch: None,
-        added_at: Some(collected_at.to_string()),
-        last_seen_at: None,
-        last_probe_at: None,
-        status: MachineStatus::Unknown,
-        tags: Vec::new(),
-        metadata: None,
-        enabled: true,
-    }
+    Machine::builder("local", hostname)
+        .with_display_name("Local Machine")
+        .with_local(true)
+        .with_added_at(collected_at)
+        .build()
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -5445,23 +12053,256 @@ fn sample_row_order_by(plan: &TableMigrationPlan) -> String {
     }
 }
 
-fn open_store(config_path: Option<&std::path::PathBuf>) -> Result<VcStore, CliError> {
-    let config = load_config(config_path)?;
+/// Fail when this cockpit is an unpromoted standby, whose data must only
+/// come from the primary
+fn refuse_on_standby(config: &VcConfig, store: &VcStore) -> Result<(), CliError> {
+    if store.replication_role(config.replication.mode)? == ReplicationMode::Standby {
+        return Err(CliError::CommandFailed(
+            "this cockpit is a replication standby; local collection is refused until \
+             `vc db promote`"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn open_store(source: ConfigSource<'_>) -> Result<VcStore, CliError> {
+    let config = load_config(source)?;
+    require_duckdb_store(&config)?;
+    let store = store_open::open_with_timeout(
+        &config.global.db_path,
+        Duration::from_secs(config.global.open_timeout_secs),
+    )
+    .map_err(|(failure, detail)| store_open::StoreOpenError {
+        failure,
+        db_path: config.global.db_path.clone(),
+        path_source: db_path_source(source),
+        detail,
+    })?
+    .with_query_log(&config.query_log, vc_store::QueryCaller::Cli)
+    .with_write_limits(&config.write_limits)
+    .with_artifacts(&config.storage.artifacts);
+    record_config_snapshot(&store, &config, source);
+    Ok(store)
+}
+
+/// Open the `DuckDB` store at `global.db_path` without the open timeout
+fn open_duckdb_store(config: &VcConfig) -> Result<VcStore, CliError> {
+    require_duckdb_store(config)?;
     Ok(VcStore::open(&config.global.db_path)?)
 }
 
-fn parse_rfc3339(value: &str) -> Result<DateTime<Utc>, CliError> {
-    let parsed = DateTime::parse_from_rfc3339(value)
-        .map_err(|err| CliError::CommandFailed(format!("Invalid timestamp: {err}")))?;
-    Ok(parsed.with_timezone(&Utc))
+/// Refuse a store configured for `SQLite` before it is opened as `DuckDB`.
+/// Only `vc db info` runs on the backend trait; every other command is built
+/// on `VcStore` and would otherwise write to a `DuckDB` file at the same path.
+fn require_duckdb_store(config: &VcConfig) -> Result<(), CliError> {
+    let backend = BackendKind::resolve(
+        &config.global.db_path,
+        config.global.store_backend.as_deref(),
+    )?;
+    if backend == BackendKind::Sqlite {
+        return Err(CliError::CommandFailed(format!(
+            "{} is configured for the sqlite backend, which only `vc db info` supports; \
+             this command needs the duckdb store (set global.store_backend = \"duckdb\" \
+             and a db_path without a .sqlite extension)",
+            config.global.db_path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Where the `db_path` commands open came from, for error messages
+fn db_path_source(source: ConfigSource<'_>) -> String {
+    if std::env::var_os("VC_DB_PATH").is_some() {
+        return "VC_DB_PATH".to_string();
+    }
+    active_config_path(source).map_or_else(
+        || "the built-in default (no config file found)".to_string(),
+        |path| path.display().to_string(),
+    )
+}
+
+/// The config file commands load: `--config`, else the first one found
+fn active_config_path(source: ConfigSource<'_>) -> Option<PathBuf> {
+    source.path.cloned().or_else(VcConfig::discover_path)
+}
+
+/// Snapshot the active config file into `config_history` if its content
+/// changed since the last snapshot. Failing to is logged, never fatal.
+fn record_config_snapshot(store: &VcStore, config: &VcConfig, source: ConfigSource<'_>) {
+    let Some(path) = active_config_path(source) else {
+        return;
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "could not read config to snapshot");
+            return;
+        }
+    };
+    let mtime = std::fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339());
+    let command = std::env::args().nth(1).unwrap_or_default();
+    let loaded_by = format!("vc {command} (pid {})", std::process::id());
+    match store.record_config_snapshot(
+        &path.display().to_string(),
+        &content,
+        mtime.as_deref(),
+        &loaded_by,
+        config.global.config_history_keep,
+    ) {
+        Ok(Some(snapshot)) => tracing::info!(
+            path = %path.display(),
+            hash = &snapshot.content_hash[..12],
+            "config changed; snapshot recorded"
+        ),
+        Ok(None) => {}
+        Err(e) => tracing::warn!(error = %e, "failed to record config snapshot"),
+    }
+}
+
+/// A config snapshot by hash or unique prefix, or an error naming it
+fn find_config_snapshot(store: &VcStore, hash: &str) -> Result<vc_store::ConfigSnapshot, CliError> {
+    store
+        .config_snapshot(hash)?
+        .ok_or_else(|| CliError::CommandFailed(format!("No config snapshot matches {hash}")))
+}
+
+/// Knowledge store with the configured embedding backend attached, when
+/// built with the `embeddings` feature
+#[cfg_attr(not(feature = "embeddings"), allow(unused_variables))]
+#[cfg(feature = "knowledge")]
+fn knowledge_store(store: Arc<VcStore>, config: &VcConfig) -> KnowledgeStore {
+    let kb = KnowledgeStore::new(store);
+    #[cfg(feature = "embeddings")]
+    if let Some(embedder) =
+        vc_knowledge::embeddings::embedder_from_config(&config.knowledge.embeddings)
+    {
+        return kb.with_embedder(embedder, config.knowledge.embeddings.semantic_weight);
+    }
+    kb
+}
+
+/// Write draft steps to a scratch file, open `$EDITOR` on it, and return the
+/// edited contents once the editor exits successfully.
+#[cfg(feature = "guardian")]
+fn edit_steps_in_editor(
+    draft_id: &str,
+    steps: &[vc_guardian::PlaybookStep],
+) -> Result<String, CliError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = std::env::temp_dir().join(format!(
+        "vc-draft-{}-{}.json",
+        draft_id.replace(['/', '\\'], "_"),
+        std::process::id()
+    ));
+
+    let initial = serde_json::to_string_pretty(steps)
+        .map_err(|e| CliError::CommandFailed(format!("Failed to serialize steps: {e}")))?;
+    std::fs::write(&path, initial)?;
+
+    let status = std::process::Command::new(&editor).arg(&path).status();
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let status = status
+        .map_err(|e| CliError::CommandFailed(format!("Failed to launch editor '{editor}': {e}")))?;
+    if !status.success() {
+        return Err(CliError::CommandFailed(format!(
+            "Editor '{editor}' exited with {status}; draft left unchanged"
+        )));
+    }
+    Ok(edited?)
+}
+
+fn parse_rfc3339(value: &str) -> Result<DateTime<Utc>, CliError> {
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map_err(|err| CliError::CommandFailed(format!("Invalid timestamp: {err}")))?;
+    Ok(parsed.with_timezone(&Utc))
+}
+
+/// Formatter for human-facing timestamps, set once per run from
+/// `--timestamps` and `global.timezone`
+static TIME_FORMAT: OnceLock<vc_query::TimeFormatter> = OnceLock::new();
+
+/// `--csv-columns`, set once per run
+static CSV_COLUMNS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// `--max-tokens` and `--chars-per-token`, set once per run
+static TOON_BUDGET: OnceLock<toon::ToonBudget> = OnceLock::new();
+
+/// TOON for a robot payload, cut to `--max-tokens` when given
+fn robot_toon<T: toon::ToToon>(data: &T) -> String {
+    TOON_BUDGET
+        .get()
+        .map_or_else(|| data.to_toon(), |budget| data.to_toon_within(*budget))
+}
+
+fn time_format() -> &'static vc_query::TimeFormatter {
+    TIME_FORMAT.get_or_init(vc_query::TimeFormatter::default)
+}
+
+/// Print a multi-machine report, saving it first with `--save-report`.
+/// When a target failed, the report is still printed in full and the
+/// command then fails with the worst outcome's exit code.
+fn emit_multi_report(
+    store: &VcStore,
+    report: &MultiMachineReport,
+    format: OutputFormat,
+    save: bool,
+    actor: Option<&str>,
+) -> Result<(), CliError> {
+    if save {
+        let saved_by = command_actor(None, actor).name;
+        store.record_operation_report(&report.to_record(Some(&saved_by)))?;
+        eprintln!(
+            "Saved report {0}; show it again with `vc ops show {0}`",
+            report.operation_id
+        );
+    }
+    print_multi_report(report, format);
+    if report.worst == Some(TargetOutcome::Failed) {
+        return Err(CliError::TargetsFailed {
+            command: report.command.clone(),
+            failed: report.counts.failed,
+            total: report.counts.total,
+        });
+    }
+    Ok(())
+}
+
+fn print_multi_report(report: &MultiMachineReport, format: OutputFormat) {
+    match format {
+        OutputFormat::Text => println!("{}", report.render_table()),
+        OutputFormat::Toon => println!("{}", robot_toon(report)),
+        OutputFormat::Json | OutputFormat::Csv => print_output(report, format),
+    }
 }
 
 fn print_output<T: Serialize>(value: &T, format: OutputFormat) {
     let output = match format {
         OutputFormat::Json => serde_json::to_string_pretty(value)
             .unwrap_or_else(|e| format!(r#"{{"error": "serialization failed: {e}"}}"#)),
-        OutputFormat::Toon => toon::to_toon_via_json(value),
-        OutputFormat::Text => serde_json::to_string_pretty(value)
+        OutputFormat::Toon => TOON_BUDGET.get().map_or_else(
+            || toon::to_toon_via_json(value),
+            |budget| toon::to_toon_via_json_within(value, *budget),
+        ),
+        // Records already end in CRLF
+        OutputFormat::Csv => {
+            print!(
+                "{}",
+                csv::to_csv_via_json(value, CSV_COLUMNS.get().map(Vec::as_slice))
+            );
+            return;
+        }
+        // Text is for people: timestamps and durations are humanized.
+        OutputFormat::Text => serde_json::to_value(value)
+            .map(|json| time_format().humanize_json(&json))
+            .and_then(|json| serde_json::to_string_pretty(&json))
             .unwrap_or_else(|e| format!(r#"{{"error": "serialization failed: {e}"}}"#)),
     };
     println!("{output}");
@@ -5510,6 +12351,17 @@ mod tests {
         assert!(debug.contains("CommandFailed"));
     }
 
+    #[test]
+    fn cli_error_partial_report_exit_code() {
+        let err = CliError::PartialReport(vec!["Alert Summary".to_string()]);
+        assert_eq!(err.exit_code(), EXIT_PARTIAL_REPORT);
+        assert_eq!(
+            err.to_string(),
+            "Report incomplete, data unavailable for: Alert Summary"
+        );
+        assert_eq!(CliError::CommandFailed("x".to_string()).exit_code(), 1);
+    }
+
     // =============================================================================
     // OutputFormat Tests
     // =============================================================================
@@ -5616,7 +12468,12 @@ mod tests {
 
     #[test]
     fn output_format_roundtrip() {
-        for format in [OutputFormat::Json, OutputFormat::Toon, OutputFormat::Text] {
+        for format in [
+            OutputFormat::Json,
+            OutputFormat::Toon,
+            OutputFormat::Text,
+            OutputFormat::Csv,
+        ] {
             let json = serde_json::to_string(&format).unwrap();
             let parsed: OutputFormat = serde_json::from_str(&json).unwrap();
             assert!(matches!(
@@ -5624,6 +12481,7 @@ mod tests {
                 (OutputFormat::Json, OutputFormat::Json)
                     | (OutputFormat::Toon, OutputFormat::Toon)
                     | (OutputFormat::Text, OutputFormat::Text)
+                    | (OutputFormat::Csv, OutputFormat::Csv)
             ));
         }
     }
@@ -5664,6 +12522,15 @@ mod tests {
         assert!(matches!(cli.format, OutputFormat::Toon));
     }
 
+    #[test]
+    fn test_global_timestamps_flag() {
+        let cli = Cli::parse_from(["vc", "status"]);
+        assert_eq!(cli.timestamps, vc_query::TimestampStyle::Relative);
+        let cli = Cli::parse_from(["vc", "status", "--timestamps", "both"]);
+        assert_eq!(cli.timestamps, vc_query::TimestampStyle::Both);
+        assert!(Cli::try_parse_from(["vc", "--timestamps", "sideways", "status"]).is_err());
+    }
+
     #[test]
     fn test_global_verbose_flag() {
         let cli = Cli::parse_from(["vc", "--verbose", "status"]);
@@ -5679,6 +12546,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_global_profile_flag() {
+        let cli = Cli::parse_from(["vc", "config", "show", "--profile", "staging", "--resolved"]);
+        assert_eq!(cli.profile.as_deref(), Some("staging"));
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Show { resolved: true, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_config_secret_commands_parse() {
+        let cli = Cli::parse_from(["vc", "config", "lint", "--deep"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Lint { deep: true, .. }
+            }
+        ));
+        let cli = Cli::parse_from(["vc", "config", "verify-secrets", "--file", "vc.toml"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::VerifySecrets { file: Some(_) }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_config_history_commands_parse() {
+        let cli = Cli::parse_from(["vc", "config", "history", "--limit", "3"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::History { limit: 3 }
+            }
+        ));
+        let cli = Cli::parse_from(["vc", "config", "diff", "--latest"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Diff { latest: true, .. }
+            }
+        ));
+        assert!(Cli::try_parse_from(["vc", "config", "diff", "abc"]).is_err());
+        assert!(Cli::try_parse_from(["vc", "config", "diff", "abc", "def", "--latest"]).is_err());
+        let cli = Cli::parse_from(["vc", "config", "rollback", "abc123", "--yes"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Config {
+                command: ConfigCommands::Rollback { yes: true, ref hash, .. }
+            } if hash == "abc123"
+        ));
+    }
+
+    #[test]
+    fn test_open_store_snapshots_changed_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vc.toml");
+        let db = dir.path().join("vc.duckdb");
+        let write = |level: &str| {
+            std::fs::write(
+                &path,
+                format!(
+                    "[global]\ndb_path = \"{}\"\nlog_level = \"{level}\"\n",
+                    db.display()
+                ),
+            )
+            .unwrap();
+        };
+        let source = ConfigSource {
+            path: Some(&path),
+            profile: None,
+        };
+
+        write("info");
+        open_store(source).unwrap();
+        open_store(source).unwrap();
+        write("debug");
+        let store = open_store(source).unwrap();
+
+        let snapshots = store.config_snapshots(10).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert!(snapshots[0].content.contains("debug"));
+        assert_eq!(snapshots[0].config_path, path.display().to_string());
+        assert!(snapshots[0].file_mtime.is_some());
+    }
+
+    #[test]
+    fn test_load_config_rejects_unknown_profile() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("vc.toml");
+        std::fs::write(&path, "[profiles.prod.global]\nlog_level = \"warn\"\n").unwrap();
+
+        let config = load_config(ConfigSource {
+            path: Some(&path),
+            profile: Some("prod"),
+        })
+        .unwrap();
+        assert_eq!(config.global.log_level, "warn");
+
+        let err = load_config(ConfigSource {
+            path: Some(&path),
+            profile: Some("dev"),
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("Available profiles: prod"));
+    }
+
     #[test]
     fn test_default_format_is_text() {
         let cli = Cli::parse_from(["vc", "status"]);
@@ -5714,8 +12693,25 @@ mod tests {
     #[test]
     fn test_daemon_parse() {
         let cli = Cli::parse_from(["vc", "daemon"]);
-        if let Commands::Daemon { foreground } = cli.command {
+        if let Commands::Daemon {
+            foreground,
+            takeover,
+            command,
+        } = cli.command
+        {
             assert!(!foreground);
+            assert!(!takeover);
+            assert!(command.is_none());
+        } else {
+            panic!("Expected Daemon command");
+        }
+    }
+
+    #[test]
+    fn test_daemon_takeover_parse() {
+        let cli = Cli::parse_from(["vc", "daemon", "--takeover"]);
+        if let Commands::Daemon { takeover, .. } = cli.command {
+            assert!(takeover);
         } else {
             panic!("Expected Daemon command");
         }
@@ -5724,7 +12720,7 @@ mod tests {
     #[test]
     fn test_daemon_foreground() {
         let cli = Cli::parse_from(["vc", "daemon", "--foreground"]);
-        if let Commands::Daemon { foreground } = cli.command {
+        if let Commands::Daemon { foreground, .. } = cli.command {
             assert!(foreground);
         } else {
             panic!("Expected Daemon command");
@@ -5734,13 +12730,89 @@ mod tests {
     #[test]
     fn test_daemon_short_foreground() {
         let cli = Cli::parse_from(["vc", "daemon", "-f"]);
-        if let Commands::Daemon { foreground } = cli.command {
+        if let Commands::Daemon { foreground, .. } = cli.command {
             assert!(foreground);
         } else {
             panic!("Expected Daemon command");
         }
     }
 
+    #[test]
+    fn test_daemon_check_parse() {
+        let cli = Cli::parse_from(["vc", "daemon", "check"]);
+        if let Commands::Daemon { command, .. } = cli.command {
+            assert!(matches!(command, Some(DaemonCommands::Check)));
+        } else {
+            panic!("Expected Daemon command");
+        }
+    }
+
+    #[test]
+    fn test_query_template_reshape_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "query",
+            "template",
+            "machine_health",
+            "--columns",
+            "machine_id,count",
+            "--aggregate",
+            "count",
+            "--group-by",
+            "machine_id",
+        ]);
+        if let Commands::Query {
+            command:
+                QueryCommands::Template {
+                    columns,
+                    aggregate,
+                    group_by,
+                    ..
+                },
+        } = cli.command
+        {
+            assert_eq!(columns.as_deref(), Some("machine_id,count"));
+            assert_eq!(aggregate.as_deref(), Some("count"));
+            assert_eq!(group_by.as_deref(), Some("machine_id"));
+        } else {
+            panic!("Expected Query Template command");
+        }
+
+        // --group-by without --aggregate is rejected up front
+        assert!(
+            Cli::try_parse_from(["vc", "query", "template", "t", "--group-by", "machine_id"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_query_proposal_commands_parse() {
+        let cli = Cli::parse_from(["vc", "query", "pending"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Query {
+                command: QueryCommands::Pending { all: false }
+            }
+        ));
+
+        let cli = Cli::parse_from(["vc", "--actor", "alice", "query", "approve", "3"]);
+        assert_eq!(cli.actor.as_deref(), Some("alice"));
+        assert!(matches!(
+            cli.command,
+            Commands::Query {
+                command: QueryCommands::Approve { id: 3 }
+            }
+        ));
+
+        let cli = Cli::parse_from(["vc", "query", "deny", "4", "--reason", "too broad"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Query {
+                command: QueryCommands::Deny { id: 4, reason: Some(ref r) }
+            } if r == "too broad"
+        ));
+    }
+
     // =============================================================================
     // Commands::Status Tests
     // =============================================================================
@@ -5748,8 +12820,9 @@ mod tests {
     #[test]
     fn test_status_no_machine() {
         let cli = Cli::parse_from(["vc", "status"]);
-        if let Commands::Status { machine } = cli.command {
+        if let Commands::Status { machine, sparkline } = cli.command {
             assert!(machine.is_none());
+            assert!(sparkline.is_none());
         } else {
             panic!("Expected Status command");
         }
@@ -5758,13 +12831,40 @@ mod tests {
     #[test]
     fn test_status_with_machine() {
         let cli = Cli::parse_from(["vc", "status", "--machine", "server-1"]);
-        if let Commands::Status { machine } = cli.command {
+        if let Commands::Status { machine, .. } = cli.command {
             assert_eq!(machine, Some("server-1".to_string()));
         } else {
             panic!("Expected Status command");
         }
     }
 
+    #[test]
+    fn test_status_sparkline_flag() {
+        let cli = Cli::parse_from(["vc", "status", "--sparkline"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Status {
+                sparkline: Some(true),
+                ..
+            }
+        ));
+        let cli = Cli::parse_from(["vc", "status", "--sparkline=false"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Status {
+                sparkline: Some(false),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_sparklines_only_in_text_output() {
+        assert!(sparklines_enabled(Some(true), OutputFormat::Text));
+        assert!(!sparklines_enabled(Some(false), OutputFormat::Text));
+        assert!(!sparklines_enabled(Some(true), OutputFormat::Json));
+    }
+
     // =============================================================================
     // Commands::Robot Tests
     // =============================================================================
@@ -5779,7 +12879,15 @@ mod tests {
     fn test_robot_health_parse() {
         let cli = Cli::parse_from(["vc", "robot", "health"]);
         if let Commands::Robot { command } = cli.command {
-            assert!(matches!(command, RobotCommands::Health));
+            assert!(matches!(command, RobotCommands::Health { verify: false }));
+        } else {
+            panic!("Expected Robot command");
+        }
+
+        let cli = Cli::parse_from(["vc", "--format", "toon", "robot", "health", "--verify"]);
+        assert!(matches!(cli.format, OutputFormat::Toon));
+        if let Commands::Robot { command } = cli.command {
+            assert!(matches!(command, RobotCommands::Health { verify: true }));
         } else {
             panic!("Expected Robot command");
         }
@@ -5789,7 +12897,17 @@ mod tests {
     fn test_robot_triage_parse() {
         let cli = Cli::parse_from(["vc", "robot", "triage"]);
         if let Commands::Robot { command } = cli.command {
-            assert!(matches!(command, RobotCommands::Triage));
+            assert!(matches!(command, RobotCommands::Triage { max_items: None }));
+        } else {
+            panic!("Expected Robot command");
+        }
+
+        let cli = Cli::parse_from(["vc", "robot", "triage", "--max-items", "5"]);
+        if let Commands::Robot { command } = cli.command {
+            assert!(matches!(
+                command,
+                RobotCommands::Triage { max_items: Some(5) }
+            ));
         } else {
             panic!("Expected Robot command");
         }
@@ -5845,6 +12963,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_robot_schema_check_parse() {
+        let cli = Cli::parse_from(["vc", "robot", "schema", "check"]);
+        if let Commands::Robot {
+            command:
+                RobotCommands::Schema {
+                    command: RobotSchemaCommands::Check { dir },
+                },
+        } = cli.command
+        {
+            assert_eq!(dir, PathBuf::from(schema_registry::GENERATED_SCHEMAS_DIR));
+        } else {
+            panic!("Expected Robot schema check command");
+        }
+    }
+
+    #[test]
+    fn test_robot_schema_export_parse() {
+        let cli = Cli::parse_from(["vc", "robot", "schema", "export", "--out", "/tmp/s"]);
+        if let Commands::Robot {
+            command:
+                RobotCommands::Schema {
+                    command: RobotSchemaCommands::Export { out, force },
+                },
+        } = cli.command
+        {
+            assert_eq!(out, PathBuf::from("/tmp/s"));
+            assert!(!force);
+        } else {
+            panic!("Expected Robot schema export command");
+        }
+    }
+
     // =============================================================================
     // Commands::Machines Tests
     // =============================================================================
@@ -5953,14 +13104,61 @@ mod tests {
     fn test_machines_probe_parse() {
         let cli = Cli::parse_from(["vc", "machines", "probe", "mac-mini-1"]);
         if let Commands::Machines { command } = cli.command {
-            if let MachineCommands::Probe { id } = command {
+            if let MachineCommands::Probe { id, http_checks } = command {
                 assert_eq!(id, "mac-mini-1");
+                assert!(!http_checks);
             } else {
                 panic!("Expected Machines probe command");
             }
         } else {
             panic!("Expected Machines command");
         }
+
+        let cli = Cli::parse_from(["vc", "machines", "probe", "orko", "--http-checks"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Machines {
+                command: MachineCommands::Probe {
+                    http_checks: true,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_machines_merge_parse() {
+        let cli = Cli::parse_from(["vc", "machines", "merge", "orko", "--into", "orko-new"]);
+        match cli.command {
+            Commands::Machines {
+                command:
+                    MachineCommands::Merge {
+                        from,
+                        into,
+                        conflicts,
+                    },
+            } => {
+                assert_eq!(from.as_deref(), Some("orko"));
+                assert_eq!(into.as_deref(), Some("orko-new"));
+                assert!(!conflicts);
+            }
+            other => panic!("Expected Machines merge command, got {other:?}"),
+        }
+
+        let cli = Cli::parse_from(["vc", "machines", "merge", "--conflicts"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Machines {
+                command: MachineCommands::Merge {
+                    from: None,
+                    conflicts: true,
+                    ..
+                }
+            }
+        ));
+        assert!(Cli::try_parse_from(["vc", "machines", "merge", "orko"]).is_err());
+        assert!(Cli::try_parse_from(["vc", "machines", "merge"]).is_err());
+        assert!(Cli::try_parse_from(["vc", "machines", "merge", "orko", "--conflicts"]).is_err());
     }
 
     #[test]
@@ -5978,6 +13176,206 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_timeline_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "timeline",
+            "--since",
+            "6h",
+            "--machine",
+            "orko",
+            "--kinds",
+            "alerts,drift",
+        ]);
+        if let Commands::Timeline {
+            command,
+            since,
+            machine,
+            kinds,
+            limit,
+            ..
+        } = cli.command
+        {
+            assert!(command.is_none());
+            assert_eq!(since, "6h");
+            assert_eq!(machine.as_deref(), Some("orko"));
+            assert_eq!(
+                kinds,
+                [
+                    vc_query::TimelineKind::Alerts,
+                    vc_query::TimelineKind::Drift
+                ]
+            );
+            assert_eq!(limit, 200);
+        } else {
+            panic!("Expected Timeline command");
+        }
+
+        let cli = Cli::parse_from(["vc", "timeline", "show", "incidents", "inc-abc123"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Timeline {
+                command: Some(TimelineCommands::Show {
+                    kind: vc_query::TimelineKind::Incidents,
+                    ..
+                }),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_search_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "search",
+            "linker oom",
+            "--kinds",
+            "knowledge,sessions",
+        ]);
+        if let Commands::Search {
+            query,
+            kinds,
+            since,
+            limit,
+        } = cli.command
+        {
+            assert_eq!(query, "linker oom");
+            assert_eq!(
+                kinds,
+                [
+                    vc_query::SearchKind::Knowledge,
+                    vc_query::SearchKind::Sessions
+                ]
+            );
+            assert_eq!(since, "30d");
+            assert_eq!(limit, 20);
+        } else {
+            panic!("Expected Search command");
+        }
+    }
+
+    #[test]
+    fn test_machines_diff_parse() {
+        let cli = Cli::parse_from(["vc", "machines", "diff", "orko", "--against", "bender"]);
+        if let Commands::Machines {
+            command:
+                MachineCommands::Diff {
+                    id,
+                    from,
+                    to,
+                    against,
+                },
+        } = cli.command
+        {
+            assert_eq!(id, "orko");
+            assert!(from.is_none() && to.is_none());
+            assert_eq!(against.as_deref(), Some("bender"));
+        } else {
+            panic!("Expected Machines diff command");
+        }
+
+        assert!(Cli::try_parse_from(["vc", "machines", "diff", "orko"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "machines",
+                "diff",
+                "orko",
+                "--from",
+                "2026-10-01T00:00:00Z"
+            ])
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_machines_depend_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "machines",
+            "depend",
+            "builder-1",
+            "--on",
+            "nfs",
+            "--kind",
+            "storage",
+        ]);
+        if let Commands::Machines {
+            command:
+                MachineCommands::Depend {
+                    id,
+                    on,
+                    kind,
+                    remove,
+                },
+        } = cli.command
+        {
+            assert_eq!(id, "builder-1");
+            assert_eq!(on, "nfs");
+            assert_eq!(kind.as_deref(), Some("storage"));
+            assert!(!remove);
+        } else {
+            panic!("Expected Machines depend command");
+        }
+
+        let depend = |args: &[&str]| {
+            Cli::try_parse_from(
+                ["vc", "machines", "depend", "b1", "--on", "nfs"]
+                    .iter()
+                    .chain(args),
+            )
+        };
+        assert!(depend(&["--remove"]).is_ok());
+        assert!(depend(&[]).is_err());
+        assert!(depend(&["--kind", "power"]).is_err());
+        assert!(depend(&["--kind", "storage", "--remove"]).is_err());
+    }
+
+    #[test]
+    fn test_machines_maintenance_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "machines",
+            "maintenance",
+            "builder-2",
+            "--on",
+            "--reason",
+            "RAM upgrade",
+            "--until",
+            "2026-10-17T09:00:00Z",
+        ]);
+        if let Commands::Machines {
+            command:
+                MachineCommands::Maintenance {
+                    id,
+                    on,
+                    off,
+                    reason,
+                    until,
+                    by,
+                },
+        } = cli.command
+        {
+            assert_eq!(id, "builder-2");
+            assert!(on && !off);
+            assert_eq!(reason.as_deref(), Some("RAM upgrade"));
+            assert_eq!(until.as_deref(), Some("2026-10-17T09:00:00Z"));
+            assert_eq!(by, None);
+        } else {
+            panic!("Expected Machines maintenance command");
+        }
+
+        assert!(
+            Cli::try_parse_from(["vc", "machines", "maintenance", "m", "--on", "--off"]).is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["vc", "machines", "maintenance", "m", "--reason", "x"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["vc", "machines", "maintenance", "m"]).is_ok());
+    }
+
     // =============================================================================
     // Commands::Watch Tests
     // =============================================================================
@@ -5992,6 +13390,7 @@ mod tests {
             machines,
             min_severity,
             buffer,
+            ..
         } = cli.command
         {
             assert!(events.is_none());
@@ -6089,23 +13488,85 @@ mod tests {
             "5",
         ]);
         if let Commands::Watch {
-            events,
-            changes_only,
-            interval,
-            machines,
-            min_severity,
-            buffer,
+            events,
+            changes_only,
+            interval,
+            machines,
+            min_severity,
+            buffer,
+            ..
+        } = cli.command
+        {
+            assert_eq!(events.unwrap().len(), 2);
+            assert!(changes_only);
+            assert_eq!(interval, Some(15));
+            assert_eq!(machines.unwrap(), vec!["orko"]);
+            assert_eq!(min_severity, Some("critical".to_string()));
+            assert_eq!(buffer, Some(5));
+        } else {
+            panic!("Expected Watch command");
+        }
+    }
+
+    #[test]
+    fn test_watch_serve_and_connect_parse() {
+        let cli = Cli::parse_from(["vc", "watch", "--serve", "/tmp/vc.sock", "--interval", "5"]);
+        if let Commands::Watch {
+            serve,
+            connect,
+            interval,
+            ..
+        } = cli.command
+        {
+            assert_eq!(serve, Some(PathBuf::from("/tmp/vc.sock")));
+            assert!(connect.is_none());
+            assert_eq!(interval, Some(5));
+        } else {
+            panic!("Expected Watch command");
+        }
+
+        let cli = Cli::parse_from([
+            "vc",
+            "watch",
+            "--connect",
+            "/tmp/vc.sock",
+            "--events",
+            "alert",
+            "--changes-only",
+        ]);
+        if let Commands::Watch {
+            connect, events, ..
         } = cli.command
         {
-            assert_eq!(events.unwrap().len(), 2);
-            assert!(changes_only);
-            assert_eq!(interval, Some(15));
-            assert_eq!(machines.unwrap(), vec!["orko"]);
-            assert_eq!(min_severity, Some("critical".to_string()));
-            assert_eq!(buffer, Some(5));
+            assert_eq!(connect, Some(PathBuf::from("/tmp/vc.sock")));
+            assert_eq!(events.unwrap(), vec!["alert"]);
         } else {
             panic!("Expected Watch command");
         }
+
+        // Filters belong to subscribers, not the server
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "watch",
+                "--serve",
+                "/tmp/vc.sock",
+                "--events",
+                "alert"
+            ])
+            .is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "watch",
+                "--connect",
+                "/tmp/vc.sock",
+                "--interval",
+                "5"
+            ])
+            .is_err()
+        );
     }
 
     // =============================================================================
@@ -6151,8 +13612,15 @@ mod tests {
     fn test_alert_list_parse() {
         let cli = Cli::parse_from(["vc", "alert", "list"]);
         if let Commands::Alert { command } = cli.command {
-            if let AlertCommands::List { unacked } = command {
+            if let AlertCommands::List {
+                unacked,
+                resolved,
+                by,
+            } = command
+            {
                 assert!(!unacked);
+                assert!(!resolved);
+                assert_eq!(by, None);
             } else {
                 panic!("Expected List subcommand");
             }
@@ -6165,7 +13633,7 @@ mod tests {
     fn test_alert_list_unacked() {
         let cli = Cli::parse_from(["vc", "alert", "list", "--unacked"]);
         if let Commands::Alert { command } = cli.command {
-            if let AlertCommands::List { unacked } = command {
+            if let AlertCommands::List { unacked, .. } = command {
                 assert!(unacked);
             } else {
                 panic!("Expected List subcommand");
@@ -6175,12 +13643,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alert_list_resolved_by() {
+        let cli = Cli::parse_from(["vc", "alert", "list", "--resolved", "--by", "guardian"]);
+        if let Commands::Alert {
+            command: AlertCommands::List { resolved, by, .. },
+        } = cli.command
+        {
+            assert!(resolved);
+            assert_eq!(by, Some(vc_store::ResolutionKind::GuardianRun));
+        } else {
+            panic!("Expected alert list");
+        }
+        // --by only narrows resolved alerts
+        assert!(Cli::try_parse_from(["vc", "alert", "list", "--by", "manual"]).is_err());
+    }
+
     #[test]
     fn test_alert_ack_parse() {
         let cli = Cli::parse_from(["vc", "alert", "ack", "123"]);
         if let Commands::Alert { command } = cli.command {
-            if let AlertCommands::Ack { id } = command {
-                assert_eq!(id, 123);
+            if let AlertCommands::Ack { id, all, .. } = command {
+                assert_eq!(id, Some(123));
+                assert!(!all);
             } else {
                 panic!("Expected Ack subcommand");
             }
@@ -6189,14 +13674,216 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alert_bulk_ack_and_resolve_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "alert",
+            "ack",
+            "--all",
+            "--machine",
+            "orko",
+            "--before",
+            "2026-10-16",
+            "--type",
+            "offline",
+            "--dry-run",
+        ]);
+        if let Commands::Alert {
+            command:
+                AlertCommands::Ack {
+                    id,
+                    all,
+                    machine,
+                    before,
+                    alert_type,
+                    dry_run,
+                    yes,
+                    ..
+                },
+        } = cli.command
+        {
+            assert_eq!(id, None);
+            assert!(all);
+            assert_eq!(machine.as_deref(), Some("orko"));
+            assert_eq!(before.as_deref(), Some("2026-10-16T00:00:00.000000Z"));
+            assert_eq!(alert_type.as_deref(), Some("offline"));
+            assert!(dry_run);
+            assert!(!yes);
+        } else {
+            panic!("Expected alert ack");
+        }
+
+        let cli = Cli::parse_from(["vc", "alert", "resolve", "--all", "--yes", "--by", "ops"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Alert {
+                command: AlertCommands::Resolve { all: true, yes: true, ref by, .. },
+            } if by.as_deref() == Some("ops")
+        ));
+
+        // Filters only make sense with --all, and an ID excludes --all.
+        assert!(Cli::try_parse_from(["vc", "alert", "ack"]).is_err());
+        assert!(Cli::try_parse_from(["vc", "alert", "ack", "7", "--all"]).is_err());
+        assert!(Cli::try_parse_from(["vc", "alert", "ack", "7", "--machine", "orko"]).is_err());
+        assert!(
+            Cli::try_parse_from(["vc", "alert", "ack", "--all", "--before", "yesterday"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_alert_correlation_parse() {
+        let cli = Cli::parse_from(["vc", "alert", "show", "--correlation", "corr-orko-1"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Alert {
+                command: AlertCommands::Show { ref correlation },
+            } if correlation == "corr-orko-1"
+        ));
+        assert!(Cli::try_parse_from(["vc", "alert", "show"]).is_err());
+
+        let cli = Cli::parse_from(["vc", "alert", "ack", "2", "--with-children"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Alert {
+                command: AlertCommands::Ack {
+                    id: Some(2),
+                    with_children: true,
+                    ..
+                },
+            }
+        ));
+        assert!(Cli::try_parse_from(["vc", "alert", "ack", "--all", "--with-children"]).is_err());
+    }
+
     #[test]
     fn test_alert_rules_parse() {
         let cli = Cli::parse_from(["vc", "alert", "rules"]);
         if let Commands::Alert { command } = cli.command {
-            assert!(matches!(command, AlertCommands::Rules));
+            assert!(matches!(
+                command,
+                AlertCommands::Rules {
+                    command: None,
+                    machine: None
+                }
+            ));
         } else {
             panic!("Expected Alert command");
         }
+
+        let cli = Cli::parse_from(["vc", "alert", "rules", "--machine", "orko"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Alert {
+                command: AlertCommands::Rules {
+                    command: None,
+                    machine: Some(ref machine)
+                }
+            } if machine == "orko"
+        ));
+    }
+
+    #[test]
+    fn test_alert_test_notify_parse() {
+        let cli = Cli::parse_from(["vc", "alert", "test-notify", "--sink", "email"]);
+        if let Commands::Alert {
+            command: AlertCommands::TestNotify { sink, render_only },
+        } = cli.command
+        {
+            assert_eq!(sink, "email");
+            assert!(!render_only);
+        } else {
+            panic!("Expected alert test-notify");
+        }
+        assert!(Cli::try_parse_from(["vc", "alert", "test-notify"]).is_err());
+
+        let cli = Cli::parse_from([
+            "vc",
+            "alert",
+            "test-notify",
+            "--sink",
+            "slack",
+            "--render-only",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Alert {
+                command: AlertCommands::TestNotify {
+                    render_only: true,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_alert_rules_test_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "alert",
+            "rules",
+            "test",
+            "--rule",
+            "disk-critical",
+            "--window",
+            "12h",
+        ]);
+        if let Commands::Alert {
+            command:
+                AlertCommands::Rules {
+                    command: Some(AlertRuleCommands::Test { rule, file, window }),
+                    ..
+                },
+        } = cli.command
+        {
+            assert_eq!(rule.as_deref(), Some("disk-critical"));
+            assert!(file.is_none());
+            assert_eq!(window, Duration::from_secs(12 * 3_600));
+        } else {
+            panic!("Expected alert rules test");
+        }
+
+        // Either --rule or --file is required, not both.
+        assert!(Cli::try_parse_from(["vc", "alert", "rules", "test"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "vc", "alert", "rules", "test", "--rule", "x", "--file", "r.json"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_window_units() {
+        assert_eq!(parse_window("7d").unwrap(), Duration::from_secs(7 * 86_400));
+        assert_eq!(parse_window("30m").unwrap(), Duration::from_secs(1_800));
+        assert_eq!(
+            parse_window("2w").unwrap(),
+            Duration::from_secs(14 * 86_400)
+        );
+        assert!(parse_window("soon").is_err());
+        assert!(parse_window("5y").is_err());
+    }
+
+    #[test]
+    fn test_resolve_alert_rule_builtin_and_file() {
+        let rule = resolve_alert_rule(Some("disk-critical"), None).unwrap();
+        assert_eq!(rule.rule_id, "disk-critical");
+        assert!(resolve_alert_rule(Some("no-such-rule"), None).is_err());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("rule.json");
+        std::fs::write(
+            &path,
+            serde_json::to_string(&vc_alert::AlertRule {
+                rule_id: "draft".to_string(),
+                ..rule
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        let draft = resolve_alert_rule(None, Some(&path)).unwrap();
+        assert_eq!(draft.rule_id, "draft");
     }
 
     // =============================================================================
@@ -6276,7 +13963,7 @@ mod tests {
             } = command
             {
                 assert_eq!(alert_type, "rate-limit");
-                assert!(actions.contains("caam"));
+                assert!(actions.unwrap().contains("caam"));
                 assert_eq!(outcome, "success");
                 assert_eq!(machine.unwrap(), "orko");
             } else {
@@ -6287,6 +13974,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_guardian_capture_assisted_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "capture",
+            "--alert-type",
+            "rate-limit",
+            "--from-session",
+            "sess-42",
+            "--alert-id",
+            "7",
+            "--yes",
+        ]);
+        let Commands::Guardian {
+            command:
+                GuardianCommands::Capture {
+                    actions,
+                    from_session,
+                    from_shell_history,
+                    alert_id,
+                    yes,
+                    ..
+                },
+        } = cli.command
+        else {
+            panic!("Expected guardian capture");
+        };
+        assert!(actions.is_none());
+        assert_eq!(from_session.as_deref(), Some("sess-42"));
+        assert!(!from_shell_history);
+        assert_eq!(alert_id, Some(7));
+        assert!(yes);
+
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "capture",
+            "--alert-type",
+            "disk-full",
+            "--from-shell-history",
+            "--window",
+            "2h",
+            "--machine",
+            "orko",
+        ]);
+        let Commands::Guardian {
+            command:
+                GuardianCommands::Capture {
+                    from_shell_history,
+                    window,
+                    machine,
+                    ..
+                },
+        } = cli.command
+        else {
+            panic!("Expected guardian capture");
+        };
+        assert!(from_shell_history);
+        assert_eq!(window, Duration::from_secs(7_200));
+        assert_eq!(machine.as_deref(), Some("orko"));
+
+        // Shell history needs a machine; some source of actions is required
+        for args in [
+            &["--from-shell-history"][..],
+            &[][..],
+            &["--actions", "[]", "--from-session", "s"][..],
+        ] {
+            let mut argv = vec!["vc", "guardian", "capture", "--alert-type", "x"];
+            argv.extend_from_slice(args);
+            assert!(Cli::try_parse_from(argv).is_err());
+        }
+    }
+
     #[test]
     fn test_guardian_generate_parse() {
         let cli = Cli::parse_from([
@@ -6377,13 +14138,23 @@ mod tests {
             "guardian",
             "approve-draft",
             "draft-1",
+            "--revision",
+            "2",
             "--approver",
             "admin",
         ]);
         if let Commands::Guardian { command } = cli.command {
-            if let GuardianCommands::ApproveDraft { draft_id, approver } = command {
+            if let GuardianCommands::ApproveDraft {
+                draft_id,
+                revision,
+                approver,
+                sandbox_run,
+            } = command
+            {
                 assert_eq!(draft_id, "draft-1");
-                assert_eq!(approver, "admin");
+                assert_eq!(revision, 2);
+                assert_eq!(approver.as_deref(), Some("admin"));
+                assert!(sandbox_run.is_none());
             } else {
                 panic!("Expected ApproveDraft subcommand");
             }
@@ -6392,6 +14163,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_guardian_run_sandbox_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "run",
+            "tmp-cleanup",
+            "--sandbox",
+            "--machine",
+            "orko",
+            "--at",
+            "2026-10-16T12:00:00Z",
+            "--param",
+            "path=/data/cache",
+            "--param",
+            "days=7",
+        ]);
+        let Commands::Guardian {
+            command:
+                GuardianCommands::Run {
+                    playbook_id,
+                    sandbox,
+                    machine,
+                    at,
+                    params,
+                },
+        } = cli.command
+        else {
+            panic!("Expected guardian run");
+        };
+        assert_eq!(playbook_id, "tmp-cleanup");
+        assert!(sandbox);
+        assert_eq!(machine, "orko");
+        assert_eq!(at.as_deref(), Some("2026-10-16T12:00:00Z"));
+        assert_eq!(params, vec!["path=/data/cache", "days=7"]);
+
+        assert!(Cli::try_parse_from(["vc", "guardian", "run", "x", "--sandbox"]).is_err());
+    }
+
+    #[test]
+    fn test_guardian_approve_draft_requires_revision() {
+        let result = Cli::try_parse_from(["vc", "guardian", "approve-draft", "draft-1"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_guardian_edit_draft_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "edit-draft",
+            "draft-4",
+            "--steps-file",
+            "/tmp/steps.json",
+            "--author",
+            "alice",
+        ]);
+        if let Commands::Guardian { command } = cli.command {
+            if let GuardianCommands::EditDraft {
+                draft_id,
+                steps_file,
+                author,
+            } = command
+            {
+                assert_eq!(draft_id, "draft-4");
+                assert_eq!(steps_file.unwrap(), PathBuf::from("/tmp/steps.json"));
+                assert_eq!(author.as_deref(), Some("alice"));
+            } else {
+                panic!("Expected EditDraft subcommand");
+            }
+        } else {
+            panic!("Expected Guardian command");
+        }
+    }
+
     #[test]
     fn test_guardian_reject_draft_parse() {
         let cli = Cli::parse_from([
@@ -6459,6 +14305,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_guardian_library_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "import",
+            "--from",
+            "playbooks/cleanup.yaml",
+            "--activate",
+        ]);
+        if let Commands::Guardian {
+            command:
+                GuardianCommands::Import {
+                    from,
+                    activate,
+                    overwrite,
+                },
+        } = cli.command
+        {
+            assert_eq!(from, PathBuf::from("playbooks/cleanup.yaml"));
+            assert!(activate);
+            assert!(!overwrite);
+        } else {
+            panic!("Expected guardian import");
+        }
+
+        let cli = Cli::parse_from([
+            "vc",
+            "guardian",
+            "export",
+            "--playbook",
+            "memory-cleanup",
+            "--out",
+            "m.yaml",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Guardian {
+                command: GuardianCommands::Export { ref playbook, .. }
+            } if playbook == "memory-cleanup"
+        ));
+
+        let cli = Cli::parse_from(["vc", "guardian", "lint", "--dir", "playbooks/"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Guardian {
+                command: GuardianCommands::Lint { .. }
+            }
+        ));
+    }
+
     // =============================================================================
     // Commands::Autopilot Tests
     // =============================================================================
@@ -6703,15 +14600,173 @@ mod tests {
             } else {
                 panic!("Expected Migrate subcommand");
             }
-        } else {
-            panic!("Expected Fleet command");
-        }
+        } else {
+            panic!("Expected Fleet command");
+        }
+    }
+
+    #[test]
+    fn test_fleet_versions_parse() {
+        let cli = Cli::parse_from(["vc", "fleet", "versions", "--tool", "sysmoni"]);
+        if let Commands::Fleet { command } = cli.command {
+            if let FleetCommands::Versions { tool } = command {
+                assert_eq!(tool.as_deref(), Some("sysmoni"));
+            } else {
+                panic!("Expected Versions subcommand");
+            }
+        } else {
+            panic!("Expected Fleet command");
+        }
+    }
+
+    #[test]
+    fn test_fleet_clock_parse() {
+        let cli = Cli::parse_from(["vc", "fleet", "clock", "--machine", "orko", "--limit", "5"]);
+        if let Commands::Fleet { command } = cli.command {
+            if let FleetCommands::Clock { machine, limit } = command {
+                assert_eq!(machine.as_deref(), Some("orko"));
+                assert_eq!(limit, 5);
+            } else {
+                panic!("Expected Clock subcommand");
+            }
+        } else {
+            panic!("Expected Fleet command");
+        }
+    }
+
+    #[test]
+    fn test_fleet_apply_parse() {
+        let cli = Cli::parse_from(["vc", "fleet", "apply", "--file", "fleet.yaml", "--dry-run"]);
+        if let Commands::Fleet { command } = cli.command {
+            if let FleetCommands::Apply {
+                file,
+                dry_run,
+                execute,
+            } = command
+            {
+                assert_eq!(file, PathBuf::from("fleet.yaml"));
+                assert!(dry_run);
+                assert!(!execute);
+            } else {
+                panic!("Expected Apply subcommand");
+            }
+        } else {
+            panic!("Expected Fleet command");
+        }
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "fleet",
+                "apply",
+                "--file",
+                "fleet.yaml",
+                "--dry-run",
+                "--execute"
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fleet_resume_and_drift_parse() {
+        let cli = Cli::parse_from(["vc", "fleet", "resume", "fa-1234abcd"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Fleet {
+                command: FleetCommands::Resume { apply_id }
+            } if apply_id == "fa-1234abcd"
+        ));
+        let cli = Cli::parse_from(["vc", "fleet", "drift"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Fleet {
+                command: FleetCommands::Drift
+            }
+        ));
+        let cli = Cli::parse_from([
+            "vc",
+            "fleet",
+            "resources",
+            "--machine",
+            "orko",
+            "--stalled-mins",
+            "45",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Fleet {
+                command: FleetCommands::Resources {
+                    machine: Some(ref m),
+                    stalled_mins: 45,
+                }
+            } if m == "orko"
+        ));
+    }
+
+    #[test]
+    fn test_fleet_run_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "fleet",
+            "run",
+            "--command",
+            "df -h /",
+            "--machines",
+            "tag:builder",
+            "--concurrency",
+            "4",
+            "--fail-fast",
+            "--yes",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Fleet {
+                command: FleetCommands::Run {
+                    ref command,
+                    machines: vc_collect::fleet_run::MachineSelector::Tag(ref tag),
+                    concurrency: 4,
+                    timeout: 15,
+                    fail_fast: true,
+                    yes: true,
+                }
+            } if command == "df -h /" && tag == "builder"
+        ));
+
+        let cli = Cli::parse_from(["vc", "fleet", "run", "--command", "uname -r"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Fleet {
+                command: FleetCommands::Run {
+                    machines: vc_collect::fleet_run::MachineSelector::All,
+                    concurrency: 8,
+                    fail_fast: false,
+                    yes: false,
+                    ..
+                }
+            }
+        ));
+        assert!(Cli::try_parse_from(["vc", "fleet", "run"]).is_err());
+        assert!(
+            Cli::try_parse_from(["vc", "fleet", "run", "--command", "x", "--machines", "tag:"])
+                .is_err()
+        );
     }
 
     // =============================================================================
     // Commands::Vacuum Tests
     // =============================================================================
 
+    #[test]
+    fn test_doctor_parse() {
+        let cli = Cli::parse_from(["vc", "--format", "json", "doctor", "--machine", "orko"]);
+        assert!(matches!(cli.format, OutputFormat::Json));
+        if let Commands::Doctor { machine } = cli.command {
+            assert_eq!(machine.as_deref(), Some("orko"));
+        } else {
+            panic!("Expected Doctor command");
+        }
+    }
+
     #[test]
     fn test_vacuum_parse() {
         let cli = Cli::parse_from(["vc", "vacuum"]);
@@ -6807,6 +14862,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_actor_flag_is_global() {
+        let cli = Cli::parse_from(["vc", "robot", "triage", "--actor", "night-orchestrator"]);
+        assert_eq!(cli.actor.as_deref(), Some("night-orchestrator"));
+
+        let cli = Cli::parse_from(["vc", "audit", "list", "--actor", "night-orchestrator"]);
+        assert_eq!(cli.actor.as_deref(), Some("night-orchestrator"));
+        assert!(matches!(
+            cli.command,
+            Commands::Audit {
+                command: AuditCommands::List { .. }
+            }
+        ));
+
+        // An explicit --by outranks --actor; with neither, the OS user.
+        assert_eq!(command_actor(Some("alice"), Some("bot")).name, "alice");
+        assert_eq!(command_actor(None, Some("bot")).name, "bot");
+        assert_eq!(
+            command_actor(None, None).source,
+            vc_store::ActorSource::OsUser
+        );
+    }
+
     #[test]
     fn test_audit_show_parse() {
         let cli = Cli::parse_from(["vc", "audit", "show", "42"]);
@@ -6851,11 +14929,14 @@ mod tests {
                 table,
                 days,
                 disabled,
+                max_total_bytes,
+                ..
             } = command
             {
                 assert_eq!(table, "sys_samples");
                 assert_eq!(days, 30);
                 assert!(!disabled); // default is not disabled (i.e., enabled)
+                assert!(max_total_bytes.is_none());
             } else {
                 panic!("Expected Retention set");
             }
@@ -6887,6 +14968,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_retention_set_size_limits_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "retention",
+            "set",
+            "--table",
+            "agent_sessions",
+            "--days",
+            "90",
+            "--max-total-bytes",
+            "5000000000",
+            "--max-row-bytes",
+            "1048576",
+            "--keep-tail-bytes",
+            "32768",
+        ]);
+        if let Commands::Retention { command } = cli.command {
+            if let RetentionCommands::Set {
+                max_total_bytes,
+                max_row_bytes,
+                keep_head_bytes,
+                keep_tail_bytes,
+                ..
+            } = command
+            {
+                assert_eq!(max_total_bytes, Some(5_000_000_000));
+                assert_eq!(max_row_bytes, Some(1_048_576));
+                assert_eq!(keep_head_bytes, None);
+                assert_eq!(keep_tail_bytes, Some(32_768));
+            } else {
+                panic!("Expected Retention set");
+            }
+        } else {
+            panic!("Expected Retention command");
+        }
+    }
+
+    #[test]
+    fn test_retention_set_scope_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "retention",
+            "set",
+            "--table",
+            "sys_samples",
+            "--days",
+            "7",
+            "--scope",
+            "tag:builder",
+        ]);
+        if let Commands::Retention { command } = cli.command {
+            if let RetentionCommands::Set { scope, .. } = command {
+                assert_eq!(scope, Some(RetentionScope::Tag("builder".to_string())));
+            } else {
+                panic!("Expected Retention set");
+            }
+        } else {
+            panic!("Expected Retention command");
+        }
+
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "retention",
+                "set",
+                "--table",
+                "sys_samples",
+                "--days",
+                "7",
+                "--scope",
+                "builder",
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn test_retention_history_parse() {
         let cli = Cli::parse_from(["vc", "retention", "history", "--limit", "50"]);
@@ -6901,6 +15059,121 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_retention_hold_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "retention",
+            "hold",
+            "create",
+            "--reason",
+            "postmortem INC-42",
+            "--incident",
+            "inc-42",
+        ]);
+        let Commands::Retention {
+            command:
+                RetentionCommands::Hold {
+                    command:
+                        RetentionHoldCommands::Create {
+                            reason, incident, ..
+                        },
+                },
+        } = cli.command
+        else {
+            panic!("Expected retention hold create");
+        };
+        assert_eq!(reason, "postmortem INC-42");
+        assert_eq!(incident.as_deref(), Some("inc-42"));
+
+        // A hold must cover something, and a time range needs a machine
+        assert!(
+            Cli::try_parse_from(["vc", "retention", "hold", "create", "--reason", "x"]).is_err()
+        );
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "retention",
+                "hold",
+                "create",
+                "--reason",
+                "x",
+                "--session",
+                "s1",
+                "--from",
+                "2026-10-01",
+            ])
+            .is_err()
+        );
+
+        let cli = Cli::parse_from(["vc", "retention", "hold", "release", "hold-1"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Retention {
+                command: RetentionCommands::Hold {
+                    command: RetentionHoldCommands::Release { confirm: false, .. }
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_retention_simulate_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "retention",
+            "simulate",
+            "--table",
+            "agent_sessions",
+            "--days",
+            "30",
+        ]);
+        if let Commands::Retention {
+            command:
+                RetentionCommands::Simulate {
+                    table,
+                    all_tables,
+                    days,
+                    horizon_days,
+                },
+        } = cli.command
+        {
+            assert_eq!(table.as_deref(), Some("agent_sessions"));
+            assert!(!all_tables);
+            assert_eq!(days, 30);
+            assert_eq!(horizon_days, 30);
+        } else {
+            panic!("Expected Retention simulate");
+        }
+
+        let cli = Cli::parse_from(["vc", "retention", "simulate", "--all-tables", "--days", "7"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Retention {
+                command: RetentionCommands::Simulate {
+                    all_tables: true,
+                    ..
+                }
+            }
+        ));
+
+        // One of --table / --all-tables, not both
+        assert!(Cli::try_parse_from(["vc", "retention", "simulate", "--days", "7"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "retention",
+                "simulate",
+                "--table",
+                "t",
+                "--all-tables",
+                "--days",
+                "7",
+            ])
+            .is_err()
+        );
+    }
+
     // =============================================================================
     // Commands::Health Tests
     // =============================================================================
@@ -6969,11 +15242,17 @@ mod tests {
                 machine,
                 collector,
                 limit,
+                by_cause,
+                oversized,
+                window,
             } = command
             {
                 assert_eq!(machine.as_deref(), Some("m1"));
                 assert_eq!(collector.as_deref(), Some("sysmoni"));
                 assert_eq!(limit, 5);
+                assert!(!by_cause);
+                assert!(!oversized);
+                assert_eq!(window, Duration::from_secs(86_400));
             } else {
                 panic!("Expected Health::Collectors");
             }
@@ -6997,11 +15276,15 @@ mod tests {
             if let HealthCommands::Drift {
                 machine,
                 severity,
+                field,
+                json,
                 limit,
             } = command
             {
                 assert!(machine.is_none());
                 assert_eq!(severity.as_deref(), Some("critical"));
+                assert!(field.is_none());
+                assert!(!json);
                 assert_eq!(limit, 10);
             } else {
                 panic!("Expected Health::Drift");
@@ -7011,6 +15294,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_health_drift_field_json_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "health",
+            "drift",
+            "--field",
+            "kernel_version",
+            "--json",
+        ]);
+        if let Commands::Health { command } = cli.command {
+            if let HealthCommands::Drift { field, json, .. } = command {
+                assert_eq!(field.as_deref(), Some("kernel_version"));
+                assert!(json);
+            } else {
+                panic!("Expected Health::Drift");
+            }
+        } else {
+            panic!("Expected Health command");
+        }
+    }
+
+    #[test]
+    fn test_drift_json_lines_oldest_first_with_nulls() {
+        let events = vec![
+            serde_json::json!({"id": 2, "metric": "kernel_version", "previous_value": "6.1.0", "new_value": "6.6.8"}),
+            serde_json::json!({"id": 1, "metric": "mem_pct", "previous_value": null}),
+        ];
+        let lines = drift_json_lines(&events);
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(first["id"], 1);
+        assert!(first["previous_value"].is_null());
+        let second: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(second["field"], "kernel_version");
+        assert_eq!(second["new_value"], "6.6.8");
+    }
+
     #[test]
     fn test_health_baselines_parse() {
         let cli = Cli::parse_from(["vc", "health", "baselines", "--machine", "m1"]);
@@ -7029,8 +15350,16 @@ mod tests {
     fn test_health_score_parse() {
         let cli = Cli::parse_from(["vc", "health", "score"]);
         if let Commands::Health { command } = cli.command {
-            if let HealthCommands::Score { machine } = command {
+            if let HealthCommands::Score {
+                machine,
+                compare,
+                trend,
+                ..
+            } = command
+            {
                 assert!(machine.is_none());
+                assert!(compare.is_none());
+                assert!(trend.is_none());
             } else {
                 panic!("Expected Health::Score");
             }
@@ -7039,18 +15368,184 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_health_rescore_window() {
+        let cli = Cli::parse_from(["vc", "health", "rescore"]);
+        let Commands::Health {
+            command: HealthCommands::Rescore { window },
+        } = cli.command
+        else {
+            panic!("Expected Health::Rescore");
+        };
+        assert_eq!(window, Duration::from_secs(7 * 86_400));
+
+        let cli = Cli::parse_from(["vc", "health", "rescore", "--window", "24h"]);
+        let Commands::Health {
+            command: HealthCommands::Rescore { window },
+        } = cli.command
+        else {
+            panic!("Expected Health::Rescore");
+        };
+        assert_eq!(window, Duration::from_secs(86_400));
+    }
+
     #[test]
     fn test_health_score_with_machine() {
         let cli = Cli::parse_from(["vc", "health", "score", "--machine", "m1"]);
         if let Commands::Health { command } = cli.command {
-            if let HealthCommands::Score { machine } = command {
+            if let HealthCommands::Score { machine, .. } = command {
+                assert_eq!(machine.as_deref(), Some("m1"));
+            } else {
+                panic!("Expected Health::Score");
+            }
+        } else {
+            panic!("Expected Health command");
+        }
+    }
+
+    #[test]
+    fn test_health_score_compare_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "health",
+            "score",
+            "--machine",
+            "m1",
+            "--compare",
+            "7d",
+        ]);
+        if let Commands::Health { command } = cli.command {
+            if let HealthCommands::Score {
+                machine, compare, ..
+            } = command
+            {
                 assert_eq!(machine.as_deref(), Some("m1"));
+                assert_eq!(compare, Some(Duration::from_secs(7 * 86_400)));
             } else {
                 panic!("Expected Health::Score");
             }
         } else {
             panic!("Expected Health command");
         }
+
+        // A comparison needs a machine to compare
+        assert!(Cli::try_parse_from(["vc", "health", "score", "--compare", "7d"]).is_err());
+    }
+
+    #[test]
+    fn test_health_score_trend_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "health",
+            "score",
+            "--machine",
+            "m1",
+            "--trend",
+            "24h",
+            "--sparkline=false",
+        ]);
+        if let Commands::Health {
+            command: HealthCommands::Score {
+                trend, sparkline, ..
+            },
+        } = cli.command
+        {
+            assert_eq!(trend, Some(Duration::from_secs(86_400)));
+            assert_eq!(sparkline, Some(false));
+        } else {
+            panic!("Expected Health::Score");
+        }
+
+        assert!(Cli::try_parse_from(["vc", "health", "score", "--trend", "24h"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "health",
+                "score",
+                "--machine",
+                "m1",
+                "--trend",
+                "1d",
+                "--compare",
+                "7d",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_costs_trend_parse() {
+        let cli = Cli::parse_from(["vc", "costs", "trend"]);
+        if let Commands::Costs {
+            command:
+                CostCommands::Trend {
+                    window,
+                    machine,
+                    sparkline,
+                },
+        } = cli.command
+        {
+            assert_eq!(window, Duration::from_secs(7 * 86_400));
+            assert!(machine.is_none());
+            assert!(sparkline.is_none());
+        } else {
+            panic!("Expected Costs::Trend");
+        }
+    }
+
+    #[test]
+    fn test_cli_run_costs_trend_empty_store() {
+        run_async(async {
+            let result = cli_with_temp_store(&["costs", "trend", "--sparkline"])
+                .run()
+                .await;
+            assert!(result.is_ok(), "{result:?}");
+        });
+    }
+
+    #[test]
+    fn test_health_checks_parse() {
+        let cli = Cli::parse_from(["vc", "health", "checks", "--machine", "orko"]);
+        if let Commands::Health { command } = cli.command {
+            if let HealthCommands::Checks { machine } = command {
+                assert_eq!(machine.as_deref(), Some("orko"));
+            } else {
+                panic!("Expected Health::Checks");
+            }
+        } else {
+            panic!("Expected Health command");
+        }
+    }
+
+    #[test]
+    fn test_health_forecast_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "health",
+            "forecast",
+            "--machine",
+            "orko",
+            "--horizon",
+            "7d",
+        ]);
+        if let Commands::Health { command } = cli.command {
+            if let HealthCommands::Forecast { machine, horizon } = command {
+                assert_eq!(machine.as_deref(), Some("orko"));
+                assert_eq!(horizon, Some(Duration::from_secs(7 * 86_400)));
+            } else {
+                panic!("Expected Health::Forecast");
+            }
+        } else {
+            panic!("Expected Health command");
+        }
+
+        let cli = Cli::parse_from(["vc", "health", "forecast"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Health {
+                command: HealthCommands::Forecast { horizon: None, .. }
+            }
+        ));
     }
 
     // =============================================================================
@@ -7066,6 +15561,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tui")]
     fn test_resolve_tui_options_defaults_to_fullscreen() {
         let config = VcConfig::default();
         let options = resolve_tui_options(&config, false);
@@ -7075,6 +15571,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tui")]
     fn test_resolve_tui_options_uses_config_defaults() {
         let mut config = VcConfig::default();
         config.tui.inline_mode = true;
@@ -7088,6 +15585,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "tui")]
     fn test_resolve_tui_options_cli_inline_overrides_config() {
         let config = VcConfig::default();
         let options = resolve_tui_options(&config, true);
@@ -7120,6 +15618,26 @@ mod tests {
         Cli::parse_from(argv)
     }
 
+    #[test]
+    fn test_duckdb_commands_refuse_a_sqlite_store() {
+        let test_dir = std::env::temp_dir().join(format!("vc-cli-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&test_dir).expect("create temp test dir");
+        let mut config = VcConfig::default();
+        config.global.db_path = test_dir.join("node.sqlite");
+        assert!(require_duckdb_store(&config).is_err());
+
+        // Picked by config as well as by extension
+        config.global.db_path = test_dir.join("node.db");
+        config.global.store_backend = Some("sqlite".to_string());
+        let err = open_duckdb_store(&config).unwrap_err();
+        assert!(err.to_string().contains("only `vc db info`"), "{err}");
+        assert!(!config.global.db_path.exists());
+
+        config.global.store_backend = Some("duckdb".to_string());
+        assert!(require_duckdb_store(&config).is_ok());
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
     #[test]
     fn test_cli_run_robot_health() {
         run_async(async {
@@ -7203,6 +15721,7 @@ mod tests {
                 file,
                 lines,
                 tags,
+                alert_types,
             } = command
             {
                 assert_eq!(entry_type, "solution");
@@ -7213,6 +15732,7 @@ mod tests {
                 assert!(file.is_none());
                 assert!(lines.is_none());
                 assert!(tags.is_none());
+                assert!(alert_types.is_empty());
             } else {
                 panic!("Expected Knowledge add command");
             }
@@ -7243,6 +15763,10 @@ mod tests {
             "10-25",
             "--tags",
             "rust,pattern,collector",
+            "--alert-type",
+            "rate-limit",
+            "--alert-type",
+            "quota",
         ]);
         if let Commands::Knowledge { command } = cli.command {
             if let KnowledgeCommands::Add {
@@ -7253,6 +15777,7 @@ mod tests {
                 file,
                 lines,
                 tags,
+                alert_types,
                 ..
             } = command
             {
@@ -7263,11 +15788,51 @@ mod tests {
                 assert_eq!(file, Some("src/lib.rs".to_string()));
                 assert_eq!(lines, Some("10-25".to_string()));
                 assert_eq!(tags, Some("rust,pattern,collector".to_string()));
+                assert_eq!(alert_types, vec!["rate-limit", "quota"]);
             } else {
                 panic!("Expected Knowledge add command");
             }
         } else {
-            panic!("Expected Knowledge command");
+            panic!("Expected Knowledge command");
+        }
+    }
+
+    #[test]
+    fn test_knowledge_link_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "knowledge",
+            "link",
+            "7",
+            "3",
+            "--relation",
+            "supersedes",
+        ]);
+        if let Commands::Knowledge { command } = cli.command {
+            if let KnowledgeCommands::Link {
+                source,
+                target,
+                relation,
+            } = command
+            {
+                assert_eq!(source, 7);
+                assert_eq!(target, 3);
+                assert_eq!(relation, "supersedes");
+            } else {
+                panic!("Expected Knowledge link command");
+            }
+        } else {
+            panic!("Expected Knowledge command");
+        }
+
+        let cli = Cli::parse_from(["vc", "knowledge", "unlink", "7", "3"]);
+        if let Commands::Knowledge {
+            command: KnowledgeCommands::Unlink { relation, .. },
+        } = cli.command
+        {
+            assert!(relation.is_none());
+        } else {
+            panic!("Expected Knowledge unlink command");
         }
     }
 
@@ -7280,12 +15845,16 @@ mod tests {
                 entry_type,
                 tags,
                 limit,
+                semantic,
+                include_archived,
             } = command
             {
                 assert_eq!(query, "duckdb connection");
                 assert!(entry_type.is_none());
                 assert!(tags.is_none());
                 assert_eq!(limit, 20);
+                assert!(!semantic);
+                assert!(!include_archived);
             } else {
                 panic!("Expected Knowledge search command");
             }
@@ -7307,6 +15876,8 @@ mod tests {
             "ssh,debug",
             "--limit",
             "5",
+            "--semantic",
+            "--include-archived",
         ]);
         if let Commands::Knowledge { command } = cli.command {
             if let KnowledgeCommands::Search {
@@ -7314,12 +15885,16 @@ mod tests {
                 entry_type,
                 tags,
                 limit,
+                semantic,
+                include_archived,
             } = command
             {
                 assert_eq!(query, "ssh");
                 assert_eq!(entry_type, Some("solution".to_string()));
                 assert_eq!(tags, Some("ssh,debug".to_string()));
                 assert_eq!(limit, 5);
+                assert!(semantic);
+                assert!(include_archived);
             } else {
                 panic!("Expected Knowledge search command");
             }
@@ -7458,6 +16033,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sessions_commands_parse() {
+        let cli = Cli::parse_from(["vc", "sessions", "stats", "--group-by", "repo"]);
+        if let Commands::Sessions {
+            command: SessionCommands::Stats { group_by, window },
+        } = cli.command
+        {
+            assert_eq!(group_by, vc_query::SessionGroupBy::Repo);
+            assert_eq!(window, Duration::from_secs(7 * 86_400));
+        } else {
+            panic!("Expected Sessions stats command");
+        }
+
+        let cli = Cli::parse_from(["vc", "sessions", "classify", "--backfill"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Sessions {
+                command: SessionCommands::Classify {
+                    backfill: true,
+                    limit: 1000
+                }
+            }
+        ));
+        assert!(Cli::try_parse_from(["vc", "sessions", "stats", "--group-by", "model"]).is_err());
+    }
+
+    #[test]
+    fn test_repos_activity_parse() {
+        let cli = Cli::parse_from(["vc", "repos", "activity", "--window", "24h"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Repos {
+                command: RepoCommands::Activity {
+                    window,
+                    stale_days: 3
+                }
+            } if window == Duration::from_secs(86_400)
+        ));
+    }
+
     #[test]
     fn test_knowledge_mine_parse() {
         let cli = Cli::parse_from(["vc", "knowledge", "mine"]);
@@ -7506,6 +16121,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_knowledge_review_and_archive_parse() {
+        let cli = Cli::parse_from(["vc", "knowledge", "review", "--limit", "5"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Knowledge {
+                command: KnowledgeCommands::Review { limit: 5 }
+            }
+        ));
+
+        let cli = Cli::parse_from(["vc", "knowledge", "archive", "42"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Knowledge {
+                command: KnowledgeCommands::Archive { id: 42 }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_knowledge_reindex_parse() {
+        let cli = Cli::parse_from(["vc", "knowledge", "reindex"]);
+        if let Commands::Knowledge { command } = cli.command {
+            assert!(matches!(command, KnowledgeCommands::Reindex));
+        } else {
+            panic!("Expected Knowledge command");
+        }
+    }
+
+    #[test]
+    fn test_telemetry_status_parse() {
+        let cli = Cli::parse_from(["vc", "telemetry", "status"]);
+        if let Commands::Telemetry { command } = cli.command {
+            assert!(matches!(command, TelemetryCommands::Status));
+        } else {
+            panic!("Expected Telemetry command");
+        }
+    }
+
     // =============================================================================
     // Commands::Incident Tests
     // =============================================================================
@@ -7514,7 +16168,7 @@ mod tests {
     fn test_incident_list_parse() {
         let cli = Cli::parse_from(["vc", "incident", "list"]);
         if let Commands::Incident { command } = cli.command {
-            if let IncidentCommands::List { status, limit } = command {
+            if let IncidentCommands::List { status, limit, .. } = command {
                 assert!(status.is_none());
                 assert_eq!(limit, 50);
             } else {
@@ -7531,7 +16185,7 @@ mod tests {
             "vc", "incident", "list", "--status", "open", "--limit", "10",
         ]);
         if let Commands::Incident { command } = cli.command {
-            if let IncidentCommands::List { status, limit } = command {
+            if let IncidentCommands::List { status, limit, .. } = command {
                 assert_eq!(status, Some("open".to_string()));
                 assert_eq!(limit, 10);
             } else {
@@ -7556,6 +16210,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_incident_sla_commands_parse() {
+        let cli = Cli::parse_from(["vc", "incident", "list", "--breached"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Incident {
+                command: IncidentCommands::List { breached: true, .. }
+            }
+        ));
+
+        let cli = Cli::parse_from(["vc", "incident", "set-severity", "inc-1", "critical"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Incident {
+                command: IncidentCommands::SetSeverity { ref id, ref severity }
+            } if id == "inc-1" && severity == "critical"
+        ));
+
+        let cli = Cli::parse_from(["vc", "incident", "ack", "inc-1"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Incident {
+                command: IncidentCommands::Ack { by: None, .. }
+            }
+        ));
+
+        let cli = Cli::parse_from([
+            "vc",
+            "incident",
+            "mitigate",
+            "inc-1",
+            "--note",
+            "failed over",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Incident {
+                command: IncidentCommands::Mitigate { note: Some(ref note), .. }
+            } if note == "failed over"
+        ));
+    }
+
     #[test]
     fn test_incident_create_parse() {
         let cli = Cli::parse_from([
@@ -7728,7 +16424,8 @@ mod tests {
     #[test]
     fn test_mcp_serve_parse() {
         let cli = Cli::parse_from(["vc", "mcp", "serve"]);
-        if let Commands::Mcp { command } = cli.command {
+        if let Commands::Mcp { role, command } = cli.command {
+            assert!(role.is_none());
             assert!(matches!(command, McpCommands::Serve));
         } else {
             panic!("Expected Mcp command");
@@ -7738,13 +16435,24 @@ mod tests {
     #[test]
     fn test_mcp_tools_parse() {
         let cli = Cli::parse_from(["vc", "mcp", "tools"]);
-        if let Commands::Mcp { command } = cli.command {
+        if let Commands::Mcp { command, .. } = cli.command {
             assert!(matches!(command, McpCommands::Tools));
         } else {
             panic!("Expected Mcp command");
         }
     }
 
+    #[test]
+    fn test_mcp_role_parse() {
+        let cli = Cli::parse_from(["vc", "mcp", "serve", "--role", "triage"]);
+        if let Commands::Mcp { role, command } = cli.command {
+            assert_eq!(role.as_deref(), Some("triage"));
+            assert!(matches!(command, McpCommands::Serve));
+        } else {
+            panic!("Expected Mcp command");
+        }
+    }
+
     // =============================================================================
     // Commands::Db Tests
     // =============================================================================
@@ -8034,6 +16742,190 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_max_tokens_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "--format",
+            "toon",
+            "robot",
+            "status",
+            "--max-tokens",
+            "500",
+        ]);
+        assert_eq!(cli.max_tokens, Some(500));
+        assert_eq!(
+            cli.chars_per_token,
+            toon::ToonBudget::DEFAULT_CHARS_PER_TOKEN
+        );
+
+        let cli = Cli::parse_from(["vc", "robot", "health", "--chars-per-token", "3"]);
+        assert_eq!(cli.max_tokens, None);
+        assert_eq!(cli.chars_per_token, 3);
+    }
+
+    #[test]
+    fn test_save_report_and_ops_parse() {
+        let cli = Cli::parse_from(["vc", "collect", "--save-report"]);
+        assert!(cli.save_report);
+        assert!(matches!(cli.command, Commands::Collect { .. }));
+
+        let cli = Cli::parse_from(["vc", "ops", "show", "op-1a2b3c4d"]);
+        assert!(!cli.save_report);
+        assert!(matches!(
+            cli.command,
+            Commands::Ops {
+                command: OpsCommands::Show { ref id }
+            } if id == "op-1a2b3c4d"
+        ));
+
+        let cli = Cli::parse_from(["vc", "ops", "list", "--limit", "5"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Ops {
+                command: OpsCommands::List { limit: 5 }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_annotate_and_annotations_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "annotate",
+            "--machine",
+            "orko",
+            "--message",
+            "release 1.42",
+            "--ts",
+            "2026-10-16T14:00:00Z",
+        ]);
+        match cli.command {
+            Commands::Annotate {
+                kind,
+                machine,
+                tag,
+                message,
+                ts,
+                source,
+            } => {
+                assert_eq!(kind, "deploy");
+                assert_eq!(machine.as_deref(), Some("orko"));
+                assert!(tag.is_none());
+                assert_eq!(message, "release 1.42");
+                assert_eq!(ts.as_deref(), Some("2026-10-16T14:00:00Z"));
+                assert_eq!(source, "cli");
+            }
+            other => panic!("Expected Annotate command, got {other:?}"),
+        }
+        assert!(
+            Cli::try_parse_from([
+                "vc",
+                "annotate",
+                "--machine",
+                "orko",
+                "--tag",
+                "gpu",
+                "--message",
+                "x"
+            ])
+            .is_err()
+        );
+
+        let cli = Cli::parse_from([
+            "vc",
+            "annotations",
+            "list",
+            "--kind",
+            "deploy,maintenance",
+            "--since",
+            "24h",
+        ]);
+        match cli.command {
+            Commands::Annotations {
+                command:
+                    AnnotationsCommands::List {
+                        kinds,
+                        since,
+                        until,
+                        limit,
+                        ..
+                    },
+            } => {
+                assert_eq!(kinds, ["deploy", "maintenance"]);
+                assert_eq!(since, "24h");
+                assert!(until.is_none());
+                assert_eq!(limit, 100);
+            }
+            other => panic!("Expected Annotations command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_emit_multi_report_saves_then_fails_on_a_failed_target() {
+        let store = VcStore::open_memory().unwrap();
+        let mut report = MultiMachineReport::new("vc collect");
+        report.push("orko", TargetOutcome::Success, None, None);
+        report.push(
+            "sydney",
+            TargetOutcome::Failed,
+            Some("git: exit 128".to_string()),
+            None,
+        );
+        report.finish();
+
+        let err = emit_multi_report(&store, &report, OutputFormat::Json, true, Some("alice"))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CliError::TargetsFailed {
+                failed: 1,
+                total: 2,
+                ..
+            }
+        ));
+        assert_eq!(err.exit_code(), 1);
+
+        let saved = store
+            .operation_report(&report.operation_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.created_by.as_deref(), Some("alice"));
+        assert_eq!(MultiMachineReport::from_record(&saved).unwrap(), report);
+
+        // Nothing failed: no error, and nothing saved without --save-report
+        let mut ok = MultiMachineReport::new("vc alert ack");
+        ok.push("orko", TargetOutcome::Skipped, None, None);
+        assert!(emit_multi_report(&store, &ok, OutputFormat::Json, false, None).is_ok());
+        assert!(store.operation_report(&ok.operation_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_csv_format_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "--format",
+            "csv",
+            "alert",
+            "list",
+            "--csv-columns",
+            "severity,machine_id",
+        ]);
+        assert!(matches!(cli.format, OutputFormat::Csv));
+        assert_eq!(
+            cli.csv_columns,
+            Some(vec!["severity".to_string(), "machine_id".to_string()])
+        );
+
+        let cli = Cli::parse_from(["vc", "db", "export", "--out", "/tmp/x", "--csv"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Export { csv: true, .. }
+            }
+        ));
+    }
+
     #[test]
     fn test_db_export_parse() {
         let cli = Cli::parse_from([
@@ -8051,12 +16943,14 @@ mod tests {
                 since,
                 until,
                 tables,
+                csv,
             } = command
             {
                 assert_eq!(out, "/tmp/export");
                 assert_eq!(since, Some("2026-01-01".to_string()));
                 assert!(until.is_none());
                 assert!(tables.is_none());
+                assert!(!csv);
             } else {
                 panic!("Expected Db export command");
             }
@@ -8079,16 +16973,254 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_db_import_prometheus_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "db",
+            "import-prometheus",
+            "--url",
+            "http://prom:9090",
+            "--match",
+            "node_.*",
+            "--since",
+            "7d",
+            "--map-file",
+            "mapping.toml",
+            "--dry-run",
+        ]);
+        let Commands::Db {
+            command:
+                DbCommands::ImportPrometheus {
+                    url,
+                    matcher,
+                    since,
+                    step,
+                    map_file,
+                    dry_run,
+                },
+        } = cli.command
+        else {
+            panic!("Expected Db import-prometheus command");
+        };
+        assert_eq!(url, "http://prom:9090");
+        assert_eq!(matcher, "node_.*");
+        assert_eq!(since, Duration::from_secs(7 * 86_400));
+        assert_eq!(step, Duration::from_secs(300));
+        assert_eq!(map_file, PathBuf::from("mapping.toml"));
+        assert!(dry_run);
+    }
+
     #[test]
     fn test_db_info_parse() {
         let cli = Cli::parse_from(["vc", "db", "info"]);
         if let Commands::Db { command } = cli.command {
-            assert!(matches!(command, DbCommands::Info));
+            assert!(matches!(command, DbCommands::Info { detailed: false }));
         } else {
             panic!("Expected Db command");
         }
     }
 
+    #[test]
+    fn test_db_artifacts_parse() {
+        let cli = Cli::parse_from(["vc", "db", "info", "--detailed"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Info { detailed: true }
+            }
+        ));
+        let cli = Cli::parse_from(["vc", "db", "artifacts", "verify", "--download"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Artifacts {
+                    command: ArtifactCommands::Verify { download: true }
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_db_replication_parse() {
+        let cli = Cli::parse_from(["vc", "db", "replication", "status"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Replication {
+                    command: ReplicationCommands::Status
+                }
+            }
+        ));
+
+        let cli = Cli::parse_from(["vc", "db", "promote", "--by", "alice"]);
+        if let Commands::Db {
+            command: DbCommands::Promote { by },
+        } = cli.command
+        {
+            assert_eq!(by.as_deref(), Some("alice"));
+        } else {
+            panic!("Expected db promote");
+        }
+    }
+
+    #[test]
+    fn test_db_slow_queries_parse() {
+        let cli = Cli::parse_from(["vc", "db", "slow-queries", "--top", "5", "--since", "1h"]);
+        if let Commands::Db {
+            command: DbCommands::SlowQueries { top, since },
+        } = cli.command
+        {
+            assert_eq!(top, 5);
+            assert_eq!(since, Some(Duration::from_secs(3_600)));
+        } else {
+            panic!("Expected db slow-queries");
+        }
+
+        let cli = Cli::parse_from(["vc", "db", "slow-queries"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::SlowQueries {
+                    top: 20,
+                    since: None
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_db_analyze_parse() {
+        let cli = Cli::parse_from(["vc", "db", "analyze", "--table", "sys_samples"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Analyze { table: Some(ref t), all: false }
+            } if t == "sys_samples"
+        ));
+
+        let cli = Cli::parse_from(["vc", "db", "analyze"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Analyze {
+                    table: None,
+                    all: false
+                }
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["vc", "db", "analyze", "--all", "--table", "t"]).is_err());
+    }
+
+    #[test]
+    fn test_db_seed_parse() {
+        let cli = Cli::parse_from(["vc", "db", "seed"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Seed {
+                    profile: vc_store::SeedProfile::Demo,
+                    machines: 5,
+                    days: 7,
+                    seed: 42,
+                    force: false,
+                }
+            }
+        ));
+
+        let cli = Cli::parse_from([
+            "vc",
+            "db",
+            "seed",
+            "--profile",
+            "load",
+            "--machines",
+            "50",
+            "--force",
+        ]);
+        assert!(matches!(
+            cli.command,
+            Commands::Db {
+                command: DbCommands::Seed {
+                    profile: vc_store::SeedProfile::Load,
+                    machines: 50,
+                    force: true,
+                    ..
+                }
+            }
+        ));
+
+        assert!(Cli::try_parse_from(["vc", "db", "seed", "--profile", "huge"]).is_err());
+    }
+
+    #[test]
+    fn test_db_correct_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "db",
+            "correct",
+            "--table",
+            "agent_sessions",
+            "--where",
+            "machine_id = 'wrong'",
+            "--set",
+            "machine_id=orko",
+            "--since",
+            "2026-10-01",
+            "--dry-run",
+        ]);
+        if let Commands::Db {
+            command:
+                DbCommands::Correct {
+                    table,
+                    filter,
+                    set,
+                    since,
+                    dry_run,
+                    batch_size,
+                    undo,
+                    ..
+                },
+        } = cli.command
+        {
+            assert_eq!(table.as_deref(), Some("agent_sessions"));
+            assert_eq!(filter.as_deref(), Some("machine_id = 'wrong'"));
+            assert_eq!(set, vec!["machine_id=orko"]);
+            assert_eq!(since.as_deref(), Some("2026-10-01"));
+            assert!(dry_run);
+            assert_eq!(batch_size, vc_store::corrections::DEFAULT_CORRECTION_BATCH);
+            assert_eq!(undo, None);
+        } else {
+            panic!("expected db correct");
+        }
+
+        assert!(Cli::try_parse_from(["vc", "db", "correct", "--undo", "3"]).is_ok());
+        assert!(Cli::try_parse_from(["vc", "db", "correct", "--set", "a=b"]).is_err());
+        assert!(
+            Cli::try_parse_from(["vc", "db", "correct", "--undo", "3", "--table", "t"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_run_db_seed() {
+        run_async(async {
+            let result = cli_with_temp_store(&[
+                "db",
+                "seed",
+                "--profile",
+                "test",
+                "--machines",
+                "2",
+                "--days",
+                "1",
+            ])
+            .run()
+            .await;
+            assert!(result.is_ok(), "{result:?}");
+        });
+    }
+
     // =============================================================================
     // Commands::Profile Tests
     // =============================================================================
@@ -8169,15 +17301,77 @@ mod tests {
     fn test_profile_decisions_parse() {
         let cli = Cli::parse_from(["vc", "profile", "decisions"]);
         if let Commands::Profile { command } = cli.command {
-            if let ProfileCommands::Decisions { machine, limit } = command {
+            if let ProfileCommands::Decisions {
+                machine,
+                limit,
+                explain,
+            } = command
+            {
                 assert!(machine.is_none());
                 assert_eq!(limit, 20);
+                assert!(!explain);
             } else {
                 panic!("Expected Profile decisions command");
             }
         } else {
             panic!("Expected Profile command");
         }
+
+        let cli = Cli::parse_from(["vc", "profile", "decisions", "--explain"]);
+        assert!(matches!(
+            cli.command,
+            Commands::Profile {
+                command: ProfileCommands::Decisions { explain: true, .. }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_profile_why_parse() {
+        let cli = Cli::parse_from(["vc", "profile", "why", "--machine", "orko"]);
+        if let Commands::Profile {
+            command: ProfileCommands::Why { machine, depth },
+        } = cli.command
+        {
+            assert_eq!(machine, "orko");
+            assert_eq!(depth, 5);
+        } else {
+            panic!("Expected Profile why command");
+        }
+        assert!(Cli::try_parse_from(["vc", "profile", "why"]).is_err());
+    }
+
+    #[test]
+    fn test_explain_poll_decision_handles_legacy_rows() {
+        let legacy = serde_json::json!({
+            "decided_at": "2026-10-16 12:00:00",
+            "machine_id": "orko",
+            "collector": "sysmoni",
+            "next_interval_seconds": 60,
+            "reason_json": r#"{"reason":"default"}"#,
+        });
+        let explained = explain_poll_decision(&legacy);
+        assert_eq!(explained["rule"], "default");
+        assert_eq!(explained["explanation"], "60s: no reasoning recorded");
+        assert_eq!(explained["inputs_summary"], serde_json::json!([]));
+
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let mut scheduler = vc_collect::scheduler::AdaptiveScheduler::with_store(
+            vc_collect::scheduler::AdaptiveConfig::default(),
+            store.clone(),
+        );
+        scheduler.record_failure("orko", "sysmoni");
+        scheduler.compute_interval("orko", "sysmoni");
+        let row = &store.list_poll_decisions(Some("orko"), 1).unwrap()[0];
+        let explained = explain_poll_decision(row);
+        assert_eq!(explained["rule"], "failure_backoff");
+        assert_eq!(explained["interval_secs"], 120);
+        assert!(
+            explained["explanation"]
+                .as_str()
+                .unwrap()
+                .contains("backed off after 1 consecutive failures")
+        );
     }
 
     // =============================================================================
@@ -8231,6 +17425,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_node_sign_parse() {
+        let cli = Cli::parse_from([
+            "vc",
+            "node",
+            "sign",
+            "--secret-file",
+            "hub.key",
+            "--body-file",
+            "-",
+            "--timestamp",
+            "1760000000",
+        ]);
+        if let Commands::Node {
+            command:
+                NodeCommands::Sign {
+                    secret_file,
+                    body_file,
+                    timestamp,
+                },
+        } = cli.command
+        {
+            assert_eq!(secret_file, PathBuf::from("hub.key"));
+            assert_eq!(body_file, PathBuf::from("-"));
+            assert_eq!(timestamp, Some(1_760_000_000));
+        } else {
+            panic!("Expected Node sign command");
+        }
+    }
+
     // =============================================================================
     // Commands::Token Tests
     // =============================================================================
@@ -8301,11 +17525,15 @@ mod tests {
             window,
             output,
             save,
+            email,
+            strict,
         } = cli.command
         {
             assert_eq!(window, 24);
             assert_eq!(output, "md");
             assert!(!save);
+            assert!(!email);
+            assert!(!strict);
         } else {
             panic!("Expected Report command");
         }
@@ -8314,17 +17542,20 @@ mod tests {
     #[test]
     fn test_report_parse_weekly_json() {
         let cli = Cli::parse_from([
-            "vc", "report", "--window", "168", "--output", "json", "--save",
+            "vc", "report", "--window", "168", "--output", "json", "--save", "--strict",
         ]);
         if let Commands::Report {
             window,
             output,
             save,
+            strict,
+            ..
         } = cli.command
         {
             assert_eq!(window, 168);
             assert_eq!(output, "json");
             assert!(save);
+            assert!(strict);
         } else {
             panic!("Expected Report command");
         }
@@ -0,0 +1,355 @@
+//! Outcomes of commands that act on several machines
+//!
+//! `vc collect`, `vc fleet apply`/`resume`/`run` and bulk
+//! `vc alert ack`/`resolve` all report through [`MultiMachineReport`]: one
+//! row per target with its outcome, message and duration, the counts, and
+//! the worst outcome, which sets the exit code. The report is printed as a table, JSON
+//! ([`MULTI_MACHINE_SCHEMA`]) or TOON, and `--save-report` keeps it in the
+//! store for `vc ops show <id>`.
+
+use chrono::{SecondsFormat, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::time::Duration;
+use vc_robot::toon::{ToToon, ToonPart, ToonRecord};
+use vc_store::OperationReportRecord;
+
+/// Schema of a multi-machine report, registered in the schema registry
+pub const MULTI_MACHINE_SCHEMA: &str = "vc.ops.report.v1";
+
+/// Longest message kept in a TOON record
+const TOON_MESSAGE_CHARS: usize = 60;
+
+/// What happened on one target. Ordered from best to worst.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetOutcome {
+    Success,
+    /// Not attempted, e.g. in maintenance or after an earlier step failed
+    Skipped,
+    Failed,
+}
+
+impl TargetOutcome {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Skipped => "skipped",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Exit code of a command whose worst outcome this is. A skipped
+    /// target was left alone on purpose, so it does not fail the command.
+    #[must_use]
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::Success | Self::Skipped => 0,
+            Self::Failed => 1,
+        }
+    }
+
+    const fn toon_code(self) -> &'static str {
+        match self {
+            Self::Success => "ok",
+            Self::Skipped => "skip",
+            Self::Failed => "fail",
+        }
+    }
+}
+
+/// One target's outcome
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct TargetResult {
+    /// Machine ID
+    pub target: String,
+    pub outcome: TargetOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+/// Targets per outcome
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct OutcomeCounts {
+    pub total: usize,
+    pub success: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// Per-target outcomes of one multi-machine command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MultiMachineReport {
+    pub schema_version: String,
+    /// `op-` and 8 hex digits; the key `--save-report` stores it under
+    pub operation_id: String,
+    /// e.g. `vc collect`
+    pub command: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub targets: Vec<TargetResult>,
+    pub counts: OutcomeCounts,
+    /// Worst outcome over the targets; `None` when there were none
+    pub worst: Option<TargetOutcome>,
+    /// Command-specific detail, e.g. the alert filter or the apply ID
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub context: serde_json::Value,
+}
+
+impl MultiMachineReport {
+    /// Start a report for `command`, with a fresh operation ID
+    #[must_use]
+    pub fn new(command: &str) -> Self {
+        Self {
+            schema_version: MULTI_MACHINE_SCHEMA.to_string(),
+            operation_id: format!("op-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+            command: command.to_string(),
+            started_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            finished_at: None,
+            targets: Vec::new(),
+            counts: OutcomeCounts::default(),
+            worst: None,
+            context: serde_json::Value::Null,
+        }
+    }
+
+    #[must_use]
+    pub fn with_context(mut self, context: serde_json::Value) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Record one target's outcome
+    pub fn push(
+        &mut self,
+        target: impl Into<String>,
+        outcome: TargetOutcome,
+        message: Option<String>,
+        duration: Option<Duration>,
+    ) {
+        self.counts.total += 1;
+        match outcome {
+            TargetOutcome::Success => self.counts.success += 1,
+            TargetOutcome::Skipped => self.counts.skipped += 1,
+            TargetOutcome::Failed => self.counts.failed += 1,
+        }
+        self.worst = self.worst.max(Some(outcome));
+        self.targets.push(TargetResult {
+            target: target.into(),
+            outcome,
+            message,
+            duration_ms: duration.map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX)),
+        });
+    }
+
+    /// Stamp the finish time; call once every target is in
+    pub fn finish(&mut self) {
+        self.finished_at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true));
+    }
+
+    /// Exit code of the worst outcome
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        self.worst.map_or(0, TargetOutcome::exit_code)
+    }
+
+    /// The report as a table, one row per target, then the counts
+    #[must_use]
+    pub fn render_table(&self) -> String {
+        let width = self
+            .targets
+            .iter()
+            .map(|t| t.target.len())
+            .max()
+            .unwrap_or(0)
+            .max("TARGET".len());
+        let mut out = format!(
+            "{:<width$}  {:<7}  {:>9}  MESSAGE\n",
+            "TARGET", "OUTCOME", "DURATION"
+        );
+        for t in &self.targets {
+            let duration = t
+                .duration_ms
+                .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms"));
+            let _ = writeln!(
+                out,
+                "{:<width$}  {:<7}  {:>9}  {}",
+                t.target,
+                t.outcome.as_str(),
+                duration,
+                t.message.as_deref().unwrap_or("")
+            );
+        }
+        let _ = write!(
+            out,
+            "{}: {} target(s), {} succeeded, {} skipped, {} failed ({})",
+            self.command,
+            self.counts.total,
+            self.counts.success,
+            self.counts.skipped,
+            self.counts.failed,
+            self.operation_id
+        );
+        out
+    }
+
+    /// The row `--save-report` stores
+    #[must_use]
+    pub fn to_record(&self, created_by: Option<&str>) -> OperationReportRecord {
+        OperationReportRecord {
+            operation_id: self.operation_id.clone(),
+            command: self.command.clone(),
+            worst_outcome: self.worst.map(|w| w.as_str().to_string()),
+            targets: i64::try_from(self.counts.total).unwrap_or(i64::MAX),
+            failed: i64::try_from(self.counts.failed).unwrap_or(i64::MAX),
+            report: serde_json::to_value(self).unwrap_or_default(),
+            created_by: created_by.map(ToString::to_string),
+            created_at: self.started_at.clone(),
+        }
+    }
+
+    /// A report saved by `--save-report`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`serde_json::Error`] if the stored JSON is not a report.
+    pub fn from_record(record: &OperationReportRecord) -> Result<Self, serde_json::Error> {
+        Self::deserialize(&record.report)
+    }
+}
+
+/// `TOON1|OP:<id>,<command>,<n>ok<n>skip<n>fail|T:<target>:<outcome>[@<ms>ms][:<message>],...`;
+/// a budget drops successful targets before skipped and failed ones
+impl ToToon for MultiMachineReport {
+    fn toon_parts(&self) -> Vec<ToonPart> {
+        let command = self
+            .command
+            .trim_start_matches("vc ")
+            .replace(char::is_whitespace, "_");
+        let mut parts = vec![ToonPart::Fixed(format!(
+            "OP:{},{command},{}ok{}skip{}fail",
+            self.operation_id, self.counts.success, self.counts.skipped, self.counts.failed
+        ))];
+        parts.push(ToonPart::Records {
+            code: "T",
+            records: self
+                .targets
+                .iter()
+                .map(|t| {
+                    let mut text = format!("{}:{}", toon_safe(&t.target), t.outcome.toon_code());
+                    if let Some(ms) = t.duration_ms {
+                        let _ = write!(text, "@{ms}ms");
+                    }
+                    if let Some(message) = &t.message {
+                        let message: String = message.chars().take(TOON_MESSAGE_CHARS).collect();
+                        let _ = write!(text, ":{}", toon_safe(&message));
+                    }
+                    let priority = match t.outcome {
+                        TargetOutcome::Failed => 0,
+                        TargetOutcome::Skipped => 1,
+                        TargetOutcome::Success => 2,
+                    };
+                    ToonRecord { text, priority }
+                })
+                .collect(),
+        });
+        parts
+    }
+}
+
+/// Keep the TOON separators out of free text
+fn toon_safe(text: &str) -> String {
+    text.replace(['|', ','], ";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> MultiMachineReport {
+        let mut report = MultiMachineReport::new("vc collect");
+        report.push(
+            "orko",
+            TargetOutcome::Success,
+            Some("5 collectors".to_string()),
+            Some(Duration::from_millis(1200)),
+        );
+        report.push(
+            "sydney",
+            TargetOutcome::Failed,
+            Some("sysmoni: timed out, git: exit 128".to_string()),
+            Some(Duration::from_millis(30_000)),
+        );
+        report.push(
+            "bender",
+            TargetOutcome::Skipped,
+            Some("in maintenance".to_string()),
+            None,
+        );
+        report.finish();
+        report
+    }
+
+    #[test]
+    fn test_worst_outcome_sets_counts_and_exit_code() {
+        let report = report();
+        assert_eq!(
+            report.counts,
+            OutcomeCounts {
+                total: 3,
+                success: 1,
+                skipped: 1,
+                failed: 1,
+            }
+        );
+        assert_eq!(report.worst, Some(TargetOutcome::Failed));
+        assert_eq!(report.exit_code(), 1);
+
+        let mut skipped_only = MultiMachineReport::new("vc collect");
+        assert_eq!(skipped_only.exit_code(), 0);
+        skipped_only.push("bender", TargetOutcome::Skipped, None, None);
+        skipped_only.push("orko", TargetOutcome::Success, None, None);
+        assert_eq!(skipped_only.worst, Some(TargetOutcome::Skipped));
+        assert_eq!(skipped_only.exit_code(), 0);
+    }
+
+    #[test]
+    fn test_report_renders_table_json_and_toon() {
+        let report = report();
+        let table = report.render_table();
+        assert!(
+            table.starts_with("TARGET  OUTCOME   DURATION  MESSAGE\n"),
+            "{table}"
+        );
+        assert!(table.contains("sydney  failed     30000ms  sysmoni: timed out"));
+        assert!(table.contains("bender  skipped          -  in maintenance"));
+        assert!(table.ends_with(&format!(
+            "vc collect: 3 target(s), 1 succeeded, 1 skipped, 1 failed ({})",
+            report.operation_id
+        )));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["schema_version"], MULTI_MACHINE_SCHEMA);
+        assert_eq!(json["targets"][1]["outcome"], "failed");
+        assert!(json.get("context").is_none());
+
+        let toon = report.to_toon();
+        assert!(toon.contains(&format!("OP:{},collect,1ok1skip1fail", report.operation_id)));
+        assert!(toon.contains("sydney:fail@30000ms:sysmoni: timed out; git: exit 128"));
+    }
+
+    #[test]
+    fn test_report_round_trips_through_the_store_record() {
+        let report = report().with_context(serde_json::json!({"collector": "git"}));
+        let record = report.to_record(Some("alice"));
+        assert_eq!(record.worst_outcome.as_deref(), Some("failed"));
+        assert_eq!((record.targets, record.failed), (3, 1));
+        assert_eq!(MultiMachineReport::from_record(&record).unwrap(), report);
+    }
+}
@@ -0,0 +1,746 @@
+//! Prometheus history import (feature `prometheus-import`)
+//!
+//! One-shot migration tooling for `vc db import-prometheus`: pull the series
+//! matching `--match` out of a Prometheus server's HTTP API and write them into
+//! the cockpit tables named by a TOML mapping file, so a fleet moving off
+//! node_exporter keeps its history. This is not a live integration.
+//!
+//! Series are fetched with `query_range` at `--step`, so Prometheus does the
+//! downsampling and the store gets one row per machine per step rather than
+//! one per scrape. The window is imported a chunk at a time; after each chunk
+//! the cursor `prometheus_import/<url>|<match>` in `ingestion_cursors` moves
+//! forward, so an interrupted import resumes where it stopped. Rows go
+//! through [`VcStore::upsert_json`], so re-importing a chunk is harmless.
+//!
+//! Mapping file:
+//!
+//! ```toml
+//! # Label naming the machine; its value (or the value without `:port`) is
+//! # looked up in [machines]
+//! machine_label = "instance"
+//!
+//! [machines]
+//! "10.0.0.5:9100" = "orko"
+//! "mac-mini" = "mac-mini"
+//!
+//! [[metric]]
+//! name = "node_load1"
+//! table = "sys_samples"
+//! column = "load1"
+//!
+//! # A metric that needs PromQL to become one value per machine
+//! [[metric]]
+//! name = "node_cpu_seconds_total"
+//! query = '100 * (1 - avg by (instance) (rate(node_cpu_seconds_total{mode="idle"}[5m])))'
+//! table = "sys_samples"
+//! column = "cpu_total"
+//! ```
+//!
+//! Matched metrics no `[[metric]]` claims, and label values no `[machines]`
+//! entry resolves, are listed in the report rather than dropped silently.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use std::time::Duration;
+use thiserror::Error;
+use vc_store::VcStore;
+
+/// `ingestion_cursors` source for import progress
+const CURSOR_SOURCE: &str = "prometheus_import";
+
+/// `ingestion_cursors` machine id for import progress
+const CURSOR_MACHINE: &str = "prometheus";
+
+/// Points Prometheus returns per series per `query_range` call is capped at
+/// 11,000; chunks stay well under that at any sensible step
+const MAX_POINTS_PER_CHUNK: i64 = 10_000;
+
+/// Longest chunk, so progress and the resume cursor move regularly
+const MAX_CHUNK: TimeDelta = TimeDelta::days(1);
+
+/// Import errors
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("mapping file {path}: {message}")]
+    Mapping { path: String, message: String },
+
+    #[error("Prometheus request failed: {0}")]
+    Http(String),
+
+    #[error("Prometheus returned an error: {0}")]
+    Api(String),
+
+    #[error("Store error: {0}")]
+    Store(#[from] vc_store::StoreError),
+}
+
+/// How Prometheus series become cockpit rows
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportMapping {
+    /// Label naming the machine a series belongs to
+    #[serde(default = "default_machine_label")]
+    pub machine_label: String,
+
+    /// Label value (with or without `:port`) to cockpit `machine_id`
+    #[serde(default)]
+    pub machines: HashMap<String, String>,
+
+    #[serde(default, rename = "metric")]
+    pub metrics: Vec<MetricMapping>,
+}
+
+/// One Prometheus metric and the cockpit column it fills
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricMapping {
+    /// Metric name this entry claims
+    pub name: String,
+
+    /// PromQL to run instead of the bare metric name
+    #[serde(default)]
+    pub query: Option<String>,
+
+    /// Target table; it must have `machine_id` and `collected_at` columns
+    pub table: String,
+
+    pub column: String,
+
+    /// Multiplier applied to every value (e.g. 1e-6 for bytes to MB)
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_machine_label() -> String {
+    "instance".to_string()
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl ImportMapping {
+    /// Read and validate a mapping file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError::Mapping`] when the file cannot be read or parsed,
+    /// names a table or column that is not a plain identifier, or maps the
+    /// same column twice.
+    pub fn load(path: &Path) -> Result<Self, ImportError> {
+        let mapping_error = |message: String| ImportError::Mapping {
+            path: path.display().to_string(),
+            message,
+        };
+        let raw = std::fs::read_to_string(path).map_err(|e| mapping_error(e.to_string()))?;
+        let mapping: Self = toml::from_str(&raw).map_err(|e| mapping_error(e.to_string()))?;
+        mapping.validate().map_err(mapping_error)?;
+        Ok(mapping)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.metrics.is_empty() {
+            return Err("no [[metric]] entries".to_string());
+        }
+        let mut targets = BTreeSet::new();
+        for metric in &self.metrics {
+            for ident in [&metric.table, &metric.column] {
+                if !is_identifier(ident) {
+                    return Err(format!(
+                        "{}: '{ident}' is not a valid identifier",
+                        metric.name
+                    ));
+                }
+            }
+            if matches!(metric.column.as_str(), "machine_id" | "collected_at") {
+                return Err(format!(
+                    "{}: {} is set by the importer",
+                    metric.name, metric.column
+                ));
+            }
+            if !metric.scale.is_finite() {
+                return Err(format!("{}: scale must be finite", metric.name));
+            }
+            if !targets.insert((metric.table.as_str(), metric.column.as_str())) {
+                return Err(format!(
+                    "{}.{} is mapped more than once",
+                    metric.table, metric.column
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The cockpit machine a series belongs to, from its labels
+    #[must_use]
+    pub fn machine_for(&self, labels: &HashMap<String, String>) -> Option<&str> {
+        let value = labels.get(&self.machine_label)?;
+        self.machines
+            .get(value)
+            .or_else(|| self.machines.get(strip_port(value)))
+            .map(String::as_str)
+    }
+
+    fn metric(&self, name: &str) -> Option<&MetricMapping> {
+        self.metrics.iter().find(|metric| metric.name == name)
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// `host:9100` to `host`; IPv6 literals keep their brackets
+fn strip_port(value: &str) -> &str {
+    match value.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) && !host.is_empty() => host,
+        _ => value,
+    }
+}
+
+// ============================================================================
+// Prometheus HTTP API
+// ============================================================================
+
+/// One series from a `query_range` response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Series {
+    pub labels: HashMap<String, String>,
+    /// (unix seconds, value)
+    pub points: Vec<(i64, f64)>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    status: String,
+    #[serde(default)]
+    data: Option<T>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MatrixData {
+    #[serde(rename = "resultType")]
+    result_type: String,
+    result: Vec<MatrixSeries>,
+}
+
+#[derive(Deserialize)]
+struct MatrixSeries {
+    metric: HashMap<String, String>,
+    values: Vec<(f64, String)>,
+}
+
+/// Read-only client for the Prometheus HTTP API
+pub struct PrometheusClient {
+    client: reqwest::Client,
+    base: String,
+}
+
+impl PrometheusClient {
+    #[must_use]
+    pub fn new(base: &str, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            base: base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Metric names with series matching `matcher` (a PromQL series selector
+    /// or a regex over `__name__`) between `start` and `end`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError`] when the request fails or Prometheus reports an error.
+    pub async fn metric_names(
+        &self,
+        matcher: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<String>, ImportError> {
+        let url = format!("{}/api/v1/label/__name__/values", self.base);
+        let params = [
+            ("match[]", series_selector(matcher)),
+            ("start", start.timestamp().to_string()),
+            ("end", end.timestamp().to_string()),
+        ];
+        let body = self.get(&url, &params).await?;
+        let mut names: Vec<String> = parse_response(&body)?;
+        names.sort();
+        Ok(names)
+    }
+
+    /// Evaluate `query` at every `step` from `start` to `end`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError`] when the request fails or Prometheus reports an error.
+    pub async fn query_range(
+        &self,
+        query: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        step: Duration,
+    ) -> Result<Vec<Series>, ImportError> {
+        let url = format!("{}/api/v1/query_range", self.base);
+        let params = [
+            ("query", query.to_string()),
+            ("start", start.timestamp().to_string()),
+            ("end", end.timestamp().to_string()),
+            ("step", format!("{}s", step.as_secs())),
+        ];
+        let body = self.get(&url, &params).await?;
+        parse_matrix(&body)
+    }
+
+    async fn get(&self, url: &str, params: &[(&str, String)]) -> Result<String, ImportError> {
+        let response = self
+            .client
+            .get(url)
+            .query(params)
+            .send()
+            .await
+            .map_err(|e| ImportError::Http(e.to_string()))?;
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| ImportError::Http(e.to_string()))?;
+        // Prometheus reports bad queries as 400/422 with a JSON error body
+        if !status.is_success() && !body.trim_start().starts_with('{') {
+            return Err(ImportError::Http(format!("{url} returned {status}")));
+        }
+        Ok(body)
+    }
+}
+
+/// `node_.*` becomes `{__name__=~"node_.*"}`; a selector is passed through
+fn series_selector(matcher: &str) -> String {
+    if matcher.contains('{') {
+        matcher.to_string()
+    } else {
+        format!("{{__name__=~\"{}\"}}", matcher.replace('"', "\\\""))
+    }
+}
+
+fn parse_response<T: serde::de::DeserializeOwned>(body: &str) -> Result<T, ImportError> {
+    let response: ApiResponse<T> = serde_json::from_str(body)
+        .map_err(|e| ImportError::Api(format!("unexpected response: {e}")))?;
+    if response.status != "success" {
+        return Err(ImportError::Api(
+            response.error.unwrap_or_else(|| response.status.clone()),
+        ));
+    }
+    response
+        .data
+        .ok_or_else(|| ImportError::Api("response has no data".to_string()))
+}
+
+fn parse_matrix(body: &str) -> Result<Vec<Series>, ImportError> {
+    let data: MatrixData = parse_response(body)?;
+    if data.result_type != "matrix" {
+        return Err(ImportError::Api(format!(
+            "expected a matrix result, got {}",
+            data.result_type
+        )));
+    }
+    Ok(data
+        .result
+        .into_iter()
+        .map(|series| Series {
+            labels: series.metric,
+            points: series
+                .values
+                .into_iter()
+                // NaN and ±Inf (stale markers, division by zero) are not data
+                .filter_map(|(ts, value)| {
+                    let value: f64 = value.parse().ok()?;
+                    #[allow(clippy::cast_possible_truncation)]
+                    value.is_finite().then_some((ts as i64, value))
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+/// What `vc db import-prometheus` was asked to do
+#[derive(Debug, Clone)]
+pub struct ImportOptions {
+    pub url: String,
+    pub matcher: String,
+    pub since: Duration,
+    pub step: Duration,
+    pub dry_run: bool,
+}
+
+impl ImportOptions {
+    fn cursor_key(&self) -> String {
+        format!("{}|{}", self.url.trim_end_matches('/'), self.matcher)
+    }
+}
+
+/// Where an import got to, per chunk
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportProgress {
+    pub chunk: usize,
+    pub chunks: usize,
+    pub through: DateTime<Utc>,
+    pub rows: u64,
+}
+
+/// Outcome of `vc db import-prometheus`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub url: String,
+    pub matcher: String,
+    pub step_secs: u64,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// Set when an earlier run had already imported up to this point
+    pub resumed_from: Option<DateTime<Utc>>,
+    /// Rows written (or, with `--dry-run`, that would be) per machine and table
+    pub machines: BTreeMap<String, BTreeMap<String, u64>>,
+    pub rows: u64,
+    /// Matched metrics no `[[metric]]` entry claims
+    pub unmapped_metrics: Vec<String>,
+    /// Machine label values no `[machines]` entry resolves
+    pub unmapped_instances: Vec<String>,
+    /// Mapped metrics that had no series in the window
+    pub missing_metrics: Vec<String>,
+}
+
+/// Import the window `--since` ago to now, resuming from the stored cursor.
+/// `progress` is called after each chunk.
+///
+/// # Errors
+///
+/// Returns [`ImportError`] if Prometheus cannot be queried or a write fails.
+/// Chunks written before the failure stay written, and the next run resumes
+/// after them.
+pub async fn run_import(
+    client: &PrometheusClient,
+    store: &VcStore,
+    mapping: &ImportMapping,
+    options: &ImportOptions,
+    mut progress: impl FnMut(&ImportProgress),
+) -> Result<ImportReport, ImportError> {
+    let step_secs = options.step.as_secs().max(1);
+    let step = TimeDelta::seconds(i64::try_from(step_secs).unwrap_or(i64::MAX));
+    let end = align(Utc::now(), step_secs);
+    let window = TimeDelta::from_std(options.since).unwrap_or(TimeDelta::MAX);
+    let mut start = align(end.checked_sub_signed(window).unwrap_or(end), step_secs);
+
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        url: options.url.clone(),
+        matcher: options.matcher.clone(),
+        step_secs,
+        ..ImportReport::default()
+    };
+
+    let cursor_key = options.cursor_key();
+    if let Some(done) = store
+        .get_cursor(CURSOR_MACHINE, CURSOR_SOURCE, &cursor_key)?
+        .and_then(|raw| DateTime::parse_from_rfc3339(&raw).ok())
+        .map(|ts| ts.with_timezone(&Utc))
+        && done > start
+    {
+        report.resumed_from = Some(done);
+        start = done + step;
+    }
+    report.start = Some(start);
+    report.end = Some(end);
+    if start > end {
+        return Ok(report);
+    }
+
+    let names = client.metric_names(&options.matcher, start, end).await?;
+    let mapped: Vec<&MetricMapping> = names
+        .iter()
+        .filter_map(|name| mapping.metric(name))
+        .collect();
+    report.unmapped_metrics = names
+        .iter()
+        .filter(|name| mapping.metric(name).is_none())
+        .cloned()
+        .collect();
+    report.missing_metrics = mapping
+        .metrics
+        .iter()
+        .filter(|metric| !names.contains(&metric.name))
+        .map(|metric| metric.name.clone())
+        .collect();
+    if mapped.is_empty() {
+        return Ok(report);
+    }
+
+    let chunk = chunk_length(step, step_secs);
+    let chunks = chunk_bounds(start, end, chunk, step);
+    let mut unmapped_instances = BTreeSet::new();
+    for (index, &(chunk_start, chunk_end)) in chunks.iter().enumerate() {
+        let mut fetched = Vec::with_capacity(mapped.len());
+        for metric in &mapped {
+            let query = metric.query.as_deref().unwrap_or(&metric.name);
+            let series = client
+                .query_range(query, chunk_start, chunk_end, options.step)
+                .await?;
+            fetched.push((*metric, series));
+        }
+
+        let rows = build_rows(mapping, &fetched, &mut unmapped_instances);
+        let mut chunk_rows = 0;
+        for (table, rows) in &rows {
+            for row in rows {
+                let machine = row["machine_id"].as_str().unwrap_or_default().to_string();
+                *report
+                    .machines
+                    .entry(machine)
+                    .or_default()
+                    .entry(table.clone())
+                    .or_default() += 1;
+            }
+            chunk_rows += u64::try_from(rows.len()).unwrap_or(0);
+            if !options.dry_run {
+                store.upsert_json(table, rows, &["machine_id", "collected_at"])?;
+            }
+        }
+        report.rows += chunk_rows;
+        if !options.dry_run {
+            store.set_cursor(
+                CURSOR_MACHINE,
+                CURSOR_SOURCE,
+                &cursor_key,
+                &chunk_end.to_rfc3339(),
+            )?;
+        }
+        progress(&ImportProgress {
+            chunk: index + 1,
+            chunks: chunks.len(),
+            through: chunk_end,
+            rows: chunk_rows,
+        });
+    }
+    report.unmapped_instances = unmapped_instances.into_iter().collect();
+    Ok(report)
+}
+
+/// Round down to a multiple of `step_secs`, so every series in a chunk lands
+/// on the same timestamps and merges into one row per machine
+fn align(ts: DateTime<Utc>, step_secs: u64) -> DateTime<Utc> {
+    let step = i64::try_from(step_secs).unwrap_or(i64::MAX);
+    let secs = ts.timestamp();
+    DateTime::from_timestamp(secs - secs.rem_euclid(step), 0).unwrap_or(ts)
+}
+
+fn chunk_length(step: TimeDelta, step_secs: u64) -> TimeDelta {
+    let by_points = step * i32::try_from(MAX_POINTS_PER_CHUNK).unwrap_or(i32::MAX);
+    let chunk = by_points.min(MAX_CHUNK);
+    // Whole steps, and at least one
+    let step_secs = i64::try_from(step_secs).unwrap_or(i64::MAX);
+    TimeDelta::seconds((chunk.num_seconds() / step_secs).max(1) * step_secs)
+}
+
+/// Inclusive, non-overlapping `[start, end]` ranges covering the window
+fn chunk_bounds(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    chunk: TimeDelta,
+    step: TimeDelta,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut bounds = Vec::new();
+    let mut from = start;
+    while from <= end {
+        let to = (from + chunk - step).min(end);
+        bounds.push((from, to));
+        from = to + step;
+    }
+    bounds
+}
+
+/// Merge fetched series into rows keyed by table, machine and timestamp, so
+/// every metric mapped to the same table fills columns of the same row
+fn build_rows(
+    mapping: &ImportMapping,
+    fetched: &[(&MetricMapping, Vec<Series>)],
+    unmapped_instances: &mut BTreeSet<String>,
+) -> BTreeMap<String, Vec<serde_json::Value>> {
+    let mut merged: BTreeMap<(&str, &str, i64), serde_json::Map<String, serde_json::Value>> =
+        BTreeMap::new();
+    for (metric, series_list) in fetched {
+        for series in series_list {
+            let Some(machine) = mapping.machine_for(&series.labels) else {
+                unmapped_instances.insert(
+                    series
+                        .labels
+                        .get(&mapping.machine_label)
+                        .cloned()
+                        .unwrap_or_else(|| format!("<no {} label>", mapping.machine_label)),
+                );
+                continue;
+            };
+            for &(ts, value) in &series.points {
+                let row = merged
+                    .entry((metric.table.as_str(), machine, ts))
+                    .or_default();
+                row.insert(
+                    metric.column.clone(),
+                    serde_json::json!(value * metric.scale),
+                );
+            }
+        }
+    }
+
+    let mut rows: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for ((table, machine, ts), mut row) in merged {
+        let Some(collected_at) = DateTime::from_timestamp(ts, 0) else {
+            continue;
+        };
+        row.insert("machine_id".to_string(), machine.into());
+        row.insert("collected_at".to_string(), collected_at.to_rfc3339().into());
+        rows.entry(table.to_string())
+            .or_default()
+            .push(serde_json::Value::Object(row));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = r#"
+        [machines]
+        "10.0.0.5:9100" = "orko"
+        "mac-mini" = "mac-mini"
+
+        [[metric]]
+        name = "node_load1"
+        table = "sys_samples"
+        column = "load1"
+
+        [[metric]]
+        name = "node_memory_MemTotal_bytes"
+        table = "sys_samples"
+        column = "mem_total_bytes"
+    "#;
+
+    fn mapping() -> ImportMapping {
+        let mapping: ImportMapping = toml::from_str(MAPPING).unwrap();
+        mapping.validate().unwrap();
+        mapping
+    }
+
+    fn labels(instance: &str) -> HashMap<String, String> {
+        HashMap::from([("instance".to_string(), instance.to_string())])
+    }
+
+    #[test]
+    fn test_machine_for_tries_with_and_without_port() {
+        let mapping = mapping();
+        assert_eq!(mapping.machine_label, "instance");
+        assert_eq!(mapping.machine_for(&labels("10.0.0.5:9100")), Some("orko"));
+        assert_eq!(
+            mapping.machine_for(&labels("mac-mini:9100")),
+            Some("mac-mini")
+        );
+        assert_eq!(mapping.machine_for(&labels("10.0.0.9:9100")), None);
+        assert_eq!(mapping.machine_for(&HashMap::new()), None);
+        assert_eq!(strip_port("[::1]:9100"), "[::1]");
+        assert_eq!(strip_port("host"), "host");
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_targets() {
+        let bad = |metric: &str| {
+            let mut mapping = mapping();
+            mapping.metrics.push(toml::from_str(metric).unwrap());
+            mapping.validate().unwrap_err()
+        };
+        assert!(
+            bad("name = 'x'\ntable = 'sys_samples; DROP'\ncolumn = 'c'").contains("identifier")
+        );
+        assert!(
+            bad("name = 'x'\ntable = 'sys_samples'\ncolumn = 'collected_at'").contains("importer")
+        );
+        assert!(
+            bad("name = 'x'\ntable = 'sys_samples'\ncolumn = 'load1'").contains("more than once")
+        );
+    }
+
+    #[test]
+    fn test_build_rows_merges_metrics_and_reports_unmapped() {
+        let mapping = mapping();
+        let load = Series {
+            labels: labels("10.0.0.5:9100"),
+            points: vec![(1_700_000_000, 0.5), (1_700_000_300, 0.75)],
+        };
+        let stranger = Series {
+            labels: labels("10.0.0.9:9100"),
+            points: vec![(1_700_000_000, 9.0)],
+        };
+        let mem = Series {
+            labels: labels("10.0.0.5:9100"),
+            points: vec![(1_700_000_000, 16e9)],
+        };
+        let fetched = vec![
+            (&mapping.metrics[0], vec![load, stranger]),
+            (&mapping.metrics[1], vec![mem]),
+        ];
+
+        let mut unmapped = BTreeSet::new();
+        let rows = build_rows(&mapping, &fetched, &mut unmapped);
+        let rows = &rows["sys_samples"];
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["machine_id"], "orko");
+        assert_eq!(rows[0]["load1"], 0.5);
+        assert_eq!(rows[0]["mem_total_bytes"], 16e9);
+        assert!(rows[1].get("mem_total_bytes").is_none());
+        assert_eq!(
+            unmapped.into_iter().collect::<Vec<_>>(),
+            vec!["10.0.0.9:9100"]
+        );
+    }
+
+    #[test]
+    fn test_parse_matrix_drops_non_finite_points() {
+        let body = r#"{"status":"success","data":{"resultType":"matrix","result":[
+            {"metric":{"__name__":"node_load1","instance":"a:9100"},
+             "values":[[1700000000,"0.5"],[1700000300,"NaN"],[1700000600,"+Inf"]]}]}}"#;
+        let series = parse_matrix(body).unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].points, vec![(1_700_000_000, 0.5)]);
+
+        let error = r#"{"status":"error","errorType":"bad_data","error":"parse error"}"#;
+        assert!(matches!(parse_matrix(error), Err(ImportError::Api(msg)) if msg == "parse error"));
+    }
+
+    #[test]
+    fn test_chunks_cover_window_without_overlap() {
+        let step = TimeDelta::minutes(5);
+        let end = align(Utc::now(), 300);
+        let start = end - TimeDelta::days(3) + step;
+        let chunks = chunk_bounds(start, end, chunk_length(step, 300), step);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].0, start);
+        assert_eq!(chunks[2].1, end);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].1 + step, pair[1].0);
+        }
+        assert_eq!(series_selector("node_.*"), r#"{__name__=~"node_.*"}"#);
+        assert_eq!(series_selector(r#"{job="node"}"#), r#"{job="node"}"#);
+    }
+}
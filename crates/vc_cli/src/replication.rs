@@ -0,0 +1,199 @@
+//! Warm standby shipping
+//!
+//! A primary daemon with `[replication] standby_url` set ships new rows to the
+//! standby's `POST /api/replication/batch` every `interval_secs`, independent
+//! of the collection poll interval. The standby's acknowledged watermark is
+//! recorded per table, so a lost response or a rejected table is simply
+//! re-shipped from where the standby actually is.
+
+use std::time::{Duration, Instant};
+use vc_config::ReplicationConfig;
+use vc_store::{ReplicationAck, ReplicationBatch, VcStore};
+
+/// Most shipments per run, so a large backlog does not stall the daemon loop
+const MAX_BATCHES_PER_RUN: usize = 20;
+
+/// Ships rows from a primary to its standby
+pub struct ReplicationShipper {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+    signing_secret: Option<String>,
+    source: String,
+    interval: Duration,
+    batch_size: usize,
+    tables: Vec<String>,
+    last_run: Option<Instant>,
+}
+
+impl ReplicationShipper {
+    /// Build the shipper, or `None` when no standby is configured or the
+    /// token's or signing secret's reference does not resolve (logged)
+    #[must_use]
+    pub fn from_config(config: &ReplicationConfig) -> Option<Self> {
+        let base = config.standby_url.as_deref()?;
+        let token = config
+            .token
+            .as_deref()
+            .map(|token| vc_config::resolve_secret("replication.token", token))
+            .transpose()
+            .inspect_err(|e| tracing::warn!(error = %e, "replication shipping disabled"))
+            .ok()?;
+        let signing_secret = config
+            .signing_secret
+            .as_deref()
+            .map(|secret| vc_config::resolve_secret("replication.signing_secret", secret))
+            .transpose()
+            .inspect_err(|e| tracing::warn!(error = %e, "replication shipping disabled"))
+            .ok()?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            client,
+            url: batch_url(base),
+            token,
+            signing_secret,
+            source: std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string()),
+            interval: Duration::from_secs(config.interval_secs.max(1)),
+            batch_size: config.batch_size.max(1),
+            tables: config.tables.clone(),
+            last_run: None,
+        })
+    }
+
+    /// Time between shipments
+    #[must_use]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Ship new rows if the interval has elapsed; failures are logged and
+    /// recorded against each table, never returned
+    pub async fn run_if_due(&mut self, store: &VcStore) {
+        if self
+            .last_run
+            .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return;
+        }
+        self.last_run = Some(Instant::now());
+
+        let mut shipped = 0;
+        for _ in 0..MAX_BATCHES_PER_RUN {
+            let batch =
+                match store.build_replication_batch(&self.source, &self.tables, self.batch_size) {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "failed to build replication batch");
+                        return;
+                    }
+                };
+            if batch.tables.is_empty() {
+                break;
+            }
+
+            match self.send(&batch).await {
+                Ok(ack) => {
+                    if let Err(e) = store.record_replication_ack(&batch, &ack) {
+                        tracing::warn!(error = %e, "failed to record replication ack");
+                        return;
+                    }
+                    let mut rejected = false;
+                    for table in ack.tables.iter().filter(|t| t.error.is_some()) {
+                        tracing::warn!(
+                            table = %table.table,
+                            error = table.error.as_deref().unwrap_or_default(),
+                            "standby rejected replicated rows"
+                        );
+                        rejected = true;
+                    }
+                    shipped += batch.row_count();
+                    // Retry from the standby's watermark on the next run
+                    if rejected {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "replication shipment failed");
+                    if let Err(e) = store.record_replication_failure(&batch, &e) {
+                        tracing::warn!(error = %e, "failed to record replication failure");
+                    }
+                    return;
+                }
+            }
+            if !batch.has_more() {
+                break;
+            }
+        }
+        if shipped > 0 {
+            tracing::debug!(rows = shipped, "replicated to standby");
+        }
+    }
+
+    async fn send(&self, batch: &ReplicationBatch) -> Result<ReplicationAck, String> {
+        let body = serde_json::to_vec(batch)
+            .map_err(|e| format!("failed to encode replication batch: {e}"))?;
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(secret) = &self.signing_secret {
+            let timestamp = chrono::Utc::now().timestamp();
+            request = request
+                .header(vc_types::signing::TIMESTAMP_HEADER, timestamp)
+                .header(
+                    vc_types::signing::SIGNATURE_HEADER,
+                    vc_types::signing::sign(secret.as_bytes(), timestamp, &body),
+                );
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("replication request failed: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!("standby returned {}", response.status()));
+        }
+        response
+            .json::<ReplicationAck>()
+            .await
+            .map_err(|e| format!("invalid replication ack: {e}"))
+    }
+}
+
+/// Batch endpoint under a standby's base URL
+fn batch_url(base: &str) -> String {
+    format!("{}/api/replication/batch", base.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config_requires_standby_url() {
+        let mut config = ReplicationConfig::default();
+        assert!(ReplicationShipper::from_config(&config).is_none());
+
+        config.standby_url = Some("http://standby:8080/".to_string());
+        let shipper = ReplicationShipper::from_config(&config).unwrap();
+        assert_eq!(shipper.url, "http://standby:8080/api/replication/batch");
+        assert_eq!(shipper.interval(), Duration::from_secs(15));
+
+        config.token = Some("secret://cmd/echo op-token".to_string());
+        let shipper = ReplicationShipper::from_config(&config).unwrap();
+        assert_eq!(shipper.token.as_deref(), Some("op-token"));
+        config.token = Some("secret://env/VC_TEST_STANDBY_TOKEN_UNSET".to_string());
+        assert!(ReplicationShipper::from_config(&config).is_none());
+
+        config.token = None;
+        config.signing_secret = Some("secret://cmd/echo sign-key".to_string());
+        let shipper = ReplicationShipper::from_config(&config).unwrap();
+        assert_eq!(shipper.signing_secret.as_deref(), Some("sign-key"));
+    }
+}
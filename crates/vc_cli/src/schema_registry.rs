@@ -4,7 +4,23 @@
 //! - Schema loading from docs/schemas/
 //! - Validation helpers for robot output
 //! - Schema listing for documentation
-
+//! - JSON Schema generation from the robot payload types and the
+//!   multi-machine report, and a check that committed schemas match what
+//!   `vc` actually emits
+//!
+//! # Versioning rule
+//!
+//! A generated schema is keyed by its envelope version (`vc.robot.health.v1`).
+//! Once committed, that file is frozen: a change to the payload that alters
+//! the schema must ship under a new version string. [`export_schemas`] refuses
+//! to overwrite a committed version and [`check_schemas`] fails on one that
+//! has drifted.
+
+use crate::multi_report::{MULTI_MACHINE_SCHEMA, MultiMachineReport};
+use crate::robot::{
+    AccountsData, HealthData, OracleData, ReposData, RobotEnvelope, StatusData, TriageData,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -60,12 +76,26 @@ impl Default for SchemaIndex {
                     command: "vc robot status".to_string(),
                 },
                 SchemaEntry {
-                    id: "vc.robot.triage.v1".to_string(),
+                    id: "vc.robot.triage.v4".to_string(),
                     file: "robot-triage.json".to_string(),
                     title: "Triage Data".to_string(),
-                    description: "Triage recommendations".to_string(),
+                    description: "Ranked triage actions".to_string(),
                     command: "vc robot triage".to_string(),
                 },
+                SchemaEntry {
+                    id: crate::watch::ENRICHED_ALERT_SCHEMA.to_string(),
+                    file: "watch-alert-enriched.json".to_string(),
+                    title: "Enriched Watch Alert".to_string(),
+                    description: "Alert event with machine health, occurrence, playbook and knowledge context".to_string(),
+                    command: "vc watch --enrich".to_string(),
+                },
+                SchemaEntry {
+                    id: MULTI_MACHINE_SCHEMA.to_string(),
+                    file: "ops-report.json".to_string(),
+                    title: "Multi-Machine Report".to_string(),
+                    description: "Per-target outcomes of a command that acted on several machines".to_string(),
+                    command: "vc collect, vc fleet apply --execute, vc fleet resume, vc alert ack, vc alert resolve, vc ops show".to_string(),
+                },
             ],
         }
     }
@@ -221,6 +251,231 @@ pub fn validate_envelope_fields(json: &str) -> Result<(), Vec<String>> {
     }
 }
 
+// ============================================================================
+// Schema generation
+// ============================================================================
+
+/// Default directory for committed generated schemas, relative to the project
+/// root
+pub const GENERATED_SCHEMAS_DIR: &str = "docs/schemas/generated";
+
+/// A JSON Schema generated for one envelope version
+#[derive(Debug, Clone)]
+pub struct GeneratedSchema {
+    /// Envelope version (e.g., "vc.robot.health.v1")
+    pub id: String,
+    /// The schema document
+    pub schema: serde_json::Value,
+}
+
+impl GeneratedSchema {
+    /// Filename the schema is written under
+    #[must_use]
+    pub fn file_name(&self) -> String {
+        format!("{}.json", self.id)
+    }
+
+    /// Serialized form written to disk; stable so diffs stay byte-exact
+    #[must_use]
+    pub fn to_file_contents(&self) -> String {
+        let mut out = serde_json::to_string_pretty(&self.schema).unwrap_or_default();
+        out.push('\n');
+        out
+    }
+}
+
+fn envelope_schema<T: Serialize + JsonSchema>(id: &str) -> GeneratedSchema {
+    let mut schema =
+        serde_json::to_value(schemars::schema_for!(RobotEnvelope<T>)).unwrap_or_default();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("title".to_string(), serde_json::Value::from(id));
+    }
+    // Pin the version field so a payload can only validate against the schema
+    // for the version it claims.
+    if let Some(field) = schema.pointer_mut("/properties/schema_version") {
+        *field = serde_json::json!({ "type": "string", "const": id });
+    }
+    GeneratedSchema {
+        id: id.to_string(),
+        schema,
+    }
+}
+
+/// Schema of a payload printed bare rather than in a robot envelope, with
+/// its own `schema_version` field pinned like an envelope's
+fn payload_schema<T: JsonSchema>(id: &str) -> GeneratedSchema {
+    let mut schema = serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default();
+    if let Some(object) = schema.as_object_mut() {
+        object.insert("title".to_string(), serde_json::Value::from(id));
+    }
+    if let Some(field) = schema.pointer_mut("/properties/schema_version") {
+        *field = serde_json::json!({ "type": "string", "const": id });
+    }
+    GeneratedSchema {
+        id: id.to_string(),
+        schema,
+    }
+}
+
+/// Generate every schema `vc` commits: the robot envelopes and the
+/// multi-machine report
+#[must_use]
+pub fn generate_schemas() -> Vec<GeneratedSchema> {
+    let mut schemas = generate_envelope_schemas();
+    schemas.push(payload_schema::<MultiMachineReport>(MULTI_MACHINE_SCHEMA));
+    schemas
+}
+
+/// Generate schemas for every envelope version `vc robot` emits
+#[must_use]
+pub fn generate_envelope_schemas() -> Vec<GeneratedSchema> {
+    vec![
+        envelope_schema::<HealthData>("vc.robot.health.v1"),
+        envelope_schema::<TriageData>("vc.robot.triage.v4"),
+        envelope_schema::<StatusData>("vc.robot.status.v1"),
+        envelope_schema::<AccountsData>("vc.robot.accounts.v1"),
+        envelope_schema::<ReposData>("vc.robot.repos.v1"),
+        envelope_schema::<OracleData>("vc.robot.oracle.v1"),
+    ]
+}
+
+/// How a generated schema compares to the committed directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaDrift {
+    /// Committed and identical
+    Unchanged,
+    /// Not committed yet; export and commit it
+    New,
+    /// Committed but the payload changed; the envelope version must be bumped
+    Modified,
+}
+
+/// Per-version result of comparing generated schemas to a directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaCheckEntry {
+    /// Envelope version
+    pub id: String,
+    /// Filename within the directory
+    pub file: String,
+    /// Comparison result
+    pub drift: SchemaDrift,
+}
+
+/// Output for `vc robot schema check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaCheckReport {
+    /// Directory compared against
+    pub dir: String,
+    /// One entry per generated envelope version
+    pub entries: Vec<SchemaCheckEntry>,
+    /// Committed schema files no longer generated (retired versions)
+    pub retired: Vec<String>,
+}
+
+impl SchemaCheckReport {
+    /// Whether the committed directory matches the generated schemas
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|entry| entry.drift == SchemaDrift::Unchanged)
+    }
+}
+
+/// Compare freshly generated schemas against a committed directory
+///
+/// Retired versions (files with no generator) are reported but are not drift:
+/// keeping old versions around is how consumers of a previous version keep
+/// validating.
+///
+/// # Errors
+///
+/// Returns [`std::io::Error`] if the directory or a schema file in it cannot
+/// be read.
+pub fn check_schemas(dir: &Path) -> Result<SchemaCheckReport, std::io::Error> {
+    let generated = generate_schemas();
+    let mut entries = Vec::with_capacity(generated.len());
+    for schema in &generated {
+        let file = schema.file_name();
+        let path = dir.join(&file);
+        let drift = if path.exists() {
+            if std::fs::read_to_string(&path)? == schema.to_file_contents() {
+                SchemaDrift::Unchanged
+            } else {
+                SchemaDrift::Modified
+            }
+        } else {
+            SchemaDrift::New
+        };
+        entries.push(SchemaCheckEntry {
+            id: schema.id.clone(),
+            file,
+            drift,
+        });
+    }
+
+    let mut retired = Vec::new();
+    if dir.exists() {
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if name.ends_with(".json") && !entries.iter().any(|e| e.file == name) {
+                retired.push(name);
+            }
+        }
+    }
+    retired.sort();
+
+    Ok(SchemaCheckReport {
+        dir: dir.display().to_string(),
+        entries,
+        retired,
+    })
+}
+
+/// Write one schema file per envelope version into `dir`
+///
+/// New versions are written; unchanged ones are left alone. A committed
+/// version whose schema would change is refused unless `force` is set, which
+/// is only meant for versions that have never been released.
+///
+/// # Errors
+///
+/// Returns [`std::io::Error`] if the directory cannot be created or written,
+/// or with [`std::io::ErrorKind::InvalidData`] naming the versions whose
+/// schemas changed without a version bump.
+pub fn export_schemas(dir: &Path, force: bool) -> Result<SchemaCheckReport, std::io::Error> {
+    let report = check_schemas(dir)?;
+    let modified: Vec<&str> = report
+        .entries
+        .iter()
+        .filter(|entry| entry.drift == SchemaDrift::Modified)
+        .map(|entry| entry.id.as_str())
+        .collect();
+    if !force && !modified.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "schema changed for existing version(s) {}; bump the envelope version instead",
+                modified.join(", ")
+            ),
+        ));
+    }
+
+    std::fs::create_dir_all(dir)?;
+    for schema in generate_schemas() {
+        let drift = report
+            .entries
+            .iter()
+            .find(|entry| entry.id == schema.id)
+            .map_or(SchemaDrift::New, |entry| entry.drift);
+        if drift != SchemaDrift::Unchanged {
+            std::fs::write(dir.join(schema.file_name()), schema.to_file_contents())?;
+        }
+    }
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,7 +493,13 @@ mod tests {
         let schemas = registry.list_schemas();
         assert!(schemas.contains(&"vc.robot.health.v1"));
         assert!(schemas.contains(&"vc.robot.status.v1"));
-        assert!(schemas.contains(&"vc.robot.triage.v1"));
+        assert!(schemas.contains(&"vc.robot.triage.v4"));
+        assert_eq!(
+            registry
+                .find_entry("vc.watch.alert_enriched.v1")
+                .map(|entry| entry.file.as_str()),
+            Some("watch-alert-enriched.json")
+        );
     }
 
     #[test]
@@ -288,6 +549,77 @@ mod tests {
         assert!(errors.iter().any(|e| e.contains("data")));
     }
 
+    #[test]
+    fn test_generated_schemas_pin_version() {
+        let schemas = generate_envelope_schemas();
+        assert_eq!(schemas.len(), 6);
+        for schema in &schemas {
+            assert!(
+                validate_schema_version(&format!(r#"{{"schema_version": "{}"}}"#, schema.id))
+                    .is_ok()
+            );
+            assert_eq!(
+                schema.schema.pointer("/properties/schema_version/const"),
+                Some(&serde_json::Value::from(schema.id.as_str()))
+            );
+            assert!(schema.schema.pointer("/properties/data").is_some());
+        }
+    }
+
+    #[test]
+    fn test_multi_machine_report_schema_is_generated_and_registered() {
+        let schema = generate_schemas()
+            .into_iter()
+            .find(|schema| schema.id == MULTI_MACHINE_SCHEMA)
+            .unwrap();
+        assert_eq!(
+            schema.schema.pointer("/properties/schema_version/const"),
+            Some(&serde_json::Value::from(MULTI_MACHINE_SCHEMA))
+        );
+        assert!(schema.schema.pointer("/properties/targets").is_some());
+        assert!(
+            SchemaRegistry::new("/tmp")
+                .find_entry(MULTI_MACHINE_SCHEMA)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_export_then_check_is_clean() {
+        let dir = tempfile::tempdir().unwrap();
+        let report = check_schemas(dir.path()).unwrap();
+        assert!(!report.is_clean());
+        assert!(
+            report
+                .entries
+                .iter()
+                .all(|entry| entry.drift == SchemaDrift::New)
+        );
+
+        export_schemas(dir.path(), false).unwrap();
+        let report = check_schemas(dir.path()).unwrap();
+        assert!(report.is_clean());
+        assert!(report.retired.is_empty());
+    }
+
+    #[test]
+    fn test_modified_version_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        export_schemas(dir.path(), false).unwrap();
+        std::fs::write(dir.path().join("vc.robot.health.v1.json"), "{}\n").unwrap();
+        std::fs::write(dir.path().join("vc.robot.health.v0.json"), "{}\n").unwrap();
+
+        let report = check_schemas(dir.path()).unwrap();
+        assert!(!report.is_clean());
+        assert_eq!(report.retired, vec!["vc.robot.health.v0.json".to_string()]);
+
+        let err = export_schemas(dir.path(), false).unwrap_err();
+        assert!(err.to_string().contains("vc.robot.health.v1"));
+
+        export_schemas(dir.path(), true).unwrap();
+        assert!(check_schemas(dir.path()).unwrap().is_clean());
+    }
+
     #[test]
     fn test_robot_docs_schemas() {
         let output = robot_docs_schemas("/tmp/project");
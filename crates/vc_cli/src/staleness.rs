@@ -0,0 +1,412 @@
+//! Stale-collector alerting
+//!
+//! Every daemon cycle the freshness of each (machine, collector) pair is
+//! compared against `[alerts.staleness]`:
+//!
+//! - A fresh pair whose last successful run is older than its threshold
+//!   raises a warning (`collector-stale:<collector>`).
+//! - Past `critical_multiplier` thresholds the warning is replaced by a
+//!   critical alert (`collector-stale-critical:<collector>`).
+//! - Both resolve as soon as the collector succeeds again.
+//!
+//! A pair that resolved less than `min_resolved_secs` ago is held back from
+//! firing again, so machines with flaky SSH links do not page on every blip.
+//! Pairs that have never succeeded are left to `vc health freshness`: they
+//! were never fresh to begin with.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::HashMap;
+use vc_config::StalenessAlertConfig;
+use vc_store::{FiredAlert, FreshnessSummary, VcStore};
+
+/// Alert rule prefix for stale pairs; the collector name follows a `:`
+pub const STALE_ALERT_RULE: &str = "collector-stale";
+
+/// Alert rule prefix for pairs stale past the critical multiplier
+pub const STALE_CRITICAL_ALERT_RULE: &str = "collector-stale-critical";
+
+/// How stale a (machine, collector) pair is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StaleLevel {
+    Fresh,
+    Stale,
+    Critical,
+}
+
+impl StaleLevel {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fresh => "fresh",
+            Self::Stale => "stale",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+/// A pair that changed level on the last [`StalenessMonitor::evaluate`] call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleChange {
+    pub machine_id: String,
+    pub collector: String,
+    pub from: StaleLevel,
+    pub to: StaleLevel,
+    pub freshness_seconds: i64,
+    pub threshold_secs: i64,
+    pub last_success_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PairState {
+    level: StaleLevel,
+    resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Tracks the staleness level of every pair across cycles
+#[derive(Debug, Clone)]
+pub struct StalenessMonitor {
+    config: StalenessAlertConfig,
+    pairs: HashMap<(String, String), PairState>,
+}
+
+impl StalenessMonitor {
+    #[must_use]
+    pub fn new(config: StalenessAlertConfig) -> Self {
+        Self {
+            config,
+            pairs: HashMap::new(),
+        }
+    }
+
+    /// Staleness threshold of `collector`, in seconds
+    #[must_use]
+    pub fn threshold(&self, collector: &str) -> i64 {
+        i64::try_from(self.config.threshold_for(collector)).unwrap_or(i64::MAX)
+    }
+
+    /// Level `summary` is at, or `None` for a pair that never succeeded
+    #[must_use]
+    pub fn level_for(&self, summary: &FreshnessSummary) -> Option<StaleLevel> {
+        if summary.last_success_at.is_none() || summary.freshness_seconds < 0 {
+            return None;
+        }
+        let threshold = self.threshold(&summary.collector);
+        let critical = threshold.saturating_mul(i64::from(self.config.critical_multiplier));
+        Some(if summary.freshness_seconds > critical {
+            StaleLevel::Critical
+        } else if summary.freshness_seconds > threshold {
+            StaleLevel::Stale
+        } else {
+            StaleLevel::Fresh
+        })
+    }
+
+    /// Fold a freshness report into the monitor and return the pairs whose
+    /// alert state changed.
+    ///
+    /// Levels only move up until the pair is fresh again; a pair first seen
+    /// stale counts as having been fresh before, since it did succeed once.
+    pub fn evaluate(
+        &mut self,
+        summaries: &[FreshnessSummary],
+        now: DateTime<Utc>,
+    ) -> Vec<StaleChange> {
+        let min_resolved = chrono::Duration::seconds(
+            i64::try_from(self.config.min_resolved_secs).unwrap_or(i64::MAX),
+        );
+        let mut changes = Vec::new();
+
+        for summary in summaries {
+            let Some(target) = self.level_for(summary) else {
+                continue;
+            };
+            let state = self
+                .pairs
+                .entry((summary.machine_id.clone(), summary.collector.clone()))
+                .or_insert(PairState {
+                    level: StaleLevel::Fresh,
+                    resolved_at: None,
+                });
+
+            let from = state.level;
+            let to = match (from, target) {
+                (StaleLevel::Fresh, StaleLevel::Fresh) => continue,
+                (StaleLevel::Fresh, _) => {
+                    // Flapping guard: stay quiet until the pair has been
+                    // resolved long enough.
+                    if state
+                        .resolved_at
+                        .is_some_and(|resolved| now - resolved < min_resolved)
+                    {
+                        continue;
+                    }
+                    target
+                }
+                (_, StaleLevel::Fresh) => {
+                    state.resolved_at = Some(now);
+                    StaleLevel::Fresh
+                }
+                (StaleLevel::Stale, StaleLevel::Critical) => StaleLevel::Critical,
+                _ => continue,
+            };
+            state.level = to;
+
+            changes.push(StaleChange {
+                machine_id: summary.machine_id.clone(),
+                collector: summary.collector.clone(),
+                from,
+                to,
+                freshness_seconds: summary.freshness_seconds,
+                threshold_secs: i64::try_from(self.config.threshold_for(&summary.collector))
+                    .unwrap_or(i64::MAX),
+                last_success_at: summary.last_success_at.clone(),
+            });
+        }
+
+        changes
+    }
+
+    /// Evaluate the store's freshness report and raise or resolve alerts.
+    pub fn check(&mut self, store: &VcStore) {
+        if !self.config.enabled {
+            return;
+        }
+        let threshold = i64::try_from(self.config.stale_threshold_secs).unwrap_or(i64::MAX);
+        let mut summaries = match store.get_freshness_summaries(None, threshold) {
+            Ok(summaries) => summaries,
+            Err(e) => {
+                tracing::warn!(error = %e, "freshness check failed");
+                return;
+            }
+        };
+        // Collectors are not run against machines in maintenance, so their
+        // data going stale is expected, not alertable.
+        let in_maintenance: Vec<String> = store
+            .active_maintenance()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|window| window.machine_id)
+            .collect();
+        summaries.retain(|summary| !in_maintenance.contains(&summary.machine_id));
+        for change in self.evaluate(&summaries, Utc::now()) {
+            record_change(store, &change);
+        }
+    }
+}
+
+/// Alert rule id of `collector` at `level`
+#[must_use]
+pub fn rule_id(level: StaleLevel, collector: &str) -> String {
+    let prefix = if level == StaleLevel::Critical {
+        STALE_CRITICAL_ALERT_RULE
+    } else {
+        STALE_ALERT_RULE
+    };
+    format!("{prefix}:{collector}")
+}
+
+/// Raise or resolve the alerts for one change.
+///
+/// Raised alerts carry the last successful run and the last recorded error
+/// so triage does not need a second lookup.
+pub fn record_change(store: &VcStore, change: &StaleChange) {
+    let warning_rule = rule_id(StaleLevel::Stale, &change.collector);
+    let critical_rule = rule_id(StaleLevel::Critical, &change.collector);
+
+    if change.to == StaleLevel::Fresh {
+        tracing::info!(
+            machine = %change.machine_id,
+            collector = %change.collector,
+            "collector fresh again"
+        );
+        resolve(store, &warning_rule, &change.machine_id);
+        resolve(store, &critical_rule, &change.machine_id);
+        return;
+    }
+
+    tracing::warn!(
+        machine = %change.machine_id,
+        collector = %change.collector,
+        level = change.to.as_str(),
+        freshness_seconds = change.freshness_seconds,
+        "collector stale"
+    );
+
+    let (rule, severity) = if change.to == StaleLevel::Critical {
+        resolve(store, &warning_rule, &change.machine_id);
+        (critical_rule, "critical")
+    } else {
+        (warning_rule, "warning")
+    };
+
+    match store.has_open_alert(&rule, Some(&change.machine_id)) {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => {
+            tracing::warn!(rule = %rule, error = %e, "stale alert lookup failed");
+            return;
+        }
+    }
+
+    let last_error = store
+        .last_collector_error(&change.machine_id, &change.collector)
+        .unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "last collector error lookup failed");
+            None
+        });
+    let context = serde_json::json!({
+        "collector": change.collector,
+        "freshness_seconds": change.freshness_seconds,
+        "threshold_secs": change.threshold_secs,
+        "last_success_at": change.last_success_at,
+        "last_error_at": last_error.as_ref().map(|(at, _)| at),
+        "last_error": last_error.and_then(|(_, error)| error),
+    });
+
+    let alert = FiredAlert {
+        rule_id: rule.clone(),
+        fired_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        severity: severity.to_string(),
+        title: format!(
+            "Collector {} stale on {}",
+            change.collector, change.machine_id
+        ),
+        message: format!(
+            "No successful {} run on {} for {}s (threshold {}s); last success {}",
+            change.collector,
+            change.machine_id,
+            change.freshness_seconds,
+            change.threshold_secs,
+            change.last_success_at.as_deref().unwrap_or("never"),
+        ),
+        context_json: Some(context.to_string()),
+        machine_id: Some(change.machine_id.clone()),
+    };
+    if let Err(e) = store.insert_alert(&alert) {
+        tracing::warn!(rule = %rule, error = %e, "stale alert persist failed");
+    }
+}
+
+fn resolve(store: &VcStore, rule_id: &str, machine_id: &str) {
+    if let Err(e) = store.resolve_open_machine_alerts(rule_id, machine_id) {
+        tracing::warn!(rule = rule_id, error = %e, "stale alert resolve failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> StalenessAlertConfig {
+        StalenessAlertConfig {
+            stale_threshold_secs: 100,
+            critical_multiplier: 3,
+            min_resolved_secs: 600,
+            ..StalenessAlertConfig::default()
+        }
+    }
+
+    fn summary(collector: &str, freshness_seconds: i64) -> FreshnessSummary {
+        FreshnessSummary {
+            machine_id: "m1".to_string(),
+            collector: collector.to_string(),
+            last_success_at: Some("2026-10-16 12:00:00".to_string()),
+            freshness_seconds,
+            success_rate_24h: 1.0,
+            total_runs_24h: 10,
+            stale: false,
+        }
+    }
+
+    fn levels(changes: &[StaleChange]) -> Vec<(StaleLevel, StaleLevel)> {
+        changes.iter().map(|c| (c.from, c.to)).collect()
+    }
+
+    #[test]
+    fn test_fires_escalates_and_resolves() {
+        let mut monitor = StalenessMonitor::new(config());
+        let now = Utc::now();
+
+        assert!(monitor.evaluate(&[summary("ntm", 50)], now).is_empty());
+        assert_eq!(
+            levels(&monitor.evaluate(&[summary("ntm", 150)], now)),
+            vec![(StaleLevel::Fresh, StaleLevel::Stale)]
+        );
+        // Still stale: nothing new
+        assert!(monitor.evaluate(&[summary("ntm", 200)], now).is_empty());
+        assert_eq!(
+            levels(&monitor.evaluate(&[summary("ntm", 301)], now)),
+            vec![(StaleLevel::Stale, StaleLevel::Critical)]
+        );
+        assert_eq!(
+            levels(&monitor.evaluate(&[summary("ntm", 10)], now)),
+            vec![(StaleLevel::Critical, StaleLevel::Fresh)]
+        );
+    }
+
+    #[test]
+    fn test_flapping_pair_waits_before_refiring() {
+        let mut monitor = StalenessMonitor::new(config());
+        let now = Utc::now();
+
+        monitor.evaluate(&[summary("ntm", 150)], now);
+        monitor.evaluate(&[summary("ntm", 10)], now);
+
+        let soon = now + chrono::Duration::seconds(60);
+        assert!(monitor.evaluate(&[summary("ntm", 150)], soon).is_empty());
+
+        let later = now + chrono::Duration::seconds(601);
+        assert_eq!(
+            levels(&monitor.evaluate(&[summary("ntm", 150)], later)),
+            vec![(StaleLevel::Fresh, StaleLevel::Stale)]
+        );
+    }
+
+    #[test]
+    fn test_per_collector_threshold_and_never_succeeded() {
+        let mut config = config();
+        config.collectors.insert("cass".to_string(), 1000);
+        let mut monitor = StalenessMonitor::new(config);
+
+        assert!(
+            monitor
+                .evaluate(&[summary("cass", 500)], Utc::now())
+                .is_empty()
+        );
+
+        let mut never = summary("ntm", -1);
+        never.last_success_at = None;
+        assert_eq!(monitor.level_for(&never), None);
+        assert!(monitor.evaluate(&[never], Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_record_change_raises_with_context_and_resolves() {
+        let store = VcStore::open_memory().unwrap();
+        let mut monitor = StalenessMonitor::new(config());
+        let now = Utc::now();
+        let warning = rule_id(StaleLevel::Stale, "ntm");
+        let critical = rule_id(StaleLevel::Critical, "ntm");
+
+        for change in monitor.evaluate(&[summary("ntm", 150)], now) {
+            record_change(&store, &change);
+        }
+        assert!(store.has_open_alert(&warning, Some("m1")).unwrap());
+        let alerts = store.list_alerts_after(0, 10).unwrap();
+        let context: serde_json::Value =
+            serde_json::from_str(alerts[0].1.context_json.as_deref().unwrap()).unwrap();
+        assert_eq!(context["last_success_at"], "2026-10-16 12:00:00");
+        assert_eq!(context["threshold_secs"], 100);
+
+        for change in monitor.evaluate(&[summary("ntm", 400)], now) {
+            record_change(&store, &change);
+        }
+        assert!(!store.has_open_alert(&warning, Some("m1")).unwrap());
+        assert!(store.has_open_alert(&critical, Some("m1")).unwrap());
+
+        for change in monitor.evaluate(&[summary("ntm", 5)], now) {
+            record_change(&store, &change);
+        }
+        assert!(!store.has_open_alert(&critical, Some("m1")).unwrap());
+    }
+}
@@ -0,0 +1,415 @@
+//! Opening the store for a CLI command
+//!
+//! A raw `IO Error: could not open` says nothing about what to fix. Here an
+//! open failure is sorted into a class — missing, unreadable, corrupt,
+//! written by a newer vc, or hung — and reported with the path, where that
+//! path came from, and a hint. Each class exits with its own code so
+//! scripts can tell them apart.
+//!
+//! The whole open, filesystem checks included, runs on its own thread with
+//! a deadline: on a dead NFS mount the thread stays stuck, but the command
+//! fails after `global.open_timeout_secs` instead of hanging.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use vc_store::{StoreError, VcStore};
+
+/// Exit code when there is no database file at `db_path`
+pub const EXIT_STORE_NOT_FOUND: i32 = 4;
+/// Exit code when the database or its directory cannot be accessed
+pub const EXIT_STORE_PERMISSION_DENIED: i32 = 5;
+/// Exit code when the file is not a readable `DuckDB` database
+pub const EXIT_STORE_CORRUPT: i32 = 6;
+/// Exit code when the store was migrated by a newer vc
+pub const EXIT_STORE_TOO_NEW: i32 = 7;
+/// Exit code when opening did not finish within the timeout
+pub const EXIT_STORE_TIMEOUT: i32 = 8;
+
+/// `DuckDB` files carry this magic after an 8-byte checksum
+const DUCKDB_MAGIC: &[u8] = b"DUCK";
+const DUCKDB_MAGIC_OFFSET: usize = 8;
+const DUCKDB_HEADER_LEN: u64 = 12;
+
+/// Why the store could not be opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOpenFailure {
+    NotFound,
+    PermissionDenied,
+    Corrupt,
+    SchemaTooNew,
+    TimedOut,
+    Other,
+}
+
+impl StoreOpenFailure {
+    #[must_use]
+    pub const fn exit_code(self) -> i32 {
+        match self {
+            Self::NotFound => EXIT_STORE_NOT_FOUND,
+            Self::PermissionDenied => EXIT_STORE_PERMISSION_DENIED,
+            Self::Corrupt => EXIT_STORE_CORRUPT,
+            Self::SchemaTooNew => EXIT_STORE_TOO_NEW,
+            Self::TimedOut => EXIT_STORE_TIMEOUT,
+            Self::Other => 1,
+        }
+    }
+
+    /// What to try next
+    #[must_use]
+    pub const fn hint(self) -> &'static str {
+        match self {
+            Self::NotFound => {
+                "point global.db_path at a file path (vc creates the database on first open), \
+                 or run `vc config wizard` to write a config"
+            }
+            Self::PermissionDenied => {
+                "run vc as the owner of the database, or fix the file and directory permissions"
+            }
+            Self::Corrupt => {
+                "run `vc doctor`, then restore the database from a backup or a standby \
+                 (`vc db promote`)"
+            }
+            Self::SchemaTooNew => "upgrade vc to the version that last wrote this store",
+            Self::TimedOut => {
+                "the filesystem holding the database is not responding (a dead network \
+                 mount?); raise global.open_timeout_secs if it is only slow"
+            }
+            Self::Other => "run `vc doctor` for a full check",
+        }
+    }
+}
+
+/// A failed open, with everything needed to act on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoreOpenError {
+    pub failure: StoreOpenFailure,
+    pub db_path: PathBuf,
+    /// Where `db_path` came from: a config file, `VC_DB_PATH` or defaults
+    pub path_source: String,
+    pub detail: String,
+}
+
+impl std::fmt::Display for StoreOpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "cannot open store at {} (db_path from {}): {}\n  hint: {}",
+            self.db_path.display(),
+            self.path_source,
+            self.detail,
+            self.failure.hint()
+        )
+    }
+}
+
+impl std::error::Error for StoreOpenError {}
+
+/// Open the store at `path`, giving up after `timeout`. The failure class
+/// and detail are returned on error; the caller adds where the path came
+/// from.
+///
+/// # Errors
+///
+/// Returns the failure class and a description when the store cannot be
+/// opened in time.
+pub fn open_with_timeout(
+    path: &Path,
+    timeout: Duration,
+) -> Result<VcStore, (StoreOpenFailure, String)> {
+    let owned = path.to_path_buf();
+    match run_with_timeout(timeout, move || open_classified(&owned)) {
+        Ok(Some(result)) => result,
+        Ok(None) => Err((
+            StoreOpenFailure::TimedOut,
+            format!("opening did not finish within {}s", timeout.as_secs()),
+        )),
+        Err(e) => Err((
+            StoreOpenFailure::Other,
+            format!("could not start the open thread: {e}"),
+        )),
+    }
+}
+
+/// Run `f` on its own thread and wait at most `timeout` for it; `None`
+/// when it did not finish. A thread that does not finish is left behind.
+fn run_with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    f: impl FnOnce() -> T + Send + 'static,
+) -> std::io::Result<Option<T>> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("vc-store-open".to_string())
+        .spawn(move || {
+            // The receiver is gone once the caller timed out
+            let _ = tx.send(f());
+        })?;
+    Ok(rx.recv_timeout(timeout).ok())
+}
+
+fn open_classified(path: &Path) -> Result<VcStore, (StoreOpenFailure, String)> {
+    if let Some(failure) = preflight(path) {
+        return Err(failure);
+    }
+    VcStore::open(path).map_err(|e| {
+        let failure = classify(&e);
+        let detail = match failure {
+            StoreOpenFailure::PermissionDenied => format!("{e} ({})", describe_access(path)),
+            _ => e.to_string(),
+        };
+        (failure, detail)
+    })
+}
+
+/// Failures visible from the filesystem alone, checked before `DuckDB`
+/// gets a chance to report them less clearly
+fn preflight(path: &Path) -> Option<(StoreOpenFailure, String)> {
+    let link = match std::fs::symlink_metadata(path) {
+        Ok(link) => link,
+        // Nothing there yet; the open creates it if the directory allows
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return parent_problem(path);
+        }
+        Err(e) => return Some(io_failure(path, &e)),
+    };
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if link.file_type().is_symlink() && e.kind() == std::io::ErrorKind::NotFound => {
+            let target = std::fs::read_link(path)
+                .map_or_else(|_| "?".to_string(), |t| t.display().to_string());
+            return Some((
+                StoreOpenFailure::NotFound,
+                format!("it is a symlink to {target}, which does not exist"),
+            ));
+        }
+        Err(e) => return Some(io_failure(path, &e)),
+    };
+    if metadata.is_dir() {
+        return Some((
+            StoreOpenFailure::NotFound,
+            "it is a directory, not a database file".to_string(),
+        ));
+    }
+
+    let mut header = Vec::new();
+    let read = std::fs::File::open(path)
+        .and_then(|file| file.take(DUCKDB_HEADER_LEN).read_to_end(&mut header));
+    if let Err(e) = read {
+        return Some(io_failure(path, &e));
+    }
+    // An empty file is one the open will initialize
+    if !header.is_empty() && header.get(DUCKDB_MAGIC_OFFSET..) != Some(DUCKDB_MAGIC) {
+        return Some((
+            StoreOpenFailure::Corrupt,
+            format!(
+                "the file header is not a DuckDB header ({} bytes, starts {:02x?})",
+                metadata.len(),
+                &header[..header.len().min(DUCKDB_MAGIC_OFFSET)]
+            ),
+        ));
+    }
+    // The store is always opened read-write
+    if let Err(e) = std::fs::OpenOptions::new().write(true).open(path) {
+        return Some(io_failure(path, &e));
+    }
+    None
+}
+
+/// A missing database is fine as long as its directory can be made
+fn parent_problem(path: &Path) -> Option<(StoreOpenFailure, String)> {
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty())?;
+    let existing = parent.ancestors().find(|p| p.exists())?;
+    if !existing.is_dir() {
+        return Some((
+            StoreOpenFailure::NotFound,
+            format!("{} is not a directory", existing.display()),
+        ));
+    }
+    None
+}
+
+fn io_failure(path: &Path, e: &std::io::Error) -> (StoreOpenFailure, String) {
+    match e.kind() {
+        std::io::ErrorKind::PermissionDenied => (
+            StoreOpenFailure::PermissionDenied,
+            format!("{e} ({})", describe_access(path)),
+        ),
+        std::io::ErrorKind::NotFound => (StoreOpenFailure::NotFound, e.to_string()),
+        _ => (StoreOpenFailure::Other, e.to_string()),
+    }
+}
+
+/// Sort an error from [`VcStore::open`] into a class
+#[must_use]
+pub fn classify(err: &StoreError) -> StoreOpenFailure {
+    match err {
+        StoreError::SchemaTooNew { .. } => StoreOpenFailure::SchemaTooNew,
+        StoreError::IoError(e) => match e.kind() {
+            std::io::ErrorKind::PermissionDenied => StoreOpenFailure::PermissionDenied,
+            std::io::ErrorKind::NotFound => StoreOpenFailure::NotFound,
+            _ => StoreOpenFailure::Other,
+        },
+        other => {
+            // DuckDB reports these as text only
+            let text = other.to_string().to_ascii_lowercase();
+            if text.contains("permission denied") {
+                StoreOpenFailure::PermissionDenied
+            } else if text.contains("no such file or directory") {
+                StoreOpenFailure::NotFound
+            } else if text.contains("not a valid duckdb database")
+                || text.contains("corrupt")
+                || text.contains("checksum")
+            {
+                StoreOpenFailure::Corrupt
+            } else {
+                StoreOpenFailure::Other
+            }
+        }
+    }
+}
+
+/// Owner and mode of the path, or of the nearest directory above it that
+/// exists
+fn describe_access(path: &Path) -> String {
+    let Some((existing, metadata)) = path
+        .ancestors()
+        .find_map(|p| std::fs::metadata(p).ok().map(|m| (p, m)))
+    else {
+        return "no part of the path exists".to_string();
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        format!(
+            "{} is owned by uid {} gid {}, mode {:04o}",
+            existing.display(),
+            metadata.uid(),
+            metadata.gid(),
+            metadata.mode() & 0o7777
+        )
+    }
+    #[cfg(not(unix))]
+    {
+        format!(
+            "{} is {}",
+            existing.display(),
+            if metadata.permissions().readonly() {
+                "read-only"
+            } else {
+                "writable"
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(path: &Path) -> StoreOpenFailure {
+        match open_with_timeout(path, Duration::from_secs(30)) {
+            Ok(_) => panic!("expected {} to fail to open", path.display()),
+            Err((failure, _)) => failure,
+        }
+    }
+
+    #[test]
+    fn test_new_path_opens() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("vc.duckdb");
+        assert!(open_with_timeout(&path, Duration::from_secs(30)).is_ok());
+        // And opens again once it exists
+        assert!(open_with_timeout(&path, Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn test_not_found_classes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(failure(dir.path()), StoreOpenFailure::NotFound);
+
+        let file = dir.path().join("plain");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(failure(&file.join("vc.duckdb")), StoreOpenFailure::NotFound);
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("dangling.duckdb");
+            std::os::unix::fs::symlink(dir.path().join("gone").join("vc.duckdb"), &link).unwrap();
+            assert_eq!(failure(&link), StoreOpenFailure::NotFound);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        std::fs::write(&path, b"this was never a database, just some text").unwrap();
+        assert_eq!(failure(&path), StoreOpenFailure::Corrupt);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_denied_names_owner_and_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        drop(VcStore::open(&path).unwrap());
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // root reads through any mode; nothing to test there
+        if std::fs::File::open(&path).is_ok() {
+            return;
+        }
+        match open_with_timeout(&path, Duration::from_secs(30)) {
+            Err((failure, detail)) => {
+                assert_eq!(failure, StoreOpenFailure::PermissionDenied);
+                assert!(detail.contains("mode 0000"), "{detail}");
+            }
+            Ok(_) => panic!("expected permission denied"),
+        }
+    }
+
+    #[test]
+    fn test_store_from_newer_vc() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        let store = VcStore::open(&path).unwrap();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO _migrations (version, name) VALUES ({}, 'from_the_future')",
+                vc_store::migrations::latest_version() + 1
+            ))
+            .unwrap();
+        drop(store);
+        assert_eq!(failure(&path), StoreOpenFailure::SchemaTooNew);
+    }
+
+    #[test]
+    fn test_hung_open_times_out() {
+        let result = run_with_timeout(Duration::from_millis(50), || {
+            std::thread::sleep(Duration::from_secs(5));
+        });
+        assert!(result.unwrap().is_none());
+        assert_eq!(
+            run_with_timeout(Duration::from_secs(5), || 7).unwrap(),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_error_names_path_source_and_hint() {
+        let err = StoreOpenError {
+            failure: StoreOpenFailure::TimedOut,
+            db_path: PathBuf::from("/mnt/nfs/vc.duckdb"),
+            path_source: "/etc/vc/vc.toml".to_string(),
+            detail: "opening did not finish within 30s".to_string(),
+        };
+        let text = err.to_string();
+        assert!(text.contains("/mnt/nfs/vc.duckdb"));
+        assert!(text.contains("db_path from /etc/vc/vc.toml"));
+        assert!(text.contains("open_timeout_secs"));
+        assert_eq!(err.failure.exit_code(), EXIT_STORE_TIMEOUT);
+    }
+}
@@ -0,0 +1,299 @@
+//! OpenTelemetry export (feature `telemetry`)
+//!
+//! After each collection tick the daemon sends new alert firings and
+//! resolutions, incident lifecycle events and collector failures to the OTLP
+//! endpoint under `[telemetry]` as OTLP/HTTP JSON log records. Every record
+//! carries the same attribute names (`machine_id`, `severity`, `alert_type`,
+//! `incident_id`) whichever stream it comes from.
+//!
+//! Each stream keeps a cursor in `telemetry_export_state` that only advances
+//! once the collector accepted a batch, so restarts neither drop nor resend
+//! events. A stream that has never been exported starts at its newest event
+//! rather than replaying history. A failed batch is retried with backoff, then
+//! left for the next tick.
+
+use std::time::Duration;
+use vc_config::TelemetryConfig;
+use vc_store::{TelemetryEvent, TelemetryStream, VcStore};
+
+/// First retry delay; doubled for each further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Most batches sent per stream per tick, so a large backlog does not stall
+/// the daemon loop
+const MAX_BATCHES_PER_TICK: usize = 10;
+
+/// Sends stored events to an OTLP/HTTP logs endpoint
+pub struct TelemetryExporter {
+    client: reqwest::Client,
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    service_name: String,
+    batch_size: usize,
+    max_retries: u32,
+}
+
+impl TelemetryExporter {
+    /// Build the exporter, or `None` when telemetry export is disabled or a
+    /// header's secret reference does not resolve (logged)
+    #[must_use]
+    pub fn from_config(config: &TelemetryConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        let endpoint = config.otlp_endpoint.clone()?;
+        let headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                vc_config::resolve_secret(&format!("telemetry.headers.{name}"), value)
+                    .map(|value| (name.clone(), value))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .inspect_err(|e| tracing::warn!(error = %e, "telemetry export disabled"))
+            .ok()?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs.max(1)))
+            .build()
+            .unwrap_or_default();
+        Some(Self {
+            client,
+            endpoint,
+            headers,
+            service_name: config.service_name.clone(),
+            batch_size: config.batch_size.max(1),
+            max_retries: config.max_retries,
+        })
+    }
+
+    /// Export new events from every stream; failures are logged and recorded
+    /// in the stream's state, never returned
+    pub async fn export(&self, store: &VcStore) {
+        for stream in TelemetryStream::ALL {
+            match self.export_stream(store, stream).await {
+                Ok(0) => {}
+                Ok(exported) => {
+                    tracing::debug!(stream = stream.as_str(), exported, "exported telemetry");
+                }
+                Err(e) => {
+                    tracing::warn!(stream = stream.as_str(), error = %e, "telemetry export failed");
+                    if let Err(e) = store.record_telemetry_failure(stream, &e) {
+                        tracing::warn!(error = %e, "failed to record telemetry export failure");
+                    }
+                }
+            }
+        }
+    }
+
+    async fn export_stream(
+        &self,
+        store: &VcStore,
+        stream: TelemetryStream,
+    ) -> Result<usize, String> {
+        let mut cursor = match store.telemetry_cursor(stream).map_err(|e| e.to_string())? {
+            Some(cursor) => cursor,
+            None => {
+                let head = store.telemetry_head(stream).map_err(|e| e.to_string())?;
+                store
+                    .init_telemetry_cursor(stream, &head)
+                    .map_err(|e| e.to_string())?;
+                head
+            }
+        };
+
+        let mut exported = 0;
+        for _ in 0..MAX_BATCHES_PER_TICK {
+            let events = store
+                .telemetry_events_after(stream, &cursor, self.batch_size)
+                .map_err(|e| e.to_string())?;
+            let Some(last) = events.last() else { break };
+            self.send_with_retry(&events).await?;
+            cursor = last.cursor.clone();
+            store
+                .record_telemetry_export(stream, &cursor, events.len())
+                .map_err(|e| e.to_string())?;
+            exported += events.len();
+            if events.len() < self.batch_size {
+                break;
+            }
+        }
+        Ok(exported)
+    }
+
+    async fn send_with_retry(&self, events: &[TelemetryEvent]) -> Result<(), String> {
+        let payload = otlp_logs_payload(&self.service_name, events);
+        let mut attempt = 0;
+        loop {
+            match self.send(&payload).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_retries => return Err(e),
+                Err(e) => {
+                    let delay = RETRY_BASE_DELAY * 2_u32.saturating_pow(attempt);
+                    tracing::debug!(attempt, error = %e, "retrying telemetry export");
+                    asupersync::time::sleep(asupersync::time::wall_now(), delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn send(&self, payload: &serde_json::Value) -> Result<(), String> {
+        let mut request = self.client.post(&self.endpoint).json(payload);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("OTLP request failed: {e}"))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("OTLP endpoint returned {}", response.status()))
+        }
+    }
+}
+
+/// OTLP severity number and text for a stored severity
+#[must_use]
+pub fn otlp_severity(severity: &str) -> (u8, &'static str) {
+    match severity.to_lowercase().as_str() {
+        "info" | "low" => (9, "INFO"),
+        "high" | "error" => (17, "ERROR"),
+        "critical" => (21, "FATAL"),
+        _ => (13, "WARN"),
+    }
+}
+
+fn string_attribute(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// One OTLP log record
+#[must_use]
+pub fn otlp_log_record(event: &TelemetryEvent) -> serde_json::Value {
+    let (severity_number, severity_text) = otlp_severity(&event.severity);
+    let time_unix_nano = vc_query::timefmt::parse_timestamp(&event.ts)
+        .and_then(|ts| ts.timestamp_nanos_opt())
+        .map_or(0, |nanos| nanos.max(0));
+
+    let mut attributes = vec![
+        string_attribute("event.name", &event.name),
+        string_attribute("vc.stream", event.stream.as_str()),
+        string_attribute("severity", &event.severity),
+    ];
+    for (key, value) in [
+        ("machine_id", &event.machine_id),
+        ("alert_type", &event.alert_type),
+        ("incident_id", &event.incident_id),
+    ] {
+        if let Some(value) = value {
+            attributes.push(string_attribute(key, value));
+        }
+    }
+
+    serde_json::json!({
+        "timeUnixNano": time_unix_nano.to_string(),
+        "severityNumber": severity_number,
+        "severityText": severity_text,
+        "body": { "stringValue": event.body },
+        "attributes": attributes,
+    })
+}
+
+/// OTLP/HTTP JSON `ExportLogsServiceRequest` for a batch of events
+#[must_use]
+pub fn otlp_logs_payload(service_name: &str, events: &[TelemetryEvent]) -> serde_json::Value {
+    serde_json::json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [string_attribute("service.name", service_name)],
+            },
+            "scopeLogs": [{
+                "scope": { "name": "vibe_cockpit", "version": env!("CARGO_PKG_VERSION") },
+                "logRecords": events.iter().map(otlp_log_record).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vc_store::TelemetryCursor;
+
+    fn event() -> TelemetryEvent {
+        TelemetryEvent {
+            stream: TelemetryStream::AlertFired,
+            name: "alert.fired".to_string(),
+            ts: "2026-10-16 12:00:00".to_string(),
+            severity: "critical".to_string(),
+            body: "disk alert: over threshold".to_string(),
+            machine_id: Some("m1".to_string()),
+            alert_type: Some("disk".to_string()),
+            incident_id: None,
+            cursor: TelemetryCursor::default(),
+        }
+    }
+
+    #[test]
+    fn test_from_config_requires_enabled_endpoint() {
+        let mut config = TelemetryConfig::default();
+        assert!(TelemetryExporter::from_config(&config).is_none());
+        config.enabled = true;
+        assert!(TelemetryExporter::from_config(&config).is_none());
+        config.otlp_endpoint = Some("http://localhost:4318/v1/logs".to_string());
+        assert!(TelemetryExporter::from_config(&config).is_some());
+    }
+
+    #[test]
+    fn test_otlp_log_record_attributes() {
+        let record = otlp_log_record(&event());
+        assert_eq!(record["severityNumber"], 21);
+        assert_eq!(record["severityText"], "FATAL");
+        assert_eq!(record["timeUnixNano"], "1792152000000000000");
+        assert_eq!(record["body"]["stringValue"], "disk alert: over threshold");
+
+        let keys: Vec<&str> = record["attributes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|a| a["key"].as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "event.name",
+                "vc.stream",
+                "severity",
+                "machine_id",
+                "alert_type"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_otlp_logs_payload_shape() {
+        let payload = otlp_logs_payload("cockpit", &[event(), event()]);
+        let resource = &payload["resourceLogs"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "cockpit"
+        );
+        assert_eq!(
+            resource["scopeLogs"][0]["logRecords"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_otlp_severity_mapping() {
+        assert_eq!(otlp_severity("info"), (9, "INFO"));
+        assert_eq!(otlp_severity("Warning"), (13, "WARN"));
+        assert_eq!(otlp_severity("high"), (17, "ERROR"));
+        assert_eq!(otlp_severity("unknown"), (13, "WARN"));
+    }
+}
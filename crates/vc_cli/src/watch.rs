@@ -2,7 +2,8 @@
 //!
 //! Emits structured events (alerts, predictions, health changes, collector status)
 //! on stdout as newline-delimited JSON. Supports filtering by event type, machine,
-//! and severity threshold.
+//! and severity threshold. With `--enrich`, alert events also carry an
+//! [`AlertContext`] so a responding agent can act without follow-up queries.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -208,6 +209,23 @@ impl WatchEvent {
         }
     }
 
+    /// Attach enrichment context, marking the event with
+    /// [`ENRICHED_ALERT_SCHEMA`].
+    #[must_use]
+    pub fn with_context(mut self, context: &AlertContext) -> Self {
+        if let serde_json::Value::Object(extra) = &mut self.extra {
+            extra.insert(
+                "schema_version".to_string(),
+                serde_json::Value::from(ENRICHED_ALERT_SCHEMA),
+            );
+            extra.insert(
+                "context".to_string(),
+                serde_json::to_value(context).unwrap_or_default(),
+            );
+        }
+        self
+    }
+
     /// Serialize to a single JSONL line.
     #[must_use]
     pub fn to_jsonl(&self) -> String {
@@ -253,15 +271,100 @@ impl WatchEvent {
     }
 }
 
+/// Schema of an alert event carrying an [`AlertContext`]; see
+/// `docs/schemas/watch-alert-enriched.json`
+pub const ENRICHED_ALERT_SCHEMA: &str = "vc.watch.alert_enriched.v1";
+
+/// Context attached to an alert event by `vc watch --enrich`.
+///
+/// A field is `null` when there is nothing to report (no health data for the
+/// machine, no matching playbook) and is also named in `skipped` when its
+/// lookup did not run within the per-event budget or failed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertContext {
+    /// The machine's latest overall health score
+    pub health_score: Option<f64>,
+    /// The factor dragging that score down most
+    pub worst_factor: Option<String>,
+    /// Alerts of the same rule on the same machine, `<machine>/<rule_id>`
+    pub group_key: Option<String>,
+    /// Times this group fired in the last 24 hours, this alert included
+    pub occurrence_count: Option<u64>,
+    /// Whether an enabled guardian playbook triggers on this alert's rule
+    pub has_playbook: Option<bool>,
+    /// The first such playbook
+    pub playbook_id: Option<String>,
+    /// Most useful knowledge entry tagged with this alert's rule
+    pub suggestion_id: Option<i64>,
+    /// Lookups that were skipped: `health`, `occurrences`, `playbook`,
+    /// `knowledge`
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
 /// Filter configuration for the watch stream.
+///
+/// Non-exhaustive; outside this crate build one with [`WatchFilter::builder`].
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct WatchFilter {
     pub event_types: Option<HashSet<WatchEventType>>,
     pub machines: Option<HashSet<String>>,
     pub min_severity: Option<WatchSeverity>,
 }
 
+/// Builder returned by [`WatchFilter::builder`]; anything not set is not
+/// filtered on.
+#[derive(Debug, Clone)]
+pub struct WatchFilterBuilder {
+    filter: WatchFilter,
+}
+
+impl WatchFilterBuilder {
+    /// Only pass these event types (heartbeats always pass)
+    #[must_use]
+    pub fn with_event_types(mut self, types: impl IntoIterator<Item = WatchEventType>) -> Self {
+        let set: HashSet<WatchEventType> = types.into_iter().collect();
+        self.filter.event_types = if set.is_empty() { None } else { Some(set) };
+        self
+    }
+
+    /// Only pass events from these machines, matched case-insensitively
+    #[must_use]
+    pub fn with_machines<S: AsRef<str>>(mut self, machines: impl IntoIterator<Item = S>) -> Self {
+        let set: HashSet<String> = machines
+            .into_iter()
+            .map(|machine| machine.as_ref().to_lowercase())
+            .collect();
+        self.filter.machines = if set.is_empty() { None } else { Some(set) };
+        self
+    }
+
+    #[must_use]
+    pub fn with_min_severity(mut self, severity: WatchSeverity) -> Self {
+        self.filter.min_severity = Some(severity);
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> WatchFilter {
+        self.filter
+    }
+}
+
 impl WatchFilter {
+    /// Start building a filter that passes everything
+    #[must_use]
+    pub fn builder() -> WatchFilterBuilder {
+        WatchFilterBuilder {
+            filter: Self {
+                event_types: None,
+                machines: None,
+                min_severity: None,
+            },
+        }
+    }
+
     /// Parse event type strings into a filter set.
     #[must_use]
     pub fn parse_event_types(events: &[String]) -> Option<HashSet<WatchEventType>> {
@@ -467,6 +570,29 @@ mod tests {
         assert!(!filter.matches(&prediction));
     }
 
+    #[test]
+    fn test_filter_builder() {
+        let filter = WatchFilter::builder()
+            .with_event_types([WatchEventType::Alert])
+            .with_machines(["Orko"])
+            .with_min_severity(WatchSeverity::High)
+            .build();
+        assert!(filter.matches(&WatchEvent::alert(
+            "orko",
+            WatchSeverity::Critical,
+            "a1",
+            "x"
+        )));
+        assert!(!filter.matches(&WatchEvent::alert("orko", WatchSeverity::Low, "a2", "x")));
+        assert!(!filter.matches(&WatchEvent::alert("sydney", WatchSeverity::High, "a3", "x")));
+
+        let everything = WatchFilter::builder()
+            .with_machines(Vec::<String>::new())
+            .build();
+        assert!(everything.machines.is_none());
+        assert!(everything.matches(&WatchEvent::prediction("m1", "rate", 0.5, "wait")));
+    }
+
     #[test]
     fn test_filter_machine() {
         let machines: HashSet<String> = ["orko".to_string()].into();
@@ -581,6 +707,25 @@ mod tests {
         assert!(toon.contains(".."));
     }
 
+    #[test]
+    fn test_enriched_alert_keeps_base_fields() {
+        let context = AlertContext {
+            health_score: Some(0.42),
+            worst_factor: Some("disk".to_string()),
+            skipped: vec!["knowledge".to_string()],
+            ..AlertContext::default()
+        };
+        let event =
+            WatchEvent::alert("orko", WatchSeverity::High, "7", "disk").with_context(&context);
+        let json: serde_json::Value = serde_json::from_str(&event.to_jsonl()).unwrap();
+        assert_eq!(json["type"], "alert");
+        assert_eq!(json["alert_id"], "7");
+        assert_eq!(json["schema_version"], ENRICHED_ALERT_SCHEMA);
+        assert_eq!(json["context"]["worst_factor"], "disk");
+        assert!(json["context"]["suggestion_id"].is_null());
+        assert_eq!(json["context"]["skipped"][0], "knowledge");
+    }
+
     #[test]
     fn test_event_roundtrip_serde() {
         let event = WatchEvent::alert("orko", WatchSeverity::Critical, "a-123", "test alert");
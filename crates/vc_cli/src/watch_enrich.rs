@@ -0,0 +1,292 @@
+//! Enrichment for watch alert events (`vc watch --enrich`).
+//!
+//! Each alert event gets an [`AlertContext`] built from four small lookups:
+//! the machine's latest health summary, how often the alert's group fired in
+//! the last day, whether a guardian playbook handles its rule, and the top
+//! knowledge entry tagged with that rule. The lookups share a per-event
+//! budget. A lookup that would start after the budget is spent is skipped
+//! and named in `skipped`, as is one that fails, so a slow store delays the
+//! stream by at most one lookup per event rather than stalling it.
+
+use crate::watch::{AlertContext, WatchEvent, WatchEventType};
+use chrono::Utc;
+use std::time::{Duration, Instant};
+use vc_store::{VcStore, escape_sql_literal};
+
+/// Time the lookups for one event may take together
+pub const DEFAULT_ENRICH_BUDGET: Duration = Duration::from_millis(50);
+
+/// Attaches [`AlertContext`] to alert events
+pub struct AlertEnricher {
+    budget: Duration,
+    #[cfg(feature = "guardian")]
+    guardian: vc_guardian::Guardian,
+}
+
+impl Default for AlertEnricher {
+    fn default() -> Self {
+        Self::new(DEFAULT_ENRICH_BUDGET)
+    }
+}
+
+impl AlertEnricher {
+    #[must_use]
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            #[cfg(feature = "guardian")]
+            guardian: vc_guardian::Guardian::new(),
+        }
+    }
+
+    /// Enrich `event` if it is an alert; other events pass through unchanged
+    #[must_use]
+    pub fn enrich(&self, store: &VcStore, event: WatchEvent) -> WatchEvent {
+        if event.event_type != WatchEventType::Alert {
+            return event;
+        }
+        let context = self.context(store, &event);
+        event.with_context(&context)
+    }
+
+    /// Build the context for one alert event
+    #[must_use]
+    pub fn context(&self, store: &VcStore, event: &WatchEvent) -> AlertContext {
+        let mut budget = Budget::start(self.budget);
+        let mut context = AlertContext::default();
+        let machine = event.machine.as_deref();
+
+        budget.run("health", || {
+            let (score, factor) = health_lookup(store, machine?)?;
+            context.health_score = score;
+            context.worst_factor = factor;
+            Some(())
+        });
+
+        let mut rule_id = None;
+        budget.run("occurrences", || {
+            let alert_id = event.extra.get("alert_id")?.as_str()?.parse::<i64>().ok()?;
+            let (rule, count) = occurrence_lookup(store, alert_id)?;
+            context.group_key = rule
+                .as_ref()
+                .map(|rule| format!("{}/{rule}", machine.unwrap_or("unknown")));
+            context.occurrence_count = Some(count);
+            rule_id = rule;
+            Some(())
+        });
+
+        // Both of the rest are keyed by the rule, which only the occurrence
+        // lookup finds
+        budget.run("playbook", || {
+            let playbook_id = self.playbook_lookup(store, rule_id.as_deref()?)?;
+            context.has_playbook = Some(playbook_id.is_some());
+            context.playbook_id = playbook_id;
+            Some(())
+        });
+        budget.run("knowledge", || {
+            context.suggestion_id = knowledge_lookup(store, rule_id.as_deref()?)?;
+            Some(())
+        });
+
+        context.skipped = budget.skipped;
+        context
+    }
+
+    /// First enabled playbook triggered by `rule_id`, built-in ones first
+    /// (when built with `guardian`)
+    #[cfg_attr(not(feature = "guardian"), allow(clippy::unused_self))]
+    fn playbook_lookup(&self, store: &VcStore, rule_id: &str) -> Option<Option<String>> {
+        #[cfg(feature = "guardian")]
+        if let Some(playbook) = self.guardian.playbooks_for_alert(rule_id).first() {
+            return Some(Some(playbook.playbook_id.clone()));
+        }
+        let rows = store
+            .query_json(
+                "SELECT playbook_id, trigger_condition FROM guardian_playbooks \
+                 WHERE enabled ORDER BY created_at, playbook_id",
+            )
+            .ok()?;
+        Some(rows.into_iter().find_map(|row| {
+            let trigger: serde_json::Value =
+                serde_json::from_str(row["trigger_condition"].as_str()?).ok()?;
+            (trigger["type"] == "on_alert" && trigger["rule_id"] == rule_id)
+                .then(|| row["playbook_id"].as_str().map(ToString::to_string))
+                .flatten()
+        }))
+    }
+}
+
+/// Time left for one event's lookups, and which were skipped
+struct Budget {
+    started: Instant,
+    limit: Duration,
+    skipped: Vec<String>,
+}
+
+impl Budget {
+    fn start(limit: Duration) -> Self {
+        Self {
+            started: Instant::now(),
+            limit,
+            skipped: Vec::new(),
+        }
+    }
+
+    /// Run `lookup` if time is left; record it as skipped if not, or if it
+    /// could not produce an answer
+    fn run(&mut self, name: &str, lookup: impl FnOnce() -> Option<()>) {
+        if self.started.elapsed() >= self.limit || lookup().is_none() {
+            self.skipped.push(name.to_string());
+        }
+    }
+}
+
+/// Latest overall score and worst factor for `machine`
+fn health_lookup(store: &VcStore, machine: &str) -> Option<(Option<f64>, Option<String>)> {
+    let rows = store
+        .query_json(&format!(
+            "SELECT overall_score, worst_factor_id FROM health_summary \
+             WHERE machine_id = '{}' ORDER BY collected_at DESC LIMIT 1",
+            escape_sql_literal(machine)
+        ))
+        .ok()?;
+    Some(rows.first().map_or((None, None), |row| {
+        (
+            row["overall_score"].as_f64(),
+            row["worst_factor_id"].as_str().map(ToString::to_string),
+        )
+    }))
+}
+
+/// The alert's rule and how often that rule fired on the same machine in the
+/// last 24 hours
+fn occurrence_lookup(store: &VcStore, alert_id: i64) -> Option<(Option<String>, u64)> {
+    let since = (Utc::now() - chrono::Duration::hours(24)).to_rfc3339();
+    let rows = store
+        .query_json(&format!(
+            "SELECT a.rule_id, \
+                    (SELECT COUNT(*) FROM alert_history h \
+                     WHERE h.rule_id = a.rule_id \
+                       AND COALESCE(h.machine_id, '') = COALESCE(a.machine_id, '') \
+                       AND h.fired_at > '{since}') AS occurrences \
+             FROM alert_history a WHERE a.id = {alert_id}"
+        ))
+        .ok()?;
+    let row = rows.first()?;
+    let rule_id = row["rule_id"].as_str().map(ToString::to_string);
+    // The alert itself counts even when its timestamp is older than a day
+    let count = row["occurrences"].as_u64().unwrap_or(0).max(1);
+    Some((rule_id, count))
+}
+
+/// Most useful knowledge entry tagged with `rule_id`, leaving out superseded
+/// ones (the same ranking as `KnowledgeStore::by_alert_type`)
+fn knowledge_lookup(store: &VcStore, rule_id: &str) -> Option<Option<i64>> {
+    let rows = store
+        .query_json(&format!(
+            "SELECT e.id FROM knowledge_entries e \
+             JOIN knowledge_alert_types t ON t.entry_id = e.id \
+             WHERE t.alert_type = '{}' \
+             AND NOT EXISTS (SELECT 1 FROM knowledge_relations r \
+                             WHERE r.target_id = e.id AND r.relation = 'supersedes') \
+             ORDER BY e.usefulness_score DESC, e.id LIMIT 1",
+            escape_sql_literal(rule_id.trim())
+        ))
+        .ok()?;
+    Some(rows.first().and_then(|row| row["id"].as_i64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watch::WatchSeverity;
+
+    fn seeded_store() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now();
+        let recent = (now - chrono::Duration::hours(1)).to_rfc3339();
+        let old = (now - chrono::Duration::days(3)).to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) VALUES \
+                 (1, 'disk-full', '{old}', 'high', 'disk', 'orko'), \
+                 (2, 'disk-full', '{recent}', 'high', 'disk', 'orko'), \
+                 (3, 'disk-full', '{recent}', 'high', 'disk', 'orko'), \
+                 (4, 'disk-full', '{recent}', 'high', 'disk', 'sydneymc'), \
+                 (5, 'rate-limit-warning', '{recent}', 'medium', 'rate', 'orko'); \
+                 INSERT INTO health_summary (machine_id, collected_at, overall_score, worst_factor_id) VALUES \
+                 ('orko', '{old}', 0.9, 'cpu'), \
+                 ('orko', '{recent}', 0.41, 'disk'); \
+                 INSERT INTO knowledge_entries (id, entry_type, title, content, tags, created_at, \
+                 usefulness_score, view_count, applied_count) VALUES \
+                 (10, 'solution', 'prune docker', 'c', '[]', '{recent}', 0.4, 0, 0), \
+                 (11, 'solution', 'prune builds', 'c', '[]', '{recent}', 0.9, 0, 0); \
+                 INSERT INTO knowledge_alert_types (entry_id, alert_type) VALUES \
+                 (10, 'disk-full'), (11, 'disk-full');"
+            ))
+            .unwrap();
+        store
+    }
+
+    fn alert(id: &str) -> WatchEvent {
+        WatchEvent::alert("orko", WatchSeverity::High, id, "disk")
+    }
+
+    #[test]
+    fn test_context_answers_follow_up_questions() {
+        let store = seeded_store();
+        let enricher = AlertEnricher::new(Duration::from_secs(10));
+
+        let context = enricher.context(&store, &alert("3"));
+        assert_eq!(context.health_score, Some(0.41));
+        assert_eq!(context.worst_factor.as_deref(), Some("disk"));
+        assert_eq!(context.group_key.as_deref(), Some("orko/disk-full"));
+        assert_eq!(context.occurrence_count, Some(2));
+        assert_eq!(context.has_playbook, Some(false));
+        assert_eq!(context.playbook_id, None);
+        assert_eq!(context.suggestion_id, Some(11));
+        assert!(context.skipped.is_empty(), "{:?}", context.skipped);
+
+        // A built-in guardian playbook handles rate limit warnings
+        if cfg!(feature = "guardian") {
+            let context = enricher.context(&store, &alert("5"));
+            assert_eq!(context.has_playbook, Some(true));
+            assert!(context.playbook_id.is_some());
+            assert_eq!(context.suggestion_id, None);
+        }
+    }
+
+    #[test]
+    fn test_spent_budget_skips_lookups() {
+        let store = seeded_store();
+        let context = AlertEnricher::new(Duration::ZERO).context(&store, &alert("3"));
+        assert_eq!(
+            context.skipped,
+            vec!["health", "occurrences", "playbook", "knowledge"]
+        );
+        assert_eq!(context.health_score, None);
+
+        // An alert that cannot be found leaves the rule-keyed lookups unanswered
+        let context = AlertEnricher::default().context(&store, &alert("999"));
+        assert_eq!(context.health_score, Some(0.41));
+        assert_eq!(
+            context.skipped,
+            vec!["occurrences", "playbook", "knowledge"]
+        );
+    }
+
+    #[test]
+    fn test_only_alerts_are_enriched() {
+        let store = seeded_store();
+        let enricher = AlertEnricher::default();
+        let heartbeat = enricher.enrich(&store, WatchEvent::heartbeat());
+        assert!(heartbeat.extra.get("context").is_none());
+
+        let event = enricher.enrich(&store, alert("2"));
+        assert_eq!(
+            event.extra["schema_version"],
+            crate::watch::ENRICHED_ALERT_SCHEMA
+        );
+        assert_eq!(event.extra["context"]["occurrence_count"], 2);
+    }
+}
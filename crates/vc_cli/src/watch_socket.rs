@@ -0,0 +1,660 @@
+//! Watch socket: one event stream served to many local subscribers.
+//!
+//! Instead of every local agent running its own `vc watch` (one store
+//! connection and one polling loop each), `vc daemon` with
+//! `[daemon] watch_socket` or `vc watch --serve <path>` polls once and fans the
+//! events out over a Unix domain socket. `vc watch --connect <path>` is the
+//! thin client.
+//!
+//! Protocol: every message, in either direction, is a frame — a 4-byte
+//! big-endian length followed by that many bytes of one JSON object ending in
+//! `\n` — so stripping the prefixes leaves plain JSONL.
+//!
+//! - The subscriber's first frame is its [`SubscribeRequest`]; every field is
+//!   optional and means the same as the matching `vc watch` flag.
+//! - The server answers `{"type":"subscribed","subscriber":N}` and then
+//!   streams [`WatchEvent`]s that pass the subscriber's filter, with alert
+//!   context attached for subscribers that set `enrich`.
+//! - Each subscriber has its own bounded queue. A subscriber that falls behind
+//!   loses events rather than stalling the others, and once its queue has
+//!   room again it receives `{"type":"dropped","count":N}` before the next
+//!   event.
+//!
+//! The socket is created with mode `0600`, so only the owning user can
+//! subscribe.
+
+use crate::watch::{WatchEvent, WatchEventType, WatchFilter, WatchSeverity};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Largest frame either side accepts; a longer length prefix is a protocol
+/// error, not an allocation.
+pub const MAX_FRAME_BYTES: usize = 1024 * 1024;
+
+/// How long a new connection has to send its subscribe frame
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the accept loop checks whether the server is shutting down
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// Write one length-prefixed JSONL frame.
+///
+/// # Errors
+///
+/// Returns the underlying I/O error, or `InvalidInput` when `json` is larger
+/// than [`MAX_FRAME_BYTES`].
+pub fn write_frame(writer: &mut impl Write, json: &str) -> io::Result<()> {
+    let len = json.len() + 1;
+    let prefix = u32::try_from(len)
+        .ok()
+        .filter(|_| len <= MAX_FRAME_BYTES)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("frame of {len} bytes exceeds {MAX_FRAME_BYTES}"),
+            )
+        })?;
+    // One write per frame, so a reader never sees half a prefix
+    let mut frame = Vec::with_capacity(4 + len);
+    frame.extend_from_slice(&prefix.to_be_bytes());
+    frame.extend_from_slice(json.as_bytes());
+    frame.push(b'\n');
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// Read one frame and return its JSON line without the trailing newline, or
+/// `None` when the peer closed the connection between frames.
+///
+/// # Errors
+///
+/// Returns the underlying I/O error, or `InvalidData` for an oversized frame
+/// or one that is not UTF-8.
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Option<String>> {
+    let mut prefix = [0_u8; 4];
+    match reader.read_exact(&mut prefix) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let len = usize::try_from(u32::from_be_bytes(prefix)).unwrap_or(usize::MAX);
+    if len > MAX_FRAME_BYTES {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds {MAX_FRAME_BYTES}"),
+        ));
+    }
+    let mut payload = vec![0_u8; len];
+    reader.read_exact(&mut payload)?;
+    if payload.last() == Some(&b'\n') {
+        payload.pop();
+    }
+    String::from_utf8(payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// First frame a subscriber sends: which events it wants.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubscribeRequest {
+    /// Event types (alert, prediction, opportunity, `health_change`, `collector_status`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<String>>,
+    /// Machine names; events without a machine always pass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub machines: Option<Vec<String>>,
+    /// Minimum severity (low, medium, high, critical)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<String>,
+    /// Skip heartbeats
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub changes_only: bool,
+    /// Receive alert events with their context attached
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub enrich: bool,
+}
+
+impl SubscribeRequest {
+    /// The [`WatchFilter`] this request describes
+    #[must_use]
+    pub fn filter(&self) -> WatchFilter {
+        WatchFilter {
+            event_types: self
+                .events
+                .as_deref()
+                .and_then(WatchFilter::parse_event_types),
+            machines: self
+                .machines
+                .as_deref()
+                .and_then(WatchFilter::parse_machines),
+            min_severity: self
+                .min_severity
+                .as_deref()
+                .and_then(WatchSeverity::from_str_loose),
+        }
+    }
+}
+
+struct Subscriber {
+    id: u64,
+    filter: WatchFilter,
+    changes_only: bool,
+    enrich: bool,
+    queue: SyncSender<Arc<str>>,
+    dropped: u64,
+}
+
+impl Subscriber {
+    /// Queue `frame`, counting it as dropped when the queue is full. Returns
+    /// `false` once the subscriber has disconnected.
+    fn offer(&mut self, frame: &Arc<str>) -> bool {
+        if self.dropped > 0 {
+            let notice = serde_json::json!({ "type": "dropped", "count": self.dropped });
+            match self.queue.try_send(Arc::from(notice.to_string())) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+        match self.queue.try_send(Arc::clone(frame)) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+type Subscribers = Arc<Mutex<Vec<Subscriber>>>;
+
+/// Serves one watch event stream to every subscriber on a Unix socket.
+///
+/// Dropping the broadcaster stops accepting, disconnects every subscriber and
+/// removes the socket file.
+pub struct WatchBroadcaster {
+    path: PathBuf,
+    subscribers: Subscribers,
+    stop: Arc<AtomicBool>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+impl WatchBroadcaster {
+    /// Bind `path` (mode `0600`) and start accepting subscribers, each with a
+    /// queue of `queue_size` events.
+    ///
+    /// A stale socket file left by a dead server is replaced; one that still
+    /// accepts connections is not.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AddrInUse` when another server is live on `path`, or the I/O
+    /// error from binding or setting permissions.
+    pub fn bind(path: &Path, queue_size: usize) -> io::Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("a watch server is already listening on {}", path.display()),
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        listener.set_nonblocking(true)?;
+
+        let subscribers: Subscribers = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let accept_thread = {
+            let subscribers = Arc::clone(&subscribers);
+            let stop = Arc::clone(&stop);
+            std::thread::Builder::new()
+                .name("vc-watch-accept".to_string())
+                .spawn(move || accept_loop(&listener, &subscribers, &stop, queue_size.max(1)))?
+        };
+
+        tracing::info!(path = %path.display(), queue_size, "serving watch socket");
+        Ok(Self {
+            path: path.to_path_buf(),
+            subscribers,
+            stop,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    /// Socket path being served
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queue `event` for every subscriber whose filter it passes, and forget
+    /// subscribers that have disconnected. Returns how many it was offered to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscriber list mutex is poisoned.
+    pub fn publish(&self, event: &WatchEvent) -> usize {
+        self.offer_frames(event, None)
+    }
+
+    /// Like [`Self::publish`], but subscribers that asked for enrichment get
+    /// `enriched` instead. Filters are applied to `event`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscriber list mutex is poisoned.
+    pub fn publish_enriched(&self, event: &WatchEvent, enriched: &WatchEvent) -> usize {
+        self.offer_frames(event, Some(enriched))
+    }
+
+    /// Whether any subscriber asked for enrichment, so the server can skip
+    /// the lookups when none did
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscriber list mutex is poisoned.
+    #[must_use]
+    pub fn wants_enrichment(&self) -> bool {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|subscriber| subscriber.enrich)
+    }
+
+    fn offer_frames(&self, event: &WatchEvent, enriched: Option<&WatchEvent>) -> usize {
+        let frame: Arc<str> = Arc::from(event.to_jsonl());
+        let enriched_frame: Option<Arc<str>> = enriched.map(|e| Arc::from(e.to_jsonl()));
+        let heartbeat = event.event_type == WatchEventType::Heartbeat;
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let mut offered = 0;
+        subscribers.retain_mut(|subscriber| {
+            if (heartbeat && subscriber.changes_only) || !subscriber.filter.matches(event) {
+                return true;
+            }
+            offered += 1;
+            let frame = match &enriched_frame {
+                Some(enriched_frame) if subscriber.enrich => enriched_frame,
+                _ => &frame,
+            };
+            let connected = subscriber.offer(frame);
+            if !connected {
+                tracing::debug!(subscriber = subscriber.id, "watch subscriber disconnected");
+            }
+            connected
+        });
+        offered
+    }
+
+    /// Subscribers currently registered
+    ///
+    /// # Panics
+    ///
+    /// Panics if the subscriber list mutex is poisoned.
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+}
+
+impl Drop for WatchBroadcaster {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.accept_thread.take() {
+            let _ = handle.join();
+        }
+        // Dropping the senders ends every subscriber's writer thread
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.clear();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn accept_loop(
+    listener: &UnixListener,
+    subscribers: &Subscribers,
+    stop: &AtomicBool,
+    queue_size: usize,
+) {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    while !stop.load(Ordering::Acquire) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+                let subscribers = Arc::clone(subscribers);
+                let spawned = std::thread::Builder::new()
+                    .name(format!("vc-watch-sub-{id}"))
+                    .spawn(move || serve_subscriber(stream, id, subscribers, queue_size));
+                if let Err(e) = spawned {
+                    tracing::warn!(error = %e, "could not start watch subscriber thread");
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+            Err(e) => {
+                tracing::warn!(error = %e, "watch socket accept failed");
+                std::thread::sleep(ACCEPT_POLL);
+            }
+        }
+    }
+}
+
+/// Handshake with one subscriber, register it, then write its queue to the
+/// socket until either side goes away.
+fn serve_subscriber(mut stream: UnixStream, id: u64, subscribers: Subscribers, queue_size: usize) {
+    let request = match handshake(&mut stream) {
+        Ok(request) => request,
+        Err(e) => {
+            tracing::debug!(subscriber = id, error = %e, "watch subscribe rejected");
+            let reply = serde_json::json!({ "type": "error", "message": e.to_string() });
+            let _ = write_frame(&mut stream, &reply.to_string());
+            return;
+        }
+    };
+
+    let (queue, events): (SyncSender<Arc<str>>, Receiver<Arc<str>>) =
+        std::sync::mpsc::sync_channel(queue_size);
+    {
+        let mut subscribers = subscribers.lock().unwrap();
+        subscribers.push(Subscriber {
+            id,
+            filter: request.filter(),
+            changes_only: request.changes_only,
+            enrich: request.enrich,
+            queue,
+            dropped: 0,
+        });
+    }
+    // Only the broadcaster may keep subscribers alive past its own drop
+    drop(subscribers);
+    tracing::debug!(subscriber = id, ?request, "watch subscriber connected");
+
+    let ack = serde_json::json!({ "type": "subscribed", "subscriber": id });
+    if write_frame(&mut stream, &ack.to_string()).is_err() {
+        return;
+    }
+    for frame in events {
+        if write_frame(&mut stream, &frame).is_err() {
+            break;
+        }
+    }
+}
+
+fn handshake(stream: &mut UnixStream) -> io::Result<SubscribeRequest> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let line = read_frame(stream)?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "closed before subscribing"))?;
+    stream.set_read_timeout(None)?;
+    serde_json::from_str(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Thin client for a watch socket.
+pub struct WatchClient {
+    stream: UnixStream,
+}
+
+impl WatchClient {
+    /// Connect to `path` and subscribe with `request`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error from connecting or sending the subscribe frame.
+    pub fn connect(path: &Path, request: &SubscribeRequest) -> io::Result<Self> {
+        let mut stream = UnixStream::connect(path)?;
+        let json = serde_json::to_string(request)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        write_frame(&mut stream, &json)?;
+        Ok(Self { stream })
+    }
+
+    /// Give up on [`Self::next_frame`] after `timeout` without a frame, so the
+    /// caller can check for shutdown.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error from setting the socket option.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    /// Next JSON line from the server, or `None` once it has closed the
+    /// connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error from reading (`WouldBlock`/`TimedOut` when a
+    /// read timeout elapsed) or a malformed frame.
+    pub fn next_frame(&mut self) -> io::Result<Option<String>> {
+        read_frame(&mut self.stream)
+    }
+}
+
+/// Render a frame received from the server as `vc watch` would print it.
+#[must_use]
+pub fn render_frame(line: &str, use_toon: bool) -> String {
+    if !use_toon {
+        return line.to_string();
+    }
+    if let Ok(event) = serde_json::from_str::<WatchEvent>(line) {
+        return event.to_toon();
+    }
+    let value: serde_json::Value = serde_json::from_str(line).unwrap_or_default();
+    match value.get("type").and_then(serde_json::Value::as_str) {
+        Some("dropped") => format!("W|DROP,{}", value["count"]),
+        Some("subscribed") => format!("W|SUB,{}", value["subscriber"]),
+        _ => format!("W|?,{line}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn socket_path(dir: &tempfile::TempDir) -> PathBuf {
+        dir.path().join("watch.sock")
+    }
+
+    fn wait_for_subscribers(server: &WatchBroadcaster, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.subscriber_count() < count {
+            assert!(Instant::now() < deadline, "subscribers never registered");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut buf = Vec::new();
+        write_frame(&mut buf, r#"{"type":"heartbeat"}"#).unwrap();
+        write_frame(&mut buf, "{}").unwrap();
+        assert_eq!(&buf[..4], &21_u32.to_be_bytes());
+
+        let mut reader = buf.as_slice();
+        assert_eq!(
+            read_frame(&mut reader).unwrap().as_deref(),
+            Some(r#"{"type":"heartbeat"}"#)
+        );
+        assert_eq!(read_frame(&mut reader).unwrap().as_deref(), Some("{}"));
+        assert!(read_frame(&mut reader).unwrap().is_none());
+
+        let oversized = u32::try_from(MAX_FRAME_BYTES + 1).unwrap().to_be_bytes();
+        assert!(read_frame(&mut oversized.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_subscribers_get_filtered_events_and_restrictive_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(&dir);
+        let server = WatchBroadcaster::bind(&path, 16).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let mut all = WatchClient::connect(&path, &SubscribeRequest::default()).unwrap();
+        let mut critical = WatchClient::connect(
+            &path,
+            &SubscribeRequest {
+                min_severity: Some("critical".to_string()),
+                changes_only: true,
+                ..SubscribeRequest::default()
+            },
+        )
+        .unwrap();
+        wait_for_subscribers(&server, 2);
+
+        server.publish(&WatchEvent::alert(
+            "orko",
+            WatchSeverity::Low,
+            "a-1",
+            "disk",
+        ));
+        server.publish(&WatchEvent::heartbeat());
+        server.publish(&WatchEvent::alert(
+            "orko",
+            WatchSeverity::Critical,
+            "a-2",
+            "down",
+        ));
+
+        let ack = all.next_frame().unwrap().unwrap();
+        assert!(ack.contains("\"subscribed\""));
+        assert!(all.next_frame().unwrap().unwrap().contains("a-1"));
+        assert!(all.next_frame().unwrap().unwrap().contains("heartbeat"));
+        assert!(all.next_frame().unwrap().unwrap().contains("a-2"));
+
+        assert!(
+            critical
+                .next_frame()
+                .unwrap()
+                .unwrap()
+                .contains("\"subscribed\"")
+        );
+        assert!(critical.next_frame().unwrap().unwrap().contains("a-2"));
+
+        drop(server);
+        assert!(!path.exists());
+        assert!(all.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_enriching_subscribers_get_enriched_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(&dir);
+        let server = WatchBroadcaster::bind(&path, 16).unwrap();
+        let mut plain = WatchClient::connect(&path, &SubscribeRequest::default()).unwrap();
+        wait_for_subscribers(&server, 1);
+        assert!(!server.wants_enrichment());
+
+        let mut enriched = WatchClient::connect(
+            &path,
+            &SubscribeRequest {
+                enrich: true,
+                ..SubscribeRequest::default()
+            },
+        )
+        .unwrap();
+        wait_for_subscribers(&server, 2);
+        assert!(server.wants_enrichment());
+
+        let event = WatchEvent::alert("orko", WatchSeverity::High, "a-1", "disk");
+        let context = crate::watch::AlertContext {
+            occurrence_count: Some(3),
+            ..crate::watch::AlertContext::default()
+        };
+        server.publish_enriched(&event, &event.clone().with_context(&context));
+
+        assert!(
+            plain
+                .next_frame()
+                .unwrap()
+                .unwrap()
+                .contains("\"subscribed\"")
+        );
+        assert!(!plain.next_frame().unwrap().unwrap().contains("\"context\""));
+        assert!(
+            enriched
+                .next_frame()
+                .unwrap()
+                .unwrap()
+                .contains("\"subscribed\"")
+        );
+        let line = enriched.next_frame().unwrap().unwrap();
+        assert!(line.contains("\"occurrence_count\":3"), "{line}");
+    }
+
+    #[test]
+    fn test_slow_subscriber_gets_dropped_notice() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(&dir);
+        let server = WatchBroadcaster::bind(&path, 1).unwrap();
+        let mut slow = WatchClient::connect(&path, &SubscribeRequest::default()).unwrap();
+        wait_for_subscribers(&server, 1);
+
+        // The client is not reading, so once the socket buffer fills the
+        // writer thread stalls and the one-slot queue overflows.
+        let mut subscribers = server.subscribers.lock().unwrap();
+        let subscriber = &mut subscribers[0];
+        let frame: Arc<str> = Arc::from(WatchEvent::heartbeat().to_jsonl());
+        while subscriber.dropped == 0 {
+            assert!(subscriber.offer(&frame));
+        }
+        drop(subscribers);
+
+        let mut saw_notice = false;
+        // Everything the socket buffered arrives before the notice
+        for _ in 0..100_000 {
+            server.publish(&WatchEvent::heartbeat());
+            let line = slow.next_frame().unwrap().unwrap();
+            if line.contains("\"dropped\"") {
+                saw_notice = true;
+                break;
+            }
+        }
+        assert!(
+            saw_notice,
+            "slow subscriber never told about dropped events"
+        );
+    }
+
+    #[test]
+    fn test_bind_refuses_live_socket_and_replaces_stale_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(&dir);
+        let server = WatchBroadcaster::bind(&path, 4).unwrap();
+        let err = WatchBroadcaster::bind(&path, 4).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+        drop(server);
+
+        // A socket file nobody is listening on is stale
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        let server = WatchBroadcaster::bind(&path, 4).unwrap();
+        assert_eq!(server.subscriber_count(), 0);
+    }
+
+    #[test]
+    fn test_render_frame_toon() {
+        let event = WatchEvent::alert("orko", WatchSeverity::High, "a-1", "cpu");
+        assert!(render_frame(&event.to_jsonl(), true).starts_with("W|AL"));
+        assert_eq!(
+            render_frame(r#"{"type":"dropped","count":3}"#, true),
+            "W|DROP,3"
+        );
+        assert_eq!(render_frame("{}", false), "{}");
+    }
+}
@@ -0,0 +1,583 @@
+//! vc_client - Typed client for the Vibe Cockpit web API
+//!
+//! Every response is decoded into the same [`vc_types`] struct the server
+//! serializes, so a field rename breaks the build instead of a script.
+//!
+//! The client:
+//! - Sends `Authorization: Bearer <token>` when a token is set
+//! - Retries connection failures, 5xx responses and short rate-limit waits
+//!   with exponential backoff
+//! - Maps error responses onto [`ClientError`]
+
+use asupersync::time::wall_now;
+use reqwest::{StatusCode, Url, header::HeaderMap};
+use serde::{Serialize, de::DeserializeOwned};
+use std::time::Duration;
+use thiserror::Error;
+
+pub use vc_types::{
+    AlertsPage, ApiErrorBody, FleetOverview, FleetSummary, HealthResponse, HealthScore,
+    IncidentDetail, IncidentsPage, MachinesPage, RobotEnvelope, RobotView, TemplateCatalog,
+    TemplateQueryRequest, TemplateQueryResult,
+};
+
+/// Client errors
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("Unauthorized: {reason}")]
+    Unauthorized { reason: String },
+
+    #[error("Forbidden: {reason}")]
+    Forbidden { reason: String },
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Rate limited, retry after {}s", retry_after.as_secs())]
+    RateLimited { retry_after: Duration },
+
+    #[error("Server error ({status}): {message}")]
+    ServerError { status: u16, message: String },
+
+    #[error("Unexpected status ({status}): {message}")]
+    UnexpectedStatus { status: u16, message: String },
+
+    #[error("Invalid base URL: {0}")]
+    InvalidUrl(String),
+
+    #[error("Request failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("Invalid response body: {0}")]
+    Decode(String),
+}
+
+impl ClientError {
+    /// Map an error response onto a variant. `body` is the raw response
+    /// text; servers in front of the API may not send an [`ApiErrorBody`].
+    #[must_use]
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let parsed: Option<ApiErrorBody> = serde_json::from_str(body).ok();
+        let detail = parsed
+            .as_ref()
+            .map(|b| b.message.clone().unwrap_or_else(|| b.error.clone()))
+            .unwrap_or_else(|| body.trim().to_string());
+        let reason = parsed
+            .as_ref()
+            .and_then(|b| b.reason.clone())
+            .unwrap_or_else(|| detail.clone());
+
+        match status {
+            StatusCode::UNAUTHORIZED => Self::Unauthorized { reason },
+            StatusCode::FORBIDDEN => Self::Forbidden { reason },
+            StatusCode::NOT_FOUND => Self::NotFound(detail),
+            StatusCode::BAD_REQUEST => Self::BadRequest(detail),
+            StatusCode::TOO_MANY_REQUESTS => {
+                let secs = retry_after_header(headers)
+                    .or_else(|| parsed.as_ref().and_then(|b| b.retry_after_secs))
+                    .unwrap_or(1);
+                Self::RateLimited {
+                    retry_after: Duration::from_secs(secs),
+                }
+            }
+            s if s.is_server_error() => Self::ServerError {
+                status: s.as_u16(),
+                message: detail,
+            },
+            s => Self::UnexpectedStatus {
+                status: s.as_u16(),
+                message: detail,
+            },
+        }
+    }
+}
+
+/// `Retry-After` in seconds; the HTTP-date form is not used by the API
+fn retry_after_header(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// When and how long to wait before retrying a failed request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Wait before the first retry; doubled on each further retry
+    pub base_delay: Duration,
+    /// Longest single wait. A rate limit asking for more is returned to
+    /// the caller instead of slept through.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::ZERO,
+            max_delay: Duration::ZERO,
+        }
+    }
+
+    /// Wait before retry number `attempt` (0-based) after `err`, or `None`
+    /// when the error is not worth retrying
+    #[must_use]
+    pub fn delay_for(&self, err: &ClientError, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        match err {
+            ClientError::RateLimited { retry_after } => {
+                (*retry_after <= self.max_delay).then_some(*retry_after)
+            }
+            ClientError::ServerError { .. } => Some(self.backoff(attempt)),
+            ClientError::Transport(e) if e.is_connect() || e.is_timeout() => {
+                Some(self.backoff(attempt))
+            }
+            _ => None,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Builder for [`VcClient`]
+#[derive(Debug, Clone)]
+pub struct VcClientBuilder {
+    base_url: String,
+    token: Option<String>,
+    timeout: Duration,
+    retry: RetryPolicy,
+}
+
+impl VcClientBuilder {
+    /// Bearer token for `Authorization`
+    #[must_use]
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Per-request timeout (default 30s)
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry policy (default [`RetryPolicy::default`])
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Build the client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the base URL is not an http(s) URL or the HTTP
+    /// client cannot be created.
+    pub fn build(self) -> Result<VcClient> {
+        let base = Url::parse(&self.base_url)
+            .map_err(|e| ClientError::InvalidUrl(format!("{}: {e}", self.base_url)))?;
+        if base.cannot_be_a_base() || !matches!(base.scheme(), "http" | "https") {
+            return Err(ClientError::InvalidUrl(self.base_url));
+        }
+        let http = reqwest::Client::builder().timeout(self.timeout).build()?;
+        Ok(VcClient {
+            http,
+            base,
+            token: self.token,
+            retry: self.retry,
+        })
+    }
+}
+
+/// Client for a running `vc web` server
+#[derive(Debug, Clone)]
+pub struct VcClient {
+    http: reqwest::Client,
+    base: Url,
+    token: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl VcClient {
+    /// Start building a client for the server at `base_url`
+    /// (e.g. `http://cockpit:8080`)
+    #[must_use]
+    pub fn builder(base_url: impl Into<String>) -> VcClientBuilder {
+        VcClientBuilder {
+            base_url: base_url.into(),
+            token: None,
+            timeout: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// `GET /api/health`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a
+    /// health report.
+    pub async fn health(&self) -> Result<HealthResponse> {
+        self.get(&["health"], &[]).await
+    }
+
+    /// `GET /api/overview`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not an
+    /// overview.
+    pub async fn overview(&self) -> Result<FleetOverview> {
+        self.get(&["overview"], &[]).await
+    }
+
+    /// `GET /api/fleet`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a
+    /// fleet summary.
+    pub async fn fleet(&self) -> Result<FleetSummary> {
+        self.get(&["fleet"], &[]).await
+    }
+
+    /// `GET /api/fleet?group_by=tag`: the fleet summary with its per-tag
+    /// breakdown
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a
+    /// fleet summary.
+    pub async fn fleet_by_tag(&self) -> Result<FleetSummary> {
+        self.get(&["fleet"], &[("group_by", "tag".to_string())])
+            .await
+    }
+
+    /// `GET /api/machines`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a page
+    /// of machines.
+    pub async fn machines(&self, limit: usize, offset: usize) -> Result<MachinesPage> {
+        self.get(
+            &["machines"],
+            &[("limit", limit.to_string()), ("offset", offset.to_string())],
+        )
+        .await
+    }
+
+    /// `GET /api/machines/{id}`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::NotFound`] for an unknown machine, or an
+    /// error if the request fails.
+    pub async fn machine(&self, machine_id: &str) -> Result<serde_json::Value> {
+        self.get(&["machines", machine_id], &[]).await
+    }
+
+    /// `GET /api/machines/{id}/health`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a
+    /// health score.
+    pub async fn machine_health(&self, machine_id: &str) -> Result<HealthScore> {
+        self.get(&["machines", machine_id, "health"], &[]).await
+    }
+
+    /// `GET /api/alerts`, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a page
+    /// of alerts.
+    pub async fn alerts(&self, limit: usize) -> Result<AlertsPage> {
+        self.get(&["alerts"], &[("limit", limit.to_string())]).await
+    }
+
+    /// `GET /api/incidents`, optionally filtered by status
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a page
+    /// of incidents.
+    pub async fn incidents(&self, status: Option<&str>, limit: usize) -> Result<IncidentsPage> {
+        let mut query = vec![("limit", limit.to_string())];
+        if let Some(status) = status {
+            query.push(("status", status.to_string()));
+        }
+        self.get(&["incidents"], &query).await
+    }
+
+    /// `GET /api/incidents/{id}`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::NotFound`] for an unknown incident, or an
+    /// error if the request fails.
+    pub async fn incident(&self, incident_id: &str) -> Result<IncidentDetail> {
+        self.get(&["incidents", incident_id], &[]).await
+    }
+
+    /// `GET /api/query/templates`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the response is not a
+    /// template catalog.
+    pub async fn query_templates(&self) -> Result<TemplateCatalog> {
+        self.get(&["query", "templates"], &[]).await
+    }
+
+    /// `POST /api/query/template`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ClientError::BadRequest`] for an unknown template or bad
+    /// parameters, [`ClientError::RateLimited`] once the caller's query
+    /// budget is spent and retries are exhausted, or an error if the
+    /// request fails.
+    pub async fn run_template(
+        &self,
+        request: &TemplateQueryRequest,
+    ) -> Result<TemplateQueryResult> {
+        self.post(&["query", "template"], request).await
+    }
+
+    /// `GET /api/robot/{view}` as JSON. `T` is the view's payload type, or
+    /// `serde_json::Value`; `max_items` only applies to triage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the payload does not
+    /// decode as `T`.
+    pub async fn robot<T: Serialize + DeserializeOwned>(
+        &self,
+        view: RobotView,
+        max_items: Option<usize>,
+    ) -> Result<RobotEnvelope<T>> {
+        let mut query = vec![("format", "json".to_string())];
+        if let Some(max_items) = max_items {
+            query.push(("max_items", max_items.to_string()));
+        }
+        self.get(&["robot", view.name()], &query).await
+    }
+
+    fn url(&self, segments: &[&str], query: &[(&str, String)]) -> Url {
+        let mut url = self.base.clone();
+        // Checked in `build`, so this always succeeds
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().push("api").extend(segments);
+        }
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        url
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        let url = self.url(segments, query);
+        self.send(|| self.http.get(url.clone())).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        segments: &[&str],
+        body: &B,
+    ) -> Result<T> {
+        let url = self.url(segments, &[]);
+        self.send(|| self.http.post(url.clone()).json(body)).await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        request: impl Fn() -> reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let err = match self.send_once(request()).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let Some(delay) = self.retry.delay_for(&err, attempt) else {
+                return Err(err);
+            };
+            attempt += 1;
+            tracing::debug!(error = %err, attempt, delay_ms = delay.as_millis(), "retrying vc request");
+            asupersync::time::sleep(wall_now(), delay).await;
+        }
+    }
+
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(ClientError::from_response(status, &headers, &body));
+        }
+        serde_json::from_str(&body).map_err(|e| ClientError::Decode(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn error_for(status: u16, body: &str) -> ClientError {
+        ClientError::from_response(
+            StatusCode::from_u16(status).unwrap(),
+            &HeaderMap::new(),
+            body,
+        )
+    }
+
+    #[test]
+    fn test_error_bodies_map_to_variants() {
+        assert!(matches!(
+            error_for(401, r#"{"error":"unauthorized","reason":"missing token","status":401}"#),
+            ClientError::Unauthorized { reason } if reason == "missing token"
+        ));
+        assert!(matches!(
+            error_for(404, r#"{"error":"Machine not found: m9","status":404}"#),
+            ClientError::NotFound(msg) if msg == "Machine not found: m9"
+        ));
+        assert!(matches!(
+            error_for(502, "Bad Gateway"),
+            ClientError::ServerError { status: 502, message } if message == "Bad Gateway"
+        ));
+        assert!(matches!(
+            error_for(409, r#"{"error":"already_decided","message":"draft was rejected","status":409}"#),
+            ClientError::UnexpectedStatus { status: 409, message } if message == "draft was rejected"
+        ));
+    }
+
+    #[test]
+    fn test_rate_limit_prefers_retry_after_header() {
+        let body = r#"{"error":"rate_limited","retry_after_secs":7,"status":429}"#;
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            ClientError::from_response(StatusCode::TOO_MANY_REQUESTS, &headers, body),
+            ClientError::RateLimited { retry_after } if retry_after == Duration::from_secs(7)
+        ));
+
+        headers.insert(reqwest::header::RETRY_AFTER, HeaderValue::from_static("12"));
+        assert!(matches!(
+            ClientError::from_response(StatusCode::TOO_MANY_REQUESTS, &headers, body),
+            ClientError::RateLimited { retry_after } if retry_after == Duration::from_secs(12)
+        ));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+        let server = error_for(503, "");
+        assert_eq!(
+            policy.delay_for(&server, 0),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.delay_for(&server, 1),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            policy.delay_for(&server, 2),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(policy.delay_for(&server, 3), None);
+
+        // Client errors are never retried
+        assert_eq!(policy.delay_for(&error_for(404, ""), 0), None);
+
+        // A rate limit is waited out only when the wait fits the cap
+        let short = ClientError::RateLimited {
+            retry_after: Duration::from_millis(250),
+        };
+        let long = ClientError::RateLimited {
+            retry_after: Duration::from_secs(60),
+        };
+        assert_eq!(
+            policy.delay_for(&short, 0),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(policy.delay_for(&long, 0), None);
+
+        assert_eq!(RetryPolicy::none().delay_for(&server, 0), None);
+    }
+
+    #[test]
+    fn test_urls() {
+        let client = VcClient::builder("http://cockpit:8080/").build().unwrap();
+        assert_eq!(
+            client.url(&["machines", "web 1"], &[]).as_str(),
+            "http://cockpit:8080/api/machines/web%201"
+        );
+        assert_eq!(
+            client
+                .url(&["incidents"], &[("status", "open".to_string())])
+                .as_str(),
+            "http://cockpit:8080/api/incidents?status=open"
+        );
+
+        let prefixed = VcClient::builder("https://ops.example/cockpit")
+            .build()
+            .unwrap();
+        assert_eq!(
+            prefixed.url(&["health"], &[]).as_str(),
+            "https://ops.example/cockpit/api/health"
+        );
+
+        assert!(matches!(
+            VcClient::builder("mailto:ops@example.com").build(),
+            Err(ClientError::InvalidUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+//! Runs the client against the real axum app on a loopback port
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use vc_client::{ClientError, RetryPolicy, RobotView, TemplateQueryRequest, VcClient};
+use vc_config::WebConfig;
+use vc_store::VcStore;
+use vc_web::WebServer;
+use vc_web::auth::{ApiToken, AuthConfig, Role};
+
+/// Same runtime shape as `vc`: the server's connection tasks need Tokio
+/// workers, the client's backoff sleeps need Asupersync.
+fn run_tokio<F: Future<Output = ()>>(future: F) {
+    let asupersync_rt = asupersync::runtime::RuntimeBuilder::new()
+        .build()
+        .expect("build Asupersync runtime for vc_client tests");
+    let tokio_rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()
+        .expect("build Tokio compat runtime for vc_client tests");
+    let _tokio_guard = tokio_rt.enter();
+    let root_cx = asupersync::Cx::for_testing();
+
+    asupersync_rt
+        .block_on(async {
+            asupersync_tokio_compat::runtime::with_tokio_context(&root_cx, || async move {
+                future.await;
+            })
+            .await
+        })
+        .expect("vc_client test future should complete");
+}
+
+/// Serve `server` on an ephemeral port and return its base URL
+async fn serve(server: WebServer) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = server
+        .router()
+        .into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{addr}")
+}
+
+fn seeded_store() -> VcStore {
+    let store = VcStore::open_memory().unwrap();
+    store
+        .execute_batch(
+            "INSERT INTO machines (machine_id, hostname) VALUES ('orko', 'orko'), \
+             ('ghost', 'ghost')",
+        )
+        .unwrap();
+    store
+        .create_incident("inc-1", "disk full on orko", "critical", None)
+        .unwrap();
+    store
+        .add_incident_note("inc-1", Some("alice"), "cleared /tmp")
+        .unwrap();
+    store
+        .create_incident("inc-2", "slow builds", "warning", None)
+        .unwrap();
+    store
+        .update_incident_status("inc-2", "closed", None, None)
+        .unwrap();
+    store
+}
+
+fn no_retry(base: &str) -> VcClient {
+    VcClient::builder(base)
+        .with_retry(RetryPolicy::none())
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_client_reads_fleet_machines_and_incidents() {
+    run_tokio(async {
+        let base = serve(WebServer::new(seeded_store(), WebConfig::default())).await;
+        let client = no_retry(&base);
+
+        let health = client.health().await.unwrap();
+        assert_eq!(health.status, "ok");
+
+        let fleet = client.fleet().await.unwrap();
+        assert_eq!(fleet.total_machines, 2);
+        assert_eq!(client.overview().await.unwrap().total_machines, 2);
+
+        let page = client.machines(1, 1).await.unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!((page.limit, page.offset), (1, 1));
+        assert_eq!(page.machines.len(), 1);
+        assert_eq!(page.machines[0]["hostname"], "orko");
+        assert_eq!(client.machine("orko").await.unwrap()["machine_id"], "orko");
+
+        assert!(client.alerts(10).await.unwrap().alerts.is_empty());
+
+        let open = client.incidents(Some("open"), 10).await.unwrap();
+        assert_eq!(open.incidents.len(), 1);
+        assert_eq!(open.incidents[0]["incident_id"], "inc-1");
+        assert_eq!(client.incidents(None, 10).await.unwrap().incidents.len(), 2);
+
+        let detail = client.incident("inc-1").await.unwrap();
+        assert_eq!(detail.incident["title"], "disk full on orko");
+        assert_eq!(detail.notes.len(), 1);
+        assert_eq!(detail.notes[0]["content"], "cleared /tmp");
+    });
+}
+
+#[test]
+fn test_client_templates_and_robot() {
+    run_tokio(async {
+        let base = serve(WebServer::new(seeded_store(), WebConfig::default())).await;
+        let client = no_retry(&base);
+
+        let catalog = client.query_templates().await.unwrap();
+        assert!(catalog.templates.iter().any(|t| t.name == "machine_status"));
+
+        let result = client
+            .run_template(&TemplateQueryRequest {
+                name: "machine_status".to_string(),
+                params: HashMap::from([("machine_id".to_string(), "orko".into())]),
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.template, "machine_status");
+        assert_eq!(result.row_count, 1);
+        assert_eq!(result.rows[0]["machine_id"], "orko");
+
+        let err = client
+            .run_template(&TemplateQueryRequest {
+                name: "no_such_template".to_string(),
+                params: HashMap::new(),
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::NotFound(_)), "{err:?}");
+
+        let envelope = client
+            .robot::<serde_json::Value>(RobotView::Health, None)
+            .await
+            .unwrap();
+        assert!(envelope.schema_version.starts_with("vc.robot.health"));
+    });
+}
+
+#[test]
+fn test_client_error_mapping() {
+    run_tokio(async {
+        let auth = AuthConfig {
+            enabled: true,
+            tokens: vec![ApiToken {
+                name: "reader".to_string(),
+                token: "tok-reader".to_string(),
+                role: Role::Read,
+                allowed_ips: vec![],
+                enabled: true,
+            }],
+            local_bypass: false,
+        };
+        let config = WebConfig {
+            query_rate_limit_per_min: 1,
+            ..WebConfig::default()
+        };
+        let base = serve(WebServer::new_with_auth(seeded_store(), config, auth)).await;
+
+        let err = no_retry(&base).fleet().await.unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Unauthorized { reason } if reason == "missing_token"),
+            "{err:?}"
+        );
+        let err = VcClient::builder(&base)
+            .with_token("tok-wrong")
+            .build()
+            .unwrap()
+            .fleet()
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, ClientError::Unauthorized { reason } if reason == "invalid_token"),
+            "{err:?}"
+        );
+
+        // A retry wait longer than the policy allows is handed back, not slept
+        let client = VcClient::builder(&base)
+            .with_token("tok-reader")
+            .with_retry(RetryPolicy {
+                max_retries: 3,
+                base_delay: Duration::from_millis(10),
+                max_delay: Duration::from_millis(50),
+            })
+            .build()
+            .unwrap();
+        assert_eq!(client.fleet().await.unwrap().total_machines, 2);
+
+        let err = client.machine("nope").await.unwrap_err();
+        assert!(matches!(err, ClientError::NotFound(_)), "{err:?}");
+
+        let request = TemplateQueryRequest {
+            name: "machine_status".to_string(),
+            params: HashMap::from([("machine_id".to_string(), "orko".into())]),
+        };
+        client.run_template(&request).await.unwrap();
+        let err = client.run_template(&request).await.unwrap_err();
+        match err {
+            ClientError::RateLimited { retry_after } => {
+                assert!(retry_after >= Duration::from_secs(1));
+            }
+            other => panic!("expected RateLimited, got {other:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_client_retries_connection_failures() {
+    run_tokio(async {
+        // Nothing listens here once the listener is dropped
+        let addr = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let client = VcClient::builder(format!("http://{addr}"))
+            .with_retry(RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(5),
+                max_delay: Duration::from_millis(20),
+            })
+            .build()
+            .unwrap();
+
+        let err = client.health().await.unwrap_err();
+        assert!(matches!(err, ClientError::Transport(_)), "{err:?}");
+    });
+}
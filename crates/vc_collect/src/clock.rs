@@ -0,0 +1,331 @@
+//! Clock skew measurement and timestamp normalization
+//!
+//! A machine with a wrong clock stamps its samples with the wrong time: a
+//! fast clock makes stale data look fresh and sorts it ahead of the rest of
+//! the fleet. Skew (remote clock minus hub time) is measured two ways:
+//!
+//! - **Probe**: `date` runs on the machine between two hub timestamps and
+//!   the reading is compared with their midpoint, so the command's round
+//!   trip adds at most `rtt / 2` of error either way.
+//! - **Ingest**: a bundle's `sent_at` (remote clock) is compared with its
+//!   arrival. Delivery delay only makes the remote look behind, so this is a
+//!   lower bound and only says something when the remote is ahead.
+//!
+//! Every measurement is kept in `machine_clock_skew`. One whose skew cannot
+//! be explained by its own uncertainty beyond `[ingest]
+//! clock_skew_warn_secs` raises a `clock-skew` warning; the next conclusive
+//! measurement within bounds resolves it. Machines with
+//! `normalize_clock_skew` get ingested row timestamps shifted back by the
+//! skew, with the offset kept in the row's `clock_skew_ms`.
+
+use crate::CollectError;
+use crate::executor::{Executor, Shell};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use std::time::Duration;
+use vc_store::{ClockSkewSample, FiredAlert, StoreError, VcStore};
+
+/// Alert rule raised for a machine whose clock is off
+pub const CLOCK_SKEW_ALERT_RULE: &str = "clock-skew";
+
+/// Row field recording the offset subtracted from a normalized row's
+/// timestamps
+pub const ROW_SKEW_FIELD: &str = "clock_skew_ms";
+
+/// Remote clock reading; `%N` is not supported everywhere (macOS prints a
+/// literal `N`), in which case only whole seconds are used
+pub const REMOTE_CLOCK_COMMAND: &str = "date -u +%s.%N";
+
+/// [`REMOTE_CLOCK_COMMAND`] for Windows, printing the same `secs.millis`
+/// whatever the machine's decimal separator
+pub const REMOTE_CLOCK_COMMAND_POWERSHELL: &str = "$ms = \
+     [DateTimeOffset]::UtcNow.ToUnixTimeMilliseconds(); \
+     '{0}.{1:D3}' -f [long][math]::Floor($ms / 1000), [int]($ms % 1000)";
+
+/// Fields holding a row's own timestamp, in order of preference
+pub(crate) const ROW_TIMESTAMP_FIELDS: &[&str] =
+    &["collected_at", "ts", "timestamp", "captured_at"];
+
+/// Naive timestamp layout used by collectors that do not write RFC 3339
+const NAIVE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Parse the output of [`REMOTE_CLOCK_COMMAND`]
+#[must_use]
+pub fn parse_remote_clock(output: &str) -> Option<DateTime<Utc>> {
+    let output = output.trim();
+    let (secs, frac) = output.split_once('.').unwrap_or((output, ""));
+    let secs: i64 = secs.parse().ok()?;
+    let nanos = if !frac.is_empty() && frac.bytes().all(|b| b.is_ascii_digit()) {
+        let digits: String = frac.chars().chain(std::iter::repeat('0')).take(9).collect();
+        digits.parse().ok()?
+    } else {
+        0
+    };
+    DateTime::from_timestamp(secs, nanos)
+}
+
+/// Skew of a remote reading taken between hub times `sent` and `received`
+#[must_use]
+pub fn probe_skew(
+    machine_id: &str,
+    sent: DateTime<Utc>,
+    remote: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> ClockSkewSample {
+    let rtt = received - sent;
+    let midpoint = sent + rtt / 2;
+    ClockSkewSample {
+        machine_id: machine_id.to_string(),
+        measured_at: received.to_rfc3339_opts(SecondsFormat::Micros, true),
+        skew_ms: (remote - midpoint).num_milliseconds(),
+        rtt_ms: Some(rtt.num_milliseconds().max(0)),
+        source: "probe".to_string(),
+    }
+}
+
+/// Skew of a bundle sent at remote time `sent_at` that arrived at `received`
+#[must_use]
+pub fn ingest_skew(
+    machine_id: &str,
+    sent_at: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> ClockSkewSample {
+    ClockSkewSample {
+        machine_id: machine_id.to_string(),
+        measured_at: received.to_rfc3339_opts(SecondsFormat::Micros, true),
+        skew_ms: (sent_at - received).num_milliseconds(),
+        rtt_ms: None,
+        source: "ingest".to_string(),
+    }
+}
+
+/// Read the machine's clock through `executor` and estimate its skew.
+///
+/// # Errors
+///
+/// Returns [`CollectError`] when the command fails or its output is not a
+/// clock reading.
+pub async fn measure_clock_skew(
+    cx: &asupersync::Cx,
+    machine_id: &str,
+    executor: &Executor,
+    timeout: Duration,
+) -> Result<ClockSkewSample, CollectError> {
+    let sent = Utc::now();
+    let command = match executor.shell() {
+        Shell::PowerShell => REMOTE_CLOCK_COMMAND_POWERSHELL,
+        Shell::Posix | Shell::Cmd => REMOTE_CLOCK_COMMAND,
+    };
+    let output = executor.run(cx, command, timeout).await?;
+    let received = Utc::now();
+    if output.exit_code != 0 {
+        return Err(CollectError::ExecutionError(
+            output.stderr.trim().to_string(),
+        ));
+    }
+    let remote = parse_remote_clock(&output.stdout).ok_or_else(|| {
+        CollectError::ParseError(format!("not a clock reading: {}", output.stdout.trim()))
+    })?;
+    Ok(probe_skew(machine_id, sent, remote, received))
+}
+
+/// Store a measurement and raise or resolve the machine's `clock-skew`
+/// alert. A `warn_secs` of 0 only stores it.
+///
+/// Ingest measurements showing the remote behind are inconclusive (the
+/// bundle may just have been delayed) and leave the alert as it is.
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if the measurement or alert cannot be written.
+pub fn record_clock_skew(
+    store: &VcStore,
+    sample: &ClockSkewSample,
+    warn_secs: u64,
+) -> Result<(), StoreError> {
+    store.record_clock_skew(sample)?;
+    if warn_secs == 0 {
+        return Ok(());
+    }
+
+    let warn_ms = i64::try_from(warn_secs.saturating_mul(1000)).unwrap_or(i64::MAX);
+    if sample.min_abs_skew_ms() <= warn_ms {
+        if sample.is_correctable() {
+            store.resolve_open_machine_alerts(CLOCK_SKEW_ALERT_RULE, &sample.machine_id)?;
+        }
+        return Ok(());
+    }
+    if store.has_open_alert(CLOCK_SKEW_ALERT_RULE, Some(&sample.machine_id))? {
+        return Ok(());
+    }
+
+    let direction = if sample.skew_ms > 0 {
+        "ahead"
+    } else {
+        "behind"
+    };
+    let context = serde_json::json!({
+        "skew_ms": sample.skew_ms,
+        "rtt_ms": sample.rtt_ms,
+        "source": sample.source,
+        "threshold_secs": warn_secs,
+    });
+    store.insert_alert(&FiredAlert {
+        rule_id: CLOCK_SKEW_ALERT_RULE.to_string(),
+        fired_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        severity: "warning".to_string(),
+        title: format!("Clock skew on {}", sample.machine_id),
+        message: format!(
+            "Clock on {} is {}s {direction} of the hub (measured on {}, threshold {warn_secs}s)",
+            sample.machine_id,
+            sample.skew_ms.unsigned_abs() / 1000,
+            sample.source,
+        ),
+        context_json: Some(context.to_string()),
+        machine_id: Some(sample.machine_id.clone()),
+    })
+}
+
+/// Parse a row timestamp written either as RFC 3339 or as a naive UTC time
+#[must_use]
+pub fn parse_row_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(value, NAIVE_FORMAT)
+                .ok()
+                .map(|ts| ts.and_utc())
+        })
+}
+
+/// The row's own timestamp, from the first of [`ROW_TIMESTAMP_FIELDS`] that
+/// parses
+#[must_use]
+pub fn row_timestamp(row: &serde_json::Value) -> Option<DateTime<Utc>> {
+    ROW_TIMESTAMP_FIELDS
+        .iter()
+        .find_map(|field| row.get(*field)?.as_str().and_then(parse_row_timestamp))
+}
+
+/// Shift every timestamp field of `row` back by `skew_ms`, keeping the
+/// layout it was written in, and record the offset in [`ROW_SKEW_FIELD`].
+/// Returns whether any field was shifted; rows without one are untouched.
+pub fn normalize_row(row: &mut serde_json::Value, skew_ms: i64) -> bool {
+    let Some(fields) = row.as_object_mut() else {
+        return false;
+    };
+    let offset = chrono::Duration::milliseconds(skew_ms);
+    let mut shifted = false;
+    for field in ROW_TIMESTAMP_FIELDS {
+        let Some(value) = fields.get(*field).and_then(serde_json::Value::as_str) else {
+            continue;
+        };
+        let normalized = if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+            (ts.with_timezone(&Utc) - offset).to_rfc3339_opts(SecondsFormat::Micros, true)
+        } else if let Ok(ts) = NaiveDateTime::parse_from_str(value, NAIVE_FORMAT) {
+            (ts - offset).format("%Y-%m-%d %H:%M:%S%.6f").to_string()
+        } else {
+            continue;
+        };
+        fields.insert((*field).to_string(), serde_json::Value::String(normalized));
+        shifted = true;
+    }
+    if shifted {
+        fields.insert(ROW_SKEW_FIELD.to_string(), serde_json::Value::from(skew_ms));
+    }
+    shifted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        parse_row_timestamp(value).unwrap()
+    }
+
+    #[test]
+    fn test_parse_remote_clock() {
+        assert_eq!(
+            parse_remote_clock("1792144800.250000000\n"),
+            Some(at("2026-10-16T10:00:00.250Z"))
+        );
+        // macOS `date` has no %N.
+        assert_eq!(
+            parse_remote_clock("1792144800.N"),
+            Some(at("2026-10-16T10:00:00Z"))
+        );
+        assert_eq!(parse_remote_clock("date: illegal option"), None);
+    }
+
+    #[test]
+    fn test_probe_skew_uses_round_trip_midpoint() {
+        // A slow link: 4s round trip, remote read 2s after sending.
+        let sample = probe_skew(
+            "m1",
+            at("2026-10-16T10:00:00Z"),
+            at("2026-10-16T10:00:02Z"),
+            at("2026-10-16T10:00:04Z"),
+        );
+        assert_eq!(sample.skew_ms, 0);
+        assert_eq!(sample.rtt_ms, Some(4_000));
+
+        let fast = probe_skew(
+            "m1",
+            at("2026-10-16T10:00:00Z"),
+            at("2026-10-16T10:40:02Z"),
+            at("2026-10-16T10:00:04Z"),
+        );
+        assert_eq!(fast.skew_ms, 2_400_000);
+    }
+
+    #[test]
+    fn test_record_clock_skew_alerts_beyond_uncertainty() {
+        let store = VcStore::open_memory().unwrap();
+        let open = || {
+            store
+                .has_open_alert(CLOCK_SKEW_ALERT_RULE, Some("m1"))
+                .unwrap()
+        };
+        let sample = |skew_ms: i64, rtt_ms: Option<i64>| ClockSkewSample {
+            machine_id: "m1".to_string(),
+            measured_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            skew_ms,
+            rtt_ms,
+            source: if rtt_ms.is_some() { "probe" } else { "ingest" }.to_string(),
+        };
+
+        // 70s off but a 30s round trip: could be within the 60s threshold.
+        record_clock_skew(&store, &sample(70_000, Some(30_000)), 60).unwrap();
+        assert!(!open());
+
+        record_clock_skew(&store, &sample(2_400_000, Some(300)), 60).unwrap();
+        assert!(open());
+
+        // Looking behind on ingest proves nothing either way.
+        record_clock_skew(&store, &sample(-1_000, None), 60).unwrap();
+        assert!(open());
+
+        record_clock_skew(&store, &sample(500, Some(200)), 60).unwrap();
+        assert!(!open());
+        assert_eq!(store.clock_skew_history("m1", 10).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_normalize_row_keeps_layout_and_records_offset() {
+        let mut row = serde_json::json!({
+            "collected_at": "2026-10-16T10:40:00Z",
+            "ts": "2026-10-16 10:40:00",
+            "cpu_total": 12.5,
+        });
+        assert!(normalize_row(&mut row, 2_400_000));
+        assert_eq!(row["collected_at"], "2026-10-16T10:00:00.000000Z");
+        assert_eq!(row["ts"], "2026-10-16 10:00:00.000000");
+        assert_eq!(row[ROW_SKEW_FIELD], 2_400_000);
+        assert_eq!(row_timestamp(&row), Some(at("2026-10-16T10:00:00Z")));
+
+        let mut untimed = serde_json::json!({"cpu_total": 1});
+        assert!(!normalize_row(&mut untimed, 2_400_000));
+        assert!(untimed.get(ROW_SKEW_FIELD).is_none());
+    }
+}
@@ -0,0 +1,492 @@
+//! accounts collector - quota and rate-limit state from provider CLIs
+//!
+//! Runs the status commands configured under `[accounts]` (by default
+//! `caam limits --format json`) and records, per account, the remaining
+//! quota, when the current window resets and whether the account is the
+//! active one.
+//!
+//! Provider CLIs change their output without notice, so parsing does not
+//! rely on a fixed schema: the JSON is walked for objects that carry an
+//! account identity plus a quota figure, under a list of known key spellings.
+//! Every row keeps the raw output it came from. When a command's output
+//! yields no accounts at all, one row with `parse_error` set is stored
+//! instead and the run is reported as failed, which lands in
+//! `collector_health`.
+//!
+//! ## Tables Populated
+//! - `account_status`: Per-account quota history
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::time::Instant;
+use vc_config::{AccountCommandConfig, AccountsConfig};
+
+use crate::{CollectContext, CollectOutcome, CollectResult, Collector, Cursor, RowBatch, Warning};
+
+/// Raw output stored per row is capped at this many bytes
+const MAX_RAW_OUTPUT_BYTES: usize = 16 * 1024;
+
+const ID_KEYS: &[&str] = &["account_id", "account", "profile", "name", "email", "id"];
+const REMAINING_KEYS: &[&str] = &[
+    "remaining_pct",
+    "remaining_percent",
+    "percent_remaining",
+    "remaining_percentage",
+    "remaining",
+];
+const USED_KEYS: &[&str] = &[
+    "used_pct",
+    "usage_pct",
+    "used_percent",
+    "utilization_percent",
+    "utilization",
+    "percent_used",
+    "usage",
+];
+const RESET_KEYS: &[&str] = &[
+    "resets_at",
+    "reset_at",
+    "resets",
+    "reset",
+    "reset_time",
+    "window_resets_at",
+];
+const ACTIVE_KEYS: &[&str] = &["is_active", "active", "is_current", "current", "selected"];
+const ACTIVE_NAME_KEYS: &[&str] = &["active_profile", "active_account", "current_account"];
+
+/// One account extracted from provider CLI output
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedAccount {
+    /// Provider (claude, openai, ...) if the output or config names one
+    pub provider: Option<String>,
+    /// Account identity (profile name, email or id)
+    pub account_id: String,
+    /// Remaining quota in the current window, 0-100
+    pub remaining_pct: Option<f64>,
+    /// Used quota in the current window, 0-100
+    pub used_pct: Option<f64>,
+    /// When the current window resets (RFC 3339 where recognizable)
+    pub resets_at: Option<String>,
+    /// Whether this is the account currently in use
+    pub is_active: bool,
+}
+
+/// Extract account entries from provider CLI output.
+///
+/// Leading non-JSON text (banners, warnings) is skipped. `default_provider`
+/// is used for accounts whose output and enclosing objects name none.
+///
+/// # Errors
+///
+/// Returns a description of the problem when the output holds no JSON or
+/// no object with both an account identity and a quota figure.
+pub fn parse_account_status(
+    output: &str,
+    default_provider: Option<&str>,
+) -> Result<Vec<ParsedAccount>, String> {
+    let value = parse_json_lenient(output)?;
+
+    let mut active_names = Vec::new();
+    collect_active_names(&value, &mut active_names);
+
+    let mut accounts = Vec::new();
+    walk(&value, default_provider, &mut accounts);
+    if accounts.is_empty() {
+        return Err("no account entries with quota fields found in output".to_string());
+    }
+
+    for account in &mut accounts {
+        if active_names.contains(&account.account_id) {
+            account.is_active = true;
+        }
+    }
+    Ok(accounts)
+}
+
+fn parse_json_lenient(output: &str) -> Result<Value, String> {
+    let trimmed = output.trim();
+    if trimmed.is_empty() {
+        return Err("empty output".to_string());
+    }
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Ok(value);
+    }
+    let start = trimmed
+        .find(['{', '['])
+        .ok_or_else(|| "output is not JSON".to_string())?;
+    serde_json::from_str(&trimmed[start..]).map_err(|e| format!("invalid JSON: {e}"))
+}
+
+fn walk(value: &Value, provider: Option<&str>, out: &mut Vec<ParsedAccount>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                walk(item, provider, out);
+            }
+        }
+        Value::Object(map) => {
+            let provider = map.get("provider").and_then(Value::as_str).or(provider);
+            if let Some(account) = account_from_object(map, provider) {
+                out.push(account);
+                return;
+            }
+            for child in map.values() {
+                walk(child, provider, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn account_from_object(
+    map: &serde_json::Map<String, Value>,
+    provider: Option<&str>,
+) -> Option<ParsedAccount> {
+    let account_id = ID_KEYS
+        .iter()
+        .find_map(|key| map.get(*key).and_then(scalar_string))?;
+    let remaining = first_pct(map, REMAINING_KEYS);
+    let used = first_pct(map, USED_KEYS);
+    if remaining.is_none() && used.is_none() {
+        return None;
+    }
+    Some(ParsedAccount {
+        provider: provider.map(str::to_string),
+        account_id,
+        remaining_pct: remaining.or_else(|| used.map(|u| (100.0 - u).max(0.0))),
+        used_pct: used.or_else(|| remaining.map(|r| (100.0 - r).max(0.0))),
+        resets_at: RESET_KEYS
+            .iter()
+            .find_map(|key| map.get(*key).and_then(parse_reset)),
+        is_active: ACTIVE_KEYS
+            .iter()
+            .any(|key| map.get(*key).and_then(Value::as_bool) == Some(true)),
+    })
+}
+
+fn collect_active_names(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_active_names(item, out);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                if ACTIVE_NAME_KEYS.contains(&key.as_str()) {
+                    if let Some(name) = child.as_str() {
+                        out.push(name.to_string());
+                    }
+                } else {
+                    collect_active_names(child, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// First key holding a percentage, as a number or a string like `"45%"`
+fn first_pct(map: &serde_json::Map<String, Value>, keys: &[&str]) -> Option<f64> {
+    keys.iter().find_map(|key| {
+        let pct = match map.get(*key)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.trim().trim_end_matches('%').trim().parse().ok(),
+            _ => None,
+        }?;
+        pct.is_finite().then_some(pct.clamp(0.0, 100.0))
+    })
+}
+
+/// Reset time as RFC 3339; numbers are taken as Unix seconds
+fn parse_reset(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => {
+            Some(DateTime::parse_from_rfc3339(s.trim()).map_or_else(
+                |_| s.trim().to_string(),
+                |dt| dt.with_timezone(&Utc).to_rfc3339(),
+            ))
+        }
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|dt| dt.to_rfc3339()),
+        _ => None,
+    }
+}
+
+fn truncate_raw(output: &str) -> &str {
+    if output.len() <= MAX_RAW_OUTPUT_BYTES {
+        return output;
+    }
+    let mut end = MAX_RAW_OUTPUT_BYTES;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    &output[..end]
+}
+
+/// accounts collector for provider quota and rate-limit state
+pub struct AccountsCollector {
+    commands: Vec<AccountCommandConfig>,
+}
+
+impl AccountsCollector {
+    /// Collector running the commands from an `[accounts]` config section
+    #[must_use]
+    pub fn from_config(config: &AccountsConfig) -> Self {
+        Self {
+            commands: config.commands.clone(),
+        }
+    }
+}
+
+impl Default for AccountsCollector {
+    fn default() -> Self {
+        Self::from_config(&AccountsConfig::default())
+    }
+}
+
+#[async_trait]
+impl Collector for AccountsCollector {
+    fn name(&self) -> &'static str {
+        "accounts"
+    }
+
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
+    fn required_tool(&self) -> Option<&'static str> {
+        None // Commands are configurable; each failure is reported on its own
+    }
+
+    fn supports_incremental(&self) -> bool {
+        false // Each collection is a point-in-time snapshot
+    }
+
+    async fn collect(&self, cx: &asupersync::Cx, ctx: &CollectContext) -> CollectOutcome {
+        let start = Instant::now();
+        let collected_at = ctx.collected_at.to_rfc3339();
+        let mut rows = Vec::new();
+        let mut warnings = Vec::new();
+        let mut parse_errors = Vec::new();
+        crate::collect_checkpoint!(cx, "collect_start");
+
+        for spec in &self.commands {
+            // Central commands describe fleet-wide accounts; run them once.
+            if spec.central && !ctx.is_local {
+                continue;
+            }
+
+            crate::collect_checkpoint!(cx, "pre_account_command");
+            let output = match ctx
+                .executor
+                .run_timeout(cx, &spec.command, ctx.timeout)
+                .await
+            {
+                Ok(output) => output,
+                Err(e) => {
+                    warnings.push(Warning::warn(format!(
+                        "Failed to run '{}': {e}",
+                        spec.command
+                    )));
+                    continue;
+                }
+            };
+
+            crate::collect_checkpoint!(cx, "post_account_command_pre_parse");
+            let raw = truncate_raw(&output);
+            match parse_account_status(&output, spec.provider.as_deref()) {
+                Ok(accounts) => {
+                    for account in accounts {
+                        rows.push(serde_json::json!({
+                            "machine_id": ctx.machine_id,
+                            "collected_at": collected_at,
+                            "source_command": spec.command,
+                            "provider": account.provider,
+                            "account_id": account.account_id,
+                            "remaining_pct": account.remaining_pct,
+                            "used_pct": account.used_pct,
+                            "resets_at": account.resets_at,
+                            "is_active": account.is_active,
+                            "parse_error": None::<String>,
+                            "raw_output": raw,
+                        }));
+                    }
+                }
+                Err(e) => {
+                    rows.push(serde_json::json!({
+                        "machine_id": ctx.machine_id,
+                        "collected_at": collected_at,
+                        "source_command": spec.command,
+                        "provider": spec.provider,
+                        "parse_error": e,
+                        "raw_output": raw,
+                    }));
+                    parse_errors.push(format!("{}: {e}", spec.command));
+                }
+            }
+        }
+
+        crate::collect_checkpoint!(cx, "post_parse_pre_return");
+        let mut result = CollectResult::with_rows(vec![RowBatch {
+            table: "account_status".to_string(),
+            rows,
+        }])
+        .with_cursor(Cursor::now())
+        .with_duration(start.elapsed());
+
+        for warning in warnings {
+            result = result.with_warning(warning);
+        }
+        if !parse_errors.is_empty() {
+            result.success = false;
+            result.error = Some(format!("parse_error: {}", parse_errors.join("; ")));
+        }
+
+        crate::collect_checkpoint!(cx, "collect_complete");
+        asupersync::Outcome::Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_collector_name() {
+        let collector = AccountsCollector::default();
+        assert_eq!(collector.name(), "accounts");
+        assert!(collector.required_tool().is_none());
+        assert!(!collector.supports_incremental());
+    }
+
+    #[test]
+    fn test_parse_caam_limits_shape() {
+        let output = r#"{
+            "providers": [{
+                "provider": "claude",
+                "profiles": [
+                    {"name": "a@example.com", "utilization_percent": 80.0,
+                     "resets_at": "2026-10-16T18:00:00Z", "is_active": true},
+                    {"name": "b@example.com", "utilization_percent": 12.5}
+                ]
+            }]
+        }"#;
+        let accounts = parse_account_status(output, None).unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].provider.as_deref(), Some("claude"));
+        assert_eq!(accounts[0].account_id, "a@example.com");
+        assert_eq!(accounts[0].remaining_pct, Some(20.0));
+        assert_eq!(accounts[0].used_pct, Some(80.0));
+        assert_eq!(
+            accounts[0].resets_at.as_deref(),
+            Some("2026-10-16T18:00:00+00:00")
+        );
+        assert!(accounts[0].is_active);
+        assert!(!accounts[1].is_active);
+        assert_eq!(accounts[1].remaining_pct, Some(87.5));
+    }
+
+    #[test]
+    fn test_parse_alternate_shape_with_banner() {
+        // Different key spellings, percent strings, epoch reset, an active
+        // name at the top level and a banner line before the JSON.
+        let output = r#"warning: update available
+{
+  "active_account": "work",
+  "accounts": [
+    {"account": "work", "remaining": "35%", "reset": 1792188000},
+    {"account": "personal", "remaining_percent": 90}
+  ]
+}"#;
+        let accounts = parse_account_status(output, Some("openai")).unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].provider.as_deref(), Some("openai"));
+        assert_eq!(accounts[0].remaining_pct, Some(35.0));
+        assert_eq!(accounts[0].used_pct, Some(65.0));
+        assert!(
+            accounts[0]
+                .resets_at
+                .as_deref()
+                .unwrap()
+                .starts_with("2026-10-16")
+        );
+        assert!(accounts[0].is_active);
+        assert!(!accounts[1].is_active);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_account_status("", None).is_err());
+        assert!(parse_account_status("Usage: caam [OPTIONS]", None).is_err());
+        let err = parse_account_status(r#"{"providers": [{"profiles": [{"name": "x"}]}]}"#, None)
+            .unwrap_err();
+        assert!(err.contains("no account entries"), "{err}");
+    }
+
+    #[test]
+    fn test_truncate_raw_respects_char_boundaries() {
+        let long = "é".repeat(MAX_RAW_OUTPUT_BYTES);
+        let truncated = truncate_raw(&long);
+        assert!(truncated.len() <= MAX_RAW_OUTPUT_BYTES);
+        assert!(truncated.chars().all(|c| c == 'é'));
+        assert_eq!(truncate_raw("short"), "short");
+    }
+
+    fn collect_with(commands: Vec<AccountCommandConfig>) -> CollectResult {
+        crate::run_async_test(async move {
+            let collector = AccountsCollector::from_config(&AccountsConfig { commands });
+            let cx = asupersync::Cx::for_testing();
+            let ctx = CollectContext::local("test", Duration::from_secs(5));
+            match collector.collect(&cx, &ctx).await {
+                asupersync::Outcome::Ok(result) => result,
+                other => panic!("unexpected outcome: {other:?}"),
+            }
+        })
+    }
+
+    #[test]
+    fn test_collect_stores_parsed_rows_with_raw_output() {
+        let result = collect_with(vec![AccountCommandConfig {
+            command: r#"echo '{"accounts":[{"id":"acct-1","remaining_pct":42}]}'"#.to_string(),
+            provider: Some("claude".to_string()),
+            central: false,
+        }]);
+        assert!(result.success);
+        let rows = &result.rows[0].rows;
+        assert_eq!(result.rows[0].table, "account_status");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["account_id"], "acct-1");
+        assert_eq!(rows[0]["provider"], "claude");
+        assert_eq!(rows[0]["remaining_pct"], 42.0);
+        assert!(rows[0]["parse_error"].is_null());
+        assert!(rows[0]["raw_output"].as_str().unwrap().contains("acct-1"));
+    }
+
+    #[test]
+    fn test_collect_records_parse_error_row() {
+        let result = collect_with(vec![AccountCommandConfig {
+            command: "echo 'Limits: plenty'".to_string(),
+            provider: None,
+            central: false,
+        }]);
+        assert!(!result.success);
+        assert!(result.error.as_deref().unwrap().starts_with("parse_error:"));
+        let rows = &result.rows[0].rows;
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0]["parse_error"].is_string());
+        assert!(rows[0]["raw_output"].as_str().unwrap().contains("plenty"));
+    }
+}
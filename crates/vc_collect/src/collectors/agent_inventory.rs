@@ -0,0 +1,530 @@
+//! `agent_inventory` collector - agents running on a machine right now
+//!
+//! Agents are started by several tools, mostly inside tmux panes, so the
+//! collector looks at the machine itself rather than at any one tool's
+//! records. Each cycle it lists every tmux pane and the process table,
+//! finds processes whose command line matches a configured agent pattern
+//! (`[agent_inventory]`), and attributes each to the pane it runs under,
+//! if any. Only the outermost match counts: a `node` child of a matching
+//! `claude` is the same agent.
+//!
+//! A cycle reports every agent found, and the store reconciles the report:
+//! agents missing from it get `ended_at`. So the collector fails outright
+//! rather than report a partial picture when the process table cannot be
+//! read. A machine without tmux, or with no tmux server running, is
+//! inventoried from the process table alone, without a warning each cycle.
+//!
+//! ## Integration Method
+//! ```bash
+//! tmux list-panes -a -F '<session>\t<window.pane>\t<pane_id>\t<pane_pid>\t...'
+//! ps -eo pid=,ppid=,etime=,args=
+//! ```
+//!
+//! ## Tables Populated
+//! - `agents_inventory`: One row per agent, reconciled each cycle
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as TimeDelta, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use vc_config::{AgentInventoryConfig, AgentPatternConfig};
+
+use crate::executor::{CommandOutput, Executor};
+use crate::platform::Platform;
+use crate::{CollectContext, CollectError, CollectOutcome, CollectResult, Collector, RowBatch};
+
+/// Fields asked of `tmux list-panes`, tab separated
+const TMUX_FORMAT: &str = "#{session_name}\t#{window_index}.#{pane_index}\t#{pane_id}\t\
+                           #{pane_pid}\t#{pane_current_path}\t#{window_activity}";
+
+/// Command lines stored per agent are capped at this many bytes
+const MAX_COMMAND_BYTES: usize = 512;
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One tmux pane
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxPane {
+    pub session_name: String,
+    /// `window.pane` index
+    pub pane: String,
+    pub pane_id: String,
+    /// The pane's shell
+    pub pane_pid: i64,
+    pub current_path: Option<String>,
+    pub window_activity: Option<DateTime<Utc>>,
+}
+
+/// One entry of the process table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: i64,
+    pub ppid: i64,
+    pub elapsed: TimeDelta,
+    pub args: String,
+}
+
+/// An agent found on the machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InventoriedAgent {
+    pub agent_key: String,
+    pub agent_type: String,
+    /// `tmux` or `process`
+    pub source: &'static str,
+    pub session_name: Option<String>,
+    pub pane: Option<String>,
+    pub pane_id: Option<String>,
+    pub pid: i64,
+    pub working_dir: Option<String>,
+    pub command: String,
+    pub started_at: DateTime<Utc>,
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
+impl InventoriedAgent {
+    /// The `agents_inventory` row for this agent
+    #[must_use]
+    pub fn to_row(&self, machine_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "machine_id": machine_id,
+            "agent_key": self.agent_key,
+            "agent_type": self.agent_type,
+            "source": self.source,
+            "session_name": self.session_name,
+            "pane": self.pane,
+            "pane_id": self.pane_id,
+            "pid": self.pid,
+            "working_dir": self.working_dir,
+            "command": self.command,
+            "started_at": self.started_at.to_rfc3339(),
+            "last_activity_at": self.last_activity_at.map(|at| at.to_rfc3339()),
+        })
+    }
+}
+
+/// The `tmux list-panes` command line
+#[must_use]
+pub fn tmux_command() -> String {
+    format!(
+        "tmux list-panes -a -F {}",
+        crate::executor::Shell::Posix.quote(TMUX_FORMAT)
+    )
+}
+
+/// Whether a failed `tmux` run means there is simply nothing to list: tmux
+/// is not installed, or no server is running
+#[must_use]
+pub fn tmux_absent(output: &CommandOutput) -> bool {
+    let stderr = output.stderr.to_lowercase();
+    output.exit_code == 127
+        || stderr.contains("not found")
+        || stderr.contains("no server running")
+        || stderr.contains("error connecting to")
+        || stderr.contains("no sessions")
+}
+
+/// Parse `tmux list-panes` output; malformed lines are skipped
+#[must_use]
+pub fn parse_tmux_panes(output: &str) -> Vec<TmuxPane> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [session_name, pane, pane_id, pane_pid, path, activity] = fields[..] else {
+                return None;
+            };
+            Some(TmuxPane {
+                session_name: session_name.to_string(),
+                pane: pane.to_string(),
+                pane_id: pane_id.to_string(),
+                pane_pid: pane_pid.trim().parse().ok()?,
+                current_path: Some(path.to_string()).filter(|p| !p.is_empty()),
+                window_activity: activity
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            })
+        })
+        .collect()
+}
+
+/// Parse a `ps` elapsed time, `[[dd-]hh:]mm:ss`
+#[must_use]
+pub fn parse_etime(etime: &str) -> Option<TimeDelta> {
+    let (days, clock) = match etime.split_once('-') {
+        Some((days, clock)) => (days.parse::<i64>().ok()?, clock),
+        None => (0, etime),
+    };
+    let mut secs = 0_i64;
+    let mut parts = 0;
+    for part in clock.split(':') {
+        secs = secs * 60 + part.parse::<i64>().ok()?;
+        parts += 1;
+    }
+    if !(2..=3).contains(&parts) {
+        return None;
+    }
+    Some(TimeDelta::days(days) + TimeDelta::seconds(secs))
+}
+
+/// Parse `ps -eo pid=,ppid=,etime=,args=` output; malformed lines are skipped
+#[must_use]
+pub fn parse_ps(output: &str) -> Vec<ProcessEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut rest = line.trim_start();
+            let mut fields = [""; 3];
+            for field in &mut fields {
+                let end = rest.find(char::is_whitespace)?;
+                *field = &rest[..end];
+                rest = rest[end..].trim_start();
+            }
+            if rest.is_empty() {
+                return None;
+            }
+            Some(ProcessEntry {
+                pid: fields[0].parse().ok()?,
+                ppid: fields[1].parse().ok()?,
+                elapsed: parse_etime(fields[2])?,
+                args: rest.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The agent type of the first pattern `args` contains. tmux itself is
+/// never an agent, though a session named after one puts the name in its
+/// command line.
+fn match_agent<'a>(patterns: &'a [AgentPatternConfig], args: &str) -> Option<&'a str> {
+    let program = args.split_whitespace().next().unwrap_or_default();
+    let program = program.rsplit('/').next().unwrap_or(program);
+    if program.starts_with("tmux") {
+        return None;
+    }
+    let args = args.to_lowercase();
+    patterns
+        .iter()
+        .find(|p| args.contains(&p.pattern.to_lowercase()))
+        .map(|p| p.agent_type.as_str())
+}
+
+fn truncate_command(args: &str) -> String {
+    if args.len() <= MAX_COMMAND_BYTES {
+        return args.to_string();
+    }
+    let mut end = MAX_COMMAND_BYTES;
+    while !args.is_char_boundary(end) {
+        end -= 1;
+    }
+    args[..end].to_string()
+}
+
+/// Find the agents among `processes`, attributing each to the tmux pane it
+/// runs under. `now` is when `ps` ran.
+#[must_use]
+pub fn find_agents(
+    patterns: &[AgentPatternConfig],
+    panes: &[TmuxPane],
+    processes: &[ProcessEntry],
+    now: DateTime<Utc>,
+) -> Vec<InventoriedAgent> {
+    let by_pid: HashMap<i64, &ProcessEntry> = processes.iter().map(|p| (p.pid, p)).collect();
+    let pane_of: HashMap<i64, &TmuxPane> = panes.iter().map(|p| (p.pane_pid, p)).collect();
+    // Parents of pid, nearest first; bounded in case of a pid cycle in a
+    // racy snapshot
+    let ancestors = |pid: i64| {
+        let mut chain = Vec::new();
+        let mut current = by_pid.get(&pid).map(|p| p.ppid);
+        while let Some(parent) = current
+            && parent > 0
+            && chain.len() < 64
+        {
+            chain.push(parent);
+            current = by_pid.get(&parent).map(|p| p.ppid);
+        }
+        chain
+    };
+
+    let mut agents: Vec<InventoriedAgent> = processes
+        .iter()
+        .filter_map(|process| {
+            let agent_type = match_agent(patterns, &process.args)?;
+            let chain = ancestors(process.pid);
+            let nested = chain.iter().any(|pid| {
+                by_pid
+                    .get(pid)
+                    .is_some_and(|parent| match_agent(patterns, &parent.args).is_some())
+            });
+            if nested {
+                return None;
+            }
+            let pane = std::iter::once(process.pid)
+                .chain(chain)
+                .find_map(|pid| pane_of.get(&pid).copied());
+            let mut agent = InventoriedAgent {
+                agent_key: format!("pid:{}", process.pid),
+                agent_type: agent_type.to_string(),
+                source: "process",
+                session_name: None,
+                pane: None,
+                pane_id: None,
+                pid: process.pid,
+                working_dir: None,
+                command: truncate_command(&process.args),
+                started_at: now - process.elapsed,
+                last_activity_at: None,
+            };
+            if let Some(pane) = pane {
+                agent.agent_key = format!("tmux:{}:{}", pane.pane_id, process.pid);
+                agent.source = "tmux";
+                agent.session_name = Some(pane.session_name.clone());
+                agent.pane = Some(pane.pane.clone());
+                agent.pane_id = Some(pane.pane_id.clone());
+                agent.working_dir.clone_from(&pane.current_path);
+                agent.last_activity_at = pane.window_activity;
+            }
+            Some(agent)
+        })
+        .collect();
+    agents.sort_by(|a, b| a.agent_key.cmp(&b.agent_key));
+    agents
+}
+
+/// Fill in the working directory of agents outside tmux from `/proc`.
+/// Best effort: machines without `/proc` keep `None`.
+async fn fill_process_cwds(
+    cx: &asupersync::Cx,
+    executor: &Executor,
+    agents: &mut [InventoriedAgent],
+) {
+    let pids: Vec<String> = agents
+        .iter()
+        .filter(|a| a.working_dir.is_none())
+        .map(|a| a.pid.to_string())
+        .collect();
+    if pids.is_empty() {
+        return;
+    }
+    let cmd = format!(
+        "for p in {}; do printf '%s\\t%s\\n' \"$p\" \"$(readlink /proc/$p/cwd 2>/dev/null)\"; done",
+        pids.join(" ")
+    );
+    let Ok(output) = executor.run(cx, &cmd, COMMAND_TIMEOUT).await else {
+        return;
+    };
+    let cwds: HashMap<i64, String> = output
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let (pid, cwd) = line.split_once('\t')?;
+            Some((pid.parse().ok()?, cwd.to_string())).filter(|(_, cwd)| !cwd.is_empty())
+        })
+        .collect();
+    for agent in agents.iter_mut().filter(|a| a.working_dir.is_none()) {
+        agent.working_dir = cwds.get(&agent.pid).cloned();
+    }
+}
+
+/// `agent_inventory` collector for running agent sessions
+pub struct AgentInventoryCollector {
+    patterns: Vec<AgentPatternConfig>,
+}
+
+impl Default for AgentInventoryCollector {
+    fn default() -> Self {
+        Self::from_config(&AgentInventoryConfig::default())
+    }
+}
+
+impl AgentInventoryCollector {
+    /// Collector matching the patterns under `[agent_inventory]`
+    #[must_use]
+    pub fn from_config(config: &AgentInventoryConfig) -> Self {
+        Self {
+            patterns: config.patterns.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for AgentInventoryCollector {
+    fn name(&self) -> &'static str {
+        "agent_inventory"
+    }
+
+    fn required_tool(&self) -> Option<&'static str> {
+        None // tmux is optional; without it only the process table is read
+    }
+
+    async fn collect(&self, cx: &asupersync::Cx, ctx: &CollectContext) -> CollectOutcome {
+        let start = Instant::now();
+        crate::collect_checkpoint!(cx, "collect_start");
+
+        let panes = match ctx.executor.run(cx, &tmux_command(), COMMAND_TIMEOUT).await {
+            Ok(output) if output.success() => parse_tmux_panes(&output.stdout),
+            Ok(output) if tmux_absent(&output) => Vec::new(),
+            Ok(output) => {
+                return asupersync::Outcome::Err(CollectError::ExecutionError(format!(
+                    "tmux list-panes exited with {}: {}",
+                    output.exit_code,
+                    output.stderr.trim()
+                )));
+            }
+            Err(e) => return asupersync::Outcome::Err(e),
+        };
+
+        crate::collect_checkpoint!(cx, "post_tmux_pre_ps");
+        let now = Utc::now();
+        let processes = match ctx
+            .executor
+            .run_timeout(cx, "ps -eo pid=,ppid=,etime=,args=", COMMAND_TIMEOUT)
+            .await
+        {
+            Ok(stdout) => parse_ps(&stdout),
+            Err(e) => return asupersync::Outcome::Err(e),
+        };
+        if processes.is_empty() {
+            // Even an idle machine has processes; an empty table means ps
+            // printed something else, and reporting it would end every agent
+            return asupersync::Outcome::Err(CollectError::ParseError(
+                "ps listed no processes".to_string(),
+            ));
+        }
+
+        let mut agents = find_agents(&self.patterns, &panes, &processes, now);
+        if ctx.platform != Platform::Macos {
+            fill_process_cwds(cx, &ctx.executor, &mut agents).await;
+        }
+
+        crate::collect_checkpoint!(cx, "post_parse_pre_return");
+        let result = CollectResult::with_rows(vec![RowBatch {
+            table: "agents_inventory".to_string(),
+            rows: agents.iter().map(|a| a.to_row(&ctx.machine_id)).collect(),
+        }])
+        .with_duration(start.elapsed());
+        asupersync::Outcome::Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns() -> Vec<AgentPatternConfig> {
+        AgentInventoryConfig::default().patterns
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tmux_panes() {
+        let output = "work\t0.0\t%0\t1000\t/data/projects/vc\t1792152000\n\
+                      work\t1.2\t%5\t1100\t\t\n\
+                      garbage line\n";
+        let panes = parse_tmux_panes(output);
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].pane_id, "%0");
+        assert_eq!(panes[0].pane_pid, 1000);
+        assert_eq!(panes[0].current_path.as_deref(), Some("/data/projects/vc"));
+        assert_eq!(
+            panes[0].window_activity,
+            Utc.timestamp_opt(1_792_152_000, 0).single()
+        );
+        assert_eq!(panes[1].pane, "1.2");
+        assert_eq!(panes[1].current_path, None);
+        assert_eq!(panes[1].window_activity, None);
+        assert!(tmux_command().starts_with("tmux list-panes -a -F '#{session_name}\t"));
+    }
+
+    #[test]
+    fn test_parse_etime_and_ps() {
+        assert_eq!(parse_etime("05:03"), Some(TimeDelta::seconds(303)));
+        assert_eq!(parse_etime("01:00:00"), Some(TimeDelta::hours(1)));
+        assert_eq!(
+            parse_etime("2-00:00:10"),
+            Some(TimeDelta::days(2) + TimeDelta::seconds(10))
+        );
+        assert_eq!(parse_etime("10"), None);
+        assert_eq!(parse_etime("x:10"), None);
+
+        let ps = parse_ps(
+            "    1     0 10-00:00:00 /sbin/init\n\
+             1001  1000       10:00 claude --continue  --verbose\n\
+             bogus\n",
+        );
+        assert_eq!(ps.len(), 2);
+        assert_eq!(ps[1].pid, 1001);
+        assert_eq!(ps[1].ppid, 1000);
+        assert_eq!(ps[1].elapsed, TimeDelta::minutes(10));
+        assert_eq!(ps[1].args, "claude --continue  --verbose");
+    }
+
+    #[test]
+    fn test_find_agents_attributes_panes_and_skips_children() {
+        let panes = parse_tmux_panes("work\t0.1\t%3\t1000\t/data/projects/vc\t1792151000\n");
+        let processes = parse_ps(
+            "    1     0  9-00:00:00 /sbin/init\n\
+             1000     1       20:00 -bash\n\
+             1001  1000       10:00 node /usr/local/bin/claude --continue\n\
+             1002  1001       09:00 node /usr/local/lib/claude/mcp.js\n\
+             2000     1     1:00:00 codex exec --full-auto\n\
+             3000     1       00:05 vim notes.md\n\
+             4000     1     2:00:00 tmux new-session -d -s claude-work\n",
+        );
+        let agents = find_agents(&patterns(), &panes, &processes, now());
+        assert_eq!(agents.len(), 2, "{agents:?}");
+
+        let codex = &agents[0];
+        assert_eq!(codex.agent_key, "pid:2000");
+        assert_eq!(codex.agent_type, "codex");
+        assert_eq!(codex.source, "process");
+        assert_eq!(codex.started_at, now() - TimeDelta::hours(1));
+        assert_eq!(codex.session_name, None);
+
+        let claude = &agents[1];
+        assert_eq!(claude.agent_key, "tmux:%3:1001");
+        assert_eq!(claude.agent_type, "claude");
+        assert_eq!(claude.source, "tmux");
+        assert_eq!(claude.session_name.as_deref(), Some("work"));
+        assert_eq!(claude.pane.as_deref(), Some("0.1"));
+        assert_eq!(claude.working_dir.as_deref(), Some("/data/projects/vc"));
+        assert!(claude.last_activity_at.is_some());
+
+        let row = claude.to_row("orko");
+        assert_eq!(row["machine_id"], "orko");
+        assert_eq!(row["pid"], 1001);
+        assert_eq!(row["source"], "tmux");
+    }
+
+    #[test]
+    fn test_tmux_absent_is_not_an_error() {
+        let output = |stderr: &str, exit_code| CommandOutput {
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+            exit_code,
+        };
+        assert!(tmux_absent(&output("sh: 1: tmux: not found\n", 127)));
+        assert!(tmux_absent(&output(
+            "no server running on /tmp/tmux-1000/default\n",
+            1
+        )));
+        assert!(tmux_absent(&output(
+            "error connecting to /tmp/tmux-1000/default (No such file or directory)\n",
+            1
+        )));
+        assert!(!tmux_absent(&output("unknown option -- a\n", 1)));
+    }
+
+    #[test]
+    fn test_truncate_command_respects_char_boundaries() {
+        let long = "é".repeat(MAX_COMMAND_BYTES);
+        let truncated = truncate_command(&long);
+        assert!(truncated.len() <= MAX_COMMAND_BYTES);
+        assert!(truncated.chars().all(|c| c == 'é'));
+    }
+}
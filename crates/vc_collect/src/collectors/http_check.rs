@@ -0,0 +1,498 @@
+//! `http_check` collector - HTTP health of services running on machines
+//!
+//! Model servers, proxies and the like are configured per machine under
+//! `[[machines.<id>.http_checks]]`. Each check is one `curl` request, run on
+//! the machine itself through the collection executor (so `localhost` URLs
+//! work) or from the cockpit host with `from = "hub"`. curl is asked to
+//! append its status code and total time after the body, and `-v` puts the
+//! server certificate's expiry date on stderr for https endpoints.
+//!
+//! A failing check is data, not a collector failure: the run succeeds and
+//! the failure is stored with its [`ErrorCause`], so `collector_health`
+//! keeps meaning "could we look" rather than "is the service up".
+//!
+//! ## Tables Populated
+//! - `http_check_results`: One row per check per collection
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use vc_config::{HttpCheckConfig, HttpCheckOrigin, VcConfig};
+
+use crate::error_cause::ErrorCause;
+use crate::executor::{CommandOutput, Executor, Shell};
+use crate::platform::Platform;
+use crate::{CollectContext, CollectOutcome, CollectResult, Collector, Cursor, RowBatch, Warning};
+
+/// Separates the response body from curl's `--write-out` trailer
+const TRAILER_MARKER: &str = "__vc_http_check__";
+
+/// Extra time given to the executor beyond curl's own `--max-time`, for
+/// the ssh round trip
+const EXECUTOR_SLACK: Duration = Duration::from_secs(5);
+
+/// The outcome of one HTTP check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HttpCheckResult {
+    pub check_name: String,
+    pub url: String,
+    pub method: String,
+    pub origin: HttpCheckOrigin,
+    pub ok: bool,
+    /// `None` when no response arrived
+    pub status_code: Option<u16>,
+    pub latency_ms: Option<f64>,
+    /// Whether the body contained `body_contains`, when that is set
+    pub body_matched: Option<bool>,
+    pub error: Option<String>,
+    pub error_cause: Option<ErrorCause>,
+    pub tls_expires_at: Option<DateTime<Utc>>,
+    pub tls_days_left: Option<i64>,
+    /// Set when the certificate expires within `tls_warn_days`; the check
+    /// still passes
+    pub tls_warning: Option<String>,
+}
+
+impl HttpCheckResult {
+    fn new(check: &HttpCheckConfig) -> Self {
+        Self {
+            check_name: check.name.clone(),
+            url: check.url.clone(),
+            method: check.method.clone(),
+            origin: check.from,
+            ok: false,
+            status_code: None,
+            latency_ms: None,
+            body_matched: None,
+            error: None,
+            error_cause: None,
+            tls_expires_at: None,
+            tls_days_left: None,
+            tls_warning: None,
+        }
+    }
+
+    fn fail(mut self, error: String, cause: ErrorCause) -> Self {
+        self.ok = false;
+        self.error = Some(error);
+        self.error_cause = Some(cause);
+        self
+    }
+
+    /// The `http_check_results` row for this result
+    #[must_use]
+    pub fn to_row(&self, machine_id: &str, collected_at: &str) -> serde_json::Value {
+        serde_json::json!({
+            "machine_id": machine_id,
+            "collected_at": collected_at,
+            "check_name": self.check_name,
+            "url": self.url,
+            "method": self.method,
+            "origin": match self.origin {
+                HttpCheckOrigin::Machine => "machine",
+                HttpCheckOrigin::Hub => "hub",
+            },
+            "ok": i32::from(self.ok),
+            "status_code": self.status_code,
+            "latency_ms": self.latency_ms,
+            "body_matched": self.body_matched.map(i32::from),
+            "error": self.error,
+            "error_cause": self.error_cause.map(|cause| cause.as_str()),
+            "tls_expires_at": self.tls_expires_at.map(|at| at.to_rfc3339()),
+            "tls_days_left": self.tls_days_left,
+            "tls_warning": self.tls_warning,
+        })
+    }
+}
+
+/// The curl command line for `check` under `shell`
+#[must_use]
+pub fn curl_command(check: &HttpCheckConfig, shell: Shell) -> String {
+    // PowerShell aliases `curl` to Invoke-WebRequest
+    let binary = if shell == Shell::Posix {
+        "curl"
+    } else {
+        "curl.exe"
+    };
+    let method = if check.method == "HEAD" {
+        "--head".to_string()
+    } else {
+        format!("-X {}", check.method)
+    };
+    let write_out = format!("\\n{TRAILER_MARKER} %{{http_code}} %{{time_total}}\\n");
+    format!(
+        "{binary} -sS -v {method} --max-time {} -w {} {}",
+        check.timeout_secs,
+        shell.quote(&write_out),
+        shell.quote(&check.url)
+    )
+}
+
+/// The cause of a curl failure: its exit code where that is specific,
+/// else the message
+fn curl_failure_cause(exit_code: i32, message: &str) -> ErrorCause {
+    match exit_code {
+        // Could not resolve host, could not connect
+        6 | 7 => ErrorCause::ConnectRefused,
+        28 => ErrorCause::Timeout,
+        127 => ErrorCause::ToolMissing,
+        _ => match ErrorCause::classify(message) {
+            ErrorCause::Unknown => ErrorCause::RemoteError,
+            cause => cause,
+        },
+    }
+}
+
+/// The server certificate's expiry from curl's verbose output, e.g.
+/// `*  expire date: Mar  3 23:59:59 2027 GMT`
+fn parse_tls_expiry(stderr: &str) -> Option<DateTime<Utc>> {
+    let raw = stderr
+        .lines()
+        .find_map(|line| line.split_once("expire date:"))?
+        .1;
+    let normalized = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    NaiveDateTime::parse_from_str(&normalized, "%b %d %H:%M:%S %Y GMT")
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// Judge `check` from curl's output
+fn parse_curl_output(
+    check: &HttpCheckConfig,
+    output: &CommandOutput,
+    now: DateTime<Utc>,
+) -> HttpCheckResult {
+    let mut result = HttpCheckResult::new(check);
+
+    if let Some(expires_at) = parse_tls_expiry(&output.stderr) {
+        let days_left = (expires_at - now).num_days();
+        result.tls_expires_at = Some(expires_at);
+        result.tls_days_left = Some(days_left);
+        if days_left <= i64::from(check.tls_warn_days) {
+            result.tls_warning = Some(format!(
+                "certificate expires in {days_left} day(s), on {}",
+                expires_at.format("%Y-%m-%d")
+            ));
+        }
+    }
+
+    let (body, trailer) = match output.stdout.rfind(TRAILER_MARKER) {
+        Some(at) => (
+            output.stdout[..at].trim_end_matches('\n'),
+            Some(&output.stdout[at + TRAILER_MARKER.len()..]),
+        ),
+        None => (output.stdout.as_str(), None),
+    };
+    if let Some(trailer) = trailer {
+        let mut fields = trailer.split_whitespace();
+        result.status_code = fields
+            .next()
+            .and_then(|code| code.parse().ok())
+            .filter(|code| *code != 0);
+        result.latency_ms = fields
+            .next()
+            .and_then(|secs| secs.parse::<f64>().ok())
+            .map(|secs| secs * 1000.0);
+    }
+
+    if !output.success() {
+        let message = output
+            .stderr
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| line.starts_with("curl:"))
+            .or_else(|| {
+                output
+                    .stderr
+                    .lines()
+                    .rev()
+                    .map(str::trim)
+                    .find(|l| !l.is_empty())
+            })
+            .unwrap_or("curl failed without output")
+            .to_string();
+        let cause = curl_failure_cause(output.exit_code, &message);
+        return result.fail(message, cause);
+    }
+    if trailer.is_none() {
+        return result.fail(
+            "curl output had no status trailer".to_string(),
+            ErrorCause::ParseError,
+        );
+    }
+
+    if let Some(needle) = &check.body_contains {
+        result.body_matched = Some(body.contains(needle.as_str()));
+    }
+    match result.status_code {
+        Some(code) if code != check.expect_status => {
+            let error = format!("expected HTTP {}, got {code}", check.expect_status);
+            result.fail(error, ErrorCause::RemoteError)
+        }
+        None => result.fail("no HTTP response".to_string(), ErrorCause::RemoteError),
+        Some(_) if result.body_matched == Some(false) => {
+            let error = format!(
+                "response body does not contain {:?}",
+                check.body_contains.as_deref().unwrap_or_default()
+            );
+            result.fail(error, ErrorCause::RemoteError)
+        }
+        Some(_) => {
+            result.ok = true;
+            result
+        }
+    }
+}
+
+/// Run `checks` for one machine: through `machine_executor`, or from this
+/// host for checks with `from = "hub"`.
+pub async fn run_http_checks(
+    cx: &asupersync::Cx,
+    machine_executor: &Executor,
+    checks: &[HttpCheckConfig],
+) -> Vec<HttpCheckResult> {
+    let hub = Executor::local().with_shell(Shell::for_platform(Platform::local()));
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        let executor = match check.from {
+            HttpCheckOrigin::Machine => machine_executor,
+            HttpCheckOrigin::Hub => &hub,
+        };
+        let cmd = curl_command(check, executor.shell());
+        let timeout = Duration::from_secs(check.timeout_secs) + EXECUTOR_SLACK;
+        let result = match executor.run(cx, &cmd, timeout).await {
+            Ok(output) => parse_curl_output(check, &output, Utc::now()),
+            Err(e) => HttpCheckResult::new(check).fail(e.to_string(), ErrorCause::of(&e)),
+        };
+        results.push(result);
+    }
+    results
+}
+
+/// `http_check` collector for service endpoints
+#[derive(Default)]
+pub struct HttpCheckCollector {
+    /// Checks by machine id
+    checks: HashMap<String, Vec<HttpCheckConfig>>,
+}
+
+impl HttpCheckCollector {
+    /// Collector running the `http_checks` of each configured machine
+    #[must_use]
+    pub fn from_config(config: &VcConfig) -> Self {
+        Self {
+            checks: config
+                .machines
+                .iter()
+                .filter(|(_, machine)| !machine.http_checks.is_empty())
+                .map(|(id, machine)| (id.clone(), machine.http_checks.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Collector for HttpCheckCollector {
+    fn name(&self) -> &'static str {
+        "http_check"
+    }
+
+    fn required_tool(&self) -> Option<&'static str> {
+        None // curl is checked per request, so hub checks run without it
+    }
+
+    fn supports_platform(&self, _platform: Platform) -> bool {
+        true // curl.exe ships with Windows 10 and later
+    }
+
+    async fn collect(&self, cx: &asupersync::Cx, ctx: &CollectContext) -> CollectOutcome {
+        let start = Instant::now();
+        let collected_at = ctx.collected_at.to_rfc3339();
+        crate::collect_checkpoint!(cx, "collect_start");
+
+        let checks = self
+            .checks
+            .get(&ctx.machine_id)
+            .map_or(&[][..], Vec::as_slice);
+        let results = run_http_checks(cx, &ctx.executor, checks).await;
+
+        crate::collect_checkpoint!(cx, "post_checks_pre_return");
+        let mut result = CollectResult::with_rows(vec![RowBatch {
+            table: "http_check_results".to_string(),
+            rows: results
+                .iter()
+                .map(|r| r.to_row(&ctx.machine_id, &collected_at))
+                .collect(),
+        }])
+        .with_cursor(Cursor::now())
+        .with_duration(start.elapsed());
+        for check in &results {
+            if let Some(error) = &check.error {
+                result =
+                    result.with_warning(Warning::warn(format!("{}: {error}", check.check_name)));
+            }
+            if let Some(warning) = &check.tls_warning {
+                result =
+                    result.with_warning(Warning::warn(format!("{}: {warning}", check.check_name)));
+            }
+        }
+        asupersync::Outcome::Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn check(url: &str) -> HttpCheckConfig {
+        HttpCheckConfig {
+            name: "model".to_string(),
+            url: url.to_string(),
+            method: "GET".to_string(),
+            expect_status: 200,
+            timeout_secs: 5,
+            body_contains: Some("\"status\":\"ok\"".to_string()),
+            from: HttpCheckOrigin::Machine,
+            tls_warn_days: 14,
+        }
+    }
+
+    fn output(stdout: &str, stderr: &str, exit_code: i32) -> CommandOutput {
+        CommandOutput {
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+            exit_code,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2027, 2, 25, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_curl_command_per_shell() {
+        let posix = curl_command(&check("http://localhost:8080/health"), Shell::Posix);
+        assert!(posix.starts_with("curl -sS -v -X GET --max-time 5 -w "));
+        assert!(posix.ends_with(" 'http://localhost:8080/health'"));
+        assert!(posix.contains("__vc_http_check__ %{http_code} %{time_total}"));
+
+        let mut head = check("https://proxy/");
+        head.method = "HEAD".to_string();
+        let powershell = curl_command(&head, Shell::PowerShell);
+        assert!(powershell.starts_with("curl.exe -sS -v --head --max-time 5"));
+        assert!(powershell.ends_with(" 'https://proxy/'"));
+    }
+
+    #[test]
+    fn test_parse_curl_output_judges_status_and_body() {
+        let check = check("https://model.local/health");
+        let stderr = "* Server certificate:\n\
+                      *  start date: Dec  3 00:00:00 2026 GMT\n\
+                      *  expire date: Mar  3 23:59:59 2027 GMT\n\
+                      < HTTP/1.1 200 OK\n";
+        let ok = parse_curl_output(
+            &check,
+            &output(
+                "{\"status\":\"ok\"}\n__vc_http_check__ 200 0.042317\n",
+                stderr,
+                0,
+            ),
+            now(),
+        );
+        assert!(ok.ok, "{ok:?}");
+        assert_eq!(ok.status_code, Some(200));
+        assert!((ok.latency_ms.unwrap() - 42.317).abs() < 1e-9);
+        assert_eq!(ok.body_matched, Some(true));
+        // Expiring soon is a warning on a passing check
+        assert_eq!(ok.tls_days_left, Some(6));
+        assert!(ok.tls_warning.unwrap().contains("2027-03-03"));
+
+        let unavailable = parse_curl_output(
+            &check,
+            &output("upstream down\n__vc_http_check__ 503 0.01\n", "", 0),
+            now(),
+        );
+        assert!(!unavailable.ok);
+        assert_eq!(
+            unavailable.error.as_deref(),
+            Some("expected HTTP 200, got 503")
+        );
+        assert_eq!(unavailable.error_cause, Some(ErrorCause::RemoteError));
+        assert_eq!(unavailable.tls_warning, None);
+
+        let wrong_body = parse_curl_output(
+            &check,
+            &output(
+                "{\"status\":\"loading\"}\n__vc_http_check__ 200 0.01\n",
+                "",
+                0,
+            ),
+            now(),
+        );
+        assert!(!wrong_body.ok);
+        assert_eq!(wrong_body.body_matched, Some(false));
+    }
+
+    #[test]
+    fn test_parse_curl_output_classifies_failures() {
+        let check = check("http://localhost:8080/health");
+        let refused = parse_curl_output(
+            &check,
+            &output(
+                "\n__vc_http_check__ 000 0.000412\n",
+                "*   Trying 127.0.0.1:8080...\n\
+                 curl: (7) Failed to connect to localhost port 8080: Connection refused\n",
+                7,
+            ),
+            now(),
+        );
+        assert!(!refused.ok);
+        assert_eq!(refused.status_code, None);
+        assert_eq!(refused.error_cause, Some(ErrorCause::ConnectRefused));
+        assert!(refused.error.unwrap().starts_with("curl: (7)"));
+
+        let timeout = parse_curl_output(
+            &check,
+            &output(
+                "",
+                "curl: (28) Operation timed out after 5001 milliseconds\n",
+                28,
+            ),
+            now(),
+        );
+        assert_eq!(timeout.error_cause, Some(ErrorCause::Timeout));
+
+        let missing = parse_curl_output(&check, &output("", "sh: curl: not found\n", 127), now());
+        assert_eq!(missing.error_cause, Some(ErrorCause::ToolMissing));
+    }
+
+    #[test]
+    fn test_collector_runs_only_configured_machines() {
+        let mut config = VcConfig::default();
+        let mut machine: vc_config::MachineConfig =
+            serde_json::from_value(serde_json::json!({"name": "orko"})).unwrap();
+        machine
+            .http_checks
+            .push(check("http://localhost:8080/health"));
+        config.machines.insert("orko".to_string(), machine);
+        let collector = HttpCheckCollector::from_config(&config);
+        assert_eq!(collector.name(), "http_check");
+        assert_eq!(collector.checks.len(), 1);
+        assert!(collector.supports_platform(Platform::Windows));
+
+        let row = parse_curl_output(
+            &check("http://localhost:8080/health"),
+            &output("{\"status\":\"ok\"}\n__vc_http_check__ 200 0.01\n", "", 0),
+            now(),
+        )
+        .to_row("orko", "2027-02-25T00:00:00Z");
+        assert_eq!(row["ok"], 1);
+        assert_eq!(row["origin"], "machine");
+        assert_eq!(row["body_matched"], 1);
+        assert!(row["error_cause"].is_null());
+    }
+}
@@ -16,6 +16,8 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
 use crate::Warning;
+use crate::executor::Shell;
+use crate::platform::{Platform, detect_platform};
 
 // Re-export all collectors at the module level
 pub mod sysmoni;
@@ -60,6 +62,15 @@ pub use afsc::AfscCollector;
 pub mod cloud_bench;
 pub use cloud_bench::CloudBenchCollector;
 
+pub mod accounts;
+pub use accounts::AccountsCollector;
+
+pub mod http_check;
+pub use http_check::HttpCheckCollector;
+
+pub mod agent_inventory;
+pub use agent_inventory::AgentInventoryCollector;
+
 // Future collectors will be added here as submodules:
 // pub mod bv_br;
 
@@ -353,6 +364,20 @@ pub struct RuRepoStatus {
     pub modified_files: Vec<String>,
     #[serde(default)]
     pub untracked_files: Vec<String>,
+    /// Recent commits on the checked-out branch, newest first, when `ru`
+    /// reports them. Kept in `raw_json` for repo activity rollups.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_commits: Vec<RuCommit>,
+}
+
+/// A commit as reported by `ru status`
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+pub struct RuCommit {
+    pub sha: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// RFC 3339 commit time
+    pub committed_at: String,
 }
 
 /// Collector for repository status via the `ru` tool
@@ -518,8 +543,8 @@ impl Collector for RuCollector {
 /// Fallback system probe collector - ALWAYS ENABLED baseline health check
 ///
 /// This collector works on ANY Linux/macOS system using only standard shell
-/// commands. It provides baseline health data when other collectors are not
-/// available.
+/// commands, and on Windows through PowerShell. It provides baseline health
+/// data when other collectors are not available.
 ///
 /// Key design principle: NEVER FAIL. If individual commands fail, store
 /// partial data with warnings.
@@ -573,6 +598,10 @@ impl Collector for FallbackProbeCollector {
         false // Each collection is a point-in-time snapshot
     }
 
+    fn supports_platform(&self, _platform: Platform) -> bool {
+        true
+    }
+
     async fn collect(&self, cx: &asupersync::Cx, ctx: &CollectContext) -> CollectOutcome {
         let start = Instant::now();
         let mut warnings = Vec::new();
@@ -583,18 +612,24 @@ impl Collector for FallbackProbeCollector {
         let platform = Self::detect_platform(cx, ctx).await;
         crate::collect_checkpoint!(cx, "post_platform_detect");
 
-        // Collect uptime and load averages
-        let uptime_data =
-            Self::collect_uptime(cx, ctx, &platform, &mut warnings, &mut raw_outputs).await;
-        crate::collect_checkpoint!(cx, "post_uptime_collect");
-
-        // Collect memory stats
-        let memory_data =
-            Self::collect_memory(cx, ctx, &platform, &mut warnings, &mut raw_outputs).await;
-        crate::collect_checkpoint!(cx, "post_memory_collect");
-
-        // Collect disk usage
-        let disk_usage = Self::collect_disk_usage(cx, ctx, &mut warnings, &mut raw_outputs).await;
+        let (uptime_data, memory_data, disk_usage, cpu_pct) = if platform.is_windows() {
+            Self::collect_windows(cx, ctx, &mut warnings, &mut raw_outputs).await
+        } else {
+            // Collect uptime and load averages
+            let uptime_data =
+                Self::collect_uptime(cx, ctx, platform, &mut warnings, &mut raw_outputs).await;
+            crate::collect_checkpoint!(cx, "post_uptime_collect");
+
+            // Collect memory stats
+            let memory_data =
+                Self::collect_memory(cx, ctx, platform, &mut warnings, &mut raw_outputs).await;
+            crate::collect_checkpoint!(cx, "post_memory_collect");
+
+            // Collect disk usage
+            let disk_usage =
+                Self::collect_disk_usage(cx, ctx, &mut warnings, &mut raw_outputs).await;
+            (uptime_data, memory_data, disk_usage, None)
+        };
         crate::collect_checkpoint!(cx, "post_disk_collect");
 
         // Build the row
@@ -610,6 +645,7 @@ impl Collector for FallbackProbeCollector {
             "mem_used_bytes": memory_data.mem_used_bytes,
             "swap_total_bytes": memory_data.swap_total_bytes,
             "swap_used_bytes": memory_data.swap_used_bytes,
+            "cpu_pct": cpu_pct,
             "disk_usage_json": serde_json::to_string(&disk_usage).ok(),
             "raw_output": raw_outputs.join("\n---\n"),
         });
@@ -634,19 +670,19 @@ impl Collector for FallbackProbeCollector {
 }
 
 impl FallbackProbeCollector {
-    /// Detect platform (linux or macos)
-    async fn detect_platform(cx: &asupersync::Cx, ctx: &CollectContext) -> String {
-        let result = ctx.executor.run(cx, "uname -s", ctx.timeout).await;
-        match result {
-            Ok(output) if output.exit_code == 0 => {
-                let os = output.stdout.trim().to_lowercase();
-                if os.contains("darwin") {
-                    "macos".to_string()
-                } else {
-                    "linux".to_string()
-                }
-            }
-            _ => "linux".to_string(), // Default to Linux
+    /// Platform recorded for the machine, or detected when it was never
+    /// probed. Anything unrecognised is treated as Linux.
+    async fn detect_platform(cx: &asupersync::Cx, ctx: &CollectContext) -> Platform {
+        let platform = if ctx.platform == Platform::Unknown {
+            detect_platform(cx, &ctx.executor, ctx.timeout)
+                .await
+                .map_or(Platform::Unknown, |detected| detected.platform)
+        } else {
+            ctx.platform
+        };
+        match platform {
+            Platform::Unknown => Platform::Linux,
+            known => known,
         }
     }
 
@@ -654,14 +690,14 @@ impl FallbackProbeCollector {
     async fn collect_uptime(
         cx: &asupersync::Cx,
         ctx: &CollectContext,
-        platform: &str,
+        platform: Platform,
         warnings: &mut Vec<String>,
         raw_outputs: &mut Vec<String>,
     ) -> UptimeData {
         let mut data = UptimeData::default();
 
         // Try /proc/loadavg on Linux first (most reliable)
-        if platform == "linux" {
+        if platform == Platform::Linux {
             if let Ok(output) = ctx.executor.run(cx, "cat /proc/loadavg", ctx.timeout).await
                 && output.exit_code == 0
             {
@@ -794,13 +830,13 @@ impl FallbackProbeCollector {
     async fn collect_memory(
         cx: &asupersync::Cx,
         ctx: &CollectContext,
-        platform: &str,
+        platform: Platform,
         warnings: &mut Vec<String>,
         raw_outputs: &mut Vec<String>,
     ) -> MemoryData {
         let mut data = MemoryData::default();
 
-        if platform == "linux" {
+        if platform == Platform::Linux {
             if let Ok(output) = ctx.executor.run(cx, "cat /proc/meminfo", ctx.timeout).await
                 && output.exit_code == 0
             {
@@ -987,8 +1023,154 @@ impl FallbackProbeCollector {
 
         disks
     }
+
+    /// Collect uptime, memory, CPU and disks on Windows. There are no load
+    /// averages; CPU utilisation is sampled instead.
+    async fn collect_windows(
+        cx: &asupersync::Cx,
+        ctx: &CollectContext,
+        warnings: &mut Vec<String>,
+        raw_outputs: &mut Vec<String>,
+    ) -> (UptimeData, MemoryData, Vec<DiskUsage>, Option<f64>) {
+        let executor = (*ctx.executor).clone().with_shell(Shell::PowerShell);
+        let mut uptime = UptimeData::default();
+        let mut memory = MemoryData::default();
+
+        match executor.run(cx, WINDOWS_OS_COMMAND, ctx.timeout).await {
+            Ok(output) if output.exit_code == 0 => {
+                raw_outputs.push(format!("Win32_OperatingSystem:\n{}", output.stdout));
+                Self::parse_windows_os(&output.stdout, &mut uptime, &mut memory);
+            }
+            Ok(_) => warnings.push("Win32_OperatingSystem query failed".to_string()),
+            Err(_) => warnings.push("Could not query Win32_OperatingSystem".to_string()),
+        }
+
+        let mut cpu_pct = None;
+        if let Ok(output) = executor.run(cx, WINDOWS_CPU_COMMAND, ctx.timeout).await
+            && output.exit_code == 0
+        {
+            raw_outputs.push(format!("Get-Counter:\n{}", output.stdout));
+            cpu_pct = output.stdout.trim().parse().ok();
+        }
+        if cpu_pct.is_none() {
+            match executor
+                .run(cx, WINDOWS_TYPEPERF_COMMAND, ctx.timeout)
+                .await
+            {
+                Ok(output) if output.exit_code == 0 => {
+                    raw_outputs.push(format!("typeperf:\n{}", output.stdout));
+                    cpu_pct = Self::parse_typeperf(&output.stdout);
+                }
+                _ => warnings.push("Could not sample CPU with Get-Counter or typeperf".to_string()),
+            }
+        }
+
+        let mut disks = Vec::new();
+        match executor.run(cx, WINDOWS_DISK_COMMAND, ctx.timeout).await {
+            Ok(output) if output.exit_code == 0 => {
+                raw_outputs.push(format!("Win32_LogicalDisk:\n{}", output.stdout));
+                disks = Self::parse_windows_disks(&output.stdout);
+            }
+            Ok(_) => warnings.push("Win32_LogicalDisk query failed".to_string()),
+            Err(_) => warnings.push("Could not query Win32_LogicalDisk".to_string()),
+        }
+
+        (uptime, memory, disks, cpu_pct)
+    }
+
+    /// Parse the `key=value` lines of [`WINDOWS_OS_COMMAND`]; sizes are in KB
+    fn parse_windows_os(output: &str, uptime: &mut UptimeData, memory: &mut MemoryData) {
+        let mut swap_free_kb = None;
+        for line in output.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            let value: Option<i64> = value.trim().parse().ok();
+            match key {
+                "TotalVisibleMemorySize" => memory.mem_total_bytes = value.map(|v| v * 1024),
+                "FreePhysicalMemory" => memory.mem_available_bytes = value.map(|v| v * 1024),
+                "SizeStoredInPagingFiles" => memory.swap_total_bytes = value.map(|v| v * 1024),
+                "FreeSpaceInPagingFiles" => swap_free_kb = value,
+                "UptimeSeconds" => uptime.uptime_seconds = value,
+                _ => {}
+            }
+        }
+        if let (Some(total), Some(avail)) = (memory.mem_total_bytes, memory.mem_available_bytes) {
+            memory.mem_used_bytes = Some(total - avail);
+        }
+        if let (Some(total), Some(free_kb)) = (memory.swap_total_bytes, swap_free_kb) {
+            memory.swap_used_bytes = Some(total - free_kb * 1024);
+        }
+    }
+
+    /// Parse the CSV `typeperf -sc 1` prints: a header row, then one
+    /// `"timestamp","value"` sample
+    fn parse_typeperf(output: &str) -> Option<f64> {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with('"') && !line.contains("PDH-CSV"))
+            .find_map(|line| {
+                let value = line.rsplit("\",\"").next()?.trim_matches('"');
+                value.replace(',', ".").parse().ok()
+            })
+    }
+
+    /// Parse the `DeviceID Size FreeSpace` lines of [`WINDOWS_DISK_COMMAND`]
+    fn parse_windows_disks(output: &str) -> Vec<DiskUsage> {
+        let mut disks: Vec<DiskUsage> = output
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let mount = parts.next()?.to_string();
+                let total: i64 = parts.next()?.parse().ok()?;
+                let avail: i64 = parts.next()?.parse().ok()?;
+                if total <= 0 {
+                    return None;
+                }
+                let used = total - avail;
+                #[allow(clippy::cast_precision_loss)]
+                let pct = used as f64 / total as f64 * 100.0;
+                Some(DiskUsage {
+                    mount,
+                    total,
+                    used,
+                    avail,
+                    pct,
+                })
+            })
+            .collect();
+        disks.sort_by(|a, b| {
+            b.pct
+                .partial_cmp(&a.pct)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        disks
+    }
 }
 
+/// Memory and uptime on Windows, one `key=value` per line. Integers only,
+/// so the output does not depend on the machine's number format.
+const WINDOWS_OS_COMMAND: &str = "$os = Get-CimInstance Win32_OperatingSystem; \
+     'TotalVisibleMemorySize=' + $os.TotalVisibleMemorySize; \
+     'FreePhysicalMemory=' + $os.FreePhysicalMemory; \
+     'SizeStoredInPagingFiles=' + $os.SizeStoredInPagingFiles; \
+     'FreeSpaceInPagingFiles=' + $os.FreeSpaceInPagingFiles; \
+     'UptimeSeconds=' + [long]((Get-Date) - $os.LastBootUpTime).TotalSeconds";
+
+/// CPU utilisation over one second, in invariant number format
+const WINDOWS_CPU_COMMAND: &str = "(Get-Counter '\\Processor(_Total)\\% Processor Time' \
+     -SampleInterval 1 -MaxSamples 1).CounterSamples[0].CookedValue\
+     .ToString([cultureinfo]::InvariantCulture)";
+
+/// `typeperf` ships with every Windows; used where `Get-Counter` is missing
+/// (PowerShell 7 before 7.1)
+const WINDOWS_TYPEPERF_COMMAND: &str = "typeperf '\\Processor(_Total)\\% Processor Time' -sc 1";
+
+/// Local fixed disks, one `DeviceID Size FreeSpace` per line, in bytes
+const WINDOWS_DISK_COMMAND: &str = "Get-CimInstance Win32_LogicalDisk -Filter 'DriveType=3' | \
+     ForEach-Object { '{0} {1} {2}' -f $_.DeviceID, $_.Size, $_.FreeSpace }";
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1161,7 +1343,10 @@ mod tests {
                     "ahead": 2,
                     "behind": 0,
                     "modified_files": ["src/main.rs"],
-                    "untracked_files": ["new_file.txt", "temp/"]
+                    "untracked_files": ["new_file.txt", "temp/"],
+                    "recent_commits": [
+                        {"sha": "9f2c1e0", "author": "alice", "committed_at": "2026-10-15T09:30:00Z"}
+                    ]
                 }
             ]
         }"#;
@@ -1176,6 +1361,12 @@ mod tests {
         assert_eq!(repo.behind, 0);
         assert_eq!(repo.modified_files.len(), 1);
         assert_eq!(repo.untracked_files.len(), 2);
+        assert_eq!(repo.recent_commits[0].sha, "9f2c1e0");
+        assert!(
+            serde_json::to_string(repo)
+                .unwrap()
+                .contains("\"recent_commits\"")
+        );
     }
 
     #[test]
@@ -1199,6 +1390,7 @@ mod tests {
         assert_eq!(repo.behind, 0);
         assert!(repo.modified_files.is_empty());
         assert!(repo.untracked_files.is_empty());
+        assert!(repo.recent_commits.is_empty());
     }
 
     #[test]
@@ -1348,4 +1540,37 @@ Swap:    4294967296  1000000000  3294967296
         assert_eq!(data.swap_total_bytes, Some(4_294_967_296));
         assert_eq!(data.swap_used_bytes, Some(1_000_000_000));
     }
+
+    #[test]
+    fn test_parse_windows_probe_output() {
+        let mut uptime = UptimeData::default();
+        let mut memory = MemoryData::default();
+        let output = "TotalVisibleMemorySize=33469952\r\nFreePhysicalMemory=16734976\r\n\
+                      SizeStoredInPagingFiles=4980736\r\nFreeSpaceInPagingFiles=4718592\r\n\
+                      UptimeSeconds=93784\r\n";
+        FallbackProbeCollector::parse_windows_os(output, &mut uptime, &mut memory);
+        assert_eq!(uptime.uptime_seconds, Some(93_784));
+        assert!(uptime.load1.is_none());
+        assert_eq!(memory.mem_total_bytes, Some(34_273_230_848));
+        assert_eq!(memory.mem_used_bytes, Some(17_136_615_424));
+        assert_eq!(memory.swap_used_bytes, Some(268_435_456));
+
+        let typeperf = "\r\n\"(PDH-CSV 4.0)\",\"\\\\WINBOX\\Processor(_Total)\\% Processor Time\"\r\n\
+                        \"10/16/2026 09:12:01.441\",\"12.5\"\r\nExiting, please wait...\r\n";
+        assert_eq!(FallbackProbeCollector::parse_typeperf(typeperf), Some(12.5));
+        assert_eq!(
+            FallbackProbeCollector::parse_typeperf("Error: no data"),
+            None
+        );
+
+        // The optical drive has no Size and is skipped
+        let disks = FallbackProbeCollector::parse_windows_disks(
+            "C: 1000000000 250000000\r\nD: 2000000000 1800000000\r\nE:  \r\n",
+        );
+        assert_eq!(disks.len(), 2);
+        assert_eq!(disks[0].mount, "C:");
+        assert_eq!(disks[0].used, 750_000_000);
+        assert!((disks[0].pct - 75.0).abs() < f64::EPSILON);
+        assert_eq!(disks[1].mount, "D:");
+    }
 }
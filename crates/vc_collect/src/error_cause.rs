@@ -0,0 +1,305 @@
+//! Failure causes for collector runs
+//!
+//! Collector errors reach `collector_health` as free text (an ssh client
+//! message, a remote stderr, a parser complaint), which is fine to read but
+//! impossible to chart. Every failed run is also given one [`ErrorCause`],
+//! stored next to the raw message, so failures can be counted by why they
+//! happened and the scheduler can back off according to whether retrying
+//! soon could help.
+//!
+//! Classification is by substring over the lowercased message, checked in a
+//! fixed order: authentication before connection (ssh reports both for a
+//! rejected key), timeouts before connection (`Connection timed out`), and
+//! anything local to the remote command last.
+
+use crate::CollectError;
+use serde::{Deserialize, Serialize};
+
+/// Why a collector run failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCause {
+    /// The machine refused the connection or could not be reached
+    ConnectRefused,
+    /// The connection or the command did not finish in time
+    Timeout,
+    /// The machine was reached but rejected our credentials
+    AuthFailed,
+    /// The tool the collector runs is not installed there
+    ToolMissing,
+    /// The command ran but its output could not be understood
+    ParseError,
+    /// The command ran and failed on the remote side
+    RemoteError,
+    /// The collector has no implementation for the machine's platform
+    UnsupportedPlatform,
+    /// None of the above
+    Unknown,
+}
+
+/// Substrings marking each cause, checked in this order
+const PATTERNS: &[(ErrorCause, &[&str])] = &[
+    (
+        ErrorCause::UnsupportedPlatform,
+        &["has no implementation for", "unsupported platform"],
+    ),
+    (
+        ErrorCause::AuthFailed,
+        &[
+            "permission denied (publickey",
+            "permission denied (password",
+            "authentication failed",
+            "too many authentication failures",
+            "host key verification failed",
+            "no supported authentication methods",
+            "key loading failed",
+        ],
+    ),
+    (
+        ErrorCause::Timeout,
+        &["timed out", "timeout after", "deadline exceeded"],
+    ),
+    (
+        ErrorCause::ConnectRefused,
+        &[
+            "connection refused",
+            "connection reset",
+            "connection closed by",
+            "no route to host",
+            "network is unreachable",
+            "could not resolve hostname",
+            "name or service not known",
+            "connection failed to",
+            "session not connected",
+        ],
+    ),
+    (
+        ErrorCause::ToolMissing,
+        &[
+            "command not found",
+            ": not found",
+            "tool not available",
+            "no such file or directory",
+            "exit code 127",
+        ],
+    ),
+    (
+        ErrorCause::ParseError,
+        &[
+            "failed to parse",
+            "json error",
+            "expected value at line",
+            "eof while parsing",
+            "invalid type:",
+            "missing field",
+        ],
+    ),
+    (
+        ErrorCause::RemoteError,
+        &[
+            "exit code",
+            "exit status",
+            "database is locked",
+            "sqlite error",
+            "http error",
+        ],
+    ),
+];
+
+impl ErrorCause {
+    /// Every cause, in reporting order
+    pub const ALL: [Self; 8] = [
+        Self::ConnectRefused,
+        Self::Timeout,
+        Self::AuthFailed,
+        Self::ToolMissing,
+        Self::ParseError,
+        Self::RemoteError,
+        Self::UnsupportedPlatform,
+        Self::Unknown,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ConnectRefused => "connect_refused",
+            Self::Timeout => "timeout",
+            Self::AuthFailed => "auth_failed",
+            Self::ToolMissing => "tool_missing",
+            Self::ParseError => "parse_error",
+            Self::RemoteError => "remote_error",
+            Self::UnsupportedPlatform => "unsupported_platform",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a stored cause name
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|cause| cause.as_str() == value)
+    }
+
+    /// Classify a raw error message
+    #[must_use]
+    pub fn classify(message: &str) -> Self {
+        let message = message.to_lowercase();
+        PATTERNS
+            .iter()
+            .find(|(_, needles)| needles.iter().any(|needle| message.contains(needle)))
+            .map_or(Self::Unknown, |(cause, _)| *cause)
+    }
+
+    /// Classify a collector error, trusting its variant where it is specific
+    #[must_use]
+    pub fn of(error: &CollectError) -> Self {
+        match error {
+            CollectError::Timeout(_) => Self::Timeout,
+            CollectError::ToolNotFound(_) => Self::ToolMissing,
+            CollectError::ParseError(_) | CollectError::JsonError(_) => Self::ParseError,
+            CollectError::SqliteError(_) | CollectError::HttpError(_) => Self::RemoteError,
+            CollectError::UnsupportedPlatform { .. } => Self::UnsupportedPlatform,
+            other => Self::classify(&other.to_string()),
+        }
+    }
+
+    /// Whether retrying soon could plausibly succeed. Rejected credentials,
+    /// a missing tool and an unsupported platform stay that way until
+    /// someone intervenes.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        !matches!(
+            self,
+            Self::AuthFailed | Self::ToolMissing | Self::UnsupportedPlatform
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Messages as they have shown up in `collector_health.error_class`
+    const CORPUS: &[(&str, ErrorCause)] = &[
+        (
+            "ssh: connect to host 10.0.4.12 port 22: Connection refused",
+            ErrorCause::ConnectRefused,
+        ),
+        (
+            "ssh: connect to host builder-2 port 22: Connection timed out",
+            ErrorCause::Timeout,
+        ),
+        (
+            "ssh: connect to host mac-mini port 22: No route to host",
+            ErrorCause::ConnectRefused,
+        ),
+        (
+            "ssh: Could not resolve hostname gpu-box: Name or service not known",
+            ErrorCause::ConnectRefused,
+        ),
+        (
+            "kex_exchange_identification: read: Connection reset by peer",
+            ErrorCause::ConnectRefused,
+        ),
+        (
+            "Connection failed to orko: handshake aborted",
+            ErrorCause::ConnectRefused,
+        ),
+        (
+            "ubuntu@10.0.4.12: Permission denied (publickey).",
+            ErrorCause::AuthFailed,
+        ),
+        (
+            "Permission denied (publickey,password).",
+            ErrorCause::AuthFailed,
+        ),
+        (
+            "Authentication failed for ubuntu@orko",
+            ErrorCause::AuthFailed,
+        ),
+        ("Host key verification failed.", ErrorCause::AuthFailed),
+        (
+            "Received disconnect from 10.0.4.12 port 22:2: Too many authentication failures",
+            ErrorCause::AuthFailed,
+        ),
+        ("Timeout after 30s", ErrorCause::Timeout),
+        ("Command timed out after 10s", ErrorCause::Timeout),
+        (
+            "Command failed with exit code 127: bash: sysmoni: command not found",
+            ErrorCause::ToolMissing,
+        ),
+        ("sh: 1: ntm: not found", ErrorCause::ToolMissing),
+        ("Tool not available: caut", ErrorCause::ToolMissing),
+        (
+            "Failed to parse output: expected value at line 1 column 1",
+            ErrorCause::ParseError,
+        ),
+        (
+            "JSON error: EOF while parsing an object at line 3 column 0",
+            ErrorCause::ParseError,
+        ),
+        (
+            "Command failed with exit code 1: error: database is locked",
+            ErrorCause::RemoteError,
+        ),
+        (
+            "Command failed with exit code 2: fatal: not a git repository",
+            ErrorCause::RemoteError,
+        ),
+        (
+            "Collector sysmoni has no implementation for windows",
+            ErrorCause::UnsupportedPlatform,
+        ),
+        ("no error message", ErrorCause::Unknown),
+        ("panicked: index out of bounds", ErrorCause::Unknown),
+    ];
+
+    #[test]
+    fn test_classify_corpus() {
+        for (message, expected) in CORPUS {
+            assert_eq!(ErrorCause::classify(message), *expected, "{message}");
+        }
+    }
+
+    #[test]
+    fn test_classify_collect_errors_by_variant() {
+        assert_eq!(
+            ErrorCause::of(&CollectError::Timeout(Duration::from_secs(5))),
+            ErrorCause::Timeout
+        );
+        assert_eq!(
+            ErrorCause::of(&CollectError::ParseError("unexpected token".to_string())),
+            ErrorCause::ParseError
+        );
+        assert_eq!(
+            ErrorCause::of(&CollectError::ExecutionError(
+                "ssh: connect to host x port 22: Connection refused".to_string()
+            )),
+            ErrorCause::ConnectRefused
+        );
+        assert_eq!(
+            ErrorCause::of(&CollectError::Other("something odd".to_string())),
+            ErrorCause::Unknown
+        );
+    }
+
+    #[test]
+    fn test_cause_names_round_trip() {
+        for cause in ErrorCause::ALL {
+            assert_eq!(ErrorCause::parse(cause.as_str()), Some(cause));
+            assert_eq!(
+                serde_json::to_value(cause).unwrap(),
+                serde_json::json!(cause.as_str())
+            );
+        }
+        assert!(!ErrorCause::AuthFailed.is_transient());
+        assert!(!ErrorCause::UnsupportedPlatform.is_transient());
+        assert!(ErrorCause::Timeout.is_transient());
+    }
+}
@@ -3,8 +3,15 @@
 //! This module provides the `Executor` abstraction for running commands
 //! both locally and remotely via SSH. It also provides file operations
 //! and `SQLite` query support.
+//!
+//! Commands are written for the executor's [`Shell`]. A POSIX command is
+//! passed through as written; a PowerShell or cmd command is wrapped so it
+//! runs the same whichever shell the Windows OpenSSH server starts. The
+//! file helpers (`read_file`, `stat`, ...) assume a POSIX shell.
 
 use crate::CollectError;
+use crate::platform::Platform;
+use crate::ssh::base64_encode;
 use asupersync::Cx;
 use asupersync::process::{Command, Stdio};
 use asupersync::time::wall_now;
@@ -18,6 +25,72 @@ use tracing::{debug, instrument, warn};
 pub struct Executor {
     /// SSH configuration for remote execution
     ssh_config: Option<SshConfig>,
+    /// Shell the commands are written for
+    shell: Shell,
+}
+
+/// The shell a command is written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Shell {
+    /// `sh` locally, the login shell over SSH; commands run as written
+    #[default]
+    Posix,
+    /// Windows PowerShell, started through `powershell -EncodedCommand` so
+    /// no quoting survives from the outer shell
+    PowerShell,
+    /// Windows `cmd.exe`
+    Cmd,
+}
+
+impl Shell {
+    /// The shell collectors use on `platform`
+    #[must_use]
+    pub fn for_platform(platform: Platform) -> Self {
+        if platform.is_windows() {
+            Self::PowerShell
+        } else {
+            Self::Posix
+        }
+    }
+
+    /// The command line that runs `cmd` under this shell
+    #[must_use]
+    pub fn wrap(self, cmd: &str) -> String {
+        match self {
+            Self::Posix => cmd.to_string(),
+            Self::PowerShell => {
+                // -EncodedCommand takes base64 of the UTF-16LE script
+                let utf16: Vec<u8> = cmd.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                format!(
+                    "powershell -NoProfile -NonInteractive -EncodedCommand {}",
+                    base64_encode(&utf16)
+                )
+            }
+            // /s: strip the outer quotes and run the rest verbatim
+            Self::Cmd => format!("cmd /d /s /c \"{cmd}\""),
+        }
+    }
+
+    /// Quote `arg` as one literal argument for this shell
+    #[must_use]
+    pub fn quote(self, arg: &str) -> String {
+        match self {
+            Self::Posix => shell_escape(arg),
+            Self::PowerShell => format!("'{}'", arg.replace('\'', "''")),
+            Self::Cmd => format!("\"{}\"", arg.replace('"', "\"\"")),
+        }
+    }
+
+    /// Run the program at `path` with `args`. POSIX paths from `command -v`
+    /// are used as they are; Windows paths often hold spaces.
+    #[must_use]
+    pub fn invoke(self, path: &str, args: &str) -> String {
+        match self {
+            Self::Posix => format!("{path} {args}"),
+            Self::PowerShell => format!("& {} {args}", self.quote(path)),
+            Self::Cmd => format!("{} {args}", self.quote(path)),
+        }
+    }
 }
 
 /// SSH configuration for remote machines
@@ -115,7 +188,10 @@ impl Executor {
     /// Create a local executor
     #[must_use]
     pub fn local() -> Self {
-        Self { ssh_config: None }
+        Self {
+            ssh_config: None,
+            shell: Shell::Posix,
+        }
     }
 
     /// Create a remote executor with SSH config
@@ -123,9 +199,23 @@ impl Executor {
     pub fn remote(config: SshConfig) -> Self {
         Self {
             ssh_config: Some(config),
+            shell: Shell::Posix,
         }
     }
 
+    /// Set the shell commands are written for
+    #[must_use]
+    pub fn with_shell(mut self, shell: Shell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// The shell commands are written for
+    #[must_use]
+    pub fn shell(&self) -> Shell {
+        self.shell
+    }
+
     /// Check if this is a local executor
     #[must_use]
     pub fn is_local(&self) -> bool {
@@ -139,7 +229,15 @@ impl Executor {
     /// Returns [`CollectError`] only if command execution fails before producing output.
     #[instrument(skip(self, cx))]
     pub async fn check_tool(&self, cx: &Cx, tool: &str) -> Result<bool, CollectError> {
-        let cmd = format!("command -v {tool}");
+        let cmd = match self.shell {
+            Shell::Posix => format!("command -v {tool}"),
+            Shell::PowerShell => format!(
+                "if (Get-Command -Name {} -ErrorAction SilentlyContinue) {{ exit 0 }} \
+                 else {{ exit 1 }}",
+                self.shell.quote(tool)
+            ),
+            Shell::Cmd => format!("where {tool}"),
+        };
         match self.run(cx, &cmd, Duration::from_secs(5)).await {
             Ok(output) => Ok(output.exit_code == 0),
             Err(_) => Ok(false),
@@ -158,9 +256,10 @@ impl Executor {
         cmd: &str,
         timeout: Duration,
     ) -> Result<CommandOutput, CollectError> {
+        let cmd = self.shell.wrap(cmd);
         let output = match &self.ssh_config {
-            None => self.run_local(cx, cmd, timeout).await?,
-            Some(ssh) => self.run_remote(cx, cmd, timeout, ssh).await?,
+            None => self.run_local(cx, &cmd, timeout).await?,
+            Some(ssh) => self.run_remote(cx, &cmd, timeout, ssh).await?,
         };
         Ok(output)
     }
@@ -692,6 +791,35 @@ mod tests {
         assert_eq!(shell_escape("with'quote"), "'with'\\''quote'");
     }
 
+    #[test]
+    fn test_shell_wrapping_and_quoting() {
+        assert_eq!(Shell::Posix.wrap("uname -s"), "uname -s");
+        // "ver" as UTF-16LE, base64
+        assert_eq!(
+            Shell::PowerShell.wrap("ver"),
+            "powershell -NoProfile -NonInteractive -EncodedCommand dgBlAHIA"
+        );
+        assert_eq!(
+            Shell::Cmd.wrap("dir \"C:\\x\""),
+            "cmd /d /s /c \"dir \"C:\\x\"\""
+        );
+
+        assert_eq!(Shell::PowerShell.quote("it's"), "'it''s'");
+        assert_eq!(Shell::Cmd.quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            Shell::PowerShell.invoke("C:\\Program Files\\nodejs\\node.exe", "--version"),
+            "& 'C:\\Program Files\\nodejs\\node.exe' --version"
+        );
+        assert_eq!(
+            Shell::Posix.invoke("/usr/bin/node", "--version"),
+            "/usr/bin/node --version"
+        );
+
+        assert_eq!(Shell::for_platform(Platform::Windows), Shell::PowerShell);
+        assert_eq!(Shell::for_platform(Platform::Macos), Shell::Posix);
+        assert_eq!(Executor::local().shell(), Shell::Posix);
+    }
+
     #[test]
     fn test_ssh_config_parse() {
         let config = SshConfig::parse("ubuntu@example.com:22").unwrap();
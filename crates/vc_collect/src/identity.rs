@@ -0,0 +1,444 @@
+//! Machine identity as the machine itself reports it
+//!
+//! A probe reads three things from the machine: the UUID in its marker
+//! file (`~/.vc/machine_uuid`), its hardware identifier (`/etc/machine-id`,
+//! the macOS `IOPlatformUUID`, or the SMBIOS UUID on Windows) and its
+//! hostname. [`reconcile_identity`] compares them with the registry:
+//!
+//! - the marker holds the UUID the machine is registered with: nothing to do;
+//! - no marker: the registered UUID is written to it, unless the hardware
+//!   identifier is one seen with another machine's UUID;
+//! - the marker (or hardware identifier) holds another registered machine's
+//!   UUID: the host was registered twice. This is reported as a conflict
+//!   for `vc machines merge`, or, with `[identity] auto_rename`, the other
+//!   entry is merged into this one;
+//! - the marker holds a UUID nothing is registered with (the registry was
+//!   rebuilt): the machine's entry adopts it.
+//!
+//! Each probe records the machine id and hostname the UUID was seen under,
+//! so a changed DHCP hostname shows up as an earlier identity rather than
+//! a new machine.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use vc_store::{ActorContext, MachineMerge, StoreError, VcStore};
+
+use crate::CollectError;
+use crate::executor::{Executor, Shell};
+
+/// Marker file holding the machine's UUID, relative to the login user's home
+pub const MARKER_PATH: &str = ".vc/machine_uuid";
+
+/// Prints the marker, hardware identifier and hostname as `key=value` lines
+pub const IDENTITY_COMMAND: &str = "\
+    printf 'marker=%s\\n' \"$(cat ~/.vc/machine_uuid 2>/dev/null)\"; \
+    printf 'hardware=%s\\n' \"$(cat /etc/machine-id 2>/dev/null || \
+    ioreg -rd1 -c IOPlatformExpertDevice 2>/dev/null \
+    | awk -F'\"' '/IOPlatformUUID/ {print $4}')\"; \
+    printf 'hostname=%s\\n' \"$(hostname)\"";
+
+/// [`IDENTITY_COMMAND`] for Windows
+pub const IDENTITY_COMMAND_POWERSHELL: &str = "\
+    $m = Get-Content -Raw -ErrorAction SilentlyContinue (Join-Path $HOME '.vc\\machine_uuid'); \
+    'marker=' + \"$m\".Trim(); \
+    'hardware=' + (Get-CimInstance Win32_ComputerSystemProduct).UUID; \
+    'hostname=' + $env:COMPUTERNAME";
+
+/// Hardware identifiers that are placeholders rather than identifiers
+const PLACEHOLDER_HARDWARE_IDS: &[&str] = &[
+    "00000000-0000-0000-0000-000000000000",
+    "FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF",
+    "03000200-0400-0500-0006-000700080009",
+];
+
+/// What a machine says about itself
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteIdentity {
+    /// UUID in the marker file, when there is a well-formed one
+    pub marker_uuid: Option<String>,
+    /// The host's own identifier, when it has a usable one
+    pub hardware_id: Option<String>,
+    /// What the machine calls itself
+    pub hostname: Option<String>,
+}
+
+/// Parse the output of [`IDENTITY_COMMAND`]
+#[must_use]
+pub fn parse_identity(output: &str) -> RemoteIdentity {
+    let mut identity = RemoteIdentity::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match key {
+            "marker" => {
+                identity.marker_uuid = uuid::Uuid::parse_str(value)
+                    .ok()
+                    .map(|uuid| uuid.to_string());
+            }
+            "hardware" => {
+                identity.hardware_id = Some(value.to_string()).filter(|id| {
+                    !PLACEHOLDER_HARDWARE_IDS
+                        .iter()
+                        .any(|placeholder| placeholder.eq_ignore_ascii_case(id))
+                });
+            }
+            "hostname" => identity.hostname = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    identity
+}
+
+/// Read the machine's identity through `executor`.
+///
+/// # Errors
+///
+/// Returns [`CollectError`] when the command cannot be run.
+pub async fn read_identity(
+    cx: &asupersync::Cx,
+    executor: &Executor,
+    timeout: Duration,
+) -> Result<RemoteIdentity, CollectError> {
+    let command = match executor.shell() {
+        Shell::PowerShell => IDENTITY_COMMAND_POWERSHELL,
+        Shell::Posix | Shell::Cmd => IDENTITY_COMMAND,
+    };
+    let output = executor.run(cx, command, timeout).await?;
+    Ok(parse_identity(&output.stdout))
+}
+
+/// Write `machine_uuid` to the machine's marker file.
+///
+/// # Errors
+///
+/// Returns [`CollectError`] when `machine_uuid` is not a UUID or the
+/// command fails.
+pub async fn write_marker(
+    cx: &asupersync::Cx,
+    executor: &Executor,
+    machine_uuid: &str,
+    timeout: Duration,
+) -> Result<(), CollectError> {
+    // Only ever a UUID goes into the command line
+    let uuid = uuid::Uuid::parse_str(machine_uuid)
+        .map_err(|e| CollectError::ParseError(format!("not a machine UUID: {e}")))?;
+    let command = match executor.shell() {
+        Shell::PowerShell => format!(
+            "$d = Join-Path $HOME '.vc'; New-Item -ItemType Directory -Force $d | Out-Null; \
+             Set-Content -NoNewline -Path (Join-Path $d 'machine_uuid') -Value '{uuid}'"
+        ),
+        Shell::Posix | Shell::Cmd => {
+            format!("mkdir -p ~/.vc && printf '%s\\n' {uuid} > ~/{MARKER_PATH}")
+        }
+    };
+    executor.run_timeout(cx, &command, timeout).await?;
+    Ok(())
+}
+
+/// How a probed machine's identity compared with the registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum IdentityOutcome {
+    /// The machine carries the UUID it is registered with
+    Confirmed { machine_uuid: String },
+    /// The machine had no marker; it gets the registered UUID
+    Unmarked { machine_uuid: String },
+    /// The machine carries a UUID nothing is registered with; its entry
+    /// takes that UUID in place of `replaced`
+    Adopted {
+        machine_uuid: String,
+        replaced: String,
+    },
+    /// The machine carries the UUID of another registered machine
+    Conflict {
+        machine_uuid: String,
+        registered_as: String,
+    },
+    /// The machine carried another registered machine's UUID, and that
+    /// entry was merged into this one
+    Renamed {
+        machine_uuid: String,
+        merge: MachineMerge,
+    },
+}
+
+impl IdentityOutcome {
+    /// The UUID the machine is known by after the probe
+    #[must_use]
+    pub fn machine_uuid(&self) -> &str {
+        match self {
+            Self::Confirmed { machine_uuid }
+            | Self::Unmarked { machine_uuid }
+            | Self::Adopted { machine_uuid, .. }
+            | Self::Conflict { machine_uuid, .. }
+            | Self::Renamed { machine_uuid, .. } => machine_uuid,
+        }
+    }
+}
+
+/// The result of [`reconcile_identity`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityCheck {
+    #[serde(flatten)]
+    pub outcome: IdentityOutcome,
+    /// UUID to write to the machine's marker file, if it lacks it
+    pub write_marker: Option<String>,
+    /// Hostname the machine reported last time under this id, when it
+    /// reports a different one now
+    pub previous_hostname: Option<String>,
+}
+
+/// Compare what `machine_id` reported about itself with the registry, and
+/// record the identity it was seen under. `auto_rename` merges another
+/// entry carrying the machine's UUID into `machine_id`
+/// (`[identity] auto_rename`).
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if `machine_id` is not registered with a UUID or
+/// a query or the merge fails.
+pub fn reconcile_identity(
+    store: &VcStore,
+    machine_id: &str,
+    remote: &RemoteIdentity,
+    auto_rename: bool,
+    actor: &ActorContext,
+) -> Result<IdentityCheck, StoreError> {
+    let registered = store.machine_uuid(machine_id)?.ok_or_else(|| {
+        StoreError::QueryError(format!("machine {machine_id} has no machine UUID yet"))
+    })?;
+    let claimed = match &remote.marker_uuid {
+        Some(uuid) => Some(uuid.clone()),
+        None => match &remote.hardware_id {
+            Some(hardware_id) => store.machine_uuid_for_hardware(hardware_id)?,
+            None => None,
+        },
+    };
+
+    let outcome = match claimed {
+        None => IdentityOutcome::Unmarked {
+            machine_uuid: registered,
+        },
+        Some(uuid) if uuid == registered && remote.marker_uuid.is_some() => {
+            IdentityOutcome::Confirmed { machine_uuid: uuid }
+        }
+        Some(uuid) if uuid == registered => IdentityOutcome::Unmarked { machine_uuid: uuid },
+        Some(uuid) => match store.machine_with_uuid(&uuid)? {
+            Some(other) if auto_rename => {
+                let merge = store.merge_machines(&other, machine_id, actor)?;
+                if merge.machine_uuid != uuid {
+                    store.set_machine_uuid(machine_id, &uuid)?;
+                }
+                IdentityOutcome::Renamed {
+                    machine_uuid: uuid,
+                    merge,
+                }
+            }
+            Some(other) => IdentityOutcome::Conflict {
+                machine_uuid: uuid,
+                registered_as: other,
+            },
+            None => {
+                store.set_machine_uuid(machine_id, &uuid)?;
+                IdentityOutcome::Adopted {
+                    machine_uuid: uuid,
+                    replaced: registered,
+                }
+            }
+        },
+    };
+
+    // A conflict is recorded too: it is what `vc machines merge --conflicts`
+    // finds. Its marker is left alone until the two entries are merged.
+    let write_marker = match &outcome {
+        IdentityOutcome::Conflict { .. } => None,
+        outcome if remote.marker_uuid.as_deref() == Some(outcome.machine_uuid()) => None,
+        outcome => Some(outcome.machine_uuid().to_string()),
+    };
+    let hostname = remote
+        .hostname
+        .clone()
+        .unwrap_or_else(|| machine_id.to_string());
+    let previous_hostname = store.record_machine_identity(
+        outcome.machine_uuid(),
+        machine_id,
+        &hostname,
+        remote.hardware_id.as_deref(),
+    )?;
+    Ok(IdentityCheck {
+        outcome,
+        write_marker,
+        previous_hostname,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(store: &VcStore, id: &str, uuid: &str, added_at: &str) {
+        store
+            .execute_simple(&format!(
+                "INSERT INTO machines (machine_id, hostname, machine_uuid, added_at) \
+                 VALUES ('{id}', '{id}', '{uuid}', '{added_at}')"
+            ))
+            .unwrap();
+    }
+
+    fn seen(marker: Option<&str>, hardware: Option<&str>, hostname: &str) -> RemoteIdentity {
+        RemoteIdentity {
+            marker_uuid: marker.map(ToString::to_string),
+            hardware_id: hardware.map(ToString::to_string),
+            hostname: Some(hostname.to_string()),
+        }
+    }
+
+    const U1: &str = "6f1c1f0e-8a4b-4c7e-9d51-0d4f3c2b1a01";
+    const U2: &str = "6f1c1f0e-8a4b-4c7e-9d51-0d4f3c2b1a02";
+
+    #[test]
+    fn test_parse_identity() {
+        let identity = parse_identity(&format!(
+            "marker={}\nhardware=4c4c4544-0042\nhostname=orko\n",
+            U1.to_uppercase()
+        ));
+        assert_eq!(identity.marker_uuid.as_deref(), Some(U1));
+        assert_eq!(identity.hardware_id.as_deref(), Some("4c4c4544-0042"));
+        assert_eq!(identity.hostname.as_deref(), Some("orko"));
+
+        let identity = parse_identity(
+            "marker=not-a-uuid\r\nhardware=FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF\r\nhostname=\r\n",
+        );
+        assert_eq!(identity, RemoteIdentity::default());
+    }
+
+    #[test]
+    fn test_unmarked_machine_gets_its_registered_uuid() {
+        let store = VcStore::open_memory().unwrap();
+        register(&store, "orko", U1, "2026-01-01T00:00:00Z");
+        let actor = ActorContext::system("test");
+
+        let check = reconcile_identity(
+            &store,
+            "orko",
+            &seen(None, Some("hw-1"), "orko"),
+            false,
+            &actor,
+        )
+        .unwrap();
+        assert_eq!(
+            check.outcome,
+            IdentityOutcome::Unmarked {
+                machine_uuid: U1.to_string()
+            }
+        );
+        assert_eq!(check.write_marker.as_deref(), Some(U1));
+
+        let check = reconcile_identity(
+            &store,
+            "orko",
+            &seen(Some(U1), Some("hw-1"), "dhcp-17"),
+            false,
+            &actor,
+        )
+        .unwrap();
+        assert_eq!(
+            check.outcome,
+            IdentityOutcome::Confirmed {
+                machine_uuid: U1.to_string()
+            }
+        );
+        assert!(check.write_marker.is_none());
+        assert_eq!(check.previous_hostname.as_deref(), Some("orko"));
+    }
+
+    #[test]
+    fn test_machine_re_added_under_a_new_id_is_a_conflict() {
+        let store = VcStore::open_memory().unwrap();
+        register(&store, "orko", U1, "2026-01-01T00:00:00Z");
+        register(&store, "orko-new", U2, "2026-06-01T00:00:00Z");
+        let actor = ActorContext::system("test");
+
+        let check = reconcile_identity(
+            &store,
+            "orko-new",
+            &seen(Some(U1), None, "orko"),
+            false,
+            &actor,
+        )
+        .unwrap();
+        assert_eq!(
+            check.outcome,
+            IdentityOutcome::Conflict {
+                machine_uuid: U1.to_string(),
+                registered_as: "orko".to_string(),
+            }
+        );
+        assert!(check.write_marker.is_none());
+        assert_eq!(store.machine_uuid_conflicts().unwrap().len(), 1);
+        assert!(store.machine_uuid("orko").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_auto_rename_merges_the_old_entry() {
+        let store = VcStore::open_memory().unwrap();
+        register(&store, "orko", U1, "2026-01-01T00:00:00Z");
+        register(&store, "orko-new", U2, "2026-06-01T00:00:00Z");
+        // Recognized by hardware id alone: the marker file was lost
+        store
+            .record_machine_identity(U1, "orko", "orko", Some("hw-1"))
+            .unwrap();
+        let actor = ActorContext::system("test");
+
+        let check = reconcile_identity(
+            &store,
+            "orko-new",
+            &seen(None, Some("hw-1"), "orko"),
+            true,
+            &actor,
+        )
+        .unwrap();
+        let IdentityOutcome::Renamed {
+            machine_uuid,
+            merge,
+        } = &check.outcome
+        else {
+            panic!("expected a rename, got {:?}", check.outcome);
+        };
+        assert_eq!(machine_uuid, U1);
+        assert_eq!(merge.from, "orko");
+        assert_eq!(check.write_marker.as_deref(), Some(U1));
+        assert!(store.machine_uuid("orko").unwrap().is_none());
+        assert_eq!(store.machine_uuid("orko-new").unwrap().as_deref(), Some(U1));
+        assert!(store.machine_uuid_conflicts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_marker_uuid_is_adopted() {
+        let store = VcStore::open_memory().unwrap();
+        register(&store, "orko", U2, "2026-01-01T00:00:00Z");
+        let check = reconcile_identity(
+            &store,
+            "orko",
+            &seen(Some(U1), None, "orko"),
+            false,
+            &ActorContext::system("test"),
+        )
+        .unwrap();
+        assert_eq!(
+            check.outcome,
+            IdentityOutcome::Adopted {
+                machine_uuid: U1.to_string(),
+                replaced: U2.to_string(),
+            }
+        );
+        assert!(check.write_marker.is_none());
+        assert_eq!(store.machine_uuid("orko").unwrap().as_deref(), Some(U1));
+    }
+}
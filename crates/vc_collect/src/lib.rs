@@ -22,18 +22,27 @@ use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 
+pub mod clock;
 pub mod collectors;
+pub mod error_cause;
 pub mod executor;
+pub mod fleet_run;
+pub mod identity;
 pub mod machine;
 pub mod node;
+pub mod platform;
 pub mod probe;
 pub mod redact;
 pub mod remote;
 pub mod scheduler;
 pub mod ssh;
 
+pub use error_cause::ErrorCause;
 pub use machine::{Machine, MachineFilter, MachineRegistry, MachineStatus, ToolInfo};
-pub use probe::{ProbeResult, TOOL_SPECS, ToolProber, ToolSpec};
+pub use platform::{DetectedPlatform, Platform, detect_platform};
+pub use probe::{
+    ProbeResult, ServiceProber, ServiceSpec, ServiceTarget, TOOL_SPECS, ToolProber, ToolSpec,
+};
 pub use remote::{
     CollectionSummary, MachineCollectResult, MultiMachineCollector, RemoteCollectError,
     RemoteCollector, RemoteCollectorConfig,
@@ -92,6 +101,12 @@ pub enum CollectError {
     #[error("File not found: {0}")]
     FileNotFound(String),
 
+    #[error("Collector {collector} has no implementation for {platform}")]
+    UnsupportedPlatform {
+        collector: String,
+        platform: platform::Platform,
+    },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -471,6 +486,9 @@ pub struct CollectContext {
 
     /// Command executor
     pub executor: Arc<executor::Executor>,
+
+    /// Platform of the machine, as recorded by `vc machines probe`
+    pub platform: platform::Platform,
 }
 
 impl CollectContext {
@@ -496,6 +514,7 @@ impl CollectContext {
             max_bytes: Self::DEFAULT_MAX_BYTES,
             max_rows: Self::DEFAULT_MAX_ROWS,
             executor: Arc::new(executor::Executor::local()),
+            platform: platform::Platform::local(),
         }
     }
 
@@ -516,6 +535,7 @@ impl CollectContext {
             max_bytes: Self::DEFAULT_MAX_BYTES,
             max_rows: Self::DEFAULT_MAX_ROWS,
             executor: Arc::new(executor::Executor::remote(ssh_config)),
+            platform: platform::Platform::Unknown,
         }
     }
 
@@ -547,6 +567,18 @@ impl CollectContext {
         self
     }
 
+    /// Set the machine's platform, switching the executor to its shell
+    #[must_use]
+    pub fn with_platform(mut self, platform: platform::Platform) -> Self {
+        self.platform = platform;
+        self.executor = Arc::new(
+            (*self.executor)
+                .clone()
+                .with_shell(executor::Shell::for_platform(platform)),
+        );
+        self
+    }
+
     /// Get the timestamp cursor if present
     #[must_use]
     pub fn timestamp_cursor(&self) -> Option<DateTime<Utc>> {
@@ -596,6 +628,12 @@ pub trait Collector: Send + Sync {
         false
     }
 
+    /// Whether this collector can run on `platform`. Collectors are written
+    /// for POSIX shells; one with a Windows variant overrides this.
+    fn supports_platform(&self, platform: platform::Platform) -> bool {
+        !platform.is_windows()
+    }
+
     /// Perform data collection
     ///
     /// The `cx` parameter is the Asupersync capability context for this
@@ -613,6 +651,23 @@ pub trait Collector: Send + Sync {
             None => true,
         }
     }
+
+    /// Perform data collection, or fail at once with
+    /// [`CollectError::UnsupportedPlatform`] when the machine's platform has
+    /// no implementation, without running anything on it
+    async fn collect_on_platform(
+        &self,
+        cx: &asupersync::Cx,
+        ctx: &CollectContext,
+    ) -> CollectOutcome {
+        if !self.supports_platform(ctx.platform) {
+            return asupersync::Outcome::Err(CollectError::UnsupportedPlatform {
+                collector: self.name().to_string(),
+                platform: ctx.platform,
+            });
+        }
+        self.collect(cx, ctx).await
+    }
 }
 
 /// Registry of available collectors
@@ -696,7 +751,26 @@ impl CollectorRegistry {
         registry.register(Arc::new(collectors::PtCollector::new()));
         registry.register(Arc::new(collectors::AfscCollector::new()));
         registry.register(Arc::new(collectors::CloudBenchCollector::new()));
+        registry.register(Arc::new(collectors::AccountsCollector::default()));
+        registry.register(Arc::new(collectors::HttpCheckCollector::default()));
+        registry.register(Arc::new(collectors::AgentInventoryCollector::default()));
+
+        registry
+    }
 
+    /// Builtin collectors, with those that take settings built from `config`
+    #[must_use]
+    pub fn from_config(config: &vc_config::VcConfig) -> Self {
+        let mut registry = Self::with_builtins();
+        registry.register(Arc::new(collectors::AccountsCollector::from_config(
+            &config.accounts,
+        )));
+        registry.register(Arc::new(collectors::HttpCheckCollector::from_config(
+            config,
+        )));
+        registry.register(Arc::new(collectors::AgentInventoryCollector::from_config(
+            &config.agent_inventory,
+        )));
         registry
     }
 }
@@ -790,6 +864,8 @@ mod tests {
             "pt",
             "afsc",
             "cloud_benchmarker",
+            "accounts",
+            "agent_inventory",
         ] {
             assert!(
                 registry.get(name).is_some(),
@@ -798,6 +874,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_collector_registry_from_config() {
+        let registry = CollectorRegistry::from_config(&vc_config::VcConfig::default());
+        assert_eq!(registry.len(), CollectorRegistry::with_builtins().len());
+        assert!(registry.get("accounts").is_some());
+    }
+
     // Cursor tests
     #[test]
     fn test_cursor_timestamp() {
@@ -905,6 +988,32 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_collect_on_unsupported_platform_fails_cleanly() {
+        crate::run_async_test(async {
+            let collector = collectors::DummyCollector;
+            let cx = asupersync::Cx::for_testing();
+            let ctx = CollectContext::local("win-box", Duration::from_secs(30))
+                .with_platform(platform::Platform::Windows);
+            assert_eq!(ctx.executor.shell(), executor::Shell::PowerShell);
+
+            match collector.collect_on_platform(&cx, &ctx).await {
+                asupersync::Outcome::Err(err) => {
+                    assert_eq!(
+                        err.to_string(),
+                        "Collector dummy has no implementation for windows"
+                    );
+                    assert_eq!(ErrorCause::of(&err), ErrorCause::UnsupportedPlatform);
+                }
+                other => panic!("expected unsupported platform, got {other:?}"),
+            }
+
+            let ctx = ctx.with_platform(platform::Platform::Linux);
+            let result = collector.collect_on_platform(&cx, &ctx).await.unwrap();
+            assert!(result.success);
+        });
+    }
+
     // =========================================================================
     // Structured Concurrency Tests (bd-3lu)
     // =========================================================================
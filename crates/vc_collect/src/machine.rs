@@ -2,6 +2,7 @@
 //!
 //! Provides CRUD operations for machines and their tool availability.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::Utc;
@@ -9,9 +10,10 @@ use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use vc_config::{MachineConfig, VcConfig};
-use vc_store::VcStore;
+use vc_store::{ClockSkewSample, MachineServiceStatus, ToolVersion, VcStore};
 
 use crate::executor::SshConfig;
+use crate::platform::Platform;
 
 #[derive(Error, Debug)]
 pub enum RegistryError {
@@ -30,6 +32,8 @@ pub enum MachineStatus {
     Offline,
     #[default]
     Unknown,
+    /// Deliberately taken out of service; see `vc machines maintenance`
+    Maintenance,
 }
 
 impl MachineStatus {
@@ -39,13 +43,22 @@ impl MachineStatus {
             Self::Online => "online",
             Self::Offline => "offline",
             Self::Unknown => "unknown",
+            Self::Maintenance => "maintenance",
         }
     }
 }
 
+/// A registered machine. Build one with [`Machine::builder`] or convert a
+/// `machines` row with [`TryFrom`]; the struct is non-exhaustive so new
+/// columns do not break either path.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct Machine {
     pub machine_id: String,
+    /// Stays the same when the machine is renamed or re-added; assigned at
+    /// registration, see [`crate::identity`]
+    #[serde(default)]
+    pub machine_uuid: Option<String>,
     pub hostname: String,
     #[serde(default)]
     pub display_name: Option<String>,
@@ -77,9 +90,42 @@ pub struct Machine {
     pub metadata: Option<serde_json::Value>,
     #[serde(default = "default_true", deserialize_with = "deserialize_enabled")]
     pub enabled: bool,
+    /// Came from vc.toml but is no longer in it; kept, not deleted
+    #[serde(default, deserialize_with = "deserialize_boolish")]
+    pub config_absent: bool,
 }
 
 impl Machine {
+    /// Start building a machine; everything besides the id and hostname
+    /// defaults as for a new registry entry (remote, SSH port 22, enabled,
+    /// status unknown)
+    #[must_use]
+    pub fn builder(machine_id: impl Into<String>, hostname: impl Into<String>) -> MachineBuilder {
+        MachineBuilder {
+            machine: Self {
+                machine_id: machine_id.into(),
+                machine_uuid: None,
+                hostname: hostname.into(),
+                display_name: None,
+                ssh_host: None,
+                ssh_user: None,
+                ssh_key_path: None,
+                ssh_port: default_ssh_port(),
+                is_local: false,
+                os_type: None,
+                arch: None,
+                added_at: None,
+                last_seen_at: None,
+                last_probe_at: None,
+                status: MachineStatus::Unknown,
+                tags: Vec::new(),
+                metadata: None,
+                enabled: true,
+                config_absent: false,
+            },
+        }
+    }
+
     #[must_use]
     pub fn ssh_config(&self) -> Option<SshConfig> {
         let host = self.ssh_host.as_ref()?;
@@ -91,6 +137,17 @@ impl Machine {
         Some(cfg)
     }
 
+    /// The platform recorded by the last probe. A local machine that was
+    /// never probed runs where vc does.
+    #[must_use]
+    pub fn platform(&self) -> Platform {
+        match &self.os_type {
+            Some(os_type) => Platform::parse(os_type),
+            None if self.is_local => Platform::local(),
+            None => Platform::Unknown,
+        }
+    }
+
     fn normalize_metadata(mut self) -> Self {
         if let Some(serde_json::Value::String(raw)) = &self.metadata
             && let Ok(value) = serde_json::from_str::<serde_json::Value>(raw)
@@ -124,6 +181,7 @@ impl Machine {
 
         serde_json::json!({
             "machine_id": self.machine_id,
+            "machine_uuid": self.machine_uuid,
             "hostname": self.hostname,
             "display_name": self.display_name,
             "ssh_host": self.ssh_host,
@@ -140,16 +198,129 @@ impl Machine {
             "tags": &self.tags,
             "metadata": if metadata.is_null() { serde_json::Value::Null } else { metadata },
             "enabled": self.enabled,
+            "config_absent": self.config_absent,
         })
     }
 }
 
+/// A `machines` row as returned by `VcStore::query_json`. Integer booleans,
+/// and tags or metadata stored as JSON strings, are accepted.
+impl TryFrom<serde_json::Value> for Machine {
+    type Error = serde_json::Error;
+
+    fn try_from(row: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value::<Self>(row).map(Self::normalize_metadata)
+    }
+}
+
+/// Builder returned by [`Machine::builder`]
+#[derive(Debug, Clone)]
+pub struct MachineBuilder {
+    machine: Machine,
+}
+
+impl MachineBuilder {
+    #[must_use]
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.machine.display_name = Some(display_name.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_ssh_host(mut self, host: impl Into<String>) -> Self {
+        self.machine.ssh_host = Some(host.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_ssh_user(mut self, user: impl Into<String>) -> Self {
+        self.machine.ssh_user = Some(user.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_ssh_key_path(mut self, path: impl Into<String>) -> Self {
+        self.machine.ssh_key_path = Some(path.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_ssh_port(mut self, port: u16) -> Self {
+        self.machine.ssh_port = port;
+        self
+    }
+
+    /// The machine vc runs on, reached without SSH
+    #[must_use]
+    pub fn with_local(mut self, is_local: bool) -> Self {
+        self.machine.is_local = is_local;
+        self
+    }
+
+    #[must_use]
+    pub fn with_added_at(mut self, added_at: impl Into<String>) -> Self {
+        self.machine.added_at = Some(added_at.into());
+        self
+    }
+
+    #[must_use]
+    pub fn with_status(mut self, status: MachineStatus) -> Self {
+        self.machine.status = status;
+        self
+    }
+
+    #[must_use]
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.machine.tags = tags;
+        self
+    }
+
+    #[must_use]
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.machine.metadata = Some(metadata);
+        self
+    }
+
+    #[must_use]
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.machine.enabled = enabled;
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> Machine {
+        self.machine
+    }
+}
+
+/// The runtime-owned values vc.toml last set for a machine. A reload only
+/// changes them where the config differs from this.
+#[derive(Debug, Serialize, Deserialize)]
+struct ConfigSnapshot {
+    enabled: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl ConfigSnapshot {
+    fn of(machine: &Machine) -> Self {
+        Self {
+            enabled: machine.enabled,
+            tags: machine.tags.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolInfo {
     pub tool_name: String,
     pub tool_path: Option<String>,
     pub tool_version: Option<String>,
     pub is_available: bool,
+    /// Where `tool_version` came from (`probe`, `version_file`); `probe`
+    /// when unset
+    #[serde(default)]
+    pub version_source: Option<String>,
 }
 
 impl ToolInfo {
@@ -159,6 +330,7 @@ impl ToolInfo {
             tool_path: None,
             tool_version: None,
             is_available: true,
+            version_source: None,
         }
     }
 }
@@ -197,6 +369,25 @@ impl MachineFilter {
     }
 }
 
+/// Every registered machine, by hostname, without writing to the store
+/// (the read side of [`MachineRegistry::list_machines`])
+///
+/// # Errors
+///
+/// Returns [`RegistryError`] when query execution fails.
+pub fn load_machines(store: &VcStore) -> Result<Vec<Machine>, RegistryError> {
+    let sql = "SELECT machine_id, machine_uuid, hostname, display_name, ssh_host, ssh_user, \
+               ssh_key_path, ssh_port, is_local, os_type, arch, \
+               COALESCE(added_at, created_at) AS added_at, last_seen_at, last_probe_at, status, \
+               tags, COALESCE(metadata, metadata_json) AS metadata, enabled, config_absent \
+               FROM machines ORDER BY hostname";
+    Ok(store
+        .query_json(sql)?
+        .into_iter()
+        .filter_map(|row| Machine::try_from(row).ok())
+        .collect())
+}
+
 pub struct MachineRegistry {
     store: Arc<VcStore>,
 }
@@ -207,7 +398,14 @@ impl MachineRegistry {
         Self { store }
     }
 
-    /// Load machines from config and persist them into the registry table.
+    /// Load machines from config and merge them into the registry table.
+    ///
+    /// The config is authoritative for connection parameters (host, user,
+    /// port, key). Everything else — status, last seen and probe results,
+    /// tags, metadata and enabled overrides set at runtime — is kept unless
+    /// the config itself changed that value since the last load. Machines
+    /// that came from the config and are no longer in it are marked
+    /// `config_absent` rather than deleted.
     ///
     /// # Errors
     ///
@@ -227,20 +425,117 @@ impl MachineRegistry {
             machines.push(local_machine_default());
         }
 
-        let rows: Vec<_> = machines.iter().map(Machine::to_row).collect();
+        let mut existing: HashMap<String, Machine> = self
+            .list_machines(None)?
+            .into_iter()
+            .map(|machine| (machine.machine_id.clone(), machine))
+            .collect();
+        let snapshots = self.config_snapshots()?;
+
+        let mut rows = Vec::with_capacity(machines.len());
+        for fresh in machines {
+            let snapshot = ConfigSnapshot::of(&fresh);
+            let last = snapshots.get(&fresh.machine_id);
+            let merged = match existing.remove(&fresh.machine_id) {
+                Some(current) => merge_config(current, fresh, last),
+                None => fresh,
+            };
+            let mut row = merged.to_row();
+            row["config_snapshot"] = serde_json::Value::String(serde_json::to_string(&snapshot)?);
+            rows.push(row);
+        }
         self.store.upsert_json("machines", &rows, &["machine_id"])?;
+
+        let configured: Vec<String> = rows
+            .iter()
+            .filter_map(|row| row["machine_id"].as_str())
+            .map(|id| format!("'{}'", escape_sql_literal(id)))
+            .collect();
+        self.store.execute_simple(&format!(
+            "UPDATE machines SET config_absent = TRUE \
+             WHERE config_snapshot IS NOT NULL AND machine_id NOT IN ({})",
+            configured.join(", ")
+        ))?;
+        self.restore_maintenance_status()?;
+        self.assign_missing_uuids()?;
         Ok(rows.len())
     }
 
-    /// Insert or update a machine row.
+    /// Give every machine registered without a UUID (new ones, and those
+    /// from before UUIDs were assigned) a fresh one
+    fn assign_missing_uuids(&self) -> Result<(), RegistryError> {
+        let rows = self
+            .store
+            .query_json("SELECT machine_id FROM machines WHERE machine_uuid IS NULL")?;
+        for id in rows.iter().filter_map(|row| row["machine_id"].as_str()) {
+            self.store.set_machine_uuid(id, &new_machine_uuid())?;
+        }
+        Ok(())
+    }
+
+    /// What the config last set for each machine loaded from it.
+    fn config_snapshots(&self) -> Result<HashMap<String, ConfigSnapshot>, RegistryError> {
+        let rows = self.store.query_json(
+            "SELECT machine_id, config_snapshot FROM machines WHERE config_snapshot IS NOT NULL",
+        )?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let id = row["machine_id"].as_str()?.to_string();
+                let snapshot = serde_json::from_str(row["config_snapshot"].as_str()?).ok()?;
+                Some((id, snapshot))
+            })
+            .collect())
+    }
+
+    /// Insert or update a machine row. A machine registered for the first
+    /// time without a UUID gets one; a registered machine keeps its own.
     ///
     /// # Errors
     ///
     /// Returns [`RegistryError`] when persistence fails.
     pub fn upsert_machine(&self, machine: &Machine) -> Result<(), RegistryError> {
-        let row = machine.to_row();
+        let mut row = machine.to_row();
+        // The row is replaced whole; keep what the config last set for it
+        // and the identity it was registered with
+        let existing = self.store.query_json(&format!(
+            "SELECT config_snapshot, machine_uuid FROM machines WHERE machine_id = '{}'",
+            escape_sql_literal(&machine.machine_id)
+        ))?;
+        let existing = existing.into_iter().next();
+        if let Some(snapshot) = existing
+            .as_ref()
+            .map(|row| &row["config_snapshot"])
+            .filter(|snapshot| !snapshot.is_null())
+        {
+            row["config_snapshot"] = snapshot.clone();
+        }
+        row["machine_uuid"] = match existing
+            .as_ref()
+            .and_then(|row| row["machine_uuid"].as_str())
+        {
+            Some(uuid) => serde_json::json!(uuid),
+            None => serde_json::json!(
+                machine
+                    .machine_uuid
+                    .clone()
+                    .unwrap_or_else(new_machine_uuid)
+            ),
+        };
         self.store
             .upsert_json("machines", &[row], &["machine_id"])?;
+        self.restore_maintenance_status()?;
+        Ok(())
+    }
+
+    /// Upserted rows carry a fresh status; machines with an open maintenance
+    /// window must keep reading `maintenance`.
+    fn restore_maintenance_status(&self) -> Result<(), RegistryError> {
+        self.store.execute_simple(
+            "UPDATE machines SET status = 'maintenance' WHERE machine_id IN ( \
+                 SELECT machine_id FROM machine_maintenance WHERE ended_at IS NULL \
+             )",
+        )?;
         Ok(())
     }
 
@@ -251,17 +546,17 @@ impl MachineRegistry {
     /// Returns [`RegistryError`] when querying or deserialization fails.
     pub fn get_machine(&self, id: &str) -> Result<Option<Machine>, RegistryError> {
         let sql = format!(
-            "SELECT machine_id, hostname, display_name, ssh_host, ssh_user, ssh_key_path, ssh_port, \
-             is_local, os_type, arch, COALESCE(added_at, created_at) AS added_at, last_seen_at, \
-             last_probe_at, status, tags, COALESCE(metadata, metadata_json) AS metadata, enabled \
+            "SELECT machine_id, machine_uuid, hostname, display_name, ssh_host, ssh_user, \
+             ssh_key_path, ssh_port, is_local, os_type, arch, \
+             COALESCE(added_at, created_at) AS added_at, last_seen_at, last_probe_at, status, \
+             tags, COALESCE(metadata, metadata_json) AS metadata, enabled, config_absent \
              FROM machines WHERE machine_id = '{}' LIMIT 1",
             escape_sql_literal(id)
         );
 
         let mut rows = self.store.query_json(&sql)?;
         if let Some(row) = rows.pop() {
-            let machine: Machine = serde_json::from_value(row)?;
-            return Ok(Some(machine.normalize_metadata()));
+            return Ok(Some(Machine::try_from(row)?));
         }
         Ok(None)
     }
@@ -275,33 +570,25 @@ impl MachineRegistry {
         &self,
         filter: Option<MachineFilter>,
     ) -> Result<Vec<Machine>, RegistryError> {
-        let sql = "SELECT machine_id, hostname, display_name, ssh_host, ssh_user, ssh_key_path, ssh_port, \
-                   is_local, os_type, arch, COALESCE(added_at, created_at) AS added_at, last_seen_at, \
-                   last_probe_at, status, tags, COALESCE(metadata, metadata_json) AS metadata, enabled \
-                   FROM machines ORDER BY hostname";
-        let rows = self.store.query_json(sql)?;
-
-        let mut machines: Vec<Machine> = rows
-            .into_iter()
-            .filter_map(|row| serde_json::from_value::<Machine>(row).ok())
-            .map(Machine::normalize_metadata)
-            .collect();
-
+        let mut machines = load_machines(&self.store)?;
         if let Some(filter) = filter {
             machines.retain(|m| filter.matches(m));
         }
-
         Ok(machines)
     }
 
     /// Update machine status and touch `last_seen_at`.
     ///
+    /// A machine in maintenance keeps that status; only ending the
+    /// maintenance window changes it.
+    ///
     /// # Errors
     ///
     /// Returns [`RegistryError`] when update fails.
     pub fn update_status(&self, id: &str, status: MachineStatus) -> Result<(), RegistryError> {
         let sql = format!(
-            "UPDATE machines SET status = '{}', last_seen_at = current_timestamp WHERE machine_id = '{}'",
+            "UPDATE machines SET status = '{}', last_seen_at = current_timestamp \
+             WHERE machine_id = '{}' AND COALESCE(status, '') <> 'maintenance'",
             status.as_str(),
             escape_sql_literal(id)
         );
@@ -311,6 +598,9 @@ impl MachineRegistry {
 
     /// Record or update tool probe information for a machine.
     ///
+    /// A reported version is also appended to the machine's version history,
+    /// which records a drift event when it differs from the last one seen.
+    ///
     /// # Errors
     ///
     /// Returns [`RegistryError`] when upsert/update fails.
@@ -326,6 +616,14 @@ impl MachineRegistry {
 
         self.store
             .upsert_json("machine_tools", &[row], &["machine_id", "tool_name"])?;
+        if let Some(version) = &tool.tool_version {
+            self.store.record_tool_version(
+                id,
+                &tool.tool_name,
+                version,
+                tool.version_source.as_deref().unwrap_or("probe"),
+            )?;
+        }
 
         let sql = format!(
             "UPDATE machines SET last_probe_at = current_timestamp WHERE machine_id = '{}'",
@@ -335,6 +633,54 @@ impl MachineRegistry {
         Ok(())
     }
 
+    /// Replace the recorded service checks for a machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError`] when the write fails.
+    pub fn record_services(
+        &self,
+        id: &str,
+        services: &[MachineServiceStatus],
+    ) -> Result<(), RegistryError> {
+        self.store.replace_machine_services(id, services)?;
+        Ok(())
+    }
+
+    /// Record a clock skew measurement, raising or resolving the machine's
+    /// skew alert against `warn_secs`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError`] when the write fails.
+    pub fn record_clock_skew(
+        &self,
+        sample: &ClockSkewSample,
+        warn_secs: u64,
+    ) -> Result<(), RegistryError> {
+        crate::clock::record_clock_skew(&self.store, sample, warn_secs)?;
+        Ok(())
+    }
+
+    /// Latest service checks recorded for a machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError`] when the query fails.
+    pub fn list_services(&self, id: &str) -> Result<Vec<MachineServiceStatus>, RegistryError> {
+        Ok(self.store.list_machine_services(Some(id))?)
+    }
+
+    /// Current version of each tool on a machine, with where it came from
+    /// and what it replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError`] when the query fails.
+    pub fn list_tool_versions(&self, id: &str) -> Result<Vec<ToolVersion>, RegistryError> {
+        Ok(self.store.list_tool_versions(Some(id))?)
+    }
+
     /// Enable or disable a machine in the registry.
     ///
     /// # Errors
@@ -349,12 +695,28 @@ impl MachineRegistry {
         self.store.execute_simple(&sql)?;
         Ok(())
     }
+
+    /// Record the platform a probe detected as the machine's `os_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegistryError`] when update fails.
+    pub fn set_platform(&self, id: &str, platform: Platform) -> Result<(), RegistryError> {
+        let sql = format!(
+            "UPDATE machines SET os_type = '{}' WHERE machine_id = '{}'",
+            platform.as_str(),
+            escape_sql_literal(id)
+        );
+        self.store.execute_simple(&sql)?;
+        Ok(())
+    }
 }
 
 fn local_machine_default() -> Machine {
     let hostname = default_hostname();
     Machine {
         machine_id: "local".to_string(),
+        machine_uuid: None,
         hostname: hostname.clone(),
         display_name: Some("Local Machine".to_string()),
         ssh_host: None,
@@ -371,6 +733,7 @@ fn local_machine_default() -> Machine {
         tags: Vec::new(),
         metadata: None,
         enabled: true,
+        config_absent: false,
     }
 }
 
@@ -397,6 +760,7 @@ fn machine_from_config(id: &str, config: &MachineConfig) -> Machine {
 
     Machine {
         machine_id: id.to_string(),
+        machine_uuid: None,
         hostname,
         display_name: Some(config.name.clone()),
         ssh_host: config.ssh_host.clone(),
@@ -413,9 +777,74 @@ fn machine_from_config(id: &str, config: &MachineConfig) -> Machine {
         tags: config.tags.clone(),
         metadata,
         enabled: config.enabled,
+        config_absent: false,
+    }
+}
+
+/// Apply a machine's config entry to its registry row. `last` is what the
+/// config set on the previous load; without it (rows from before snapshots
+/// were kept, or added by hand) config tags are added and none removed.
+fn merge_config(current: Machine, fresh: Machine, last: Option<&ConfigSnapshot>) -> Machine {
+    let enabled = if last.is_some_and(|last| last.enabled == fresh.enabled) {
+        current.enabled
+    } else {
+        fresh.enabled
+    };
+
+    let last_tags = last.map(|last| last.tags.as_slice()).unwrap_or_default();
+    let mut tags: Vec<String> = current
+        .tags
+        .into_iter()
+        .filter(|tag| !(last_tags.contains(tag) && !fresh.tags.contains(tag)))
+        .collect();
+    for tag in &fresh.tags {
+        if !last_tags.contains(tag) && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    let metadata = match (current.metadata, fresh.metadata) {
+        (Some(serde_json::Value::Object(mut map)), fresh) => {
+            if let Some(serde_json::Value::Object(fresh)) = fresh {
+                map.extend(fresh.into_iter().filter(|(key, _)| key != "tags"));
+            } else {
+                // Collectors only ever come from the config
+                map.remove("collectors");
+            }
+            if map.contains_key("tags") {
+                map.insert("tags".to_string(), serde_json::json!(tags));
+            }
+            Some(serde_json::Value::Object(map))
+        }
+        (None, Some(serde_json::Value::Object(mut map))) => {
+            map.insert("tags".to_string(), serde_json::json!(tags));
+            Some(serde_json::Value::Object(map))
+        }
+        (current, fresh) => current.or(fresh),
+    };
+
+    Machine {
+        hostname: fresh.hostname,
+        display_name: fresh.display_name,
+        ssh_host: fresh.ssh_host,
+        ssh_user: fresh.ssh_user,
+        ssh_key_path: fresh.ssh_key_path,
+        ssh_port: fresh.ssh_port,
+        is_local: fresh.is_local,
+        added_at: current.added_at.or(fresh.added_at),
+        tags,
+        metadata,
+        enabled,
+        config_absent: false,
+        ..current
     }
 }
 
+/// A fresh machine UUID
+pub(crate) fn new_machine_uuid() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
 fn escape_sql_literal(value: &str) -> String {
     value.replace('\'', "''")
 }
@@ -523,6 +952,35 @@ mod tests {
         assert!(machines[0].is_local);
     }
 
+    #[test]
+    fn test_registry_assigns_stable_machine_uuids() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store);
+        registry.load_from_config(&VcConfig::default()).unwrap();
+        let uuid = registry
+            .get_machine("local")
+            .unwrap()
+            .unwrap()
+            .machine_uuid
+            .expect("assigned on load");
+
+        registry.load_from_config(&VcConfig::default()).unwrap();
+        let mut machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.machine_uuid.as_deref(), Some(uuid.as_str()));
+
+        // Re-adding a machine does not give it a new identity
+        machine.machine_uuid = None;
+        registry.upsert_machine(&machine).unwrap();
+        let machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.machine_uuid.as_deref(), Some(uuid.as_str()));
+
+        registry
+            .upsert_machine(&Machine::builder("added", "added.lan").build())
+            .unwrap();
+        let added = registry.get_machine("added").unwrap().unwrap();
+        assert!(added.machine_uuid.is_some_and(|other| other != uuid));
+    }
+
     #[test]
     fn test_registry_loads_remote_machine() {
         let store = Arc::new(VcStore::open_memory().unwrap());
@@ -538,8 +996,10 @@ mod tests {
                 ssh_key: None,
                 ssh_port: 2222,
                 enabled: true,
-                collectors: std::collections::HashMap::new(),
+                collectors: HashMap::new(),
                 tags: vec!["builder".to_string()],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
 
@@ -568,6 +1028,220 @@ mod tests {
         assert!(!machine.enabled);
     }
 
+    #[test]
+    fn test_registry_set_platform() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store);
+        registry.load_from_config(&VcConfig::default()).unwrap();
+
+        let machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.platform(), Platform::local());
+
+        registry.set_platform("local", Platform::Windows).unwrap();
+        let machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.os_type.as_deref(), Some("windows"));
+        assert_eq!(machine.platform(), Platform::Windows);
+    }
+
+    #[test]
+    fn test_maintenance_status_survives_updates_and_reload() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store.clone());
+        let config = VcConfig::default();
+        registry.load_from_config(&config).unwrap();
+        store
+            .start_maintenance(
+                "local",
+                Some("RAM upgrade"),
+                None,
+                &vc_store::ActorContext::system("op"),
+            )
+            .unwrap();
+
+        registry
+            .update_status("local", MachineStatus::Offline)
+            .unwrap();
+        registry.load_from_config(&config).unwrap();
+        let machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.status, MachineStatus::Maintenance);
+
+        store
+            .end_maintenance("local", &vc_store::ActorContext::system("op"))
+            .unwrap();
+        registry
+            .update_status("local", MachineStatus::Online)
+            .unwrap();
+        let machine = registry.get_machine("local").unwrap().unwrap();
+        assert_eq!(machine.status, MachineStatus::Online);
+    }
+
+    fn remote_config(host: &str, tags: &[&str]) -> MachineConfig {
+        MachineConfig {
+            name: "Remote 1".to_string(),
+            ssh_host: Some(host.to_string()),
+            ssh_user: Some("ubuntu".to_string()),
+            ssh_key: None,
+            ssh_port: 22,
+            enabled: true,
+            collectors: HashMap::new(),
+            tags: tags.iter().map(ToString::to_string).collect(),
+            normalize_clock_skew: false,
+            http_checks: vec![],
+        }
+    }
+
+    #[test]
+    fn test_reload_keeps_runtime_state() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store.clone());
+        let mut config = VcConfig::default();
+        config.machines.insert(
+            "remote-1".to_string(),
+            remote_config("example.com", &["builder"]),
+        );
+        registry.load_from_config(&config).unwrap();
+
+        registry
+            .update_status("remote-1", MachineStatus::Online)
+            .unwrap();
+        registry
+            .record_tool("remote-1", &ToolInfo::new("sysmoni"))
+            .unwrap();
+        registry.set_enabled("remote-1", false).unwrap();
+        let mut machine = registry.get_machine("remote-1").unwrap().unwrap();
+        machine.tags.push("gpu".to_string());
+        machine.os_type = Some("Linux".to_string());
+        registry.upsert_machine(&machine).unwrap();
+        let before = registry.get_machine("remote-1").unwrap().unwrap();
+
+        registry.load_from_config(&config).unwrap();
+        registry.load_from_config(&config).unwrap();
+        let after = registry.get_machine("remote-1").unwrap().unwrap();
+
+        assert_eq!(after.status, MachineStatus::Online);
+        assert!(after.last_seen_at.is_some());
+        assert_eq!(after.last_seen_at, before.last_seen_at);
+        assert_eq!(after.last_probe_at, before.last_probe_at);
+        assert_eq!(after.added_at, before.added_at);
+        assert_eq!(after.os_type.as_deref(), Some("Linux"));
+        assert!(!after.enabled);
+        assert_eq!(after.tags, vec!["builder", "gpu"]);
+        assert!(!after.config_absent);
+    }
+
+    #[test]
+    fn test_reload_applies_config_changes() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store);
+        let mut config = VcConfig::default();
+        config.machines.insert(
+            "remote-1".to_string(),
+            remote_config("example.com", &["builder", "arm"]),
+        );
+        registry.load_from_config(&config).unwrap();
+        registry
+            .update_status("remote-1", MachineStatus::Offline)
+            .unwrap();
+        let mut machine = registry.get_machine("remote-1").unwrap().unwrap();
+        machine.tags.push("flaky".to_string());
+        registry.upsert_machine(&machine).unwrap();
+
+        // Connection parameters follow the config; config tags follow it
+        // too, but tags added at runtime stay
+        let mut changed = remote_config("10.0.4.12", &["builder", "gpu"]);
+        changed.ssh_port = 2222;
+        changed.enabled = false;
+        config.machines.insert("remote-1".to_string(), changed);
+        registry.load_from_config(&config).unwrap();
+
+        let machine = registry.get_machine("remote-1").unwrap().unwrap();
+        assert_eq!(machine.ssh_host.as_deref(), Some("10.0.4.12"));
+        assert_eq!(machine.hostname, "10.0.4.12");
+        assert_eq!(machine.ssh_port, 2222);
+        assert!(!machine.enabled);
+        assert_eq!(machine.tags, vec!["builder", "flaky", "gpu"]);
+        assert_eq!(machine.status, MachineStatus::Offline);
+
+        // Re-enabled at runtime, the unchanged config leaves it enabled
+        registry.set_enabled("remote-1", true).unwrap();
+        registry.load_from_config(&config).unwrap();
+        assert!(registry.get_machine("remote-1").unwrap().unwrap().enabled);
+    }
+
+    #[test]
+    fn test_machine_removed_from_config_is_marked_absent() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store);
+        let mut config = VcConfig::default();
+        config
+            .machines
+            .insert("remote-1".to_string(), remote_config("example.com", &[]));
+        registry.load_from_config(&config).unwrap();
+        registry
+            .update_status("remote-1", MachineStatus::Online)
+            .unwrap();
+
+        // Added by hand, never in the config
+        let mut manual = local_machine_default();
+        manual.machine_id = "scratch".to_string();
+        manual.is_local = false;
+        registry.upsert_machine(&manual).unwrap();
+
+        config.machines.clear();
+        registry.load_from_config(&config).unwrap();
+
+        let machine = registry.get_machine("remote-1").unwrap().unwrap();
+        assert!(machine.config_absent);
+        assert_eq!(machine.status, MachineStatus::Online);
+        assert!(
+            !registry
+                .get_machine("scratch")
+                .unwrap()
+                .unwrap()
+                .config_absent
+        );
+        assert!(
+            !registry
+                .get_machine("local")
+                .unwrap()
+                .unwrap()
+                .config_absent
+        );
+        assert_eq!(registry.list_machines(None).unwrap().len(), 3);
+
+        config
+            .machines
+            .insert("remote-1".to_string(), remote_config("example.com", &[]));
+        registry.load_from_config(&config).unwrap();
+        assert!(
+            !registry
+                .get_machine("remote-1")
+                .unwrap()
+                .unwrap()
+                .config_absent
+        );
+    }
+
+    #[test]
+    fn test_record_tool_keeps_version_history() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let registry = MachineRegistry::new(store.clone());
+        registry.load_from_config(&VcConfig::default()).unwrap();
+
+        for version in ["0.4.0", "0.4.0", "0.5.0"] {
+            let mut tool = ToolInfo::new("sysmoni");
+            tool.tool_version = Some(version.to_string());
+            registry.record_tool("local", &tool).unwrap();
+        }
+
+        let history = store
+            .tool_version_history("local", Some("sysmoni"), 10)
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].tool_version, "0.5.0");
+        assert_eq!(history[0].source, "probe");
+    }
+
     #[test]
     fn test_machine_deserializes_stringified_tags_and_integer_flags() {
         let row = serde_json::json!({
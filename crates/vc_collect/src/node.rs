@@ -3,8 +3,10 @@
 //! Bundles collected data into compressed JSONL batches with signed manifests.
 //! Supports offline buffering and deduplication on ingest.
 
-use chrono::{DateTime, Utc};
+use crate::clock;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
@@ -29,6 +31,21 @@ pub struct BundleManifest {
     pub content_hash: String,
     /// Total payload size in bytes
     pub total_bytes: u64,
+    /// Version of the vc-node agent that built the bundle (absent from
+    /// bundles built by older agents)
+    #[serde(default)]
+    pub agent_version: Option<String>,
+    /// When the agent shipped the bundle, by its own clock; compared with
+    /// arrival on ingest to measure the machine's clock skew
+    #[serde(default)]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl BundleManifest {
+    /// Stamp the send time just before the bundle leaves the agent
+    pub fn mark_sent(&mut self) {
+        self.sent_at = Some(Utc::now());
+    }
 }
 
 /// A single batch within a bundle
@@ -159,6 +176,8 @@ impl BundleBuilder {
             batches: self.batches,
             content_hash,
             total_bytes,
+            agent_version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            sent_at: None,
         }
     }
 }
@@ -167,66 +186,379 @@ impl BundleBuilder {
 // Ingest result
 // ============================================================================
 
+/// What happened to one batch of a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    /// Written in one transaction
+    Applied {
+        rows_inserted: usize,
+        rows_deduplicated: usize,
+        /// Malformed lines and rows the table cannot hold
+        rows_skipped: usize,
+    },
+    /// The same batch content was applied before
+    SkippedDuplicate,
+    /// Rolled back; a rerun of the bundle retries it
+    Failed { error: String },
+}
+
+/// Outcome of one batch, identified as `<bundle_id>:<index>`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub batch_id: String,
+    pub collector: String,
+    pub table: String,
+    #[serde(flatten)]
+    pub outcome: BatchOutcome,
+}
+
+/// Whether every batch of a bundle is now in the store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestStatus {
+    #[default]
+    Complete,
+    /// At least one batch failed
+    Partial,
+}
+
 /// Result of ingesting a bundle
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IngestResult {
     pub bundle_id: String,
+    #[serde(default)]
+    pub status: IngestStatus,
     pub batches_processed: usize,
     pub rows_ingested: usize,
     pub rows_deduplicated: usize,
+    #[serde(default)]
+    pub batches: Vec<BatchResult>,
 }
 
-/// Ingest a bundle into the store, deduplicating by content hash
+/// Per-bundle inputs shared by its batches
+struct BatchContext<'a> {
+    manifest: &'a BundleManifest,
+    received: DateTime<Utc>,
+    since: Option<DateTime<Utc>>,
+    skew_ms: Option<i64>,
+    normalize: bool,
+}
+
+/// Ingest a bundle into the store, deduplicating by content hash.
+///
+/// Each batch is applied in its own transaction and gets an explicit
+/// outcome in the result: applied, skipped as a duplicate, or failed with
+/// the error. A failed batch is rolled back and recorded as failed, the
+/// remaining batches still run, and the bundle's status is `partial`.
+/// Ingesting the same bundle again skips the batches already applied and
+/// retries only the failed ones.
+///
+/// Batches whose hash was ingested before are skipped whole. Otherwise each
+/// row is checked against row hashes seen for its table within
+/// `config.dedup_lookback_hours` (and against earlier rows of the same
+/// batch), so a resend reassembled under a fresh bundle ID only inserts rows
+/// that are actually new. Skipped rows count towards `rows_deduplicated`.
+/// The agent version the bundle carries is added to the machine's
+/// `vc-node` version history.
+///
+/// A bundle stamped with `sent_at` is also a clock skew measurement (see
+/// [`crate::clock`]). When `machine` opts into `normalize_clock_skew`, row
+/// timestamps are shifted by the machine's measured skew before insert,
+/// in tables that can record the offset. Each batch lands in
+/// `collector_health` as of its newest row in hub time, so freshness
+/// reflects how old pushed data is rather than when it arrived.
 ///
 /// # Errors
 ///
-/// Returns [`vc_store::StoreError`] when dedup checks, row insertion, or ingest recording fails.
+/// Returns [`vc_store::StoreError`] when recording the agent version, the
+/// clock skew or a batch failure fails. Errors within a batch become its
+/// `failed` outcome instead.
 pub fn ingest_bundle(
     store: &vc_store::VcStore,
     manifest: &BundleManifest,
+    config: &vc_config::IngestConfig,
+    machine: Option<&vc_config::MachineConfig>,
 ) -> Result<IngestResult, vc_store::StoreError> {
-    let mut rows_ingested = 0;
-    let mut rows_deduplicated = 0;
+    let received = Utc::now();
 
-    for batch in &manifest.batches {
-        let dedup_key = DedupKey::new(&manifest.machine_id, &batch.collector, &batch.batch_hash);
+    if let Some(version) = &manifest.agent_version {
+        store.record_tool_version(&manifest.machine_id, "vc-node", version, "bundle")?;
+    }
+    if let Some(sent_at) = manifest.sent_at {
+        let sample = clock::ingest_skew(&manifest.machine_id, sent_at, received);
+        clock::record_clock_skew(store, &sample, config.clock_skew_warn_secs)?;
+    }
+    let context = BatchContext {
+        manifest,
+        received,
+        since: dedup_since(config, received),
+        skew_ms: store
+            .effective_clock_skew(&manifest.machine_id)?
+            .map(|sample| sample.skew_ms),
+        normalize: machine.is_some_and(|machine| machine.normalize_clock_skew),
+    };
+
+    let mut result = IngestResult {
+        bundle_id: manifest.bundle_id.clone(),
+        status: IngestStatus::Complete,
+        batches_processed: manifest.batches.len(),
+        rows_ingested: 0,
+        rows_deduplicated: 0,
+        batches: Vec::with_capacity(manifest.batches.len()),
+    };
+    for (index, batch) in manifest.batches.iter().enumerate() {
+        let batch_id = format!("{}:{index}", manifest.bundle_id);
+        let table = collector_to_table(&batch.collector);
+        let outcome = match ingest_batch(store, &context, batch, &batch_id, &table) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                let error = e.to_string();
+                tracing::warn!(
+                    batch = %batch_id,
+                    table = %table,
+                    error = %error,
+                    "ingest batch failed"
+                );
+                store.record_ingest_failure(
+                    &vc_store::IngestBatch {
+                        bundle_id: &manifest.bundle_id,
+                        batch_id: &batch_id,
+                        machine_id: &manifest.machine_id,
+                        collector: &batch.collector,
+                        content_hash: &batch.batch_hash,
+                        row_count: batch.row_count,
+                        table: &table,
+                        rows: &[],
+                        row_hashes: &[],
+                    },
+                    &error,
+                )?;
+                BatchOutcome::Failed { error }
+            }
+        };
 
-        // Check if this batch was already ingested
-        if store.has_ingest_record(&dedup_key.payload_hash)? {
-            rows_deduplicated += batch.row_count;
-            continue;
+        match &outcome {
+            BatchOutcome::Applied {
+                rows_inserted,
+                rows_deduplicated,
+                ..
+            } => {
+                result.rows_ingested += rows_inserted;
+                result.rows_deduplicated += rows_deduplicated;
+            }
+            BatchOutcome::SkippedDuplicate => result.rows_deduplicated += batch.row_count,
+            BatchOutcome::Failed { .. } => result.status = IngestStatus::Partial,
         }
+        result.batches.push(BatchResult {
+            batch_id,
+            collector: batch.collector.clone(),
+            table,
+            outcome,
+        });
+    }
 
-        // Ingest each line as a JSON record
-        let table = collector_to_table(&batch.collector);
-        for line in &batch.lines {
-            let Ok(json_val) = serde_json::from_str::<serde_json::Value>(line) else {
-                continue; // Skip malformed rows (fail-soft)
-            };
-            if store.insert_json(&table, &json_val).is_err() {
+    Ok(result)
+}
+
+/// Dedup, normalize and apply one batch, then record its freshness
+fn ingest_batch(
+    store: &vc_store::VcStore,
+    context: &BatchContext<'_>,
+    batch: &BatchEntry,
+    batch_id: &str,
+    table: &str,
+) -> Result<BatchOutcome, vc_store::StoreError> {
+    let manifest = context.manifest;
+    let dedup_key = DedupKey::new(&manifest.machine_id, &batch.collector, &batch.batch_hash);
+
+    // Check if this batch was already ingested
+    if store.has_ingest_record(&dedup_key.payload_hash)? {
+        return Ok(BatchOutcome::SkippedDuplicate);
+    }
+
+    let row_skew = match context.skew_ms {
+        Some(skew)
+            if context.normalize && store.table_has_column(table, clock::ROW_SKEW_FIELD)? =>
+        {
+            Some(skew)
+        }
+        _ => None,
+    };
+
+    // Skip malformed rows (fail-soft). Hashes are taken before
+    // normalization so a new skew measurement does not defeat dedup.
+    let parsed: Vec<(serde_json::Value, String)> = batch
+        .lines
+        .iter()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .map(|mut row| {
+            let hash = row_content_hash(&manifest.machine_id, &row);
+            if let Some(skew) = row_skew {
+                clock::normalize_row(&mut row, skew);
+            }
+            (row, hash)
+        })
+        .collect();
+    let malformed = batch.lines.len() - parsed.len();
+    let seen = match context.since {
+        Some(since) => {
+            let hashes: Vec<String> = parsed.iter().map(|(_, hash)| hash.clone()).collect();
+            store.seen_ingest_row_hashes(table, &hashes, since)?
+        }
+        None => HashSet::new(),
+    };
+    let newest_row = parsed
+        .iter()
+        .filter_map(|(row, _)| clock::row_timestamp(row))
+        .max();
+
+    let mut batch_hashes = HashSet::new();
+    let mut rows = Vec::with_capacity(parsed.len());
+    let mut row_hashes = Vec::new();
+    let mut rows_deduplicated = 0;
+    for (row, hash) in parsed {
+        if context.since.is_some() {
+            if seen.contains(&hash) || !batch_hashes.insert(hash.clone()) {
+                rows_deduplicated += 1;
                 continue;
             }
-            rows_ingested += 1;
+            row_hashes.push(hash);
         }
+        rows.push(row);
+    }
 
-        // Record the ingestion for future dedup
-        store.record_ingest(
-            &manifest.bundle_id,
-            &manifest.machine_id,
-            &batch.collector,
-            &dedup_key.payload_hash,
-            batch.row_count,
-        )?;
+    // Rows, row hashes and the ingest record commit together
+    let Some(applied) = store.apply_ingest_batch(&vc_store::IngestBatch {
+        bundle_id: &manifest.bundle_id,
+        batch_id,
+        machine_id: &manifest.machine_id,
+        collector: &batch.collector,
+        content_hash: &dedup_key.payload_hash,
+        row_count: batch.row_count,
+        table,
+        rows: &rows,
+        row_hashes: &row_hashes,
+    })?
+    else {
+        return Ok(BatchOutcome::SkippedDuplicate);
+    };
+
+    // Rows not normalized on insert are still read in hub time here
+    let pending_skew = if row_skew.is_some() {
+        0
+    } else {
+        context.skew_ms.unwrap_or(0)
+    };
+    let received = context.received;
+    let data_at = newest_row.map_or(received, |newest| {
+        (newest - chrono::Duration::milliseconds(pending_skew)).min(received)
+    });
+    // The batch is committed; a missing freshness sample must not undo that
+    if let Err(e) = store.insert_collector_health(&vc_store::CollectorHealth {
+        machine_id: manifest.machine_id.clone(),
+        collector: batch.collector.clone(),
+        collected_at: data_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        success: true,
+        duration_ms: None,
+        rows_inserted: i64::try_from(applied.rows_inserted).unwrap_or(i64::MAX),
+        bytes_parsed: batch
+            .lines
+            .iter()
+            .map(|line| i64::try_from(line.len()).unwrap_or(i64::MAX))
+            .sum(),
+        error_class: None,
+        error_cause: None,
+        freshness_seconds: Some((received - data_at).num_seconds()),
+        payload_hash: Some(dedup_key.payload_hash.clone()),
+        collector_version: manifest.agent_version.clone(),
+        schema_version: Some(manifest.schema_version.to_string()),
+        cursor_json: None,
+    }) {
+        tracing::warn!(batch = %batch_id, error = %e, "failed to record ingest freshness");
     }
 
-    Ok(IngestResult {
-        bundle_id: manifest.bundle_id.clone(),
-        batches_processed: manifest.batches.len(),
-        rows_ingested,
+    Ok(BatchOutcome::Applied {
+        rows_inserted: applied.rows_inserted,
         rows_deduplicated,
+        rows_skipped: malformed + applied.rows_skipped,
     })
 }
 
+/// Oldest row hash still consulted for dedup, or `None` when row-level dedup
+/// is disabled
+#[must_use]
+pub fn dedup_since(config: &vc_config::IngestConfig, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if config.dedup_lookback_hours == 0 {
+        return None;
+    }
+    let hours = i64::try_from(config.dedup_lookback_hours).unwrap_or(i64::MAX);
+    let lookback = chrono::Duration::try_hours(hours).unwrap_or(chrono::Duration::MAX);
+    Some(
+        now.checked_sub_signed(lookback)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC),
+    )
+}
+
+/// Fields a sender stamps per transmission; they differ between resends of
+/// the same row and are left out of its content hash
+const NON_CANONICAL_FIELDS: &[&str] = &["bundle_id", "batch_id", "sent_at", "ingested_at"];
+
+/// Stable content hash of an ingested row: its canonical fields (keys sorted,
+/// per-transmission fields dropped) plus source machine and row timestamp
+#[must_use]
+pub fn row_content_hash(machine_id: &str, row: &serde_json::Value) -> String {
+    let timestamp = clock::ROW_TIMESTAMP_FIELDS
+        .iter()
+        .find_map(|field| row.get(*field))
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let mut canonical = String::new();
+    match row {
+        serde_json::Value::Object(fields) => {
+            let fields: serde_json::Map<String, serde_json::Value> = fields
+                .iter()
+                .filter(|(key, _)| !NON_CANONICAL_FIELDS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            write_canonical_json(&serde_json::Value::Object(fields), &mut canonical);
+        }
+        other => write_canonical_json(other, &mut canonical),
+    }
+    hash_content(&format!("{machine_id}\n{timestamp}\n{canonical}"))
+}
+
+/// Serialize `value` with object keys sorted, independent of map ordering
+fn write_canonical_json(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical_json(&fields[key], out);
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
 /// Map collector names to table names
 fn collector_to_table(collector: &str) -> String {
     match collector {
@@ -256,6 +588,7 @@ fn hash_content(content: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use vc_config::IngestConfig;
 
     // ========================================================================
     // Hash tests
@@ -462,14 +795,27 @@ mod tests {
     fn test_ingest_result_serialization() {
         let result = IngestResult {
             bundle_id: "bundle-orko-123".to_string(),
+            status: IngestStatus::Partial,
             batches_processed: 2,
             rows_ingested: 10,
             rows_deduplicated: 3,
+            batches: vec![BatchResult {
+                batch_id: "bundle-orko-123:1".to_string(),
+                collector: "sysmoni".to_string(),
+                table: "sys_samples".to_string(),
+                outcome: BatchOutcome::Failed {
+                    error: "constraint".to_string(),
+                },
+            }],
         };
-        let json = serde_json::to_string(&result).unwrap();
-        let parsed: IngestResult = serde_json::from_str(&json).unwrap();
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["status"], "partial");
+        assert_eq!(json["batches"][0]["outcome"], "failed");
+        assert_eq!(json["batches"][0]["error"], "constraint");
+        let parsed: IngestResult = serde_json::from_value(json).unwrap();
         assert_eq!(parsed.rows_ingested, 10);
         assert_eq!(parsed.rows_deduplicated, 3);
+        assert_eq!(parsed.batches, result.batches);
     }
 
     // ========================================================================
@@ -487,9 +833,16 @@ mod tests {
         );
         let manifest = builder.build();
 
-        let result = ingest_bundle(&store, &manifest).unwrap();
+        let result = ingest_bundle(&store, &manifest, &IngestConfig::default(), None).unwrap();
         assert_eq!(result.batches_processed, 1);
         assert_eq!(result.rows_deduplicated, 0);
+
+        let agent = store
+            .latest_tool_version("orko", "vc-node")
+            .unwrap()
+            .unwrap();
+        assert_eq!(agent.tool_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(agent.source, "bundle");
     }
 
     #[test]
@@ -507,11 +860,11 @@ mod tests {
         let m2 = b2.build();
 
         // First ingest succeeds
-        let r1 = ingest_bundle(&store, &m1).unwrap();
+        let r1 = ingest_bundle(&store, &m1, &IngestConfig::default(), None).unwrap();
         assert_eq!(r1.rows_deduplicated, 0);
 
         // Second ingest deduplicates
-        let r2 = ingest_bundle(&store, &m2).unwrap();
+        let r2 = ingest_bundle(&store, &m2, &IngestConfig::default(), None).unwrap();
         assert_eq!(r2.rows_deduplicated, 1);
         assert_eq!(r2.rows_ingested, 0);
     }
@@ -528,11 +881,222 @@ mod tests {
         b2.add_batch("sysmoni", vec![r#"{"v":2}"#.to_string()], None);
         let m2 = b2.build();
 
-        let r1 = ingest_bundle(&store, &m1).unwrap();
-        let r2 = ingest_bundle(&store, &m2).unwrap();
+        let r1 = ingest_bundle(&store, &m1, &IngestConfig::default(), None).unwrap();
+        let r2 = ingest_bundle(&store, &m2, &IngestConfig::default(), None).unwrap();
 
         // Both should ingest (different content)
         assert_eq!(r1.rows_deduplicated, 0);
         assert_eq!(r2.rows_deduplicated, 0);
     }
+
+    #[test]
+    fn test_ingest_resend_with_new_bundle_id_dedups_rows() {
+        let store = vc_store::VcStore::open_memory().unwrap();
+        let config = IngestConfig::default();
+        let row = |ts: &str, cpu: f64| {
+            format!(r#"{{"machine_id": "orko", "collected_at": "{ts}", "cpu_total": {cpu}}}"#)
+        };
+
+        let mut b1 = BundleBuilder::new("orko");
+        b1.add_batch(
+            "sysmoni",
+            vec![
+                row("2026-10-16 10:00:00", 12.5),
+                row("2026-10-16 10:01:00", 14.0),
+            ],
+            None,
+        );
+        let m1 = b1.build();
+        let r1 = ingest_bundle(&store, &m1, &config, None).unwrap();
+        assert_eq!(r1.rows_ingested, 2);
+
+        // The agent crashed before the ack and reassembles the spool with a
+        // newer row and a fresh bundle ID; field order differs and the
+        // per-transmission stamp is new
+        let mut b2 = BundleBuilder::new("orko");
+        b2.add_batch(
+            "sysmoni",
+            vec![
+                r#"{"cpu_total": 12.5, "collected_at": "2026-10-16 10:00:00", "machine_id": "orko", "sent_at": "later"}"#
+                    .to_string(),
+                row("2026-10-16 10:01:00", 14.0),
+                row("2026-10-16 10:02:00", 15.5),
+            ],
+            None,
+        );
+        let mut m2 = b2.build();
+        m2.bundle_id = format!("{}-retry", m1.bundle_id);
+        let r2 = ingest_bundle(&store, &m2, &config, None).unwrap();
+        assert_eq!(r2.rows_ingested, 1);
+        assert_eq!(r2.rows_deduplicated, 2);
+
+        let rows: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM sys_samples")
+            .unwrap();
+        assert_eq!(rows, 3);
+
+        let pruned = store
+            .prune_ingest_row_hashes(Utc::now() + chrono::Duration::hours(1), false)
+            .unwrap();
+        assert_eq!(pruned, 3);
+    }
+
+    #[test]
+    fn test_ingest_failed_batch_rolls_back_and_resumes() {
+        let store = vc_store::VcStore::open_memory().unwrap();
+        let config = IngestConfig::default();
+        let row = |minute: u32| {
+            format!(
+                r#"{{"machine_id": "orko", "collected_at": "2026-10-16 10:0{minute}:00", "cpu_total": 1.0}}"#
+            )
+        };
+        let mut builder = BundleBuilder::new("orko");
+        builder.add_batch("sysmoni", vec![row(1)], None);
+        builder.add_batch("sysmoni", vec![row(2)], None);
+        // The third batch's second row collides with a row already stored
+        builder.add_batch("sysmoni", vec![row(3), row(9)], None);
+        builder.add_batch("sysmoni", vec![row(4)], None);
+        builder.add_batch("sysmoni", vec![row(5)], None);
+        let manifest = builder.build();
+        store
+            .execute_simple(
+                "INSERT INTO sys_samples (machine_id, collected_at, cpu_total) \
+                 VALUES ('orko', '2026-10-16 10:09:00', 9.0)",
+            )
+            .unwrap();
+        let count = || -> i64 {
+            store
+                .query_scalar("SELECT COUNT(*) FROM sys_samples WHERE cpu_total = 1.0")
+                .unwrap()
+        };
+
+        let first = ingest_bundle(&store, &manifest, &config, None).unwrap();
+        assert_eq!(first.status, IngestStatus::Partial);
+        assert!(matches!(
+            first.batches[2].outcome,
+            BatchOutcome::Failed { .. }
+        ));
+        for index in [0, 1, 3, 4] {
+            assert!(matches!(
+                first.batches[index].outcome,
+                BatchOutcome::Applied {
+                    rows_inserted: 1,
+                    ..
+                }
+            ));
+        }
+        assert_eq!(first.rows_ingested, 4);
+        // Nothing of the failed batch stayed behind, row hashes included
+        assert_eq!(count(), 4);
+        let bundles = store.list_ingest_bundles(Some("orko"), 10).unwrap();
+        assert_eq!(bundles[0]["status"], "partial");
+        assert_eq!(bundles[0]["batches_failed"], 1);
+
+        store
+            .execute_simple("DELETE FROM sys_samples WHERE cpu_total = 9.0")
+            .unwrap();
+        let resumed = ingest_bundle(&store, &manifest, &config, None).unwrap();
+        assert_eq!(resumed.status, IngestStatus::Complete);
+        assert_eq!(resumed.rows_ingested, 2);
+        assert_eq!(
+            resumed.batches[2].outcome,
+            BatchOutcome::Applied {
+                rows_inserted: 2,
+                rows_deduplicated: 0,
+                rows_skipped: 0,
+            }
+        );
+        for index in [0, 1, 3, 4] {
+            assert_eq!(
+                resumed.batches[index].outcome,
+                BatchOutcome::SkippedDuplicate
+            );
+        }
+        assert_eq!(count(), 6);
+
+        let bundles = store.list_ingest_bundles(Some("orko"), 10).unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0]["status"], "complete");
+        assert_eq!(bundles[0]["batches_applied"], 5);
+    }
+
+    #[test]
+    fn test_ingest_from_fast_clock_measures_and_normalizes() {
+        let store = vc_store::VcStore::open_memory().unwrap();
+        let machine = vc_config::MachineConfig {
+            name: "orko".to_string(),
+            ssh_host: None,
+            ssh_user: None,
+            ssh_key: None,
+            ssh_port: 22,
+            enabled: true,
+            collectors: std::collections::HashMap::new(),
+            tags: vec![],
+            normalize_clock_skew: true,
+            http_checks: vec![],
+        };
+        // The node's clock runs 40 minutes fast.
+        let remote_now = Utc::now() + chrono::Duration::minutes(40);
+        let collected_at = remote_now.to_rfc3339_opts(SecondsFormat::Micros, true);
+
+        let mut builder = BundleBuilder::new("orko");
+        builder.add_batch(
+            "sysmoni",
+            vec![format!(
+                r#"{{"machine_id": "orko", "collected_at": "{collected_at}", "cpu_total": 12.5}}"#
+            )],
+            None,
+        );
+        let mut manifest = builder.build();
+        manifest.sent_at = Some(remote_now);
+        let result =
+            ingest_bundle(&store, &manifest, &IngestConfig::default(), Some(&machine)).unwrap();
+        assert_eq!(result.rows_ingested, 1);
+
+        let skew = store.effective_clock_skew("orko").unwrap().unwrap();
+        assert_eq!(skew.source, "ingest");
+        assert!((2_390_000..=2_400_000).contains(&skew.skew_ms));
+        assert!(
+            store
+                .has_open_alert(clock::CLOCK_SKEW_ALERT_RULE, Some("orko"))
+                .unwrap()
+        );
+
+        let rows = store
+            .query_json("SELECT collected_at, clock_skew_ms FROM sys_samples")
+            .unwrap();
+        assert_eq!(rows[0]["clock_skew_ms"], skew.skew_ms);
+        let stored = clock::parse_row_timestamp(rows[0]["collected_at"].as_str().unwrap()).unwrap();
+        assert!((Utc::now() - stored).num_seconds().abs() < 10);
+
+        let freshness = store.get_freshness_summaries(Some("orko"), 600).unwrap();
+        assert_eq!(freshness.len(), 1);
+        assert!(!freshness[0].stale);
+        assert!(freshness[0].freshness_seconds < 10);
+    }
+
+    #[test]
+    fn test_row_content_hash_is_canonical() {
+        let a = serde_json::json!({"collected_at": "t1", "cpu": 1, "nested": {"x": 1, "y": 2}});
+        let b = serde_json::json!({"nested": {"y": 2, "x": 1}, "cpu": 1, "collected_at": "t1", "batch_id": "b9"});
+        assert_eq!(row_content_hash("orko", &a), row_content_hash("orko", &b));
+        assert_ne!(row_content_hash("orko", &a), row_content_hash("bender", &a));
+
+        let later = serde_json::json!({"collected_at": "t2", "cpu": 1, "nested": {"x": 1, "y": 2}});
+        assert_ne!(
+            row_content_hash("orko", &a),
+            row_content_hash("orko", &later)
+        );
+    }
+
+    #[test]
+    fn test_dedup_since_zero_disables() {
+        let now = Utc::now();
+        let config = IngestConfig {
+            dedup_lookback_hours: 0,
+        };
+        assert!(dedup_since(&config, now).is_none());
+        let since = dedup_since(&IngestConfig::default(), now).unwrap();
+        assert_eq!(now - since, chrono::Duration::hours(48));
+    }
 }
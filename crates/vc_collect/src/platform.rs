@@ -0,0 +1,194 @@
+//! Remote platform detection
+//!
+//! Collectors and the tool prober pick their commands by platform, and a
+//! Windows machine needs PowerShell rather than a POSIX shell. The platform
+//! is found by asking the machine: `uname -s` answers on Linux and macOS
+//! (and under MSYS or Cygwin on Windows); a plain `cmd.exe` answers `ver`;
+//! a machine whose OpenSSH server starts PowerShell answers neither, so
+//! PowerShell is asked directly last.
+
+use crate::CollectError;
+use crate::executor::{Executor, Shell};
+use asupersync::Cx;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The platform a machine runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Linux,
+    Macos,
+    Windows,
+    /// Not probed yet, or the probe could not tell
+    #[default]
+    Unknown,
+}
+
+impl Platform {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linux => "linux",
+            Self::Macos => "macos",
+            Self::Windows => "windows",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a stored `os_type` or the output of `uname -s`, `ver` or
+    /// PowerShell's OS version string
+    #[must_use]
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim().to_lowercase();
+        if raw.starts_with("darwin") || raw.starts_with("macos") {
+            Self::Macos
+        } else if ["windows", "mingw", "msys", "cygwin"]
+            .iter()
+            .any(|marker| raw.contains(marker))
+        {
+            Self::Windows
+        } else if raw.starts_with("linux") {
+            Self::Linux
+        } else {
+            Self::Unknown
+        }
+    }
+
+    #[must_use]
+    pub fn is_windows(&self) -> bool {
+        *self == Self::Windows
+    }
+
+    /// The platform vc itself runs on
+    #[must_use]
+    pub fn local() -> Self {
+        Self::parse(std::env::consts::OS)
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a platform probe found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedPlatform {
+    pub platform: Platform,
+    /// The first line the machine answered with, e.g. `Linux` or
+    /// `Microsoft Windows [Version 10.0.22631.4317]`
+    pub detail: String,
+}
+
+/// ssh exits 255 when it could not connect or log in at all
+const SSH_FAILED: i32 = 255;
+
+/// Ask the machine behind `executor` what it runs.
+///
+/// # Errors
+///
+/// Returns [`CollectError`] if the machine cannot be reached or answers
+/// none of the probes. A machine whose `uname` names something else is
+/// [`Platform::Unknown`], not an error.
+pub async fn detect_platform(
+    cx: &Cx,
+    executor: &Executor,
+    timeout: Duration,
+) -> Result<DetectedPlatform, CollectError> {
+    let posix = executor.clone().with_shell(Shell::Posix);
+    let uname = posix.run(cx, "uname -s", timeout).await?;
+    if !executor.is_local() && uname.exit_code == SSH_FAILED {
+        return Err(CollectError::ExecutionError(
+            uname.stderr.trim().to_string(),
+        ));
+    }
+    let first_line = |stdout: &str| {
+        stdout
+            .trim()
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+
+    if uname.success() {
+        let detail = first_line(&uname.stdout);
+        let platform = Platform::parse(&detail);
+        if platform != Platform::Unknown {
+            return Ok(DetectedPlatform { platform, detail });
+        }
+    }
+
+    // `ver` is a cmd.exe builtin, so this only answers when cmd is the shell
+    if let Ok(ver) = posix.run(cx, "ver", timeout).await
+        && ver.success()
+        && ver.stdout.contains("Windows")
+    {
+        return Ok(DetectedPlatform {
+            platform: Platform::Windows,
+            detail: first_line(&ver.stdout),
+        });
+    }
+
+    let powershell = executor.clone().with_shell(Shell::PowerShell);
+    if let Ok(version) = powershell
+        .run(
+            cx,
+            "[Environment]::OSVersion.VersionString; $PSVersionTable.PSVersion.ToString()",
+            timeout,
+        )
+        .await
+        && version.success()
+    {
+        let detail = first_line(&version.stdout);
+        let platform = Platform::parse(&detail);
+        if platform != Platform::Unknown {
+            return Ok(DetectedPlatform { platform, detail });
+        }
+    }
+
+    if uname.success() {
+        return Ok(DetectedPlatform {
+            platform: Platform::Unknown,
+            detail: first_line(&uname.stdout),
+        });
+    }
+    Err(CollectError::ExecutionError(format!(
+        "no platform probe answered: {}",
+        uname.stderr.trim()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_platform_parse() {
+        assert_eq!(Platform::parse("Linux\n"), Platform::Linux);
+        assert_eq!(Platform::parse("Darwin"), Platform::Macos);
+        assert_eq!(Platform::parse("macos"), Platform::Macos);
+        assert_eq!(
+            Platform::parse("Microsoft Windows [Version 10.0.22631.4317]"),
+            Platform::Windows
+        );
+        assert_eq!(
+            Platform::parse("Microsoft Windows NT 10.0.22631.0"),
+            Platform::Windows
+        );
+        assert_eq!(Platform::parse("MINGW64_NT-10.0-22631"), Platform::Windows);
+        assert_eq!(Platform::parse("windows"), Platform::Windows);
+        assert_eq!(Platform::parse("FreeBSD"), Platform::Unknown);
+        for platform in [
+            Platform::Linux,
+            Platform::Macos,
+            Platform::Windows,
+            Platform::Unknown,
+        ] {
+            assert_eq!(Platform::parse(platform.as_str()), platform);
+        }
+    }
+}
@@ -1,14 +1,22 @@
 //! Tool probing system for detecting installed tools on machines
 //!
 //! This module provides tool detection capabilities to discover which
-//! tools are installed on each machine and their versions.
+//! tools are installed on each machine and their versions, and service
+//! checks that tell whether configured daemons are actually running.
+//!
+//! Both follow the executor's shell: on Windows, tools are looked up with
+//! `Get-Command` (and only those with a Windows build are probed),
+//! processes come from `Win32_Process` and units are Windows services.
 
 use crate::CollectError;
-use crate::executor::Executor;
+use crate::executor::{Executor, Shell};
 use crate::machine::{MachineRegistry, ToolInfo};
+use chrono::Utc;
 use regex::Regex;
 use std::time::Duration;
 use tracing::{debug, info, warn};
+use vc_config::ServiceCheckConfig;
+use vc_store::MachineServiceStatus;
 
 /// Specification for detecting a tool
 #[derive(Debug, Clone)]
@@ -21,99 +29,175 @@ pub struct ToolSpec {
     pub version_flag: &'static str,
     /// Regex to extract version from output
     pub version_regex: &'static str,
+    /// File holding the version, read when `version_flag` reports nothing
+    /// (agents installed as services rather than CLIs)
+    pub version_file: Option<&'static str>,
+    /// Binary name on Windows; `None` for tools without a Windows build,
+    /// which are not probed there
+    pub windows_binary: Option<&'static str>,
+}
+
+impl ToolSpec {
+    /// Commands that print the tool's path, in order, for `shell`
+    #[must_use]
+    pub fn detect_commands_for(&self, shell: Shell) -> Vec<String> {
+        match (shell, self.windows_binary) {
+            (Shell::Posix, _) => self
+                .detect_commands
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            (Shell::PowerShell, Some(binary)) => vec![format!(
+                "(Get-Command -Name {} -CommandType Application -ErrorAction SilentlyContinue \
+                 | Select-Object -First 1).Source",
+                shell.quote(binary)
+            )],
+            (Shell::Cmd, Some(binary)) => vec![format!("where {binary}")],
+            (_, None) => Vec::new(),
+        }
+    }
 }
 
+/// Version pattern for `ToolSpec::version_file` contents
+const VERSION_FILE_REGEX: &str = r"v?(\d+\.\d+(?:\.\d+)?)";
+
 /// Known tools and their detection specs
 pub const TOOL_SPECS: &[ToolSpec] = &[
+    ToolSpec {
+        name: "sysmoni",
+        detect_commands: &["command -v sysmoni", "which sysmoni"],
+        version_flag: "--version",
+        version_regex: r"sysmoni[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
+    },
+    ToolSpec {
+        name: "vc-node",
+        detect_commands: &["command -v vc-node", "which vc-node"],
+        version_flag: "--version",
+        version_regex: r"vc-node[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: Some("/var/lib/vc-node/VERSION"),
+        windows_binary: None,
+    },
     ToolSpec {
         name: "caut",
         detect_commands: &["command -v caut", "which caut"],
         version_flag: "--version",
         version_regex: r"caut[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "ntm",
         detect_commands: &["command -v ntm", "which ntm"],
         version_flag: "--version",
         version_regex: r"ntm[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "rch",
         detect_commands: &["command -v rch", "which rch"],
         version_flag: "--version",
         version_regex: r"rch[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "rano",
         detect_commands: &["command -v rano", "which rano"],
         version_flag: "--version",
         version_regex: r"rano[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "dcg",
         detect_commands: &["command -v dcg", "which dcg"],
         version_flag: "--version",
         version_regex: r"dcg[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "pt",
         detect_commands: &["command -v pt", "which pt"],
         version_flag: "--version",
         version_regex: r"pt[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "claude-code",
         detect_commands: &["command -v claude", "which claude"],
         version_flag: "--version",
         version_regex: r"(?:claude|claude-code)[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("claude"),
     },
     ToolSpec {
         name: "codex",
         detect_commands: &["command -v codex", "which codex"],
         version_flag: "--version",
         version_regex: r"codex[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("codex"),
     },
     ToolSpec {
         name: "gmi",
         detect_commands: &["command -v gmi", "which gmi"],
         version_flag: "--version",
         version_regex: r"gmi[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "br",
         detect_commands: &["command -v br", "which br"],
         version_flag: "--version",
         version_regex: r"br[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "bv",
         detect_commands: &["command -v bv", "which bv"],
         version_flag: "--version",
         version_regex: r"bv[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: None,
     },
     ToolSpec {
         name: "cargo",
         detect_commands: &["command -v cargo", "which cargo"],
         version_flag: "--version",
         version_regex: r"cargo[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("cargo"),
     },
     ToolSpec {
         name: "rustc",
         detect_commands: &["command -v rustc", "which rustc"],
         version_flag: "--version",
         version_regex: r"rustc[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("rustc"),
     },
     ToolSpec {
         name: "node",
         detect_commands: &["command -v node", "which node"],
         version_flag: "--version",
         version_regex: r"v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("node"),
     },
     ToolSpec {
         name: "python3",
         detect_commands: &["command -v python3", "which python3"],
         version_flag: "--version",
         version_regex: r"Python[- ]?v?(\d+\.\d+(?:\.\d+)?)",
+        version_file: None,
+        windows_binary: Some("python"),
     },
 ];
 
@@ -182,7 +266,13 @@ impl ToolProber {
 
         info!(machine_id = %machine_id, "Starting tool probe");
 
-        for spec in TOOL_SPECS {
+        // Tools without a Windows build are not probed there at all, rather
+        // than recorded as missing on every probe
+        let windows = executor.shell() != Shell::Posix;
+        for spec in TOOL_SPECS
+            .iter()
+            .filter(|spec| !windows || spec.windows_binary.is_some())
+        {
             match self.probe_tool(cx, executor, spec).await {
                 Ok(Some(info)) => {
                     debug!(
@@ -206,6 +296,7 @@ impl ToolProber {
                         tool_path: None,
                         tool_version: None,
                         is_available: false,
+                        version_source: None,
                     };
                     let _ = registry.record_tool(machine_id, &not_found);
                 }
@@ -238,35 +329,70 @@ impl ToolProber {
         spec: &ToolSpec,
     ) -> Result<Option<ToolInfo>, CollectError> {
         // Try each detection command
-        for cmd in spec.detect_commands {
-            let result = executor.run(cx, cmd, self.timeout).await;
+        let shell = executor.shell();
+        for cmd in spec.detect_commands_for(shell) {
+            let result = executor.run(cx, &cmd, self.timeout).await;
             if let Ok(output) = result
                 && output.exit_code == 0
                 && !output.stdout.trim().is_empty()
             {
-                let path = output.stdout.trim().to_string();
+                // `where` lists every match on PATH; the first one runs
+                let path = output
+                    .stdout
+                    .trim()
+                    .lines()
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
 
                 // Get version
-                let version_cmd = format!("{} {}", path, spec.version_flag);
-                let version = match executor.run(cx, &version_cmd, self.timeout).await {
+                let version_cmd = shell.invoke(&path, spec.version_flag);
+                let mut version = match executor.run(cx, &version_cmd, self.timeout).await {
                     Ok(out) if out.exit_code == 0 => {
                         Self::extract_version(&out.stdout, spec.version_regex)
                             .or_else(|| Self::extract_version(&out.stderr, spec.version_regex))
                     }
                     _ => None,
                 };
+                let mut version_source = "probe";
+                if version.is_none()
+                    && shell == Shell::Posix
+                    && let Some(file) = spec.version_file
+                {
+                    version = self.read_version_file(cx, executor, file).await;
+                    version_source = "version_file";
+                }
 
                 return Ok(Some(ToolInfo {
                     tool_name: spec.name.to_string(),
                     tool_path: Some(path),
                     tool_version: version,
                     is_available: true,
+                    version_source: Some(version_source.to_string()),
                 }));
             }
         }
         Ok(None)
     }
 
+    /// Read a version file on the machine; its contents are a bare version
+    async fn read_version_file(
+        &self,
+        cx: &asupersync::Cx,
+        executor: &Executor,
+        file: &str,
+    ) -> Option<String> {
+        let out = executor
+            .run(cx, &format!("cat {file}"), self.timeout)
+            .await
+            .ok()?;
+        if out.exit_code != 0 {
+            return None;
+        }
+        Self::extract_version(&out.stdout, VERSION_FILE_REGEX)
+    }
+
     /// Extract version from output using regex
     fn extract_version(output: &str, pattern: &str) -> Option<String> {
         let re = Regex::new(pattern).ok()?;
@@ -276,6 +402,270 @@ impl ToolProber {
     }
 }
 
+/// How a service is found on the machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceTarget {
+    /// Regex matched against running process command lines
+    Process(String),
+    /// systemd unit, or launchd label where systemd is absent
+    Unit(String),
+}
+
+/// A service to check, resolved for one machine
+#[derive(Debug, Clone)]
+pub struct ServiceSpec {
+    pub name: String,
+    pub target: ServiceTarget,
+    pub required: bool,
+}
+
+impl ServiceSpec {
+    /// Build from a `[[services]]` entry; `None` if it names neither a
+    /// process nor a unit (config validation rejects that)
+    #[must_use]
+    pub fn from_config(config: &ServiceCheckConfig, required: bool) -> Option<Self> {
+        let target = match (&config.process, &config.unit) {
+            (Some(pattern), _) => ServiceTarget::Process(pattern.clone()),
+            (None, Some(unit)) => ServiceTarget::Unit(unit.clone()),
+            (None, None) => return None,
+        };
+        Some(Self {
+            name: config.name.clone(),
+            target,
+            required,
+        })
+    }
+}
+
+/// Every Windows process as `pid elapsed_secs command_line`, matching
+/// `ps -A -o pid=,etime=,args=`. System processes have no creation date or
+/// command line; they get `-` and their image name.
+const WINDOWS_PROCESS_COMMAND: &str = "$now = Get-Date; Get-CimInstance Win32_Process | \
+     ForEach-Object { '{0} {1} {2}' -f $_.ProcessId, \
+     $(if ($_.CreationDate) { [long]($now - $_.CreationDate).TotalSeconds } else { '-' }), \
+     $(if ($_.CommandLine) { $_.CommandLine } else { $_.Name }) }";
+
+/// One line of `ps -A -o pid=,etime=,args=`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PsEntry {
+    pid: i64,
+    elapsed_secs: Option<i64>,
+    args: String,
+}
+
+/// Service checker: whether configured processes and units are running
+pub struct ServiceProber {
+    timeout: Duration,
+}
+
+impl Default for ServiceProber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceProber {
+    /// Create a new service prober with default timeout
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Set the command timeout
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Check every spec on the machine behind `executor`
+    ///
+    /// One process listing serves all process checks and supplies uptime
+    /// for units too. If the listing itself fails, process checks report
+    /// not running with the error as detail.
+    pub async fn check_services(
+        &self,
+        cx: &asupersync::Cx,
+        machine_id: &str,
+        executor: &Executor,
+        specs: &[ServiceSpec],
+    ) -> Vec<MachineServiceStatus> {
+        if specs.is_empty() {
+            return Vec::new();
+        }
+
+        // Windows checks are PowerShell whatever shell the executor uses
+        let windows = executor.shell() != Shell::Posix;
+        let powershell = executor.clone().with_shell(Shell::PowerShell);
+        let listing = if windows {
+            powershell
+                .run_timeout(cx, WINDOWS_PROCESS_COMMAND, self.timeout)
+                .await
+        } else {
+            executor
+                .run_timeout(cx, "ps -A -o pid=,etime=,args=", self.timeout)
+                .await
+        };
+        let processes = match listing {
+            Ok(stdout) => Ok(Self::parse_ps(&stdout)),
+            Err(e) => {
+                warn!(machine_id = %machine_id, error = %e, "Process listing failed");
+                Err(e.to_string())
+            }
+        };
+        let checked_at = Utc::now().to_rfc3339();
+
+        let mut statuses = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let (kind, target, running, pid, detail) = match &spec.target {
+                ServiceTarget::Process(pattern) => {
+                    let (running, pid, detail) = match (&processes, Regex::new(pattern)) {
+                        (Err(e), _) => (false, None, Some(format!("process list failed: {e}"))),
+                        (_, Err(e)) => (false, None, Some(format!("invalid pattern: {e}"))),
+                        (Ok(entries), Ok(re)) => {
+                            // Oldest match: the long-lived server, not a
+                            // short-lived client that happens to match too
+                            let found = entries
+                                .iter()
+                                .filter(|entry| re.is_match(&entry.args))
+                                .max_by_key(|entry| entry.elapsed_secs.unwrap_or(0));
+                            match found {
+                                Some(entry) => (true, Some(entry.pid), None),
+                                None => (false, None, Some("no matching process".to_string())),
+                            }
+                        }
+                    };
+                    ("process", pattern, running, pid, detail)
+                }
+                ServiceTarget::Unit(unit) => {
+                    let result = if windows {
+                        powershell
+                            .run(cx, &Self::windows_service_command(unit), self.timeout)
+                            .await
+                    } else {
+                        let cmd = format!(
+                            "systemctl show --property=ActiveState,MainPID -- '{unit}' 2>/dev/null \
+                             || launchctl list 2>/dev/null | awk '$3 == \"{unit}\"'"
+                        );
+                        executor.run(cx, &cmd, self.timeout).await
+                    };
+                    let (running, pid, detail) = match result {
+                        Ok(output) => Self::parse_unit_status(&output.stdout),
+                        Err(e) => (false, None, Some(format!("unit check failed: {e}"))),
+                    };
+                    ("unit", unit, running, pid, detail)
+                }
+            };
+
+            let uptime_secs = pid.and_then(|pid| {
+                processes
+                    .as_ref()
+                    .ok()?
+                    .iter()
+                    .find(|entry| entry.pid == pid)?
+                    .elapsed_secs
+            });
+            debug!(
+                machine_id = %machine_id,
+                service = %spec.name,
+                running,
+                "Service checked"
+            );
+
+            statuses.push(MachineServiceStatus {
+                machine_id: machine_id.to_string(),
+                service_name: spec.name.clone(),
+                kind: kind.to_string(),
+                target: target.clone(),
+                required: spec.required,
+                running,
+                pid,
+                uptime_secs,
+                detail,
+                checked_at: checked_at.clone(),
+            });
+        }
+        statuses
+    }
+
+    /// Look up a Windows service, answering in `systemctl show` form so
+    /// [`Self::parse_unit_status`] reads both
+    fn windows_service_command(name: &str) -> String {
+        // WQL escapes quotes with a backslash
+        let filter = format!("Name='{}'", name.replace('\\', "\\\\").replace('\'', "\\'"));
+        format!(
+            "$s = Get-CimInstance Win32_Service -Filter {}; \
+             if ($s) {{ 'ActiveState=' + $(if ($s.State -eq 'Running') {{ 'active' }} \
+             else {{ $s.State.ToLower() }}); 'MainPID=' + $s.ProcessId }}",
+            Shell::PowerShell.quote(&filter)
+        )
+    }
+
+    /// Parse `ps -A -o pid=,etime=,args=` output, or the same columns from
+    /// [`WINDOWS_PROCESS_COMMAND`] with the elapsed time in plain seconds
+    fn parse_ps(output: &str) -> Vec<PsEntry> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pid = parts.next()?.parse().ok()?;
+                let elapsed_secs = Self::parse_etime(parts.next()?);
+                let args = parts.collect::<Vec<_>>().join(" ");
+                Some(PsEntry {
+                    pid,
+                    elapsed_secs,
+                    args,
+                })
+            })
+            .collect()
+    }
+
+    /// Parse a `ps` elapsed time, `[[dd-]hh:]mm:ss`, into seconds
+    fn parse_etime(value: &str) -> Option<i64> {
+        let (days, clock) = match value.split_once('-') {
+            Some((days, clock)) => (days.parse::<i64>().ok()?, clock),
+            None => (0, value),
+        };
+        let mut secs = 0_i64;
+        for part in clock.split(':') {
+            secs = secs * 60 + part.parse::<i64>().ok()?;
+        }
+        Some(days * 86_400 + secs)
+    }
+
+    /// Interpret `systemctl show` or `launchctl list` output for one unit
+    fn parse_unit_status(output: &str) -> (bool, Option<i64>, Option<String>) {
+        if output.contains("ActiveState=") {
+            let mut state = "unknown";
+            let mut pid = None;
+            for line in output.lines() {
+                if let Some(value) = line.strip_prefix("ActiveState=") {
+                    state = value.trim();
+                } else if let Some(value) = line.strip_prefix("MainPID=") {
+                    pid = value.trim().parse::<i64>().ok().filter(|pid| *pid > 0);
+                }
+            }
+            let running = state == "active";
+            return (running, pid, (!running).then(|| format!("unit is {state}")));
+        }
+
+        // launchctl list: "<pid or -> <last exit status> <label>"
+        if let Some(line) = output.lines().find(|line| !line.trim().is_empty()) {
+            return match line.split_whitespace().next().map(str::parse::<i64>) {
+                Some(Ok(pid)) => (true, Some(pid), None),
+                _ => (
+                    false,
+                    None,
+                    Some("unit is loaded but not running".to_string()),
+                ),
+            };
+        }
+        (false, None, Some("unit not found".to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -345,6 +735,8 @@ mod tests {
                 detect_commands: &["command -v sh"],
                 version_flag: "--version",
                 version_regex: r"(\d+\.\d+(?:\.\d+)?)",
+                version_file: None,
+                windows_binary: None,
             };
 
             let result = prober.probe_tool(&cx, &executor, &spec).await;
@@ -358,6 +750,37 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_probe_reads_version_file_when_flag_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("VERSION");
+        std::fs::write(&path, "v1.4.2\n").unwrap();
+        let version_file: &'static str = Box::leak(path.display().to_string().into_boxed_str());
+
+        crate::run_async_test(async {
+            let cx = asupersync::Cx::for_testing();
+            let prober = ToolProber::new();
+            let executor = Executor::local();
+
+            let spec = ToolSpec {
+                name: "sh",
+                detect_commands: &["command -v sh"],
+                version_flag: "-c 'exit 3'",
+                version_regex: r"(\d+\.\d+(?:\.\d+)?)",
+                version_file: Some(version_file),
+                windows_binary: None,
+            };
+
+            let info = prober
+                .probe_tool(&cx, &executor, &spec)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(info.tool_version.as_deref(), Some("1.4.2"));
+            assert_eq!(info.version_source.as_deref(), Some("version_file"));
+        });
+    }
+
     #[test]
     fn test_probe_nonexistent_tool() {
         crate::run_async_test(async {
@@ -370,6 +793,8 @@ mod tests {
                 detect_commands: &["command -v nonexistent_tool_xyz"],
                 version_flag: "--version",
                 version_regex: r"(\d+\.\d+(?:\.\d+)?)",
+                version_file: None,
+                windows_binary: None,
             };
 
             let result = prober.probe_tool(&cx, &executor, &spec).await;
@@ -377,4 +802,131 @@ mod tests {
             assert!(result.unwrap().is_none());
         });
     }
+
+    #[test]
+    fn test_check_local_services() {
+        crate::run_async_test(async {
+            let cx = asupersync::Cx::for_testing();
+            let prober = ServiceProber::new();
+            let executor = Executor::local();
+
+            let specs = vec![
+                // Something is always running
+                ServiceSpec {
+                    name: "anything".to_string(),
+                    target: ServiceTarget::Process(".".to_string()),
+                    required: true,
+                },
+                ServiceSpec {
+                    name: "ghost".to_string(),
+                    target: ServiceTarget::Process("nonexistent_daemon_xyz".to_string()),
+                    required: true,
+                },
+            ];
+
+            let statuses = prober.check_services(&cx, "local", &executor, &specs).await;
+            assert_eq!(statuses.len(), 2);
+            assert!(statuses[0].running);
+            assert!(statuses[0].pid.is_some());
+            assert!(!statuses[1].running);
+            assert_eq!(statuses[1].kind, "process");
+            assert!(statuses[1].required);
+        });
+    }
+
+    #[test]
+    fn test_parse_etime() {
+        assert_eq!(ServiceProber::parse_etime("00:05"), Some(5));
+        assert_eq!(ServiceProber::parse_etime("12:34"), Some(754));
+        assert_eq!(ServiceProber::parse_etime("01:00:00"), Some(3600));
+        assert_eq!(ServiceProber::parse_etime("2-03:00:10"), Some(183_610));
+        assert_eq!(ServiceProber::parse_etime("junk"), None);
+    }
+
+    #[test]
+    fn test_parse_ps_listing() {
+        let output = "    1  9-01:02:03 /sbin/init splash\n\
+                       812     45:10 tmux new-session -s main\n\
+                       not a line\n";
+        let entries = ServiceProber::parse_ps(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].pid, 812);
+        assert_eq!(entries[1].elapsed_secs, Some(2710));
+        assert_eq!(entries[1].args, "tmux new-session -s main");
+    }
+
+    #[test]
+    fn test_windows_listing_and_service_lookup() {
+        let output = "4 - System\r\n\
+                      9120 86400 \"C:\\Program Files\\nodejs\\node.exe\" server.js\r\n";
+        let entries = ServiceProber::parse_ps(output);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].elapsed_secs, None);
+        assert_eq!(entries[1].elapsed_secs, Some(86_400));
+        assert!(entries[1].args.ends_with("node.exe\" server.js"));
+
+        let cmd = ServiceProber::windows_service_command("o'brien");
+        assert!(cmd.contains("-Filter 'Name=''o\\''brien'''"), "{cmd}");
+        assert_eq!(
+            ServiceProber::parse_unit_status("ActiveState=stopped\r\nMainPID=0\r\n"),
+            (false, None, Some("unit is stopped".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_tool_specs_are_platform_aware() {
+        let claude = TOOL_SPECS.iter().find(|s| s.name == "claude-code").unwrap();
+        assert_eq!(
+            claude.detect_commands_for(Shell::Posix),
+            vec!["command -v claude", "which claude"]
+        );
+        assert_eq!(
+            claude.detect_commands_for(Shell::PowerShell),
+            vec![
+                "(Get-Command -Name 'claude' -CommandType Application -ErrorAction SilentlyContinue \
+                 | Select-Object -First 1).Source"
+            ]
+        );
+        let ntm = TOOL_SPECS.iter().find(|s| s.name == "ntm").unwrap();
+        assert!(ntm.detect_commands_for(Shell::PowerShell).is_empty());
+    }
+
+    #[test]
+    fn test_parse_unit_status() {
+        let systemd = "MainPID=4242\nActiveState=active\n";
+        assert_eq!(
+            ServiceProber::parse_unit_status(systemd),
+            (true, Some(4242), None)
+        );
+
+        let (running, pid, detail) =
+            ServiceProber::parse_unit_status("ActiveState=failed\nMainPID=0\n");
+        assert!(!running);
+        assert_eq!(pid, None);
+        assert_eq!(detail.as_deref(), Some("unit is failed"));
+
+        assert_eq!(
+            ServiceProber::parse_unit_status("501\t0\tcom.example.vc-node\n"),
+            (true, Some(501), None)
+        );
+        assert!(!ServiceProber::parse_unit_status("-\t78\tcom.example.vc-node\n").0);
+        assert_eq!(
+            ServiceProber::parse_unit_status("").2.as_deref(),
+            Some("unit not found")
+        );
+    }
+
+    #[test]
+    fn test_service_spec_from_config() {
+        let config = ServiceCheckConfig {
+            name: "tmux".to_string(),
+            process: Some("tmux".to_string()),
+            unit: None,
+            tags: vec![],
+            optional_tags: vec![],
+        };
+        let spec = ServiceSpec::from_config(&config, false).unwrap();
+        assert_eq!(spec.target, ServiceTarget::Process("tmux".to_string()));
+        assert!(!spec.required);
+    }
 }
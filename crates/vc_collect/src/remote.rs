@@ -552,7 +552,8 @@ impl MultiMachineCollector {
         let machine_id = machine.machine_id.clone();
 
         let ctx = CollectContext::local(&machine_id, self.config.timeout)
-            .with_poll_window(self.config.poll_window);
+            .with_poll_window(self.config.poll_window)
+            .with_platform(machine.platform());
 
         let ctx = if let Some(c) = cursor {
             ctx.with_cursor(c.clone())
@@ -560,7 +561,7 @@ impl MultiMachineCollector {
             ctx
         };
 
-        let result = collector.collect(cx, &ctx).await;
+        let result = collector.collect_on_platform(cx, &ctx).await;
 
         MachineCollectResult {
             machine_id,
@@ -591,6 +592,23 @@ impl MultiMachineCollector {
             };
         }
 
+        // Fail without connecting, so the health row says why rather than
+        // carrying whatever a POSIX command printed on Windows
+        let platform = machine.platform();
+        if !collector.supports_platform(platform) {
+            return MachineCollectResult {
+                machine_id,
+                result: asupersync::Outcome::Err(RemoteCollectError::CollectError(
+                    CollectError::UnsupportedPlatform {
+                        collector: collector.name().to_string(),
+                        platform,
+                    },
+                )),
+                duration: machine_start.elapsed(),
+                was_online: true,
+            };
+        }
+
         let machine_limiter = self.machine_limiter(&machine.machine_id);
         let machine_wait_start = Instant::now();
         let _machine_permit = match machine_limiter.acquire(cx, 1).await {
@@ -1157,6 +1175,8 @@ mod tests {
             enabled: true,
             collectors: StdHashMap::new(),
             tags: vec![],
+            normalize_clock_skew: false,
+            http_checks: vec![],
         }
     }
 
@@ -4,12 +4,20 @@
 //! - Collector health (recent failures → backoff)
 //! - Alert severity (active alerts → shorter intervals)
 //! - Machine freshness (stale data → poll sooner)
+//! - Hub load and idle collectors (→ longer intervals)
 //!
-//! Includes quarantine for repeatedly failing collectors and
-//! on-demand profiling burst support.
+//! Includes quarantine for repeatedly failing collectors, manual pins and
+//! on-demand profiling burst support. Failures whose cause will not clear on
+//! its own (rejected credentials, a missing tool) quarantine at once instead
+//! of backing off.
+//!
+//! Every decision carries the inputs it was made from, the rule that fired
+//! (its [`ScheduleReason`]), the previous interval and a one-sentence
+//! explanation, and is logged that way to `poll_schedule_decisions`.
 
+use crate::ErrorCause;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use vc_store::VcStore;
 
@@ -19,6 +27,7 @@ use vc_store::VcStore;
 
 /// Adaptive polling configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AdaptiveConfig {
     /// Minimum poll interval in seconds
     pub min_interval_secs: u32,
@@ -32,6 +41,11 @@ pub struct AdaptiveConfig {
     pub quarantine_duration_secs: u32,
     /// Backoff multiplier per failure (e.g. 2.0 for exponential)
     pub backoff_multiplier: f64,
+    /// Hub load (fraction of its budget) from which intervals are stretched
+    pub hub_load_threshold: f64,
+    /// Change rate (fraction of recent polls that brought new data) at or
+    /// below which a collector counts as idle and is stretched
+    pub idle_change_rate: f64,
 }
 
 impl Default for AdaptiveConfig {
@@ -43,6 +57,8 @@ impl Default for AdaptiveConfig {
             quarantine_after_failures: 5,
             quarantine_duration_secs: 600,
             backoff_multiplier: 2.0,
+            hub_load_threshold: 0.8,
+            idle_change_rate: 0.0,
         }
     }
 }
@@ -51,30 +67,65 @@ impl Default for AdaptiveConfig {
 // Collector state tracking
 // ============================================================================
 
+/// Poll outcomes kept per collector for its failure rate
+const OUTCOME_WINDOW: usize = 20;
+
 /// Per-collector state for adaptive scheduling
 #[derive(Debug, Clone)]
 pub struct CollectorState {
     pub machine_id: String,
     pub collector: String,
     pub consecutive_failures: u32,
-    pub last_interval_secs: u32,
+    /// Interval of the last decision; `None` before the first
+    pub last_interval_secs: Option<u32>,
     pub quarantined: bool,
     pub has_active_alert: bool,
     pub freshness_secs: Option<f64>,
+    /// Fraction of recent polls that brought new data
+    pub change_rate: Option<f64>,
+    /// Interval set by an operator, overriding the heuristics
+    pub pinned_secs: Option<u32>,
+    /// Recent poll outcomes, oldest first (`true` = failed)
+    recent_failures: VecDeque<bool>,
 }
 
 impl CollectorState {
     #[must_use]
-    pub fn new(machine_id: &str, collector: &str, default_interval: u32) -> Self {
+    pub fn new(machine_id: &str, collector: &str) -> Self {
         Self {
             machine_id: machine_id.to_string(),
             collector: collector.to_string(),
             consecutive_failures: 0,
-            last_interval_secs: default_interval,
+            last_interval_secs: None,
             quarantined: false,
             has_active_alert: false,
             freshness_secs: None,
+            change_rate: None,
+            pinned_secs: None,
+            recent_failures: VecDeque::new(),
+        }
+    }
+
+    /// Share of the recent polls that failed; `None` before any poll
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // at most OUTCOME_WINDOW polls
+    pub fn failure_rate(&self) -> Option<f64> {
+        if self.recent_failures.is_empty() {
+            return None;
+        }
+        let failed = self
+            .recent_failures
+            .iter()
+            .filter(|failed| **failed)
+            .count();
+        Some(failed as f64 / self.recent_failures.len() as f64)
+    }
+
+    fn push_outcome(&mut self, failed: bool) {
+        if self.recent_failures.len() == OUTCOME_WINDOW {
+            self.recent_failures.pop_front();
         }
+        self.recent_failures.push_back(failed);
     }
 }
 
@@ -88,11 +139,80 @@ pub struct ScheduleDecision {
     pub machine_id: String,
     pub collector: String,
     pub interval_secs: u32,
+    /// The rule that fired
     pub reason: ScheduleReason,
+    /// Interval of the collector's previous decision
+    #[serde(default)]
+    pub previous_interval_secs: Option<u32>,
+    /// What the scheduler knew when deciding
+    #[serde(default)]
+    pub inputs: DecisionInputs,
+    /// One sentence on why, e.g. "60s → 240s: backed off after 2 consecutive
+    /// failures"
+    #[serde(default)]
+    pub explanation: String,
+}
+
+/// Everything the scheduler weighed for one decision, whichever rule fired
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecisionInputs {
+    /// Fraction of recent polls that brought new data
+    pub change_rate: Option<f64>,
+    /// Fraction of recent polls that failed
+    pub failure_rate: Option<f64>,
+    pub consecutive_failures: u32,
+    pub quarantined: bool,
+    pub active_alert: bool,
+    /// Age of the newest data, in seconds
+    pub freshness_secs: Option<f64>,
+    /// Profiling session in progress on the machine
+    pub profile_id: Option<String>,
+    pub profiling_interval_secs: Option<u32>,
+    /// Interval pinned by an operator
+    pub pinned_secs: Option<u32>,
+    /// Hub load as a fraction of its budget
+    pub hub_load: Option<f64>,
+}
+
+impl DecisionInputs {
+    /// The inputs as short phrases, e.g. `["2 failures in a row", "hub load 85%"]`;
+    /// unset inputs are left out
+    #[must_use]
+    pub fn summary(&self) -> Vec<String> {
+        let pct = |value: f64| format!("{:.0}%", value * 100.0);
+        let mut parts = Vec::new();
+        if let Some(profile) = &self.profile_id {
+            parts.push(format!("profiling {profile}"));
+        }
+        if let Some(pinned) = self.pinned_secs {
+            parts.push(format!("pinned to {pinned}s"));
+        }
+        if self.quarantined {
+            parts.push("quarantined".to_string());
+        }
+        parts.push(format!("{} failures in a row", self.consecutive_failures));
+        if let Some(rate) = self.failure_rate {
+            parts.push(format!("failure rate {}", pct(rate)));
+        }
+        if self.active_alert {
+            parts.push("alert active".to_string());
+        }
+        if let Some(age) = self.freshness_secs {
+            parts.push(format!("data {age:.0}s old"));
+        }
+        if let Some(rate) = self.change_rate {
+            parts.push(format!("change rate {}", pct(rate)));
+        }
+        if let Some(load) = self.hub_load {
+            parts.push(format!("hub load {}", pct(load)));
+        }
+        parts
+    }
 }
 
 /// Why the interval was chosen
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ScheduleReason {
     /// Using default interval
@@ -107,6 +227,12 @@ pub enum ScheduleReason {
     Quarantined,
     /// On-demand profiling burst
     ProfilingBurst,
+    /// Interval pinned by an operator
+    ManualPin,
+    /// Lengthened while the hub is over its load threshold
+    HubLoad,
+    /// Lengthened because recent polls brought nothing new
+    Idle,
 }
 
 impl ScheduleReason {
@@ -119,6 +245,9 @@ impl ScheduleReason {
             Self::FailureBackoff => "failure_backoff",
             Self::Quarantined => "quarantined",
             Self::ProfilingBurst => "profiling_burst",
+            Self::ManualPin => "manual_pin",
+            Self::HubLoad => "hub_load",
+            Self::Idle => "idle",
         }
     }
 }
@@ -146,6 +275,7 @@ pub struct AdaptiveScheduler {
     config: AdaptiveConfig,
     states: HashMap<String, CollectorState>,
     profiling_sessions: HashMap<String, ProfilingSession>,
+    hub_load: Option<f64>,
     store: Option<Arc<VcStore>>,
 }
 
@@ -157,6 +287,7 @@ impl AdaptiveScheduler {
             config,
             states: HashMap::new(),
             profiling_sessions: HashMap::new(),
+            hub_load: None,
             store: None,
         }
     }
@@ -168,6 +299,7 @@ impl AdaptiveScheduler {
             config,
             states: HashMap::new(),
             profiling_sessions: HashMap::new(),
+            hub_load: None,
             store: Some(store),
         }
     }
@@ -175,9 +307,9 @@ impl AdaptiveScheduler {
     /// Get or create state for a collector
     fn get_state(&mut self, machine_id: &str, collector: &str) -> &mut CollectorState {
         let key = format!("{machine_id}:{collector}");
-        self.states.entry(key).or_insert_with(|| {
-            CollectorState::new(machine_id, collector, self.config.default_interval_secs)
-        })
+        self.states
+            .entry(key)
+            .or_insert_with(|| CollectorState::new(machine_id, collector))
     }
 
     /// Record a successful poll
@@ -185,6 +317,7 @@ impl AdaptiveScheduler {
         let state = self.get_state(machine_id, collector);
         state.consecutive_failures = 0;
         state.quarantined = false;
+        state.push_outcome(false);
     }
 
     /// Record a failed poll
@@ -192,11 +325,22 @@ impl AdaptiveScheduler {
         let threshold = self.config.quarantine_after_failures;
         let state = self.get_state(machine_id, collector);
         state.consecutive_failures += 1;
+        state.push_outcome(true);
         if state.consecutive_failures >= threshold {
             state.quarantined = true;
         }
     }
 
+    /// Record a failed poll along with why it failed. Transient causes back
+    /// off as usual; the others quarantine immediately, since retrying
+    /// cannot help until someone fixes the machine.
+    pub fn record_failure_cause(&mut self, machine_id: &str, collector: &str, cause: ErrorCause) {
+        self.record_failure(machine_id, collector);
+        if !cause.is_transient() {
+            self.get_state(machine_id, collector).quarantined = true;
+        }
+    }
+
     /// Set active alert status for a machine
     pub fn set_active_alert(&mut self, machine_id: &str, collector: &str, has_alert: bool) {
         let state = self.get_state(machine_id, collector);
@@ -209,6 +353,23 @@ impl AdaptiveScheduler {
         state.freshness_secs = Some(freshness_secs);
     }
 
+    /// Set the fraction of recent polls that brought new data
+    pub fn set_change_rate(&mut self, machine_id: &str, collector: &str, change_rate: f64) {
+        let state = self.get_state(machine_id, collector);
+        state.change_rate = Some(change_rate.clamp(0.0, 1.0));
+    }
+
+    /// Pin a collector to a fixed interval, or unpin it with `None`
+    pub fn pin_interval(&mut self, machine_id: &str, collector: &str, interval_secs: Option<u32>) {
+        let state = self.get_state(machine_id, collector);
+        state.pinned_secs = interval_secs;
+    }
+
+    /// Set the hub's load as a fraction of its budget
+    pub fn set_hub_load(&mut self, load: f64) {
+        self.hub_load = Some(load.max(0.0));
+    }
+
     /// Reset quarantine for a collector
     pub fn reset_quarantine(&mut self, machine_id: &str, collector: &str) {
         let state = self.get_state(machine_id, collector);
@@ -246,108 +407,164 @@ impl AdaptiveScheduler {
     }
 
     /// Calculate the next poll interval for a collector
-    #[allow(
-        clippy::cast_possible_truncation,
-        clippy::cast_sign_loss,
-        clippy::cast_possible_wrap
-    )]
+    ///
+    /// The first rule that applies wins: profiling burst, manual pin,
+    /// quarantine, failure backoff, alert response, freshness recovery, hub
+    /// load, idle, then the default interval.
     pub fn compute_interval(&mut self, machine_id: &str, collector: &str) -> ScheduleDecision {
-        // 1. Check profiling override
-        if let Some(session) = self.profiling_sessions.get(machine_id)
-            && session.expires_at > chrono::Utc::now()
-        {
-            let decision = ScheduleDecision {
-                machine_id: machine_id.to_string(),
-                collector: collector.to_string(),
-                interval_secs: session.interval_secs,
-                reason: ScheduleReason::ProfilingBurst,
-            };
-            self.log_decision(&decision);
-            return decision;
-        }
+        let inputs = self.inputs(machine_id, collector);
+        let (reason, interval_secs) = self.choose(&inputs);
 
-        let key = format!("{machine_id}:{collector}");
-        let state = self.states.get(&key);
+        let state = self.get_state(machine_id, collector);
+        let previous_interval_secs = state.last_interval_secs.replace(interval_secs);
+        let explanation = self.explain(reason, interval_secs, previous_interval_secs, &inputs);
 
-        // 2. Check quarantine
-        if let Some(state) = state
-            && state.quarantined
-        {
-            let decision = ScheduleDecision {
-                machine_id: machine_id.to_string(),
-                collector: collector.to_string(),
-                interval_secs: self.config.quarantine_duration_secs,
-                reason: ScheduleReason::Quarantined,
-            };
-            self.log_decision(&decision);
-            return decision;
+        let decision = ScheduleDecision {
+            machine_id: machine_id.to_string(),
+            collector: collector.to_string(),
+            interval_secs,
+            reason,
+            previous_interval_secs,
+            inputs,
+            explanation,
+        };
+        self.log_decision(&decision);
+        decision
+    }
+
+    /// What the scheduler knows about a collector right now
+    fn inputs(&self, machine_id: &str, collector: &str) -> DecisionInputs {
+        let state = self.states.get(&format!("{machine_id}:{collector}"));
+        let profiling = self.active_profiling(machine_id);
+        DecisionInputs {
+            change_rate: state.and_then(|s| s.change_rate),
+            failure_rate: state.and_then(CollectorState::failure_rate),
+            consecutive_failures: state.map_or(0, |s| s.consecutive_failures),
+            quarantined: state.is_some_and(|s| s.quarantined),
+            active_alert: state.is_some_and(|s| s.has_active_alert),
+            freshness_secs: state.and_then(|s| s.freshness_secs),
+            profile_id: profiling.map(|p| p.profile_id.clone()),
+            profiling_interval_secs: profiling.map(|p| p.interval_secs),
+            pinned_secs: state.and_then(|s| s.pinned_secs),
+            hub_load: self.hub_load,
         }
+    }
 
-        // 3. Check failure backoff
-        if let Some(state) = state
-            && state.consecutive_failures > 0
+    /// The rule that applies to `inputs` and the interval it sets
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn choose(&self, inputs: &DecisionInputs) -> (ScheduleReason, u32) {
+        let config = &self.config;
+        let stretched = || {
+            let interval = f64::from(config.default_interval_secs) * config.backoff_multiplier;
+            (interval as u32).min(config.max_interval_secs)
+        };
+
+        if let Some(interval) = inputs.profiling_interval_secs {
+            return (ScheduleReason::ProfilingBurst, interval);
+        }
+        if let Some(interval) = inputs.pinned_secs {
+            return (ScheduleReason::ManualPin, interval);
+        }
+        if inputs.quarantined {
+            return (ScheduleReason::Quarantined, config.quarantine_duration_secs);
+        }
+        if inputs.consecutive_failures > 0 {
+            let exponent = i32::try_from(inputs.consecutive_failures).unwrap_or(i32::MAX);
+            let backoff =
+                f64::from(config.default_interval_secs) * config.backoff_multiplier.powi(exponent);
+            let interval = (backoff as u32).min(config.max_interval_secs);
+            return (ScheduleReason::FailureBackoff, interval);
+        }
+        if inputs.active_alert {
+            return (ScheduleReason::AlertResponse, config.min_interval_secs);
+        }
+        if inputs
+            .freshness_secs
+            .is_some_and(|age| age > f64::from(config.default_interval_secs * 3))
         {
-            let exponent = i32::try_from(state.consecutive_failures).unwrap_or(i32::MAX);
-            let backoff = f64::from(self.config.default_interval_secs)
-                * self.config.backoff_multiplier.powi(exponent);
-            let interval = (backoff as u32).min(self.config.max_interval_secs);
-            let decision = ScheduleDecision {
-                machine_id: machine_id.to_string(),
-                collector: collector.to_string(),
-                interval_secs: interval,
-                reason: ScheduleReason::FailureBackoff,
-            };
-            self.log_decision(&decision);
-            return decision;
+            return (ScheduleReason::FreshnessRecovery, config.min_interval_secs);
         }
-
-        // 4. Check active alerts (shorter interval)
-        if let Some(state) = state
-            && state.has_active_alert
+        if inputs
+            .hub_load
+            .is_some_and(|load| load >= config.hub_load_threshold)
         {
-            let decision = ScheduleDecision {
-                machine_id: machine_id.to_string(),
-                collector: collector.to_string(),
-                interval_secs: self.config.min_interval_secs,
-                reason: ScheduleReason::AlertResponse,
-            };
-            self.log_decision(&decision);
-            return decision;
+            return (ScheduleReason::HubLoad, stretched());
         }
-
-        // 5. Check freshness (stale → poll sooner)
-        if let Some(state) = state
-            && let Some(freshness) = state.freshness_secs
-            && freshness > f64::from(self.config.default_interval_secs * 3)
+        if inputs
+            .change_rate
+            .is_some_and(|rate| rate <= config.idle_change_rate)
         {
-            let decision = ScheduleDecision {
-                machine_id: machine_id.to_string(),
-                collector: collector.to_string(),
-                interval_secs: self.config.min_interval_secs,
-                reason: ScheduleReason::FreshnessRecovery,
-            };
-            self.log_decision(&decision);
-            return decision;
+            return (ScheduleReason::Idle, stretched());
         }
+        (ScheduleReason::Default, config.default_interval_secs)
+    }
 
-        // 6. Default interval
-        let decision = ScheduleDecision {
-            machine_id: machine_id.to_string(),
-            collector: collector.to_string(),
-            interval_secs: self.config.default_interval_secs,
-            reason: ScheduleReason::Default,
+    /// One sentence: the interval change, then why
+    fn explain(
+        &self,
+        reason: ScheduleReason,
+        interval: u32,
+        previous: Option<u32>,
+        inputs: &DecisionInputs,
+    ) -> String {
+        let config = &self.config;
+        let change = match previous {
+            Some(previous) if previous != interval => format!("{previous}s → {interval}s"),
+            Some(_) => format!("{interval}s unchanged"),
+            None => format!("{interval}s"),
         };
-        self.log_decision(&decision);
-        decision
+        let why = match reason {
+            ScheduleReason::ProfilingBurst => format!(
+                "burst polling for profiling session {}",
+                inputs.profile_id.as_deref().unwrap_or("-")
+            ),
+            ScheduleReason::ManualPin => "pinned by an operator".to_string(),
+            ScheduleReason::Quarantined => format!(
+                "quarantined after {} consecutive failures, retrying after the quarantine period",
+                inputs.consecutive_failures
+            ),
+            ScheduleReason::FailureBackoff => format!(
+                "backed off after {} consecutive failures ({}s × {}^{}, capped at {}s)",
+                inputs.consecutive_failures,
+                config.default_interval_secs,
+                config.backoff_multiplier,
+                inputs.consecutive_failures,
+                config.max_interval_secs
+            ),
+            ScheduleReason::AlertResponse => {
+                "shortened to the minimum while an alert is active".to_string()
+            }
+            ScheduleReason::FreshnessRecovery => format!(
+                "shortened to the minimum because the newest data is {:.0}s old, over 3× the \
+                 {}s default",
+                inputs.freshness_secs.unwrap_or_default(),
+                config.default_interval_secs
+            ),
+            ScheduleReason::HubLoad => format!(
+                "stretched because the hub is at {:.0}% load (threshold {:.0}%)",
+                inputs.hub_load.unwrap_or_default() * 100.0,
+                config.hub_load_threshold * 100.0
+            ),
+            ScheduleReason::Idle => format!(
+                "stretched because only {:.0}% of recent polls brought new data",
+                inputs.change_rate.unwrap_or_default() * 100.0
+            ),
+            ScheduleReason::Default => "default interval, no rule called for a change".to_string(),
+        };
+        format!("{change}: {why}")
     }
 
     /// Log a decision to the store
     fn log_decision(&self, decision: &ScheduleDecision) {
         if let Some(ref store) = self.store {
             let interval_i32 = i32::try_from(decision.interval_secs).unwrap_or(i32::MAX);
-            let reason_json =
-                serde_json::to_string(&serde_json::json!({"reason": decision.reason.as_str()}))
-                    .unwrap_or_default();
+            let reason_json = serde_json::json!({
+                "reason": decision.reason.as_str(),
+                "previous_interval_secs": decision.previous_interval_secs,
+                "inputs": decision.inputs,
+                "explanation": decision.explanation,
+            })
+            .to_string();
             let _ = store.insert_poll_decision(
                 &decision.machine_id,
                 &decision.collector,
@@ -374,6 +591,7 @@ mod tests {
             quarantine_after_failures: 3,
             quarantine_duration_secs: 600,
             backoff_multiplier: 2.0,
+            ..AdaptiveConfig::default()
         }
     }
 
@@ -476,6 +694,19 @@ mod tests {
         assert_eq!(decision.interval_secs, 600);
     }
 
+    #[test]
+    fn test_failure_cause_decides_quarantine() {
+        let mut sched = AdaptiveScheduler::new(default_config());
+        sched.record_failure_cause("orko", "sysmoni", ErrorCause::Timeout);
+        let decision = sched.compute_interval("orko", "sysmoni");
+        assert_eq!(decision.reason, ScheduleReason::FailureBackoff);
+
+        sched.record_failure_cause("orko", "caut", ErrorCause::AuthFailed);
+        let decision = sched.compute_interval("orko", "caut");
+        assert_eq!(decision.reason, ScheduleReason::Quarantined);
+        assert_eq!(decision.interval_secs, 600);
+    }
+
     #[test]
     fn test_quarantine_reset() {
         let mut sched = AdaptiveScheduler::new(default_config());
@@ -641,6 +872,125 @@ mod tests {
         assert_eq!(decisions.len(), 1);
     }
 
+    #[test]
+    fn test_every_rule_logs_its_identifier_and_reasoning() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let mut sched = AdaptiveScheduler::with_store(default_config(), store.clone());
+        // Each path on its own collector so earlier setups do not interfere.
+        let paths: [(&str, fn(&mut AdaptiveScheduler, &str), &str, u32); 9] = [
+            ("c-default", |_, _| {}, "default", 60),
+            (
+                "c-profiling",
+                |s, _| s.start_profiling("prof-1", "burst", 2, 120),
+                "profiling_burst",
+                2,
+            ),
+            (
+                "c-pin",
+                |s, c| s.pin_interval("orko", c, Some(90)),
+                "manual_pin",
+                90,
+            ),
+            (
+                "c-quarantine",
+                |s, c| s.record_failure_cause("orko", c, ErrorCause::AuthFailed),
+                "quarantined",
+                600,
+            ),
+            (
+                "c-backoff",
+                |s, c| s.record_failure("orko", c),
+                "failure_backoff",
+                120,
+            ),
+            (
+                "c-alert",
+                |s, c| s.set_active_alert("orko", c, true),
+                "alert_response",
+                10,
+            ),
+            (
+                "c-stale",
+                |s, c| s.set_freshness("orko", c, 400.0),
+                "freshness_recovery",
+                10,
+            ),
+            (
+                "c-idle",
+                |s, c| s.set_change_rate("orko", c, 0.0),
+                "idle",
+                120,
+            ),
+            ("c-hub", |s, _| s.set_hub_load(0.9), "hub_load", 120),
+        ];
+
+        for (collector, setup, rule, interval) in paths {
+            setup(&mut sched, collector);
+            let machine = if rule == "profiling_burst" {
+                "burst"
+            } else {
+                "orko"
+            };
+            let decision = sched.compute_interval(machine, collector);
+            assert_eq!(decision.reason.as_str(), rule, "{collector}");
+            assert_eq!(decision.interval_secs, interval, "{collector}");
+
+            let rows = store.list_poll_decisions(Some(machine), 50).unwrap();
+            let row = rows
+                .iter()
+                .find(|row| row["collector"] == collector)
+                .expect("decision row");
+            let reasoning: serde_json::Value =
+                serde_json::from_str(row["reason_json"].as_str().unwrap()).unwrap();
+            assert_eq!(reasoning["reason"], rule, "{collector}");
+            assert!(
+                reasoning["explanation"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with(&format!("{interval}s: ")),
+                "{reasoning}"
+            );
+            assert!(reasoning["inputs"].is_object());
+        }
+    }
+
+    #[test]
+    fn test_decision_records_previous_interval_and_inputs() {
+        let mut sched = AdaptiveScheduler::new(default_config());
+        assert_eq!(
+            sched
+                .compute_interval("orko", "sysmoni")
+                .previous_interval_secs,
+            None
+        );
+
+        sched.record_success("orko", "sysmoni");
+        sched.record_failure("orko", "sysmoni");
+        sched.record_failure("orko", "sysmoni");
+        sched.set_hub_load(0.5);
+        let decision = sched.compute_interval("orko", "sysmoni");
+        assert_eq!(decision.previous_interval_secs, Some(60));
+        assert_eq!(decision.inputs.consecutive_failures, 2);
+        assert_eq!(decision.inputs.failure_rate, Some(2.0 / 3.0));
+        assert_eq!(decision.inputs.hub_load, Some(0.5));
+        assert!(
+            decision
+                .explanation
+                .starts_with("60s → 240s: backed off after 2 consecutive failures"),
+            "{}",
+            decision.explanation
+        );
+        assert!(
+            decision
+                .inputs
+                .summary()
+                .contains(&"hub load 50%".to_string())
+        );
+
+        let again = sched.compute_interval("orko", "sysmoni");
+        assert!(again.explanation.starts_with("240s unchanged"));
+    }
+
     // ========================================================================
     // Serialization tests
     // ========================================================================
@@ -652,12 +1002,22 @@ mod tests {
             collector: "sysmoni".to_string(),
             interval_secs: 60,
             reason: ScheduleReason::Default,
+            previous_interval_secs: None,
+            inputs: DecisionInputs::default(),
+            explanation: "60s: default interval".to_string(),
         };
 
         let json = serde_json::to_string(&decision).unwrap();
         let parsed: ScheduleDecision = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.interval_secs, 60);
         assert_eq!(parsed.reason, ScheduleReason::Default);
+
+        // Decisions serialized before reasoning was recorded still parse
+        let legacy: ScheduleDecision = serde_json::from_str(
+            r#"{"machine_id":"orko","collector":"sysmoni","interval_secs":60,"reason":"default"}"#,
+        )
+        .unwrap();
+        assert!(legacy.explanation.is_empty());
     }
 
     #[test]
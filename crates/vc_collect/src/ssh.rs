@@ -631,7 +631,7 @@ fn escape_path(path: &str) -> String {
 }
 
 /// Simple base64 encoding
-fn base64_encode(data: &[u8]) -> String {
+pub(crate) fn base64_encode(data: &[u8]) -> String {
     use std::io::Write;
     let mut output = Vec::new();
     {
@@ -828,6 +828,7 @@ mod tests {
 
             let machine = Machine {
                 machine_id: "remote-test".to_string(),
+                machine_uuid: None,
                 hostname: "remote-test".to_string(),
                 display_name: None,
                 ssh_host: None,
@@ -844,6 +845,7 @@ mod tests {
                 tags: vec![],
                 metadata: None,
                 enabled: true,
+                config_absent: false,
             };
 
             let result = runner.exec_with_cx(&cx, &machine, "echo hello").await;
@@ -0,0 +1,216 @@
+//! Config snapshots
+//!
+//! Every time a config file with new content is loaded, a snapshot of it
+//! goes into the store's `config_history` table, so an edit to vc.toml
+//! always leaves a record. This module holds what is needed on both sides
+//! of that table: the content hash that identifies a snapshot, masking of
+//! secret-bearing values before a snapshot is shown, and the line diff
+//! between two snapshots.
+
+use crate::{SecretRef, VcConfig};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::sync::LazyLock;
+
+/// Shown in place of a secret value
+pub const MASK: &str = "********";
+
+/// Unchanged lines shown around each change in a diff
+const DIFF_CONTEXT: usize = 2;
+
+/// `key = value` lines, commented out or not, whose key names a secret.
+/// `*_file` and `*_env` keys only say where a secret lives, so they are
+/// left alone.
+static SECRET_KEY_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)^(\s*#?\s*"?[a-z0-9_.-]*(?:password|token|secret|webhook_url|api_key|apikey|authorization)"?\s*=\s*)(.+)$"#,
+    )
+    .expect("secret key pattern is valid")
+});
+
+/// SHA-256 of the file content, hex encoded
+#[must_use]
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Replace every inline secret in `content` with [`MASK`]
+///
+/// Values of the keys [`VcConfig::secret_fields`] knows about are masked
+/// wherever they appear, and so is the value of any line whose key looks
+/// like it holds a secret, which covers content too old or broken to parse.
+/// `secret://` references are kept: they name where a secret lives, not
+/// the secret.
+#[must_use]
+pub fn mask_secrets(content: &str) -> String {
+    let mut values: Vec<String> = toml::from_str::<VcConfig>(content)
+        .map(|config| {
+            config
+                .secret_fields()
+                .into_iter()
+                .map(|(_, value)| value.to_string())
+                .filter(|value| !value.is_empty() && !SecretRef::is_reference(value))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Longest first, so a secret that contains another is masked whole
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+    let mut masked: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let mut line = line.to_string();
+            for value in &values {
+                line = line.replace(value.as_str(), MASK);
+            }
+            mask_secret_key_line(&line)
+        })
+        .collect();
+    if content.ends_with('\n') {
+        masked.push(String::new());
+    }
+    masked.join("\n")
+}
+
+fn mask_secret_key_line(line: &str) -> String {
+    let Some(caps) = SECRET_KEY_LINE.captures(line) else {
+        return line.to_string();
+    };
+    let key = caps[1].trim_end().trim_end_matches('=').trim();
+    let key = key.trim_matches('"');
+    let value = caps[2].trim();
+    let unquoted = value.trim_matches(|c| c == '"' || c == '\'');
+    if key.ends_with("_file")
+        || key.ends_with("_env")
+        || SecretRef::is_reference(unquoted)
+        || unquoted.contains(MASK)
+    {
+        return line.to_string();
+    }
+    format!("{}\"{MASK}\"", &caps[1])
+}
+
+/// Line diff from `old` to `new`: `-` for removed lines, `+` for added
+/// ones, each run of changes under an `@@ -line +line @@` header with a
+/// little unchanged context. Empty when the two are the same.
+#[must_use]
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths, from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // (old line, new line, marker, text), in order
+    let mut ops: Vec<(usize, usize, char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((i, j, ' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((i, j, '-', old[i]));
+            i += 1;
+        } else {
+            ops.push((i, j, '+', new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| op.2 != ' ')
+        .map(|(index, _)| index)
+        .collect();
+    let mut out = String::new();
+    let mut shown_to = 0;
+    let mut index = 0;
+    while index < changed.len() {
+        let start = changed[index].saturating_sub(DIFF_CONTEXT).max(shown_to);
+        // Extend the hunk while the next change is within reach of its context
+        let mut last = changed[index];
+        while index + 1 < changed.len() && changed[index + 1] <= last + 2 * DIFF_CONTEXT + 1 {
+            index += 1;
+            last = changed[index];
+        }
+        let end = (last + DIFF_CONTEXT + 1).min(ops.len());
+        let _ = writeln!(out, "@@ -{} +{} @@", ops[start].0 + 1, ops[start].1 + 1);
+        for &(_, _, marker, text) in &ops[start..end] {
+            let _ = writeln!(out, "{marker}{text}");
+        }
+        shown_to = end;
+        index += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash("a"), content_hash("a"));
+        assert_ne!(content_hash("a"), content_hash("a\n"));
+        assert_eq!(content_hash("").len(), 64);
+    }
+
+    #[test]
+    fn test_mask_secrets() {
+        let content = r#"[alerts]
+webhook_url = "https://hooks.example.com/T000/abc"
+slack_webhook_url = "secret://file/~/.config/vc/slack"
+
+[alerts.email]
+password_env = "SMTP_PASSWORD"
+
+[telemetry.headers]
+x-honeycomb-team = "hc-key-1234"
+
+[replication]
+token = 'r3pl1c4'
+"#;
+        let masked = mask_secrets(content);
+        assert!(!masked.contains("abc"), "{masked}");
+        assert!(!masked.contains("hc-key-1234"), "{masked}");
+        assert!(!masked.contains("r3pl1c4"), "{masked}");
+        assert!(masked.contains("secret://file/~/.config/vc/slack"));
+        assert!(masked.contains("password_env = \"SMTP_PASSWORD\""));
+        assert!(masked.ends_with('\n'));
+
+        // Unparseable content still has secret-looking keys masked
+        let masked = mask_secrets("[broken\napi_token = \"xyz\"\n");
+        assert!(
+            masked.contains(&format!("api_token = \"{MASK}\"")),
+            "{masked}"
+        );
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert!(line_diff("a\nb\n", "a\nb\n").is_empty());
+
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let diff = line_diff(old, new);
+        assert_eq!(
+            diff,
+            "@@ -1 +1 @@\n a\n-b\n+B\n c\n d\n@@ -9 +9 @@\n i\n j\n+k\n"
+        );
+    }
+}
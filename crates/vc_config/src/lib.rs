@@ -4,21 +4,61 @@
 //! - TOML configuration parsing
 //! - Default value handling
 //! - Environment variable overrides
+//! - Named profiles layered over the base config
 //! - Path expansion (`~/` to home directory)
 //! - Auto-discovery from standard config paths
 //! - Machine inventory definitions
 //! - Configuration linting with actionable suggestions
 //! - Configuration wizard for generating new configs
+//! - External secret references (`secret://file|env|cmd/...`)
+//! - Config snapshots: content hashing, secret masking and line diffs
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use thiserror::Error;
 use tracing::info;
 
+pub mod history;
+pub mod secret;
+pub mod template;
+
+pub use secret::{SecretCheck, SecretRef, resolve_secret};
+pub use template::{Escape, MessageTemplate, Rendered, TemplateError};
+
 /// Valid log level strings (trace, debug, info, warn, error)
 const VALID_LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+const VALID_STORE_BACKENDS: &[&str] = &["duckdb", "sqlite"];
+const VALID_DIGEST_SINKS: &[&str] = &["webhook", "slack", "discord", "desktop", "email"];
+const VALID_SMTP_TLS_MODES: &[&str] = &["none", "starttls", "tls"];
+const VALID_SEVERITIES: &[&str] = &["info", "warning", "critical"];
+const VALID_ESCALATION_ACTIONS: &[&str] = &["bump_severity", "notify", "open_incident"];
+const VALID_HTTP_METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+/// Audit event types frequent enough to sample or roll up. Guardian,
+/// autopilot and user-command events are never in this list.
+const SAMPLEABLE_AUDIT_EVENTS: &[&str] = &["collector_run"];
+
+/// Every tool `vc mcp serve` offers; `[mcp.roles.*]` may only name these
+pub const MCP_TOOLS: &[&str] = &[
+    "vc_fleet_status",
+    "vc_query_machines",
+    "vc_query_alerts",
+    "vc_query_sessions",
+    "vc_query_incidents",
+    "vc_query_nl",
+    "vc_collector_status",
+    "vc_playbook_drafts",
+    "vc_audit_log",
+    "vc_timeline",
+    "vc_search",
+    "vc_propose_query",
+    "vc_check_query",
+    "vc_get_session_transcript",
+];
+
+/// Environment variable selecting a profile when `--profile` is not given
+pub const PROFILE_ENV: &str = "VC_PROFILE";
 
 // =============================================================================
 // Lint Types
@@ -171,6 +211,9 @@ pub enum ConfigError {
 
     #[error("Missing required field: {0}")]
     MissingField(String),
+
+    #[error("Cannot resolve secret {key}: {reason}")]
+    SecretError { key: String, reason: String },
 }
 
 /// Top-level configuration structure
@@ -197,6 +240,94 @@ pub struct VcConfig {
 
     /// Web dashboard settings
     pub web: WebConfig,
+
+    /// Daemon settings
+    pub daemon: DaemonConfig,
+
+    /// Audit log write policy
+    pub audit: AuditConfig,
+
+    /// Provider CLI commands polled for account quota state
+    pub accounts: AccountsConfig,
+
+    /// How the `agent_inventory` collector recognizes agent processes
+    pub agent_inventory: AgentInventoryConfig,
+
+    /// Knowledge base settings
+    pub knowledge: KnowledgeConfig,
+
+    /// OpenTelemetry export of alerts, incidents and collector failures
+    pub telemetry: TelemetryConfig,
+
+    /// Warm standby replication to a second cockpit
+    pub replication: ReplicationConfig,
+
+    /// Ingest of bundles pushed by vc-node agents
+    pub ingest: IngestConfig,
+
+    /// Per-query timing recorded by the store for `vc db slow-queries`
+    pub query_log: QueryLogConfig,
+
+    /// Size guard on every value the store writes
+    pub write_limits: WriteLimitsConfig,
+
+    /// Guardrails on queries run from the CLI, web API and MCP
+    pub query: QueryConfig,
+
+    /// Raw queries agents propose over MCP for an operator to approve
+    pub query_approval: QueryApprovalConfig,
+
+    /// Which MCP tools each agent role sees
+    pub mcp: McpConfig,
+
+    /// Automatic `ANALYZE` of tables that changed a lot since their last one
+    pub analyze: AnalyzeConfig,
+
+    /// Machine maintenance windows (`vc machines maintenance`)
+    pub maintenance: MaintenanceConfig,
+
+    /// What a probe does when a machine turns up under a new id
+    pub identity: IdentityConfig,
+
+    /// Component checks behind `vc doctor` and `/api/health/components`
+    pub health_components: HealthComponentsConfig,
+
+    /// Guardrails on `vc fleet run` and `POST /api/fleet/run`
+    pub fleet_run: FleetRunConfig,
+
+    /// Incident SLA targets
+    pub incidents: IncidentConfig,
+
+    /// Disk usage trend fitting for `vc health forecast`
+    pub forecast: ForecastConfig,
+
+    /// Services that should be running on machines, checked during probe
+    /// and collection
+    pub services: Vec<ServiceCheckConfig>,
+
+    /// Where large artifacts live once they leave the database
+    pub storage: StorageConfig,
+
+    /// Named overrides (`[profiles.<name>]`) merged over the rest of the
+    /// file when selected: tables merge key by key, everything else is
+    /// replaced
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, toml::Table>,
+}
+
+/// Merge `overrides` into `base`: tables merge recursively, any other value
+/// replaces what was there
+fn merge_toml_tables(base: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(value)) => {
+                merge_toml_tables(existing, value);
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
 }
 
 /// Global configuration settings
@@ -214,6 +345,22 @@ pub struct GlobalConfig {
 
     /// Enable JSON logging
     pub json_logs: bool,
+
+    /// Storage engine: `duckdb` or `sqlite`. Unset picks by `db_path`
+    /// extension (`.sqlite` / `.sqlite3` → `SQLite`, otherwise `DuckDB`).
+    pub store_backend: Option<String>,
+
+    /// Timezone for human-facing timestamps: `local` (the system timezone)
+    /// or an IANA name such as `Europe/Berlin`. JSON output is always UTC.
+    pub timezone: String,
+
+    /// Config snapshots kept in `config_history`; older ones are dropped and
+    /// 0 turns snapshotting off
+    pub config_history_keep: usize,
+
+    /// Seconds the CLI waits for the database to open (migrations
+    /// included) before giving up, so a hung filesystem fails fast
+    pub open_timeout_secs: u64,
 }
 
 impl Default for GlobalConfig {
@@ -223,6 +370,10 @@ impl Default for GlobalConfig {
             poll_interval_secs: 120,
             log_level: "info".to_string(),
             json_logs: false,
+            store_backend: None,
+            timezone: "local".to_string(),
+            config_history_keep: 50,
+            open_timeout_secs: 30,
         }
     }
 }
@@ -288,6 +439,74 @@ pub struct MachineConfig {
     /// Tags for filtering
     #[serde(default)]
     pub tags: Vec<String>,
+
+    /// Shift timestamps of rows ingested from this machine by its measured
+    /// clock skew; each shifted row records the offset in `clock_skew_ms`
+    #[serde(default)]
+    pub normalize_clock_skew: bool,
+
+    /// HTTP endpoints of services running here, checked by the
+    /// `http_check` collector
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub http_checks: Vec<HttpCheckConfig>,
+}
+
+/// Where an HTTP check's request is sent from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpCheckOrigin {
+    /// The machine itself, so `localhost` URLs work
+    #[default]
+    Machine,
+    /// The cockpit host, to check what the rest of the network sees
+    Hub,
+}
+
+/// An HTTP endpoint checked on each collection, with curl
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpCheckConfig {
+    /// Check name as shown in `vc health checks`; unique per machine
+    pub name: String,
+
+    pub url: String,
+
+    #[serde(default = "default_http_method")]
+    pub method: String,
+
+    /// Status code the endpoint answers with when healthy
+    #[serde(default = "default_http_status")]
+    pub expect_status: u16,
+
+    #[serde(default = "default_http_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Text the response body must contain
+    #[serde(default)]
+    pub body_contains: Option<String>,
+
+    #[serde(default)]
+    pub from: HttpCheckOrigin,
+
+    /// Warn when an https certificate expires within this many days; the
+    /// check itself still passes
+    #[serde(default = "default_tls_warn_days")]
+    pub tls_warn_days: u32,
+}
+
+fn default_http_method() -> String {
+    "GET".to_string()
+}
+
+fn default_http_status() -> u16 {
+    200
+}
+
+fn default_http_timeout_secs() -> u64 {
+    10
+}
+
+fn default_tls_warn_days() -> u32 {
+    14
 }
 
 fn default_true() -> bool {
@@ -362,6 +581,18 @@ pub struct CollectorConfig {
     /// Enable `cloud_benchmarker` collector
     pub cloud_benchmarker: bool,
 
+    /// Enable the accounts collector (provider CLI quota and rate-limit
+    /// state; commands are configured under `[accounts]`)
+    pub accounts: bool,
+
+    /// Enable the `http_check` collector (checks are configured per machine
+    /// under `http_checks`)
+    pub http_check: bool,
+
+    /// Enable the `agent_inventory` collector (running agents found in tmux
+    /// panes and the process table; patterns under `[agent_inventory]`)
+    pub agent_inventory: bool,
+
     /// Collector timeout in seconds
     pub timeout_secs: u64,
 
@@ -370,6 +601,12 @@ pub struct CollectorConfig {
 
     /// Maximum concurrent collector operations allowed against one machine
     pub max_concurrent_per_machine: u32,
+
+    /// Oldest acceptable version per tool, e.g. `sysmoni = "0.5.0"`.
+    /// Machines running anything older are flagged by robot triage and
+    /// `vc fleet versions`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub min_versions: HashMap<String, String>,
 }
 
 impl Default for CollectorConfig {
@@ -392,9 +629,13 @@ impl Default for CollectorConfig {
             afsc: false,
             github: false,
             cloud_benchmarker: false,
+            accounts: true,
+            http_check: true,
+            agent_inventory: true,
             timeout_secs: 30,
             max_concurrent_collectors: 8,
             max_concurrent_per_machine: 4,
+            min_versions: HashMap::new(),
         }
     }
 }
@@ -409,17 +650,45 @@ pub struct AlertConfig {
     /// Default cooldown between duplicate alerts (seconds)
     pub default_cooldown_secs: u64,
 
-    /// Webhook URL for alerts
+    /// Webhook URL for alerts (or a `secret://` reference to it)
     pub webhook_url: Option<String>,
 
-    /// Slack webhook URL
+    /// Slack webhook URL (or a `secret://` reference to it)
     pub slack_webhook_url: Option<String>,
 
-    /// Discord webhook URL
+    /// Discord webhook URL (or a `secret://` reference to it)
     pub discord_webhook_url: Option<String>,
 
     /// Enable desktop notifications
     pub desktop_notifications: bool,
+
+    /// Email delivery over SMTP
+    pub email: Option<EmailConfig>,
+
+    /// Digest mode per sink (`webhook`, `slack`, `discord`, `desktop`, `email`);
+    /// sinks not listed deliver every alert immediately
+    pub digest: HashMap<String, DigestConfig>,
+
+    /// Message template per sink, replacing its default formatting of single
+    /// alerts (digests keep theirs)
+    pub templates: HashMap<String, NotifyTemplateConfig>,
+
+    /// Base URL of the web UI, for `{{links.web_ui}}` in templates; falls
+    /// back to `alerts.email.web_url`
+    pub web_url: Option<String>,
+
+    /// Automatic alerts for collectors that stop producing fresh data
+    pub staleness: StalenessAlertConfig,
+
+    /// Escalation policies for alerts left open and unacknowledged, tried in
+    /// order; an alert group follows the first that matches it
+    pub escalation: Vec<EscalationPolicy>,
+
+    /// Per-machine changes to alert rules, keyed by machine id or tag
+    pub overrides: Vec<AlertRuleOverride>,
+
+    /// Grouping of alerts on one machine that share a probable root cause
+    pub correlation: CorrelationConfig,
 }
 
 impl Default for AlertConfig {
@@ -431,7 +700,507 @@ impl Default for AlertConfig {
             slack_webhook_url: None,
             discord_webhook_url: None,
             desktop_notifications: false,
+            email: None,
+            digest: HashMap::new(),
+            templates: HashMap::new(),
+            web_url: None,
+            staleness: StalenessAlertConfig::default(),
+            escalation: Vec::new(),
+            overrides: Vec::new(),
+            correlation: CorrelationConfig::default(),
+        }
+    }
+}
+
+/// Message template for one sink under `[alerts.templates.<sink>]`
+///
+/// Exactly one of `template` and `file` is set. See [`template`] for the
+/// placeholder syntax.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyTemplateConfig {
+    /// Inline template text
+    pub template: Option<String>,
+
+    /// File holding the template text
+    pub file: Option<PathBuf>,
+}
+
+impl NotifyTemplateConfig {
+    /// Read and parse the template
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ValidationError`] if the file cannot be read or
+    /// the template does not parse.
+    pub fn load(&self, sink: &str) -> Result<MessageTemplate, ConfigError> {
+        let source = match (&self.template, &self.file) {
+            (Some(template), None) => template.clone(),
+            (None, Some(path)) => {
+                let path = expand_path(path);
+                std::fs::read_to_string(&path).map_err(|e| {
+                    ConfigError::ValidationError(format!(
+                        "alerts.templates.{sink}.file {}: {e}",
+                        path.display()
+                    ))
+                })?
+            }
+            _ => {
+                return Err(ConfigError::ValidationError(format!(
+                    "alerts.templates.{sink} requires exactly one of template or file"
+                )));
+            }
+        };
+        MessageTemplate::parse(&source)
+            .map_err(|e| ConfigError::ValidationError(format!("alerts.templates.{sink}: {e}")))
+    }
+}
+
+/// SMTP sink under `[alerts.email]`
+///
+/// The password is read from `password_file`, the `password_env` variable or
+/// a `secret://` reference in `password` at send time and is never stored in
+/// the config itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// SMTP server hostname
+    pub server: String,
+
+    /// SMTP port; defaults to 25, 587 or 465 for the `tls` mode
+    pub port: Option<u16>,
+
+    /// Transport security: `none`, `starttls` or `tls` (implicit TLS)
+    pub tls: String,
+
+    /// SMTP auth user; no auth when unset
+    pub username: Option<String>,
+
+    /// File holding the SMTP password
+    pub password_file: Option<PathBuf>,
+
+    /// Environment variable holding the SMTP password
+    pub password_env: Option<String>,
+
+    /// Secret reference (`secret://...`) for the SMTP password; an inline
+    /// password is rejected by validation
+    #[serde(skip_serializing_if = "is_not_secret_reference")]
+    pub password: Option<String>,
+
+    /// Sender address
+    pub from: String,
+
+    /// Recipient addresses
+    pub to: Vec<String>,
+
+    /// Alerts below this severity are not emailed
+    pub min_severity: String,
+
+    /// Base URL of the web UI, linked from alert emails
+    pub web_url: Option<String>,
+
+    /// Delivery attempts per message before giving up
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubles on each further attempt
+    pub retry_backoff_secs: u64,
+
+    /// Per-attempt timeout (seconds)
+    pub timeout_secs: u64,
+}
+
+/// Keeps an inline SMTP password out of `vc config show`
+#[allow(clippy::ref_option)]
+fn is_not_secret_reference(password: &Option<String>) -> bool {
+    !password.as_deref().is_some_and(SecretRef::is_reference)
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            server: String::new(),
+            port: None,
+            tls: "starttls".to_string(),
+            username: None,
+            password_file: None,
+            password_env: None,
+            password: None,
+            from: String::new(),
+            to: Vec::new(),
+            min_severity: "critical".to_string(),
+            web_url: None,
+            max_attempts: 3,
+            retry_backoff_secs: 5,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl EmailConfig {
+    /// Configured port, or the conventional one for the TLS mode
+    #[must_use]
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(match self.tls.to_lowercase().as_str() {
+            "none" => 25,
+            "tls" => 465,
+            _ => 587,
+        })
+    }
+
+    /// Read the SMTP password from `password_file`, `password_env` or the
+    /// `password` secret reference
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ValidationError`] if the file cannot be read or
+    /// the variable is unset, and [`ConfigError::SecretError`] if the
+    /// reference does not resolve.
+    pub fn resolve_password(&self) -> Result<Option<String>, ConfigError> {
+        if let Some(path) = &self.password_file {
+            let path = expand_path(path);
+            return std::fs::read_to_string(&path)
+                .map(|raw| Some(raw.trim_end_matches(['\r', '\n']).to_string()))
+                .map_err(|e| {
+                    ConfigError::ValidationError(format!(
+                        "alerts.email.password_file {}: {e}",
+                        path.display()
+                    ))
+                });
+        }
+        if let Some(var) = &self.password_env {
+            return std::env::var(var).map(Some).map_err(|_| {
+                ConfigError::ValidationError(format!(
+                    "alerts.email.password_env: ${var} is not set"
+                ))
+            });
         }
+        self.password
+            .as_deref()
+            .map(|reference| resolve_secret("alerts.email.password", reference))
+            .transpose()
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::ValidationError(msg));
+        if self
+            .password
+            .as_deref()
+            .is_some_and(|password| !SecretRef::is_reference(password))
+        {
+            return invalid(
+                "alerts.email.password must not be set inline; use password_file, password_env \
+                 or a secret:// reference"
+                    .to_string(),
+            );
+        }
+        if self.server.trim().is_empty() || self.from.trim().is_empty() || self.to.is_empty() {
+            return invalid("alerts.email requires server, from and at least one to".to_string());
+        }
+        if !VALID_SMTP_TLS_MODES.contains(&self.tls.to_lowercase().as_str()) {
+            return invalid(format!(
+                "Invalid alerts.email.tls '{}'. Must be one of: {}",
+                self.tls,
+                VALID_SMTP_TLS_MODES.join(", ")
+            ));
+        }
+        if !VALID_SEVERITIES.contains(&self.min_severity.to_lowercase().as_str()) {
+            return invalid(format!(
+                "Invalid alerts.email.min_severity '{}'. Must be one of: {}",
+                self.min_severity,
+                VALID_SEVERITIES.join(", ")
+            ));
+        }
+        if self.username.is_some()
+            && self.password_file.is_none()
+            && self.password_env.is_none()
+            && self.password.is_none()
+        {
+            return invalid(
+                "alerts.email.username requires password_file, password_env or password"
+                    .to_string(),
+            );
+        }
+        if self.max_attempts == 0 || self.timeout_secs == 0 {
+            return invalid("alerts.email max_attempts and timeout_secs must be > 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Batching of low-severity alerts for one notification sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Alerts below this severity are batched; critical always bypasses
+    pub min_severity: String,
+
+    /// Send the pending digest this often (seconds)
+    pub flush_interval_secs: u64,
+
+    /// Send early once this many alerts are pending
+    pub max_batch_size: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            min_severity: "warning".to_string(),
+            flush_interval_secs: 1800,
+            max_batch_size: 50,
+        }
+    }
+}
+
+/// One `[[alerts.escalation]]` policy, evaluated by the daemon every cycle
+/// against alert groups (one rule on one machine) that are open and
+/// unacknowledged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EscalationPolicy {
+    /// Names the policy on escalated alerts and in the audit log
+    pub name: String,
+
+    /// Rule ID, or its prefix before `:` (`collector-stale` matches
+    /// `collector-stale:sysmoni`); unset matches every type
+    pub alert_type: Option<String>,
+
+    /// Alert group as `<machine>/<rule>`; unset matches every group
+    pub group: Option<String>,
+
+    /// Only groups that fired at this severity; unset matches any
+    pub severity: Option<String>,
+
+    /// Escalation levels in order: level N applies `steps[N-1]`
+    pub steps: Vec<EscalationStep>,
+}
+
+/// One escalation level
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EscalationStep {
+    /// Age of the group's oldest open occurrence before this level applies
+    /// (seconds)
+    pub after_secs: u64,
+
+    /// `bump_severity`, `notify` or `open_incident`
+    pub action: String,
+
+    /// Sink the `notify` action delivers to
+    pub sink: Option<String>,
+}
+
+impl EscalationPolicy {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::ValidationError(msg));
+        if self.name.trim().is_empty() {
+            return invalid("alerts.escalation policies need a name".to_string());
+        }
+        let name = &self.name;
+        if let Some(severity) = &self.severity
+            && !VALID_SEVERITIES.contains(&severity.to_lowercase().as_str())
+        {
+            return invalid(format!(
+                "Invalid alerts.escalation '{name}' severity '{severity}'. Must be one of: {}",
+                VALID_SEVERITIES.join(", ")
+            ));
+        }
+        if self.steps.is_empty() {
+            return invalid(format!("alerts.escalation '{name}' has no steps"));
+        }
+        let mut after = 0;
+        for step in &self.steps {
+            if step.after_secs == 0 || step.after_secs < after {
+                return invalid(format!(
+                    "alerts.escalation '{name}' step after_secs must be > 0 and in order"
+                ));
+            }
+            after = step.after_secs;
+            if !VALID_ESCALATION_ACTIONS.contains(&step.action.as_str()) {
+                return invalid(format!(
+                    "Invalid alerts.escalation '{name}' action '{}'. Must be one of: {}",
+                    step.action,
+                    VALID_ESCALATION_ACTIONS.join(", ")
+                ));
+            }
+            match (step.action.as_str(), step.sink.as_deref()) {
+                ("notify", Some(sink)) if VALID_DIGEST_SINKS.contains(&sink) => {}
+                ("notify", _) => {
+                    return invalid(format!(
+                        "alerts.escalation '{name}' notify needs a sink, one of: {}",
+                        VALID_DIGEST_SINKS.join(", ")
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One `[[alerts.overrides]]` block: changes to a rule on the machines it
+/// covers, leaving the rest of the rule as defined
+///
+/// Precedence is machine id > tag > the rule itself. When several tag
+/// overrides match a machine, the highest `priority` applies and equal
+/// priorities go to the block listed first; a machine override is layered on
+/// top field by field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRuleOverride {
+    /// Rule ID the override applies to
+    pub rule: String,
+
+    /// Machine the override applies to; exclusive with `tag`
+    pub machine: Option<String>,
+
+    /// Machines carrying this tag; exclusive with `machine`
+    pub tag: Option<String>,
+
+    /// Orders tag overrides that match the same machine, highest first
+    pub priority: i32,
+
+    /// Threshold value (`threshold_per_sec` for rate-of-change rules)
+    pub threshold: Option<f64>,
+
+    /// Time window of absence (`max_age_secs`) and rate-of-change
+    /// (`window_secs`) rules
+    pub duration_secs: Option<u64>,
+
+    /// Severity of the alerts the rule raises
+    pub severity: Option<String>,
+}
+
+impl AlertRuleOverride {
+    /// `machine:<id>` or `tag:<tag>`
+    #[must_use]
+    pub fn scope(&self) -> String {
+        match (&self.machine, &self.tag) {
+            (Some(machine), _) => format!("machine:{machine}"),
+            (None, Some(tag)) => format!("tag:{tag}"),
+            (None, None) => "none".to_string(),
+        }
+    }
+
+    /// Whether the override covers `machine_id` carrying `tags`
+    #[must_use]
+    pub fn matches(&self, machine_id: &str, tags: &[String]) -> bool {
+        match (&self.machine, &self.tag) {
+            (Some(machine), _) => machine == machine_id,
+            (None, Some(tag)) => tags.contains(tag),
+            (None, None) => false,
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::ValidationError(msg));
+        if self.rule.trim().is_empty() {
+            return invalid("alerts.overrides entries need a rule".to_string());
+        }
+        let rule = &self.rule;
+        if self.machine.is_some() == self.tag.is_some() {
+            return invalid(format!(
+                "alerts.overrides for '{rule}' requires exactly one of machine or tag"
+            ));
+        }
+        if self.threshold.is_none() && self.duration_secs.is_none() && self.severity.is_none() {
+            return invalid(format!(
+                "alerts.overrides for '{rule}' ({}) changes nothing; set threshold, \
+                 duration_secs or severity",
+                self.scope()
+            ));
+        }
+        if self.threshold.is_some_and(|value| !value.is_finite()) {
+            return invalid(format!(
+                "alerts.overrides for '{rule}' threshold must be a finite number"
+            ));
+        }
+        if self.duration_secs == Some(0) {
+            return invalid(format!(
+                "alerts.overrides for '{rule}' duration_secs must be > 0"
+            ));
+        }
+        if let Some(severity) = &self.severity
+            && !VALID_SEVERITIES.contains(&severity.to_lowercase().as_str())
+        {
+            return invalid(format!(
+                "Invalid alerts.overrides '{rule}' severity '{severity}'. Must be one of: {}",
+                VALID_SEVERITIES.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Alert correlation under `[alerts.correlation]`, run by the daemon every
+/// cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CorrelationConfig {
+    /// Group correlated alerts
+    pub enabled: bool,
+
+    /// Open alerts on one machine fired within this many seconds of each
+    /// other join the same group
+    pub window_secs: u64,
+
+    /// Rule-id fragments, most likely cause first. Among the group's most
+    /// severe alerts, the one matching the earliest entry is the root; the
+    /// earliest fired breaks ties.
+    pub causal_order: Vec<String>,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 600,
+            causal_order: ["disk", "memory", "offline", "collector", "session", "agent"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+/// Stale-collector alerting, evaluated by the daemon every cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StalenessAlertConfig {
+    /// Raise alerts for stale (machine, collector) pairs
+    pub enabled: bool,
+
+    /// Seconds since the last successful run before a pair counts as stale
+    pub stale_threshold_secs: u64,
+
+    /// Per-collector overrides of `stale_threshold_secs`
+    pub collectors: HashMap<String, u64>,
+
+    /// Escalate to critical once a pair is this many thresholds stale
+    pub critical_multiplier: u32,
+
+    /// Seconds a resolved pair must stay fresh before it may fire again
+    pub min_resolved_secs: u64,
+}
+
+impl Default for StalenessAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stale_threshold_secs: 600,
+            collectors: HashMap::new(),
+            critical_multiplier: 6,
+            min_resolved_secs: 900,
+        }
+    }
+}
+
+impl StalenessAlertConfig {
+    /// Staleness threshold for `collector`, in seconds
+    #[must_use]
+    pub fn threshold_for(&self, collector: &str) -> u64 {
+        self.collectors
+            .get(collector)
+            .copied()
+            .unwrap_or(self.stale_threshold_secs)
     }
 }
 
@@ -533,7 +1302,20 @@ pub struct WebConfig {
 
     /// Allowed origins for CORS
     pub cors_origins: Vec<String>,
-}
+
+    /// Requests per minute each caller may make to `POST /api/query/template`
+    /// (0 disables the limit)
+    pub query_rate_limit_per_min: u32,
+
+    /// HMAC signatures required on inbound pushes
+    pub signing: RequestSigningConfig,
+
+    /// The `GET /api/events` stream
+    pub events: EventStreamConfig,
+
+    /// Single sign-on for people using the dashboard
+    pub oidc: OidcConfig,
+}
 
 impl Default for WebConfig {
     fn default() -> Self {
@@ -543,668 +1325,4444 @@ impl Default for WebConfig {
             port: 8080,
             cors_enabled: false,
             cors_origins: vec![],
+            query_rate_limit_per_min: 30,
+            signing: RequestSigningConfig::default(),
+            events: EventStreamConfig::default(),
+            oidc: OidcConfig::default(),
         }
     }
 }
 
-impl VcConfig {
-    /// Standard config file paths, in order of precedence
-    #[must_use]
-    pub fn config_paths() -> Vec<PathBuf> {
-        let mut paths = vec![
-            // 1. Current directory (project-local)
-            PathBuf::from("vc.toml"),
-        ];
+/// Server-sent events under `[web.events]`
+///
+/// The server polls the store for new alerts and health changes and fans
+/// them out to every `GET /api/events` client. Each client has its own
+/// queue; a client that falls behind loses heartbeats first, then all but
+/// the latest health change per machine, then its oldest events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventStreamConfig {
+    /// Events queued per client
+    pub queue_size: usize,
 
-        // 2. User config directory (~/.config/vc/vc.toml)
-        if let Some(config_dir) = dirs::config_dir() {
-            paths.push(config_dir.join("vc").join("vc.toml"));
+    /// How often the store is polled; a heartbeat goes out on each poll
+    pub poll_interval_secs: u64,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 256,
+            poll_interval_secs: 5,
         }
+    }
+}
 
-        // 3. System config
-        paths.push(PathBuf::from("/etc/vc/vc.toml"));
+/// Request signing under `[web.signing]`
+///
+/// When enabled, pushes to `POST /api/replication/batch` and
+/// `POST /api/annotations` must carry an HMAC-SHA256 signature of their
+/// timestamp and body, keyed by `secret`, and each signature is accepted
+/// once. `vc node sign` produces the headers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestSigningConfig {
+    /// Reject unsigned and badly signed pushes
+    pub enabled: bool,
 
-        paths
-    }
+    /// Shared secret, or a `secret://` reference to it
+    pub secret: Option<String>,
 
-    /// Discover and load configuration from standard paths.
-    ///
-    /// Returns defaults if no config file is found.
-    ///
-    /// # Errors
-    /// Returns a [`ConfigError`] if a discovered config file cannot be loaded.
-    pub fn discover() -> Result<Self, ConfigError> {
-        for path in Self::config_paths() {
-            if path.exists() {
-                info!(path = %path.display(), "Loading config from");
-                return Self::load(&path);
-            }
-        }
+    /// Largest accepted difference between a request's timestamp and the
+    /// server clock, either way (seconds)
+    pub max_skew_secs: u64,
 
-        info!("No config file found, using defaults");
-        Ok(Self::default())
-    }
+    /// Signatures remembered in memory for replay detection; the store is
+    /// checked for any not in memory
+    pub replay_cache_size: usize,
+}
 
-    /// Discover config and apply environment variable overrides.
-    ///
-    /// # Errors
-    /// Returns a [`ConfigError`] if config discovery or validation fails.
-    pub fn discover_with_env() -> Result<Self, ConfigError> {
-        let mut config = Self::discover()?;
-        config.apply_env_overrides();
-        config.expand_all_paths();
-        Ok(config)
+impl Default for RequestSigningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secret: None,
+            max_skew_secs: 300,
+            replay_cache_size: 10_000,
+        }
     }
+}
 
-    /// Load configuration from a specific TOML file.
-    ///
-    /// # Errors
-    /// Returns a [`ConfigError`] if the file cannot be read, parsed, or validated.
-    pub fn load(path: &Path) -> Result<Self, ConfigError> {
-        let content = std::fs::read_to_string(path)?;
-        let mut config: VcConfig = toml::from_str(&content)?;
-        config.expand_all_paths();
-        config.validate()?;
-        Ok(config)
-    }
+/// OpenID Connect login under `[web.oidc]`
+///
+/// `GET /auth/login` sends the browser to the provider; the callback maps the
+/// user's `groups_claim` through `role_map` to a role and sets a signed
+/// session cookie that the API accepts in place of a bearer token. API
+/// tokens keep working as before.
+///
+/// ```toml
+/// [web.oidc]
+/// enabled = true
+/// issuer = "https://sso.example.com/realms/ops"
+/// client_id = "vibe-cockpit"
+/// client_secret = "secret://env/VC_OIDC_CLIENT_SECRET"
+/// redirect_url = "https://cockpit.example.com/auth/callback"
+///
+/// [web.oidc.role_map]
+/// sre = "admin"
+/// oncall = "operator"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OidcConfig {
+    pub enabled: bool,
 
-    /// Load configuration with environment variable overrides.
-    ///
-    /// # Errors
-    /// Returns a [`ConfigError`] if the file cannot be read, parsed, or validated.
-    pub fn load_with_env(path: &Path) -> Result<Self, ConfigError> {
-        let mut config = Self::load(path)?;
-        config.apply_env_overrides();
-        Ok(config)
-    }
+    /// Issuer URL; its `/.well-known/openid-configuration` names the
+    /// endpoints
+    pub issuer: Option<String>,
 
-    /// Expand all paths in configuration (resolve `~/` to home directory)
-    pub fn expand_all_paths(&mut self) {
-        self.global.expand_paths();
+    pub client_id: Option<String>,
 
-        // Expand SSH key paths for all machines
-        for machine in self.machines.values_mut() {
-            if let Some(ref mut key_path) = machine.ssh_key {
-                *key_path = expand_path(key_path);
-            }
+    /// Client secret, or a `secret://` reference to it
+    pub client_secret: Option<String>,
+
+    /// Where the provider sends the browser back: this server's
+    /// `/auth/callback`
+    pub redirect_url: Option<String>,
+
+    /// Scopes requested besides `openid`
+    pub scopes: Vec<String>,
+
+    /// ID token claim holding the user's groups
+    pub groups_claim: String,
+
+    /// Group to role (`read`, `operator` or `admin`); a user in several
+    /// groups gets the highest of their roles
+    pub role_map: BTreeMap<String, String>,
+
+    /// Role of a user in none of the mapped groups; unset refuses them
+    pub default_role: Option<String>,
+
+    /// How long a login lasts
+    pub session_ttl_secs: u64,
+
+    /// Key the session cookie is signed with, or a `secret://` reference
+    /// to it. Unset picks a random key at startup, which logs everyone out
+    /// on restart.
+    pub session_secret: Option<String>,
+
+    /// Send the cookie over HTTPS only; turn off only for local testing
+    pub secure_cookie: bool,
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            issuer: None,
+            client_id: None,
+            client_secret: None,
+            redirect_url: None,
+            scopes: vec!["profile".to_string(), "email".to_string()],
+            groups_claim: "groups".to_string(),
+            role_map: BTreeMap::new(),
+            default_role: None,
+            session_ttl_secs: 8 * 3600,
+            session_secret: None,
+            secure_cookie: true,
         }
     }
+}
 
-    /// Apply environment variable overrides
-    fn apply_env_overrides(&mut self) {
-        if let Ok(val) = std::env::var("VC_DB_PATH") {
-            self.global.db_path = expand_path(&PathBuf::from(val));
+impl OidcConfig {
+    /// What keeps the login from working, keyed by config path; empty when
+    /// OIDC is off. `vc config lint` reports these and `vc web` refuses to
+    /// start with any.
+    #[must_use]
+    pub fn problems(&self) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if !self.enabled {
+            return problems;
         }
-        if let Ok(val) = std::env::var("VC_LOG_LEVEL") {
-            self.global.log_level = val;
+        let mut url = |key: &str, value: &Option<String>| match value.as_deref() {
+            None | Some("") => problems.push((
+                format!("web.oidc.{key}"),
+                format!("web.oidc.enabled requires web.oidc.{key}"),
+            )),
+            Some(value) => {
+                if let Err(reason) = check_oidc_url(value) {
+                    problems.push((format!("web.oidc.{key}"), format!("'{value}' {reason}")));
+                }
+            }
+        };
+        url("issuer", &self.issuer);
+        url("redirect_url", &self.redirect_url);
+        for (key, value) in [
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+        ] {
+            if value.as_deref().is_none_or(str::is_empty) {
+                problems.push((
+                    format!("web.oidc.{key}"),
+                    format!("web.oidc.enabled requires web.oidc.{key}"),
+                ));
+            }
         }
-        if let Ok(val) = std::env::var("VC_POLL_INTERVAL")
-            && let Ok(secs) = val.parse()
-        {
-            self.global.poll_interval_secs = secs;
+        for (group, role) in &self.role_map {
+            if !VALID_WEB_ROLES.contains(&role.as_str()) {
+                problems.push((
+                    format!("web.oidc.role_map.{group}"),
+                    format!(
+                        "Unknown role '{role}'. Must be one of: {}",
+                        VALID_WEB_ROLES.join(", ")
+                    ),
+                ));
+            }
         }
-        if let Ok(val) = std::env::var("VC_WEB_PORT")
-            && let Ok(port) = val.parse()
+        if let Some(role) = &self.default_role
+            && !VALID_WEB_ROLES.contains(&role.as_str())
         {
-            self.web.port = port;
+            problems.push((
+                "web.oidc.default_role".to_string(),
+                format!(
+                    "Unknown role '{role}'. Must be one of: {}",
+                    VALID_WEB_ROLES.join(", ")
+                ),
+            ));
         }
-        if let Ok(val) = std::env::var("VC_WEB_BIND") {
-            self.web.bind_address = val;
+        if self.session_ttl_secs == 0 {
+            problems.push((
+                "web.oidc.session_ttl_secs".to_string(),
+                "web.oidc.session_ttl_secs must be > 0".to_string(),
+            ));
         }
+        problems
     }
+}
 
-    /// Validate configuration.
-    ///
-    /// # Errors
-    /// Returns a [`ConfigError`] when validation rules are violated.
-    pub fn validate(&self) -> Result<(), ConfigError> {
-        // Validate poll interval
-        if self.global.poll_interval_secs == 0 {
-            return Err(ConfigError::ValidationError(
-                "poll_interval_secs must be > 0".to_string(),
-            ));
+/// Roles a web caller can hold
+const VALID_WEB_ROLES: &[&str] = &["read", "operator", "admin"];
+
+/// An issuer or redirect URL must be absolute HTTPS with a host and no query
+/// or fragment; plain HTTP is allowed for localhost only
+fn check_oidc_url(url: &str) -> Result<(), &'static str> {
+    let rest = if let Some(rest) = url.strip_prefix("https://") {
+        rest
+    } else if let Some(rest) = url.strip_prefix("http://") {
+        let host = rest.split(['/', ':']).next().unwrap_or_default();
+        if !matches!(host, "localhost" | "127.0.0.1" | "[::1]") {
+            return Err("must use https (http is allowed for localhost only)");
         }
+        rest
+    } else {
+        return Err("is not an http(s) URL");
+    };
+    let host = rest.split('/').next().unwrap_or_default();
+    if host.is_empty() || host.starts_with(':') {
+        return Err("has no host");
+    }
+    if url.contains(['?', '#']) || url.chars().any(char::is_whitespace) {
+        return Err("must not contain a query, fragment or whitespace");
+    }
+    Ok(())
+}
 
-        // Validate collector timeout
-        if self.collectors.timeout_secs == 0 {
-            return Err(ConfigError::ValidationError(
-                "collector timeout_secs must be > 0".to_string(),
-            ));
-        }
+/// Daemon configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Unix socket the daemon serves its watch event stream on, so local
+    /// agents can `vc watch --connect` instead of each polling the store
+    pub watch_socket: Option<PathBuf>,
 
-        if self.collectors.max_concurrent_collectors == 0 {
-            return Err(ConfigError::ValidationError(
-                "collector max_concurrent_collectors must be > 0".to_string(),
-            ));
-        }
+    /// Events queued per watch subscriber before further events are dropped
+    pub watch_queue_size: usize,
 
-        if self.collectors.max_concurrent_per_machine == 0 {
-            return Err(ConfigError::ValidationError(
-                "collector max_concurrent_per_machine must be > 0".to_string(),
-            ));
-        }
+    /// How long the daemon's store lease outlives its last heartbeat. A
+    /// daemon that stops heartbeating for this long is treated as gone and
+    /// another may take the store over.
+    pub lease_ttl_secs: u64,
 
-        // Validate log level
-        if !VALID_LOG_LEVELS.contains(&self.global.log_level.to_lowercase().as_str()) {
-            return Err(ConfigError::ValidationError(format!(
-                "Invalid log_level '{}'. Must be one of: {}",
-                self.global.log_level,
-                VALID_LOG_LEVELS.join(", ")
-            )));
-        }
+    /// Resource self-limits for the daemon process
+    pub limits: DaemonLimitsConfig,
+}
 
-        // Validate autopilot thresholds
-        if self.autopilot.min_confidence < 0.0 || self.autopilot.min_confidence > 1.0 {
-            return Err(ConfigError::ValidationError(
-                "autopilot.min_confidence must be between 0.0 and 1.0".to_string(),
-            ));
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            watch_socket: None,
+            watch_queue_size: 256,
+            lease_ttl_secs: 300,
+            limits: DaemonLimitsConfig::default(),
         }
+    }
+}
 
-        // Validate web port
-        if self.web.port == 0 {
-            return Err(ConfigError::ValidationError(
-                "web.port must be > 0".to_string(),
-            ));
-        }
+/// Resource self-limits checked on every daemon cycle.
+///
+/// Both limits are off unless set. When resident memory exceeds
+/// `max_rss_mb` the daemon sheds load one step per cycle (pause transcript
+/// collectors, then stretch poll intervals, then refuse ingest) and steps
+/// back down once memory falls under `recovery_ratio * max_rss_mb`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonLimitsConfig {
+    /// Maximum resident set size of the daemon in MiB
+    pub max_rss_mb: Option<u64>,
 
-        // Validate machine configurations
-        for (id, machine) in &self.machines {
-            if machine.ssh_host.is_some() && machine.ssh_user.is_none() {
-                return Err(ConfigError::ValidationError(format!(
-                    "Machine '{id}' has ssh_host but missing ssh_user"
-                )));
-            }
-        }
+    /// Minimum free space on the store volume in MiB; below this, all writes
+    /// except alerts are paused
+    pub min_free_disk_mb: Option<u64>,
 
-        Ok(())
-    }
+    /// Multiplier applied to the poll interval once polling is stretched
+    pub poll_stretch_factor: u32,
 
-    /// Get poll interval as Duration
-    #[must_use]
-    pub fn poll_interval(&self) -> Duration {
-        Duration::from_secs(self.global.poll_interval_secs)
-    }
+    /// Fraction of `max_rss_mb` memory must drop below before load is restored
+    pub recovery_ratio: f64,
+}
 
-    /// Get collector timeout as Duration
-    #[must_use]
-    pub fn collector_timeout(&self) -> Duration {
-        Duration::from_secs(self.collectors.timeout_secs)
+impl Default for DaemonLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_rss_mb: None,
+            min_free_disk_mb: None,
+            poll_stretch_factor: 4,
+            recovery_ratio: 0.9,
+        }
     }
+}
 
-    /// Check if a machine is local (no SSH required)
-    #[must_use]
-    pub fn is_local_machine(&self, machine_id: &str) -> bool {
-        self.machines
-            .get(machine_id)
-            .is_none_or(|m| m.ssh_host.is_none())
-    }
+/// Audit log write policy for high-frequency event types.
+///
+/// Event types not listed in `events` are written one row per event.
+/// Sensitive events (guardian approvals, autopilot actions, user commands)
+/// cannot be listed: they are always written individually and synchronously.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Sampling or rollup per event type (e.g. `collector_run`)
+    pub events: HashMap<String, AuditSampling>,
 
-    /// Get enabled machines
-    pub fn enabled_machines(&self) -> impl Iterator<Item = (&String, &MachineConfig)> {
-        self.machines.iter().filter(|(_, m)| m.enabled)
+    /// Buffered events written per batch
+    pub batch_size: usize,
+
+    /// Length of one rollup window in seconds
+    pub rollup_window_secs: u64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            events: HashMap::from([("collector_run".to_string(), AuditSampling::Rollup)]),
+            batch_size: 100,
+            rollup_window_secs: 3600,
+        }
     }
+}
 
-    /// Check if a collector is enabled for a specific machine.
-    ///
-    /// `collector_name` is the name the collector registers itself under in the
-    /// `vc_collect` registry (e.g. `fallback_probe`, `beads`, `github`). Every
-    /// registered collector must have an arm here: a missing arm silently
-    /// disables the collector forever, which is how `fallback_probe` — the
-    /// always-on baseline — never ran.
-    ///
-    /// The legacy config key `bv_br` is accepted as an alias for `beads`.
-    #[must_use]
-    pub fn is_collector_enabled(&self, machine_id: &str, collector_name: &str) -> bool {
-        // Check machine-specific override first
-        if let Some(machine) = self.machines.get(machine_id)
-            && let Some(&enabled) = machine.collectors.get(collector_name)
-        {
-            return enabled;
+/// Provider CLI commands run by the `accounts` collector.
+///
+/// Each command should print the accounts it knows about with their
+/// remaining quota, reset time and which one is active. Output is parsed
+/// leniently; whatever cannot be parsed is stored raw with a parse error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountsConfig {
+    /// Commands to run, in order
+    pub commands: Vec<AccountCommandConfig>,
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            commands: vec![AccountCommandConfig {
+                command: "caam limits --format json".to_string(),
+                provider: None,
+                central: false,
+            }],
         }
+    }
+}
 
-        // Fall back to global collector config
-        match collector_name {
-            "fallback_probe" => self.collectors.fallback_probe,
-            "sysmoni" => self.collectors.sysmoni,
-            "ru" => self.collectors.ru,
-            "caut" => self.collectors.caut,
-            "caam" => self.collectors.caam,
-            "cass" => self.collectors.cass,
-            "mcp_agent_mail" => self.collectors.mcp_agent_mail,
-            "ntm" => self.collectors.ntm,
-            "rch" => self.collectors.rch,
-            "rano" => self.collectors.rano,
-            "dcg" => self.collectors.dcg,
-            "pt" => self.collectors.pt,
-            // Registry name is `beads`; `bv_br` is the legacy config key.
-            "beads" | "bv_br" => self.collectors.bv_br,
-            "afsc" => self.collectors.afsc,
-            "github" => self.collectors.github,
-            "cloud_benchmarker" => self.collectors.cloud_benchmarker,
-            _ => false, // Unknown collectors are disabled
+/// One provider status command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountCommandConfig {
+    /// Shell command printing account state (JSON preferred)
+    pub command: String,
+
+    /// Provider recorded when the output does not name one
+    #[serde(default)]
+    pub provider: Option<String>,
+
+    /// Run once on the local machine instead of on every machine, for
+    /// accounts shared across the fleet
+    #[serde(default)]
+    pub central: bool,
+}
+
+/// Agent processes the `agent_inventory` collector looks for.
+///
+/// A process is an agent of the first pattern its command line contains
+/// (case-insensitively). Agents inside tmux panes are attributed to the
+/// pane; the rest are inventoried as plain processes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentInventoryConfig {
+    /// Patterns to match, in order
+    pub patterns: Vec<AgentPatternConfig>,
+}
+
+impl Default for AgentInventoryConfig {
+    fn default() -> Self {
+        let pattern = |agent_type: &str, pattern: &str| AgentPatternConfig {
+            agent_type: agent_type.to_string(),
+            pattern: pattern.to_string(),
+        };
+        Self {
+            patterns: vec![
+                pattern("claude", "claude"),
+                pattern("codex", "codex"),
+                pattern("gemini", "gemini"),
+                pattern("aider", "aider"),
+            ],
         }
     }
+}
 
-    /// Lint the configuration and return all issues found.
-    ///
-    /// This performs more extensive checks than `validate()`, including:
-    /// - Validation errors (also caught by `validate()`)
-    /// - Warnings about suboptimal configurations
-    /// - Suggestions for improvements
+/// One agent type and the text that identifies its processes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentPatternConfig {
+    /// Agent type recorded for matches, e.g. `claude`
+    pub agent_type: String,
+
+    /// Substring of the process command line
+    pub pattern: String,
+}
+
+/// Knowledge base settings
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct KnowledgeConfig {
+    /// Embedding backend for semantic search
+    pub embeddings: EmbeddingsConfig,
+
+    /// How finished agent sessions are classified by outcome
+    pub outcomes: SessionOutcomeConfig,
+}
+
+/// Session outcome classification.
+///
+/// `default` applies to every agent type without an entry in `agents`
+/// (keyed by the session's `program`, e.g. `claude-code`). Fields an agent
+/// entry leaves out take the built-in defaults, not those of `default`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SessionOutcomeConfig {
+    pub default: OutcomeHeuristics,
+    pub agents: HashMap<String, OutcomeHeuristics>,
+}
+
+impl SessionOutcomeConfig {
+    /// Heuristics for sessions of `program`
     #[must_use]
-    #[allow(clippy::too_many_lines)]
-    pub fn lint(&self) -> LintResult {
-        let mut result = LintResult::new();
+    pub fn heuristics_for(&self, program: Option<&str>) -> &OutcomeHeuristics {
+        program
+            .and_then(|program| self.agents.get(program))
+            .unwrap_or(&self.default)
+    }
+}
 
-        // === ERRORS ===
+/// Thresholds and phrases one agent type's sessions are classified with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutcomeHeuristics {
+    /// Share of tool calls that errored at which a session counts as failed
+    pub failed_error_ratio: f64,
 
-        // Poll interval must be positive
-        if self.global.poll_interval_secs == 0 {
-            result.add(
-                LintIssue::error(
-                    "global.poll_interval_secs",
-                    "Poll interval must be greater than 0",
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Set a reasonable poll interval (e.g., 120 seconds)".to_string(),
-                    path: "global.poll_interval_secs".to_string(),
-                    suggested_value: Some("120".to_string()),
-                }),
-            );
-        }
+    /// Share of tool calls that errored at which a session is at best partial
+    pub partial_error_ratio: f64,
 
-        // Collector timeout must be positive
-        if self.collectors.timeout_secs == 0 {
-            result.add(
-                LintIssue::error(
-                    "collectors.timeout_secs",
-                    "Collector timeout must be greater than 0",
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Set a reasonable timeout (e.g., 30 seconds)".to_string(),
-                    path: "collectors.timeout_secs".to_string(),
-                    suggested_value: Some("30".to_string()),
-                }),
-            );
-        }
+    /// Sessions with this many turns or fewer and no sign of success were
+    /// abandoned
+    pub abandoned_max_turns: u32,
 
-        if self.collectors.max_concurrent_collectors == 0 {
-            result.add(
-                LintIssue::error(
-                    "collectors.max_concurrent_collectors",
-                    "Global collector concurrency must be greater than 0",
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Allow at least one collector to run".to_string(),
-                    path: "collectors.max_concurrent_collectors".to_string(),
-                    suggested_value: Some("8".to_string()),
-                }),
-            );
-        }
+    /// Messages at the end of the transcript searched for the phrases below
+    pub tail_messages: usize,
 
-        if self.collectors.max_concurrent_per_machine == 0 {
-            result.add(
-                LintIssue::error(
-                    "collectors.max_concurrent_per_machine",
-                    "Per-machine collector concurrency must be greater than 0",
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Allow at least one concurrent collector per machine".to_string(),
-                    path: "collectors.max_concurrent_per_machine".to_string(),
-                    suggested_value: Some("4".to_string()),
-                }),
-            );
-        }
+    /// Phrases (case-insensitive) in the ending that mark success
+    pub success_phrases: Vec<String>,
 
-        // Validate log level
-        if !VALID_LOG_LEVELS.contains(&self.global.log_level.to_lowercase().as_str()) {
-            result.add(
-                LintIssue::error(
-                    "global.log_level",
-                    format!(
-                        "Invalid log level '{}'. Must be one of: {}",
-                        self.global.log_level,
-                        VALID_LOG_LEVELS.join(", ")
-                    ),
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Use a valid log level".to_string(),
-                    path: "global.log_level".to_string(),
-                    suggested_value: Some("\"info\"".to_string()),
-                }),
-            );
-        }
+    /// Phrases (case-insensitive) in the ending that mark failure
+    pub failure_phrases: Vec<String>,
+}
 
-        // Autopilot confidence must be in [0, 1]
-        if self.autopilot.min_confidence < 0.0 || self.autopilot.min_confidence > 1.0 {
-            result.add(
-                LintIssue::error(
-                    "autopilot.min_confidence",
-                    "Min confidence must be between 0.0 and 1.0",
-                )
-                .with_suggestion(LintSuggestion {
-                    description: "Set confidence to a value between 0 and 1".to_string(),
-                    path: "autopilot.min_confidence".to_string(),
-                    suggested_value: Some("0.8".to_string()),
-                }),
-            );
+impl Default for OutcomeHeuristics {
+    fn default() -> Self {
+        Self {
+            failed_error_ratio: 0.5,
+            partial_error_ratio: 0.2,
+            abandoned_max_turns: 2,
+            tail_messages: 3,
+            success_phrases: [
+                "all tests pass",
+                "tests pass",
+                "successfully",
+                "is complete",
+                "completed",
+                "fixed",
+                "done",
+            ]
+            .map(String::from)
+            .to_vec(),
+            failure_phrases: [
+                "unable to",
+                "i couldn't",
+                "i could not",
+                "still failing",
+                "giving up",
+                "blocked on",
+            ]
+            .map(String::from)
+            .to_vec(),
         }
+    }
+}
 
-        // Web port must be positive
-        if self.web.port == 0 {
-            result.add(
-                LintIssue::error("web.port", "Web port must be greater than 0").with_suggestion(
-                    LintSuggestion {
-                        description: "Set a valid port number".to_string(),
-                        path: "web.port".to_string(),
-                        suggested_value: Some("8080".to_string()),
-                    },
-                ),
-            );
-        }
+/// Embedding backend for knowledge base semantic search.
+///
+/// Only used by builds with the `embeddings` feature. Exactly one of
+/// `command` (reads text on stdin, prints a JSON array of floats) or
+/// `endpoint` (an OpenAI-compatible `/v1/embeddings` URL) must be set when
+/// enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingsConfig {
+    /// Compute and store embeddings for knowledge entries
+    pub enabled: bool,
 
-        // Machine SSH validation
-        for (id, machine) in &self.machines {
-            let path_prefix = format!("machines.{id}");
+    /// Local command that embeds the text on stdin
+    pub command: Option<String>,
 
-            // SSH host without user
-            if machine.ssh_host.is_some() && machine.ssh_user.is_none() {
-                result.add(
-                    LintIssue::error(
-                        format!("{path_prefix}.ssh_user"),
-                        format!("Machine '{id}' has ssh_host but missing ssh_user"),
-                    )
-                    .with_suggestion(LintSuggestion {
-                        description: "Add SSH user for remote machine".to_string(),
-                        path: format!("{path_prefix}.ssh_user"),
-                        suggested_value: Some("\"ubuntu\"".to_string()),
-                    }),
-                );
-            }
+    /// HTTP embeddings endpoint
+    pub endpoint: Option<String>,
 
-            // SSH key path doesn't exist
-            if let Some(ref key_path) = machine.ssh_key {
-                let expanded = expand_path(key_path);
-                if !expanded.exists() {
-                    result.add(LintIssue::error(
-                        format!("{path_prefix}.ssh_key"),
-                        format!("SSH key file does not exist: {}", expanded.display()),
-                    ));
-                }
-            }
-        }
+    /// Model name sent to the endpoint and recorded with each vector
+    pub model: String,
 
-        // === WARNINGS ===
+    /// Weight of cosine similarity in semantic ranking (0.0-1.0); the rest
+    /// goes to the keyword score
+    pub semantic_weight: f64,
 
-        // Very short poll interval
-        if self.global.poll_interval_secs > 0 && self.global.poll_interval_secs < 30 {
-            result.add(LintIssue::warning(
-                "global.poll_interval_secs",
-                format!(
-                    "Poll interval of {} seconds is very short and may cause high load",
-                    self.global.poll_interval_secs
-                ),
-            ));
-        }
+    /// Timeout for one embedding request in seconds
+    pub timeout_secs: u64,
+}
 
-        // Very long poll interval
-        if self.global.poll_interval_secs > 600 {
-            result.add(LintIssue::warning(
-                "global.poll_interval_secs",
-                format!(
-                    "Poll interval of {} seconds is long; data may become stale",
-                    self.global.poll_interval_secs
-                ),
-            ));
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: None,
+            endpoint: None,
+            model: "default".to_string(),
+            semantic_weight: 0.7,
+            timeout_secs: 10,
         }
+    }
+}
 
-        // Autopilot enabled with low confidence
-        if self.autopilot.enabled && self.autopilot.min_confidence < 0.5 {
-            result.add(LintIssue::warning(
-                "autopilot.min_confidence",
-                "Autopilot enabled with low confidence threshold; may take risky actions",
-            ));
-        }
+/// OTLP export of operational events.
+///
+/// Only used by builds with the `telemetry` feature. The daemon sends alert
+/// firings and resolutions, incident lifecycle events and collector failures
+/// as OTLP/HTTP JSON log records to `otlp_endpoint` (e.g.
+/// `http://collector:4318/v1/logs`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Export events from the daemon
+    pub enabled: bool,
 
-        // Autopilot enabled but account switching disabled
-        if self.autopilot.enabled && !self.autopilot.auto_switch_accounts {
-            result.add(LintIssue::info(
-                "autopilot.auto_switch_accounts",
-                "Autopilot is enabled but account switching is disabled",
-            ));
+    /// OTLP/HTTP logs endpoint
+    pub otlp_endpoint: Option<String>,
+
+    /// Extra request headers (e.g. an API key for the collector); values
+    /// may be `secret://` references
+    pub headers: HashMap<String, String>,
+
+    /// `service.name` resource attribute
+    pub service_name: String,
+
+    /// Most log records per export request
+    pub batch_size: usize,
+
+    /// Retries per batch within one daemon tick; unexported events are
+    /// retried again on the next tick
+    pub max_retries: u32,
+
+    /// Timeout for one export request in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            otlp_endpoint: None,
+            headers: HashMap::new(),
+            service_name: "vibe_cockpit".to_string(),
+            batch_size: 200,
+            max_retries: 3,
+            timeout_secs: 10,
         }
+    }
+}
 
-        // Web enabled without CORS in production
-        if self.web.enabled && !self.web.cors_enabled && self.web.bind_address != "127.0.0.1" {
-            result.add(LintIssue::warning(
-                "web.cors_enabled",
-                "Web dashboard bound to non-localhost without CORS configuration",
-            ));
+/// Role of this cockpit in warm standby replication
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicationMode {
+    /// Collects locally and ships rows to `standby_url` when set
+    #[default]
+    Primary,
+    /// Applies rows shipped by the primary and refuses local collector
+    /// writes until `vc db promote`
+    Standby,
+}
+
+impl ReplicationMode {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Primary => "primary",
+            Self::Standby => "standby",
         }
+    }
+}
+
+/// Warm standby replication settings
+///
+/// A primary daemon with `standby_url` set ships new rows every
+/// `interval_secs` to the standby's `POST /api/replication/batch` (served by
+/// `vc web`). Failover is manual: promote the standby with `vc db promote`
+/// and point clients at it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Whether this cockpit is the primary or the standby
+    pub mode: ReplicationMode,
+
+    /// Base URL of the standby's web server (primary only)
+    pub standby_url: Option<String>,
+
+    /// Bearer token presented to the standby, or a `secret://` reference to
+    /// it; needs the operator role
+    pub token: Option<String>,
+
+    /// Secret to sign shipments with, matching the standby's
+    /// `web.signing.secret` (or a `secret://` reference to it)
+    pub signing_secret: Option<String>,
+
+    /// Seconds between shipments
+    pub interval_secs: u64,
+
+    /// Most rows per table in one shipment
+    pub batch_size: usize,
+
+    /// Timeout for one shipment request in seconds
+    pub timeout_secs: u64,
+
+    /// Tables to replicate; empty means every table with a timestamp column
+    pub tables: Vec<String>,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            mode: ReplicationMode::Primary,
+            standby_url: None,
+            token: None,
+            signing_secret: None,
+            interval_secs: 15,
+            batch_size: 1000,
+            timeout_secs: 30,
+            tables: Vec::new(),
+        }
+    }
+}
+
+/// Ingest settings for bundles pushed by vc-node agents
+///
+/// Besides skipping batches already ingested, ingest hashes every row (its
+/// canonical fields plus source machine and timestamp) and skips rows whose
+/// hash was seen within `dedup_lookback_hours`, so an agent that resends
+/// after a crash under a fresh bundle ID does not duplicate data. `vc vacuum`
+/// prunes hashes older than the lookback.
+///
+/// Probe and ingest also measure each machine's clock against the hub; a
+/// skew beyond `clock_skew_warn_secs` (after allowing for the measurement's
+/// round trip) raises a warning alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestConfig {
+    /// How far back row hashes are checked; 0 disables row-level dedup
+    pub dedup_lookback_hours: u64,
+
+    /// Clock skew that raises a warning alert; 0 disables the alert
+    pub clock_skew_warn_secs: u64,
+}
+
+impl Default for IngestConfig {
+    fn default() -> Self {
+        Self {
+            dedup_lookback_hours: 48,
+            clock_skew_warn_secs: 60,
+        }
+    }
+}
+
+/// Query observability inside the store.
+///
+/// Each query's fingerprint (its SQL with literals stripped), caller, duration
+/// and row count are buffered in memory and written to the `query_log` table
+/// every `flush_interval_secs` or `buffer_size` queries, whichever comes
+/// first. The table keeps the newest `max_rows` entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryLogConfig {
+    /// Record queries at all
+    pub enabled: bool,
+
+    /// Rows kept in the `query_log` table; older ones are dropped on flush
+    pub max_rows: usize,
+
+    /// Queries buffered before a flush is forced
+    pub buffer_size: usize,
+
+    /// Longest a recorded query waits in the buffer
+    pub flush_interval_secs: u64,
+}
+
+impl Default for QueryLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_rows: 50_000,
+            buffer_size: 500,
+            flush_interval_secs: 30,
+        }
+    }
+}
+
+/// Write-time size guard under `[write_limits]`.
+///
+/// The store checks the length of every value it writes: strings against
+/// `max_text_bytes`, objects and arrays (stored as JSON text) against
+/// `max_json_bytes`. With `policy = "truncate"` an oversized string keeps its
+/// head and a marker, and an oversized JSON value becomes a small marker
+/// object; with `"reject"` the row is dropped and the rest of its batch is
+/// still written. Every occurrence is recorded for `/metrics` and
+/// `vc health collectors --oversized`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WriteLimitsConfig {
+    /// Check writes at all
+    pub enabled: bool,
+
+    /// Longest string value written
+    pub max_text_bytes: usize,
+
+    /// Longest object or array value written, as JSON text
+    pub max_json_bytes: usize,
+
+    /// What happens to an oversized value
+    pub policy: OversizePolicy,
+}
+
+impl Default for WriteLimitsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_text_bytes: 4 * 1024 * 1024,
+            max_json_bytes: 16 * 1024 * 1024,
+            policy: OversizePolicy::Truncate,
+        }
+    }
+}
+
+/// Handling of a value over `[write_limits]`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OversizePolicy {
+    /// Keep a head of the value and a marker saying how much was cut
+    #[default]
+    Truncate,
+    /// Drop the whole row
+    Reject,
+}
+
+impl OversizePolicy {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Truncate => "truncate",
+            Self::Reject => "reject",
+        }
+    }
+}
+
+/// Guardrails on queries, whoever runs them.
+///
+/// Agent queries (MCP, natural language) get the shorter
+/// `agent_max_execution_ms`; operator queries get `max_execution_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryConfig {
+    /// Most rows a query returns
+    pub max_rows: usize,
+
+    /// Deadline for operator queries (CLI, web) in milliseconds
+    pub max_execution_ms: u64,
+
+    /// Deadline for agent queries (MCP, NL) in milliseconds
+    pub agent_max_execution_ms: u64,
+
+    /// Largest result, in bytes
+    pub max_output_bytes: usize,
+
+    /// Allow raw SQL; when false only the named templates run
+    pub allow_raw_sql: bool,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self {
+            max_rows: 10_000,
+            max_execution_ms: 30_000,
+            agent_max_execution_ms: 5_000,
+            max_output_bytes: 10 * 1024 * 1024,
+            allow_raw_sql: true,
+        }
+    }
+}
+
+/// Approval queue for raw queries proposed by agents (`vc_propose_query`).
+///
+/// A proposal nobody approves or denies within `ttl_secs` expires; the agent
+/// has to propose it again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryApprovalConfig {
+    /// How long a proposal waits for an operator
+    pub ttl_secs: u64,
+}
+
+impl Default for QueryApprovalConfig {
+    fn default() -> Self {
+        Self { ttl_secs: 86_400 }
+    }
+}
+
+/// MCP tool visibility per role, and how much one tool call may return.
+///
+/// A session runs as `vc mcp serve --role`, else `default_role`. A role
+/// only lists and calls the tools in its `tools`. A session without a role
+/// gets every tool, unless `deny_by_default` is set, in which case it gets
+/// none.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpConfig {
+    /// Role for sessions started without `--role`
+    pub default_role: Option<String>,
+
+    /// Sessions without a role see no tools instead of all of them
+    pub deny_by_default: bool,
+
+    /// Tool sets by role name (`[mcp.roles.<name>]`)
+    pub roles: HashMap<String, McpRoleConfig>,
+
+    /// Largest tool result, in bytes of response text; rows past it are
+    /// dropped and the result is marked `truncated`
+    pub max_response_bytes: usize,
+}
+
+impl Default for McpConfig {
+    fn default() -> Self {
+        Self {
+            default_role: None,
+            deny_by_default: false,
+            roles: HashMap::new(),
+            max_response_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// The tools one MCP role may see and call
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpRoleConfig {
+    /// Tool names from [`MCP_TOOLS`]
+    pub tools: Vec<String>,
+}
+
+impl McpConfig {
+    /// Tools a session may use as `role` (else `default_role`); `None`
+    /// means all of them
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ValidationError`] if the role is not defined.
+    pub fn allowed_tools(&self, role: Option<&str>) -> Result<Option<Vec<String>>, ConfigError> {
+        match role.or(self.default_role.as_deref()) {
+            Some(name) => self
+                .roles
+                .get(name)
+                .map(|role| Some(role.tools.clone()))
+                .ok_or_else(|| {
+                    ConfigError::ValidationError(format!(
+                        "Unknown MCP role '{name}'. Defined roles: {}",
+                        self.role_names().join(", ")
+                    ))
+                }),
+            None if self.deny_by_default => Ok(Some(Vec::new())),
+            None => Ok(None),
+        }
+    }
+
+    /// Defined role names, sorted
+    #[must_use]
+    pub fn role_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.roles.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// `(role, tool)` for every tool name that is not in [`MCP_TOOLS`], sorted
+    fn unknown_tools(&self) -> Vec<(&str, &str)> {
+        let mut unknown: Vec<(&str, &str)> = self
+            .roles
+            .iter()
+            .flat_map(|(name, role)| {
+                role.tools
+                    .iter()
+                    .map(move |tool| (name.as_str(), tool.as_str()))
+            })
+            .filter(|(_, tool)| !MCP_TOOLS.contains(tool))
+            .collect();
+        unknown.sort_unstable();
+        unknown
+    }
+}
+
+/// Automatic `ANALYZE` by the daemon.
+///
+/// The store counts rows written to each table since its last `ANALYZE`.
+/// A table is due once those changes reach `change_ratio` of its rows (and
+/// at least `min_changes`). Due tables are analyzed one at a time between
+/// collection ticks, only inside the quiet window and only while the daemon
+/// is not shedding load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyzeConfig {
+    /// Let the daemon run `ANALYZE` on its own (`vc db analyze` works either way)
+    pub enabled: bool,
+
+    /// Changed rows, as a fraction of the table's rows, that make it due
+    pub change_ratio: f64,
+
+    /// Changes below this never make a table due
+    pub min_changes: u64,
+
+    /// UTC hour the quiet window opens (inclusive)
+    pub quiet_start_hour: u32,
+
+    /// UTC hour the quiet window closes (exclusive); equal to the start means
+    /// any hour
+    pub quiet_end_hour: u32,
+
+    /// Tables analyzed per daemon tick at most
+    pub max_tables_per_tick: usize,
+}
+
+impl Default for AnalyzeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            change_ratio: 0.2,
+            min_changes: 1_000,
+            quiet_start_hour: 2,
+            quiet_end_hour: 5,
+            max_tables_per_tick: 2,
+        }
+    }
+}
+
+impl AnalyzeConfig {
+    /// Whether `hour` (0-23, UTC) falls inside the quiet window, which may
+    /// wrap past midnight
+    #[must_use]
+    pub fn in_quiet_window(&self, hour: u32) -> bool {
+        let (start, end) = (self.quiet_start_hour, self.quiet_end_hour);
+        if start == end {
+            true
+        } else if start < end {
+            (start..end).contains(&hour)
+        } else {
+            hour >= start || hour < end
+        }
+    }
+}
+
+/// Machine maintenance windows.
+///
+/// A machine in maintenance is skipped by collectors and left out of the
+/// fleet aggregate, so one forgotten in that state silently drops out of
+/// monitoring; `vc status` warns about windows open longer than `max_hours`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// Longest a machine may stay in maintenance before `vc status` warns
+    /// (0 = never warn)
+    pub max_hours: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self { max_hours: 72 }
+    }
+}
+
+/// Machine identity across renames.
+///
+/// Every machine gets a UUID when it is registered, and a probe leaves it
+/// in a marker file on the machine. When a probe of one machine id finds
+/// the UUID of another registered machine (the host was re-added under a
+/// new id), `vc machines probe` reports the conflict and suggests `vc
+/// machines merge`; with `auto_rename` it merges the old id's history into
+/// the new one itself.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdentityConfig {
+    /// Merge a re-added machine's old id into its new one during probe
+    pub auto_rename: bool,
+}
+
+/// Component health under `[health_components]`
+///
+/// `vc doctor` and `GET /api/health/components` judge the store, daemon,
+/// ingest, notification and replication components the same way. The
+/// overall status is the worst component's, after each status is scaled by
+/// the component's weight (default 1.0): a weight of 0.5 turns a failure
+/// into a degradation, and 0 or an entry in `ignore` keeps the component
+/// out of the overall status while still reporting it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthComponentsConfig {
+    /// Seconds the web endpoint reuses a computed report
+    pub cache_ttl_secs: u64,
+
+    /// Components left out of the overall status
+    pub ignore: Vec<String>,
+
+    /// Weight per component name
+    pub weights: HashMap<String, f64>,
+
+    /// Failed ingest batches awaiting retry at which ingest counts as failed
+    /// (any backlog is a degradation)
+    pub ingest_backlog_max: i64,
+
+    /// Seconds without a successful alert delivery, while deliveries fail,
+    /// after which notifications count as failed
+    pub notification_silence_secs: u64,
+
+    /// Replication lag in seconds that degrades the replication component
+    pub replication_lag_warn_secs: i64,
+
+    /// Replication lag in seconds that fails it
+    pub replication_lag_fail_secs: i64,
+}
+
+impl Default for HealthComponentsConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_secs: 10,
+            ignore: Vec::new(),
+            weights: HashMap::new(),
+            ingest_backlog_max: 100,
+            notification_silence_secs: 86_400,
+            replication_lag_warn_secs: 300,
+            replication_lag_fail_secs: 3600,
+        }
+    }
+}
+
+/// Guardrails on fleet-wide commands under `[fleet_run]`
+///
+/// `vc fleet run` and `POST /api/fleet/run` run one shell command on every
+/// selected machine. Output past `max_output_bytes` per stream and machine is
+/// dropped before it is shown or stored. Web callers below admin may only run
+/// commands that a pattern in `allow` matches in full and none in `deny`
+/// does, and never ones chaining commands, so an empty `allow` keeps the
+/// endpoint admin-only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FleetRunConfig {
+    /// Regexes a non-admin web caller's whole command must match one of
+    pub allow: Vec<String>,
+
+    /// Regexes for whole commands refused for non-admin web callers even
+    /// when allowed
+    pub deny: Vec<String>,
+
+    /// Bytes of stdout, and of stderr, kept per machine
+    pub max_output_bytes: usize,
+
+    /// Targets above which the CLI asks before running (or needs `--yes`)
+    pub confirm_above: usize,
+}
+
+impl Default for FleetRunConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            max_output_bytes: 65_536,
+            confirm_above: 10,
+        }
+    }
+}
+
+/// Shell syntax that runs a second command; never allowed below admin,
+/// since the command goes through `sh -c`
+const SHELL_METACHARACTERS: &[&str] = &[";", "&", "|", "`", "$(", "\n", "\r"];
+
+impl FleetRunConfig {
+    /// Whether a caller may run `command`; admins may run anything
+    ///
+    /// Patterns must match the whole command, so `uptime` allows `uptime`
+    /// and nothing appended to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason when a non-admin caller's command contains shell
+    /// metacharacters, matches a deny pattern, or matches no allow pattern.
+    pub fn check_command(&self, command: &str, admin: bool) -> Result<(), String> {
+        if admin {
+            return Ok(());
+        }
+        if let Some(meta) = SHELL_METACHARACTERS.iter().find(|m| command.contains(*m)) {
+            return Err(format!("command contains shell metacharacter {meta:?}"));
+        }
+        let matches = |pattern: &String| {
+            regex::Regex::new(&format!("^(?:{pattern})$"))
+                .is_ok_and(|regex| regex.is_match(command))
+        };
+        if let Some(pattern) = self.deny.iter().find(|p| matches(p)) {
+            return Err(format!("command matches denied pattern '{pattern}'"));
+        }
+        if !self.allow.iter().any(matches) {
+            return Err("command matches no allowed pattern".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Incident SLA targets under `[incidents]`
+///
+/// An incident is timed from when it started against the targets for its
+/// current severity, so changing the severity later re-times it from the
+/// start. Severities without an entry in `sla` are not timed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IncidentConfig {
+    /// Targets per incident severity (info, warning, critical)
+    pub sla: HashMap<String, SlaTargets>,
+
+    /// Alert sink told about each breach (webhook, slack, discord, desktop,
+    /// email). Breaches always go on the incident timeline.
+    pub breach_sink: Option<String>,
+}
+
+impl Default for IncidentConfig {
+    fn default() -> Self {
+        Self {
+            sla: HashMap::from([
+                (
+                    "critical".to_string(),
+                    SlaTargets {
+                        ack_secs: Some(900),
+                        mitigate_secs: Some(3600),
+                        resolve_secs: Some(14_400),
+                    },
+                ),
+                (
+                    "warning".to_string(),
+                    SlaTargets {
+                        ack_secs: Some(3600),
+                        mitigate_secs: Some(14_400),
+                        resolve_secs: Some(86_400),
+                    },
+                ),
+            ]),
+            breach_sink: None,
+        }
+    }
+}
+
+/// Disk forecasting under `[forecast]`
+///
+/// Each mount's used bytes are averaged into `bucket_secs` buckets over the
+/// last `window_days`, and the trend fitted to those buckets is projected to
+/// the mount's size. The daemon keeps the latest forecast per mount for the
+/// `disk-forecast-full` alert rule, whose threshold (7 days) is changed with
+/// `[[alerts.overrides]]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForecastConfig {
+    /// History the trend is fitted to
+    pub window_days: u64,
+
+    /// Length of one bucket of the fitted series
+    pub bucket_secs: u64,
+
+    /// How far ahead `vc health forecast` looks by default
+    pub horizon_days: u64,
+}
+
+impl Default for ForecastConfig {
+    fn default() -> Self {
+        Self {
+            window_days: 14,
+            bucket_secs: 3600,
+            horizon_days: 30,
+        }
+    }
+}
+
+/// Time allowed from an incident's start to each milestone; unset means
+/// that milestone is not timed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlaTargets {
+    /// First note or `vc incident ack`
+    pub ack_secs: Option<u64>,
+
+    /// Marked mitigated (closing counts too)
+    pub mitigate_secs: Option<u64>,
+
+    /// Closed
+    pub resolve_secs: Option<u64>,
+}
+
+/// Storage outside the database
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Transcript offloading
+    pub artifacts: ArtifactStorageConfig,
+}
+
+/// Transcript offloading under `[storage.artifacts]`
+///
+/// With `enabled`, the daemon moves `agent_sessions` transcripts older than
+/// `offload_after_days` to a directory or an S3-compatible bucket, leaving a
+/// pointer row with the location, size and SHA-256. Readers fetch offloaded
+/// transcripts back transparently, so the backend must stay configured (even
+/// with `enabled` off) for as long as pointers reference it. `vc vacuum`
+/// deletes the artifacts of sessions retention removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtifactStorageConfig {
+    /// Offload old transcripts from the daemon
+    pub enabled: bool,
+
+    /// Directory to keep artifacts in (set this or `s3_endpoint`)
+    pub dir: Option<PathBuf>,
+
+    /// S3-compatible endpoint, e.g. `https://s3.us-east-1.amazonaws.com`
+    pub s3_endpoint: Option<String>,
+
+    /// Bucket on `s3_endpoint`
+    pub bucket: Option<String>,
+
+    /// Region requests are signed for
+    pub region: String,
+
+    /// Access key ID, or a `secret://` reference to it
+    pub access_key: Option<String>,
+
+    /// Secret access key, or a `secret://` reference to it
+    pub secret_key: Option<String>,
+
+    /// Key prefix for every artifact
+    pub prefix: String,
+
+    /// Age (by session end) after which a transcript is offloaded
+    pub offload_after_days: u32,
+
+    /// Most transcripts offloaded per daemon tick
+    pub batch_size: usize,
+
+    /// Timeout for one upload or download in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for ArtifactStorageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            s3_endpoint: None,
+            bucket: None,
+            region: "us-east-1".to_string(),
+            access_key: None,
+            secret_key: None,
+            prefix: "vc".to_string(),
+            offload_after_days: 30,
+            batch_size: 100,
+            timeout_secs: 60,
+        }
+    }
+}
+
+impl ArtifactStorageConfig {
+    /// Whether a backend is configured, enabled or not
+    #[must_use]
+    pub fn has_backend(&self) -> bool {
+        self.dir.is_some() || self.s3_endpoint.is_some()
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.dir.is_some() && self.s3_endpoint.is_some() {
+            return Err(ConfigError::ValidationError(
+                "storage.artifacts must set only one of dir or s3_endpoint".to_string(),
+            ));
+        }
+        if self.enabled && !self.has_backend() {
+            return Err(ConfigError::ValidationError(
+                "storage.artifacts.enabled needs dir or s3_endpoint".to_string(),
+            ));
+        }
+        if self.s3_endpoint.is_some()
+            && (self.bucket.is_none() || self.access_key.is_none() || self.secret_key.is_none())
+        {
+            return Err(ConfigError::ValidationError(
+                "storage.artifacts.s3_endpoint needs bucket, access_key and secret_key".to_string(),
+            ));
+        }
+        if self.offload_after_days == 0 || self.batch_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "storage.artifacts.offload_after_days and batch_size must be > 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How a high-frequency audit event type is written
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum AuditSampling {
+    /// Every event, written in batches
+    All,
+    /// One event in every `keep_one_in`; failures are always kept
+    Sample { keep_one_in: u32 },
+    /// One summary event per machine and action per rollup window
+    Rollup,
+}
+
+/// A service whose presence is checked on each machine.
+///
+/// Exactly one of `process` (a regex matched against running command lines)
+/// or `unit` (a systemd unit, or a launchd label on macOS) identifies it.
+/// A service is required unless the machine carries one of `optional_tags`,
+/// and is only checked on machines carrying one of `tags` when that is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceCheckConfig {
+    /// Service name as shown in status output
+    pub name: String,
+
+    /// Regex matched against process command lines
+    #[serde(default)]
+    pub process: Option<String>,
+
+    /// systemd unit or launchd label
+    #[serde(default)]
+    pub unit: Option<String>,
+
+    /// Only check machines carrying one of these tags (empty: all machines)
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Machines carrying one of these tags treat the service as optional
+    #[serde(default)]
+    pub optional_tags: Vec<String>,
+}
+
+impl ServiceCheckConfig {
+    /// Whether the service is checked on a machine with `machine_tags`
+    #[must_use]
+    pub fn applies_to(&self, machine_tags: &[String]) -> bool {
+        self.tags.is_empty() || self.tags.iter().any(|tag| machine_tags.contains(tag))
+    }
+
+    /// Whether a missing instance counts against a machine with `machine_tags`
+    #[must_use]
+    pub fn is_required_for(&self, machine_tags: &[String]) -> bool {
+        !self
+            .optional_tags
+            .iter()
+            .any(|tag| machine_tags.contains(tag))
+    }
+}
+
+impl HttpCheckConfig {
+    fn validate(&self, machine_id: &str) -> Result<(), ConfigError> {
+        let invalid = |msg: String| {
+            Err(ConfigError::ValidationError(format!(
+                "Machine '{machine_id}' http check '{}': {msg}",
+                self.name
+            )))
+        };
+        if self.name.trim().is_empty() {
+            return Err(ConfigError::ValidationError(format!(
+                "Machine '{machine_id}' has an http check without a name"
+            )));
+        }
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return invalid(format!(
+                "url must be http:// or https://, got '{}'",
+                self.url
+            ));
+        }
+        if !VALID_HTTP_METHODS.contains(&self.method.as_str()) {
+            return invalid(format!(
+                "method must be one of {}, got '{}'",
+                VALID_HTTP_METHODS.join(", "),
+                self.method
+            ));
+        }
+        if !(100..=599).contains(&self.expect_status) {
+            return invalid(format!(
+                "expect_status {} is not an HTTP status",
+                self.expect_status
+            ));
+        }
+        if self.timeout_secs == 0 {
+            return invalid("timeout_secs must be > 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl VcConfig {
+    /// Standard config file paths, in order of precedence
+    #[must_use]
+    pub fn config_paths() -> Vec<PathBuf> {
+        let mut paths = vec![
+            // 1. Current directory (project-local)
+            PathBuf::from("vc.toml"),
+        ];
+
+        // 2. User config directory (~/.config/vc/vc.toml)
+        if let Some(config_dir) = dirs::config_dir() {
+            paths.push(config_dir.join("vc").join("vc.toml"));
+        }
+
+        // 3. System config
+        paths.push(PathBuf::from("/etc/vc/vc.toml"));
+
+        paths
+    }
+
+    /// Discover and load configuration from standard paths.
+    ///
+    /// Returns defaults if no config file is found.
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] if a discovered config file cannot be loaded.
+    pub fn discover() -> Result<Self, ConfigError> {
+        if let Some(path) = Self::discover_path() {
+            info!(path = %path.display(), "Loading config from");
+            return Self::load(&path);
+        }
+
+        info!("No config file found, using defaults");
+        Ok(Self::default())
+    }
+
+    /// The first of [`Self::config_paths`] that exists, which
+    /// [`Self::discover`] loads
+    #[must_use]
+    pub fn discover_path() -> Option<PathBuf> {
+        Self::config_paths().into_iter().find(|path| path.exists())
+    }
+
+    /// Discover config, apply the selected profile and then environment
+    /// variable overrides.
+    ///
+    /// `profile` wins over `VC_PROFILE`; see [`Self::selected_profile`].
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] if config discovery or validation fails, or
+    /// the profile does not exist.
+    pub fn discover_with_env(profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut config = Self::discover()?;
+        if let Some(profile) = Self::selected_profile(profile) {
+            config = config.with_profile(&profile)?;
+        }
+        config.apply_env_overrides();
+        config.expand_all_paths();
+        Ok(config)
+    }
+
+    /// Load configuration from a specific TOML file.
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] if the file cannot be read, parsed, or validated.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        let mut config: VcConfig = toml::from_str(&content)?;
+        config.expand_all_paths();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load configuration, apply the selected profile and then environment
+    /// variable overrides.
+    ///
+    /// `profile` wins over `VC_PROFILE`; see [`Self::selected_profile`].
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] if the file cannot be read, parsed, or
+    /// validated, or the profile does not exist.
+    pub fn load_with_env(path: &Path, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut config = Self::load(path)?;
+        if let Some(profile) = Self::selected_profile(profile) {
+            config = config.with_profile(&profile)?;
+        }
+        config.apply_env_overrides();
+        Ok(config)
+    }
+
+    /// Profile to apply: `explicit` if given, else a non-empty `VC_PROFILE`
+    #[must_use]
+    pub fn selected_profile(explicit: Option<&str>) -> Option<String> {
+        explicit
+            .map(str::to_string)
+            .or_else(|| std::env::var(PROFILE_ENV).ok())
+            .filter(|profile| !profile.trim().is_empty())
+    }
+
+    /// Names of the profiles defined in this config, sorted
+    #[must_use]
+    pub fn profile_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// This config with profile `name` merged over it, validated.
+    ///
+    /// The result carries no profiles of its own.
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] if the profile does not exist or the merged
+    /// config is invalid.
+    pub fn with_profile(&self, name: &str) -> Result<Self, ConfigError> {
+        let config = self.merge_profile(name)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Merge profile `name` over this config without validating the result
+    fn merge_profile(&self, name: &str) -> Result<Self, ConfigError> {
+        let Some(overrides) = self.profiles.get(name) else {
+            let available = self.profile_names();
+            return Err(ConfigError::ValidationError(format!(
+                "Unknown profile '{name}'. Available profiles: {}",
+                if available.is_empty() {
+                    "(none)".to_string()
+                } else {
+                    available.join(", ")
+                }
+            )));
+        };
+
+        let mut base = self.clone();
+        base.profiles.clear();
+        let toml::Value::Table(mut merged) = toml::Value::try_from(&base).map_err(|e| {
+            ConfigError::ValidationError(format!("Failed to serialize config: {e}"))
+        })?
+        else {
+            unreachable!("a struct serializes to a table");
+        };
+        merge_toml_tables(&mut merged, overrides);
+
+        let mut config: VcConfig = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e| ConfigError::ValidationError(format!("Invalid profile '{name}': {e}")))?;
+        config.expand_all_paths();
+        Ok(config)
+    }
+
+    /// Expand all paths in configuration (resolve `~/` to home directory)
+    pub fn expand_all_paths(&mut self) {
+        self.global.expand_paths();
+
+        // Expand SSH key paths for all machines
+        for machine in self.machines.values_mut() {
+            if let Some(ref mut key_path) = machine.ssh_key {
+                *key_path = expand_path(key_path);
+            }
+        }
+        if let Some(dir) = &mut self.storage.artifacts.dir {
+            *dir = expand_path(dir);
+        }
+    }
+
+    /// Apply environment variable overrides
+    fn apply_env_overrides(&mut self) {
+        if let Ok(val) = std::env::var("VC_DB_PATH") {
+            self.global.db_path = expand_path(&PathBuf::from(val));
+        }
+        if let Ok(val) = std::env::var("VC_LOG_LEVEL") {
+            self.global.log_level = val;
+        }
+        if let Ok(val) = std::env::var("VC_POLL_INTERVAL")
+            && let Ok(secs) = val.parse()
+        {
+            self.global.poll_interval_secs = secs;
+        }
+        if let Ok(val) = std::env::var("VC_WEB_PORT")
+            && let Ok(port) = val.parse()
+        {
+            self.web.port = port;
+        }
+        if let Ok(val) = std::env::var("VC_WEB_BIND") {
+            self.web.bind_address = val;
+        }
+    }
+
+    /// Validate configuration.
+    ///
+    /// # Errors
+    /// Returns a [`ConfigError`] when validation rules are violated.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        // Validate poll interval
+        if self.global.poll_interval_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "poll_interval_secs must be > 0".to_string(),
+            ));
+        }
+
+        if self.global.open_timeout_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "open_timeout_secs must be > 0".to_string(),
+            ));
+        }
+
+        // Validate collector timeout
+        if self.collectors.timeout_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "collector timeout_secs must be > 0".to_string(),
+            ));
+        }
+
+        if self.collectors.max_concurrent_collectors == 0 {
+            return Err(ConfigError::ValidationError(
+                "collector max_concurrent_collectors must be > 0".to_string(),
+            ));
+        }
+
+        if self.collectors.max_concurrent_per_machine == 0 {
+            return Err(ConfigError::ValidationError(
+                "collector max_concurrent_per_machine must be > 0".to_string(),
+            ));
+        }
+
+        for (tool, version) in &self.collectors.min_versions {
+            let version = version.trim();
+            if !version
+                .strip_prefix('v')
+                .unwrap_or(version)
+                .starts_with(|c: char| c.is_ascii_digit())
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "collectors.min_versions.{tool} '{version}' is not a version number"
+                )));
+            }
+        }
+
+        // Validate log level
+        if !VALID_LOG_LEVELS.contains(&self.global.log_level.to_lowercase().as_str()) {
+            return Err(ConfigError::ValidationError(format!(
+                "Invalid log_level '{}'. Must be one of: {}",
+                self.global.log_level,
+                VALID_LOG_LEVELS.join(", ")
+            )));
+        }
+
+        if let Some(backend) = &self.global.store_backend
+            && !VALID_STORE_BACKENDS.contains(&backend.to_lowercase().as_str())
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "Invalid store_backend '{backend}'. Must be one of: {}",
+                VALID_STORE_BACKENDS.join(", ")
+            )));
+        }
+
+        if !self.global.timezone.eq_ignore_ascii_case("local")
+            && self.global.timezone.parse::<chrono_tz::Tz>().is_err()
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "Invalid timezone '{}'. Use \"local\" or an IANA name like \"Europe/Berlin\"",
+                self.global.timezone
+            )));
+        }
+
+        // Validate autopilot thresholds
+        if self.autopilot.min_confidence < 0.0 || self.autopilot.min_confidence > 1.0 {
+            return Err(ConfigError::ValidationError(
+                "autopilot.min_confidence must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        // Validate web port
+        if self.web.port == 0 {
+            return Err(ConfigError::ValidationError(
+                "web.port must be > 0".to_string(),
+            ));
+        }
+
+        let signing = &self.web.signing;
+        if signing.enabled && signing.secret.is_none() {
+            return Err(ConfigError::ValidationError(
+                "web.signing.enabled requires web.signing.secret".to_string(),
+            ));
+        }
+        if signing.max_skew_secs == 0 || signing.replay_cache_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "web.signing.max_skew_secs and web.signing.replay_cache_size must be > 0"
+                    .to_string(),
+            ));
+        }
+        if self.web.events.queue_size == 0 || self.web.events.poll_interval_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "web.events.queue_size and web.events.poll_interval_secs must be > 0".to_string(),
+            ));
+        }
+
+        if self.query_log.enabled
+            && (self.query_log.max_rows == 0 || self.query_log.buffer_size == 0)
+        {
+            return Err(ConfigError::ValidationError(
+                "query_log.max_rows and query_log.buffer_size must be > 0".to_string(),
+            ));
+        }
+
+        // The truncation markers need some room
+        if self.write_limits.enabled
+            && (self.write_limits.max_text_bytes < 4096 || self.write_limits.max_json_bytes < 4096)
+        {
+            return Err(ConfigError::ValidationError(
+                "write_limits.max_text_bytes and write_limits.max_json_bytes must be >= 4096"
+                    .to_string(),
+            ));
+        }
+
+        if self.query.max_rows == 0
+            || self.query.max_execution_ms == 0
+            || self.query.agent_max_execution_ms == 0
+        {
+            return Err(ConfigError::ValidationError(
+                "query.max_rows, query.max_execution_ms and query.agent_max_execution_ms \
+                 must be > 0"
+                    .to_string(),
+            ));
+        }
+
+        if self.query_approval.ttl_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "query_approval.ttl_secs must be > 0".to_string(),
+            ));
+        }
+
+        if let Some((role, tool)) = self.mcp.unknown_tools().first() {
+            return Err(ConfigError::ValidationError(format!(
+                "Unknown tool '{tool}' in mcp.roles.{role}.tools. Must be one of: {}",
+                MCP_TOOLS.join(", ")
+            )));
+        }
+        if let Some(role) = &self.mcp.default_role
+            && !self.mcp.roles.contains_key(role)
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "mcp.default_role '{role}' is not defined under mcp.roles"
+            )));
+        }
+        // A budget smaller than the truncation markers could return nothing
+        if self.mcp.max_response_bytes < 1024 {
+            return Err(ConfigError::ValidationError(
+                "mcp.max_response_bytes must be >= 1024".to_string(),
+            ));
+        }
+
+        if self.analyze.change_ratio.is_nan()
+            || self.analyze.change_ratio <= 0.0
+            || self.analyze.quiet_start_hour > 23
+            || self.analyze.quiet_end_hour > 23
+        {
+            return Err(ConfigError::ValidationError(
+                "analyze.change_ratio must be > 0 and the quiet hours within 0-23".to_string(),
+            ));
+        }
+
+        if self.forecast.window_days == 0
+            || self.forecast.bucket_secs == 0
+            || self.forecast.horizon_days == 0
+        {
+            return Err(ConfigError::ValidationError(
+                "forecast.window_days, forecast.bucket_secs and forecast.horizon_days must be > 0"
+                    .to_string(),
+            ));
+        }
+
+        if self
+            .health_components
+            .weights
+            .values()
+            .any(|weight| !weight.is_finite() || *weight < 0.0)
+            || self.health_components.replication_lag_warn_secs
+                > self.health_components.replication_lag_fail_secs
+        {
+            return Err(ConfigError::ValidationError(
+                "health_components.weights must be >= 0 and replication_lag_warn_secs \
+                 must not exceed replication_lag_fail_secs"
+                    .to_string(),
+            ));
+        }
+
+        if self.fleet_run.max_output_bytes == 0 {
+            return Err(ConfigError::ValidationError(
+                "fleet_run.max_output_bytes must be > 0".to_string(),
+            ));
+        }
+        for pattern in self.fleet_run.allow.iter().chain(&self.fleet_run.deny) {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(ConfigError::ValidationError(format!(
+                    "fleet_run has an invalid pattern '{pattern}': {e}"
+                )));
+            }
+        }
+
+        if self.daemon.watch_queue_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "daemon.watch_queue_size must be > 0".to_string(),
+            ));
+        }
+        if self.daemon.lease_ttl_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "daemon.lease_ttl_secs must be > 0".to_string(),
+            ));
+        }
+
+        // Validate daemon limits
+        if self.daemon.limits.poll_stretch_factor == 0 {
+            return Err(ConfigError::ValidationError(
+                "daemon.limits.poll_stretch_factor must be > 0".to_string(),
+            ));
+        }
+        if self.daemon.limits.recovery_ratio <= 0.0 || self.daemon.limits.recovery_ratio > 1.0 {
+            return Err(ConfigError::ValidationError(
+                "daemon.limits.recovery_ratio must be in (0.0, 1.0]".to_string(),
+            ));
+        }
+
+        // Validate alert digests
+        for (sink, digest) in &self.alerts.digest {
+            if !VALID_DIGEST_SINKS.contains(&sink.as_str()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Unknown alerts.digest sink '{sink}'. Must be one of: {}",
+                    VALID_DIGEST_SINKS.join(", ")
+                )));
+            }
+            if !VALID_SEVERITIES.contains(&digest.min_severity.to_lowercase().as_str()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Invalid alerts.digest.{sink}.min_severity '{}'. Must be one of: {}",
+                    digest.min_severity,
+                    VALID_SEVERITIES.join(", ")
+                )));
+            }
+            if digest.flush_interval_secs == 0 || digest.max_batch_size == 0 {
+                return Err(ConfigError::ValidationError(format!(
+                    "alerts.digest.{sink} flush_interval_secs and max_batch_size must be > 0"
+                )));
+            }
+        }
+
+        if let Some(email) = &self.alerts.email {
+            email.validate()?;
+        }
+
+        // Validate message templates; their text is checked by lint
+        for (sink, template) in &self.alerts.templates {
+            if !VALID_DIGEST_SINKS.contains(&sink.as_str()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Unknown alerts.templates sink '{sink}'. Must be one of: {}",
+                    VALID_DIGEST_SINKS.join(", ")
+                )));
+            }
+            if template.template.is_some() == template.file.is_some() {
+                return Err(ConfigError::ValidationError(format!(
+                    "alerts.templates.{sink} requires exactly one of template or file"
+                )));
+            }
+        }
+
+        // Validate escalation policies
+        for (index, policy) in self.alerts.escalation.iter().enumerate() {
+            policy.validate()?;
+            if self.alerts.escalation[..index]
+                .iter()
+                .any(|other| other.name == policy.name)
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "Duplicate alerts.escalation name '{}'",
+                    policy.name
+                )));
+            }
+        }
+
+        // Validate rule overrides
+        for (index, entry) in self.alerts.overrides.iter().enumerate() {
+            entry.validate()?;
+            if entry.machine.is_some()
+                && self.alerts.overrides[..index]
+                    .iter()
+                    .any(|other| other.rule == entry.rule && other.machine == entry.machine)
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "Duplicate alerts.overrides for '{}' on {}",
+                    entry.rule,
+                    entry.scope()
+                )));
+            }
+        }
+
+        // Validate staleness alerting
+        let staleness = &self.alerts.staleness;
+        if staleness.stale_threshold_secs == 0 || staleness.collectors.values().any(|t| *t == 0) {
+            return Err(ConfigError::ValidationError(
+                "alerts.staleness thresholds must be > 0".to_string(),
+            ));
+        }
+        if staleness.critical_multiplier < 2 {
+            return Err(ConfigError::ValidationError(
+                "alerts.staleness.critical_multiplier must be >= 2".to_string(),
+            ));
+        }
+
+        if self.alerts.correlation.window_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "alerts.correlation.window_secs must be > 0".to_string(),
+            ));
+        }
+
+        // Validate incident SLA targets
+        for (severity, targets) in &self.incidents.sla {
+            if !VALID_SEVERITIES.contains(&severity.as_str()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "Unknown incidents.sla severity '{severity}'. Must be one of: {}",
+                    VALID_SEVERITIES.join(", ")
+                )));
+            }
+            if [
+                targets.ack_secs,
+                targets.mitigate_secs,
+                targets.resolve_secs,
+            ]
+            .contains(&Some(0))
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "incidents.sla.{severity} targets must be > 0"
+                )));
+            }
+        }
+        if let Some(sink) = &self.incidents.breach_sink
+            && !VALID_DIGEST_SINKS.contains(&sink.as_str())
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "Unknown incidents.breach_sink '{sink}'. Must be one of: {}",
+                VALID_DIGEST_SINKS.join(", ")
+            )));
+        }
+
+        // Validate audit sampling
+        for (event_type, sampling) in &self.audit.events {
+            if !SAMPLEABLE_AUDIT_EVENTS.contains(&event_type.as_str()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "audit.events.{event_type} cannot be sampled. Must be one of: {}",
+                    SAMPLEABLE_AUDIT_EVENTS.join(", ")
+                )));
+            }
+            if *sampling == (AuditSampling::Sample { keep_one_in: 0 }) {
+                return Err(ConfigError::ValidationError(format!(
+                    "audit.events.{event_type}.keep_one_in must be > 0"
+                )));
+            }
+        }
+        if self.audit.batch_size == 0 || self.audit.rollup_window_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "audit.batch_size and audit.rollup_window_secs must be > 0".to_string(),
+            ));
+        }
+
+        // Validate account status commands
+        if self
+            .accounts
+            .commands
+            .iter()
+            .any(|c| c.command.trim().is_empty())
+        {
+            return Err(ConfigError::ValidationError(
+                "accounts.commands entries must have a non-empty command".to_string(),
+            ));
+        }
+
+        // Validate agent inventory patterns
+        if self
+            .agent_inventory
+            .patterns
+            .iter()
+            .any(|p| p.agent_type.trim().is_empty() || p.pattern.trim().is_empty())
+        {
+            return Err(ConfigError::ValidationError(
+                "agent_inventory.patterns entries must have a non-empty agent_type and pattern"
+                    .to_string(),
+            ));
+        }
+
+        // Validate knowledge embeddings backend
+        let embeddings = &self.knowledge.embeddings;
+        if embeddings.enabled && embeddings.command.is_some() == embeddings.endpoint.is_some() {
+            return Err(ConfigError::ValidationError(
+                "knowledge.embeddings must set exactly one of command or endpoint when enabled"
+                    .to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&embeddings.semantic_weight) {
+            return Err(ConfigError::ValidationError(
+                "knowledge.embeddings.semantic_weight must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+
+        let outcomes = &self.knowledge.outcomes;
+        for (name, heuristics) in std::iter::once(("default", &outcomes.default)).chain(
+            outcomes
+                .agents
+                .iter()
+                .map(|(program, heuristics)| (program.as_str(), heuristics)),
+        ) {
+            if !(0.0..=1.0).contains(&heuristics.partial_error_ratio)
+                || !(0.0..=1.0).contains(&heuristics.failed_error_ratio)
+                || heuristics.partial_error_ratio > heuristics.failed_error_ratio
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "knowledge.outcomes ({name}): error ratios must be between 0.0 and 1.0 \
+                     with partial_error_ratio <= failed_error_ratio"
+                )));
+            }
+        }
+
+        // Validate telemetry export
+        if self.telemetry.enabled && self.telemetry.otlp_endpoint.is_none() {
+            return Err(ConfigError::ValidationError(
+                "telemetry.otlp_endpoint is required when telemetry is enabled".to_string(),
+            ));
+        }
+        if self.telemetry.batch_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "telemetry.batch_size must be > 0".to_string(),
+            ));
+        }
+
+        // Validate replication
+        if self.replication.mode == ReplicationMode::Standby
+            && self.replication.standby_url.is_some()
+        {
+            return Err(ConfigError::ValidationError(
+                "replication.standby_url is only valid on a primary".to_string(),
+            ));
+        }
+        if self.replication.interval_secs == 0 || self.replication.batch_size == 0 {
+            return Err(ConfigError::ValidationError(
+                "replication.interval_secs and replication.batch_size must be > 0".to_string(),
+            ));
+        }
+
+        self.storage.artifacts.validate()?;
+
+        // Secret references must at least be well-formed; whether they
+        // resolve is only checked when used, or by `vc config verify-secrets`
+        for (key, value) in self.secret_fields() {
+            if let Err(reason) = SecretRef::parse(value) {
+                return Err(ConfigError::SecretError { key, reason });
+            }
+        }
+
+        // Validate service checks
+        for service in &self.services {
+            if service.process.is_some() == service.unit.is_some() {
+                return Err(ConfigError::ValidationError(format!(
+                    "Service '{}' must set exactly one of process or unit",
+                    service.name
+                )));
+            }
+            if let Some(pattern) = &service.process
+                && let Err(e) = regex::Regex::new(pattern)
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "Service '{}' has an invalid process pattern: {e}",
+                    service.name
+                )));
+            }
+            // Unit names are interpolated into a shell command on the target
+            if let Some(unit) = &service.unit
+                && (unit.is_empty()
+                    || !unit
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "@._:-".contains(c)))
+            {
+                return Err(ConfigError::ValidationError(format!(
+                    "Service '{}' has an invalid unit name '{unit}'",
+                    service.name
+                )));
+            }
+        }
+
+        // Validate machine configurations
+        for (id, machine) in &self.machines {
+            if machine.ssh_host.is_some() && machine.ssh_user.is_none() {
+                return Err(ConfigError::ValidationError(format!(
+                    "Machine '{id}' has ssh_host but missing ssh_user"
+                )));
+            }
+            for (i, check) in machine.http_checks.iter().enumerate() {
+                check.validate(id)?;
+                if machine.http_checks[..i]
+                    .iter()
+                    .any(|other| other.name == check.name)
+                {
+                    return Err(ConfigError::ValidationError(format!(
+                        "Machine '{id}' has two http checks named '{}'",
+                        check.name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get poll interval as Duration
+    #[must_use]
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.global.poll_interval_secs)
+    }
+
+    /// Get collector timeout as Duration
+    #[must_use]
+    pub fn collector_timeout(&self) -> Duration {
+        Duration::from_secs(self.collectors.timeout_secs)
+    }
+
+    /// Check if a machine is local (no SSH required)
+    #[must_use]
+    pub fn is_local_machine(&self, machine_id: &str) -> bool {
+        self.machines
+            .get(machine_id)
+            .is_none_or(|m| m.ssh_host.is_none())
+    }
+
+    /// Service checks that apply to a machine with `tags`, with whether each
+    /// is required there
+    #[must_use]
+    pub fn service_checks_for(&self, tags: &[String]) -> Vec<(&ServiceCheckConfig, bool)> {
+        self.services
+            .iter()
+            .filter(|service| service.applies_to(tags))
+            .map(|service| (service, service.is_required_for(tags)))
+            .collect()
+    }
+
+    /// Get enabled machines
+    pub fn enabled_machines(&self) -> impl Iterator<Item = (&String, &MachineConfig)> {
+        self.machines.iter().filter(|(_, m)| m.enabled)
+    }
+
+    /// Check if a collector is enabled for a specific machine.
+    ///
+    /// `collector_name` is the name the collector registers itself under in the
+    /// `vc_collect` registry (e.g. `fallback_probe`, `beads`, `github`). Every
+    /// registered collector must have an arm here: a missing arm silently
+    /// disables the collector forever, which is how `fallback_probe` — the
+    /// always-on baseline — never ran.
+    ///
+    /// The legacy config key `bv_br` is accepted as an alias for `beads`.
+    #[must_use]
+    pub fn is_collector_enabled(&self, machine_id: &str, collector_name: &str) -> bool {
+        // Check machine-specific override first
+        if let Some(machine) = self.machines.get(machine_id)
+            && let Some(&enabled) = machine.collectors.get(collector_name)
+        {
+            return enabled;
+        }
+
+        // Fall back to global collector config
+        match collector_name {
+            "fallback_probe" => self.collectors.fallback_probe,
+            "sysmoni" => self.collectors.sysmoni,
+            "ru" => self.collectors.ru,
+            "caut" => self.collectors.caut,
+            "caam" => self.collectors.caam,
+            "cass" => self.collectors.cass,
+            "mcp_agent_mail" => self.collectors.mcp_agent_mail,
+            "ntm" => self.collectors.ntm,
+            "rch" => self.collectors.rch,
+            "rano" => self.collectors.rano,
+            "dcg" => self.collectors.dcg,
+            "pt" => self.collectors.pt,
+            // Registry name is `beads`; `bv_br` is the legacy config key.
+            "beads" | "bv_br" => self.collectors.bv_br,
+            "afsc" => self.collectors.afsc,
+            "github" => self.collectors.github,
+            "cloud_benchmarker" => self.collectors.cloud_benchmarker,
+            "accounts" => self.collectors.accounts,
+            "http_check" => self.collectors.http_check,
+            "agent_inventory" => self.collectors.agent_inventory,
+            _ => false, // Unknown collectors are disabled
+        }
+    }
+
+    /// Lint the configuration and return all issues found.
+    ///
+    /// This performs more extensive checks than `validate()`, including:
+    /// - Validation errors (also caught by `validate()`)
+    /// - Warnings about suboptimal configurations
+    /// - Suggestions for improvements
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub fn lint(&self) -> LintResult {
+        let mut result = LintResult::new();
+
+        // === ERRORS ===
+
+        // Poll interval must be positive
+        if self.global.poll_interval_secs == 0 {
+            result.add(
+                LintIssue::error(
+                    "global.poll_interval_secs",
+                    "Poll interval must be greater than 0",
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Set a reasonable poll interval (e.g., 120 seconds)".to_string(),
+                    path: "global.poll_interval_secs".to_string(),
+                    suggested_value: Some("120".to_string()),
+                }),
+            );
+        }
+
+        // Collector timeout must be positive
+        if self.collectors.timeout_secs == 0 {
+            result.add(
+                LintIssue::error(
+                    "collectors.timeout_secs",
+                    "Collector timeout must be greater than 0",
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Set a reasonable timeout (e.g., 30 seconds)".to_string(),
+                    path: "collectors.timeout_secs".to_string(),
+                    suggested_value: Some("30".to_string()),
+                }),
+            );
+        }
+
+        if self.collectors.max_concurrent_collectors == 0 {
+            result.add(
+                LintIssue::error(
+                    "collectors.max_concurrent_collectors",
+                    "Global collector concurrency must be greater than 0",
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Allow at least one collector to run".to_string(),
+                    path: "collectors.max_concurrent_collectors".to_string(),
+                    suggested_value: Some("8".to_string()),
+                }),
+            );
+        }
+
+        if self.collectors.max_concurrent_per_machine == 0 {
+            result.add(
+                LintIssue::error(
+                    "collectors.max_concurrent_per_machine",
+                    "Per-machine collector concurrency must be greater than 0",
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Allow at least one concurrent collector per machine".to_string(),
+                    path: "collectors.max_concurrent_per_machine".to_string(),
+                    suggested_value: Some("4".to_string()),
+                }),
+            );
+        }
+
+        // Validate log level
+        if !VALID_LOG_LEVELS.contains(&self.global.log_level.to_lowercase().as_str()) {
+            result.add(
+                LintIssue::error(
+                    "global.log_level",
+                    format!(
+                        "Invalid log level '{}'. Must be one of: {}",
+                        self.global.log_level,
+                        VALID_LOG_LEVELS.join(", ")
+                    ),
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Use a valid log level".to_string(),
+                    path: "global.log_level".to_string(),
+                    suggested_value: Some("\"info\"".to_string()),
+                }),
+            );
+        }
+
+        // Autopilot confidence must be in [0, 1]
+        if self.autopilot.min_confidence < 0.0 || self.autopilot.min_confidence > 1.0 {
+            result.add(
+                LintIssue::error(
+                    "autopilot.min_confidence",
+                    "Min confidence must be between 0.0 and 1.0",
+                )
+                .with_suggestion(LintSuggestion {
+                    description: "Set confidence to a value between 0 and 1".to_string(),
+                    path: "autopilot.min_confidence".to_string(),
+                    suggested_value: Some("0.8".to_string()),
+                }),
+            );
+        }
+
+        // Web port must be positive
+        if self.web.port == 0 {
+            result.add(
+                LintIssue::error("web.port", "Web port must be greater than 0").with_suggestion(
+                    LintSuggestion {
+                        description: "Set a valid port number".to_string(),
+                        path: "web.port".to_string(),
+                        suggested_value: Some("8080".to_string()),
+                    },
+                ),
+            );
+        }
+
+        // Machine SSH validation
+        for (id, machine) in &self.machines {
+            let path_prefix = format!("machines.{id}");
+
+            // SSH host without user
+            if machine.ssh_host.is_some() && machine.ssh_user.is_none() {
+                result.add(
+                    LintIssue::error(
+                        format!("{path_prefix}.ssh_user"),
+                        format!("Machine '{id}' has ssh_host but missing ssh_user"),
+                    )
+                    .with_suggestion(LintSuggestion {
+                        description: "Add SSH user for remote machine".to_string(),
+                        path: format!("{path_prefix}.ssh_user"),
+                        suggested_value: Some("\"ubuntu\"".to_string()),
+                    }),
+                );
+            }
+
+            // SSH key path doesn't exist
+            if let Some(ref key_path) = machine.ssh_key {
+                let expanded = expand_path(key_path);
+                if !expanded.exists() {
+                    result.add(LintIssue::error(
+                        format!("{path_prefix}.ssh_key"),
+                        format!("SSH key file does not exist: {}", expanded.display()),
+                    ));
+                }
+            }
+        }
+
+        // === WARNINGS ===
+
+        // Very short poll interval
+        if self.global.poll_interval_secs > 0 && self.global.poll_interval_secs < 30 {
+            result.add(LintIssue::warning(
+                "global.poll_interval_secs",
+                format!(
+                    "Poll interval of {} seconds is very short and may cause high load",
+                    self.global.poll_interval_secs
+                ),
+            ));
+        }
+
+        // Very long poll interval
+        if self.global.poll_interval_secs > 600 {
+            result.add(LintIssue::warning(
+                "global.poll_interval_secs",
+                format!(
+                    "Poll interval of {} seconds is long; data may become stale",
+                    self.global.poll_interval_secs
+                ),
+            ));
+        }
+
+        // Autopilot enabled with low confidence
+        if self.autopilot.enabled && self.autopilot.min_confidence < 0.5 {
+            result.add(LintIssue::warning(
+                "autopilot.min_confidence",
+                "Autopilot enabled with low confidence threshold; may take risky actions",
+            ));
+        }
+
+        // Autopilot enabled but account switching disabled
+        if self.autopilot.enabled && !self.autopilot.auto_switch_accounts {
+            result.add(LintIssue::info(
+                "autopilot.auto_switch_accounts",
+                "Autopilot is enabled but account switching is disabled",
+            ));
+        }
+
+        // Web enabled without CORS in production
+        if self.web.enabled && !self.web.cors_enabled && self.web.bind_address != "127.0.0.1" {
+            result.add(LintIssue::warning(
+                "web.cors_enabled",
+                "Web dashboard bound to non-localhost without CORS configuration",
+            ));
+        }
+
+        // Alerts enabled but no delivery channels configured
+        if self.alerts.enabled
+            && self.alerts.webhook_url.is_none()
+            && self.alerts.slack_webhook_url.is_none()
+            && self.alerts.discord_webhook_url.is_none()
+            && !self.alerts.desktop_notifications
+            && self.alerts.email.is_none()
+        {
+            result.add(LintIssue::warning(
+                "alerts",
+                "Alerts are enabled but no delivery channels are configured",
+            ));
+        }
+
+        // === INFO ===
+
+        // No machines defined
+        if self.machines.is_empty() {
+            result.add(LintIssue::info(
+                "machines",
+                "No machines defined; vc will only monitor local machine",
+            ));
+        }
+
+        // All collectors disabled
+        if !self.collectors.sysmoni
+            && !self.collectors.ru
+            && !self.collectors.caut
+            && !self.collectors.caam
+        {
+            result.add(LintIssue::warning(
+                "collectors",
+                "All primary collectors are disabled; vc will collect minimal data",
+            ));
+        }
+
+        // Debug logging in production
+        if self.global.log_level.to_lowercase() == "trace"
+            || self.global.log_level.to_lowercase() == "debug"
+        {
+            result.add(LintIssue::info(
+                "global.log_level",
+                "Debug/trace logging enabled; logs may grow large",
+            ));
+        }
+
+        // Machine with no tags
+        for (id, machine) in &self.machines {
+            if machine.tags.is_empty() {
+                result.add(LintIssue::info(
+                    format!("machines.{id}.tags"),
+                    format!("Machine '{id}' has no tags; consider adding tags for filtering"),
+                ));
+            }
+        }
+
+        for (key, value) in self.secret_fields() {
+            if let Err(reason) = SecretRef::parse(value) {
+                result.add(LintIssue::error(key, reason));
+            }
+        }
+
+        for (key, message) in self.web.oidc.problems() {
+            result.add(LintIssue::error(key, message));
+        }
+
+        // A misspelled tool would silently grant nothing
+        for (role, tool) in self.mcp.unknown_tools() {
+            result.add(
+                LintIssue::error(
+                    format!("mcp.roles.{role}.tools"),
+                    format!("Unknown MCP tool '{tool}'"),
+                )
+                .with_suggestion(LintSuggestion {
+                    description: format!("Use one of: {}", MCP_TOOLS.join(", ")),
+                    path: format!("mcp.roles.{role}.tools"),
+                    suggested_value: None,
+                }),
+            );
+        }
+        if let Some(role) = &self.mcp.default_role
+            && !self.mcp.roles.contains_key(role)
+        {
+            result.add(LintIssue::error(
+                "mcp.default_role",
+                format!("Role '{role}' is not defined under mcp.roles"),
+            ));
+        }
+
+        // A template that fails to parse would fall back to the default format
+        for (sink, template) in &self.alerts.templates {
+            if let Err(e) = template.load(sink) {
+                result.add(LintIssue::error(
+                    format!("alerts.templates.{sink}"),
+                    e.to_string(),
+                ));
+            }
+        }
+
+        self.lint_profiles(&mut result);
+
+        result
+    }
+
+    /// [`lint`](Self::lint) plus checks that look outside the config: every
+    /// `secret://file/` reference must name an existing file that only its
+    /// owner can read. The files are inspected, never read.
+    #[must_use]
+    pub fn lint_deep(&self) -> LintResult {
+        let mut result = self.lint();
+        for (key, value) in self.secret_fields() {
+            let Ok(Some(SecretRef::File(path))) = SecretRef::parse(value) else {
+                continue;
+            };
+            let path = expand_path(&path);
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    result.add(LintIssue::error(
+                        key,
+                        format!("secret file {}: {}", path.display(), e.kind()),
+                    ));
+                    continue;
+                }
+            };
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = metadata.permissions().mode() & 0o777;
+                if mode & 0o077 != 0 {
+                    result.add(
+                        LintIssue::warning(
+                            key,
+                            format!(
+                                "secret file {} is accessible to other users (mode {mode:o})",
+                                path.display()
+                            ),
+                        )
+                        .with_suggestion(LintSuggestion {
+                            description: format!("chmod 600 {}", path.display()),
+                            path: path.display().to_string(),
+                            suggested_value: None,
+                        }),
+                    );
+                }
+            }
+            #[cfg(not(unix))]
+            let _ = metadata;
+        }
+        result
+    }
+
+    /// Every secret-bearing value that is set, keyed by its config path.
+    /// Each may hold the secret itself or a `secret://` reference.
+    #[must_use]
+    pub fn secret_fields(&self) -> Vec<(String, &str)> {
+        let mut fields: Vec<(String, &str)> = [
+            ("alerts.webhook_url", &self.alerts.webhook_url),
+            ("alerts.slack_webhook_url", &self.alerts.slack_webhook_url),
+            (
+                "alerts.discord_webhook_url",
+                &self.alerts.discord_webhook_url,
+            ),
+            ("replication.token", &self.replication.token),
+            (
+                "replication.signing_secret",
+                &self.replication.signing_secret,
+            ),
+            ("web.signing.secret", &self.web.signing.secret),
+            ("web.oidc.client_secret", &self.web.oidc.client_secret),
+            ("web.oidc.session_secret", &self.web.oidc.session_secret),
+            (
+                "storage.artifacts.access_key",
+                &self.storage.artifacts.access_key,
+            ),
+            (
+                "storage.artifacts.secret_key",
+                &self.storage.artifacts.secret_key,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_string(), value.as_deref()?)))
+        .collect();
+        if let Some(password) = self
+            .alerts
+            .email
+            .as_ref()
+            .and_then(|e| e.password.as_deref())
+        {
+            fields.push(("alerts.email.password".to_string(), password));
+        }
+        let mut headers: Vec<_> = self.telemetry.headers.iter().collect();
+        headers.sort();
+        fields.extend(
+            headers
+                .into_iter()
+                .map(|(name, value)| (format!("telemetry.headers.{name}"), value.as_str())),
+        );
+        fields
+    }
+
+    /// Resolve every `secret://` reference, reporting which ones fail
+    /// without keeping anything they resolve to
+    #[must_use]
+    pub fn verify_secrets(&self) -> Vec<SecretCheck> {
+        self.secret_fields()
+            .into_iter()
+            .filter(|(_, value)| SecretRef::is_reference(value))
+            .map(|(key, value)| {
+                let error = resolve_secret(&key, value).err().map(|e| match e {
+                    ConfigError::SecretError { reason, .. } => reason,
+                    other => other.to_string(),
+                });
+                SecretCheck {
+                    key,
+                    reference: value.to_string(),
+                    ok: error.is_none(),
+                    error,
+                }
+            })
+            .collect()
+    }
+
+    /// Lint every profile as the config it resolves to.
+    ///
+    /// Issues the base config already has are not repeated; the rest are
+    /// reported under `profiles.<name>`.
+    fn lint_profiles(&self, result: &mut LintResult) {
+        if self.profiles.is_empty() {
+            return;
+        }
+
+        let mut base = self.clone();
+        base.profiles.clear();
+        let base_issues = base.lint().issues;
+
+        for name in self.profile_names() {
+            let prefix = format!("profiles.{name}");
+            let resolved = match self.merge_profile(name) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    result.add(LintIssue::error(prefix, e.to_string()));
+                    continue;
+                }
+            };
+            if let Err(e) = resolved.validate() {
+                result.add(LintIssue::error(prefix.clone(), e.to_string()));
+            }
+            for mut issue in resolved.lint().issues {
+                if base_issues
+                    .iter()
+                    .any(|b| b.path == issue.path && b.message == issue.message)
+                {
+                    continue;
+                }
+                issue.path = format!("{prefix}: {}", issue.path);
+                result.add(issue);
+            }
+        }
+    }
+
+    /// Generate a minimal default configuration as TOML string.
+    #[must_use]
+    pub fn generate_default_toml() -> String {
+        r#"# Vibe Cockpit Configuration
+# Generated by vc config wizard
+
+[global]
+# Path to DuckDB database (default: ~/.local/share/vc/vc.duckdb)
+# db_path = "~/.local/share/vc/vc.duckdb"
+
+# Poll interval in seconds (default: 120)
+poll_interval_secs = 120
+
+# Log level: trace, debug, info, warn, error (default: info)
+log_level = "info"
+
+# Storage engine: duckdb or sqlite (sqlite needs a build with the `sqlite`
+# feature, and only `vc db info` runs on it; other commands refuse it).
+# Default: chosen by db_path extension, .sqlite/.sqlite3 -> sqlite.
+# store_backend = "duckdb"
+
+# Timezone for human-facing timestamps: "local" (system TZ) or an IANA name
+# like "Europe/Berlin". JSON/TOON output is always UTC.
+timezone = "local"
+
+# Snapshots of this file kept for `vc config history` / `diff` / `rollback`
+# (0 turns snapshotting off)
+config_history_keep = 50
+
+# Seconds the CLI waits for the database to open before giving up (a dead
+# NFS mount then fails with a message instead of hanging)
+open_timeout_secs = 30
+
+[collectors]
+# Enable/disable individual collectors
+fallback_probe = true   # Always-on baseline probe (no external tooling needed)
+sysmoni = true          # System metrics (CPU, memory, disk, network)
+ru = true               # Repo updater status
+caut = true             # Claude usage tracking
+caam = true             # Claude account management
+cass = true             # Claude session search
+mcp_agent_mail = true   # MCP Agent Mail
+ntm = true              # Named Tmux Manager
+rch = true              # Remote Compilation Helper
+rano = true             # Network observer
+dcg = true              # Dangerous command guard
+pt = true               # Process tracker
+bv_br = true            # Beads (issue tracker); also accepted as `beads`
+github = false          # GitHub (requires a token)
+accounts = true         # Provider quota / rate-limit state (see [accounts])
+agent_inventory = true  # Running agents in tmux panes and processes
+
+# Collector timeout in seconds
+timeout_secs = 30
+
+# Shared collector backpressure limits
+max_concurrent_collectors = 8
+max_concurrent_per_machine = 4
+
+# Oldest acceptable tool versions; older machines are flagged by
+# `vc robot triage` and `vc fleet versions`
+# [collectors.min_versions]
+# sysmoni = "0.5.0"
+# vc-node = "0.1.0"
+
+[alerts]
+enabled = true
+default_cooldown_secs = 300
+# webhook_url = "https://example.com/webhook"
+# slack_webhook_url = "secret://file/~/.config/vc/slack_webhook"
+desktop_notifications = false
+
+# Secrets (webhook URLs, the SMTP password, replication.token and
+# telemetry.headers values) can be references instead of literal values:
+# secret://file/<path>, secret://env/<VAR> or secret://cmd/<command>. They are
+# resolved when used; check them all with `vc config verify-secrets`.
+
+# Email over SMTP. The password comes from password_file, password_env or a
+# secret:// reference in password, never from this file. Try it with `vc alert test-notify --sink email`.
+# [alerts.email]
+# server = "smtp.example.com"
+# tls = "starttls"              # none, starttls or tls
+# username = "vc@example.com"
+# password_file = "~/.config/vc/smtp_password"
+# from = "vc@example.com"
+# to = ["ops@example.com"]
+# min_severity = "critical"
+# web_url = "https://vc.example.com"
+
+# Replace a sink's default message with a template (or file = "path").
+# Placeholders: {{machine.id}}, {{machine.hostname}}, {{machine.health_score}},
+# {{alert.severity}}, {{alert.title}}, {{alert.message}}, {{alert.rule_id}},
+# {{alert.occurrences}}, {{alert.fired_at}} and {{links.web_ui}}. Unknown ones
+# are sent as written. Preview with `vc alert test-notify --sink slack --render-only`.
+# [alerts.templates.slack]
+# template = "{{alert.severity}} on {{machine.hostname}}: {{alert.message}}"
+
+# Batch alerts below min_severity into one grouped message per sink.
+# Critical alerts are always delivered immediately.
+# [alerts.digest.slack]
+# min_severity = "warning"
+# flush_interval_secs = 1800
+# max_batch_size = 50
+
+# Escalate alert groups (one rule on one machine) that stay open and
+# unacknowledged. Each step applies once, when the group's oldest open
+# occurrence reaches after_secs: bump_severity, notify (another sink) or
+# open_incident. Acknowledging or resolving the alerts stops escalation.
+# [[alerts.escalation]]
+# name = "unacked-warnings"
+# severity = "warning"          # also alert_type = "disk-full" or group = "orko/disk-full"
+# steps = [
+#   { after_secs = 21600, action = "bump_severity" },
+#   { after_secs = 21600, action = "notify", sink = "email" },
+#   { after_secs = 86400, action = "open_incident" },
+# ]
+
+# Change a rule's threshold, duration_secs or severity on some machines
+# without redefining it. A machine override beats a tag override, which beats
+# the rule itself; among matching tags the highest priority wins, then the
+# first listed. `vc alert rules --machine <id>` shows the result.
+# [[alerts.overrides]]
+# rule = "disk-critical"
+# tag = "build"                 # or machine = "orko"
+# priority = 10
+# threshold = 95.0
+# severity = "warning"
+
+# Group open alerts on one machine that fire within window_secs of each other
+# and treat the most severe as the probable root (ties go to the earliest
+# causal_order match, then the earliest fired). Triage shows one item per
+# group; `vc alert show --correlation <id>` lists it.
+[alerts.correlation]
+enabled = true
+window_secs = 600
+causal_order = ["disk", "memory", "offline", "collector", "session", "agent"]
+
+# Warn when a collector stops producing fresh data on a machine; escalate to
+# critical at critical_multiplier x the threshold. A resolved pair must stay
+# fresh for min_resolved_secs before it can fire again.
+[alerts.staleness]
+enabled = true
+stale_threshold_secs = 600
+critical_multiplier = 6
+min_resolved_secs = 900
+# [alerts.staleness.collectors]
+# cass = 3600
+
+[autopilot]
+enabled = false
+min_confidence = 0.8
+auto_switch_accounts = false
+switch_threshold = 0.75
+preemptive_mins = 15
+
+[tui]
+refresh_ms = 1000
+inline_mode = false
+inline_height = 20
+mouse_support = true
+theme = "default"
+show_charts = true
+
+[web]
+enabled = false
+bind_address = "127.0.0.1"
+port = 8080
+# Per-caller limit for POST /api/query/template (0 = unlimited)
+query_rate_limit_per_min = 30
+
+# Require HMAC-signed pushes to POST /api/replication/batch and POST
+# /api/annotations. Requests whose timestamp is more than max_skew_secs off,
+# or whose signature was already used, are refused. Script pushers can sign
+# with `vc node sign`.
+[web.signing]
+enabled = false
+# secret = "secret://env/VC_SIGNING_SECRET"
+max_skew_secs = 300
+replay_cache_size = 10000
+
+# GET /api/events: alerts and health changes pushed to dashboards. A client
+# that falls behind loses heartbeats, then repeated health changes, then its
+# oldest events (it is told how many).
+[web.events]
+queue_size = 256
+poll_interval_secs = 5
+
+[daemon]
+# Serve the watch event stream to local agents (`vc watch --connect <path>`)
+# watch_socket = "/run/user/1000/vc-watch.sock"
+watch_queue_size = 256
+# Seconds without a heartbeat before another daemon may take over the store
+lease_ttl_secs = 300
+
+[daemon.limits]
+# Shed load when the daemon's resident memory exceeds this (MiB)
+# max_rss_mb = 512
+# Pause all writes except alerts when the store volume has less free space (MiB)
+# min_free_disk_mb = 1024
+poll_stretch_factor = 4
+recovery_ratio = 0.9
+
+# High-frequency audit events. Guardian, autopilot and user-command events
+# are always written individually and cannot be listed here.
+[audit]
+batch_size = 100
+rollup_window_secs = 3600
+
+[audit.events.collector_run]
+# "all", "rollup", or "sample" with keep_one_in = N
+mode = "rollup"
+
+# Provider CLI commands polled by the accounts collector. Output that cannot
+# be parsed is stored raw and reported as a collector parse error.
+[[accounts.commands]]
+command = "caam limits --format json"
+# provider = "claude"   # Used when the output does not name a provider
+# central = false       # true: run once locally instead of on every machine
+
+# Agent processes the agent_inventory collector looks for: the first pattern
+# found in a command line names its agent type. Machines without tmux are
+# inventoried from the process table alone.
+[[agent_inventory.patterns]]
+agent_type = "claude"
+pattern = "claude"
+
+[[agent_inventory.patterns]]
+agent_type = "codex"
+pattern = "codex"
+
+# Semantic knowledge search (builds with the `embeddings` feature only).
+# Set exactly one of `command` (text on stdin, JSON float array on stdout) or
+# `endpoint` (OpenAI-compatible /v1/embeddings). Entries added before this was
+# enabled are embedded by `vc knowledge reindex`.
+[knowledge.embeddings]
+enabled = false
+# command = "embed-text --model all-minilm"
+# endpoint = "http://localhost:11434/v1/embeddings"
+model = "default"
+semantic_weight = 0.7   # Cosine share of the ranking; the rest is keyword score
+timeout_secs = 10
+
+# Finished agent sessions are classified as success, partial, failed or
+# abandoned from their exit status, tool-call error density and how the
+# transcript ends. Override per agent type with [knowledge.outcomes.agents.<program>].
+# After changing these, `vc sessions classify --backfill` reclassifies history.
+[knowledge.outcomes.default]
+failed_error_ratio = 0.5    # Share of errored tool calls that means failed
+partial_error_ratio = 0.2   # ...that means at best partial
+abandoned_max_turns = 2     # This few turns without a sign of success: abandoned
+tail_messages = 3           # Closing messages searched for the phrases
+success_phrases = ["all tests pass", "tests pass", "successfully", "is complete", "completed", "fixed", "done"]
+failure_phrases = ["unable to", "i couldn't", "i could not", "still failing", "giving up", "blocked on"]
+
+# [knowledge.outcomes.agents.codex]
+# failed_error_ratio = 0.6
+
+# OpenTelemetry export (builds with the `telemetry` feature only). Alert
+# firings/resolutions, incident lifecycle events and collector failures are
+# sent as OTLP/HTTP JSON log records. Check progress with `vc telemetry status`.
+[telemetry]
+enabled = false
+# otlp_endpoint = "http://localhost:4318/v1/logs"
+service_name = "vibe_cockpit"
+batch_size = 200
+max_retries = 3
+timeout_secs = 10
+
+# [telemetry.headers]
+# "x-api-key" = "..."
+
+# Warm standby replication. The primary daemon ships new rows to the standby's
+# `vc web` server; the standby refuses local collection until `vc db promote`.
+# Check lag on either side with `vc db replication status`.
+[replication]
+mode = "primary"        # "primary" or "standby"
+# standby_url = "http://standby:8080"
+# token = "secret://env/VC_STANDBY_TOKEN"   # Operator-role token on the standby
+# signing_secret = "secret://env/VC_SIGNING_SECRET"   # The standby's web.signing.secret
+interval_secs = 15
+batch_size = 1000       # Rows per table per shipment
+timeout_secs = 30
+tables = []             # Empty = every table with a timestamp column
+
+# Bundles pushed by vc-node agents. Rows already seen within the lookback are
+# skipped even when resent under a new bundle ID (0 = batch-level dedup only).
+[ingest]
+dedup_lookback_hours = 48
+clock_skew_warn_secs = 60  # Warn when a machine's clock is off by more (0 = off)
+
+# Query fingerprints, callers and timings for `vc db slow-queries`. Buffered
+# in memory and flushed to the query_log table, which keeps the newest rows.
+[query_log]
+enabled = true
+max_rows = 50000
+buffer_size = 500          # Queries buffered before a forced flush
+flush_interval_secs = 30
+
+# Size guard on every value written to the store. Oversized strings keep
+# their head plus a marker and oversized JSON becomes a marker object
+# (policy = "truncate"), or the row is dropped (policy = "reject").
+[write_limits]
+enabled = true
+max_text_bytes = 4194304      # 4 MiB
+max_json_bytes = 16777216     # 16 MiB
+policy = "truncate"
+
+# Limits on every query run from the CLI, web API or MCP. Agent queries (MCP,
+# natural language) get the shorter agent_max_execution_ms. With
+# allow_raw_sql = false only the named query templates run.
+[query]
+max_rows = 10000
+max_execution_ms = 30000
+agent_max_execution_ms = 5000
+max_output_bytes = 10485760   # 10 MiB
+allow_raw_sql = true
+
+# Raw queries agents propose over MCP wait for `vc query approve <id>`; ones
+# nobody decides on expire after ttl_secs.
+[query_approval]
+ttl_secs = 86400
+
+# MCP tools per agent role. `vc mcp serve --role triage` (else default_role)
+# only lists and calls the role's tools; with no role a session gets every
+# tool, or none when deny_by_default is set. A tool result larger than
+# max_response_bytes is cut at a row boundary and marked truncated.
+[mcp]
+# default_role = "triage"
+deny_by_default = false
+max_response_bytes = 262144
+
+# [mcp.roles.triage]
+# tools = ["vc_fleet_status", "vc_query_alerts", "vc_query_incidents", "vc_timeline"]
+
+# The daemon runs ANALYZE on tables whose rows changed by change_ratio since
+# their last one, during the quiet window (UTC hours; start = end: any time).
+# `vc db analyze` runs it by hand.
+[analyze]
+enabled = true
+change_ratio = 0.2
+min_changes = 1000         # Fewer changes never make a table due
+quiet_start_hour = 2
+quiet_end_hour = 5
+max_tables_per_tick = 2
+
+# Machines in maintenance (`vc machines maintenance <id> --on`) are not
+# collected from and leave the fleet aggregate. `vc status` warns loudly
+# about any still in maintenance after max_hours (0 = never).
+[maintenance]
+max_hours = 72
+
+# A probe finding a machine's UUID marker under another machine id means the
+# host was re-added under a new name. By default `vc machines probe` only
+# reports it (fix it with `vc machines merge <old> --into <new>`); with
+# auto_rename the probe moves the old id's history to the new one itself.
+[identity]
+auto_rename = false
+
+# Component health shown by `vc doctor` and GET /api/health/components.
+# The overall status is the worst component's after weighting; a weight of
+# 0.5 turns a failure into a degradation, 0 or `ignore` leaves it out.
+[health_components]
+cache_ttl_secs = 10
+ignore = []
+ingest_backlog_max = 100
+notification_silence_secs = 86400
+replication_lag_warn_secs = 300
+replication_lag_fail_secs = 3600
+
+[health_components.weights]
+# notifications = 0.5
+
+# `vc fleet run --command ...` runs one command on every selected machine.
+# Output past max_output_bytes per stream and machine is dropped, and the
+# CLI asks before running on more than confirm_above machines. Web callers
+# below admin may only run commands an allow pattern matches in full and no
+# deny pattern does (regexes), and never ones with shell metacharacters
+# (; & | ` $( or newlines); with no allow patterns the endpoint is admin-only.
+[fleet_run]
+allow = []
+deny = []
+max_output_bytes = 65536
+confirm_above = 10
+
+# Incident SLA targets, timed from when the incident started against its
+# current severity. `vc incident list --breached` shows the incidents that
+# missed one; the daemon notes each breach on the timeline and tells
+# breach_sink when set.
+# [incidents]
+# breach_sink = "slack"
+[incidents.sla.critical]
+ack_secs = 900
+mitigate_secs = 3600
+resolve_secs = 14400
+
+[incidents.sla.warning]
+ack_secs = 3600
+mitigate_secs = 14400
+resolve_secs = 86400
+
+# Disk forecasts (`vc health forecast`): per-mount usage averaged into
+# bucket_secs buckets over window_days, fitted with a trend that ignores
+# short bursts and restarts after a cleanup. The `disk-forecast-full` rule
+# warns at 7 days to full; change it with [[alerts.overrides]].
+[forecast]
+window_days = 14
+bucket_secs = 3600
+horizon_days = 30
+
+# Services expected to be running (checked on probe and every collection).
+# Set exactly one of `process` (regex over command lines) or `unit`
+# (systemd unit / launchd label). Machines tagged with an `optional_tags`
+# entry are not flagged when the service is missing.
+# [[services]]
+# name = "tmux"
+# process = "tmux.*server|^tmux"
+#
+# [[services]]
+# name = "model-server"
+# unit = "model-server.service"
+# optional_tags = ["laptop"]
+
+# Offload transcripts older than offload_after_days out of the database to a
+# directory or an S3-compatible bucket (set one of dir / s3_endpoint). Reads
+# fetch them back transparently; `vc db artifacts verify` checks the pointers.
+[storage.artifacts]
+enabled = false
+# dir = "~/.local/share/vc/artifacts"
+# s3_endpoint = "https://s3.us-east-1.amazonaws.com"
+# bucket = "vc-artifacts"
+# access_key = "secret://env/VC_S3_ACCESS_KEY"
+# secret_key = "secret://env/VC_S3_SECRET_KEY"
+region = "us-east-1"
+prefix = "vc"
+offload_after_days = 30
+batch_size = 100        # Most transcripts moved per daemon tick
+timeout_secs = 60
+
+# Machine inventory (uncomment and customize for remote monitoring)
+# [machines.local]
+# name = "Local Machine"
+# enabled = true
+# tags = ["primary"]
+#
+# HTTP endpoints of services on a machine, checked with curl on every
+# collection (`vc health checks`). `from = "hub"` sends the request from the
+# cockpit instead of the machine itself.
+# [[machines.local.http_checks]]
+# name = "model-server"
+# url = "http://localhost:8080/health"
+# expect_status = 200
+# body_contains = "ok"
+# timeout_secs = 5
+
+# [machines.remote-server]
+# name = "Remote Server"
+# ssh_host = "192.168.1.100"
+# ssh_user = "ubuntu"
+# ssh_port = 22
+# # ssh_key = "~/.ssh/id_ed25519"
+# enabled = true
+# tags = ["worker", "builder"]
+
+# Profiles override the settings above when selected with --profile <name>
+# or VC_PROFILE. Tables merge key by key; values and arrays are replaced.
+# [profiles.dev.global]
+# db_path = "./dev.duckdb"
+# log_level = "debug"
+#
+# [profiles.prod.alerts]
+# desktop_notifications = true
+"#
+        .to_string()
+    }
+
+    /// Generate a TOML representation of this config.
+    ///
+    /// # Errors
+    /// Returns an error if serialization fails.
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        toml::to_string_pretty(self)
+            .map_err(|e| ConfigError::ValidationError(format!("Failed to serialize config: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_default_config() {
+        let config = VcConfig::default();
+        assert!(config.collectors.sysmoni);
+        assert_eq!(config.global.poll_interval_secs, 120);
+        assert_eq!(config.global.log_level, "info");
+        assert_eq!(config.collectors.max_concurrent_collectors, 8);
+        assert_eq!(config.collectors.max_concurrent_per_machine, 4);
+    }
+
+    #[test]
+    fn test_config_validation_poll_interval() {
+        let mut config = VcConfig::default();
+        config.global.poll_interval_secs = 0;
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("poll_interval_secs")
+        );
+    }
+
+    #[test]
+    fn test_config_validation_collector_timeout() {
+        let mut config = VcConfig::default();
+        config.collectors.timeout_secs = 0;
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("timeout_secs"));
+    }
+
+    #[test]
+    fn test_collectors_min_versions_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [collectors.min_versions]
+            sysmoni = "0.5.0"
+            vc-node = "v0.1"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config
+                .collectors
+                .min_versions
+                .get("sysmoni")
+                .map(String::as_str),
+            Some("0.5.0")
+        );
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config
+            .collectors
+            .min_versions
+            .insert("sysmoni".to_string(), "latest".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("min_versions.sysmoni"));
+    }
+
+    #[test]
+    fn test_config_validation_collector_concurrency() {
+        let mut config = VcConfig::default();
+        config.collectors.max_concurrent_collectors = 0;
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("max_concurrent_collectors")
+        );
+
+        let mut config = VcConfig::default();
+        config.collectors.max_concurrent_per_machine = 0;
+        let result = config.validate();
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("max_concurrent_per_machine")
+        );
+    }
+
+    #[test]
+    fn test_daemon_limits_default_off() {
+        let config = VcConfig::default();
+        assert!(config.daemon.limits.max_rss_mb.is_none());
+        assert!(config.daemon.limits.min_free_disk_mb.is_none());
+        assert_eq!(config.daemon.limits.poll_stretch_factor, 4);
+    }
+
+    #[test]
+    fn test_daemon_limits_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r"
+            [daemon.limits]
+            max_rss_mb = 256
+            min_free_disk_mb = 512
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.daemon.limits.max_rss_mb, Some(256));
+        assert_eq!(config.daemon.limits.min_free_disk_mb, Some(512));
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config.daemon.limits.recovery_ratio = 1.5;
+        assert!(config.validate().is_err());
+
+        let mut config = VcConfig::default();
+        config.daemon.limits.poll_stretch_factor = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = VcConfig::default();
+        config.daemon.lease_ttl_secs = 0;
+        assert!(config.validate().is_err());
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [daemon]
+            watch_socket = "/tmp/vc-watch.sock"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.daemon.watch_socket.as_deref(),
+            Some(Path::new("/tmp/vc-watch.sock"))
+        );
+        assert_eq!(config.daemon.watch_queue_size, 256);
+
+        let mut config = VcConfig::default();
+        config.daemon.watch_queue_size = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_web_event_stream_settings() {
+        let config: VcConfig = toml::from_str("[web.events]\nqueue_size = 32\n").unwrap();
+        assert_eq!(config.web.events.queue_size, 32);
+        assert_eq!(config.web.events.poll_interval_secs, 5);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config.web.events.queue_size = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_write_limits_settings() {
+        let config = VcConfig::default();
+        assert!(config.write_limits.enabled);
+        assert_eq!(config.write_limits.policy, OversizePolicy::Truncate);
+
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [write_limits]
+            max_text_bytes = 65536
+            policy = "reject"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.write_limits.max_text_bytes, 65_536);
+        assert_eq!(config.write_limits.max_json_bytes, 16 * 1024 * 1024);
+        assert_eq!(config.write_limits.policy, OversizePolicy::Reject);
+        assert!(config.validate().is_ok());
+        config.write_limits.max_json_bytes = 100;
+        assert!(config.validate().is_err());
+        config.write_limits.enabled = false;
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_query_log_settings() {
+        let config = VcConfig::default();
+        assert!(config.query_log.enabled);
+        assert_eq!(config.query_log.max_rows, 50_000);
+
+        let mut config: VcConfig = toml::from_str(
+            r"
+            [query_log]
+            enabled = false
+            max_rows = 0
+            ",
+        )
+        .unwrap();
+        assert!(!config.query_log.enabled);
+        assert_eq!(config.query_log.flush_interval_secs, 30);
+        // A disabled log may carry any limits
+        assert!(config.validate().is_ok());
+        config.query_log.enabled = true;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_query_guardrails() {
+        let config = VcConfig::default();
+        assert_eq!(config.query.max_rows, 10_000);
+        assert!(config.query.allow_raw_sql);
+
+        let config: VcConfig =
+            toml::from_str("[query]\nallow_raw_sql = false\nmax_rows = 50\n").unwrap();
+        assert!(!config.query.allow_raw_sql);
+        assert_eq!(config.query.max_rows, 50);
+        assert_eq!(config.query.agent_max_execution_ms, 5_000);
+
+        let config: VcConfig = toml::from_str("[query]\nmax_execution_ms = 0\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_query_approval_ttl() {
+        let config = VcConfig::default();
+        assert_eq!(config.query_approval.ttl_secs, 86_400);
+
+        let config: VcConfig = toml::from_str("[query_approval]\nttl_secs = 0\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_mcp_roles() {
+        let config: VcConfig = toml::from_str(
+            r#"
+[mcp]
+default_role = "triage"
+
+[mcp.roles.triage]
+tools = ["vc_fleet_status", "vc_query_alerts"]
+
+[mcp.roles.remediation]
+tools = ["vc_fleet_status", "vc_query_alerts", "vc_propose_query"]
+"#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.mcp.allowed_tools(None).unwrap().unwrap().len(), 2);
+        assert_eq!(
+            config
+                .mcp
+                .allowed_tools(Some("remediation"))
+                .unwrap()
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(config.mcp.allowed_tools(Some("admin")).is_err());
+
+        let mut open = VcConfig::default();
+        assert!(open.mcp.allowed_tools(None).unwrap().is_none());
+        open.mcp.deny_by_default = true;
+        assert_eq!(open.mcp.allowed_tools(None).unwrap(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_mcp_role_typos_are_rejected() {
+        let config: VcConfig = toml::from_str(
+            "[mcp.roles.triage]\ntools = [\"vc_fleet_status\", \"vc_query_alert\"]\n",
+        )
+        .unwrap();
+        assert!(config.validate().is_err());
+        let lint = config.lint();
+        assert!(
+            lint.issues
+                .iter()
+                .any(|issue| issue.path == "mcp.roles.triage.tools"
+                    && issue.message.contains("vc_query_alert"))
+        );
+
+        let config: VcConfig = toml::from_str("[mcp]\ndefault_role = \"triage\"\n").unwrap();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_analyze_quiet_window() {
+        let mut config = VcConfig::default();
+        assert!(config.analyze.in_quiet_window(2));
+        assert!(!config.analyze.in_quiet_window(5));
+
+        config.analyze.quiet_start_hour = 22;
+        config.analyze.quiet_end_hour = 3;
+        assert!(config.analyze.in_quiet_window(23));
+        assert!(config.analyze.in_quiet_window(0));
+        assert!(!config.analyze.in_quiet_window(12));
+
+        config.analyze.quiet_end_hour = 22;
+        assert!(config.analyze.in_quiet_window(12));
+
+        config.analyze.quiet_end_hour = 24;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_maintenance_settings() {
+        assert_eq!(VcConfig::default().maintenance.max_hours, 72);
+        let config: VcConfig = toml::from_str(
+            r"
+            [maintenance]
+            max_hours = 0
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.maintenance.max_hours, 0);
+    }
+
+    #[test]
+    fn test_identity_settings() {
+        assert!(!VcConfig::default().identity.auto_rename);
+        let config: VcConfig = toml::from_str(
+            r"
+            [identity]
+            auto_rename = true
+            ",
+        )
+        .unwrap();
+        assert!(config.identity.auto_rename);
+    }
+
+    #[test]
+    fn test_health_components_settings() {
+        let defaults = VcConfig::default();
+        assert_eq!(defaults.health_components.cache_ttl_secs, 10);
+        assert!(defaults.health_components.weights.is_empty());
+
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [health_components]
+            ignore = ["replication"]
+
+            [health_components.weights]
+            notifications = 0.5
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.health_components.ignore, vec!["replication"]);
+        assert!(config.validate().is_ok());
+
+        config
+            .health_components
+            .weights
+            .insert("ingest".to_string(), -1.0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_fleet_run_settings() {
+        let defaults = VcConfig::default();
+        assert_eq!(defaults.fleet_run.max_output_bytes, 65_536);
+        assert!(defaults.fleet_run.check_command("uname -r", true).is_ok());
+        assert!(defaults.fleet_run.check_command("uname -r", false).is_err());
+
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [fleet_run]
+            allow = ["uptime", "df( -h)? /\\S*", "uname .*"]
+            deny = ["uname -a"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        let fleet_run = &config.fleet_run;
+        assert!(fleet_run.check_command("uptime", false).is_ok());
+        assert!(fleet_run.check_command("df -h /", false).is_ok());
+        assert!(fleet_run.check_command("uname -r", false).is_ok());
+        assert!(fleet_run.check_command("uname -a", false).is_err());
+        assert!(fleet_run.check_command("rm -rf /", false).is_err());
+        assert!(fleet_run.check_command("rm -rf /", true).is_ok());
+        // Patterns match the whole command
+        assert!(fleet_run.check_command("uptime --help", false).is_err());
+        assert!(fleet_run.check_command("sudo uptime", false).is_err());
+        // Chained commands and command substitution, even where a pattern
+        // would take them
+        for chained in [
+            "uptime; rm -rf ~",
+            "uptime && rm -rf ~",
+            "uptime || rm -rf ~",
+            "uptime | sh",
+            "uptime\nrm -rf ~",
+            "uname $(rm -rf ~)",
+            "uname `rm -rf ~`",
+        ] {
+            assert!(
+                fleet_run.check_command(chained, false).is_err(),
+                "{chained}"
+            );
+            assert!(fleet_run.check_command(chained, true).is_ok(), "{chained}");
+        }
+
+        config.fleet_run.deny.push("(".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_forecast_settings() {
+        let defaults = VcConfig::default();
+        assert_eq!(defaults.forecast.window_days, 14);
+        assert_eq!(defaults.forecast.horizon_days, 30);
+
+        let mut config: VcConfig = toml::from_str(
+            r"
+            [forecast]
+            window_days = 30
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.forecast.window_days, 30);
+        assert_eq!(config.forecast.bucket_secs, 3600);
+        assert!(config.validate().is_ok());
+        config.forecast.bucket_secs = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_incident_sla_settings() {
+        let defaults = VcConfig::default();
+        assert_eq!(defaults.incidents.sla["critical"].ack_secs, Some(900));
+        assert!(!defaults.incidents.sla.contains_key("info"));
+
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [incidents]
+            breach_sink = "slack"
+
+            [incidents.sla.info]
+            resolve_secs = 604800
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            config.incidents.sla["info"],
+            SlaTargets {
+                resolve_secs: Some(604_800),
+                ..SlaTargets::default()
+            }
+        );
+
+        config.incidents.breach_sink = Some("pager".to_string());
+        assert!(config.validate().is_err());
+        config.incidents.breach_sink = None;
+        config.incidents.sla.insert(
+            "sev1".to_string(),
+            SlaTargets {
+                ack_secs: Some(60),
+                ..SlaTargets::default()
+            },
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_clock_skew_settings_parse() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [ingest]
+            clock_skew_warn_secs = 120
+
+            [machines.orko]
+            name = "orko"
+            normalize_clock_skew = true
+
+            [machines.bender]
+            name = "bender"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.ingest.clock_skew_warn_secs, 120);
+        assert_eq!(config.ingest.dedup_lookback_hours, 48);
+        assert!(config.machines["orko"].normalize_clock_skew);
+        assert!(!config.machines["bender"].normalize_clock_skew);
+    }
+
+    #[test]
+    fn test_alert_digest_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [alerts.digest.slack]
+            min_severity = "critical"
+            flush_interval_secs = 600
+            "#,
+        )
+        .unwrap();
+        let slack = &config.alerts.digest["slack"];
+        assert_eq!(slack.min_severity, "critical");
+        assert_eq!(slack.flush_interval_secs, 600);
+        assert_eq!(slack.max_batch_size, 50);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config
+            .alerts
+            .digest
+            .insert("pager".to_string(), DigestConfig::default());
+        assert!(config.validate().unwrap_err().to_string().contains("pager"));
+
+        let mut config = VcConfig::default();
+        config.alerts.digest.insert(
+            "webhook".to_string(),
+            DigestConfig {
+                min_severity: "loud".to_string(),
+                ..DigestConfig::default()
+            },
+        );
+        assert!(config.validate().is_err());
+
+        let mut config = VcConfig::default();
+        config.alerts.digest.insert(
+            "webhook".to_string(),
+            DigestConfig {
+                max_batch_size: 0,
+                ..DigestConfig::default()
+            },
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_alert_escalation_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [[alerts.escalation]]
+            name = "unacked-warnings"
+            severity = "warning"
+            steps = [
+              { after_secs = 21600, action = "bump_severity" },
+              { after_secs = 21600, action = "notify", sink = "email" },
+              { after_secs = 86400, action = "open_incident" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let policy = &config.alerts.escalation[0];
+        assert_eq!(policy.name, "unacked-warnings");
+        assert_eq!(policy.alert_type, None);
+        assert_eq!(policy.steps.len(), 3);
+        assert_eq!(policy.steps[1].sink.as_deref(), Some("email"));
+        assert!(config.validate().is_ok());
+
+        let broken = |edit: fn(&mut EscalationPolicy)| {
+            let mut config = config.clone();
+            edit(&mut config.alerts.escalation[0]);
+            config.validate().unwrap_err().to_string()
+        };
+        assert!(broken(|p| p.steps[1].sink = None).contains("sink"));
+        assert!(broken(|p| p.steps[0].action = "page".to_string()).contains("page"));
+        assert!(broken(|p| p.steps[2].after_secs = 60).contains("in order"));
+        assert!(broken(|p| p.steps.clear()).contains("no steps"));
+        assert!(broken(|p| p.severity = Some("loud".to_string())).contains("loud"));
+
+        let mut config = config.clone();
+        config
+            .alerts
+            .escalation
+            .push(config.alerts.escalation[0].clone());
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("Duplicate")
+        );
+    }
+
+    #[test]
+    fn test_alert_overrides_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [[alerts.overrides]]
+            rule = "disk-critical"
+            tag = "build"
+            priority = 10
+            threshold = 95.0
+
+            [[alerts.overrides]]
+            rule = "disk-critical"
+            machine = "orko"
+            severity = "warning"
+            "#,
+        )
+        .unwrap();
+        let tagged = &config.alerts.overrides[0];
+        assert_eq!(tagged.scope(), "tag:build");
+        assert!(tagged.matches("any", &["build".to_string()]));
+        assert!(!tagged.matches("any", &[]));
+        assert_eq!(config.alerts.overrides[1].scope(), "machine:orko");
+        assert!(config.validate().is_ok());
+
+        let broken = |edit: fn(&mut AlertRuleOverride)| {
+            let mut config = config.clone();
+            edit(&mut config.alerts.overrides[0]);
+            config.validate().unwrap_err().to_string()
+        };
+        assert!(broken(|o| o.machine = Some("orko".to_string())).contains("exactly one"));
+        assert!(broken(|o| o.tag = None).contains("exactly one"));
+        assert!(broken(|o| o.threshold = None).contains("changes nothing"));
+        assert!(broken(|o| o.duration_secs = Some(0)).contains("duration_secs"));
+        assert!(broken(|o| o.severity = Some("loud".to_string())).contains("loud"));
+
+        let mut config = config.clone();
+        config
+            .alerts
+            .overrides
+            .push(config.alerts.overrides[1].clone());
+        assert!(
+            config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("Duplicate")
+        );
+    }
+
+    #[test]
+    fn test_alert_email_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [alerts.email]
+            server = "smtp.example.com"
+            tls = "tls"
+            username = "vc"
+            password_env = "VC_TEST_SMTP_PASSWORD_UNSET"
+            from = "vc@example.com"
+            to = ["ops@example.com"]
+            "#,
+        )
+        .unwrap();
+        let email = config.alerts.email.as_ref().unwrap();
+        assert_eq!(email.port(), 465);
+        assert_eq!(email.min_severity, "critical");
+        assert!(config.validate().is_ok());
+        assert!(email.resolve_password().is_err());
+
+        let inline: VcConfig = toml::from_str(
+            r#"
+            [alerts.email]
+            server = "smtp.example.com"
+            password = "hunter2"
+            from = "vc@example.com"
+            to = ["ops@example.com"]
+            "#,
+        )
+        .unwrap();
+        let err = inline.validate().unwrap_err().to_string();
+        assert!(err.contains("inline"));
+
+        let mut config = VcConfig::default();
+        config.alerts.email = Some(EmailConfig {
+            server: "smtp.example.com".to_string(),
+            from: "vc@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+            tls: "ssl3".to_string(),
+            ..EmailConfig::default()
+        });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_staleness_alert_parse_and_validate() {
+        let config: VcConfig = toml::from_str(
+            r"
+            [alerts.staleness]
+            stale_threshold_secs = 300
+
+            [alerts.staleness.collectors]
+            cass = 3600
+            ",
+        )
+        .unwrap();
+        let staleness = &config.alerts.staleness;
+        assert!(staleness.enabled);
+        assert_eq!(staleness.threshold_for("sysmoni"), 300);
+        assert_eq!(staleness.threshold_for("cass"), 3600);
+        assert_eq!(staleness.critical_multiplier, 6);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config
+            .alerts
+            .staleness
+            .collectors
+            .insert("ntm".to_string(), 0);
+        assert!(config.validate().is_err());
+
+        let mut config = VcConfig::default();
+        config.alerts.staleness.critical_multiplier = 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_alert_correlation_parse_and_validate() {
+        let config = VcConfig::default();
+        assert!(config.alerts.correlation.enabled);
+        assert_eq!(config.alerts.correlation.causal_order[0], "disk");
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [alerts.correlation]
+            window_secs = 120
+            causal_order = ["offline", "disk"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.alerts.correlation.window_secs, 120);
+        assert_eq!(config.alerts.correlation.causal_order, ["offline", "disk"]);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config.alerts.correlation.window_secs = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_audit_sampling_parse_and_validate() {
+        let config = VcConfig::default();
+        assert_eq!(config.audit.events["collector_run"], AuditSampling::Rollup);
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [audit.events.collector_run]
+            mode = "sample"
+            keep_one_in = 10
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.audit.events["collector_run"],
+            AuditSampling::Sample { keep_one_in: 10 }
+        );
+        assert_eq!(config.audit.batch_size, 100);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config
+            .audit
+            .events
+            .insert("guardian_action".to_string(), AuditSampling::Rollup);
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("guardian_action"), "{err}");
+
+        let mut config = VcConfig::default();
+        config.audit.events.insert(
+            "collector_run".to_string(),
+            AuditSampling::Sample { keep_one_in: 0 },
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_accounts_commands_parse_and_validate() {
+        let config = VcConfig::default();
+        assert_eq!(config.accounts.commands.len(), 1);
+        assert_eq!(
+            config.accounts.commands[0].command,
+            "caam limits --format json"
+        );
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [[accounts.commands]]
+            command = "codex status --json"
+            provider = "openai"
+            central = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.accounts.commands.len(), 1);
+        assert_eq!(
+            config.accounts.commands[0].provider.as_deref(),
+            Some("openai")
+        );
+        assert!(config.accounts.commands[0].central);
+        assert!(config.validate().is_ok());
+
+        let mut config = VcConfig::default();
+        config.accounts.commands[0].command = "  ".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_agent_inventory_patterns_parse_and_validate() {
+        let config = VcConfig::default();
+        assert!(config.collectors.agent_inventory);
+        assert_eq!(config.agent_inventory.patterns[0].agent_type, "claude");
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [[agent_inventory.patterns]]
+            agent_type = "opencode"
+            pattern = "opencode run"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.agent_inventory.patterns.len(), 1);
+        assert_eq!(config.agent_inventory.patterns[0].pattern, "opencode run");
+        assert!(config.validate().is_ok());
 
-        // Alerts enabled but no delivery channels configured
-        if self.alerts.enabled
-            && self.alerts.webhook_url.is_none()
-            && self.alerts.slack_webhook_url.is_none()
-            && self.alerts.discord_webhook_url.is_none()
-            && !self.alerts.desktop_notifications
-        {
-            result.add(LintIssue::warning(
-                "alerts",
-                "Alerts are enabled but no delivery channels are configured",
-            ));
-        }
+        let mut config = VcConfig::default();
+        config.agent_inventory.patterns[0].pattern = " ".to_string();
+        assert!(config.validate().is_err());
+    }
 
-        // === INFO ===
+    #[test]
+    fn test_knowledge_embeddings_validate() {
+        let config = VcConfig::default();
+        assert!(!config.knowledge.embeddings.enabled);
+        assert!(config.validate().is_ok());
+
+        let config: VcConfig = toml::from_str(
+            r#"
+            [knowledge.embeddings]
+            enabled = true
+            endpoint = "http://localhost:11434/v1/embeddings"
+            model = "nomic-embed-text"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.knowledge.embeddings.model, "nomic-embed-text");
+        assert!(config.validate().is_ok());
+
+        let mut both = config.clone();
+        both.knowledge.embeddings.command = Some("embed".to_string());
+        assert!(both.validate().is_err());
+
+        let mut neither = config.clone();
+        neither.knowledge.embeddings.endpoint = None;
+        assert!(neither.validate().is_err());
+
+        let mut weight = config;
+        weight.knowledge.embeddings.semantic_weight = 1.5;
+        assert!(weight.validate().is_err());
+    }
 
-        // No machines defined
-        if self.machines.is_empty() {
-            result.add(LintIssue::info(
-                "machines",
-                "No machines defined; vc will only monitor local machine",
-            ));
-        }
+    #[test]
+    fn test_session_outcome_overrides() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [knowledge.outcomes.default]
+            abandoned_max_turns = 1
+
+            [knowledge.outcomes.agents.codex]
+            failed_error_ratio = 0.7
+            success_phrases = ["task complete"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let outcomes = &config.knowledge.outcomes;
+        assert_eq!(outcomes.heuristics_for(None).abandoned_max_turns, 1);
+        assert_eq!(
+            outcomes.heuristics_for(Some("claude-code")),
+            &outcomes.default
+        );
+        let codex = outcomes.heuristics_for(Some("codex"));
+        assert!((codex.failed_error_ratio - 0.7).abs() < f64::EPSILON);
+        assert_eq!(codex.success_phrases, vec!["task complete".to_string()]);
+        assert_eq!(codex.abandoned_max_turns, 2);
+
+        let mut inverted = config;
+        inverted.knowledge.outcomes.default.partial_error_ratio = 0.9;
+        assert!(inverted.validate().is_err());
+    }
 
-        // All collectors disabled
-        if !self.collectors.sysmoni
-            && !self.collectors.ru
-            && !self.collectors.caut
-            && !self.collectors.caam
-        {
-            result.add(LintIssue::warning(
-                "collectors",
-                "All primary collectors are disabled; vc will collect minimal data",
-            ));
-        }
+    #[test]
+    fn test_secret_references() {
+        let path = std::env::temp_dir().join(format!("vc-config-secret-{}", std::process::id()));
+        std::fs::write(&path, "https://hooks.slack.com/services/T0/B0/xyz\n").unwrap();
+        let config: VcConfig = toml::from_str(&format!(
+            r#"
+            [alerts]
+            slack_webhook_url = "secret://file/{}"
+
+            [alerts.email]
+            server = "smtp.example.com"
+            username = "vc"
+            password = "secret://env/VC_TEST_SMTP_PASSWORD_UNSET"
+            from = "vc@example.com"
+            to = ["ops@example.com"]
+
+            [replication]
+            token = "secret://cmd/echo standby-token"
+            "#,
+            path.display()
+        ))
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        // Shown as written, never resolved
+        let shown = config.to_toml().unwrap();
+        assert!(shown.contains("secret://env/VC_TEST_SMTP_PASSWORD_UNSET"));
+        assert!(!shown.contains("hooks.slack.com"));
+
+        let checks = config.verify_secrets();
+        assert_eq!(checks.len(), 3);
+        let failed: Vec<_> = checks.iter().filter(|c| !c.ok).collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].key, "alerts.email.password");
+        assert!(
+            config
+                .alerts
+                .email
+                .as_ref()
+                .unwrap()
+                .resolve_password()
+                .is_err()
+        );
 
-        // Debug logging in production
-        if self.global.log_level.to_lowercase() == "trace"
-            || self.global.log_level.to_lowercase() == "debug"
+        #[cfg(unix)]
         {
-            result.add(LintIssue::info(
-                "global.log_level",
-                "Debug/trace logging enabled; logs may grow large",
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let lint = config.lint_deep();
+            assert!(lint.issues.iter().any(
+                |i| i.path == "alerts.slack_webhook_url" && i.severity == LintSeverity::Warning
             ));
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            assert!(
+                config
+                    .lint_deep()
+                    .issues
+                    .iter()
+                    .all(|i| i.path != "alerts.slack_webhook_url")
+            );
         }
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            config
+                .lint_deep()
+                .issues
+                .iter()
+                .any(|i| i.path == "alerts.slack_webhook_url" && i.severity == LintSeverity::Error)
+        );
 
-        // Machine with no tags
-        for (id, machine) in &self.machines {
-            if machine.tags.is_empty() {
-                result.add(LintIssue::info(
-                    format!("machines.{id}.tags"),
-                    format!("Machine '{id}' has no tags; consider adding tags for filtering"),
-                ));
-            }
-        }
+        let mut malformed = VcConfig::default();
+        malformed
+            .telemetry
+            .headers
+            .insert("x-api-key".to_string(), "secret://vault/vc".to_string());
+        let err = malformed.validate().unwrap_err().to_string();
+        assert!(err.contains("telemetry.headers.x-api-key"), "{err}");
+    }
 
-        result
+    #[test]
+    fn test_telemetry_config_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [telemetry]
+            enabled = true
+            otlp_endpoint = "http://localhost:4318/v1/logs"
+
+            [telemetry.headers]
+            "x-api-key" = "secret"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.telemetry.headers["x-api-key"], "secret");
+        assert_eq!(config.telemetry.service_name, "vibe_cockpit");
+        assert!(config.validate().is_ok());
+
+        let mut missing = config.clone();
+        missing.telemetry.otlp_endpoint = None;
+        assert!(missing.validate().is_err());
+
+        let mut batch = config;
+        batch.telemetry.batch_size = 0;
+        assert!(batch.validate().is_err());
     }
 
-    /// Generate a minimal default configuration as TOML string.
-    #[must_use]
-    pub fn generate_default_toml() -> String {
-        r#"# Vibe Cockpit Configuration
-# Generated by vc config wizard
+    #[test]
+    fn test_replication_config_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [replication]
+            mode = "standby"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.replication.mode, ReplicationMode::Standby);
+        assert_eq!(config.replication.interval_secs, 15);
+        assert!(config.validate().is_ok());
+
+        let mut shipping_standby = config.clone();
+        shipping_standby.replication.standby_url = Some("http://other:8080".to_string());
+        assert!(shipping_standby.validate().is_err());
+
+        let mut interval = config;
+        interval.replication.interval_secs = 0;
+        assert!(interval.validate().is_err());
+    }
 
-[global]
-# Path to DuckDB database (default: ~/.local/share/vc/vc.duckdb)
-# db_path = "~/.local/share/vc/vc.duckdb"
+    #[test]
+    fn test_lint_oidc_issuer_and_roles() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [web.oidc]
+            enabled = true
+            issuer = "https://sso.example.com/realms/ops"
+            client_id = "vibe-cockpit"
+            client_secret = "secret://env/VC_OIDC_CLIENT_SECRET"
+            redirect_url = "http://localhost:8080/auth/callback"
+
+            [web.oidc.role_map]
+            sre = "admin"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.web.oidc.groups_claim, "groups");
+        assert!(config.web.oidc.problems().is_empty());
+        assert!(!config.lint().has_errors());
+        assert!(
+            config
+                .secret_fields()
+                .iter()
+                .any(|(key, _)| key == "web.oidc.client_secret")
+        );
 
-# Poll interval in seconds (default: 120)
-poll_interval_secs = 120
+        let mut missing = config.clone();
+        missing.web.oidc.issuer = None;
+        let result = missing.lint();
+        assert!(result.has_errors());
+        assert!(result.issues.iter().any(|i| i.path == "web.oidc.issuer"));
 
-# Log level: trace, debug, info, warn, error (default: info)
-log_level = "info"
+        for bad in [
+            "sso.example.com",
+            "http://sso.example.com",
+            "https://",
+            "https://sso.example.com/?x=1",
+        ] {
+            let mut invalid = config.clone();
+            invalid.web.oidc.issuer = Some(bad.to_string());
+            let problems = invalid.web.oidc.problems();
+            assert_eq!(problems.len(), 1, "{bad}");
+            assert_eq!(problems[0].0, "web.oidc.issuer");
+        }
 
-[collectors]
-# Enable/disable individual collectors
-fallback_probe = true   # Always-on baseline probe (no external tooling needed)
-sysmoni = true          # System metrics (CPU, memory, disk, network)
-ru = true               # Repo updater status
-caut = true             # Claude usage tracking
-caam = true             # Claude account management
-cass = true             # Claude session search
-mcp_agent_mail = true   # MCP Agent Mail
-ntm = true              # Named Tmux Manager
-rch = true              # Remote Compilation Helper
-rano = true             # Network observer
-dcg = true              # Dangerous command guard
-pt = true               # Process tracker
-bv_br = true            # Beads (issue tracker); also accepted as `beads`
-github = false          # GitHub (requires a token)
+        let mut role = config.clone();
+        role.web
+            .oidc
+            .role_map
+            .insert("ops".to_string(), "root".to_string());
+        assert_eq!(role.web.oidc.problems()[0].0, "web.oidc.role_map.ops");
 
-# Collector timeout in seconds
-timeout_secs = 30
+        let mut off = missing;
+        off.web.oidc.enabled = false;
+        assert!(off.web.oidc.problems().is_empty());
+    }
 
-# Shared collector backpressure limits
-max_concurrent_collectors = 8
-max_concurrent_per_machine = 4
+    #[test]
+    fn test_web_signing_config_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [web.signing]
+            enabled = true
+            secret = "secret://env/VC_SIGNING_SECRET"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.web.signing.max_skew_secs, 300);
+        assert_eq!(config.web.signing.replay_cache_size, 10_000);
+        assert!(config.validate().is_ok());
+        assert!(
+            config
+                .secret_fields()
+                .iter()
+                .any(|(key, _)| key == "web.signing.secret")
+        );
 
-[alerts]
-enabled = true
-default_cooldown_secs = 300
-# webhook_url = "https://example.com/webhook"
-# slack_webhook_url = "https://hooks.slack.com/services/..."
-desktop_notifications = false
+        let mut no_secret = config.clone();
+        no_secret.web.signing.secret = None;
+        assert!(no_secret.validate().is_err());
+        no_secret.web.signing.enabled = false;
+        assert!(no_secret.validate().is_ok());
 
-[autopilot]
-enabled = false
-min_confidence = 0.8
-auto_switch_accounts = false
-switch_threshold = 0.75
-preemptive_mins = 15
+        let mut skew = config;
+        skew.web.signing.max_skew_secs = 0;
+        assert!(skew.validate().is_err());
+    }
 
-[tui]
-refresh_ms = 1000
-inline_mode = false
-inline_height = 20
-mouse_support = true
-theme = "default"
-show_charts = true
+    #[test]
+    fn test_artifact_storage_config_validate() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [storage.artifacts]
+            enabled = true
+            s3_endpoint = "http://minio:9000"
+            bucket = "vc"
+            access_key = "secret://env/VC_S3_ACCESS_KEY"
+            secret_key = "secret://env/VC_S3_SECRET_KEY"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.storage.artifacts.offload_after_days, 30);
+        assert_eq!(config.storage.artifacts.region, "us-east-1");
+        assert!(config.validate().is_ok());
+        assert!(
+            config
+                .secret_fields()
+                .iter()
+                .any(|(key, _)| key == "storage.artifacts.secret_key")
+        );
 
-[web]
-enabled = false
-bind_address = "127.0.0.1"
-port = 8080
+        let mut both = config.clone();
+        both.storage.artifacts.dir = Some(PathBuf::from("/tmp/vc"));
+        assert!(both.validate().is_err());
 
-# Machine inventory (uncomment and customize for remote monitoring)
-# [machines.local]
-# name = "Local Machine"
-# enabled = true
-# tags = ["primary"]
+        let mut no_bucket = config.clone();
+        no_bucket.storage.artifacts.bucket = None;
+        assert!(no_bucket.validate().is_err());
 
-# [machines.remote-server]
-# name = "Remote Server"
-# ssh_host = "192.168.1.100"
-# ssh_user = "ubuntu"
-# ssh_port = 22
-# # ssh_key = "~/.ssh/id_ed25519"
-# enabled = true
-# tags = ["worker", "builder"]
-"#
-        .to_string()
+        let mut nowhere = config;
+        nowhere.storage.artifacts.s3_endpoint = None;
+        assert!(nowhere.validate().is_err());
+        nowhere.storage.artifacts.enabled = false;
+        assert!(nowhere.validate().is_ok());
     }
 
-    /// Generate a TOML representation of this config.
-    ///
-    /// # Errors
-    /// Returns an error if serialization fails.
-    pub fn to_toml(&self) -> Result<String, ConfigError> {
-        toml::to_string_pretty(self)
-            .map_err(|e| ConfigError::ValidationError(format!("Failed to serialize config: {e}")))
+    #[test]
+    fn test_service_checks_by_tag() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [machines.laptop]
+            name = "Laptop"
+            tags = ["laptop"]
+
+            [machines.box]
+            name = "Box"
+            tags = ["server", "gpu"]
+
+            [[services]]
+            name = "tmux"
+            process = "tmux"
+
+            [[services]]
+            name = "model-server"
+            unit = "model-server.service"
+            optional_tags = ["laptop"]
+
+            [[services]]
+            name = "gpu-exporter"
+            process = "nvidia_gpu_exporter"
+            tags = ["gpu"]
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+
+        let laptop: Vec<_> = config
+            .service_checks_for(&config.machines["laptop"].tags)
+            .into_iter()
+            .map(|(s, required)| (s.name.as_str(), required))
+            .collect();
+        assert_eq!(laptop, vec![("tmux", true), ("model-server", false)]);
+
+        let server = config.service_checks_for(&config.machines["box"].tags);
+        assert_eq!(server.len(), 3);
+        assert!(server.iter().all(|(_, required)| *required));
+
+        // Untagged machines get every untagged service, all required
+        assert_eq!(config.service_checks_for(&[]).len(), 2);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
 
     #[test]
-    fn test_default_config() {
-        let config = VcConfig::default();
-        assert!(config.collectors.sysmoni);
-        assert_eq!(config.global.poll_interval_secs, 120);
-        assert_eq!(config.global.log_level, "info");
-        assert_eq!(config.collectors.max_concurrent_collectors, 8);
-        assert_eq!(config.collectors.max_concurrent_per_machine, 4);
+    fn test_service_check_validation() {
+        let service = |process: Option<&str>, unit: Option<&str>| ServiceCheckConfig {
+            name: "svc".to_string(),
+            process: process.map(ToString::to_string),
+            unit: unit.map(ToString::to_string),
+            tags: vec![],
+            optional_tags: vec![],
+        };
+
+        let mut config = VcConfig::default();
+        config.services = vec![service(None, None)];
+        assert!(config.validate().is_err());
+
+        config.services = vec![service(Some("x"), Some("x.service"))];
+        assert!(config.validate().is_err());
+
+        config.services = vec![service(Some("(unclosed"), None)];
+        assert!(config.validate().is_err());
+
+        config.services = vec![service(None, Some("x.service; rm -rf /"))];
+        assert!(config.validate().is_err());
+
+        config.services = vec![service(None, Some("vc-node@1.service"))];
+        assert!(config.validate().is_ok());
     }
 
     #[test]
-    fn test_config_validation_poll_interval() {
-        let mut config = VcConfig::default();
-        config.global.poll_interval_secs = 0;
-        let result = config.validate();
-        assert!(result.is_err());
+    fn test_http_checks_parse_and_validate() {
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [machines.orko]
+            name = "orko"
+
+            [[machines.orko.http_checks]]
+            name = "model-server"
+            url = "http://localhost:8080/health"
+            body_contains = "ok"
+
+            [[machines.orko.http_checks]]
+            name = "proxy"
+            url = "https://proxy.example.com/"
+            method = "HEAD"
+            expect_status = 204
+            from = "hub"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        let checks = &config.machines["orko"].http_checks;
+        assert_eq!(checks[0].method, "GET");
+        assert_eq!(checks[0].expect_status, 200);
+        assert_eq!(checks[0].timeout_secs, 10);
+        assert_eq!(checks[0].tls_warn_days, 14);
+        assert_eq!(checks[0].from, HttpCheckOrigin::Machine);
+        assert_eq!(checks[1].from, HttpCheckOrigin::Hub);
+
+        let machine = config.machines.get_mut("orko").unwrap();
+        machine.http_checks[1].name = "model-server".to_string();
         assert!(
-            result
+            config
+                .validate()
                 .unwrap_err()
                 .to_string()
-                .contains("poll_interval_secs")
+                .contains("two http checks")
         );
+
+        let machine = config.machines.get_mut("orko").unwrap();
+        machine.http_checks.truncate(1);
+        machine.http_checks[0].url = "ftp://localhost/".to_string();
+        assert!(config.validate().is_err());
+        let machine = config.machines.get_mut("orko").unwrap();
+        machine.http_checks[0].url = "http://localhost/".to_string();
+        machine.http_checks[0].method = "get".to_string();
+        assert!(config.validate().is_err());
     }
 
     #[test]
-    fn test_config_validation_collector_timeout() {
+    fn test_config_validation_log_level() {
         let mut config = VcConfig::default();
-        config.collectors.timeout_secs = 0;
+        config.global.log_level = "invalid".to_string();
         let result = config.validate();
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("timeout_secs"));
+        assert!(result.unwrap_err().to_string().contains("log_level"));
     }
 
     #[test]
-    fn test_config_validation_collector_concurrency() {
+    fn test_config_validation_store_backend() {
         let mut config = VcConfig::default();
-        config.collectors.max_concurrent_collectors = 0;
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("max_concurrent_collectors")
-        );
+        config.global.store_backend = Some("SQLite".to_string());
+        assert!(config.validate().is_ok());
 
-        let mut config = VcConfig::default();
-        config.collectors.max_concurrent_per_machine = 0;
+        config.global.store_backend = Some("postgres".to_string());
         let result = config.validate();
         assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("max_concurrent_per_machine")
-        );
+        assert!(result.unwrap_err().to_string().contains("store_backend"));
     }
 
     #[test]
-    fn test_config_validation_log_level() {
+    fn test_config_validation_timezone() {
         let mut config = VcConfig::default();
-        config.global.log_level = "invalid".to_string();
-        let result = config.validate();
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("log_level"));
+        assert_eq!(config.global.timezone, "local");
+        config.global.timezone = "Europe/Berlin".to_string();
+        assert!(config.validate().is_ok());
+
+        config.global.timezone = "Mars/Olympus".to_string();
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("timezone"), "{err}");
     }
 
     #[test]
@@ -1230,6 +5788,8 @@ mod tests {
                 enabled: true,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
         let result = config.validate();
@@ -1237,6 +5797,87 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("ssh_user"));
     }
 
+    #[test]
+    fn test_profile_merges_tables_and_replaces_values() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [global]
+            poll_interval_secs = 120
+            log_level = "info"
+
+            [machines.builder]
+            name = "Builder"
+            tags = ["builder", "fast"]
+
+            [profiles.staging.global]
+            log_level = "debug"
+
+            [profiles.staging.machines.builder]
+            tags = ["staging"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.profile_names(), vec!["staging"]);
+
+        let staging = config.with_profile("staging").unwrap();
+        assert_eq!(staging.global.log_level, "debug");
+        assert_eq!(staging.global.poll_interval_secs, 120);
+        let builder = &staging.machines["builder"];
+        assert_eq!(builder.name, "Builder");
+        assert_eq!(builder.tags, vec!["staging".to_string()]);
+        assert!(staging.profiles.is_empty());
+        // The base config is untouched
+        assert_eq!(config.global.log_level, "info");
+
+        let err = config.with_profile("prod").unwrap_err().to_string();
+        assert!(err.contains("Unknown profile 'prod'"));
+        assert!(err.contains("staging"));
+    }
+
+    #[test]
+    fn test_selected_profile_prefers_explicit() {
+        assert_eq!(
+            VcConfig::selected_profile(Some("prod")).as_deref(),
+            Some("prod")
+        );
+    }
+
+    #[test]
+    fn test_lint_checks_each_profile() {
+        let config: VcConfig = toml::from_str(
+            r#"
+            [profiles.good.global]
+            log_level = "warn"
+
+            [profiles.bad.global]
+            poll_interval_secs = 0
+
+            [profiles.broken]
+            global = "not a table"
+            "#,
+        )
+        .unwrap();
+        let result = config.lint();
+        assert!(
+            result
+                .issues
+                .iter()
+                .any(|i| i.path.starts_with("profiles.bad") && i.severity == LintSeverity::Error)
+        );
+        assert!(
+            result
+                .issues
+                .iter()
+                .any(|i| i.path == "profiles.broken" && i.severity == LintSeverity::Error)
+        );
+        assert!(
+            !result
+                .issues
+                .iter()
+                .any(|i| i.path.starts_with("profiles.good"))
+        );
+    }
+
     #[test]
     fn test_path_expansion_tilde() {
         let path = PathBuf::from("~/test/path");
@@ -1323,6 +5964,8 @@ enabled = true
                 enabled: true,
                 collectors,
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
 
@@ -1363,6 +6006,8 @@ enabled = true
         config.collectors.afsc = true;
         config.collectors.github = true;
         config.collectors.cloud_benchmarker = true;
+        config.collectors.accounts = true;
+        config.collectors.http_check = true;
 
         for name in [
             "fallback_probe",
@@ -1381,6 +6026,8 @@ enabled = true
             "afsc",
             "github",
             "cloud_benchmarker",
+            "accounts",
+            "http_check",
         ] {
             assert!(
                 config.is_collector_enabled("any-machine", name),
@@ -1460,6 +6107,8 @@ github = true
                 enabled: true,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
         assert!(config.is_local_machine("local"));
@@ -1476,6 +6125,8 @@ github = true
                 enabled: true,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
         assert!(!config.is_local_machine("remote"));
@@ -1496,6 +6147,8 @@ github = true
                 enabled: true,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
 
@@ -1510,6 +6163,8 @@ github = true
                 enabled: false,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
 
@@ -1626,6 +6281,8 @@ github = true
                 enabled: true,
                 collectors: HashMap::new(),
                 tags: vec![],
+                normalize_clock_skew: false,
+                http_checks: vec![],
             },
         );
         let result = config.lint();
@@ -1651,6 +6308,45 @@ github = true
         );
     }
 
+    #[test]
+    fn test_alert_templates_validate_and_lint() {
+        let mut config: VcConfig = toml::from_str(
+            r#"
+            [alerts.templates.slack]
+            template = "{{alert.severity}} on {{machine.hostname}}"
+            "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert!(!config.lint().has_errors());
+        let template = config.alerts.templates["slack"].load("slack").unwrap();
+        assert_eq!(template.variables().count(), 2);
+
+        config.alerts.templates.get_mut("slack").unwrap().template =
+            Some("{{alert.message".to_string());
+        assert!(config.validate().is_ok());
+        assert!(
+            config
+                .lint()
+                .issues
+                .iter()
+                .any(|i| i.path == "alerts.templates.slack" && i.severity == LintSeverity::Error)
+        );
+
+        let file = config.alerts.templates.get_mut("slack").unwrap();
+        file.file = Some(PathBuf::from("/nonexistent/vc-template.txt"));
+        assert!(config.validate().is_err());
+        config.alerts.templates.get_mut("slack").unwrap().template = None;
+        assert!(config.validate().is_ok());
+        assert!(config.lint().has_errors());
+
+        config
+            .alerts
+            .templates
+            .insert("pager".to_string(), NotifyTemplateConfig::default());
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_lint_autopilot_low_confidence() {
         let mut config = VcConfig::default();
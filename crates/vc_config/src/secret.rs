@@ -0,0 +1,192 @@
+//! External secret references
+//!
+//! Any secret-bearing config value may name where the secret lives instead
+//! of holding it, so vc.toml can be committed:
+//!
+//! - `secret://file/<path>`: the file's contents (`~/` is expanded)
+//! - `secret://env/<VAR>`: the environment variable
+//! - `secret://cmd/<command>`: stdout of `sh -c <command>`
+//!
+//! References stay as written when the config is loaded, so `vc config show`
+//! prints the reference and never the secret. They are resolved by whatever
+//! uses the value, when it uses it. Resolution errors name the config key and
+//! the reference but never include anything read from the source.
+
+use crate::{ConfigError, expand_path};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Prefix marking a value as a secret reference
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Where a secret is read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretRef {
+    File(PathBuf),
+    Env(String),
+    Cmd(String),
+}
+
+impl SecretRef {
+    /// Whether `value` is written as a secret reference
+    #[must_use]
+    pub fn is_reference(value: &str) -> bool {
+        value.starts_with(SECRET_SCHEME)
+    }
+
+    /// Parse `secret://<kind>/<target>`; `None` for a plain value
+    ///
+    /// # Errors
+    ///
+    /// Returns a message when the value uses the scheme but the kind is
+    /// unknown or the target is empty.
+    pub fn parse(value: &str) -> Result<Option<Self>, String> {
+        let Some(rest) = value.strip_prefix(SECRET_SCHEME) else {
+            return Ok(None);
+        };
+        let (kind, target) = rest.split_once('/').unwrap_or((rest, ""));
+        if target.trim().is_empty() {
+            return Err(format!("{SECRET_SCHEME}{kind} reference has no target"));
+        }
+        match kind {
+            "file" => Ok(Some(Self::File(PathBuf::from(target)))),
+            "env" => Ok(Some(Self::Env(target.to_string()))),
+            "cmd" => Ok(Some(Self::Cmd(target.to_string()))),
+            other => Err(format!(
+                "unknown secret reference kind '{other}' (use file, env or cmd)"
+            )),
+        }
+    }
+
+    /// Read the secret. Trailing newlines are dropped; an empty result is an
+    /// error, since no secret is ever legitimately empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing why the source could not be read, without
+    /// any of its contents.
+    pub fn resolve(&self) -> Result<String, String> {
+        let raw = match self {
+            Self::File(path) => {
+                let path = expand_path(path);
+                std::fs::read_to_string(&path)
+                    .map_err(|e| format!("cannot read {}: {}", path.display(), e.kind()))?
+            }
+            Self::Env(var) => std::env::var(var).map_err(|_| format!("${var} is not set"))?,
+            Self::Cmd(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stdin(Stdio::null())
+                    .stderr(Stdio::null())
+                    .output()
+                    .map_err(|e| format!("cannot run `{command}`: {}", e.kind()))?;
+                if !output.status.success() {
+                    return Err(format!("`{command}` failed ({})", output.status));
+                }
+                String::from_utf8(output.stdout)
+                    .map_err(|_| format!("`{command}` printed non-UTF-8 output"))?
+            }
+        };
+        let secret = raw.trim_end_matches(['\r', '\n']);
+        if secret.is_empty() {
+            return Err(format!("{self} is empty"));
+        }
+        Ok(secret.to_string())
+    }
+}
+
+impl std::fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{SECRET_SCHEME}file/{}", path.display()),
+            Self::Env(var) => write!(f, "{SECRET_SCHEME}env/{var}"),
+            Self::Cmd(command) => write!(f, "{SECRET_SCHEME}cmd/{command}"),
+        }
+    }
+}
+
+/// Resolve a secret-bearing value: references are read from their source,
+/// plain values are returned as they are
+///
+/// # Errors
+///
+/// Returns [`ConfigError::SecretError`] naming `key` when the reference is
+/// malformed or cannot be resolved.
+pub fn resolve_secret(key: &str, value: &str) -> Result<String, ConfigError> {
+    let secret_error = |reason: String| ConfigError::SecretError {
+        key: key.to_string(),
+        reason,
+    };
+    match SecretRef::parse(value).map_err(secret_error)? {
+        Some(reference) => reference.resolve().map_err(secret_error),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Outcome of resolving one reference for `vc config verify-secrets`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretCheck {
+    /// Config key holding the reference
+    pub key: String,
+    /// The reference as written
+    pub reference: String,
+    pub ok: bool,
+    /// Why it did not resolve
+    pub error: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(SecretRef::parse("https://hooks.example/x").unwrap(), None);
+        assert_eq!(
+            SecretRef::parse("secret://file/~/.config/vc/smtp").unwrap(),
+            Some(SecretRef::File(PathBuf::from("~/.config/vc/smtp")))
+        );
+        assert_eq!(
+            SecretRef::parse("secret://cmd/pass show vc/token").unwrap(),
+            Some(SecretRef::Cmd("pass show vc/token".to_string()))
+        );
+        assert!(SecretRef::parse("secret://vault/kv/vc").is_err());
+        assert!(SecretRef::parse("secret://env/").is_err());
+        assert!(SecretRef::parse("secret://env").is_err());
+    }
+
+    #[test]
+    fn test_resolve_sources() {
+        let path = std::env::temp_dir().join(format!("vc-secret-{}", std::process::id()));
+        std::fs::write(&path, "s3cr3t\n").unwrap();
+        let reference = format!("secret://file/{}", path.display());
+        assert_eq!(
+            resolve_secret("replication.token", &reference).unwrap(),
+            "s3cr3t"
+        );
+
+        // A failing command's output is discarded, even what it printed
+        let failing = format!("secret://cmd/cat {}; exit 3", path.display());
+        let err = resolve_secret("alerts.webhook_url", &failing)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("alerts.webhook_url"), "{err}");
+        assert!(!err.contains("s3cr3t"), "{err}");
+
+        std::fs::remove_file(&path).unwrap();
+        let err = resolve_secret("replication.token", &reference)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("replication.token"), "{err}");
+
+        assert_eq!(
+            resolve_secret("k", "secret://cmd/printf 'abc\\n'").unwrap(),
+            "abc"
+        );
+        assert!(resolve_secret("k", "secret://cmd/true").is_err());
+        assert!(resolve_secret("k", "secret://env/VC_TEST_SECRET_UNSET").is_err());
+        assert_eq!(resolve_secret("k", "plain").unwrap(), "plain");
+    }
+}
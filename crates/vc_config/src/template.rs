@@ -0,0 +1,233 @@
+//! Notification message templates
+//!
+//! A template is plain text with `{{path}}` placeholders, where the path
+//! names a value in a JSON tree by dotted keys (`{{machine.hostname}}`).
+//! Templates are parsed when the config is linted and again when a sink is
+//! built, and rendered at delivery time:
+//!
+//! - Strings are inserted as they are, numbers and booleans as written
+//! - A path that does not resolve, or resolves to null, stays in the output as
+//!   the literal placeholder and is reported back to the caller
+//! - Values are escaped for the sink's payload, never the surrounding text
+
+use serde_json::Value;
+use std::fmt;
+
+/// How resolved values are escaped before insertion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escape {
+    /// Inserted verbatim
+    None,
+    /// `&`, `<` and `>` become entities (HTML text and Slack mrkdwn)
+    Html,
+    /// Escaped for the inside of a JSON string literal
+    Json,
+}
+
+impl Escape {
+    fn apply(self, value: &str) -> String {
+        match self {
+            Self::None => value.to_string(),
+            Self::Html => value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            Self::Json => {
+                let quoted = Value::String(value.to_string()).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            }
+        }
+    }
+}
+
+/// Why a template could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    /// Byte offset of the offending placeholder
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Var(String),
+}
+
+/// A parsed message template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    segments: Vec<Segment>,
+}
+
+/// A rendered message and the placeholders left unresolved in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub text: String,
+    pub unresolved: Vec<String>,
+}
+
+impl MessageTemplate {
+    /// Parse `source`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TemplateError`] for an unclosed `{{`, an empty placeholder or
+    /// a path that is not dot-separated `[A-Za-z0-9_]` keys.
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+        let mut offset = 0;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("}}") else {
+                return Err(TemplateError {
+                    offset: offset + start,
+                    message: "unclosed '{{'".to_string(),
+                });
+            };
+            let path = after[..end].trim();
+            if let Some(message) = invalid_path(path) {
+                return Err(TemplateError {
+                    offset: offset + start,
+                    message,
+                });
+            }
+            segments.push(Segment::Var(path.to_string()));
+            let consumed = start + 2 + end + 2;
+            rest = &rest[consumed..];
+            offset += consumed;
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Placeholder paths in order of appearance
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Var(path) => Some(path.as_str()),
+            Segment::Text(_) => None,
+        })
+    }
+
+    /// Fill the placeholders from `vars`
+    #[must_use]
+    pub fn render(&self, vars: &Value, escape: Escape) -> Rendered {
+        let mut text = String::new();
+        let mut unresolved = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(literal) => text.push_str(literal),
+                Segment::Var(path) => match lookup(vars, path) {
+                    Some(value) => text.push_str(&escape.apply(&value)),
+                    None => {
+                        text.push_str("{{");
+                        text.push_str(path);
+                        text.push_str("}}");
+                        if !unresolved.contains(path) {
+                            unresolved.push(path.clone());
+                        }
+                    }
+                },
+            }
+        }
+        Rendered { text, unresolved }
+    }
+}
+
+fn invalid_path(path: &str) -> Option<String> {
+    if path.is_empty() {
+        return Some("empty placeholder".to_string());
+    }
+    let valid = path
+        .split('.')
+        .all(|key| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    (!valid).then(|| format!("invalid placeholder '{{{{{path}}}}}'"))
+}
+
+/// Text for `path` in `vars`; `None` when missing or null
+fn lookup(vars: &Value, path: &str) -> Option<String> {
+    let value = path
+        .split('.')
+        .try_fold(vars, |value, key| value.get(key))?;
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_resolves_nested_paths() {
+        let template =
+            MessageTemplate::parse("{{ machine.hostname }} at {{machine.health_score}}: {{x}}")
+                .unwrap();
+        assert_eq!(
+            template.variables().collect::<Vec<_>>(),
+            ["machine.hostname", "machine.health_score", "x"]
+        );
+
+        let vars = json!({"machine": {"hostname": "orko", "health_score": 0.5}, "x": true});
+        let rendered = template.render(&vars, Escape::None);
+        assert_eq!(rendered.text, "orko at 0.5: true");
+        assert!(rendered.unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_unresolved_stay_literal() {
+        let template = MessageTemplate::parse("{{a.b}} {{a.c}} {{a.c}} {{d}}").unwrap();
+        let rendered = template.render(&json!({"a": {"b": "ok", "c": null}}), Escape::None);
+        assert_eq!(rendered.text, "ok {{a.c}} {{a.c}} {{d}}");
+        assert_eq!(rendered.unresolved, ["a.c", "d"]);
+    }
+
+    #[test]
+    fn test_escaping_per_sink() {
+        let template = MessageTemplate::parse("{\"text\": \"{{m}}\"} <{{m}}>").unwrap();
+        let vars = json!({"m": "a \"b\" & <c>"});
+        assert_eq!(
+            template.render(&vars, Escape::Json).text,
+            r#"{"text": "a \"b\" & <c>"} <a \"b\" & <c>>"#
+        );
+        assert_eq!(
+            template.render(&vars, Escape::Html).text,
+            "{\"text\": \"a \"b\" &amp; &lt;c&gt;\"} <a \"b\" &amp; &lt;c&gt;>"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = MessageTemplate::parse("ok {{alert.message").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert!(err.to_string().contains("unclosed"), "{err}");
+        assert!(MessageTemplate::parse("{{ }}").is_err());
+        assert!(MessageTemplate::parse("{{alert..message}}").is_err());
+        assert!(MessageTemplate::parse("{{alert message}}").is_err());
+        // A lone closing brace pair is plain text
+        assert_eq!(
+            MessageTemplate::parse("}} {")
+                .unwrap()
+                .render(&json!({}), Escape::None)
+                .text,
+            "}} {"
+        );
+    }
+}
@@ -7,6 +7,7 @@
 //! 4. Validate drafts for safety
 //! 5. Require approval before activation
 
+use crate::transcript::CaptureProvenance;
 use crate::{GuardianError, PlaybookStep, PlaybookTrigger};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -104,6 +105,52 @@ pub struct PlaybookDraft {
     pub source_pattern: ResolutionPattern,
 }
 
+impl PlaybookDraft {
+    /// Rebuild a draft from a `playbook_drafts` row as returned by the store.
+    ///
+    /// The source pattern is not persisted in full, so only the fields that
+    /// validation looks at (alert type, confidence, sample count) are filled.
+    #[must_use]
+    pub fn from_row(row: &serde_json::Value) -> Self {
+        let steps: Vec<PlaybookStep> = row["steps_json"]
+            .as_str()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default();
+        let trigger: PlaybookTrigger = row["trigger_json"]
+            .as_str()
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or(PlaybookTrigger::Manual);
+        let status = row["status"]
+            .as_str()
+            .and_then(|s| serde_json::from_value(serde_json::Value::String(s.to_string())).ok())
+            .unwrap_or(DraftStatus::PendingReview);
+
+        let confidence = row["confidence"].as_f64().unwrap_or(0.0);
+        let sample_count =
+            usize::try_from(row["sample_count"].as_u64().unwrap_or(0)).unwrap_or(usize::MAX);
+        let alert_type = row["alert_type"].as_str().unwrap_or("").to_string();
+
+        Self {
+            draft_id: row["draft_id"].as_str().unwrap_or("").to_string(),
+            name: row["name"].as_str().unwrap_or("").to_string(),
+            description: row["description"].as_str().unwrap_or("").to_string(),
+            alert_type: alert_type.clone(),
+            trigger,
+            steps,
+            confidence,
+            sample_count,
+            status,
+            source_pattern: ResolutionPattern {
+                alert_type,
+                description: String::new(),
+                common_steps: vec![],
+                confidence,
+                sample_count,
+            },
+        }
+    }
+}
+
 /// Draft lifecycle status
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -125,10 +172,56 @@ pub struct ValidationResult {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ValidationIssue {
-    DangerousCommand { cmd: String, reason: String },
-    LowConfidence { confidence: f64, threshold: f64 },
-    InsufficientSamples { count: usize, minimum: usize },
+    DangerousCommand {
+        cmd: String,
+        reason: String,
+    },
+    LowConfidence {
+        confidence: f64,
+        threshold: f64,
+    },
+    InsufficientSamples {
+        count: usize,
+        minimum: usize,
+    },
     EmptySteps,
+    /// A `{{name}}` template names no declared parameter
+    UndeclaredParameter {
+        name: String,
+    },
+    /// A `{{` with no closing `}}`, or an empty or malformed name
+    BadTemplate {
+        text: String,
+    },
+    /// A library file written for a newer format
+    UnsupportedFormat {
+        version: u32,
+        supported: u32,
+    },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DangerousCommand { cmd, reason } => write!(f, "{reason}: {cmd}"),
+            Self::LowConfidence {
+                confidence,
+                threshold,
+            } => write!(f, "confidence {confidence:.2} is below {threshold:.2}"),
+            Self::InsufficientSamples { count, minimum } => {
+                write!(f, "{count} samples, at least {minimum} needed")
+            }
+            Self::EmptySteps => write!(f, "no steps beyond the log and notify wrapper"),
+            Self::UndeclaredParameter { name } => {
+                write!(f, "template {{{{{name}}}}} names no declared parameter")
+            }
+            Self::BadTemplate { text } => write!(f, "malformed template in {text:?}"),
+            Self::UnsupportedFormat { version, supported } => write!(
+                f,
+                "format_version {version} is newer than the supported {supported}"
+            ),
+        }
+    }
 }
 
 // ============================================================================
@@ -138,21 +231,32 @@ pub enum ValidationIssue {
 /// Captures operator actions that resolve alerts
 pub struct ActionCapture {
     store: Arc<VcStore>,
+    provenance: Option<CaptureProvenance>,
 }
 
 impl ActionCapture {
     #[must_use]
     pub fn new(store: Arc<VcStore>) -> Self {
-        Self { store }
+        Self {
+            store,
+            provenance: None,
+        }
+    }
+
+    /// Record where the captured actions were extracted from
+    #[must_use]
+    pub fn with_provenance(mut self, provenance: CaptureProvenance) -> Self {
+        self.provenance = Some(provenance);
+        self
     }
 
     /// Record a resolution (actions taken to resolve an alert)
     ///
     /// # Errors
     ///
-    /// Returns [`GuardianError::ExecutionFailed`] if `actions` cannot be
-    /// serialized to JSON, or [`GuardianError::StoreError`] if the resolution
-    /// row cannot be inserted.
+    /// Returns [`GuardianError::ExecutionFailed`] if `actions` or the
+    /// provenance cannot be serialized to JSON, or
+    /// [`GuardianError::StoreError`] if the resolution row cannot be written.
     pub fn capture(
         &self,
         alert_type: &str,
@@ -178,6 +282,15 @@ impl ActionCapture {
             )
             .map_err(GuardianError::StoreError)?;
 
+        if let Some(provenance) = &self.provenance {
+            let provenance = serde_json::to_string(provenance).map_err(|e| {
+                GuardianError::ExecutionFailed(format!("JSON serialization error: {e}"))
+            })?;
+            self.store
+                .set_resolution_provenance(id, &provenance)
+                .map_err(GuardianError::StoreError)?;
+        }
+
         Ok(id)
     }
 
@@ -651,6 +764,102 @@ pub fn is_dangerous_command(cmd: &str, args: &[String]) -> bool {
         .any(|a| DANGEROUS_ARGS.contains(&a.as_str()) || DANGEROUS_COMMANDS.contains(&a.as_str()))
 }
 
+// ============================================================================
+// Draft editing
+// ============================================================================
+
+/// An operator edit stored as a new draft revision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftRevision {
+    pub draft_id: String,
+    pub revision: i64,
+    pub editor: String,
+    pub diff_summary: String,
+    pub validation: ValidationResult,
+}
+
+/// Summarize how an edited step list differs from the previous one
+///
+/// Steps are compared by position, so inserting a step early in the list
+/// shows up as changes to every later position plus one addition.
+#[must_use]
+pub fn summarize_step_diff(old: &[PlaybookStep], new: &[PlaybookStep]) -> String {
+    let as_values = |steps: &[PlaybookStep]| -> Vec<serde_json::Value> {
+        steps
+            .iter()
+            .map(|s| serde_json::to_value(s).unwrap_or_default())
+            .collect()
+    };
+    let old_values = as_values(old);
+    let new_values = as_values(new);
+
+    let changed = old_values
+        .iter()
+        .zip(&new_values)
+        .filter(|(a, b)| a != b)
+        .count();
+    let added = new_values.len().saturating_sub(old_values.len());
+    let removed = old_values.len().saturating_sub(new_values.len());
+
+    if changed == 0 && added == 0 && removed == 0 {
+        return "no step changes".to_string();
+    }
+    format!(
+        "{changed} changed, {added} added, {removed} removed ({} -> {} steps)",
+        old.len(),
+        new.len()
+    )
+}
+
+/// Store edited steps as a new revision of a pending draft
+///
+/// The edited version is re-validated and the validation result is kept
+/// with the revision so approvers can see it. The original generated steps
+/// remain available as revision 1.
+///
+/// # Errors
+///
+/// Returns [`GuardianError::PlaybookNotFound`] if the draft does not exist,
+/// or [`GuardianError::StoreError`] if the draft is not pending review or
+/// the revision cannot be stored.
+pub fn edit_draft(
+    store: &VcStore,
+    draft_id: &str,
+    steps: Vec<PlaybookStep>,
+    editor: &str,
+) -> Result<DraftRevision, GuardianError> {
+    let row = store
+        .get_playbook_draft(draft_id)?
+        .ok_or_else(|| GuardianError::PlaybookNotFound(draft_id.to_string()))?;
+    let mut draft = PlaybookDraft::from_row(&row);
+
+    let diff_summary = summarize_step_diff(&draft.steps, &steps);
+    draft.steps = steps;
+    let validation = validate_draft(&draft);
+
+    let steps_json = serde_json::to_string(&draft.steps)
+        .map_err(|e| GuardianError::ExecutionFailed(format!("JSON serialization error: {e}")))?;
+    let validation_json = serde_json::to_string(&validation).ok();
+
+    let revision = store
+        .insert_playbook_draft_revision(
+            draft_id,
+            &steps_json,
+            editor,
+            &diff_summary,
+            validation_json.as_deref(),
+        )?
+        .ok_or_else(|| GuardianError::PlaybookNotFound(draft_id.to_string()))?;
+
+    Ok(DraftRevision {
+        draft_id: draft_id.to_string(),
+        revision,
+        editor: editor.to_string(),
+        diff_summary,
+        validation,
+    })
+}
+
 // ============================================================================
 // Full pipeline
 // ============================================================================
@@ -1311,7 +1520,9 @@ mod tests {
         assert_eq!(draft["name"].as_str().unwrap(), "Auto: test");
 
         // Approve
-        let affected = store.approve_playbook_draft("draft-1", "admin").unwrap();
+        let affected = store
+            .approve_playbook_draft("draft-1", &vc_store::ActorContext::system("admin"), 1)
+            .unwrap();
         assert_eq!(affected, 1);
 
         // Verify status
@@ -1365,7 +1576,9 @@ mod tests {
             .unwrap();
 
         // Must approve first
-        store.approve_playbook_draft("draft-act", "admin").unwrap();
+        store
+            .approve_playbook_draft("draft-act", &vc_store::ActorContext::system("admin"), 1)
+            .unwrap();
 
         // Now activate
         let result = store.activate_playbook_from_draft("draft-act").unwrap();
@@ -1396,6 +1609,142 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_edit_draft_creates_revision() {
+        let store = test_store();
+
+        store
+            .insert_playbook_draft(
+                "draft-edit",
+                "Auto: edit test",
+                "Will be edited",
+                "test-alert",
+                r#"{"type":"on_alert","rule_id":"test-alert"}"#,
+                r#"[{"type":"log","message":"hello"}]"#,
+                0.9,
+                10,
+                None,
+            )
+            .unwrap();
+
+        let steps = vec![
+            PlaybookStep::Log {
+                message: "hello".to_string(),
+            },
+            PlaybookStep::Wait { seconds: 5 },
+        ];
+        let revision = edit_draft(&store, "draft-edit", steps, "alice").unwrap();
+        assert_eq!(revision.revision, 2);
+        assert_eq!(
+            revision.diff_summary,
+            "0 changed, 1 added, 0 removed (1 -> 2 steps)"
+        );
+
+        let revisions = store.list_playbook_draft_revisions("draft-edit").unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0]["editor"].as_str().unwrap(), "generator");
+        assert_eq!(revisions[1]["editor"].as_str().unwrap(), "alice");
+
+        let drafts = store.list_playbook_drafts(None, 10).unwrap();
+        assert_eq!(drafts[0]["revision_count"].as_i64().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_edit_missing_draft_fails() {
+        let store = test_store();
+        let result = edit_draft(&store, "nope", vec![], "alice");
+        assert!(matches!(result, Err(GuardianError::PlaybookNotFound(_))));
+    }
+
+    #[test]
+    fn test_approve_requires_current_revision() {
+        let store = test_store();
+
+        store
+            .insert_playbook_draft(
+                "draft-stale",
+                "Auto: stale test",
+                "Edited after review",
+                "test-alert",
+                r#"{"type":"on_alert","rule_id":"test-alert"}"#,
+                r#"[{"type":"log","message":"hello"}]"#,
+                0.9,
+                10,
+                None,
+            )
+            .unwrap();
+        edit_draft(
+            &store,
+            "draft-stale",
+            vec![PlaybookStep::Wait { seconds: 1 }],
+            "bob",
+        )
+        .unwrap();
+
+        // Approving the revision the reviewer saw earlier must not succeed
+        let affected = store
+            .approve_playbook_draft("draft-stale", &vc_store::ActorContext::system("admin"), 1)
+            .unwrap();
+        assert_eq!(affected, 0);
+
+        let affected = store
+            .approve_playbook_draft("draft-stale", &vc_store::ActorContext::system("admin"), 2)
+            .unwrap();
+        assert_eq!(affected, 1);
+
+        // Approved drafts are frozen
+        let result = edit_draft(&store, "draft-stale", vec![], "bob");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_activate_uses_approved_revision_steps() {
+        let store = test_store();
+
+        store
+            .insert_playbook_draft(
+                "draft-rev",
+                "Auto: revision test",
+                "Activated at revision 2",
+                "test-alert",
+                r#"{"type":"on_alert","rule_id":"test-alert"}"#,
+                r#"[{"type":"log","message":"original"}]"#,
+                0.9,
+                10,
+                None,
+            )
+            .unwrap();
+        edit_draft(
+            &store,
+            "draft-rev",
+            vec![PlaybookStep::Log {
+                message: "edited".to_string(),
+            }],
+            "carol",
+        )
+        .unwrap();
+        store
+            .approve_playbook_draft("draft-rev", &vc_store::ActorContext::system("admin"), 2)
+            .unwrap();
+
+        let result = store
+            .activate_playbook_from_draft("draft-rev")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result["revision"].as_i64().unwrap(), 2);
+
+        let playbooks = store
+            .query_json("SELECT steps FROM guardian_playbooks WHERE playbook_id = 'draft-rev'")
+            .unwrap();
+        assert!(playbooks[0]["steps"].as_str().unwrap().contains("edited"));
+    }
+
+    #[test]
+    fn test_summarize_step_diff_no_changes() {
+        let steps = vec![PlaybookStep::Wait { seconds: 1 }];
+        assert_eq!(summarize_step_diff(&steps, &steps), "no step changes");
+    }
+
     // Full pipeline test
     #[test]
     fn test_full_pipeline() {
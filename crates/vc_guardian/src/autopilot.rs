@@ -4,7 +4,10 @@
 //! to make autonomous decisions about account switching, workload
 //! balancing, and cost optimization.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vc_config::AutopilotConfig;
 
 /// Autopilot operating mode
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -163,6 +166,19 @@ pub struct AutopilotStatus {
     pub last_decision_at: Option<String>,
     pub account_switches: u64,
     pub cost_alerts: u64,
+    /// Switches the current `account_status` data calls for
+    #[serde(default)]
+    pub pending_switches: Vec<SwitchRecommendation>,
+}
+
+/// One parsed `account_status` row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSample {
+    pub provider: String,
+    pub account_id: String,
+    pub used_pct: f64,
+    pub is_active: bool,
+    pub collected_at: DateTime<Utc>,
 }
 
 // =============================================================================
@@ -242,6 +258,63 @@ pub fn evaluate_account_switch(
     })
 }
 
+/// Evaluate account switches from `account_status` history.
+///
+/// Each active account is checked against the other accounts of its
+/// provider, with velocity taken from its two most recent samples.
+#[must_use]
+pub fn evaluate_account_switches(
+    samples: &[AccountSample],
+    config: &AutopilotConfig,
+) -> Vec<SwitchRecommendation> {
+    let mut by_account: BTreeMap<(&str, &str), Vec<&AccountSample>> = BTreeMap::new();
+    for sample in samples {
+        by_account
+            .entry((sample.provider.as_str(), sample.account_id.as_str()))
+            .or_default()
+            .push(sample);
+    }
+    for history in by_account.values_mut() {
+        history.sort_by_key(|sample| std::cmp::Reverse(sample.collected_at));
+    }
+
+    let mut recommendations = Vec::new();
+    for (&(provider, account_id), history) in &by_account {
+        let latest = history[0];
+        if !latest.is_active {
+            continue;
+        }
+        let velocity = history.get(1).map_or(0.0, |previous| {
+            #[allow(clippy::cast_precision_loss)]
+            let minutes = (latest.collected_at - previous.collected_at).num_seconds() as f64 / 60.0;
+            if minutes > 0.0 {
+                (latest.used_pct - previous.used_pct) / minutes
+            } else {
+                0.0
+            }
+        });
+        let alternatives: Vec<(String, f64)> = by_account
+            .iter()
+            .filter(|((p, a), _)| *p == provider && *a != account_id)
+            .map(|((_, a), h)| ((*a).to_string(), h[0].used_pct))
+            .collect();
+
+        if let Some(mut recommendation) = evaluate_account_switch(
+            latest.used_pct,
+            velocity,
+            config.switch_threshold,
+            config.preemptive_mins,
+            config.min_confidence,
+            &alternatives,
+        ) {
+            recommendation.from_account = account_id.to_string();
+            recommendation.provider = provider.to_string();
+            recommendations.push(recommendation);
+        }
+    }
+    recommendations
+}
+
 /// Evaluate workload balance across machines.
 ///
 /// Returns balance actions for overloaded or underutilized machines.
@@ -555,6 +628,40 @@ mod tests {
         assert_eq!(result.unwrap().to_account, "alt2");
     }
 
+    fn sample(account: &str, used_pct: f64, is_active: bool, mins_ago: i64) -> AccountSample {
+        AccountSample {
+            provider: "claude".to_string(),
+            account_id: account.to_string(),
+            used_pct,
+            is_active,
+            collected_at: Utc::now() - chrono::TimeDelta::minutes(mins_ago),
+        }
+    }
+
+    #[test]
+    fn test_evaluate_account_switches_from_history() {
+        let config = AutopilotConfig::default();
+        // Active account climbing 2%/min from 60%: 15%/2 = 7.5 minutes to 75%.
+        let samples = vec![
+            sample("main", 50.0, true, 5),
+            sample("main", 60.0, true, 0),
+            sample("spare", 20.0, false, 0),
+        ];
+        let switches = evaluate_account_switches(&samples, &config);
+        assert_eq!(switches.len(), 1);
+        assert_eq!(switches[0].from_account, "main");
+        assert_eq!(switches[0].to_account, "spare");
+        assert_eq!(switches[0].provider, "claude");
+
+        // A flat history at the same usage stays put.
+        let samples = vec![
+            sample("main", 60.0, true, 5),
+            sample("main", 60.0, true, 0),
+            sample("spare", 20.0, false, 0),
+        ];
+        assert!(evaluate_account_switches(&samples, &config).is_empty());
+    }
+
     // =========================================================================
     // Workload Balance Tests
     // =========================================================================
@@ -740,6 +847,7 @@ mod tests {
             last_decision_at: Some(chrono::Utc::now().to_rfc3339()),
             account_switches: 2,
             cost_alerts: 1,
+            pending_switches: vec![],
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -7,9 +7,14 @@
 //! - Approval workflow
 //! - Autopilot mode for autonomous fleet management
 //! - Automatic playbook generation from resolution patterns
+//! - Playbook library files for sharing curated playbooks
+//! - Sandbox runs that walk a playbook against recorded machine state
 
 pub mod autogen;
 pub mod autopilot;
+pub mod library;
+pub mod sandbox;
+pub mod transcript;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -30,6 +35,15 @@ pub enum GuardianError {
     #[error("Approval required")]
     ApprovalRequired,
 
+    #[error("Invalid playbook file: {0}")]
+    InvalidPlaybook(String),
+
+    #[error("Invalid parameter: {0}")]
+    InvalidParameter(String),
+
+    #[error("Unknown machine: {0}")]
+    UnknownMachine(String),
+
     #[error("Store error: {0}")]
     StoreError(#[from] vc_store::StoreError),
 }
@@ -88,6 +102,49 @@ pub enum PlaybookStep {
     },
 }
 
+/// A command step's program and arguments after parameter resolution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl RenderedCommand {
+    /// The command as a POSIX shell line, quoting words that need it
+    #[must_use]
+    pub fn command_line(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(|word| shell_quote(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn shell_quote(word: &str) -> String {
+    if !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c))
+    {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Runs a playbook's commands on a machine. Live execution goes through an
+/// implementation of this; a sandbox run never holds one.
+pub trait CommandExecutor {
+    /// Run `command` on `machine_id`, returning its exit code
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GuardianError::ExecutionFailed`] if the command could not
+    /// be started or did not finish in time.
+    fn run(&mut self, machine_id: &str, command: &RenderedCommand) -> Result<i32, GuardianError>;
+}
+
 /// Playbook run status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlaybookRun {
@@ -0,0 +1,850 @@
+//! Playbook library files (`vc guardian export/import/lint`)
+//!
+//! Curated playbooks are kept in git, one file each, in YAML (or JSON when
+//! the file ends in `.json`):
+//!
+//! ```yaml
+//! format_version: 1
+//! id: tmp-cleanup              # optional; derived from the name otherwise
+//! name: Temp Cleanup
+//! description: Prune old files when a volume fills up
+//! trigger: { type: on_alert, rule_id: disk-full }
+//! parameters:
+//!   - name: path
+//!     description: Directory to prune
+//!     default: /tmp
+//! required_tools: [find]
+//! steps:
+//!   - { type: command, cmd: find, args: ["{{path}}", -mtime, "+7", -delete],
+//!       timeout_secs: 60, allow_failure: false }
+//! rollback:
+//!   - { type: notify, channel: tui, message: "pruning {{path}} failed" }
+//! requires_approval: true      # default true
+//! max_runs_per_hour: 2         # default 3
+//! ```
+//!
+//! Step strings may use `{{name}}` templates for declared parameters; a
+//! parameter without a default must be supplied when the playbook runs.
+//! A file is valid when its templates are well formed and declared, and the
+//! draft validator's command checks pass on the steps and rollback after a
+//! dry resolution of every template (to its default, or a placeholder), so a
+//! default that expands into a dangerous command is caught too.
+//!
+//! Imported playbooks keep the SHA-256 of their file and of their content as
+//! stored, so an export can report when the live version has diverged.
+
+use crate::autogen::{
+    DraftStatus, PlaybookDraft, ResolutionPattern, ValidationIssue, ValidationResult,
+    validate_draft,
+};
+use crate::{Guardian, GuardianError, Playbook, PlaybookStep, PlaybookTrigger};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use vc_store::{VcStore, escape_sql_literal};
+
+/// Newest library file format this build reads
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Serialization of a library file, chosen by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Yaml,
+    Json,
+}
+
+impl FileFormat {
+    /// `.json` files are JSON; anything else is YAML
+    #[must_use]
+    pub fn for_path(path: &Path) -> Self {
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+}
+
+/// A parameter step templates may refer to as `{{name}}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterDecl {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Used when a run does not supply a value; without one the parameter
+    /// is required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// One playbook as written in a library file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlaybookFile {
+    #[serde(default = "default_format_version")]
+    pub format_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub trigger: PlaybookTrigger,
+    pub steps: Vec<PlaybookStep>,
+    /// Steps run when a step fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rollback: Vec<PlaybookStep>,
+    /// Programs that must be installed on the target machine
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<ParameterDecl>,
+    #[serde(default = "default_requires_approval")]
+    pub requires_approval: bool,
+    #[serde(default = "default_max_runs_per_hour")]
+    pub max_runs_per_hour: u32,
+}
+
+fn default_format_version() -> u32 {
+    FORMAT_VERSION
+}
+
+fn default_requires_approval() -> bool {
+    true
+}
+
+fn default_max_runs_per_hour() -> u32 {
+    3
+}
+
+impl PlaybookFile {
+    /// Parse a library file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GuardianError::InvalidPlaybook`] if the text does not match
+    /// the format.
+    pub fn parse(text: &str, format: FileFormat) -> Result<Self, GuardianError> {
+        match format {
+            FileFormat::Yaml => serde_yaml::from_str(text)
+                .map_err(|e| GuardianError::InvalidPlaybook(e.to_string())),
+            FileFormat::Json => serde_json::from_str(text)
+                .map_err(|e| GuardianError::InvalidPlaybook(e.to_string())),
+        }
+    }
+
+    /// Write the playbook out as a library file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GuardianError::ExecutionFailed`] if serialization fails.
+    pub fn render(&self, format: FileFormat) -> Result<String, GuardianError> {
+        let rendered = match format {
+            FileFormat::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            FileFormat::Json => serde_json::to_string_pretty(self)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+        };
+        rendered.map_err(|e| GuardianError::ExecutionFailed(format!("serialization error: {e}")))
+    }
+
+    /// A built-in playbook in file form
+    #[must_use]
+    pub fn from_playbook(playbook: &Playbook) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            id: Some(playbook.playbook_id.clone()),
+            name: playbook.name.clone(),
+            description: playbook.description.clone(),
+            trigger: playbook.trigger.clone(),
+            steps: playbook.steps.clone(),
+            rollback: Vec::new(),
+            required_tools: Vec::new(),
+            parameters: Vec::new(),
+            requires_approval: playbook.requires_approval,
+            max_runs_per_hour: playbook.max_runs_per_hour,
+        }
+    }
+
+    /// Rebuild the file form of a `guardian_playbooks` row
+    #[must_use]
+    pub fn from_row(row: &serde_json::Value) -> Self {
+        fn column<T: serde::de::DeserializeOwned + Default>(
+            row: &serde_json::Value,
+            name: &str,
+        ) -> T {
+            row[name]
+                .as_str()
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or_default()
+        }
+
+        Self {
+            format_version: FORMAT_VERSION,
+            id: row["playbook_id"].as_str().map(ToString::to_string),
+            name: row["name"].as_str().unwrap_or("").to_string(),
+            description: row["description"].as_str().unwrap_or("").to_string(),
+            trigger: row["trigger_condition"]
+                .as_str()
+                .and_then(|json| serde_json::from_str(json).ok())
+                .unwrap_or(PlaybookTrigger::Manual),
+            steps: column(row, "steps"),
+            rollback: column(row, "rollback_steps"),
+            required_tools: column(row, "required_tools"),
+            parameters: column(row, "parameters"),
+            requires_approval: row["requires_approval"].as_i64().unwrap_or(1) != 0,
+            max_runs_per_hour: row["max_runs_per_hour"]
+                .as_u64()
+                .and_then(|runs| u32::try_from(runs).ok())
+                .unwrap_or_else(default_max_runs_per_hour),
+        }
+    }
+
+    /// The `id` field, or one derived from the name
+    #[must_use]
+    pub fn playbook_id(&self) -> String {
+        if let Some(id) = self.id.as_deref().filter(|id| !id.trim().is_empty()) {
+            return id.trim().to_string();
+        }
+        let mut slug = String::new();
+        for c in self.name.trim().to_lowercase().chars() {
+            if c.is_ascii_alphanumeric() {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        slug.trim_end_matches('-').to_string()
+    }
+
+    /// SHA-256 of the playbook's content, leaving out its id and format
+    /// version, so the same playbook hashes the same wherever it is stored
+    #[must_use]
+    pub fn content_hash(&self) -> String {
+        let mut content = self.clone();
+        content.id = None;
+        content.format_version = FORMAT_VERSION;
+        sha256_hex(
+            serde_json::to_string(&content)
+                .unwrap_or_default()
+                .as_bytes(),
+        )
+    }
+
+    /// Check templates, then run the draft validator's command checks on the
+    /// steps and rollback with every template resolved
+    #[must_use]
+    pub fn validate(&self) -> ValidationResult {
+        let mut issues = Vec::new();
+        if self.format_version > FORMAT_VERSION {
+            issues.push(ValidationIssue::UnsupportedFormat {
+                version: self.format_version,
+                supported: FORMAT_VERSION,
+            });
+        }
+
+        let mut steps = serde_json::json!([self.steps, self.rollback]);
+        let mut used = BTreeSet::new();
+        for_each_string(&mut steps, &mut |text| {
+            if !scan_templates(text, &mut used) {
+                issues.push(ValidationIssue::BadTemplate { text: text.clone() });
+            }
+        });
+        let declared: BTreeSet<&str> = self.parameters.iter().map(|p| p.name.as_str()).collect();
+        for name in &used {
+            if !declared.contains(name.as_str()) {
+                issues.push(ValidationIssue::UndeclaredParameter { name: name.clone() });
+            }
+        }
+
+        if self.steps.is_empty() {
+            issues.push(ValidationIssue::EmptySteps);
+        }
+        if let Some(resolved) = self.dry_resolve(steps) {
+            let draft = PlaybookDraft {
+                draft_id: self.playbook_id(),
+                name: self.name.clone(),
+                description: self.description.clone(),
+                alert_type: String::new(),
+                trigger: self.trigger.clone(),
+                steps: resolved,
+                confidence: 1.0,
+                sample_count: 0,
+                status: DraftStatus::PendingReview,
+                source_pattern: ResolutionPattern {
+                    alert_type: String::new(),
+                    description: String::new(),
+                    common_steps: vec![],
+                    confidence: 1.0,
+                    sample_count: 0,
+                },
+            };
+            // Sample counts, confidence and the log/notify wrapper only
+            // apply to generated drafts
+            issues.extend(
+                validate_draft(&draft)
+                    .issues
+                    .into_iter()
+                    .filter(|issue| matches!(issue, ValidationIssue::DangerousCommand { .. })),
+            );
+        }
+
+        ValidationResult {
+            valid: issues.is_empty(),
+            issues,
+        }
+    }
+
+    /// Steps and rollback as one list with templates replaced by parameter
+    /// defaults, or `<name>` when there is none
+    fn dry_resolve(&self, mut steps: serde_json::Value) -> Option<Vec<PlaybookStep>> {
+        let placeholders: HashMap<&str, String> = self
+            .parameters
+            .iter()
+            .map(|p| {
+                let value = p.default.clone().unwrap_or_else(|| format!("<{}>", p.name));
+                (p.name.as_str(), value)
+            })
+            .collect();
+        for_each_string(&mut steps, &mut |text| {
+            *text = substitute(text, &placeholders);
+        });
+        let [steps, rollback]: [Vec<PlaybookStep>; 2] = serde_json::from_value(steps).ok()?;
+        Some(steps.into_iter().chain(rollback).collect())
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Apply `f` to every string inside `value`
+pub(crate) fn for_each_string(value: &mut serde_json::Value, f: &mut impl FnMut(&mut String)) {
+    match value {
+        serde_json::Value::String(text) => f(text),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                for_each_string(item, f);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                for_each_string(item, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Add the names of `text`'s `{{name}}` templates to `found`. Returns false
+/// if a template is unterminated or its name is not made of letters, digits,
+/// `_` and `-`.
+fn scan_templates(text: &str, found: &mut BTreeSet<String>) -> bool {
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            return false;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return false;
+        }
+        found.insert(name.to_string());
+        rest = &rest[start + 2 + len + 2..];
+    }
+    true
+}
+
+/// Replace each `{{name}}` in `text` that `values` has a value for
+pub(crate) fn substitute(text: &str, values: &HashMap<&str, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        out.push_str(&rest[..start]);
+        match values.get(rest[start + 2..start + 2 + len].trim()) {
+            Some(value) => out.push_str(value),
+            None => out.push_str(&rest[start..end]),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// What `import` did with a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    /// A new playbook
+    Imported,
+    /// Replaced the playbook of the same name (`--overwrite`)
+    Overwritten,
+    /// Stored next to the playbook of the same name under a versioned id
+    VersionedCopy,
+    /// Same content as the stored playbook; only the source was updated
+    Unchanged,
+}
+
+/// Result of [`import_playbook`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOutcome {
+    pub playbook_id: String,
+    pub name: String,
+    pub status: ImportStatus,
+    pub enabled: bool,
+    pub source_hash: String,
+}
+
+/// Result of [`export_playbook`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportOutcome {
+    pub playbook_id: String,
+    pub path: PathBuf,
+    pub builtin: bool,
+    /// File the playbook was imported from
+    pub source_path: Option<String>,
+    pub source_hash: Option<String>,
+    /// Whether the live playbook changed since it was imported; `None` for
+    /// playbooks that did not come from a file
+    pub diverged: Option<bool>,
+}
+
+/// Result of checking one file with [`lint_dir`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintResult {
+    pub path: PathBuf,
+    pub playbook_id: Option<String>,
+    pub valid: bool,
+    pub issues: Vec<String>,
+}
+
+pub(crate) fn stored_playbook(
+    store: &VcStore,
+    column: &str,
+    value: &str,
+) -> Result<Option<serde_json::Value>, GuardianError> {
+    Ok(store
+        .query_json(&format!(
+            "SELECT * FROM guardian_playbooks WHERE {column} = '{}' \
+             ORDER BY created_at LIMIT 1",
+            escape_sql_literal(value)
+        ))?
+        .into_iter()
+        .next())
+}
+
+fn read_playbook_file(path: &Path) -> Result<(PlaybookFile, Vec<u8>), GuardianError> {
+    let invalid =
+        |reason: String| GuardianError::InvalidPlaybook(format!("{}: {reason}", path.display()));
+    let bytes = std::fs::read(path).map_err(|e| invalid(e.to_string()))?;
+    let text = std::str::from_utf8(&bytes).map_err(|e| invalid(e.to_string()))?;
+    let file = PlaybookFile::parse(text, FileFormat::for_path(path)).map_err(|e| match e {
+        GuardianError::InvalidPlaybook(reason) => invalid(reason),
+        other => other,
+    })?;
+    Ok((file, bytes))
+}
+
+/// Validate a library file and store it as a guardian playbook, disabled
+/// unless `activate`.
+///
+/// A playbook of the same name (or id) is replaced when `overwrite` is set
+/// and otherwise kept, with the import stored beside it as `<id>-v<n>`.
+/// Re-importing content identical to the stored playbook only refreshes the
+/// recorded source.
+///
+/// # Errors
+///
+/// Returns [`GuardianError::InvalidPlaybook`] if the file cannot be read,
+/// parsed or validated, [`GuardianError::ExecutionFailed`] when asked to
+/// overwrite a built-in playbook, or [`GuardianError::StoreError`] if the
+/// write fails.
+pub fn import_playbook(
+    store: &VcStore,
+    guardian: &Guardian,
+    path: &Path,
+    activate: bool,
+    overwrite: bool,
+) -> Result<ImportOutcome, GuardianError> {
+    let (mut file, bytes) = read_playbook_file(path)?;
+    let validation = file.validate();
+    if !validation.valid {
+        let issues: Vec<String> = validation.issues.iter().map(ToString::to_string).collect();
+        return Err(GuardianError::InvalidPlaybook(format!(
+            "{}: {}",
+            path.display(),
+            issues.join("; ")
+        )));
+    }
+    let source_hash = sha256_hex(&bytes);
+    let source_path = path.display().to_string();
+    let mut playbook_id = file.playbook_id();
+
+    let existing = match stored_playbook(store, "name", &file.name)? {
+        Some(row) => Some(row),
+        None => stored_playbook(store, "playbook_id", &playbook_id)?,
+    };
+    let builtin = guardian
+        .playbooks()
+        .iter()
+        .any(|p| p.playbook_id == playbook_id || p.name == file.name);
+
+    let status = match &existing {
+        Some(row) if row["content_hash"].as_str() == Some(file.content_hash().as_str()) => {
+            let stored_id = row["playbook_id"].as_str().unwrap_or(&playbook_id);
+            let enable = if activate { ", enabled = 1" } else { "" };
+            store.execute(
+                &format!(
+                    "UPDATE guardian_playbooks SET source_path = ?, source_hash = ?, \
+                     imported_at = ?{enable} WHERE playbook_id = ?"
+                ),
+                &[
+                    &source_path,
+                    &source_hash,
+                    &Utc::now().to_rfc3339(),
+                    stored_id,
+                ],
+            )?;
+            return Ok(ImportOutcome {
+                playbook_id: stored_id.to_string(),
+                name: file.name,
+                status: ImportStatus::Unchanged,
+                enabled: activate || row["enabled"].as_i64().unwrap_or(0) != 0,
+                source_hash,
+            });
+        }
+        Some(row) if overwrite => {
+            if let Some(id) = row["playbook_id"].as_str() {
+                playbook_id = id.to_string();
+            }
+            ImportStatus::Overwritten
+        }
+        None if !builtin => ImportStatus::Imported,
+        _ if overwrite => {
+            return Err(GuardianError::ExecutionFailed(format!(
+                "built-in playbook {playbook_id} cannot be overwritten"
+            )));
+        }
+        _ => {
+            let base_id = playbook_id.clone();
+            let base_name = file.name.clone();
+            for version in 2.. {
+                playbook_id = format!("{base_id}-v{version}");
+                file.name = format!("{base_name} (v{version})");
+                if guardian.get_playbook(&playbook_id).is_none()
+                    && stored_playbook(store, "playbook_id", &playbook_id)?.is_none()
+                    && stored_playbook(store, "name", &file.name)?.is_none()
+                {
+                    break;
+                }
+            }
+            ImportStatus::VersionedCopy
+        }
+    };
+
+    let row = serde_json::json!({
+        "playbook_id": playbook_id,
+        "name": file.name,
+        "description": file.description,
+        "trigger_condition": json_text(&file.trigger),
+        "steps": json_text(&file.steps),
+        "enabled": i64::from(activate),
+        "requires_approval": i64::from(file.requires_approval),
+        "max_runs_per_hour": file.max_runs_per_hour,
+        "rollback_steps": json_text(&file.rollback),
+        "required_tools": json_text(&file.required_tools),
+        "parameters": json_text(&file.parameters),
+        "source_path": source_path,
+        "source_hash": source_hash,
+        "content_hash": file.content_hash(),
+        "imported_at": Utc::now().to_rfc3339(),
+    });
+    store.upsert_json("guardian_playbooks", &[row], &["playbook_id"])?;
+
+    Ok(ImportOutcome {
+        playbook_id,
+        name: file.name,
+        status,
+        enabled: activate,
+        source_hash,
+    })
+}
+
+/// JSON text for a TEXT column
+fn json_text<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Write a stored or built-in playbook to `out` as a library file, reporting
+/// whether a stored playbook diverged from the file it was imported from.
+///
+/// # Errors
+///
+/// Returns [`GuardianError::PlaybookNotFound`] if no playbook has the id,
+/// [`GuardianError::ExecutionFailed`] if the file cannot be written, or
+/// [`GuardianError::StoreError`] if the lookup fails.
+pub fn export_playbook(
+    store: &VcStore,
+    guardian: &Guardian,
+    playbook_id: &str,
+    out: &Path,
+) -> Result<ExportOutcome, GuardianError> {
+    let stored = stored_playbook(store, "playbook_id", playbook_id)?;
+    let (file, builtin) = match (&stored, guardian.get_playbook(playbook_id)) {
+        (Some(row), _) => (PlaybookFile::from_row(row), false),
+        (None, Some(playbook)) => (PlaybookFile::from_playbook(playbook), true),
+        (None, None) => return Err(GuardianError::PlaybookNotFound(playbook_id.to_string())),
+    };
+
+    let rendered = file.render(FileFormat::for_path(out))?;
+    std::fs::write(out, rendered).map_err(|e| {
+        GuardianError::ExecutionFailed(format!("cannot write {}: {e}", out.display()))
+    })?;
+
+    let column = |name: &str| {
+        stored
+            .as_ref()
+            .and_then(|row| row[name].as_str())
+            .map(ToString::to_string)
+    };
+    let content_hash = column("content_hash");
+    Ok(ExportOutcome {
+        playbook_id: playbook_id.to_string(),
+        path: out.to_path_buf(),
+        builtin,
+        source_path: column("source_path"),
+        source_hash: column("source_hash"),
+        diverged: content_hash.map(|hash| hash != file.content_hash()),
+    })
+}
+
+/// Parse and validate every `.yaml`, `.yml` and `.json` file in `dir`,
+/// also flagging ids used by more than one file
+///
+/// # Errors
+///
+/// Returns [`GuardianError::InvalidPlaybook`] if the directory cannot be
+/// read.
+pub fn lint_dir(dir: &Path) -> Result<Vec<LintResult>, GuardianError> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| GuardianError::InvalidPlaybook(format!("{}: {e}", dir.display())))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ["yaml", "yml", "json"].contains(&ext.to_ascii_lowercase().as_str())
+                })
+        })
+        .collect();
+    paths.sort();
+
+    let mut seen: HashMap<String, PathBuf> = HashMap::new();
+    let mut results = Vec::new();
+    for path in paths {
+        let result = match read_playbook_file(&path) {
+            Ok((file, _)) => {
+                let playbook_id = file.playbook_id();
+                let mut issues: Vec<String> = file
+                    .validate()
+                    .issues
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                if let Some(first) = seen.get(&playbook_id) {
+                    issues.push(format!(
+                        "id {playbook_id} is also used by {}",
+                        first.display()
+                    ));
+                } else {
+                    seen.insert(playbook_id.clone(), path.clone());
+                }
+                LintResult {
+                    path,
+                    playbook_id: Some(playbook_id),
+                    valid: issues.is_empty(),
+                    issues,
+                }
+            }
+            Err(e) => LintResult {
+                path,
+                playbook_id: None,
+                valid: false,
+                issues: vec![e.to_string()],
+            },
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLEANUP: &str = r#"
+format_version: 1
+name: Temp Cleanup
+description: Prune old files when a volume fills up
+trigger: { type: on_alert, rule_id: disk-full }
+parameters:
+  - name: path
+    default: /tmp
+required_tools: [find]
+steps:
+  - { type: command, cmd: find, args: ["{{path}}", -mtime, "+7", -delete],
+      timeout_secs: 60, allow_failure: false }
+rollback:
+  - { type: notify, channel: tui, message: "pruning {{ path }} failed" }
+max_runs_per_hour: 2
+"#;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("vc-playbooks-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_and_validate() {
+        let file = PlaybookFile::parse(CLEANUP, FileFormat::Yaml).unwrap();
+        assert_eq!(file.playbook_id(), "temp-cleanup");
+        assert!(file.requires_approval);
+        assert_eq!(file.rollback.len(), 1);
+        let validation = file.validate();
+        assert!(validation.valid, "{:?}", validation.issues);
+
+        // The rendered file reads back to the same content
+        for format in [FileFormat::Yaml, FileFormat::Json] {
+            let again = PlaybookFile::parse(&file.render(format).unwrap(), format).unwrap();
+            assert_eq!(again.content_hash(), file.content_hash());
+        }
+
+        assert!(PlaybookFile::parse("name: x\nbogus: 1\n", FileFormat::Yaml).is_err());
+    }
+
+    #[test]
+    fn test_templates_are_declared_and_resolved() {
+        let undeclared = CLEANUP.replace("{{ path }}", "{{target}}");
+        let issues = PlaybookFile::parse(&undeclared, FileFormat::Yaml)
+            .unwrap()
+            .validate()
+            .issues;
+        assert!(matches!(
+            issues.as_slice(),
+            [ValidationIssue::UndeclaredParameter { name }] if name == "target"
+        ));
+
+        let unterminated = CLEANUP.replace("{{ path }}", "{{path");
+        let file = PlaybookFile::parse(&unterminated, FileFormat::Yaml).unwrap();
+        assert!(matches!(
+            file.validate().issues.as_slice(),
+            [ValidationIssue::BadTemplate { .. }]
+        ));
+
+        // A default that expands into a dangerous command is caught
+        let dangerous = CLEANUP.replace("cmd: find", "cmd: \"{{tool}}\"").replace(
+            "  - name: path\n",
+            "  - name: tool\n    default: rm\n  - name: path\n",
+        );
+        let file = PlaybookFile::parse(&dangerous, FileFormat::Yaml).unwrap();
+        assert!(matches!(
+            file.validate().issues.as_slice(),
+            [ValidationIssue::DangerousCommand { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_import_collisions_and_export_divergence() {
+        let store = VcStore::open_memory().unwrap();
+        let guardian = Guardian::new();
+        let dir = temp_dir("import");
+        let path = dir.join("cleanup.yaml");
+        std::fs::write(&path, CLEANUP).unwrap();
+
+        let first = import_playbook(&store, &guardian, &path, false, false).unwrap();
+        assert_eq!(first.status, ImportStatus::Imported);
+        assert_eq!(first.playbook_id, "temp-cleanup");
+        assert!(!first.enabled);
+
+        let again = import_playbook(&store, &guardian, &path, true, false).unwrap();
+        assert_eq!(again.status, ImportStatus::Unchanged);
+        assert!(again.enabled);
+
+        std::fs::write(&path, CLEANUP.replace("+7", "+14")).unwrap();
+        let copy = import_playbook(&store, &guardian, &path, false, false).unwrap();
+        assert_eq!(copy.status, ImportStatus::VersionedCopy);
+        assert_eq!(copy.playbook_id, "temp-cleanup-v2");
+        assert_eq!(copy.name, "Temp Cleanup (v2)");
+
+        let replaced = import_playbook(&store, &guardian, &path, false, true).unwrap();
+        assert_eq!(replaced.status, ImportStatus::Overwritten);
+        assert_eq!(replaced.playbook_id, "temp-cleanup");
+
+        let out = dir.join("exported.json");
+        let export = export_playbook(&store, &guardian, "temp-cleanup", &out).unwrap();
+        assert_eq!(export.diverged, Some(false));
+        assert_eq!(export.source_hash, Some(replaced.source_hash));
+        let exported =
+            PlaybookFile::parse(&std::fs::read_to_string(&out).unwrap(), FileFormat::Json).unwrap();
+        assert_eq!(exported.id.as_deref(), Some("temp-cleanup"));
+        assert_eq!(exported.parameters.len(), 1);
+
+        store
+            .execute_simple(
+                "UPDATE guardian_playbooks SET max_runs_per_hour = 9 \
+                 WHERE playbook_id = 'temp-cleanup'",
+            )
+            .unwrap();
+        let export = export_playbook(&store, &guardian, "temp-cleanup", &out).unwrap();
+        assert_eq!(export.diverged, Some(true));
+
+        let builtin = export_playbook(&store, &guardian, "memory-cleanup", &out).unwrap();
+        assert!(builtin.builtin);
+        assert_eq!(builtin.diverged, None);
+        assert!(matches!(
+            export_playbook(&store, &guardian, "missing", &out),
+            Err(GuardianError::PlaybookNotFound(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lint_dir() {
+        let dir = temp_dir("lint");
+        std::fs::write(dir.join("a.yaml"), CLEANUP).unwrap();
+        std::fs::write(dir.join("b.yml"), CLEANUP).unwrap();
+        std::fs::write(dir.join("c.json"), "{\"name\": \"broken\"}").unwrap();
+        std::fs::write(dir.join("notes.md"), "not a playbook").unwrap();
+
+        let results = lint_dir(&dir).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].valid);
+        assert!(!results[1].valid, "duplicate id");
+        assert!(results[1].issues[0].contains("a.yaml"));
+        assert!(!results[2].valid);
+        assert_eq!(results[2].playbook_id, None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1036 @@
+//! Sandbox runs (`vc guardian run --sandbox`)
+//!
+//! A sandbox run walks a playbook against what the store recorded about one
+//! machine at one instant, instead of against the machine. Parameters are
+//! resolved as for a live run, the trigger is judged as of that instant,
+//! and each step is rendered to the exact command line it would run, with
+//! the condition it runs under: steps after a command that may not fail
+//! only run if it succeeds.
+//!
+//! Effects are predicted from the recorded state for these commands (also
+//! under `sudo`):
+//!
+//! - `find <dir> ... -delete` (or `-exec rm`) and `rm <path>`: at most the
+//!   used bytes of the filesystems holding the paths. Per-file sizes and
+//!   ages are not collected, so this is an upper bound and says nothing
+//!   about what `-mtime` keeps.
+//! - `pkill [-f] [-x] <pattern>`, `killall <name>` and `kill <pid>`: the
+//!   processes they would signal, from the agent inventory and the newest
+//!   top-processes sample
+//!
+//! Any other command is rendered verbatim with an unknown effect.
+//!
+//! Nothing is ever executed: [`SandboxExecutor`] holds recorded state and
+//! nothing else, and no function here takes a [`CommandExecutor`].
+//!
+//! [`CommandExecutor`]: crate::CommandExecutor
+
+use crate::library::{ParameterDecl, PlaybookFile, for_each_string, stored_playbook, substitute};
+use crate::{Guardian, GuardianError, Playbook, PlaybookStep, PlaybookTrigger, RenderedCommand};
+use chrono::{DateTime, SecondsFormat, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use vc_alert::ThresholdOp;
+use vc_store::{VcStore, escape_sql_literal};
+
+/// A playbook to walk, with the draft revision it came from
+#[derive(Debug, Clone)]
+pub struct SandboxTarget {
+    pub playbook: PlaybookFile,
+    /// `(draft_id, revision)` when the playbook is a draft
+    pub draft: Option<(String, i64)>,
+}
+
+impl SandboxTarget {
+    /// The stored playbook with `id`, else the built-in one, else the
+    /// playbook draft at its current revision
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GuardianError::PlaybookNotFound`] if nothing has the id, or
+    /// [`GuardianError::StoreError`] if a lookup fails.
+    pub fn resolve(store: &VcStore, guardian: &Guardian, id: &str) -> Result<Self, GuardianError> {
+        if let Some(row) = stored_playbook(store, "playbook_id", id)? {
+            return Ok(Self {
+                playbook: PlaybookFile::from_row(&row),
+                draft: None,
+            });
+        }
+        if let Some(playbook) = guardian.get_playbook(id) {
+            return Ok(Self {
+                playbook: PlaybookFile::from_playbook(playbook),
+                draft: None,
+            });
+        }
+        let draft = store
+            .get_playbook_draft(id)?
+            .ok_or_else(|| GuardianError::PlaybookNotFound(id.to_string()))?;
+        let json = |column: &str| draft[column].as_str().unwrap_or_default().to_string();
+        let playbook = PlaybookFile::from_playbook(&Playbook {
+            playbook_id: id.to_string(),
+            name: json("name"),
+            description: json("description"),
+            trigger: serde_json::from_str(&json("trigger_json")).unwrap_or(PlaybookTrigger::Manual),
+            steps: serde_json::from_str(&json("steps_json")).map_err(|e| {
+                GuardianError::InvalidPlaybook(format!("draft {id} has unreadable steps: {e}"))
+            })?,
+            requires_approval: true,
+            max_runs_per_hour: 3,
+            enabled: false,
+        });
+        Ok(Self {
+            playbook,
+            draft: Some((
+                id.to_string(),
+                draft["current_revision"].as_i64().unwrap_or(1),
+            )),
+        })
+    }
+}
+
+/// Values for a playbook's parameters: the supplied one where given, else
+/// the declared default
+///
+/// # Errors
+///
+/// Returns [`GuardianError::InvalidParameter`] if a supplied name is not
+/// declared or a parameter without a default is not supplied.
+pub fn resolve_parameters(
+    declared: &[ParameterDecl],
+    supplied: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, GuardianError> {
+    if let Some(name) = supplied
+        .keys()
+        .find(|name| !declared.iter().any(|p| &p.name == *name))
+    {
+        return Err(GuardianError::InvalidParameter(format!(
+            "{name} is not declared by the playbook"
+        )));
+    }
+    declared
+        .iter()
+        .map(|p| {
+            supplied
+                .get(&p.name)
+                .or(p.default.as_ref())
+                .map(|value| (p.name.clone(), value.clone()))
+                .ok_or_else(|| {
+                    GuardianError::InvalidParameter(format!("{} has no default; supply it", p.name))
+                })
+        })
+        .collect()
+}
+
+/// A filesystem as last sampled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedFilesystem {
+    pub mount: String,
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+}
+
+/// A process known to be running
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedProcess {
+    pub pid: Option<i64>,
+    /// Executable name, as `pkill` without `-f` matches it
+    pub name: String,
+    /// Full command line, or the name when only that was recorded
+    pub command: String,
+    /// `agents_inventory` or `sys_top_processes`
+    pub source: String,
+}
+
+/// What the store recorded about a machine as of one instant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedState {
+    pub machine_id: String,
+    pub at: DateTime<Utc>,
+    /// From the newest filesystem sample at or before `at`
+    pub filesystems: Vec<RecordedFilesystem>,
+    pub filesystems_sampled_at: Option<String>,
+    /// Inventoried agents alive at `at`, then the newest top-processes
+    /// sample's other processes
+    pub processes: Vec<RecordedProcess>,
+    /// Rules with an alert open on the machine at `at`
+    pub open_alert_rules: Vec<String>,
+}
+
+fn text(row: &serde_json::Value, column: &str) -> Option<String> {
+    row[column].as_str().map(ToString::to_string)
+}
+
+/// Last path component of a program, so `/usr/bin/find` is `find`
+fn program_name(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+impl RecordedState {
+    /// Reconstruct `machine_id`'s state as of `at`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GuardianError::UnknownMachine`] if the machine is not
+    /// registered, or [`GuardianError::StoreError`] if a query fails.
+    pub fn load(
+        store: &VcStore,
+        machine_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Self, GuardianError> {
+        let id = escape_sql_literal(machine_id);
+        let at_text = at.to_rfc3339_opts(SecondsFormat::Micros, true);
+        if store
+            .query_json(&format!(
+                "SELECT machine_id FROM machines WHERE machine_id = '{id}'"
+            ))?
+            .is_empty()
+        {
+            return Err(GuardianError::UnknownMachine(machine_id.to_string()));
+        }
+        let newest_sample = |table: &str| {
+            format!(
+                "SELECT MAX(collected_at) FROM {table} \
+                 WHERE machine_id = '{id}' AND collected_at <= '{at_text}'"
+            )
+        };
+
+        let filesystem_rows = store.query_json(&format!(
+            "SELECT mount, total_bytes, used_bytes, collected_at FROM sys_filesystems \
+             WHERE machine_id = '{id}' AND collected_at = ({}) ORDER BY mount",
+            newest_sample("sys_filesystems")
+        ))?;
+        let filesystems_sampled_at = filesystem_rows
+            .first()
+            .and_then(|row| text(row, "collected_at"));
+        let filesystems = filesystem_rows
+            .iter()
+            .filter_map(|row| {
+                Some(RecordedFilesystem {
+                    mount: text(row, "mount")?,
+                    total_bytes: row["total_bytes"].as_i64().unwrap_or(0),
+                    used_bytes: row["used_bytes"].as_i64().unwrap_or(0),
+                })
+            })
+            .collect();
+
+        let mut processes: Vec<RecordedProcess> = store
+            .query_json(&format!(
+                "SELECT pid, agent_type, command FROM agents_inventory \
+                 WHERE machine_id = '{id}' AND first_seen_at <= '{at_text}' \
+                 AND (ended_at IS NULL OR ended_at > '{at_text}') ORDER BY pid"
+            ))?
+            .iter()
+            .map(|row| {
+                let agent_type = text(row, "agent_type").unwrap_or_default();
+                let command = text(row, "command").unwrap_or_else(|| agent_type.clone());
+                let name = command
+                    .split_whitespace()
+                    .next()
+                    .map_or(agent_type, |program| program_name(program).to_string());
+                RecordedProcess {
+                    pid: row["pid"].as_i64(),
+                    name,
+                    command,
+                    source: "agents_inventory".to_string(),
+                }
+            })
+            .collect();
+        for row in store.query_json(&format!(
+            "SELECT pid, comm FROM sys_top_processes \
+             WHERE machine_id = '{id}' AND collected_at = ({}) ORDER BY pid",
+            newest_sample("sys_top_processes")
+        ))? {
+            let pid = row["pid"].as_i64();
+            if processes.iter().any(|p| p.pid.is_some() && p.pid == pid) {
+                continue;
+            }
+            let comm = text(&row, "comm").unwrap_or_default();
+            processes.push(RecordedProcess {
+                pid,
+                name: comm.clone(),
+                command: comm,
+                source: "sys_top_processes".to_string(),
+            });
+        }
+
+        let open_alert_rules = store
+            .query_json(&format!(
+                "SELECT DISTINCT rule_id FROM alert_history \
+                 WHERE machine_id = '{id}' AND rule_id IS NOT NULL \
+                 AND fired_at <= '{at_text}' \
+                 AND (resolved_at IS NULL OR resolved_at > '{at_text}') ORDER BY rule_id"
+            ))?
+            .iter()
+            .filter_map(|row| text(row, "rule_id"))
+            .collect();
+
+        Ok(Self {
+            machine_id: machine_id.to_string(),
+            at,
+            filesystems,
+            filesystems_sampled_at,
+            processes,
+            open_alert_rules,
+        })
+    }
+
+    /// The filesystem an absolute path lives on: the longest mount that
+    /// contains it
+    #[must_use]
+    pub fn filesystem_of(&self, path: &str) -> Option<&RecordedFilesystem> {
+        if !path.starts_with('/') {
+            return None;
+        }
+        self.filesystems
+            .iter()
+            .filter(|fs| {
+                fs.mount == "/" || path == fs.mount || path.starts_with(&format!("{}/", fs.mount))
+            })
+            .max_by_key(|fs| fs.mount.len())
+    }
+
+    fn at_text(&self) -> String {
+        self.at.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+}
+
+/// Whether the playbook's trigger held at the sandbox instant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerCheck {
+    pub condition: String,
+    /// `None` when it could not be judged from recorded data
+    pub met: Option<bool>,
+    pub detail: String,
+}
+
+impl TriggerCheck {
+    /// Judge `trigger` against `state`; threshold queries are run with
+    /// `current_timestamp` pinned to the state's instant
+    #[must_use]
+    pub fn evaluate(store: &VcStore, trigger: &PlaybookTrigger, state: &RecordedState) -> Self {
+        match trigger {
+            PlaybookTrigger::Manual => Self {
+                condition: "manual".to_string(),
+                met: Some(true),
+                detail: "runs when triggered by hand".to_string(),
+            },
+            PlaybookTrigger::OnAlert { rule_id } => {
+                let open = state.open_alert_rules.contains(rule_id);
+                Self {
+                    condition: format!("alert {rule_id} open"),
+                    met: Some(open),
+                    detail: format!(
+                        "{} {rule_id} alert open on {} at {}",
+                        if open { "an" } else { "no" },
+                        state.machine_id,
+                        state.at_text()
+                    ),
+                }
+            }
+            PlaybookTrigger::OnThreshold {
+                query,
+                operator,
+                value,
+            } => {
+                let condition = format!("({query}) {operator} {value}");
+                let unjudged = |detail: String| Self {
+                    condition: condition.clone(),
+                    met: None,
+                    detail,
+                };
+                let Ok(op) = serde_json::from_value::<ThresholdOp>(serde_json::Value::String(
+                    operator.to_lowercase(),
+                )) else {
+                    return unjudged(format!("unknown operator {operator}"));
+                };
+                let rows = match store.query_json(&vc_alert::evaluate::query_as_of(query, state.at))
+                {
+                    Ok(rows) => rows,
+                    Err(e) => return unjudged(format!("query failed: {e}")),
+                };
+                let actual = rows
+                    .first()
+                    .and_then(serde_json::Value::as_object)
+                    .and_then(|row| {
+                        row.iter()
+                            .filter(|(key, _)| key.as_str() != "machine_id")
+                            .find_map(|(_, v)| v.as_f64())
+                    });
+                let Some(actual) = actual else {
+                    return unjudged(format!("query returned no value at {}", state.at_text()));
+                };
+                Self {
+                    met: Some(op.check(actual, *value)),
+                    detail: format!("query gave {actual} at {}", state.at_text()),
+                    condition,
+                }
+            }
+        }
+    }
+}
+
+/// How a step's effect is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectKind {
+    /// Changes nothing on the machine
+    NoChange,
+    /// Estimated from recorded state
+    Predicted,
+    /// No prediction model for the command
+    Unknown,
+}
+
+/// What a step would do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepEffect {
+    pub kind: EffectKind,
+    pub summary: String,
+    /// Processes a kill would signal
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub targets: Vec<String>,
+    /// Upper bound on the bytes a deletion frees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes_freed: Option<i64>,
+}
+
+impl StepEffect {
+    fn no_change(summary: String) -> Self {
+        Self {
+            kind: EffectKind::NoChange,
+            summary,
+            targets: Vec::new(),
+            max_bytes_freed: None,
+        }
+    }
+
+    fn predicted(summary: String) -> Self {
+        Self {
+            kind: EffectKind::Predicted,
+            ..Self::no_change(summary)
+        }
+    }
+
+    fn unknown(summary: String) -> Self {
+        Self {
+            kind: EffectKind::Unknown,
+            ..Self::no_change(summary)
+        }
+    }
+}
+
+/// One step of a sandbox report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxStep {
+    /// 1-based position in its list
+    pub index: usize,
+    pub step_type: String,
+    /// When the step runs
+    pub condition: String,
+    /// The exact command line, for command steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command_line: Option<String>,
+    pub effect: StepEffect,
+}
+
+/// A playbook walked against recorded state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxReport {
+    pub playbook_id: String,
+    pub playbook_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft_revision: Option<i64>,
+    pub machine_id: String,
+    pub at: String,
+    pub parameters: BTreeMap<String, String>,
+    pub trigger: TriggerCheck,
+    pub steps: Vec<SandboxStep>,
+    /// Run when a step fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rollback: Vec<SandboxStep>,
+    /// Steps whose effect has no prediction model
+    pub unknown_effects: usize,
+}
+
+/// Renders and predicts commands against recorded state. It holds nothing
+/// that can reach a machine.
+#[derive(Debug, Clone, Copy)]
+pub struct SandboxExecutor<'a> {
+    state: &'a RecordedState,
+}
+
+impl<'a> SandboxExecutor<'a> {
+    #[must_use]
+    pub fn new(state: &'a RecordedState) -> Self {
+        Self { state }
+    }
+
+    /// The effect `command` would have, as far as recorded state tells
+    #[must_use]
+    pub fn predict(&self, command: &RenderedCommand) -> StepEffect {
+        let Some((program, args)) = without_sudo(&command.program, &command.args) else {
+            return StepEffect::unknown("sudo with no command".to_string());
+        };
+        match program_name(program) {
+            "find" => self.predict_find(args),
+            "rm" => {
+                let paths: Vec<&str> = non_flags(args, &[]);
+                if paths.is_empty() {
+                    StepEffect::no_change("removes nothing".to_string())
+                } else {
+                    self.deletion("removes", &paths)
+                }
+            }
+            "pkill" => self.predict_pkill(args),
+            "killall" => {
+                let names = non_flags(args, &["-s", "--signal", "-u", "--user"]);
+                self.kill(&format!("`{}`", names.join(" ")), |p| {
+                    names.contains(&p.name.as_str())
+                })
+            }
+            "kill" => self.predict_kill(args),
+            other => StepEffect::unknown(format!("no prediction model for {other}")),
+        }
+    }
+
+    fn predict_find(&self, args: &[String]) -> StepEffect {
+        let runs = |pred: fn(&str) -> bool| {
+            args.windows(2).any(|pair| {
+                matches!(pair[0].as_str(), "-exec" | "-execdir" | "-ok" | "-okdir")
+                    && pred(program_name(&pair[1]))
+            })
+        };
+        if runs(|program| program != "rm") {
+            return StepEffect::unknown("find runs a command on its matches".to_string());
+        }
+        if !args.iter().any(|arg| arg == "-delete") && !runs(|program| program == "rm") {
+            return StepEffect::no_change("lists matching files; deletes nothing".to_string());
+        }
+        // Starting points come before the first expression
+        let mut roots: Vec<&str> = args
+            .iter()
+            .take_while(|arg| !arg.starts_with('-') && !matches!(arg.as_str(), "(" | "!"))
+            .map(String::as_str)
+            .collect();
+        if roots.is_empty() {
+            roots.push(".");
+        }
+        self.deletion("deletes matching files under", &roots)
+    }
+
+    /// A deletion under `paths`, bounded by their filesystems' used bytes
+    fn deletion(&self, verb: &str, paths: &[&str]) -> StepEffect {
+        let mut mounts: Vec<&RecordedFilesystem> = Vec::new();
+        let mut unplaced = Vec::new();
+        for path in paths {
+            match self.state.filesystem_of(path) {
+                Some(fs) if !mounts.iter().any(|m| m.mount == fs.mount) => mounts.push(fs),
+                Some(_) => {}
+                None => unplaced.push(*path),
+            }
+        }
+        let paths = paths.join(" ");
+        if mounts.is_empty() {
+            return StepEffect::unknown(format!("{verb} {paths}; no recorded filesystem holds it"));
+        }
+        let bound: i64 = mounts.iter().map(|fs| fs.used_bytes).sum();
+        let names: Vec<&str> = mounts.iter().map(|fs| fs.mount.as_str()).collect();
+        let mut summary = format!(
+            "{verb} {paths}; frees at most {bound} bytes, the used space of {} as sampled at {} \
+             (file sizes and ages are not recorded)",
+            names.join(", "),
+            self.state
+                .filesystems_sampled_at
+                .as_deref()
+                .unwrap_or("an unknown time")
+        );
+        if !unplaced.is_empty() {
+            summary.push_str(&format!(
+                "; no recorded filesystem holds {}",
+                unplaced.join(" ")
+            ));
+        }
+        StepEffect {
+            max_bytes_freed: Some(bound),
+            ..StepEffect::predicted(summary)
+        }
+    }
+
+    fn predict_pkill(&self, args: &[String]) -> StepEffect {
+        // Short flags may be bundled, as in `-fx`
+        let has_flag = |short: char, long: &str| {
+            args.iter().any(|arg| {
+                arg == long
+                    || arg.strip_prefix('-').is_some_and(|flags| {
+                        flags.contains(short) && flags.chars().all(|c| c.is_ascii_lowercase())
+                    })
+            })
+        };
+        let (full, exact) = (has_flag('f', "--full"), has_flag('x', "--exact"));
+        let Some(pattern) = args.iter().rev().find(|arg| !arg.starts_with('-')) else {
+            return StepEffect::unknown("pkill without a pattern".to_string());
+        };
+        let anchored = if exact {
+            format!("^(?:{pattern})$")
+        } else {
+            pattern.clone()
+        };
+        let Ok(regex) = Regex::new(&anchored) else {
+            return StepEffect::unknown(format!("pattern {pattern} is not a regex vc understands"));
+        };
+        self.kill(&format!("matching `{pattern}`"), |p| {
+            regex.is_match(if full { &p.command } else { &p.name })
+        })
+    }
+
+    fn predict_kill(&self, args: &[String]) -> StepEffect {
+        let pids: Vec<i64> = non_flags(args, &["-s", "-n"])
+            .iter()
+            .filter_map(|arg| arg.parse().ok())
+            .filter(|pid| *pid > 0)
+            .collect();
+        if pids.is_empty() {
+            return StepEffect::unknown("kill names no literal pid".to_string());
+        }
+        let listed: Vec<String> = pids.iter().map(ToString::to_string).collect();
+        let mut effect = self.kill(&format!("with pid {}", listed.join(", ")), |p| {
+            p.pid.is_some_and(|pid| pids.contains(&pid))
+        });
+        for pid in pids {
+            if !self.state.processes.iter().any(|p| p.pid == Some(pid)) {
+                effect.targets.push(format!("pid {pid}: not recorded"));
+            }
+        }
+        effect
+    }
+
+    /// Signalling the recorded processes `matches` selects
+    fn kill(&self, what: &str, matches: impl Fn(&RecordedProcess) -> bool) -> StepEffect {
+        let targets: Vec<String> = self
+            .state
+            .processes
+            .iter()
+            .filter(|p| matches(p))
+            .map(|p| {
+                format!(
+                    "pid {}: {} [{}]",
+                    p.pid.map_or_else(|| "?".to_string(), |pid| pid.to_string()),
+                    p.command,
+                    p.source
+                )
+            })
+            .collect();
+        let summary = if targets.is_empty() {
+            format!(
+                "signals processes {what}; none recorded at {}",
+                self.state.at_text()
+            )
+        } else {
+            format!("signals {} recorded process(es) {what}", targets.len())
+        };
+        StepEffect {
+            targets,
+            ..StepEffect::predicted(summary)
+        }
+    }
+}
+
+/// The command `sudo` runs, skipping its options; other commands as they are
+fn without_sudo<'c>(program: &'c str, args: &'c [String]) -> Option<(&'c str, &'c [String])> {
+    if program_name(program) != "sudo" {
+        return Some((program, args));
+    }
+    let mut i = 0;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "-u" | "-g" | "-C" | "-h" => i += 2,
+            flag if flag.starts_with('-') => i += 1,
+            _ => break,
+        }
+    }
+    let (inner, rest) = args.get(i..)?.split_first()?;
+    Some((inner.as_str(), rest))
+}
+
+/// Arguments that are not options, skipping the values of `valued` options
+fn non_flags<'c>(args: &'c [String], valued: &[&str]) -> Vec<&'c str> {
+    let mut found = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if valued.contains(&arg.as_str()) {
+            iter.next();
+        } else if !arg.starts_with('-') {
+            found.push(arg.as_str());
+        }
+    }
+    found
+}
+
+/// Walk `steps`, gating each on the last earlier command that may not fail
+fn walk(steps: &[PlaybookStep], executor: SandboxExecutor<'_>, base: &str) -> Vec<SandboxStep> {
+    let mut gate: Option<(usize, String)> = None;
+    let mut walked = Vec::with_capacity(steps.len());
+    for (i, step) in steps.iter().enumerate() {
+        let index = i + 1;
+        let condition = gate.as_ref().map_or_else(
+            || base.to_string(),
+            |(at, program)| format!("if step {at} ({program}) succeeds"),
+        );
+        let (command_line, effect) = match step {
+            PlaybookStep::Log { message } => {
+                (None, StepEffect::no_change(format!("logs \"{message}\"")))
+            }
+            PlaybookStep::Notify { channel, message } => (
+                None,
+                StepEffect::no_change(format!("notifies {channel}: \"{message}\"")),
+            ),
+            PlaybookStep::Wait { seconds } => {
+                (None, StepEffect::no_change(format!("waits {seconds}s")))
+            }
+            PlaybookStep::SwitchAccount { program, strategy } => (
+                None,
+                StepEffect::unknown(format!("switches the {program} account ({strategy})")),
+            ),
+            PlaybookStep::Command {
+                cmd,
+                args,
+                allow_failure,
+                ..
+            } => {
+                let command = RenderedCommand {
+                    program: cmd.clone(),
+                    args: args.clone(),
+                };
+                if !allow_failure {
+                    gate = Some((index, program_name(cmd).to_string()));
+                }
+                (Some(command.command_line()), executor.predict(&command))
+            }
+        };
+        walked.push(SandboxStep {
+            index,
+            step_type: step.type_name().to_string(),
+            condition,
+            command_line,
+            effect,
+        });
+    }
+    walked
+}
+
+/// Walk `target` against `state` with `supplied` parameter values.
+/// Nothing is executed.
+///
+/// # Errors
+///
+/// Returns [`GuardianError::InvalidParameter`] if the parameters do not
+/// resolve, or [`GuardianError::InvalidPlaybook`] if the resolved steps no
+/// longer parse.
+pub fn simulate(
+    target: &SandboxTarget,
+    supplied: &BTreeMap<String, String>,
+    state: &RecordedState,
+    trigger: TriggerCheck,
+) -> Result<SandboxReport, GuardianError> {
+    let playbook = &target.playbook;
+    let parameters = resolve_parameters(&playbook.parameters, supplied)?;
+    let values: HashMap<&str, String> = parameters
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.clone()))
+        .collect();
+    let mut steps = serde_json::json!([playbook.steps, playbook.rollback]);
+    for_each_string(&mut steps, &mut |text| *text = substitute(text, &values));
+    let [steps, rollback]: [Vec<PlaybookStep>; 2] = serde_json::from_value(steps)
+        .map_err(|e| GuardianError::InvalidPlaybook(format!("resolved steps: {e}")))?;
+
+    let executor = SandboxExecutor::new(state);
+    let steps = walk(&steps, executor, "always");
+    let rollback = walk(&rollback, executor, "if a step fails");
+    let unknown_effects = steps
+        .iter()
+        .chain(&rollback)
+        .filter(|step| step.effect.kind == EffectKind::Unknown)
+        .count();
+    Ok(SandboxReport {
+        playbook_id: playbook.playbook_id(),
+        playbook_name: playbook.name.clone(),
+        draft_id: target.draft.as_ref().map(|(id, _)| id.clone()),
+        draft_revision: target.draft.as_ref().map(|(_, revision)| *revision),
+        machine_id: state.machine_id.clone(),
+        at: state.at_text(),
+        parameters,
+        trigger,
+        steps,
+        rollback,
+        unknown_effects,
+    })
+}
+
+/// Load `machine_id`'s state as of `at`, judge the trigger and walk the
+/// playbook. Nothing is executed.
+///
+/// # Errors
+///
+/// As [`RecordedState::load`] and [`simulate`].
+pub fn run_sandbox(
+    store: &VcStore,
+    target: &SandboxTarget,
+    supplied: &BTreeMap<String, String>,
+    machine_id: &str,
+    at: DateTime<Utc>,
+) -> Result<SandboxReport, GuardianError> {
+    let state = RecordedState::load(store, machine_id, at)?;
+    let trigger = TriggerCheck::evaluate(store, &target.playbook.trigger, &state);
+    simulate(target, supplied, &state, trigger)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CommandExecutor;
+    use crate::library::FileFormat;
+    use mockall::mock;
+
+    mock! {
+        Executor {}
+
+        impl CommandExecutor for Executor {
+            fn run(&mut self, machine_id: &str, command: &RenderedCommand) -> Result<i32, GuardianError>;
+        }
+    }
+
+    fn ts(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn state() -> RecordedState {
+        RecordedState {
+            machine_id: "orko".to_string(),
+            at: ts("2026-10-16T12:00:00Z"),
+            filesystems: vec![
+                RecordedFilesystem {
+                    mount: "/".to_string(),
+                    total_bytes: 100_000,
+                    used_bytes: 60_000,
+                },
+                RecordedFilesystem {
+                    mount: "/data".to_string(),
+                    total_bytes: 500_000,
+                    used_bytes: 400_000,
+                },
+            ],
+            filesystems_sampled_at: Some("2026-10-16T11:59:00Z".to_string()),
+            processes: vec![
+                RecordedProcess {
+                    pid: Some(41),
+                    name: "claude".to_string(),
+                    command: "claude --resume".to_string(),
+                    source: "agents_inventory".to_string(),
+                },
+                RecordedProcess {
+                    pid: Some(77),
+                    name: "node".to_string(),
+                    command: "node".to_string(),
+                    source: "sys_top_processes".to_string(),
+                },
+            ],
+            open_alert_rules: vec!["disk-full".to_string()],
+        }
+    }
+
+    fn predict(line: &[&str]) -> StepEffect {
+        let state = state();
+        SandboxExecutor::new(&state).predict(&RenderedCommand {
+            program: line[0].to_string(),
+            args: line[1..].iter().map(ToString::to_string).collect(),
+        })
+    }
+
+    #[test]
+    fn test_predict_deletions() {
+        let effect = predict(&["find", "/data/cache", "-mtime", "+7", "-delete"]);
+        assert_eq!(effect.kind, EffectKind::Predicted);
+        assert_eq!(effect.max_bytes_freed, Some(400_000));
+
+        // Both filesystems, each counted once, under sudo
+        let effect = predict(&["sudo", "-n", "rm", "-rf", "/tmp/a", "/tmp/b", "/data/x"]);
+        assert_eq!(effect.max_bytes_freed, Some(460_000));
+
+        assert_eq!(
+            predict(&["find", "/tmp", "-name", "*.log"]).kind,
+            EffectKind::NoChange
+        );
+        assert_eq!(
+            predict(&["find", "/tmp", "-exec", "gzip", "{}", ";"]).kind,
+            EffectKind::Unknown
+        );
+        assert_eq!(
+            predict(&["rm", "-rf", "relative"]).kind,
+            EffectKind::Unknown
+        );
+    }
+
+    #[test]
+    fn test_predict_kills() {
+        let effect = predict(&["pkill", "-f", "claude --res"]);
+        assert_eq!(effect.targets.len(), 1);
+        assert!(effect.targets[0].starts_with("pid 41:"));
+        // Without -f only the name is matched
+        assert!(predict(&["pkill", "resume"]).targets.is_empty());
+        assert!(predict(&["pkill", "-x", "nod"]).targets.is_empty());
+        assert_eq!(predict(&["killall", "-s", "KILL", "node"]).targets.len(), 1);
+
+        let effect = predict(&["kill", "-9", "77", "99"]);
+        assert_eq!(
+            effect.targets,
+            vec!["pid 77: node [sys_top_processes]", "pid 99: not recorded"]
+        );
+        assert_eq!(predict(&["kill", "%1"]).kind, EffectKind::Unknown);
+        assert_eq!(
+            predict(&["systemctl", "restart", "x"]).kind,
+            EffectKind::Unknown
+        );
+    }
+
+    #[test]
+    fn test_resolve_parameters() {
+        let declared = vec![
+            ParameterDecl {
+                name: "path".to_string(),
+                description: String::new(),
+                default: Some("/tmp".to_string()),
+            },
+            ParameterDecl {
+                name: "days".to_string(),
+                description: String::new(),
+                default: None,
+            },
+        ];
+        let supplied = BTreeMap::from([("days".to_string(), "7".to_string())]);
+        let resolved = resolve_parameters(&declared, &supplied).unwrap();
+        assert_eq!(resolved["path"], "/tmp");
+        assert_eq!(resolved["days"], "7");
+
+        assert!(matches!(
+            resolve_parameters(&declared, &BTreeMap::new()),
+            Err(GuardianError::InvalidParameter(_))
+        ));
+        let mut extra = supplied.clone();
+        extra.insert("target".to_string(), "x".to_string());
+        assert!(matches!(
+            resolve_parameters(&declared, &extra),
+            Err(GuardianError::InvalidParameter(_))
+        ));
+    }
+
+    const CLEANUP: &str = r#"
+name: Data Cleanup
+trigger: { type: on_alert, rule_id: disk-full }
+parameters:
+  - { name: path, default: /data/cache }
+steps:
+  - { type: command, cmd: find, args: ["{{path}}", -mtime, "+7", -delete],
+      timeout_secs: 60, allow_failure: false }
+  - { type: command, cmd: pkill, args: [-f, claude], timeout_secs: 10, allow_failure: true }
+  - { type: notify, channel: tui, message: "pruned {{path}}" }
+rollback:
+  - { type: command, cmd: systemctl, args: [restart, vc-agent], timeout_secs: 10,
+      allow_failure: true }
+"#;
+
+    #[test]
+    fn test_sandbox_run_uses_state_as_of_and_never_executes() {
+        let mut executor = MockExecutor::new();
+        executor.expect_run().never();
+
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname) VALUES ('orko', 'orko');
+                 INSERT INTO sys_filesystems VALUES
+                     ('orko', '2026-10-16T10:00:00Z', '/data', 1000, 300, 30.0),
+                     ('orko', '2026-10-16T14:00:00Z', '/data', 1000, 900, 90.0);
+                 INSERT INTO agents_inventory (machine_id, agent_key, agent_type, source, pid,
+                     command, first_seen_at, last_seen_at, ended_at) VALUES
+                     ('orko', 'pid:41', 'claude-code', 'process', 41, 'claude --resume',
+                      '2026-10-16T09:00:00Z', '2026-10-16T11:00:00Z', NULL),
+                     ('orko', 'pid:12', 'claude-code', 'process', 12, 'claude',
+                      '2026-10-16T08:00:00Z', '2026-10-16T09:00:00Z', '2026-10-16T09:00:00Z');
+                 INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id)
+                     VALUES (1, 'disk-full', '2026-10-16T10:30:00Z', 'warning', 'Disk', 'orko');",
+            )
+            .unwrap();
+
+        let target = SandboxTarget {
+            playbook: PlaybookFile::parse(CLEANUP, FileFormat::Yaml).unwrap(),
+            draft: None,
+        };
+        let at = ts("2026-10-16T12:00:00Z");
+        let report = run_sandbox(&store, &target, &BTreeMap::new(), "orko", at).unwrap();
+
+        assert_eq!(report.trigger.met, Some(true));
+        let [find, pkill, notify] = report.steps.as_slice() else {
+            panic!("expected three steps");
+        };
+        assert_eq!(
+            find.command_line.as_deref(),
+            Some("find /data/cache -mtime +7 -delete")
+        );
+        // The sample before `at`, not the later one
+        assert_eq!(find.effect.max_bytes_freed, Some(300));
+        assert_eq!(pkill.condition, "if step 1 (find) succeeds");
+        assert_eq!(pkill.effect.targets.len(), 1);
+        assert_eq!(
+            notify.effect.summary,
+            "notifies tui: \"pruned /data/cache\""
+        );
+        assert_eq!(report.rollback[0].condition, "if a step fails");
+        assert_eq!(report.unknown_effects, 1);
+
+        // Before the alert fired the trigger did not hold
+        let early = run_sandbox(
+            &store,
+            &target,
+            &BTreeMap::new(),
+            "orko",
+            ts("2026-10-16T10:15:00Z"),
+        )
+        .unwrap();
+        assert_eq!(early.trigger.met, Some(false));
+
+        assert!(matches!(
+            run_sandbox(&store, &target, &BTreeMap::new(), "nowhere", at),
+            Err(GuardianError::UnknownMachine(_))
+        ));
+        executor.checkpoint();
+    }
+
+    #[test]
+    fn test_resolve_target_falls_back_to_builtin_and_drafts() {
+        let store = VcStore::open_memory().unwrap();
+        let guardian = Guardian::new();
+        let builtin = SandboxTarget::resolve(&store, &guardian, "stuck-agent-restart").unwrap();
+        assert!(builtin.draft.is_none());
+
+        store
+            .execute_batch(
+                "INSERT INTO playbook_drafts (draft_id, name, alert_type, trigger_json, steps_json) \
+                 VALUES ('draft-1', 'Cleanup', 'disk-full', \
+                 '{\"type\":\"manual\"}', '[{\"type\":\"wait\",\"seconds\":5}]')",
+            )
+            .unwrap();
+        let draft = SandboxTarget::resolve(&store, &guardian, "draft-1").unwrap();
+        assert_eq!(draft.draft, Some(("draft-1".to_string(), 1)));
+        assert_eq!(draft.playbook.steps.len(), 1);
+
+        assert!(matches!(
+            SandboxTarget::resolve(&store, &guardian, "missing"),
+            Err(GuardianError::PlaybookNotFound(_))
+        ));
+    }
+}
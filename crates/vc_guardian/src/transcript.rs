@@ -0,0 +1,330 @@
+//! Command extraction for assisted resolution capture
+//!
+//! Operators rarely hand-assemble an actions list for `vc guardian
+//! capture`, so the commands that fixed an alert are pulled from where they
+//! were already recorded instead:
+//!
+//! - an agent session transcript (`agent_sessions.raw_json`), windowed
+//!   around the alert's firing and resolution, or
+//! - the machine's shell history (bash `HISTTIMEFORMAT` stamps or zsh
+//!   extended history), windowed to the last few minutes.
+//!
+//! Extraction is purely syntactic. Redacting secrets and letting the
+//! operator trim the list happen before anything is stored.
+
+use crate::autogen::CapturedAction;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Reads both common history files; missing ones are skipped
+pub const SHELL_HISTORY_COMMAND: &str =
+    "cat ~/.bash_history ~/.zsh_history 2>/dev/null | tail -n 5000";
+
+/// History entries kept when the history carries no timestamps at all
+pub const UNTIMED_HISTORY_TAIL: usize = 50;
+
+/// Fields holding a transcript entry's time, in order of preference
+const TIME_FIELDS: &[&str] = &["timestamp", "ts", "created_at", "time"];
+
+/// Fields holding a shell command within a transcript entry
+const COMMAND_FIELDS: &[&str] = &["command", "cmd"];
+
+/// A command found in a transcript or shell history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedCommand {
+    /// When it ran, if the source recorded it
+    pub at: Option<DateTime<Utc>>,
+    pub command: String,
+    /// Whether it succeeded, if the source recorded it
+    pub success: Option<bool>,
+}
+
+impl ExtractedCommand {
+    /// The captured action this command amounts to: a service restart when
+    /// it is `systemctl restart <unit>`, a plain command otherwise
+    #[must_use]
+    pub fn to_action(&self) -> CapturedAction {
+        let mut words: Vec<&str> = self.command.split_whitespace().collect();
+        if words.first() == Some(&"sudo") {
+            words.remove(0);
+        }
+        if let ["systemctl", "restart", unit, ..] = words.as_slice() {
+            return CapturedAction::ServiceRestart {
+                name: (*unit).to_string(),
+            };
+        }
+        CapturedAction::Command {
+            cmd: words.first().copied().unwrap_or_default().to_string(),
+            args: words.iter().skip(1).map(ToString::to_string).collect(),
+            success: self.success.unwrap_or(true),
+        }
+    }
+}
+
+/// Where an assisted capture took its actions from; stored with the
+/// resolution
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureProvenance {
+    /// `session` or `shell_history`
+    pub source: String,
+    pub session_id: Option<String>,
+    pub machine_id: Option<String>,
+    /// Alert whose firing and resolution framed the window
+    pub alert_id: Option<i64>,
+    pub window_start: Option<DateTime<Utc>>,
+    pub window_end: Option<DateTime<Utc>>,
+    /// Commands found in the window
+    pub extracted: usize,
+    /// Commands the operator kept
+    pub kept: usize,
+    /// Secrets redacted from the kept commands
+    pub redactions: usize,
+}
+
+/// Parse a stored transcript: a JSON document, or JSON lines (one entry per
+/// line, unparseable lines skipped)
+#[must_use]
+pub fn parse_transcript(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| {
+        serde_json::Value::Array(
+            raw.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+        )
+    })
+}
+
+/// Commands run in a transcript, in order, keeping those inside `window`.
+///
+/// Any object with a `command`/`cmd` string counts as one (tool-call inputs
+/// in practice); it takes its time from the nearest enclosing entry that has
+/// one, and its outcome from an `exit_code` or `is_error` alongside it.
+/// Commands whose time is unknown are kept: the operator trims them.
+#[must_use]
+pub fn extract_session_commands(
+    transcript: &serde_json::Value,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<ExtractedCommand> {
+    let mut found = Vec::new();
+    walk(transcript, None, &mut found);
+    found.retain(|command| match (window, command.at) {
+        (Some((start, end)), Some(at)) => at >= start && at <= end,
+        _ => true,
+    });
+    found
+}
+
+fn walk(value: &serde_json::Value, at: Option<DateTime<Utc>>, found: &mut Vec<ExtractedCommand>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let at = TIME_FIELDS
+                .iter()
+                .find_map(|field| {
+                    let raw = fields.get(*field)?.as_str()?;
+                    DateTime::parse_from_rfc3339(raw).ok()
+                })
+                .map(|ts| ts.with_timezone(&Utc))
+                .or(at);
+            if let Some(command) = COMMAND_FIELDS
+                .iter()
+                .find_map(|field| fields.get(*field)?.as_str())
+                .map(str::trim)
+                .filter(|command| !command.is_empty())
+            {
+                let success = fields
+                    .get("exit_code")
+                    .and_then(serde_json::Value::as_i64)
+                    .map(|code| code == 0)
+                    .or_else(|| {
+                        fields
+                            .get("is_error")
+                            .and_then(serde_json::Value::as_bool)
+                            .map(|error| !error)
+                    });
+                found.push(ExtractedCommand {
+                    at,
+                    command: command.to_string(),
+                    success,
+                });
+            }
+            for (key, child) in fields {
+                if !COMMAND_FIELDS.contains(&key.as_str()) {
+                    walk(child, at, found);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                walk(item, at, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Shell history entries run since `since`.
+///
+/// Understands bash `#<epoch>` stamp lines and zsh `: <epoch>:<secs>;cmd`
+/// entries. History without any timestamps cannot be windowed, so its last
+/// [`UNTIMED_HISTORY_TAIL`] entries are returned instead (with no `at`).
+/// `vc guardian capture` invocations themselves are left out.
+#[must_use]
+pub fn parse_shell_history(text: &str, since: DateTime<Utc>) -> Vec<ExtractedCommand> {
+    let mut entries = Vec::new();
+    let mut pending_at = None;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if let Some(epoch) = line.strip_prefix('#')
+            && let Ok(epoch) = epoch.parse::<i64>()
+        {
+            pending_at = DateTime::from_timestamp(epoch, 0);
+            continue;
+        }
+        let (at, command) = match parse_zsh_entry(line) {
+            Some((at, command)) => (Some(at), command),
+            None => (pending_at.take(), line),
+        };
+        let command = command.trim();
+        if command.is_empty() || command.starts_with("vc guardian capture") {
+            continue;
+        }
+        entries.push(ExtractedCommand {
+            at,
+            command: command.to_string(),
+            success: None,
+        });
+    }
+
+    if entries.iter().any(|entry| entry.at.is_some()) {
+        entries.retain(|entry| entry.at.is_some_and(|at| at >= since));
+    } else {
+        let skip = entries.len().saturating_sub(UNTIMED_HISTORY_TAIL);
+        entries.drain(..skip);
+    }
+    entries
+}
+
+/// `: 1792144800:0;systemctl restart foo` → (time, command)
+fn parse_zsh_entry(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    let rest = line.strip_prefix(": ")?;
+    let (stamp, command) = rest.split_once(';')?;
+    let epoch = stamp.split(':').next()?.parse::<i64>().ok()?;
+    Some((DateTime::from_timestamp(epoch, 0)?, command))
+}
+
+/// Which of `len` listed commands to keep, from an operator's answer:
+/// empty or `all`, `none`, or 1-based numbers and ranges such as `1-3,5`.
+///
+/// # Errors
+///
+/// Returns a message naming the part of `spec` that is not a valid choice.
+pub fn select_commands(spec: &str, len: usize) -> Result<Vec<usize>, String> {
+    let spec = spec.trim().to_lowercase();
+    match spec.as_str() {
+        "" | "a" | "all" | "y" | "yes" => return Ok((0..len).collect()),
+        "n" | "none" => return Ok(Vec::new()),
+        _ => {}
+    }
+
+    let mut picked = Vec::new();
+    for part in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (from, to) = part.split_once('-').unwrap_or((part, part));
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (1..=len).contains(n))
+                .ok_or_else(|| format!("'{part}' is not in 1-{len}"))
+        };
+        let (from, to) = (parse(from)?, parse(to)?);
+        if from > to {
+            return Err(format!("'{part}' is backwards"));
+        }
+        for n in from..=to {
+            if !picked.contains(&(n - 1)) {
+                picked.push(n - 1);
+            }
+        }
+    }
+    picked.sort_unstable();
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_extract_session_commands_windowed() {
+        let transcript = parse_transcript(
+            r#"{"type":"user","timestamp":"2026-10-16T09:00:00Z","message":"hi"}
+{"type":"assistant","timestamp":"2026-10-16T10:02:00Z","content":[{"type":"tool_use","name":"Bash","input":{"command":"caam switch claude --next"}}]}
+{"type":"tool_result","timestamp":"2026-10-16T10:03:00Z","command":"systemctl restart vc-node","exit_code":0}
+not json
+{"type":"assistant","timestamp":"2026-10-16T12:00:00Z","input":{"command":"cargo build"}}"#,
+        );
+
+        let all = extract_session_commands(&transcript, None);
+        assert_eq!(all.len(), 3);
+
+        let window = (at("2026-10-16T10:00:00Z"), at("2026-10-16T10:30:00Z"));
+        let commands = extract_session_commands(&transcript, Some(window));
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].command, "caam switch claude --next");
+        assert_eq!(commands[0].at, Some(at("2026-10-16T10:02:00Z")));
+        assert_eq!(commands[1].success, Some(true));
+        assert!(matches!(
+            commands[1].to_action(),
+            CapturedAction::ServiceRestart { ref name } if name == "vc-node"
+        ));
+        assert!(matches!(
+            commands[0].to_action(),
+            CapturedAction::Command { ref cmd, ref args, success: true }
+                if cmd == "caam" && args.len() == 3
+        ));
+    }
+
+    #[test]
+    fn test_parse_shell_history_timestamped_and_untimed() {
+        let since = at("2026-10-16T10:00:00Z");
+        let bash = "#1792140000\nls\n#1792144900\nsudo systemctl restart rch\n\
+                    #1792144960\nvc guardian capture --from-shell-history\n";
+        let commands = parse_shell_history(bash, since);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].command, "sudo systemctl restart rch");
+        assert!(matches!(
+            commands[0].to_action(),
+            CapturedAction::ServiceRestart { ref name } if name == "rch"
+        ));
+
+        let zsh = ": 1792144700:0;git pull\n: 1792144810:3;pkill -f stuck-agent\n";
+        let commands = parse_shell_history(zsh, since);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].at, Some(at("2026-10-16T10:00:10Z")));
+
+        let untimed: String = (0..60).map(|i| format!("echo {i}\n")).collect();
+        let commands = parse_shell_history(&untimed, since);
+        assert_eq!(commands.len(), UNTIMED_HISTORY_TAIL);
+        assert_eq!(commands[0].command, "echo 10");
+    }
+
+    #[test]
+    fn test_select_commands() {
+        assert_eq!(select_commands("", 3).unwrap(), vec![0, 1, 2]);
+        assert!(select_commands("none", 3).unwrap().is_empty());
+        assert_eq!(select_commands("3, 1-2,2", 4).unwrap(), vec![0, 1, 2]);
+        assert!(select_commands("5", 4).is_err());
+        assert!(select_commands("3-1", 4).is_err());
+        assert!(select_commands("x", 4).is_err());
+    }
+}
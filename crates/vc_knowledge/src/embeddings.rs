@@ -0,0 +1,593 @@
+//! Embedding-based semantic search (feature `embeddings`)
+//!
+//! Keyword search misses entries that say the same thing in other words
+//! ("ssh hangs on connect" vs "remote shell times out"). With an embedding
+//! backend configured, every entry gets a vector when it is inserted and
+//! [`SearchOptions::semantic`](crate::SearchOptions) ranks entries by cosine
+//! similarity to the query blended with the keyword score.
+//!
+//! Backends are either a local command (text on stdin, JSON float array on
+//! stdout) or an OpenAI-compatible HTTP endpoint. When the backend is down,
+//! inserts still succeed and search falls back to keyword-only with a
+//! warning; `vc knowledge reindex` backfills entries that have no vector for
+//! the configured model.
+
+use crate::{KnowledgeEntry, KnowledgeError, KnowledgeStore, SearchOptions, SearchResult};
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use vc_config::EmbeddingsConfig;
+
+/// Characters of entry text sent to the backend; longer content is cut
+const MAX_EMBED_CHARS: usize = 8_000;
+
+/// Reindex gives up after this many failures in a row (backend down)
+const MAX_CONSECUTIVE_FAILURES: usize = 3;
+
+/// Something that turns text into a vector
+pub trait Embedder: Send + Sync {
+    /// Model name recorded with each stored vector
+    fn model(&self) -> &str;
+
+    /// Embed one piece of text
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KnowledgeError::EmbeddingError`] when the backend fails or
+    /// returns something that is not a vector.
+    fn embed(&self, text: &str) -> Result<Vec<f32>, KnowledgeError>;
+}
+
+/// Build the configured backend, or `None` when embeddings are disabled
+#[must_use]
+pub fn embedder_from_config(config: &EmbeddingsConfig) -> Option<Box<dyn Embedder>> {
+    if !config.enabled {
+        return None;
+    }
+    let timeout = Duration::from_secs(config.timeout_secs.max(1));
+    if let Some(command) = &config.command {
+        return Some(Box::new(CommandEmbedder::new(
+            command,
+            &config.model,
+            timeout,
+        )));
+    }
+    config.endpoint.as_ref().map(|endpoint| {
+        Box::new(EndpointEmbedder::new(endpoint, &config.model, timeout)) as Box<dyn Embedder>
+    })
+}
+
+/// Local command reading text on stdin and printing a vector on stdout
+pub struct CommandEmbedder {
+    command: String,
+    model: String,
+    timeout: Duration,
+}
+
+impl CommandEmbedder {
+    #[must_use]
+    pub fn new(command: impl Into<String>, model: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            command: command.into(),
+            model: model.into(),
+            timeout,
+        }
+    }
+}
+
+impl Embedder for CommandEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, KnowledgeError> {
+        let fail = |msg: String| KnowledgeError::EmbeddingError(format!("{}: {msg}", self.command));
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| fail(e.to_string()))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let text = text.to_string();
+            std::thread::spawn(move || stdin.write_all(text.as_bytes()));
+        }
+        let mut stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| fail("no stdout".to_string()))?;
+        let reader = std::thread::spawn(move || {
+            let mut out = String::new();
+            stdout.read_to_string(&mut out).map(|_| out)
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            match child.try_wait().map_err(|e| fail(e.to_string()))? {
+                Some(status) => break status,
+                None if Instant::now() >= deadline => {
+                    child.kill().ok();
+                    child.wait().ok();
+                    return Err(fail(format!("timed out after {:?}", self.timeout)));
+                }
+                None => std::thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        if !status.success() {
+            return Err(fail(format!("exited with {status}")));
+        }
+        let out = reader
+            .join()
+            .map_err(|_| fail("stdout reader panicked".to_string()))?
+            .map_err(|e| fail(e.to_string()))?;
+        let value: serde_json::Value =
+            serde_json::from_str(out.trim()).map_err(|e| fail(e.to_string()))?;
+        parse_vector(&value).ok_or_else(|| fail("output is not a vector".to_string()))
+    }
+}
+
+/// OpenAI-compatible `/v1/embeddings` endpoint
+pub struct EndpointEmbedder {
+    endpoint: String,
+    model: String,
+    client: reqwest::blocking::Client,
+}
+
+impl EndpointEmbedder {
+    #[must_use]
+    pub fn new(endpoint: impl Into<String>, model: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            model: model.into(),
+            client: reqwest::blocking::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Embedder for EndpointEmbedder {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, KnowledgeError> {
+        let fail =
+            |msg: String| KnowledgeError::EmbeddingError(format!("{}: {msg}", self.endpoint));
+
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .json(&serde_json::json!({ "model": self.model, "input": text }))
+            .send()
+            .map_err(|e| fail(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(fail(format!("HTTP {}", response.status())));
+        }
+        let value: serde_json::Value = response.json().map_err(|e| fail(e.to_string()))?;
+        parse_vector(&value).ok_or_else(|| fail("response has no embedding".to_string()))
+    }
+}
+
+/// Accept a bare array, `{"embedding": [...]}` or `{"data": [{"embedding": [...]}]}`
+fn parse_vector(value: &serde_json::Value) -> Option<Vec<f32>> {
+    let array = value
+        .as_array()
+        .or_else(|| value["embedding"].as_array())
+        .or_else(|| value["data"][0]["embedding"].as_array())?;
+    #[allow(clippy::cast_possible_truncation)]
+    let vector: Option<Vec<f32>> = array.iter().map(|v| v.as_f64().map(|f| f as f32)).collect();
+    vector.filter(|v| !v.is_empty())
+}
+
+/// Cosine similarity of two vectors; 0.0 when the dimensions differ or
+/// either vector is all zeros
+#[must_use]
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+/// Text embedded for an entry: title, summary and the start of the content
+#[must_use]
+pub fn entry_text(entry: &KnowledgeEntry) -> String {
+    let mut text = entry.title.clone();
+    if let Some(summary) = &entry.summary {
+        text.push('\n');
+        text.push_str(summary);
+    }
+    text.push('\n');
+    text.extend(entry.content.chars().take(MAX_EMBED_CHARS));
+    text
+}
+
+/// Outcome of [`KnowledgeStore::reindex_missing`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReindexReport {
+    pub model: String,
+    /// Entries that had no vector for the model
+    pub missing: usize,
+    pub embedded: usize,
+    pub failed: usize,
+    /// Set when reindexing stopped early because the backend kept failing
+    pub aborted: Option<String>,
+}
+
+fn lock_error(e: impl std::fmt::Display) -> KnowledgeError {
+    KnowledgeError::StoreError(vc_store::StoreError::QueryError(format!("lock error: {e}")))
+}
+
+fn no_backend() -> KnowledgeError {
+    KnowledgeError::EmbeddingError("no embedding backend configured".to_string())
+}
+
+impl KnowledgeStore {
+    /// Embed entries on insert and enable [`SearchOptions::semantic`] ranking,
+    /// giving cosine similarity `semantic_weight` (0.0-1.0) of the score
+    #[must_use]
+    pub fn with_embedder(mut self, embedder: Box<dyn Embedder>, semantic_weight: f64) -> Self {
+        self.embedder = Some(embedder);
+        self.semantic_weight = semantic_weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Compute and store the embedding for one entry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no backend is configured, the backend fails, or
+    /// the vector cannot be stored.
+    pub fn embed_entry(&self, id: i64, entry: &KnowledgeEntry) -> Result<(), KnowledgeError> {
+        let embedder = self.embedder.as_deref().ok_or_else(no_backend)?;
+        let vector = embedder.embed(&entry_text(entry))?;
+        let sql = r"
+            INSERT INTO knowledge_embeddings (entry_id, model, dims, vector, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (entry_id) DO UPDATE SET
+                model = excluded.model,
+                dims = excluded.dims,
+                vector = excluded.vector,
+                created_at = excluded.created_at
+        ";
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        conn_guard.execute(
+            sql,
+            duckdb::params![
+                id,
+                embedder.model(),
+                i64::try_from(vector.len()).unwrap_or(i64::MAX),
+                serde_json::to_string(&vector)?,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Embed every entry that has no vector for the configured model,
+    /// calling `progress(done, total)` after each one
+    ///
+    /// Individual failures are counted and skipped; after
+    /// a few failures in a row the backend is assumed down and the run stops.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no backend is configured or the missing entries
+    /// cannot be listed.
+    pub fn reindex_missing(
+        &self,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<ReindexReport, KnowledgeError> {
+        let model = self
+            .embedder
+            .as_deref()
+            .ok_or_else(no_backend)?
+            .model()
+            .to_string();
+        let missing = {
+            let conn = self.store.connection();
+            let conn_guard = conn.lock().map_err(lock_error)?;
+            let mut stmt = conn_guard.prepare(
+                "SELECT e.* FROM knowledge_entries e \
+                 LEFT JOIN knowledge_embeddings k ON k.entry_id = e.id AND k.model = ? \
+                 WHERE k.entry_id IS NULL \
+                 ORDER BY e.id",
+            )?;
+            stmt.query_map([&model], Self::row_to_entry)?
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let mut report = ReindexReport {
+            model,
+            missing: missing.len(),
+            ..ReindexReport::default()
+        };
+        let mut consecutive_failures = 0;
+        for (i, entry) in missing.iter().enumerate() {
+            let Some(id) = entry.id else { continue };
+            match self.embed_entry(id, entry) {
+                Ok(()) => {
+                    report.embedded += 1;
+                    consecutive_failures = 0;
+                }
+                Err(e) => {
+                    report.failed += 1;
+                    consecutive_failures += 1;
+                    tracing::warn!(entry_id = id, error = %e, "failed to embed knowledge entry");
+                    if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                        report.aborted = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+            progress(i + 1, missing.len());
+        }
+        Ok(report)
+    }
+
+    /// Rank entries by cosine similarity to `query` blended with the keyword
+    /// score.
+    ///
+    /// The keyword score is half "query appears in title, summary or
+    /// content" and half the usual popularity score normalized to the best
+    /// candidate. Entries without a vector for the model rank on keyword
+    /// score alone.
+    pub(crate) fn semantic_search(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>, KnowledgeError> {
+        let embedder = self.embedder.as_deref().ok_or_else(no_backend)?;
+        let query_vector = embedder.embed(query)?;
+
+        // Entry types are a fixed set of identifiers, safe to inline
+        let mut conditions = vec!["1=1".to_string()];
+        if let Some(entry_type) = options.entry_type {
+            conditions.push(format!("e.entry_type = '{}'", entry_type.as_str()));
+        }
+        if !options.include_archived {
+            conditions.push("e.archived_at IS NULL".to_string());
+        }
+        let type_filter = format!("WHERE {}", conditions.join(" AND "));
+        let sql = format!(
+            "SELECT e.*, \
+                    (e.usefulness_score * 0.5 + e.view_count * 0.1 + e.applied_count * 0.3) AS score, \
+                    k.vector AS embedding_vector \
+             FROM knowledge_entries e \
+             LEFT JOIN knowledge_embeddings k ON k.entry_id = e.id AND k.model = ? \
+             {type_filter}"
+        );
+        let rows = {
+            let conn = self.store.connection();
+            let conn_guard = conn.lock().map_err(lock_error)?;
+            let mut stmt = conn_guard.prepare(&sql)?;
+            stmt.query_map([embedder.model()], |row| {
+                Ok((
+                    Self::row_to_entry(row)?,
+                    row.get::<_, f64>("score")?,
+                    row.get::<_, Option<String>>("embedding_vector")?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?
+        };
+
+        let max_popularity = rows
+            .iter()
+            .map(|(_, score, _)| *score)
+            .fold(0.0_f64, f64::max);
+        let needle = query.to_lowercase();
+        let weight = self.semantic_weight;
+
+        let mut results: Vec<SearchResult> = rows
+            .into_iter()
+            .map(|(entry, popularity, vector)| {
+                let cosine = vector
+                    .and_then(|s| serde_json::from_str::<Vec<f32>>(&s).ok())
+                    .map_or(0.0, |v| cosine_similarity(&query_vector, &v));
+                let matched = entry.title.to_lowercase().contains(&needle)
+                    || entry.content.to_lowercase().contains(&needle)
+                    || entry
+                        .summary
+                        .as_ref()
+                        .is_some_and(|s| s.to_lowercase().contains(&needle));
+                let keyword = 0.5 * f64::from(u8::from(matched))
+                    + if max_popularity > 0.0 {
+                        0.5 * (popularity.max(0.0) / max_popularity)
+                    } else {
+                        0.0
+                    };
+                let score = weight * cosine + (1.0 - weight) * keyword;
+                SearchResult { entry, score }
+            })
+            .collect();
+
+        results.retain(|r| r.score > 0.0 && options.min_score.is_none_or(|min| r.score >= min));
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(if options.limit == 0 {
+            20
+        } else {
+            options.limit
+        });
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use vc_store::VcStore;
+
+    /// Bag-of-words embedder over a tiny fixed vocabulary, where "ssh"
+    /// and "remote shell" land on the same axis
+    struct FakeEmbedder {
+        down: Arc<AtomicBool>,
+    }
+
+    impl Embedder for FakeEmbedder {
+        fn model(&self) -> &str {
+            "fake"
+        }
+
+        fn embed(&self, text: &str) -> Result<Vec<f32>, KnowledgeError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(KnowledgeError::EmbeddingError("backend down".to_string()));
+            }
+            let text = text.to_lowercase();
+            let axis = |words: &[&str]| {
+                if words.iter().any(|w| text.contains(w)) {
+                    1.0
+                } else {
+                    0.0
+                }
+            };
+            Ok(vec![
+                axis(&["ssh", "remote shell"]),
+                axis(&["hang", "times out", "timeout"]),
+                axis(&["disk", "storage"]),
+            ])
+        }
+    }
+
+    /// Seed entries with explicit ids, as inserts rely on the backend
+    /// assigning one
+    fn kb_with_fake(titles: &[&str]) -> (KnowledgeStore, Arc<AtomicBool>) {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        for (id, title) in (1_i64..).zip(titles) {
+            store
+                .execute(
+                    "INSERT INTO knowledge_entries (id, entry_type, title, content, tags, \
+                     created_at, usefulness_score, view_count, applied_count) \
+                     VALUES (?, 'solution', ?, 'body', '[]', '2026-10-16T12:00:00+00:00', 0, 0, 0)",
+                    &[&id.to_string(), title],
+                )
+                .unwrap();
+        }
+        let down = Arc::new(AtomicBool::new(false));
+        let kb = KnowledgeStore::new(store).with_embedder(
+            Box::new(FakeEmbedder {
+                down: Arc::clone(&down),
+            }),
+            0.7,
+        );
+        (kb, down)
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-9);
+        assert!(cosine_similarity(&[1.0], &[1.0, 0.0]).abs() < 1e-9);
+        assert!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_vector_shapes() {
+        let expected = Some(vec![0.5_f32, -1.0]);
+        assert_eq!(parse_vector(&serde_json::json!([0.5, -1.0])), expected);
+        assert_eq!(
+            parse_vector(&serde_json::json!({"embedding": [0.5, -1.0]})),
+            expected
+        );
+        assert_eq!(
+            parse_vector(&serde_json::json!({"data": [{"embedding": [0.5, -1.0]}]})),
+            expected
+        );
+        assert_eq!(parse_vector(&serde_json::json!({"error": "no"})), None);
+        assert_eq!(parse_vector(&serde_json::json!([])), None);
+    }
+
+    #[test]
+    fn test_command_embedder() {
+        let embedder = CommandEmbedder::new(
+            "cat >/dev/null; echo '[1, 2, 3]'",
+            "cmd",
+            Duration::from_secs(5),
+        );
+        assert_eq!(embedder.embed("hello").unwrap(), vec![1.0, 2.0, 3.0]);
+
+        let slow = CommandEmbedder::new("sleep 5", "cmd", Duration::from_millis(100));
+        assert!(matches!(
+            slow.embed("hello"),
+            Err(KnowledgeError::EmbeddingError(_))
+        ));
+    }
+
+    #[test]
+    fn test_semantic_search_finds_paraphrase() {
+        let (kb, _down) = kb_with_fake(&["Remote shell times out", "Disk full on build host"]);
+        kb.reindex_missing(|_, _| {}).unwrap();
+
+        let keyword = kb
+            .search("ssh hangs on connect", &SearchOptions::new())
+            .unwrap();
+        assert!(keyword.is_empty());
+
+        let semantic = kb
+            .search(
+                "ssh hangs on connect",
+                &SearchOptions::new().with_semantic(true),
+            )
+            .unwrap();
+        assert_eq!(semantic.len(), 1);
+        assert_eq!(semantic[0].entry.title, "Remote shell times out");
+    }
+
+    #[test]
+    fn test_semantic_search_degrades_to_keyword_when_backend_down() {
+        let (kb, down) = kb_with_fake(&["Disk full on build host", "Remote shell times out"]);
+        down.store(true, Ordering::SeqCst);
+
+        let results = kb
+            .search("disk", &SearchOptions::new().with_semantic(true))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.title, "Disk full on build host");
+    }
+
+    #[test]
+    fn test_reindex_backfills_missing_embeddings() {
+        let (kb, _down) = kb_with_fake(&["Remote shell times out", "Disk full"]);
+
+        let mut ticks = Vec::new();
+        let report = kb
+            .reindex_missing(|done, total| ticks.push((done, total)))
+            .unwrap();
+        assert_eq!(report.missing, 2);
+        assert_eq!(report.embedded, 2);
+        assert_eq!(ticks, vec![(1, 2), (2, 2)]);
+
+        let again = kb.reindex_missing(|_, _| {}).unwrap();
+        assert_eq!(again.missing, 0);
+    }
+
+    #[test]
+    fn test_reindex_stops_when_backend_down() {
+        let (kb, down) = kb_with_fake(&["a", "b", "c", "d", "e"]);
+        down.store(true, Ordering::SeqCst);
+
+        let report = kb.reindex_missing(|_, _| {}).unwrap();
+        assert_eq!(report.failed, MAX_CONSECUTIVE_FAILURES);
+        assert_eq!(report.embedded, 0);
+        assert!(report.aborted.is_some());
+    }
+}
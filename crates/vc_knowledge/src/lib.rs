@@ -3,11 +3,23 @@
 //! This crate provides:
 //! - Knowledge entry storage (solutions, patterns, prompts, `debug_logs`)
 //! - Feedback tracking for usefulness scoring
-//! - Search capabilities (keyword-based)
+//! - Typed relations between entries and alert-type tags
+//! - Search capabilities (keyword-based, plus embedding similarity with the
+//!   `embeddings` feature)
 //! - Integration with agent sessions
 //! - Solution mining pipeline for extracting knowledge from sessions
+//! - Outcome classification of finished sessions
+//! - Review queue for stale entries, and archiving
 
+#[cfg(feature = "embeddings")]
+pub mod embeddings;
 pub mod mining;
+pub mod outcome;
+pub mod relations;
+pub mod review;
+
+pub use relations::{EntryRelations, KnowledgeRelation, RelationType};
+pub use review::{FeedbackStats, ReviewAction, ReviewItem};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -35,6 +47,9 @@ pub enum KnowledgeError {
 
     #[error("Validation error: {0}")]
     ValidationError(String),
+
+    #[error("Embedding error: {0}")]
+    EmbeddingError(String),
 }
 
 /// Entry type for knowledge items
@@ -133,6 +148,12 @@ pub struct KnowledgeEntry {
     pub usefulness_score: f64,
     pub view_count: i32,
     pub applied_count: i32,
+    #[serde(default)]
+    pub last_viewed_at: Option<DateTime<Utc>>,
+    /// Set when the entry is archived: hidden from default search, still
+    /// retrievable by ID or with `include_archived`
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
 }
 
 impl KnowledgeEntry {
@@ -158,6 +179,8 @@ impl KnowledgeEntry {
             usefulness_score: 0.0,
             view_count: 0,
             applied_count: 0,
+            last_viewed_at: None,
+            archived_at: None,
         }
     }
 
@@ -254,13 +277,20 @@ pub struct SearchResult {
     pub score: f64,
 }
 
-/// Search options
+/// Search options; non-exhaustive, so build them from
+/// [`SearchOptions::new`] and the `with_*` methods
 #[derive(Debug, Clone, Default)]
+#[non_exhaustive]
 pub struct SearchOptions {
     pub entry_type: Option<EntryType>,
     pub tags: Vec<String>,
     pub min_score: Option<f64>,
     pub limit: usize,
+    /// Rank by embedding similarity blended with the keyword score; falls
+    /// back to keyword-only when no embedding backend is available
+    pub semantic: bool,
+    /// Also return archived entries
+    pub include_archived: bool,
 }
 
 impl SearchOptions {
@@ -284,23 +314,51 @@ impl SearchOptions {
         self
     }
 
+    #[must_use]
+    pub fn with_min_score(mut self, min_score: f64) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
     #[must_use]
     pub fn with_limit(mut self, limit: usize) -> Self {
         self.limit = limit;
         self
     }
+
+    #[must_use]
+    pub fn with_semantic(mut self, semantic: bool) -> Self {
+        self.semantic = semantic;
+        self
+    }
+
+    #[must_use]
+    pub fn with_include_archived(mut self, include_archived: bool) -> Self {
+        self.include_archived = include_archived;
+        self
+    }
 }
 
 /// Knowledge store for database operations
 pub struct KnowledgeStore {
     store: Arc<VcStore>,
+    #[cfg(feature = "embeddings")]
+    embedder: Option<Box<dyn embeddings::Embedder>>,
+    #[cfg(feature = "embeddings")]
+    semantic_weight: f64,
 }
 
 impl KnowledgeStore {
     /// Create a new knowledge store
     #[must_use]
     pub fn new(store: Arc<VcStore>) -> Self {
-        Self { store }
+        Self {
+            store,
+            #[cfg(feature = "embeddings")]
+            embedder: None,
+            #[cfg(feature = "embeddings")]
+            semantic_weight: 0.0,
+        }
     }
 
     /// Insert a new knowledge entry
@@ -342,6 +400,15 @@ impl KnowledgeStore {
             ],
             |row: &duckdb::Row<'_>| row.get(0),
         )?;
+        drop(conn_guard);
+
+        // Best effort: entries left without a vector are picked up by reindex
+        #[cfg(feature = "embeddings")]
+        if self.embedder.is_some()
+            && let Err(e) = self.embed_entry(id, entry)
+        {
+            tracing::warn!(entry_id = id, error = %e, "failed to embed knowledge entry");
+        }
 
         Ok(id)
     }
@@ -363,17 +430,36 @@ impl KnowledgeStore {
             .map_err(|_| KnowledgeError::NotFound(format!("entry with id {id}")))
     }
 
-    /// Increment view count
+    /// Increment view count and note when the entry was last viewed
     ///
     /// # Errors
     ///
     /// Returns an error if the update query fails.
     pub fn record_view(&self, id: i64) -> Result<(), KnowledgeError> {
-        let sql = "UPDATE knowledge_entries SET view_count = view_count + 1 WHERE id = ?";
-        self.store.execute(sql, &[&id.to_string()])?;
+        let sql = "UPDATE knowledge_entries SET view_count = view_count + 1, last_viewed_at = ? \
+                   WHERE id = ?";
+        self.store
+            .execute(sql, &[&Utc::now().to_rfc3339(), &id.to_string()])?;
         Ok(())
     }
 
+    /// Archive an entry: default search stops returning it, but it stays
+    /// retrievable by ID and mining will not re-create it. Archiving twice
+    /// keeps the original time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KnowledgeError::NotFound`] when no entry exists for the given
+    /// ID, or an error if the update fails.
+    pub fn archive(&self, id: i64) -> Result<KnowledgeEntry, KnowledgeError> {
+        self.get(id)?;
+        self.store.execute(
+            "UPDATE knowledge_entries SET archived_at = ? WHERE id = ? AND archived_at IS NULL",
+            &[&Utc::now().to_rfc3339(), &id.to_string()],
+        )?;
+        self.get(id)
+    }
+
     /// Increment applied count
     ///
     /// # Errors
@@ -445,7 +531,8 @@ impl KnowledgeStore {
         Ok(())
     }
 
-    /// Search for entries by keyword
+    /// Search for entries by keyword, or by embedding similarity when
+    /// `options.semantic` is set and an embedding backend is available
     ///
     /// # Errors
     ///
@@ -455,25 +542,49 @@ impl KnowledgeStore {
         query: &str,
         options: &SearchOptions,
     ) -> Result<Vec<SearchResult>, KnowledgeError> {
+        if options.semantic && !query.trim().is_empty() {
+            #[cfg(feature = "embeddings")]
+            match self.semantic_search(query, options) {
+                Ok(results) => return Ok(results),
+                Err(e) => tracing::warn!(
+                    error = %e,
+                    "semantic search unavailable, falling back to keyword search"
+                ),
+            }
+            #[cfg(not(feature = "embeddings"))]
+            tracing::warn!(
+                "semantic search needs the embeddings feature, falling back to keyword search"
+            );
+        }
+
         let mut conditions = vec!["1=1".to_string()];
         let mut params: Vec<String> = vec![];
 
+        if !options.include_archived {
+            conditions.push("archived_at IS NULL".to_string());
+        }
+
         // Filter by entry type
         if let Some(entry_type) = &options.entry_type {
-            conditions.push(format!("entry_type = ${}", params.len() + 1));
+            conditions.push("entry_type = ?".to_string());
             params.push(entry_type.as_str().to_string());
         }
 
         // Keyword search in title and content
         if !query.trim().is_empty() {
-            let pattern = format!("%{}%", query.replace('%', "\\%").replace('_', "\\_"));
-            conditions.push(format!(
-                "(title ILIKE ${} OR content ILIKE ${} OR summary ILIKE ${})",
-                params.len() + 1,
-                params.len() + 1,
-                params.len() + 1
-            ));
-            params.push(pattern);
+            let pattern = format!(
+                "%{}%",
+                query
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_")
+            );
+            conditions.push(
+                "(title ILIKE ? ESCAPE '\\' OR content ILIKE ? ESCAPE '\\' \
+                 OR summary ILIKE ? ESCAPE '\\')"
+                    .to_string(),
+            );
+            params.extend([pattern.clone(), pattern.clone(), pattern]);
         }
 
         // Build query
@@ -495,18 +606,20 @@ impl KnowledgeStore {
             limit
         );
 
-        let results = self.store.query_json(&sql)?;
-
-        results
-            .into_iter()
-            .map(|row| {
-                let entry = serde_json::from_value::<KnowledgeEntry>(row.clone()).map_err(|e| {
-                    KnowledgeError::StoreError(vc_store::StoreError::SerializationError(e))
-                })?;
-                let score = row["score"].as_f64().unwrap_or(0.0);
-                Ok(SearchResult { entry, score })
-            })
-            .collect()
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(|e| {
+            KnowledgeError::StoreError(vc_store::StoreError::QueryError(format!("lock error: {e}")))
+        })?;
+        let mut stmt = conn_guard.prepare(&sql)?;
+        let results = stmt
+            .query_map(duckdb::params_from_iter(params.iter()), |row| {
+                Ok(SearchResult {
+                    entry: Self::row_to_entry(row)?,
+                    score: row.get("score")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(results)
     }
 
     /// Get entries by tag
@@ -597,12 +710,24 @@ impl KnowledgeStore {
     ///
     /// # Errors
     ///
-    /// Returns an error if deleting feedback or the entry fails.
+    /// Returns an error if deleting feedback, links, or the entry fails.
     pub fn delete(&self, id: i64) -> Result<(), KnowledgeError> {
         self.store.execute(
             "DELETE FROM knowledge_feedback WHERE entry_id = ?",
             &[&id.to_string()],
         )?;
+        self.store.execute(
+            "DELETE FROM knowledge_relations WHERE source_id = ? OR target_id = ?",
+            &[&id.to_string(), &id.to_string()],
+        )?;
+        self.store.execute(
+            "DELETE FROM knowledge_alert_types WHERE entry_id = ?",
+            &[&id.to_string()],
+        )?;
+        self.store.execute(
+            "DELETE FROM knowledge_embeddings WHERE entry_id = ?",
+            &[&id.to_string()],
+        )?;
         self.store.execute(
             "DELETE FROM knowledge_entries WHERE id = ?",
             &[&id.to_string()],
@@ -626,12 +751,14 @@ impl KnowledgeStore {
         let created_at = DateTime::parse_from_rfc3339(&created_str)
             .map_or_else(|_| Utc::now(), |dt| dt.with_timezone(&Utc));
 
-        let updated_str: Option<String> = row.get("updated_at")?;
-        let updated_at = updated_str.and_then(|s| {
-            DateTime::parse_from_rfc3339(&s)
-                .map(|dt| dt.with_timezone(&Utc))
-                .ok()
-        });
+        let optional_time = |column: &str| -> Result<Option<DateTime<Utc>>, duckdb::Error> {
+            let raw: Option<String> = row.get(column)?;
+            Ok(raw.and_then(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| dt.with_timezone(&Utc))
+                    .ok()
+            }))
+        };
 
         Ok(KnowledgeEntry {
             id: Some(row.get("id")?),
@@ -644,10 +771,12 @@ impl KnowledgeStore {
             source_lines: row.get("source_lines")?,
             tags,
             created_at,
-            updated_at,
+            updated_at: optional_time("updated_at")?,
             usefulness_score: row.get("usefulness_score")?,
             view_count: row.get("view_count")?,
             applied_count: row.get("applied_count")?,
+            last_viewed_at: optional_time("last_viewed_at")?,
+            archived_at: optional_time("archived_at")?,
         })
     }
 }
@@ -904,6 +1033,7 @@ mod tests {
         assert!(opts.entry_type.is_none());
         assert!(opts.tags.is_empty());
         assert_eq!(opts.limit, 0);
+        assert!(!opts.semantic);
     }
 
     #[test]
@@ -911,8 +1041,10 @@ mod tests {
         let opts = SearchOptions::new()
             .with_type(EntryType::Solution)
             .with_tags(vec!["rust".to_string()])
-            .with_limit(50);
+            .with_limit(50)
+            .with_semantic(true);
 
+        assert!(opts.semantic);
         assert_eq!(opts.entry_type, Some(EntryType::Solution));
         assert_eq!(opts.tags, vec!["rust".to_string()]);
         assert_eq!(opts.limit, 50);
@@ -6,7 +6,8 @@
 //! 3. Solution Extraction - Extract reusable patterns
 //! 4. Quality Scoring - Rank by usefulness
 //! 5. Knowledge Storage - Store in knowledge base
-//! 6. Deduplication - Skip entries too similar to existing ones
+//! 6. Deduplication - Skip entries too similar to existing ones, including
+//!    archived ones (counted as suppressed duplicates)
 
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
@@ -47,6 +48,9 @@ pub struct MiningResult {
     pub patterns_extracted: usize,
     pub quality_avg: f64,
     pub entries_created: Vec<i64>,
+    /// Entries not created because they nearly duplicate an archived entry
+    #[serde(default)]
+    pub suppressed_duplicates: usize,
 }
 
 /// Mining statistics summary.
@@ -56,6 +60,8 @@ pub struct MiningStats {
     pub total_solutions: i64,
     pub total_patterns: i64,
     pub avg_quality: f64,
+    #[serde(default)]
+    pub suppressed_duplicates: i64,
 }
 
 /// The solution miner orchestrates the mining pipeline.
@@ -199,6 +205,7 @@ impl SolutionMiner {
                 patterns_extracted: 0,
                 quality_avg: 0.0,
                 entries_created: vec![],
+                suppressed_duplicates: 0,
             });
         }
 
@@ -209,6 +216,7 @@ impl SolutionMiner {
         let mut quality_sum = 0u32;
         let mut solutions = 0_i32;
         let patterns = 0_i32;
+        let mut suppressed = 0_i32;
 
         for pair in &pairs {
             if pair.quality < self.min_quality {
@@ -233,12 +241,24 @@ impl SolutionMiner {
                 continue;
             }
 
+            // Archived entries are hidden from search but were retired on
+            // purpose; mining the same lesson again would bring them back
+            let title = Self::generate_title(pair);
             let content = Self::format_solution(pair);
-            let entry =
-                KnowledgeEntry::new(EntryType::Solution, Self::generate_title(pair), content)
-                    .with_summary(&pair.problem)
-                    .with_session(&candidate.session_id)
-                    .with_tags(pair.tags.clone());
+            if let Some(archived) = self.knowledge.archived_duplicate(&title, &content)? {
+                tracing::debug!(
+                    session_id = %candidate.session_id,
+                    archived_entry = archived,
+                    "skipping near-duplicate of an archived entry"
+                );
+                suppressed += 1;
+                continue;
+            }
+
+            let entry = KnowledgeEntry::new(EntryType::Solution, title, content)
+                .with_summary(&pair.problem)
+                .with_session(&candidate.session_id)
+                .with_tags(pair.tags.clone());
 
             match self.knowledge.insert(&entry) {
                 Ok(id) => {
@@ -264,6 +284,7 @@ impl SolutionMiner {
             &candidate.machine_id,
             solutions,
             patterns,
+            suppressed,
             if solutions > 0 {
                 Some(quality_avg)
             } else {
@@ -280,6 +301,7 @@ impl SolutionMiner {
             patterns_extracted,
             quality_avg,
             entries_created,
+            suppressed_duplicates: usize::try_from(suppressed).unwrap_or_default(),
         })
     }
 
@@ -332,6 +354,10 @@ impl SolutionMiner {
                 .get("avg_quality")
                 .and_then(serde_json::Value::as_f64)
                 .unwrap_or(0.0),
+            suppressed_duplicates: json
+                .get("suppressed_duplicates")
+                .and_then(serde_json::Value::as_i64)
+                .unwrap_or(0),
         })
     }
 
@@ -416,6 +442,7 @@ mod tests {
             patterns_extracted: 1,
             quality_avg: 3.5,
             entries_created: vec![1, 2, 3],
+            suppressed_duplicates: 0,
         };
         let json = serde_json::to_string(&result).unwrap();
         let parsed: MiningResult = serde_json::from_str(&json).unwrap();
@@ -430,6 +457,7 @@ mod tests {
             total_solutions: 0,
             total_patterns: 0,
             avg_quality: 0.0,
+            suppressed_duplicates: 0,
         };
         assert_eq!(stats.total_mined, 0);
     }
@@ -575,4 +603,29 @@ mod tests {
         let stats = miner.stats().unwrap();
         assert_eq!(stats.total_mined, 0);
     }
+
+    #[test]
+    fn test_extract_suppresses_archived_duplicates() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let miner = SolutionMiner::new(store.clone()).with_min_quality(1);
+        let candidate = |session_id: &str| SessionCandidate {
+            session_id: session_id.to_string(),
+            machine_id: "m1".to_string(),
+            program: Some("claude-code".to_string()),
+            model: Some("opus-4.6".to_string()),
+            repo_path: Some("/data/projects/vibe_cockpit".to_string()),
+            started_at: None,
+            ended_at: None,
+            token_count: Some(30_000),
+        };
+
+        let first = miner.extract(&candidate("s1")).unwrap();
+        assert_eq!(first.entries_created.len(), 1);
+        miner.knowledge.archive(first.entries_created[0]).unwrap();
+
+        let second = miner.extract(&candidate("s2")).unwrap();
+        assert!(second.entries_created.is_empty());
+        assert_eq!(second.suppressed_duplicates, 1);
+        assert_eq!(miner.stats().unwrap().suppressed_duplicates, 1);
+    }
 }
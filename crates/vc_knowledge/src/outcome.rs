@@ -0,0 +1,472 @@
+//! Outcome classification for finished agent sessions.
+//!
+//! A session row only records that the agent stopped. Whether it got
+//! anywhere is read from three signals:
+//!
+//! 1. Exit status: a transcript-level `exit_code`/`exit_status`, or the
+//!    closing `result` entry of a stream-json transcript.
+//! 2. Tool-call error density: the share of tool results flagged
+//!    `is_error` or carrying a nonzero `exit_code`.
+//! 3. The ending: success and failure phrases in the last few messages.
+//!
+//! Each session gets one [`SessionOutcome`] and a confidence in 0.0-1.0 that
+//! says how much the signals agreed. Thresholds and phrases come from
+//! [`OutcomeHeuristics`], overridable per agent type.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vc_config::{OutcomeHeuristics, SessionOutcomeConfig};
+use vc_store::VcStore;
+
+use crate::KnowledgeError;
+
+/// Fields holding a transcript-level exit status
+const EXIT_FIELDS: &[&str] = &["exit_code", "exit_status", "exitCode"];
+
+/// Fields holding message text
+const TEXT_FIELDS: &[&str] = &["text", "content", "message", "result"];
+
+/// How a finished agent session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    /// The task got done
+    Success,
+    /// Some progress, but with errors along the way or loose ends
+    Partial,
+    /// The agent failed or gave up
+    Failed,
+    /// The session stopped before any real work happened
+    Abandoned,
+}
+
+impl SessionOutcome {
+    /// Every outcome, in reporting order
+    pub const ALL: [Self; 4] = [Self::Success, Self::Partial, Self::Failed, Self::Abandoned];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Partial => "partial",
+            Self::Failed => "failed",
+            Self::Abandoned => "abandoned",
+        }
+    }
+
+    /// Parse a stored outcome name
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|outcome| outcome.as_str() == value)
+    }
+}
+
+impl std::fmt::Display for SessionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a session's transcript and row say about how it went
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSignals {
+    pub exit_code: Option<i64>,
+    pub turns: Option<i64>,
+    pub tool_calls: usize,
+    pub tool_errors: usize,
+    /// A success phrase appears in the closing messages
+    pub success_phrase: bool,
+    /// A failure phrase appears in the closing messages
+    pub failure_phrase: bool,
+}
+
+impl SessionSignals {
+    /// Share of tool calls that errored, 0.0 without any
+    #[must_use]
+    pub fn error_ratio(&self) -> f64 {
+        if self.tool_calls == 0 {
+            return 0.0;
+        }
+        let calls = f64::from(u32::try_from(self.tool_calls).unwrap_or(u32::MAX));
+        let errors = f64::from(u32::try_from(self.tool_errors).unwrap_or(u32::MAX));
+        errors / calls
+    }
+
+    /// Read the signals from a stored transcript (a JSON document or JSON
+    /// lines) and the session's recorded turn count
+    #[must_use]
+    pub fn from_transcript(
+        raw: Option<&str>,
+        turn_count: Option<i64>,
+        heuristics: &OutcomeHeuristics,
+    ) -> Self {
+        let transcript = raw.map_or(serde_json::Value::Null, parse_transcript);
+        let entries = transcript.as_array().map_or(&[][..], Vec::as_slice);
+
+        let mut signals = Self {
+            exit_code: exit_code(&transcript, entries.last()),
+            turns: turn_count.or_else(|| i64::try_from(entries.len()).ok().filter(|n| *n > 0)),
+            ..Self::default()
+        };
+
+        let mut texts = Vec::new();
+        match &transcript {
+            // A transcript document's own fields describe the session, not a
+            // tool call, so only what it holds is collected.
+            serde_json::Value::Object(fields) => collect_fields(fields, &mut signals, &mut texts),
+            other => collect(other, &mut signals, &mut texts),
+        }
+        let ending = texts
+            .iter()
+            .rev()
+            .take(heuristics.tail_messages)
+            .map(|text| text.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mentions = |phrases: &[String]| {
+            phrases
+                .iter()
+                .any(|phrase| ending.contains(&phrase.to_lowercase()))
+        };
+        signals.success_phrase = mentions(&heuristics.success_phrases);
+        signals.failure_phrase = mentions(&heuristics.failure_phrases);
+        signals
+    }
+}
+
+/// A session's outcome and how sure the classifier is of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeClassification {
+    pub outcome: SessionOutcome,
+    /// 0.0-1.0; higher when more signals agree
+    pub confidence: f64,
+    pub signals: SessionSignals,
+}
+
+/// Classify a session from its signals.
+///
+/// Rules, first match wins: a nonzero exit fails; an error density at
+/// `failed_error_ratio` fails; a short session without any sign of success
+/// was abandoned; a failure phrase fails (partial if success is claimed
+/// too); an error density at `partial_error_ratio` is partial; anything
+/// else succeeded, with confidence depending on how much says so.
+#[must_use]
+pub fn classify(signals: SessionSignals, heuristics: &OutcomeHeuristics) -> OutcomeClassification {
+    let ratio = signals.error_ratio();
+    let clean_exit = signals.exit_code == Some(0);
+    let short = signals
+        .turns
+        .is_some_and(|turns| turns <= i64::from(heuristics.abandoned_max_turns));
+
+    let (outcome, confidence) = if signals.exit_code.is_some_and(|code| code != 0) {
+        let corroborated = signals.failure_phrase || ratio >= heuristics.partial_error_ratio;
+        (
+            SessionOutcome::Failed,
+            if corroborated { 0.95 } else { 0.85 },
+        )
+    } else if signals.tool_calls > 0 && ratio >= heuristics.failed_error_ratio {
+        (
+            SessionOutcome::Failed,
+            if signals.failure_phrase { 0.85 } else { 0.75 },
+        )
+    } else if short && !signals.success_phrase && !clean_exit {
+        (
+            SessionOutcome::Abandoned,
+            if signals.tool_calls == 0 { 0.8 } else { 0.65 },
+        )
+    } else if signals.failure_phrase {
+        if signals.success_phrase {
+            (SessionOutcome::Partial, 0.55)
+        } else {
+            (SessionOutcome::Failed, 0.65)
+        }
+    } else if signals.tool_calls > 0 && ratio >= heuristics.partial_error_ratio {
+        (SessionOutcome::Partial, 0.65)
+    } else if signals.success_phrase {
+        (SessionOutcome::Success, if clean_exit { 0.9 } else { 0.8 })
+    } else if clean_exit {
+        (SessionOutcome::Success, 0.7)
+    } else {
+        (SessionOutcome::Success, 0.5)
+    };
+
+    OutcomeClassification {
+        outcome,
+        confidence,
+        signals,
+    }
+}
+
+/// Sessions classified by one [`classify_sessions`] pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassifyReport {
+    pub classified: usize,
+    pub by_outcome: BTreeMap<String, usize>,
+    /// Sessions whose transcript was missing or empty; classified from the
+    /// row alone
+    pub without_transcript: usize,
+}
+
+/// Classify up to `limit` finished sessions and store their outcomes. Only
+/// unclassified sessions unless `reclassify`, which reruns the classifier
+/// over history (after the heuristics changed, say).
+///
+/// # Errors
+///
+/// Returns an error if reading sessions or writing an outcome fails.
+pub fn classify_sessions(
+    store: &VcStore,
+    config: &SessionOutcomeConfig,
+    reclassify: bool,
+    limit: usize,
+) -> Result<ClassifyReport, KnowledgeError> {
+    let mut report = ClassifyReport::default();
+    for row in store.sessions_to_classify(reclassify, limit)? {
+        let (Some(machine_id), Some(session_id)) =
+            (row["machine_id"].as_str(), row["session_id"].as_str())
+        else {
+            continue;
+        };
+        let heuristics = config.heuristics_for(row["program"].as_str());
+        let raw = row["raw_json"]
+            .as_str()
+            .filter(|raw| !raw.trim().is_empty());
+        if raw.is_none() {
+            report.without_transcript += 1;
+        }
+        let signals = SessionSignals::from_transcript(raw, row["turn_count"].as_i64(), heuristics);
+        let classification = classify(signals, heuristics);
+
+        store.set_session_outcome(
+            machine_id,
+            session_id,
+            classification.outcome.as_str(),
+            classification.confidence,
+        )?;
+        report.classified += 1;
+        *report
+            .by_outcome
+            .entry(classification.outcome.as_str().to_string())
+            .or_default() += 1;
+    }
+    Ok(report)
+}
+
+/// A JSON document, or JSON lines with unparseable lines skipped
+fn parse_transcript(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| {
+        serde_json::Value::Array(
+            raw.lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+        )
+    })
+}
+
+/// Exit status from the transcript root, or from a closing `result` entry
+fn exit_code(transcript: &serde_json::Value, last: Option<&serde_json::Value>) -> Option<i64> {
+    let root = transcript.as_object().and_then(|fields| {
+        EXIT_FIELDS
+            .iter()
+            .find_map(|field| fields.get(*field)?.as_i64())
+    });
+    root.or_else(|| {
+        let last = last?.as_object()?;
+        if last.get("type")?.as_str()? != "result" {
+            return None;
+        }
+        let is_error = last.get("is_error")?.as_bool()?;
+        Some(i64::from(is_error))
+    })
+}
+
+/// Count tool results and gather message text, in order
+fn collect(value: &serde_json::Value, signals: &mut SessionSignals, texts: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            let kind = fields.get("type").and_then(serde_json::Value::as_str);
+            let is_error = fields.get("is_error").and_then(serde_json::Value::as_bool);
+            let exit = fields.get("exit_code").and_then(serde_json::Value::as_i64);
+            let tool_result = kind == Some("tool_result")
+                || (kind != Some("result") && (is_error.is_some() || exit.is_some()));
+            if tool_result {
+                signals.tool_calls += 1;
+                if is_error == Some(true) || exit.is_some_and(|code| code != 0) {
+                    signals.tool_errors += 1;
+                }
+                return;
+            }
+            if kind != Some("tool_use") {
+                collect_fields(fields, signals, texts);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect(item, signals, texts);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// [`collect`] over an object's fields
+fn collect_fields(
+    fields: &serde_json::Map<String, serde_json::Value>,
+    signals: &mut SessionSignals,
+    texts: &mut Vec<String>,
+) {
+    for (key, child) in fields {
+        match child {
+            serde_json::Value::String(text) if TEXT_FIELDS.contains(&key.as_str()) => {
+                if !text.trim().is_empty() {
+                    texts.push(text.clone());
+                }
+            }
+            serde_json::Value::Object(_) | serde_json::Value::Array(_) => {
+                collect(child, signals, texts);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signals(raw: &str, turns: Option<i64>) -> SessionSignals {
+        SessionSignals::from_transcript(Some(raw), turns, &OutcomeHeuristics::default())
+    }
+
+    #[test]
+    fn test_signals_from_stream_transcript() {
+        let raw = r#"{"type":"user","message":"fix the flaky test"}
+{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Bash","input":{"command":"cargo test"}}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","is_error":true,"content":"1 failed"}]}}
+{"type":"user","message":{"content":[{"type":"tool_result","is_error":false,"content":"ok"}]}}
+{"type":"assistant","message":{"content":[{"type":"text","text":"Fixed the race; all tests pass now."}]}}
+{"type":"result","subtype":"success","is_error":false,"result":"Fixed the race; all tests pass now."}"#;
+        let signals = signals(raw, Some(8));
+        assert_eq!(signals.exit_code, Some(0));
+        assert_eq!(signals.tool_calls, 2);
+        assert_eq!(signals.tool_errors, 1);
+        assert!(signals.success_phrase);
+        assert!(!signals.failure_phrase);
+    }
+
+    #[test]
+    fn test_classify_rules() {
+        let heuristics = OutcomeHeuristics::default();
+        let outcome = |signals: SessionSignals| classify(signals, &heuristics).outcome;
+
+        let base = SessionSignals {
+            turns: Some(10),
+            tool_calls: 10,
+            ..SessionSignals::default()
+        };
+        assert_eq!(outcome(base.clone()), SessionOutcome::Success);
+        assert_eq!(
+            outcome(SessionSignals {
+                exit_code: Some(1),
+                success_phrase: true,
+                ..base.clone()
+            }),
+            SessionOutcome::Failed
+        );
+        assert_eq!(
+            outcome(SessionSignals {
+                tool_errors: 6,
+                ..base.clone()
+            }),
+            SessionOutcome::Failed
+        );
+        assert_eq!(
+            outcome(SessionSignals {
+                tool_errors: 3,
+                ..base.clone()
+            }),
+            SessionOutcome::Partial
+        );
+        assert_eq!(
+            outcome(SessionSignals {
+                failure_phrase: true,
+                success_phrase: true,
+                ..base.clone()
+            }),
+            SessionOutcome::Partial
+        );
+        assert_eq!(
+            outcome(SessionSignals {
+                turns: Some(1),
+                tool_calls: 0,
+                ..SessionSignals::default()
+            }),
+            SessionOutcome::Abandoned
+        );
+
+        let sure = classify(
+            SessionSignals {
+                exit_code: Some(0),
+                success_phrase: true,
+                ..base.clone()
+            },
+            &heuristics,
+        );
+        let unsure = classify(base, &heuristics);
+        assert!(sure.confidence > unsure.confidence);
+    }
+
+    #[test]
+    fn test_classify_sessions_uses_agent_overrides() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                r#"INSERT INTO agent_sessions (machine_id, session_id, program, ended_at, turn_count, raw_json)
+                   VALUES ('m1', 's1', 'claude-code', '2026-10-16T10:00:00Z', 6,
+                           '{"exit_code":0,"messages":[{"text":"Task complete."}]}'),
+                          ('m1', 's2', 'codex', '2026-10-16T10:05:00Z', 6,
+                           '{"exit_code":0,"messages":[{"text":"Task complete."}]}'),
+                          ('m1', 's3', 'codex', '2026-10-16T10:10:00Z', 1, NULL);"#,
+            )
+            .unwrap();
+
+        let mut config = SessionOutcomeConfig::default();
+        config.agents.insert(
+            "codex".to_string(),
+            OutcomeHeuristics {
+                success_phrases: vec!["task complete".to_string()],
+                ..OutcomeHeuristics::default()
+            },
+        );
+
+        let report = classify_sessions(&store, &config, false, 10).unwrap();
+        assert_eq!(report.classified, 3);
+        assert_eq!(report.without_transcript, 1);
+        assert_eq!(report.by_outcome["abandoned"], 1);
+
+        let confidence = |session: &str| -> f64 {
+            store
+                .query_scalar(&format!(
+                    "SELECT outcome_confidence FROM agent_sessions WHERE session_id = '{session}'"
+                ))
+                .unwrap()
+        };
+        // Only the codex heuristics know "task complete" as a success phrase.
+        assert!(confidence("s2") > confidence("s1"));
+
+        assert_eq!(
+            classify_sessions(&store, &config, false, 10)
+                .unwrap()
+                .classified,
+            0
+        );
+        assert_eq!(
+            classify_sessions(&store, &config, true, 10)
+                .unwrap()
+                .classified,
+            3
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! Relations between knowledge entries, and alert-type tags
+//!
+//! Entries link to each other with a typed, directed relation:
+//! - `a relates_to b` - loosely connected (a solution and the prompt that applies it)
+//! - `a supersedes b` - `b` is replaced by `a`; chains must stay acyclic
+//! - `a derived_from b` - `a` was distilled from `b` (a solution from a pattern)
+//!
+//! Alert-type tags (`rate-limit`, `disk-full`, ...) let alert handling fetch
+//! the entries for an alert directly instead of searching titles and content.
+
+use crate::{KnowledgeEntry, KnowledgeError, KnowledgeStore};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Kind of link between two entries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationType {
+    RelatesTo,
+    Supersedes,
+    DerivedFrom,
+}
+
+impl RelationType {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RelationType::RelatesTo => "relates_to",
+            RelationType::Supersedes => "supersedes",
+            RelationType::DerivedFrom => "derived_from",
+        }
+    }
+}
+
+impl std::str::FromStr for RelationType {
+    type Err = KnowledgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "relates_to" => Ok(RelationType::RelatesTo),
+            "supersedes" => Ok(RelationType::Supersedes),
+            "derived_from" => Ok(RelationType::DerivedFrom),
+            other => Err(KnowledgeError::ValidationError(format!(
+                "unknown relation type: {other} (expected relates_to, supersedes or derived_from)"
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for RelationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A directed link: `source_id <relation> target_id`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct KnowledgeRelation {
+    pub source_id: i64,
+    pub target_id: i64,
+    pub relation: RelationType,
+    pub created_at: Option<String>,
+}
+
+/// Links touching one entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntryRelations {
+    /// Links from this entry to others
+    pub outgoing: Vec<KnowledgeRelation>,
+    /// Links from other entries to this one
+    pub incoming: Vec<KnowledgeRelation>,
+}
+
+impl EntryRelations {
+    /// Entries that supersede this one
+    #[must_use]
+    pub fn superseded_by(&self) -> Vec<i64> {
+        self.incoming
+            .iter()
+            .filter(|r| r.relation == RelationType::Supersedes)
+            .map(|r| r.source_id)
+            .collect()
+    }
+}
+
+/// Whether adding `source supersedes target` would close a cycle, i.e.
+/// `target` already supersedes `source` directly or through a chain
+fn creates_supersedes_cycle(edges: &[(i64, i64)], source: i64, target: i64) -> bool {
+    let mut next: HashMap<i64, Vec<i64>> = HashMap::new();
+    for &(from, to) in edges {
+        next.entry(from).or_default().push(to);
+    }
+
+    let mut seen = HashSet::new();
+    let mut stack = vec![target];
+    while let Some(id) = stack.pop() {
+        if id == source {
+            return true;
+        }
+        if seen.insert(id) {
+            stack.extend(next.get(&id).into_iter().flatten().copied());
+        }
+    }
+    false
+}
+
+fn lock_error(e: impl std::fmt::Display) -> KnowledgeError {
+    KnowledgeError::StoreError(vc_store::StoreError::QueryError(format!("lock error: {e}")))
+}
+
+impl KnowledgeStore {
+    /// Link `source_id` to `target_id`; linking twice is a no-op
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KnowledgeError::NotFound`] if either entry is missing, or
+    /// [`KnowledgeError::ValidationError`] for a self-link or a `supersedes`
+    /// link that would create a cycle.
+    pub fn link(
+        &self,
+        source_id: i64,
+        target_id: i64,
+        relation: RelationType,
+    ) -> Result<(), KnowledgeError> {
+        if source_id == target_id {
+            return Err(KnowledgeError::ValidationError(
+                "an entry cannot be linked to itself".to_string(),
+            ));
+        }
+        self.get(source_id)?;
+        self.get(target_id)?;
+
+        if relation == RelationType::Supersedes {
+            let edges = self.supersedes_edges()?;
+            if creates_supersedes_cycle(&edges, source_id, target_id) {
+                return Err(KnowledgeError::ValidationError(format!(
+                    "entry {target_id} already supersedes entry {source_id}; \
+                     the supersedes chain would become a cycle"
+                )));
+            }
+        }
+
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        conn_guard.execute(
+            "INSERT INTO knowledge_relations (source_id, target_id, relation, created_at) \
+             VALUES (?, ?, ?, ?) ON CONFLICT DO NOTHING",
+            duckdb::params![
+                source_id,
+                target_id,
+                relation.as_str(),
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Remove links from `source_id` to `target_id`, of one relation type or
+    /// all of them; returns how many were removed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub fn unlink(
+        &self,
+        source_id: i64,
+        target_id: i64,
+        relation: Option<RelationType>,
+    ) -> Result<usize, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let removed = match relation {
+            Some(relation) => conn_guard.execute(
+                "DELETE FROM knowledge_relations \
+                 WHERE source_id = ? AND target_id = ? AND relation = ?",
+                duckdb::params![source_id, target_id, relation.as_str()],
+            )?,
+            None => conn_guard.execute(
+                "DELETE FROM knowledge_relations WHERE source_id = ? AND target_id = ?",
+                duckdb::params![source_id, target_id],
+            )?,
+        };
+        Ok(removed)
+    }
+
+    /// Links to and from an entry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn relations(&self, id: i64) -> Result<EntryRelations, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(
+            "SELECT source_id, target_id, relation, created_at FROM knowledge_relations \
+             WHERE source_id = ? OR target_id = ? \
+             ORDER BY relation, source_id, target_id",
+        )?;
+        let rows = stmt.query_map(duckdb::params![id, id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+
+        let mut relations = EntryRelations::default();
+        for row in rows {
+            let (source_id, target_id, relation, created_at) = row?;
+            let relation = KnowledgeRelation {
+                source_id,
+                target_id,
+                relation: relation.parse()?,
+                created_at,
+            };
+            if source_id == id {
+                relations.outgoing.push(relation);
+            } else {
+                relations.incoming.push(relation);
+            }
+        }
+        Ok(relations)
+    }
+
+    fn supersedes_edges(&self) -> Result<Vec<(i64, i64)>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(
+            "SELECT source_id, target_id FROM knowledge_relations WHERE relation = 'supersedes'",
+        )?;
+        let edges = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(edges)
+    }
+
+    /// Tag an entry with alert types (e.g. `rate-limit`); existing tags are kept
+    ///
+    /// # Errors
+    ///
+    /// Returns [`KnowledgeError::ValidationError`] for a blank alert type, or
+    /// an error if the insert fails.
+    pub fn add_alert_types(&self, id: i64, alert_types: &[String]) -> Result<(), KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        for alert_type in alert_types {
+            let alert_type = alert_type.trim();
+            if alert_type.is_empty() {
+                return Err(KnowledgeError::ValidationError(
+                    "alert type cannot be empty".to_string(),
+                ));
+            }
+            conn_guard.execute(
+                "INSERT INTO knowledge_alert_types (entry_id, alert_type) VALUES (?, ?) \
+                 ON CONFLICT DO NOTHING",
+                duckdb::params![id, alert_type],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Alert types an entry is tagged with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn alert_types(&self, id: i64) -> Result<Vec<String>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(
+            "SELECT alert_type FROM knowledge_alert_types WHERE entry_id = ? ORDER BY alert_type",
+        )?;
+        let types = stmt
+            .query_map([id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(types)
+    }
+
+    /// Entries tagged with `alert_type`, most useful first. Entries that have
+    /// been superseded are left out; their replacements are returned instead
+    /// if they carry the tag.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn by_alert_type(
+        &self,
+        alert_type: &str,
+        limit: usize,
+    ) -> Result<Vec<KnowledgeEntry>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(&format!(
+            "SELECT e.* FROM knowledge_entries e \
+             JOIN knowledge_alert_types t ON t.entry_id = e.id \
+             WHERE t.alert_type = ? \
+             AND NOT EXISTS (SELECT 1 FROM knowledge_relations r \
+                             WHERE r.target_id = e.id AND r.relation = 'supersedes') \
+             ORDER BY e.usefulness_score DESC, e.id \
+             LIMIT {limit}"
+        ))?;
+        let entries = stmt
+            .query_map([alert_type.trim()], Self::row_to_entry)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use vc_store::VcStore;
+
+    fn store_with_entries(ids: &[i64]) -> KnowledgeStore {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        for id in ids {
+            store
+                .execute(
+                    "INSERT INTO knowledge_entries (id, entry_type, title, content, tags, \
+                     created_at, usefulness_score, view_count, applied_count) \
+                     VALUES (?, 'solution', ?, 'content', '[]', '2026-10-16T12:00:00+00:00', ?, 0, 0)",
+                    &[&id.to_string(), &format!("entry {id}"), &id.to_string()],
+                )
+                .unwrap();
+        }
+        KnowledgeStore::new(store)
+    }
+
+    #[test]
+    fn test_relation_type_roundtrip() {
+        for relation in [
+            RelationType::RelatesTo,
+            RelationType::Supersedes,
+            RelationType::DerivedFrom,
+        ] {
+            assert_eq!(relation.as_str().parse::<RelationType>().unwrap(), relation);
+        }
+        assert_eq!(
+            "derived-from".parse::<RelationType>().unwrap(),
+            RelationType::DerivedFrom
+        );
+        assert!("blocks".parse::<RelationType>().is_err());
+    }
+
+    #[test]
+    fn test_supersedes_cycle_detection() {
+        // 3 supersedes 2, 2 supersedes 1
+        let edges = [(3, 2), (2, 1)];
+        assert!(creates_supersedes_cycle(&edges, 1, 3));
+        assert!(creates_supersedes_cycle(&edges, 1, 2));
+        assert!(!creates_supersedes_cycle(&edges, 4, 3));
+        assert!(!creates_supersedes_cycle(&edges, 3, 1));
+    }
+
+    #[test]
+    fn test_link_and_unlink() {
+        let kb = store_with_entries(&[1, 2, 3]);
+        kb.link(2, 1, RelationType::Supersedes).unwrap();
+        kb.link(2, 1, RelationType::Supersedes).unwrap();
+        kb.link(3, 1, RelationType::DerivedFrom).unwrap();
+
+        let relations = kb.relations(1).unwrap();
+        assert!(relations.outgoing.is_empty());
+        assert_eq!(relations.incoming.len(), 2);
+        assert_eq!(relations.superseded_by(), vec![2]);
+        assert_eq!(kb.relations(2).unwrap().outgoing.len(), 1);
+
+        assert!(kb.link(1, 2, RelationType::Supersedes).is_err());
+        assert!(kb.link(1, 2, RelationType::RelatesTo).is_ok());
+        assert!(kb.link(1, 1, RelationType::RelatesTo).is_err());
+        assert!(matches!(
+            kb.link(1, 99, RelationType::RelatesTo),
+            Err(KnowledgeError::NotFound(_))
+        ));
+
+        assert_eq!(kb.unlink(2, 1, Some(RelationType::Supersedes)).unwrap(), 1);
+        assert!(kb.relations(1).unwrap().superseded_by().is_empty());
+        assert_eq!(kb.unlink(1, 2, None).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_by_alert_type_skips_superseded() {
+        let kb = store_with_entries(&[1, 2, 3]);
+        kb.add_alert_types(1, &["rate-limit".to_string()]).unwrap();
+        kb.add_alert_types(2, &["rate-limit".to_string(), "quota".to_string()])
+            .unwrap();
+        kb.add_alert_types(3, &["disk-full".to_string()]).unwrap();
+        assert_eq!(kb.alert_types(2).unwrap(), vec!["quota", "rate-limit"]);
+        assert!(kb.add_alert_types(1, &[" ".to_string()]).is_err());
+
+        let ids = |entries: Vec<KnowledgeEntry>| -> Vec<i64> {
+            entries.into_iter().filter_map(|e| e.id).collect()
+        };
+        assert_eq!(ids(kb.by_alert_type("rate-limit", 10).unwrap()), vec![2, 1]);
+
+        kb.link(2, 1, RelationType::Supersedes).unwrap();
+        assert_eq!(ids(kb.by_alert_type("rate-limit", 10).unwrap()), vec![2]);
+        assert!(kb.by_alert_type("unknown", 10).unwrap().is_empty());
+    }
+}
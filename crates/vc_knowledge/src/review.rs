@@ -0,0 +1,455 @@
+//! Review queue for knowledge entries that have gone stale
+//!
+//! Entries rot quietly: nobody reads them, feedback turns negative, or the
+//! tools they describe get upgraded underneath them. The review queue ranks
+//! active (non-archived) entries by a staleness score built from those three
+//! signals and suggests what to do with each one:
+//! - `archive` - mostly negative feedback, or nobody has looked at it in months
+//! - `update` - flagged outdated, or a tool it is tagged with changed version
+//!   after it was written (per `tool_version_history`)
+//! - `merge` - near-duplicate of another active entry that is more useful
+//!
+//! Near-duplicates are detected with word-set similarity over title and
+//! content; identical content scores 1.0, so exact copies are always caught.
+
+use crate::{EntryType, KnowledgeEntry, KnowledgeError, KnowledgeStore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Days without a view after which the view-age signal is maxed out.
+const VIEW_AGE_FULL_DAYS: f64 = 90.0;
+/// Days without a view after which an entry nobody found helpful is
+/// suggested for archiving.
+const ARCHIVE_UNVIEWED_DAYS: i64 = 180;
+/// Share of negative feedback at which archiving is suggested.
+const ARCHIVE_NEGATIVE_RATIO: f64 = 0.5;
+/// Minimum feedback count before the negative ratio suggests archiving.
+const ARCHIVE_MIN_FEEDBACK: i64 = 2;
+/// Similarity at which two entries count as near-duplicates.
+pub const DUPLICATE_SIMILARITY: f64 = 0.8;
+
+/// Weights of the staleness signals; they sum to 1.0.
+const VIEW_AGE_WEIGHT: f64 = 0.4;
+const FEEDBACK_WEIGHT: f64 = 0.4;
+const TOOL_CHANGE_WEIGHT: f64 = 0.2;
+
+/// Feedback counts for one entry
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedbackStats {
+    pub entry_id: i64,
+    pub helpful: i64,
+    pub not_helpful: i64,
+    pub outdated: i64,
+}
+
+impl FeedbackStats {
+    #[must_use]
+    pub fn total(&self) -> i64 {
+        self.helpful + self.not_helpful + self.outdated
+    }
+
+    /// Share of feedback that is `not_helpful` or `outdated`; 0.0 without
+    /// feedback
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // feedback counts are far below 2^52
+    pub fn negative_ratio(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            0.0
+        } else {
+            (self.not_helpful + self.outdated) as f64 / total as f64
+        }
+    }
+}
+
+/// Suggested follow-up for a stale entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReviewAction {
+    Archive,
+    Update,
+    /// Fold this entry into `with`, the more useful of the two
+    Merge {
+        with: i64,
+    },
+}
+
+/// One entry in the review queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub entry_id: i64,
+    pub entry_type: EntryType,
+    pub title: String,
+    /// 0.0 (fresh) to 1.0 (almost certainly rotten)
+    pub staleness: f64,
+    /// Days since the entry was last viewed, or created if never viewed
+    pub days_since_view: i64,
+    pub feedback: FeedbackStats,
+    /// Tags naming tools whose version changed after the entry was written
+    pub changed_tools: Vec<String>,
+    pub reasons: Vec<String>,
+    pub actions: Vec<ReviewAction>,
+}
+
+fn lock_error(e: impl std::fmt::Display) -> KnowledgeError {
+    KnowledgeError::StoreError(vc_store::StoreError::QueryError(format!("lock error: {e}")))
+}
+
+fn words(entry_title: &str, content: &str) -> HashSet<String> {
+    entry_title
+        .split(|c: char| !c.is_alphanumeric())
+        .chain(content.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| word.len() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard similarity of the word sets of two title + content pairs
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // word counts are far below 2^52
+pub fn similarity(a: (&str, &str), b: (&str, &str)) -> f64 {
+    let a = words(a.0, a.1);
+    let b = words(b.0, b.1);
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+impl KnowledgeStore {
+    /// Feedback counts per entry, for every entry that has feedback
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn feedback_stats(&self) -> Result<Vec<FeedbackStats>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(
+            "SELECT entry_id, \
+                    SUM(CASE WHEN feedback_type = 'helpful' THEN 1 ELSE 0 END), \
+                    SUM(CASE WHEN feedback_type = 'not_helpful' THEN 1 ELSE 0 END), \
+                    SUM(CASE WHEN feedback_type = 'outdated' THEN 1 ELSE 0 END) \
+             FROM knowledge_feedback \
+             WHERE entry_id IS NOT NULL \
+             GROUP BY entry_id \
+             ORDER BY entry_id",
+        )?;
+        let stats = stmt
+            .query_map([], |row| {
+                Ok(FeedbackStats {
+                    entry_id: row.get(0)?,
+                    helpful: row.get(1)?,
+                    not_helpful: row.get(2)?,
+                    outdated: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stats)
+    }
+
+    /// Active entries ranked by staleness, most stale first, each with the
+    /// reasons and suggested actions
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading entries, feedback or tool history fails.
+    pub fn review_queue(
+        &self,
+        limit: usize,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ReviewItem>, KnowledgeError> {
+        let entries = self.entries_where("archived_at IS NULL")?;
+        let feedback: HashMap<i64, FeedbackStats> = self
+            .feedback_stats()?
+            .into_iter()
+            .map(|stats| (stats.entry_id, stats))
+            .collect();
+        let tool_changes = self.latest_tool_changes()?;
+        let merge_into = merge_targets(&entries);
+
+        let mut queue: Vec<ReviewItem> = entries
+            .iter()
+            .filter_map(|entry| {
+                let id = entry.id?;
+                let feedback = feedback.get(&id).cloned().unwrap_or(FeedbackStats {
+                    entry_id: id,
+                    ..FeedbackStats::default()
+                });
+                let changed_tools: Vec<String> = entry
+                    .tags
+                    .iter()
+                    .filter(|tag| {
+                        tool_changes
+                            .get(&tag.to_lowercase())
+                            .is_some_and(|changed_at| *changed_at > entry.created_at)
+                    })
+                    .cloned()
+                    .collect();
+                Some(review_item(
+                    entry,
+                    id,
+                    feedback,
+                    changed_tools,
+                    merge_into.get(&id).copied(),
+                    now,
+                ))
+            })
+            .collect();
+
+        queue.sort_by(|a, b| {
+            b.staleness
+                .total_cmp(&a.staleness)
+                .then(a.entry_id.cmp(&b.entry_id))
+        });
+        queue.truncate(limit);
+        Ok(queue)
+    }
+
+    /// ID of an archived entry that `title` and `content` nearly duplicate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if loading archived entries fails.
+    pub fn archived_duplicate(
+        &self,
+        title: &str,
+        content: &str,
+    ) -> Result<Option<i64>, KnowledgeError> {
+        let archived = self.entries_where("archived_at IS NOT NULL")?;
+        Ok(archived
+            .iter()
+            .find(|entry| {
+                similarity((title, content), (&entry.title, &entry.content)) >= DUPLICATE_SIMILARITY
+            })
+            .and_then(|entry| entry.id))
+    }
+
+    fn entries_where(&self, condition: &str) -> Result<Vec<KnowledgeEntry>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(&format!(
+            "SELECT * FROM knowledge_entries WHERE {condition} ORDER BY id"
+        ))?;
+        let entries = stmt
+            .query_map([], Self::row_to_entry)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Most recent version change per tool name (lowercased), on any machine
+    fn latest_tool_changes(&self) -> Result<HashMap<String, DateTime<Utc>>, KnowledgeError> {
+        let conn = self.store.connection();
+        let conn_guard = conn.lock().map_err(lock_error)?;
+        let mut stmt = conn_guard.prepare(
+            "SELECT tool_name, MAX(first_seen_at) FROM tool_version_history \
+             WHERE previous_version IS NOT NULL AND previous_version <> tool_version \
+             GROUP BY tool_name",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(tool, changed_at)| {
+                let changed_at = DateTime::parse_from_rfc3339(&changed_at).ok()?;
+                Some((tool.to_lowercase(), changed_at.with_timezone(&Utc)))
+            })
+            .collect())
+    }
+}
+
+/// For each near-duplicate pair of entries, map the less useful one to the
+/// one it should be merged into (higher usefulness, then older)
+fn merge_targets(entries: &[KnowledgeEntry]) -> HashMap<i64, i64> {
+    let mut targets = HashMap::new();
+    for (i, a) in entries.iter().enumerate() {
+        for b in &entries[i + 1..] {
+            let (Some(a_id), Some(b_id)) = (a.id, b.id) else {
+                continue;
+            };
+            if similarity((&a.title, &a.content), (&b.title, &b.content)) < DUPLICATE_SIMILARITY {
+                continue;
+            }
+            // Entries are ordered by id, so on a tie `a` is the older one
+            let (weaker, keeper) = if b.usefulness_score > a.usefulness_score {
+                (a_id, b_id)
+            } else {
+                (b_id, a_id)
+            };
+            targets.entry(weaker).or_insert(keeper);
+        }
+    }
+    targets
+}
+
+#[allow(clippy::cast_precision_loss)] // day counts are far below 2^52
+fn review_item(
+    entry: &KnowledgeEntry,
+    id: i64,
+    feedback: FeedbackStats,
+    changed_tools: Vec<String>,
+    merge_with: Option<i64>,
+    now: DateTime<Utc>,
+) -> ReviewItem {
+    let last_seen = entry.last_viewed_at.unwrap_or(entry.created_at);
+    let days_since_view = (now - last_seen).num_days().max(0);
+    let negative_ratio = feedback.negative_ratio();
+
+    let staleness = VIEW_AGE_WEIGHT * (days_since_view as f64 / VIEW_AGE_FULL_DAYS).min(1.0)
+        + FEEDBACK_WEIGHT * negative_ratio
+        + if changed_tools.is_empty() {
+            0.0
+        } else {
+            TOOL_CHANGE_WEIGHT
+        };
+
+    let mut reasons = Vec::new();
+    let mut actions = Vec::new();
+    if feedback.total() >= ARCHIVE_MIN_FEEDBACK && negative_ratio >= ARCHIVE_NEGATIVE_RATIO {
+        reasons.push(format!(
+            "{}/{} feedback negative",
+            feedback.not_helpful + feedback.outdated,
+            feedback.total()
+        ));
+        actions.push(ReviewAction::Archive);
+    } else if days_since_view >= ARCHIVE_UNVIEWED_DAYS && feedback.helpful == 0 {
+        reasons.push(format!("not viewed in {days_since_view} days"));
+        actions.push(ReviewAction::Archive);
+    }
+    if feedback.outdated > 0 || !changed_tools.is_empty() {
+        if feedback.outdated > 0 {
+            reasons.push(format!("flagged outdated {} time(s)", feedback.outdated));
+        }
+        if !changed_tools.is_empty() {
+            reasons.push(format!(
+                "tool version changed since written: {}",
+                changed_tools.join(", ")
+            ));
+        }
+        actions.push(ReviewAction::Update);
+    }
+    if let Some(with) = merge_with {
+        reasons.push(format!("near-duplicate of entry {with}"));
+        actions.push(ReviewAction::Merge { with });
+    }
+
+    ReviewItem {
+        entry_id: id,
+        entry_type: entry.entry_type,
+        title: entry.title.clone(),
+        staleness,
+        days_since_view,
+        feedback,
+        changed_tools,
+        reasons,
+        actions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FeedbackType, KnowledgeFeedback};
+    use chrono::Duration;
+    use std::sync::Arc;
+    use vc_store::VcStore;
+
+    fn insert(
+        kb: &KnowledgeStore,
+        title: &str,
+        content: &str,
+        tags: &[&str],
+        age_days: i64,
+    ) -> i64 {
+        let mut entry = KnowledgeEntry::new(EntryType::Solution, title, content)
+            .with_tags(tags.iter().map(ToString::to_string).collect());
+        entry.created_at = Utc::now() - Duration::days(age_days);
+        kb.insert(&entry).unwrap()
+    }
+
+    #[test]
+    fn test_similarity() {
+        let a = ("Fix cargo lock", "run cargo update then rebuild");
+        assert!((similarity(a, a) - 1.0).abs() < f64::EPSILON);
+        assert!(similarity(a, ("Disk full", "clear the docker cache")) < 0.1);
+        assert!(similarity(("", ""), ("", "")).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_review_queue_ranks_and_suggests() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        let kb = KnowledgeStore::new(store.clone());
+
+        let fresh = insert(&kb, "Fresh tip", "use ripgrep for search", &[], 0);
+        let disliked = insert(&kb, "Bad advice", "delete the lock file", &[], 10);
+        for feedback_type in [FeedbackType::NotHelpful, FeedbackType::Outdated] {
+            kb.add_feedback(&KnowledgeFeedback::new(disliked, feedback_type))
+                .unwrap();
+        }
+        let tooled = insert(
+            &kb,
+            "Pin node",
+            "pin node to 18 for the build",
+            &["node"],
+            30,
+        );
+        store
+            .execute(
+                "INSERT INTO tool_version_history (machine_id, tool_name, tool_version, \
+                 previous_version, source, first_seen_at, last_seen_at) \
+                 VALUES ('m1', 'node', '20.1.0', '18.2.0', 'probe', ?, ?)",
+                &[
+                    &(Utc::now() - Duration::days(5)).to_rfc3339(),
+                    &Utc::now().to_rfc3339(),
+                ],
+            )
+            .unwrap();
+        let original = insert(
+            &kb,
+            "Restart agent",
+            "kill the pane and restart it",
+            &[],
+            20,
+        );
+        let copy = insert(&kb, "Restart agent", "kill the pane and restart it", &[], 2);
+        kb.add_feedback(&KnowledgeFeedback::new(original, FeedbackType::Helpful))
+            .unwrap();
+
+        let queue = kb.review_queue(10, Utc::now()).unwrap();
+        let item = |id: i64| queue.iter().find(|item| item.entry_id == id).unwrap();
+
+        assert_eq!(queue[0].entry_id, disliked);
+        assert_eq!(
+            item(disliked).actions,
+            vec![ReviewAction::Archive, ReviewAction::Update]
+        );
+        assert_eq!(item(tooled).changed_tools, vec!["node".to_string()]);
+        assert_eq!(item(tooled).actions, vec![ReviewAction::Update]);
+        assert_eq!(
+            item(copy).actions,
+            vec![ReviewAction::Merge { with: original }]
+        );
+        assert!(item(original).actions.is_empty());
+        assert!(item(fresh).staleness < item(tooled).staleness);
+
+        // Archived entries leave the queue and default search
+        kb.archive(disliked).unwrap();
+        let queue = kb.review_queue(10, Utc::now()).unwrap();
+        assert!(queue.iter().all(|item| item.entry_id != disliked));
+        let options = crate::SearchOptions::new();
+        assert!(kb.search("lock file", &options).unwrap().is_empty());
+        let options = options.with_include_archived(true);
+        assert_eq!(kb.search("lock file", &options).unwrap().len(), 1);
+        assert_eq!(
+            kb.archived_duplicate("Bad advice", "delete the lock file")
+                .unwrap(),
+            Some(disliked)
+        );
+        assert_eq!(kb.archived_duplicate("Fresh tip", "ripgrep").unwrap(), None);
+    }
+}
@@ -0,0 +1,277 @@
+//! Output budgeting for tool results
+//!
+//! Every tool result goes through [`render`] on its way to the client, so
+//! no tool, present or future, hands an agent more than `[mcp]
+//! max_response_bytes` of text. Two things keep a result small:
+//!
+//! - Projection: the fields a tool's [`OutputPolicy`] lists as heavy
+//!   (transcripts, raw payload JSON) are left out of every row unless the
+//!   call passes `include_full: true`. The result names what was left out
+//!   in `omitted_fields`.
+//! - Truncation: rows are dropped from the end of the result's row array
+//!   until the text fits. A cut result carries `truncated: true` and
+//!   `total_available`, the number of rows before the cut; `count` is the
+//!   number kept.
+//!
+//! A tool without a policy is cut at its largest top-level array.
+
+use serde_json::Value;
+
+/// How one tool's result is cut down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputPolicy {
+    /// Top-level key of the array holding the rows
+    pub rows: &'static str,
+    /// Row fields left out unless the call passes `include_full: true`
+    pub heavy_fields: &'static [&'static str],
+}
+
+impl OutputPolicy {
+    const fn new(rows: &'static str, heavy_fields: &'static [&'static str]) -> Self {
+        Self { rows, heavy_fields }
+    }
+}
+
+/// Machine rows carry their probe metadata and config snapshot
+const MACHINE_HEAVY_FIELDS: &[&str] = &["metadata_json", "metadata", "config_snapshot"];
+
+/// The output policy of `tool`, `None` for one that has none
+#[must_use]
+pub fn policy(tool: &str) -> Option<OutputPolicy> {
+    Some(match tool {
+        "vc_fleet_status" | "vc_query_machines" => {
+            OutputPolicy::new("machines", MACHINE_HEAVY_FIELDS)
+        }
+        "vc_query_alerts" => OutputPolicy::new("alerts", &["context_json"]),
+        // `raw_json` is the session transcript; `vc_get_session_transcript` pages it
+        "vc_query_sessions" => OutputPolicy::new("sessions", &["raw_json"]),
+        "vc_query_incidents" => OutputPolicy::new("incidents", &[]),
+        "vc_query_nl" => OutputPolicy::new("results", &["raw_json"]),
+        "vc_collector_status" => OutputPolicy::new("collectors", &["cursor_json"]),
+        "vc_playbook_drafts" => OutputPolicy::new("drafts", &[]),
+        "vc_audit_log" | "vc_timeline" => OutputPolicy::new("events", &[]),
+        "vc_search" => OutputPolicy::new("hits", &[]),
+        "vc_check_query" => OutputPolicy::new("result", &[]),
+        _ => return None,
+    })
+}
+
+/// Whether the call asked for the heavy fields too
+#[must_use]
+pub fn include_full(args: &Value) -> bool {
+    args.get("include_full")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// The text sent for `value`
+#[must_use]
+pub fn to_text(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_else(|_| "{}".to_string())
+}
+
+/// The text of `tool`'s result `value`, projected and cut to `max_bytes`
+#[must_use]
+pub fn render(tool: &str, args: &Value, mut value: Value, max_bytes: usize) -> String {
+    let policy = policy(tool);
+    if let Some(policy) = policy
+        && !include_full(args)
+    {
+        project(&mut value, &policy);
+    }
+    let rows = policy
+        .map(|policy| policy.rows.to_string())
+        .or_else(|| largest_array(&value));
+    fit(value, rows.as_deref(), max_bytes)
+}
+
+/// Leave the heavy fields out of every row
+fn project(value: &mut Value, policy: &OutputPolicy) {
+    let Some(rows) = value.get_mut(policy.rows).and_then(Value::as_array_mut) else {
+        return;
+    };
+    let mut omitted: Vec<&str> = Vec::new();
+    for row in rows.iter_mut().filter_map(Value::as_object_mut) {
+        for field in policy.heavy_fields {
+            if row.remove(*field).is_some() && !omitted.contains(field) {
+                omitted.push(*field);
+            }
+        }
+    }
+    if !omitted.is_empty() {
+        value["omitted_fields"] = serde_json::json!(omitted);
+    }
+}
+
+/// Top-level key of the longest array in an object result
+fn largest_array(value: &Value) -> Option<String> {
+    value
+        .as_object()?
+        .iter()
+        .filter_map(|(key, v)| Some((key, v.as_array()?.len())))
+        .max_by_key(|(_, len)| *len)
+        .map(|(key, _)| key.clone())
+}
+
+/// The text of `value`, keeping as many leading rows of `rows` as fit in
+/// `max_bytes`
+fn fit(mut value: Value, rows: Option<&str>, max_bytes: usize) -> String {
+    let text = to_text(&value);
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let all = rows
+        .and_then(|key| value.get_mut(key))
+        .and_then(|v| v.as_array_mut())
+        .map(std::mem::take);
+    let (Some(key), Some(all)) = (rows, all) else {
+        return overflow(text.len(), max_bytes);
+    };
+
+    value["truncated"] = Value::Bool(true);
+    value["total_available"] = serde_json::json!(all.len());
+    let has_count = value.get("count").is_some_and(Value::is_number);
+    let mut render_first = |kept: usize| {
+        value[key] = Value::Array(all[..kept].to_vec());
+        if has_count {
+            value["count"] = serde_json::json!(kept);
+        }
+        to_text(&value)
+    };
+
+    // Longest prefix that fits; the text only grows with the rows kept
+    let (mut lo, mut hi) = (0, all.len());
+    let mut best = render_first(0);
+    if best.len() > max_bytes {
+        return overflow(text.len(), max_bytes);
+    }
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        let candidate = render_first(mid);
+        if candidate.len() <= max_bytes {
+            lo = mid;
+            best = candidate;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    best
+}
+
+/// What is sent when not even an empty row list fits
+fn overflow(bytes: usize, max_bytes: usize) -> String {
+    to_text(&serde_json::json!({
+        "truncated": true,
+        "total_bytes": bytes,
+        "message": format!(
+            "result of {bytes} bytes does not fit in max_response_bytes ({max_bytes}); \
+             narrow the call or raise [mcp] max_response_bytes"
+        ),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(n: usize) -> Value {
+        let rows: Vec<Value> = (0..n)
+            .map(|i| serde_json::json!({"id": i, "raw_json": "x".repeat(100)}))
+            .collect();
+        serde_json::json!({"sessions": rows, "count": n})
+    }
+
+    /// Text length of the first `kept` of `n` full rows, cut
+    fn cut_len(n: usize, kept: usize) -> usize {
+        let mut value = rows(n);
+        let all = value["sessions"].as_array().unwrap().clone();
+        value["sessions"] = Value::Array(all[..kept].to_vec());
+        value["count"] = serde_json::json!(kept);
+        value["truncated"] = Value::Bool(true);
+        value["total_available"] = serde_json::json!(n);
+        to_text(&value).len()
+    }
+
+    fn parse(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_heavy_fields_left_out_unless_include_full() {
+        let args = serde_json::json!({});
+        let out = parse(&render("vc_query_sessions", &args, rows(2), usize::MAX));
+        assert!(out["sessions"][0].get("raw_json").is_none());
+        assert_eq!(out["omitted_fields"], serde_json::json!(["raw_json"]));
+        assert!(out.get("truncated").is_none());
+
+        let args = serde_json::json!({"include_full": true});
+        let out = parse(&render("vc_query_sessions", &args, rows(2), usize::MAX));
+        assert_eq!(out["sessions"][1]["raw_json"], "x".repeat(100));
+        assert!(out.get("omitted_fields").is_none());
+    }
+
+    #[test]
+    fn test_result_exactly_at_the_budget_is_not_cut() {
+        let args = serde_json::json!({"include_full": true});
+        let full = render("vc_query_sessions", &args, rows(5), usize::MAX);
+        let exact = render("vc_query_sessions", &args, rows(5), full.len());
+        assert_eq!(exact, full);
+
+        // One byte less and the last row goes
+        let out = parse(&render("vc_query_sessions", &args, rows(5), full.len() - 1));
+        assert_eq!(out["truncated"], true);
+        assert_eq!(out["total_available"], 5);
+        assert_eq!(out["count"], 4);
+        assert_eq!(out["sessions"].as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_truncation_keeps_the_longest_prefix_that_fits() {
+        let args = serde_json::json!({"include_full": true});
+        for kept in 0..9 {
+            let budget = cut_len(10, kept);
+            let text = render("vc_query_sessions", &args, rows(10), budget);
+            assert_eq!(text.len(), budget);
+            let out = parse(&text);
+            assert_eq!(out["count"], kept, "budget {budget}");
+            assert_eq!(out["total_available"], 10);
+
+            // A byte short of room for the next row still keeps only `kept`
+            let text = render(
+                "vc_query_sessions",
+                &args,
+                rows(10),
+                cut_len(10, kept + 1) - 1,
+            );
+            assert_eq!(parse(&text)["count"], kept);
+        }
+    }
+
+    #[test]
+    fn test_tool_without_policy_is_cut_at_its_largest_array() {
+        let value = serde_json::json!({
+            "tags": ["a"],
+            "items": (0..50).map(|i| format!("item-{i}")).collect::<Vec<_>>(),
+        });
+        let out = parse(&render(
+            "vc_future_tool",
+            &serde_json::json!({}),
+            value,
+            300,
+        ));
+        assert_eq!(out["truncated"], true);
+        assert_eq!(out["total_available"], 50);
+        assert!(out["items"].as_array().unwrap().len() < 50);
+        assert_eq!(out["tags"], serde_json::json!(["a"]));
+    }
+
+    #[test]
+    fn test_result_with_nothing_to_drop_reports_overflow() {
+        let value = serde_json::json!({"text": "y".repeat(4000)});
+        let text = render("vc_future_tool", &serde_json::json!({}), value, 1024);
+        assert!(text.len() <= 1024);
+        let out = parse(&text);
+        assert_eq!(out["truncated"], true);
+        assert!(out["total_bytes"].as_u64().unwrap() > 4000);
+    }
+}
@@ -14,24 +14,49 @@
 //! - `vc_collector_status` - Collector health status
 //! - `vc_playbook_drafts` - List pending playbook drafts
 //! - `vc_audit_log` - Recent audit events
+//! - `vc_timeline` - Alerts, incidents, fleet commands, audit and drift
+//!   events merged in time order
+//! - `vc_search` - Knowledge, incidents, session transcripts and alerts
+//!   searched at once
+//! - `vc_propose_query` - Queue a raw read query for operator approval
+//! - `vc_check_query` - Status of a query this session proposed, with its rows once run
+//! - `vc_get_session_transcript` - One session's transcript, a page at a
+//!   time
 //!
 //! ## Resources
 //! - `vc://fleet/overview` - Fleet status snapshot
 //! - `vc://machines` - Machine list
 //!
+//! ## Identity
+//! Each session acts as one [`ActorContext`]: `vc mcp serve --actor`, else
+//! the `clientInfo.name` the client sends with `initialize`, else the OS
+//! user.
+//!
+//! ## Roles
+//! `vc mcp serve --role <name>` (else `[mcp] default_role`) limits the
+//! session to the tools listed under `[mcp.roles.<name>]`. Hidden tools are
+//! left out of `tools/list`, and calling one fails with
+//! [`McpError::ToolNotAllowed`] rather than [`McpError::ToolNotFound`].
+//!
+//! ## Output budget
+//! No tool result is larger than `[mcp] max_response_bytes`; see
+//! [`budget`] for how one is cut down.
+//!
 //! ## Transport
 //! JSON-RPC 2.0 over stdin/stdout (standard MCP transport)
 
+pub mod budget;
+
 use serde::{Deserialize, Serialize};
 use std::sync::{
-    Arc,
+    Arc, Mutex,
     atomic::{AtomicBool, Ordering},
     mpsc,
 };
 use std::time::Duration;
 use thiserror::Error;
-use tracing::debug;
-use vc_store::{VcStore, escape_sql_literal};
+use tracing::{debug, warn};
+use vc_store::{ActorContext, AuditEventFilter, VcStore, escape_sql_literal};
 
 // ============================================================================
 // Error types
@@ -43,6 +68,9 @@ pub enum McpError {
     #[error("Tool not found: {0}")]
     ToolNotFound(String),
 
+    #[error("Tool not available for this role: {tool} (role: {role})")]
+    ToolNotAllowed { tool: String, role: String },
+
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
 
@@ -152,11 +180,34 @@ pub struct ServerCapabilities {
 // MCP Server
 // ============================================================================
 
+/// Default page size of `vc_get_session_transcript`, in bytes
+const TRANSCRIPT_PAGE_BYTES: usize = 64 * 1024;
+
+/// The largest char boundary in `text` at or before `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
 /// MCP server implementation backed by `VcStore`
 pub struct McpServer {
     store: Arc<VcStore>,
     tools: Vec<McpTool>,
     resources: Vec<McpResource>,
+    /// `--actor`, which outranks the client's own name
+    explicit_actor: Option<String>,
+    actor: Mutex<ActorContext>,
+    /// `[query]` guardrails proposed queries are checked against
+    query_validator: vc_query::QueryValidator,
+    /// How long a proposed query waits for an operator
+    proposal_ttl: Duration,
+    /// Role whose tool set `tools` was cut down to, if any
+    role: Option<String>,
+    /// Largest tool result text, in bytes
+    max_response_bytes: usize,
 }
 
 impl McpServer {
@@ -167,9 +218,65 @@ impl McpServer {
             store,
             tools: Self::define_tools(),
             resources: Self::define_resources(),
+            explicit_actor: None,
+            actor: Mutex::new(ActorContext::mcp(None, None)),
+            query_validator: vc_query::QueryValidator::new(vc_query::GuardrailConfig::default()),
+            proposal_ttl: Duration::from_secs(vc_config::QueryApprovalConfig::default().ttl_secs),
+            role: None,
+            max_response_bytes: vc_config::McpConfig::default().max_response_bytes,
         }
     }
 
+    /// Only list and run `tools` (see [`vc_config::McpConfig::allowed_tools`]);
+    /// `None` keeps every tool. `role` names the restriction in errors.
+    #[must_use]
+    pub fn with_allowed_tools(mut self, role: Option<&str>, tools: Option<&[String]>) -> Self {
+        if let Some(allowed) = tools {
+            self.tools.retain(|tool| allowed.contains(&tool.name));
+            self.role = Some(role.unwrap_or("default").to_string());
+        }
+        self
+    }
+
+    /// Check proposed queries with `validator` (see [`vc_config::QueryConfig`])
+    #[must_use]
+    pub fn with_query_validator(mut self, validator: vc_query::QueryValidator) -> Self {
+        self.query_validator = validator;
+        self
+    }
+
+    /// Let proposed queries wait `ttl` for an operator
+    #[must_use]
+    pub fn with_proposal_ttl(mut self, ttl: Duration) -> Self {
+        self.proposal_ttl = ttl;
+        self
+    }
+
+    /// Cut tool results down to `max_bytes` of text
+    #[must_use]
+    pub fn with_max_response_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    /// Act as `actor` whatever the client calls itself
+    #[must_use]
+    pub fn with_actor(mut self, actor: Option<&str>) -> Self {
+        self.explicit_actor = actor.map(str::to_string);
+        self.actor = Mutex::new(ActorContext::mcp(actor, None));
+        self
+    }
+
+    /// Who this session acts for
+    ///
+    /// # Panics
+    ///
+    /// Panics if the actor mutex is poisoned.
+    #[must_use]
+    pub fn actor(&self) -> ActorContext {
+        self.actor.lock().unwrap().clone()
+    }
+
     /// Define available tools
     #[allow(clippy::too_many_lines)]
     fn define_tools() -> Vec<McpTool> {
@@ -183,6 +290,10 @@ impl McpServer {
                         "machine": {
                             "type": "string",
                             "description": "Optional machine ID to filter by"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     }
                 }),
@@ -200,6 +311,10 @@ impl McpServer {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum results (default 50)"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     }
                 }),
@@ -218,13 +333,17 @@ impl McpServer {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum results (default 50)"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     }
                 }),
             },
             McpTool {
                 name: "vc_query_sessions".to_string(),
-                description: "Search agent session history".to_string(),
+                description: "Search agent session history; transcripts are left out unless include_full is set, vc_get_session_transcript pages through one".to_string(),
                 input_schema: serde_json::json!({
                     "type": "object",
                     "properties": {
@@ -235,6 +354,10 @@ impl McpServer {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum results (default 50)"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     }
                 }),
@@ -265,6 +388,10 @@ impl McpServer {
                         "question": {
                             "type": "string",
                             "description": "Natural language question (e.g. 'how many critical alerts are there?')"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     },
                     "required": ["question"]
@@ -279,6 +406,10 @@ impl McpServer {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum results (default 50)"
+                        },
+                        "include_full": {
+                            "type": "boolean",
+                            "description": "Also return the large fields left out by default"
                         }
                     }
                 }),
@@ -305,10 +436,135 @@ impl McpServer {
                         "limit": {
                             "type": "integer",
                             "description": "Maximum results (default 50)"
+                        },
+                        "actor": {
+                            "type": "string",
+                            "description": "Only events performed by this actor"
+                        }
+                    }
+                }),
+            },
+            McpTool {
+                name: "vc_timeline".to_string(),
+                description: "What happened when: alerts, incidents, fleet commands, audit and drift events merged oldest first, one page at a time".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "since": {
+                            "type": "string",
+                            "description": "Window start: an age like 24h or 7d, or a timestamp (default 24h)"
+                        },
+                        "machine": {
+                            "type": "string",
+                            "description": "Only events on this machine"
+                        },
+                        "kinds": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "enum": ["alerts", "incidents", "fleet", "audit", "drift", "annotations"]
+                            },
+                            "description": "Kinds to include (default all)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Events per page (default 200)"
+                        },
+                        "after": {
+                            "type": "string",
+                            "description": "The previous page's next cursor"
                         }
                     }
                 }),
             },
+            McpTool {
+                name: "vc_search".to_string(),
+                description: "Search knowledge entries, incidents (with their notes), session transcripts and alerts at once; hits are ranked together, each with a snippet and the vc command that opens it".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Words to look for; every one must match"
+                        },
+                        "kinds": {
+                            "type": "array",
+                            "items": {
+                                "type": "string",
+                                "enum": ["knowledge", "incidents", "sessions", "alerts"]
+                            },
+                            "description": "Kinds to search (default all)"
+                        },
+                        "since": {
+                            "type": "string",
+                            "description": "Window start: an age like 24h or 30d, or a timestamp (default 30d)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum results (default 20)"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+            McpTool {
+                name: "vc_propose_query".to_string(),
+                description: "Propose a raw read-only SQL query that no template covers; an operator approves or denies it, then poll vc_check_query for the rows".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "sql": {
+                            "type": "string",
+                            "description": "A single read-only statement (SELECT, WITH, EXPLAIN, DESCRIBE, SHOW)"
+                        },
+                        "justification": {
+                            "type": "string",
+                            "description": "Why the query is needed, shown to the approving operator"
+                        }
+                    },
+                    "required": ["sql", "justification"]
+                }),
+            },
+            McpTool {
+                name: "vc_check_query".to_string(),
+                description: "Status of a query this session proposed: pending, denied, expired, failed, or executed with its rows".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "id": {
+                            "type": "integer",
+                            "description": "The id vc_propose_query returned"
+                        }
+                    },
+                    "required": ["id"]
+                }),
+            },
+            McpTool {
+                name: "vc_get_session_transcript".to_string(),
+                description: "One agent session's full transcript, a page at a time: pass the previous page's next_offset as offset until it is null".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session ID, as vc_query_sessions lists it"
+                        },
+                        "machine": {
+                            "type": "string",
+                            "description": "Machine the session ran on; needed when the ID is not unique"
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Byte offset to start at (default 0)"
+                        },
+                        "length": {
+                            "type": "integer",
+                            "description": "Bytes to return (default 65536, fewer if the page would not fit the response budget)"
+                        }
+                    },
+                    "required": ["session_id"]
+                }),
+            },
         ]
     }
 
@@ -346,9 +602,20 @@ impl McpServer {
     ///
     /// # Errors
     ///
-    /// Returns [`McpError::ToolNotFound`] when `name` is unknown.
+    /// Returns [`McpError::ToolNotFound`] when `name` is unknown, or
+    /// [`McpError::ToolNotAllowed`] when the session's role hides it.
     pub fn call_tool(&self, name: &str, args: &serde_json::Value) -> Result<ToolResult, McpError> {
-        debug!(tool = name, "Executing MCP tool");
+        if let Some(role) = &self.role
+            && vc_config::MCP_TOOLS.contains(&name)
+            && !self.tools.iter().any(|tool| tool.name == name)
+        {
+            warn!(tool = name, role = %role, actor = %self.actor(), "MCP tool hidden by role");
+            return Err(McpError::ToolNotAllowed {
+                tool: name.to_string(),
+                role: role.clone(),
+            });
+        }
+        debug!(tool = name, actor = %self.actor(), "Executing MCP tool");
 
         let result = match name {
             "vc_fleet_status" => self.tool_fleet_status(args),
@@ -360,6 +627,11 @@ impl McpServer {
             "vc_collector_status" => self.tool_collector_status(args),
             "vc_playbook_drafts" => self.tool_playbook_drafts(args),
             "vc_audit_log" => self.tool_audit_log(args),
+            "vc_timeline" => self.tool_timeline(args),
+            "vc_search" => self.tool_search(args),
+            "vc_propose_query" => self.tool_propose_query(args),
+            "vc_check_query" => self.tool_check_query(args),
+            "vc_get_session_transcript" => self.tool_get_session_transcript(args),
             _ => return Err(McpError::ToolNotFound(name.to_string())),
         };
 
@@ -367,7 +639,8 @@ impl McpServer {
             Ok(value) => Ok(ToolResult {
                 content: vec![ToolContent {
                     content_type: "text".to_string(),
-                    text: serde_json::to_string_pretty(&value).unwrap_or_else(|_| "{}".to_string()),
+                    // Every tool's result is cut to the budget here
+                    text: budget::render(name, args, value, self.max_response_bytes),
                 }],
                 is_error: None,
             }),
@@ -400,36 +673,61 @@ impl McpServer {
     // Tool implementations
     // ========================================================================
 
+    /// Add a `missing_capabilities` list to a tool result when any of the
+    /// tables backing it are absent, so an empty result on an older store is
+    /// not mistaken for "nothing happened".
+    fn annotate_missing(&self, tables: &[&str], mut value: serde_json::Value) -> serde_json::Value {
+        let Ok(caps) = self.store.capabilities() else {
+            return value;
+        };
+        if !caps.require(tables) {
+            value["missing_capabilities"] = serde_json::json!(caps.missing());
+        }
+        value
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     fn tool_fleet_status(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
         let machine_filter = args.get("machine").and_then(|v| v.as_str());
 
         let sql = if let Some(machine) = machine_filter {
             format!(
-                "SELECT machine_id, hostname, enabled, last_seen_at, tags \
+                "SELECT machine_id, hostname, enabled, status, last_seen_at, tags \
                  FROM machines WHERE machine_id = '{}' \
                  ORDER BY hostname LIMIT 50",
                 escape_sql_literal(machine)
             )
         } else {
-            "SELECT machine_id, hostname, enabled, last_seen_at, tags \
+            "SELECT machine_id, hostname, enabled, status, last_seen_at, tags \
              FROM machines ORDER BY hostname LIMIT 100"
                 .to_string()
         };
 
         let machines = self.store.query_json(&sql).unwrap_or_default();
         let total = machines.len();
+        // A machine in maintenance was taken out of service on purpose and
+        // counts as neither online nor offline.
+        let in_maintenance =
+            |m: &serde_json::Value| m.get("status").and_then(|v| v.as_str()) == Some("maintenance");
+        let maintenance = machines.iter().filter(|m| in_maintenance(m)).count();
         let online = machines
             .iter()
-            .filter(|m| m.get("enabled").and_then(serde_json::Value::as_bool) == Some(true))
+            .filter(|m| {
+                m.get("enabled").and_then(serde_json::Value::as_bool) == Some(true)
+                    && !in_maintenance(m)
+            })
             .count();
 
-        Ok(serde_json::json!({
-            "total_machines": total,
-            "online": online,
-            "offline": total - online,
-            "machines": machines
-        }))
+        Ok(self.annotate_missing(
+            &["machines"],
+            serde_json::json!({
+                "total_machines": total,
+                "online": online,
+                "offline": total - online - maintenance,
+                "maintenance": maintenance,
+                "machines": machines
+            }),
+        ))
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -455,7 +753,10 @@ impl McpServer {
         };
 
         let machines = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "machines": machines, "count": machines.len() }))
+        Ok(self.annotate_missing(
+            &["machines"],
+            serde_json::json!({ "machines": machines, "count": machines.len() }),
+        ))
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -477,10 +778,12 @@ impl McpServer {
         };
 
         let alerts = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "alerts": alerts, "count": alerts.len() }))
+        Ok(self.annotate_missing(
+            &["alert_history"],
+            serde_json::json!({ "alerts": alerts, "count": alerts.len() }),
+        ))
     }
 
-    #[allow(clippy::unnecessary_wraps)]
     fn tool_query_sessions(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
         let limit = args
             .get("limit")
@@ -490,16 +793,24 @@ impl McpServer {
 
         let sql = if let Some(machine) = machine {
             format!(
-                "SELECT * FROM sessions WHERE machine_id = '{}' \
+                "SELECT * FROM agent_sessions WHERE machine_id = '{}' \
                  ORDER BY started_at DESC LIMIT {limit}",
                 escape_sql_literal(machine)
             )
         } else {
-            format!("SELECT * FROM sessions ORDER BY started_at DESC LIMIT {limit}")
+            format!("SELECT * FROM agent_sessions ORDER BY started_at DESC LIMIT {limit}")
         };
 
-        let sessions = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "sessions": sessions, "count": sessions.len() }))
+        let mut sessions = self.store.query_json(&sql).unwrap_or_default();
+        // Offloaded transcripts come back from the artifact store, but only
+        // when they are not going to be left out anyway
+        if budget::include_full(args) {
+            self.store.fill_offloaded_transcripts(&mut sessions)?;
+        }
+        Ok(self.annotate_missing(
+            &["agent_sessions"],
+            serde_json::json!({ "sessions": sessions, "count": sessions.len() }),
+        ))
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -524,7 +835,10 @@ impl McpServer {
         };
 
         let incidents = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "incidents": incidents, "count": incidents.len() }))
+        Ok(self.annotate_missing(
+            &["incidents"],
+            serde_json::json!({ "incidents": incidents, "count": incidents.len() }),
+        ))
     }
 
     fn tool_query_nl(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
@@ -533,7 +847,8 @@ impl McpServer {
             .and_then(|v| v.as_str())
             .ok_or_else(|| McpError::InvalidRequest("'question' parameter required".to_string()))?;
 
-        let engine = vc_query::NlEngine::new(self.store.clone());
+        let engine =
+            vc_query::NlEngine::new(self.store.clone()).with_origin(vc_query::QueryOrigin::Agent);
         let result = engine.ask(question)?;
 
         Ok(serde_json::json!({
@@ -560,7 +875,10 @@ impl McpServer {
             format!("SELECT * FROM collector_health ORDER BY collected_at DESC LIMIT {limit}");
 
         let collectors = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "collectors": collectors, "count": collectors.len() }))
+        Ok(self.annotate_missing(
+            &["collector_health"],
+            serde_json::json!({ "collectors": collectors, "count": collectors.len() }),
+        ))
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -575,7 +893,10 @@ impl McpServer {
             .list_playbook_drafts(status, 100)
             .unwrap_or_default();
         let count = drafts.len();
-        Ok(serde_json::json!({ "drafts": drafts, "count": count }))
+        Ok(self.annotate_missing(
+            &["playbook_drafts", "playbook_draft_revisions"],
+            serde_json::json!({ "drafts": drafts, "count": count }),
+        ))
     }
 
     #[allow(clippy::unnecessary_wraps)]
@@ -584,11 +905,207 @@ impl McpServer {
             .get("limit")
             .and_then(serde_json::Value::as_u64)
             .unwrap_or(50);
+        let filter = AuditEventFilter {
+            actor: args
+                .get("actor")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            limit: usize::try_from(limit).unwrap_or(usize::MAX),
+            ..AuditEventFilter::default()
+        };
+
+        let events = self.store.list_audit_events(&filter).unwrap_or_default();
+        Ok(self.annotate_missing(
+            &["audit_events"],
+            serde_json::json!({ "events": events, "count": events.len() }),
+        ))
+    }
+
+    fn tool_timeline(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let str_arg = |key: &str| args.get(key).and_then(|v| v.as_str());
+        let since = str_arg("since").unwrap_or("24h");
+        let since = vc_query::timefmt::parse_since(since, chrono::Utc::now())
+            .ok_or_else(|| McpError::InvalidRequest(format!("invalid 'since': {since}")))?;
+        let kinds = args
+            .get("kinds")
+            .and_then(serde_json::Value::as_array)
+            .map(|kinds| {
+                kinds
+                    .iter()
+                    .filter_map(|kind| kind.as_str())
+                    .map(str::parse)
+                    .collect::<Result<Vec<vc_query::TimelineKind>, String>>()
+            })
+            .transpose()
+            .map_err(McpError::InvalidRequest)?
+            .unwrap_or_default();
+        let filter = vc_query::TimelineFilter {
+            since,
+            machine: str_arg("machine").map(str::to_string),
+            kinds,
+            limit: args
+                .get("limit")
+                .and_then(serde_json::Value::as_u64)
+                .and_then(|limit| usize::try_from(limit).ok())
+                .unwrap_or(vc_query::timeline::DEFAULT_TIMELINE_LIMIT),
+            after: str_arg("after").map(str::to_string),
+        };
+        let page = vc_query::QueryBuilder::new(&self.store).timeline(&filter)?;
+        Ok(serde_json::json!({
+            "events": page.events,
+            "count": page.events.len(),
+            "next": page.next,
+        }))
+    }
+
+    fn tool_search(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let query = args
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| McpError::InvalidRequest("'query' parameter required".to_string()))?;
+        let since = args.get("since").and_then(|v| v.as_str()).unwrap_or("30d");
+        let since = vc_query::timefmt::parse_since(since, chrono::Utc::now())
+            .ok_or_else(|| McpError::InvalidRequest(format!("invalid 'since': {since}")))?;
+        let kinds = args
+            .get("kinds")
+            .and_then(serde_json::Value::as_array)
+            .map(|kinds| {
+                kinds
+                    .iter()
+                    .filter_map(|kind| kind.as_str())
+                    .map(str::parse)
+                    .collect::<Result<Vec<vc_query::SearchKind>, String>>()
+            })
+            .transpose()
+            .map_err(McpError::InvalidRequest)?
+            .unwrap_or_default();
+        let filter = vc_query::SearchFilter {
+            query: query.to_string(),
+            kinds,
+            since,
+            limit: args
+                .get("limit")
+                .and_then(serde_json::Value::as_u64)
+                .and_then(|limit| usize::try_from(limit).ok())
+                .unwrap_or(vc_query::search::DEFAULT_SEARCH_LIMIT),
+        };
+        let results = vc_query::QueryBuilder::new(&self.store).search(&filter)?;
+        Ok(serde_json::json!({
+            "hits": results.hits,
+            "count": results.hits.len(),
+            "warnings": results.warnings,
+        }))
+    }
 
-        let sql = format!("SELECT * FROM audit_events ORDER BY timestamp DESC LIMIT {limit}");
+    fn tool_propose_query(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let str_arg = |key: &str| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| McpError::InvalidRequest(format!("'{key}' parameter required")))
+        };
+        let proposal = self.query_validator.propose(
+            &self.store,
+            str_arg("sql")?,
+            str_arg("justification")?,
+            &self.actor(),
+            self.proposal_ttl,
+        )?;
+        Ok(serde_json::json!({
+            "id": proposal.id,
+            "status": proposal.status,
+            "expires_at": proposal.expires_at,
+        }))
+    }
 
-        let events = self.store.query_json(&sql).unwrap_or_default();
-        Ok(serde_json::json!({ "events": events, "count": events.len() }))
+    fn tool_check_query(&self, args: &serde_json::Value) -> Result<serde_json::Value, McpError> {
+        let id = args
+            .get("id")
+            .and_then(serde_json::Value::as_i64)
+            .ok_or_else(|| McpError::InvalidRequest("'id' parameter required".to_string()))?;
+        // Another session's proposal answers as missing, so ids cannot be
+        // walked to read what other agents asked and got back
+        let proposal = self
+            .store
+            .query_proposal(id)?
+            .filter(|proposal| proposal.proposed_by == self.actor().name)
+            .ok_or_else(|| McpError::InvalidRequest(format!("no query proposal {id}")))?;
+        Ok(serde_json::to_value(proposal)?)
+    }
+
+    fn tool_get_session_transcript(
+        &self,
+        args: &serde_json::Value,
+    ) -> Result<serde_json::Value, McpError> {
+        let session_id = args
+            .get("session_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                McpError::InvalidRequest("'session_id' parameter required".to_string())
+            })?;
+        let machine = args.get("machine").and_then(|v| v.as_str());
+        let usize_arg = |key: &str, default: usize| {
+            args.get(key)
+                .and_then(serde_json::Value::as_u64)
+                .map_or(default, |n| usize::try_from(n).unwrap_or(usize::MAX))
+        };
+
+        let mut sql = format!(
+            "SELECT machine_id, session_id, raw_json FROM agent_sessions WHERE session_id = '{}'",
+            escape_sql_literal(session_id)
+        );
+        if let Some(machine) = machine {
+            sql.push_str(&format!(
+                " AND machine_id = '{}'",
+                escape_sql_literal(machine)
+            ));
+        }
+        sql.push_str(" LIMIT 2");
+        let rows = self.store.query_json(&sql)?;
+        let row = match rows.as_slice() {
+            [row] => row,
+            [] => {
+                return Err(McpError::InvalidRequest(format!("no session {session_id}")));
+            }
+            _ => {
+                return Err(McpError::InvalidRequest(format!(
+                    "session {session_id} is on several machines; pass 'machine'"
+                )));
+            }
+        };
+        let machine_id = row["machine_id"].as_str().unwrap_or_default();
+        let transcript = match row["raw_json"].as_str() {
+            Some(text) => text.to_string(),
+            None => self
+                .store
+                .offloaded_transcript(machine_id, session_id)?
+                .unwrap_or_default(),
+        };
+
+        let total = transcript.len();
+        let start = floor_char_boundary(&transcript, usize_arg("offset", 0).min(total));
+        let mut length = usize_arg("length", TRANSCRIPT_PAGE_BYTES);
+        // Shrink the page until it fits the budget with room to spare, so
+        // `budget::render` never has to cut it
+        loop {
+            let mut end = floor_char_boundary(&transcript, start.saturating_add(length).min(total));
+            if end == start && start < total {
+                // Always move on by at least one character
+                end = start + transcript[start..].chars().next().map_or(0, char::len_utf8);
+            }
+            let page = serde_json::json!({
+                "machine_id": machine_id,
+                "session_id": session_id,
+                "offset": start,
+                "length": end - start,
+                "total_length": total,
+                "next_offset": (end < total).then_some(end),
+                "text": &transcript[start..end],
+            });
+            if length == 0 || budget::to_text(&page).len() <= self.max_response_bytes {
+                return Ok(page);
+            }
+            length = (end - start) * 3 / 4;
+        }
     }
 
     // ========================================================================
@@ -596,10 +1113,21 @@ impl McpServer {
     // ========================================================================
 
     /// Handle a JSON-RPC request and return a response
+    ///
+    /// # Panics
+    ///
+    /// Panics if the actor mutex is poisoned.
     #[must_use]
     pub fn handle_request(&self, request: &JsonRpcRequest) -> JsonRpcResponse {
         let result = match request.method.as_str() {
-            "initialize" => Ok(serde_json::json!({
+            "initialize" => {
+                let client_name = request
+                    .params
+                    .pointer("/clientInfo/name")
+                    .and_then(|v| v.as_str());
+                *self.actor.lock().unwrap() =
+                    ActorContext::mcp(self.explicit_actor.as_deref(), client_name);
+                Ok(serde_json::json!({
                 "protocolVersion": "2024-11-05",
                 "capabilities": {
                     "tools": {},
@@ -609,7 +1137,8 @@ impl McpServer {
                     "name": "vibe-cockpit",
                     "version": env!("CARGO_PKG_VERSION")
                 }
-            })),
+                }))
+            }
 
             "notifications/initialized" => {
                 // Client acknowledged initialization - no response needed for notifications
@@ -889,6 +1418,64 @@ mod tests {
         assert!(names.contains(&"vc_collector_status"));
         assert!(names.contains(&"vc_playbook_drafts"));
         assert!(names.contains(&"vc_audit_log"));
+        assert!(names.contains(&"vc_timeline"));
+        assert!(names.contains(&"vc_search"));
+        assert!(names.contains(&"vc_propose_query"));
+        assert!(names.contains(&"vc_check_query"));
+        assert!(names.contains(&"vc_get_session_transcript"));
+        assert_eq!(names, vc_config::MCP_TOOLS);
+    }
+
+    #[test]
+    fn test_role_hides_tools_from_list_and_call() {
+        let allowed = vec!["vc_fleet_status".to_string(), "vc_query_alerts".to_string()];
+        let server = test_server().with_allowed_tools(Some("triage"), Some(&allowed));
+        let names: Vec<&str> = server
+            .list_tools()
+            .iter()
+            .map(|t| t.name.as_str())
+            .collect();
+        assert_eq!(names, ["vc_fleet_status", "vc_query_alerts"]);
+
+        assert!(
+            server
+                .call_tool("vc_fleet_status", &serde_json::json!({}))
+                .is_ok()
+        );
+        match server.call_tool("vc_query_nl", &serde_json::json!({"question": "x"})) {
+            Err(McpError::ToolNotAllowed { tool, role }) => {
+                assert_eq!(tool, "vc_query_nl");
+                assert_eq!(role, "triage");
+            }
+            other => panic!("Expected ToolNotAllowed, got: {other:?}"),
+        }
+        assert!(matches!(
+            server.call_tool("vc_nonexistent", &serde_json::json!({})),
+            Err(McpError::ToolNotFound(_))
+        ));
+
+        let req = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(1)),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "vc_query_nl", "arguments": {}}),
+        };
+        let result = server.handle_request(&req).result.unwrap();
+        assert_eq!(result["isError"], true);
+        assert!(
+            result["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("not available for this role")
+        );
+
+        // Deny by default: no role, no tools
+        let server = test_server().with_allowed_tools(None, Some(&[]));
+        assert!(server.list_tools().is_empty());
+        assert!(matches!(
+            server.call_tool("vc_fleet_status", &serde_json::json!({})),
+            Err(McpError::ToolNotAllowed { .. })
+        ));
     }
 
     #[test]
@@ -954,6 +1541,37 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_call_fleet_status_counts_maintenance_apart() {
+        let store = Arc::new(VcStore::open_memory().unwrap());
+        store
+            .execute_simple(
+                "INSERT INTO machines (machine_id, hostname, enabled, status) VALUES \
+                 ('orko', 'orko', 1, 'online'), ('box', 'box', 1, 'online')",
+            )
+            .unwrap();
+        store
+            .start_maintenance(
+                "box",
+                Some("RAM upgrade"),
+                None,
+                &vc_store::ActorContext::system("op"),
+            )
+            .unwrap();
+        let server = McpServer::new(store);
+
+        let result = server
+            .call_tool("vc_fleet_status", &serde_json::json!({}))
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert_eq!(parsed["total_machines"], 2);
+        assert_eq!(parsed["maintenance"], 1);
+        assert_eq!(
+            parsed["online"].as_u64().unwrap() + parsed["offline"].as_u64().unwrap(),
+            1
+        );
+    }
+
     #[test]
     fn test_call_query_machines() {
         let server = test_server();
@@ -1011,6 +1629,125 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    /// `n` sessions on orko, each with a `bytes`-long transcript
+    fn seed_sessions(server: &McpServer, n: usize, bytes: usize) {
+        let values: Vec<String> = (0..n)
+            .map(|i| {
+                format!(
+                    "('orko', 's-{i}', '2026-10-16T12:{:02}:00Z', '{}')",
+                    i % 60,
+                    "t".repeat(bytes)
+                )
+            })
+            .collect();
+        server
+            .store
+            .execute_batch(&format!(
+                "INSERT INTO agent_sessions (machine_id, session_id, started_at, raw_json) VALUES {}",
+                values.join(", ")
+            ))
+            .unwrap();
+    }
+
+    fn call_json(server: &McpServer, tool: &str, args: serde_json::Value) -> serde_json::Value {
+        let result = server.call_tool(tool, &args).unwrap();
+        serde_json::from_str(&result.content[0].text).unwrap()
+    }
+
+    #[test]
+    fn test_query_sessions_leaves_out_transcripts_and_fits_the_budget() {
+        let server = test_server().with_max_response_bytes(16 * 1024);
+        seed_sessions(&server, 20, 4_000);
+
+        // Without transcripts all 20 fit
+        let parsed = call_json(&server, "vc_query_sessions", serde_json::json!({}));
+        assert_eq!(parsed["count"], 20);
+        assert!(parsed["sessions"][0].get("raw_json").is_none());
+        assert_eq!(parsed["omitted_fields"], serde_json::json!(["raw_json"]));
+        assert!(parsed.get("truncated").is_none());
+
+        // With them only a few do, and the cut says so
+        let result = server
+            .call_tool(
+                "vc_query_sessions",
+                &serde_json::json!({"include_full": true}),
+            )
+            .unwrap();
+        assert!(result.content[0].text.len() <= 16 * 1024);
+        let parsed: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert_eq!(parsed["truncated"], true);
+        assert_eq!(parsed["total_available"], 20);
+        let kept = parsed["sessions"].as_array().unwrap().len();
+        assert!((1..20).contains(&kept), "{kept}");
+        assert_eq!(parsed["count"], kept);
+        assert_eq!(parsed["sessions"][0]["raw_json"], "t".repeat(4_000));
+    }
+
+    #[test]
+    fn test_get_session_transcript_pages_through_the_text() {
+        let server = test_server().with_max_response_bytes(4 * 1024);
+        // Multi-byte characters, so pages have to end on char boundaries
+        let transcript = "héllo wörld ".repeat(1_000);
+        server
+            .store
+            .execute_batch(&format!(
+                "INSERT INTO agent_sessions (machine_id, session_id, raw_json) \
+                 VALUES ('orko', 's-1', '{transcript}'), ('bender', 's-2', 'a'), ('orko', 's-2', 'b')"
+            ))
+            .unwrap();
+
+        let mut text = String::new();
+        let mut offset = Some(0);
+        let mut pages = 0;
+        while let Some(at) = offset {
+            let page = call_json(
+                &server,
+                "vc_get_session_transcript",
+                serde_json::json!({"session_id": "s-1", "offset": at}),
+            );
+            assert_eq!(page["offset"], at);
+            assert_eq!(page["total_length"], transcript.len());
+            text.push_str(page["text"].as_str().unwrap());
+            offset = page["next_offset"].as_u64();
+            pages += 1;
+        }
+        assert_eq!(text, transcript);
+        // The default page does not fit 4 KiB, so it was shrunk
+        assert!(pages > 3, "{pages}");
+
+        let page = call_json(
+            &server,
+            "vc_get_session_transcript",
+            serde_json::json!({"session_id": "s-1", "offset": 1, "length": 4}),
+        );
+        // 'é' is two bytes, so four bytes from offset 1 are three characters
+        assert_eq!(page["text"], "éll");
+        assert_eq!(page["next_offset"], 5);
+        // An offset inside a character starts at the character
+        let page = call_json(
+            &server,
+            "vc_get_session_transcript",
+            serde_json::json!({"session_id": "s-1", "offset": 2, "length": 4}),
+        );
+        assert_eq!(page["offset"], 1);
+
+        // An ID on two machines needs the machine
+        let result = server
+            .call_tool(
+                "vc_get_session_transcript",
+                &serde_json::json!({"session_id": "s-2"}),
+            )
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        let page = call_json(
+            &server,
+            "vc_get_session_transcript",
+            serde_json::json!({"session_id": "s-2", "machine": "bender"}),
+        );
+        assert_eq!(page["text"], "a");
+        assert!(page["next_offset"].is_null());
+    }
+
     #[test]
     fn test_call_query_incidents() {
         let server = test_server();
@@ -1091,6 +1828,74 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_call_timeline() {
+        let server = test_server();
+        server
+            .store
+            .execute_batch(&format!(
+                "INSERT INTO audit_events (id, ts, event_type, actor, machine_id, action, result) \
+                 VALUES (1, '{}', 'user_command', 'ops', 'orko', 'vc alert ack 3', 'success')",
+                chrono::Utc::now().to_rfc3339()
+            ))
+            .unwrap();
+        let result = server
+            .call_tool(
+                "vc_timeline",
+                &serde_json::json!({"since": "1h", "kinds": ["audit"], "machine": "orko"}),
+            )
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert_eq!(parsed["count"], 1);
+        assert_eq!(parsed["events"][0]["kind"], "audit");
+
+        let result = server.call_tool("vc_timeline", &serde_json::json!({"kinds": ["deploys"]}));
+        assert_eq!(result.unwrap().is_error, Some(true));
+    }
+
+    #[test]
+    fn test_call_search() {
+        let server = test_server();
+        server
+            .store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, message, machine_id) \
+                 VALUES (1, 'memory', '{}', 'critical', 'Memory critical', 'OOM kills', 'orko')",
+                chrono::Utc::now().to_rfc3339()
+            ))
+            .unwrap();
+        let result = server
+            .call_tool("vc_search", &serde_json::json!({"query": "oom"}))
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert_eq!(parsed["count"], 1);
+        assert_eq!(parsed["hits"][0]["kind"], "alerts");
+        assert_eq!(parsed["hits"][0]["open"], "vc timeline show alerts 1");
+
+        let result = server.call_tool("vc_search", &serde_json::json!({}));
+        assert_eq!(result.unwrap().is_error, Some(true));
+    }
+
+    #[test]
+    fn test_tools_report_missing_tables() {
+        let server = test_server();
+        let result = server.call_tool("vc_query_incidents", &serde_json::json!({}));
+        let parsed: serde_json::Value =
+            serde_json::from_str(&result.unwrap().content[0].text).unwrap();
+        assert!(parsed.get("missing_capabilities").is_none());
+
+        server.store.execute_batch("DROP TABLE incidents;").unwrap();
+        let result = server.call_tool("vc_query_incidents", &serde_json::json!({}));
+        let r = result.unwrap();
+        assert_eq!(r.is_error, None);
+        let parsed: serde_json::Value = serde_json::from_str(&r.content[0].text).unwrap();
+        assert_eq!(parsed["count"], 0);
+        assert_eq!(
+            parsed["missing_capabilities"],
+            serde_json::json!(["incidents"])
+        );
+    }
+
     #[test]
     fn test_call_tool_not_found() {
         let server = test_server();
@@ -1157,6 +1962,107 @@ mod tests {
         assert!(result.get("serverInfo").is_some());
     }
 
+    #[test]
+    fn test_explicit_actor_outranks_client_info() {
+        let server = test_server().with_actor(Some("night-orchestrator"));
+        server.handle_request(&JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(serde_json::json!(1)),
+            method: "initialize".to_string(),
+            params: serde_json::json!({ "clientInfo": { "name": "claude-desktop" } }),
+        });
+        let actor = server.actor();
+        assert_eq!(actor.name, "night-orchestrator");
+        assert_eq!(actor.via, vc_store::QueryCaller::Mcp);
+
+        server
+            .store
+            .insert_audit_event(&actor.audit_event(
+                vc_store::AuditEventType::UserCommand,
+                "alert_bulk_acknowledge",
+                vc_store::AuditResult::Success,
+                serde_json::json!({}),
+            ))
+            .unwrap();
+        let result = server
+            .call_tool(
+                "vc_audit_log",
+                &serde_json::json!({"actor": "night-orchestrator"}),
+            )
+            .unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&result.content[0].text).unwrap();
+        assert_eq!(parsed["count"], 1);
+        assert_eq!(parsed["events"][0]["actor_via"], "mcp");
+    }
+
+    #[test]
+    fn test_proposed_query_is_returned_once_approved() {
+        let server = test_server().with_actor(Some("night-orchestrator"));
+        let call = |name: &str, args: serde_json::Value| -> serde_json::Value {
+            let result = server.call_tool(name, &args).unwrap();
+            assert!(result.is_error.is_none(), "{}", result.content[0].text);
+            serde_json::from_str(&result.content[0].text).unwrap()
+        };
+
+        let rejected = server
+            .call_tool(
+                "vc_propose_query",
+                &serde_json::json!({"sql": "DROP TABLE machines", "justification": "x"}),
+            )
+            .unwrap();
+        assert_eq!(rejected.is_error, Some(true));
+
+        let proposed = call(
+            "vc_propose_query",
+            serde_json::json!({"sql": "SELECT 7 AS n", "justification": "sanity check"}),
+        );
+        let id = proposed["id"].as_i64().unwrap();
+        assert_eq!(proposed["status"], "pending");
+        let pending = call("vc_check_query", serde_json::json!({"id": id}));
+        assert_eq!(pending["proposed_by"], "night-orchestrator");
+        assert!(pending["result"].is_null());
+
+        vc_query::QueryValidator::new(vc_query::GuardrailConfig::default())
+            .approve_proposal(&server.store, id, &ActorContext::cli(Some("alice")))
+            .unwrap();
+        let executed = call("vc_check_query", serde_json::json!({"id": id}));
+        assert_eq!(executed["status"], "executed");
+        assert_eq!(executed["result"][0]["n"], 7);
+
+        // Another agent on the same store cannot read it
+        let other = McpServer::new(server.store.clone()).with_actor(Some("day-orchestrator"));
+        let result = other
+            .call_tool("vc_check_query", &serde_json::json!({"id": id}))
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.content[0].text.contains("no query proposal"));
+        assert!(!result.content[0].text.contains("\"n\""));
+    }
+
+    #[test]
+    fn test_proposed_query_uses_configured_guardrails() {
+        let server = test_server()
+            .with_actor(Some("night-orchestrator"))
+            .with_query_validator(vc_query::QueryValidator::new(vc_query::GuardrailConfig {
+                allow_raw_sql: false,
+                ..vc_query::GuardrailConfig::default()
+            }));
+        let result = server
+            .call_tool(
+                "vc_propose_query",
+                &serde_json::json!({"sql": "SELECT 7 AS n", "justification": "x"}),
+            )
+            .unwrap();
+        assert_eq!(result.is_error, Some(true), "{}", result.content[0].text);
+        assert!(
+            server
+                .store
+                .list_query_proposals(None, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_jsonrpc_tools_list() {
         let server = test_server();
@@ -1413,6 +2319,7 @@ mod tests {
             }),
         });
         assert!(init.error.is_none());
+        assert_eq!(server.actor().name, "test-client");
 
         // 2. List tools
         let tools_resp = server.handle_request(&JsonRpcRequest {
@@ -0,0 +1,180 @@
+//! Running agents from the agent inventory
+//!
+//! The `agent_inventory` collector keeps `agents_inventory` current: one
+//! open row per agent running on a machine. This module rolls it up per
+//! machine for `vc fleet resources` and picks out stalled agents, those in
+//! a tmux pane with no output for a while. Agents outside tmux have no
+//! activity time and are never counted as stalled.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vc_store::{AgentInventoryRecord, VcStore};
+
+use crate::QueryError;
+use crate::timefmt::parse_timestamp;
+
+/// Minutes without output before an agent in a tmux pane counts as stalled
+pub const DEFAULT_STALLED_MINS: i64 = 30;
+
+/// Running agents on one machine
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineAgents {
+    pub machine_id: String,
+    pub active: usize,
+    /// Active agents per agent type
+    pub by_type: BTreeMap<String, usize>,
+    /// Of the active agents, those running in a tmux pane
+    pub in_tmux: usize,
+    /// Of those, the ones with no output for the stall threshold
+    pub stalled: usize,
+    /// Start of the longest-running agent
+    pub oldest_started_at: Option<String>,
+    /// When the collector last saw any of them
+    pub last_seen_at: Option<String>,
+}
+
+fn is_stalled(
+    agent: &AgentInventoryRecord,
+    stalled_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> bool {
+    agent
+        .last_activity_at
+        .as_deref()
+        .and_then(parse_timestamp)
+        .is_some_and(|at| now - at >= stalled_after)
+}
+
+/// Active agents with no output for `stalled_after`, quietest first
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if the store query fails.
+pub fn stalled_agents(
+    store: &VcStore,
+    stalled_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<Vec<AgentInventoryRecord>, QueryError> {
+    let mut stalled: Vec<AgentInventoryRecord> = store
+        .list_agents_inventory(None, true)?
+        .into_iter()
+        .filter(|agent| is_stalled(agent, stalled_after, now))
+        .collect();
+    stalled.sort_by(|a, b| {
+        a.last_activity_at
+            .cmp(&b.last_activity_at)
+            .then_with(|| a.agent_key.cmp(&b.agent_key))
+    });
+    Ok(stalled)
+}
+
+/// Running agents per machine, for one machine or the whole fleet,
+/// ordered by machine
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if the store query fails.
+pub fn fleet_resources(
+    store: &VcStore,
+    machine_id: Option<&str>,
+    stalled_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<Vec<MachineAgents>, QueryError> {
+    let mut machines: BTreeMap<String, MachineAgents> = BTreeMap::new();
+    for agent in store.list_agents_inventory(machine_id, true)? {
+        let entry = machines
+            .entry(agent.machine_id.clone())
+            .or_insert_with(|| MachineAgents {
+                machine_id: agent.machine_id.clone(),
+                ..MachineAgents::default()
+            });
+        entry.active += 1;
+        *entry.by_type.entry(agent.agent_type.clone()).or_default() += 1;
+        if agent.source == "tmux" {
+            entry.in_tmux += 1;
+        }
+        if is_stalled(&agent, stalled_after, now) {
+            entry.stalled += 1;
+        }
+        let started = agent
+            .started_at
+            .clone()
+            .unwrap_or_else(|| agent.first_seen_at.clone());
+        if entry
+            .oldest_started_at
+            .as_ref()
+            .is_none_or(|oldest| started < *oldest)
+        {
+            entry.oldest_started_at = Some(started);
+        }
+        if entry
+            .last_seen_at
+            .as_ref()
+            .is_none_or(|seen| agent.last_seen_at > *seen)
+        {
+            entry.last_seen_at = Some(agent.last_seen_at.clone());
+        }
+    }
+    Ok(machines.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn agent(key: &str, agent_type: &str, activity: Option<&str>) -> serde_json::Value {
+        serde_json::json!({
+            "agent_key": key,
+            "agent_type": agent_type,
+            "source": if activity.is_some() { "tmux" } else { "process" },
+            "started_at": "2026-10-16T08:00:00Z",
+            "last_activity_at": activity,
+        })
+    }
+
+    #[test]
+    fn test_fleet_resources_and_stalled_agents() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .record_agent_inventory(
+                "orko",
+                "2026-10-16T12:00:00Z",
+                &[
+                    agent("tmux:%1:10", "claude", Some("2026-10-16T11:58:00Z")),
+                    agent("tmux:%2:20", "claude", Some("2026-10-16T10:00:00Z")),
+                    agent("pid:30", "codex", None),
+                ],
+            )
+            .unwrap();
+        store
+            .record_agent_inventory(
+                "mac",
+                "2026-10-16T12:00:00Z",
+                &[agent("pid:7", "codex", None)],
+            )
+            .unwrap();
+        store
+            .record_agent_inventory("mac", "2026-10-16T12:05:00Z", &[])
+            .unwrap();
+
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let after = chrono::Duration::minutes(DEFAULT_STALLED_MINS);
+        let machines = fleet_resources(&store, None, after, now).unwrap();
+        assert_eq!(machines.len(), 1, "mac's only agent has ended");
+        let orko = &machines[0];
+        assert_eq!(orko.active, 3);
+        assert_eq!(orko.by_type["claude"], 2);
+        assert_eq!(orko.in_tmux, 2);
+        assert_eq!(orko.stalled, 1);
+        assert_eq!(
+            orko.oldest_started_at.as_deref(),
+            Some("2026-10-16T08:00:00Z")
+        );
+
+        let stalled = stalled_agents(&store, after, now).unwrap();
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].agent_key, "tmux:%2:20");
+    }
+}
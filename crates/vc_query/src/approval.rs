@@ -0,0 +1,152 @@
+//! Operator approval of raw queries proposed by agents
+//!
+//! Proposals are checked with the same read-only guardrails as any raw query
+//! before they are queued, and again when approved, right before they run
+//! under the agent execution deadline. The rows (capped at the guardrail row
+//! limit) or the error are kept on the proposal for the agent to fetch.
+
+use std::time::{Duration, Instant};
+
+use vc_store::{ActorContext, QueryProposal, VcStore};
+
+use crate::{QueryError, QueryOrigin, QueryValidator};
+
+impl QueryValidator {
+    /// Queue `sql` for an operator to approve, pending for `ttl`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] if the justification is empty, raw
+    /// SQL is disallowed, or the query is not a single read-only statement,
+    /// or [`QueryError::StoreError`] if recording it fails.
+    pub fn propose(
+        &self,
+        store: &VcStore,
+        sql: &str,
+        justification: &str,
+        proposer: &ActorContext,
+        ttl: Duration,
+    ) -> Result<QueryProposal, QueryError> {
+        if justification.trim().is_empty() {
+            return Err(QueryError::InvalidQuery(
+                "a justification is required".to_string(),
+            ));
+        }
+        self.validate_raw(sql)
+            .map_err(|e| QueryError::InvalidQuery(e.to_string()))?;
+        Ok(store.propose_query(sql.trim(), justification.trim(), proposer, ttl)?)
+    }
+
+    /// Approve a pending proposal and run it. A query that fails or times
+    /// out leaves the proposal `failed` with the error; it is not an error
+    /// here. Returns `None` if the proposal is not pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::StoreError`] if recording the decision or the
+    /// outcome fails.
+    pub fn approve_proposal(
+        &self,
+        store: &VcStore,
+        id: i64,
+        operator: &ActorContext,
+    ) -> Result<Option<QueryProposal>, QueryError> {
+        let Some(proposal) = store.decide_query_proposal(id, true, None, operator)? else {
+            return Ok(None);
+        };
+
+        let started = Instant::now();
+        let outcome = self
+            .validate_readonly(&proposal.sql)
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                self.execute(store, &proposal.sql, QueryOrigin::Agent)
+                    .map_err(|e| e.to_string())
+            });
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let proposal = match outcome {
+            Ok(mut rows) => {
+                rows.truncate(self.config().max_rows);
+                store.record_query_proposal_result(id, Ok(&rows), duration_ms, operator)?
+            }
+            Err(error) => {
+                store.record_query_proposal_result(id, Err(&error), duration_ms, operator)?
+            }
+        };
+        Ok(Some(proposal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GuardrailConfig;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn test_proposals_are_validated_and_run_on_approval() {
+        let store = VcStore::open_memory().unwrap();
+        let validator = QueryValidator::new(GuardrailConfig::default());
+        let agent = ActorContext::mcp(Some("agent"), None);
+        let operator = ActorContext::cli(Some("alice"));
+
+        assert!(
+            validator
+                .propose(&store, "DELETE FROM machines", "cleanup", &agent, HOUR)
+                .is_err()
+        );
+        assert!(
+            validator
+                .propose(&store, "SELECT 1", "  ", &agent, HOUR)
+                .is_err()
+        );
+
+        let proposal = validator
+            .propose(
+                &store,
+                "SELECT 42 AS answer",
+                "need the answer",
+                &agent,
+                HOUR,
+            )
+            .unwrap();
+        let executed = validator
+            .approve_proposal(&store, proposal.id, &operator)
+            .unwrap()
+            .unwrap();
+        assert_eq!(executed.status, "executed");
+        assert_eq!(executed.row_count, Some(1));
+        assert_eq!(executed.result.unwrap()[0]["answer"], 42);
+
+        // Already decided
+        assert!(
+            validator
+                .approve_proposal(&store, proposal.id, &operator)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_failing_query_leaves_proposal_failed() {
+        let store = VcStore::open_memory().unwrap();
+        let validator = QueryValidator::new(GuardrailConfig::default());
+        let proposal = validator
+            .propose(
+                &store,
+                "SELECT * FROM no_such_table",
+                "look",
+                &ActorContext::mcp(Some("agent"), None),
+                HOUR,
+            )
+            .unwrap();
+        let failed = validator
+            .approve_proposal(&store, proposal.id, &ActorContext::cli(Some("alice")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(failed.status, "failed");
+        assert!(failed.error.is_some());
+        assert!(failed.result.is_none());
+    }
+}
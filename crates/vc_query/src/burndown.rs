@@ -0,0 +1,370 @@
+//! Alert and incident burndown
+//!
+//! Per UTC day over a window: alerts fired and resolved, by severity, and
+//! incidents opened and closed. It answers "are things getting better"
+//! without reading the alert list.
+//!
+//! A day nothing was watching is not a quiet day. A day is observed when the
+//! daemon left a heartbeat on it (`daemon_heartbeats`), a collector ran, or
+//! something fired, resolved, opened or closed; the counts of a day that is
+//! not observed are `None`, not zero.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use vc_store::VcStore;
+
+use crate::QueryError;
+
+/// Longest window a burndown covers
+pub const MAX_BURNDOWN_DAYS: u32 = 366;
+
+/// Counts per severity. `high` counts as critical and `medium` as warning;
+/// anything else is info.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeverityCounts {
+    pub critical: u64,
+    pub warning: u64,
+    pub info: u64,
+}
+
+impl SeverityCounts {
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        self.critical + self.warning + self.info
+    }
+
+    fn add(&mut self, severity: &str, count: u64) {
+        match severity.to_ascii_lowercase().as_str() {
+            "critical" | "high" => self.critical += count,
+            "warning" | "medium" => self.warning += count,
+            _ => self.info += count,
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.critical += other.critical;
+        self.warning += other.warning;
+        self.info += other.info;
+    }
+}
+
+/// One UTC day of the burndown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BurndownDay {
+    /// `YYYY-MM-DD`
+    pub day: String,
+    /// Whether anything was watching; when `false` every count is `None`
+    pub observed: bool,
+    pub alerts_fired: Option<SeverityCounts>,
+    pub alerts_resolved: Option<SeverityCounts>,
+    pub incidents_opened: Option<u64>,
+    pub incidents_closed: Option<u64>,
+}
+
+/// Sums over the observed days of the window
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BurndownTotals {
+    pub alerts_fired: SeverityCounts,
+    pub alerts_resolved: SeverityCounts,
+    pub incidents_opened: u64,
+    pub incidents_closed: u64,
+    pub days_observed: u32,
+    pub days_unobserved: u32,
+}
+
+impl BurndownTotals {
+    /// Alerts fired minus alerts resolved; negative means burning down
+    #[must_use]
+    pub fn alerts_net(&self) -> i64 {
+        signed(self.alerts_fired.total()) - signed(self.alerts_resolved.total())
+    }
+
+    /// Incidents opened minus incidents closed
+    #[must_use]
+    pub fn incidents_net(&self) -> i64 {
+        signed(self.incidents_opened) - signed(self.incidents_closed)
+    }
+}
+
+fn signed(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Daily burndown, oldest day first, ending today
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Burndown {
+    pub window_days: u32,
+    pub generated_at: String,
+    pub days: Vec<BurndownDay>,
+    pub totals: BurndownTotals,
+}
+
+impl Burndown {
+    /// Alerts fired per day, `None` where unobserved; for sparklines
+    #[must_use]
+    pub fn fired_series(&self) -> Vec<Option<f64>> {
+        self.series(|day| day.alerts_fired.map(|c| c.total()))
+    }
+
+    /// Alerts resolved per day, `None` where unobserved
+    #[must_use]
+    pub fn resolved_series(&self) -> Vec<Option<f64>> {
+        self.series(|day| day.alerts_resolved.map(|c| c.total()))
+    }
+
+    /// Incidents opened per day, `None` where unobserved
+    #[must_use]
+    pub fn incidents_opened_series(&self) -> Vec<Option<f64>> {
+        self.series(|day| day.incidents_opened)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn series(&self, value: impl Fn(&BurndownDay) -> Option<u64>) -> Vec<Option<f64>> {
+        self.days
+            .iter()
+            .map(|day| value(day).map(|v| v as f64))
+            .collect()
+    }
+}
+
+/// `CAST(...)` of a text timestamp column to its UTC day as `YYYY-MM-DD`
+fn day_of(column: &str) -> String {
+    format!("CAST(CAST(CAST({column} AS TIMESTAMP) AS DATE) AS TEXT)")
+}
+
+/// Rows of `(day, key, count)` from a query selecting `day`, `key` and `n`
+fn day_counts(store: &VcStore, sql: &str) -> Result<Vec<(String, String, u64)>, QueryError> {
+    Ok(store
+        .query_json(sql)?
+        .into_iter()
+        .filter_map(|row| {
+            Some((
+                row["day"].as_str()?.to_string(),
+                row["key"].as_str().unwrap_or_default().to_string(),
+                row["n"].as_u64().unwrap_or(0),
+            ))
+        })
+        .collect())
+}
+
+/// Build the burndown for the `window_days` UTC days ending on `now`'s day
+///
+/// # Errors
+///
+/// Returns [`QueryError::InvalidQuery`] if `window_days` is 0 or more than
+/// [`MAX_BURNDOWN_DAYS`], or [`QueryError`] if a store query fails.
+pub fn burndown(
+    store: &VcStore,
+    window_days: u32,
+    now: DateTime<Utc>,
+) -> Result<Burndown, QueryError> {
+    if window_days == 0 || window_days > MAX_BURNDOWN_DAYS {
+        return Err(QueryError::InvalidQuery(format!(
+            "burndown window must be 1 to {MAX_BURNDOWN_DAYS} days, got {window_days}"
+        )));
+    }
+    let today = now.date_naive();
+    let first = today - Duration::days(i64::from(window_days) - 1);
+    let since = format!("CAST('{} 00:00:00' AS TIMESTAMP)", first.format("%Y-%m-%d"));
+
+    let fired = day_counts(
+        store,
+        &format!(
+            "SELECT {day} AS day, COALESCE(severity, 'info') AS key, COUNT(*) AS n \
+             FROM alert_history WHERE CAST(fired_at AS TIMESTAMP) >= {since} GROUP BY 1, 2",
+            day = day_of("fired_at")
+        ),
+    )?;
+    let resolved = day_counts(
+        store,
+        &format!(
+            "SELECT {day} AS day, COALESCE(severity, 'info') AS key, COUNT(*) AS n \
+             FROM alert_history WHERE resolved_at IS NOT NULL \
+             AND CAST(resolved_at AS TIMESTAMP) >= {since} GROUP BY 1, 2",
+            day = day_of("resolved_at")
+        ),
+    )?;
+    let opened = day_counts(
+        store,
+        &format!(
+            "SELECT {day} AS day, '' AS key, COUNT(*) AS n FROM incidents \
+             WHERE CAST(started_at AS TIMESTAMP) >= {since} GROUP BY 1",
+            day = day_of("started_at")
+        ),
+    )?;
+    let closed = day_counts(
+        store,
+        &format!(
+            "SELECT {day} AS day, '' AS key, COUNT(*) AS n FROM incidents \
+             WHERE ended_at IS NOT NULL AND CAST(ended_at AS TIMESTAMP) >= {since} GROUP BY 1",
+            day = day_of("ended_at")
+        ),
+    )?;
+    let watched = day_counts(
+        store,
+        &format!(
+            "SELECT day, '' AS key, 1 AS n FROM daemon_heartbeats \
+             WHERE day >= '{first}' \
+             UNION SELECT DISTINCT {day} AS day, '' AS key, 1 AS n FROM collector_health \
+             WHERE CAST(collected_at AS TIMESTAMP) >= {since}",
+            first = first.format("%Y-%m-%d"),
+            day = day_of("collected_at")
+        ),
+    )?;
+
+    let mut observed: BTreeSet<String> = watched.into_iter().map(|(day, _, _)| day).collect();
+    let mut by_severity = |rows: Vec<(String, String, u64)>| {
+        let mut counts: BTreeMap<String, SeverityCounts> = BTreeMap::new();
+        for (day, severity, n) in rows {
+            counts.entry(day.clone()).or_default().add(&severity, n);
+            observed.insert(day);
+        }
+        counts
+    };
+    let fired = by_severity(fired);
+    let resolved = by_severity(resolved);
+    let mut by_day = |rows: Vec<(String, String, u64)>| {
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for (day, _, n) in rows {
+            *counts.entry(day.clone()).or_default() += n;
+            observed.insert(day);
+        }
+        counts
+    };
+    let opened = by_day(opened);
+    let closed = by_day(closed);
+
+    let mut totals = BurndownTotals::default();
+    let days = first
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|date: NaiveDate| {
+            let day = date.format("%Y-%m-%d").to_string();
+            if !observed.contains(&day) {
+                totals.days_unobserved += 1;
+                return BurndownDay {
+                    day,
+                    observed: false,
+                    alerts_fired: None,
+                    alerts_resolved: None,
+                    incidents_opened: None,
+                    incidents_closed: None,
+                };
+            }
+            let day_fired = fired.get(&day).copied().unwrap_or_default();
+            let day_resolved = resolved.get(&day).copied().unwrap_or_default();
+            let day_opened = opened.get(&day).copied().unwrap_or(0);
+            let day_closed = closed.get(&day).copied().unwrap_or(0);
+            totals.days_observed += 1;
+            totals.alerts_fired.merge(&day_fired);
+            totals.alerts_resolved.merge(&day_resolved);
+            totals.incidents_opened += day_opened;
+            totals.incidents_closed += day_closed;
+            BurndownDay {
+                day,
+                observed: true,
+                alerts_fired: Some(day_fired),
+                alerts_resolved: Some(day_resolved),
+                incidents_opened: Some(day_opened),
+                incidents_closed: Some(day_closed),
+            }
+        })
+        .collect();
+
+    Ok(Burndown {
+        window_days,
+        generated_at: now.to_rfc3339(),
+        days,
+        totals,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-10-16T18:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_burndown_separates_quiet_days_from_unwatched_days() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO alert_history (id, rule_id, fired_at, resolved_at, severity, title) VALUES \
+                 (1, 'disk', '2026-10-14T09:00:00Z', '2026-10-16T08:00:00Z', 'critical', 't'), \
+                 (2, 'disk', '2026-10-14T10:00:00Z', NULL, 'medium', 't'), \
+                 (3, 'load', '2026-10-16T01:00:00Z', '2026-10-16T02:00:00Z', 'info', 't'), \
+                 (4, 'load', '2026-10-01T01:00:00Z', NULL, 'warning', 't');
+                 INSERT INTO incidents (incident_id, title, severity, started_at, ended_at) VALUES \
+                 ('inc-1', 'Disk', 'critical', '2026-10-14T09:30:00Z', '2026-10-16T09:00:00Z');
+                 INSERT INTO daemon_heartbeats (day, first_at, last_at) VALUES \
+                 ('2026-10-12', '2026-10-12T00:00:00Z', '2026-10-12T23:59:00Z'), \
+                 ('2026-10-15', '2026-10-15T00:00:00Z', '2026-10-15T23:59:00Z');",
+            )
+            .unwrap();
+
+        let burndown = burndown(&store, 5, now()).unwrap();
+        let days: Vec<(&str, bool)> = burndown
+            .days
+            .iter()
+            .map(|d| (d.day.as_str(), d.observed))
+            .collect();
+        assert_eq!(
+            days,
+            vec![
+                ("2026-10-12", true),
+                ("2026-10-13", false),
+                ("2026-10-14", true),
+                ("2026-10-15", true),
+                ("2026-10-16", true),
+            ]
+        );
+
+        // A watched day with nothing happening is zero, an unwatched one null
+        assert_eq!(
+            burndown.days[0].alerts_fired,
+            Some(SeverityCounts::default())
+        );
+        assert_eq!(burndown.days[1].alerts_fired, None);
+        assert_eq!(burndown.days[1].incidents_opened, None);
+
+        let fired = burndown.days[2].alerts_fired.unwrap();
+        assert_eq!((fired.critical, fired.warning, fired.info), (1, 1, 0));
+        assert_eq!(burndown.days[2].incidents_opened, Some(1));
+        let resolved = burndown.days[4].alerts_resolved.unwrap();
+        assert_eq!((resolved.critical, resolved.info), (1, 1));
+        assert_eq!(burndown.days[4].incidents_closed, Some(1));
+
+        // The alert fired before the window is not counted
+        assert_eq!(burndown.totals.alerts_fired.total(), 3);
+        assert_eq!(burndown.totals.alerts_net(), 1);
+        assert_eq!(burndown.totals.incidents_net(), 0);
+        assert_eq!(
+            (
+                burndown.totals.days_observed,
+                burndown.totals.days_unobserved
+            ),
+            (4, 1)
+        );
+        assert_eq!(
+            burndown.fired_series(),
+            vec![Some(0.0), None, Some(2.0), Some(0.0), Some(1.0)]
+        );
+    }
+
+    #[test]
+    fn test_burndown_rejects_empty_and_huge_windows() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(matches!(
+            burndown(&store, 0, now()),
+            Err(QueryError::InvalidQuery(_))
+        ));
+        assert!(burndown(&store, MAX_BURNDOWN_DAYS + 1, now()).is_err());
+        assert_eq!(burndown(&store, 1, now()).unwrap().days.len(), 1);
+    }
+}
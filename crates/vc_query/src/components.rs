@@ -0,0 +1,667 @@
+//! Component health shared by `vc doctor` and `GET /api/health/components`.
+//!
+//! An uptime monitor wants more than "the web server answered": it wants to
+//! know whether the store is reachable, the daemon is still collecting,
+//! node bundles are getting applied, alerts are getting delivered and the
+//! standby is keeping up. Each of those is a component judged ok, degraded
+//! or failed, with one number behind the judgement (`detail`, in `unit`).
+//!
+//! Both the doctor and the web endpoint call [`evaluate_component`], so the
+//! two never disagree. The overall status of a [`ComponentsReport`] is the
+//! worst component's after `[health_components]` weights and ignores.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use vc_config::{ReplicationMode, VcConfig};
+use vc_store::{
+    DaemonResourceState, DeliveryHealth, ReplicationTableStatus, VcStore, WatermarkSide,
+};
+
+use crate::timefmt::parse_timestamp;
+
+/// Components in report order; `replication` only when configured
+pub const COMPONENTS: [&str; 5] = ["store", "daemon", "ingest", "notifications", "replication"];
+
+/// Daemon poll intervals that may pass without a heartbeat before the
+/// daemon is considered stopped
+pub const HEARTBEAT_MISSED_INTERVALS: u64 = 3;
+
+/// Degradation levels under which the daemon stretches its poll interval
+const STRETCHED_LEVELS: [&str; 2] = ["polling_stretched", "ingest_paused"];
+
+/// Judgement of one component, ordered from best to worst
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Ok,
+    Degraded,
+    Failed,
+}
+
+impl ComponentStatus {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Degraded => "degraded",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Scale the status by a component weight: its rank (ok 0, degraded 1,
+    /// failed 2) times the weight, rounded down and capped at failed
+    #[must_use]
+    pub fn weighted(self, weight: f64) -> Self {
+        let rank = match self {
+            Self::Ok => 0.0,
+            Self::Degraded => 1.0,
+            Self::Failed => 2.0,
+        };
+        let scaled = (rank * weight.max(0.0)).floor();
+        if scaled >= 2.0 {
+            Self::Failed
+        } else if scaled >= 1.0 {
+            Self::Degraded
+        } else {
+            Self::Ok
+        }
+    }
+}
+
+/// Health of one component
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub component: &'static str,
+    pub status: ComponentStatus,
+    /// The number the status was judged on, in `unit`; absent when there
+    /// was nothing to measure
+    pub detail: Option<i64>,
+    pub unit: &'static str,
+    pub message: String,
+    /// How to fix a degradation or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    pub weight: f64,
+    /// Listed in `[health_components] ignore`
+    pub ignored: bool,
+}
+
+impl ComponentHealth {
+    fn new(
+        component: &'static str,
+        status: ComponentStatus,
+        detail: Option<i64>,
+        unit: &'static str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            component,
+            status,
+            detail,
+            unit,
+            message: message.into(),
+            hint: None,
+            weight: 1.0,
+            ignored: false,
+        }
+    }
+
+    #[must_use]
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Status this component contributes to the overall status
+    #[must_use]
+    pub fn effective_status(&self) -> ComponentStatus {
+        if self.ignored {
+            ComponentStatus::Ok
+        } else {
+            self.status.weighted(self.weight)
+        }
+    }
+}
+
+/// Every component, and the worst of them
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentsReport {
+    pub status: ComponentStatus,
+    pub checked_at: String,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl ComponentsReport {
+    /// Judge every component of the hub at `now`
+    #[must_use]
+    pub fn collect(store: Result<&VcStore, &str>, config: &VcConfig, now: DateTime<Utc>) -> Self {
+        let components: Vec<ComponentHealth> = COMPONENTS
+            .iter()
+            .filter_map(|name| evaluate_component(name, store, config, now))
+            .collect();
+        let status = components
+            .iter()
+            .map(ComponentHealth::effective_status)
+            .max()
+            .unwrap_or(ComponentStatus::Ok);
+        Self {
+            status,
+            checked_at: now.to_rfc3339(),
+            components,
+        }
+    }
+}
+
+/// Judge one component by name, with its configured weight and ignore
+/// applied. `None` for an unknown name or a component that does not apply
+/// (replication on a hub without a standby).
+#[must_use]
+pub fn evaluate_component(
+    name: &str,
+    store: Result<&VcStore, &str>,
+    config: &VcConfig,
+    now: DateTime<Utc>,
+) -> Option<ComponentHealth> {
+    let mut health = match (name, store) {
+        ("store", _) => store_health(store, config),
+        ("replication", _) if !replication_configured(config) => return None,
+        (name, Err(_)) => {
+            let component = COMPONENTS.iter().copied().find(|c| *c == name)?;
+            ComponentHealth::new(
+                component,
+                ComponentStatus::Degraded,
+                None,
+                "",
+                "skipped: store did not open",
+            )
+            .with_hint("fix the store first")
+        }
+        ("daemon", Ok(store)) => match store.get_daemon_resource_state() {
+            Ok(state) => daemon_health(state.as_ref(), config, now),
+            Err(e) => unreadable("daemon", &e),
+        },
+        ("ingest", Ok(store)) => match store.ingest_backlog() {
+            Ok(backlog) => ingest_health(backlog, config),
+            Err(e) => unreadable("ingest", &e),
+        },
+        ("notifications", Ok(store)) => match store.delivery_health() {
+            Ok(delivery) => notifications_health(&delivery, config, now),
+            Err(e) => unreadable("notifications", &e),
+        },
+        ("replication", Ok(store)) => {
+            let status = store
+                .replication_role(config.replication.mode)
+                .and_then(|role| {
+                    let tables = store.replication_status(
+                        WatermarkSide::for_mode(role),
+                        &config.replication.tables,
+                    )?;
+                    Ok((role, tables))
+                });
+            match status {
+                Ok((role, tables)) => replication_health(role, &tables, config),
+                Err(e) => unreadable("replication", &e),
+            }
+        }
+        _ => return None,
+    };
+    let settings = &config.health_components;
+    health.weight = settings.weights.get(name).copied().unwrap_or(1.0);
+    health.ignored = settings.ignore.iter().any(|ignored| ignored == name);
+    Some(health)
+}
+
+/// A replication component applies to a standby, or to a primary that
+/// ships to one
+fn replication_configured(config: &VcConfig) -> bool {
+    config.replication.mode == ReplicationMode::Standby || config.replication.standby_url.is_some()
+}
+
+fn unreadable(component: &'static str, error: &vc_store::StoreError) -> ComponentHealth {
+    ComponentHealth::new(
+        component,
+        ComponentStatus::Degraded,
+        None,
+        "",
+        format!("cannot read {component} state: {error}"),
+    )
+    .with_hint("fix the store first")
+}
+
+/// Store opens, its schema is current, and it holds collected data
+#[must_use]
+pub fn store_health(store: Result<&VcStore, &str>, config: &VcConfig) -> ComponentHealth {
+    let name = "store";
+    let unit = "schema_version";
+    let path = config.global.db_path.display();
+    let store = match store {
+        Ok(store) => store,
+        Err(e) => {
+            return ComponentHealth::new(
+                name,
+                ComponentStatus::Failed,
+                None,
+                unit,
+                format!("cannot open store at {path}: {e}"),
+            )
+            .with_hint(
+                "make sure the directory of global.db_path exists and is writable, \
+                 and that no other process holds the database lock",
+            );
+        }
+    };
+
+    let latest = vc_store::migrations::latest_version();
+    let version = match store.schema_version() {
+        Ok(version) => version,
+        Err(e) => {
+            return ComponentHealth::new(
+                name,
+                ComponentStatus::Failed,
+                None,
+                unit,
+                format!("cannot read schema version of {path}: {e}"),
+            )
+            .with_hint("the file may not be a vc store; check global.db_path");
+        }
+    };
+    let detail = Some(i64::from(version));
+    if version > latest {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Failed,
+            detail,
+            unit,
+            format!("store at {path} has schema v{version}, newer than this vc (v{latest})"),
+        )
+        .with_hint("upgrade vc to the version that last wrote this store");
+    }
+    if version < latest {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Failed,
+            detail,
+            unit,
+            format!("store at {path} is at schema v{version}, expected v{latest}"),
+        )
+        .with_hint("reopen the store with a writable path so pending migrations can apply");
+    }
+
+    // A store with no collector runs at all is usually not the one the
+    // daemon writes to
+    let runs = store
+        .query_scalar::<i64>("SELECT COUNT(*) FROM collector_health")
+        .unwrap_or(0);
+    if runs == 0 {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Degraded,
+            detail,
+            unit,
+            format!("store at {path} is current (v{latest}) but holds no collector runs"),
+        )
+        .with_hint(
+            "if the daemon is running, check it uses the same global.db_path \
+             (`vc config show --resolved`)",
+        );
+    }
+    ComponentHealth::new(
+        name,
+        ComponentStatus::Ok,
+        detail,
+        unit,
+        format!("store at {path} is current (v{latest})"),
+    )
+}
+
+/// Judge the daemon's last recorded state at `now`.
+///
+/// The daemon records its state every collection cycle; while it is
+/// shedding load the cycle is stretched by `poll_stretch_factor`.
+#[must_use]
+pub fn daemon_health(
+    state: Option<&DaemonResourceState>,
+    config: &VcConfig,
+    now: DateTime<Utc>,
+) -> ComponentHealth {
+    let name = "daemon";
+    let unit = "seconds_since_heartbeat";
+    let Some(state) = state else {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Degraded,
+            None,
+            unit,
+            "no daemon heartbeat recorded",
+        )
+        .with_hint("start the daemon with `vc daemon` so data is collected");
+    };
+    let Ok(checked_at) = DateTime::parse_from_rfc3339(&state.checked_at) else {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Degraded,
+            None,
+            unit,
+            format!("unreadable daemon heartbeat '{}'", state.checked_at),
+        )
+        .with_hint("restart `vc daemon` to record a fresh heartbeat");
+    };
+
+    let stretch = if STRETCHED_LEVELS.contains(&state.level.as_str()) {
+        u64::from(config.daemon.limits.poll_stretch_factor.max(1))
+    } else {
+        1
+    };
+    let max_age = config.global.poll_interval_secs * stretch * HEARTBEAT_MISSED_INTERVALS;
+    let age = now
+        .signed_duration_since(checked_at)
+        .num_seconds()
+        .max(0)
+        .unsigned_abs();
+    let detail = i64::try_from(age).ok();
+    if age > max_age {
+        ComponentHealth::new(
+            name,
+            ComponentStatus::Failed,
+            detail,
+            unit,
+            format!("last daemon heartbeat was {age}s ago (expected within {max_age}s)"),
+        )
+        .with_hint("the daemon has stopped; restart it with `vc daemon` and check its log")
+    } else if state.level != "normal" || state.disk_pressure {
+        ComponentHealth::new(
+            name,
+            ComponentStatus::Degraded,
+            detail,
+            unit,
+            format!(
+                "daemon is running but degraded ({}): {}",
+                state.level,
+                state.reason.as_deref().unwrap_or("resource limit exceeded")
+            ),
+        )
+        .with_hint("see `vc daemon check` and the [daemon.limits] config section")
+    } else {
+        ComponentHealth::new(
+            name,
+            ComponentStatus::Ok,
+            detail,
+            unit,
+            format!("daemon heartbeat {age}s ago"),
+        )
+    }
+}
+
+/// Any failed node batch awaiting retry degrades ingest; a backlog of
+/// `ingest_backlog_max` fails it
+#[must_use]
+pub fn ingest_health(backlog: i64, config: &VcConfig) -> ComponentHealth {
+    let name = "ingest";
+    let unit = "failed_batches";
+    let max = config.health_components.ingest_backlog_max;
+    let status = if backlog >= max.max(1) {
+        ComponentStatus::Failed
+    } else if backlog > 0 {
+        ComponentStatus::Degraded
+    } else {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Ok,
+            Some(0),
+            unit,
+            "no failed ingest batches",
+        );
+    };
+    ComponentHealth::new(
+        name,
+        status,
+        Some(backlog),
+        unit,
+        format!("{backlog} ingest batch(es) failed and await retry"),
+    )
+    .with_hint("see `vc node history` and re-push the partial bundles")
+}
+
+/// Deliveries failing since the last success degrade notifications; once
+/// nothing was delivered for `notification_silence_secs` they fail
+#[must_use]
+pub fn notifications_health(
+    delivery: &DeliveryHealth,
+    config: &VcConfig,
+    now: DateTime<Utc>,
+) -> ComponentHealth {
+    let name = "notifications";
+    let unit = "seconds_since_success";
+    let since_success = delivery
+        .last_success_at
+        .as_deref()
+        .and_then(parse_timestamp)
+        .map(|at| now.signed_duration_since(at).num_seconds().max(0));
+    if delivery.failures_since_success == 0 {
+        let message = if delivery.last_attempt_at.is_none() {
+            "no alert deliveries attempted yet".to_string()
+        } else {
+            "last alert delivery succeeded".to_string()
+        };
+        return ComponentHealth::new(name, ComponentStatus::Ok, since_success, unit, message);
+    }
+
+    let silence =
+        i64::try_from(config.health_components.notification_silence_secs).unwrap_or(i64::MAX);
+    let status = if since_success.is_none_or(|secs| secs > silence) {
+        ComponentStatus::Failed
+    } else {
+        ComponentStatus::Degraded
+    };
+    let last = since_success.map_or_else(
+        || "nothing was ever delivered".to_string(),
+        |secs| format!("last success {secs}s ago"),
+    );
+    ComponentHealth::new(
+        name,
+        status,
+        since_success,
+        unit,
+        format!(
+            "{} alert delivery attempt(s) failed since the last success; {last}",
+            delivery.failures_since_success
+        ),
+    )
+    .with_hint("see `vc alert deliveries` and check the sink settings under [alerts]")
+}
+
+/// Judge the worst table lag against `replication_lag_warn_secs` and
+/// `replication_lag_fail_secs`
+#[must_use]
+pub fn replication_health(
+    role: ReplicationMode,
+    tables: &[ReplicationTableStatus],
+    config: &VcConfig,
+) -> ComponentHealth {
+    let name = "replication";
+    let unit = "lag_seconds";
+    let settings = &config.health_components;
+    let lag = tables.iter().filter_map(|table| table.lag_secs).max();
+    let errors = tables
+        .iter()
+        .filter(|table| table.last_error.is_some())
+        .count();
+    let role = role.as_str();
+    let Some(lag) = lag else {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Degraded,
+            None,
+            unit,
+            format!("{role}: nothing replicated yet"),
+        )
+        .with_hint("see `vc db replication status`");
+    };
+    let status = if lag >= settings.replication_lag_fail_secs {
+        ComponentStatus::Failed
+    } else if lag >= settings.replication_lag_warn_secs || errors > 0 {
+        ComponentStatus::Degraded
+    } else {
+        return ComponentHealth::new(
+            name,
+            ComponentStatus::Ok,
+            Some(lag),
+            unit,
+            format!("{role}: replication lag {lag}s"),
+        );
+    };
+    let mut message = format!("{role}: replication lag {lag}s");
+    if errors > 0 {
+        let _ = write!(message, ", {errors} table(s) with errors");
+    }
+    ComponentHealth::new(name, status, Some(lag), unit, message)
+        .with_hint("see `vc db replication status` and the standby's log")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(checked_at: DateTime<Utc>, level: &str) -> DaemonResourceState {
+        DaemonResourceState {
+            checked_at: checked_at.to_rfc3339(),
+            level: level.to_string(),
+            disk_pressure: false,
+            rss_mb: None,
+            free_disk_mb: None,
+            max_rss_mb: None,
+            min_free_disk_mb: None,
+            reason: None,
+            watch_subscribers: None,
+        }
+    }
+
+    fn lagging(lag_secs: Option<i64>) -> ReplicationTableStatus {
+        ReplicationTableStatus {
+            table: "sys_samples".to_string(),
+            ts_column: "collected_at".to_string(),
+            watermark: None,
+            source_head: None,
+            rows_pending: 0,
+            rows_total: 0,
+            lag_secs,
+            updated_at: None,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_weighted_status() {
+        assert_eq!(
+            ComponentStatus::Failed.weighted(1.0),
+            ComponentStatus::Failed
+        );
+        assert_eq!(
+            ComponentStatus::Failed.weighted(0.5),
+            ComponentStatus::Degraded
+        );
+        assert_eq!(ComponentStatus::Degraded.weighted(0.5), ComponentStatus::Ok);
+        assert_eq!(
+            ComponentStatus::Degraded.weighted(2.0),
+            ComponentStatus::Failed
+        );
+        assert_eq!(ComponentStatus::Failed.weighted(0.0), ComponentStatus::Ok);
+    }
+
+    #[test]
+    fn test_daemon_heartbeat_freshness() {
+        let config = VcConfig::default();
+        let now = Utc::now();
+        let interval = i64::try_from(config.global.poll_interval_secs).unwrap();
+
+        let missing = daemon_health(None, &config, now);
+        assert_eq!(missing.status, ComponentStatus::Degraded);
+
+        let fresh = state(now - chrono::Duration::seconds(interval), "normal");
+        let health = daemon_health(Some(&fresh), &config, now);
+        assert_eq!(health.status, ComponentStatus::Ok);
+        assert_eq!(health.detail, Some(interval));
+
+        let stale = state(now - chrono::Duration::seconds(interval * 4), "normal");
+        assert_eq!(
+            daemon_health(Some(&stale), &config, now).status,
+            ComponentStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_ingest_notifications_and_replication_thresholds() {
+        let config = VcConfig::default();
+        let now = Utc::now();
+        assert_eq!(ingest_health(0, &config).status, ComponentStatus::Ok);
+        assert_eq!(ingest_health(3, &config).status, ComponentStatus::Degraded);
+        assert_eq!(ingest_health(100, &config).status, ComponentStatus::Failed);
+
+        let recent = DeliveryHealth {
+            last_success_at: Some((now - chrono::Duration::minutes(5)).to_rfc3339()),
+            last_attempt_at: Some(now.to_rfc3339()),
+            failures_since_success: 2,
+        };
+        let health = notifications_health(&recent, &config, now);
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert_eq!(health.detail, Some(300));
+        let never = DeliveryHealth {
+            last_success_at: None,
+            last_attempt_at: Some(now.to_rfc3339()),
+            failures_since_success: 1,
+        };
+        assert_eq!(
+            notifications_health(&never, &config, now).status,
+            ComponentStatus::Failed
+        );
+        assert_eq!(
+            notifications_health(&DeliveryHealth::default(), &config, now).status,
+            ComponentStatus::Ok
+        );
+
+        let primary = ReplicationMode::Primary;
+        let tables = [lagging(Some(30)), lagging(Some(600))];
+        let health = replication_health(primary, &tables, &config);
+        assert_eq!(health.status, ComponentStatus::Degraded);
+        assert_eq!(health.detail, Some(600));
+        assert_eq!(
+            replication_health(primary, &[lagging(Some(7200))], &config).status,
+            ComponentStatus::Failed
+        );
+    }
+
+    #[test]
+    fn test_report_takes_the_worst_weighted_component() {
+        let store = VcStore::open_memory().unwrap();
+        let mut config = VcConfig::default();
+        let now = Utc::now();
+
+        // An empty store and no daemon heartbeat both degrade; replication
+        // is not configured and left out
+        let report = ComponentsReport::collect(Ok(&store), &config, now);
+        let names: Vec<&str> = report.components.iter().map(|c| c.component).collect();
+        assert_eq!(names, vec!["store", "daemon", "ingest", "notifications"]);
+        assert_eq!(report.status, ComponentStatus::Degraded);
+
+        store
+            .insert_delivery_log("a1", "slack", "failed", Some("err"), None)
+            .unwrap();
+        let report = ComponentsReport::collect(Ok(&store), &config, now);
+        assert_eq!(report.status, ComponentStatus::Failed);
+
+        config
+            .health_components
+            .weights
+            .insert("notifications".to_string(), 0.5);
+        config.health_components.ignore = vec!["store".to_string(), "daemon".to_string()];
+        let report = ComponentsReport::collect(Ok(&store), &config, now);
+        assert_eq!(report.status, ComponentStatus::Degraded);
+        assert!(report.components[0].ignored);
+
+        let report = ComponentsReport::collect(Err("locked"), &VcConfig::default(), now);
+        assert_eq!(report.status, ComponentStatus::Failed);
+        assert_eq!(report.components[0].detail, None);
+    }
+}
@@ -305,6 +305,42 @@ impl<'a> CostQueryBuilder<'a> {
         })
     }
 
+    /// Estimated spend per attribution snapshot between `since` and `until`,
+    /// optionally for one machine; the raw series behind `vc costs trend`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if querying the snapshots fails.
+    pub fn spend_samples(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<(DateTime<Utc>, f64)>, QueryError> {
+        let machine_clause = machine_id
+            .map(|id| format!(" AND machine_id = '{}'", escape_sql_literal(id)))
+            .unwrap_or_default();
+        let sql = format!(
+            "SELECT CAST(collected_at AS TEXT) AS collected_at, estimated_cost_usd \
+             FROM cost_attribution_snapshot \
+             WHERE TRY_CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP) \
+               AND TRY_CAST(collected_at AS TIMESTAMP) < CAST('{}' AS TIMESTAMP){machine_clause} \
+             ORDER BY TRY_CAST(collected_at AS TIMESTAMP)",
+            since.format("%Y-%m-%d %H:%M:%S"),
+            until.format("%Y-%m-%d %H:%M:%S")
+        );
+
+        Ok(self
+            .store
+            .query_json(&sql)?
+            .iter()
+            .filter_map(|row| {
+                let at = crate::timefmt::parse_timestamp(row["collected_at"].as_str()?)?;
+                Some((at, row["estimated_cost_usd"].as_f64()?))
+            })
+            .collect())
+    }
+
     /// Get cost breakdown by provider
     fn cost_by_provider(
         &self,
@@ -743,4 +779,32 @@ mod tests {
         // Should have default pricing from migration
         assert!(!pricing.is_empty());
     }
+
+    #[test]
+    fn test_spend_samples_filters_window_and_machine() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                r"
+                INSERT INTO cost_attribution_snapshot
+                    (id, collected_at, machine_id, estimated_cost_usd)
+                VALUES (1, '2026-10-14 09:00:00', 'm1', 1.5),
+                       (2, '2026-10-15 09:00:00', 'm2', 2.0),
+                       (3, '2026-10-15 10:00:00', 'm1', 0.5),
+                       (4, '2026-10-01 09:00:00', 'm1', 9.0);
+                ",
+            )
+            .unwrap();
+        let builder = CostQueryBuilder::new(&store);
+        let since = DateTime::parse_from_rfc3339("2026-10-10T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let until = since + chrono::Duration::days(7);
+
+        let all = builder.spend_samples(since, until, None).unwrap();
+        assert_eq!(all.len(), 3);
+        let m1 = builder.spend_samples(since, until, Some("m1")).unwrap();
+        let total: f64 = m1.iter().map(|(_, usd)| usd).sum();
+        assert!((total - 2.0).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,154 @@
+//! Upstream failures across declared machine dependencies
+//!
+//! When a machine others depend on (`vc machines depend`) has an unresolved
+//! critical alert, alerts on the machines downstream of it are most likely
+//! its fallout. [`upstream_failures`] finds those machines and everything
+//! that depends on them, directly or through another machine. A failing
+//! machine that is itself downstream of another failure is folded into that
+//! one, so a chain of failures reads as one.
+
+use std::collections::{BTreeMap, BTreeSet};
+use vc_store::{MachineDependency, VcStore};
+use vc_types::UpstreamFailure;
+
+use crate::QueryError;
+
+/// Every machine downstream of each machine with dependents, directly or
+/// through others
+#[must_use]
+pub fn downstream_closure(
+    dependencies: &[MachineDependency],
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for dep in dependencies {
+        dependents
+            .entry(dep.depends_on.as_str())
+            .or_default()
+            .push(dep.machine_id.as_str());
+    }
+    dependents
+        .keys()
+        .map(|&upstream| {
+            let mut reached = BTreeSet::new();
+            let mut stack = vec![upstream];
+            while let Some(machine) = stack.pop() {
+                for &next in dependents.get(machine).into_iter().flatten() {
+                    if reached.insert(next.to_string()) {
+                        stack.push(next);
+                    }
+                }
+            }
+            (upstream.to_string(), reached)
+        })
+        .collect()
+}
+
+/// Machines with dependents and an unresolved critical alert, with the
+/// machines they affect. Empty when no dependencies are declared.
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if a store query fails.
+pub fn upstream_failures(store: &VcStore) -> Result<Vec<UpstreamFailure>, QueryError> {
+    let dependencies = store.machine_dependencies()?;
+    if dependencies.is_empty() {
+        return Ok(Vec::new());
+    }
+    let downstream = downstream_closure(&dependencies);
+
+    let mut critical: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for row in store.query_json(
+        "SELECT id, machine_id FROM alert_history \
+         WHERE resolved_at IS NULL AND LOWER(severity) = 'critical' \
+         AND machine_id IS NOT NULL ORDER BY id",
+    )? {
+        if let (Some(id), Some(machine_id)) = (row["id"].as_i64(), row["machine_id"].as_str()) {
+            critical.entry(machine_id.to_string()).or_default().push(id);
+        }
+    }
+
+    Ok(critical
+        .iter()
+        .filter(|(machine_id, _)| {
+            !critical.keys().any(|other| {
+                downstream
+                    .get(other)
+                    .is_some_and(|reached| reached.contains(*machine_id))
+            })
+        })
+        .filter_map(|(machine_id, alert_ids)| {
+            let affected = downstream.get(machine_id)?;
+            Some(UpstreamFailure {
+                machine_id: machine_id.clone(),
+                alert_ids: alert_ids.clone(),
+                affected_machines: affected.iter().cloned().collect(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_chain() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname) VALUES \
+                 ('gw', 'gw'), ('nfs', 'nfs'), ('b1', 'b1'), ('b2', 'b2'), ('lone', 'lone')",
+            )
+            .unwrap();
+        store
+            .add_machine_dependency("nfs", "gw", "network")
+            .unwrap();
+        store
+            .add_machine_dependency("b1", "nfs", "storage")
+            .unwrap();
+        store
+            .add_machine_dependency("b2", "nfs", "storage")
+            .unwrap();
+        store
+    }
+
+    fn fire(store: &VcStore, id: i64, machine_id: &str, severity: &str) {
+        store
+            .execute_simple(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) \
+                 VALUES ({id}, 'r', current_timestamp, '{severity}', 't', '{machine_id}')"
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_downstream_closure_follows_chains() {
+        let store = store_with_chain();
+        let closure = downstream_closure(&store.machine_dependencies().unwrap());
+        let names = |id: &str| closure[id].iter().cloned().collect::<Vec<_>>();
+        assert_eq!(names("gw"), vec!["b1", "b2", "nfs"]);
+        assert_eq!(names("nfs"), vec!["b1", "b2"]);
+        assert!(!closure.contains_key("b1"));
+    }
+
+    #[test]
+    fn test_upstream_failures_fold_chained_failures() {
+        let store = store_with_chain();
+        assert!(upstream_failures(&store).unwrap().is_empty());
+
+        fire(&store, 1, "nfs", "critical");
+        fire(&store, 2, "b1", "warning");
+        fire(&store, 3, "lone", "critical");
+        let failures = upstream_failures(&store).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].machine_id, "nfs");
+        assert_eq!(failures[0].alert_ids, vec![1]);
+        assert_eq!(failures[0].affected_machines, vec!["b1", "b2"]);
+
+        // The gateway failing too makes the NFS failure part of its fallout
+        fire(&store, 4, "gw", "Critical");
+        let failures = upstream_failures(&store).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].machine_id, "gw");
+        assert_eq!(failures[0].affected_machines, vec!["b1", "b2", "nfs"]);
+    }
+}
@@ -1,12 +1,56 @@
 //! Digest report generation
 //!
-//! Aggregates fleet health, alerts, usage, and notable events
-//! into a concise daily/weekly summary.
+//! Aggregates fleet health, alerts, usage, agent session outcomes, repo
+//! activity, incident SLA attainment, notable events and annotations
+//! (deploys and the like) into a concise daily/weekly summary. Sections backed by tables the store does not have
+//! are left out and named in `missing_capabilities`.
+//!
+//! The fleet section compares average health with the window before only
+//! when both windows were scored under the same scoring version; across an
+//! upgrade it says so and points at `vc health rescore` instead.
+//!
+//! A section whose query fails is kept, marked failed with the error and
+//! listed in `sections_failed`, so a report written during an outage says
+//! "data unavailable" instead of quietly reporting zero alerts.
+//!
+//! Below the summary, a daily burndown of alerts and incidents over the
+//! window (at least [`DIGEST_BURNDOWN_MIN_DAYS`] days) shows whether things
+//! are getting better; days nothing was watching are marked, not zeroed.
 
 use serde::{Deserialize, Serialize};
 use std::fmt::Write as _;
+use vc_config::IncidentConfig;
 use vc_store::VcStore;
 
+use crate::burndown::{self, Burndown, BurndownDay};
+use crate::repos::{self, RepoActivity};
+use crate::sla::{self, SlaAttainment};
+use crate::sparkline::{self, SparkStyle};
+use crate::timefmt::{TimeFormatter, parse_timestamp};
+use crate::{QueryBuilder, QueryError, SessionGroupBy};
+
+/// Agent/repo success rates listed in the session outcomes section
+const DIGEST_SESSION_GROUPS: usize = 10;
+
+/// Repositories listed in the repo activity section, after any stale ones
+const DIGEST_REPOS: usize = 10;
+
+/// Annotations listed in the annotations section, newest kept
+const DIGEST_ANNOTATIONS: usize = 20;
+
+/// Shortest burndown a digest shows, so a daily digest still has a trend
+pub const DIGEST_BURNDOWN_MIN_DAYS: u32 = 7;
+
+const FLEET_TITLE: &str = "Fleet Overview";
+const ALERTS_TITLE: &str = "Alert Summary";
+const COLLECTORS_TITLE: &str = "Collector Health";
+const SESSIONS_TITLE: &str = "Session Outcomes";
+const REPOS_TITLE: &str = "Repo Activity";
+const SLA_TITLE: &str = "Incident SLA";
+const EVENTS_TITLE: &str = "Notable Events";
+const ANNOTATIONS_TITLE: &str = "Annotations";
+const BURNDOWN_TITLE: &str = "Burndown";
+
 // ============================================================================
 // Digest sections
 // ============================================================================
@@ -16,6 +60,35 @@ use vc_store::VcStore;
 pub struct DigestSection {
     pub title: String,
     pub items: Vec<String>,
+    /// False when the section's data could not be read; `items` is empty
+    #[serde(default = "section_ok")]
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn section_ok() -> bool {
+    true
+}
+
+impl DigestSection {
+    fn new(title: &str, items: Vec<String>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            ok: true,
+            error: None,
+        }
+    }
+
+    fn failed(title: &str, error: &QueryError) -> Self {
+        Self {
+            title: title.to_string(),
+            items: Vec::new(),
+            ok: false,
+            error: Some(error.to_string()),
+        }
+    }
 }
 
 /// Complete digest report
@@ -26,6 +99,23 @@ pub struct DigestReport {
     pub generated_at: String,
     pub sections: Vec<DigestSection>,
     pub summary: DigestSummary,
+    /// Tables this store lacks; their sections are omitted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_capabilities: Vec<String>,
+    /// Titles of sections whose data could not be read
+    #[serde(default)]
+    pub sections_failed: Vec<String>,
+    /// Daily alert and incident counts; absent when they could not be read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burndown: Option<Burndown>,
+}
+
+impl DigestReport {
+    /// Whether any section failed, making the report incomplete
+    #[must_use]
+    pub fn is_partial(&self) -> bool {
+        !self.sections_failed.is_empty()
+    }
 }
 
 /// High-level summary numbers
@@ -45,30 +135,100 @@ pub struct DigestSummary {
 // Report generator
 // ============================================================================
 
-/// Generate a digest report from the store
+/// Generate a digest report from the store, with incident SLA attainment
+/// measured against `incidents`
 #[must_use]
-pub fn generate_digest(store: &VcStore, window_hours: u32) -> DigestReport {
+pub fn generate_digest(
+    store: &VcStore,
+    window_hours: u32,
+    incidents: &IncidentConfig,
+) -> DigestReport {
     let now = chrono::Utc::now();
     let report_id = format!("digest-{}h-{}", window_hours, now.timestamp());
 
     let mut sections = Vec::new();
     let mut summary = DigestSummary::default();
 
+    // If the catalog cannot be read, assume every table is there; a section
+    // whose table is really missing then shows up as failed.
+    let caps = store.capabilities().ok();
+    let available = |tables: &[&str]| caps.as_ref().is_none_or(|caps| caps.require(tables));
+    let mut section = |title: &str, built: Result<DigestSection, QueryError>| {
+        sections.push(built.unwrap_or_else(|e| {
+            tracing::warn!(section = title, error = %e, "digest section failed");
+            DigestSection::failed(title, &e)
+        }));
+    };
+
     // Section 1: Fleet overview
-    let fleet_section = build_fleet_section(store, &mut summary);
-    sections.push(fleet_section);
+    if available(&["machines", "health_summary"]) {
+        section(
+            FLEET_TITLE,
+            build_fleet_section(store, &mut summary, window_hours),
+        );
+    }
 
     // Section 2: Alert summary
-    let alert_section = build_alert_section(store, &mut summary);
-    sections.push(alert_section);
+    if available(&["alert_history"]) {
+        section(
+            ALERTS_TITLE,
+            build_alert_section(store, &mut summary, window_hours),
+        );
+    }
 
     // Section 3: Collector health
-    let collector_section = build_collector_section(store, &mut summary);
-    sections.push(collector_section);
+    if available(&["collector_health"]) {
+        section(
+            COLLECTORS_TITLE,
+            build_collector_section(store, &mut summary),
+        );
+    }
+
+    // Section 4: Agent session outcomes
+    if available(&["agent_sessions"]) {
+        section(SESSIONS_TITLE, build_session_section(store, window_hours));
+    }
+
+    // Section 5: Repo activity and stale agent work
+    if available(&["repos", "repo_status_snapshots", "agent_sessions"]) {
+        section(REPOS_TITLE, build_repo_section(store, window_hours));
+    }
+
+    // Section 6: Incident SLA attainment
+    if available(&["incidents", "incident_notes", "incident_timeline_events"]) {
+        section(SLA_TITLE, build_sla_section(store, window_hours, incidents));
+    }
+
+    // Section 7: Notable events
+    if available(&["audit_events"]) {
+        section(EVENTS_TITLE, build_events_section(store, window_hours));
+    }
+
+    // Section 8: Deploys and other annotations
+    if available(&["annotations"]) {
+        section(
+            ANNOTATIONS_TITLE,
+            build_annotation_section(store, window_hours, now),
+        );
+    }
 
-    // Section 4: Notable events
-    let events_section = build_events_section(store, window_hours);
-    sections.push(events_section);
+    let mut sections_failed: Vec<String> = sections
+        .iter()
+        .filter(|section| !section.ok)
+        .map(|section| section.title.clone())
+        .collect();
+
+    let burndown = if available(&["alert_history", "incidents", "collector_health"]) {
+        let days = window_hours.div_ceil(24).max(DIGEST_BURNDOWN_MIN_DAYS);
+        burndown::burndown(store, days, now)
+            .inspect_err(|e| {
+                tracing::warn!(error = %e, "digest burndown failed");
+                sections_failed.push(BURNDOWN_TITLE.to_string());
+            })
+            .ok()
+    } else {
+        None
+    };
 
     DigestReport {
         report_id,
@@ -76,39 +236,43 @@ pub fn generate_digest(store: &VcStore, window_hours: u32) -> DigestReport {
         generated_at: now.to_rfc3339(),
         sections,
         summary,
+        missing_capabilities: caps.map(|caps| caps.missing()).unwrap_or_default(),
+        sections_failed,
+        burndown,
     }
 }
 
-fn build_fleet_section(store: &VcStore, summary: &mut DigestSummary) -> DigestSection {
+/// A `COUNT(*)`-style scalar as a `usize`
+fn count(store: &VcStore, sql: &str) -> Result<usize, QueryError> {
+    let value = store.query_scalar::<i64>(sql)?;
+    Ok(usize::try_from(value).unwrap_or(0))
+}
+
+fn build_fleet_section(
+    store: &VcStore,
+    summary: &mut DigestSummary,
+    window_hours: u32,
+) -> Result<DigestSection, QueryError> {
     let mut items = Vec::new();
 
     // Count machines
-    let machines: usize = store
-        .query_scalar::<i64>("SELECT COUNT(DISTINCT machine_id) FROM machines")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
+    let machines = count(store, "SELECT COUNT(DISTINCT machine_id) FROM machines")?;
     summary.total_machines = machines;
     items.push(format!("Total machines: {machines}"));
 
     // Health scores
-    let healthy: usize = store
-        .query_scalar::<i64>("SELECT COUNT(*) FROM health_summary WHERE overall_score >= 80")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
-    let degraded: usize = store
-        .query_scalar::<i64>(
-            "SELECT COUNT(*) FROM health_summary WHERE overall_score < 80 AND overall_score >= 50",
-        )
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
-    let critical: usize = store
-        .query_scalar::<i64>("SELECT COUNT(*) FROM health_summary WHERE overall_score < 50")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
+    let healthy = count(
+        store,
+        "SELECT COUNT(*) FROM health_summary WHERE overall_score >= 80",
+    )?;
+    let degraded = count(
+        store,
+        "SELECT COUNT(*) FROM health_summary WHERE overall_score < 80 AND overall_score >= 50",
+    )?;
+    let critical = count(
+        store,
+        "SELECT COUNT(*) FROM health_summary WHERE overall_score < 50",
+    )?;
 
     summary.machines_healthy = healthy;
     summary.machines_degraded = degraded + critical;
@@ -116,31 +280,82 @@ fn build_fleet_section(store: &VcStore, summary: &mut DigestSummary) -> DigestSe
     items.push(format!(
         "Healthy: {healthy}, Degraded: {degraded}, Critical: {critical}"
     ));
+    items.extend(health_change_item(store, window_hours)?);
 
-    DigestSection {
-        title: "Fleet Overview".to_string(),
-        items,
+    Ok(DigestSection::new(FLEET_TITLE, items))
+}
+
+/// Average fleet health over the window against the window before it, or
+/// why the two cannot be compared; `None` until both windows have scores
+fn health_change_item(store: &VcStore, window_hours: u32) -> Result<Option<String>, QueryError> {
+    let current_start = chrono::Utc::now() - chrono::Duration::hours(i64::from(window_hours));
+    let previous_start = current_start - chrono::Duration::hours(i64::from(window_hours));
+    let rows = store.query_json(&format!(
+        "SELECT CASE WHEN TRY_CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP) \
+         THEN 1 ELSE 0 END AS current, \
+         AVG(overall_score) AS avg_score, \
+         MIN(scoring_version) AS min_version, MAX(scoring_version) AS max_version \
+         FROM health_summary \
+         WHERE TRY_CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP) \
+         GROUP BY 1",
+        current_start.format("%Y-%m-%d %H:%M:%S"),
+        previous_start.format("%Y-%m-%d %H:%M:%S")
+    ))?;
+    let window = |current: i64| {
+        rows.iter()
+            .find(|row| row["current"].as_i64() == Some(current))
+    };
+    let (Some(current), Some(previous)) = (window(1), window(0)) else {
+        return Ok(None);
+    };
+
+    let versions: Vec<i64> = [current, previous]
+        .iter()
+        .flat_map(|row| [row["min_version"].as_i64(), row["max_version"].as_i64()])
+        .flatten()
+        .collect();
+    let (lowest, highest) = (versions.iter().min(), versions.iter().max());
+    if lowest != highest {
+        return Ok(Some(format!(
+            "Health change vs previous {window_hours}h not shown: scores span scoring \
+             versions {} to {}; run `vc health rescore --window {}h` to compare",
+            lowest.copied().unwrap_or_default(),
+            highest.copied().unwrap_or_default(),
+            u64::from(window_hours) * 2
+        )));
     }
+
+    let (Some(now), Some(before)) = (
+        current["avg_score"].as_f64(),
+        previous["avg_score"].as_f64(),
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(format!(
+        "Average health: {now:.2} ({:+.2} vs previous {window_hours}h)",
+        now - before
+    )))
 }
 
-fn build_alert_section(store: &VcStore, summary: &mut DigestSummary) -> DigestSection {
+fn build_alert_section(
+    store: &VcStore,
+    summary: &mut DigestSummary,
+    window_hours: u32,
+) -> Result<DigestSection, QueryError> {
     let mut items = Vec::new();
 
-    let open: usize = store
-        .query_scalar::<i64>("SELECT COUNT(*) FROM alert_history WHERE resolved_at IS NULL")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
+    let open = count(
+        store,
+        "SELECT COUNT(*) FROM alert_history WHERE resolved_at IS NULL",
+    )?;
     summary.open_alerts = open;
     items.push(format!("Open alerts: {open}"));
 
     // Recent alerts by severity
-    let by_severity = store
-        .query_json(
-            "SELECT severity, COUNT(*) as cnt FROM alert_history \
-             GROUP BY severity ORDER BY cnt DESC",
-        )
-        .unwrap_or_default();
+    let by_severity = store.query_json(
+        "SELECT severity, COUNT(*) as cnt FROM alert_history \
+         GROUP BY severity ORDER BY cnt DESC",
+    )?;
 
     for entry in &by_severity {
         if let (Some(sev), Some(cnt)) = (entry["severity"].as_str(), entry["cnt"].as_i64()) {
@@ -148,25 +363,45 @@ fn build_alert_section(store: &VcStore, summary: &mut DigestSummary) -> DigestSe
         }
     }
 
-    DigestSection {
-        title: "Alert Summary".to_string(),
-        items,
+    // What resolved the alerts closed in this window, per rule
+    let since = chrono::Utc::now() - chrono::Duration::hours(i64::from(window_hours));
+    let sources = store.alert_resolution_sources(Some(since))?;
+    let resolved: i64 = sources.iter().map(|source| source.resolved).sum();
+    summary.alerts_resolved = usize::try_from(resolved).unwrap_or(0);
+    items.push(format!("Resolved in window: {resolved}"));
+    let mut rules: Vec<&str> = Vec::new();
+    for source in &sources {
+        if !rules.contains(&source.rule_id.as_str()) {
+            rules.push(&source.rule_id);
+        }
+    }
+    for rule in rules {
+        let breakdown: Vec<String> = sources
+            .iter()
+            .filter(|source| source.rule_id == rule)
+            .map(|source| format!("{} {}", source.resolution_kind, source.resolved))
+            .collect();
+        items.push(format!("  {rule}: {}", breakdown.join(", ")));
     }
+
+    Ok(DigestSection::new(ALERTS_TITLE, items))
 }
 
-fn build_collector_section(store: &VcStore, summary: &mut DigestSummary) -> DigestSection {
+fn build_collector_section(
+    store: &VcStore,
+    summary: &mut DigestSummary,
+) -> Result<DigestSection, QueryError> {
     let mut items = Vec::new();
 
-    let healthy: usize = store
-        .query_scalar::<i64>("SELECT COUNT(*) FROM collector_health WHERE success = true AND (freshness_seconds IS NULL OR freshness_seconds <= 600)")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
-    let stale: usize = store
-        .query_scalar::<i64>("SELECT COUNT(*) FROM collector_health WHERE freshness_seconds > 600")
-        .ok()
-        .and_then(|value| usize::try_from(value).ok())
-        .unwrap_or(0);
+    let healthy = count(
+        store,
+        "SELECT COUNT(*) FROM collector_health WHERE success = true \
+         AND (freshness_seconds IS NULL OR freshness_seconds <= 600)",
+    )?;
+    let stale = count(
+        store,
+        "SELECT COUNT(*) FROM collector_health WHERE freshness_seconds > 600",
+    )?;
 
     summary.collectors_healthy = healthy;
     summary.collectors_stale = stale;
@@ -174,23 +409,116 @@ fn build_collector_section(store: &VcStore, summary: &mut DigestSummary) -> Dige
     items.push(format!("Healthy collectors: {healthy}"));
     items.push(format!("Stale collectors: {stale}"));
 
-    DigestSection {
-        title: "Collector Health".to_string(),
-        items,
+    Ok(DigestSection::new(COLLECTORS_TITLE, items))
+}
+
+fn build_session_section(store: &VcStore, window_hours: u32) -> Result<DigestSection, QueryError> {
+    let rates = QueryBuilder::new(store).session_success_rates(
+        SessionGroupBy::AgentRepo,
+        chrono::Duration::hours(i64::from(window_hours)),
+    )?;
+
+    let mut items: Vec<String> = rates
+        .iter()
+        .take(DIGEST_SESSION_GROUPS)
+        .map(crate::SessionSuccessRate::summary)
+        .collect();
+    if items.is_empty() {
+        items.push("No classified sessions in this window".to_string());
+    }
+
+    Ok(DigestSection::new(SESSIONS_TITLE, items))
+}
+
+fn build_repo_section(store: &VcStore, window_hours: u32) -> Result<DigestSection, QueryError> {
+    let activity = repos::repo_activity(
+        store,
+        chrono::Duration::hours(i64::from(window_hours)),
+        chrono::Duration::days(repos::DEFAULT_STALE_DAYS),
+        chrono::Utc::now(),
+    )?;
+
+    let mut items: Vec<String> = activity
+        .iter()
+        .filter_map(RepoActivity::stale_summary)
+        .map(|line| format!("Stale: {line}"))
+        .collect();
+    items.extend(
+        activity
+            .iter()
+            .filter(|repo| repo.stale_days.is_none())
+            .take(DIGEST_REPOS)
+            .map(RepoActivity::summary),
+    );
+    if items.is_empty() {
+        items.push("No repo activity in this window".to_string());
+    }
+
+    Ok(DigestSection::new(REPOS_TITLE, items))
+}
+
+fn build_sla_section(
+    store: &VcStore,
+    window_hours: u32,
+    incidents: &IncidentConfig,
+) -> Result<DigestSection, QueryError> {
+    let attained = sla::attainment(store, incidents, window_hours, chrono::Utc::now())?;
+
+    let mut items = Vec::new();
+    let mut severities: Vec<&str> = attained.iter().map(|a| a.severity.as_str()).collect();
+    severities.dedup();
+    // Most severe first
+    severities.sort_by_key(|severity| match *severity {
+        "critical" => 0,
+        "warning" => 1,
+        _ => 2,
+    });
+    for severity in severities {
+        let parts: Vec<String> = attained
+            .iter()
+            .filter(|a| a.severity == severity)
+            .map(attainment_part)
+            .collect();
+        items.push(format!("{severity}: {}", parts.join(", ")));
     }
+    if items.is_empty() {
+        items.push("No timed incidents in this window".to_string());
+    }
+
+    Ok(DigestSection::new(SLA_TITLE, items))
 }
 
-fn build_events_section(store: &VcStore, window_hours: u32) -> DigestSection {
+/// "ack 75% (3/4, 1 pending)"
+fn attainment_part(attained: &SlaAttainment) -> String {
+    let decided = attained.met + attained.breached;
+    let mut part = match attained.rate() {
+        Some(rate) => format!(
+            "{} {:.0}% ({}/{decided}",
+            attained.milestone.as_str(),
+            rate * 100.0,
+            attained.met
+        ),
+        None => format!("{} - (", attained.milestone.as_str()),
+    };
+    if attained.pending > 0 {
+        if decided > 0 {
+            part.push_str(", ");
+        }
+        let _ = write!(part, "{} pending", attained.pending);
+    }
+    part.push(')');
+    part
+}
+
+fn build_events_section(store: &VcStore, window_hours: u32) -> Result<DigestSection, QueryError> {
     let mut items = Vec::new();
 
     // Recent audit events
-    let events = store
-        .query_json(&format!(
-            "SELECT event_type, COUNT(*) as cnt FROM audit_events \
-             WHERE ts >= current_timestamp - INTERVAL '{window_hours} hours' \
-             GROUP BY event_type ORDER BY cnt DESC LIMIT 5"
-        ))
-        .unwrap_or_default();
+    let events = store.query_json(&format!(
+        "SELECT event_type, COUNT(*) as cnt FROM audit_events \
+         WHERE CAST(ts AS TIMESTAMP) >= current_timestamp - INTERVAL '{window_hours} hours' \
+         GROUP BY event_type ORDER BY cnt DESC LIMIT 5"
+    ))?;
 
     if events.is_empty() {
         items.push("No notable events in this window".to_string());
@@ -203,19 +531,57 @@ fn build_events_section(store: &VcStore, window_hours: u32) -> DigestSection {
         }
     }
 
-    DigestSection {
-        title: "Notable Events".to_string(),
-        items,
+    Ok(DigestSection::new(EVENTS_TITLE, items))
+}
+
+/// Annotations in the window, oldest first, e.g.
+/// "2026-10-16 14:00 deploy on machine:orko: release 1.42 (ci)"
+fn build_annotation_section(
+    store: &VcStore,
+    window_hours: u32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<DigestSection, QueryError> {
+    let filter = vc_store::AnnotationFilter {
+        since: Some(now - chrono::Duration::hours(i64::from(window_hours))),
+        limit: DIGEST_ANNOTATIONS + 1,
+        ..vc_store::AnnotationFilter::default()
+    };
+    let mut annotations = store.list_annotations(&filter)?;
+    let more = annotations.len() > DIGEST_ANNOTATIONS;
+    annotations.truncate(DIGEST_ANNOTATIONS);
+
+    let mut items: Vec<String> = annotations
+        .iter()
+        .rev()
+        .map(|annotation| {
+            let at = parse_timestamp(&annotation.ts).map_or_else(
+                || annotation.ts.clone(),
+                |ts| ts.format("%Y-%m-%d %H:%M").to_string(),
+            );
+            format!("{at} {}", annotation.summary())
+        })
+        .collect();
+    if more {
+        items.insert(
+            0,
+            format!("Showing the newest {DIGEST_ANNOTATIONS}; see `vc annotations list`"),
+        );
     }
+    if items.is_empty() {
+        items.push("No annotations in this window".to_string());
+    }
+
+    Ok(DigestSection::new(ANNOTATIONS_TITLE, items))
 }
 
 // ============================================================================
 // Markdown rendering
 // ============================================================================
 
-/// Render a digest report as Markdown
+/// Render a digest report as Markdown, with the generation time shown in
+/// the display zone of `time`
 #[must_use]
-pub fn render_markdown(report: &DigestReport) -> String {
+pub fn render_markdown(report: &DigestReport, time: &TimeFormatter) -> String {
     let mut md = String::new();
 
     let _ = write!(
@@ -223,7 +589,17 @@ pub fn render_markdown(report: &DigestReport) -> String {
         "# Vibe Cockpit Digest ({}h window)\n\n",
         report.window_hours
     );
-    let _ = write!(md, "Generated: {}\n\n", report.generated_at);
+    let generated = parse_timestamp(&report.generated_at)
+        .map_or_else(|| report.generated_at.clone(), |ts| time.absolute(ts));
+    let _ = write!(md, "Generated: {generated}\n\n");
+
+    if report.is_partial() {
+        let _ = write!(
+            md,
+            "> **Incomplete report:** data unavailable for {}.\n\n",
+            report.sections_failed.join(", ")
+        );
+    }
 
     // Summary box
     md.push_str("## Summary\n\n");
@@ -242,18 +618,115 @@ pub fn render_markdown(report: &DigestReport) -> String {
     );
     md.push('\n');
 
+    if let Some(burndown) = &report.burndown {
+        render_burndown(&mut md, burndown);
+    }
+
     // Sections
     for section in &report.sections {
         let _ = write!(md, "## {}\n\n", section.title);
+        if !section.ok {
+            let _ = writeln!(
+                md,
+                "> **Data unavailable:** {}",
+                section.error.as_deref().unwrap_or("unknown error")
+            );
+        }
         for item in &section.items {
             let _ = writeln!(md, "- {item}");
         }
         md.push('\n');
     }
 
+    if !report.missing_capabilities.is_empty() {
+        let _ = writeln!(
+            md,
+            "_Sections omitted; this store has no {} table(s)._",
+            report.missing_capabilities.join(", ")
+        );
+    }
+
     md
 }
 
+/// Sparklines of the daily counts, then one table row per day
+fn render_burndown(md: &mut String, burndown: &Burndown) {
+    let totals = &burndown.totals;
+    let _ = write!(
+        md,
+        "## {BURNDOWN_TITLE} ({} days)\n\n",
+        burndown.window_days
+    );
+    let _ = writeln!(
+        md,
+        "Alerts: {} fired, {} resolved (net {:+}). Incidents: {} opened, {} closed (net {:+}).",
+        totals.alerts_fired.total(),
+        totals.alerts_resolved.total(),
+        totals.alerts_net(),
+        totals.incidents_opened,
+        totals.incidents_closed,
+        totals.incidents_net()
+    );
+    if totals.days_unobserved > 0 {
+        let _ = writeln!(
+            md,
+            "\n_{} of {} days had nothing watching and are left blank._",
+            totals.days_unobserved, burndown.window_days
+        );
+    }
+    md.push('\n');
+
+    let label = |v: f64| format!("{v:.0}");
+    let _ = writeln!(
+        md,
+        "- Fired: `{}`",
+        sparkline::render_labeled(&burndown.fired_series(), SparkStyle::Unicode, label)
+    );
+    let _ = writeln!(
+        md,
+        "- Resolved: `{}`",
+        sparkline::render_labeled(&burndown.resolved_series(), SparkStyle::Unicode, label)
+    );
+    let _ = write!(
+        md,
+        "- Incidents opened: `{}`\n\n",
+        sparkline::render_labeled(
+            &burndown.incidents_opened_series(),
+            SparkStyle::Unicode,
+            label
+        )
+    );
+
+    md.push_str(
+        "| Day | Fired (crit/warn/info) | Resolved | Incidents opened | Incidents closed |\n",
+    );
+    md.push_str("| --- | --- | --- | --- | --- |\n");
+    for day in &burndown.days {
+        let _ = writeln!(md, "{}", burndown_row(day));
+    }
+    md.push('\n');
+}
+
+fn burndown_row(day: &BurndownDay) -> String {
+    let (Some(fired), Some(resolved), Some(opened), Some(closed)) = (
+        day.alerts_fired,
+        day.alerts_resolved,
+        day.incidents_opened,
+        day.incidents_closed,
+    ) else {
+        return format!("| {} | no data | no data | no data | no data |", day.day);
+    };
+    format!(
+        "| {} | {} ({}/{}/{}) | {} | {opened} | {closed} |",
+        day.day,
+        fired.total(),
+        fired.critical,
+        fired.warning,
+        fired.info,
+        resolved.total()
+    )
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -261,6 +734,7 @@ pub fn render_markdown(report: &DigestReport) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::timefmt::TimestampStyle;
 
     fn test_store() -> VcStore {
         VcStore::open_memory().unwrap()
@@ -273,16 +747,151 @@ mod tests {
     #[test]
     fn test_generate_digest_empty_db() {
         let store = test_store();
-        let report = generate_digest(&store, 24);
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
         assert_eq!(report.window_hours, 24);
         assert!(!report.report_id.is_empty());
         assert!(report.sections.len() >= 4);
+        assert!(!report.is_partial(), "{:?}", report.sections_failed);
+    }
+
+    #[test]
+    fn test_generate_digest_omits_sections_for_missing_tables() {
+        let store = test_store();
+        store
+            .execute_batch("DROP TABLE audit_events; DROP TABLE collector_health;")
+            .unwrap();
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        let titles: Vec<&str> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Fleet Overview",
+                "Alert Summary",
+                "Session Outcomes",
+                "Repo Activity",
+                "Incident SLA",
+                "Annotations"
+            ]
+        );
+        assert_eq!(
+            report.missing_capabilities,
+            vec!["audit_events", "collector_health"]
+        );
+        assert!(
+            render_markdown(&report, &TimeFormatter::default())
+                .contains("audit_events, collector_health")
+        );
+    }
+
+    #[test]
+    fn test_failed_section_is_flagged_and_rest_still_renders() {
+        let store = test_store();
+        // The table is still there, so the section is attempted, but its
+        // queries fail
+        store
+            .execute_batch("DROP TABLE alert_history; CREATE TABLE alert_history (id INTEGER);")
+            .unwrap();
+
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        assert!(report.is_partial());
+        // The burndown reads the same table, so it is unavailable too
+        assert_eq!(report.sections_failed, vec!["Alert Summary", "Burndown"]);
+        assert!(report.burndown.is_none());
+        let alerts = report
+            .sections
+            .iter()
+            .find(|s| s.title == "Alert Summary")
+            .unwrap();
+        assert!(!alerts.ok);
+        assert!(alerts.items.is_empty());
+        assert!(alerts.error.is_some());
+        assert!(
+            report
+                .sections
+                .iter()
+                .filter(|s| s.title != "Alert Summary")
+                .all(|s| s.ok && !s.items.is_empty())
+        );
+
+        let md = render_markdown(&report, &TimeFormatter::default());
+        assert!(
+            md.contains("> **Incomplete report:** data unavailable for Alert Summary, Burndown.")
+        );
+        assert!(md.contains("## Alert Summary\n\n> **Data unavailable:**"));
+        assert!(md.contains("## Fleet Overview\n\n- Total machines: 0"));
+        assert!(md.contains("## Notable Events"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["sections_failed"],
+            serde_json::json!(["Alert Summary", "Burndown"])
+        );
+    }
+
+    #[test]
+    fn test_health_change_is_not_compared_across_scoring_versions() {
+        let store = test_store();
+        let ago = |hours: i64| (chrono::Utc::now() - chrono::Duration::hours(hours)).to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO health_summary \
+                   (machine_id, collected_at, overall_score, scoring_version) \
+                 VALUES ('m1', '{}', 0.9, 1), ('m1', '{}', 0.6, 2);",
+                ago(30),
+                ago(2)
+            ))
+            .unwrap();
+        let fleet_items = |store: &VcStore| {
+            generate_digest(store, 24, &IncidentConfig::default())
+                .sections
+                .into_iter()
+                .find(|s| s.title == FLEET_TITLE)
+                .unwrap()
+                .items
+        };
+
+        let items = fleet_items(&store);
+        assert!(items.iter().all(|item| !item.starts_with("Average health")));
+        assert!(items.iter().any(|item| {
+            item.contains("scoring versions 1 to 2") && item.contains("rescore --window 48h")
+        }));
+
+        store
+            .execute_batch("UPDATE health_summary SET scoring_version = 2")
+            .unwrap();
+        let items = fleet_items(&store);
+        assert!(items.contains(&"Average health: 0.60 (-0.30 vs previous 24h)".to_string()));
+    }
+
+    #[test]
+    fn test_digest_lists_session_success_rates() {
+        let store = test_store();
+        let ended = chrono::Utc::now().to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO agent_sessions \
+                   (machine_id, session_id, program, repo_path, ended_at, outcome) \
+                 VALUES ('m1', 's1', 'claude-code', '/src/vc', '{ended}', 'success'), \
+                        ('m1', 's2', 'claude-code', '/src/vc', '{ended}', 'partial');"
+            ))
+            .unwrap();
+
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        let section = report
+            .sections
+            .iter()
+            .find(|s| s.title == "Session Outcomes")
+            .unwrap();
+        assert_eq!(
+            section.items,
+            vec!["claude-code on vc: 50% success over 2 session(s)".to_string()]
+        );
     }
 
     #[test]
     fn test_generate_digest_weekly() {
         let store = test_store();
-        let report = generate_digest(&store, 168);
+        let report = generate_digest(&store, 168, &IncidentConfig::default());
         assert_eq!(report.window_hours, 168);
         assert!(report.report_id.contains("168h"));
     }
@@ -297,7 +906,7 @@ mod tests {
     #[test]
     fn test_digest_report_serialization() {
         let store = test_store();
-        let report = generate_digest(&store, 24);
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
         let json = serde_json::to_string(&report).unwrap();
         let parsed: DigestReport = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.window_hours, 24);
@@ -311,7 +920,7 @@ mod tests {
     fn test_fleet_section() {
         let store = test_store();
         let mut summary = DigestSummary::default();
-        let section = build_fleet_section(&store, &mut summary);
+        let section = build_fleet_section(&store, &mut summary).unwrap();
         assert_eq!(section.title, "Fleet Overview");
         assert!(!section.items.is_empty());
     }
@@ -320,26 +929,131 @@ mod tests {
     fn test_alert_section() {
         let store = test_store();
         let mut summary = DigestSummary::default();
-        let section = build_alert_section(&store, &mut summary);
+        let section = build_alert_section(&store, &mut summary, 24).unwrap();
         assert_eq!(section.title, "Alert Summary");
     }
 
+    #[test]
+    fn test_alert_section_breaks_down_resolvers() {
+        let store = test_store();
+        let now = chrono::Utc::now().to_rfc3339();
+        let long_ago = (chrono::Utc::now() - chrono::Duration::days(3)).to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history \
+                 (id, rule_id, fired_at, resolved_at, resolution_kind, severity, title) VALUES \
+                 (901, 'disk-full', '{now}', '{now}', 'guardian_run', 'warning', 'd'), \
+                 (902, 'disk-full', '{now}', '{now}', 'manual', 'warning', 'd'), \
+                 (903, 'disk-full', '{now}', '{now}', 'guardian_run', 'warning', 'd'), \
+                 (904, 'disk-full', '{long_ago}', '{long_ago}', 'manual', 'warning', 'd')"
+            ))
+            .unwrap();
+        let mut summary = DigestSummary::default();
+        let section = build_alert_section(&store, &mut summary, 24).unwrap();
+        assert_eq!(summary.alerts_resolved, 3);
+        assert!(
+            section
+                .items
+                .contains(&"  disk-full: guardian_run 2, manual 1".to_string()),
+            "{:?}",
+            section.items
+        );
+    }
+
     #[test]
     fn test_collector_section() {
         let store = test_store();
         let mut summary = DigestSummary::default();
-        let section = build_collector_section(&store, &mut summary);
+        let section = build_collector_section(&store, &mut summary).unwrap();
         assert_eq!(section.title, "Collector Health");
     }
 
+    #[test]
+    fn test_repo_section() {
+        let store = test_store();
+        let section = build_repo_section(&store, 24).unwrap();
+        assert_eq!(section.title, "Repo Activity");
+        assert_eq!(section.items, vec!["No repo activity in this window"]);
+
+        let ended = (chrono::Utc::now() - chrono::Duration::days(4)).to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO repos (machine_id, repo_id, path, name) VALUES ('m1', 'r1', '/src/vc', 'vc'); \
+                 INSERT INTO repo_status_snapshots (machine_id, collected_at, repo_id, modified_count, untracked_count) \
+                   VALUES ('m1', '{ended}', 'r1', 2, 0); \
+                 INSERT INTO agent_sessions (machine_id, session_id, repo_path, started_at, ended_at) \
+                   VALUES ('m1', 's1', '/src/vc', '{ended}', '{ended}');"
+            ))
+            .unwrap();
+        let section = build_repo_section(&store, 24).unwrap();
+        assert_eq!(
+            section.items,
+            vec!["Stale: vc on m1: 2 dirty file(s) untouched for 4d"]
+        );
+    }
+
+    #[test]
+    fn test_sla_section() {
+        let store = test_store();
+        let config = IncidentConfig::default();
+        let section = build_sla_section(&store, 24, &config).unwrap();
+        assert_eq!(section.items, vec!["No timed incidents in this window"]);
+
+        store
+            .create_incident("inc-1", "Disk pressure", "critical", None)
+            .unwrap();
+        store
+            .add_incident_note("inc-1", Some("oncall"), "looking")
+            .unwrap();
+        let section = build_sla_section(&store, 24, &config).unwrap();
+        assert_eq!(
+            section.items,
+            vec!["critical: ack 100% (1/1), mitigate - (1 pending), resolve - (1 pending)"]
+        );
+    }
+
     #[test]
     fn test_events_section() {
         let store = test_store();
-        let section = build_events_section(&store, 24);
+        let section = build_events_section(&store, 24).unwrap();
         assert_eq!(section.title, "Notable Events");
         assert!(!section.items.is_empty());
     }
 
+    #[test]
+    fn test_annotation_section_lists_the_window_oldest_first() {
+        let store = test_store();
+        let actor = vc_store::ActorContext::cli(Some("ci-bot"));
+        let now = chrono::Utc::now();
+        for (hours_ago, message) in [(30, "too old"), (3, "release 1.42"), (1, "release 1.43")] {
+            store
+                .record_annotation(
+                    &vc_store::NewAnnotation {
+                        kind: "deploy".to_string(),
+                        scope: vc_store::AnnotationScope::Machine("orko".to_string()),
+                        ts: Some(now - chrono::Duration::hours(hours_ago)),
+                        message: message.to_string(),
+                        source: "ci".to_string(),
+                    },
+                    &actor,
+                )
+                .unwrap();
+        }
+
+        let section = build_annotation_section(&store, 24, now).unwrap();
+        assert_eq!(section.title, "Annotations");
+        assert_eq!(section.items.len(), 2, "{:?}", section.items);
+        assert!(
+            section.items[0].ends_with(" deploy on machine:orko: release 1.42 (ci)"),
+            "{:?}",
+            section.items
+        );
+        assert!(section.items[1].ends_with("release 1.43 (ci)"));
+
+        let empty = build_annotation_section(&test_store(), 24, now).unwrap();
+        assert_eq!(empty.items, vec!["No annotations in this window"]);
+    }
+
     // ========================================================================
     // Markdown rendering tests
     // ========================================================================
@@ -347,8 +1061,8 @@ mod tests {
     #[test]
     fn test_render_markdown() {
         let store = test_store();
-        let report = generate_digest(&store, 24);
-        let md = render_markdown(&report);
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        let md = render_markdown(&report, &TimeFormatter::default());
         assert!(md.contains("# Vibe Cockpit Digest"));
         assert!(md.contains("24h window"));
         assert!(md.contains("## Summary"));
@@ -359,17 +1073,67 @@ mod tests {
     #[test]
     fn test_render_markdown_has_table() {
         let store = test_store();
-        let report = generate_digest(&store, 24);
-        let md = render_markdown(&report);
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        let md = render_markdown(&report, &TimeFormatter::default());
         assert!(md.contains("| Metric | Value |"));
         assert!(md.contains("| Machines |"));
     }
 
+    #[test]
+    fn test_render_markdown_generated_in_display_zone() {
+        let store = test_store();
+        let mut report = generate_digest(&store, 24, &IncidentConfig::default());
+        report.generated_at = "2026-07-01T12:00:00+00:00".to_string();
+        let time = TimeFormatter::new(TimestampStyle::Relative, "Europe/Berlin".parse().unwrap());
+        let md = render_markdown(&report, &time);
+        assert!(md.contains("Generated: 2026-07-01 14:00:00 CEST"), "{md}");
+    }
+
+    #[test]
+    fn test_render_markdown_burndown_blanks_unwatched_days() {
+        let store = test_store();
+        let today = chrono::Utc::now();
+        let yesterday = today - chrono::Duration::days(1);
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title) VALUES \
+                 (1, 'disk', '{}', 'critical', 't'), (2, 'load', '{}', 'info', 't');
+                 INSERT INTO daemon_heartbeats (day, first_at, last_at) VALUES \
+                 ('{}', '{}', '{}');",
+                today.to_rfc3339(),
+                today.to_rfc3339(),
+                yesterday.format("%Y-%m-%d"),
+                yesterday.to_rfc3339(),
+                yesterday.to_rfc3339()
+            ))
+            .unwrap();
+
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
+        let burndown = report.burndown.as_ref().unwrap();
+        assert_eq!(burndown.window_days, DIGEST_BURNDOWN_MIN_DAYS);
+        assert_eq!(burndown.totals.days_observed, 2);
+
+        let md = render_markdown(&report, &TimeFormatter::default());
+        assert!(md.contains("## Burndown (7 days)"), "{md}");
+        assert!(md.contains("Alerts: 2 fired, 0 resolved (net +2)."), "{md}");
+        assert!(md.contains("_5 of 7 days had nothing watching"), "{md}");
+        assert!(md.contains(&format!(
+            "| {} | 0 (0/0/0) | 0 | 0 | 0 |",
+            yesterday.format("%Y-%m-%d")
+        )));
+        assert!(md.contains(&format!(
+            "| {} | 2 (1/0/1) | 0 | 0 | 0 |",
+            today.format("%Y-%m-%d")
+        )));
+        assert!(md.contains("| no data | no data | no data | no data |"));
+        assert!(md.contains("- Fired: `0 "), "{md}");
+    }
+
     #[test]
     fn test_render_markdown_weekly() {
         let store = test_store();
-        let report = generate_digest(&store, 168);
-        let md = render_markdown(&report);
+        let report = generate_digest(&store, 168, &IncidentConfig::default());
+        let md = render_markdown(&report, &TimeFormatter::default());
         assert!(md.contains("168h window"));
     }
 
@@ -380,9 +1144,9 @@ mod tests {
     #[test]
     fn test_store_digest_report() {
         let store = test_store();
-        let report = generate_digest(&store, 24);
+        let report = generate_digest(&store, 24, &IncidentConfig::default());
         let json = serde_json::to_string(&report.summary).unwrap();
-        let md = render_markdown(&report);
+        let md = render_markdown(&report, &TimeFormatter::default());
 
         store
             .insert_digest_report(&report.report_id, 24, &json, &md)
@@ -397,8 +1161,8 @@ mod tests {
         let store = test_store();
 
         // Generate two reports
-        let r1 = generate_digest(&store, 24);
-        let r2 = generate_digest(&store, 168);
+        let r1 = generate_digest(&store, 24, &IncidentConfig::default());
+        let r2 = generate_digest(&store, 168, &IncidentConfig::default());
 
         store
             .insert_digest_report(&r1.report_id, 24, "{}", "# daily")
@@ -417,12 +1181,16 @@ mod tests {
 
     #[test]
     fn test_digest_section_serialization() {
-        let section = DigestSection {
-            title: "Test".to_string(),
-            items: vec!["item1".to_string(), "item2".to_string()],
-        };
+        let section = DigestSection::new("Test", vec!["item1".to_string(), "item2".to_string()]);
         let json = serde_json::to_string(&section).unwrap();
         let parsed: DigestSection = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.items.len(), 2);
+        assert!(parsed.ok);
+
+        // Sections saved before failures were tracked read back as ok
+        let parsed: DigestSection =
+            serde_json::from_str(r#"{"title": "Old", "items": []}"#).unwrap();
+        assert!(parsed.ok);
+        assert!(parsed.error.is_none());
     }
 }
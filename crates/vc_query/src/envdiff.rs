@@ -0,0 +1,412 @@
+//! Machine environment snapshots and field-level diffs.
+//!
+//! "What changed on it?" is answered by reconstructing a machine's
+//! environment as of two points in time (or two machines as of one) and
+//! comparing field by field. A snapshot groups fields by category:
+//!
+//! - `agents`, `toolchain`, `tools`: the version in effect at the time,
+//!   from `tool_version_history`
+//! - `hardware`: core count and memory/swap totals from the newest
+//!   `sys_samples` (or `sys_fallback_samples`) row at or before the time
+//! - `platform`: hostname, OS and architecture from the machine registry,
+//!   which keeps no history, so these are always current values
+//!
+//! Changes that a drift event recorded are marked with its description, so
+//! `vc machines diff` and the web machine page show the same thing.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use vc_store::escape_sql_literal;
+
+use crate::timefmt::parse_timestamp;
+use crate::{QueryBuilder, QueryError};
+
+/// Tools that are coding agents
+const AGENT_TOOLS: &[&str] = &["claude-code", "codex", "gmi"];
+
+/// Language toolchains
+const TOOLCHAIN_TOOLS: &[&str] = &["cargo", "rustc", "node", "python3"];
+
+/// A machine's environment as of one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvSnapshot {
+    pub machine_id: String,
+    pub at: String,
+    /// Category → field → value
+    pub categories: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// One field that differs between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub category: String,
+    pub field: String,
+    /// `None` when the field is absent from the first snapshot
+    pub before: Option<String>,
+    /// `None` when the field is absent from the second snapshot
+    pub after: Option<String>,
+    /// Descriptions of drift events recorded for this field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drift: Vec<String>,
+}
+
+/// Field-level difference between two environment snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvDiff {
+    pub before: EnvSnapshot,
+    pub after: EnvSnapshot,
+    /// Ordered by category, then field
+    pub changes: Vec<FieldChange>,
+    /// Fields present in both with the same value
+    pub unchanged: usize,
+}
+
+impl EnvDiff {
+    /// Unified-diff style rendering grouped by category
+    #[must_use]
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "--- {} @ {}", self.before.machine_id, self.before.at);
+        let _ = writeln!(out, "+++ {} @ {}", self.after.machine_id, self.after.at);
+
+        let mut category = None;
+        for change in &self.changes {
+            if category != Some(&change.category) {
+                let _ = writeln!(out, "[{}]", change.category);
+                category = Some(&change.category);
+            }
+            if let Some(before) = &change.before {
+                let _ = writeln!(out, "- {} = {before}", change.field);
+            }
+            if let Some(after) = &change.after {
+                let _ = write!(out, "+ {} = {after}", change.field);
+                if !change.drift.is_empty() {
+                    let _ = write!(out, "  (drift: {})", change.drift.join("; "));
+                }
+                out.push('\n');
+            }
+        }
+        if self.changes.is_empty() {
+            out.push_str("no differences\n");
+        }
+        let _ = writeln!(out, "{} field(s) unchanged", self.unchanged);
+        out
+    }
+}
+
+/// Category a tool's version is reported under
+fn tool_category(tool: &str) -> &'static str {
+    if AGENT_TOOLS.contains(&tool) {
+        "agents"
+    } else if TOOLCHAIN_TOOLS.contains(&tool) {
+        "toolchain"
+    } else {
+        "tools"
+    }
+}
+
+/// JSON scalar as display text; `None` for null
+fn value_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Compare two snapshots field by field
+#[must_use]
+pub fn diff_snapshots(before: &EnvSnapshot, after: &EnvSnapshot) -> (Vec<FieldChange>, usize) {
+    let empty = BTreeMap::new();
+    let mut categories: Vec<&String> = before
+        .categories
+        .keys()
+        .chain(after.categories.keys())
+        .collect();
+    categories.sort();
+    categories.dedup();
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+    for category in categories {
+        let old = before.categories.get(category).unwrap_or(&empty);
+        let new = after.categories.get(category).unwrap_or(&empty);
+        let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+        fields.sort();
+        fields.dedup();
+        for field in fields {
+            let (was, is) = (old.get(field), new.get(field));
+            if was == is {
+                unchanged += 1;
+                continue;
+            }
+            changes.push(FieldChange {
+                category: category.clone(),
+                field: field.clone(),
+                before: was.cloned(),
+                after: is.cloned(),
+                drift: Vec::new(),
+            });
+        }
+    }
+    (changes, unchanged)
+}
+
+impl QueryBuilder<'_> {
+    /// Reconstruct a machine's environment as of `at`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] for an unknown machine, or
+    /// [`QueryError`] if a store query fails.
+    pub fn environment_snapshot(
+        &self,
+        machine_id: &str,
+        at: DateTime<Utc>,
+    ) -> Result<EnvSnapshot, QueryError> {
+        let id = escape_sql_literal(machine_id);
+        let at_text = at.to_rfc3339_opts(SecondsFormat::Micros, true);
+        let mut categories: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+        let machine = self
+            .store
+            .query_json(&format!(
+                "SELECT hostname, os_type, arch FROM machines WHERE machine_id = '{id}'"
+            ))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| QueryError::InvalidQuery(format!("Unknown machine: {machine_id}")))?;
+        let platform = categories.entry("platform".to_string()).or_default();
+        for field in ["hostname", "os_type", "arch"] {
+            if let Some(value) = value_text(&machine[field]) {
+                platform.insert(field.to_string(), value);
+            }
+        }
+
+        // Newest version first per tool, so the first one seen is in effect.
+        let versions = self.store.tool_version_history(machine_id, None, 1000)?;
+        for version in versions.iter().filter(|version| {
+            parse_timestamp(&version.first_seen_at).is_some_and(|seen| seen <= at)
+        }) {
+            categories
+                .entry(tool_category(&version.tool_name).to_string())
+                .or_default()
+                .entry(version.tool_name.clone())
+                .or_insert_with(|| version.tool_version.clone());
+        }
+
+        let hardware_sql = |table: &str, columns: &str| {
+            format!(
+                "SELECT {columns} FROM {table} \
+                 WHERE machine_id = '{id}' AND collected_at <= '{at_text}' \
+                 ORDER BY collected_at DESC LIMIT 1"
+            )
+        };
+        let sample = match self
+            .store
+            .query_json(&hardware_sql(
+                "sys_samples",
+                "core_count, mem_total_bytes, swap_total_bytes",
+            ))?
+            .into_iter()
+            .next()
+        {
+            Some(sample) => Some(sample),
+            None => self
+                .store
+                .query_json(&hardware_sql(
+                    "sys_fallback_samples",
+                    "mem_total_bytes, swap_total_bytes",
+                ))?
+                .into_iter()
+                .next(),
+        };
+        if let Some(serde_json::Value::Object(sample)) = sample {
+            let hardware = categories.entry("hardware".to_string()).or_default();
+            for (field, value) in &sample {
+                if let Some(value) = value_text(value) {
+                    hardware.insert(field.clone(), value);
+                }
+            }
+        }
+
+        categories.retain(|_, fields| !fields.is_empty());
+        Ok(EnvSnapshot {
+            machine_id: machine_id.to_string(),
+            at: at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            categories,
+        })
+    }
+
+    /// Diff `before_machine` as of `before_at` against `after_machine` as of
+    /// `after_at` (the same machine for "what changed", two machines for
+    /// "works on A, fails on B"). Changed fields are marked with drift
+    /// events either machine recorded for them after `drift_since` and up
+    /// to the later of the two times.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if either snapshot or the drift lookup fails.
+    pub fn environment_diff(
+        &self,
+        before_machine: &str,
+        before_at: DateTime<Utc>,
+        after_machine: &str,
+        after_at: DateTime<Utc>,
+        drift_since: DateTime<Utc>,
+    ) -> Result<EnvDiff, QueryError> {
+        let before = self.environment_snapshot(before_machine, before_at)?;
+        let after = self.environment_snapshot(after_machine, after_at)?;
+        let (mut changes, unchanged) = diff_snapshots(&before, &after);
+
+        let until = before_at.max(after_at);
+        let mut machines = vec![before_machine];
+        if after_machine != before_machine {
+            machines.push(after_machine);
+        }
+        let mut events = Vec::new();
+        for machine in machines {
+            events.extend(
+                self.store
+                    .list_drift_events(Some(machine), None, None, 1000)?
+                    .into_iter()
+                    .filter(|event| {
+                        event["detected_at"]
+                            .as_str()
+                            .and_then(parse_timestamp)
+                            .is_some_and(|at| at > drift_since && at <= until)
+                    }),
+            );
+        }
+        for change in &mut changes {
+            let versioned = format!("{}_version", change.field);
+            change.drift = events
+                .iter()
+                .filter(|event| {
+                    event["metric"]
+                        .as_str()
+                        .is_some_and(|metric| metric == change.field || metric == versioned)
+                })
+                .filter_map(|event| event["description"].as_str().map(str::to_string))
+                .collect();
+        }
+
+        Ok(EnvDiff {
+            before,
+            after,
+            changes,
+            unchanged,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vc_store::VcStore;
+
+    fn store_with_machines() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname, os_type, arch) \
+                 VALUES ('orko', 'orko', 'linux', 'x86_64'), \
+                        ('bender', 'bender', 'linux', 'aarch64');",
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn test_snapshot_takes_versions_in_effect() {
+        let store = store_with_machines();
+        store
+            .record_tool_version("orko", "claude-code", "1.0.3", "probe")
+            .unwrap();
+        let between = Utc::now();
+        store
+            .record_tool_version("orko", "claude-code", "1.0.7", "probe")
+            .unwrap();
+        store
+            .record_tool_version("orko", "rustc", "1.90.0", "probe")
+            .unwrap();
+
+        let qb = QueryBuilder::new(&store);
+        let then = qb.environment_snapshot("orko", between).unwrap();
+        assert_eq!(then.categories["agents"]["claude-code"], "1.0.3");
+        assert!(!then.categories.contains_key("toolchain"));
+        assert_eq!(then.categories["platform"]["arch"], "x86_64");
+
+        let now = qb.environment_snapshot("orko", Utc::now()).unwrap();
+        assert_eq!(now.categories["agents"]["claude-code"], "1.0.7");
+        assert_eq!(now.categories["toolchain"]["rustc"], "1.90.0");
+
+        assert!(qb.environment_snapshot("nope", Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_diff_over_time_marks_drift() {
+        let store = store_with_machines();
+        let start = Utc::now();
+        store
+            .record_tool_version("orko", "claude-code", "1.0.3", "probe")
+            .unwrap();
+        let from = Utc::now();
+        store
+            .record_tool_version("orko", "claude-code", "1.0.7", "probe")
+            .unwrap();
+        store
+            .record_tool_version("orko", "node", "22.1.0", "probe")
+            .unwrap();
+        let to = Utc::now();
+
+        let diff = QueryBuilder::new(&store)
+            .environment_diff("orko", from, "orko", to, from)
+            .unwrap();
+        assert_eq!(diff.changes.len(), 2);
+        let agent = &diff.changes[0];
+        assert_eq!(
+            (agent.category.as_str(), agent.field.as_str()),
+            ("agents", "claude-code")
+        );
+        assert_eq!(agent.before.as_deref(), Some("1.0.3"));
+        assert_eq!(agent.drift, vec!["claude-code_version: 1.0.3 → 1.0.7"]);
+        assert_eq!(diff.changes[1].before, None);
+        assert_eq!(diff.unchanged, 3);
+
+        let text = diff.render_text();
+        assert!(text.contains("[agents]\n- claude-code = 1.0.3\n+ claude-code = 1.0.7  (drift:"));
+        assert!(text.contains("[toolchain]\n+ node = 22.1.0\n"));
+
+        // Drift outside the window is not attributed.
+        let diff = QueryBuilder::new(&store)
+            .environment_diff("orko", start, "orko", from, start)
+            .unwrap();
+        assert!(diff.changes.iter().all(|change| change.drift.is_empty()));
+    }
+
+    #[test]
+    fn test_diff_across_machines() {
+        let store = store_with_machines();
+        store
+            .record_tool_version("orko", "codex", "0.40.0", "probe")
+            .unwrap();
+        store
+            .record_tool_version("bender", "codex", "0.38.2", "probe")
+            .unwrap();
+        let now = Utc::now();
+
+        let diff = QueryBuilder::new(&store)
+            .environment_diff("orko", now, "bender", now, now - chrono::Duration::days(7))
+            .unwrap();
+        let fields: Vec<&str> = diff
+            .changes
+            .iter()
+            .map(|change| change.field.as_str())
+            .collect();
+        assert_eq!(fields, vec!["codex", "arch", "hostname"]);
+        assert_eq!(diff.changes[0].after.as_deref(), Some("0.38.2"));
+    }
+}
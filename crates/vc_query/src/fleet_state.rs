@@ -0,0 +1,547 @@
+//! Desired fleet state and reconciliation
+//!
+//! A fleet file declares the agents machines should run, per machine or
+//! per tag:
+//!
+//! ```yaml
+//! machines:
+//!   orko:
+//!     - type: claude
+//!       count: 3
+//! tags:
+//!   gpu:
+//!     - type: codex
+//!       count: 1
+//!       config: { model: o3 }
+//! ```
+//!
+//! A machine's own entry for an agent type wins over its tags'. The file
+//! owns every machine it covers: an agent type not declared for one of
+//! them should not be running there. Observed counts come from each
+//! machine's latest ntm collection, plus the apply steps completed since,
+//! so applying the same file again before the collector catches up plans
+//! nothing.
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use vc_store::VcStore;
+
+use crate::QueryError;
+use crate::timefmt::parse_timestamp;
+
+/// How far back completed apply steps are counted as not yet collected
+const IN_FLIGHT_HOURS: i64 = 24;
+
+/// A fleet file as written
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleetSpec {
+    #[serde(default)]
+    pub machines: BTreeMap<String, Vec<AgentSpec>>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, Vec<AgentSpec>>,
+}
+
+/// Agents of one type a machine or tag should run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentSpec {
+    #[serde(rename = "type")]
+    pub agent_type: String,
+    pub count: u32,
+    /// Passed through to the spawn command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+/// Agents of one type one machine should run, after tags are expanded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DesiredAgents {
+    pub machine_id: String,
+    pub agent_type: String,
+    pub count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+/// A fleet file resolved against the machine registry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DesiredState {
+    /// Every machine the file covers, including those declared with no agents
+    pub machines: BTreeSet<String>,
+    pub agents: Vec<DesiredAgents>,
+}
+
+/// Where desired and observed counts of one agent type differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentCountDiff {
+    pub machine_id: String,
+    pub agent_type: String,
+    pub desired: u32,
+    pub observed: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Spawn,
+    Stop,
+}
+
+impl PlanAction {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Spawn => "spawn",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+/// One step of a reconciliation plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub machine_id: String,
+    pub agent_type: String,
+    pub action: PlanAction,
+    pub count: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+/// Differences between the last apply's desired state and what runs now
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FleetDrift {
+    pub apply_id: String,
+    pub applied_at: String,
+    pub source: Option<String>,
+    pub drift: Vec<AgentCountDiff>,
+}
+
+/// Observed agent counts by machine and agent type
+pub type ObservedAgents = BTreeMap<(String, String), u32>;
+
+impl FleetSpec {
+    /// Parse a fleet file; YAML, or JSON as a subset of it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] if the text is not a fleet file.
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        serde_yaml::from_str(text)
+            .map_err(|e| QueryError::InvalidQuery(format!("invalid fleet file: {e}")))
+    }
+
+    /// Expand tags over `machines` (id and tags of every registered machine).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] for a machine that is not
+    /// registered, an agent type listed twice for one machine or tag, or
+    /// two tags of one machine asking for different counts of a type the
+    /// machine does not declare itself.
+    pub fn resolve(&self, machines: &[(String, Vec<String>)]) -> Result<DesiredState, QueryError> {
+        let mut desired: BTreeMap<(String, String), (DesiredAgents, Option<&str>)> =
+            BTreeMap::new();
+        let mut covered = BTreeSet::new();
+
+        for (tag, agents) in &self.tags {
+            check_unique(tag, agents)?;
+            for (machine_id, _) in machines
+                .iter()
+                .filter(|(_, tags)| tags.iter().any(|t| t == tag))
+            {
+                covered.insert(machine_id.clone());
+                for agent in agents {
+                    let key = (machine_id.clone(), agent.agent_type.clone());
+                    if let Some((existing, Some(other))) = desired.get(&key)
+                        && existing.count != agent.count
+                    {
+                        return Err(QueryError::InvalidQuery(format!(
+                            "tags {other} and {tag} both set {} agents on {machine_id}; \
+                             declare them under machines.{machine_id}",
+                            agent.agent_type
+                        )));
+                    }
+                    desired.insert(key, (desired_agents(machine_id, agent), Some(tag)));
+                }
+            }
+        }
+
+        for (machine_id, agents) in &self.machines {
+            if !machines.iter().any(|(id, _)| id == machine_id) {
+                return Err(QueryError::InvalidQuery(format!(
+                    "unknown machine: {machine_id}"
+                )));
+            }
+            check_unique(machine_id, agents)?;
+            covered.insert(machine_id.clone());
+            for agent in agents {
+                desired.insert(
+                    (machine_id.clone(), agent.agent_type.clone()),
+                    (desired_agents(machine_id, agent), None),
+                );
+            }
+        }
+
+        Ok(DesiredState {
+            machines: covered,
+            agents: desired.into_values().map(|(agents, _)| agents).collect(),
+        })
+    }
+}
+
+fn desired_agents(machine_id: &str, agent: &AgentSpec) -> DesiredAgents {
+    DesiredAgents {
+        machine_id: machine_id.to_string(),
+        agent_type: agent.agent_type.clone(),
+        count: agent.count,
+        config: agent.config.clone(),
+    }
+}
+
+fn check_unique(owner: &str, agents: &[AgentSpec]) -> Result<(), QueryError> {
+    let mut seen = BTreeSet::new();
+    for agent in agents {
+        if !seen.insert(agent.agent_type.as_str()) {
+            return Err(QueryError::InvalidQuery(format!(
+                "{owner} lists agent type {} more than once",
+                agent.agent_type
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Id and tags of every registered machine
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if the query fails.
+pub fn fleet_machines(store: &VcStore) -> Result<Vec<(String, Vec<String>)>, QueryError> {
+    Ok(store
+        .query_json("SELECT machine_id, tags FROM machines ORDER BY machine_id")?
+        .into_iter()
+        .filter_map(|row| {
+            let machine_id = row["machine_id"].as_str()?.to_string();
+            let tags = row["tags"]
+                .as_str()
+                .and_then(|raw| serde_json::from_str::<Vec<String>>(raw).ok())
+                .unwrap_or_default();
+            Some((machine_id, tags))
+        })
+        .collect())
+}
+
+/// Agents running per machine and type: each machine's latest ntm
+/// collection, adjusted by apply steps completed after it
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if a query fails.
+pub fn observed_agents(store: &VcStore) -> Result<ObservedAgents, QueryError> {
+    let mut collected_at = BTreeMap::new();
+    for row in store.query_json(
+        "SELECT machine_id, MAX(collected_at) AS collected_at \
+         FROM ntm_activity_snapshot GROUP BY machine_id",
+    )? {
+        if let (Some(machine_id), Some(at)) = (
+            row["machine_id"].as_str(),
+            row["collected_at"].as_str().and_then(parse_timestamp),
+        ) {
+            collected_at.insert(machine_id.to_string(), at);
+        }
+    }
+
+    let mut observed = ObservedAgents::new();
+    for row in store.query_json(
+        "SELECT a.machine_id, COALESCE(a.agent_type, 'unknown') AS agent_type, \
+         COUNT(*) AS agents \
+         FROM ntm_agent_snapshot a \
+         JOIN (SELECT machine_id, MAX(collected_at) AS collected_at \
+               FROM ntm_activity_snapshot GROUP BY machine_id) l \
+           ON a.machine_id = l.machine_id AND a.collected_at = l.collected_at \
+         GROUP BY a.machine_id, COALESCE(a.agent_type, 'unknown')",
+    )? {
+        if let (Some(machine_id), Some(agent_type), Some(agents)) = (
+            row["machine_id"].as_str(),
+            row["agent_type"].as_str(),
+            row["agents"].as_u64(),
+        ) {
+            observed.insert(
+                (machine_id.to_string(), agent_type.to_string()),
+                u32::try_from(agents).unwrap_or(u32::MAX),
+            );
+        }
+    }
+
+    let since = (Utc::now() - Duration::hours(IN_FLIGHT_HOURS))
+        .to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+    for step in store.completed_fleet_apply_steps_since(&since)? {
+        let completed_at = step.completed_at.as_deref().and_then(parse_timestamp);
+        let collected = collected_at.get(&step.machine_id);
+        if let (Some(completed_at), Some(collected)) = (completed_at, collected)
+            && completed_at <= *collected
+        {
+            continue;
+        }
+        let count = observed
+            .entry((step.machine_id.clone(), step.agent_type.clone()))
+            .or_default();
+        let delta = u32::try_from(step.count).unwrap_or(0);
+        *count = match step.action.as_str() {
+            "stop" => count.saturating_sub(delta),
+            _ => count.saturating_add(delta),
+        };
+    }
+    Ok(observed)
+}
+
+/// Every agent type whose count differs from the desired one on a machine
+/// the desired state covers, ordered by machine then type
+#[must_use]
+pub fn diff(desired: &DesiredState, observed: &ObservedAgents) -> Vec<AgentCountDiff> {
+    let mut counts: BTreeMap<(String, String), (u32, u32)> = BTreeMap::new();
+    for agents in &desired.agents {
+        counts
+            .entry((agents.machine_id.clone(), agents.agent_type.clone()))
+            .or_default()
+            .0 = agents.count;
+    }
+    for ((machine_id, agent_type), count) in observed {
+        if desired.machines.contains(machine_id) {
+            counts
+                .entry((machine_id.clone(), agent_type.clone()))
+                .or_default()
+                .1 = *count;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, (desired, observed))| desired != observed)
+        .map(
+            |((machine_id, agent_type), (desired, observed))| AgentCountDiff {
+                machine_id,
+                agent_type,
+                desired,
+                observed,
+            },
+        )
+        .collect()
+}
+
+/// The spawns and stops that bring `observed` to `desired`: stops first,
+/// so capacity is freed before new agents start
+#[must_use]
+pub fn plan(desired: &DesiredState, observed: &ObservedAgents) -> Vec<PlanStep> {
+    let mut steps: Vec<PlanStep> = diff(desired, observed)
+        .into_iter()
+        .map(|d| {
+            let config = desired
+                .agents
+                .iter()
+                .find(|a| a.machine_id == d.machine_id && a.agent_type == d.agent_type)
+                .and_then(|a| a.config.clone());
+            if d.desired > d.observed {
+                PlanStep {
+                    machine_id: d.machine_id,
+                    agent_type: d.agent_type,
+                    action: PlanAction::Spawn,
+                    count: d.desired - d.observed,
+                    config,
+                }
+            } else {
+                PlanStep {
+                    machine_id: d.machine_id,
+                    agent_type: d.agent_type,
+                    action: PlanAction::Stop,
+                    count: d.observed - d.desired,
+                    config: None,
+                }
+            }
+        })
+        .collect();
+    steps.sort_by_key(|step| step.action == PlanAction::Spawn);
+    steps
+}
+
+/// Drift from the most recent apply's desired state; `None` before the
+/// first apply
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if a query fails or the stored desired state
+/// cannot be read.
+pub fn drift(store: &VcStore) -> Result<Option<FleetDrift>, QueryError> {
+    let Some(apply) = store.latest_fleet_apply()? else {
+        return Ok(None);
+    };
+    let desired: DesiredState = serde_json::from_str(&apply.desired_json)?;
+    Ok(Some(FleetDrift {
+        drift: diff(&desired, &observed_agents(store)?),
+        apply_id: apply.apply_id,
+        applied_at: apply.created_at,
+        source: apply.source,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLEET: &str = "
+machines:
+  orko:
+    - type: claude
+      count: 3
+  mac: []
+tags:
+  gpu:
+    - type: codex
+      count: 1
+      config: { model: o3 }
+    - type: claude
+      count: 1
+";
+
+    fn registry() -> Vec<(String, Vec<String>)> {
+        vec![
+            ("orko".to_string(), vec!["gpu".to_string()]),
+            ("mac".to_string(), vec![]),
+            ("gpu-2".to_string(), vec!["gpu".to_string()]),
+            ("spare".to_string(), vec![]),
+        ]
+    }
+
+    fn observed(entries: &[(&str, &str, u32)]) -> ObservedAgents {
+        entries
+            .iter()
+            .map(|(m, t, n)| ((m.to_string(), t.to_string()), *n))
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_expands_tags_and_machine_entries_win() {
+        let desired = FleetSpec::parse(FLEET)
+            .unwrap()
+            .resolve(&registry())
+            .unwrap();
+        assert_eq!(
+            desired.machines.iter().collect::<Vec<_>>(),
+            vec!["gpu-2", "mac", "orko"]
+        );
+        let count = |m: &str, t: &str| {
+            desired
+                .agents
+                .iter()
+                .find(|a| a.machine_id == m && a.agent_type == t)
+                .map(|a| a.count)
+        };
+        assert_eq!(count("orko", "claude"), Some(3));
+        assert_eq!(count("orko", "codex"), Some(1));
+        assert_eq!(count("gpu-2", "claude"), Some(1));
+
+        let err = FleetSpec::parse("machines:\n  ghost: []\n")
+            .unwrap()
+            .resolve(&registry())
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown machine: ghost"));
+        assert!(FleetSpec::parse("machine: {}").is_err());
+        let clash = "tags:\n  gpu: [{type: codex, count: 1}]\n  \
+                     big: [{type: codex, count: 2}]\n";
+        let machines = vec![(
+            "orko".to_string(),
+            vec!["gpu".to_string(), "big".to_string()],
+        )];
+        assert!(FleetSpec::parse(clash).unwrap().resolve(&machines).is_err());
+    }
+
+    #[test]
+    fn test_plan_stops_first_and_ignores_unmanaged_machines() {
+        let desired = FleetSpec::parse(FLEET)
+            .unwrap()
+            .resolve(&registry())
+            .unwrap();
+        let running = observed(&[
+            ("orko", "claude", 1),
+            ("orko", "codex", 1),
+            ("mac", "claude", 2),
+            ("spare", "claude", 5),
+        ]);
+        let steps = plan(&desired, &running);
+        let summary: Vec<_> = steps
+            .iter()
+            .map(|s| {
+                (
+                    s.action.as_str(),
+                    s.machine_id.as_str(),
+                    s.agent_type.as_str(),
+                    s.count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("stop", "mac", "claude", 2),
+                ("spawn", "gpu-2", "claude", 1),
+                ("spawn", "gpu-2", "codex", 1),
+                ("spawn", "orko", "claude", 2),
+            ]
+        );
+        assert_eq!(steps[2].config, Some(serde_json::json!({"model": "o3"})));
+
+        let converged = observed(&[
+            ("orko", "claude", 3),
+            ("orko", "codex", 1),
+            ("gpu-2", "claude", 1),
+            ("gpu-2", "codex", 1),
+        ]);
+        assert!(plan(&desired, &converged).is_empty());
+    }
+
+    #[test]
+    fn test_observed_counts_completed_steps_until_collected() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO ntm_activity_snapshot (machine_id, collected_at) VALUES \
+                 ('orko', '2026-01-01T00:00:00+00:00'), ('orko', '2026-01-02T00:00:00+00:00'); \
+                 INSERT INTO ntm_agent_snapshot \
+                 (machine_id, collected_at, session_name, pane_id, agent_type) VALUES \
+                 ('orko', '2026-01-01T00:00:00+00:00', 's', '1', 'claude'), \
+                 ('orko', '2026-01-01T00:00:00+00:00', 's', '2', 'claude'), \
+                 ('orko', '2026-01-02T00:00:00+00:00', 's', '1', 'claude')",
+            )
+            .unwrap();
+        assert_eq!(
+            observed_agents(&store).unwrap(),
+            observed(&[("orko", "claude", 1)])
+        );
+
+        let step = vc_store::FleetApplyStep {
+            apply_id: String::new(),
+            step: 0,
+            machine_id: "orko".to_string(),
+            agent_type: "claude".to_string(),
+            action: "spawn".to_string(),
+            count: 2,
+            config_json: None,
+            status: "pending".to_string(),
+            command_id: None,
+            error: None,
+            completed_at: None,
+        };
+        store
+            .create_fleet_apply("fa-1", None, "{}", &[step])
+            .unwrap();
+        store
+            .finish_fleet_apply_step("fa-1", 0, Ok("fc-1"))
+            .unwrap();
+        assert_eq!(
+            observed_agents(&store).unwrap(),
+            observed(&[("orko", "claude", 3)])
+        );
+    }
+}
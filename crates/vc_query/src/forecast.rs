@@ -0,0 +1,645 @@
+//! Disk forecasts
+//!
+//! Per machine and mount, used bytes from `sys_filesystems` are averaged
+//! into fixed buckets over a window, and a growth rate is fitted to those
+//! buckets:
+//!
+//! - a three-bucket running median drops single-bucket bursts
+//! - a fall of more than [`CLEANUP_FRACTION`] of the mount's size within
+//!   three buckets is a cleanup. The fit starts after the last one, and a
+//!   mount cleaned up [`CYCLIC_CLEANUPS`] or more times in the window is
+//!   cyclic: its usage is a sawtooth, so it gets a low confidence and no
+//!   date
+//! - the rate is the median of the rates between consecutive buckets, so a
+//!   sudden jump moves the level the projection starts from but not the
+//!   rate, and a gap between buckets is one rate over the gap's length
+//!
+//! Days until full is the free space at the latest bucket over that rate.
+//! Confidence grows with how much of the window the fit covers, how few of
+//! its buckets are missing, and how many bucket-to-bucket rates agree in
+//! sign with the fitted one.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vc_config::ForecastConfig;
+use vc_store::{VcStore, escape_sql_literal};
+
+use crate::QueryError;
+
+/// Fall, as a fraction of the mount's size, that counts as a cleanup
+pub const CLEANUP_FRACTION: f64 = 0.05;
+
+/// Cleanups in the window that make a mount cyclic
+pub const CYCLIC_CLEANUPS: usize = 2;
+
+/// Fewest buckets a rate is fitted to
+pub const MIN_BUCKETS: usize = 6;
+
+/// Confidence of a cyclic mount
+const CYCLIC_CONFIDENCE: f64 = 0.1;
+
+/// Growth per day, as a fraction of the mount's size, below which usage is flat
+const FLAT_FRACTION_PER_DAY: f64 = 0.0001;
+
+/// Buckets a cleanup may take
+const CLEANUP_SPAN: usize = 3;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// Where a mount's usage is heading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskTrend {
+    Filling,
+    Flat,
+    Shrinking,
+    /// Repeated cleanups; no date is projected
+    Cyclic,
+    /// Too few buckets since the last cleanup to fit a rate
+    InsufficientData,
+}
+
+impl DiskTrend {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Filling => "filling",
+            Self::Flat => "flat",
+            Self::Shrinking => "shrinking",
+            Self::Cyclic => "cyclic",
+            Self::InsufficientData => "insufficient_data",
+        }
+    }
+}
+
+/// What to forecast
+#[derive(Debug, Clone)]
+pub struct DiskForecastOptions {
+    /// Only this machine's mounts
+    pub machine: Option<String>,
+    /// History the rate is fitted to
+    pub window: chrono::Duration,
+    /// Length of one bucket
+    pub bucket: chrono::Duration,
+    /// Mounts forecast to fill within this are flagged
+    pub horizon: chrono::Duration,
+}
+
+impl Default for DiskForecastOptions {
+    fn default() -> Self {
+        Self::from_config(&ForecastConfig::default())
+    }
+}
+
+impl DiskForecastOptions {
+    #[must_use]
+    pub fn from_config(config: &ForecastConfig) -> Self {
+        let days = |d: u64| {
+            i64::try_from(d)
+                .ok()
+                .and_then(chrono::Duration::try_days)
+                .unwrap_or(chrono::Duration::MAX)
+        };
+        Self {
+            machine: None,
+            window: days(config.window_days),
+            bucket: i64::try_from(config.bucket_secs)
+                .ok()
+                .and_then(chrono::Duration::try_seconds)
+                .unwrap_or(chrono::Duration::MAX),
+            horizon: days(config.horizon_days),
+        }
+    }
+}
+
+/// Mean used bytes over one bucket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UsagePoint {
+    pub at: DateTime<Utc>,
+    pub used_bytes: f64,
+}
+
+/// A rate fitted to one mount's buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendFit {
+    pub trend: DiskTrend,
+    /// `None` when cyclic or short of data
+    pub bytes_per_sec: Option<f64>,
+    /// Used bytes in the latest bucket
+    pub level: f64,
+    /// 0.0 to 1.0
+    pub confidence: f64,
+    pub cleanups: usize,
+    /// Buckets the rate was fitted to
+    pub buckets: usize,
+}
+
+impl TrendFit {
+    /// Days until `total_bytes` are used, when filling
+    #[must_use]
+    pub fn days_until_full(&self, total_bytes: f64) -> Option<f64> {
+        if self.trend != DiskTrend::Filling {
+            return None;
+        }
+        let rate = self.bytes_per_sec?;
+        Some(((total_bytes - self.level).max(0.0) / rate / SECS_PER_DAY).max(0.0))
+    }
+}
+
+/// One mount's forecast
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountForecast {
+    pub machine_id: String,
+    pub mount: String,
+    pub total_bytes: i64,
+    /// In the latest bucket
+    pub used_bytes: i64,
+    pub usage_pct: f64,
+    pub trend: DiskTrend,
+    pub growth_bytes_per_day: Option<f64>,
+    pub days_until_full: Option<f64>,
+    pub full_at: Option<DateTime<Utc>>,
+    pub confidence: f64,
+    /// Cleanups seen in the window
+    pub cleanups: usize,
+    /// Buckets the rate was fitted to
+    pub buckets: usize,
+    /// Forecast to fill within the horizon
+    pub within_horizon: bool,
+}
+
+/// Forecasts for every mount, soonest to fill first, with fleet totals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskForecast {
+    pub generated_at: DateTime<Utc>,
+    pub window_days: i64,
+    pub horizon_days: i64,
+    pub total_bytes: i64,
+    pub used_bytes: i64,
+    /// Summed over the filling mounts
+    pub growth_bytes_per_day: f64,
+    /// Mounts forecast to fill within the horizon
+    pub filling_within_horizon: usize,
+    pub mounts: Vec<MountForecast>,
+}
+
+/// Median of `values`; `values` must not be empty
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        f64::midpoint(values[mid - 1], values[mid])
+    } else {
+        values[mid]
+    }
+}
+
+/// Three-bucket running median; the ends are kept as they are
+fn running_median(values: &[f64]) -> Vec<f64> {
+    let mut smoothed = values.to_vec();
+    for i in 1..values.len().saturating_sub(1) {
+        smoothed[i] = median(&mut [values[i - 1], values[i], values[i + 1]]);
+    }
+    smoothed
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn secs(duration: chrono::Duration) -> f64 {
+    // Exact up to 2^53 seconds, far past any window
+    #[allow(clippy::cast_precision_loss)]
+    let secs = duration.num_seconds() as f64;
+    secs
+}
+
+/// Fit a growth rate to a mount's buckets, oldest first
+#[must_use]
+pub fn fit_trend(
+    points: &[UsagePoint],
+    total_bytes: f64,
+    window: chrono::Duration,
+    bucket: chrono::Duration,
+) -> TrendFit {
+    let level = points.last().map_or(0.0, |p| p.used_bytes);
+    let values: Vec<f64> = points.iter().map(|p| p.used_bytes).collect();
+    let smoothed = running_median(&values);
+
+    // A cleanup is a fall from the highest of the few buckets before it;
+    // the buckets of one cleanup count once.
+    let drop = total_bytes * CLEANUP_FRACTION;
+    let mut cleanups = 0;
+    let mut start = 0;
+    for i in 1..smoothed.len() {
+        let from = i.saturating_sub(CLEANUP_SPAN).max(start);
+        let peak = smoothed[from..i]
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if smoothed[i] < peak - drop {
+            cleanups += 1;
+            start = i;
+        }
+    }
+
+    let unfit = |trend, confidence| TrendFit {
+        trend,
+        bytes_per_sec: None,
+        level,
+        confidence,
+        cleanups,
+        buckets: 0,
+    };
+    if cleanups >= CYCLIC_CLEANUPS {
+        return unfit(DiskTrend::Cyclic, CYCLIC_CONFIDENCE);
+    }
+    let segment = &points[start..];
+    let fitted = &smoothed[start..];
+    if segment.len() < MIN_BUCKETS {
+        return unfit(DiskTrend::InsufficientData, 0.0);
+    }
+
+    let mut rates: Vec<f64> = segment
+        .windows(2)
+        .zip(fitted.windows(2))
+        .filter_map(|(at, used)| {
+            let dt = secs(at[1].at - at[0].at);
+            (dt > 0.0).then(|| (used[1] - used[0]) / dt)
+        })
+        .collect();
+    if rates.is_empty() {
+        return unfit(DiskTrend::InsufficientData, 0.0);
+    }
+    let rate = median(&mut rates.clone());
+
+    let flat = total_bytes * FLAT_FRACTION_PER_DAY / SECS_PER_DAY;
+    let trend = if rate > flat {
+        DiskTrend::Filling
+    } else if rate < -flat {
+        DiskTrend::Shrinking
+    } else {
+        DiskTrend::Flat
+    };
+
+    let span = secs(segment[segment.len() - 1].at - segment[0].at);
+    let coverage = (span / secs(window)).clamp(0.0, 1.0);
+    let expected = span / secs(bucket).max(1.0) + 1.0;
+    #[allow(clippy::cast_precision_loss)]
+    let density = (segment.len() as f64 / expected).clamp(0.0, 1.0);
+    let agreeing = rates
+        .iter()
+        .filter(|r| match trend {
+            DiskTrend::Filling => **r >= 0.0,
+            DiskTrend::Shrinking => **r <= 0.0,
+            _ => r.abs() <= flat,
+        })
+        .count();
+    #[allow(clippy::cast_precision_loss)]
+    let agreement = agreeing as f64 / rates.len() as f64;
+
+    TrendFit {
+        trend,
+        bytes_per_sec: Some(rate),
+        level,
+        confidence: round2(0.4 * coverage + 0.2 * density + 0.4 * agreement),
+        cleanups,
+        buckets: segment.len(),
+    }
+}
+
+/// Forecast every mount with samples in the window before `now`
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if the store query fails.
+pub fn disk_forecast(
+    store: &VcStore,
+    options: &DiskForecastOptions,
+    now: DateTime<Utc>,
+) -> Result<DiskForecast, QueryError> {
+    let since = now
+        .checked_sub_signed(options.window)
+        .unwrap_or(DateTime::<Utc>::MIN_UTC)
+        .to_rfc3339_opts(SecondsFormat::Micros, true);
+    let bucket_secs = options.bucket.num_seconds().max(1);
+    let machine_filter = options
+        .machine
+        .as_deref()
+        .map(|m| format!("AND machine_id = '{}'", escape_sql_literal(m)))
+        .unwrap_or_default();
+    let sql = format!(
+        "SELECT machine_id, mount, \
+             CAST(FLOOR(epoch(CAST(collected_at AS TIMESTAMP)) / {bucket_secs}) AS BIGINT) \
+                 * {bucket_secs} AS bucket_start, \
+             AVG(used_bytes) AS used_bytes, MAX(total_bytes) AS total_bytes \
+         FROM sys_filesystems \
+         WHERE collected_at >= '{since}' AND used_bytes IS NOT NULL \
+             AND total_bytes > 0 {machine_filter} \
+         GROUP BY 1, 2, 3 ORDER BY 1, 2, 3"
+    );
+
+    let mut series: BTreeMap<(String, String), (Vec<UsagePoint>, f64)> = BTreeMap::new();
+    for row in store.query_json(&sql)? {
+        let (Some(machine_id), Some(mount), Some(start), Some(used), Some(total)) = (
+            row["machine_id"].as_str(),
+            row["mount"].as_str(),
+            row["bucket_start"].as_i64(),
+            row["used_bytes"].as_f64(),
+            row["total_bytes"].as_f64(),
+        ) else {
+            continue;
+        };
+        let Some(at) = DateTime::from_timestamp(start, 0) else {
+            continue;
+        };
+        let entry = series
+            .entry((machine_id.to_string(), mount.to_string()))
+            .or_default();
+        entry.0.push(UsagePoint {
+            at,
+            used_bytes: used,
+        });
+        // The latest bucket's size, in case the volume was resized
+        entry.1 = total;
+    }
+
+    let horizon_days = secs(options.horizon) / SECS_PER_DAY;
+    let mut mounts: Vec<MountForecast> = series
+        .into_iter()
+        .map(|((machine_id, mount), (points, total))| {
+            let fit = fit_trend(&points, total, options.window, options.bucket);
+            let days_until_full = fit.days_until_full(total);
+            let full_at = days_until_full.and_then(|days| {
+                // Whole seconds are plenty; far-off dates past chrono's
+                // range are left out
+                #[allow(clippy::cast_possible_truncation)]
+                let whole = (days * SECS_PER_DAY).min(1e15) as i64;
+                chrono::Duration::try_seconds(whole).and_then(|d| now.checked_add_signed(d))
+            });
+            #[allow(clippy::cast_possible_truncation)]
+            let as_bytes = |v: f64| v.round() as i64;
+            MountForecast {
+                usage_pct: round2(fit.level / total * 100.0),
+                total_bytes: as_bytes(total),
+                used_bytes: as_bytes(fit.level),
+                machine_id,
+                mount,
+                trend: fit.trend,
+                growth_bytes_per_day: fit.bytes_per_sec.map(|r| (r * SECS_PER_DAY).round()),
+                days_until_full: days_until_full.map(|d| (d * 10.0).round() / 10.0),
+                full_at,
+                confidence: fit.confidence,
+                cleanups: fit.cleanups,
+                buckets: fit.buckets,
+                within_horizon: days_until_full.is_some_and(|d| d <= horizon_days),
+            }
+        })
+        .collect();
+    mounts.sort_by(|a, b| {
+        match (a.days_until_full, b.days_until_full) {
+            (Some(x), Some(y)) => x.total_cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        }
+        .then_with(|| a.machine_id.cmp(&b.machine_id))
+        .then_with(|| a.mount.cmp(&b.mount))
+    });
+
+    Ok(DiskForecast {
+        generated_at: now,
+        window_days: options.window.num_days(),
+        horizon_days: options.horizon.num_days(),
+        total_bytes: mounts.iter().map(|m| m.total_bytes).sum(),
+        used_bytes: mounts.iter().map(|m| m.used_bytes).sum(),
+        growth_bytes_per_day: mounts
+            .iter()
+            .filter(|m| m.trend == DiskTrend::Filling)
+            .filter_map(|m| m.growth_bytes_per_day)
+            .sum(),
+        filling_within_horizon: mounts.iter().filter(|m| m.within_horizon).count(),
+        mounts,
+    })
+}
+
+/// Keep each mount's forecast in `disk_forecasts` for the
+/// `disk-forecast-full` alert rule. Returns the rows written.
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if the write fails.
+pub fn record_forecasts(store: &VcStore, forecast: &DiskForecast) -> Result<usize, QueryError> {
+    let computed_at = forecast
+        .generated_at
+        .to_rfc3339_opts(SecondsFormat::Micros, true);
+    let rows: Vec<serde_json::Value> = forecast
+        .mounts
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "machine_id": m.machine_id,
+                "mount": m.mount,
+                "total_bytes": m.total_bytes,
+                "used_bytes": m.used_bytes,
+                "growth_bytes_per_day": m.growth_bytes_per_day,
+                "days_until_full": m.days_until_full,
+                "full_at": m.full_at.map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                "confidence": m.confidence,
+                "trend": m.trend.as_str(),
+                "computed_at": computed_at,
+            })
+        })
+        .collect();
+    Ok(store.upsert_json("disk_forecasts", &rows, &["machine_id", "mount"])?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const TOTAL: f64 = 1000.0 * GIB;
+
+    fn hours(h: i64) -> chrono::Duration {
+        chrono::Duration::hours(h)
+    }
+
+    fn hour(h: i64) -> f64 {
+        f64::from(i32::try_from(h).unwrap())
+    }
+
+    /// Hourly buckets over `count` hours of `used(hour)` GiB, skipping the
+    /// hours in `missing`
+    fn series(count: i64, missing: &[i64], used: impl Fn(i64) -> f64) -> Vec<UsagePoint> {
+        let start = DateTime::parse_from_rfc3339("2026-10-02T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        (0..count)
+            .filter(|h| !missing.contains(h))
+            .map(|h| UsagePoint {
+                at: start + hours(h),
+                used_bytes: used(h) * GIB,
+            })
+            .collect()
+    }
+
+    fn fit(points: &[UsagePoint]) -> TrendFit {
+        fit_trend(points, TOTAL, chrono::Duration::days(14), hours(1))
+    }
+
+    fn gib_per_hour(fit: &TrendFit) -> f64 {
+        fit.bytes_per_sec.unwrap() * 3600.0 / GIB
+    }
+
+    #[test]
+    fn test_steady_growth_projects_days_until_full() {
+        // Ends at 500 GiB used, growing 1 GiB an hour: ~20.8 days left
+        let points = series(14 * 24, &[], |h| 165.0 + hour(h));
+        let fit = fit(&points);
+        assert_eq!(fit.trend, DiskTrend::Filling);
+        assert_eq!(fit.cleanups, 0);
+        let days = fit.days_until_full(TOTAL).unwrap();
+        assert!((days - 500.0 / 24.0).abs() < 0.01, "days={days}");
+        assert!(fit.confidence > 0.9, "confidence={}", fit.confidence);
+    }
+
+    #[test]
+    fn test_gaps_keep_the_rate_and_lower_confidence() {
+        let missing: Vec<i64> = (100..200).chain(250..260).collect();
+        let fit = fit(&series(14 * 24, &missing, |h| 165.0 + hour(h)));
+        assert_eq!(fit.trend, DiskTrend::Filling);
+        assert!((gib_per_hour(&fit) - 1.0).abs() < 1e-6);
+        assert!(fit.confidence < 0.95, "confidence={}", fit.confidence);
+    }
+
+    #[test]
+    fn test_bursts_and_a_sudden_jump_do_not_move_the_rate() {
+        let points = series(14 * 24, &[], |h| {
+            let mut used = 100.0 + 0.5 * hour(h);
+            // A one-hour 200 GiB burst, e.g. a build's scratch files
+            if h == 50 {
+                used += 200.0;
+            }
+            // A 150 GiB copy that stays
+            if h >= 200 {
+                used += 150.0;
+            }
+            used
+        });
+        let fit = fit(&points);
+        assert_eq!(fit.trend, DiskTrend::Filling);
+        assert_eq!(fit.cleanups, 0);
+        assert!((gib_per_hour(&fit) - 0.5).abs() < 1e-6);
+        // Projected from the level after the jump: 1000 - (250 + 167.5) GiB
+        // left at 12 GiB a day
+        let days = fit.days_until_full(TOTAL).unwrap();
+        assert!((days - 582.5 / 12.0).abs() < 0.01, "days={days}");
+    }
+
+    #[test]
+    fn test_one_cleanup_fits_what_came_after() {
+        let fit = fit(&series(14 * 24, &[], |h| {
+            if h < 100 {
+                500.0 + hour(h)
+            } else {
+                200.0 + 2.0 * hour(h - 100)
+            }
+        }));
+        assert_eq!(fit.cleanups, 1);
+        assert_eq!(fit.trend, DiskTrend::Filling);
+        assert_eq!(fit.buckets, 14 * 24 - 100);
+        assert!((gib_per_hour(&fit) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cleanup_cycles_report_low_confidence_and_no_date() {
+        // Fills 4 GiB an hour and is cleaned back to 300 GiB every two days
+        let fit = fit(&series(14 * 24, &[], |h| 300.0 + 4.0 * hour(h % 48)));
+        assert_eq!(fit.trend, DiskTrend::Cyclic);
+        assert!(fit.cleanups >= CYCLIC_CLEANUPS);
+        assert!(fit.confidence <= CYCLIC_CONFIDENCE);
+        assert_eq!(fit.days_until_full(TOTAL), None);
+    }
+
+    #[test]
+    fn test_flat_short_and_shrinking_series() {
+        let flat = fit(&series(48, &[], |_| 400.0));
+        assert_eq!(flat.trend, DiskTrend::Flat);
+        assert_eq!(flat.days_until_full(TOTAL), None);
+
+        let short = fit(&series(4, &[], |h| 400.0 + hour(h)));
+        assert_eq!(short.trend, DiskTrend::InsufficientData);
+        assert_eq!(short.days_until_full(TOTAL), None);
+
+        let shrinking = fit(&series(48, &[], |h| 400.0 - 0.1 * hour(h)));
+        assert_eq!(shrinking.trend, DiskTrend::Shrinking);
+        assert_eq!(shrinking.days_until_full(TOTAL), None);
+    }
+
+    #[test]
+    fn test_disk_forecast_buckets_samples_per_mount() {
+        const GIB_BYTES: i64 = 1024 * 1024 * 1024;
+        let store = VcStore::open_memory().unwrap();
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut rows = Vec::new();
+        for h in 0..48_i64 {
+            let hour_start = now - hours(48 - h);
+            // Two samples an hour on /data, filling 1 GiB an hour
+            for (minute, extra) in [(0, 0), (30, GIB_BYTES / 2)] {
+                rows.push(serde_json::json!({
+                    "machine_id": "orko",
+                    "collected_at": (hour_start + chrono::Duration::minutes(minute))
+                        .to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "mount": "/data",
+                    "total_bytes": 100 * GIB_BYTES,
+                    "used_bytes": (40 + h) * GIB_BYTES + extra,
+                    "usage_pct": 40.0 + hour(h),
+                }));
+            }
+            rows.push(serde_json::json!({
+                "machine_id": "sydney",
+                "collected_at": hour_start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                "mount": "/",
+                "total_bytes": 100 * GIB_BYTES,
+                "used_bytes": 10 * GIB_BYTES,
+                "usage_pct": 10.0,
+            }));
+        }
+        store.insert_json_batch("sys_filesystems", &rows).unwrap();
+
+        let forecast = disk_forecast(&store, &DiskForecastOptions::default(), now).unwrap();
+        assert_eq!(forecast.mounts.len(), 2);
+        let data = &forecast.mounts[0];
+        assert_eq!(
+            (data.machine_id.as_str(), data.mount.as_str()),
+            ("orko", "/data")
+        );
+        assert_eq!(data.trend, DiskTrend::Filling);
+        assert_eq!(data.buckets, 48);
+        // 87.25 GiB used and 1 GiB an hour: 12.75 GiB is about half a day
+        let days = data.days_until_full.unwrap();
+        assert!((days - 0.5).abs() < 0.1, "days={days}");
+        assert!(data.within_horizon);
+        assert_eq!(forecast.mounts[1].trend, DiskTrend::Flat);
+        assert_eq!(forecast.filling_within_horizon, 1);
+
+        let only = DiskForecastOptions {
+            machine: Some("sydney".to_string()),
+            ..DiskForecastOptions::default()
+        };
+        assert_eq!(disk_forecast(&store, &only, now).unwrap().mounts.len(), 1);
+
+        // One row per mount, replaced on every write
+        assert_eq!(record_forecasts(&store, &forecast).unwrap(), 2);
+        assert_eq!(record_forecasts(&store, &forecast).unwrap(), 2);
+        let stored = store
+            .query_json("SELECT trend FROM disk_forecasts ORDER BY machine_id")
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0]["trend"], "filling");
+    }
+}
@@ -1,13 +1,19 @@
 //! Query Guardrails - Safety constraints for SQL queries
 //!
 //! This module provides:
-//! - Query validation (read-only enforcement)
+//! - Query validation (single read-only statement, checked on tokens)
 //! - Safe query templates with parameter substitution
-//! - Runtime and row limits
+//! - Runtime and row limits, with execution deadlines that cancel the query
 //! - Query audit logging
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+use vc_store::sql_tokens::{self, Token, TokenKind};
+use vc_store::{StoreError, VcStore};
+
+use crate::QueryError;
 
 /// Query validation errors
 #[derive(Debug, Clone, Serialize)]
@@ -24,6 +30,20 @@ pub enum ValidationError {
     MissingParameter { param: String },
     /// Invalid parameter value
     InvalidParameter { param: String, reason: String },
+    /// Post-processing named a column the result does not have
+    UnknownColumn {
+        column: String,
+        available: Vec<String>,
+    },
+    /// Malformed or inapplicable post-processing aggregate
+    InvalidAggregate { reason: String },
+    /// Raw SQL contains a construct that is not allowed in a read-only query
+    RejectedConstruct {
+        construct: String,
+        line: usize,
+        column: usize,
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for ValidationError {
@@ -53,6 +73,27 @@ impl std::fmt::Display for ValidationError {
             Self::InvalidParameter { param, reason } => {
                 write!(f, "Invalid parameter '{param}': {reason}")
             }
+            Self::UnknownColumn { column, available } => {
+                write!(
+                    f,
+                    "Unknown column '{column}'. Available columns: {}",
+                    available.join(", ")
+                )
+            }
+            Self::InvalidAggregate { reason } => {
+                write!(f, "Invalid aggregate: {reason}")
+            }
+            Self::RejectedConstruct {
+                construct,
+                line,
+                column,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "Rejected {construct} at line {line}, column {column}: {reason}"
+                )
+            }
         }
     }
 }
@@ -64,8 +105,11 @@ impl std::error::Error for ValidationError {}
 pub struct GuardrailConfig {
     /// Maximum rows to return
     pub max_rows: usize,
-    /// Maximum query runtime in milliseconds
-    pub max_runtime_ms: u64,
+    /// Execution deadline for operator queries (CLI, web) in milliseconds
+    pub max_execution_ms: u64,
+    /// Execution deadline for agent-originated queries (MCP, NL) in
+    /// milliseconds
+    pub agent_max_execution_ms: u64,
     /// Maximum output size in bytes
     pub max_output_bytes: usize,
     /// Allow raw SQL (if false, only templates allowed)
@@ -76,13 +120,90 @@ impl Default for GuardrailConfig {
     fn default() -> Self {
         Self {
             max_rows: 10000,
-            max_runtime_ms: 30000,              // 30 seconds
+            max_execution_ms: 30_000,           // 30 seconds
+            agent_max_execution_ms: 5_000,      // 5 seconds
             max_output_bytes: 10 * 1024 * 1024, // 10 MB
             allow_raw_sql: true,
         }
     }
 }
 
+impl GuardrailConfig {
+    /// The guardrails set under `[query]`
+    #[must_use]
+    pub fn from_config(config: &vc_config::QueryConfig) -> Self {
+        Self {
+            max_rows: config.max_rows,
+            max_execution_ms: config.max_execution_ms,
+            agent_max_execution_ms: config.agent_max_execution_ms,
+            max_output_bytes: config.max_output_bytes,
+            allow_raw_sql: config.allow_raw_sql,
+        }
+    }
+
+    /// Execution deadline for a query issued by `origin`
+    #[must_use]
+    pub fn execution_timeout(&self, origin: QueryOrigin) -> Duration {
+        Duration::from_millis(match origin {
+            QueryOrigin::Operator => self.max_execution_ms,
+            QueryOrigin::Agent => self.agent_max_execution_ms,
+        })
+    }
+}
+
+/// Who issued a query; agents get the tighter execution budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryOrigin {
+    Operator,
+    Agent,
+}
+
+/// Stable short identifier for a query's shape: literals and numbers are
+/// masked and whitespace collapsed, so the same query with different values
+/// shares a fingerprint
+#[must_use]
+pub fn sql_fingerprint(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            while let Some(next) = chars.next() {
+                if next == '\'' && chars.peek() != Some(&'\'') {
+                    break;
+                }
+                if next == '\'' {
+                    chars.next();
+                }
+            }
+            normalized.push('?');
+        } else if c.is_ascii_digit()
+            && !normalized.ends_with(|p: char| p.is_alphanumeric() || p == '_')
+        {
+            while chars
+                .peek()
+                .is_some_and(|n| n.is_ascii_digit() || *n == '.')
+            {
+                chars.next();
+            }
+            normalized.push('?');
+        } else if c.is_whitespace() {
+            if !normalized.is_empty() && !normalized.ends_with(' ') {
+                normalized.push(' ');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    normalized
+        .trim_end()
+        .trim_end_matches(';')
+        .hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
 /// A safe query template with named parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueryTemplate {
@@ -298,6 +419,31 @@ impl QueryValidator {
         &self.templates
     }
 
+    /// Template listing shown by `vc query templates` and the web API,
+    /// sorted by name
+    #[must_use]
+    pub fn template_catalog(&self) -> Vec<vc_types::TemplateInfo> {
+        let mut templates: Vec<&QueryTemplate> = self.templates.values().collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        templates
+            .into_iter()
+            .map(|t| vc_types::TemplateInfo {
+                name: t.name.clone(),
+                description: t.description.clone(),
+                params: t
+                    .params
+                    .iter()
+                    .map(|p| vc_types::TemplateParamInfo {
+                        name: p.name.clone(),
+                        description: p.description.clone(),
+                        default: p.default.clone(),
+                    })
+                    .collect(),
+                agent_safe: t.agent_safe,
+            })
+            .collect()
+    }
+
     /// Validate a raw SQL query
     ///
     /// # Errors
@@ -313,73 +459,179 @@ impl QueryValidator {
         self.validate_readonly(sql)
     }
 
-    /// Check that a query is read-only (SELECT only)
+    /// Check that a query is a single read-only statement
+    ///
+    /// The query is tokenized so that string literals, quoted identifiers and
+    /// comments cannot hide or fake keywords. Allowed forms are `SELECT`,
+    /// `WITH ... SELECT`, `EXPLAIN [ANALYZE] SELECT`, `DESCRIBE` and `SHOW`;
+    /// a trailing `;` is fine but a second statement is not. Write and DDL
+    /// keywords, `PRAGMA`/`ATTACH`-style escape hatches and file-reading
+    /// table functions are rejected anywhere in the statement.
     ///
     /// # Errors
     ///
-    /// Returns [`ValidationError`] when a forbidden statement is detected or the query is not
-    /// `SELECT`/`WITH`.
+    /// Returns [`ValidationError::RejectedConstruct`] naming the offending
+    /// construct and its line and column.
     pub fn validate_readonly(&self, sql: &str) -> Result<(), ValidationError> {
-        // Replace whitespace and common boundary characters with spaces
-        let mut normalized = String::with_capacity(sql.len());
-        for c in sql.chars() {
-            if c.is_whitespace() || c == ';' || c == '(' || c == ')' || c == ',' || c == '=' {
-                normalized.push(' ');
-            } else {
-                normalized.push(c);
+        let reject = |token: &Token, construct: &str, reason: &str| {
+            let (line, column) = line_column(sql, token.offset);
+            ValidationError::RejectedConstruct {
+                construct: construct.to_string(),
+                line,
+                column,
+                reason: reason.to_string(),
             }
+        };
+
+        let tokens = tokenize(sql)?;
+        let end = tokens
+            .iter()
+            .position(|t| t.kind == TokenKind::Symbol(';'))
+            .unwrap_or(tokens.len());
+        if let Some(extra) = tokens[end..]
+            .iter()
+            .find(|t| t.kind != TokenKind::Symbol(';'))
+        {
+            return Err(reject(
+                extra,
+                "second statement",
+                "only a single statement is allowed",
+            ));
         }
-        let normalized = normalized.trim().to_uppercase();
-
-        let forbidden = [
-            "INSERT",
-            "UPDATE",
-            "DELETE",
-            "DROP",
-            "CREATE",
-            "ALTER",
-            "TRUNCATE",
-            "REPLACE",
-            "MERGE",
-            "UPSERT",
-            "GRANT",
-            "REVOKE",
-            "VACUUM",
-            "PRAGMA",
-            "ATTACH",
-            "DETACH",
-            "BEGIN",
-            "COMMIT",
-            "ROLLBACK",
-            "SAVEPOINT",
-            "EXECUTE",
-            "PREPARE",
-            "CALL",
-            "COPY",
-            "EXPORT",
-            "LOAD",
-            "INSTALL",
-        ];
+        let statement = &tokens[..end];
 
-        let words: Vec<&str> = normalized.split_whitespace().collect();
-        for (i, &word) in words.iter().enumerate() {
-            if forbidden.contains(&word) {
-                // Allow if it's explicitly used as an alias with 'AS'
-                if i > 0 && words[i - 1] == "AS" {
-                    continue;
+        let Some(first) = statement
+            .iter()
+            .position(|t| t.kind != TokenKind::Symbol('('))
+        else {
+            return Err(ValidationError::RejectedConstruct {
+                construct: "empty query".to_string(),
+                line: 1,
+                column: 1,
+                reason: "no statement to run".to_string(),
+            });
+        };
+        let leading = &statement[first];
+        match (leading.kind == TokenKind::Word).then_some(leading.text.as_str()) {
+            Some("SELECT" | "WITH" | "DESCRIBE" | "SHOW") => {}
+            Some("EXPLAIN") => {
+                let mut rest = statement[first + 1..].iter();
+                let mut next = rest.next();
+                if next.is_some_and(|t| t.is_word("ANALYZE")) {
+                    next = rest.next();
+                }
+                match next {
+                    Some(t) if t.is_word("SELECT") || t.is_word("WITH") => {}
+                    Some(t) => {
+                        return Err(reject(
+                            t,
+                            &format!("EXPLAIN {}", t.text),
+                            "EXPLAIN is only allowed for SELECT queries",
+                        ));
+                    }
+                    None => {
+                        return Err(reject(leading, "EXPLAIN", "EXPLAIN needs a SELECT query"));
+                    }
                 }
-                return Err(ValidationError::ForbiddenStatement {
-                    statement_type: word.to_string(),
-                });
+            }
+            _ => {
+                return Err(reject(
+                    leading,
+                    &leading.text,
+                    "query must start with SELECT, WITH, EXPLAIN, DESCRIBE or SHOW",
+                ));
             }
         }
 
-        // Ensure query is a SELECT or WITH ... SELECT
-        let sql_upper = sql.trim_start().to_uppercase();
-        if !sql_upper.starts_with("SELECT") && !sql_upper.starts_with("WITH") {
-            return Err(ValidationError::ForbiddenStatement {
-                statement_type: "non-SELECT".to_string(),
-            });
+        // Per parenthesis level, whether it is inside a FROM clause, where a
+        // comma starts another table
+        let mut in_from = vec![false];
+        let mut table_position = false;
+        for (i, token) in statement.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|j| &statement[j]);
+            let next = statement.get(i + 1);
+            let called = next.is_some_and(|t| t.kind == TokenKind::Symbol('('));
+            match token.kind {
+                TokenKind::Word => {
+                    // `AS delete` and `t.update` name columns, not statements
+                    let is_name =
+                        prev.is_some_and(|p| p.is_word("AS") || p.kind == TokenKind::Symbol('.'));
+                    let word = token.text.as_str();
+                    if WRITE_KEYWORDS.contains(&word) && !is_name && !(word == "REPLACE" && called)
+                    {
+                        return Err(reject(
+                            token,
+                            word,
+                            "write and DDL statements are not allowed",
+                        ));
+                    }
+                    if ESCAPE_KEYWORDS.contains(&word) && !is_name {
+                        return Err(reject(
+                            token,
+                            word,
+                            "database, extension and file commands are not allowed",
+                        ));
+                    }
+                }
+                // DuckDB reads a file named as a table whether it is written as
+                // a string or as a quoted identifier
+                TokenKind::Str | TokenKind::QuotedIdent
+                    if table_position
+                        && (token.kind == TokenKind::Str || names_file(&token.text)) =>
+                {
+                    return Err(reject(
+                        token,
+                        "file path in FROM",
+                        "querying files by path is not allowed",
+                    ));
+                }
+                _ => {}
+            }
+
+            // A quoted function name calls the same function
+            let function = match token.kind {
+                TokenKind::Word if called => Some(token.text.clone()),
+                TokenKind::QuotedIdent if called => Some(unquote(&token.text).to_uppercase()),
+                _ => None,
+            };
+            if let Some(function) = function
+                && (FILE_FUNCTIONS.contains(&function.as_str()) || function.starts_with("PRAGMA_"))
+            {
+                return Err(reject(
+                    token,
+                    &format!("{}()", function.to_lowercase()),
+                    "table functions that reach outside the database are not allowed",
+                ));
+            }
+
+            // Whether the next token names a table: it follows FROM, JOIN, a
+            // comma in a FROM list, or a parenthesis opened where a table goes
+            let depth = in_from.len() - 1;
+            table_position = match token.kind {
+                TokenKind::Word
+                    if (token.text == "FROM" && !prev.is_some_and(|p| p.is_word("DISTINCT")))
+                        || token.text == "JOIN" =>
+                {
+                    in_from[depth] = true;
+                    true
+                }
+                TokenKind::Word if FROM_CLAUSE_END.contains(&token.text.as_str()) => {
+                    in_from[depth] = false;
+                    false
+                }
+                TokenKind::Symbol(',') => in_from[depth],
+                TokenKind::Symbol('(') => {
+                    in_from.push(false);
+                    table_position
+                }
+                TokenKind::Symbol(')') => {
+                    if depth > 0 {
+                        in_from.pop();
+                    }
+                    false
+                }
+                _ => false,
+            };
         }
 
         Ok(())
@@ -504,12 +756,217 @@ impl QueryValidator {
     pub fn config(&self) -> &GuardrailConfig {
         &self.config
     }
+
+    /// Run an already-validated query under the execution deadline for
+    /// `origin`; the store interrupts it when the deadline passes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::Timeout`] with the elapsed time and
+    /// [`sql_fingerprint`] if the query was cancelled, or
+    /// [`QueryError::StoreError`] if it failed otherwise.
+    pub fn execute(
+        &self,
+        store: &VcStore,
+        sql: &str,
+        origin: QueryOrigin,
+    ) -> Result<Vec<serde_json::Value>, QueryError> {
+        let timeout = self.config.execution_timeout(origin);
+        match store.query_json_with_timeout(sql, timeout) {
+            Ok(rows) => Ok(rows),
+            Err(StoreError::Timeout(elapsed)) => {
+                let fingerprint = sql_fingerprint(sql);
+                tracing::warn!(?origin, ?elapsed, %fingerprint, "query cancelled at deadline");
+                Err(QueryError::Timeout {
+                    elapsed_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+                    fingerprint,
+                })
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Keywords that modify data or schema, or control transactions
+const WRITE_KEYWORDS: &[&str] = &[
+    "INSERT",
+    "UPDATE",
+    "DELETE",
+    "DROP",
+    "CREATE",
+    "ALTER",
+    "TRUNCATE",
+    "REPLACE",
+    "MERGE",
+    "UPSERT",
+    "GRANT",
+    "REVOKE",
+    "VACUUM",
+    "CHECKPOINT",
+    "BEGIN",
+    "COMMIT",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "EXECUTE",
+    "PREPARE",
+];
+
+/// Keywords that reach other databases, extensions, settings or files
+const ESCAPE_KEYWORDS: &[&str] = &[
+    "PRAGMA", "ATTACH", "DETACH", "LOAD", "INSTALL", "COPY", "EXPORT", "IMPORT", "CALL",
+];
+
+/// Table functions that read files, the environment or other databases
+const FILE_FUNCTIONS: &[&str] = &[
+    "READ_CSV",
+    "READ_CSV_AUTO",
+    "READ_JSON",
+    "READ_JSON_AUTO",
+    "READ_JSON_OBJECTS",
+    "READ_NDJSON",
+    "READ_NDJSON_AUTO",
+    "READ_PARQUET",
+    "PARQUET_SCAN",
+    "READ_TEXT",
+    "READ_BLOB",
+    "GLOB",
+    "READ_XLSX",
+    "SNIFF_CSV",
+    "PARQUET_METADATA",
+    "PARQUET_FILE_METADATA",
+    "PARQUET_KV_METADATA",
+    "PARQUET_SCHEMA",
+    "SQLITE_SCAN",
+    "POSTGRES_SCAN",
+    "MYSQL_SCAN",
+    "GETENV",
+];
+
+/// Keywords that end a FROM clause at their parenthesis level
+const FROM_CLAUSE_END: &[&str] = &[
+    "WHERE",
+    "GROUP",
+    "HAVING",
+    "QUALIFY",
+    "WINDOW",
+    "ORDER",
+    "LIMIT",
+    "OFFSET",
+    "UNION",
+    "EXCEPT",
+    "INTERSECT",
+    "SELECT",
+];
+
+/// A quoted identifier (quotes included) without its quotes, with doubled
+/// quotes inside undone
+fn unquote(quoted: &str) -> String {
+    let quote = &quoted[..1];
+    quoted[1..quoted.len() - 1].replace(&quote.repeat(2), quote)
+}
+
+/// Whether a quoted identifier (quotes included) looks like a file: it has a
+/// path separator or ends in a file extension such as `.csv`
+fn names_file(quoted: &str) -> bool {
+    let name = unquote(quoted);
+    name.contains(['/', '\\'])
+        || name.rsplit_once('.').is_some_and(|(stem, ext)| {
+            !stem.is_empty()
+                && ext.starts_with(|c: char| c.is_ascii_alphabetic())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// 1-based line and column of a byte offset
+fn line_column(sql: &str, offset: usize) -> (usize, usize) {
+    let before = &sql[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |s| s.chars().count()) + 1;
+    (line, column)
+}
+
+/// Tokenize a query, reporting where an unterminated literal or comment
+/// starts
+fn tokenize(sql: &str) -> Result<Vec<Token>, ValidationError> {
+    sql_tokens::tokenize(sql).map_err(|unterminated| {
+        let (line, column) = line_column(sql, unterminated.offset);
+        ValidationError::RejectedConstruct {
+            construct: unterminated.construct.to_string(),
+            line,
+            column,
+            reason: "never closed".to_string(),
+        }
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sql_fingerprint_masks_literals() {
+        let a = sql_fingerprint("SELECT * FROM machines WHERE machine_id = 'orko' LIMIT 10");
+        let b = sql_fingerprint("select *  from machines\nwhere machine_id = 'it''s' limit 500;");
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 16);
+        assert_ne!(
+            a,
+            sql_fingerprint("SELECT * FROM alerts WHERE machine_id = 'orko'")
+        );
+        assert_ne!(
+            sql_fingerprint("SELECT c1 FROM t"),
+            sql_fingerprint("SELECT c2 FROM t")
+        );
+    }
+
+    #[test]
+    fn test_execution_timeout_by_origin() {
+        let config = GuardrailConfig::default();
+        assert!(
+            config.execution_timeout(QueryOrigin::Agent)
+                < config.execution_timeout(QueryOrigin::Operator)
+        );
+    }
+
+    #[test]
+    fn test_execute_cancels_at_deadline() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch("CREATE TABLE slow_seed AS SELECT range AS x FROM range(3000);")
+            .unwrap();
+        let validator = QueryValidator::new(GuardrailConfig {
+            agent_max_execution_ms: 200,
+            ..GuardrailConfig::default()
+        });
+
+        let sql = "SELECT count(*) AS n FROM slow_seed a, slow_seed b, slow_seed c \
+                   WHERE (a.x * b.x + c.x) % 7 = 3";
+        let started = std::time::Instant::now();
+        let err = validator
+            .execute(&store, sql, QueryOrigin::Agent)
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        match err {
+            QueryError::Timeout {
+                elapsed_ms,
+                fingerprint,
+            } => {
+                assert!(elapsed_ms >= 200);
+                assert_eq!(fingerprint, sql_fingerprint(sql));
+            }
+            other => panic!("expected timeout, got {other}"),
+        }
+
+        let rows = validator
+            .execute(
+                &store,
+                "SELECT count(*) AS n FROM slow_seed",
+                QueryOrigin::Agent,
+            )
+            .unwrap();
+        assert_eq!(rows[0]["n"], 3000);
+    }
+
     #[test]
     fn test_validate_select() {
         let validator = QueryValidator::new(GuardrailConfig::default());
@@ -543,7 +1000,7 @@ mod tests {
         let result = validator.validate_readonly("INSERT INTO machines VALUES (1, 'test')");
         assert!(matches!(
             result,
-            Err(ValidationError::ForbiddenStatement { .. })
+            Err(ValidationError::RejectedConstruct { .. })
         ));
     }
 
@@ -553,7 +1010,7 @@ mod tests {
         let result = validator.validate_readonly("DELETE FROM machines WHERE id = 1");
         assert!(matches!(
             result,
-            Err(ValidationError::ForbiddenStatement { .. })
+            Err(ValidationError::RejectedConstruct { .. })
         ));
     }
 
@@ -563,10 +1020,155 @@ mod tests {
         let result = validator.validate_readonly("DROP TABLE machines");
         assert!(matches!(
             result,
-            Err(ValidationError::ForbiddenStatement { .. })
+            Err(ValidationError::RejectedConstruct { .. })
         ));
     }
 
+    /// Rejected construct name, or `None` when the query is accepted
+    fn rejection(sql: &str) -> Option<String> {
+        let validator = QueryValidator::new(GuardrailConfig::default());
+        match validator.validate_readonly(sql) {
+            Ok(()) => None,
+            Err(ValidationError::RejectedConstruct { construct, .. }) => Some(construct),
+            Err(other) => panic!("unexpected error for {sql:?}: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_accepted_queries() {
+        let accepted = [
+            "SELECT * FROM machines",
+            "SELECT * FROM machines;",
+            "SELECT * FROM machines ;  -- trailing comment",
+            "(SELECT 1)",
+            "WITH recent AS (SELECT * FROM machines) SELECT * FROM recent",
+            "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 5) \
+             SELECT * FROM n",
+            "EXPLAIN SELECT * FROM machines",
+            "EXPLAIN ANALYZE SELECT count(*) FROM sys_samples",
+            "explain with t AS (SELECT 1) SELECT * FROM t",
+            "DESCRIBE machines",
+            "SHOW TABLES",
+            "SELECT 'DELETE FROM machines; DROP TABLE x' AS note",
+            "SELECT \"update\", \"drop\" FROM events",
+            "SELECT * FROM \"machines\" JOIN \"alert_history\" USING (machine_id)",
+            "SELECT a, 'b' FROM machines, alerts WHERE x IN ('a', 'b') ORDER BY a, 'c'",
+            "SELECT * FROM machines WHERE a IS DISTINCT FROM 'x'",
+            "SELECT 1 AS delete",
+            "SELECT e.update FROM events e",
+            "SELECT replace(hostname, '-', '_') FROM machines",
+            "SELECT * FROM machines -- DELETE FROM machines",
+            "SELECT /* DROP TABLE machines */ * FROM machines",
+            "SELECT 'it''s; DROP TABLE x' FROM machines",
+            "SELECT $$; DELETE FROM x$$ AS body",
+            "SELECT $q$; DELETE FROM x$q$ AS body",
+            "SELECT E'it\\'s; DROP TABLE x' AS body",
+            "SELECT created_at, updated_at FROM incidents",
+        ];
+        for sql in accepted {
+            assert_eq!(rejection(sql), None, "should accept: {sql}");
+        }
+    }
+
+    #[test]
+    fn test_rejected_queries() {
+        let rejected = [
+            ("INSERT INTO machines VALUES (1, 'test')", "INSERT"),
+            ("SELECT 1; DELETE FROM machines", "second statement"),
+            ("SELECT 1;; SELECT 2", "second statement"),
+            ("SELECT 1 --\n; DELETE FROM machines", "second statement"),
+            ("WITH x AS (SELECT 1) DELETE FROM machines", "DELETE"),
+            (
+                "WITH x AS (DELETE FROM machines RETURNING *) SELECT * FROM x",
+                "DELETE",
+            ),
+            ("EXPLAIN DELETE FROM machines", "EXPLAIN DELETE"),
+            ("EXPLAIN", "EXPLAIN"),
+            ("SEL/**/ECT * FROM machines", "SEL"),
+            ("/* hi */ DROP TABLE machines", "DROP"),
+            ("-- SELECT\nDROP TABLE machines", "DROP"),
+            ("UPDATE machines SET enabled = false", "UPDATE"),
+            ("PRAGMA table_info('machines')", "PRAGMA"),
+            (
+                "SELECT * FROM pragma_table_info('machines')",
+                "pragma_table_info()",
+            ),
+            ("ATTACH 'other.db' AS other", "ATTACH"),
+            ("SELECT 1 FROM machines WHERE 1 = 1 AND (ATTACH)", "ATTACH"),
+            ("COPY (SELECT * FROM machines) TO '/tmp/out.csv'", "COPY"),
+            ("SELECT * FROM read_csv('/etc/passwd')", "read_csv()"),
+            ("SELECT * FROM \"read_csv\"('/etc/passwd')", "read_csv()"),
+            (
+                "SELECT * FROM \"READ_CSV_AUTO\"('/etc/passwd')",
+                "read_csv_auto()",
+            ),
+            ("SELECT * FROM read_xlsx('/tmp/a.xlsx')", "read_xlsx()"),
+            ("SELECT * FROM sniff_csv('/etc/passwd')", "sniff_csv()"),
+            (
+                "SELECT * FROM parquet_metadata('x.parquet')",
+                "parquet_metadata()",
+            ),
+            (
+                "SELECT * FROM parquet_schema('x.parquet')",
+                "parquet_schema()",
+            ),
+            ("SELECT * FROM '/etc/passwd'", "file path in FROM"),
+            (
+                "SELECT * FROM machines JOIN 'x.parquet' USING (id)",
+                "file path in FROM",
+            ),
+            ("SELECT * FROM \"/home/u/secrets.csv\"", "file path in FROM"),
+            (
+                "SELECT * FROM machines JOIN \"x.parquet\" USING (id)",
+                "file path in FROM",
+            ),
+            ("SELECT * FROM machines, '/etc/passwd'", "file path in FROM"),
+            ("SELECT * FROM machines, \"data.csv\"", "file path in FROM"),
+            (
+                "SELECT * FROM machines m JOIN alerts a ON m.id = a.id, 'x.csv'",
+                "file path in FROM",
+            ),
+            (
+                "SELECT * FROM (SELECT 1) AS t, '/etc/passwd'",
+                "file path in FROM",
+            ),
+            ("SELECT * FROM ('/etc/passwd')", "file path in FROM"),
+            ("SELECT getenv('HOME')", "getenv()"),
+            ("SET memory_limit = '1GB'", "SET"),
+            ("SELECT 'unterminated", "quoted text"),
+            ("SELECT 1 /* never closed", "comment"),
+            ("", "empty query"),
+            ("  ;  ", "empty query"),
+        ];
+        for (sql, construct) in rejected {
+            assert_eq!(
+                rejection(sql).as_deref(),
+                Some(construct),
+                "should reject: {sql}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejection_reports_position() {
+        let validator = QueryValidator::new(GuardrailConfig::default());
+        let err = validator
+            .validate_readonly("SELECT *\nFROM machines;\n  DELETE FROM machines")
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::RejectedConstruct {
+                line: 3,
+                column: 3,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Rejected second statement at line 3, column 3: only a single statement is allowed"
+        );
+    }
+
     #[test]
     fn test_expand_template() {
         let validator = QueryValidator::new(GuardrailConfig::default());
@@ -643,6 +1245,17 @@ mod tests {
         assert!(!sql.contains("= ''; DROP"));
     }
 
+    #[test]
+    fn test_template_catalog_sorted() {
+        let validator = QueryValidator::new(GuardrailConfig::default());
+        let catalog = validator.template_catalog();
+        assert_eq!(catalog.len(), validator.templates().len());
+        let names: Vec<&str> = catalog.iter().map(|t| t.name.as_str()).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+    }
+
     #[test]
     fn test_templates_registered() {
         let validator = QueryValidator::new(GuardrailConfig::default());
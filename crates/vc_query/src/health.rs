@@ -19,10 +19,23 @@
 //! Error in `DuckDB`, so **no** SQL in this module does time arithmetic: the
 //! SQL only ever compares `collected_at` against `collected_at` (same type),
 //! and all age/window math is done in Rust after parsing the text timestamp.
+//!
+//! ## Scoring versions
+//!
+//! Every summary records the [`HEALTH_SCORING_VERSION`] it was computed
+//! under. [`QueryBuilder::rescore_health_since`] brings older history up to
+//! the current version after an upgrade so trends compare like with like.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
 
 use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 
-use crate::{HealthFactor, HealthScore, HealthWeights, QueryBuilder, QueryError, classify_metric};
+use crate::{
+    HEALTH_SCORING_VERSION, HealthFactor, HealthRescore, HealthScore, HealthWeights, QueryBuilder,
+    QueryError, Severity, classify_metric, compute_overall_score, stored_health_factor,
+    worst_factor_id,
+};
 
 /// CPU utilisation percentage that counts as a warning.
 const CPU_WARNING_PCT: f64 = 75.0;
@@ -61,6 +74,19 @@ const COLLECTOR_SUCCESS_WARNING_PCT: f64 = 95.0;
 /// Collector success rate (percent) below which we go critical.
 const COLLECTOR_SUCCESS_CRITICAL_PCT: f64 = 60.0;
 
+/// Share (percent) of classified collector failures left as `unknown` at
+/// which we warn: the error taxonomy is drifting from what collectors report.
+const UNKNOWN_CAUSE_WARNING_PCT: f64 = 25.0;
+/// Share (percent) of `unknown` failures at which we go critical.
+const UNKNOWN_CAUSE_CRITICAL_PCT: f64 = 50.0;
+
+/// Failed HTTP check results in a row that make a check count as down,
+/// rather than a blip.
+const HTTP_CHECK_REPEATED_FAILURES: i64 = 3;
+/// Age (seconds) past which an HTTP check's latest result is ignored: the
+/// check has most likely been removed from the config.
+const HTTP_CHECK_MAX_AGE_SECS: i64 = 3600;
+
 /// Window (seconds) over which the collector success rate is computed.
 const COLLECTOR_WINDOW_SECS: i64 = 3600;
 /// Cap on how many `collector_health` rows we pull per machine.
@@ -119,6 +145,8 @@ fn build_factor(weights: &HealthWeights, spec: FactorSpec<'_>) -> HealthFactor {
         weight: weights.weight_for(spec.factor_id),
         severity,
         details: spec.details,
+        baseline_score: None,
+        trend: None,
     }
 }
 
@@ -166,14 +194,15 @@ impl QueryBuilder<'_> {
     /// Compute health factors for a machine from its current telemetry.
     ///
     /// Emits, when the underlying telemetry exists: `sys_cpu`, `sys_memory`,
-    /// `sys_load`, `sys_disk`, `rate_limit`, `process_health`. `data_freshness`
+    /// `sys_load`, `sys_disk`, `rate_limit`, `process_health`,
+    /// `service_health`, `http_checks`. `data_freshness`
     /// is always emitted so that a machine with no telemetry at all scores
     /// badly instead of silently scoring "perfectly healthy".
     ///
     /// # Errors
     ///
     /// Returns [`QueryError`] if any underlying store query fails.
-    // Seven factors, each read from a different table and classified the same
+    // Eight factors, each read from a different table and classified the same
     // way. Splitting it would scatter one linear computation across seven
     // one-caller helpers without making any of it easier to follow.
     #[allow(clippy::too_many_lines)]
@@ -310,6 +339,97 @@ impl QueryBuilder<'_> {
             ));
         }
 
+        if let Some(unknown_pct) = collectors.unknown_cause_pct_in_window {
+            factors.push(build_factor(
+                &weights,
+                FactorSpec {
+                    factor_id: "error_classification",
+                    name: "Unclassified collector failures",
+                    value: unknown_pct,
+                    warning: UNKNOWN_CAUSE_WARNING_PCT,
+                    critical: UNKNOWN_CAUSE_CRITICAL_PCT,
+                    inverted: false,
+                    details: format!(
+                        "{}/{} collector failures in the last hour had an unknown cause",
+                        collectors.unknown_failures_in_window,
+                        collectors.classified_failures_in_window
+                    ),
+                },
+            ));
+        }
+
+        // A missing required service is a warning, never critical: the machine
+        // still works, it just is not doing everything it should.
+        let (required, missing) = self.required_service_status(machine_id)?;
+        if required > 0 {
+            let (score, severity, details) = if missing.is_empty() {
+                (
+                    1.0,
+                    Severity::Healthy,
+                    format!("all {required} required service(s) running"),
+                )
+            } else {
+                (
+                    0.5,
+                    Severity::Warning,
+                    format!(
+                        "{}/{required} required service(s) not running: {}",
+                        missing.len(),
+                        missing.join(", ")
+                    ),
+                )
+            };
+            factors.push(HealthFactor {
+                factor_id: "service_health".to_string(),
+                name: "Required services".to_string(),
+                score,
+                weight: weights.weight_for("service_health"),
+                severity,
+                details,
+                baseline_score: None,
+                trend: None,
+            });
+        }
+
+        // One failed request is a blip; only checks failing repeatedly count,
+        // and the machine is only critical when every endpoint is down.
+        let (checked, down) = self.http_check_status(machine_id)?;
+        if checked > 0 {
+            let (score, severity, details) = if down.is_empty() {
+                (
+                    1.0,
+                    Severity::Healthy,
+                    format!("all {checked} http check(s) passing"),
+                )
+            } else {
+                let (score, severity) = if down.len() == checked {
+                    (0.0, Severity::Critical)
+                } else {
+                    (0.5, Severity::Warning)
+                };
+                (
+                    score,
+                    severity,
+                    format!(
+                        "{}/{checked} http check(s) failing {HTTP_CHECK_REPEATED_FAILURES}+ \
+                         times in a row: {}",
+                        down.len(),
+                        down.join(", ")
+                    ),
+                )
+            };
+            factors.push(HealthFactor {
+                factor_id: "http_checks".to_string(),
+                name: "HTTP checks".to_string(),
+                score,
+                weight: weights.weight_for("http_checks"),
+                severity,
+                details,
+                baseline_score: None,
+                trend: None,
+            });
+        }
+
         Ok(factors)
     }
 
@@ -328,14 +448,17 @@ impl QueryBuilder<'_> {
     ///
     /// This is the entry point for the daemon tick. Machines are processed in a
     /// stable order and one failing machine aborts the tick (the store is the
-    /// same for all of them, so a failure is a store-level failure).
+    /// same for all of them, so a failure is a store-level failure). Machines
+    /// in maintenance are skipped, so their last score stands instead of
+    /// decaying while nothing is collected from them.
     ///
     /// # Errors
     ///
     /// Returns [`QueryError`] if telemetry reads or the health writes fail.
     pub fn compute_and_persist_health_all(&self) -> Result<Vec<HealthScore>, QueryError> {
         let sql = "SELECT machine_id FROM machines \
-                   WHERE enabled IS NULL OR enabled <> 0 \
+                   WHERE (enabled IS NULL OR enabled <> 0) \
+                   AND COALESCE(status, '') <> 'maintenance' \
                    ORDER BY machine_id";
         let rows = self.store.query_json(sql)?;
 
@@ -396,6 +519,48 @@ impl QueryBuilder<'_> {
         })
     }
 
+    /// Number of required services checked on the machine, and the names of
+    /// those not running, from `machine_services`.
+    fn required_service_status(
+        &self,
+        machine_id: &str,
+    ) -> Result<(usize, Vec<String>), QueryError> {
+        let escaped = vc_store::escape_sql_literal(machine_id);
+        let sql = format!(
+            "SELECT service_name, running FROM machine_services \
+             WHERE machine_id = '{escaped}' AND required <> 0 \
+             ORDER BY service_name"
+        );
+        let rows = self.store.query_json(&sql)?;
+        let missing = rows
+            .iter()
+            .filter(|row| row["running"].as_i64() == Some(0))
+            .filter_map(|row| row["service_name"].as_str().map(ToString::to_string))
+            .collect();
+        Ok((rows.len(), missing))
+    }
+
+    /// Number of HTTP checks with a recent result on the machine, and the
+    /// names of those failing repeatedly.
+    fn http_check_status(&self, machine_id: &str) -> Result<(usize, Vec<String>), QueryError> {
+        let now = Utc::now();
+        let recent: Vec<_> = self
+            .store
+            .latest_http_checks(Some(machine_id))?
+            .into_iter()
+            .filter(|check| {
+                parse_stored_timestamp(&check.collected_at)
+                    .is_some_and(|at| (now - at).num_seconds() <= HTTP_CHECK_MAX_AGE_SECS)
+            })
+            .collect();
+        let down = recent
+            .iter()
+            .filter(|check| check.consecutive_failures >= HTTP_CHECK_REPEATED_FAILURES)
+            .map(|check| check.check_name.clone())
+            .collect();
+        Ok((recent.len(), down))
+    }
+
     /// Worst filesystem usage percent in the most recent `sys_filesystems` snapshot.
     fn worst_filesystem_pct(&self, machine_id: &str) -> Result<Option<f64>, QueryError> {
         let escaped = vc_store::escape_sql_literal(machine_id);
@@ -432,7 +597,7 @@ impl QueryBuilder<'_> {
         // `collected_at` is TEXT, so the window filter is applied in Rust after
         // parsing rather than with a `current_timestamp` comparison in SQL.
         let sql = format!(
-            "SELECT success, CAST(collected_at AS TEXT) AS collected_at \
+            "SELECT success, error_cause, CAST(collected_at AS TEXT) AS collected_at \
              FROM collector_health WHERE machine_id = '{escaped}' \
              ORDER BY collected_at DESC LIMIT {COLLECTOR_ROW_LIMIT}"
         );
@@ -468,6 +633,11 @@ impl QueryBuilder<'_> {
                 stats.runs_in_window += 1;
                 if success {
                     stats.successes_in_window += 1;
+                } else if let Some(cause) = row["error_cause"].as_str() {
+                    stats.classified_failures_in_window += 1;
+                    if cause == "unknown" {
+                        stats.unknown_failures_in_window += 1;
+                    }
                 }
             }
         }
@@ -477,11 +647,121 @@ impl QueryBuilder<'_> {
             let successes = f64::from(u32::try_from(stats.successes_in_window).unwrap_or(u32::MAX));
             stats.success_pct_in_window = Some(successes / runs * 100.0);
         }
+        if stats.classified_failures_in_window > 0 {
+            let failures =
+                f64::from(u32::try_from(stats.classified_failures_in_window).unwrap_or(u32::MAX));
+            let unknown =
+                f64::from(u32::try_from(stats.unknown_failures_in_window).unwrap_or(u32::MAX));
+            stats.unknown_cause_pct_in_window = Some(unknown / failures * 100.0);
+        }
 
         Ok(stats)
     }
 }
 
+impl QueryBuilder<'_> {
+    /// Re-score health summaries collected since `since` that were scored
+    /// under an older [`HEALTH_SCORING_VERSION`], and stamp them with the
+    /// current one.
+    ///
+    /// The telemetry behind a stored factor is not kept, so factor scores
+    /// stay as recorded; the current weights and [`compute_overall_score`]
+    /// are applied to them again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if reading the history or writing it back fails.
+    pub fn rescore_health_since(&self, since: DateTime<Utc>) -> Result<HealthRescore, QueryError> {
+        let stale = format!(
+            "COALESCE(scoring_version, 0) <> {HEALTH_SCORING_VERSION} \
+             AND TRY_CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP)",
+            since.format("%Y-%m-%d %H:%M:%S")
+        );
+        let summaries = self.store.query_json(&format!(
+            "SELECT machine_id, CAST(collected_at AS TEXT) AS collected_at \
+             FROM health_summary WHERE {stale}"
+        ))?;
+        let factor_rows = self.store.query_json(&format!(
+            "SELECT machine_id, CAST(collected_at AS TEXT) AS collected_at, factor_id, \
+             severity, score, weight, details_json \
+             FROM health_factors hf WHERE EXISTS ( \
+                 SELECT 1 FROM health_summary \
+                 WHERE machine_id = hf.machine_id AND collected_at = hf.collected_at \
+                 AND {stale} \
+             )"
+        ))?;
+
+        let mut factors: HashMap<(String, String), Vec<HealthFactor>> = HashMap::new();
+        for row in &factor_rows {
+            let (Some(machine_id), Some(collected_at)) =
+                (row["machine_id"].as_str(), row["collected_at"].as_str())
+            else {
+                continue;
+            };
+            factors
+                .entry((machine_id.to_string(), collected_at.to_string()))
+                .or_default()
+                .push(stored_health_factor(row));
+        }
+
+        let weights = HealthWeights::default();
+        let mut result = HealthRescore {
+            scoring_version: HEALTH_SCORING_VERSION,
+            ..HealthRescore::default()
+        };
+        let mut sql = String::new();
+        for row in &summaries {
+            let (Some(machine_id), Some(collected_at)) =
+                (row["machine_id"].as_str(), row["collected_at"].as_str())
+            else {
+                continue;
+            };
+            let Some(mut factors) =
+                factors.remove(&(machine_id.to_string(), collected_at.to_string()))
+            else {
+                result.without_factors += 1;
+                continue;
+            };
+
+            let machine_id = vc_store::escape_sql_literal(machine_id);
+            let collected_at = vc_store::escape_sql_literal(collected_at);
+            for factor in &mut factors {
+                factor.weight = weights.weight_for(&factor.factor_id);
+                let _ = writeln!(
+                    sql,
+                    "UPDATE health_factors SET weight = {} \
+                     WHERE machine_id = '{machine_id}' AND collected_at = '{collected_at}' \
+                     AND factor_id = '{}';",
+                    factor.weight,
+                    vc_store::escape_sql_literal(&factor.factor_id)
+                );
+            }
+            let worst = worst_factor_id(&factors).map_or_else(
+                || "NULL".to_string(),
+                |id| format!("'{}'", vc_store::escape_sql_literal(&id)),
+            );
+            let count =
+                |severity: Severity| factors.iter().filter(|f| f.severity == severity).count();
+            let _ = writeln!(
+                sql,
+                "UPDATE health_summary SET overall_score = {}, worst_factor_id = {worst}, \
+                 critical_count = {}, warning_count = {}, \
+                 scoring_version = {HEALTH_SCORING_VERSION} \
+                 WHERE machine_id = '{machine_id}' AND collected_at = '{collected_at}';",
+                compute_overall_score(&factors),
+                count(Severity::Critical),
+                count(Severity::Warning)
+            );
+            result.rescored += 1;
+        }
+
+        if !sql.is_empty() {
+            self.store.execute_batch(&sql)?;
+        }
+        Ok(result)
+    }
+}
+
 /// Aggregated `collector_health` statistics for one machine.
 #[derive(Debug, Default, Clone)]
 struct CollectorStats {
@@ -493,6 +773,13 @@ struct CollectorStats {
     successes_in_window: usize,
     /// Success rate over the window, `None` when the window is empty.
     success_pct_in_window: Option<f64>,
+    /// Failed runs within the window that carry an error cause (runs from
+    /// before causes were recorded are left out).
+    classified_failures_in_window: usize,
+    /// Of those, failures whose cause is `unknown`.
+    unknown_failures_in_window: usize,
+    /// Share of `unknown` among classified failures, `None` without any.
+    unknown_cause_pct_in_window: Option<f64>,
 }
 
 #[cfg(test)]
@@ -691,6 +978,80 @@ mod tests {
         assert_eq!(disk.severity, Severity::Critical);
     }
 
+    #[test]
+    fn test_missing_required_service_is_a_warning() {
+        let store = store_with_machine("m1");
+        let now = ts_ago(10);
+        store
+            .execute_batch(&format!(
+                "INSERT INTO machine_services \
+                   (machine_id, service_name, kind, target, required, running, checked_at) \
+                 VALUES ('m1', 'tmux', 'process', 'tmux', 1, 1, '{now}'), \
+                        ('m1', 'vc-node', 'unit', 'vc-node.service', 1, 0, '{now}'), \
+                        ('m1', 'model-server', 'process', 'llama', 0, 0, '{now}');"
+            ))
+            .unwrap();
+
+        let qb = QueryBuilder::new(&store);
+        let factors = qb.compute_health_factors("m1").unwrap();
+        let services = factor(&factors, "service_health").unwrap();
+        assert_eq!(services.severity, Severity::Warning);
+        // Optional services do not count
+        assert!(services.details.starts_with("1/2"));
+        assert!(services.details.contains("vc-node"));
+        assert!(!services.details.contains("model-server"));
+
+        // No checks configured: no factor at all
+        let other = store_with_machine("m2");
+        let factors = QueryBuilder::new(&other)
+            .compute_health_factors("m2")
+            .unwrap();
+        assert!(factor(&factors, "service_health").is_none());
+    }
+
+    #[test]
+    fn test_repeatedly_failing_http_check_is_a_warning() {
+        let store = store_with_machine("m1");
+        let mut values = Vec::new();
+        for (secs_ago, api_ok) in [(30, 1), (20, 0), (10, 0), (5, 0)] {
+            let at = ts_ago(secs_ago);
+            values.push(format!(
+                "('m1', '{at}', 'api', 'http://h/api', 'GET', 'hub', {api_ok})"
+            ));
+            values.push(format!(
+                "('m1', '{at}', 'ui', 'http://h/', 'GET', 'hub', 1)"
+            ));
+        }
+        // A single failure is a blip
+        let at = ts_ago(5);
+        values.push(format!(
+            "('m1', '{at}', 'docs', 'http://h/docs', 'GET', 'hub', 0)"
+        ));
+        store
+            .execute_batch(&format!(
+                "INSERT INTO http_check_results \
+                   (machine_id, collected_at, check_name, url, method, origin, ok) \
+                 VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+
+        let factors = QueryBuilder::new(&store)
+            .compute_health_factors("m1")
+            .unwrap();
+        let checks = factor(&factors, "http_checks").unwrap();
+        assert_eq!(checks.severity, Severity::Warning);
+        assert!(checks.details.starts_with("1/3"), "{}", checks.details);
+        assert!(checks.details.contains("api"));
+        assert!(!checks.details.contains("docs"));
+
+        let other = store_with_machine("m2");
+        let factors = QueryBuilder::new(&other)
+            .compute_health_factors("m2")
+            .unwrap();
+        assert!(factor(&factors, "http_checks").is_none());
+    }
+
     #[test]
     fn test_stale_telemetry_flags_freshness() {
         let store = store_with_machine("m1");
@@ -738,6 +1099,47 @@ mod tests {
         assert!(process.score < f64::EPSILON);
     }
 
+    #[test]
+    fn test_unknown_failure_causes_flag_error_classification() {
+        let store = store_with_machine("m1");
+        let mut sql = String::new();
+        for (i, cause) in ["unknown", "unknown", "timeout", "auth_failed"]
+            .iter()
+            .enumerate()
+        {
+            let ts = ts_ago(60 * (i64::try_from(i).unwrap() + 1));
+            write!(
+                &mut sql,
+                "INSERT INTO collector_health \
+                   (machine_id, collector, collected_at, success, error_cause) \
+                 VALUES ('m1', 'c{i}', '{ts}', 0, '{cause}'); "
+            )
+            .expect("writing to a String cannot fail");
+        }
+        // Failures from before causes were recorded do not count.
+        write!(
+            &mut sql,
+            "INSERT INTO collector_health (machine_id, collector, collected_at, success) \
+             VALUES ('m1', 'old', '{}', 0);",
+            ts_ago(30)
+        )
+        .expect("writing to a String cannot fail");
+        store.execute_batch(&sql).unwrap();
+
+        let qb = QueryBuilder::new(&store);
+        let factors = qb.compute_health_factors("m1").unwrap();
+
+        let classification = factor(&factors, "error_classification").unwrap();
+        assert_eq!(classification.severity, Severity::Critical);
+        assert!(classification.details.starts_with("2/4 "));
+
+        let healthy = store_with_machine("m2");
+        let factors = QueryBuilder::new(&healthy)
+            .compute_health_factors("m2")
+            .unwrap();
+        assert!(factor(&factors, "error_classification").is_none());
+    }
+
     #[test]
     fn test_compute_and_persist_health_writes_tables() {
         let store = store_with_machine("m1");
@@ -812,5 +1214,77 @@ mod tests {
         let overview = qb.fleet_overview().unwrap();
         assert!(overview.fleet_health_score < 1.0);
         assert_eq!(overview.worst_machine, Some("m2".to_string()));
+
+        // In maintenance, m2 keeps its last score and leaves the aggregate.
+        store
+            .start_maintenance(
+                "m2",
+                Some("RAM upgrade"),
+                None,
+                &vc_store::ActorContext::system("op"),
+            )
+            .unwrap();
+        let scores = qb.compute_and_persist_health_all().unwrap();
+        assert_eq!(scores.len(), 1);
+        let overview = qb.fleet_overview().unwrap();
+        assert_eq!(overview.maintenance_machines, 1);
+        assert!(overview.fleet_health_score > 0.9);
+        assert_ne!(overview.worst_machine, Some("m2".to_string()));
+    }
+
+    #[test]
+    fn test_rescore_brings_older_history_to_the_current_version() {
+        let store = VcStore::open_memory().unwrap();
+        let (no_factors, scored, expired) = (ts_ago(10_800), ts_ago(7200), ts_ago(30 * 86_400));
+        store
+            .execute_batch(&format!(
+                "INSERT INTO health_summary \
+                   (machine_id, collected_at, overall_score, scoring_version) \
+                 VALUES ('m1', '{expired}', 0.9, 0), ('m1', '{no_factors}', 0.9, 0), \
+                        ('m1', '{scored}', 0.75, 0); \
+                 INSERT INTO health_factors \
+                   (machine_id, collected_at, factor_id, severity, score, weight) \
+                 VALUES ('m1', '{scored}', 'sys_disk', 'warning', 0.5, 1.0), \
+                        ('m1', '{scored}', 'sys_load', 'healthy', 1.0, 1.0);"
+            ))
+            .unwrap();
+
+        let qb = QueryBuilder::new(&store);
+        qb.compute_and_persist_health("m1").unwrap();
+        assert_eq!(
+            qb.scoring_version_changes(Some("m1")).unwrap(),
+            vec![crate::ScoringVersionChange {
+                machine_id: "m1".to_string(),
+                previous_version: 0,
+                scoring_version: HEALTH_SCORING_VERSION,
+            }]
+        );
+
+        let rescore = qb
+            .rescore_health_since(Utc::now() - chrono::Duration::days(7))
+            .unwrap();
+        assert_eq!(rescore.rescored, 1);
+        assert_eq!(rescore.without_factors, 1);
+
+        let rows = store
+            .query_json(&format!(
+                "SELECT overall_score, worst_factor_id, warning_count, scoring_version \
+                 FROM health_summary WHERE collected_at = '{scored}'"
+            ))
+            .unwrap();
+        // sys_disk now weighs 2.0 against sys_load's 1.0
+        assert!((rows[0]["overall_score"].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(rows[0]["worst_factor_id"], "sys_disk");
+        assert_eq!(rows[0]["warning_count"], 1);
+        assert_eq!(rows[0]["scoring_version"], HEALTH_SCORING_VERSION);
+
+        // Outside the window, history keeps its version
+        let expired_version = store
+            .query_scalar::<i64>(&format!(
+                "SELECT scoring_version FROM health_summary WHERE collected_at = '{expired}'"
+            ))
+            .unwrap();
+        assert_eq!(expired_version, 0);
+        assert!(qb.scoring_version_changes(Some("m1")).unwrap().is_empty());
     }
 }
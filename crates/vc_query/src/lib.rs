@@ -3,31 +3,93 @@
 //! This crate provides:
 //! - Canonical queries for health, rollups, and anomalies
 //! - Health score calculation
-//! - Time-travel query support
+//! - Agent session success rates
+//! - Running agents per machine from the agent inventory
+//! - Per-repository activity rollups and stale agent work
+//! - Disk forecasts: days until each mount is full
+//! - Daily alert and incident burndown, with unwatched days kept apart
+//! - Time-travel query support, including machine environment diffs
+//! - A merged fleet timeline of alerts, incidents, fleet commands, audit and
+//!   drift events
+//! - One search across knowledge, incidents, session transcripts and alerts
 //! - Aggregation utilities
 //! - Query guardrails and safe templates
+//! - Operator approval of raw queries proposed by agents
+//! - Column selection and aggregation over template results
+//! - Incident SLA timers, breaches and attainment
+//! - Declarative fleet state: reconciliation plans and drift
+//! - Human-facing timestamp and duration formatting
+//! - Inline sparklines for trends in text output
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use thiserror::Error;
 use vc_store::VcStore;
 
 pub mod guardrails;
-pub use guardrails::{GuardrailConfig, QueryTemplate, QueryValidator, ValidationError};
+pub use guardrails::{
+    GuardrailConfig, QueryOrigin, QueryTemplate, QueryValidator, ValidationError, sql_fingerprint,
+};
+
+pub mod agents;
+
+pub mod approval;
+
+pub mod burndown;
+pub use burndown::{Burndown, BurndownDay, BurndownTotals, SeverityCounts};
+
+pub mod components;
+pub use components::{ComponentHealth, ComponentStatus, ComponentsReport};
 
 pub mod cost;
 
+pub mod dependencies;
+
 pub mod digest;
 
+pub mod envdiff;
+pub use envdiff::{EnvDiff, EnvSnapshot, FieldChange};
+
+pub mod fleet_state;
+pub use fleet_state::{DesiredState, FleetDrift, FleetSpec, PlanAction, PlanStep};
+
+pub mod forecast;
+pub use forecast::{DiskForecast, DiskForecastOptions, DiskTrend, MountForecast};
+
 pub mod health;
 
 pub mod nl;
+
+pub mod repos;
+
+pub mod reshape;
+
+pub mod search;
+
+pub mod sla;
+
+pub mod sparkline;
+
+pub mod timefmt;
+
+pub mod timeline;
 pub use cost::{
     AnomalySeverity, AnomalyType, ConfidenceFactors, CostAnomaly, CostAttribution, CostDriver,
     CostQueryBuilder, CostSummary, CostTrend, MachineCost, ProviderCost, ProviderPricing, RepoCost,
     estimate_cost,
 };
 pub use nl::{NlEngine, NlQueryResult, QueryIntent};
+pub use repos::RepoActivity;
+pub use reshape::{Aggregate, Reshape};
+pub use search::{SearchFilter, SearchHit, SearchKind, SearchResults};
+pub use timefmt::{DisplayZone, TimeFormatter, TimestampStyle};
+pub use timeline::{TimelineEvent, TimelineFilter, TimelineKind, TimelinePage};
+// Served as-is by the web API, so they live with its other wire types
+pub use vc_types::{
+    FleetOverview, HealthFactor, HealthScore, HealthTrend, Severity, TREND_THRESHOLD, TagBreakdown,
+    UNTAGGED_GROUP, UpstreamFailure,
+};
 
 /// Query errors
 #[derive(Error, Debug)]
@@ -40,62 +102,59 @@ pub enum QueryError {
 
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
+
+    #[error("Query timed out after {elapsed_ms} ms and was cancelled (fingerprint {fingerprint})")]
+    Timeout {
+        elapsed_ms: u64,
+        fingerprint: String,
+    },
 }
 
-/// Health score for a machine
+/// Trailing window for the per-factor baseline reported by `machine_health`
+pub const HEALTH_BASELINE_DAYS: i64 = 7;
+
+/// Historical samples a factor needs before it gets a baseline
+pub const MIN_BASELINE_SAMPLES: u64 = 3;
+
+/// A machine's current health next to its average over a comparison window
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HealthScore {
+pub struct HealthComparison {
     pub machine_id: String,
-    pub overall_score: f64,
+    pub window_secs: i64,
+    pub current_score: f64,
+    /// Average overall score across the window, `None` without enough history
+    pub baseline_score: Option<f64>,
+    pub trend: Option<HealthTrend>,
+    /// Factors ordered by how far they moved from their baseline; factors
+    /// without a baseline come last
     pub factors: Vec<HealthFactor>,
-    pub worst_factor: Option<String>,
 }
 
-/// Individual health factor
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HealthFactor {
-    pub factor_id: String,
-    pub name: String,
-    pub score: f64,
-    pub weight: f64,
-    pub severity: Severity,
-    pub details: String,
+/// A machine whose two latest health summaries were scored by different
+/// versions of the scoring logic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoringVersionChange {
+    pub machine_id: String,
+    pub previous_version: i64,
+    pub scoring_version: i64,
 }
 
-/// Severity levels
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
-#[serde(rename_all = "lowercase")]
-pub enum Severity {
-    Healthy,
-    Info,
-    Warning,
-    Critical,
+/// Outcome of re-scoring health history under [`HEALTH_SCORING_VERSION`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthRescore {
+    pub scoring_version: i64,
+    /// Summaries re-scored and stamped with the current version
+    pub rescored: usize,
+    /// Summaries under an older version left alone because their factor
+    /// rows are gone
+    pub without_factors: usize,
 }
 
-impl Severity {
-    #[must_use]
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Severity::Healthy => "healthy",
-            Severity::Info => "info",
-            Severity::Warning => "warning",
-            Severity::Critical => "critical",
-        }
-    }
-}
-
-impl std::str::FromStr for Severity {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim().to_lowercase().as_str() {
-            "healthy" => Ok(Severity::Healthy),
-            "info" => Ok(Severity::Info),
-            "warning" => Ok(Severity::Warning),
-            "critical" => Ok(Severity::Critical),
-            other => Err(format!("unknown severity: {other}")),
-        }
-    }
-}
+/// Version of the health scoring logic, recorded with every
+/// `health_summary` row. Bump it whenever factor thresholds, weights or
+/// [`compute_overall_score`] change, so history scored under the old logic
+/// is not compared with new scores as if nothing had changed.
+pub const HEALTH_SCORING_VERSION: i64 = 1;
 
 /// Default factor weights for health score calculation.
 /// Each `factor_id` maps to a weight (higher = more important).
@@ -113,6 +172,8 @@ pub struct HealthWeights {
     pub repo_cleanliness: f64,
     pub process_health: f64,
     pub data_freshness: f64,
+    pub service_health: f64,
+    pub http_checks: f64,
 }
 
 impl Default for HealthWeights {
@@ -131,6 +192,8 @@ impl Default for HealthWeights {
             repo_cleanliness: 0.5,
             process_health: 1.0,
             data_freshness: 1.0,
+            service_health: 1.0,
+            http_checks: 1.0,
         }
     }
 }
@@ -153,6 +216,8 @@ impl HealthWeights {
             "repo_cleanliness" => self.repo_cleanliness,
             "process_health" => self.process_health,
             "data_freshness" => self.data_freshness,
+            "service_health" => self.service_health,
+            "http_checks" => self.http_checks,
             _ => 1.0,
         }
     }
@@ -248,18 +313,338 @@ pub fn classify_metric(
     }
 }
 
-/// Fleet overview summary
+/// Guardian runs waiting for an operator before they execute
+const PENDING_RUNS_FILTER: &str = " WHERE status = 'pending_approval'";
+
+/// Generated playbook drafts waiting for review
+const PENDING_DRAFTS_FILTER: &str = " WHERE status = 'pending_review'";
+
+/// Guardian runs and playbook drafts awaiting approval
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PendingApprovals {
+    /// Pending runs, newest first, each with an `age_secs` field
+    pub runs: Vec<serde_json::Value>,
+    /// Pending drafts, newest first, each with an `age_secs` field
+    pub drafts: Vec<serde_json::Value>,
+    pub total_runs: usize,
+    pub total_drafts: usize,
+}
+
+impl PendingApprovals {
+    /// Runs plus drafts, across all pages
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.total_runs + self.total_drafts
+    }
+}
+
+/// Machines running one version of a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionGroup {
+    pub version: String,
+    pub machines: Vec<String>,
+    /// Not the version most of the fleet runs
+    pub outlier: bool,
+    /// Older than the configured minimum for the tool
+    pub below_minimum: bool,
+}
+
+/// How one tool's versions spread across the fleet
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FleetOverview {
-    pub total_machines: usize,
-    pub online_machines: usize,
-    pub offline_machines: usize,
-    pub total_agents: usize,
-    pub active_agents: usize,
-    pub fleet_health_score: f64,
-    pub worst_machine: Option<String>,
-    pub active_alerts: usize,
-    pub pending_approvals: usize,
+pub struct ToolVersionSpread {
+    pub tool: String,
+    /// Version on the most machines (the newest one on a tie)
+    pub majority_version: String,
+    pub minimum_version: Option<String>,
+    /// Newest version first
+    pub versions: Vec<VersionGroup>,
+}
+
+impl ToolVersionSpread {
+    /// Group current per-machine versions of one tool
+    #[must_use]
+    pub fn from_versions(
+        tool: &str,
+        versions: &[vc_store::ToolVersion],
+        minimum_version: Option<&str>,
+    ) -> Self {
+        let mut groups: Vec<VersionGroup> = Vec::new();
+        for version in versions.iter().filter(|v| v.tool_name == tool) {
+            match groups
+                .iter_mut()
+                .find(|g| g.version == version.tool_version)
+            {
+                Some(group) => group.machines.push(version.machine_id.clone()),
+                None => groups.push(VersionGroup {
+                    version: version.tool_version.clone(),
+                    machines: vec![version.machine_id.clone()],
+                    outlier: false,
+                    below_minimum: false,
+                }),
+            }
+        }
+        groups.sort_by(|a, b| vc_store::compare_versions(&b.version, &a.version));
+
+        // Groups are newest first, so `max_by_key` would pick the oldest on a
+        // tie; scanning in order and keeping strict improvements picks the newest.
+        let mut majority: Option<&VersionGroup> = None;
+        for group in &groups {
+            if majority.is_none_or(|m| group.machines.len() > m.machines.len()) {
+                majority = Some(group);
+            }
+        }
+        let majority_version = majority.map(|g| g.version.clone()).unwrap_or_default();
+
+        for group in &mut groups {
+            group.machines.sort();
+            group.outlier = group.version != majority_version;
+            group.below_minimum = minimum_version
+                .is_some_and(|min| vc_store::compare_versions(&group.version, min).is_lt());
+        }
+
+        Self {
+            tool: tool.to_string(),
+            majority_version,
+            minimum_version: minimum_version.map(str::to_string),
+            versions: groups,
+        }
+    }
+}
+
+/// What session success rates are grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionGroupBy {
+    /// Agent type (the session's `program`)
+    Agent,
+    /// Repository (last component of `repo_path`)
+    Repo,
+    /// Agent type within each repository
+    AgentRepo,
+}
+
+impl SessionGroupBy {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionGroupBy::Agent => "agent",
+            SessionGroupBy::Repo => "repo",
+            SessionGroupBy::AgentRepo => "agent_repo",
+        }
+    }
+}
+
+impl std::str::FromStr for SessionGroupBy {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "agent" | "program" => Ok(SessionGroupBy::Agent),
+            "repo" => Ok(SessionGroupBy::Repo),
+            "agent_repo" => Ok(SessionGroupBy::AgentRepo),
+            other => Err(format!(
+                "unknown session grouping: {other} (expected agent, repo or agent_repo)"
+            )),
+        }
+    }
+}
+
+/// Outcome counts and success rate of one group of classified sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionSuccessRate {
+    /// `claude-code`, `vibe_cockpit` or `claude-code on vibe_cockpit`
+    pub group: String,
+    pub agent: Option<String>,
+    pub repo: Option<String>,
+    pub sessions: usize,
+    pub success: usize,
+    pub partial: usize,
+    pub failed: usize,
+    pub abandoned: usize,
+    /// Share of the window's sessions that succeeded, in percent
+    pub success_pct: f64,
+    /// The same over the window before, if it had any sessions
+    pub previous_success_pct: Option<f64>,
+    /// `success_pct` minus `previous_success_pct`, in percentage points
+    pub change_points: Option<f64>,
+}
+
+impl SessionSuccessRate {
+    /// One line for reports: `claude-code on repo: 78% success, down 9 points`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let trend = match self.change_points {
+            Some(change) if change.round() >= 1.0 => format!(", up {change:.0} points"),
+            Some(change) if change.round() <= -1.0 => format!(", down {:.0} points", -change),
+            Some(_) => ", unchanged".to_string(),
+            None => String::new(),
+        };
+        format!(
+            "{}: {:.0}% success over {} session(s){trend}",
+            self.group, self.success_pct, self.sessions
+        )
+    }
+}
+
+/// Outcome tallies for one session group: success, partial, failed, abandoned
+type OutcomeTally = [usize; 4];
+
+fn tally_outcome(tally: &mut OutcomeTally, outcome: &str) {
+    let slot = match outcome {
+        "success" => 0,
+        "partial" => 1,
+        "failed" => 2,
+        "abandoned" => 3,
+        _ => return,
+    };
+    tally[slot] += 1;
+}
+
+fn success_pct(tally: &OutcomeTally) -> Option<f64> {
+    let sessions: usize = tally.iter().sum();
+    if sessions == 0 {
+        return None;
+    }
+    let sessions = f64::from(u32::try_from(sessions).unwrap_or(u32::MAX));
+    let success = f64::from(u32::try_from(tally[0]).unwrap_or(u32::MAX));
+    Some(success / sessions * 100.0)
+}
+
+/// `SELECT` column counting rows of `table` matching `filter`, or a literal
+/// zero when the store lacks the table
+fn count_column(caps: &vc_store::Capabilities, table: &str, filter: &str, alias: &str) -> String {
+    if caps.require(&[table]) {
+        format!("(SELECT COUNT(*) FROM {table}{filter}) AS {alias}")
+    } else {
+        format!("0 AS {alias}")
+    }
+}
+
+/// Split `machines` rows (`machine_id`, `status`, `tags`) into one group per
+/// tag plus [`UNTAGGED_GROUP`]. `scores` holds the latest score of each
+/// machine outside maintenance, worst first.
+fn tag_breakdown(
+    machines: &[serde_json::Value],
+    scores: &[(String, f64)],
+    alerts_by_machine: &HashMap<String, usize>,
+) -> Vec<TagBreakdown> {
+    let mut groups: BTreeMap<String, Vec<(&str, &str)>> = BTreeMap::new();
+    for row in machines {
+        let Some(machine_id) = row["machine_id"].as_str() else {
+            continue;
+        };
+        let status = row["status"].as_str().unwrap_or("unknown");
+        let mut tags: Vec<String> = row["tags"]
+            .as_str()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        tags.sort_unstable();
+        tags.dedup();
+        if tags.is_empty() {
+            tags.push(UNTAGGED_GROUP.to_string());
+        }
+        for tag in tags {
+            groups.entry(tag).or_default().push((machine_id, status));
+        }
+    }
+
+    let untagged = groups.remove(UNTAGGED_GROUP);
+    groups
+        .into_iter()
+        .chain(untagged.map(|members| (UNTAGGED_GROUP.to_string(), members)))
+        .map(|(tag, members)| {
+            let with_status = |status: &str| members.iter().filter(|(_, s)| *s == status).count();
+            let member_scores: Vec<&(String, f64)> = scores
+                .iter()
+                .filter(|(machine_id, _)| members.iter().any(|(id, _)| *id == machine_id.as_str()))
+                .collect();
+            let health_score = if member_scores.is_empty() {
+                1.0
+            } else {
+                let total: f64 = member_scores.iter().map(|(_, score)| score).sum();
+                total / f64::from(u32::try_from(member_scores.len()).unwrap_or(u32::MAX))
+            };
+            TagBreakdown {
+                total_machines: members.len(),
+                online_machines: with_status("online"),
+                offline_machines: with_status("offline"),
+                maintenance_machines: with_status("maintenance"),
+                health_score,
+                active_alerts: members
+                    .iter()
+                    .filter_map(|(id, _)| alerts_by_machine.get(*id))
+                    .sum(),
+                worst_machine: member_scores
+                    .first()
+                    .filter(|(_, score)| *score < 1.0)
+                    .map(|(machine_id, _)| machine_id.clone()),
+                tag,
+            }
+        })
+        .collect()
+}
+
+/// Read a `COUNT(*)` column from a JSON row
+fn count_of(row: &serde_json::Value, key: &str) -> usize {
+    usize::try_from(row[key].as_u64().unwrap_or(0)).unwrap_or(usize::MAX)
+}
+
+/// Start of a baseline window that ends at `collected_at`
+fn baseline_start(collected_at: &str, window: chrono::Duration) -> String {
+    let end = timefmt::parse_timestamp(collected_at).unwrap_or_else(Utc::now);
+    (end - window).to_rfc3339()
+}
+
+/// `avg_score` from a baseline aggregate row, if it has enough samples
+fn baseline_average(row: &serde_json::Value) -> Option<f64> {
+    if row["samples"].as_u64().unwrap_or(0) < MIN_BASELINE_SAMPLES {
+        return None;
+    }
+    row["avg_score"].as_f64()
+}
+
+/// The lowest-scoring factor, which a summary names as its worst
+fn worst_factor_id(factors: &[HealthFactor]) -> Option<String> {
+    factors
+        .iter()
+        .min_by(|a, b| {
+            a.score
+                .partial_cmp(&b.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|f| f.factor_id.clone())
+}
+
+/// A [`HealthFactor`] from a `health_factors` row
+fn stored_health_factor(row: &serde_json::Value) -> HealthFactor {
+    let factor_id = row["factor_id"].as_str().unwrap_or("unknown").to_string();
+    let parsed_details: serde_json::Value = row["details_json"]
+        .as_str()
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    let name = parsed_details["name"]
+        .as_str()
+        .map_or_else(|| factor_id.replace('_', " "), String::from);
+    let details = parsed_details["details"].as_str().unwrap_or("").to_string();
+
+    HealthFactor {
+        name,
+        factor_id,
+        score: row["score"].as_f64().unwrap_or(1.0),
+        weight: row["weight"].as_f64().unwrap_or(1.0),
+        severity: row["severity"]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(Severity::Healthy),
+        details,
+        baseline_score: None,
+        trend: None,
+    }
+}
+
+/// Seconds between `timestamp` and now, if it parses
+fn age_secs(timestamp: Option<&str>) -> Option<i64> {
+    let ts = timefmt::parse_timestamp(timestamp?)?;
+    Some((Utc::now() - ts).num_seconds().max(0))
 }
 
 /// Query builder for common operations
@@ -277,32 +662,88 @@ impl<'a> QueryBuilder<'a> {
     ///
     /// Counts are gathered in a single round-trip. The fleet health score is the
     /// mean of the latest per-machine health summary, defaulting to 1.0 when no
-    /// health data has been persisted yet.
+    /// health data has been persisted yet. Counts whose table the store lacks
+    /// read as zero and the table is listed in `missing_capabilities`.
+    /// Machines others depend on that have a critical alert are listed in
+    /// `upstream_failures`. `by_tag` splits the machine, health and alert
+    /// numbers by tag from the same rows, whatever the number of tags.
+    /// Agents are counted from the agent inventory once it has any rows,
+    /// from session records before that.
     ///
     /// # Errors
     ///
     /// Returns [`QueryError`] if retrieval fails.
     pub fn fleet_overview(&self) -> Result<FleetOverview, QueryError> {
-        let counts_sql = "SELECT \
-             (SELECT COUNT(*) FROM machines) AS total_machines, \
-             (SELECT COUNT(*) FROM machines WHERE status = 'online') AS online_machines, \
-             (SELECT COUNT(*) FROM machines WHERE status = 'offline') AS offline_machines, \
-             (SELECT COUNT(*) FROM agent_sessions) AS total_agents, \
-             (SELECT COUNT(*) FROM agent_sessions WHERE ended_at IS NULL) AS active_agents, \
-             (SELECT COUNT(*) FROM alert_history WHERE resolved_at IS NULL) AS active_alerts, \
-             (SELECT COUNT(*) FROM guardian_runs WHERE status = 'pending_approval') \
-             AS pending_approvals";
-        let rows = self.store.query_json(counts_sql)?;
+        let caps = self.store.capabilities()?;
+        let count =
+            |table: &str, filter: &str, alias: &str| count_column(&caps, table, filter, alias);
+        let counts_sql = format!(
+            "SELECT {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}",
+            count("machines", "", "total_machines"),
+            count("machines", " WHERE status = 'online'", "online_machines"),
+            count("machines", " WHERE status = 'offline'", "offline_machines"),
+            count(
+                "machines",
+                " WHERE status = 'maintenance'",
+                "maintenance_machines"
+            ),
+            count("agent_sessions", "", "total_agents"),
+            count("agent_sessions", " WHERE ended_at IS NULL", "active_agents"),
+            count("agents_inventory", "", "inventory_agents"),
+            count(
+                "agents_inventory",
+                " WHERE ended_at IS NULL",
+                "inventory_active_agents"
+            ),
+            count(
+                "alert_history",
+                " WHERE resolved_at IS NULL",
+                "active_alerts"
+            ),
+            count("guardian_runs", PENDING_RUNS_FILTER, "pending_runs"),
+            count("playbook_drafts", PENDING_DRAFTS_FILTER, "pending_drafts"),
+        );
+        let rows = self.store.query_json(&counts_sql)?;
         let counts = rows
             .first()
             .cloned()
             .unwrap_or_else(|| serde_json::json!({}));
-        let count_of = |key: &str| -> usize {
-            usize::try_from(counts[key].as_u64().unwrap_or(0)).unwrap_or(usize::MAX)
-        };
+        let counted = |key: &str| count_of(&counts, key);
 
         // `list_health_summaries` returns the latest row per machine, worst score first.
-        let summaries = self.list_health_summaries()?;
+        let summaries = if caps.require(&["health_summary"]) {
+            self.list_health_summaries()?
+        } else {
+            Vec::new()
+        };
+        let machines = if caps.require(&["machines"]) {
+            self.store
+                .query_json("SELECT machine_id, status, tags FROM machines")?
+        } else {
+            Vec::new()
+        };
+        let alerts_by_machine: HashMap<String, usize> = if caps.require(&["alert_history"]) {
+            self.store
+                .query_json(
+                    "SELECT machine_id, COUNT(*) AS active_alerts FROM alert_history \
+                     WHERE resolved_at IS NULL AND machine_id IS NOT NULL GROUP BY machine_id",
+                )?
+                .iter()
+                .filter_map(|row| {
+                    let machine_id = row["machine_id"].as_str()?;
+                    Some((machine_id.to_string(), count_of(row, "active_alerts")))
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        // Machines in maintenance keep their last score but do not weigh on
+        // the fleet's.
+        let in_maintenance: Vec<String> = machines
+            .iter()
+            .filter(|row| row["status"] == "maintenance")
+            .filter_map(|row| row["machine_id"].as_str().map(str::to_string))
+            .collect();
         let scores: Vec<(String, f64)> = summaries
             .iter()
             .filter_map(|row| {
@@ -310,6 +751,7 @@ impl<'a> QueryBuilder<'a> {
                 let score = row["overall_score"].as_f64()?;
                 Some((machine_id.to_string(), score))
             })
+            .filter(|(machine_id, _)| !in_maintenance.contains(machine_id))
             .collect();
 
         let fleet_health_score = if scores.is_empty() {
@@ -324,26 +766,213 @@ impl<'a> QueryBuilder<'a> {
             .filter(|(_, score)| *score < 1.0)
             .map(|(machine_id, _)| machine_id.clone());
 
-        Ok(FleetOverview {
-            total_machines: count_of("total_machines"),
-            online_machines: count_of("online_machines"),
-            offline_machines: count_of("offline_machines"),
-            total_agents: count_of("total_agents"),
-            active_agents: count_of("active_agents"),
-            fleet_health_score,
-            worst_machine,
-            active_alerts: count_of("active_alerts"),
-            pending_approvals: count_of("pending_approvals"),
+        let upstream_failures = if caps.require(&["machine_dependencies", "alert_history"]) {
+            dependencies::upstream_failures(self.store)?
+        } else {
+            Vec::new()
+        };
+
+        // `FleetOverview` is non-exhaustive, so it is filled in field by field
+        let mut overview = FleetOverview::default();
+        overview.total_machines = counted("total_machines");
+        overview.online_machines = counted("online_machines");
+        overview.offline_machines = counted("offline_machines");
+        overview.maintenance_machines = counted("maintenance_machines");
+        // The agent inventory sees agents as they run; session records are
+        // the fallback until its collector has reported
+        if counted("inventory_agents") > 0 {
+            overview.total_agents = counted("inventory_agents");
+            overview.active_agents = counted("inventory_active_agents");
+        } else {
+            overview.total_agents = counted("total_agents");
+            overview.active_agents = counted("active_agents");
+        }
+        overview.fleet_health_score = fleet_health_score;
+        overview.worst_machine = worst_machine;
+        overview.active_alerts = counted("active_alerts");
+        overview.pending_approvals = counted("pending_runs") + counted("pending_drafts");
+        overview.missing_capabilities = caps.missing();
+        overview.upstream_failures = upstream_failures;
+        overview.by_tag = tag_breakdown(&machines, &scores, &alerts_by_machine);
+        Ok(overview)
+    }
+
+    /// Current version of each tool across the fleet, grouped by version.
+    ///
+    /// `min_versions` maps tool names to the oldest acceptable version
+    /// (`[collectors.min_versions]`); groups older than that are marked
+    /// `below_minimum`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if retrieval fails.
+    pub fn fleet_versions(
+        &self,
+        tool: Option<&str>,
+        min_versions: &std::collections::HashMap<String, String>,
+    ) -> Result<Vec<ToolVersionSpread>, QueryError> {
+        let versions = self.store.list_tool_versions(None)?;
+        let mut tools: Vec<&str> = versions
+            .iter()
+            .map(|v| v.tool_name.as_str())
+            .filter(|name| tool.is_none_or(|t| t == *name))
+            .collect();
+        tools.dedup();
+
+        Ok(tools
+            .into_iter()
+            .map(|name| {
+                ToolVersionSpread::from_versions(
+                    name,
+                    &versions,
+                    min_versions.get(name).map(String::as_str),
+                )
+            })
+            .collect())
+    }
+
+    /// Guardian runs and playbook drafts awaiting approval, newest first.
+    ///
+    /// `limit` and `offset` page each list independently; the totals cover
+    /// every pending row and use the same filters as
+    /// [`QueryBuilder::fleet_overview`], so the overview's
+    /// `pending_approvals` always equals [`PendingApprovals::total`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if retrieval fails.
+    pub fn pending_approvals(
+        &self,
+        limit: usize,
+        offset: usize,
+    ) -> Result<PendingApprovals, QueryError> {
+        let caps = self.store.capabilities()?;
+        let counts_sql = format!(
+            "SELECT {}, {}",
+            count_column(&caps, "guardian_runs", PENDING_RUNS_FILTER, "pending_runs"),
+            count_column(
+                &caps,
+                "playbook_drafts",
+                PENDING_DRAFTS_FILTER,
+                "pending_drafts"
+            ),
+        );
+        let rows = self.store.query_json(&counts_sql)?;
+        let counts = rows
+            .first()
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({}));
+
+        let with_age = |mut rows: Vec<serde_json::Value>, column: &str| {
+            for row in &mut rows {
+                let age = age_secs(row[column].as_str());
+                row["age_secs"] = serde_json::json!(age);
+            }
+            rows
+        };
+        let runs = if caps.has_table("guardian_runs") {
+            with_age(
+                self.store.query_json(&format!(
+                    "SELECT * FROM guardian_runs{PENDING_RUNS_FILTER} \
+                     ORDER BY started_at DESC LIMIT {limit} OFFSET {offset}"
+                ))?,
+                "started_at",
+            )
+        } else {
+            Vec::new()
+        };
+        let drafts = if caps.has_table("playbook_drafts") {
+            with_age(
+                self.store.query_json(&format!(
+                    "SELECT * FROM playbook_drafts{PENDING_DRAFTS_FILTER} \
+                     ORDER BY created_at DESC LIMIT {limit} OFFSET {offset}"
+                ))?,
+                "created_at",
+            )
+        } else {
+            Vec::new()
+        };
+
+        Ok(PendingApprovals {
+            runs,
+            drafts,
+            total_runs: count_of(&counts, "pending_runs"),
+            total_drafts: count_of(&counts, "pending_drafts"),
         })
     }
 
     /// Get health score for a machine by reading the latest stored summary.
     /// Falls back to score 1.0 (healthy) if no health data exists yet.
     ///
+    /// Each factor carries its average over the preceding
+    /// [`HEALTH_BASELINE_DAYS`] and a trend against that baseline.
+    ///
     /// # Errors
     ///
     /// Returns [`QueryError`] if underlying store queries fail.
     pub fn machine_health(&self, machine_id: &str) -> Result<HealthScore, QueryError> {
+        let (mut health, collected_at) = self.latest_health(machine_id)?;
+        if let Some(collected_at) = collected_at {
+            self.apply_factor_baselines(
+                &mut health,
+                &collected_at,
+                chrono::Duration::days(HEALTH_BASELINE_DAYS),
+            )?;
+        }
+        Ok(health)
+    }
+
+    /// Compare a machine's latest health against its average over `window`.
+    ///
+    /// Only snapshots taken before the latest one count towards the baseline,
+    /// so a machine with too little history reports `None` rather than
+    /// comparing against itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if underlying store queries fail.
+    pub fn health_comparison(
+        &self,
+        machine_id: &str,
+        window: chrono::Duration,
+    ) -> Result<HealthComparison, QueryError> {
+        let (mut health, collected_at) = self.latest_health(machine_id)?;
+        let mut baseline_score = None;
+        if let Some(collected_at) = collected_at {
+            self.apply_factor_baselines(&mut health, &collected_at, window)?;
+
+            let sql = format!(
+                "SELECT AVG(overall_score) AS avg_score, COUNT(*) AS samples \
+                 FROM health_summary \
+                 WHERE machine_id = '{}' AND collected_at >= '{}' AND collected_at < '{}'",
+                vc_store::escape_sql_literal(machine_id),
+                vc_store::escape_sql_literal(&baseline_start(&collected_at, window)),
+                vc_store::escape_sql_literal(&collected_at)
+            );
+            let rows = self.store.query_json(&sql)?;
+            baseline_score = rows.first().and_then(baseline_average);
+        }
+
+        let mut factors = health.factors;
+        factors.sort_by(|a, b| {
+            let moved = |f: &HealthFactor| f.delta().map(f64::abs);
+            moved(b)
+                .partial_cmp(&moved(a))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        Ok(HealthComparison {
+            machine_id: machine_id.to_string(),
+            window_secs: window.num_seconds(),
+            current_score: health.overall_score,
+            baseline_score,
+            trend: baseline_score.map(|b| HealthTrend::from_delta(health.overall_score - b)),
+            factors,
+        })
+    }
+
+    /// Latest stored health for a machine plus the snapshot time it came from
+    fn latest_health(&self, machine_id: &str) -> Result<(HealthScore, Option<String>), QueryError> {
         let sql = format!(
             "SELECT overall_score, worst_factor_id, details_json, collected_at \
              FROM health_summary \
@@ -353,17 +982,11 @@ impl<'a> QueryBuilder<'a> {
         );
         let rows = self.store.query_json(&sql)?;
         if rows.is_empty() {
-            return Ok(HealthScore {
-                machine_id: machine_id.to_string(),
-                overall_score: 1.0,
-                factors: vec![],
-                worst_factor: None,
-            });
+            return Ok((HealthScore::new(machine_id, 1.0), None));
         }
 
         let row = &rows[0];
         let overall_score = row["overall_score"].as_f64().unwrap_or(1.0);
-        let worst_factor = row["worst_factor_id"].as_str().map(String::from);
         let collected_at = row["collected_at"].as_str().unwrap_or("");
 
         // Load factors from health_factors table
@@ -376,38 +999,88 @@ impl<'a> QueryBuilder<'a> {
             vc_store::escape_sql_literal(collected_at)
         );
         let factor_rows = self.store.query_json(&factors_sql)?;
-        let factors: Vec<HealthFactor> = factor_rows
+        let factors: Vec<HealthFactor> = factor_rows.iter().map(stored_health_factor).collect();
+
+        let mut health = HealthScore::new(machine_id, overall_score).with_factors(factors);
+        health.worst_factor = row["worst_factor_id"].as_str().map(String::from);
+        Ok((health, Some(collected_at.to_string())))
+    }
+
+    /// Machines whose latest health summary was scored under a different
+    /// [`HEALTH_SCORING_VERSION`] than the one before it, so a jump in their
+    /// score may come from the upgrade rather than the machine
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn scoring_version_changes(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<ScoringVersionChange>, QueryError> {
+        let machine_filter = machine_id.map_or_else(String::new, |id| {
+            format!("WHERE machine_id = '{}'", vc_store::escape_sql_literal(id))
+        });
+        let sql = format!(
+            "SELECT machine_id, scoring_version, previous_version FROM ( \
+                 SELECT machine_id, scoring_version, \
+                 LAG(scoring_version) OVER ( \
+                     PARTITION BY machine_id ORDER BY collected_at \
+                 ) AS previous_version, \
+                 ROW_NUMBER() OVER ( \
+                     PARTITION BY machine_id ORDER BY collected_at DESC \
+                 ) AS rn \
+                 FROM health_summary {machine_filter} \
+             ) latest \
+             WHERE rn = 1 AND previous_version <> scoring_version \
+             ORDER BY machine_id"
+        );
+        Ok(self
+            .store
+            .query_json(&sql)?
             .iter()
-            .map(|r| {
-                let factor_id = r["factor_id"].as_str().unwrap_or("unknown").to_string();
-                let severity_str = r["severity"].as_str().unwrap_or("healthy");
-
-                let details_json_str = r["details_json"].as_str().unwrap_or("{}");
-                let parsed_details: serde_json::Value = serde_json::from_str(details_json_str)
-                    .unwrap_or_else(|_| serde_json::json!({}));
-                let name = parsed_details["name"]
-                    .as_str()
-                    .unwrap_or(&factor_id.replace('_', " "))
-                    .to_string();
-                let details = parsed_details["details"].as_str().unwrap_or("").to_string();
-
-                HealthFactor {
-                    name,
-                    factor_id,
-                    score: r["score"].as_f64().unwrap_or(1.0),
-                    weight: r["weight"].as_f64().unwrap_or(1.0),
-                    severity: severity_str.parse().unwrap_or(Severity::Healthy),
-                    details,
-                }
+            .filter_map(|row| {
+                Some(ScoringVersionChange {
+                    machine_id: row["machine_id"].as_str()?.to_string(),
+                    previous_version: row["previous_version"].as_i64()?,
+                    scoring_version: row["scoring_version"].as_i64()?,
+                })
             })
+            .collect())
+    }
+
+    /// Fill in `baseline_score` and `trend` from the factor history in the
+    /// `window` leading up to (but excluding) the `collected_at` snapshot
+    fn apply_factor_baselines(
+        &self,
+        health: &mut HealthScore,
+        collected_at: &str,
+        window: chrono::Duration,
+    ) -> Result<(), QueryError> {
+        if health.factors.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!(
+            "SELECT factor_id, AVG(score) AS avg_score, COUNT(*) AS samples \
+             FROM health_factors \
+             WHERE machine_id = '{}' AND collected_at >= '{}' AND collected_at < '{}' \
+             GROUP BY factor_id",
+            vc_store::escape_sql_literal(&health.machine_id),
+            vc_store::escape_sql_literal(&baseline_start(collected_at, window)),
+            vc_store::escape_sql_literal(collected_at)
+        );
+        let baselines: std::collections::HashMap<String, f64> = self
+            .store
+            .query_json(&sql)?
+            .iter()
+            .filter_map(|r| Some((r["factor_id"].as_str()?.to_string(), baseline_average(r)?)))
             .collect();
 
-        Ok(HealthScore {
-            machine_id: machine_id.to_string(),
-            overall_score,
-            factors,
-            worst_factor,
-        })
+        for factor in &mut health.factors {
+            factor.baseline_score = baselines.get(&factor.factor_id).copied();
+            factor.trend = factor.delta().map(HealthTrend::from_delta);
+        }
+        Ok(())
     }
 
     /// Compute and persist health factors and summary for a machine.
@@ -423,15 +1096,7 @@ impl<'a> QueryBuilder<'a> {
     ) -> Result<HealthScore, QueryError> {
         let overall_score = compute_overall_score(factors);
         let now = Utc::now().to_rfc3339();
-
-        let worst = factors
-            .iter()
-            .min_by(|a, b| {
-                a.score
-                    .partial_cmp(&b.score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
-            .map(|f| f.factor_id.clone());
+        let worst = worst_factor_id(factors);
 
         let critical_count = factors
             .iter()
@@ -486,6 +1151,7 @@ impl<'a> QueryBuilder<'a> {
             "critical_count": critical_count,
             "warning_count": warning_count,
             "details_json": details_str,
+            "scoring_version": HEALTH_SCORING_VERSION,
         });
 
         self.store.upsert_json(
@@ -494,12 +1160,9 @@ impl<'a> QueryBuilder<'a> {
             &["machine_id", "collected_at"],
         )?;
 
-        Ok(HealthScore {
-            machine_id: machine_id.to_string(),
-            overall_score,
-            factors: factors.to_vec(),
-            worst_factor: worst,
-        })
+        let mut score = HealthScore::new(machine_id, overall_score).with_factors(factors.to_vec());
+        score.worst_factor = worst;
+        Ok(score)
     }
 
     /// List all stored health summaries (latest per machine)
@@ -510,7 +1173,7 @@ impl<'a> QueryBuilder<'a> {
     pub fn list_health_summaries(&self) -> Result<Vec<serde_json::Value>, QueryError> {
         let sql = "SELECT hs.machine_id, hs.overall_score, hs.worst_factor_id, \
                    hs.factor_count, hs.critical_count, hs.warning_count, \
-                   CAST(hs.collected_at AS TEXT) AS collected_at \
+                   hs.scoring_version, CAST(hs.collected_at AS TEXT) AS collected_at \
                    FROM health_summary hs \
                    INNER JOIN ( \
                        SELECT machine_id, MAX(collected_at) AS max_ts \
@@ -520,6 +1183,71 @@ impl<'a> QueryBuilder<'a> {
         Ok(self.store.query_json(sql)?)
     }
 
+    /// Overall health scores for a machine collected since `since`, oldest
+    /// first; the raw series behind `vc health score --trend`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn health_history(
+        &self,
+        machine_id: &str,
+        since: chrono::DateTime<Utc>,
+    ) -> Result<Vec<(chrono::DateTime<Utc>, f64)>, QueryError> {
+        let sql = format!(
+            "SELECT CAST(collected_at AS TEXT) AS collected_at, overall_score \
+             FROM health_summary \
+             WHERE machine_id = '{}' \
+               AND TRY_CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP) \
+             ORDER BY TRY_CAST(collected_at AS TIMESTAMP)",
+            vc_store::escape_sql_literal(machine_id),
+            since.format("%Y-%m-%d %H:%M:%S")
+        );
+        Ok(self
+            .store
+            .query_json(&sql)?
+            .iter()
+            .filter_map(|row| {
+                let at = timefmt::parse_timestamp(row["collected_at"].as_str()?)?;
+                Some((at, row["overall_score"].as_f64()?))
+            })
+            .collect())
+    }
+
+    /// Alerts fired since `since`, one sample of 1.0 per alert, grouped by
+    /// machine; alerts without a machine are left out
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn alert_samples_by_machine(
+        &self,
+        since: chrono::DateTime<Utc>,
+    ) -> Result<std::collections::BTreeMap<String, Vec<(chrono::DateTime<Utc>, f64)>>, QueryError>
+    {
+        let sql = format!(
+            "SELECT machine_id, CAST(fired_at AS TEXT) AS fired_at FROM alert_history \
+             WHERE machine_id IS NOT NULL \
+               AND TRY_CAST(fired_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP)",
+            since.format("%Y-%m-%d %H:%M:%S")
+        );
+        let mut samples: std::collections::BTreeMap<String, Vec<_>> =
+            std::collections::BTreeMap::new();
+        for row in self.store.query_json(&sql)? {
+            let (Some(machine_id), Some(at)) = (
+                row["machine_id"].as_str(),
+                row["fired_at"].as_str().and_then(timefmt::parse_timestamp),
+            ) else {
+                continue;
+            };
+            samples
+                .entry(machine_id.to_string())
+                .or_default()
+                .push((at, 1.0));
+        }
+        Ok(samples)
+    }
+
     /// Get recent alerts
     ///
     /// # Errors
@@ -539,6 +1267,136 @@ impl<'a> QueryBuilder<'a> {
         let sql = "SELECT * FROM machines ORDER BY hostname";
         Ok(self.store.query_json(sql)?)
     }
+
+    /// Success rates of classified sessions that ended within `window`,
+    /// grouped by agent type and/or repository and compared with the window
+    /// before. Groups with the most sessions come first; groups that only
+    /// had sessions in the earlier window are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn session_success_rates(
+        &self,
+        group_by: SessionGroupBy,
+        window: chrono::Duration,
+    ) -> Result<Vec<SessionSuccessRate>, QueryError> {
+        let current_start = Utc::now() - window;
+        let previous_start = current_start - window;
+        // `ended_at` is TEXT in more than one rendering; the date prefix they
+        // share narrows the scan and the exact cut is made after parsing.
+        let sql = format!(
+            "SELECT program, repo_path, outcome, CAST(ended_at AS TEXT) AS ended_at \
+             FROM agent_sessions \
+             WHERE outcome IS NOT NULL AND ended_at >= '{}'",
+            (previous_start - chrono::Duration::days(1)).format("%Y-%m-%d")
+        );
+
+        let mut groups: std::collections::BTreeMap<
+            (Option<String>, Option<String>),
+            (OutcomeTally, OutcomeTally),
+        > = std::collections::BTreeMap::new();
+        for row in self.store.query_json(&sql)? {
+            let (Some(outcome), Some(ended_at)) = (
+                row["outcome"].as_str(),
+                row["ended_at"].as_str().and_then(timefmt::parse_timestamp),
+            ) else {
+                continue;
+            };
+            if ended_at < previous_start {
+                continue;
+            }
+            let agent = row["program"].as_str().map(str::to_string);
+            let repo = row["repo_path"]
+                .as_str()
+                .map(|path| path.trim_end_matches('/'))
+                .and_then(|path| path.rsplit('/').next())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string);
+            let key = match group_by {
+                SessionGroupBy::Agent => (agent, None),
+                SessionGroupBy::Repo => (None, repo),
+                SessionGroupBy::AgentRepo => (agent, repo),
+            };
+            let (current, previous) = groups.entry(key).or_default();
+            if ended_at >= current_start {
+                tally_outcome(current, outcome);
+            } else {
+                tally_outcome(previous, outcome);
+            }
+        }
+
+        let mut rates: Vec<SessionSuccessRate> = groups
+            .into_iter()
+            .filter_map(|((agent, repo), (current, previous))| {
+                let current_pct = success_pct(&current)?;
+                let previous_pct = success_pct(&previous);
+                let name = |part: &Option<String>| part.as_deref().unwrap_or("unknown").to_string();
+                let group = match group_by {
+                    SessionGroupBy::Agent => name(&agent),
+                    SessionGroupBy::Repo => name(&repo),
+                    SessionGroupBy::AgentRepo => format!("{} on {}", name(&agent), name(&repo)),
+                };
+                Some(SessionSuccessRate {
+                    group,
+                    agent,
+                    repo,
+                    sessions: current.iter().sum(),
+                    success: current[0],
+                    partial: current[1],
+                    failed: current[2],
+                    abandoned: current[3],
+                    success_pct: current_pct,
+                    previous_success_pct: previous_pct,
+                    change_points: previous_pct.map(|previous| current_pct - previous),
+                })
+            })
+            .collect();
+        rates.sort_by(|a, b| {
+            b.sessions
+                .cmp(&a.sessions)
+                .then_with(|| a.group.cmp(&b.group))
+        });
+        Ok(rates)
+    }
+
+    /// Sessions, commits and leftover changes per repository over `window`,
+    /// with work an agent left uncommitted for [`repos::DEFAULT_STALE_DAYS`]
+    /// flagged stale. See [`repos`] for how sessions and commits are
+    /// attributed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn repo_activity(&self, window: chrono::Duration) -> Result<Vec<RepoActivity>, QueryError> {
+        repos::repo_activity(
+            self.store,
+            window,
+            chrono::Duration::days(repos::DEFAULT_STALE_DAYS),
+            Utc::now(),
+        )
+    }
+
+    /// Days until each mount is full, fitted over `options.window` of
+    /// filesystem samples, soonest first. See [`forecast`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if query execution fails.
+    pub fn disk_forecast(&self, options: &DiskForecastOptions) -> Result<DiskForecast, QueryError> {
+        forecast::disk_forecast(self.store, options, Utc::now())
+    }
+
+    /// Alerts fired and resolved by severity, and incidents opened and
+    /// closed, per UTC day over the last `window_days` days. See
+    /// [`burndown`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if the window is out of range or a query fails.
+    pub fn burndown(&self, window_days: u32) -> Result<Burndown, QueryError> {
+        burndown::burndown(self.store, window_days, Utc::now())
+    }
 }
 
 #[cfg(test)]
@@ -581,12 +1439,7 @@ mod tests {
     // HealthScore tests
     #[test]
     fn test_health_score_creation() {
-        let score = HealthScore {
-            machine_id: "test-machine".to_string(),
-            overall_score: 0.85,
-            factors: vec![],
-            worst_factor: None,
-        };
+        let score = HealthScore::new("test-machine", 0.85);
         assert_eq!(score.machine_id, "test-machine");
         assert!((score.overall_score - 0.85).abs() < f64::EPSILON);
     }
@@ -601,6 +1454,8 @@ mod tests {
                 weight: 1.0,
                 severity: Severity::Healthy,
                 details: "CPU is fine".to_string(),
+                baseline_score: None,
+                trend: None,
             },
             HealthFactor {
                 factor_id: "mem".to_string(),
@@ -609,15 +1464,14 @@ mod tests {
                 weight: 1.0,
                 severity: Severity::Warning,
                 details: "Memory usage high".to_string(),
+                baseline_score: None,
+                trend: None,
             },
         ];
 
-        let score = HealthScore {
-            machine_id: "machine1".to_string(),
-            overall_score: 0.75,
-            factors: factors.clone(),
-            worst_factor: Some("mem".to_string()),
-        };
+        let score = HealthScore::new("machine1", 0.75)
+            .with_factors(factors.clone())
+            .with_worst_factor("mem");
 
         assert_eq!(score.factors.len(), 2);
         assert_eq!(score.worst_factor, Some("mem".to_string()));
@@ -625,12 +1479,7 @@ mod tests {
 
     #[test]
     fn test_health_score_serialization() {
-        let score = HealthScore {
-            machine_id: "m1".to_string(),
-            overall_score: 1.0,
-            factors: vec![],
-            worst_factor: None,
-        };
+        let score = HealthScore::new("m1", 1.0);
 
         let json = serde_json::to_string(&score).unwrap();
         assert!(json.contains("\"machine_id\":\"m1\""));
@@ -647,6 +1496,8 @@ mod tests {
             weight: 2.0,
             severity: Severity::Critical,
             details: "Disk almost full".to_string(),
+            baseline_score: None,
+            trend: None,
         };
 
         assert_eq!(factor.factor_id, "disk");
@@ -657,17 +1508,15 @@ mod tests {
     // FleetOverview tests
     #[test]
     fn test_fleet_overview_defaults() {
-        let overview = FleetOverview {
-            total_machines: 5,
-            online_machines: 4,
-            offline_machines: 1,
-            total_agents: 20,
-            active_agents: 18,
-            fleet_health_score: 0.9,
-            worst_machine: Some("machine3".to_string()),
-            active_alerts: 2,
-            pending_approvals: 0,
-        };
+        let mut overview = FleetOverview::default();
+        overview.total_machines = 5;
+        overview.online_machines = 4;
+        overview.offline_machines = 1;
+        overview.total_agents = 20;
+        overview.active_agents = 18;
+        overview.fleet_health_score = 0.9;
+        overview.worst_machine = Some("machine3".to_string());
+        overview.active_alerts = 2;
 
         assert_eq!(overview.total_machines, 5);
         assert_eq!(
@@ -679,17 +1528,11 @@ mod tests {
 
     #[test]
     fn test_fleet_overview_serialization() {
-        let overview = FleetOverview {
-            total_machines: 1,
-            online_machines: 1,
-            offline_machines: 0,
-            total_agents: 5,
-            active_agents: 5,
-            fleet_health_score: 1.0,
-            worst_machine: None,
-            active_alerts: 0,
-            pending_approvals: 0,
-        };
+        let mut overview = FleetOverview::default();
+        overview.total_machines = 1;
+        overview.online_machines = 1;
+        overview.total_agents = 5;
+        overview.active_agents = 5;
 
         let json = serde_json::to_string(&overview).unwrap();
         let parsed: FleetOverview = serde_json::from_str(&json).unwrap();
@@ -744,6 +1587,238 @@ mod tests {
         assert!(overview.fleet_health_score < 1.0);
     }
 
+    #[test]
+    fn test_fleet_overview_by_tag_reconciles_with_totals() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                r#"
+                INSERT INTO machines (machine_id, hostname, status, tags) VALUES
+                    ('b1', 'b1', 'online', '["builder"]'),
+                    ('b2', 'b2', 'offline', '["builder", "gpu"]'),
+                    ('g1', 'g1', 'online', '["gpu"]'),
+                    ('l1', 'l1', 'online', NULL),
+                    ('l2', 'l2', 'maintenance', '[]');
+                INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id)
+                VALUES (1, 'r1', '2026-01-01 00:00:00', 'critical', 'Disk full', 'b2'),
+                       (2, 'r2', '2026-01-01 00:00:00', 'warning', 'Hot', 'b1');
+                "#,
+            )
+            .unwrap();
+        let builder = QueryBuilder::new(&store);
+        builder
+            .persist_health_score(
+                "b2",
+                &[make_factor("sys_disk", 0.2, 2.0, Severity::Warning)],
+            )
+            .unwrap();
+
+        let overview = builder.fleet_overview().unwrap();
+        let tags: Vec<&str> = overview.by_tag.iter().map(|g| g.tag.as_str()).collect();
+        assert_eq!(tags, ["builder", "gpu", UNTAGGED_GROUP]);
+
+        let builder_group = &overview.by_tag[0];
+        assert_eq!(builder_group.total_machines, 2);
+        assert_eq!(builder_group.online_machines, 1);
+        assert_eq!(builder_group.offline_machines, 1);
+        assert_eq!(builder_group.active_alerts, 2);
+        assert_eq!(builder_group.worst_machine.as_deref(), Some("b2"));
+        assert!(builder_group.health_score < 1.0);
+        let untagged = &overview.by_tag[2];
+        assert_eq!(untagged.total_machines, 2);
+        assert_eq!(untagged.maintenance_machines, 1);
+        assert!((untagged.health_score - 1.0).abs() < f64::EPSILON);
+
+        // Every machine lands in at least one group; one with n tags counts
+        // n times, so the group totals add up to the tag memberships.
+        let memberships: usize = [1, 2, 1, 1, 1].iter().sum();
+        let grouped: usize = overview.by_tag.iter().map(|g| g.total_machines).sum();
+        assert_eq!(grouped, memberships);
+        let tagged = overview.total_machines - untagged.total_machines;
+        assert_eq!(tagged, 3);
+        let online: usize = overview.by_tag.iter().map(|g| g.online_machines).sum();
+        assert_eq!(online, overview.online_machines);
+        // b2 is the only machine counted twice, and it has one alert
+        let alerts: usize = overview.by_tag.iter().map(|g| g.active_alerts).sum();
+        assert_eq!(alerts, overview.active_alerts + 1);
+    }
+
+    #[test]
+    fn test_pending_approvals_match_fleet_overview() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                r"
+                INSERT INTO guardian_runs (id, playbook_id, started_at, status)
+                VALUES (1, 'pb', '2026-01-01 00:00:00', 'pending_approval');
+                INSERT INTO guardian_runs (id, playbook_id, started_at, status)
+                VALUES (2, 'pb', '2026-01-01 01:00:00', 'success');
+                INSERT INTO playbook_drafts (draft_id, name, alert_type, trigger_json, steps_json, created_at)
+                VALUES ('d1', 'Draft', 'disk', '{}', '[]', '2026-01-01 00:00:00');
+                INSERT INTO playbook_drafts (draft_id, name, alert_type, trigger_json, steps_json, status)
+                VALUES ('d2', 'Draft', 'disk', '{}', '[]', 'rejected');
+                ",
+            )
+            .unwrap();
+
+        let builder = QueryBuilder::new(&store);
+        let pending = builder.pending_approvals(1, 0).unwrap();
+        assert_eq!(pending.runs.len(), 1);
+        assert_eq!(pending.runs[0]["id"], 1);
+        assert!(pending.runs[0]["age_secs"].as_i64().unwrap() > 0);
+        assert_eq!(pending.drafts.len(), 1);
+        assert_eq!(pending.drafts[0]["draft_id"], "d1");
+        assert_eq!(pending.total(), 2);
+        assert_eq!(builder.fleet_overview().unwrap().pending_approvals, 2);
+    }
+
+    #[test]
+    fn test_fleet_overview_without_optional_tables() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "DROP TABLE guardian_runs; DROP TABLE agent_sessions; DROP TABLE health_summary;",
+            )
+            .unwrap();
+
+        let overview = QueryBuilder::new(&store).fleet_overview().unwrap();
+        assert_eq!(overview.pending_approvals, 0);
+        assert_eq!(overview.total_agents, 0);
+        assert!((overview.fleet_health_score - 1.0).abs() < f64::EPSILON);
+        assert_eq!(
+            overview.missing_capabilities,
+            vec!["agent_sessions", "guardian_runs", "health_summary"]
+        );
+    }
+
+    #[test]
+    fn test_fleet_overview_counts_agents_from_inventory() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO agent_sessions (machine_id, session_id, program) \
+                 VALUES ('orko', 's1', 'claude-code')",
+            )
+            .unwrap();
+        let builder = QueryBuilder::new(&store);
+        assert_eq!(builder.fleet_overview().unwrap().active_agents, 1);
+
+        let agent = |key: &str| serde_json::json!({"agent_key": key, "agent_type": "claude"});
+        store
+            .record_agent_inventory(
+                "orko",
+                "2026-10-16T10:00:00Z",
+                &[agent("pid:1"), agent("pid:2")],
+            )
+            .unwrap();
+        store
+            .record_agent_inventory("orko", "2026-10-16T10:05:00Z", &[agent("pid:2")])
+            .unwrap();
+        let overview = builder.fleet_overview().unwrap();
+        assert_eq!(overview.total_agents, 2);
+        assert_eq!(overview.active_agents, 1);
+    }
+
+    #[test]
+    fn test_session_success_rates_compare_with_previous_window() {
+        let store = VcStore::open_memory().unwrap();
+        let ago = |hours: i64| (Utc::now() - chrono::Duration::hours(hours)).to_rfc3339();
+        let mut values = Vec::new();
+        // This week: 3 of 4 succeeded. The week before: 4 of 4.
+        for (i, (hours, outcome)) in [
+            (1, "success"),
+            (2, "success"),
+            (3, "success"),
+            (4, "failed"),
+            (200, "success"),
+            (201, "success"),
+            (202, "success"),
+            (203, "success"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            values.push(format!(
+                "('m1', 's{i}', 'claude-code', '/data/projects/vibe_cockpit/', '{}', '{outcome}')",
+                ago(*hours)
+            ));
+        }
+        values.push(format!(
+            "('m1', 'c1', 'codex', '/data/projects/other', '{}', 'abandoned')",
+            ago(5)
+        ));
+        values.push(format!(
+            "('m1', 'u1', 'codex', '/data/projects/other', '{}', NULL)",
+            ago(5)
+        ));
+        store
+            .execute_batch(&format!(
+                "INSERT INTO agent_sessions \
+                   (machine_id, session_id, program, repo_path, ended_at, outcome) VALUES {};",
+                values.join(", ")
+            ))
+            .unwrap();
+
+        let qb = QueryBuilder::new(&store);
+        let rates = qb
+            .session_success_rates(SessionGroupBy::AgentRepo, chrono::Duration::days(7))
+            .unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].group, "claude-code on vibe_cockpit");
+        assert_eq!((rates[0].sessions, rates[0].failed), (4, 1));
+        assert!((rates[0].success_pct - 75.0).abs() < 1e-9);
+        assert_eq!(
+            rates[0].summary(),
+            "claude-code on vibe_cockpit: 75% success over 4 session(s), down 25 points"
+        );
+        assert_eq!(rates[1].group, "codex on other");
+        assert_eq!(rates[1].abandoned, 1);
+        assert!(rates[1].previous_success_pct.is_none());
+
+        let by_repo = qb
+            .session_success_rates(SessionGroupBy::Repo, chrono::Duration::days(7))
+            .unwrap();
+        assert_eq!(by_repo[0].group, "vibe_cockpit");
+        assert!("agent-repo".parse::<SessionGroupBy>().is_ok());
+        assert!("model".parse::<SessionGroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_fleet_versions_marks_outliers_and_minimums() {
+        let store = VcStore::open_memory().unwrap();
+        for (machine, version) in [("m1", "0.5.0"), ("m2", "0.5.0"), ("m3", "0.4.2")] {
+            store
+                .record_tool_version(machine, "sysmoni", version, "probe")
+                .unwrap();
+        }
+        store
+            .record_tool_version("m1", "vc-node", "0.1.0", "bundle")
+            .unwrap();
+
+        let mut min_versions = std::collections::HashMap::new();
+        min_versions.insert("sysmoni".to_string(), "0.5.0".to_string());
+        let spreads = QueryBuilder::new(&store)
+            .fleet_versions(None, &min_versions)
+            .unwrap();
+        assert_eq!(spreads.len(), 2);
+
+        let sysmoni = &spreads[0];
+        assert_eq!(sysmoni.tool, "sysmoni");
+        assert_eq!(sysmoni.majority_version, "0.5.0");
+        assert_eq!(sysmoni.versions[0].machines, vec!["m1", "m2"]);
+        assert!(!sysmoni.versions[0].outlier);
+        assert!(!sysmoni.versions[0].below_minimum);
+        assert_eq!(sysmoni.versions[1].machines, vec!["m3"]);
+        assert!(sysmoni.versions[1].outlier);
+        assert!(sysmoni.versions[1].below_minimum);
+
+        let only_node = QueryBuilder::new(&store)
+            .fleet_versions(Some("vc-node"), &min_versions)
+            .unwrap();
+        assert_eq!(only_node.len(), 1);
+        assert_eq!(only_node[0].minimum_version, None);
+    }
+
     #[test]
     fn test_query_builder_machine_health() {
         let store = VcStore::open_memory().unwrap();
@@ -822,6 +1897,8 @@ mod tests {
             weight,
             severity,
             details: String::new(),
+            baseline_score: None,
+            trend: None,
         }
     }
 
@@ -1122,4 +2199,163 @@ mod tests {
         assert!((health.overall_score - 1.0).abs() < f64::EPSILON);
         assert!(health.factors.is_empty());
     }
+
+    /// Seed one past health snapshot for `machine_id` per entry in `hours` (hours ago)
+    fn seed_health_history(store: &VcStore, machine_id: &str, cpu: f64, disk: f64, hours: &[i64]) {
+        for h in hours {
+            let at = (Utc::now() - chrono::Duration::hours(*h)).to_rfc3339();
+            let factors: Vec<serde_json::Value> = [("sys_cpu", cpu), ("sys_disk", disk)]
+                .iter()
+                .map(|(id, score)| {
+                    serde_json::json!({
+                        "machine_id": machine_id,
+                        "collected_at": at,
+                        "factor_id": id,
+                        "severity": "healthy",
+                        "score": score,
+                        "weight": 1.0,
+                        "details_json": "{}",
+                    })
+                })
+                .collect();
+            store
+                .upsert_json(
+                    "health_factors",
+                    &factors,
+                    &["machine_id", "collected_at", "factor_id"],
+                )
+                .unwrap();
+            store
+                .upsert_json(
+                    "health_summary",
+                    &[serde_json::json!({
+                        "machine_id": machine_id,
+                        "collected_at": at,
+                        "overall_score": f64::midpoint(cpu, disk),
+                        "details_json": "{}",
+                    })],
+                    &["machine_id", "collected_at"],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_health_trend_from_delta() {
+        assert_eq!(HealthTrend::from_delta(0.2), HealthTrend::Improving);
+        assert_eq!(HealthTrend::from_delta(0.01), HealthTrend::Stable);
+        assert_eq!(HealthTrend::from_delta(-0.01), HealthTrend::Stable);
+        assert_eq!(HealthTrend::from_delta(-0.3), HealthTrend::Degrading);
+    }
+
+    #[test]
+    fn test_machine_health_without_history_has_no_baseline() {
+        let store = VcStore::open_memory().unwrap();
+        let qb = QueryBuilder::new(&store);
+
+        qb.persist_health_score("m1", &[make_factor("sys_cpu", 0.9, 1.0, Severity::Healthy)])
+            .unwrap();
+
+        let health = qb.machine_health("m1").unwrap();
+        assert_eq!(health.factors.len(), 1);
+        assert!(health.factors[0].baseline_score.is_none());
+        assert!(health.factors[0].trend.is_none());
+
+        let json = serde_json::to_value(&health).unwrap();
+        assert!(json["factors"][0]["baseline_score"].is_null());
+    }
+
+    #[test]
+    fn test_machine_health_baseline_from_history() {
+        let store = VcStore::open_memory().unwrap();
+        let qb = QueryBuilder::new(&store);
+
+        seed_health_history(&store, "m1", 0.9, 0.8, &[24, 48, 72]);
+        // Too old for the 7-day baseline
+        seed_health_history(&store, "m1", 0.1, 0.1, &[24 * 10]);
+
+        qb.persist_health_score(
+            "m1",
+            &[
+                make_factor("sys_cpu", 0.9, 1.0, Severity::Healthy),
+                make_factor("sys_disk", 0.3, 1.0, Severity::Warning),
+            ],
+        )
+        .unwrap();
+
+        let health = qb.machine_health("m1").unwrap();
+        let cpu = health
+            .factors
+            .iter()
+            .find(|f| f.factor_id == "sys_cpu")
+            .unwrap();
+        let disk = health
+            .factors
+            .iter()
+            .find(|f| f.factor_id == "sys_disk")
+            .unwrap();
+        assert!((cpu.baseline_score.unwrap() - 0.9).abs() < 1e-9);
+        assert_eq!(cpu.trend, Some(HealthTrend::Stable));
+        assert!((disk.baseline_score.unwrap() - 0.8).abs() < 1e-9);
+        assert_eq!(disk.trend, Some(HealthTrend::Degrading));
+    }
+
+    #[test]
+    fn test_health_comparison_orders_by_movement() {
+        let store = VcStore::open_memory().unwrap();
+        let qb = QueryBuilder::new(&store);
+
+        seed_health_history(&store, "m1", 0.9, 0.8, &[1, 2, 3]);
+        qb.persist_health_score(
+            "m1",
+            &[
+                make_factor("sys_cpu", 0.85, 1.0, Severity::Healthy),
+                make_factor("sys_disk", 0.3, 1.0, Severity::Warning),
+            ],
+        )
+        .unwrap();
+
+        let cmp = qb
+            .health_comparison("m1", chrono::Duration::days(1))
+            .unwrap();
+        assert_eq!(cmp.window_secs, 86_400);
+        assert!((cmp.baseline_score.unwrap() - 0.85).abs() < 1e-9);
+        assert_eq!(cmp.trend, Some(HealthTrend::Degrading));
+        assert_eq!(cmp.factors[0].factor_id, "sys_disk");
+        assert_eq!(cmp.factors[1].factor_id, "sys_cpu");
+
+        // A window shorter than the history leaves nothing to compare against
+        let narrow = qb
+            .health_comparison("m1", chrono::Duration::minutes(30))
+            .unwrap();
+        assert!(narrow.baseline_score.is_none());
+        assert!(narrow.factors.iter().all(|f| f.baseline_score.is_none()));
+    }
+
+    #[test]
+    fn test_health_history_and_alert_samples_respect_window() {
+        let store = VcStore::open_memory().unwrap();
+        let qb = QueryBuilder::new(&store);
+        seed_health_history(&store, "m1", 0.9, 0.5, &[30, 3, 1]);
+        seed_health_history(&store, "m2", 0.1, 0.1, &[1]);
+
+        let since = Utc::now() - chrono::Duration::hours(24);
+        let history = qb.health_history("m1", since).unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].0 < history[1].0);
+        assert!((history[0].1 - 0.7).abs() < 1e-9);
+
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S");
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) \
+                 VALUES (1, 'r1', '{now}', 'warning', 'Now', 'm1'), \
+                        (2, 'r1', '2020-01-01 00:00:00', 'warning', 'Old', 'm1'), \
+                        (3, 'r2', '{now}', 'info', 'Fleet-wide', NULL);"
+            ))
+            .unwrap();
+        let alerts = qb.alert_samples_by_machine(since).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts["m1"].len(), 1);
+    }
 }
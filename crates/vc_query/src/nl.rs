@@ -13,7 +13,7 @@
 
 use crate::{
     QueryError,
-    guardrails::{GuardrailConfig, QueryValidator},
+    guardrails::{GuardrailConfig, QueryOrigin, QueryValidator},
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -682,6 +682,7 @@ pub fn explain_query(intent: QueryIntent, entities: &QueryEntities) -> String {
 pub struct NlEngine {
     store: Arc<VcStore>,
     validator: QueryValidator,
+    origin: QueryOrigin,
 }
 
 impl NlEngine {
@@ -690,14 +691,23 @@ impl NlEngine {
         Self {
             store,
             validator: QueryValidator::new(GuardrailConfig::default()),
+            origin: QueryOrigin::Operator,
         }
     }
 
+    /// Set who is asking, which picks the execution deadline
+    #[must_use]
+    pub fn with_origin(mut self, origin: QueryOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
     /// Process a natural language question and return results
     ///
     /// # Errors
     ///
-    /// Returns [`QueryError`] when query safety checks fail.
+    /// Returns [`QueryError`] when query safety checks fail or the query is
+    /// cancelled at its execution deadline.
     pub fn ask(&self, question: &str) -> Result<NlQueryResult, QueryError> {
         let intent = classify_intent(question);
         let entities = extract_entities(question);
@@ -711,8 +721,13 @@ impl NlEngine {
             )));
         }
 
-        // Execute query
-        let results = self.store.query_json(&sql).unwrap_or_default();
+        // Execute under the deadline; a missing table on a fresh store just
+        // means no results, but a cancelled query is reported
+        let results = match self.validator.execute(&self.store, &sql, self.origin) {
+            Ok(rows) => rows,
+            Err(e @ QueryError::Timeout { .. }) => return Err(e),
+            Err(_) => Vec::new(),
+        };
         let result_count = results.len();
 
         Ok(NlQueryResult {
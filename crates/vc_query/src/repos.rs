@@ -0,0 +1,542 @@
+//! Per-repository activity rollups
+//!
+//! Joins agent sessions onto the repositories `ru` tracks: a session
+//! belongs to the repository on its machine whose path contains the
+//! session's `repo_path` (the deepest one, for nested checkouts). Per
+//! repository that gives sessions run and their success rate, commits
+//! made, the working tree left behind, and the last agent and last human
+//! commit.
+//!
+//! A commit counts as an agent's when it was made while a session was
+//! running in the repository (give or take [`ATTRIBUTION_GRACE_MINS`]);
+//! every other commit is a human's. Commits come from the `recent_commits`
+//! that `ru status` reports, so repositories on an older `ru` show none.
+//!
+//! Work is stale when the working tree is dirty, an agent session touched
+//! the repository last and no session has touched it since the stale
+//! threshold. Repositories with commits or a dirty tree but no session in
+//! the window are kept as unattributed activity, and sessions in a path
+//! `ru` does not track are reported under that path.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use vc_store::VcStore;
+
+use crate::QueryError;
+use crate::timefmt::parse_timestamp;
+
+/// Days without a session before a dirty tree an agent left counts as stale
+pub const DEFAULT_STALE_DAYS: i64 = 3;
+
+/// Slack either side of a session when attributing a commit to it
+pub const ATTRIBUTION_GRACE_MINS: i64 = 10;
+
+/// Activity in one repository over a window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoActivity {
+    pub machine_id: String,
+    /// `None` for sessions in a path `ru` does not track
+    pub repo_id: Option<String>,
+    pub name: String,
+    pub path: Option<String>,
+    /// Sessions active in the window
+    pub sessions: usize,
+    /// Of those, the ones classified as a success
+    pub succeeded: usize,
+    /// Of those, the ones with any outcome yet
+    pub classified: usize,
+    /// `succeeded` over `classified`, in percent
+    pub success_pct: Option<f64>,
+    /// Commits made in the window
+    pub commits: usize,
+    /// Of those, the ones made while a session was running
+    pub agent_commits: usize,
+    /// Modified plus untracked files in the latest status snapshot
+    pub dirty_files: u64,
+    pub last_session_at: Option<DateTime<Utc>>,
+    pub last_agent_commit_at: Option<DateTime<Utc>>,
+    pub last_human_commit_at: Option<DateTime<Utc>>,
+    /// Commits or a dirty tree, but no session in the window
+    pub unattributed: bool,
+    /// Days since an agent left the tree dirty, when that makes it stale
+    pub stale_days: Option<i64>,
+}
+
+impl RepoActivity {
+    /// One line for reports:
+    /// `vc on orko: 4 session(s), 75% success, 6 commit(s) (5 agent), 3 dirty file(s)`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let mut line = format!("{} on {}: ", self.name, self.machine_id);
+        if self.unattributed {
+            line.push_str("unattributed activity");
+        } else {
+            let _ = write!(line, "{} session(s)", self.sessions);
+            if let Some(pct) = self.success_pct {
+                let _ = write!(line, ", {pct:.0}% success");
+            }
+        }
+        let _ = write!(
+            line,
+            ", {} commit(s) ({} agent)",
+            self.commits, self.agent_commits
+        );
+        if self.dirty_files > 0 {
+            let _ = write!(line, ", {} dirty file(s)", self.dirty_files);
+        }
+        line
+    }
+
+    /// `vc on orko: 3 dirty file(s) untouched for 5d`, if the work is stale
+    #[must_use]
+    pub fn stale_summary(&self) -> Option<String> {
+        let days = self.stale_days?;
+        Some(format!(
+            "{} on {}: {} dirty file(s) untouched for {days}d",
+            self.name, self.machine_id, self.dirty_files
+        ))
+    }
+}
+
+/// A repository as `ru` knows it, with its latest status
+#[derive(Debug, Default)]
+struct TrackedRepo {
+    machine_id: String,
+    repo_id: String,
+    name: Option<String>,
+    path: Option<String>,
+    dirty_files: u64,
+    commits: BTreeMap<String, DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+struct Session {
+    started: Option<DateTime<Utc>>,
+    ended: Option<DateTime<Utc>>,
+    outcome: Option<String>,
+}
+
+impl Session {
+    fn last_touch(&self) -> Option<DateTime<Utc>> {
+        self.ended.or(self.started)
+    }
+
+    fn covers(&self, ts: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let grace = chrono::Duration::minutes(ATTRIBUTION_GRACE_MINS);
+        let Some(started) = self.started.or(self.ended) else {
+            return false;
+        };
+        ts >= started - grace && ts <= self.ended.unwrap_or(now) + grace
+    }
+}
+
+/// Activity per repository over the `window` before `now`, most active
+/// first. Repositories with no sessions, no commits and a clean tree are
+/// left out.
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if a store query fails.
+pub fn repo_activity(
+    store: &VcStore,
+    window: chrono::Duration,
+    stale_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<Vec<RepoActivity>, QueryError> {
+    let since = now - window;
+    let repos = load_tracked_repos(store, since)?;
+
+    // Sessions keyed by index into `repos`, or by their own path if no
+    // tracked repository contains it
+    let mut linked: BTreeMap<usize, Vec<Session>> = BTreeMap::new();
+    let mut untracked: BTreeMap<(String, String), Vec<Session>> = BTreeMap::new();
+    let sql = "SELECT machine_id, repo_path, outcome, \
+               CAST(started_at AS TEXT) AS started_at, CAST(ended_at AS TEXT) AS ended_at \
+               FROM agent_sessions WHERE repo_path IS NOT NULL AND repo_path <> ''";
+    for row in store.query_json(sql)? {
+        let (Some(machine_id), Some(repo_path)) =
+            (row["machine_id"].as_str(), row["repo_path"].as_str())
+        else {
+            continue;
+        };
+        let session = Session {
+            started: row["started_at"].as_str().and_then(parse_timestamp),
+            ended: row["ended_at"].as_str().and_then(parse_timestamp),
+            outcome: row["outcome"].as_str().map(str::to_string),
+        };
+        match containing_repo(&repos, machine_id, repo_path) {
+            Some(index) => linked.entry(index).or_default().push(session),
+            None => untracked
+                .entry((
+                    machine_id.to_string(),
+                    repo_path.trim_end_matches('/').to_string(),
+                ))
+                .or_default()
+                .push(session),
+        }
+    }
+
+    let mut activity = Vec::new();
+    for (index, repo) in repos.into_iter().enumerate() {
+        let sessions = linked.remove(&index).unwrap_or_default();
+        let name = repo
+            .name
+            .clone()
+            .or_else(|| repo.path.as_deref().map(path_name))
+            .unwrap_or_else(|| repo.repo_id.clone());
+        let dirty_files = repo.dirty_files;
+        activity.push(rollup(
+            RepoActivity {
+                machine_id: repo.machine_id,
+                repo_id: Some(repo.repo_id),
+                name,
+                path: repo.path,
+                ..RepoActivity::default()
+            },
+            &sessions,
+            &repo.commits,
+            dirty_files,
+            since,
+            stale_after,
+            now,
+        ));
+    }
+    for ((machine_id, path), sessions) in untracked {
+        activity.push(rollup(
+            RepoActivity {
+                machine_id,
+                name: path_name(&path),
+                path: Some(path),
+                ..RepoActivity::default()
+            },
+            &sessions,
+            &BTreeMap::new(),
+            0,
+            since,
+            stale_after,
+            now,
+        ));
+    }
+
+    activity.retain(|repo| repo.sessions > 0 || repo.commits > 0 || repo.dirty_files > 0);
+    activity.sort_by(|a, b| {
+        (b.sessions + b.commits)
+            .cmp(&(a.sessions + a.commits))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.machine_id.cmp(&b.machine_id))
+    });
+    Ok(activity)
+}
+
+/// Repositories where an agent left uncommitted work and no session has
+/// been back for `stale_after`, longest untouched first
+///
+/// # Errors
+///
+/// Returns [`QueryError`] if a store query fails.
+pub fn stale_work(
+    store: &VcStore,
+    stale_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<Vec<RepoActivity>, QueryError> {
+    let mut stale: Vec<RepoActivity> = repo_activity(store, stale_after, stale_after, now)?
+        .into_iter()
+        .filter(|repo| repo.stale_days.is_some())
+        .collect();
+    stale.sort_by(|a, b| {
+        b.stale_days
+            .cmp(&a.stale_days)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(stale)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn rollup(
+    mut repo: RepoActivity,
+    sessions: &[Session],
+    commits: &BTreeMap<String, DateTime<Utc>>,
+    dirty_files: u64,
+    since: DateTime<Utc>,
+    stale_after: chrono::Duration,
+    now: DateTime<Utc>,
+) -> RepoActivity {
+    for session in sessions {
+        if session.last_touch().is_some_and(|ts| ts >= since) || session.ended.is_none() {
+            repo.sessions += 1;
+            if let Some(outcome) = &session.outcome {
+                repo.classified += 1;
+                if outcome == "success" {
+                    repo.succeeded += 1;
+                }
+            }
+        }
+    }
+    repo.success_pct =
+        (repo.classified > 0).then(|| repo.succeeded as f64 * 100.0 / repo.classified as f64);
+    repo.last_session_at = sessions.iter().filter_map(Session::last_touch).max();
+
+    for ts in commits.values().copied() {
+        let by_agent = sessions.iter().any(|session| session.covers(ts, now));
+        if ts >= since {
+            repo.commits += 1;
+            if by_agent {
+                repo.agent_commits += 1;
+            }
+        }
+        let last = if by_agent {
+            &mut repo.last_agent_commit_at
+        } else {
+            &mut repo.last_human_commit_at
+        };
+        *last = (*last).max(Some(ts));
+    }
+
+    repo.dirty_files = dirty_files;
+    repo.unattributed = repo.sessions == 0 && (repo.commits > 0 || dirty_files > 0);
+    if dirty_files > 0
+        && let Some(touched) = repo.last_session_at
+        && now - touched >= stale_after
+        && repo
+            .last_human_commit_at
+            .is_none_or(|human| human < touched)
+    {
+        repo.stale_days = Some((now - touched).num_days());
+    }
+    repo
+}
+
+/// Index of the deepest tracked repository on `machine_id` containing `path`
+fn containing_repo(repos: &[TrackedRepo], machine_id: &str, path: &str) -> Option<usize> {
+    let path = path.trim_end_matches('/');
+    repos
+        .iter()
+        .enumerate()
+        .filter(|(_, repo)| repo.machine_id == machine_id)
+        .filter_map(|(index, repo)| {
+            let root = repo.path.as_deref()?.trim_end_matches('/');
+            let inside = path == root
+                || path
+                    .strip_prefix(root)
+                    .is_some_and(|rest| rest.starts_with('/'));
+            inside.then_some((index, root.len()))
+        })
+        .max_by_key(|(_, depth)| *depth)
+        .map(|(index, _)| index)
+}
+
+fn path_name(path: &str) -> String {
+    path.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// The `repos` inventory merged with status snapshots: the latest one's
+/// dirty state, and the commits reported by any snapshot since `since`
+fn load_tracked_repos(
+    store: &VcStore,
+    since: DateTime<Utc>,
+) -> Result<Vec<TrackedRepo>, QueryError> {
+    let mut repos: BTreeMap<(String, String), TrackedRepo> = BTreeMap::new();
+    for row in store.query_json("SELECT machine_id, repo_id, path, name FROM repos")? {
+        let (Some(machine_id), Some(repo_id)) =
+            (row["machine_id"].as_str(), row["repo_id"].as_str())
+        else {
+            continue;
+        };
+        repos.insert(
+            (machine_id.to_string(), repo_id.to_string()),
+            TrackedRepo {
+                machine_id: machine_id.to_string(),
+                repo_id: repo_id.to_string(),
+                name: row["name"].as_str().map(str::to_string),
+                path: row["path"].as_str().map(str::to_string),
+                ..TrackedRepo::default()
+            },
+        );
+    }
+
+    // Latest snapshot per repository first, then the window's, so the dirty
+    // state is the latest and commits are the union
+    let latest_sql = "SELECT rs.machine_id, rs.repo_id, rs.modified_count, rs.untracked_count, \
+                      rs.raw_json, TRUE AS latest \
+                      FROM repo_status_snapshots rs \
+                      JOIN (SELECT machine_id, repo_id, MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                            FROM repo_status_snapshots GROUP BY machine_id, repo_id) l \
+                        ON rs.machine_id = l.machine_id AND rs.repo_id = l.repo_id \
+                       AND CAST(rs.collected_at AS TIMESTAMP) = l.max_ts";
+    let window_sql = format!(
+        "SELECT machine_id, repo_id, raw_json, FALSE AS latest FROM repo_status_snapshots \
+         WHERE CAST(collected_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP)",
+        since.format("%Y-%m-%d %H:%M:%S")
+    );
+    let rows = store
+        .query_json(latest_sql)?
+        .into_iter()
+        .chain(store.query_json(&window_sql)?);
+    for row in rows {
+        let (Some(machine_id), Some(repo_id)) =
+            (row["machine_id"].as_str(), row["repo_id"].as_str())
+        else {
+            continue;
+        };
+        let raw: serde_json::Value = row["raw_json"]
+            .as_str()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default();
+        let repo = repos
+            .entry((machine_id.to_string(), repo_id.to_string()))
+            .or_insert_with(|| TrackedRepo {
+                machine_id: machine_id.to_string(),
+                repo_id: repo_id.to_string(),
+                path: raw["path"].as_str().map(str::to_string),
+                ..TrackedRepo::default()
+            });
+        if row["latest"].as_bool() == Some(true) {
+            let count = |key: &str| row[key].as_u64().unwrap_or(0);
+            repo.dirty_files = count("modified_count") + count("untracked_count");
+        }
+        for commit in raw["recent_commits"].as_array().into_iter().flatten() {
+            if let (Some(sha), Some(ts)) = (
+                commit["sha"].as_str(),
+                commit["committed_at"].as_str().and_then(parse_timestamp),
+            ) {
+                repo.commits.insert(sha.to_string(), ts);
+            }
+        }
+    }
+
+    Ok(repos.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(hours_ago: i64) -> String {
+        (Utc::now() - chrono::Duration::hours(hours_ago)).to_rfc3339()
+    }
+
+    fn seed(store: &VcStore) {
+        let raw = serde_json::json!({
+            "path": "/src/vc",
+            "dirty": true,
+            "recent_commits": [
+                {"sha": "a1", "author": "agent", "committed_at": ts(2)},
+                {"sha": "h1", "author": "human", "committed_at": ts(30)},
+            ],
+        })
+        .to_string();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO repos (machine_id, repo_id, path, url, name) VALUES \
+                   ('orko', 'r-vc', '/src/vc', NULL, 'vc'), \
+                   ('orko', 'r-old', '/src/old', NULL, 'old'), \
+                   ('orko', 'r-quiet', '/src/quiet', NULL, 'quiet'); \
+                 INSERT INTO repo_status_snapshots \
+                   (machine_id, collected_at, repo_id, dirty, modified_count, untracked_count, raw_json) VALUES \
+                   ('orko', '{now}', 'r-vc', 1, 2, 1, '{raw}'), \
+                   ('orko', '{now}', 'r-old', 1, 4, 0, NULL), \
+                   ('orko', '{now}', 'r-quiet', 0, 0, 0, NULL); \
+                 INSERT INTO agent_sessions \
+                   (machine_id, session_id, program, repo_path, started_at, ended_at, outcome) VALUES \
+                   ('orko', 's1', 'claude-code', '/src/vc/crates', '{s1_start}', '{s1_end}', 'success'), \
+                   ('orko', 's2', 'claude-code', '/src/vc', '{s2_start}', '{s2_end}', 'failed'), \
+                   ('orko', 's3', 'codex', '/src/old', '{s3_start}', '{s3_end}', NULL), \
+                   ('orko', 's4', 'codex', '/tmp/scratch', '{s4_start}', '{s4_end}', 'success');",
+                now = ts(0),
+                s1_start = ts(3),
+                s1_end = ts(1),
+                s2_start = ts(10),
+                s2_end = ts(9),
+                s3_start = ts(24 * 6),
+                s3_end = ts(24 * 5),
+                s4_start = ts(5),
+                s4_end = ts(4),
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_repo_activity_rollups() {
+        let store = VcStore::open_memory().unwrap();
+        seed(&store);
+        let now = Utc::now();
+        let activity = repo_activity(
+            &store,
+            chrono::Duration::days(2),
+            chrono::Duration::days(DEFAULT_STALE_DAYS),
+            now,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = activity.iter().map(|r| r.name.as_str()).collect();
+        // The clean, quiet repo is left out; the untracked path is kept
+        assert_eq!(names, vec!["vc", "scratch", "old"]);
+
+        let vc = &activity[0];
+        assert_eq!(vc.sessions, 2);
+        assert_eq!((vc.succeeded, vc.classified), (1, 2));
+        assert_eq!(vc.success_pct, Some(50.0));
+        assert_eq!((vc.commits, vc.agent_commits), (2, 1));
+        assert_eq!(vc.dirty_files, 3);
+        assert!(vc.last_agent_commit_at.unwrap() > vc.last_human_commit_at.unwrap());
+        assert!(!vc.unattributed);
+        assert_eq!(vc.stale_days, None);
+
+        let scratch = &activity[1];
+        assert_eq!(scratch.repo_id, None);
+        assert_eq!(scratch.path.as_deref(), Some("/tmp/scratch"));
+        assert_eq!(scratch.sessions, 1);
+
+        // Its only session is outside the window
+        let old = &activity[2];
+        assert!(old.unattributed);
+        assert_eq!(old.stale_days, Some(5));
+        assert!(old.summary().contains("unattributed activity"));
+    }
+
+    #[test]
+    fn test_stale_work() {
+        let store = VcStore::open_memory().unwrap();
+        seed(&store);
+        let stale = stale_work(
+            &store,
+            chrono::Duration::days(DEFAULT_STALE_DAYS),
+            Utc::now(),
+        )
+        .unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(
+            stale[0].stale_summary().unwrap(),
+            "old on orko: 4 dirty file(s) untouched for 5d"
+        );
+
+        // A longer threshold leaves nothing stale
+        assert!(
+            stale_work(&store, chrono::Duration::days(7), Utc::now())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_containing_repo_prefers_deepest_checkout() {
+        let repo = |repo_id: &str, path: &str| TrackedRepo {
+            machine_id: "m".to_string(),
+            repo_id: repo_id.to_string(),
+            path: Some(path.to_string()),
+            ..TrackedRepo::default()
+        };
+        let repos = vec![repo("outer", "/src"), repo("inner", "/src/vc/")];
+        assert_eq!(containing_repo(&repos, "m", "/src/vc/crates"), Some(1));
+        assert_eq!(containing_repo(&repos, "m", "/src/vcx"), Some(0));
+        assert_eq!(containing_repo(&repos, "m", "/srcx"), None);
+        assert_eq!(containing_repo(&repos, "other", "/src/vc"), None);
+    }
+}
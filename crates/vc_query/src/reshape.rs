@@ -0,0 +1,352 @@
+//! Post-processing of template results
+//!
+//! Templates return whatever their SQL selects. Callers that only need a few
+//! fields, or a rollup, reshape the rows here after execution instead of
+//! needing another template:
+//!
+//! - `columns` keeps only the named fields
+//! - `aggregate` (`count`, `sum:<col>`, `avg:<col>`) collapses the rows,
+//!   optionally per distinct value of `group_by`
+//!
+//! Output is deterministic: groups appear in first-seen order, and aggregate
+//! fields are named `count`, `sum_<col>` and `avg_<col>`.
+
+use crate::ValidationError;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Aggregation applied over the result rows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum(String),
+    Avg(String),
+}
+
+impl Aggregate {
+    /// Output field holding the aggregate value
+    #[must_use]
+    pub fn output_name(&self) -> String {
+        match self {
+            Self::Count => "count".to_string(),
+            Self::Sum(column) => format!("sum_{column}"),
+            Self::Avg(column) => format!("avg_{column}"),
+        }
+    }
+
+    fn column(&self) -> Option<&str> {
+        match self {
+            Self::Count => None,
+            Self::Sum(column) | Self::Avg(column) => Some(column),
+        }
+    }
+}
+
+impl std::str::FromStr for Aggregate {
+    type Err = ValidationError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || ValidationError::InvalidAggregate {
+            reason: format!("'{value}' is not one of count, sum:<column>, avg:<column>"),
+        };
+        match value.trim().split_once(':') {
+            None if value.trim().eq_ignore_ascii_case("count") => Ok(Self::Count),
+            Some((func, column)) if !column.trim().is_empty() => {
+                let column = column.trim().to_string();
+                match func.trim().to_lowercase().as_str() {
+                    "sum" => Ok(Self::Sum(column)),
+                    "avg" => Ok(Self::Avg(column)),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Column selection and aggregation applied to a result set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reshape {
+    /// Keep only these columns (applied last, to the aggregated rows if any)
+    pub columns: Option<Vec<String>>,
+    /// Collapse rows into aggregates
+    pub aggregate: Option<Aggregate>,
+    /// Aggregate per distinct value of this column
+    pub group_by: Option<String>,
+}
+
+impl Reshape {
+    /// Parse `--columns` / `--aggregate` / `--group-by` style options
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError::InvalidAggregate`] for a malformed
+    /// aggregate, or `group_by` without an aggregate.
+    pub fn parse(
+        columns: Option<&str>,
+        aggregate: Option<&str>,
+        group_by: Option<&str>,
+    ) -> Result<Self, ValidationError> {
+        let columns = columns.map(|list| {
+            list.split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        });
+        let aggregate = aggregate.map(str::parse).transpose()?;
+        let group_by = group_by.map(|c| c.trim().to_string());
+        if group_by.is_some() && aggregate.is_none() {
+            return Err(ValidationError::InvalidAggregate {
+                reason: "group_by requires an aggregate".to_string(),
+            });
+        }
+        Ok(Self {
+            columns,
+            aggregate,
+            group_by,
+        })
+    }
+
+    /// Whether applying this reshape leaves rows untouched
+    #[must_use]
+    pub fn is_noop(&self) -> bool {
+        self.columns.is_none() && self.aggregate.is_none()
+    }
+
+    /// Apply the reshape to query result rows
+    ///
+    /// Column names are checked against the columns actually present; an
+    /// empty result has nothing to check and aggregates to a zero count.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError::UnknownColumn`] listing the available
+    /// columns, or [`ValidationError::InvalidAggregate`] when `sum`/`avg`
+    /// meets a non-numeric value.
+    pub fn apply(&self, rows: Vec<Value>) -> Result<Vec<Value>, ValidationError> {
+        let rows = match &self.aggregate {
+            Some(aggregate) => aggregate_rows(&rows, aggregate, self.group_by.as_deref())?,
+            None => rows,
+        };
+        match &self.columns {
+            Some(columns) => select_columns(rows, columns),
+            None => Ok(rows),
+        }
+    }
+}
+
+/// Column names of the first row
+fn available_columns(rows: &[Value]) -> Vec<String> {
+    rows.first()
+        .and_then(Value::as_object)
+        .map(|row| row.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn check_column(rows: &[Value], column: &str) -> Result<(), ValidationError> {
+    let available = available_columns(rows);
+    if rows.is_empty() || available.iter().any(|c| c == column) {
+        Ok(())
+    } else {
+        Err(ValidationError::UnknownColumn {
+            column: column.to_string(),
+            available,
+        })
+    }
+}
+
+fn select_columns(rows: Vec<Value>, columns: &[String]) -> Result<Vec<Value>, ValidationError> {
+    for column in columns {
+        check_column(&rows, column)?;
+    }
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let mut selected = Map::with_capacity(columns.len());
+            for column in columns {
+                selected.insert(
+                    column.clone(),
+                    row.get(column).cloned().unwrap_or(Value::Null),
+                );
+            }
+            Value::Object(selected)
+        })
+        .collect())
+}
+
+#[derive(Default)]
+struct Accumulator {
+    rows: u64,
+    values: u64,
+    sum: f64,
+}
+
+fn aggregate_rows(
+    rows: &[Value],
+    aggregate: &Aggregate,
+    group_by: Option<&str>,
+) -> Result<Vec<Value>, ValidationError> {
+    if let Some(column) = aggregate.column() {
+        check_column(rows, column)?;
+    }
+    if let Some(column) = group_by {
+        check_column(rows, column)?;
+    }
+
+    let mut groups: Vec<(Value, Accumulator)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    if group_by.is_none() {
+        groups.push((Value::Null, Accumulator::default()));
+    }
+
+    for row in rows {
+        let slot = match group_by {
+            Some(column) => {
+                let key = row.get(column).cloned().unwrap_or(Value::Null);
+                *index.entry(key.to_string()).or_insert_with(|| {
+                    groups.push((key, Accumulator::default()));
+                    groups.len() - 1
+                })
+            }
+            None => 0,
+        };
+        let acc = &mut groups[slot].1;
+        acc.rows += 1;
+
+        if let Some(column) = aggregate.column() {
+            match row.get(column) {
+                None | Some(Value::Null) => {}
+                Some(value) => {
+                    let number =
+                        value
+                            .as_f64()
+                            .ok_or_else(|| ValidationError::InvalidAggregate {
+                                reason: format!(
+                                    "column '{column}' is not numeric (found {value}); \
+                                 sum and avg need numbers"
+                                ),
+                            })?;
+                    acc.values += 1;
+                    acc.sum += number;
+                }
+            }
+        }
+    }
+
+    let name = aggregate.output_name();
+    Ok(groups
+        .into_iter()
+        .map(|(key, acc)| {
+            let mut out = Map::new();
+            if let Some(column) = group_by {
+                out.insert(column.to_string(), key);
+            }
+            #[allow(clippy::cast_precision_loss)]
+            let value = match aggregate {
+                Aggregate::Count => Value::from(acc.rows),
+                Aggregate::Sum(_) => Value::from(acc.sum),
+                Aggregate::Avg(_) if acc.values == 0 => Value::Null,
+                Aggregate::Avg(_) => Value::from(acc.sum / acc.values as f64),
+            };
+            out.insert(name.clone(), value);
+            Value::Object(out)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({"machine_id": "a", "cpu": 10.0, "state": "ok"}),
+            json!({"machine_id": "b", "cpu": 30.0, "state": "ok"}),
+            json!({"machine_id": "a", "cpu": 50.0, "state": "hot"}),
+            json!({"machine_id": "a", "cpu": null, "state": "ok"}),
+        ]
+    }
+
+    #[test]
+    fn test_select_columns() {
+        let reshape = Reshape::parse(Some("state, machine_id"), None, None).unwrap();
+        let out = reshape.apply(rows()).unwrap();
+        assert_eq!(out.len(), 4);
+        let keys: Vec<&String> = out[0].as_object().unwrap().keys().collect();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(out[2], json!({"state": "hot", "machine_id": "a"}));
+    }
+
+    #[test]
+    fn test_unknown_column_lists_available() {
+        let reshape = Reshape::parse(Some("machine_id,nope"), None, None).unwrap();
+        let err = reshape.apply(rows()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("nope"));
+        assert!(message.contains("cpu"));
+        assert!(message.contains("machine_id"));
+
+        let reshape = Reshape::parse(None, Some("sum:nope"), None).unwrap();
+        assert!(matches!(
+            reshape.apply(rows()),
+            Err(ValidationError::UnknownColumn { .. })
+        ));
+    }
+
+    #[test]
+    fn test_grouped_aggregates() {
+        let count = Reshape::parse(None, Some("count"), Some("machine_id")).unwrap();
+        assert_eq!(
+            count.apply(rows()).unwrap(),
+            vec![
+                json!({"machine_id": "a", "count": 3}),
+                json!({"machine_id": "b", "count": 1}),
+            ]
+        );
+
+        let avg = Reshape::parse(None, Some("avg:cpu"), Some("machine_id")).unwrap();
+        let out = avg.apply(rows()).unwrap();
+        assert_eq!(out[0]["avg_cpu"], json!(30.0));
+        assert_eq!(out[1]["avg_cpu"], json!(30.0));
+
+        let sum = Reshape::parse(Some("sum_cpu"), Some("sum:cpu"), None).unwrap();
+        assert_eq!(sum.apply(rows()).unwrap(), vec![json!({"sum_cpu": 90.0})]);
+    }
+
+    #[test]
+    fn test_aggregate_over_empty_results() {
+        let count = Reshape::parse(None, Some("count"), None).unwrap();
+        assert_eq!(count.apply(vec![]).unwrap(), vec![json!({"count": 0})]);
+
+        let avg = Reshape::parse(None, Some("avg:cpu"), None).unwrap();
+        assert_eq!(avg.apply(vec![]).unwrap(), vec![json!({"avg_cpu": null})]);
+
+        let grouped = Reshape::parse(None, Some("sum:cpu"), Some("machine_id")).unwrap();
+        assert!(grouped.apply(vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_non_numeric_rejected_for_sum_and_avg() {
+        for aggregate in ["sum:state", "avg:state"] {
+            let reshape = Reshape::parse(None, Some(aggregate), None).unwrap();
+            assert!(matches!(
+                reshape.apply(rows()),
+                Err(ValidationError::InvalidAggregate { .. })
+            ));
+        }
+        // Counting a text column is fine
+        let count = Reshape::parse(None, Some("count"), Some("state")).unwrap();
+        assert_eq!(count.apply(rows()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_options() {
+        assert!(Reshape::parse(None, Some("median:cpu"), None).is_err());
+        assert!(Reshape::parse(None, Some("sum:"), None).is_err());
+        assert!(Reshape::parse(None, None, Some("machine_id")).is_err());
+        assert!(Reshape::parse(None, None, None).unwrap().is_noop());
+        assert_eq!("COUNT".parse::<Aggregate>().unwrap(), Aggregate::Count);
+    }
+}
@@ -0,0 +1,412 @@
+//! Federated search: knowledge entries, incidents, session transcripts and
+//! alerts in one ranked list.
+//!
+//! Each kind is searched on its own with the same keyword match as
+//! `vc knowledge search`: every whitespace-separated term must appear
+//! (case-insensitively) in the kind's title or body text. A kind reads at
+//! most one page of its newest matches inside the window, so a wide search
+//! stays cheap; the pages are then merged and ranked in memory. A kind
+//! whose query fails is reported as a warning and the others still answer.
+//!
+//! Incident notes count as incident body text. Sessions are matched on
+//! their inline transcript only; transcripts offloaded to the artifact
+//! store are not read.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use vc_store::escape_sql_literal;
+
+use crate::timefmt::parse_timestamp;
+use crate::timeline::sortable_ts;
+use crate::{QueryBuilder, QueryError};
+
+/// Results when no limit is given
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Largest result list a caller may ask for
+pub const MAX_SEARCH_LIMIT: usize = 200;
+
+/// Characters of body text shown around the first match
+const SNIPPET_CHARS: usize = 160;
+
+/// Characters of context kept before the first match
+const SNIPPET_LEAD: usize = 60;
+
+/// What a search hit is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Knowledge,
+    Incidents,
+    Sessions,
+    Alerts,
+}
+
+impl SearchKind {
+    /// Every kind, in the order hits with equal rank are listed
+    pub const ALL: [Self; 4] = [
+        Self::Knowledge,
+        Self::Incidents,
+        Self::Sessions,
+        Self::Alerts,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Knowledge => "knowledge",
+            Self::Incidents => "incidents",
+            Self::Sessions => "sessions",
+            Self::Alerts => "alerts",
+        }
+    }
+
+    /// Table the hits come from
+    #[must_use]
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Knowledge => "knowledge_entries",
+            Self::Incidents => "incidents",
+            Self::Sessions => "agent_sessions",
+            Self::Alerts => "alert_history",
+        }
+    }
+
+    /// Rows as `ref_id, ts, machine_id, title, body`
+    fn source(self) -> &'static str {
+        match self {
+            Self::Knowledge => {
+                "SELECT CAST(id AS TEXT) AS ref_id, COALESCE(updated_at, created_at) AS ts, \
+                 CAST(NULL AS TEXT) AS machine_id, title, concat_ws(' ', summary, content) AS body \
+                 FROM knowledge_entries WHERE archived_at IS NULL"
+            }
+            Self::Incidents => {
+                "SELECT incident_id AS ref_id, started_at AS ts, CAST(NULL AS TEXT) AS machine_id, \
+                 title, concat_ws(' ', description, root_cause, resolution, \
+                   (SELECT string_agg(n.content, ' ') FROM incident_notes n \
+                    WHERE n.incident_id = i.incident_id)) AS body \
+                 FROM incidents i"
+            }
+            Self::Sessions => {
+                "SELECT session_id AS ref_id, COALESCE(started_at, collected_at) AS ts, machine_id, \
+                 concat_ws(' ', program, repo_path) AS title, raw_json AS body \
+                 FROM agent_sessions"
+            }
+            Self::Alerts => {
+                "SELECT CAST(id AS TEXT) AS ref_id, fired_at AS ts, machine_id, title, \
+                 message AS body FROM alert_history"
+            }
+        }
+    }
+
+    /// Command that shows the full record behind a hit
+    fn open_command(self, ref_id: &str, machine_id: Option<&str>) -> String {
+        match self {
+            Self::Knowledge => format!("vc knowledge show {ref_id}"),
+            Self::Incidents => format!("vc incident show {ref_id}"),
+            Self::Sessions => format!(
+                "vc query raw \"SELECT * FROM agent_sessions WHERE machine_id = '{}' \
+                 AND session_id = '{}'\"",
+                escape_sql_literal(machine_id.unwrap_or_default()),
+                escape_sql_literal(ref_id)
+            ),
+            Self::Alerts => format!("vc timeline show alerts {ref_id}"),
+        }
+    }
+}
+
+impl std::fmt::Display for SearchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SearchKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "knowledge" => Ok(Self::Knowledge),
+            "incidents" | "incident" => Ok(Self::Incidents),
+            "sessions" | "session" => Ok(Self::Sessions),
+            "alerts" | "alert" => Ok(Self::Alerts),
+            other => Err(format!(
+                "unknown search kind: {other} (expected knowledge, incidents, sessions or alerts)"
+            )),
+        }
+    }
+}
+
+/// What `QueryBuilder::search` looks for
+#[derive(Debug, Clone)]
+pub struct SearchFilter {
+    pub query: String,
+    /// Kinds to search; empty means all
+    pub kinds: Vec<SearchKind>,
+    /// Only hits at or after this time
+    pub since: DateTime<Utc>,
+    /// Hits read per kind, and hits returned in total
+    pub limit: usize,
+}
+
+impl SearchFilter {
+    /// Every kind since `since`, default limit
+    #[must_use]
+    pub fn new(query: impl Into<String>, since: DateTime<Utc>) -> Self {
+        Self {
+            query: query.into(),
+            kinds: Vec::new(),
+            since,
+            limit: DEFAULT_SEARCH_LIMIT,
+        }
+    }
+}
+
+/// One search hit, the same shape for every kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Key of the row in the kind's table
+    pub ref_id: String,
+    pub ts: String,
+    pub machine_id: Option<String>,
+    pub title: String,
+    /// Body text around the first match
+    pub snippet: String,
+    /// Higher ranks first: terms found in the title, then the whole query
+    /// found as written
+    pub score: f64,
+    /// Command that shows the full record
+    pub open: String,
+}
+
+/// Merged hits, with a warning per kind that could not be searched
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub warnings: Vec<String>,
+}
+
+/// `term` as an `ILIKE` pattern matching it anywhere
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("'%{}%'", escape_sql_literal(&escaped))
+}
+
+/// `text` with its whitespace runs collapsed to single spaces
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// 1 for a match, up to 2 more for terms in the title, 1 more when a
+/// several-word query appears as written
+fn rank(terms: &[String], query: &str, title: &str, snippet: &str) -> f64 {
+    let title = title.to_lowercase();
+    let in_title = terms.iter().filter(|term| title.contains(*term)).count();
+    let count = |n: usize| f64::from(u32::try_from(n).unwrap_or(u32::MAX));
+    let mut score = 1.0 + 2.0 * count(in_title) / count(terms.len().max(1));
+    if terms.len() > 1 && (title.contains(query) || snippet.to_lowercase().contains(query)) {
+        score += 1.0;
+    }
+    score
+}
+
+impl QueryBuilder<'_> {
+    /// Search every requested kind and merge the hits, best first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] for an empty query, or
+    /// [`QueryError`] if the store's tables cannot be listed. A kind whose
+    /// own query fails becomes a warning instead.
+    pub fn search(&self, filter: &SearchFilter) -> Result<SearchResults, QueryError> {
+        let terms: Vec<String> = filter
+            .query
+            .split_whitespace()
+            .map(str::to_lowercase)
+            .collect();
+        if terms.is_empty() {
+            return Err(QueryError::InvalidQuery(
+                "search query is empty".to_string(),
+            ));
+        }
+        let query = terms.join(" ");
+        let limit = filter.limit.clamp(1, MAX_SEARCH_LIMIT);
+        let caps = self.store.capabilities()?;
+        let since = filter.since.format("%Y-%m-%d %H:%M:%S").to_string();
+        let first = escape_sql_literal(&terms[0]);
+        let matches: Vec<String> = terms
+            .iter()
+            .map(|term| {
+                let pattern = like_pattern(term);
+                format!("(title ILIKE {pattern} ESCAPE '\\' OR body ILIKE {pattern} ESCAPE '\\')")
+            })
+            .collect();
+
+        let mut results = SearchResults::default();
+        for kind in SearchKind::ALL {
+            if !(filter.kinds.is_empty() || filter.kinds.contains(&kind))
+                || !caps.has_table(kind.table())
+            {
+                continue;
+            }
+            let ts = sortable_ts("ts");
+            let sql = format!(
+                "SELECT ref_id, CAST(ts AS TEXT) AS raw_ts, machine_id, title, \
+                 greatest(strpos(lower(COALESCE(body, '')), '{first}') - {SNIPPET_LEAD}, 1) AS snippet_start, \
+                 length(COALESCE(body, '')) AS body_len, \
+                 substr(COALESCE(body, ''), \
+                   greatest(strpos(lower(COALESCE(body, '')), '{first}') - {SNIPPET_LEAD}, 1), \
+                   {SNIPPET_CHARS}) AS excerpt \
+                 FROM ({source}) s \
+                 WHERE {ts} >= '{since}' AND {matches} \
+                 ORDER BY {ts} DESC, ref_id LIMIT {limit}",
+                source = kind.source(),
+                matches = matches.join(" AND "),
+            );
+            let rows = match self.store.query_json(&sql) {
+                Ok(rows) => rows,
+                Err(e) => {
+                    results.warnings.push(format!("{kind} search failed: {e}"));
+                    continue;
+                }
+            };
+            for row in &rows {
+                let field = |key: &str| row[key].as_str().unwrap_or_default().to_string();
+                let ref_id = field("ref_id");
+                let machine_id = row["machine_id"].as_str().map(str::to_string);
+                let title = one_line(&field("title"));
+                let mut snippet = one_line(&field("excerpt"));
+                let start = row["snippet_start"].as_i64().unwrap_or(1);
+                let body_len = row["body_len"].as_i64().unwrap_or_default();
+                if !snippet.is_empty() {
+                    if start > 1 {
+                        snippet.insert_str(0, "...");
+                    }
+                    if start + i64::try_from(SNIPPET_CHARS).unwrap_or(i64::MAX) <= body_len {
+                        snippet.push_str("...");
+                    }
+                }
+                let raw_ts = field("raw_ts");
+                results.hits.push(SearchHit {
+                    kind,
+                    score: rank(&terms, &query, &title, &snippet),
+                    ts: parse_timestamp(&raw_ts)
+                        .map_or(raw_ts, |ts| ts.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                    open: kind.open_command(&ref_id, machine_id.as_deref()),
+                    ref_id,
+                    machine_id,
+                    title,
+                    snippet,
+                });
+            }
+        }
+
+        // Stable, so equal ranks keep newest first within a kind
+        results
+            .hits
+            .sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| b.ts.cmp(&a.ts)));
+        results.hits.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vc_store::VcStore;
+
+    fn store_with_records() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO knowledge_entries (id, entry_type, title, summary, content, created_at) VALUES \
+                   (1, 'solution', 'Fixing linker OOM', 'Lower codegen units', \
+                    'When the linker runs out of memory on the build box, set codegen-units = 4.', \
+                    '2026-10-15 09:00:00'), \
+                   (2, 'pattern', 'Retry flaky tests', NULL, 'Use nextest retries.', '2026-10-15 09:00:00'); \
+                 INSERT INTO incidents (incident_id, title, severity, status, started_at) VALUES \
+                   ('inc-1', 'Build fleet degraded', 'critical', 'open', '2026-10-16T10:02:00Z'); \
+                 INSERT INTO incident_notes (id, incident_id, author, content) VALUES \
+                   (1, 'inc-1', 'ops', 'linker killed by the OOM killer on orko'); \
+                 INSERT INTO agent_sessions (machine_id, session_id, program, repo_path, started_at, raw_json) VALUES \
+                   ('orko', 's-1', 'claude', '/src/app', '2026-10-16T09:00:00Z', \
+                    '{\"messages\":[\"cargo build failed: linker OOM\"]}'); \
+                 INSERT INTO alert_history (id, rule_id, fired_at, severity, title, message, machine_id) VALUES \
+                   (1, 'memory', '2026-10-16T10:00:00Z', 'critical', 'Memory critical', 'OOM kills on orko', 'orko'), \
+                   (2, 'memory', '2026-09-01T10:00:00Z', 'critical', 'Memory critical', 'OOM kills on bender', 'bender');",
+            )
+            .unwrap();
+        store
+    }
+
+    fn since() -> DateTime<Utc> {
+        parse_timestamp("2026-10-01T00:00:00Z").unwrap()
+    }
+
+    #[test]
+    fn test_search_merges_kinds_by_rank() {
+        let store = store_with_records();
+        let results = QueryBuilder::new(&store)
+            .search(&SearchFilter::new("linker OOM", since()))
+            .unwrap();
+        assert!(results.warnings.is_empty());
+        let order: Vec<_> = results
+            .hits
+            .iter()
+            .map(|hit| format!("{}:{}", hit.kind, hit.ref_id))
+            .collect();
+        assert_eq!(order, ["knowledge:1", "sessions:s-1", "incidents:inc-1"]);
+        assert_eq!(results.hits[0].open, "vc knowledge show 1");
+        assert!(results.hits[1].snippet.contains("linker OOM"));
+        assert_eq!(results.hits[1].machine_id.as_deref(), Some("orko"));
+        assert_eq!(results.hits[2].ts, "2026-10-16T10:02:00Z");
+    }
+
+    #[test]
+    fn test_search_filters_kinds_window_and_limit() {
+        let store = store_with_records();
+        let query = QueryBuilder::new(&store);
+
+        let mut filter = SearchFilter::new("oom", since());
+        filter.kinds = vec![SearchKind::Alerts];
+        let results = query.search(&filter).unwrap();
+        assert_eq!(
+            results.hits.len(),
+            1,
+            "the September alert is outside the window"
+        );
+        assert_eq!(results.hits[0].open, "vc timeline show alerts 1");
+
+        let mut filter = SearchFilter::new("oom", since());
+        filter.limit = 2;
+        assert_eq!(query.search(&filter).unwrap().hits.len(), 2);
+
+        assert!(query.search(&SearchFilter::new("  ", since())).is_err());
+    }
+
+    #[test]
+    fn test_search_kind_failure_is_a_warning() {
+        let store = store_with_records();
+        store.execute_batch("DROP TABLE incident_notes;").unwrap();
+        let results = QueryBuilder::new(&store)
+            .search(&SearchFilter::new("oom", since()))
+            .unwrap();
+        assert_eq!(results.warnings.len(), 1);
+        assert!(results.warnings[0].starts_with("incidents search failed"));
+        assert!(
+            results
+                .hits
+                .iter()
+                .all(|hit| hit.kind != SearchKind::Incidents)
+        );
+        assert!(
+            results
+                .hits
+                .iter()
+                .any(|hit| hit.kind == SearchKind::Alerts)
+        );
+    }
+}
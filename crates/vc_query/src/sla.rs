@@ -0,0 +1,531 @@
+//! Incident SLA timers
+//!
+//! Each incident is timed against the `[incidents.sla]` targets for its
+//! current severity, always from when it started, so a severity change
+//! re-times the whole incident rather than starting a new clock. The
+//! milestones come from the incident's history:
+//!
+//! - acknowledged: the first note or `acknowledged` timeline event, or
+//!   mitigation if that came first
+//! - mitigated: the first change of status to mitigated or closed
+//! - resolved: the change of status to closed
+//!
+//! Incidents from before status changes were put on the timeline fall back
+//! to `ended_at`. A missed target is recorded once per milestone and
+//! severity as an `sla_breached` timeline event by [`record_breaches`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vc_config::IncidentConfig;
+use vc_store::VcStore;
+
+use crate::QueryError;
+use crate::timefmt::parse_timestamp;
+
+/// Timeline event type of a recorded breach
+pub const BREACH_EVENT: &str = "sla_breached";
+
+/// Most incidents looked at when scanning for breaches or attainment
+const SCAN_LIMIT: usize = 1000;
+
+/// A point an incident is timed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Milestone {
+    Ack,
+    Mitigate,
+    Resolve,
+}
+
+impl Milestone {
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ack => "ack",
+            Self::Mitigate => "mitigate",
+            Self::Resolve => "resolve",
+        }
+    }
+}
+
+/// Where a timer stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimerState {
+    /// Reached within the target
+    Met,
+    /// Reached late, or not reached and already overdue
+    Breached,
+    /// Not reached, still within the target
+    Pending,
+}
+
+/// One milestone timed against its target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaTimer {
+    pub milestone: Milestone,
+    pub target_secs: u64,
+    pub due_at: DateTime<Utc>,
+    pub reached_at: Option<DateTime<Utc>>,
+    /// From the start to the milestone, or to now while it is not reached
+    pub elapsed_secs: i64,
+    pub state: TimerState,
+}
+
+/// The SLA view of one incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentSla {
+    pub incident_id: String,
+    pub title: String,
+    pub severity: String,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub mitigated_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Empty when the severity has no targets
+    pub timers: Vec<SlaTimer>,
+}
+
+impl IncidentSla {
+    /// Timers that missed their target
+    pub fn breaches(&self) -> impl Iterator<Item = &SlaTimer> {
+        self.timers
+            .iter()
+            .filter(|timer| timer.state == TimerState::Breached)
+    }
+
+    #[must_use]
+    pub fn breached(&self) -> bool {
+        self.breaches().next().is_some()
+    }
+}
+
+/// A breach newly recorded on an incident's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaBreach {
+    pub incident_id: String,
+    pub title: String,
+    pub severity: String,
+    pub milestone: Milestone,
+    pub target_secs: u64,
+    pub due_at: DateTime<Utc>,
+}
+
+/// Met and missed targets for one severity and milestone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaAttainment {
+    pub severity: String,
+    pub milestone: Milestone,
+    pub met: usize,
+    pub breached: usize,
+    pub pending: usize,
+}
+
+impl SlaAttainment {
+    /// Share of decided timers that were met; `None` while none are decided
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rate(&self) -> Option<f64> {
+        let decided = self.met + self.breached;
+        (decided > 0).then(|| self.met as f64 / decided as f64)
+    }
+}
+
+/// Incident columns are TEXT filled from `current_timestamp`, which reads
+/// back with an offset such as `+00` that [`parse_timestamp`] does not take
+fn row_ts(row: &serde_json::Value, key: &str) -> Option<DateTime<Utc>> {
+    let raw = row[key].as_str()?.trim();
+    parse_timestamp(raw).or_else(|| {
+        DateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S%.f%#z")
+            .ok()
+            .map(|ts| ts.with_timezone(&Utc))
+    })
+}
+
+/// Time each milestone of `incident` (a row of `incidents`) given its notes
+/// and timeline. `None` if the row has no usable start time.
+#[must_use]
+pub fn evaluate(
+    incident: &serde_json::Value,
+    notes: &[serde_json::Value],
+    timeline: &[serde_json::Value],
+    config: &IncidentConfig,
+    now: DateTime<Utc>,
+) -> Option<IncidentSla> {
+    let started_at = row_ts(incident, "started_at")?;
+    let status = incident["status"].as_str().unwrap_or("open").to_string();
+    let severity = incident["severity"]
+        .as_str()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let status_reached = |statuses: &[&str]| {
+        timeline
+            .iter()
+            .filter(|event| event["event_type"] == "status_changed")
+            .filter(|event| {
+                event["details_json"]
+                    .as_str()
+                    .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+                    .and_then(|details| details["to"].as_str().map(str::to_string))
+                    .is_some_and(|to| statuses.contains(&to.as_str()))
+            })
+            .filter_map(|event| row_ts(event, "ts"))
+            .min()
+            .or_else(|| {
+                statuses
+                    .contains(&status.as_str())
+                    .then(|| row_ts(incident, "ended_at"))
+                    .flatten()
+            })
+    };
+    let resolved_at = status_reached(&["closed"]);
+    let mitigated_at = status_reached(&["mitigated", "closed"]);
+    let acknowledged_at = notes
+        .iter()
+        .filter_map(|note| row_ts(note, "created_at"))
+        .chain(
+            timeline
+                .iter()
+                .filter(|event| event["event_type"] == "acknowledged")
+                .filter_map(|event| row_ts(event, "ts")),
+        )
+        .chain(mitigated_at)
+        .min();
+
+    let targets = config.sla.get(&severity).copied().unwrap_or_default();
+    let timers = [
+        (Milestone::Ack, targets.ack_secs, acknowledged_at),
+        (Milestone::Mitigate, targets.mitigate_secs, mitigated_at),
+        (Milestone::Resolve, targets.resolve_secs, resolved_at),
+    ]
+    .into_iter()
+    .filter_map(|(milestone, target, reached_at)| {
+        let target_secs = target?;
+        let due_at = started_at.checked_add_signed(chrono::Duration::try_seconds(
+            i64::try_from(target_secs).ok()?,
+        )?)?;
+        let state = match reached_at {
+            Some(reached) if reached <= due_at => TimerState::Met,
+            Some(_) => TimerState::Breached,
+            None if now > due_at => TimerState::Breached,
+            None => TimerState::Pending,
+        };
+        Some(SlaTimer {
+            milestone,
+            target_secs,
+            due_at,
+            reached_at,
+            elapsed_secs: (reached_at.unwrap_or(now) - started_at).num_seconds(),
+            state,
+        })
+    })
+    .collect();
+
+    Some(IncidentSla {
+        incident_id: incident["incident_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        title: incident["title"].as_str().unwrap_or_default().to_string(),
+        severity,
+        status,
+        started_at,
+        acknowledged_at,
+        mitigated_at,
+        resolved_at,
+        timers,
+    })
+}
+
+/// SLA of one stored incident; `None` if there is no such incident
+///
+/// # Errors
+///
+/// Returns [`QueryError::StoreError`] if the incident cannot be read.
+pub fn incident_sla(
+    store: &VcStore,
+    incident_id: &str,
+    config: &IncidentConfig,
+    now: DateTime<Utc>,
+) -> Result<Option<IncidentSla>, QueryError> {
+    let Some(incident) = store.get_incident(incident_id)? else {
+        return Ok(None);
+    };
+    sla_of(store, &incident, config, now).map_err(QueryError::from)
+}
+
+fn sla_of(
+    store: &VcStore,
+    incident: &serde_json::Value,
+    config: &IncidentConfig,
+    now: DateTime<Utc>,
+) -> Result<Option<IncidentSla>, vc_store::StoreError> {
+    let id = incident["incident_id"].as_str().unwrap_or_default();
+    let notes = store.get_incident_notes(id)?;
+    let timeline = store.get_incident_timeline(id)?;
+    Ok(evaluate(incident, &notes, &timeline, config, now))
+}
+
+/// Incidents (optionally of one status) that missed any target, newest
+/// first, each with its SLA
+///
+/// # Errors
+///
+/// Returns [`QueryError::StoreError`] if incidents cannot be read.
+pub fn breached_incidents(
+    store: &VcStore,
+    status: Option<&str>,
+    config: &IncidentConfig,
+    now: DateTime<Utc>,
+    limit: usize,
+) -> Result<Vec<(serde_json::Value, IncidentSla)>, QueryError> {
+    let mut breached = Vec::new();
+    for incident in store.list_incidents(status, SCAN_LIMIT)? {
+        if let Some(sla) = sla_of(store, &incident, config, now)?
+            && sla.breached()
+        {
+            breached.push((incident, sla));
+            if breached.len() == limit {
+                break;
+            }
+        }
+    }
+    Ok(breached)
+}
+
+/// Post an `sla_breached` timeline event for every missed target of an
+/// open or mitigated incident that has none yet for that milestone at the
+/// current severity, and return those breaches
+///
+/// # Errors
+///
+/// Returns [`QueryError::StoreError`] if incidents cannot be read or an
+/// event cannot be written.
+pub fn record_breaches(
+    store: &VcStore,
+    config: &IncidentConfig,
+    now: DateTime<Utc>,
+) -> Result<Vec<SlaBreach>, QueryError> {
+    let mut recorded = Vec::new();
+    for status in ["open", "mitigated"] {
+        for incident in store.list_incidents(Some(status), SCAN_LIMIT)? {
+            let id = incident["incident_id"].as_str().unwrap_or_default();
+            let notes = store.get_incident_notes(id)?;
+            let timeline = store.get_incident_timeline(id)?;
+            let Some(sla) = evaluate(&incident, &notes, &timeline, config, now) else {
+                continue;
+            };
+            for timer in sla.breaches() {
+                let already = timeline.iter().any(|event| {
+                    event["event_type"] == BREACH_EVENT
+                        && event["details_json"]
+                            .as_str()
+                            .and_then(|json| serde_json::from_str::<serde_json::Value>(json).ok())
+                            .is_some_and(|details| {
+                                details["milestone"] == timer.milestone.as_str()
+                                    && details["severity"] == sla.severity.as_str()
+                            })
+                });
+                if already {
+                    continue;
+                }
+                let details = serde_json::json!({
+                    "milestone": timer.milestone,
+                    "severity": sla.severity,
+                    "target_secs": timer.target_secs,
+                    "due_at": timer.due_at.to_rfc3339(),
+                });
+                store.add_incident_timeline_event(
+                    id,
+                    BREACH_EVENT,
+                    "vc",
+                    &format!(
+                        "Missed the {} {} target of {}s",
+                        sla.severity,
+                        timer.milestone.as_str(),
+                        timer.target_secs
+                    ),
+                    Some(&details.to_string()),
+                )?;
+                recorded.push(SlaBreach {
+                    incident_id: sla.incident_id.clone(),
+                    title: sla.title.clone(),
+                    severity: sla.severity.clone(),
+                    milestone: timer.milestone,
+                    target_secs: timer.target_secs,
+                    due_at: timer.due_at,
+                });
+            }
+        }
+    }
+    Ok(recorded)
+}
+
+/// Met, breached and pending timers per severity and milestone, over the
+/// incidents that started in the `window_hours` before `now`
+///
+/// # Errors
+///
+/// Returns [`QueryError::StoreError`] if incidents cannot be read.
+pub fn attainment(
+    store: &VcStore,
+    config: &IncidentConfig,
+    window_hours: u32,
+    now: DateTime<Utc>,
+) -> Result<Vec<SlaAttainment>, QueryError> {
+    let since = now - chrono::Duration::hours(i64::from(window_hours));
+    let mut tally: BTreeMap<(String, Milestone), SlaAttainment> = BTreeMap::new();
+    for incident in store.list_incidents(None, SCAN_LIMIT)? {
+        if row_ts(&incident, "started_at").is_none_or(|started| started < since) {
+            continue;
+        }
+        let Some(sla) = sla_of(store, &incident, config, now)? else {
+            continue;
+        };
+        for timer in &sla.timers {
+            let entry = tally
+                .entry((sla.severity.clone(), timer.milestone))
+                .or_insert_with(|| SlaAttainment {
+                    severity: sla.severity.clone(),
+                    milestone: timer.milestone,
+                    met: 0,
+                    breached: 0,
+                    pending: 0,
+                });
+            match timer.state {
+                TimerState::Met => entry.met += 1,
+                TimerState::Breached => entry.breached += 1,
+                TimerState::Pending => entry.pending += 1,
+            }
+        }
+    }
+    Ok(tally.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 1, 12, minute, 0).unwrap()
+    }
+
+    fn incident(severity: &str, status: &str) -> serde_json::Value {
+        serde_json::json!({
+            "incident_id": "inc-1",
+            "title": "Disk pressure",
+            "severity": severity,
+            "status": status,
+            "started_at": "2026-10-01 12:00:00.000000+00",
+        })
+    }
+
+    fn status_event(minute: u32, to: &str) -> serde_json::Value {
+        serde_json::json!({
+            "event_type": "status_changed",
+            "ts": at(minute).to_rfc3339(),
+            "details_json": serde_json::json!({ "from": "open", "to": to }).to_string(),
+        })
+    }
+
+    fn state(sla: &IncidentSla, milestone: Milestone) -> TimerState {
+        sla.timers
+            .iter()
+            .find(|timer| timer.milestone == milestone)
+            .unwrap()
+            .state
+    }
+
+    #[test]
+    fn test_timers_from_notes_and_status_changes() {
+        let config = IncidentConfig::default();
+        let notes = vec![serde_json::json!({ "created_at": at(20).to_rfc3339() })];
+        let timeline = vec![status_event(50, "mitigated")];
+
+        let sla = evaluate(
+            &incident("critical", "mitigated"),
+            &notes,
+            &timeline,
+            &config,
+            at(55),
+        )
+        .unwrap();
+        assert_eq!(sla.acknowledged_at, Some(at(20)));
+        assert_eq!(sla.mitigated_at, Some(at(50)));
+        // 15 minute ack target missed, 1h mitigate target met, resolve pending
+        assert_eq!(state(&sla, Milestone::Ack), TimerState::Breached);
+        assert_eq!(state(&sla, Milestone::Mitigate), TimerState::Met);
+        assert_eq!(state(&sla, Milestone::Resolve), TimerState::Pending);
+
+        // Timed from the same start at the new severity's targets
+        let sla = evaluate(
+            &incident("warning", "mitigated"),
+            &notes,
+            &timeline,
+            &config,
+            at(55),
+        )
+        .unwrap();
+        assert!(!sla.breached());
+
+        // Untimed severity
+        let sla = evaluate(&incident("info", "open"), &[], &[], &config, at(55)).unwrap();
+        assert!(sla.timers.is_empty());
+    }
+
+    #[test]
+    fn test_closing_counts_as_mitigation_and_acknowledgement() {
+        let config = IncidentConfig::default();
+        let sla = evaluate(
+            &incident("critical", "closed"),
+            &[],
+            &[status_event(10, "closed")],
+            &config,
+            at(59),
+        )
+        .unwrap();
+        assert_eq!(sla.acknowledged_at, Some(at(10)));
+        assert_eq!(sla.mitigated_at, Some(at(10)));
+        assert_eq!(sla.resolved_at, Some(at(10)));
+        assert!(sla.timers.iter().all(|t| t.state == TimerState::Met));
+    }
+
+    #[test]
+    fn test_record_breaches_once_per_milestone_and_severity() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .create_incident("inc-1", "Disk pressure", "warning", None)
+            .unwrap();
+        let config = IncidentConfig::default();
+        let now = Utc::now();
+
+        assert!(record_breaches(&store, &config, now).unwrap().is_empty());
+
+        let later = now + chrono::Duration::hours(2);
+        let breaches = record_breaches(&store, &config, later).unwrap();
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].milestone, Milestone::Ack);
+        assert!(record_breaches(&store, &config, later).unwrap().is_empty());
+
+        // Raising the severity applies the tighter targets from the start
+        store.set_incident_severity("inc-1", "critical").unwrap();
+        let breaches = record_breaches(&store, &config, later).unwrap();
+        let milestones: Vec<Milestone> = breaches.iter().map(|b| b.milestone).collect();
+        assert_eq!(milestones, vec![Milestone::Ack, Milestone::Mitigate]);
+
+        let breached = breached_incidents(&store, None, &config, later, 10).unwrap();
+        assert_eq!(breached.len(), 1);
+
+        let attained = attainment(&store, &config, 24, later).unwrap();
+        let ack = attained
+            .iter()
+            .find(|a| a.milestone == Milestone::Ack)
+            .unwrap();
+        assert_eq!((ack.severity.as_str(), ack.breached), ("critical", 1));
+        assert_eq!(ack.rate(), Some(0.0));
+    }
+}
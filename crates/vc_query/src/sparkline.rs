@@ -0,0 +1,260 @@
+//! Inline sparklines for trend-capable text output
+//!
+//! A series of samples is folded into a fixed number of time buckets and each
+//! bucket becomes one character, scaled between the series minimum and
+//! maximum. Terminals without a UTF-8 locale get an ASCII ramp instead of the
+//! block characters.
+//!
+//! - Missing buckets (no samples, or a NaN) render as a blank
+//! - A flat series renders as the lowest level rather than dividing by zero
+//! - Empty and single-sample series are valid input
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+
+/// Block characters from lowest to highest
+const UNICODE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// ASCII ramp for terminals that cannot draw the blocks
+const ASCII_LEVELS: [char; 8] = ['_', '.', ',', '-', '~', '=', '*', '#'];
+
+/// Drawn for buckets with no usable value
+const MISSING: char = ' ';
+
+/// Which character set a sparkline is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparkStyle {
+    Unicode,
+    Ascii,
+}
+
+impl SparkStyle {
+    /// Unicode when the locale (`LC_ALL`, `LC_CTYPE`, then `LANG`) is UTF-8
+    #[must_use]
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+        Self::for_locale(locale.as_deref())
+    }
+
+    /// Style for a locale string such as `en_US.UTF-8`
+    #[must_use]
+    pub fn for_locale(locale: Option<&str>) -> Self {
+        let utf8 = locale.is_some_and(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("utf-8") || value.contains("utf8")
+        });
+        if utf8 { Self::Unicode } else { Self::Ascii }
+    }
+
+    fn levels(self) -> &'static [char; 8] {
+        match self {
+            Self::Unicode => &UNICODE_LEVELS,
+            Self::Ascii => &ASCII_LEVELS,
+        }
+    }
+}
+
+/// How samples falling in the same bucket are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bucketing {
+    /// Average of the samples; a bucket without samples is missing
+    Mean,
+    /// Sum of the samples; a bucket without samples counts as zero
+    Sum,
+}
+
+/// Fold timestamped samples into `buckets` equal slices of `[start, end)`
+///
+/// Samples outside the range and non-finite values are ignored. Returns an
+/// empty series when `buckets` is zero or the range is empty.
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // bucket counts and offsets are small
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn bucketize(
+    samples: &[(DateTime<Utc>, f64)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    buckets: usize,
+    bucketing: Bucketing,
+) -> Vec<Option<f64>> {
+    let span = (end - start).num_milliseconds();
+    if buckets == 0 || span <= 0 {
+        return Vec::new();
+    }
+
+    let mut sums = vec![0.0_f64; buckets];
+    let mut counts = vec![0_usize; buckets];
+    for (ts, value) in samples {
+        if !value.is_finite() || *ts < start || *ts >= end {
+            continue;
+        }
+        let offset = (*ts - start).num_milliseconds();
+        let index = ((offset as f64 / span as f64) * buckets as f64) as usize;
+        let index = index.min(buckets - 1);
+        sums[index] += value;
+        counts[index] += 1;
+    }
+
+    sums.into_iter()
+        .zip(counts)
+        .map(|(sum, count)| match (bucketing, count) {
+            (Bucketing::Mean, 0) => None,
+            (Bucketing::Mean, n) => Some(sum / n as f64),
+            (Bucketing::Sum, _) => Some(sum),
+        })
+        .collect()
+}
+
+/// One character per bucket, scaled between the series minimum and maximum
+#[must_use]
+#[allow(clippy::cast_precision_loss)] // eight levels
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn render(values: &[Option<f64>], style: SparkStyle) -> String {
+    let levels = style.levels();
+    let top = (levels.len() - 1) as f64;
+    let Some((min, max)) = range(values) else {
+        return values.iter().map(|_| MISSING).collect();
+    };
+    let spread = max - min;
+
+    values
+        .iter()
+        .map(|value| match value {
+            Some(v) if v.is_finite() => {
+                if spread <= f64::EPSILON {
+                    levels[0]
+                } else {
+                    let level = ((v - min) / spread * top).round() as usize;
+                    levels[level.min(levels.len() - 1)]
+                }
+            }
+            _ => MISSING,
+        })
+        .collect()
+}
+
+/// Sparkline with min and max labels, e.g. `0.42 ▁▂▃▅▇ 0.91`
+///
+/// Labels are formatted by `label`; a series without any value reads
+/// `(no data)`.
+#[must_use]
+pub fn render_labeled(
+    values: &[Option<f64>],
+    style: SparkStyle,
+    label: impl Fn(f64) -> String,
+) -> String {
+    let Some((min, max)) = range(values) else {
+        return "(no data)".to_string();
+    };
+    let mut out = label(min);
+    let _ = write!(out, " {} {}", render(values, style), label(max));
+    out
+}
+
+/// Smallest and largest finite value, `None` when there are none
+fn range(values: &[Option<f64>]) -> Option<(f64, f64)> {
+    values
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(None, |acc, v| match acc {
+            None => Some((v, v)),
+            Some((min, max)) => Some((f64::min(min, v), f64::max(max, v))),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ts(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_render_scales_between_min_and_max() {
+        let values = [Some(0.0), Some(1.0), Some(2.0), Some(7.0)];
+        assert_eq!(render(&values, SparkStyle::Unicode), "▁▂▃█");
+        assert_eq!(render(&values, SparkStyle::Ascii), "_.,#");
+    }
+
+    #[test]
+    fn test_all_equal_values_render_flat() {
+        let values = [Some(0.5); 4];
+        assert_eq!(render(&values, SparkStyle::Unicode), "▁▁▁▁");
+        assert_eq!(
+            render_labeled(&values, SparkStyle::Unicode, |v| format!("{v:.1}")),
+            "0.5 ▁▁▁▁ 0.5"
+        );
+    }
+
+    #[test]
+    fn test_missing_and_nan_buckets_render_blank() {
+        let values = [Some(1.0), None, Some(f64::NAN), Some(3.0)];
+        assert_eq!(render(&values, SparkStyle::Unicode), "▁  █");
+
+        let nothing = [None, Some(f64::NAN)];
+        assert_eq!(render(&nothing, SparkStyle::Unicode), "  ");
+        assert_eq!(
+            render_labeled(&nothing, SparkStyle::Unicode, |v| v.to_string()),
+            "(no data)"
+        );
+    }
+
+    #[test]
+    fn test_short_series() {
+        assert_eq!(render(&[], SparkStyle::Unicode), "");
+        assert_eq!(render(&[Some(4.0)], SparkStyle::Ascii), "_");
+        assert_eq!(
+            render_labeled(&[Some(4.0)], SparkStyle::Ascii, |v| format!("{v:.0}")),
+            "4 _ 4"
+        );
+    }
+
+    #[test]
+    fn test_bucketize_mean_leaves_gaps_and_sum_counts_zero() {
+        let samples = [
+            (ts(0), 1.0),
+            (ts(0), 3.0),
+            (ts(2), 5.0),
+            (ts(3), f64::NAN),
+            (ts(5), 9.0), // past the end
+        ];
+        assert_eq!(
+            bucketize(&samples, ts(0), ts(4), 4, Bucketing::Mean),
+            vec![Some(2.0), None, Some(5.0), None]
+        );
+        assert_eq!(
+            bucketize(&samples, ts(0), ts(4), 4, Bucketing::Sum),
+            vec![Some(4.0), Some(0.0), Some(5.0), Some(0.0)]
+        );
+    }
+
+    #[test]
+    fn test_bucketize_degenerate_ranges() {
+        let samples = [(ts(1), 1.0)];
+        assert!(bucketize(&samples, ts(0), ts(4), 0, Bucketing::Sum).is_empty());
+        assert!(bucketize(&samples, ts(4), ts(4), 8, Bucketing::Sum).is_empty());
+        assert!(bucketize(&samples, ts(4), ts(0), 8, Bucketing::Sum).is_empty());
+        assert_eq!(
+            bucketize(&[], ts(0), ts(4), 2, Bucketing::Mean),
+            vec![None, None]
+        );
+    }
+
+    #[test]
+    fn test_style_from_locale() {
+        assert_eq!(
+            SparkStyle::for_locale(Some("en_US.UTF-8")),
+            SparkStyle::Unicode
+        );
+        assert_eq!(SparkStyle::for_locale(Some("C.utf8")), SparkStyle::Unicode);
+        assert_eq!(SparkStyle::for_locale(Some("C")), SparkStyle::Ascii);
+        assert_eq!(SparkStyle::for_locale(None), SparkStyle::Ascii);
+    }
+}
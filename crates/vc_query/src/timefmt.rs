@@ -0,0 +1,412 @@
+//! Human-facing timestamp and duration formatting
+//!
+//! Stores, robot envelopes and JSON/TOON output carry raw RFC 3339 UTC. Text
+//! output, the TUI and digest markdown render through [`TimeFormatter`]
+//! instead, so every surface agrees on what "4m ago" and "3h 5m" look like.
+//!
+//! - [`TimestampStyle`] picks relative ("4m ago"), absolute, or both
+//! - [`DisplayZone`] is the system timezone or an IANA override from
+//!   `global.timezone`
+//! - Relative times are computed between instants, so a DST change never
+//!   makes "1d ago" read as 23 or 25 hours
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+
+/// Past this age, relative style falls back to the absolute timestamp
+const RELATIVE_HORIZON_DAYS: i64 = 30;
+
+/// How timestamps are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampStyle {
+    /// "4m ago" for recent values, absolute beyond 30 days
+    #[default]
+    Relative,
+    /// Local date and time with zone abbreviation
+    Absolute,
+    /// Absolute followed by the relative age in parentheses
+    Both,
+}
+
+impl TimestampStyle {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Relative => "relative",
+            Self::Absolute => "absolute",
+            Self::Both => "both",
+        }
+    }
+}
+
+impl FromStr for TimestampStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute),
+            "both" => Ok(Self::Both),
+            other => Err(format!(
+                "unknown timestamp style: {other} (expected relative, absolute or both)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TimestampStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Timezone absolute timestamps are shown in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayZone {
+    /// The system timezone (`TZ` / `/etc/localtime`)
+    #[default]
+    Local,
+    /// An IANA zone such as `Europe/Berlin` or `UTC`
+    Named(Tz),
+}
+
+impl FromStr for DisplayZone {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        value
+            .parse::<Tz>()
+            .map(Self::Named)
+            .map_err(|_| format!("unknown timezone: {value}"))
+    }
+}
+
+/// Renders timestamps and durations for people
+#[derive(Debug, Clone, Default)]
+pub struct TimeFormatter {
+    style: TimestampStyle,
+    zone: DisplayZone,
+    now: Option<DateTime<Utc>>,
+}
+
+impl TimeFormatter {
+    #[must_use]
+    pub fn new(style: TimestampStyle, zone: DisplayZone) -> Self {
+        Self {
+            style,
+            zone,
+            now: None,
+        }
+    }
+
+    /// Measure relative times from `now` instead of the wall clock
+    #[must_use]
+    pub fn at(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    #[must_use]
+    pub fn style(&self) -> TimestampStyle {
+        self.style
+    }
+
+    /// Format `ts` in the configured style
+    #[must_use]
+    pub fn timestamp(&self, ts: DateTime<Utc>) -> String {
+        let now = self.now.unwrap_or_else(Utc::now);
+        match self.style {
+            TimestampStyle::Absolute => self.absolute(ts),
+            TimestampStyle::Relative if (now - ts).num_days().abs() < RELATIVE_HORIZON_DAYS => {
+                relative(ts, now)
+            }
+            TimestampStyle::Relative => self.absolute(ts),
+            TimestampStyle::Both => format!("{} ({})", self.absolute(ts), relative(ts, now)),
+        }
+    }
+
+    /// Format a stored timestamp string; anything unparseable is returned as-is
+    #[must_use]
+    pub fn timestamp_str(&self, raw: &str) -> String {
+        parse_timestamp(raw).map_or_else(|| raw.to_string(), |ts| self.timestamp(ts))
+    }
+
+    /// `ts` as date, time and zone abbreviation in the display zone
+    #[must_use]
+    pub fn absolute(&self, ts: DateTime<Utc>) -> String {
+        const FORMAT: &str = "%Y-%m-%d %H:%M:%S %Z";
+        match self.zone {
+            DisplayZone::Local => ts.with_timezone(&Local).format(FORMAT).to_string(),
+            DisplayZone::Named(tz) => ts.with_timezone(&tz).format(FORMAT).to_string(),
+        }
+    }
+
+    /// Copy of `value` with timestamp fields (`*_at`, `ts`, `timestamp`,
+    /// `last_seen`) and duration fields (`*_ms`, `*_secs`, `*_seconds`)
+    /// rendered for people. Everything else is left untouched.
+    #[must_use]
+    pub fn humanize_json(&self, value: &Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, field)| (key.clone(), self.humanize_field(key, field)))
+                    .collect(),
+            ),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.humanize_json(item)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn humanize_field(&self, key: &str, field: &Value) -> Value {
+        match field {
+            Value::String(raw) if is_timestamp_key(key) => Value::String(self.timestamp_str(raw)),
+            Value::Number(n) if key.ends_with("_ms") => n
+                .as_f64()
+                .map_or_else(|| field.clone(), |ms| Value::String(duration_ms(ms))),
+            Value::Number(n) if key.ends_with("_secs") || key.ends_with("_seconds") => n
+                .as_f64()
+                .map_or_else(|| field.clone(), |s| Value::String(duration_ms(s * 1000.0))),
+            other => self.humanize_json(other),
+        }
+    }
+}
+
+fn is_timestamp_key(key: &str) -> bool {
+    key.ends_with("_at") || key.ends_with("_ts") || matches!(key, "ts" | "timestamp" | "last_seen")
+}
+
+/// Parse RFC 3339, or the `YYYY-MM-DD HH:MM:SS[.f]` that `CAST(ts AS TEXT)`
+/// produces (taken as UTC)
+#[must_use]
+pub fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .map(|naive| naive.and_utc())
+}
+
+/// Start of a window given as a timestamp (anything [`parse_timestamp`]
+/// accepts) or an age before `now` such as `90m`, `24h`, `7d` or `2w`
+#[must_use]
+pub fn parse_since(raw: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Some(ts) = parse_timestamp(raw) {
+        return Some(ts);
+    }
+    let split = raw.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = raw.split_at(split);
+    let amount: i64 = amount.parse().ok()?;
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(amount),
+        "m" => chrono::Duration::try_minutes(amount),
+        "h" => chrono::Duration::try_hours(amount),
+        "d" => chrono::Duration::try_days(amount),
+        "w" => chrono::Duration::try_weeks(amount),
+        _ => None,
+    }?;
+    now.checked_sub_signed(age)
+}
+
+/// Age of `ts` relative to `now`: "just now", "45s ago", "4m ago", "3h ago",
+/// "3d ago", or "in 5m" for future values
+#[must_use]
+pub fn relative(ts: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let secs = (now - ts).num_seconds();
+    if secs == 0 {
+        return "just now".to_string();
+    }
+    let magnitude = secs.unsigned_abs();
+    let amount = if magnitude < 60 {
+        format!("{magnitude}s")
+    } else if magnitude < 3_600 {
+        format!("{}m", magnitude / 60)
+    } else if magnitude < 86_400 {
+        format!("{}h", magnitude / 3_600)
+    } else {
+        format!("{}d", magnitude / 86_400)
+    };
+    if secs > 0 {
+        format!("{amount} ago")
+    } else {
+        format!("in {amount}")
+    }
+}
+
+/// Duration in milliseconds as "850ms", "42s", "4m 12s", "3h 5m" or "2d 4h"
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn duration_ms(ms: f64) -> String {
+    if !ms.is_finite() || ms < 0.0 {
+        return "-".to_string();
+    }
+    if ms < 1_000.0 {
+        return format!("{}ms", ms.round() as u64);
+    }
+    duration_secs((ms / 1_000.0).round() as u64)
+}
+
+/// Duration in whole seconds, two most significant units
+#[must_use]
+pub fn duration_secs(secs: u64) -> String {
+    let (days, hours, minutes, seconds) = (
+        secs / 86_400,
+        (secs % 86_400) / 3_600,
+        (secs % 3_600) / 60,
+        secs % 60,
+    );
+    let (major, minor) = if days > 0 {
+        ((days, "d"), (hours, "h"))
+    } else if hours > 0 {
+        ((hours, "h"), (minutes, "m"))
+    } else if minutes > 0 {
+        ((minutes, "m"), (seconds, "s"))
+    } else {
+        return format!("{seconds}s");
+    };
+    if minor.0 == 0 {
+        format!("{}{}", major.0, major.1)
+    } else {
+        format!("{}{} {}{}", major.0, major.1, minor.0, minor.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(raw: &str) -> DateTime<Utc> {
+        parse_timestamp(raw).unwrap()
+    }
+
+    fn new_york() -> TimeFormatter {
+        TimeFormatter::new(
+            TimestampStyle::Absolute,
+            "America/New_York".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_absolute_across_spring_forward() {
+        let fmt = new_york();
+        assert_eq!(
+            fmt.absolute(utc("2026-03-08T06:59:00Z")),
+            "2026-03-08 01:59:00 EST"
+        );
+        // 02:00 EST does not exist; the next instant is 03:00 EDT.
+        assert_eq!(
+            fmt.absolute(utc("2026-03-08T07:00:00Z")),
+            "2026-03-08 03:00:00 EDT"
+        );
+    }
+
+    #[test]
+    fn test_absolute_across_fall_back() {
+        let fmt = new_york();
+        // 01:30 happens twice; the zone abbreviation tells them apart.
+        assert_eq!(
+            fmt.absolute(utc("2026-11-01T05:30:00Z")),
+            "2026-11-01 01:30:00 EDT"
+        );
+        assert_eq!(
+            fmt.absolute(utc("2026-11-01T06:30:00Z")),
+            "2026-11-01 01:30:00 EST"
+        );
+    }
+
+    #[test]
+    fn test_relative_uses_elapsed_time_not_wall_clock() {
+        // 24 real hours across spring-forward are 25 wall-clock hours locally.
+        let now = utc("2026-03-08T12:00:00Z");
+        assert_eq!(relative(utc("2026-03-07T12:00:00Z"), now), "1d ago");
+        assert_eq!(relative(utc("2026-03-08T11:56:00Z"), now), "4m ago");
+        assert_eq!(relative(now, now), "just now");
+        assert_eq!(relative(utc("2026-03-08T12:05:00Z"), now), "in 5m");
+    }
+
+    #[test]
+    fn test_styles() {
+        let now = utc("2026-10-16T12:00:00Z");
+        let zone = "UTC".parse().unwrap();
+        let recent = utc("2026-10-16T11:56:00Z");
+        let old = utc("2026-08-01T00:00:00Z");
+
+        let fmt = TimeFormatter::new(TimestampStyle::Relative, zone).at(now);
+        assert_eq!(fmt.timestamp(recent), "4m ago");
+        assert_eq!(fmt.timestamp(old), "2026-08-01 00:00:00 UTC");
+
+        let fmt = TimeFormatter::new(TimestampStyle::Both, zone).at(now);
+        assert_eq!(fmt.timestamp(recent), "2026-10-16 11:56:00 UTC (4m ago)");
+
+        assert_eq!(fmt.timestamp_str("not a time"), "not a time");
+        assert_eq!(
+            fmt.timestamp_str("2026-10-16 11:56:00.123"),
+            "2026-10-16 11:56:00 UTC (4m ago)"
+        );
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(duration_ms(850.0), "850ms");
+        assert_eq!(duration_ms(42_000.0), "42s");
+        assert_eq!(duration_secs(252), "4m 12s");
+        assert_eq!(duration_secs(3_600), "1h");
+        assert_eq!(duration_secs(11_100), "3h 5m");
+        assert_eq!(duration_secs(187_200), "2d 4h");
+        assert_eq!(duration_ms(-1.0), "-");
+    }
+
+    #[test]
+    fn test_humanize_json_only_touches_time_fields() {
+        let fmt = TimeFormatter::new(TimestampStyle::Relative, DisplayZone::default())
+            .at(utc("2026-10-16T12:00:00Z"));
+        let value = serde_json::json!({
+            "machine_id": "orko",
+            "last_seen": "2026-10-16T11:57:00Z",
+            "rows": [{"collected_at": "2026-10-16T09:00:00Z", "duration_ms": 1500}],
+            "uptime_secs": 90,
+            "count": 3,
+        });
+        let human = fmt.humanize_json(&value);
+        assert_eq!(human["machine_id"], "orko");
+        assert_eq!(human["last_seen"], "3m ago");
+        assert_eq!(human["rows"][0]["collected_at"], "3h ago");
+        assert_eq!(human["rows"][0]["duration_ms"], "2s");
+        assert_eq!(human["uptime_secs"], "1m 30s");
+        assert_eq!(human["count"], 3);
+    }
+
+    #[test]
+    fn test_parse_style_and_zone() {
+        assert_eq!("BOTH".parse::<TimestampStyle>(), Ok(TimestampStyle::Both));
+        assert!("sideways".parse::<TimestampStyle>().is_err());
+        assert_eq!("local".parse::<DisplayZone>(), Ok(DisplayZone::Local));
+        assert!("Mars/Olympus".parse::<DisplayZone>().is_err());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let now = utc("2026-10-16T12:00:00Z");
+        assert_eq!(parse_since("24h", now), Some(utc("2026-10-15T12:00:00Z")));
+        assert_eq!(parse_since("90m", now), Some(utc("2026-10-16T10:30:00Z")));
+        assert_eq!(
+            parse_since("2026-10-01T00:00:00Z", now),
+            Some(utc("2026-10-01T00:00:00Z"))
+        );
+        assert_eq!(parse_since("24", now), None);
+        assert_eq!(parse_since("3y", now), None);
+    }
+}
@@ -0,0 +1,564 @@
+//! Fleet timeline: alerts, incidents, fleet commands, audit events, drift
+//! events and annotations merged into one time-ordered stream.
+//!
+//! Each source table is read oldest first from the window start (or from a
+//! page cursor), at most one page's worth per source, and the sources are
+//! merged in memory. A wide window is therefore read a page at a time: the
+//! last event of a page is the `after` cursor for the next one.
+//!
+//! Timestamps are TEXT in more than one rendering (`T` or space between date
+//! and time), so sources are filtered and ordered on the rendering with the
+//! `T` replaced by a space, which sorts the same way for both.
+//!
+//! Incidents carry no machine and are kept under a machine filter. Fleet
+//! commands name their machine in their parameters, if at all; fleet-wide
+//! ones are kept too. Annotations are kept when they apply to the machine:
+//! its own, its tags' and fleet-wide ones.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use vc_store::escape_sql_literal;
+
+use crate::timefmt::parse_timestamp;
+use crate::{QueryBuilder, QueryError};
+
+/// Events per page when no limit is given
+pub const DEFAULT_TIMELINE_LIMIT: usize = 200;
+
+/// Largest page a caller may ask for
+pub const MAX_TIMELINE_LIMIT: usize = 1000;
+
+/// Source of a timeline event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineKind {
+    Alerts,
+    Incidents,
+    Fleet,
+    Audit,
+    Drift,
+    Annotations,
+}
+
+impl TimelineKind {
+    /// Every kind, in the order events with equal timestamps are listed
+    pub const ALL: [Self; 6] = [
+        Self::Alerts,
+        Self::Incidents,
+        Self::Fleet,
+        Self::Audit,
+        Self::Drift,
+        Self::Annotations,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Alerts => "alerts",
+            Self::Incidents => "incidents",
+            Self::Fleet => "fleet",
+            Self::Audit => "audit",
+            Self::Drift => "drift",
+            Self::Annotations => "annotations",
+        }
+    }
+
+    /// Table the events come from
+    #[must_use]
+    pub fn table(&self) -> &'static str {
+        match self {
+            Self::Alerts => "alert_history",
+            Self::Incidents => "incidents",
+            Self::Fleet => "fleet_commands",
+            Self::Audit => "audit_events",
+            Self::Drift => "drift_events",
+            Self::Annotations => "annotations",
+        }
+    }
+
+    /// Key column, and whether it is numeric
+    fn id_column(self) -> (&'static str, bool) {
+        match self {
+            Self::Incidents => ("incident_id", false),
+            Self::Fleet => ("command_id", false),
+            Self::Alerts | Self::Audit | Self::Drift | Self::Annotations => ("id", true),
+        }
+    }
+
+    fn ts_column(self) -> &'static str {
+        match self {
+            Self::Alerts => "fired_at",
+            Self::Incidents | Self::Fleet => "started_at",
+            Self::Audit | Self::Annotations => "ts",
+            Self::Drift => "detected_at",
+        }
+    }
+
+    /// Columns read for the summary, besides key and time
+    fn detail_columns(self) -> &'static str {
+        match self {
+            Self::Alerts => "machine_id, severity, title",
+            Self::Incidents => "severity, title, status",
+            Self::Fleet => "command_type, status, params_json, error_message, initiated_by",
+            Self::Audit => "machine_id, event_type, action, result, actor",
+            Self::Drift => "machine_id, severity, metric, previous_value, new_value, z_score",
+            Self::Annotations => "kind, scope, message, source",
+        }
+    }
+}
+
+impl std::fmt::Display for TimelineKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TimelineKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "alerts" | "alert" => Ok(Self::Alerts),
+            "incidents" | "incident" => Ok(Self::Incidents),
+            "fleet" => Ok(Self::Fleet),
+            "audit" => Ok(Self::Audit),
+            "drift" => Ok(Self::Drift),
+            "annotations" | "annotation" => Ok(Self::Annotations),
+            other => Err(format!(
+                "unknown timeline kind: {other} (expected alerts, incidents, fleet, audit, \
+                 drift or annotations)"
+            )),
+        }
+    }
+}
+
+/// What `QueryBuilder::timeline` reads
+#[derive(Debug, Clone)]
+pub struct TimelineFilter {
+    /// Window start
+    pub since: DateTime<Utc>,
+    pub machine: Option<String>,
+    /// Kinds to include; empty means all
+    pub kinds: Vec<TimelineKind>,
+    /// Events per page
+    pub limit: usize,
+    /// Cursor from a previous page's `next`
+    pub after: Option<String>,
+}
+
+impl TimelineFilter {
+    /// Every kind on every machine since `since`, first page
+    #[must_use]
+    pub fn new(since: DateTime<Utc>) -> Self {
+        Self {
+            since,
+            machine: None,
+            kinds: Vec::new(),
+            limit: DEFAULT_TIMELINE_LIMIT,
+            after: None,
+        }
+    }
+}
+
+/// One timeline entry, the same shape for every kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub ts: String,
+    pub kind: TimelineKind,
+    pub machine_id: Option<String>,
+    pub severity: Option<String>,
+    pub summary: String,
+    /// Key of the row in the kind's table (`vc timeline show <kind> <id>`)
+    pub ref_id: String,
+    /// Sort key this event was ordered by
+    #[serde(skip)]
+    sort_ts: String,
+}
+
+impl TimelineEvent {
+    fn cursor(&self) -> String {
+        format!("{}|{}|{}", self.sort_ts, self.kind, self.ref_id)
+    }
+}
+
+/// One page of the timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelinePage {
+    pub events: Vec<TimelineEvent>,
+    /// Pass as `after` to read the next page; `None` on the last page
+    pub next: Option<String>,
+}
+
+/// Decoded `after` cursor
+struct Cursor {
+    sort_ts: String,
+    kind: TimelineKind,
+    ref_id: String,
+}
+
+impl Cursor {
+    fn parse(raw: &str) -> Result<Self, QueryError> {
+        let invalid = || QueryError::InvalidQuery(format!("invalid timeline cursor: {raw}"));
+        let mut parts = raw.splitn(3, '|');
+        let (Some(sort_ts), Some(kind), Some(ref_id)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            sort_ts: sort_ts.to_string(),
+            kind: kind.parse().map_err(|_| invalid())?,
+            ref_id: ref_id.to_string(),
+        })
+    }
+}
+
+/// `column` rendered so that both TEXT timestamp styles sort chronologically
+pub(crate) fn sortable_ts(column: &str) -> String {
+    format!("replace(CAST({column} AS TEXT), 'T', ' ')")
+}
+
+/// Machine a fleet command targets, from its parameters
+fn fleet_machine(params: &serde_json::Value) -> Option<String> {
+    ["machine", "from"]
+        .iter()
+        .find_map(|key| params[*key].as_str())
+        .or_else(|| params["scope"].as_str()?.strip_prefix("machine:"))
+        .map(str::to_string)
+}
+
+fn text(row: &serde_json::Value, key: &str) -> Option<String> {
+    match &row[key] {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// The event for one source row
+fn to_event(kind: TimelineKind, row: &serde_json::Value) -> TimelineEvent {
+    let field = |key: &str| text(row, key).unwrap_or_default();
+    let (machine_id, severity, summary) = match kind {
+        TimelineKind::Alerts => (
+            text(row, "machine_id"),
+            text(row, "severity"),
+            field("title"),
+        ),
+        TimelineKind::Incidents => (
+            None,
+            text(row, "severity"),
+            format!("{} ({})", field("title"), field("status")),
+        ),
+        TimelineKind::Fleet => {
+            let params = row["params_json"]
+                .as_str()
+                .and_then(|raw| serde_json::from_str(raw).ok())
+                .unwrap_or_default();
+            let status = field("status");
+            let mut summary = format!("{} {status}", field("command_type"));
+            if let Some(by) = text(row, "initiated_by") {
+                summary.push_str(&format!(" by {by}"));
+            }
+            if let Some(error) = text(row, "error_message") {
+                summary.push_str(&format!(": {error}"));
+            }
+            let severity = (status == "failed").then(|| "warning".to_string());
+            (fleet_machine(&params), severity, summary)
+        }
+        TimelineKind::Audit => {
+            let mut summary = format!("{}: {}", field("event_type"), field("action"));
+            if let Some(result) = text(row, "result") {
+                summary.push_str(&format!(" ({result})"));
+            }
+            if let Some(actor) = text(row, "actor") {
+                summary.push_str(&format!(" by {actor}"));
+            }
+            (text(row, "machine_id"), None, summary)
+        }
+        TimelineKind::Drift => {
+            let summary = match (text(row, "previous_value"), text(row, "new_value")) {
+                (Some(before), Some(after)) => {
+                    format!("{} changed: {before} -> {after}", field("metric"))
+                }
+                _ => format!(
+                    "{} drifted (z = {:.1})",
+                    field("metric"),
+                    row["z_score"].as_f64().unwrap_or_default()
+                ),
+            };
+            (text(row, "machine_id"), text(row, "severity"), summary)
+        }
+        TimelineKind::Annotations => {
+            let scope = field("scope");
+            let summary = format!(
+                "{} on {scope}: {} ({})",
+                field("kind"),
+                field("message"),
+                field("source")
+            );
+            let machine = scope.strip_prefix("machine:").map(str::to_string);
+            (machine, None, summary)
+        }
+    };
+    let raw_ts = field("raw_ts");
+    TimelineEvent {
+        ts: parse_timestamp(&raw_ts)
+            .map_or(raw_ts, |ts| ts.to_rfc3339_opts(SecondsFormat::Micros, true)),
+        kind,
+        machine_id,
+        severity,
+        summary,
+        ref_id: field("ref_id"),
+        sort_ts: field("sort_ts"),
+    }
+}
+
+impl QueryBuilder<'_> {
+    /// One page of the fleet timeline, oldest first.
+    ///
+    /// Every source is read only up to the page size past the cursor, so the
+    /// cost of a page does not grow with the window.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidQuery`] for a malformed `after` cursor,
+    /// or [`QueryError`] if a source query fails.
+    pub fn timeline(&self, filter: &TimelineFilter) -> Result<TimelinePage, QueryError> {
+        let limit = filter.limit.clamp(1, MAX_TIMELINE_LIMIT);
+        let cursor = filter.after.as_deref().map(Cursor::parse).transpose()?;
+        let caps = self.store.capabilities()?;
+        let since = filter.since.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let mut events = Vec::new();
+        let mut source_full = false;
+        for kind in TimelineKind::ALL {
+            if !(filter.kinds.is_empty() || filter.kinds.contains(&kind))
+                || !caps.has_table(kind.table())
+            {
+                continue;
+            }
+            let (id, numeric) = kind.id_column();
+            let ts = sortable_ts(kind.ts_column());
+            let mut clauses = vec![format!("{ts} >= '{since}'")];
+            if let Some(cursor) = &cursor {
+                let at = escape_sql_literal(&cursor.sort_ts);
+                clauses.push(match kind.cmp(&cursor.kind) {
+                    std::cmp::Ordering::Less => format!("{ts} > '{at}'"),
+                    std::cmp::Ordering::Greater => format!("{ts} >= '{at}'"),
+                    std::cmp::Ordering::Equal => {
+                        let after_id = if numeric {
+                            cursor.ref_id.parse::<i64>().unwrap_or(i64::MAX).to_string()
+                        } else {
+                            format!("'{}'", escape_sql_literal(&cursor.ref_id))
+                        };
+                        format!("({ts} > '{at}' OR ({ts} = '{at}' AND {id} > {after_id}))")
+                    }
+                });
+            }
+            if let Some(machine) = &filter.machine
+                && matches!(
+                    kind,
+                    TimelineKind::Alerts | TimelineKind::Audit | TimelineKind::Drift
+                )
+            {
+                clauses.push(format!("machine_id = '{}'", escape_sql_literal(machine)));
+            }
+            if let Some(machine) = &filter.machine
+                && kind == TimelineKind::Annotations
+            {
+                let scopes: Vec<String> = self
+                    .store
+                    .annotation_scopes(machine)?
+                    .iter()
+                    .map(|scope| format!("'{}'", escape_sql_literal(scope)))
+                    .collect();
+                clauses.push(format!("scope IN ({})", scopes.join(", ")));
+            }
+            let sql = format!(
+                "SELECT CAST({id} AS TEXT) AS ref_id, {ts} AS sort_ts, \
+                 CAST({raw} AS TEXT) AS raw_ts, {columns} \
+                 FROM {table} WHERE {where_sql} ORDER BY sort_ts, {id} LIMIT {limit}",
+                raw = kind.ts_column(),
+                columns = kind.detail_columns(),
+                table = kind.table(),
+                where_sql = clauses.join(" AND "),
+            );
+            let rows = self.store.query_json(&sql)?;
+            source_full |= rows.len() == limit;
+            events.extend(rows.iter().map(|row| to_event(kind, row)).filter(|event| {
+                kind != TimelineKind::Fleet
+                    || filter.machine.is_none()
+                    || event.machine_id.is_none()
+                    || event.machine_id == filter.machine
+            }));
+        }
+
+        // Stable, so each source keeps its own (time, key) order
+        events.sort_by(|a, b| (&a.sort_ts, a.kind).cmp(&(&b.sort_ts, b.kind)));
+        let more = source_full || events.len() > limit;
+        events.truncate(limit);
+        let next = more
+            .then(|| events.last().map(TimelineEvent::cursor))
+            .flatten();
+        Ok(TimelinePage { events, next })
+    }
+
+    /// The full row behind a timeline event, or `None` if it is gone
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError`] if the query fails.
+    pub fn timeline_event(
+        &self,
+        kind: TimelineKind,
+        ref_id: &str,
+    ) -> Result<Option<serde_json::Value>, QueryError> {
+        let (id, numeric) = kind.id_column();
+        let key = if numeric {
+            ref_id
+                .parse::<i64>()
+                .map_err(|_| QueryError::InvalidQuery(format!("invalid {kind} id: {ref_id}")))?
+                .to_string()
+        } else {
+            format!("'{}'", escape_sql_literal(ref_id))
+        };
+        let sql = format!("SELECT * FROM {} WHERE {id} = {key} LIMIT 1", kind.table());
+        Ok(self.store.query_json(&sql)?.into_iter().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vc_store::VcStore;
+
+    fn store_with_history() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) VALUES \
+                   (1, 'disk', '2026-10-16T10:00:00.000000Z', 'critical', 'Disk full', 'orko'), \
+                   (2, 'load', '2026-10-16T10:05:00.000000Z', 'warning', 'High load', 'bender'); \
+                 INSERT INTO incidents (incident_id, title, severity, status, started_at) VALUES \
+                   ('inc-1', 'Build fleet degraded', 'critical', 'open', '2026-10-16T10:02:00Z'); \
+                 INSERT INTO fleet_commands (command_id, command_type, params_json, status, started_at) VALUES \
+                   ('cmd-1', 'emergency_stop', '{\"scope\":\"machine:orko\"}', 'completed', '2026-10-16 10:03:00'), \
+                   ('cmd-2', 'spawn', '{\"machine\":\"bender\"}', 'failed', '2026-10-16 10:04:00'); \
+                 INSERT INTO audit_events (id, ts, event_type, actor, machine_id, action, result) VALUES \
+                   (1, '2026-10-16 10:01:00', 'user_command', 'ops', 'orko', 'vc alert ack 1', 'success'); \
+                 INSERT INTO drift_events (id, machine_id, detected_at, metric, current_value, \
+                   baseline_mean, baseline_std, z_score, severity, previous_value, new_value) VALUES \
+                   (1, 'orko', '2026-10-16T10:00:00.000000Z', 'rustc_version', 0, 0, 0, 0, 'info', '1.89.0', '1.90.0'); \
+                 INSERT INTO annotations (id, kind, scope, ts, message, source, created_at) VALUES \
+                   (1, 'deploy', 'machine:orko', '2026-10-16T10:06:00.000000Z', 'release 1.42', 'ci', '2026-10-16T10:06:00.000000Z'), \
+                   (2, 'deploy', 'machine:bender', '2026-10-16T10:07:00.000000Z', 'release 1.43', 'ci', '2026-10-16T10:07:00.000000Z');",
+            )
+            .unwrap();
+        store
+    }
+
+    fn since() -> DateTime<Utc> {
+        parse_timestamp("2026-10-16T00:00:00Z").unwrap()
+    }
+
+    #[test]
+    fn test_timeline_merges_sources_in_time_order() {
+        let store = store_with_history();
+        let page = QueryBuilder::new(&store)
+            .timeline(&TimelineFilter::new(since()))
+            .unwrap();
+        let order: Vec<_> = page
+            .events
+            .iter()
+            .map(|e| format!("{}:{}", e.kind, e.ref_id))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                "alerts:1",
+                "drift:1",
+                "audit:1",
+                "incidents:inc-1",
+                "fleet:cmd-1",
+                "fleet:cmd-2",
+                "alerts:2",
+                "annotations:1",
+                "annotations:2"
+            ]
+        );
+        assert!(page.next.is_none());
+        assert_eq!(
+            page.events[1].summary,
+            "rustc_version changed: 1.89.0 -> 1.90.0"
+        );
+        assert_eq!(page.events[4].machine_id.as_deref(), Some("orko"));
+        assert_eq!(page.events[5].severity.as_deref(), Some("warning"));
+        assert_eq!(page.events[2].ts, "2026-10-16T10:01:00.000000Z");
+        assert_eq!(
+            page.events[7].summary,
+            "deploy on machine:orko: release 1.42 (ci)"
+        );
+        assert_eq!(page.events[7].machine_id.as_deref(), Some("orko"));
+    }
+
+    #[test]
+    fn test_timeline_filters_and_pages() {
+        let store = store_with_history();
+        let query = QueryBuilder::new(&store);
+
+        let mut filter = TimelineFilter::new(since());
+        filter.machine = Some("orko".to_string());
+        filter.kinds = vec![
+            TimelineKind::Alerts,
+            TimelineKind::Fleet,
+            TimelineKind::Incidents,
+        ];
+        let page = query.timeline(&filter).unwrap();
+        let refs: Vec<_> = page.events.iter().map(|e| e.ref_id.as_str()).collect();
+        assert_eq!(refs, ["1", "inc-1", "cmd-1"]);
+
+        filter.kinds = vec![TimelineKind::Annotations];
+        let page = query.timeline(&filter).unwrap();
+        let refs: Vec<_> = page.events.iter().map(|e| e.ref_id.as_str()).collect();
+        assert_eq!(refs, ["1"]);
+
+        // Pages of two cover every event exactly once
+        let mut filter = TimelineFilter::new(since());
+        filter.limit = 2;
+        let mut seen = Vec::new();
+        loop {
+            let page = query.timeline(&filter).unwrap();
+            seen.extend(
+                page.events
+                    .iter()
+                    .map(|e| format!("{}:{}", e.kind, e.ref_id)),
+            );
+            match page.next {
+                Some(next) => filter.after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 9);
+        assert_eq!(seen.first().map(String::as_str), Some("alerts:1"));
+        assert_eq!(seen.last().map(String::as_str), Some("annotations:2"));
+
+        filter.after = Some("garbage".to_string());
+        assert!(query.timeline(&filter).is_err());
+    }
+
+    #[test]
+    fn test_timeline_event_detail() {
+        let store = store_with_history();
+        let query = QueryBuilder::new(&store);
+        let row = query
+            .timeline_event(TimelineKind::Fleet, "cmd-2")
+            .unwrap()
+            .unwrap();
+        assert_eq!(row["command_type"], "spawn");
+        assert!(
+            query
+                .timeline_event(TimelineKind::Alerts, "99")
+                .unwrap()
+                .is_none()
+        );
+        assert!(query.timeline_event(TimelineKind::Drift, "x").is_err());
+    }
+}
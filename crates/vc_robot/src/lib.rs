@@ -0,0 +1,3487 @@
+//! `vc_robot` - Robot mode output for agent consumption
+//!
+//! This crate provides:
+//! - Standard envelope format for all robot output
+//! - Health status data structures
+//! - Ranked triage actions
+//! - Machine, account, repo and forecast status
+//! - TOON encoding ([`toon`])
+//! - View names and client cache TTLs ([`RobotView`])
+//!
+//! `vc robot <name>` and `GET /api/robot/<name>` both build their envelopes
+//! here, so an agent gets the same bytes from either.
+//!
+//! Every payload here is derived from the store. Values that genuinely cannot be
+//! known (a machine that has never been collected from, an account with no usage
+//! snapshot) are reported as `null` rather than filled with a plausible-looking
+//! default — a `null` is information, an invented number is a lie.
+//!
+//! # `DuckDB` timestamp handling
+//!
+//! Timestamp columns are stored as `TEXT`. Comparing them against another
+//! timestamp (or `current_timestamp`) raises a Binder Error, so every comparison
+//! and `MAX()` used for "latest row per key" wraps the column in
+//! `CAST(col AS TIMESTAMP)`, and every timestamp we read back out is projected as
+//! `CAST(col AS TEXT)`.
+//!
+//! # Older stores
+//!
+//! Each command takes one [`Capabilities`] snapshot and skips the sections
+//! whose tables the store does not have, listing them in the envelope's
+//! `missing_capabilities` instead of surfacing a "table does not exist" error.
+
+pub mod toon;
+
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use vc_oracle::rate_limit::{RateLimitForecaster, UsageSample};
+use vc_query::{QueryBuilder, TagBreakdown, UpstreamFailure};
+use vc_store::{Capabilities, VcStore};
+// The web API serves these as-is, so they live with its other wire types
+pub use vc_types::{RobotEnvelope, RobotView};
+
+/// Robot envelope errors
+#[derive(Error, Debug)]
+pub enum RobotError {
+    #[error("Store error: {0}")]
+    StoreError(#[from] vc_store::StoreError),
+
+    #[error("Query error: {0}")]
+    QueryError(#[from] vc_query::QueryError),
+
+    #[error("Machine registry error: {0}")]
+    RegistryError(#[from] vc_collect::machine::RegistryError),
+}
+
+// ============================================================================
+// Health Data Structures
+// ============================================================================
+
+/// Overall fleet health data
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HealthData {
+    /// Overall health summary
+    pub overall: OverallHealth,
+
+    /// Per-machine health
+    pub machines: Vec<MachineHealth>,
+
+    /// Active alert count by severity
+    pub alerts_by_severity: AlertCounts,
+
+    /// Latest daemon resource self-limit check, `None` if the daemon has
+    /// never recorded one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon: Option<vc_store::DaemonResourceState>,
+
+    /// Daemon holding the store lease, `None` if no daemon has taken it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon_lease: Option<vc_store::Lease>,
+}
+
+/// Overall health summary
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OverallHealth {
+    /// Health score (0.0 to 1.0)
+    pub score: f64,
+
+    /// Severity level: "healthy", "warning", "critical"
+    pub severity: String,
+
+    /// Total active alerts
+    pub active_alerts: u32,
+
+    /// Number of machines monitored
+    pub machine_count: u32,
+
+    /// Number of active agents
+    pub agent_count: u32,
+}
+
+/// Per-machine health
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MachineHealth {
+    /// Machine identifier
+    pub id: String,
+
+    /// Display name
+    pub name: String,
+
+    /// Health score (0.0 to 1.0), `None` when no health summary has been persisted
+    pub score: Option<f64>,
+
+    /// Status: "online", "degraded", "offline", "unknown", "maintenance"
+    pub status: String,
+
+    /// Top issue affecting this machine (if any)
+    pub top_issue: Option<String>,
+
+    /// Last data collection timestamp, `None` if never collected from
+    pub last_seen: Option<DateTime<Utc>>,
+
+    /// Active agent count on this machine
+    pub agent_count: u32,
+
+    /// CPU usage percentage (0-100)
+    pub cpu_percent: Option<f64>,
+
+    /// Memory usage percentage (0-100)
+    pub memory_percent: Option<f64>,
+
+    /// Set while the machine is in maintenance; `score` is then the last
+    /// one computed before it went in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<MaintenanceInfo>,
+}
+
+/// An open maintenance window, as shown on a machine
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MaintenanceInfo {
+    /// When the machine went into maintenance
+    pub since: Option<DateTime<Utc>>,
+
+    /// When maintenance ends by itself, if it was given an end
+    pub until: Option<DateTime<Utc>>,
+
+    /// Why the machine is in maintenance
+    pub reason: Option<String>,
+}
+
+/// Alert counts by severity
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AlertCounts {
+    pub critical: u32,
+    pub warning: u32,
+    pub info: u32,
+}
+
+// ============================================================================
+// Triage Data Structures
+// ============================================================================
+
+/// Ranked triage actions
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TriageData {
+    /// Action items, the one to do first at the top
+    pub actions: Vec<ActionItem>,
+
+    /// How many lower-ranked items `--max-items` left out
+    pub truncated: usize,
+}
+
+/// What kind of work an action item asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionCategory {
+    /// Acknowledge or approve something that is waiting on a human
+    Ack,
+    /// Look closer before changing anything
+    Investigate,
+    /// Fix the underlying condition
+    Remediate,
+    /// Make room before a limit is reached
+    Capacity,
+}
+
+impl ActionCategory {
+    /// Lowercase name, as serialized
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ack => "ack",
+            Self::Investigate => "investigate",
+            Self::Remediate => "remediate",
+            Self::Capacity => "capacity",
+        }
+    }
+}
+
+/// How bad the condition behind an action item is
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl ActionSeverity {
+    /// Map a stored severity onto the action vocabulary. Alert rules use
+    /// high/medium/low as well as critical/warning/info.
+    fn parse(raw: &str) -> Self {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "critical" | "high" => Self::Critical,
+            "warning" | "warn" | "medium" => Self::Warning,
+            _ => Self::Info,
+        }
+    }
+
+    /// Share of the severity part of the ranking score
+    fn weight(self) -> f64 {
+        match self {
+            Self::Critical => 1.0,
+            Self::Warning => 0.5,
+            Self::Info => 0.2,
+        }
+    }
+}
+
+/// An MCP tool call that answers the same question as the `vc` command
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct McpToolCall {
+    /// Tool name on the `vc mcp` server
+    pub tool: String,
+
+    /// Tool arguments
+    pub arguments: serde_json::Value,
+}
+
+/// One thing for an agent to do
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ActionItem {
+    /// Stable identifier (e.g. `alert-disk-full`, `machine-offline-orko`)
+    pub id: String,
+
+    /// Position in the ranking (1 = do first)
+    pub rank: u32,
+
+    /// Kind of work
+    pub category: ActionCategory,
+
+    /// How bad the condition is
+    pub severity: ActionSeverity,
+
+    /// Short title
+    pub title: String,
+
+    /// Exact `vc` command to run
+    pub command: String,
+
+    /// Equivalent MCP tool call, for the items one exists for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_tool: Option<McpToolCall>,
+
+    /// Machines affected
+    pub machine_ids: Vec<String>,
+
+    /// Alerts this item covers
+    pub alert_ids: Vec<i64>,
+
+    /// Incidents this item covers
+    pub incident_ids: Vec<String>,
+
+    /// Confidence that `command` is the right next step (0.0 to 1.0)
+    pub confidence: f64,
+
+    /// What running `command` should achieve
+    pub expected_outcome: String,
+
+    /// Guardian playbook that already covers the condition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playbook_id: Option<String>,
+
+    /// Seconds since the condition began, when known
+    pub age_secs: Option<u64>,
+
+    /// Ranking score (0.0 to 1.0) from severity, age, blast radius and
+    /// playbook coverage
+    pub score: f64,
+
+    /// Alerts grouped under one probable root cause, for `alert-corr-*` items
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation: Option<CorrelationSummary>,
+
+    /// Upstream machine whose critical alert probably caused this item's
+    /// alerts, per the dependencies declared with `vc machines depend`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<String>,
+}
+
+/// A correlated alert group: the probable root and what it likely caused
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CorrelationSummary {
+    /// Id for `vc alert show --correlation`
+    pub correlation_id: String,
+
+    /// The probable root alert
+    pub root_alert_id: i64,
+
+    /// Rule that raised the root alert
+    pub root_rule_id: String,
+
+    /// The other alerts in the group
+    pub child_alert_ids: Vec<i64>,
+
+    /// Distinct rules among the children
+    pub child_rules: Vec<String>,
+}
+
+// ============================================================================
+// Status Data Structures
+// ============================================================================
+
+/// Comprehensive fleet status data for `vc robot status`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StatusData {
+    /// Fleet-level summary
+    pub fleet: FleetSummary,
+
+    /// Per-machine status
+    pub machines: Vec<MachineStatus>,
+
+    /// Repository status summary
+    pub repos: RepoSummary,
+
+    /// Alert counts by severity
+    pub alerts: AlertSummary,
+
+    /// Machine, health and alert numbers per machine tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_tag: Vec<TagBreakdown>,
+}
+
+/// Fleet-level summary
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FleetSummary {
+    /// Total number of machines
+    pub total_machines: u32,
+
+    /// Number of online machines
+    pub online: u32,
+
+    /// Number of offline machines
+    pub offline: u32,
+
+    /// Number of machines in maintenance, which are left out of
+    /// `health_score`
+    #[serde(default)]
+    pub maintenance: u32,
+
+    /// Overall fleet health score (0.0 to 1.0)
+    pub health_score: f64,
+}
+
+/// Per-machine status
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MachineStatus {
+    /// Machine identifier
+    pub id: String,
+
+    /// Status: "online", "offline", "degraded", "unknown", "maintenance"
+    pub status: String,
+
+    /// Last data collection timestamp, `None` if never collected from
+    pub last_seen: Option<DateTime<Utc>>,
+
+    /// Health score (0.0 to 1.0), `None` when no health summary has been persisted
+    pub health_score: Option<f64>,
+
+    /// Latest resource metrics (`None` when no sample has ever been collected)
+    pub metrics: Option<MachineMetrics>,
+
+    /// Top issue affecting this machine
+    pub top_issue: Option<String>,
+
+    /// Set while the machine is in maintenance; `health_score` is then the
+    /// last one computed before it went in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<MaintenanceInfo>,
+}
+
+/// Machine resource metrics
+///
+/// Each field is independently optional: a machine may have a load sample from
+/// the fallback probe but no CPU total, or system samples but no filesystem
+/// snapshot. Missing components are `null`, never zero-filled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct MachineMetrics {
+    /// CPU usage percentage (0-100)
+    pub cpu_pct: Option<f64>,
+
+    /// Memory usage percentage (0-100)
+    pub mem_pct: Option<f64>,
+
+    /// 5-minute load average
+    pub load5: Option<f64>,
+
+    /// Available disk percentage (0-100)
+    pub disk_free_pct: Option<f64>,
+}
+
+impl MachineMetrics {
+    /// True when every component is missing — such a struct is reported as `null`.
+    fn is_empty(&self) -> bool {
+        self.cpu_pct.is_none()
+            && self.mem_pct.is_none()
+            && self.load5.is_none()
+            && self.disk_free_pct.is_none()
+    }
+}
+
+/// Repository status summary
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RepoSummary {
+    /// Total tracked repositories
+    pub total: u32,
+
+    /// Repositories with uncommitted changes
+    pub dirty: u32,
+
+    /// Repositories ahead of remote
+    pub ahead: u32,
+
+    /// Repositories behind remote
+    pub behind: u32,
+}
+
+/// Unresolved alert counts by severity level
+///
+/// The severity vocabulary is the one `vc_alert` actually writes into
+/// `alert_history`: `critical`, `warning`, `info`. The previous
+/// critical/high/medium/low shape had no producer and was always zero.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AlertSummary {
+    /// Critical alerts
+    pub critical: u32,
+
+    /// Warning alerts
+    pub warning: u32,
+
+    /// Informational alerts
+    pub info: u32,
+}
+
+// ============================================================================
+// Accounts / Repos / Oracle Data Structures
+// ============================================================================
+
+/// Account status payload for `vc robot accounts`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AccountsData {
+    /// One entry per (machine, provider, account) known to the store
+    pub accounts: Vec<AccountInfo>,
+
+    /// Number of accounts returned
+    pub total: u32,
+}
+
+/// A single provider account, joining the latest usage and profile snapshots
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AccountInfo {
+    /// Machine the snapshot was collected on
+    pub machine_id: String,
+
+    /// Provider (e.g. "claude", "openai")
+    pub provider: String,
+
+    /// Provider-side account identifier
+    pub account_id: String,
+
+    /// Account email (from the caam profile collector), `None` if unknown
+    pub email: Option<String>,
+
+    /// Plan type (from the caam profile collector), `None` if unknown
+    pub plan_type: Option<String>,
+
+    /// Whether this is the account currently in use, `None` if unknown
+    pub is_current: Option<bool>,
+
+    /// Whether the account is enabled for rotation, `None` if unknown
+    pub is_active: Option<bool>,
+
+    /// Usage percentage (0-100), `None` if the provider did not report it
+    pub usage_pct: Option<f64>,
+
+    /// Remaining quota percentage (0-100), `None` if not reported
+    pub remaining_pct: Option<f64>,
+
+    /// Tokens consumed in the current window
+    pub tokens_used: Option<i64>,
+
+    /// Token limit for the current window
+    pub tokens_limit: Option<i64>,
+
+    /// When the current window resets
+    pub resets_at: Option<DateTime<Utc>>,
+
+    /// When the account status was collected
+    pub collected_at: Option<DateTime<Utc>>,
+}
+
+/// Repository payload for `vc robot repos`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ReposData {
+    /// One entry per tracked repository
+    pub repos: Vec<RepoInfo>,
+
+    /// Roll-up of the same repositories
+    pub summary: RepoSummary,
+}
+
+/// A single repository and its latest git status snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RepoInfo {
+    /// Machine the repository lives on
+    pub machine_id: String,
+
+    /// Repository identifier used by the `ru` collector
+    pub repo_id: String,
+
+    /// Repository name, `None` if the inventory row is missing
+    pub name: Option<String>,
+
+    /// Absolute path on the machine
+    pub path: Option<String>,
+
+    /// Remote URL
+    pub url: Option<String>,
+
+    /// Checked-out branch, `None` if no status snapshot exists yet
+    pub branch: Option<String>,
+
+    /// Whether the working tree has uncommitted changes
+    pub dirty: Option<bool>,
+
+    /// Commits ahead of the tracking branch
+    pub ahead: Option<u32>,
+
+    /// Commits behind the tracking branch
+    pub behind: Option<u32>,
+
+    /// Modified file count
+    pub modified: Option<u32>,
+
+    /// Untracked file count
+    pub untracked: Option<u32>,
+
+    /// When the status snapshot was taken
+    pub collected_at: Option<DateTime<Utc>>,
+}
+
+/// Oracle payload for `vc robot oracle`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct OracleData {
+    /// Rate-limit forecasts, most urgent first
+    pub forecasts: Vec<ForecastInfo>,
+
+    /// Number of usage samples fed to the forecaster
+    pub sample_count: u32,
+
+    /// Size of the history window the samples were drawn from
+    pub lookback_hours: u32,
+
+    /// Mounts forecast to fill within the default 30-day horizon, soonest
+    /// first
+    pub disk_forecasts: Vec<DiskForecastInfo>,
+}
+
+/// A mount forecast to fill, from `vc_query::forecast`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiskForecastInfo {
+    pub machine_id: String,
+    pub mount: String,
+
+    /// Used percentage in the latest bucket
+    pub usage_pct: f64,
+
+    /// Fitted growth rate
+    pub growth_bytes_per_day: f64,
+
+    /// Days until the mount is full at that rate
+    pub days_until_full: f64,
+
+    /// When the mount is projected to be full
+    pub full_at: Option<DateTime<Utc>>,
+
+    /// Forecast confidence (0.0 to 1.0), driven by history covered and how
+    /// steadily usage grew
+    pub confidence: f64,
+}
+
+/// A single rate-limit forecast produced by `vc_oracle`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ForecastInfo {
+    /// Provider the forecast applies to
+    pub provider: String,
+
+    /// Account the forecast applies to
+    pub account: String,
+
+    /// Most recent observed usage percentage
+    pub current_usage_pct: f64,
+
+    /// Observed burn rate, in usage-percent per minute
+    pub velocity_pct_per_min: f64,
+
+    /// Seconds until the account is projected to hit 100%.
+    /// `None` when usage is flat or falling, i.e. the limit is never reached at
+    /// the observed velocity.
+    pub time_to_limit_secs: Option<u64>,
+
+    /// Forecast confidence (0.0 to 1.0), driven by sample count and fit
+    pub confidence: f64,
+
+    /// Recommended action, tagged: `continue`, `slow_down`, `prepare_swap`,
+    /// `swap_now`, `emergency_pause`
+    pub recommended_action: serde_json::Value,
+
+    /// Best moment to swap accounts, if a swap is recommended
+    pub optimal_swap_time: Option<DateTime<Utc>>,
+
+    /// Alternative accounts ranked by remaining headroom
+    pub alternative_accounts: Vec<AlternativeAccount>,
+}
+
+/// An alternative account the Oracle could swap to
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AlternativeAccount {
+    /// Account identifier
+    pub account: String,
+
+    /// Remaining headroom as a usage percentage (100 - `usage_pct`)
+    pub headroom_pct: f64,
+}
+
+// ============================================================================
+// Store Access Helpers
+// ============================================================================
+
+/// Lookback window used when feeding the Oracle from usage history.
+const ORACLE_LOOKBACK_HOURS: i64 = 24;
+
+/// Usage percentage at or above which an account is worth triaging.
+const ACCOUNT_PRESSURE_PCT: f64 = 80.0;
+
+/// Tables `load_accounts` requires; caam/caut snapshots are joined if present.
+const ACCOUNT_TABLES: &[&str] = &["account_status"];
+
+/// Tables `load_repos` joins.
+const REPO_TABLES: &[&str] = &["repos", "repo_status_snapshots"];
+
+/// Parse a timestamp column. `DuckDB` hands these back as strings in either
+/// RFC 3339 (what the collectors write) or `CURRENT_TIMESTAMP`'s
+/// `YYYY-MM-DD HH:MM:SS` form (what column defaults write).
+fn parse_ts(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(raw, format) {
+            return Some(naive.and_utc());
+        }
+    }
+    None
+}
+
+/// Read a timestamp out of a JSON row field.
+fn row_ts(row: &serde_json::Value, key: &str) -> Option<DateTime<Utc>> {
+    row.get(key)?.as_str().and_then(parse_ts)
+}
+
+/// Read a string out of a JSON row field, treating empty strings as absent.
+fn row_str(row: &serde_json::Value, key: &str) -> Option<String> {
+    let value = row.get(key)?.as_str()?.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Read an f64 out of a JSON row field.
+fn row_f64(row: &serde_json::Value, key: &str) -> Option<f64> {
+    row.get(key)?.as_f64()
+}
+
+/// Read an i64 out of a JSON row field.
+fn row_i64(row: &serde_json::Value, key: &str) -> Option<i64> {
+    row.get(key)?.as_i64()
+}
+
+/// Read an unsigned count out of a JSON row field, saturating at zero.
+fn row_u32(row: &serde_json::Value, key: &str) -> Option<u32> {
+    let value = row.get(key)?.as_i64()?;
+    Some(u32::try_from(value.max(0)).unwrap_or(u32::MAX))
+}
+
+/// Read a boolean out of a JSON row field. `DuckDB` may hand back either a JSON
+/// bool or the 0/1 integer the SQLite-flavoured schema declares.
+fn row_bool(row: &serde_json::Value, key: &str) -> Option<bool> {
+    match row.get(key)? {
+        serde_json::Value::Bool(value) => Some(*value),
+        serde_json::Value::Number(value) => Some(value.as_i64().unwrap_or(0) != 0),
+        _ => None,
+    }
+}
+
+/// Seconds elapsed since `ts`, clamped at zero for clock skew.
+fn seconds_since(ts: DateTime<Utc>) -> u64 {
+    u64::try_from((Utc::now() - ts).num_seconds().max(0)).unwrap_or(0)
+}
+
+/// Latest `collected_at` in a table, or `None` when the table has no rows.
+fn latest_collected_at(store: &VcStore, table: &str) -> Option<DateTime<Utc>> {
+    let sql =
+        format!("SELECT CAST(MAX(CAST(collected_at AS TIMESTAMP)) AS TEXT) AS max_ts FROM {table}");
+    let rows = store.query_json(&sql).ok()?;
+    row_ts(rows.first()?, "max_ts")
+}
+
+/// Build the envelope staleness map: seconds since the newest row in each
+/// collector-backed table this command reads. Tables that have never been
+/// written are simply absent from the map.
+fn staleness_for(store: &VcStore, tables: &[&str]) -> HashMap<String, u64> {
+    let mut staleness = HashMap::new();
+    for table in tables {
+        if let Some(ts) = latest_collected_at(store, table) {
+            staleness.insert((*table).to_string(), seconds_since(ts));
+        }
+    }
+    staleness
+}
+
+/// Run `load` only when every table it reads exists. Otherwise the section
+/// comes back empty and the absent tables are recorded in `caps`.
+fn if_tables<T: Default>(
+    caps: &Capabilities,
+    tables: &[&str],
+    load: impl FnOnce() -> Result<T, RobotError>,
+) -> Result<T, RobotError> {
+    if caps.require(tables) {
+        load()
+    } else {
+        Ok(T::default())
+    }
+}
+
+/// Map a health score onto the severity vocabulary the robot schemas allow.
+fn severity_for_score(score: f64) -> &'static str {
+    if score >= 0.8 {
+        "healthy"
+    } else if score >= 0.5 {
+        "warning"
+    } else {
+        "critical"
+    }
+}
+
+/// A machine as the registry knows it.
+struct MachineRow {
+    id: String,
+    name: String,
+    status: String,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+/// Registry inventory. `last_seen` is `None` for machines that have been
+/// declared but never collected from — we do not substitute "now".
+fn load_machines(store: &VcStore) -> Result<Vec<MachineRow>, RobotError> {
+    let sql = "SELECT machine_id, hostname, display_name, status, \
+               CAST(last_seen_at AS TEXT) AS last_seen_at \
+               FROM machines ORDER BY hostname";
+    let rows = store.query_json(sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let id = row_str(row, "machine_id")?;
+            let name = row_str(row, "display_name")
+                .or_else(|| row_str(row, "hostname"))
+                .unwrap_or_else(|| id.clone());
+            Some(MachineRow {
+                id,
+                name,
+                status: row_str(row, "status").unwrap_or_else(|| "unknown".to_string()),
+                last_seen: row_ts(row, "last_seen_at"),
+            })
+        })
+        .collect())
+}
+
+/// Open maintenance windows per machine.
+fn load_maintenance(store: &VcStore) -> Result<HashMap<String, MaintenanceInfo>, RobotError> {
+    Ok(store
+        .active_maintenance()?
+        .into_iter()
+        .map(|window| {
+            let info = MaintenanceInfo {
+                since: parse_ts(&window.started_at),
+                until: window.until_at.as_deref().and_then(parse_ts),
+                reason: window.reason,
+            };
+            (window.machine_id, info)
+        })
+        .collect())
+}
+
+/// Latest persisted health summary per machine: score plus worst factor.
+fn load_health_scores(
+    store: &VcStore,
+) -> Result<HashMap<String, (f64, Option<String>)>, RobotError> {
+    let summaries = QueryBuilder::new(store).list_health_summaries()?;
+
+    Ok(summaries
+        .iter()
+        .filter_map(|row| {
+            let machine_id = row_str(row, "machine_id")?;
+            let overall = row_f64(row, "overall_score")?;
+            Some((machine_id, (overall, row_str(row, "worst_factor_id"))))
+        })
+        .collect())
+}
+
+/// Running agents per machine: from the agent inventory once it has rows,
+/// else active (unfinished) agent sessions.
+fn load_agent_counts(
+    store: &VcStore,
+    caps: &Capabilities,
+) -> Result<HashMap<String, u32>, RobotError> {
+    let inventoried = caps.has_table("agents_inventory")
+        && !store
+            .query_json("SELECT 1 FROM agents_inventory LIMIT 1")?
+            .is_empty();
+    let sql = if inventoried {
+        "SELECT machine_id, COUNT(*) AS active_agents FROM agents_inventory \
+         WHERE ended_at IS NULL GROUP BY machine_id"
+    } else {
+        "SELECT machine_id, COUNT(*) AS active_agents FROM agent_sessions \
+         WHERE ended_at IS NULL GROUP BY machine_id"
+    };
+    let rows = store.query_json(sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            let machine_id = row_str(row, "machine_id")?;
+            Some((machine_id, row_u32(row, "active_agents").unwrap_or(0)))
+        })
+        .collect())
+}
+
+/// Latest resource metrics per machine.
+///
+/// Prefers `sys_samples` (full sysmoni sample) and falls back to
+/// `sys_fallback_samples` (the always-on baseline probe, which has no CPU
+/// total). Disk headroom comes from the newest `sys_filesystems` snapshot.
+/// Anything we do not have stays `None`.
+fn load_latest_metrics(
+    store: &VcStore,
+    caps: &Capabilities,
+) -> Result<HashMap<String, MachineMetrics>, RobotError> {
+    let mut metrics: HashMap<String, MachineMetrics> = HashMap::new();
+
+    let fallback_sql = "SELECT f.machine_id, f.load5, f.mem_used_bytes, f.mem_total_bytes \
+                        FROM sys_fallback_samples f \
+                        INNER JOIN ( \
+                            SELECT machine_id, MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                            FROM sys_fallback_samples GROUP BY machine_id \
+                        ) latest ON f.machine_id = latest.machine_id \
+                            AND CAST(f.collected_at AS TIMESTAMP) = latest.max_ts";
+    let sys_sql = "SELECT s.machine_id, s.cpu_total, s.load5, s.mem_used_bytes, s.mem_total_bytes \
+                   FROM sys_samples s \
+                   INNER JOIN ( \
+                       SELECT machine_id, MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                       FROM sys_samples GROUP BY machine_id \
+                   ) latest ON s.machine_id = latest.machine_id \
+                       AND CAST(s.collected_at AS TIMESTAMP) = latest.max_ts";
+
+    // Fallback first so the richer sysmoni sample overwrites it.
+    for (table, sql) in [
+        ("sys_fallback_samples", fallback_sql),
+        ("sys_samples", sys_sql),
+    ] {
+        if !caps.require(&[table]) {
+            continue;
+        }
+        for row in store.query_json(sql)? {
+            let Some(machine_id) = row_str(&row, "machine_id") else {
+                continue;
+            };
+            let mem_pct = match (
+                row_f64(&row, "mem_used_bytes"),
+                row_f64(&row, "mem_total_bytes"),
+            ) {
+                (Some(used), Some(total)) if total > 0.0 => Some((used / total) * 100.0),
+                _ => None,
+            };
+            let entry = metrics.entry(machine_id).or_default();
+            entry.cpu_pct = row_f64(&row, "cpu_total").or(entry.cpu_pct);
+            entry.load5 = row_f64(&row, "load5").or(entry.load5);
+            entry.mem_pct = mem_pct.or(entry.mem_pct);
+        }
+    }
+
+    let disk_sql = "SELECT f.machine_id, MAX(f.usage_pct) AS max_usage_pct \
+                    FROM sys_filesystems f \
+                    INNER JOIN ( \
+                        SELECT machine_id, MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                        FROM sys_filesystems GROUP BY machine_id \
+                    ) latest ON f.machine_id = latest.machine_id \
+                        AND CAST(f.collected_at AS TIMESTAMP) = latest.max_ts \
+                    GROUP BY f.machine_id";
+    let disk_rows = if_tables(caps, &["sys_filesystems"], || {
+        Ok(store.query_json(disk_sql)?)
+    })?;
+    for row in disk_rows {
+        let Some(machine_id) = row_str(&row, "machine_id") else {
+            continue;
+        };
+        // Fullest filesystem drives headroom: the tightest mount is what hurts.
+        if let Some(used_pct) = row_f64(&row, "max_usage_pct") {
+            metrics.entry(machine_id).or_default().disk_free_pct =
+                Some((100.0 - used_pct).clamp(0.0, 100.0));
+        }
+    }
+
+    Ok(metrics)
+}
+
+/// Unresolved alerts grouped by the severity vocabulary `vc_alert` writes.
+fn load_alert_counts(store: &VcStore) -> Result<AlertCounts, RobotError> {
+    let sql = "SELECT LOWER(severity) AS severity, COUNT(*) AS alert_count \
+               FROM alert_history WHERE resolved_at IS NULL GROUP BY LOWER(severity)";
+    let rows = store.query_json(sql)?;
+
+    let mut counts = AlertCounts::default();
+    for row in &rows {
+        let count = row_u32(row, "alert_count").unwrap_or(0);
+        match row_str(row, "severity").as_deref() {
+            Some("critical") => counts.critical += count,
+            Some("warning") => counts.warning += count,
+            Some("info") => counts.info += count,
+            _ => {}
+        }
+    }
+    Ok(counts)
+}
+
+/// Latest git status per repository, joined onto the repo inventory.
+///
+/// A `FULL OUTER JOIN` because the two sides can drift: `repos` may list a
+/// repository the status collector has not reached yet, and a status snapshot
+/// can outlive an inventory row.
+fn load_repos(store: &VcStore) -> Result<Vec<RepoInfo>, RobotError> {
+    let sql = "SELECT \
+                   COALESCE(r.machine_id, s.machine_id) AS machine_id, \
+                   COALESCE(r.repo_id, s.repo_id) AS repo_id, \
+                   r.name AS name, \
+                   r.path AS path, \
+                   r.url AS url, \
+                   s.branch AS branch, \
+                   s.dirty AS dirty, \
+                   s.ahead AS ahead, \
+                   s.behind AS behind, \
+                   s.modified_count AS modified_count, \
+                   s.untracked_count AS untracked_count, \
+                   CAST(s.collected_at AS TEXT) AS collected_at \
+               FROM repos r \
+               FULL OUTER JOIN ( \
+                   SELECT rs.machine_id, rs.repo_id, rs.branch, rs.dirty, rs.ahead, rs.behind, \
+                          rs.modified_count, rs.untracked_count, rs.collected_at \
+                   FROM repo_status_snapshots rs \
+                   INNER JOIN ( \
+                       SELECT machine_id, repo_id, MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                       FROM repo_status_snapshots GROUP BY machine_id, repo_id \
+                   ) latest ON rs.machine_id = latest.machine_id \
+                       AND rs.repo_id = latest.repo_id \
+                       AND CAST(rs.collected_at AS TIMESTAMP) = latest.max_ts \
+               ) s ON r.machine_id = s.machine_id AND r.repo_id = s.repo_id \
+               ORDER BY 1, 2";
+    let rows = store.query_json(sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(RepoInfo {
+                machine_id: row_str(row, "machine_id")?,
+                repo_id: row_str(row, "repo_id")?,
+                name: row_str(row, "name"),
+                path: row_str(row, "path"),
+                url: row_str(row, "url"),
+                branch: row_str(row, "branch"),
+                dirty: row_bool(row, "dirty"),
+                ahead: row_u32(row, "ahead"),
+                behind: row_u32(row, "behind"),
+                modified: row_u32(row, "modified_count"),
+                untracked: row_u32(row, "untracked_count"),
+                collected_at: row_ts(row, "collected_at"),
+            })
+        })
+        .collect())
+}
+
+/// Roll repositories up into the summary carried by `vc robot status`.
+fn summarize_repos(repos: &[RepoInfo]) -> RepoSummary {
+    let count = |predicate: fn(&RepoInfo) -> bool| {
+        u32::try_from(repos.iter().filter(|repo| predicate(repo)).count()).unwrap_or(u32::MAX)
+    };
+
+    RepoSummary {
+        total: u32::try_from(repos.len()).unwrap_or(u32::MAX),
+        dirty: count(|repo| repo.dirty == Some(true)),
+        ahead: count(|repo| repo.ahead.is_some_and(|value| value > 0)),
+        behind: count(|repo| repo.behind.is_some_and(|value| value > 0)),
+    }
+}
+
+/// Latest parsed `account_status` row per account.
+///
+/// Quota, reset time and the active flag come from the accounts collector.
+/// Email, plan and rotation flags from caam profiles and token counts from
+/// caut usage are joined in when those tables exist; neither is required.
+fn load_accounts(store: &VcStore, caps: &Capabilities) -> Result<Vec<AccountInfo>, RobotError> {
+    let latest = |table: &str, columns: &str, filter: &str| {
+        format!(
+            "SELECT {columns} FROM {table} t \
+             INNER JOIN ( \
+                 SELECT machine_id, provider, account_id, \
+                        MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                 FROM {table} WHERE account_id IS NOT NULL {filter} \
+                 GROUP BY machine_id, provider, account_id \
+             ) latest ON t.machine_id = latest.machine_id \
+                 AND t.provider IS NOT DISTINCT FROM latest.provider \
+                 AND t.account_id = latest.account_id \
+                 AND CAST(t.collected_at AS TIMESTAMP) = latest.max_ts \
+             WHERE TRUE {filter}"
+        )
+    };
+
+    let mut select = String::from(
+        "SELECT s.machine_id AS machine_id, \
+                COALESCE(s.provider, 'unknown') AS provider, \
+                s.account_id AS account_id, \
+                s.used_pct AS usage_pct, \
+                s.remaining_pct AS remaining_pct, \
+                CAST(s.resets_at AS TEXT) AS resets_at, \
+                CAST(s.collected_at AS TEXT) AS collected_at, \
+                s.is_active AS is_current",
+    );
+    let mut joins = format!(
+        " FROM ({}) s",
+        latest(
+            "account_status",
+            "t.machine_id, t.provider, t.account_id, t.used_pct, t.remaining_pct, \
+             t.resets_at, t.collected_at, t.is_active",
+            "AND parse_error IS NULL",
+        )
+    );
+    if caps.has_table("account_profile_snapshots") {
+        select.push_str(", p.email AS email, p.plan_type AS plan_type, p.is_active AS is_active");
+        joins.push_str(&format!(
+            " LEFT JOIN ({}) p ON s.machine_id = p.machine_id \
+                 AND s.provider IS NOT DISTINCT FROM p.provider \
+                 AND s.account_id = p.account_id",
+            latest(
+                "account_profile_snapshots",
+                "t.machine_id, t.provider, t.account_id, t.email, t.plan_type, t.is_active",
+                "",
+            )
+        ));
+    }
+    if caps.has_table("account_usage_snapshots") {
+        select.push_str(", u.tokens_used AS tokens_used, u.tokens_limit AS tokens_limit");
+        joins.push_str(&format!(
+            " LEFT JOIN ({}) u ON s.machine_id = u.machine_id \
+                 AND s.provider IS NOT DISTINCT FROM u.provider \
+                 AND s.account_id = u.account_id",
+            latest(
+                "account_usage_snapshots",
+                "t.machine_id, t.provider, t.account_id, t.tokens_used, t.tokens_limit",
+                "",
+            )
+        ));
+    }
+    let sql = format!("{select}{joins} ORDER BY 4 DESC NULLS LAST, 2, 3");
+    let rows = store.query_json(&sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(AccountInfo {
+                machine_id: row_str(row, "machine_id")?,
+                provider: row_str(row, "provider")?,
+                account_id: row_str(row, "account_id")?,
+                email: row_str(row, "email"),
+                plan_type: row_str(row, "plan_type"),
+                is_current: row_bool(row, "is_current"),
+                is_active: row_bool(row, "is_active"),
+                usage_pct: row_f64(row, "usage_pct"),
+                remaining_pct: row_f64(row, "remaining_pct"),
+                tokens_used: row_i64(row, "tokens_used"),
+                tokens_limit: row_i64(row, "tokens_limit"),
+                resets_at: row_ts(row, "resets_at"),
+                collected_at: row_ts(row, "collected_at"),
+            })
+        })
+        .collect())
+}
+
+/// Commands whose most recent run produced output the accounts collector
+/// could not parse, as `(machine_id, command, error)`.
+fn load_account_parse_errors(store: &VcStore) -> Result<Vec<(String, String, String)>, RobotError> {
+    let sql = "SELECT s.machine_id, s.source_command, s.parse_error \
+               FROM account_status s \
+               INNER JOIN ( \
+                   SELECT machine_id, source_command, \
+                          MAX(CAST(collected_at AS TIMESTAMP)) AS max_ts \
+                   FROM account_status \
+                   GROUP BY machine_id, source_command \
+               ) latest ON s.machine_id = latest.machine_id \
+                   AND s.source_command = latest.source_command \
+                   AND CAST(s.collected_at AS TIMESTAMP) = latest.max_ts \
+               WHERE s.parse_error IS NOT NULL \
+               ORDER BY 1, 2";
+    let rows = store.query_json(sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some((
+                row_str(row, "machine_id")?,
+                row_str(row, "source_command")?,
+                row_str(row, "parse_error")?,
+            ))
+        })
+        .collect())
+}
+
+/// Load account usage history for the Oracle.
+///
+/// The `predictions` table is never written by anything, so forecasts are
+/// computed live: this pulls the raw usage series and `vc_oracle` turns it into
+/// velocity, time-to-limit and a recommended action.
+fn load_usage_samples(store: &VcStore) -> Result<Vec<UsageSample>, RobotError> {
+    let cutoff = (Utc::now() - TimeDelta::hours(ORACLE_LOOKBACK_HOURS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let sql = format!(
+        "SELECT provider, account_id, usage_pct, \
+                CAST(collected_at AS TEXT) AS collected_at, \
+                CAST(resets_at AS TEXT) AS resets_at \
+         FROM account_usage_snapshots \
+         WHERE usage_pct IS NOT NULL \
+           AND CAST(collected_at AS TIMESTAMP) >= CAST('{cutoff}' AS TIMESTAMP) \
+         ORDER BY CAST(collected_at AS TIMESTAMP) ASC"
+    );
+    let rows = store.query_json(&sql)?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|row| {
+            Some(UsageSample {
+                provider: row_str(row, "provider")?,
+                account: row_str(row, "account_id")?,
+                used_percent: row_f64(row, "usage_pct")?,
+                collected_at: row_ts(row, "collected_at")?,
+                resets_at: row_ts(row, "resets_at"),
+            })
+        })
+        .collect())
+}
+
+// ============================================================================
+// Health Command Implementation
+// ============================================================================
+
+/// Generate fleet health from the store.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+pub fn robot_health(store: &VcStore) -> Result<RobotEnvelope<HealthData>, RobotError> {
+    let caps = store.capabilities()?;
+    let overview = QueryBuilder::new(store).fleet_overview()?;
+    let machines = if_tables(&caps, &["machines"], || load_machines(store))?;
+    let health_scores = if_tables(&caps, &["health_summary"], || load_health_scores(store))?;
+    let maintenance = if_tables(&caps, &["machine_maintenance"], || load_maintenance(store))?;
+    let agent_counts = if_tables(&caps, &["agent_sessions"], || {
+        load_agent_counts(store, &caps)
+    })?;
+    let metrics = load_latest_metrics(store, &caps)?;
+    let alerts_by_severity = if_tables(&caps, &["alert_history"], || load_alert_counts(store))?;
+    let daemon = if_tables(&caps, &["daemon_resource_state"], || {
+        Ok(store.get_daemon_resource_state()?)
+    })?;
+    let daemon_lease = if_tables(&caps, &["leases"], || {
+        Ok(store.current_lease(vc_store::DAEMON_LEASE)?)
+    })?;
+
+    let mut warnings = Vec::new();
+    if let Some(state) = &daemon
+        && (state.level != "normal" || state.disk_pressure)
+    {
+        warnings.push(format!(
+            "daemon is degraded ({}{}): {}",
+            state.level,
+            if state.disk_pressure {
+                ", writes paused for disk"
+            } else {
+                ""
+            },
+            state.reason.as_deref().unwrap_or("no reason recorded")
+        ));
+    }
+    if let Some(lease) = &daemon_lease
+        && !lease.is_live(Utc::now())
+    {
+        warnings.push(format!(
+            "daemon lease held by {} expired at {} - the daemon may have stopped without \
+             releasing it",
+            lease.holder_id, lease.expires_at
+        ));
+    }
+    if machines.is_empty() {
+        warnings.push(
+            "machine registry is empty - run `vc machine add` or `vc collect` to populate it"
+                .to_string(),
+        );
+    }
+    if health_scores.is_empty() {
+        warnings.push(
+            "no health summaries persisted - per-machine scores are null and the fleet score \
+             falls back to 1.0"
+                .to_string(),
+        );
+    }
+
+    let machine_health: Vec<MachineHealth> = machines
+        .iter()
+        .map(|machine| {
+            let scored = health_scores.get(&machine.id);
+            let overall = scored.map(|(value, _)| *value);
+            let top_issue = scored.and_then(|(_, worst)| worst.clone());
+            // A machine the registry calls online but whose score has fallen is
+            // degraded, not healthy.
+            let status = match (machine.status.as_str(), overall) {
+                ("online", Some(value)) if value < 0.8 => "degraded".to_string(),
+                _ => machine.status.clone(),
+            };
+            let machine_metrics = metrics.get(&machine.id);
+
+            MachineHealth {
+                id: machine.id.clone(),
+                name: machine.name.clone(),
+                score: overall,
+                status,
+                top_issue,
+                last_seen: machine.last_seen,
+                agent_count: agent_counts.get(&machine.id).copied().unwrap_or(0),
+                cpu_percent: machine_metrics.and_then(|m| m.cpu_pct),
+                memory_percent: machine_metrics.and_then(|m| m.mem_pct),
+                maintenance: maintenance.get(&machine.id).cloned(),
+            }
+        })
+        .collect();
+
+    let data = HealthData {
+        overall: OverallHealth {
+            score: overview.fleet_health_score,
+            severity: severity_for_score(overview.fleet_health_score).to_string(),
+            active_alerts: u32::try_from(overview.active_alerts).unwrap_or(u32::MAX),
+            machine_count: u32::try_from(overview.total_machines).unwrap_or(u32::MAX),
+            agent_count: u32::try_from(overview.active_agents).unwrap_or(u32::MAX),
+        },
+        machines: machine_health,
+        alerts_by_severity,
+        daemon,
+        daemon_lease,
+    };
+
+    Ok(RobotEnvelope::new("vc.robot.health.v1", data)
+        .with_staleness(staleness_for(
+            store,
+            &["sys_samples", "sys_fallback_samples", "health_summary"],
+        ))
+        .with_warnings(warnings)
+        .add_missing_capabilities(caps.missing())
+        .add_missing_capabilities(overview.missing_capabilities))
+}
+
+/// Age at which an action item gets the whole age part of its ranking score.
+const TRIAGE_FULL_AGE_SECS: f64 = 86_400.0;
+
+/// Affected machines at which an action item gets the whole blast-radius part
+/// of its ranking score.
+const TRIAGE_FULL_BLAST: f64 = 16.0;
+
+impl ActionItem {
+    /// An unranked item that affects nothing yet.
+    fn new(
+        id: impl Into<String>,
+        category: ActionCategory,
+        severity: ActionSeverity,
+        title: impl Into<String>,
+        command: impl Into<String>,
+        confidence: f64,
+        expected_outcome: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            rank: 0,
+            category,
+            severity,
+            title: title.into(),
+            command: command.into(),
+            mcp_tool: None,
+            machine_ids: Vec::new(),
+            alert_ids: Vec::new(),
+            incident_ids: Vec::new(),
+            confidence,
+            expected_outcome: expected_outcome.into(),
+            playbook_id: None,
+            age_secs: None,
+            score: 0.0,
+            correlation: None,
+            caused_by: None,
+        }
+    }
+
+    fn machines(mut self, machine_ids: impl IntoIterator<Item = String>) -> Self {
+        self.machine_ids.extend(machine_ids);
+        self
+    }
+
+    fn mcp(mut self, tool: &str, arguments: serde_json::Value) -> Self {
+        self.mcp_tool = Some(McpToolCall {
+            tool: tool.to_string(),
+            arguments,
+        });
+        self
+    }
+
+    fn since(mut self, started: Option<DateTime<Utc>>) -> Self {
+        self.age_secs = started.map(seconds_since);
+        self
+    }
+
+    /// Severity carries most of the score. Among items of one severity, the
+    /// condition that has lasted longer and touches more machines comes
+    /// first, and one a playbook already covers is cheap to act on, so it
+    /// moves up too.
+    #[allow(clippy::cast_precision_loss)]
+    fn rank_score(&self) -> f64 {
+        let age = self
+            .age_secs
+            .map_or(0.0, |secs| (secs as f64 / TRIAGE_FULL_AGE_SECS).min(1.0));
+        let blast =
+            ((self.machine_ids.len().max(1) as f64).log2() / TRIAGE_FULL_BLAST.log2()).min(1.0);
+        let playbook = if self.playbook_id.is_some() { 1.0 } else { 0.0 };
+        let score = 0.55 * self.severity.weight() + 0.2 * age + 0.15 * blast + 0.1 * playbook;
+        (score * 1000.0).round() / 1000.0
+    }
+}
+
+/// Unresolved alerts that share a rule, or a probable root cause.
+struct AlertGroup {
+    /// The rule, or for a correlated group the root alert's rule
+    rule_id: String,
+    title: String,
+    severity: ActionSeverity,
+    alert_ids: Vec<i64>,
+    unacked_ids: Vec<i64>,
+    machine_ids: Vec<String>,
+    oldest: Option<DateTime<Utc>>,
+    correlation: Option<CorrelationSummary>,
+}
+
+/// Group unresolved alerts by correlation where the daemon has linked them,
+/// otherwise by rule, oldest group first.
+fn load_alert_groups(store: &VcStore) -> Result<Vec<AlertGroup>, RobotError> {
+    let sql = "SELECT id, rule_id, severity, title, machine_id, acknowledged, \
+               CAST(fired_at AS TEXT) AS fired_at, correlation_id, \
+               COALESCE(correlation_root, 0) = 1 AS correlation_root \
+               FROM alert_history WHERE resolved_at IS NULL \
+               ORDER BY CAST(fired_at AS TIMESTAMP), id";
+    let mut groups: Vec<AlertGroup> = Vec::new();
+    for row in store.query_json(sql)? {
+        let Some(id) = row_i64(&row, "id") else {
+            continue;
+        };
+        let rule_id = row_str(&row, "rule_id").unwrap_or_else(|| "unknown".to_string());
+        let title = row_str(&row, "title").unwrap_or_else(|| rule_id.clone());
+        let severity = ActionSeverity::parse(&row_str(&row, "severity").unwrap_or_default());
+        let correlation_id = row_str(&row, "correlation_id");
+        let index =
+            match groups
+                .iter()
+                .position(|group| match (&group.correlation, &correlation_id) {
+                    (Some(summary), Some(correlation_id)) => {
+                        summary.correlation_id == *correlation_id
+                    }
+                    (None, None) => group.rule_id == rule_id,
+                    _ => false,
+                }) {
+                Some(index) => index,
+                None => {
+                    groups.push(AlertGroup {
+                        title: title.clone(),
+                        rule_id: rule_id.clone(),
+                        severity,
+                        alert_ids: Vec::new(),
+                        unacked_ids: Vec::new(),
+                        machine_ids: Vec::new(),
+                        oldest: row_ts(&row, "fired_at"),
+                        correlation: correlation_id.map(|correlation_id| CorrelationSummary {
+                            correlation_id,
+                            ..CorrelationSummary::default()
+                        }),
+                    });
+                    groups.len() - 1
+                }
+            };
+        let group = &mut groups[index];
+        if let Some(summary) = &mut group.correlation {
+            if row_bool(&row, "correlation_root") == Some(true) {
+                summary.root_alert_id = id;
+                summary.root_rule_id.clone_from(&rule_id);
+                group.rule_id = rule_id;
+                group.title = title;
+            } else {
+                summary.child_alert_ids.push(id);
+                if !summary.child_rules.contains(&rule_id) {
+                    summary.child_rules.push(rule_id);
+                }
+            }
+        }
+        group.severity = group.severity.max(severity);
+        group.alert_ids.push(id);
+        if row_bool(&row, "acknowledged") != Some(true) {
+            group.unacked_ids.push(id);
+        }
+        if let Some(machine_id) = row_str(&row, "machine_id")
+            && !group.machine_ids.contains(&machine_id)
+        {
+            group.machine_ids.push(machine_id);
+        }
+    }
+    for group in &mut groups {
+        group.machine_ids.sort();
+    }
+    Ok(groups)
+}
+
+/// Enabled stored playbooks that fire on an alert rule, keyed by the rule.
+/// The oldest wins when several share a rule.
+fn load_alert_playbooks(store: &VcStore) -> Result<HashMap<String, String>, RobotError> {
+    let rows = store.query_json(
+        "SELECT playbook_id, trigger_condition FROM guardian_playbooks \
+         WHERE enabled ORDER BY created_at, playbook_id",
+    )?;
+    let mut playbooks = HashMap::new();
+    for row in rows {
+        // Read as plain JSON so the triage does not need `vc_guardian`
+        let Some(trigger) = row_str(&row, "trigger_condition")
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        else {
+            continue;
+        };
+        if trigger.get("type").and_then(serde_json::Value::as_str) != Some("on_alert") {
+            continue;
+        }
+        if let (Some(rule_id), Some(playbook_id)) = (
+            trigger.get("rule_id").and_then(serde_json::Value::as_str),
+            row_str(&row, "playbook_id"),
+        ) {
+            playbooks.entry(rule_id.to_string()).or_insert(playbook_id);
+        }
+    }
+    Ok(playbooks)
+}
+
+/// The first built-in playbook that fires on an alert rule
+#[cfg(feature = "guardian")]
+fn builtin_alert_playbook(guardian: &vc_guardian::Guardian, rule_id: &str) -> Option<String> {
+    guardian
+        .playbooks_for_alert(rule_id)
+        .first()
+        .map(|playbook| playbook.playbook_id.clone())
+}
+
+/// The action for one rule's unresolved alerts: trigger the playbook that
+/// covers it, acknowledge what nobody has looked at, or dig into what keeps
+/// an acknowledged alert firing.
+fn alert_action(group: AlertGroup, playbook_id: Option<String>) -> ActionItem {
+    if group.correlation.is_some() {
+        return correlated_alert_action(group, playbook_id);
+    }
+    let count = group.alert_ids.len();
+    let rule = &group.rule_id;
+    let mut action = if let Some(playbook_id) = &playbook_id {
+        ActionItem::new(
+            format!("alert-{rule}"),
+            ActionCategory::Remediate,
+            group.severity,
+            format!("Trigger playbook {playbook_id} for {count} {rule} alert(s)"),
+            format!("vc guardian trigger {playbook_id}"),
+            0.85,
+            format!("Playbook {playbook_id} runs its steps; the alerts resolve once {rule} clears"),
+        )
+    } else if let [id] = group.unacked_ids.as_slice() {
+        ActionItem::new(
+            format!("alert-{rule}"),
+            ActionCategory::Ack,
+            group.severity,
+            format!("Acknowledge alert {id}: {}", group.title),
+            format!("vc alert ack {id}"),
+            0.95,
+            "The alert is marked as seen so it stops re-notifying while you work the cause",
+        )
+    } else if !group.unacked_ids.is_empty() {
+        ActionItem::new(
+            format!("alert-{rule}"),
+            ActionCategory::Ack,
+            group.severity,
+            format!(
+                "Acknowledge {} {rule} alert(s): {}",
+                group.unacked_ids.len(),
+                group.title
+            ),
+            format!("vc alert ack --all --type {rule}"),
+            0.9,
+            "The alerts are marked as seen so they stop re-notifying while you work the cause",
+        )
+    } else {
+        ActionItem::new(
+            format!("alert-{rule}"),
+            ActionCategory::Investigate,
+            group.severity,
+            format!(
+                "{count} acknowledged {rule} alert(s) still firing: {}",
+                group.title
+            ),
+            "vc alert list".to_string(),
+            0.6,
+            format!("Shows the open {rule} alerts, to find the condition that keeps them firing"),
+        )
+        .mcp("vc_query_alerts", serde_json::json!({}))
+    };
+    action.playbook_id = playbook_id;
+    action.alert_ids = group.alert_ids;
+    action.machines(group.machine_ids).since(group.oldest)
+}
+
+/// The action for alerts linked to one probable root cause: work the root,
+/// since clearing it should clear the rest.
+fn correlated_alert_action(group: AlertGroup, playbook_id: Option<String>) -> ActionItem {
+    let summary = group.correlation.unwrap_or_default();
+    let root = summary.root_alert_id;
+    let rule = &group.rule_id;
+    let children = summary.child_alert_ids.len();
+    let id = format!("alert-corr-{}", summary.correlation_id);
+    let mut action = if let Some(playbook_id) = &playbook_id {
+        ActionItem::new(
+            id,
+            ActionCategory::Remediate,
+            group.severity,
+            format!("Trigger playbook {playbook_id} for {rule} and {children} correlated alert(s)"),
+            format!("vc guardian trigger {playbook_id}"),
+            0.85,
+            format!("Playbook {playbook_id} works the probable root cause, {rule}"),
+        )
+    } else if group.unacked_ids.contains(&root) {
+        ActionItem::new(
+            id,
+            ActionCategory::Ack,
+            group.severity,
+            format!(
+                "Acknowledge alert {root} and {children} correlated alert(s): {}",
+                group.title
+            ),
+            format!("vc alert ack {root} --with-children"),
+            0.9,
+            "The probable root and the alerts it likely caused stop re-notifying while you work it",
+        )
+    } else {
+        ActionItem::new(
+            id,
+            ActionCategory::Investigate,
+            group.severity,
+            format!(
+                "Alert {root} is the probable root of {children} other alert(s): {}",
+                group.title
+            ),
+            format!("vc alert show --correlation {}", summary.correlation_id),
+            0.7,
+            format!("Lists the correlated alerts, to confirm {rule} is the cause to work"),
+        )
+    };
+    action.playbook_id = playbook_id;
+    action.alert_ids = group.alert_ids;
+    action.correlation = Some(summary);
+    action.machines(group.machine_ids).since(group.oldest)
+}
+
+/// Rank alert actions by the failures they stem from: the action covering
+/// an upstream machine's critical alert takes on its downstream machines as
+/// blast radius, and an action whose machines are all downstream of a
+/// failure is demoted to info as "probably caused by" that machine.
+fn trace_upstream_failures(actions: &mut [ActionItem], failures: &[UpstreamFailure]) {
+    for action in actions
+        .iter_mut()
+        .filter(|action| !action.alert_ids.is_empty())
+    {
+        if let Some(failure) = failures.iter().find(|failure| {
+            failure
+                .alert_ids
+                .iter()
+                .any(|id| action.alert_ids.contains(id))
+        }) {
+            for machine_id in &failure.affected_machines {
+                if !action.machine_ids.contains(machine_id) {
+                    action.machine_ids.push(machine_id.clone());
+                }
+            }
+            action.machine_ids.sort();
+        } else if !action.machine_ids.is_empty()
+            && let Some(failure) = failures.iter().find(|failure| {
+                action
+                    .machine_ids
+                    .iter()
+                    .all(|machine_id| failure.affected_machines.contains(machine_id))
+            })
+        {
+            action.severity = ActionSeverity::Info;
+            action.confidence = (action.confidence * 0.5 * 100.0).round() / 100.0;
+            action.title = format!(
+                "{} (probably caused by {})",
+                action.title, failure.machine_id
+            );
+            action.caused_by = Some(failure.machine_id.clone());
+        }
+    }
+}
+
+/// Generate ranked triage actions from the store.
+///
+/// Every action is derived from a row that exists: an unresolved alert, an
+/// open incident, an offline machine, a failing collector, an account under
+/// pressure, a repository that has drifted from its remote, uncommitted work
+/// an agent left and nobody came back to, or a tool older than its entry in
+/// `min_versions` (`[collectors.min_versions]`), or an agent in a tmux pane
+/// that has stopped producing output. Alerts
+/// whose rule an enabled guardian playbook handles become a "trigger the
+/// playbook" action. Alerts on machines downstream of a machine with a
+/// critical alert rank as info, marked as probably caused by it.
+/// `max_items` keeps only the best-ranked actions.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+#[allow(clippy::too_many_lines)]
+pub fn robot_triage(
+    store: &VcStore,
+    min_versions: &HashMap<String, String>,
+    max_items: Option<usize>,
+) -> Result<RobotEnvelope<TriageData>, RobotError> {
+    let caps = store.capabilities()?;
+    let overview = QueryBuilder::new(store).fleet_overview()?;
+    let machines = if_tables(&caps, &["machines"], || load_machines(store))?;
+    let health_scores = if_tables(&caps, &["health_summary"], || load_health_scores(store))?;
+    let accounts = if_tables(&caps, ACCOUNT_TABLES, || load_accounts(store, &caps))?;
+    let repos = if_tables(&caps, REPO_TABLES, || load_repos(store))?;
+    let stored_playbooks = if_tables(&caps, &["guardian_playbooks"], || {
+        load_alert_playbooks(store)
+    })?;
+    #[cfg(feature = "guardian")]
+    let guardian = vc_guardian::Guardian::new();
+
+    let mut actions: Vec<ActionItem> = Vec::new();
+    let mut warnings = Vec::new();
+
+    // 1. Unresolved alerts, one action per correlated group or rule.
+    for group in if_tables(&caps, &["alert_history"], || load_alert_groups(store))? {
+        // Without `guardian` only the stored playbooks are known
+        #[cfg(feature = "guardian")]
+        let builtin = builtin_alert_playbook(&guardian, &group.rule_id);
+        #[cfg(not(feature = "guardian"))]
+        let builtin: Option<String> = None;
+        let playbook_id = builtin.or_else(|| stored_playbooks.get(&group.rule_id).cloned());
+        actions.push(alert_action(group, playbook_id));
+    }
+    trace_upstream_failures(&mut actions, &overview.upstream_failures);
+
+    // 2. Open incidents.
+    let incident_sql = "SELECT incident_id, title, severity, \
+                        CAST(started_at AS TEXT) AS started_at FROM incidents \
+                        WHERE COALESCE(status, 'open') NOT IN ('closed', 'resolved') \
+                        ORDER BY CAST(started_at AS TIMESTAMP) LIMIT 20";
+    for row in if_tables(
+        &caps,
+        &["incidents"],
+        || Ok(store.query_json(incident_sql)?),
+    )? {
+        let Some(incident_id) = row_str(&row, "incident_id") else {
+            continue;
+        };
+        let mut action = ActionItem::new(
+            format!("incident-{incident_id}"),
+            ActionCategory::Investigate,
+            ActionSeverity::parse(&row_str(&row, "severity").unwrap_or_default()),
+            format!(
+                "Incident {incident_id} is open: {}",
+                row_str(&row, "title").unwrap_or_default()
+            ),
+            format!("vc incident show {incident_id}"),
+            0.85,
+            "Shows the incident timeline and notes, to decide whether it can be mitigated",
+        )
+        .mcp(
+            "vc_query_incidents",
+            serde_json::json!({ "status": "open" }),
+        )
+        .since(row_ts(&row, "started_at"));
+        action.incident_ids.push(incident_id);
+        actions.push(action);
+    }
+
+    // 3. Machines that are offline or scoring badly. One in maintenance is
+    // neither: it was taken out of service on purpose.
+    for machine in &machines {
+        if machine.status == "maintenance" {
+            continue;
+        }
+        let overall = health_scores.get(&machine.id).map(|(value, _)| *value);
+        let worst = health_scores
+            .get(&machine.id)
+            .and_then(|(_, worst)| worst.clone());
+
+        if machine.status == "offline" {
+            actions.push(
+                ActionItem::new(
+                    format!("machine-offline-{}", machine.id),
+                    ActionCategory::Investigate,
+                    ActionSeverity::Critical,
+                    format!("Machine {} is offline", machine.name),
+                    format!("vc machine probe {}", machine.id),
+                    0.8,
+                    "Shows whether the machine answers again, or why the probe fails",
+                )
+                .mcp(
+                    "vc_query_machines",
+                    serde_json::json!({ "status": "offline" }),
+                )
+                .machines([machine.id.clone()])
+                .since(machine.last_seen),
+            );
+        } else if let Some(value) = overall
+            && value < 0.8
+        {
+            actions.push(
+                ActionItem::new(
+                    format!("machine-degraded-{}", machine.id),
+                    ActionCategory::Investigate,
+                    if value < 0.5 {
+                        ActionSeverity::Critical
+                    } else {
+                        ActionSeverity::Warning
+                    },
+                    format!("Machine {} health is {:.2}", machine.name, value),
+                    format!("vc status --machine {}", machine.id),
+                    0.75,
+                    match worst {
+                        Some(ref factor) => format!("Shows how far {factor} is dragging the score"),
+                        None => "Shows which health factor is dragging the score".to_string(),
+                    },
+                )
+                .mcp(
+                    "vc_fleet_status",
+                    serde_json::json!({ "machine": machine.id }),
+                )
+                .machines([machine.id.clone()]),
+            );
+        }
+    }
+
+    // 4. Accounts approaching their rate limit.
+    for account in &accounts {
+        let Some(usage) = account.usage_pct else {
+            continue;
+        };
+        if usage < ACCOUNT_PRESSURE_PCT {
+            continue;
+        }
+        let label = account
+            .email
+            .clone()
+            .unwrap_or_else(|| account.account_id.clone());
+        actions.push(
+            ActionItem::new(
+                format!("account-usage-{}-{}", account.provider, account.account_id),
+                ActionCategory::Capacity,
+                if usage >= 95.0 {
+                    ActionSeverity::Critical
+                } else {
+                    ActionSeverity::Warning
+                },
+                format!("{} account {label} at {usage:.0}%", account.provider),
+                "vc robot oracle",
+                0.85,
+                format!("Forecasts when {label} hits its limit and which account to swap to"),
+            )
+            .machines([account.machine_id.clone()]),
+        );
+    }
+
+    // 5. Collectors that are failing or have never succeeded.
+    let collector_sql = "SELECT machine_id, collector_name, status, \
+                         CAST(last_success_at AS TEXT) AS last_success_at \
+                         FROM collector_status \
+                         WHERE status IS NOT NULL AND LOWER(status) <> 'ok' \
+                         ORDER BY machine_id, collector_name LIMIT 10";
+    for row in if_tables(&caps, &["collector_status"], || {
+        Ok(store.query_json(collector_sql)?)
+    })? {
+        let Some(collector) = row_str(&row, "collector_name") else {
+            continue;
+        };
+        let machine_id = row_str(&row, "machine_id").unwrap_or_else(|| "local".to_string());
+        let status = row_str(&row, "status").unwrap_or_else(|| "unknown".to_string());
+        actions.push(
+            ActionItem::new(
+                format!("collector-{machine_id}-{collector}"),
+                ActionCategory::Remediate,
+                ActionSeverity::Warning,
+                format!("Collector {collector} is {status} on {machine_id}"),
+                format!("vc collect --collector {collector}"),
+                0.75,
+                "Re-runs the collector so its data is fresh, or shows the error that stops it",
+            )
+            .mcp("vc_collector_status", serde_json::json!({}))
+            .machines([machine_id])
+            .since(row_ts(&row, "last_success_at")),
+        );
+    }
+
+    // 6. Required services that are not running.
+    for service in if_tables(&caps, &["machine_services"], || {
+        Ok(store.list_machine_services(None)?)
+    })? {
+        if !service.required || service.running {
+            continue;
+        }
+        actions.push(
+            ActionItem::new(
+                format!("service-{}-{}", service.machine_id, service.service_name),
+                ActionCategory::Remediate,
+                ActionSeverity::Warning,
+                format!(
+                    "Start service {} on {}",
+                    service.service_name, service.machine_id
+                ),
+                format!("vc machine probe {}", service.machine_id),
+                0.7,
+                format!(
+                    "Once {} ({} {}) is started, the probe confirms it is running",
+                    service.service_name, service.kind, service.target
+                ),
+            )
+            .machines([service.machine_id.clone()]),
+        );
+    }
+
+    // 7. Repositories that have drifted from their remotes.
+    let repo_summary = summarize_repos(&repos);
+    if repo_summary.behind > 0 || repo_summary.dirty > 0 {
+        let mut drifted: Vec<String> = repos
+            .iter()
+            .filter(|repo| repo.dirty == Some(true) || repo.behind.is_some_and(|n| n > 0))
+            .map(|repo| repo.machine_id.clone())
+            .collect();
+        drifted.sort();
+        drifted.dedup();
+        actions.push(
+            ActionItem::new(
+                "repos-drift",
+                ActionCategory::Investigate,
+                ActionSeverity::Info,
+                format!(
+                    "{} dirty, {} behind of {} repos",
+                    repo_summary.dirty, repo_summary.behind, repo_summary.total
+                ),
+                "vc robot repos",
+                0.7,
+                "Lists which working trees have uncommitted changes or need a pull",
+            )
+            .machines(drifted),
+        );
+    }
+
+    // 8. Guardian runs waiting on a human.
+    if overview.pending_approvals > 0 {
+        actions.push(ActionItem::new(
+            "guardian-pending-approvals",
+            ActionCategory::Ack,
+            ActionSeverity::Warning,
+            format!(
+                "{} playbook run(s) awaiting approval",
+                overview.pending_approvals
+            ),
+            "vc guardian runs",
+            0.9,
+            "Lists the blocked runs; approving one with `vc guardian approve` lets it act",
+        ));
+    }
+
+    // 9. Tools older than the configured minimum, one action per tool.
+    let spreads = if_tables(&caps, &["tool_version_history"], || {
+        Ok(QueryBuilder::new(store).fleet_versions(None, min_versions)?)
+    })?;
+    for spread in &spreads {
+        let Some(minimum) = &spread.minimum_version else {
+            continue;
+        };
+        let mut outdated: Vec<String> = spread
+            .versions
+            .iter()
+            .filter(|group| group.below_minimum)
+            .flat_map(|group| group.machines.iter().cloned())
+            .collect();
+        if outdated.is_empty() {
+            continue;
+        }
+        outdated.sort();
+        actions.push(
+            ActionItem::new(
+                format!("version-{}", spread.tool),
+                ActionCategory::Remediate,
+                ActionSeverity::Warning,
+                format!(
+                    "{} machine(s) run {} older than {minimum}",
+                    outdated.len(),
+                    spread.tool
+                ),
+                "vc fleet versions",
+                0.8,
+                format!(
+                    "Shows each install of {} to upgrade; most of the fleet runs {}",
+                    spread.tool, spread.majority_version
+                ),
+            )
+            .machines(outdated),
+        );
+    }
+
+    // 10. Uncommitted work an agent left and no session has come back to.
+    let stale_after = TimeDelta::days(vc_query::repos::DEFAULT_STALE_DAYS);
+    let stale = if_tables(
+        &caps,
+        &["repos", "repo_status_snapshots", "agent_sessions"],
+        || Ok(vc_query::repos::stale_work(store, stale_after, Utc::now())?),
+    )?;
+    for repo in stale.into_iter().take(10) {
+        let days = repo.stale_days.unwrap_or_default();
+        let path = repo.path.clone().unwrap_or_else(|| repo.name.clone());
+        actions.push(
+            ActionItem::new(
+                format!(
+                    "repo-stale-{}-{}",
+                    repo.machine_id,
+                    repo.repo_id.as_deref().unwrap_or(&repo.name)
+                ),
+                ActionCategory::Investigate,
+                ActionSeverity::Warning,
+                format!(
+                    "{} dirty file(s) left in {} on {} by an agent {days}d ago",
+                    repo.dirty_files, repo.name, repo.machine_id
+                ),
+                "vc repos activity",
+                0.7,
+                format!("Shows the sessions behind {path}, to commit or discard what they left"),
+            )
+            .machines([repo.machine_id.clone()])
+            .since(repo.last_session_at),
+        );
+    }
+
+    // 11. Agents in tmux panes that have stopped producing output.
+    let stalled_after = TimeDelta::minutes(vc_query::agents::DEFAULT_STALLED_MINS);
+    let stalled = if_tables(&caps, &["agents_inventory"], || {
+        Ok(vc_query::agents::stalled_agents(
+            store,
+            stalled_after,
+            Utc::now(),
+        )?)
+    })?;
+    for agent in stalled.into_iter().take(10) {
+        let last_activity = agent.last_activity_at.as_deref().and_then(parse_ts);
+        let idle_mins = last_activity.map_or(0, |at| (Utc::now() - at).num_minutes());
+        let location = match (&agent.session_name, &agent.pane) {
+            (Some(session), Some(pane)) => format!("{session}:{pane}"),
+            _ => agent.agent_key.clone(),
+        };
+        actions.push(
+            ActionItem::new(
+                format!("agent-stalled-{}-{}", agent.machine_id, agent.agent_key),
+                ActionCategory::Investigate,
+                ActionSeverity::Warning,
+                format!(
+                    "{} agent in {location} on {} has been silent for {idle_mins}m",
+                    agent.agent_type, agent.machine_id
+                ),
+                format!("vc fleet resources --machine {}", agent.machine_id),
+                0.65,
+                format!(
+                    "Lists the agents on {} so the stalled one can be checked or restarted",
+                    agent.machine_id
+                ),
+            )
+            .machines([agent.machine_id.clone()])
+            .since(last_activity),
+        );
+    }
+
+    // Nothing to triage because nothing has been collected is a different
+    // finding from nothing to triage because everything is fine. Say which.
+    let store_is_empty = machines.is_empty() && accounts.is_empty() && repos.is_empty();
+    if store_is_empty {
+        warnings.push(
+            "store has no machines, accounts or repos - nothing has been collected yet".to_string(),
+        );
+        actions.push(ActionItem::new(
+            "store-empty",
+            ActionCategory::Investigate,
+            ActionSeverity::Info,
+            "Nothing has been collected yet",
+            "vc collect",
+            0.9,
+            "Populates machines, accounts and repos so triage has something to rank",
+        ));
+    }
+
+    for action in &mut actions {
+        action.score = action.rank_score();
+    }
+    actions.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    let truncated = max_items.map_or(0, |max| actions.len().saturating_sub(max));
+    actions.truncate(actions.len() - truncated);
+    for (index, action) in actions.iter_mut().enumerate() {
+        action.rank = u32::try_from(index + 1).unwrap_or(u32::MAX);
+    }
+
+    Ok(
+        RobotEnvelope::new("vc.robot.triage.v4", TriageData { actions, truncated })
+            .with_staleness(staleness_for(
+                store,
+                &["account_status", "repo_status_snapshots", "sys_samples"],
+            ))
+            .with_warnings(warnings)
+            .add_missing_capabilities(caps.missing())
+            .add_missing_capabilities(overview.missing_capabilities),
+    )
+}
+
+/// Generate comprehensive fleet status from the store.
+///
+/// Returns machine status, repo summary, and unresolved alert counts. This is
+/// the primary command for agents to understand overall system state.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+pub fn robot_status(store: &VcStore) -> Result<RobotEnvelope<StatusData>, RobotError> {
+    let caps = store.capabilities()?;
+    let overview = QueryBuilder::new(store).fleet_overview()?;
+    let machines = if_tables(&caps, &["machines"], || load_machines(store))?;
+    let health_scores = if_tables(&caps, &["health_summary"], || load_health_scores(store))?;
+    let maintenance = if_tables(&caps, &["machine_maintenance"], || load_maintenance(store))?;
+    let metrics = load_latest_metrics(store, &caps)?;
+    let repos = if_tables(&caps, REPO_TABLES, || load_repos(store))?;
+    let alert_counts = if_tables(&caps, &["alert_history"], || load_alert_counts(store))?;
+
+    let mut warnings = Vec::new();
+    if machines.is_empty() {
+        warnings.push(
+            "machine registry is empty - run `vc machine add` or `vc collect` to populate it"
+                .to_string(),
+        );
+    }
+    if health_scores.is_empty() {
+        warnings.push(
+            "no health summaries persisted - per-machine scores are null and the fleet score \
+             falls back to 1.0"
+                .to_string(),
+        );
+    }
+
+    let machine_status: Vec<MachineStatus> = machines
+        .iter()
+        .map(|machine| {
+            let scored = health_scores.get(&machine.id);
+            let health_score = scored.map(|(value, _)| *value);
+            let status = match (machine.status.as_str(), health_score) {
+                ("online", Some(value)) if value < 0.8 => "degraded".to_string(),
+                _ => machine.status.clone(),
+            };
+
+            MachineStatus {
+                id: machine.id.clone(),
+                status,
+                last_seen: machine.last_seen,
+                health_score,
+                metrics: metrics.get(&machine.id).filter(|m| !m.is_empty()).cloned(),
+                top_issue: scored.and_then(|(_, worst)| worst.clone()),
+                maintenance: maintenance.get(&machine.id).cloned(),
+            }
+        })
+        .collect();
+
+    if let Some(headline) = overview.upstream_headline() {
+        let upstreams: Vec<String> = overview
+            .upstream_failures
+            .iter()
+            .map(|failure| {
+                format!(
+                    "{} has a critical alert, {} machine(s) depend on it",
+                    failure.machine_id,
+                    failure.affected_machines.len()
+                )
+            })
+            .collect();
+        warnings.push(format!("{headline}: {}", upstreams.join("; ")));
+    }
+
+    let data = StatusData {
+        fleet: FleetSummary {
+            total_machines: u32::try_from(overview.total_machines).unwrap_or(u32::MAX),
+            online: u32::try_from(overview.online_machines).unwrap_or(u32::MAX),
+            offline: u32::try_from(overview.offline_machines).unwrap_or(u32::MAX),
+            maintenance: u32::try_from(overview.maintenance_machines).unwrap_or(u32::MAX),
+            health_score: overview.fleet_health_score,
+        },
+        machines: machine_status,
+        repos: summarize_repos(&repos),
+        alerts: AlertSummary {
+            critical: alert_counts.critical,
+            warning: alert_counts.warning,
+            info: alert_counts.info,
+        },
+        by_tag: overview.by_tag,
+    };
+
+    Ok(RobotEnvelope::new("vc.robot.status.v1", data)
+        .with_staleness(staleness_for(
+            store,
+            &[
+                "sys_samples",
+                "sys_fallback_samples",
+                "repo_status_snapshots",
+                "health_summary",
+            ],
+        ))
+        .with_warnings(warnings)
+        .add_missing_capabilities(caps.missing())
+        .add_missing_capabilities(overview.missing_capabilities))
+}
+
+// ============================================================================
+// Accounts / Repos / Oracle Command Implementations
+// ============================================================================
+
+/// Account status for `vc robot accounts`, from the accounts collector.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+pub fn robot_accounts(store: &VcStore) -> Result<RobotEnvelope<AccountsData>, RobotError> {
+    let caps = store.capabilities()?;
+    let accounts = if_tables(&caps, ACCOUNT_TABLES, || load_accounts(store, &caps))?;
+    let parse_errors = if_tables(&caps, ACCOUNT_TABLES, || load_account_parse_errors(store))?;
+
+    let mut warnings: Vec<String> = parse_errors
+        .into_iter()
+        .map(|(machine, command, error)| {
+            format!("could not parse output of `{command}` on {machine}: {error}")
+        })
+        .collect();
+    if accounts.is_empty() && warnings.is_empty() {
+        warnings.push(
+            "no account status in the store - run `vc collect` with the accounts collector"
+                .to_string(),
+        );
+    }
+
+    let data = AccountsData {
+        total: u32::try_from(accounts.len()).unwrap_or(u32::MAX),
+        accounts,
+    };
+
+    Ok(RobotEnvelope::new("vc.robot.accounts.v1", data)
+        .with_staleness(staleness_for(store, &["account_status"]))
+        .with_warnings(warnings)
+        .add_missing_capabilities(caps.missing()))
+}
+
+/// Repository status for `vc robot repos`, from the ru collector.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+pub fn robot_repos(store: &VcStore) -> Result<RobotEnvelope<ReposData>, RobotError> {
+    let caps = store.capabilities()?;
+    let repos = if_tables(&caps, REPO_TABLES, || load_repos(store))?;
+
+    let mut warnings = Vec::new();
+    if repos.is_empty() {
+        warnings.push(
+            "no repositories in the store - run `vc collect` with the ru collector".to_string(),
+        );
+    } else if repos.iter().all(|repo| repo.branch.is_none()) {
+        warnings
+            .push("repositories are inventoried but no git status snapshot exists yet".to_string());
+    }
+
+    let data = ReposData {
+        summary: summarize_repos(&repos),
+        repos,
+    };
+
+    Ok(RobotEnvelope::new("vc.robot.repos.v1", data)
+        .with_staleness(staleness_for(store, &["repo_status_snapshots"]))
+        .with_warnings(warnings)
+        .add_missing_capabilities(caps.missing()))
+}
+
+/// Rate-limit forecasts for `vc robot oracle`.
+///
+/// Forecasts are computed live from the `account_usage_snapshots` history via
+/// [`vc_oracle::RateLimitForecaster`]. Nothing writes the `predictions` table, so
+/// nothing reads it: a stored prediction here would be a fabrication.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if any store query fails.
+pub fn robot_oracle(store: &VcStore) -> Result<RobotEnvelope<OracleData>, RobotError> {
+    let caps = store.capabilities()?;
+    let samples = if_tables(&caps, &["account_usage_snapshots"], || {
+        load_usage_samples(store)
+    })?;
+    let sample_count = u32::try_from(samples.len()).unwrap_or(u32::MAX);
+
+    let forecasts = RateLimitForecaster::new().forecast(samples.clone());
+
+    let mut warnings = Vec::new();
+    if samples.is_empty() {
+        warnings.push(
+            format!("no account usage samples in the last {ORACLE_LOOKBACK_HOURS}h - run `vc collect` with the caut collector"),
+        );
+    } else if forecasts.is_empty() {
+        warnings
+            .push("usage samples exist but no account had enough history to forecast".to_string());
+    }
+
+    let forecast_info: Vec<ForecastInfo> = forecasts
+        .into_iter()
+        .map(|forecast| {
+            let alternatives = vc_oracle::rate_limit::rank_alternative_accounts(
+                &samples,
+                &forecast.provider,
+                &forecast.account,
+            );
+
+            // The forecaster leaves `to_account` empty because it has no store
+            // access; we do, so fill it with the account with the most headroom.
+            let mut action = serde_json::to_value(&forecast.recommended_action)
+                .unwrap_or_else(|_| serde_json::json!({"type": "continue"}));
+            if action.get("type").and_then(serde_json::Value::as_str) == Some("swap_now") {
+                match alternatives.first() {
+                    Some((account, _)) => {
+                        action["to_account"] = serde_json::Value::String(account.clone());
+                    }
+                    None => action["to_account"] = serde_json::Value::Null,
+                }
+            }
+
+            // The forecaster encodes "never" as u64::MAX / 2. Report that as null
+            // rather than as a 292-billion-year countdown.
+            let time_to_limit_secs = forecast.time_to_limit.as_secs();
+            let time_to_limit_secs = if time_to_limit_secs >= u64::MAX / 2 {
+                None
+            } else {
+                Some(time_to_limit_secs)
+            };
+
+            ForecastInfo {
+                provider: forecast.provider,
+                account: forecast.account,
+                current_usage_pct: forecast.current_usage_pct,
+                velocity_pct_per_min: forecast.current_velocity,
+                time_to_limit_secs,
+                confidence: forecast.confidence,
+                recommended_action: action,
+                optimal_swap_time: forecast.optimal_swap_time,
+                alternative_accounts: alternatives
+                    .into_iter()
+                    .map(|(account, headroom_pct)| AlternativeAccount {
+                        account,
+                        headroom_pct,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    // Disk exhaustion is forecast the same way `vc health forecast` does it,
+    // with the default window and horizon. Cyclic mounts get no date; they
+    // are only counted.
+    let mounts = if_tables(&caps, &["sys_filesystems"], || {
+        Ok(vc_query::forecast::disk_forecast(
+            store,
+            &vc_query::DiskForecastOptions::default(),
+            Utc::now(),
+        )?
+        .mounts)
+    })?;
+    let cyclic = mounts
+        .iter()
+        .filter(|m| m.trend == vc_query::DiskTrend::Cyclic)
+        .count();
+    if cyclic > 0 {
+        warnings.push(format!(
+            "{cyclic} mount(s) cycle through cleanups, so no disk-full date is forecast for them"
+        ));
+    }
+    let disk_forecasts = mounts
+        .into_iter()
+        .filter(|m| m.within_horizon)
+        .filter_map(|m| {
+            Some(DiskForecastInfo {
+                growth_bytes_per_day: m.growth_bytes_per_day?,
+                days_until_full: m.days_until_full?,
+                machine_id: m.machine_id,
+                mount: m.mount,
+                usage_pct: m.usage_pct,
+                full_at: m.full_at,
+                confidence: m.confidence,
+            })
+        })
+        .collect();
+
+    let data = OracleData {
+        forecasts: forecast_info,
+        sample_count,
+        lookback_hours: u32::try_from(ORACLE_LOOKBACK_HOURS).unwrap_or(u32::MAX),
+        disk_forecasts,
+    };
+
+    Ok(RobotEnvelope::new("vc.robot.oracle.v1", data)
+        .with_staleness(staleness_for(
+            store,
+            &["account_usage_snapshots", "sys_filesystems"],
+        ))
+        .with_warnings(warnings)
+        .add_missing_capabilities(caps.missing()))
+}
+
+// ============================================================================
+// Machines
+// ============================================================================
+
+/// Wrap a machine inventory as the `vc robot machines` envelope.
+///
+/// `warning` explains an inventory that did not come from the registry (the
+/// CLI falls back to the config file when the store cannot be opened).
+#[must_use]
+pub fn machines_envelope(
+    machines: &[vc_collect::machine::Machine],
+    warning: Option<String>,
+) -> RobotEnvelope<serde_json::Value> {
+    let mut data = serde_json::json!({
+        "machines": machines,
+        "total": machines.len(),
+    });
+    if let Some(warning) = warning {
+        data["warning"] = serde_json::Value::String(warning);
+    }
+    RobotEnvelope::new("vc.robot.machines.v1", data)
+}
+
+/// The registered machine inventory, read from the store without syncing it
+/// from config first.
+///
+/// # Errors
+///
+/// Returns [`RobotError`] if the registry query fails.
+pub fn robot_machines(store: &VcStore) -> Result<RobotEnvelope<serde_json::Value>, RobotError> {
+    let caps = store.capabilities()?;
+    let machines = if_tables(&caps, &["machines"], || {
+        Ok(vc_collect::machine::load_machines(store)?)
+    })?;
+    Ok(machines_envelope(&machines, None).add_missing_capabilities(caps.missing()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robot_envelope_new() {
+        let envelope = RobotEnvelope::new("test.v1", "hello");
+        assert_eq!(envelope.schema_version, "test.v1");
+        assert_eq!(envelope.data, "hello");
+        assert!(envelope.staleness.is_empty());
+        assert!(envelope.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_robot_envelope_with_staleness() {
+        let mut staleness = HashMap::new();
+        staleness.insert("sysmoni".to_string(), 60);
+
+        let envelope = RobotEnvelope::new("test.v1", "data").with_staleness(staleness);
+
+        assert_eq!(envelope.staleness.get("sysmoni"), Some(&60));
+    }
+
+    #[test]
+    fn test_robot_envelope_with_warnings() {
+        let envelope = RobotEnvelope::new("test.v1", "data")
+            .add_warning("warning 1")
+            .add_warning("warning 2");
+
+        assert_eq!(envelope.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_robot_envelope_to_json() {
+        let envelope = RobotEnvelope::new("test.v1", serde_json::json!({"key": "value"}));
+        let json = envelope.to_json();
+
+        assert!(json.contains("test.v1"));
+        assert!(json.contains("key"));
+        assert!(json.contains("value"));
+    }
+
+    /// Store with one machine, one repo (dirty, behind), one account at 92%,
+    /// one unresolved critical alert and a health summary of 0.4.
+    fn populated_store() -> VcStore {
+        let store = VcStore::open_memory().expect("open store");
+        let now = Utc::now().to_rfc3339();
+
+        store
+            .execute_batch(&format!(
+                "INSERT INTO machines (machine_id, hostname, display_name, status, last_seen_at) \
+                 VALUES ('orko', 'orko.local', 'Orko', 'online', '{now}'); \
+                 INSERT INTO machines (machine_id, hostname, status) \
+                 VALUES ('ghost', 'ghost.local', 'offline'); \
+                 INSERT INTO health_summary (machine_id, collected_at, overall_score, \
+                     worst_factor_id, factor_count, critical_count, warning_count, details_json) \
+                 VALUES ('orko', '{now}', 0.4, 'disk_pressure', 1, 1, 0, '{{}}'); \
+                 INSERT INTO sys_samples (machine_id, collected_at, cpu_total, load5, \
+                     mem_used_bytes, mem_total_bytes) \
+                 VALUES ('orko', '{now}', 42.5, 1.5, 8000000000, 16000000000); \
+                 INSERT INTO sys_filesystems (machine_id, collected_at, mount, total_bytes, \
+                     used_bytes, usage_pct) \
+                 VALUES ('orko', '{now}', '/', 100, 91, 91.0); \
+                 INSERT INTO repos (machine_id, repo_id, path, url, name) \
+                 VALUES ('orko', 'vibe_cockpit', '/src/vibe_cockpit', 'git@x:vc.git', 'vibe_cockpit'); \
+                 INSERT INTO repo_status_snapshots (machine_id, collected_at, repo_id, branch, \
+                     dirty, ahead, behind, modified_count, untracked_count) \
+                 VALUES ('orko', '{now}', 'vibe_cockpit', 'main', 1, 0, 3, 2, 1); \
+                 INSERT INTO account_usage_snapshots (machine_id, collected_at, provider, \
+                     account_id, usage_pct, tokens_used, tokens_limit) \
+                 VALUES ('orko', '{now}', 'claude', 'acct-1', 92.0, 920, 1000); \
+                 INSERT INTO account_profile_snapshots (machine_id, collected_at, provider, \
+                     account_id, email, plan_type, is_active, is_current) \
+                 VALUES ('orko', '{now}', 'claude', 'acct-1', 'a@b.c', 'max', 1, 1); \
+                 INSERT INTO account_status (machine_id, collected_at, source_command, \
+                     provider, account_id, remaining_pct, used_pct, is_active) \
+                 VALUES ('orko', '{now}', 'caam limits --format json', 'claude', 'acct-1', \
+                     8.0, 92.0, 1); \
+                 INSERT INTO alert_history (id, rule_id, fired_at, severity, title, message) \
+                 VALUES (1, 'disk-critical', '{now}', 'critical', 'Disk full', 'root at 91%');"
+            ))
+            .expect("seed store");
+
+        store
+    }
+
+    #[test]
+    fn test_robot_health_empty_store_reports_nothing_rather_than_inventing_a_machine() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_health(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.health.v1");
+        // The old stub fabricated a machine called "local" with score 1.0.
+        assert!(envelope.data.machines.is_empty());
+        assert_eq!(envelope.data.overall.machine_count, 0);
+        assert!(!envelope.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_robot_health_reads_the_store() {
+        let store = populated_store();
+        let envelope = robot_health(&store).unwrap();
+
+        assert_eq!(envelope.data.machines.len(), 2);
+        let orko = envelope
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "orko")
+            .expect("orko present");
+        assert_eq!(orko.name, "Orko");
+        assert_eq!(orko.score, Some(0.4));
+        // Registry says online, but the health summary says 0.4 - that is degraded.
+        assert_eq!(orko.status, "degraded");
+        assert_eq!(orko.top_issue.as_deref(), Some("disk_pressure"));
+        assert!(orko.last_seen.is_some());
+        assert_eq!(orko.cpu_percent, Some(42.5));
+        assert_eq!(orko.memory_percent, Some(50.0));
+
+        // A machine that has never been collected from has a null last_seen and
+        // a null score - not Utc::now() and not 1.0.
+        let ghost = envelope
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "ghost")
+            .expect("ghost present");
+        assert!(ghost.last_seen.is_none());
+        assert!(ghost.score.is_none());
+        assert_eq!(ghost.status, "offline");
+
+        assert_eq!(envelope.data.alerts_by_severity.critical, 1);
+        assert_eq!(envelope.data.overall.active_alerts, 1);
+    }
+
+    #[test]
+    fn test_robot_health_reports_daemon_lease_holder() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(robot_health(&store).unwrap().data.daemon_lease.is_none());
+
+        store
+            .acquire_lease(
+                vc_store::DAEMON_LEASE,
+                "orko:42:abcd",
+                Some("orko"),
+                std::time::Duration::from_secs(60),
+                false,
+            )
+            .unwrap();
+        let envelope = robot_health(&store).unwrap();
+        let lease = envelope.data.daemon_lease.expect("lease reported");
+        assert_eq!(lease.holder_id, "orko:42:abcd");
+        assert!(!envelope.warnings.iter().any(|w| w.contains("lease")));
+
+        store
+            .execute(
+                "UPDATE leases SET expires_at = '2026-01-01T00:00:00.000Z'",
+                &[],
+            )
+            .unwrap();
+        let envelope = robot_health(&store).unwrap();
+        assert!(
+            envelope.warnings.iter().any(|w| w.contains("orko:42:abcd")),
+            "{:?}",
+            envelope.warnings
+        );
+    }
+
+    #[test]
+    fn test_robot_triage_derives_actions_from_rows() {
+        let store = populated_store();
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.triage.v4");
+        let actions = &envelope.data.actions;
+        let ids: Vec<&str> = actions.iter().map(|a| a.id.as_str()).collect();
+
+        assert!(
+            ids.contains(&"alert-disk-critical"),
+            "unresolved alert: {ids:?}"
+        );
+        assert!(ids.contains(&"machine-offline-ghost"), "offline: {ids:?}");
+        assert!(ids.contains(&"machine-degraded-orko"), "degraded: {ids:?}");
+        assert!(
+            ids.contains(&"account-usage-claude-acct-1"),
+            "account at 92%: {ids:?}"
+        );
+        assert!(ids.contains(&"repos-drift"), "dirty/behind repo: {ids:?}");
+
+        let alert = actions
+            .iter()
+            .find(|a| a.id == "alert-disk-critical")
+            .unwrap();
+        assert_eq!(alert.category, ActionCategory::Ack);
+        assert_eq!(alert.command, "vc alert ack 1");
+        assert_eq!(alert.alert_ids, vec![1]);
+        let account = actions
+            .iter()
+            .find(|a| a.id == "account-usage-claude-acct-1")
+            .unwrap();
+        assert_eq!(account.category, ActionCategory::Capacity);
+
+        // Ranked best first, with 1-based ranks in order.
+        assert!(actions.windows(2).all(|w| w[0].score >= w[1].score));
+        let ranks: Vec<u32> = actions.iter().map(|a| a.rank).collect();
+        assert_eq!(
+            ranks,
+            (1..=u32::try_from(actions.len()).unwrap()).collect::<Vec<_>>()
+        );
+        assert_eq!(actions[0].severity, ActionSeverity::Critical);
+        assert_eq!(envelope.data.truncated, 0);
+
+        let capped = robot_triage(&store, &HashMap::new(), Some(2)).unwrap();
+        assert_eq!(capped.data.actions.len(), 2);
+        assert_eq!(capped.data.truncated, actions.len() - 2);
+        assert_eq!(capped.data.actions[0].id, actions[0].id);
+    }
+
+    #[test]
+    fn test_robot_triage_groups_alerts_and_prefers_playbooks() {
+        let store = VcStore::open_memory().unwrap();
+        let old = (Utc::now() - TimeDelta::hours(30)).to_rfc3339();
+        let now = Utc::now().to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id, \
+                     acknowledged) VALUES \
+                 (1, 'disk-full', '{old}', 'warning', 'Disk full', 'orko', 0), \
+                 (2, 'disk-full', '{now}', 'critical', 'Disk full', 'ghost', 0), \
+                 (3, 'rate-limit-warning', '{now}', 'warning', 'Rate limit', 'orko', 0), \
+                 (4, 'cpu-hot', '{now}', 'warning', 'CPU hot', 'orko', 1), \
+                 (5, 'gpu-hot', '{now}', 'warning', 'GPU hot', 'orko', 0);"
+            ))
+            .unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO guardian_playbooks (playbook_id, name, trigger_condition, steps) \
+                 VALUES ('cool-gpu', 'Cool GPU', '{\"type\":\"on_alert\",\"rule_id\":\"gpu-hot\"}', '[]');",
+            )
+            .unwrap();
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let find = |id: &str| {
+            envelope
+                .data
+                .actions
+                .iter()
+                .find(|a| a.id == id)
+                .unwrap_or_else(|| panic!("{id} missing"))
+                .clone()
+        };
+
+        // Two alerts on two machines under one rule: one action, worst severity
+        let disk = find("alert-disk-full");
+        assert_eq!(disk.alert_ids, vec![1, 2]);
+        assert_eq!(disk.machine_ids, vec!["ghost", "orko"]);
+        assert_eq!(disk.severity, ActionSeverity::Critical);
+        assert_eq!(disk.command, "vc alert ack --all --type disk-full");
+        assert!(disk.age_secs.is_some_and(|age| age >= 30 * 3600));
+
+        // A built-in playbook and a stored one both turn into a trigger action
+        if cfg!(feature = "guardian") {
+            let rate = find("alert-rate-limit-warning");
+            assert_eq!(rate.category, ActionCategory::Remediate);
+            assert_eq!(rate.playbook_id.as_deref(), Some("rate-limit-switch"));
+            assert_eq!(rate.command, "vc guardian trigger rate-limit-switch");
+        }
+        let gpu = find("alert-gpu-hot");
+        assert_eq!(gpu.command, "vc guardian trigger cool-gpu");
+
+        // Acknowledged but still firing: investigate, with an MCP equivalent
+        let cpu = find("alert-cpu-hot");
+        assert_eq!(cpu.category, ActionCategory::Investigate);
+        assert_eq!(
+            cpu.mcp_tool.as_ref().map(|call| call.tool.as_str()),
+            Some("vc_query_alerts")
+        );
+
+        // Same severity: the playbook-covered alert outranks the bare one
+        assert!(gpu.score > cpu.score);
+        assert!(disk.rank < gpu.rank);
+    }
+
+    #[test]
+    fn test_robot_triage_presents_correlated_alerts_as_one_item() {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now().to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id, \
+                     acknowledged, correlation_id, correlation_root) VALUES \
+                 (1, 'collector-stale:sysmoni', '{now}', 'warning', 'Stale', 'orko', 0, 'corr-orko-1', 0), \
+                 (2, 'disk-full', '{now}', 'critical', 'Disk full', 'orko', 0, 'corr-orko-1', 1), \
+                 (3, 'session-stuck', '{now}', 'warning', 'Stuck', 'orko', 0, 'corr-orko-1', 0), \
+                 (4, 'disk-full', '{now}', 'warning', 'Disk full', 'ghost', 0, NULL, 0);"
+            ))
+            .unwrap();
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let alert_ids: Vec<&str> = envelope
+            .data
+            .actions
+            .iter()
+            .filter(|a| a.id.starts_with("alert-"))
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(alert_ids.len(), 2, "{alert_ids:?}");
+
+        let corr = envelope
+            .data
+            .actions
+            .iter()
+            .find(|a| a.id == "alert-corr-corr-orko-1")
+            .expect("correlated item");
+        assert_eq!(corr.alert_ids, vec![1, 2, 3]);
+        assert_eq!(corr.severity, ActionSeverity::Critical);
+        assert_eq!(corr.command, "vc alert ack 2 --with-children");
+        let summary = corr.correlation.as_ref().unwrap();
+        assert_eq!(summary.root_alert_id, 2);
+        assert_eq!(summary.root_rule_id, "disk-full");
+        assert_eq!(summary.child_alert_ids, vec![1, 3]);
+        assert_eq!(
+            summary.child_rules,
+            vec!["collector-stale:sysmoni", "session-stuck"]
+        );
+
+        // The uncorrelated alert of the root's rule keeps its own item
+        let disk = envelope
+            .data
+            .actions
+            .iter()
+            .find(|a| a.id == "alert-disk-full")
+            .expect("rule item");
+        assert_eq!(disk.alert_ids, vec![4]);
+        assert!(disk.correlation.is_none());
+    }
+
+    #[test]
+    fn test_robot_triage_flags_missing_required_services() {
+        let store = VcStore::open_memory().unwrap();
+        let service = |name: &str, required: bool| vc_store::MachineServiceStatus {
+            machine_id: "orko".to_string(),
+            service_name: name.to_string(),
+            kind: "unit".to_string(),
+            target: format!("{name}.service"),
+            required,
+            running: false,
+            pid: None,
+            uptime_secs: None,
+            detail: None,
+            checked_at: "2026-10-16T12:00:00Z".to_string(),
+        };
+        store
+            .replace_machine_services(
+                "orko",
+                &[service("postgres", true), service("redis", false)],
+            )
+            .unwrap();
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let ids: Vec<&str> = envelope
+            .data
+            .actions
+            .iter()
+            .map(|a| a.id.as_str())
+            .collect();
+        assert!(ids.contains(&"service-orko-postgres"), "required: {ids:?}");
+        assert!(!ids.contains(&"service-orko-redis"), "optional: {ids:?}");
+    }
+
+    #[test]
+    fn test_robot_triage_flags_versions_below_minimum() {
+        let store = VcStore::open_memory().unwrap();
+        for (machine, version) in [("orko", "0.5.0"), ("ghost", "0.4.1")] {
+            store
+                .record_tool_version(machine, "sysmoni", version, "probe")
+                .unwrap();
+        }
+
+        let ids = |min_versions: &HashMap<String, String>| -> Vec<String> {
+            robot_triage(&store, min_versions, None)
+                .unwrap()
+                .data
+                .actions
+                .into_iter()
+                .map(|a| a.id)
+                .collect()
+        };
+        assert!(
+            !ids(&HashMap::new())
+                .iter()
+                .any(|id| id.starts_with("version-"))
+        );
+
+        let min_versions = HashMap::from([("sysmoni".to_string(), "0.5.0".to_string())]);
+        let envelope = robot_triage(&store, &min_versions, None).unwrap();
+        let action = envelope
+            .data
+            .actions
+            .iter()
+            .find(|a| a.id == "version-sysmoni")
+            .expect("sysmoni flagged");
+        assert_eq!(action.machine_ids, vec!["ghost"]);
+    }
+
+    #[test]
+    fn test_robot_triage_flags_stale_agent_work() {
+        let store = VcStore::open_memory().unwrap();
+        let ended = (Utc::now() - TimeDelta::days(6)).to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO repos (machine_id, repo_id, path, name) \
+                   VALUES ('orko', 'r1', '/src/vc', 'vc'); \
+                 INSERT INTO repo_status_snapshots \
+                   (machine_id, collected_at, repo_id, dirty, modified_count, untracked_count) \
+                   VALUES ('orko', '{now}', 'r1', 1, 2, 1); \
+                 INSERT INTO agent_sessions (machine_id, session_id, repo_path, started_at, ended_at) \
+                   VALUES ('orko', 's1', '/src/vc', '{ended}', '{ended}');",
+                now = Utc::now().to_rfc3339(),
+            ))
+            .unwrap();
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let action = envelope
+            .data
+            .actions
+            .iter()
+            .find(|a| a.id == "repo-stale-orko-r1")
+            .expect("stale work flagged");
+        assert_eq!(
+            action.title,
+            "3 dirty file(s) left in vc on orko by an agent 6d ago"
+        );
+        assert_eq!(action.machine_ids, vec!["orko"]);
+    }
+
+    #[test]
+    fn test_robot_triage_flags_stalled_agents() {
+        let store = VcStore::open_memory().unwrap();
+        let quiet = (Utc::now() - TimeDelta::minutes(90)).to_rfc3339();
+        let busy = Utc::now().to_rfc3339();
+        let agent = |key: &str, activity: &str| {
+            serde_json::json!({
+                "agent_key": key,
+                "agent_type": "claude",
+                "source": "tmux",
+                "session_name": "work",
+                "pane": "0.1",
+                "last_activity_at": activity,
+            })
+        };
+        store
+            .record_agent_inventory(
+                "orko",
+                &busy,
+                &[agent("tmux:%1:10", &quiet), agent("tmux:%2:20", &busy)],
+            )
+            .unwrap();
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let stalled: Vec<_> = envelope
+            .data
+            .actions
+            .iter()
+            .filter(|a| a.id.starts_with("agent-stalled-"))
+            .collect();
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].id, "agent-stalled-orko-tmux:%1:10");
+        assert!(
+            stalled[0]
+                .title
+                .starts_with("claude agent in work:0.1 on orko"),
+            "{}",
+            stalled[0].title
+        );
+        assert_eq!(stalled[0].command, "vc fleet resources --machine orko");
+    }
+
+    #[test]
+    fn test_robot_triage_traces_alerts_to_upstream_failure() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname, status) VALUES \
+                   ('nfs', 'nfs', 'online'), ('b1', 'b1', 'online'), ('b2', 'b2', 'online'); \
+                 INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) VALUES \
+                   (1, 'disk-full', current_timestamp, 'critical', 'disk full', 'nfs'), \
+                   (2, 'mount-stale', current_timestamp, 'critical', 'stale mount', 'b1'), \
+                   (3, 'mount-stale', current_timestamp, 'critical', 'stale mount', 'b2');",
+            )
+            .unwrap();
+        for builder in ["b1", "b2"] {
+            store
+                .add_machine_dependency(builder, "nfs", "storage")
+                .unwrap();
+        }
+
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+        let actions = &envelope.data.actions;
+        let upstream = actions.iter().find(|a| a.id == "alert-disk-full").unwrap();
+        assert_eq!(upstream.machine_ids, vec!["b1", "b2", "nfs"]);
+        assert!(upstream.caused_by.is_none());
+        let downstream = actions
+            .iter()
+            .find(|a| a.id == "alert-mount-stale")
+            .unwrap();
+        assert_eq!(downstream.severity, ActionSeverity::Info);
+        assert_eq!(downstream.caused_by.as_deref(), Some("nfs"));
+        assert!(downstream.title.ends_with("(probably caused by nfs)"));
+        assert!(upstream.rank < downstream.rank);
+
+        let status = robot_status(&store).unwrap();
+        assert!(
+            status
+                .warnings
+                .iter()
+                .any(|w| w.starts_with("1 upstream failure affecting 2 machines")),
+            "{:?}",
+            status.warnings
+        );
+    }
+
+    #[test]
+    fn test_robot_triage_empty_store_suggests_collection() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_triage(&store, &HashMap::new(), None).unwrap();
+
+        assert_eq!(envelope.data.actions.len(), 1);
+        assert_eq!(envelope.data.actions[0].command, "vc collect");
+    }
+
+    #[test]
+    fn test_robot_commands_degrade_when_optional_tables_are_missing() {
+        let store = populated_store();
+        store
+            .execute_batch(
+                "DROP TABLE guardian_runs; DROP TABLE incidents; DROP TABLE collector_status; \
+                 DROP TABLE machine_services; DROP TABLE repo_status_snapshots; \
+                 DROP TABLE sys_filesystems; DROP TABLE account_profile_snapshots;",
+            )
+            .expect("drop optional tables");
+
+        let health = robot_health(&store).unwrap();
+        assert_eq!(health.data.machines.len(), 2);
+        assert_eq!(
+            health.missing_capabilities,
+            vec!["guardian_runs", "sys_filesystems"]
+        );
+
+        let triage = robot_triage(&store, &HashMap::new(), None).unwrap();
+        assert!(
+            triage
+                .data
+                .actions
+                .iter()
+                .any(|a| a.id == "alert-disk-critical")
+        );
+        for table in [
+            "collector_status",
+            "guardian_runs",
+            "machine_services",
+            "repo_status_snapshots",
+        ] {
+            assert!(
+                triage.missing_capabilities.iter().any(|t| t == table),
+                "{table}: {:?}",
+                triage.missing_capabilities
+            );
+        }
+
+        let status = robot_status(&store).unwrap();
+        assert_eq!(status.data.repos.total, 0);
+        assert!(robot_repos(&store).unwrap().data.repos.is_empty());
+        // Profiles only enrich account status; their absence is not a gap.
+        let accounts = robot_accounts(&store).unwrap();
+        assert!(accounts.missing_capabilities.is_empty());
+        assert_eq!(accounts.data.accounts[0].usage_pct, Some(92.0));
+        assert!(accounts.data.accounts[0].email.is_none());
+        robot_oracle(&store).unwrap();
+
+        let json = serde_json::to_value(&triage).unwrap();
+        assert!(json["missing_capabilities"].is_array());
+    }
+
+    #[test]
+    fn test_robot_status_reads_the_store() {
+        let store = populated_store();
+        let envelope = robot_status(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.status.v1");
+        assert_eq!(envelope.data.fleet.total_machines, 2);
+        assert_eq!(envelope.data.fleet.online, 1);
+        assert_eq!(envelope.data.fleet.offline, 1);
+        // Neither machine is tagged
+        assert_eq!(envelope.data.by_tag.len(), 1);
+        assert_eq!(envelope.data.by_tag[0].total_machines, 2);
+
+        assert_eq!(envelope.data.repos.total, 1);
+        assert_eq!(envelope.data.repos.dirty, 1);
+        assert_eq!(envelope.data.repos.behind, 1);
+        assert_eq!(envelope.data.repos.ahead, 0);
+
+        assert_eq!(envelope.data.alerts.critical, 1);
+
+        let orko = envelope
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "orko")
+            .expect("orko present");
+        let metrics = orko.metrics.as_ref().expect("orko has metrics");
+        assert_eq!(metrics.cpu_pct, Some(42.5));
+        assert_eq!(metrics.load5, Some(1.5));
+        assert_eq!(metrics.disk_free_pct, Some(9.0));
+
+        // Never collected from: no metrics, no last_seen, no score.
+        let ghost = envelope
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "ghost")
+            .expect("ghost present");
+        assert!(ghost.metrics.is_none());
+        assert!(ghost.last_seen.is_none());
+        assert!(ghost.health_score.is_none());
+    }
+
+    #[test]
+    fn test_robot_outputs_show_machines_in_maintenance() {
+        let store = populated_store();
+        store
+            .start_maintenance(
+                "ghost",
+                Some("RAM upgrade"),
+                None,
+                &vc_store::ActorContext::system("op"),
+            )
+            .unwrap();
+
+        let status = robot_status(&store).unwrap();
+        assert_eq!(status.data.fleet.offline, 0);
+        assert_eq!(status.data.fleet.maintenance, 1);
+        let ghost = status
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "ghost")
+            .expect("ghost present");
+        assert_eq!(ghost.status, "maintenance");
+        assert_eq!(
+            ghost.maintenance.as_ref().and_then(|m| m.reason.as_deref()),
+            Some("RAM upgrade")
+        );
+
+        let health = robot_health(&store).unwrap();
+        let ghost = health
+            .data
+            .machines
+            .iter()
+            .find(|m| m.id == "ghost")
+            .expect("ghost present");
+        assert_eq!(ghost.status, "maintenance");
+        assert!(ghost.maintenance.is_some());
+
+        let triage = robot_triage(&store, &HashMap::new(), None).unwrap();
+        assert!(
+            !triage
+                .data
+                .actions
+                .iter()
+                .any(|a| a.machine_ids.iter().any(|id| id == "ghost"))
+        );
+    }
+
+    #[test]
+    fn test_robot_accounts_reads_status_and_profile() {
+        let store = populated_store();
+        let envelope = robot_accounts(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.accounts.v1");
+        assert_eq!(envelope.data.total, 1);
+
+        let account = &envelope.data.accounts[0];
+        assert_eq!(account.provider, "claude");
+        assert_eq!(account.account_id, "acct-1");
+        assert_eq!(account.usage_pct, Some(92.0));
+        assert_eq!(account.remaining_pct, Some(8.0));
+        assert_eq!(account.tokens_used, Some(920));
+        assert_eq!(account.email.as_deref(), Some("a@b.c"));
+        assert_eq!(account.plan_type.as_deref(), Some("max"));
+        assert_eq!(account.is_current, Some(true));
+        assert!(envelope.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_robot_accounts_reports_latest_parse_errors() {
+        let store = populated_store();
+        let earlier = (Utc::now() - TimeDelta::minutes(5)).to_rfc3339();
+        let now = Utc::now().to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO account_status (machine_id, collected_at, source_command, \
+                     parse_error, raw_output) \
+                 VALUES ('orko', '{earlier}', 'old-cli status', 'output is not JSON', 'x'); \
+                 INSERT INTO account_status (machine_id, collected_at, source_command, \
+                     provider, account_id, remaining_pct) \
+                 VALUES ('orko', '{now}', 'old-cli status', 'openai', 'o-1', 50.0); \
+                 INSERT INTO account_status (machine_id, collected_at, source_command, \
+                     parse_error, raw_output) \
+                 VALUES ('orko', '{now}', 'new-cli status', 'output is not JSON', 'y');"
+            ))
+            .expect("seed account status");
+
+        let envelope = robot_accounts(&store).unwrap();
+        assert_eq!(envelope.data.total, 2);
+        assert_eq!(envelope.warnings.len(), 1, "{:?}", envelope.warnings);
+        assert!(envelope.warnings[0].contains("new-cli status"));
+    }
+
+    #[test]
+    fn test_robot_accounts_empty_store_warns() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_accounts(&store).unwrap();
+
+        assert_eq!(envelope.data.total, 0);
+        assert!(envelope.data.accounts.is_empty());
+        assert!(!envelope.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_robot_repos_reads_status_snapshots() {
+        let store = populated_store();
+        let envelope = robot_repos(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.repos.v1");
+        assert_eq!(envelope.data.repos.len(), 1);
+
+        let repo = &envelope.data.repos[0];
+        assert_eq!(repo.repo_id, "vibe_cockpit");
+        assert_eq!(repo.branch.as_deref(), Some("main"));
+        assert_eq!(repo.dirty, Some(true));
+        assert_eq!(repo.behind, Some(3));
+        assert_eq!(repo.modified, Some(2));
+        assert_eq!(repo.path.as_deref(), Some("/src/vibe_cockpit"));
+
+        assert_eq!(envelope.data.summary.total, 1);
+        assert_eq!(envelope.data.summary.dirty, 1);
+    }
+
+    #[test]
+    fn test_robot_oracle_forecasts_from_usage_history() {
+        let store = VcStore::open_memory().unwrap();
+        // Two samples an hour apart: 40% -> 70%, i.e. 0.5%/min, 60 minutes to 100%.
+        let earlier = (Utc::now() - TimeDelta::hours(1)).to_rfc3339();
+        let now = Utc::now().to_rfc3339();
+        store
+            .execute_batch(&format!(
+                "INSERT INTO account_usage_snapshots (machine_id, collected_at, provider, \
+                     account_id, usage_pct) VALUES ('orko', '{earlier}', 'claude', 'acct-1', 40.0); \
+                 INSERT INTO account_usage_snapshots (machine_id, collected_at, provider, \
+                     account_id, usage_pct) VALUES ('orko', '{now}', 'claude', 'acct-1', 70.0);"
+            ))
+            .unwrap();
+
+        let envelope = robot_oracle(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.oracle.v1");
+        assert_eq!(envelope.data.sample_count, 2);
+        assert_eq!(envelope.data.forecasts.len(), 1);
+
+        let forecast = &envelope.data.forecasts[0];
+        assert_eq!(forecast.provider, "claude");
+        assert_eq!(forecast.current_usage_pct, 70.0);
+        assert!((forecast.velocity_pct_per_min - 0.5).abs() < 0.05);
+        let secs = forecast
+            .time_to_limit_secs
+            .expect("rising usage hits a limit");
+        assert!((3000..=4200).contains(&secs), "time_to_limit_secs={secs}");
+    }
+
+    #[test]
+    fn test_robot_oracle_empty_store_warns_and_forecasts_nothing() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_oracle(&store).unwrap();
+
+        assert_eq!(envelope.data.sample_count, 0);
+        assert!(envelope.data.forecasts.is_empty());
+        assert!(!envelope.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_robot_oracle_forecasts_filling_disks() {
+        let store = VcStore::open_memory().unwrap();
+        let now = Utc::now();
+        let gib = 1024_i64.pow(3);
+        let mut rows = Vec::new();
+        // /data fills 1 GiB an hour and has ~2 days left; / holds steady
+        for h in 0..24_i64 {
+            let at = (now - TimeDelta::hours(24 - h)).to_rfc3339();
+            for (mount, used) in [("/data", (50 + h) * gib), ("/", 10 * gib)] {
+                rows.push(serde_json::json!({
+                    "machine_id": "orko", "collected_at": at, "mount": mount,
+                    "total_bytes": 120 * gib, "used_bytes": used, "usage_pct": 0.0,
+                }));
+            }
+        }
+        store.insert_json_batch("sys_filesystems", &rows).unwrap();
+
+        let envelope = robot_oracle(&store).unwrap();
+        assert_eq!(envelope.data.disk_forecasts.len(), 1);
+        let disk = &envelope.data.disk_forecasts[0];
+        assert_eq!(disk.mount, "/data");
+        assert!(
+            (1.5..=2.5).contains(&disk.days_until_full),
+            "days_until_full={}",
+            disk.days_until_full
+        );
+    }
+
+    #[test]
+    fn test_null_last_seen_survives_serialization() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname, status) \
+                 VALUES ('ghost', 'ghost.local', 'unknown')",
+            )
+            .unwrap();
+
+        let json = robot_health(&store).unwrap().to_json();
+        assert!(json.contains("\"last_seen\":null"), "{json}");
+        assert!(json.contains("\"score\":null"), "{json}");
+    }
+
+    #[test]
+    fn test_health_data_serialization() {
+        let health = HealthData {
+            overall: OverallHealth {
+                score: 0.85,
+                severity: "warning".to_string(),
+                active_alerts: 2,
+                machine_count: 3,
+                agent_count: 5,
+            },
+            machines: vec![],
+            alerts_by_severity: AlertCounts {
+                critical: 0,
+                warning: 2,
+                info: 1,
+            },
+            daemon: None,
+            daemon_lease: None,
+        };
+
+        let envelope = RobotEnvelope::new("vc.robot.health.v1", health);
+        let json = envelope.to_json_pretty();
+
+        // Verify it parses back
+        let parsed: RobotEnvelope<HealthData> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.data.overall.score, 0.85);
+        assert_eq!(parsed.data.alerts_by_severity.warning, 2);
+    }
+
+    // ========================================================================
+    // Status Tests
+    // ========================================================================
+
+    #[test]
+    fn test_robot_status_empty_store() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_status(&store).unwrap();
+
+        assert_eq!(envelope.schema_version, "vc.robot.status.v1");
+        assert!(envelope.data.fleet.health_score >= 0.0);
+        assert!(envelope.data.fleet.health_score <= 1.0);
+        // The old stub always produced a machine named "local". An empty store
+        // has no machines.
+        assert!(envelope.data.machines.is_empty());
+        assert_eq!(envelope.data.fleet.total_machines, 0);
+    }
+
+    #[test]
+    fn test_status_data_serialization() {
+        let status = StatusData {
+            fleet: FleetSummary {
+                total_machines: 4,
+                online: 3,
+                offline: 1,
+                maintenance: 0,
+                health_score: 0.85,
+            },
+            machines: vec![MachineStatus {
+                id: "orko".to_string(),
+                status: "online".to_string(),
+                last_seen: Some(Utc::now()),
+                health_score: Some(0.91),
+                metrics: Some(MachineMetrics {
+                    cpu_pct: Some(45.2),
+                    mem_pct: Some(68.0),
+                    load5: Some(1.8),
+                    disk_free_pct: Some(35.0),
+                }),
+                top_issue: None,
+                maintenance: None,
+            }],
+            repos: RepoSummary {
+                total: 15,
+                dirty: 2,
+                ahead: 3,
+                behind: 1,
+            },
+            alerts: AlertSummary {
+                critical: 0,
+                warning: 1,
+                info: 2,
+            },
+            by_tag: Vec::new(),
+        };
+
+        let envelope = RobotEnvelope::new("vc.robot.status.v1", status);
+        let json = envelope.to_json_pretty();
+
+        // Verify it parses back
+        let parsed: RobotEnvelope<StatusData> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.data.fleet.total_machines, 4);
+        assert_eq!(parsed.data.fleet.online, 3);
+        assert_eq!(parsed.data.repos.dirty, 2);
+        assert_eq!(parsed.data.alerts.warning, 1);
+    }
+
+    #[test]
+    fn test_fleet_summary_creation() {
+        let fleet = FleetSummary {
+            total_machines: 5,
+            online: 4,
+            offline: 1,
+            maintenance: 0,
+            health_score: 0.9,
+        };
+
+        assert_eq!(fleet.total_machines, fleet.online + fleet.offline);
+    }
+
+    #[test]
+    fn test_machine_status_with_metrics() {
+        let machine = MachineStatus {
+            id: "test".to_string(),
+            status: "online".to_string(),
+            last_seen: Some(Utc::now()),
+            health_score: Some(0.95),
+            metrics: Some(MachineMetrics {
+                cpu_pct: Some(50.0),
+                mem_pct: Some(60.0),
+                load5: Some(1.5),
+                disk_free_pct: Some(40.0),
+            }),
+            top_issue: None,
+            maintenance: None,
+        };
+
+        assert!(machine.metrics.is_some());
+        let m = machine.metrics.unwrap();
+        assert_eq!(m.cpu_pct, Some(50.0));
+    }
+
+    #[test]
+    fn test_machine_status_never_collected_from() {
+        let machine = MachineStatus {
+            id: "offline-box".to_string(),
+            status: "offline".to_string(),
+            last_seen: None,
+            health_score: None,
+            metrics: None,
+            top_issue: Some("no_response".to_string()),
+            maintenance: None,
+        };
+
+        assert!(machine.metrics.is_none());
+        assert!(machine.last_seen.is_none());
+        assert!(machine.top_issue.is_some());
+    }
+
+    #[test]
+    fn test_repo_summary_defaults() {
+        let repos = RepoSummary::default();
+        assert_eq!(repos.total, 0);
+        assert_eq!(repos.dirty, 0);
+    }
+
+    #[test]
+    fn test_alert_summary_defaults() {
+        let alerts = AlertSummary::default();
+        assert_eq!(alerts.critical, 0);
+        assert_eq!(alerts.warning, 0);
+        assert_eq!(alerts.info, 0);
+    }
+
+    #[test]
+    fn test_machine_metrics_is_empty() {
+        assert!(MachineMetrics::default().is_empty());
+        assert!(
+            !MachineMetrics {
+                load5: Some(0.1),
+                ..MachineMetrics::default()
+            }
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_ts_accepts_both_stored_formats() {
+        // What the collectors write.
+        assert!(parse_ts("2026-07-11T12:00:00.123456+00:00").is_some());
+        // What CURRENT_TIMESTAMP column defaults write.
+        assert!(parse_ts("2026-07-11 12:00:00").is_some());
+        assert!(parse_ts("").is_none());
+        assert!(parse_ts("not a timestamp").is_none());
+    }
+
+    #[test]
+    fn test_severity_for_score() {
+        assert_eq!(severity_for_score(1.0), "healthy");
+        assert_eq!(severity_for_score(0.8), "healthy");
+        assert_eq!(severity_for_score(0.79), "warning");
+        assert_eq!(severity_for_score(0.49), "critical");
+    }
+
+    #[test]
+    fn test_status_json_contains_expected_fields() {
+        let store = VcStore::open_memory().unwrap();
+        let envelope = robot_status(&store).unwrap();
+        let json = envelope.to_json();
+
+        assert!(json.contains("\"fleet\""));
+        assert!(json.contains("\"machines\""));
+        assert!(json.contains("\"repos\""));
+        assert!(json.contains("\"alerts\""));
+        assert!(json.contains("\"schema_version\""));
+        assert!(json.contains("vc.robot.status.v1"));
+    }
+
+    #[test]
+    fn test_robot_view_names_round_trip() {
+        for view in RobotView::ALL {
+            assert_eq!(RobotView::parse(view.name()), Some(view));
+            assert!(view.cache_ttl_secs() > 0);
+        }
+        assert_eq!(RobotView::parse("schema"), None);
+    }
+
+    #[test]
+    fn test_robot_machines_reads_registry() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "INSERT INTO machines (machine_id, hostname, enabled, status) \
+                 VALUES ('orko', 'orko', 1, 'online')",
+            )
+            .unwrap();
+
+        let envelope = robot_machines(&store).unwrap();
+        assert_eq!(envelope.schema_version, "vc.robot.machines.v1");
+        assert_eq!(envelope.data["total"], 1);
+        assert_eq!(envelope.data["machines"][0]["machine_id"], "orko");
+        assert!(envelope.data.get("warning").is_none());
+    }
+}
@@ -0,0 +1,1615 @@
+//! TOON (Token-Optimized Object Notation) output format
+//!
+//! TOON reduces JSON output by 60-70% for agent consumption while preserving
+//! essential information for decision-making.
+//!
+//! Format specification:
+//! - Version header: `TOON1|`
+//! - Sections delimited by `|`
+//! - Items within sections delimited by `,`
+//! - Key-value pairs use `:`
+//! - `!` prefix indicates alert/warning severity
+//! - `@` indicates location/time relationship
+//! - `%` suffix indicates percentage
+//!
+//! Section codes:
+//! - `F:` Fleet summary
+//! - `M:` Machines
+//! - `AL:` Alerts
+//! - `PR:` Predictions
+//! - `AC:` Accounts
+//! - `RP:` Repos
+//! - `EV:` Events
+//! - `TR:` Triage action count
+//! - `TA:` Ranked triage actions
+//! - `KB:` Knowledge base results
+//! - `X:` Exact (lossless) value, decodable with [`from_toon`]
+//! - `CT:` Records left out to fit a token budget, per section: `CT:M12`
+//!   means twelve `M:` records are missing
+//!
+//! Lossless encoding (`TOON1|X:<value>`):
+//! - `~` null, `T`/`F` booleans, numbers as JSON number text
+//! - Strings bare when they match `[A-Za-z_][A-Za-z0-9_./-]*` (and are not
+//!   `T`/`F`), JSON-quoted otherwise
+//! - Objects `{k:v,k:v}`, arrays `[v,v]`
+//! - Tabular arrays `#N{k1,k2}[v,v;v,v]` for two or more objects that share
+//!   the same keys in the same order
+//!
+//! Limits: nesting deeper than [`MAX_DEPTH`] is rejected, and numbers are
+//! limited to what `serde_json::Number` holds (i64, u64 or finite f64).
+
+use crate::{
+    AccountsData, HealthData, MachineHealth, OracleData, ReposData, StatusData, TriageData,
+};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Trait for types that can be serialized to TOON format
+///
+/// An implementation lays its document out as [`ToonPart`]s; list sections
+/// give each record a priority, which is how a payload says what matters
+/// most when [`ToToon::to_toon_within`] has to leave records out.
+pub trait ToToon {
+    /// The document's sections, in order, without the `TOON1` header
+    fn toon_parts(&self) -> Vec<ToonPart>;
+
+    fn to_toon(&self) -> String {
+        render_parts(&self.toon_parts(), None)
+    }
+
+    /// The document cut down to `budget` by dropping whole records, least
+    /// important first, with a `CT:` section counting what was left out.
+    /// Sections that are not lists are always kept, so a document made of
+    /// nothing else can still come out over budget.
+    fn to_toon_within(&self, budget: ToonBudget) -> String {
+        render_parts(&self.toon_parts(), Some(budget))
+    }
+}
+
+/// One `|`-separated section of a summary document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToonPart {
+    /// A section kept whole whatever the budget, e.g. `F:4on1off,h85`
+    Fixed(String),
+    /// `code:record,record,...`; a budget drops whole records
+    Records {
+        code: &'static str,
+        records: Vec<ToonRecord>,
+    },
+}
+
+/// One record of a [`ToonPart::Records`] section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToonRecord {
+    pub text: String,
+    /// Lower is kept first; ties keep document order
+    pub priority: u32,
+}
+
+impl ToonRecord {
+    fn new(text: String, priority: u32) -> Self {
+        Self { text, priority }
+    }
+}
+
+/// Output budget for a TOON document, in approximate tokens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToonBudget {
+    pub max_tokens: usize,
+    /// Bytes counted as one token
+    pub chars_per_token: usize,
+}
+
+impl ToonBudget {
+    pub const DEFAULT_CHARS_PER_TOKEN: usize = 4;
+
+    #[must_use]
+    pub const fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            chars_per_token: Self::DEFAULT_CHARS_PER_TOKEN,
+        }
+    }
+
+    #[must_use]
+    pub const fn max_bytes(&self) -> usize {
+        let per_token = if self.chars_per_token == 0 {
+            1
+        } else {
+            self.chars_per_token
+        };
+        self.max_tokens.saturating_mul(per_token)
+    }
+}
+
+/// Section code of the continuation marker
+pub const CONTINUATION_CODE: &str = "CT";
+
+/// Join `parts` into a document, keeping the records `keep` accepts
+fn join_parts(parts: &[ToonPart], keep: impl Fn(usize, usize) -> bool) -> String {
+    let mut out = String::from("TOON1");
+    for (p, part) in parts.iter().enumerate() {
+        match part {
+            ToonPart::Fixed(text) => {
+                out.push('|');
+                out.push_str(text);
+            }
+            ToonPart::Records { code, records } => {
+                let kept: Vec<&str> = records
+                    .iter()
+                    .enumerate()
+                    .filter(|(r, _)| keep(p, *r))
+                    .map(|(_, record)| record.text.as_str())
+                    .collect();
+                if !kept.is_empty() {
+                    write!(&mut out, "|{code}:{}", kept.join(","))
+                        .expect("writing to a String cannot fail");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// `|CT:M12,TA3` for the records each section lost, empty if none
+fn continuation_marker(parts: &[ToonPart], omitted: &[usize]) -> String {
+    let counts: Vec<String> = parts
+        .iter()
+        .zip(omitted)
+        .filter_map(|(part, &count)| match part {
+            ToonPart::Records { code, .. } if count > 0 => Some(format!("{code}{count}")),
+            _ => None,
+        })
+        .collect();
+    if counts.is_empty() {
+        String::new()
+    } else {
+        format!("|{CONTINUATION_CODE}:{}", counts.join(","))
+    }
+}
+
+fn render_parts(parts: &[ToonPart], budget: Option<ToonBudget>) -> String {
+    let full = join_parts(parts, |_, _| true);
+    let Some(budget) = budget else {
+        return full;
+    };
+    let max_bytes = budget.max_bytes();
+    if full.len() <= max_bytes {
+        return full;
+    }
+
+    let totals: Vec<usize> = parts
+        .iter()
+        .map(|part| match part {
+            ToonPart::Records { records, .. } => records.len(),
+            ToonPart::Fixed(_) => 0,
+        })
+        .collect();
+    // Room for the marker at its longest, when every record is left out
+    let mut used =
+        join_parts(parts, |_, _| false).len() + continuation_marker(parts, &totals).len();
+
+    let mut ranked: Vec<(u32, usize, usize)> = Vec::new();
+    for (p, part) in parts.iter().enumerate() {
+        if let ToonPart::Records { records, .. } = part {
+            ranked.extend(
+                records
+                    .iter()
+                    .enumerate()
+                    .map(|(r, record)| (record.priority, p, r)),
+            );
+        }
+    }
+    ranked.sort_unstable();
+
+    let mut kept = vec![Vec::new(); parts.len()];
+    for (_, p, r) in ranked {
+        let ToonPart::Records { code, records } = &parts[p] else {
+            continue;
+        };
+        let separator = if kept[p].is_empty() {
+            code.len() + 2
+        } else {
+            1
+        };
+        let cost = separator + records[r].text.len();
+        // Stop at the first record that does not fit rather than skip to a
+        // smaller, less important one
+        if used + cost > max_bytes {
+            break;
+        }
+        used += cost;
+        kept[p].push(r);
+    }
+
+    let omitted: Vec<usize> = totals
+        .iter()
+        .zip(&kept)
+        .map(|(total, kept)| total - kept.len())
+        .collect();
+    let mut out = join_parts(parts, |p, r| kept[p].contains(&r));
+    out.push_str(&continuation_marker(parts, &omitted));
+    out
+}
+
+/// Importance of a machine record: down, degraded and unknown machines
+/// first, then ones with an issue, then healthy ones and those in
+/// maintenance; within a tier the lowest score first
+fn machine_priority(status: &str, score: Option<f64>, has_issue: bool) -> u32 {
+    let tier = match status {
+        "offline" => 0,
+        "degraded" => 1,
+        "unknown" => 2,
+        "maintenance" => 5,
+        _ if has_issue => 3,
+        _ => 4,
+    };
+    tier * 101 + score.map_or(0, |score| pct(score).min(100))
+}
+
+/// Convert a `HealthData` to TOON format
+///
+/// Example output:
+/// ```text
+/// TOON1|F:1on0off,h100,ag0,al0|M:local:on,h100|AL:0c0w0i
+/// ```
+impl ToToon for HealthData {
+    fn toon_parts(&self) -> Vec<ToonPart> {
+        let mut parts = Vec::new();
+
+        // Fleet summary section
+        let online = self
+            .machines
+            .iter()
+            .filter(|m| m.status == "online")
+            .count();
+        let maintenance = self
+            .machines
+            .iter()
+            .filter(|m| m.status == "maintenance")
+            .count();
+        let offline = self.machines.len() - online - maintenance;
+        let maintenance = if maintenance > 0 {
+            format!("{maintenance}mnt")
+        } else {
+            String::new()
+        };
+        let fleet = format!(
+            "F:{}on{}off{maintenance},h{},ag{},al{}",
+            online,
+            offline,
+            pct(self.overall.score),
+            self.overall.agent_count,
+            self.overall.active_alerts,
+        );
+        parts.push(ToonPart::Fixed(fleet));
+
+        // Machine section
+        parts.push(ToonPart::Records {
+            code: "M",
+            records: self
+                .machines
+                .iter()
+                .map(|m| {
+                    ToonRecord::new(
+                        machine_health_toon(m),
+                        machine_priority(&m.status, m.score, m.top_issue.is_some()),
+                    )
+                })
+                .collect(),
+        });
+
+        // Alerts section
+        let al = &self.alerts_by_severity;
+        if al.critical > 0 || al.warning > 0 || al.info > 0 {
+            parts.push(ToonPart::Fixed(format!(
+                "AL:{}c{}w{}i",
+                al.critical, al.warning, al.info
+            )));
+        }
+
+        // Daemon degradation (only when shedding load)
+        if let Some(daemon) = &self.daemon
+            && (daemon.level != "normal" || daemon.disk_pressure)
+        {
+            let disk = if daemon.disk_pressure { ",disk" } else { "" };
+            parts.push(ToonPart::Fixed(format!("D:{}{disk}", daemon.level)));
+        }
+
+        parts
+    }
+}
+
+/// Convert `TriageData` to TOON format
+///
+/// Each action is `rank:category:command(confidence%)`; `+N` counts the
+/// actions `--max-items` left out. Under a budget the best-ranked actions
+/// are kept.
+///
+/// Example output:
+/// ```text
+/// TOON1|TR:2acts|TA:1:ack:vc alert ack 1(95%),2:capacity:vc robot oracle(85%)|+3
+/// ```
+impl ToToon for TriageData {
+    fn toon_parts(&self) -> Vec<ToonPart> {
+        let mut parts = vec![
+            ToonPart::Fixed(format!("TR:{}acts", self.actions.len())),
+            ToonPart::Records {
+                code: "TA",
+                records: self
+                    .actions
+                    .iter()
+                    .map(|a| {
+                        ToonRecord::new(
+                            format!(
+                                "{}:{}:{}({}%)",
+                                a.rank,
+                                a.category.as_str(),
+                                abbreviate(&a.command, 30),
+                                pct(a.confidence)
+                            ),
+                            a.rank,
+                        )
+                    })
+                    .collect(),
+            },
+        ];
+        if self.truncated > 0 {
+            parts.push(ToonPart::Fixed(format!("+{}", self.truncated)));
+        }
+
+        parts
+    }
+}
+
+/// Convert `StatusData` to TOON format
+///
+/// Example output:
+/// ```text
+/// TOON1|F:4on1off,h85|M:orko:on,h91,cpu45,mem68|RP:15t2d3a1b|AL:0c1w2i
+/// ```
+impl ToToon for StatusData {
+    fn toon_parts(&self) -> Vec<ToonPart> {
+        let mut parts = Vec::new();
+
+        // Fleet summary
+        let f = &self.fleet;
+        let maintenance = if f.maintenance > 0 {
+            format!("{}mnt", f.maintenance)
+        } else {
+            String::new()
+        };
+        parts.push(ToonPart::Fixed(format!(
+            "F:{}on{}off{maintenance},h{}",
+            f.online,
+            f.offline,
+            pct(f.health_score)
+        )));
+
+        // Machines
+        parts.push(ToonPart::Records {
+            code: "M",
+            records: self
+                .machines
+                .iter()
+                .map(|m| {
+                    let mut s = format!(
+                        "{}:{},h{}",
+                        abbreviate(&m.id, 12),
+                        status_abbrev(&m.status),
+                        pct_opt(m.health_score)
+                    );
+                    if let Some(ref metrics) = m.metrics {
+                        write!(
+                            &mut s,
+                            ",cpu{},mem{}",
+                            metric_opt(metrics.cpu_pct),
+                            metric_opt(metrics.mem_pct)
+                        )
+                        .expect("writing to a String cannot fail");
+                    }
+                    if let Some(ref issue) = m.top_issue {
+                        write!(&mut s, ",!{}", abbreviate(issue, 15))
+                            .expect("writing to a String cannot fail");
+                    }
+                    ToonRecord::new(
+                        s,
+                        machine_priority(&m.status, m.health_score, m.top_issue.is_some()),
+                    )
+                })
+                .collect(),
+        });
+
+        // Repos
+        let r = &self.repos;
+        if r.total > 0 {
+            parts.push(ToonPart::Fixed(format!(
+                "RP:{}t{}d{}a{}b",
+                r.total, r.dirty, r.ahead, r.behind
+            )));
+        }
+
+        // Alerts
+        let a = &self.alerts;
+        if a.critical > 0 || a.warning > 0 || a.info > 0 {
+            parts.push(ToonPart::Fixed(format!(
+                "AL:{}c{}w{}i",
+                a.critical, a.warning, a.info
+            )));
+        }
+
+        parts
+    }
+}
+
+/// Generic TOON for `serde_json::Value` — produces a compact key:value summary
+impl ToToon for serde_json::Value {
+    fn toon_parts(&self) -> Vec<ToonPart> {
+        match self {
+            // An empty object still says it was one
+            serde_json::Value::Object(map) if map.is_empty() => {
+                vec![ToonPart::Fixed("D:".to_string())]
+            }
+            serde_json::Value::Object(map) => vec![ToonPart::Records {
+                code: "D",
+                records: map
+                    .iter()
+                    .map(|(k, v)| {
+                        ToonRecord::new(format!("{}:{}", abbreviate(k, 12), value_toon(v)), 0)
+                    })
+                    .collect(),
+            }],
+            serde_json::Value::Array(arr) => vec![ToonPart::Fixed(format!("A:{}items", arr.len()))],
+            other => vec![ToonPart::Fixed(format!("V:{}", value_toon(other)))],
+        }
+    }
+}
+
+/// Payloads with no dedicated encoding go through their JSON form
+macro_rules! toon_via_json {
+    ($($data:ty),*) => {
+        $(impl ToToon for $data {
+            fn toon_parts(&self) -> Vec<ToonPart> {
+                json_toon_parts(self)
+            }
+        })*
+    };
+}
+
+toon_via_json!(AccountsData, ReposData, OracleData);
+
+fn json_toon_parts<T: Serialize>(value: &T) -> Vec<ToonPart> {
+    match serde_json::to_value(value) {
+        Ok(json_val) => json_val.toon_parts(),
+        Err(e) => vec![ToonPart::Fixed(format!("ERR:{e}"))],
+    }
+}
+
+/// Format any Serialize type as TOON by going through JSON first
+pub fn to_toon_via_json<T: Serialize>(value: &T) -> String {
+    render_parts(&json_toon_parts(value), None)
+}
+
+/// [`to_toon_via_json`] cut down to `budget`
+pub fn to_toon_via_json_within<T: Serialize>(value: &T, budget: ToonBudget) -> String {
+    render_parts(&json_toon_parts(value), Some(budget))
+}
+
+// ============================================================================
+// Lossless encoding
+// ============================================================================
+
+/// Deepest array/object nesting `from_toon` accepts. Robot envelopes are a
+/// handful of levels deep; anything past this is a corrupt or hostile input.
+pub const MAX_DEPTH: usize = 64;
+
+/// Error returned when a TOON document cannot be decoded (or, from
+/// [`encode_verified`], when it does not decode back to its input).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid TOON at byte {position}: {message}")]
+pub struct ToonError {
+    pub position: usize,
+    pub message: String,
+}
+
+/// Encode a JSON value as a lossless `TOON1|X:` document.
+///
+/// Unlike the summary sections above, this layout keeps every key and value
+/// so [`from_toon`] can rebuild the exact input. Arrays of two or more objects
+/// sharing the same keys use the tabular layout, which states each key once.
+pub fn encode(value: &serde_json::Value) -> String {
+    let mut out = String::from("TOON1|X:");
+    encode_into(&mut out, value);
+    out
+}
+
+/// Encode `value`, decode the result and fail unless the two are equal.
+///
+/// # Errors
+///
+/// Returns [`ToonError`] when the encoded document does not decode, or decodes
+/// to a different value — either way the encoding lost information.
+pub fn encode_verified(value: &serde_json::Value) -> Result<String, ToonError> {
+    let encoded = encode(value);
+    let decoded = from_toon(&encoded)?;
+    if decoded != *value {
+        return Err(ToonError {
+            position: 0,
+            message: "lossy encoding: decoded value differs from the input".to_string(),
+        });
+    }
+    Ok(encoded)
+}
+
+/// Decode a lossless `TOON1|X:` document back into JSON.
+///
+/// Summary documents (`F:`, `M:`, `TR:` ...) are deliberately lossy and are
+/// rejected rather than guessed at.
+///
+/// # Errors
+///
+/// Returns [`ToonError`] with the byte offset of the first malformed token, a
+/// missing `TOON1|X:` header, nesting beyond [`MAX_DEPTH`] or trailing input.
+pub fn from_toon(input: &str) -> Result<serde_json::Value, ToonError> {
+    const HEADER: &str = "TOON1|X:";
+    if !input.starts_with(HEADER) {
+        return Err(ToonError {
+            position: 0,
+            message: format!("expected `{HEADER}` header (summary sections are not decodable)"),
+        });
+    }
+    let mut decoder = Decoder {
+        input,
+        pos: HEADER.len(),
+        depth: 0,
+    };
+    let value = decoder.value()?;
+    if decoder.pos != input.len() {
+        return Err(decoder.error("trailing input after value"));
+    }
+    Ok(value)
+}
+
+fn encode_into(out: &mut String, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Null => out.push('~'),
+        serde_json::Value::Bool(b) => out.push(if *b { 'T' } else { 'F' }),
+        // Debug prints the shortest text that parses back to the same f64 and
+        // always keeps a `.` or exponent, so `1.0` never decodes as `1`.
+        serde_json::Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() => write!(out, "{f:?}").expect("writing to a String cannot fail"),
+            _ => out.push_str(&n.to_string()),
+        },
+        serde_json::Value::String(s) => encode_str(out, s),
+        serde_json::Value::Array(items) => {
+            if let Some(keys) = table_keys(items) {
+                write!(out, "#{}{{", items.len()).expect("writing to a String cannot fail");
+                for (i, key) in keys.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    encode_str(out, key);
+                }
+                out.push_str("}[");
+                for (row, item) in items.iter().enumerate() {
+                    if row > 0 {
+                        out.push(';');
+                    }
+                    let fields = item.as_object().expect("table rows are objects");
+                    for (i, field) in fields.values().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        encode_into(out, field);
+                    }
+                }
+                out.push(']');
+            } else {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    encode_into(out, item);
+                }
+                out.push(']');
+            }
+        }
+        serde_json::Value::Object(map) => {
+            out.push('{');
+            for (i, (key, field)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                encode_str(out, key);
+                out.push(':');
+                encode_into(out, field);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Keys shared by every element, in order, when `items` can use the tabular
+/// layout: at least two objects, each with the same non-empty key sequence.
+fn table_keys(items: &[serde_json::Value]) -> Option<Vec<&String>> {
+    if items.len() < 2 {
+        return None;
+    }
+    let keys: Vec<&String> = items[0].as_object()?.keys().collect();
+    if keys.is_empty() {
+        return None;
+    }
+    items[1..]
+        .iter()
+        .all(|item| {
+            item.as_object()
+                .is_some_and(|map| map.len() == keys.len() && map.keys().eq(keys.iter().copied()))
+        })
+        .then_some(keys)
+}
+
+/// Strings go out bare when they cannot be mistaken for another token, and
+/// JSON-quoted otherwise.
+fn encode_str(out: &mut String, s: &str) {
+    if is_bare(s) {
+        out.push_str(s);
+    } else {
+        out.push_str(&serde_json::to_string(s).expect("strings always serialize"));
+    }
+}
+
+fn is_bare(s: &str) -> bool {
+    let mut chars = s.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '/'))
+        && s != "T"
+        && s != "F"
+}
+
+struct Decoder<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: impl Into<String>) -> ToonError {
+        ToonError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ToonError> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", char::from(byte))))
+        }
+    }
+
+    fn value(&mut self) -> Result<serde_json::Value, ToonError> {
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'~') => {
+                self.pos += 1;
+                Ok(serde_json::Value::Null)
+            }
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'#') => self.nested(Self::table),
+            Some(b'"') => self.string().map(serde_json::Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                let word = self.bare()?;
+                Ok(match word.as_str() {
+                    "T" => serde_json::Value::Bool(true),
+                    "F" => serde_json::Value::Bool(false),
+                    _ => serde_json::Value::String(word),
+                })
+            }
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<serde_json::Value, ToonError>,
+    ) -> Result<serde_json::Value, ToonError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(format!("nesting deeper than {MAX_DEPTH}")));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<serde_json::Value, ToonError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(serde_json::Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(serde_json::Value::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]` in array")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<serde_json::Value, ToonError> {
+        self.expect(b'{')?;
+        let mut map = serde_json::Map::new();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(serde_json::Value::Object(map));
+        }
+        loop {
+            let key = self.key()?;
+            self.expect(b':')?;
+            let field = self.value()?;
+            map.insert(key, field);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(serde_json::Value::Object(map));
+                }
+                _ => return Err(self.error("expected `,` or `}` in object")),
+            }
+        }
+    }
+
+    /// `#N{k1,k2}[v,v;v,v]` — N rows, each holding one value per key.
+    fn table(&mut self) -> Result<serde_json::Value, ToonError> {
+        self.expect(b'#')?;
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let rows: usize = self.input[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("expected table row count"))?;
+
+        self.expect(b'{')?;
+        let mut keys = vec![self.key()?];
+        while self.peek() == Some(b',') {
+            self.pos += 1;
+            keys.push(self.key()?);
+        }
+        self.expect(b'}')?;
+
+        self.expect(b'[')?;
+        let mut items = Vec::with_capacity(rows.min(1024));
+        loop {
+            let mut map = serde_json::Map::new();
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    self.expect(b',')?;
+                }
+                map.insert(key.clone(), self.value()?);
+            }
+            items.push(serde_json::Value::Object(map));
+            match self.peek() {
+                Some(b';') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return Err(self.error("expected `;` or `]` in table")),
+            }
+        }
+        if items.len() != rows {
+            return Err(self.error(format!(
+                "table declares {rows} rows but holds {}",
+                items.len()
+            )));
+        }
+        Ok(serde_json::Value::Array(items))
+    }
+
+    fn key(&mut self) -> Result<String, ToonError> {
+        if self.peek() == Some(b'"') {
+            self.string()
+        } else {
+            self.bare()
+        }
+    }
+
+    fn bare(&mut self) -> Result<String, ToonError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'/'))
+        {
+            self.pos += 1;
+        }
+        let word = &self.input[start..self.pos];
+        if word.is_empty() {
+            self.pos = start;
+            return Err(self.error("expected a value"));
+        }
+        Ok(word.to_string())
+    }
+
+    fn string(&mut self) -> Result<String, ToonError> {
+        let start = self.pos;
+        self.expect(b'"')?;
+        loop {
+            match self.peek() {
+                None => {
+                    self.pos = start;
+                    return Err(self.error("unterminated string"));
+                }
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+        let literal = self.input.get(start..self.pos).unwrap_or_default();
+        serde_json::from_str(literal).map_err(|e| ToonError {
+            position: start,
+            message: format!("bad string literal: {e}"),
+        })
+    }
+
+    fn number(&mut self) -> Result<serde_json::Value, ToonError> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))
+        {
+            self.pos += 1;
+        }
+        let text = &self.input[start..self.pos];
+        let bad = || ToonError {
+            position: start,
+            message: format!("bad number `{text}`"),
+        };
+        // Parse floats with std, which rounds correctly, so the shortest text
+        // the encoder printed comes back bit-for-bit.
+        let number = if text.contains(['.', 'e', 'E']) {
+            text.parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .ok_or_else(bad)?
+        } else if let Ok(i) = text.parse::<i64>() {
+            i.into()
+        } else {
+            text.parse::<u64>().map_err(|_| bad())?.into()
+        };
+        Ok(serde_json::Value::Number(number))
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+/// Convert a 0.0-1.0 score to a percentage integer (0-100)
+// The NaN guard and the `clamp` to `0.0..=u32::MAX` above the cast mean the
+// value is always a non-negative, in-range `f64` by the time it is cast.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn pct(score: f64) -> u32 {
+    if score.is_nan() {
+        return 0;
+    }
+    (score * 100.0).clamp(0.0, f64::from(u32::MAX)).round() as u32
+}
+
+/// Render an optional score. An unknown score is `-`, not `0` — a machine with
+/// no health summary is not a machine scoring zero.
+fn pct_opt(score: Option<f64>) -> String {
+    score.map_or_else(|| "-".to_string(), |value| pct(value).to_string())
+}
+
+/// Render an optional percentage metric, `-` when it was never collected.
+fn metric_opt(value: Option<f64>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.round().to_string())
+}
+
+/// Abbreviate a status string
+fn status_abbrev(status: &str) -> &str {
+    match status {
+        "online" => "on",
+        "offline" => "off",
+        "degraded" => "deg",
+        "unknown" => "unk",
+        "maintenance" => "mnt",
+        other => other,
+    }
+}
+
+/// Abbreviate a string to max length, appending ".." if truncated
+fn abbreviate(s: &str, max_len: usize) -> String {
+    let char_count = s.chars().count();
+    if char_count <= max_len {
+        s.to_string()
+    } else if max_len <= 2 {
+        s.chars().take(max_len).collect()
+    } else {
+        let truncated: String = s.chars().take(max_len - 2).collect();
+        format!("{truncated}..")
+    }
+}
+
+/// Convert a `MachineHealth` to a compact TOON segment
+// `cpu_percent` / `memory_percent` are collected percentages (0-100), so the
+// rounded value always fits in a `u32`; a float-to-int `as` cast saturates
+// rather than wrapping, so a nonsensical reading still renders a sane digit.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn machine_health_toon(m: &MachineHealth) -> String {
+    let mut s = format!(
+        "{}:{},h{},{}ag",
+        abbreviate(&m.id, 12),
+        status_abbrev(&m.status),
+        pct_opt(m.score),
+        m.agent_count
+    );
+    if let Some(cpu) = m.cpu_percent {
+        write!(&mut s, ",cpu{}", cpu.round() as u32).expect("writing to a String cannot fail");
+    }
+    if let Some(mem) = m.memory_percent {
+        write!(&mut s, ",mem{}", mem.round() as u32).expect("writing to a String cannot fail");
+    }
+    if let Some(ref issue) = m.top_issue {
+        write!(&mut s, ",!{}", abbreviate(issue, 15)).expect("writing to a String cannot fail");
+    }
+    s
+}
+
+/// Compact TOON representation of a JSON value
+fn value_toon(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::Null => "-".to_string(),
+        serde_json::Value::Bool(b) => if *b { "T" } else { "F" }.to_string(),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.to_string()
+            } else if let Some(f) = n.as_f64() {
+                format!("{f:.1}")
+            } else {
+                n.to_string()
+            }
+        }
+        serde_json::Value::String(s) => abbreviate(s, 25),
+        serde_json::Value::Array(arr) => format!("[{}]", arr.len()),
+        serde_json::Value::Object(map) => format!("{{{}}}", map.len()),
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use chrono::Utc;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_health_data_toon() {
+        let health = HealthData {
+            overall: OverallHealth {
+                score: 0.85,
+                severity: "warning".to_string(),
+                active_alerts: 2,
+                machine_count: 3,
+                agent_count: 15,
+            },
+            machines: vec![
+                MachineHealth {
+                    id: "orko".to_string(),
+                    name: "Orko".to_string(),
+                    score: Some(0.91),
+                    status: "online".to_string(),
+                    top_issue: None,
+                    last_seen: Some(Utc::now()),
+                    agent_count: 15,
+                    cpu_percent: Some(45.0),
+                    memory_percent: Some(68.0),
+                    maintenance: None,
+                },
+                MachineHealth {
+                    id: "backup".to_string(),
+                    name: "Backup".to_string(),
+                    score: Some(0.0),
+                    status: "offline".to_string(),
+                    top_issue: Some("no_response".to_string()),
+                    last_seen: None,
+                    agent_count: 0,
+                    cpu_percent: None,
+                    memory_percent: None,
+                    maintenance: None,
+                },
+            ],
+            alerts_by_severity: AlertCounts {
+                critical: 0,
+                warning: 2,
+                info: 1,
+            },
+            daemon: None,
+            daemon_lease: None,
+        };
+
+        let toon = health.to_toon();
+        assert!(toon.starts_with("TOON1|"));
+        assert!(toon.contains("F:1on1off"));
+        assert!(toon.contains("h85"));
+        assert!(toon.contains("ag15"));
+        assert!(toon.contains("al2"));
+        assert!(toon.contains("M:"));
+        assert!(toon.contains("orko:on"));
+        assert!(toon.contains("cpu45"));
+        assert!(toon.contains("mem68"));
+        assert!(toon.contains("backup:off"));
+        assert!(toon.contains("!no_response"));
+        assert!(toon.contains("AL:0c2w1i"));
+
+        // Verify significant token reduction
+        let json = serde_json::to_string(&health).unwrap();
+        assert!(
+            toon.len() < json.len() / 2,
+            "TOON ({} bytes) should be less than half of JSON ({} bytes)",
+            toon.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_triage_data_toon() {
+        let mut action = ActionItem {
+            id: "alert-disk-full".to_string(),
+            rank: 1,
+            category: ActionCategory::Ack,
+            severity: ActionSeverity::Critical,
+            title: "Acknowledge alert 1: Disk full".to_string(),
+            command: "vc alert ack 1".to_string(),
+            mcp_tool: None,
+            machine_ids: vec!["orko".to_string()],
+            alert_ids: vec![1],
+            incident_ids: vec![],
+            confidence: 0.95,
+            expected_outcome: "The alert is marked as seen".to_string(),
+            playbook_id: None,
+            age_secs: Some(60),
+            score: 0.56,
+            correlation: None,
+            caused_by: None,
+        };
+        let mut triage = TriageData {
+            actions: vec![action.clone()],
+            truncated: 0,
+        };
+        action.rank = 2;
+        action.category = ActionCategory::Capacity;
+        action.command = "vc robot oracle".to_string();
+        action.confidence = 0.85;
+        triage.actions.push(action);
+        triage.truncated = 3;
+
+        let toon = triage.to_toon();
+        assert_eq!(
+            toon,
+            "TOON1|TR:2acts|TA:1:ack:vc alert ack 1(95%),2:capacity:vc robot oracle(85%)|+3"
+        );
+    }
+
+    #[test]
+    fn test_triage_empty_toon() {
+        let triage = TriageData {
+            actions: vec![],
+            truncated: 0,
+        };
+
+        assert_eq!(triage.to_toon(), "TOON1|TR:0acts");
+    }
+
+    #[test]
+    fn test_status_data_toon() {
+        let status = StatusData {
+            fleet: FleetSummary {
+                total_machines: 4,
+                online: 3,
+                offline: 1,
+                maintenance: 0,
+                health_score: 0.85,
+            },
+            machines: vec![MachineStatus {
+                id: "orko".to_string(),
+                status: "online".to_string(),
+                last_seen: Some(Utc::now()),
+                health_score: Some(0.91),
+                metrics: Some(MachineMetrics {
+                    cpu_pct: Some(45.2),
+                    mem_pct: Some(68.0),
+                    load5: Some(1.8),
+                    disk_free_pct: Some(35.0),
+                }),
+                top_issue: None,
+                maintenance: None,
+            }],
+            repos: RepoSummary {
+                total: 15,
+                dirty: 2,
+                ahead: 3,
+                behind: 1,
+            },
+            alerts: AlertSummary {
+                critical: 0,
+                warning: 1,
+                info: 2,
+            },
+            by_tag: Vec::new(),
+        };
+
+        let toon = status.to_toon();
+        assert!(toon.starts_with("TOON1|"));
+        assert!(toon.contains("F:3on1off,h85"));
+        assert!(toon.contains("M:orko:on,h91,cpu45,mem68"));
+        assert!(toon.contains("RP:15t2d3a1b"));
+        assert!(toon.contains("AL:0c1w2i"));
+
+        // Verify significant token reduction
+        let json = serde_json::to_string(&status).unwrap();
+        assert!(
+            toon.len() < json.len() / 2,
+            "TOON ({} bytes) should be less than half of JSON ({} bytes)",
+            toon.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn test_status_no_alerts_toon() {
+        let status = StatusData {
+            fleet: FleetSummary {
+                total_machines: 1,
+                online: 1,
+                offline: 0,
+                maintenance: 0,
+                health_score: 1.0,
+            },
+            machines: vec![],
+            repos: RepoSummary::default(),
+            alerts: AlertSummary::default(),
+            by_tag: Vec::new(),
+        };
+
+        let toon = status.to_toon();
+        // No AL section when all zero
+        assert!(!toon.contains("AL:"));
+        // No RP section when total is 0
+        assert!(!toon.contains("RP:"));
+    }
+
+    #[test]
+    fn test_status_maintenance_toon() {
+        let status = StatusData {
+            fleet: FleetSummary {
+                total_machines: 2,
+                online: 1,
+                offline: 0,
+                maintenance: 1,
+                health_score: 0.95,
+            },
+            machines: vec![MachineStatus {
+                id: "builder-2".to_string(),
+                status: "maintenance".to_string(),
+                last_seen: None,
+                health_score: Some(0.7),
+                metrics: None,
+                top_issue: None,
+                maintenance: Some(MaintenanceInfo {
+                    since: Some(Utc::now()),
+                    until: None,
+                    reason: Some("RAM upgrade".to_string()),
+                }),
+            }],
+            repos: RepoSummary::default(),
+            alerts: AlertSummary::default(),
+            by_tag: Vec::new(),
+        };
+
+        let toon = status.to_toon();
+        assert!(toon.contains("F:1on0off1mnt,h95"));
+        assert!(toon.contains("M:builder-2:mnt,h70"));
+    }
+
+    #[test]
+    fn test_pct_helper() {
+        assert_eq!(pct(0.0), 0);
+        assert_eq!(pct(0.5), 50);
+        assert_eq!(pct(1.0), 100);
+        assert_eq!(pct(0.85), 85);
+        assert_eq!(pct(0.999), 100); // rounds
+    }
+
+    #[test]
+    fn test_abbreviate() {
+        assert_eq!(abbreviate("short", 10), "short");
+        assert_eq!(abbreviate("exactly-ten", 11), "exactly-ten");
+        assert_eq!(abbreviate("this is a long string", 10), "this is ..");
+        assert_eq!(abbreviate("ab", 2), "ab");
+        assert_eq!(abbreviate("abc", 2), "ab");
+    }
+
+    #[test]
+    fn test_status_abbrev() {
+        assert_eq!(status_abbrev("online"), "on");
+        assert_eq!(status_abbrev("offline"), "off");
+        assert_eq!(status_abbrev("degraded"), "deg");
+        assert_eq!(status_abbrev("unknown"), "unk");
+        assert_eq!(status_abbrev("custom"), "custom");
+    }
+
+    fn fleet_health(healthy: usize) -> HealthData {
+        let machine = |id: String, status: &str, score: f64, issue: Option<&str>| MachineHealth {
+            id,
+            name: String::new(),
+            score: Some(score),
+            status: status.to_string(),
+            top_issue: issue.map(ToString::to_string),
+            last_seen: None,
+            agent_count: 2,
+            cpu_percent: Some(40.0),
+            memory_percent: Some(60.0),
+            maintenance: None,
+        };
+        let mut machines: Vec<MachineHealth> = (0..healthy)
+            .map(|i| machine(format!("worker-{i}"), "online", 0.97, None))
+            .collect();
+        // The ones that matter sit at the end of the list
+        machines.push(machine(
+            "db-1".to_string(),
+            "online",
+            0.4,
+            Some("disk_full"),
+        ));
+        machines.push(machine(
+            "backup".to_string(),
+            "offline",
+            0.0,
+            Some("no_response"),
+        ));
+        HealthData {
+            overall: OverallHealth {
+                score: 0.9,
+                severity: "warning".to_string(),
+                active_alerts: 3,
+                machine_count: u32::try_from(machines.len()).unwrap(),
+                agent_count: 40,
+            },
+            machines,
+            alerts_by_severity: AlertCounts {
+                critical: 1,
+                warning: 2,
+                info: 0,
+            },
+            daemon: None,
+            daemon_lease: None,
+        }
+    }
+
+    /// Check every list section of `doc` is made of whole records of
+    /// `parts`, and that kept plus `CT:`-counted records add up. Returns the
+    /// records kept.
+    fn assert_whole_records(doc: &str, parts: &[ToonPart]) -> usize {
+        let mut sections = doc.split('|');
+        assert_eq!(sections.next(), Some("TOON1"));
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        let mut omitted: HashMap<String, usize> = HashMap::new();
+        for section in sections {
+            if parts
+                .iter()
+                .any(|part| matches!(part, ToonPart::Fixed(text) if text == section))
+            {
+                continue;
+            }
+            if let Some(counts) = section.strip_prefix("CT:") {
+                for count in counts.split(',') {
+                    let split = count.find(|c: char| c.is_ascii_digit()).unwrap();
+                    omitted.insert(count[..split].to_string(), count[split..].parse().unwrap());
+                }
+                continue;
+            }
+            let (code, records) = parts
+                .iter()
+                .find_map(|part| match part {
+                    ToonPart::Records { code, records }
+                        if section.starts_with(&format!("{code}:")) =>
+                    {
+                        Some((*code, records))
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| panic!("unknown section {section:?}"));
+            let mut rest = &section[code.len() + 1..];
+            while !rest.is_empty() {
+                let record = records
+                    .iter()
+                    .find(|r| {
+                        rest.strip_prefix(r.text.as_str())
+                            .is_some_and(|after| after.is_empty() || after.starts_with(','))
+                    })
+                    .unwrap_or_else(|| panic!("partial record at {rest:?}"));
+                rest = rest[record.text.len()..].trim_start_matches(',');
+                *kept.entry(code).or_default() += 1;
+            }
+        }
+        for part in parts {
+            if let ToonPart::Records { code, records } = part {
+                let kept = kept.get(code).copied().unwrap_or(0);
+                let omitted = omitted.get(*code).copied().unwrap_or(0);
+                assert_eq!(kept + omitted, records.len(), "{code} in {doc}");
+            }
+        }
+        kept.values().sum()
+    }
+
+    #[test]
+    fn test_budget_cuts_at_record_boundaries() {
+        let health = fleet_health(40);
+        let parts = health.toon_parts();
+        let full = health.to_toon();
+        let mut last_kept = 0;
+        for max_tokens in [1, 12, 25, 40, 80, 160, 10_000] {
+            let budget = ToonBudget::new(max_tokens);
+            let doc = health.to_toon_within(budget);
+            let kept = assert_whole_records(&doc, &parts);
+            if kept > 0 {
+                assert!(doc.len() <= budget.max_bytes(), "{max_tokens}: {doc}");
+            }
+            assert!(kept >= last_kept, "a bigger budget kept fewer records");
+            last_kept = kept;
+            if doc.len() < full.len() {
+                assert!(doc.contains("|CT:M"), "{doc}");
+            }
+        }
+        assert_eq!(health.to_toon_within(ToonBudget::new(10_000)), full);
+        // Fleet and alert counts survive any budget
+        let tiny = health.to_toon_within(ToonBudget::new(1));
+        assert!(tiny.contains("F:41on1off"));
+        assert!(tiny.contains("AL:1c2w0i"));
+        assert!(tiny.ends_with("|CT:M42"), "{tiny}");
+    }
+
+    #[test]
+    fn test_budget_keeps_troubled_machines_first() {
+        let health = fleet_health(40);
+        let doc = health.to_toon_within(ToonBudget::new(40));
+        assert!(doc.contains("backup:off"), "{doc}");
+        assert!(doc.contains("db-1:on"), "{doc}");
+        assert!(!doc.contains("worker-39"), "{doc}");
+        // Kept records stay in document order
+        assert!(doc.find("db-1").unwrap() < doc.find("backup").unwrap());
+    }
+
+    #[test]
+    fn test_budget_respects_chars_per_token() {
+        let health = fleet_health(40);
+        let coarse = health.to_toon_within(ToonBudget {
+            max_tokens: 40,
+            chars_per_token: 8,
+        });
+        let fine = health.to_toon_within(ToonBudget {
+            max_tokens: 40,
+            chars_per_token: 2,
+        });
+        assert!(coarse.len() <= 320 && fine.len() <= 80);
+        assert!(coarse.len() > fine.len());
+    }
+
+    #[test]
+    fn test_budget_on_triage_keeps_best_ranked() {
+        let action = |rank: u32| ActionItem {
+            id: format!("a{rank}"),
+            rank,
+            category: ActionCategory::Investigate,
+            severity: ActionSeverity::Warning,
+            title: String::new(),
+            command: format!("vc alert show {rank}"),
+            mcp_tool: None,
+            machine_ids: vec![],
+            alert_ids: vec![],
+            incident_ids: vec![],
+            confidence: 0.5,
+            expected_outcome: String::new(),
+            playbook_id: None,
+            age_secs: None,
+            score: 0.5,
+            correlation: None,
+            caused_by: None,
+        };
+        let triage = TriageData {
+            actions: (1..=20).map(action).collect(),
+            truncated: 0,
+        };
+        let parts = triage.toon_parts();
+        for max_tokens in [5, 15, 30, 60] {
+            let doc = triage.to_toon_within(ToonBudget::new(max_tokens));
+            let kept = assert_whole_records(&doc, &parts);
+            if kept > 0 {
+                assert!(doc.contains("TA:1:investigate"), "{doc}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_value_toon() {
+        let val = serde_json::json!({
+            "name": "test",
+            "count": 42,
+            "active": true
+        });
+        let toon = val.to_toon();
+        assert!(toon.starts_with("TOON1|"));
+        assert!(toon.contains("D:"));
+        assert!(toon.contains("name:test"));
+        assert!(toon.contains("count:42"));
+        assert!(toon.contains("active:T"));
+    }
+
+    #[test]
+    fn test_to_toon_via_json() {
+        let data = serde_json::json!({"key": "value"});
+        let toon = to_toon_via_json(&data);
+        assert!(toon.starts_with("TOON1|"));
+        assert!(toon.contains("key:value"));
+    }
+
+    #[test]
+    fn test_value_toon_types() {
+        assert_eq!(value_toon(&serde_json::Value::Null), "-");
+        assert_eq!(value_toon(&serde_json::json!(true)), "T");
+        assert_eq!(value_toon(&serde_json::json!(false)), "F");
+        assert_eq!(value_toon(&serde_json::json!(42)), "42");
+        assert_eq!(value_toon(&serde_json::json!(3.14)), "3.1");
+        assert_eq!(value_toon(&serde_json::json!("hello")), "hello");
+        assert_eq!(value_toon(&serde_json::json!([1, 2, 3])), "[3]");
+        assert_eq!(value_toon(&serde_json::json!({"a": 1})), "{1}");
+    }
+
+    #[test]
+    fn test_encode_tabular_array_round_trip() {
+        let val = serde_json::json!({
+            "machines": [
+                {"id": "orko", "online": true, "score": 0.91},
+                {"id": "backup", "online": false, "score": 0.0},
+            ],
+            "note": "two words, one comma",
+            "empty": [],
+            "missing": null,
+        });
+        let toon = encode(&val);
+        assert!(toon.starts_with("TOON1|X:"));
+        assert!(toon.contains("machines:#2{id,online,score}[orko,T,0.91;backup,F,0.0]"));
+        assert!(toon.contains(r#"note:"two words, one comma""#));
+        assert!(toon.contains("missing:~"));
+        assert_eq!(from_toon(&toon).unwrap(), val);
+    }
+
+    #[test]
+    fn test_encode_quotes_ambiguous_strings() {
+        for s in ["T", "F", "", "42", "-1", "a b", "x:y", "quote\"d", "ünï"] {
+            let val = serde_json::json!([s]);
+            assert_eq!(from_toon(&encode(&val)).unwrap(), val, "string {s:?}");
+        }
+        assert_eq!(
+            encode(&serde_json::json!("plain_word")),
+            "TOON1|X:plain_word"
+        );
+    }
+
+    #[test]
+    fn test_from_toon_rejects_malformed_input() {
+        assert!(from_toon("TOON1|F:1on0off,h100").is_err());
+        assert!(from_toon("TOON1|X:[1,2").is_err());
+        assert!(from_toon("TOON1|X:{a:1}x").is_err());
+        let err = from_toon("TOON1|X:#3{a}[1;2]").unwrap_err();
+        assert!(err.message.contains("3 rows"), "{err}");
+        let deep = format!(
+            "TOON1|X:{}{}",
+            "[".repeat(MAX_DEPTH + 1),
+            "]".repeat(MAX_DEPTH + 1)
+        );
+        assert!(from_toon(&deep).is_err());
+    }
+
+    #[test]
+    fn test_encode_verified_health_data() {
+        let health = HealthData {
+            overall: OverallHealth {
+                score: 1.0,
+                severity: "healthy".to_string(),
+                active_alerts: 0,
+                machine_count: 1,
+                agent_count: 2,
+            },
+            machines: vec![MachineHealth {
+                id: "local".to_string(),
+                name: "Local".to_string(),
+                score: Some(1.0),
+                status: "online".to_string(),
+                top_issue: None,
+                last_seen: Some(Utc::now()),
+                agent_count: 2,
+                cpu_percent: Some(12.5),
+                memory_percent: None,
+                maintenance: None,
+            }],
+            alerts_by_severity: AlertCounts::default(),
+            daemon: None,
+            daemon_lease: None,
+        };
+        let val = serde_json::to_value(&health).unwrap();
+        let toon = encode_verified(&val).unwrap();
+        assert_eq!(from_toon(&toon).unwrap(), val);
+    }
+
+    fn arb_json() -> impl Strategy<Value = serde_json::Value> {
+        let leaf = prop_oneof![
+            Just(serde_json::Value::Null),
+            any::<bool>().prop_map(serde_json::Value::Bool),
+            any::<i64>().prop_map(serde_json::Value::from),
+            any::<u64>().prop_map(serde_json::Value::from),
+            any::<f64>()
+                .prop_filter("JSON numbers are finite", |f| f.is_finite())
+                .prop_map(serde_json::Value::from),
+            "\\PC{0,12}".prop_map(serde_json::Value::String),
+            "[a-z_][a-z0-9_./-]{0,8}".prop_map(serde_json::Value::String),
+        ];
+        leaf.prop_recursive(6, 64, 6, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..6).prop_map(serde_json::Value::Array),
+                prop::collection::btree_map("\\PC{0,6}", inner.clone(), 0..6)
+                    .prop_map(|map| serde_json::Value::Object(map.into_iter().collect())),
+                // Uniform rows, so the tabular layout gets exercised too.
+                (1usize..4, prop::collection::vec(inner, 2..12)).prop_map(|(width, cells)| {
+                    let rows = cells
+                        .chunks_exact(width)
+                        .map(|row| {
+                            serde_json::Value::Object(
+                                row.iter()
+                                    .enumerate()
+                                    .map(|(i, cell)| (format!("k{i}"), cell.clone()))
+                                    .collect(),
+                            )
+                        })
+                        .collect();
+                    serde_json::Value::Array(rows)
+                }),
+            ]
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn lossless_round_trip(val in arb_json()) {
+            let toon = encode(&val);
+            prop_assert_eq!(from_toon(&toon).unwrap(), val);
+        }
+    }
+}
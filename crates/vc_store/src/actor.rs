@@ -0,0 +1,216 @@
+//! Who a write is performed for
+//!
+//! Every frontend resolves one [`ActorContext`] per request (a CLI
+//! invocation, an MCP session, a web request) and passes it to the store's
+//! write helpers, which record its name on the rows they change and on the
+//! audit events they write. A consumer that does not name itself gets a
+//! stable fallback instead of an anonymous one:
+//!
+//! - CLI: `--actor`, else the OS user
+//! - MCP: `--actor`, else the client's `clientInfo.name`, else the OS user
+//! - web: the `X-VC-Actor` header, else the token name, else `local`; a
+//!   dashboard login session is always its OIDC subject
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AuditEvent, AuditEventType, AuditResult, QueryCaller};
+
+/// Longest actor name kept; longer names are cut
+const MAX_ACTOR_LEN: usize = 128;
+
+/// Where an actor's name came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorSource {
+    /// `--actor` or the `X-VC-Actor` header
+    Explicit,
+    /// The MCP client's `clientInfo.name`
+    ClientInfo,
+    /// The web API token's name
+    Token,
+    /// The OIDC subject of a dashboard login session
+    Session,
+    /// The user running the process
+    OsUser,
+    /// The daemon or another internal job
+    System,
+}
+
+/// The consumer a request or write is performed for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActorContext {
+    /// Recorded as `actor` on audit events and as `*_by` on changed rows
+    pub name: String,
+    /// Frontend the request came through
+    pub via: QueryCaller,
+    pub source: ActorSource,
+}
+
+impl ActorContext {
+    /// A CLI invocation: `--actor`, else the OS user
+    #[must_use]
+    pub fn cli(explicit: Option<&str>) -> Self {
+        Self::resolve(QueryCaller::Cli, [(explicit, ActorSource::Explicit)])
+            .unwrap_or_else(|| Self::os_user(QueryCaller::Cli))
+    }
+
+    /// An MCP session: `--actor`, else `clientInfo.name`, else the OS user
+    #[must_use]
+    pub fn mcp(explicit: Option<&str>, client_name: Option<&str>) -> Self {
+        Self::resolve(
+            QueryCaller::Mcp,
+            [
+                (explicit, ActorSource::Explicit),
+                (client_name, ActorSource::ClientInfo),
+            ],
+        )
+        .unwrap_or_else(|| Self::os_user(QueryCaller::Mcp))
+    }
+
+    /// A web request: `X-VC-Actor`, else the token name, else `local` (a
+    /// request let through by local bypass or with auth disabled)
+    #[must_use]
+    pub fn web(explicit: Option<&str>, token_name: Option<&str>) -> Self {
+        Self::resolve(
+            QueryCaller::Web,
+            [
+                (explicit, ActorSource::Explicit),
+                (token_name, ActorSource::Token),
+            ],
+        )
+        .unwrap_or_else(|| Self {
+            name: "local".to_string(),
+            via: QueryCaller::Web,
+            source: ActorSource::System,
+        })
+    }
+
+    /// A web request in a dashboard login session, named by the ID token's
+    /// subject; `X-VC-Actor` does not apply
+    #[must_use]
+    pub fn web_session(subject: &str) -> Self {
+        Self::resolve(QueryCaller::Web, [(Some(subject), ActorSource::Session)])
+            .unwrap_or_else(|| Self::web(None, None))
+    }
+
+    /// The daemon or an internal job acting under a fixed name
+    #[must_use]
+    pub fn system(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            via: QueryCaller::Daemon,
+            source: ActorSource::System,
+        }
+    }
+
+    /// An audit event performed by this actor
+    #[must_use]
+    pub fn audit_event(
+        &self,
+        event_type: AuditEventType,
+        action: impl Into<String>,
+        result: AuditResult,
+        details: serde_json::Value,
+    ) -> AuditEvent {
+        let mut event = AuditEvent::new(event_type, self.name.clone(), action, result, details);
+        event.via = Some(self.via);
+        event
+    }
+
+    /// The first usable name of `names`
+    fn resolve<const N: usize>(
+        via: QueryCaller,
+        names: [(Option<&str>, ActorSource); N],
+    ) -> Option<Self> {
+        names.into_iter().find_map(|(name, source)| {
+            Some(Self {
+                name: clean(name?)?,
+                via,
+                source,
+            })
+        })
+    }
+
+    fn os_user(via: QueryCaller) -> Self {
+        let name = ["USER", "LOGNAME", "USERNAME"]
+            .into_iter()
+            .find_map(|var| clean(&std::env::var(var).ok()?))
+            .unwrap_or_else(|| "local".to_string());
+        Self {
+            name,
+            via,
+            source: ActorSource::OsUser,
+        }
+    }
+}
+
+impl std::fmt::Display for ActorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Trimmed, control characters dropped, at most [`MAX_ACTOR_LEN`] characters;
+/// `None` when nothing is left
+fn clean(name: &str) -> Option<String> {
+    let name: String = name
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_ACTOR_LEN)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_name_wins_over_fallbacks() {
+        let actor = ActorContext::mcp(Some(" night-orchestrator "), Some("claude-desktop"));
+        assert_eq!(actor.name, "night-orchestrator");
+        assert_eq!(actor.source, ActorSource::Explicit);
+        assert_eq!(actor.via, QueryCaller::Mcp);
+
+        let actor = ActorContext::mcp(Some("  "), Some("claude-desktop"));
+        assert_eq!(actor.name, "claude-desktop");
+        assert_eq!(actor.source, ActorSource::ClientInfo);
+
+        let actor = ActorContext::web(None, Some("ci-token"));
+        assert_eq!(actor.name, "ci-token");
+        assert_eq!(actor.source, ActorSource::Token);
+
+        let actor = ActorContext::web_session("00u1a2b3c4");
+        assert_eq!(actor.name, "00u1a2b3c4");
+        assert_eq!(actor.source, ActorSource::Session);
+    }
+
+    #[test]
+    fn test_missing_identity_falls_back_to_a_stable_name() {
+        let cli = ActorContext::cli(None);
+        assert_eq!(cli.source, ActorSource::OsUser);
+        assert!(!cli.name.is_empty());
+        assert_ne!(cli.name, "unknown");
+        assert_eq!(ActorContext::mcp(None, None).name, cli.name);
+
+        let web = ActorContext::web(None, None);
+        assert_eq!(web.name, "local");
+        assert_eq!(web.via, QueryCaller::Web);
+    }
+
+    #[test]
+    fn test_audit_event_carries_actor_and_frontend() {
+        let event = ActorContext::cli(Some("alice")).audit_event(
+            AuditEventType::UserCommand,
+            "alert_bulk_ack",
+            AuditResult::Success,
+            serde_json::json!({}),
+        );
+        assert_eq!(event.actor, "alice");
+        assert_eq!(event.via, Some(QueryCaller::Cli));
+
+        let long = "x".repeat(500);
+        assert_eq!(ActorContext::cli(Some(&long)).name.len(), MAX_ACTOR_LEN);
+    }
+}
@@ -0,0 +1,332 @@
+//! Agent inventory
+//!
+//! The `agent_inventory` collector reports every agent it finds on a
+//! machine each cycle. Each report is a complete picture of that machine,
+//! so reconciling it is an upsert plus disappearance detection: agents seen
+//! again move `last_seen_at` forward, new ones get a row, and agents still
+//! open from an earlier cycle but missing from this one get `ended_at`.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::tables::AGENTS_INVENTORY;
+use crate::{StoreConnectionGuard, StoreError, VcStore, WriteOrigin};
+
+/// One agent found on a machine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentInventoryRecord {
+    pub machine_id: String,
+    /// `tmux:<pane_id>:<pid>` or `pid:<pid>`
+    pub agent_key: String,
+    pub agent_type: String,
+    /// `tmux` or `process`
+    pub source: String,
+    pub session_name: Option<String>,
+    /// `window.pane` index within the tmux session
+    pub pane: Option<String>,
+    pub pane_id: Option<String>,
+    pub pid: Option<i64>,
+    pub working_dir: Option<String>,
+    pub command: Option<String>,
+    pub started_at: Option<String>,
+    /// Last output in the agent's tmux window; `None` outside tmux
+    pub last_activity_at: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    /// When a cycle first missed the agent; `None` while it runs
+    pub ended_at: Option<String>,
+}
+
+const COLUMNS: &str = "machine_id, agent_key, agent_type, source, session_name, pane, pane_id, \
+                       pid, working_dir, command, started_at, last_activity_at, first_seen_at, \
+                       last_seen_at, ended_at";
+
+fn text(row: &serde_json::Value, key: &str) -> Option<String> {
+    match &row[key] {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// Reconcile one machine's report into `agents_inventory`, returning the
+/// number of agents written. Runs on a connection the caller holds, so the
+/// collect and ingest paths share it.
+pub(crate) fn reconcile(
+    conn: &StoreConnectionGuard<'_>,
+    machine_id: &str,
+    observed_at: &str,
+    rows: &[serde_json::Value],
+) -> Result<usize, StoreError> {
+    let mut seen = Vec::with_capacity(rows.len());
+    for row in rows {
+        let Some(agent_key) = text(row, "agent_key") else {
+            continue;
+        };
+        let agent_type = text(row, "agent_type").unwrap_or_else(|| "unknown".to_string());
+        let source = text(row, "source").unwrap_or_else(|| "process".to_string());
+        let pid = row["pid"].as_i64();
+        let updated = conn.execute(
+            "UPDATE agents_inventory SET last_seen_at = ?, last_activity_at = ?, \
+             working_dir = COALESCE(?, working_dir), command = COALESCE(?, command) \
+             WHERE machine_id = ? AND agent_key = ? AND ended_at IS NULL",
+            duckdb::params![
+                observed_at,
+                text(row, "last_activity_at"),
+                text(row, "working_dir"),
+                text(row, "command"),
+                machine_id,
+                agent_key,
+            ],
+        )?;
+        if updated == 0 {
+            conn.execute(
+                &format!(
+                    "INSERT INTO agents_inventory ({COLUMNS}) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NULL)"
+                ),
+                duckdb::params![
+                    machine_id,
+                    agent_key,
+                    agent_type,
+                    source,
+                    text(row, "session_name"),
+                    text(row, "pane"),
+                    text(row, "pane_id"),
+                    pid,
+                    text(row, "working_dir"),
+                    text(row, "command"),
+                    text(row, "started_at"),
+                    text(row, "last_activity_at"),
+                    observed_at,
+                    observed_at,
+                ],
+            )?;
+        }
+        seen.push(agent_key);
+    }
+
+    // Everything still open that this report did not mention has gone
+    let mut open_stmt = conn.prepare(
+        "SELECT agent_key FROM agents_inventory WHERE machine_id = ? AND ended_at IS NULL",
+    )?;
+    let open: Vec<String> = open_stmt
+        .query_map([machine_id], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for agent_key in open.iter().filter(|key| !seen.contains(key)) {
+        conn.execute(
+            "UPDATE agents_inventory SET ended_at = ? \
+             WHERE machine_id = ? AND agent_key = ? AND ended_at IS NULL",
+            [observed_at, machine_id, agent_key.as_str()],
+        )?;
+    }
+    conn.changes
+        .note(AGENTS_INVENTORY, u64::try_from(rows.len()).unwrap_or(0));
+    Ok(seen.len())
+}
+
+impl VcStore {
+    /// Reconcile one `agent_inventory` collection of `machine_id` observed
+    /// at `observed_at`. `rows` is every agent found, so an empty slice
+    /// ends every agent still open on the machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a write fails; the reconciliation is rolled
+    /// back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_agent_inventory(
+        &self,
+        machine_id: &str,
+        observed_at: &str,
+        rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("BEGIN TRANSACTION", [])?;
+        match reconcile(&conn, machine_id, observed_at, rows) {
+            Ok(count) => {
+                conn.execute("COMMIT", [])?;
+                Ok(count)
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// Persist one batch of `collector`'s rows: agent inventory reports are
+    /// reconciled, every other table is appended to under the write limits
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the write fails.
+    pub fn write_collected_batch(
+        &self,
+        machine_id: &str,
+        collector: &str,
+        table: &str,
+        rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError> {
+        if table == AGENTS_INVENTORY {
+            let observed_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+            self.record_agent_inventory(machine_id, &observed_at, rows)
+        } else {
+            self.insert_json_batch_from(table, rows, WriteOrigin::collector(machine_id, collector))
+        }
+    }
+
+    /// Agents in the inventory, for one machine or the whole fleet, ordered
+    /// by machine and start. `active_only` leaves out ended agents.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_agents_inventory(
+        &self,
+        machine_id: Option<&str>,
+        active_only: bool,
+    ) -> Result<Vec<AgentInventoryRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut filters = Vec::new();
+        if machine_id.is_some() {
+            filters.push("machine_id = ?");
+        }
+        if active_only {
+            filters.push("ended_at IS NULL");
+        }
+        let filter = if filters.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", filters.join(" AND "))
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM agents_inventory {filter} \
+             ORDER BY machine_id, COALESCE(started_at, first_seen_at), agent_key"
+        ))?;
+        let map_row = |row: &duckdb::Row<'_>| -> duckdb::Result<AgentInventoryRecord> {
+            Ok(AgentInventoryRecord {
+                machine_id: row.get(0)?,
+                agent_key: row.get(1)?,
+                agent_type: row.get(2)?,
+                source: row.get(3)?,
+                session_name: row.get(4)?,
+                pane: row.get(5)?,
+                pane_id: row.get(6)?,
+                pid: row.get(7)?,
+                working_dir: row.get(8)?,
+                command: row.get(9)?,
+                started_at: row.get(10)?,
+                last_activity_at: row.get(11)?,
+                first_seen_at: row.get(12)?,
+                last_seen_at: row.get(13)?,
+                ended_at: row.get(14)?,
+            })
+        };
+        let rows = match machine_id {
+            Some(id) => stmt.query_map([id], map_row)?,
+            None => stmt.query_map([], map_row)?,
+        };
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(key: &str, pid: i64) -> serde_json::Value {
+        serde_json::json!({
+            "machine_id": "orko",
+            "agent_key": key,
+            "agent_type": "claude",
+            "source": "tmux",
+            "session_name": "work",
+            "pane": "0.1",
+            "pane_id": "%3",
+            "pid": pid,
+            "working_dir": "/data/projects/vc",
+            "command": "claude --continue",
+            "started_at": "2026-10-16T09:00:00Z",
+            "last_activity_at": "2026-10-16T09:59:00Z",
+        })
+    }
+
+    #[test]
+    fn test_reconcile_upserts_and_ends_missing_agents() {
+        let store = VcStore::open_memory().unwrap();
+        let a = agent("tmux:%3:100", 100);
+        let b = agent("tmux:%4:200", 200);
+        assert_eq!(
+            store
+                .record_agent_inventory("orko", "2026-10-16T10:00:00Z", &[a.clone(), b])
+                .unwrap(),
+            2
+        );
+        store
+            .record_agent_inventory("mac", "2026-10-16T10:00:00Z", &[agent("pid:7", 7)])
+            .unwrap();
+
+        // The next cycle sees only `a`: it is updated in place, `b` ends
+        store
+            .record_agent_inventory("orko", "2026-10-16T10:05:00Z", &[a])
+            .unwrap();
+        let all = store.list_agents_inventory(Some("orko"), false).unwrap();
+        assert_eq!(all.len(), 2);
+        let active = store.list_agents_inventory(Some("orko"), true).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].agent_key, "tmux:%3:100");
+        assert_eq!(active[0].first_seen_at, "2026-10-16T10:00:00Z");
+        assert_eq!(active[0].last_seen_at, "2026-10-16T10:05:00Z");
+        let ended = all.iter().find(|r| r.agent_key == "tmux:%4:200").unwrap();
+        assert_eq!(ended.ended_at.as_deref(), Some("2026-10-16T10:05:00Z"));
+
+        // Another machine's agents are untouched, and an empty report ends
+        // everything still open
+        assert_eq!(
+            store
+                .list_agents_inventory(Some("mac"), true)
+                .unwrap()
+                .len(),
+            1
+        );
+        store
+            .write_collected_batch("orko", "agent_inventory", AGENTS_INVENTORY, &[])
+            .unwrap();
+        assert!(
+            store
+                .list_agents_inventory(Some("orko"), true)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_agent_seen_again_after_ending_gets_a_new_row() {
+        let store = VcStore::open_memory().unwrap();
+        let a = agent("pid:42", 42);
+        store
+            .record_agent_inventory("orko", "2026-10-16T10:00:00Z", &[a.clone()])
+            .unwrap();
+        store
+            .record_agent_inventory("orko", "2026-10-16T10:05:00Z", &[])
+            .unwrap();
+        store
+            .record_agent_inventory("orko", "2026-10-16T10:10:00Z", &[a])
+            .unwrap();
+        let rows = store.list_agents_inventory(None, false).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows.iter().filter(|r| r.ended_at.is_none()).count(),
+            1,
+            "{rows:?}"
+        );
+    }
+}
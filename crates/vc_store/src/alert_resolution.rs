@@ -0,0 +1,277 @@
+//! Alert resolution attribution
+//!
+//! Every path that resolves an alert says what resolved it: the condition
+//! clearing, a guardian run, or a person. A guardian run claims the alerts
+//! it acts on before it starts ([`VcStore::claim_alerts_for_guardian_run`]),
+//! and resolving through the run
+//! ([`VcStore::resolve_guardian_run_alerts`]) attributes exactly those. So
+//! "how much does automation resolve" is a count, not a guess.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// What resolved an alert
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionKind {
+    /// The rule's condition cleared on its own
+    ConditionCleared,
+    /// A guardian run that claimed the alert
+    GuardianRun,
+    /// A person, with `vc alert resolve` or the web API
+    Manual,
+    /// A silence on the alert ran out. vc has no silences yet, so nothing
+    /// records this.
+    SilenceExpired,
+    /// Resolved before attribution was recorded
+    Unknown,
+}
+
+impl ResolutionKind {
+    pub const ALL: [Self; 5] = [
+        Self::ConditionCleared,
+        Self::GuardianRun,
+        Self::Manual,
+        Self::SilenceExpired,
+        Self::Unknown,
+    ];
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::ConditionCleared => "condition_cleared",
+            Self::GuardianRun => "guardian_run",
+            Self::Manual => "manual",
+            Self::SilenceExpired => "silence_expired",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for ResolutionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ResolutionKind {
+    type Err = String;
+
+    /// Full names, or their first word (`condition`, `guardian`, `silence`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "condition_cleared" | "condition" => Ok(Self::ConditionCleared),
+            "guardian_run" | "guardian" => Ok(Self::GuardianRun),
+            "manual" => Ok(Self::Manual),
+            "silence_expired" | "silence" => Ok(Self::SilenceExpired),
+            "unknown" => Ok(Self::Unknown),
+            other => Err(format!(
+                "unknown resolver '{other}'; expected condition, guardian, manual, silence or unknown"
+            )),
+        }
+    }
+}
+
+/// Alerts of one rule resolved one way
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolutionSourceCount {
+    pub rule_id: String,
+    pub resolution_kind: String,
+    pub resolved: i64,
+}
+
+impl VcStore {
+    /// Mark the open alerts in `alert_ids` as acted on by guardian run
+    /// `run_id`. Alerts already claimed by another run are left to it.
+    /// Returns how many were claimed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn claim_alerts_for_guardian_run(
+        &self,
+        run_id: i64,
+        alert_ids: &[i64],
+    ) -> Result<usize, StoreError> {
+        if alert_ids.is_empty() {
+            return Ok(0);
+        }
+        let ids: Vec<String> = alert_ids.iter().map(ToString::to_string).collect();
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            &format!(
+                "UPDATE alert_history SET guardian_run_id = ? \
+                 WHERE id IN ({}) AND resolved_at IS NULL \
+                 AND (guardian_run_id IS NULL OR guardian_run_id = ?)",
+                ids.join(", ")
+            ),
+            duckdb::params![run_id, run_id],
+        )?;
+        Ok(affected)
+    }
+
+    /// Resolve the open alerts guardian run `run_id` claimed, attributing
+    /// them to it. Returns how many were resolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn resolve_guardian_run_alerts(&self, run_id: i64) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE alert_history SET resolved_at = ?, resolution_kind = 'guardian_run' \
+             WHERE guardian_run_id = ? AND resolved_at IS NULL",
+            duckdb::params![
+                Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+                run_id
+            ],
+        )?;
+        Ok(affected)
+    }
+
+    /// Resolved alerts per rule and resolver, optionally only those resolved
+    /// since a time. Largest counts first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn alert_resolution_sources(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ResolutionSourceCount>, StoreError> {
+        let since_filter = since
+            .map(|since| {
+                format!(
+                    "AND CAST(resolved_at AS TIMESTAMP) >= CAST('{}' AS TIMESTAMP)",
+                    since.format("%Y-%m-%d %H:%M:%S")
+                )
+            })
+            .unwrap_or_default();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT COALESCE(rule_id, 'unknown'), COALESCE(resolution_kind, 'unknown'), COUNT(*) \
+             FROM alert_history WHERE resolved_at IS NOT NULL {since_filter} \
+             GROUP BY 1, 2 ORDER BY 3 DESC, 1, 2"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ResolutionSourceCount {
+                rule_id: row.get(0)?,
+                resolution_kind: row.get(1)?,
+                resolved: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorContext, AlertFilter, BulkAlertAction};
+
+    fn fire(store: &VcStore, id: i64, rule_id: &str, machine_id: &str) {
+        store
+            .execute_batch(&format!(
+                "INSERT INTO alert_history (id, rule_id, fired_at, severity, title, machine_id) \
+                 VALUES ({id}, '{rule_id}', '2026-10-16T12:00:00Z', 'warning', 't', '{machine_id}')"
+            ))
+            .unwrap();
+    }
+
+    fn kinds(store: &VcStore) -> Vec<(i64, Option<String>, Option<String>)> {
+        store
+            .query_json("SELECT id, resolution_kind, resolved_by FROM alert_history ORDER BY id")
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                (
+                    row["id"].as_i64().unwrap(),
+                    row["resolution_kind"].as_str().map(str::to_string),
+                    row["resolved_by"].as_str().map(str::to_string),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_resolve_path_records_its_resolver() {
+        let store = VcStore::open_memory().unwrap();
+        fire(&store, 1, "disk", "orko");
+        fire(&store, 2, "clock-skew", "orko");
+        fire(&store, 3, "agent-stuck", "orko");
+        fire(&store, 4, "agent-stuck", "sydney");
+        fire(&store, 5, "memory-critical", "orko");
+
+        store.resolve_open_alerts("disk").unwrap();
+        store
+            .resolve_open_machine_alerts("clock-skew", "orko")
+            .unwrap();
+
+        assert_eq!(store.claim_alerts_for_guardian_run(7, &[3, 1]).unwrap(), 1);
+        // Another run cannot take an alert already claimed
+        assert_eq!(store.claim_alerts_for_guardian_run(8, &[3]).unwrap(), 0);
+        assert_eq!(store.resolve_guardian_run_alerts(7).unwrap(), 1);
+
+        let filter = AlertFilter {
+            ids: vec![4],
+            ..AlertFilter::default()
+        };
+        store
+            .bulk_update_alerts(
+                BulkAlertAction::Resolve,
+                &filter,
+                &ActorContext::cli(Some("alice")),
+            )
+            .unwrap();
+
+        assert_eq!(
+            kinds(&store),
+            vec![
+                (1, Some("condition_cleared".to_string()), None),
+                (2, Some("condition_cleared".to_string()), None),
+                (3, Some("guardian_run".to_string()), None),
+                (4, Some("manual".to_string()), Some("alice".to_string())),
+                (5, None, None),
+            ]
+        );
+
+        let sources = store.alert_resolution_sources(None).unwrap();
+        assert_eq!(sources.len(), 4);
+        let stuck: Vec<&str> = sources
+            .iter()
+            .filter(|s| s.rule_id == "agent-stuck")
+            .map(|s| s.resolution_kind.as_str())
+            .collect();
+        assert_eq!(stuck, vec!["guardian_run", "manual"]);
+    }
+
+    #[test]
+    fn test_resolution_kind_parses_short_names() {
+        assert_eq!(
+            "guardian".parse::<ResolutionKind>(),
+            Ok(ResolutionKind::GuardianRun)
+        );
+        assert_eq!(
+            "condition-cleared".parse::<ResolutionKind>(),
+            Ok(ResolutionKind::ConditionCleared)
+        );
+        assert!("robot".parse::<ResolutionKind>().is_err());
+        for kind in ResolutionKind::ALL {
+            assert_eq!(kind.as_str().parse::<ResolutionKind>(), Ok(kind));
+        }
+    }
+}
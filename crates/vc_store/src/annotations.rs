@@ -0,0 +1,570 @@
+//! Annotations
+//!
+//! An annotation marks an outside event, typically a deploy, at a point in
+//! time so it can be read next to the alerts it may explain: "alerts spiked
+//! at 14:02" next to "release 1.42 deployed at 14:00". CI and deploy systems
+//! post them through `POST /api/annotations`, people through `vc annotate`,
+//! and the store adds its own when a maintenance window opens or closes and
+//! when a new config is loaded.
+//!
+//! An annotation applies to the fleet, one machine or every machine with a
+//! tag. Rows are append-only: there is no update or delete, and each one
+//! recorded through [`VcStore::record_annotation`] leaves an audit event.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ActorContext, AuditEventType, AuditResult, StoreConnectionGuard, StoreError, VcStore,
+    escape_sql_literal,
+};
+
+/// Longest kind name accepted
+const MAX_KIND_LEN: usize = 32;
+
+/// Annotations listed when no limit is given
+pub const DEFAULT_ANNOTATION_LIMIT: usize = 100;
+
+/// Kind the store records for maintenance windows opening and closing
+pub const MAINTENANCE_KIND: &str = "maintenance";
+
+/// Kind the store records for a newly loaded config
+pub const CONFIG_KIND: &str = "config";
+
+/// Source of the annotations the store records itself
+pub const INTERNAL_SOURCE: &str = "vc";
+
+/// What an annotation applies to. Written as `fleet`, `machine:<id>` or
+/// `tag:<tag>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum AnnotationScope {
+    Fleet,
+    Machine(String),
+    Tag(String),
+}
+
+impl AnnotationScope {
+    /// The machine a machine-scoped annotation names
+    #[must_use]
+    pub fn machine_id(&self) -> Option<&str> {
+        match self {
+            Self::Machine(machine) => Some(machine),
+            Self::Fleet | Self::Tag(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AnnotationScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fleet => f.write_str("fleet"),
+            Self::Machine(machine) => write!(f, "machine:{machine}"),
+            Self::Tag(tag) => write!(f, "tag:{tag}"),
+        }
+    }
+}
+
+impl std::str::FromStr for AnnotationScope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value == "fleet" {
+            return Ok(Self::Fleet);
+        }
+        match value.split_once(':') {
+            Some(("machine", machine)) if !machine.trim().is_empty() => {
+                Ok(Self::Machine(machine.trim().to_string()))
+            }
+            Some(("tag", tag)) if !tag.trim().is_empty() => Ok(Self::Tag(tag.trim().to_string())),
+            _ => Err(format!(
+                "annotation scope must be fleet, machine:<id> or tag:<tag>: {value}"
+            )),
+        }
+    }
+}
+
+impl From<AnnotationScope> for String {
+    fn from(scope: AnnotationScope) -> Self {
+        scope.to_string()
+    }
+}
+
+impl TryFrom<String> for AnnotationScope {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// A recorded annotation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: i64,
+    /// e.g. `deploy`, `release`, `maintenance`, `config`
+    pub kind: String,
+    pub scope: AnnotationScope,
+    /// When the event happened (RFC 3339, UTC)
+    pub ts: String,
+    pub message: String,
+    /// What reported it, e.g. `ci`, `argocd`, `cli`, or `vc` for the store
+    pub source: String,
+    pub created_by: Option<String>,
+    pub created_at: String,
+}
+
+impl Annotation {
+    /// `deploy on machine:orko: release 1.42 (ci)`
+    #[must_use]
+    pub fn summary(&self) -> String {
+        format!(
+            "{} on {}: {} ({})",
+            self.kind, self.scope, self.message, self.source
+        )
+    }
+}
+
+/// An annotation to record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAnnotation {
+    pub kind: String,
+    pub scope: AnnotationScope,
+    /// `None` is now
+    pub ts: Option<DateTime<Utc>>,
+    pub message: String,
+    pub source: String,
+}
+
+impl NewAnnotation {
+    fn validate(&self) -> Result<(), StoreError> {
+        let kind_ok = !self.kind.is_empty()
+            && self.kind.len() <= MAX_KIND_LEN
+            && self
+                .kind
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+        if !kind_ok {
+            return Err(StoreError::QueryError(format!(
+                "annotation kind must be 1-{MAX_KIND_LEN} lowercase letters, digits, '_' or '-': {}",
+                self.kind
+            )));
+        }
+        if self.message.trim().is_empty() {
+            return Err(StoreError::QueryError(
+                "an annotation needs a message".to_string(),
+            ));
+        }
+        if self.source.trim().is_empty() {
+            return Err(StoreError::QueryError(
+                "an annotation needs a source".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Which annotations [`VcStore::list_annotations`] returns
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnnotationFilter {
+    /// Kinds to include; empty means all
+    pub kinds: Vec<String>,
+    /// Only at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only at or before this time
+    pub until: Option<DateTime<Utc>>,
+    /// Only those that apply to this machine: its own, its tags' and the
+    /// fleet's
+    pub machine: Option<String>,
+    /// 0 is [`DEFAULT_ANNOTATION_LIMIT`]
+    pub limit: usize,
+}
+
+const COLUMNS: &str = "id, kind, scope, ts, message, source, created_by, created_at";
+
+fn map_annotation(row: &duckdb::Row<'_>) -> duckdb::Result<Annotation> {
+    let scope: String = row.get(2)?;
+    Ok(Annotation {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        scope: scope.parse().unwrap_or(AnnotationScope::Fleet),
+        ts: row.get(3)?,
+        message: row.get(4)?,
+        source: row.get(5)?,
+        created_by: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// How annotation times are written, so they compare as text
+fn annotation_ts(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Insert `new` over an open connection. Used by
+/// [`VcStore::record_annotation`] and by the writes that annotate
+/// themselves while holding the connection.
+pub(crate) fn insert_annotation(
+    conn: &StoreConnectionGuard<'_>,
+    new: &NewAnnotation,
+    created_by: Option<&str>,
+) -> Result<Annotation, StoreError> {
+    new.validate()?;
+    let id: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) + 1 FROM annotations",
+        [],
+        |row| row.get(0),
+    )?;
+    let now = Utc::now();
+    let annotation = Annotation {
+        id,
+        kind: new.kind.clone(),
+        scope: new.scope.clone(),
+        ts: annotation_ts(new.ts.unwrap_or(now)),
+        message: new.message.trim().to_string(),
+        source: new.source.trim().to_string(),
+        created_by: created_by.map(str::to_string),
+        created_at: annotation_ts(now),
+    };
+    conn.execute(
+        &format!("INSERT INTO annotations ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"),
+        duckdb::params![
+            annotation.id,
+            annotation.kind,
+            annotation.scope.to_string(),
+            annotation.ts,
+            annotation.message,
+            annotation.source,
+            annotation.created_by,
+            annotation.created_at,
+        ],
+    )?;
+    Ok(annotation)
+}
+
+impl VcStore {
+    /// Record an annotation for `actor`, with an audit event
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] for an invalid kind or an empty
+    /// message or source, or [`StoreError`] if a write fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_annotation(
+        &self,
+        new: &NewAnnotation,
+        actor: &ActorContext,
+    ) -> Result<Annotation, StoreError> {
+        let annotation = {
+            let conn = self.conn.lock().unwrap();
+            insert_annotation(&conn, new, Some(&actor.name))?
+        };
+        let event = actor.audit_event(
+            AuditEventType::UserCommand,
+            "annotation_add",
+            AuditResult::Success,
+            serde_json::json!({
+                "annotation_id": annotation.id,
+                "kind": annotation.kind,
+                "scope": annotation.scope,
+                "ts": annotation.ts,
+                "source": annotation.source,
+            }),
+        );
+        self.insert_audit_event(&match annotation.scope.machine_id() {
+            Some(machine) => event.with_machine_id(machine),
+            None => event,
+        })?;
+        Ok(annotation)
+    }
+
+    /// Annotations matching `filter`, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_annotations(
+        &self,
+        filter: &AnnotationFilter,
+    ) -> Result<Vec<Annotation>, StoreError> {
+        let mut clauses = Vec::new();
+        if !filter.kinds.is_empty() {
+            let kinds: Vec<String> = filter
+                .kinds
+                .iter()
+                .map(|kind| format!("'{}'", escape_sql_literal(kind)))
+                .collect();
+            clauses.push(format!("kind IN ({})", kinds.join(", ")));
+        }
+        if let Some(since) = filter.since {
+            clauses.push(format!("ts >= '{}'", annotation_ts(since)));
+        }
+        if let Some(until) = filter.until {
+            clauses.push(format!("ts <= '{}'", annotation_ts(until)));
+        }
+        if let Some(machine) = &filter.machine {
+            let scopes: Vec<String> = self
+                .annotation_scopes(machine)?
+                .iter()
+                .map(|scope| format!("'{}'", escape_sql_literal(scope)))
+                .collect();
+            clauses.push(format!("scope IN ({})", scopes.join(", ")));
+        }
+        let where_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let limit = if filter.limit == 0 {
+            DEFAULT_ANNOTATION_LIMIT
+        } else {
+            filter.limit.min(10_000)
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM annotations {where_sql} \
+             ORDER BY ts DESC, id DESC LIMIT {limit}"
+        ))?;
+        let rows = stmt.query_map([], map_annotation)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// The scopes whose annotations apply to `machine_id`, as written in
+    /// the `scope` column: the fleet, the machine and each of its tags
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the machine's tags cannot be read.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn annotation_scopes(&self, machine_id: &str) -> Result<Vec<String>, StoreError> {
+        let tags: Option<String> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT tags FROM machines WHERE machine_id = ?")?;
+            let mut rows = stmt.query_map([machine_id], |row| row.get(0))?;
+            rows.next().transpose()?.flatten()
+        };
+        let tags: Vec<String> = tags
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let mut scopes = vec![
+            AnnotationScope::Fleet.to_string(),
+            AnnotationScope::Machine(machine_id.to_string()).to_string(),
+        ];
+        scopes.extend(
+            tags.into_iter()
+                .map(|tag| AnnotationScope::Tag(tag).to_string()),
+        );
+        Ok(scopes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ts: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(ts)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn deploy(scope: AnnotationScope, ts: &str, message: &str) -> NewAnnotation {
+        NewAnnotation {
+            kind: "deploy".to_string(),
+            scope,
+            ts: Some(at(ts)),
+            message: message.to_string(),
+            source: "ci".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_scope_round_trips_as_text() {
+        for raw in ["fleet", "machine:orko", "tag:gpu"] {
+            let scope: AnnotationScope = raw.parse().unwrap();
+            assert_eq!(scope.to_string(), raw);
+        }
+        assert_eq!(
+            serde_json::to_value(AnnotationScope::Machine("orko".to_string())).unwrap(),
+            "machine:orko"
+        );
+        assert!("machine:".parse::<AnnotationScope>().is_err());
+        assert!("rack:3".parse::<AnnotationScope>().is_err());
+    }
+
+    #[test]
+    fn test_record_audits_and_lists_by_kind_window_and_machine() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname, tags) VALUES \
+                   ('orko', 'orko.lan', '[\"gpu\"]'), ('bender', 'bender.lan', '[]');",
+            )
+            .unwrap();
+        let actor = ActorContext::cli(Some("ci-bot"));
+        let first = store
+            .record_annotation(
+                &deploy(
+                    AnnotationScope::Machine("orko".to_string()),
+                    "2026-10-16T14:00:00Z",
+                    "release 1.42",
+                ),
+                &actor,
+            )
+            .unwrap();
+        assert_eq!(first.ts, "2026-10-16T14:00:00.000000Z");
+        assert_eq!(first.created_by.as_deref(), Some("ci-bot"));
+        store
+            .record_annotation(
+                &deploy(
+                    AnnotationScope::Tag("gpu".to_string()),
+                    "2026-10-16T15:00:00Z",
+                    "driver 550",
+                ),
+                &actor,
+            )
+            .unwrap();
+        store
+            .record_annotation(
+                &deploy(
+                    AnnotationScope::Machine("bender".to_string()),
+                    "2026-10-16T16:00:00Z",
+                    "release 1.43",
+                ),
+                &actor,
+            )
+            .unwrap();
+        let mut note = deploy(AnnotationScope::Fleet, "2026-10-16T17:00:00Z", "freeze");
+        note.kind = "note".to_string();
+        store.record_annotation(&note, &actor).unwrap();
+
+        let audited: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM audit_events WHERE action = 'annotation_add'")
+            .unwrap();
+        assert_eq!(audited, 4);
+
+        let messages = |filter: &AnnotationFilter| -> Vec<String> {
+            store
+                .list_annotations(filter)
+                .unwrap()
+                .into_iter()
+                .map(|a| a.message)
+                .collect()
+        };
+        assert_eq!(
+            messages(&AnnotationFilter::default()),
+            ["freeze", "release 1.43", "driver 550", "release 1.42"]
+        );
+        let orko = AnnotationFilter {
+            machine: Some("orko".to_string()),
+            ..AnnotationFilter::default()
+        };
+        assert_eq!(messages(&orko), ["freeze", "driver 550", "release 1.42"]);
+        let deploys_in_window = AnnotationFilter {
+            kinds: vec!["deploy".to_string()],
+            since: Some(at("2026-10-16T14:30:00Z")),
+            until: Some(at("2026-10-16T16:00:00Z")),
+            ..AnnotationFilter::default()
+        };
+        assert_eq!(messages(&deploys_in_window), ["release 1.43", "driver 550"]);
+    }
+
+    #[test]
+    fn test_incident_timeline_attaches_annotations_in_its_window() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO incidents (incident_id, title, severity, status, started_at, ended_at) VALUES \
+                   ('inc-1', 'Build fleet degraded', 'critical', 'closed', \
+                    '2026-10-16 14:01:00', '2026-10-16 15:00:00'); \
+                 INSERT INTO incident_timeline_events (id, incident_id, ts, event_type, source, description) VALUES \
+                   (1, 'inc-1', '2026-10-16 14:10:00', 'note', 'cli', 'rolled back');",
+            )
+            .unwrap();
+        let actor = ActorContext::cli(Some("ci-bot"));
+        for (ts, message) in [
+            ("2026-10-16T14:00:00Z", "before"),
+            ("2026-10-16T14:05:00Z", "release 1.42"),
+            ("2026-10-16T15:30:00Z", "after"),
+        ] {
+            store
+                .record_annotation(&deploy(AnnotationScope::Fleet, ts, message), &actor)
+                .unwrap();
+        }
+
+        let timeline = store.get_incident_timeline("inc-1").unwrap();
+        let described: Vec<&str> = timeline
+            .iter()
+            .filter_map(|e| e["description"].as_str())
+            .collect();
+        assert_eq!(described, ["deploy on fleet: release 1.42", "rolled back"]);
+        assert_eq!(timeline[0]["event_type"], "annotation");
+        let details: serde_json::Value =
+            serde_json::from_str(timeline[0]["details_json"].as_str().unwrap()).unwrap();
+        assert_eq!(details["annotation_id"], 2);
+    }
+
+    #[test]
+    fn test_maintenance_windows_annotate_themselves() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO machines (machine_id, hostname) VALUES ('orko', 'orko.lan');",
+            )
+            .unwrap();
+        let actor = ActorContext::cli(Some("ops"));
+        store
+            .start_maintenance("orko", Some("disk swap"), None, &actor)
+            .unwrap();
+        // Updating the open window adds nothing
+        store
+            .start_maintenance("orko", Some("disk swap, take 2"), None, &actor)
+            .unwrap();
+        store.end_maintenance("orko", &actor).unwrap();
+
+        let filter = AnnotationFilter {
+            kinds: vec![MAINTENANCE_KIND.to_string()],
+            machine: Some("orko".to_string()),
+            ..AnnotationFilter::default()
+        };
+        let messages: Vec<String> = store
+            .list_annotations(&filter)
+            .unwrap()
+            .into_iter()
+            .map(|a| a.message)
+            .collect();
+        assert_eq!(
+            messages,
+            ["maintenance ended", "maintenance started: disk swap"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_annotations_are_rejected() {
+        let store = VcStore::open_memory().unwrap();
+        let actor = ActorContext::cli(Some("ops"));
+        let mut bad = deploy(AnnotationScope::Fleet, "2026-10-16T14:00:00Z", "x");
+        bad.kind = "Deploy!".to_string();
+        assert!(store.record_annotation(&bad, &actor).is_err());
+        let empty = deploy(AnnotationScope::Fleet, "2026-10-16T14:00:00Z", "  ");
+        assert!(store.record_annotation(&empty, &actor).is_err());
+        assert!(
+            store
+                .list_annotations(&AnnotationFilter::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
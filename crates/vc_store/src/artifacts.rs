@@ -0,0 +1,922 @@
+//! Offloaded artifacts
+//!
+//! Old transcript bodies move out of `agent_sessions.raw_json` into a
+//! directory or an S3-compatible bucket configured under
+//! `[storage.artifacts]`. Each leaves an `artifact_pointers` row with the
+//! body's location, size and SHA-256. Reading a session whose transcript was
+//! offloaded fetches it back through the [`ArtifactStore`] and checks the
+//! hash; an unreachable backend is an error, never an empty transcript.
+//!
+//! S3 requests go through `curl --aws-sigv4` (curl 7.75 or later), with the
+//! credentials passed in a config on stdin rather than on the command line.
+
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use vc_config::ArtifactStorageConfig;
+
+use crate::{StoreError, VcStore};
+
+/// Pointer kind of an offloaded `agent_sessions.raw_json`
+pub const TRANSCRIPT_KIND: &str = "transcript";
+
+/// Somewhere artifact bodies are written to and read back from
+pub trait ArtifactBackend: Send + Sync {
+    /// Location recorded in the pointer of the object stored under `key`
+    fn location(&self, key: &str) -> String;
+
+    /// Key of `location`, if it lives in this backend
+    fn key_of<'a>(&self, location: &'a str) -> Option<&'a str>;
+
+    /// Store `body` under `key`, replacing any previous object
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if the backend rejects the write or
+    /// cannot be reached.
+    fn put(&self, key: &str, body: &[u8]) -> Result<(), StoreError>;
+
+    /// Read the object stored under `key`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if the object is missing or the
+    /// backend cannot be reached.
+    fn get(&self, key: &str) -> Result<Vec<u8>, StoreError>;
+
+    /// Size of the object under `key`, `None` when there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if the backend cannot be reached.
+    fn size(&self, key: &str) -> Result<Option<u64>, StoreError>;
+
+    /// Delete the object under `key`; a missing object is not an error
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if the backend cannot be reached.
+    fn delete(&self, key: &str) -> Result<(), StoreError>;
+}
+
+/// Artifacts as files under a local or mounted directory
+pub struct DirBackend {
+    root: PathBuf,
+}
+
+impl DirBackend {
+    #[must_use]
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+impl ArtifactBackend for DirBackend {
+    fn location(&self, key: &str) -> String {
+        format!("file://{}", self.path(key).display())
+    }
+
+    fn key_of<'a>(&self, location: &'a str) -> Option<&'a str> {
+        let path = Path::new(location.strip_prefix("file://")?);
+        path.strip_prefix(&self.root).ok()?.to_str()
+    }
+
+    fn put(&self, key: &str, body: &[u8]) -> Result<(), StoreError> {
+        let path = self.path(key);
+        let io_error = |e: std::io::Error| artifact_error(&path, &e);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        // Write then rename, so a reader never sees half a transcript
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, body).map_err(io_error)?;
+        std::fs::rename(&partial, &path).map_err(io_error)
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, StoreError> {
+        let path = self.path(key);
+        std::fs::read(&path).map_err(|e| artifact_error(&path, &e))
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>, StoreError> {
+        let path = self.path(key);
+        match std::fs::metadata(&path) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(artifact_error(&path, &e)),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StoreError> {
+        let path = self.path(key);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(artifact_error(&path, &e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn artifact_error(path: &Path, e: &std::io::Error) -> StoreError {
+    StoreError::Artifact(format!("{}: {e}", path.display()))
+}
+
+/// Artifacts as objects in an S3-compatible bucket, addressed path-style
+pub struct S3Backend {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    timeout: Duration,
+}
+
+/// Status and body of one S3 request
+struct S3Response {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl S3Backend {
+    #[must_use]
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: String,
+        secret_key: String,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key,
+            secret_key,
+            timeout,
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}/{key}", self.endpoint, self.bucket)
+    }
+
+    /// Run one signed request; `options` are extra curl config lines
+    fn request(&self, key: &str, options: &[String]) -> Result<S3Response, StoreError> {
+        let url = self.url(key);
+        let mut config = format!(
+            "url = {}\naws-sigv4 = {}\nuser = {}\nmax-time = {}\nsilent\nshow-error\n\
+             write-out = \"\\n%{{http_code}}\"\n",
+            curlrc_quote(&url),
+            curlrc_quote(&format!("aws:amz:{}:s3", self.region)),
+            curlrc_quote(&format!("{}:{}", self.access_key, self.secret_key)),
+            self.timeout.as_secs().max(1),
+        );
+        for option in options {
+            config.push_str(option);
+            config.push('\n');
+        }
+
+        let unreachable = |reason: String| {
+            StoreError::Artifact(format!("{url}: artifact store unreachable: {reason}"))
+        };
+        let mut child = Command::new("curl")
+            .args(["--config", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| unreachable(format!("failed to run curl: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(config.as_bytes())
+                .map_err(|e| unreachable(format!("failed to configure curl: {e}")))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| unreachable(format!("curl failed: {e}")))?;
+        if !output.status.success() {
+            return Err(unreachable(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        // The status code is the line `write-out` appends after the body
+        let mut body = output.stdout;
+        let split = body.iter().rposition(|b| *b == b'\n').unwrap_or(0);
+        let status = String::from_utf8_lossy(&body[split..])
+            .trim()
+            .parse()
+            .unwrap_or(0);
+        body.truncate(split);
+        Ok(S3Response { status, body })
+    }
+
+    fn failed(&self, method: &str, key: &str, response: &S3Response) -> StoreError {
+        let detail = String::from_utf8_lossy(&response.body);
+        StoreError::Artifact(format!(
+            "{method} {} returned HTTP {}: {}",
+            self.url(key),
+            response.status,
+            detail.chars().take(200).collect::<String>().trim()
+        ))
+    }
+}
+
+impl ArtifactBackend for S3Backend {
+    fn location(&self, key: &str) -> String {
+        format!("s3://{}/{key}", self.bucket)
+    }
+
+    fn key_of<'a>(&self, location: &'a str) -> Option<&'a str> {
+        location
+            .strip_prefix("s3://")?
+            .strip_prefix(self.bucket.as_str())?
+            .strip_prefix('/')
+    }
+
+    fn put(&self, key: &str, body: &[u8]) -> Result<(), StoreError> {
+        let mut staged = tempfile::NamedTempFile::new()?;
+        staged.write_all(body)?;
+        let response = self.request(
+            key,
+            &[
+                format!(
+                    "upload-file = {}",
+                    curlrc_quote(&staged.path().to_string_lossy())
+                ),
+                // curl cannot hash an upload it streams; S3 accepts this marker
+                "header = \"x-amz-content-sha256: UNSIGNED-PAYLOAD\"".to_string(),
+            ],
+        )?;
+        if (200..300).contains(&response.status) {
+            Ok(())
+        } else {
+            Err(self.failed("PUT", key, &response))
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, StoreError> {
+        let response = self.request(key, &[])?;
+        if (200..300).contains(&response.status) {
+            Ok(response.body)
+        } else {
+            Err(self.failed("GET", key, &response))
+        }
+    }
+
+    fn size(&self, key: &str) -> Result<Option<u64>, StoreError> {
+        let response = self.request(key, &["head".to_string()])?;
+        match response.status {
+            404 => Ok(None),
+            200..300 => {
+                let headers = String::from_utf8_lossy(&response.body);
+                Ok(headers.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.trim()
+                        .eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse().ok())?
+                }))
+            }
+            _ => Err(self.failed("HEAD", key, &response)),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StoreError> {
+        let response = self.request(key, &["request = \"DELETE\"".to_string()])?;
+        if (200..300).contains(&response.status) || response.status == 404 {
+            Ok(())
+        } else {
+            Err(self.failed("DELETE", key, &response))
+        }
+    }
+}
+
+/// Quote a value for a curl config file
+fn curlrc_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A configured backend plus the key layout artifacts are stored under
+pub struct ArtifactStore {
+    backend: Box<dyn ArtifactBackend>,
+    prefix: String,
+}
+
+impl ArtifactStore {
+    #[must_use]
+    pub fn new(backend: Box<dyn ArtifactBackend>, prefix: &str) -> Self {
+        Self {
+            backend,
+            prefix: prefix.trim_matches('/').to_string(),
+        }
+    }
+
+    /// The backend `config` describes, `None` when it names none
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if an S3 credential does not
+    /// resolve.
+    pub fn from_config(config: &ArtifactStorageConfig) -> Result<Option<Self>, StoreError> {
+        let backend: Box<dyn ArtifactBackend> = if let Some(dir) = &config.dir {
+            Box::new(DirBackend::new(dir))
+        } else if let Some(endpoint) = &config.s3_endpoint {
+            let secret = |key: &str, value: Option<&String>| {
+                let value = value.ok_or_else(|| {
+                    StoreError::Artifact(format!("storage.artifacts.{key} is not set"))
+                })?;
+                vc_config::resolve_secret(&format!("storage.artifacts.{key}"), value)
+                    .map_err(|e| StoreError::Artifact(e.to_string()))
+            };
+            Box::new(S3Backend::new(
+                endpoint,
+                config.bucket.as_deref().unwrap_or_default(),
+                &config.region,
+                secret("access_key", config.access_key.as_ref())?,
+                secret("secret_key", config.secret_key.as_ref())?,
+                Duration::from_secs(config.timeout_secs),
+            ))
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(Self::new(backend, &config.prefix)))
+    }
+
+    #[must_use]
+    pub fn backend(&self) -> &dyn ArtifactBackend {
+        self.backend.as_ref()
+    }
+
+    /// Key a session's transcript is stored under
+    #[must_use]
+    pub fn transcript_key(&self, machine_id: &str, session_id: &str) -> String {
+        let name = format!(
+            "transcripts/{}/{}.json",
+            key_component(machine_id),
+            key_component(session_id)
+        );
+        if self.prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{name}", self.prefix)
+        }
+    }
+}
+
+/// Ids made safe to use as one path segment of a key
+fn key_component(id: &str) -> String {
+    let safe: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if safe.is_empty() || safe.chars().all(|c| c == '.') {
+        "_".to_string()
+    } else {
+        safe
+    }
+}
+
+/// Hex SHA-256 of an artifact body
+#[must_use]
+pub fn sha256_hex(body: &[u8]) -> String {
+    Sha256::digest(body)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Where an offloaded body went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactPointer {
+    pub kind: String,
+    pub machine_id: String,
+    pub session_id: String,
+    pub location: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub offloaded_at: String,
+}
+
+/// Outcome of one offload pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffloadSummary {
+    pub offloaded: usize,
+    pub bytes: i64,
+}
+
+/// Pointer totals for `vc db info --detailed`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactStats {
+    pub pointers: i64,
+    pub offloaded_bytes: i64,
+}
+
+/// What `vc db artifacts verify` found for one pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Ok,
+    /// The backend has no object at the location
+    Missing,
+    SizeMismatch,
+    /// Only checked when the bodies are downloaded
+    HashMismatch,
+    /// The location is not in the configured backend
+    Foreign,
+    Unreachable,
+}
+
+/// One pointer checked against the backend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactCheck {
+    #[serde(flatten)]
+    pub pointer: ArtifactPointer,
+    pub status: ArtifactStatus,
+    pub detail: Option<String>,
+}
+
+const POINTER_COLUMNS: &str =
+    "kind, machine_id, session_id, location, size_bytes, sha256, offloaded_at";
+
+fn pointer_from_row(row: &duckdb::Row<'_>) -> Result<ArtifactPointer, duckdb::Error> {
+    Ok(ArtifactPointer {
+        kind: row.get(0)?,
+        machine_id: row.get(1)?,
+        session_id: row.get(2)?,
+        location: row.get(3)?,
+        size_bytes: row.get(4)?,
+        sha256: row.get(5)?,
+        offloaded_at: row.get(6)?,
+    })
+}
+
+impl VcStore {
+    /// Read offloaded artifacts through the backend `config` names; also
+    /// what [`VcStore::offload_transcripts`] writes to. A backend that
+    /// cannot be set up is logged and reads of offloaded bodies then fail.
+    #[must_use]
+    pub fn with_artifacts(self, config: &ArtifactStorageConfig) -> Self {
+        match ArtifactStore::from_config(config) {
+            Ok(Some(artifacts)) => {
+                let _ = self.conn.shared.artifacts.set(artifacts);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, "artifact store unavailable"),
+        }
+        self
+    }
+
+    /// The artifact store offloaded bodies are read from, if configured
+    #[must_use]
+    pub fn artifact_store(&self) -> Option<&ArtifactStore> {
+        self.conn.shared.artifacts.get()
+    }
+
+    fn require_artifact_store(&self) -> Result<&ArtifactStore, StoreError> {
+        self.artifact_store().ok_or_else(|| {
+            StoreError::Artifact("no artifact store configured under [storage.artifacts]".into())
+        })
+    }
+
+    /// Move up to `limit` transcripts of sessions that ended before
+    /// `ended_before` to the artifact store, oldest first, leaving pointers
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if no artifact store is configured, an upload
+    /// fails (transcripts moved before it stay moved), or a query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn offload_transcripts(
+        &self,
+        ended_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<OffloadSummary, StoreError> {
+        let artifacts = self.require_artifact_store()?;
+        let due: Vec<(String, String, String)> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT machine_id, session_id, raw_json FROM agent_sessions \
+                 WHERE raw_json IS NOT NULL \
+                   AND TRY_CAST(ended_at AS TIMESTAMP) < CAST(? AS TIMESTAMP) \
+                 ORDER BY TRY_CAST(ended_at AS TIMESTAMP) LIMIT {limit}"
+            ))?;
+            let cutoff = ended_before.format("%Y-%m-%d %H:%M:%S").to_string();
+            stmt.query_map([cutoff], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut summary = OffloadSummary::default();
+        for (machine_id, session_id, body) in due {
+            // Upload without holding the store; only the pointer swap locks it
+            let key = artifacts.transcript_key(&machine_id, &session_id);
+            artifacts.backend().put(&key, body.as_bytes())?;
+            let size = i64::try_from(body.len()).unwrap_or(i64::MAX);
+
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO artifact_pointers ({POINTER_COLUMNS}) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?)"
+                ),
+                duckdb::params![
+                    TRANSCRIPT_KIND,
+                    machine_id,
+                    session_id,
+                    artifacts.backend().location(&key),
+                    size,
+                    sha256_hex(body.as_bytes()),
+                    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+                ],
+            )?;
+            conn.execute(
+                "UPDATE agent_sessions SET raw_json = NULL \
+                 WHERE machine_id = ? AND session_id = ?",
+                duckdb::params![machine_id, session_id],
+            )?;
+            summary.offloaded += 1;
+            summary.bytes += size;
+        }
+        Ok(summary)
+    }
+
+    /// The pointer left by an offloaded transcript
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn transcript_pointer(
+        &self,
+        machine_id: &str,
+        session_id: &str,
+    ) -> Result<Option<ArtifactPointer>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {POINTER_COLUMNS} FROM artifact_pointers \
+             WHERE kind = ? AND machine_id = ? AND session_id = ?"
+        ))?;
+        let mut rows = stmt.query_map(
+            duckdb::params![TRANSCRIPT_KIND, machine_id, session_id],
+            pointer_from_row,
+        )?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Fetch an offloaded transcript back from the artifact store, checking
+    /// it against the pointer's hash; `None` when it was never offloaded
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] if the transcript is offloaded but
+    /// no artifact store is configured, the backend is unreachable, or the
+    /// body no longer matches its hash.
+    pub fn offloaded_transcript(
+        &self,
+        machine_id: &str,
+        session_id: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let Some(pointer) = self.transcript_pointer(machine_id, session_id)? else {
+            return Ok(None);
+        };
+        let unavailable = |reason: String| {
+            StoreError::Artifact(format!(
+                "transcript of session {session_id} is offloaded to {}: {reason}",
+                pointer.location
+            ))
+        };
+        let artifacts = self
+            .require_artifact_store()
+            .map_err(|e| unavailable(e.to_string()))?;
+        let key = artifacts
+            .backend()
+            .key_of(&pointer.location)
+            .ok_or_else(|| unavailable("not in the configured artifact store".to_string()))?;
+        let body = artifacts
+            .backend()
+            .get(key)
+            .map_err(|e| unavailable(e.to_string()))?;
+        if sha256_hex(&body) != pointer.sha256 {
+            return Err(unavailable(
+                "content does not match its SHA-256".to_string(),
+            ));
+        }
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|_| unavailable("content is not UTF-8".to_string()))
+    }
+
+    /// Put offloaded transcripts back into session rows (objects with
+    /// `machine_id`, `session_id` and a null `raw_json`)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Artifact`] as [`VcStore::offloaded_transcript`]
+    /// does.
+    pub fn fill_offloaded_transcripts(
+        &self,
+        rows: &mut [serde_json::Value],
+    ) -> Result<(), StoreError> {
+        for row in rows {
+            if !row["raw_json"].is_null() {
+                continue;
+            }
+            let (Some(machine_id), Some(session_id)) =
+                (row["machine_id"].as_str(), row["session_id"].as_str())
+            else {
+                continue;
+            };
+            if let Some(body) = self.offloaded_transcript(machine_id, session_id)? {
+                row["raw_json"] = serde_json::Value::String(body);
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of pointers and bytes held outside the database
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn artifact_stats(&self) -> Result<ArtifactStats, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row(
+            "SELECT COUNT(*), CAST(COALESCE(SUM(size_bytes), 0) AS BIGINT) FROM artifact_pointers",
+            [],
+            |row| {
+                Ok(ArtifactStats {
+                    pointers: row.get(0)?,
+                    offloaded_bytes: row.get(1)?,
+                })
+            },
+        )?)
+    }
+
+    fn artifact_pointers(&self, orphans_only: bool) -> Result<Vec<ArtifactPointer>, StoreError> {
+        let filter = if orphans_only {
+            "WHERE NOT EXISTS (SELECT 1 FROM agent_sessions s \
+                 WHERE s.machine_id = p.machine_id AND s.session_id = p.session_id)"
+        } else {
+            ""
+        };
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {POINTER_COLUMNS} FROM artifact_pointers p {filter} \
+             ORDER BY offloaded_at, machine_id, session_id"
+        ))?;
+        let pointers = stmt
+            .query_map([], pointer_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(pointers)
+    }
+
+    /// Check every pointer against the artifact store: that the object
+    /// exists with the recorded size, and with `download` that its content
+    /// still hashes to the recorded SHA-256
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if no artifact store is configured or the
+    /// pointers cannot be read. Backend failures are reported per pointer.
+    pub fn verify_artifacts(&self, download: bool) -> Result<Vec<ArtifactCheck>, StoreError> {
+        let artifacts = self.require_artifact_store()?;
+        let backend = artifacts.backend();
+        Ok(self
+            .artifact_pointers(false)?
+            .into_iter()
+            .map(|pointer| {
+                let (status, detail) = match backend.key_of(&pointer.location) {
+                    None => (ArtifactStatus::Foreign, None),
+                    Some(key) => check_artifact(backend, key, &pointer, download),
+                };
+                ArtifactCheck {
+                    pointer,
+                    status,
+                    detail,
+                }
+            })
+            .collect())
+    }
+
+    /// Delete the artifacts (and pointers) of sessions that are gone from
+    /// `agent_sessions`, e.g. removed by retention; with `dry_run` only
+    /// count them. Returns the number of artifacts and their bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query fails, or if there are artifacts to
+    /// delete but no artifact store is configured or a delete fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn prune_orphan_artifacts(&self, dry_run: bool) -> Result<(usize, i64), StoreError> {
+        let orphans = self.artifact_pointers(true)?;
+        let bytes = orphans.iter().map(|p| p.size_bytes).sum();
+        if dry_run || orphans.is_empty() {
+            return Ok((orphans.len(), bytes));
+        }
+
+        let artifacts = self.require_artifact_store()?;
+        for pointer in &orphans {
+            if let Some(key) = artifacts.backend().key_of(&pointer.location) {
+                artifacts.backend().delete(key)?;
+            }
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "DELETE FROM artifact_pointers WHERE kind = ? AND machine_id = ? AND session_id = ?",
+                duckdb::params![pointer.kind, pointer.machine_id, pointer.session_id],
+            )?;
+        }
+        Ok((orphans.len(), bytes))
+    }
+}
+
+fn check_artifact(
+    backend: &dyn ArtifactBackend,
+    key: &str,
+    pointer: &ArtifactPointer,
+    download: bool,
+) -> (ArtifactStatus, Option<String>) {
+    let expected = u64::try_from(pointer.size_bytes).unwrap_or_default();
+    match backend.size(key) {
+        Err(e) => return (ArtifactStatus::Unreachable, Some(e.to_string())),
+        Ok(None) => return (ArtifactStatus::Missing, None),
+        Ok(Some(size)) if size != expected => {
+            return (
+                ArtifactStatus::SizeMismatch,
+                Some(format!("{size} bytes, expected {expected}")),
+            );
+        }
+        Ok(Some(_)) => {}
+    }
+    if download {
+        match backend.get(key) {
+            Err(e) => return (ArtifactStatus::Unreachable, Some(e.to_string())),
+            Ok(body) if sha256_hex(&body) != pointer.sha256 => {
+                return (ArtifactStatus::HashMismatch, None);
+            }
+            Ok(_) => {}
+        }
+    }
+    (ArtifactStatus::Ok, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_dir(dir: &Path) -> VcStore {
+        let config = ArtifactStorageConfig {
+            dir: Some(dir.to_path_buf()),
+            ..ArtifactStorageConfig::default()
+        };
+        VcStore::open_memory().unwrap().with_artifacts(&config)
+    }
+
+    fn seed_session(store: &VcStore, session_id: &str, ended_at: &str, body: &str) {
+        store
+            .execute(
+                "INSERT INTO agent_sessions (machine_id, session_id, program, ended_at, raw_json) \
+                 VALUES ('m1', ?, 'claude', ?, ?)",
+                &[session_id, ended_at, body],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_offload_and_read_back_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_dir(dir.path());
+        seed_session(
+            &store,
+            "old",
+            "2026-01-01 00:00:00",
+            r#"{"messages":["hi"]}"#,
+        );
+        seed_session(&store, "new", "2026-10-15 00:00:00", r#"{"messages":[]}"#);
+
+        let cutoff = DateTime::parse_from_rfc3339("2026-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let summary = store.offload_transcripts(cutoff, 10).unwrap();
+        assert_eq!(summary.offloaded, 1);
+        assert_eq!(summary.bytes, 19);
+        assert_eq!(
+            store
+                .query_scalar::<i64>("SELECT COUNT(*) FROM agent_sessions WHERE raw_json IS NULL")
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.artifact_stats().unwrap().offloaded_bytes, 19);
+
+        // Session reads fetch the body back transparently
+        let session = store.get_agent_session("old").unwrap().unwrap();
+        assert_eq!(session["raw_json"], r#"{"messages":["hi"]}"#);
+
+        let checks = store.verify_artifacts(true).unwrap();
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].status, ArtifactStatus::Ok);
+
+        // A tampered object fails the read and the check instead of
+        // passing for the transcript
+        let key = store.artifact_store().unwrap().transcript_key("m1", "old");
+        std::fs::write(dir.path().join(&key), "{}").unwrap();
+        assert!(store.get_agent_session("old").is_err());
+        assert_eq!(
+            store.verify_artifacts(false).unwrap()[0].status,
+            ArtifactStatus::SizeMismatch
+        );
+        std::fs::remove_file(dir.path().join(&key)).unwrap();
+        assert_eq!(
+            store.verify_artifacts(false).unwrap()[0].status,
+            ArtifactStatus::Missing
+        );
+    }
+
+    #[test]
+    fn test_offloaded_transcript_without_backend_is_an_error() {
+        let bare = VcStore::open_memory().unwrap();
+        assert!(bare.offloaded_transcript("m1", "s1").unwrap().is_none());
+        bare.execute_batch(
+            "INSERT INTO artifact_pointers VALUES \
+             ('transcript', 'm1', 's1', 'file:///nowhere/s1.json', 4, 'x', '2026-10-16')",
+        )
+        .unwrap();
+        let err = bare.offloaded_transcript("m1", "s1").unwrap_err();
+        assert!(err.to_string().contains("no artifact store configured"));
+    }
+
+    #[test]
+    fn test_prune_orphan_artifacts_deletes_removed_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_with_dir(dir.path());
+        seed_session(&store, "gone", "2026-01-01 00:00:00", "body");
+        seed_session(&store, "kept", "2026-01-01 00:00:00", "body");
+        store.offload_transcripts(Utc::now(), 10).unwrap();
+        store
+            .execute("DELETE FROM agent_sessions WHERE session_id = ?", &["gone"])
+            .unwrap();
+
+        assert_eq!(store.prune_orphan_artifacts(true).unwrap(), (1, 4));
+        assert_eq!(store.artifact_stats().unwrap().pointers, 2);
+        assert_eq!(store.prune_orphan_artifacts(false).unwrap(), (1, 4));
+        assert_eq!(store.artifact_stats().unwrap().pointers, 1);
+
+        let key = |id: &str| store.artifact_store().unwrap().transcript_key("m1", id);
+        assert!(!dir.path().join(key("gone")).exists());
+        assert!(dir.path().join(key("kept")).exists());
+    }
+
+    #[test]
+    fn test_transcript_keys_are_single_segments() {
+        let store = ArtifactStore::new(Box::new(DirBackend::new("/tmp/vc")), "/vc/");
+        assert_eq!(
+            store.transcript_key("m/1", "../etc"),
+            "vc/transcripts/m_1/.._etc.json"
+        );
+        assert_eq!(store.transcript_key("m1", ".."), "vc/transcripts/m1/_.json");
+
+        let backend = DirBackend::new("/tmp/vc");
+        let location = backend.location("vc/transcripts/m1/s1.json");
+        assert_eq!(backend.key_of(&location), Some("vc/transcripts/m1/s1.json"));
+        assert_eq!(backend.key_of("s3://bucket/vc/s1.json"), None);
+
+        let s3 = S3Backend::new(
+            "http://minio:9000/",
+            "bucket",
+            "us-east-1",
+            String::new(),
+            String::new(),
+            Duration::from_secs(1),
+        );
+        assert_eq!(s3.url("k/s1.json"), "http://minio:9000/bucket/k/s1.json");
+        assert_eq!(s3.key_of(&s3.location("k/s1.json")), Some("k/s1.json"));
+        assert_eq!(s3.key_of("s3://other/k/s1.json"), None);
+    }
+}
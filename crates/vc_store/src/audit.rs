@@ -0,0 +1,446 @@
+//! Batched, sampled audit writes for hot paths
+//!
+//! Collector runs, watch polling and web requests would each write an audit
+//! row per event. [`AuditWriter`] buffers those, samples or rolls them up
+//! according to [`vc_config::AuditConfig`], and writes them in batches.
+//!
+//! Every call names its [`AuditDurability`]. Sensitive event types
+//! ([`AuditEventType::is_sensitive`]) are written individually and
+//! synchronously even when a caller asks for best effort, so an approval can
+//! never be sampled away.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use tracing::warn;
+use vc_config::{AuditConfig, AuditSampling};
+
+use crate::{AuditEvent, AuditEventType, AuditResult, QueryCaller, StoreError, VcStore};
+
+/// How durably an audit event must be recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditDurability {
+    /// Written as its own row before `record` returns
+    Synchronous,
+    /// May be buffered, sampled or rolled up; lost if the process dies first
+    BestEffort,
+}
+
+/// Events rolled up per (type, machine, action) and window
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct RollupKey {
+    event_type: AuditEventType,
+    machine_id: Option<String>,
+    action: String,
+}
+
+#[derive(Debug)]
+struct Rollup {
+    window_start: DateTime<Utc>,
+    actor: String,
+    via: Option<QueryCaller>,
+    last_ts: DateTime<Utc>,
+    count: u64,
+    failures: u64,
+    skipped: u64,
+}
+
+/// Audit writer for hot paths
+#[derive(Debug)]
+pub struct AuditWriter {
+    sampling: HashMap<AuditEventType, AuditSampling>,
+    batch_size: usize,
+    window: Duration,
+    pending: Vec<AuditEvent>,
+    seen: HashMap<AuditEventType, u64>,
+    rollups: HashMap<RollupKey, Rollup>,
+}
+
+impl AuditWriter {
+    /// Build a writer from the `[audit]` config section. Entries for unknown
+    /// or sensitive event types are ignored.
+    #[must_use]
+    pub fn new(config: &AuditConfig) -> Self {
+        let sampling = config
+            .events
+            .iter()
+            .filter_map(|(name, sampling)| {
+                let event_type: AuditEventType = name.parse().ok()?;
+                (!event_type.is_sensitive()).then_some((event_type, *sampling))
+            })
+            .collect();
+        Self {
+            sampling,
+            batch_size: config.batch_size.max(1),
+            window: Duration::seconds(
+                i64::try_from(config.rollup_window_secs.max(1)).unwrap_or(i64::MAX),
+            ),
+            pending: Vec::new(),
+            seen: HashMap::new(),
+            rollups: HashMap::new(),
+        }
+    }
+
+    /// Record an audit event.
+    ///
+    /// `Synchronous` events, and any sensitive event, are inserted before this
+    /// returns. `BestEffort` events follow the configured sampling and are
+    /// written once a full batch is pending or on [`AuditWriter::flush`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a synchronous insert or a triggered batch
+    /// write fails.
+    pub fn record(
+        &mut self,
+        store: &VcStore,
+        event: AuditEvent,
+        durability: AuditDurability,
+    ) -> Result<(), StoreError> {
+        if durability == AuditDurability::Synchronous || event.event_type.is_sensitive() {
+            if durability == AuditDurability::BestEffort {
+                warn!(
+                    event_type = event.event_type.as_str(),
+                    action = %event.action,
+                    "sensitive audit event recorded as best effort; writing synchronously"
+                );
+            }
+            return store.insert_audit_event(&event);
+        }
+
+        match self
+            .sampling
+            .get(&event.event_type)
+            .copied()
+            .unwrap_or(AuditSampling::All)
+        {
+            AuditSampling::All => self.pending.push(event),
+            AuditSampling::Sample { keep_one_in } => self.sample(event, keep_one_in),
+            AuditSampling::Rollup => self.roll_up(event),
+        }
+
+        if self.pending.len() >= self.batch_size {
+            self.write_pending(store)?;
+        }
+        Ok(())
+    }
+
+    /// Close rollup windows that ended before `now` and write everything
+    /// pending. Returns the number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the batch write fails; the batch is dropped.
+    pub fn flush(&mut self, store: &VcStore, now: DateTime<Utc>) -> Result<usize, StoreError> {
+        let closed: Vec<RollupKey> = self
+            .rollups
+            .iter()
+            .filter(|(_, rollup)| rollup.window_start + self.window <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in closed {
+            if let Some(rollup) = self.rollups.remove(&key) {
+                let event = self.rollup_event(key, rollup);
+                self.pending.push(event);
+            }
+        }
+        self.write_pending(store)
+    }
+
+    /// Close every open rollup window and write everything pending, for
+    /// shutdown. Returns the number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the batch write fails; the batch is dropped.
+    pub fn shutdown(&mut self, store: &VcStore) -> Result<usize, StoreError> {
+        let open: Vec<(RollupKey, Rollup)> = self.rollups.drain().collect();
+        for (key, rollup) in open {
+            let event = self.rollup_event(key, rollup);
+            self.pending.push(event);
+        }
+        self.write_pending(store)
+    }
+
+    /// Best-effort events not yet written (including open rollup windows)
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.pending.len() + self.rollups.len()
+    }
+
+    fn sample(&mut self, mut event: AuditEvent, keep_one_in: u32) {
+        let seen = self.seen.entry(event.event_type).or_default();
+        let keep = *seen % u64::from(keep_one_in.max(1)) == 0;
+        *seen += 1;
+        // Failures are rare and the reason to read the log; never drop them.
+        if !keep && event.result != AuditResult::Failure {
+            return;
+        }
+        if let Some(details) = event.details.as_object_mut() {
+            details.insert("sampled_one_in".to_string(), keep_one_in.into());
+        }
+        self.pending.push(event);
+    }
+
+    fn roll_up(&mut self, event: AuditEvent) {
+        let window_secs = self.window.num_seconds();
+        let secs = event.ts.timestamp();
+        let window_start =
+            DateTime::from_timestamp(secs - secs.rem_euclid(window_secs), 0).unwrap_or(event.ts);
+        let key = RollupKey {
+            event_type: event.event_type,
+            machine_id: event.machine_id.clone(),
+            action: event.action.clone(),
+        };
+
+        if let Some(open) = self.rollups.get(&key)
+            && open.window_start != window_start
+            && let Some(closed) = self.rollups.remove(&key)
+        {
+            let summary = self.rollup_event(key.clone(), closed);
+            self.pending.push(summary);
+        }
+
+        let rollup = self.rollups.entry(key).or_insert_with(|| Rollup {
+            window_start,
+            actor: event.actor.clone(),
+            via: event.via,
+            last_ts: event.ts,
+            count: 0,
+            failures: 0,
+            skipped: 0,
+        });
+        rollup.count += 1;
+        rollup.last_ts = rollup.last_ts.max(event.ts);
+        match event.result {
+            AuditResult::Success => {}
+            AuditResult::Failure => rollup.failures += 1,
+            AuditResult::Skipped => rollup.skipped += 1,
+        }
+    }
+
+    fn rollup_event(&self, key: RollupKey, rollup: Rollup) -> AuditEvent {
+        let result = if rollup.failures > 0 {
+            AuditResult::Failure
+        } else {
+            AuditResult::Success
+        };
+        AuditEvent {
+            ts: rollup.last_ts,
+            event_type: key.event_type,
+            actor: rollup.actor,
+            via: rollup.via,
+            machine_id: key.machine_id,
+            action: key.action,
+            result,
+            details: serde_json::json!({
+                "rollup": {
+                    "count": rollup.count,
+                    "failures": rollup.failures,
+                    "skipped": rollup.skipped,
+                    "window_start": rollup.window_start.to_rfc3339(),
+                    "window_end": (rollup.window_start + self.window).to_rfc3339(),
+                }
+            }),
+        }
+    }
+
+    fn write_pending(&mut self, store: &VcStore) -> Result<usize, StoreError> {
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+        let batch = std::mem::take(&mut self.pending);
+        store.insert_audit_events(&batch)?;
+        Ok(batch.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuditEventFilter;
+
+    fn collector_run(machine: &str, result: AuditResult, ts: DateTime<Utc>) -> AuditEvent {
+        let mut event = AuditEvent::new(
+            AuditEventType::CollectorRun,
+            "daemon",
+            "sysmoni",
+            result,
+            serde_json::json!({}),
+        )
+        .with_machine_id(machine);
+        event.ts = ts;
+        event
+    }
+
+    fn audit_rows(store: &VcStore) -> Vec<serde_json::Value> {
+        store
+            .list_audit_events(&AuditEventFilter {
+                limit: 1000,
+                ..AuditEventFilter::default()
+            })
+            .unwrap()
+    }
+
+    fn ts(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rollup_writes_one_event_per_machine_per_window() {
+        let store = VcStore::open_memory().unwrap();
+        let mut writer = AuditWriter::new(&AuditConfig::default());
+
+        for i in 0..120 {
+            let result = if i % 40 == 0 {
+                AuditResult::Failure
+            } else {
+                AuditResult::Success
+            };
+            writer
+                .record(
+                    &store,
+                    collector_run("orko", result, ts(7200 + i)),
+                    AuditDurability::BestEffort,
+                )
+                .unwrap();
+        }
+        writer
+            .record(
+                &store,
+                collector_run("ghost", AuditResult::Success, ts(7300)),
+                AuditDurability::BestEffort,
+            )
+            .unwrap();
+        assert!(audit_rows(&store).is_empty());
+
+        // Window [7200, 10800) is still open at 9000.
+        assert_eq!(writer.flush(&store, ts(9000)).unwrap(), 0);
+        assert_eq!(writer.flush(&store, ts(10_800)).unwrap(), 2);
+
+        let rows = audit_rows(&store);
+        let orko = rows.iter().find(|r| r["machine_id"] == "orko").unwrap();
+        assert_eq!(orko["result"], "failure");
+        let details: serde_json::Value =
+            serde_json::from_str(orko["details_json"].as_str().unwrap()).unwrap();
+        assert_eq!(details["rollup"]["count"], 120);
+        assert_eq!(details["rollup"]["failures"], 3);
+        assert_eq!(writer.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_rollup_closes_window_when_next_one_starts() {
+        let store = VcStore::open_memory().unwrap();
+        let mut writer = AuditWriter::new(&AuditConfig {
+            batch_size: 1,
+            ..AuditConfig::default()
+        });
+
+        for secs in [0, 10, 3600] {
+            writer
+                .record(
+                    &store,
+                    collector_run("orko", AuditResult::Success, ts(secs)),
+                    AuditDurability::BestEffort,
+                )
+                .unwrap();
+        }
+        // The first window closed when the 3600s event arrived.
+        assert_eq!(audit_rows(&store).len(), 1);
+        assert_eq!(writer.shutdown(&store).unwrap(), 1);
+        assert_eq!(audit_rows(&store).len(), 2);
+    }
+
+    #[test]
+    fn test_sampling_keeps_one_in_n_and_every_failure() {
+        let store = VcStore::open_memory().unwrap();
+        let mut config = AuditConfig::default();
+        config.events.insert(
+            "collector_run".to_string(),
+            AuditSampling::Sample { keep_one_in: 10 },
+        );
+        let mut writer = AuditWriter::new(&config);
+
+        for i in 0..30 {
+            let result = if i == 5 {
+                AuditResult::Failure
+            } else {
+                AuditResult::Success
+            };
+            writer
+                .record(
+                    &store,
+                    collector_run("orko", result, ts(i)),
+                    AuditDurability::BestEffort,
+                )
+                .unwrap();
+        }
+        assert_eq!(writer.flush(&store, ts(30)).unwrap(), 4);
+        assert!(audit_rows(&store).iter().all(|row| {
+            row["details_json"]
+                .as_str()
+                .is_some_and(|d| d.contains("sampled_one_in"))
+        }));
+    }
+
+    #[test]
+    fn test_sensitive_events_are_never_buffered() {
+        let store = VcStore::open_memory().unwrap();
+        let mut config = AuditConfig::default();
+        config
+            .events
+            .insert("guardian_action".to_string(), AuditSampling::Rollup);
+        let mut writer = AuditWriter::new(&config);
+
+        let approval = AuditEvent::new(
+            AuditEventType::GuardianAction,
+            "alice",
+            "approve_draft",
+            AuditResult::Success,
+            serde_json::json!({"draft_id": "d1"}),
+        );
+        writer
+            .record(&store, approval, AuditDurability::BestEffort)
+            .unwrap();
+
+        let rows = audit_rows(&store);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["action"], "approve_draft");
+        assert_eq!(writer.pending_len(), 0);
+    }
+
+    #[test]
+    fn test_synchronous_event_survives_crash_after_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        let store = VcStore::open(&path).unwrap();
+        let mut writer = AuditWriter::new(&AuditConfig::default());
+
+        let approval = AuditEvent::new(
+            AuditEventType::UserCommand,
+            "alice",
+            "fleet_restart",
+            AuditResult::Success,
+            serde_json::json!({}),
+        );
+        writer
+            .record(&store, approval, AuditDurability::Synchronous)
+            .unwrap();
+        writer
+            .record(
+                &store,
+                collector_run("orko", AuditResult::Success, Utc::now()),
+                AuditDurability::BestEffort,
+            )
+            .unwrap();
+
+        // Simulated crash: nothing is flushed, dropped or closed cleanly.
+        std::mem::forget(writer);
+        std::mem::forget(store);
+
+        let reopened = VcStore::open(&path).unwrap();
+        let rows = audit_rows(&reopened);
+        assert_eq!(rows.len(), 1, "{rows:?}");
+        assert_eq!(rows[0]["action"], "fleet_restart");
+    }
+}
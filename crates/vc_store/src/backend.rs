@@ -0,0 +1,320 @@
+//! Storage backend abstraction
+//!
+//! [`VcStore`] (`DuckDB`) is the default engine. Constrained nodes can build
+//! with the `sqlite` feature and use [`crate::SqliteStore`] instead, selected
+//! either by `global.store_backend` in config or by a `.sqlite` / `.sqlite3`
+//! database path. Built without the default `duckdb` feature, only
+//! `SqliteStore` is left and no `DuckDB` is compiled.
+//!
+//! The trait surface is deliberately the generic one: raw statements,
+//! `query_json`, JSON row appends and table introspection. Domain methods on
+//! [`VcStore`] are still `DuckDB`-only. Engine-specific operations return
+//! [`StoreError::NotSupported`] by default so callers can degrade instead of
+//! failing outright.
+
+#[cfg(feature = "duckdb")]
+use crate::VcStore;
+use crate::{StoreError, migrations};
+use std::path::Path;
+
+/// Storage engine behind a store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendKind {
+    DuckDb,
+    Sqlite,
+}
+
+impl BackendKind {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::DuckDb => "duckdb",
+            BackendKind::Sqlite => "sqlite",
+        }
+    }
+
+    /// Pick the backend for `path`: an explicit `configured` name wins,
+    /// otherwise a `.sqlite` / `.sqlite3` extension selects `SQLite` and
+    /// anything else stays on `DuckDB`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if `configured` names an unknown
+    /// backend.
+    pub fn resolve(path: &Path, configured: Option<&str>) -> Result<Self, StoreError> {
+        if let Some(name) = configured {
+            return name.parse().map_err(StoreError::QueryError);
+        }
+        let is_sqlite = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("sqlite") || ext.eq_ignore_ascii_case("sqlite3")
+            });
+        Ok(if is_sqlite {
+            BackendKind::Sqlite
+        } else {
+            BackendKind::DuckDb
+        })
+    }
+}
+
+impl std::str::FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "duckdb" => Ok(BackendKind::DuckDb),
+            "sqlite" => Ok(BackendKind::Sqlite),
+            other => Err(format!("unknown store backend: {other}")),
+        }
+    }
+}
+
+/// Operations every storage engine provides
+pub trait StoreBackend: Send + Sync {
+    /// Engine behind this store
+    fn kind(&self) -> BackendKind;
+
+    /// Database path as opened
+    fn db_path(&self) -> &str;
+
+    /// Execute a statement with positional text parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if statement preparation or execution fails.
+    fn execute(&self, sql: &str, params: &[&str]) -> Result<usize, StoreError>;
+
+    /// Execute a batch of statements
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if batch execution fails.
+    fn execute_batch(&self, sql: &str) -> Result<(), StoreError>;
+
+    /// Run a query and return each row as a JSON object keyed by column name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    fn query_json(&self, sql: &str) -> Result<Vec<serde_json::Value>, StoreError>;
+
+    /// Append JSON object rows to `table` in one transaction
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if any row fails to insert; no rows are kept.
+    fn insert_json_batch(
+        &self,
+        table: &str,
+        rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError>;
+
+    /// List user tables (excludes internal `_`-prefixed tables)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the catalog query fails.
+    fn list_tables(&self) -> Result<Vec<String>, StoreError>;
+
+    /// Row count for a table
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the count query fails.
+    fn table_row_count(&self, table: &str) -> Result<i64, StoreError>;
+
+    /// Write a consistent copy of the whole store to `dest` using the
+    /// engine's own backup mechanism
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::NotSupported`] unless the engine implements it,
+    /// or [`StoreError`] if the backup fails.
+    fn native_backup(&self, dest: &Path) -> Result<(), StoreError> {
+        let _ = dest;
+        Err(StoreError::NotSupported(format!(
+            "native backup on the {} backend",
+            self.kind().as_str()
+        )))
+    }
+}
+
+#[cfg(feature = "duckdb")]
+impl StoreBackend for VcStore {
+    fn kind(&self) -> BackendKind {
+        BackendKind::DuckDb
+    }
+
+    fn db_path(&self) -> &str {
+        VcStore::db_path(self)
+    }
+
+    fn execute(&self, sql: &str, params: &[&str]) -> Result<usize, StoreError> {
+        VcStore::execute(self, sql, params)
+    }
+
+    fn execute_batch(&self, sql: &str) -> Result<(), StoreError> {
+        VcStore::execute_batch(self, sql)
+    }
+
+    fn query_json(&self, sql: &str) -> Result<Vec<serde_json::Value>, StoreError> {
+        VcStore::query_json(self, sql)
+    }
+
+    fn insert_json_batch(
+        &self,
+        table: &str,
+        rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError> {
+        VcStore::insert_json_batch(self, table, rows)
+    }
+
+    fn list_tables(&self) -> Result<Vec<String>, StoreError> {
+        VcStore::list_tables(self)
+    }
+
+    fn table_row_count(&self, table: &str) -> Result<i64, StoreError> {
+        VcStore::table_row_count(self, table)
+    }
+
+    fn native_backup(&self, dest: &Path) -> Result<(), StoreError> {
+        // EXPORT DATABASE writes a directory (schema.sql, load.sql, one file
+        // per table) that IMPORT DATABASE can restore.
+        let dest = crate::escape_sql_literal(&dest.to_string_lossy());
+        VcStore::execute_batch(self, &format!("EXPORT DATABASE '{dest}'"))
+    }
+}
+
+/// Open the store at `path` on the backend chosen by [`BackendKind::resolve`],
+/// running migrations
+///
+/// # Errors
+///
+/// Returns [`StoreError::NotSupported`] for `SQLite` when built without the
+/// `sqlite` feature, or [`StoreError`] if opening or migrating fails.
+pub fn open_backend(
+    path: &Path,
+    configured: Option<&str>,
+) -> Result<Box<dyn StoreBackend>, StoreError> {
+    match BackendKind::resolve(path, configured)? {
+        #[cfg(feature = "duckdb")]
+        BackendKind::DuckDb => Ok(Box::new(VcStore::open(path)?)),
+        #[cfg(not(feature = "duckdb"))]
+        BackendKind::DuckDb => Err(StoreError::NotSupported(
+            "the duckdb backend (rebuild vc_store with the `duckdb` feature)".to_string(),
+        )),
+        #[cfg(feature = "sqlite")]
+        BackendKind::Sqlite => Ok(Box::new(crate::SqliteStore::open(path)?)),
+        #[cfg(not(feature = "sqlite"))]
+        BackendKind::Sqlite => Err(StoreError::NotSupported(
+            "the sqlite backend (rebuild vc_store with the `sqlite` feature)".to_string(),
+        )),
+    }
+}
+
+/// Apply pending migrations through the backend trait
+pub(crate) fn migrate(backend: &dyn StoreBackend) -> Result<(), StoreError> {
+    migrations::run_all(backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Core behaviour every backend must share. Run once per engine so the
+    /// `SQLite` fallback cannot quietly drift from `DuckDB`.
+    fn exercise_backend(store: &dyn StoreBackend) {
+        let tables = store.list_tables().unwrap();
+        assert!(tables.iter().any(|t| t == "machines"));
+        assert!(!tables.iter().any(|t| t.starts_with('_')));
+
+        let rows = vec![
+            serde_json::json!({"machine_id": "m1", "hostname": "one", "is_local": 1}),
+            serde_json::json!({"machine_id": "m2", "hostname": "two", "is_local": 0}),
+        ];
+        assert_eq!(store.insert_json_batch("machines", &rows).unwrap(), 2);
+        assert_eq!(store.table_row_count("machines").unwrap(), 2);
+
+        store
+            .execute(
+                "UPDATE machines SET hostname = ? WHERE machine_id = ?",
+                &["uno", "m1"],
+            )
+            .unwrap();
+        let found = store
+            .query_json("SELECT machine_id, hostname FROM machines ORDER BY machine_id")
+            .unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0]["machine_id"], "m1");
+        assert_eq!(found[0]["hostname"], "uno");
+
+        // A bad row rolls back the whole batch
+        let bad = vec![
+            serde_json::json!({"machine_id": "m3", "hostname": "three"}),
+            serde_json::json!({"no_such_column": 1}),
+        ];
+        assert!(store.insert_json_batch("machines", &bad).is_err());
+        assert_eq!(store.table_row_count("machines").unwrap(), 2);
+
+        store
+            .execute_batch("CREATE TABLE scratch (k TEXT, v TEXT)")
+            .unwrap();
+        store
+            .execute("INSERT INTO scratch VALUES (?, ?)", &["a", "7"])
+            .unwrap();
+        let scratch = store.query_json("SELECT k FROM scratch").unwrap();
+        assert_eq!(scratch[0]["k"], "a");
+    }
+
+    #[test]
+    fn test_resolve_backend_kind() {
+        let duck = PathBuf::from("/data/vc.duckdb");
+        let lite = PathBuf::from("/data/vc.sqlite3");
+        assert_eq!(
+            BackendKind::resolve(&duck, None).unwrap(),
+            BackendKind::DuckDb
+        );
+        assert_eq!(
+            BackendKind::resolve(&lite, None).unwrap(),
+            BackendKind::Sqlite
+        );
+        assert_eq!(
+            BackendKind::resolve(&duck, Some("sqlite")).unwrap(),
+            BackendKind::Sqlite
+        );
+        assert!(BackendKind::resolve(&duck, Some("postgres")).is_err());
+    }
+
+    #[cfg(feature = "duckdb")]
+    #[test]
+    fn test_duckdb_backend_core_suite() {
+        let store = VcStore::open_memory().unwrap();
+        assert_eq!(StoreBackend::kind(&store), BackendKind::DuckDb);
+        exercise_backend(&store);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_sqlite_backend_core_suite() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::SqliteStore::open(&dir.path().join("vc.sqlite")).unwrap();
+        assert_eq!(store.kind(), BackendKind::Sqlite);
+        exercise_backend(&store);
+
+        let backup = dir.path().join("backup.sqlite");
+        store.native_backup(&backup).unwrap();
+        let restored = crate::SqliteStore::open(&backup).unwrap();
+        assert_eq!(restored.table_row_count("machines").unwrap(), 2);
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn test_sqlite_backend_requires_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = open_backend(&dir.path().join("vc.sqlite"), None);
+        assert!(matches!(result, Err(StoreError::NotSupported(_))));
+    }
+}
@@ -0,0 +1,85 @@
+//! Table-level capability detection
+//!
+//! A store created by an older `vc` may predate tables that newer commands
+//! read (incidents, knowledge, guardian, ...). Migrations normally add them on
+//! open, but read-only composite commands must not fail on a store that is
+//! missing one. They take a [`Capabilities`] snapshot once, skip the sections
+//! whose tables are absent, and report those tables as `missing_capabilities`.
+
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Mutex;
+
+/// Tables present in a store, plus the ones callers found missing
+#[derive(Debug, Default)]
+pub struct Capabilities {
+    tables: HashSet<String>,
+    missing: Mutex<BTreeSet<String>>,
+}
+
+impl Capabilities {
+    /// Snapshot from a list of table names
+    #[must_use]
+    pub fn from_tables<I, S>(tables: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            tables: tables.into_iter().map(Into::into).collect(),
+            missing: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Whether `table` exists
+    #[must_use]
+    pub fn has_table(&self, table: &str) -> bool {
+        self.tables.contains(table)
+    }
+
+    /// Whether every table in `tables` exists; absent ones are recorded and
+    /// returned later by [`Capabilities::missing`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn require(&self, tables: &[&str]) -> bool {
+        let absent: Vec<&str> = tables
+            .iter()
+            .copied()
+            .filter(|table| !self.has_table(table))
+            .collect();
+        if absent.is_empty() {
+            return true;
+        }
+        let mut missing = self.missing.lock().unwrap();
+        missing.extend(absent.into_iter().map(ToString::to_string));
+        false
+    }
+
+    /// Tables that [`Capabilities::require`] found missing, sorted
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    #[must_use]
+    pub fn missing(&self) -> Vec<String> {
+        self.missing.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_records_missing_tables() {
+        let caps = Capabilities::from_tables(["machines", "alert_history"]);
+        assert!(caps.has_table("machines"));
+        assert!(caps.require(&["machines", "alert_history"]));
+        assert!(caps.missing().is_empty());
+
+        assert!(!caps.require(&["machines", "incidents"]));
+        assert!(!caps.require(&["guardian_runs", "incidents"]));
+        assert_eq!(caps.missing(), vec!["guardian_runs", "incidents"]);
+    }
+}
@@ -0,0 +1,230 @@
+//! Config change history
+//!
+//! Each time a config file is loaded whose content differs from the newest
+//! snapshot of that path, [`VcStore::record_config_snapshot`] keeps a copy
+//! in `config_history`: the full content, its hash, the file's mtime and
+//! which process loaded it. Only the newest snapshots are kept. Snapshots
+//! are found by hash or any unambiguous prefix of it, the way git finds
+//! commits. A new snapshot also leaves a fleet-wide `config` annotation.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use vc_config::history::content_hash;
+
+use crate::annotations::{CONFIG_KIND, INTERNAL_SOURCE};
+use crate::{AnnotationScope, NewAnnotation, StoreError, VcStore};
+
+/// One stored copy of a config file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigSnapshot {
+    pub content_hash: String,
+    pub config_path: String,
+    pub content: String,
+    pub file_mtime: Option<String>,
+    /// The command and pid that loaded it, e.g. `vc daemon (pid 4242)`
+    pub loaded_by: String,
+    pub recorded_at: String,
+}
+
+const SNAPSHOT_COLUMNS: &str =
+    "content_hash, config_path, content, file_mtime, loaded_by, recorded_at";
+
+fn read_snapshots(
+    conn: &crate::StoreConnectionGuard<'_>,
+    sql: &str,
+    params: &[&str],
+) -> Result<Vec<ConfigSnapshot>, StoreError> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(duckdb::params_from_iter(params.iter()), |row| {
+        Ok(ConfigSnapshot {
+            content_hash: row.get(0)?,
+            config_path: row.get(1)?,
+            content: row.get(2)?,
+            file_mtime: row.get(3)?,
+            loaded_by: row.get(4)?,
+            recorded_at: row.get(5)?,
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+impl VcStore {
+    /// Snapshot `content` as loaded from `path`, unless it matches the
+    /// newest snapshot of that path, then drop all but the newest `keep`
+    /// snapshots. `keep` of 0 records nothing. Returns the new snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if reading, inserting or pruning fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_config_snapshot(
+        &self,
+        path: &str,
+        content: &str,
+        file_mtime: Option<&str>,
+        loaded_by: &str,
+        keep: usize,
+    ) -> Result<Option<ConfigSnapshot>, StoreError> {
+        if keep == 0 {
+            return Ok(None);
+        }
+        let hash = content_hash(content);
+        let conn = self.conn.lock().unwrap();
+        let newest = read_snapshots(
+            &conn,
+            &format!(
+                "SELECT {SNAPSHOT_COLUMNS} FROM config_history WHERE config_path = ? \
+                 ORDER BY recorded_at DESC LIMIT 1"
+            ),
+            &[path],
+        )?;
+        if newest.first().is_some_and(|s| s.content_hash == hash) {
+            return Ok(None);
+        }
+
+        let snapshot = ConfigSnapshot {
+            content_hash: hash,
+            config_path: path.to_string(),
+            content: content.to_string(),
+            file_mtime: file_mtime.map(str::to_string),
+            loaded_by: loaded_by.to_string(),
+            recorded_at: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        };
+        conn.execute(
+            &format!("INSERT INTO config_history ({SNAPSHOT_COLUMNS}) VALUES (?, ?, ?, ?, ?, ?)"),
+            duckdb::params![
+                snapshot.content_hash,
+                snapshot.config_path,
+                snapshot.content,
+                snapshot.file_mtime,
+                snapshot.loaded_by,
+                snapshot.recorded_at,
+            ],
+        )?;
+        crate::annotations::insert_annotation(
+            &conn,
+            &NewAnnotation {
+                kind: CONFIG_KIND.to_string(),
+                scope: AnnotationScope::Fleet,
+                ts: None,
+                message: format!(
+                    "config loaded: {path} ({})",
+                    &snapshot.content_hash[..snapshot.content_hash.len().min(8)]
+                ),
+                source: INTERNAL_SOURCE.to_string(),
+            },
+            Some(loaded_by),
+        )?;
+        // Fewer than `keep` snapshots: the subquery is NULL and nothing goes
+        conn.execute(
+            &format!(
+                "DELETE FROM config_history WHERE recorded_at < (\
+                 SELECT recorded_at FROM config_history ORDER BY recorded_at DESC \
+                 LIMIT 1 OFFSET {})",
+                keep - 1
+            ),
+            [],
+        )?;
+        Ok(Some(snapshot))
+    }
+
+    /// The newest `limit` snapshots, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn config_snapshots(&self, limit: usize) -> Result<Vec<ConfigSnapshot>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        read_snapshots(
+            &conn,
+            &format!(
+                "SELECT {SNAPSHOT_COLUMNS} FROM config_history \
+                 ORDER BY recorded_at DESC LIMIT {limit}"
+            ),
+            &[],
+        )
+    }
+
+    /// The newest snapshot whose hash starts with `hash_prefix`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if the prefix matches snapshots of
+    /// different content, or [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn config_snapshot(&self, hash_prefix: &str) -> Result<Option<ConfigSnapshot>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let pattern = format!("{}%", hash_prefix.trim().to_lowercase());
+        let matches = read_snapshots(
+            &conn,
+            &format!(
+                "SELECT {SNAPSHOT_COLUMNS} FROM config_history WHERE content_hash LIKE ? \
+                 ORDER BY recorded_at DESC"
+            ),
+            &[pattern.as_str()],
+        )?;
+        if matches
+            .iter()
+            .any(|s| s.content_hash != matches[0].content_hash)
+        {
+            return Err(StoreError::QueryError(format!(
+                "config hash prefix '{hash_prefix}' is ambiguous"
+            )));
+        }
+        Ok(matches.into_iter().next())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshots_only_new_content_and_keeps_the_newest() {
+        let store = VcStore::open_memory().unwrap();
+        let record = |content: &str| {
+            store
+                .record_config_snapshot("/etc/vc/vc.toml", content, None, "vc status (pid 1)", 3)
+                .unwrap()
+        };
+
+        let first = record("a = 1\n").expect("first load is recorded");
+        assert!(record("a = 1\n").is_none(), "unchanged content");
+        record("a = 2\n").unwrap();
+        // Going back to earlier content is a change too
+        record("a = 1\n").unwrap();
+        record("a = 3\n").unwrap();
+
+        let snapshots = store.config_snapshots(10).unwrap();
+        let contents: Vec<&str> = snapshots.iter().map(|s| s.content.as_str()).collect();
+        assert_eq!(contents, vec!["a = 3\n", "a = 1\n", "a = 2\n"]);
+
+        let found = store
+            .config_snapshot(&first.content_hash[..8])
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.content, "a = 1\n");
+        assert!(store.config_snapshot("zzzz").unwrap().is_none());
+        assert!(matches!(
+            store.config_snapshot(""),
+            Err(StoreError::QueryError(_))
+        ));
+
+        assert!(
+            store
+                .record_config_snapshot("/etc/vc/vc.toml", "a = 4\n", None, "vc", 0)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
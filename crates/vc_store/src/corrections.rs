@@ -0,0 +1,839 @@
+//! Data corrections
+//!
+//! When a collector writes rows wrong (three days of sessions under the
+//! wrong `machine_id`, say) the fix is an `UPDATE`, but a raw one leaves no
+//! record of what it changed. [`VcStore::apply_correction`] runs it under
+//! supervision instead: the rows are picked by a filter the caller has
+//! validated as read-only, updated in batches, and every changed row keeps
+//! the original values of the corrected columns in `data_correction_rows`.
+//! The correction itself, with its full specification, goes into
+//! `data_corrections` and the audit log, and
+//! [`VcStore::undo_correction`] puts the original values back.
+//!
+//! A row is found again by its primary key as it is after the correction,
+//! or by its `rowid` in a table without one. Columns in a primary or unique
+//! key, and machine identity columns, are only corrected when the caller
+//! allows it: changing them changes which row is which.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::sql_tokens::{self, TokenKind};
+use crate::{
+    ActorContext, AuditEventType, AuditResult, StoreConnectionGuard, StoreError, VcStore,
+    collect_json_rows, escape_sql_identifier, escape_sql_literal, json_value_to_sql,
+};
+
+/// Rows a correction may change before `vc db correct` asks for `--confirm`
+pub const CONFIRM_ROWS: i64 = 100;
+
+/// Rows updated per transaction
+pub const DEFAULT_CORRECTION_BATCH: usize = 500;
+
+/// Before/after pairs shown by a preview
+pub const PREVIEW_SAMPLE: usize = 5;
+
+/// Tables that record what happened and are never corrected
+const PROTECTED_TABLES: &[&str] = &[
+    "audit_events",
+    "data_corrections",
+    "data_correction_rows",
+    "machine_identities",
+];
+
+/// Columns naming a machine's stable identity rather than its current id
+const IDENTITY_COLUMNS: &[&str] = &["machine_uuid"];
+
+/// What to correct: `assignments` set on the rows of `table` matching
+/// `where_clause`, optionally limited to a time window on the table's
+/// timestamp column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CorrectionSpec {
+    pub table: String,
+    pub where_clause: String,
+    /// New value per column, given as text and cast to the column's type
+    pub assignments: BTreeMap<String, String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+/// One sampled row, before and after the correction
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrectionSample {
+    pub before: serde_json::Value,
+    pub after: serde_json::Value,
+}
+
+/// What a correction would change
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrectionPreview {
+    pub table: String,
+    pub rows: i64,
+    /// Corrected columns that are key or machine identity columns
+    pub key_columns: Vec<String>,
+    pub sample: Vec<CorrectionSample>,
+}
+
+/// A recorded correction
+#[derive(Debug, Clone, Serialize)]
+pub struct Correction {
+    pub id: i64,
+    #[serde(flatten)]
+    pub spec: CorrectionSpec,
+    pub rows_changed: i64,
+    pub corrected_by: String,
+    pub corrected_at: String,
+    pub undone_by: Option<String>,
+    pub undone_at: Option<String>,
+}
+
+/// What [`VcStore::undo_correction`] did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CorrectionUndo {
+    pub id: i64,
+    pub restored: usize,
+    /// Rows gone, or changed again since the correction, and left alone
+    pub skipped: usize,
+}
+
+/// The rows a spec selects, and how to find them again
+struct Target {
+    filter: String,
+    primary_key: Vec<String>,
+    key_columns: Vec<String>,
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn quoted(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("\"{}\"", escape_sql_identifier(column)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn table_columns(conn: &StoreConnectionGuard<'_>, table: &str) -> Result<Vec<String>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT column_name FROM information_schema.columns \
+         WHERE table_schema = 'main' AND table_name = ? ORDER BY ordinal_position",
+    )?;
+    let columns = stmt
+        .query_map([table], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(columns)
+}
+
+/// Columns of `table`'s constraints of `kinds`, in key order
+fn constraint_columns(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    kinds: &str,
+) -> Result<Vec<String>, StoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT unnest(constraint_column_names) FROM duckdb_constraints() \
+         WHERE schema_name = 'main' AND table_name = ? AND constraint_type IN ({kinds})"
+    ))?;
+    let mut columns = Vec::new();
+    for column in stmt.query_map([table], |row| row.get::<_, String>(0))? {
+        let column = column?;
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    Ok(columns)
+}
+
+/// One batch of a correction, its rows numbered from `first_index`: back up
+/// the corrected columns of each row, then apply the assignments
+fn correct_batch(
+    conn: &StoreConnectionGuard<'_>,
+    id: i64,
+    first_index: usize,
+    rows: &[serde_json::Value],
+    spec: &CorrectionSpec,
+    target: &Target,
+) -> Result<usize, StoreError> {
+    let table = escape_sql_identifier(&spec.table);
+    let mut rowids = Vec::with_capacity(rows.len());
+    for (offset, row) in rows.iter().enumerate() {
+        let locator: serde_json::Map<String, serde_json::Value> = if target.primary_key.is_empty() {
+            std::iter::once(("rowid".to_string(), row["__rowid"].clone())).collect()
+        } else {
+            target
+                .primary_key
+                .iter()
+                .map(|column| {
+                    let value = spec.assignments.get(column).map_or_else(
+                        || row[column].clone(),
+                        |value| serde_json::Value::String(value.clone()),
+                    );
+                    (column.clone(), value)
+                })
+                .collect()
+        };
+        let original: serde_json::Map<String, serde_json::Value> = spec
+            .assignments
+            .keys()
+            .map(|column| (column.clone(), row[column].clone()))
+            .collect();
+        conn.execute(
+            "INSERT INTO data_correction_rows \
+             (correction_id, row_index, locator_json, original_json) VALUES (?, ?, ?, ?)",
+            duckdb::params![
+                id,
+                i64::try_from(first_index + offset).unwrap_or(i64::MAX),
+                serde_json::Value::Object(locator).to_string(),
+                serde_json::Value::Object(original).to_string()
+            ],
+        )?;
+        if let Some(rowid) = row["__rowid"].as_i64() {
+            rowids.push(rowid.to_string());
+        }
+    }
+
+    let set = spec
+        .assignments
+        .keys()
+        .map(|column| format!("\"{}\" = ?", escape_sql_identifier(column)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let changed = conn.execute(
+        &format!(
+            "UPDATE \"{table}\" SET {set} WHERE rowid IN ({})",
+            rowids.join(", ")
+        ),
+        duckdb::params_from_iter(spec.assignments.values()),
+    )?;
+    conn.execute(
+        "UPDATE data_corrections SET rows_changed = rows_changed + ? WHERE id = ?",
+        duckdb::params![i64::try_from(changed).unwrap_or(i64::MAX), id],
+    )?;
+    Ok(changed)
+}
+
+/// Put one row's original values back if it still holds the corrected ones
+fn restore_row(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    assignments: &BTreeMap<String, String>,
+    locator: &serde_json::Map<String, serde_json::Value>,
+    original: &serde_json::Map<String, serde_json::Value>,
+) -> Result<usize, StoreError> {
+    let mut params: Vec<Box<dyn duckdb::ToSql>> = Vec::new();
+    let mut set = Vec::new();
+    for (column, value) in original {
+        set.push(format!("\"{}\" = ?", escape_sql_identifier(column)));
+        params.push(json_value_to_sql(value));
+    }
+    let mut conditions = Vec::new();
+    for (column, value) in locator {
+        if column == "rowid" {
+            conditions.push("rowid = ?".to_string());
+        } else {
+            conditions.push(format!(
+                "\"{}\" IS NOT DISTINCT FROM ?",
+                escape_sql_identifier(column)
+            ));
+        }
+        params.push(json_value_to_sql(value));
+    }
+    for (column, value) in assignments {
+        conditions.push(format!(
+            "\"{}\" IS NOT DISTINCT FROM ?",
+            escape_sql_identifier(column)
+        ));
+        params.push(Box::new(value.clone()));
+    }
+    let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+    Ok(conn.execute(
+        &format!(
+            "UPDATE \"{}\" SET {} WHERE {}",
+            escape_sql_identifier(table),
+            set.join(", "),
+            conditions.join(" AND ")
+        ),
+        param_refs.as_slice(),
+    )?)
+}
+
+/// Check that a where clause stays inside the parentheses it is wrapped in
+///
+/// The filter is `(where) AND ts >= ...`, so a clause like `1=1) OR (1=1`
+/// would otherwise escape the `--since/--until` window. The clause is
+/// tokenized as DuckDB reads it, so parentheses in any kind of quoted text
+/// or comment don't count.
+fn check_where_clause(clause: &str) -> Result<(), StoreError> {
+    let invalid = |reason: &str| StoreError::QueryError(format!("where clause {reason}"));
+    let tokens = sql_tokens::tokenize(clause)
+        .map_err(|unterminated| invalid(&format!("has {} never closed", unterminated.construct)))?;
+    let mut depth = 0_usize;
+    for token in &tokens {
+        match token.kind {
+            TokenKind::Symbol(';') => return Err(invalid("cannot contain ';'")),
+            TokenKind::Symbol('(') => depth += 1,
+            TokenKind::Symbol(')') => {
+                depth = match depth.checked_sub(1) {
+                    Some(depth) => depth,
+                    None => return Err(invalid("has unbalanced parentheses")),
+                };
+            }
+            _ => {}
+        }
+    }
+    if depth == 0 {
+        Ok(())
+    } else {
+        Err(invalid("has unbalanced parentheses"))
+    }
+}
+
+impl VcStore {
+    /// Check a spec against the table it names and build its row filter
+    fn correction_target(&self, spec: &CorrectionSpec) -> Result<Target, StoreError> {
+        if spec.where_clause.trim().is_empty() {
+            return Err(StoreError::QueryError(
+                "a correction needs a where clause".to_string(),
+            ));
+        }
+        check_where_clause(&spec.where_clause)?;
+        if spec.assignments.is_empty() {
+            return Err(StoreError::QueryError(
+                "a correction needs at least one column to set".to_string(),
+            ));
+        }
+        if PROTECTED_TABLES.contains(&spec.table.as_str())
+            || !self.list_tables()?.contains(&spec.table)
+        {
+            return Err(StoreError::QueryError(format!(
+                "cannot correct table '{}'",
+                spec.table
+            )));
+        }
+
+        // On lines of its own, so a trailing `--` comment ends before the `)`
+        let mut filter = format!("(\n{}\n)", spec.where_clause);
+        if spec.since.is_some() || spec.until.is_some() {
+            let ts = self.guess_timestamp_column(&spec.table).ok_or_else(|| {
+                StoreError::QueryError(format!(
+                    "table '{}' has no timestamp column for --since/--until",
+                    spec.table
+                ))
+            })?;
+            let ts = escape_sql_identifier(&ts);
+            if let Some(since) = &spec.since {
+                let _ = write!(filter, " AND \"{ts}\" >= '{}'", escape_sql_literal(since));
+            }
+            if let Some(until) = &spec.until {
+                let _ = write!(filter, " AND \"{ts}\" < '{}'", escape_sql_literal(until));
+            }
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let columns = table_columns(&conn, &spec.table)?;
+        if let Some(unknown) = spec.assignments.keys().find(|c| !columns.contains(*c)) {
+            return Err(StoreError::QueryError(format!(
+                "table '{}' has no column '{unknown}'",
+                spec.table
+            )));
+        }
+        let primary_key = constraint_columns(&conn, &spec.table, "'PRIMARY KEY'")?;
+        let keys = constraint_columns(&conn, &spec.table, "'PRIMARY KEY', 'UNIQUE'")?;
+        let key_columns = spec
+            .assignments
+            .keys()
+            .filter(|c| keys.contains(*c) || IDENTITY_COLUMNS.contains(&c.as_str()))
+            .cloned()
+            .collect();
+        Ok(Target {
+            filter,
+            primary_key,
+            key_columns,
+        })
+    }
+
+    /// How many rows a correction would change, with a sample of them
+    /// before and after
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] for a spec naming an unknown or
+    /// protected table or column, or [`StoreError`] if a query fails (an
+    /// invalid where clause among them).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn preview_correction(
+        &self,
+        spec: &CorrectionSpec,
+        sample: usize,
+    ) -> Result<CorrectionPreview, StoreError> {
+        let target = self.correction_target(spec)?;
+        let table = escape_sql_identifier(&spec.table);
+        let conn = self.conn.lock().unwrap();
+        let rows: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{table}\" WHERE {}", target.filter),
+            [],
+            |row| row.get(0),
+        )?;
+
+        let mut shown = target.primary_key.clone();
+        shown.extend(
+            spec.assignments
+                .keys()
+                .filter(|c| !target.primary_key.contains(*c))
+                .cloned(),
+        );
+        let sample = collect_json_rows(
+            &conn,
+            &format!(
+                "SELECT {} FROM \"{table}\" WHERE {} LIMIT {sample}",
+                quoted(&shown),
+                target.filter
+            ),
+        )?
+        .into_iter()
+        .map(|before| {
+            let mut after = before.clone();
+            for (column, value) in &spec.assignments {
+                after[column] = serde_json::Value::String(value.clone());
+            }
+            CorrectionSample { before, after }
+        })
+        .collect();
+        Ok(CorrectionPreview {
+            table: spec.table.clone(),
+            rows,
+            key_columns: target.key_columns,
+            sample,
+        })
+    }
+
+    /// Apply a correction in batches of `batch_size` rows, each in its own
+    /// transaction, backing up the original values of every changed row and
+    /// recording the correction in the audit log. A failed batch is rolled
+    /// back; the batches before it stay applied and can be undone.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] for an invalid spec or one that
+    /// corrects key or machine identity columns without
+    /// `allow_key_columns`, or [`StoreError`] if a batch fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn apply_correction(
+        &self,
+        spec: &CorrectionSpec,
+        allow_key_columns: bool,
+        batch_size: usize,
+        actor: &ActorContext,
+    ) -> Result<Correction, StoreError> {
+        let target = self.correction_target(spec)?;
+        if !target.key_columns.is_empty() && !allow_key_columns {
+            return Err(StoreError::QueryError(format!(
+                "{} of '{}' identify rows or machines; correcting them needs \
+                 --allow-key-columns",
+                target.key_columns.join(", "),
+                spec.table
+            )));
+        }
+
+        let table = escape_sql_identifier(&spec.table);
+        let mut located = target.primary_key.clone();
+        located.extend(
+            spec.assignments
+                .keys()
+                .filter(|c| !target.primary_key.contains(*c))
+                .cloned(),
+        );
+        let conn = self.conn.lock().unwrap();
+        let rows = collect_json_rows(
+            &conn,
+            &format!(
+                "SELECT rowid AS \"__rowid\", {} FROM \"{table}\" WHERE {} ORDER BY rowid",
+                quoted(&located),
+                target.filter
+            ),
+        )?;
+        let id: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) + 1 FROM data_corrections",
+            [],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "INSERT INTO data_corrections \
+             (id, table_name, where_clause, assignments_json, since, until, \
+              corrected_by, corrected_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                id,
+                spec.table,
+                spec.where_clause,
+                serde_json::to_string(&spec.assignments)?,
+                spec.since,
+                spec.until,
+                actor.name,
+                now()
+            ],
+        )?;
+
+        let batch_size = batch_size.max(1);
+        let mut changed = 0;
+        let mut failure = None;
+        for (batch, chunk) in rows.chunks(batch_size).enumerate() {
+            conn.execute("BEGIN TRANSACTION", [])?;
+            match correct_batch(&conn, id, batch * batch_size, chunk, spec, &target) {
+                Ok(count) => {
+                    conn.execute("COMMIT", [])?;
+                    changed += count;
+                }
+                Err(e) => {
+                    let _ = conn.execute("ROLLBACK", []);
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        drop(conn);
+
+        let result = if failure.is_some() {
+            AuditResult::Failure
+        } else {
+            AuditResult::Success
+        };
+        self.insert_audit_event(&actor.audit_event(
+            AuditEventType::UserCommand,
+            "data_correction",
+            result,
+            serde_json::json!({
+                "correction_id": id,
+                "spec": spec,
+                "rows_matched": rows.len(),
+                "rows_changed": changed,
+                "error": failure.as_ref().map(ToString::to_string),
+            }),
+        ))?;
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        self.correction(id)?
+            .ok_or_else(|| StoreError::QueryError(format!("correction {id} was not recorded")))
+    }
+
+    /// Put back the original values of every row a correction changed.
+    /// Rows deleted or changed again since are left alone and counted as
+    /// skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] for an unknown or already undone
+    /// correction, or [`StoreError`] if the restore fails; it is rolled back
+    /// as a whole.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn undo_correction(
+        &self,
+        id: i64,
+        actor: &ActorContext,
+    ) -> Result<CorrectionUndo, StoreError> {
+        let correction = self
+            .correction(id)?
+            .ok_or_else(|| StoreError::QueryError(format!("no correction {id}")))?;
+        if let Some(undone_at) = &correction.undone_at {
+            return Err(StoreError::QueryError(format!(
+                "correction {id} was already undone at {undone_at}"
+            )));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let backups: Vec<(String, String)> = {
+            let mut stmt = conn.prepare(
+                "SELECT locator_json, original_json FROM data_correction_rows \
+                 WHERE correction_id = ? ORDER BY row_index",
+            )?;
+            stmt.query_map([id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut undo = CorrectionUndo {
+            id,
+            restored: 0,
+            skipped: 0,
+        };
+        conn.execute("BEGIN TRANSACTION", [])?;
+        let restored = (|| -> Result<(), StoreError> {
+            for (locator, original) in &backups {
+                let locator: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(locator)?;
+                let original: serde_json::Map<String, serde_json::Value> =
+                    serde_json::from_str(original)?;
+                let count = restore_row(
+                    &conn,
+                    &correction.spec.table,
+                    &correction.spec.assignments,
+                    &locator,
+                    &original,
+                )?;
+                if count == 0 {
+                    undo.skipped += 1;
+                } else {
+                    undo.restored += count;
+                }
+            }
+            conn.execute(
+                "UPDATE data_corrections SET undone_by = ?, undone_at = ? WHERE id = ?",
+                duckdb::params![actor.name, now(), id],
+            )?;
+            Ok(())
+        })();
+        if let Err(e) = restored {
+            let _ = conn.execute("ROLLBACK", []);
+            return Err(e);
+        }
+        conn.execute("COMMIT", [])?;
+        drop(conn);
+
+        self.insert_audit_event(&actor.audit_event(
+            AuditEventType::UserCommand,
+            "data_correction_undo",
+            AuditResult::Success,
+            serde_json::json!({
+                "correction_id": id,
+                "table": correction.spec.table,
+                "restored": undo.restored,
+                "skipped": undo.skipped,
+            }),
+        ))?;
+        Ok(undo)
+    }
+
+    /// One recorded correction
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn correction(&self, id: i64) -> Result<Option<Correction>, StoreError> {
+        Ok(self
+            .load_corrections(&format!("WHERE id = {id}"), 1)?
+            .into_iter()
+            .next())
+    }
+
+    /// Recorded corrections, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn list_corrections(&self, limit: usize) -> Result<Vec<Correction>, StoreError> {
+        self.load_corrections("", limit.clamp(1, 1000))
+    }
+
+    fn load_corrections(&self, filter: &str, limit: usize) -> Result<Vec<Correction>, StoreError> {
+        self.query_json(&format!(
+            "SELECT id, table_name, where_clause, assignments_json, since, until, \
+                    rows_changed, corrected_by, corrected_at, undone_by, undone_at \
+             FROM data_corrections {filter} ORDER BY id DESC LIMIT {limit}"
+        ))?
+        .into_iter()
+        .map(|row| -> Result<Correction, StoreError> {
+            let text = |key: &str| row[key].as_str().map(ToString::to_string);
+            Ok(Correction {
+                id: row["id"].as_i64().unwrap_or_default(),
+                spec: CorrectionSpec {
+                    table: text("table_name").unwrap_or_default(),
+                    where_clause: text("where_clause").unwrap_or_default(),
+                    assignments: serde_json::from_str(
+                        row["assignments_json"].as_str().unwrap_or("{}"),
+                    )?,
+                    since: text("since"),
+                    until: text("until"),
+                },
+                rows_changed: row["rows_changed"].as_i64().unwrap_or_default(),
+                corrected_by: text("corrected_by").unwrap_or_default(),
+                corrected_at: text("corrected_at").unwrap_or_default(),
+                undone_by: text("undone_by"),
+                undone_at: text("undone_at"),
+            })
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_sessions() -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        for (machine, session, collected) in [
+            ("wrong", "s1", "2026-10-01T10:00:00Z"),
+            ("wrong", "s2", "2026-10-02T10:00:00Z"),
+            ("wrong", "s3", "2026-10-05T10:00:00Z"),
+            ("orko", "s4", "2026-10-02T11:00:00Z"),
+        ] {
+            store
+                .execute_simple(&format!(
+                    "INSERT INTO agent_sessions (machine_id, session_id, program, collected_at) \
+                     VALUES ('{machine}', '{session}', 'claude', '{collected}')"
+                ))
+                .unwrap();
+        }
+        store
+    }
+
+    fn spec(set: &[(&str, &str)]) -> CorrectionSpec {
+        CorrectionSpec {
+            table: "agent_sessions".to_string(),
+            where_clause: "machine_id = 'wrong'".to_string(),
+            assignments: set
+                .iter()
+                .map(|(column, value)| ((*column).to_string(), (*value).to_string()))
+                .collect(),
+            since: None,
+            until: None,
+        }
+    }
+
+    fn count(store: &VcStore, filter: &str) -> i64 {
+        store
+            .query_scalar(&format!(
+                "SELECT COUNT(*) FROM agent_sessions WHERE {filter}"
+            ))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_preview_counts_and_samples_rows() {
+        let store = store_with_sessions();
+        let mut window = spec(&[("program", "codex")]);
+        window.until = Some("2026-10-03".to_string());
+        let preview = store.preview_correction(&window, PREVIEW_SAMPLE).unwrap();
+        assert_eq!(preview.rows, 2);
+        assert!(preview.key_columns.is_empty());
+        assert_eq!(preview.sample[0].before["program"], "claude");
+        assert_eq!(preview.sample[0].after["program"], "codex");
+
+        let keyed = store
+            .preview_correction(&spec(&[("machine_id", "orko")]), 1)
+            .unwrap();
+        assert_eq!(keyed.key_columns, vec!["machine_id"]);
+
+        let mut audit = spec(&[("program", "x")]);
+        audit.table = "audit_events".to_string();
+        assert!(store.preview_correction(&audit, 1).is_err());
+        assert!(
+            store
+                .preview_correction(&spec(&[("no_such_column", "x")]), 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_correction_applies_in_batches_and_undoes() {
+        let store = store_with_sessions();
+        let actor = ActorContext::system("test");
+
+        // machine_id is part of the primary key
+        assert!(
+            store
+                .apply_correction(&spec(&[("machine_id", "orko")]), false, 2, &actor)
+                .is_err()
+        );
+        assert_eq!(count(&store, "machine_id = 'wrong'"), 3);
+
+        let correction = store
+            .apply_correction(&spec(&[("machine_id", "orko")]), true, 2, &actor)
+            .unwrap();
+        assert_eq!(correction.rows_changed, 3);
+        assert_eq!(count(&store, "machine_id = 'orko'"), 4);
+        assert_eq!(store.list_corrections(10).unwrap().len(), 1);
+
+        // A row changed again after the correction is left alone
+        store
+            .execute_simple("UPDATE agent_sessions SET machine_id = 'mac' WHERE session_id = 's3'")
+            .unwrap();
+        let undo = store.undo_correction(correction.id, &actor).unwrap();
+        assert_eq!(undo.restored, 2);
+        assert_eq!(undo.skipped, 1);
+        assert_eq!(count(&store, "machine_id = 'wrong'"), 2);
+        assert!(store.undo_correction(correction.id, &actor).is_err());
+
+        let undone = store.correction(correction.id).unwrap().unwrap();
+        assert_eq!(undone.undone_by.as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn test_where_clause_cannot_escape_time_window() {
+        let store = store_with_sessions();
+        let actor = ActorContext::system("test");
+        for clause in [
+            "1=1) OR (1=1",
+            "machine_id = 'wrong')",
+            "(machine_id = 'wrong'",
+            "machine_id = 'wrong' /*",
+            "program = $$'$$) OR (program = $$'$$",
+            "program = $q$'$q$) OR (program = $q$'$q$",
+            "program = E'\\'') OR (program = E'\\''",
+            "machine_id = 'wrong'; DELETE FROM agent_sessions",
+        ] {
+            let mut escape = spec(&[("program", "codex")]);
+            escape.where_clause = clause.to_string();
+            escape.since = Some("2026-10-04".to_string());
+            assert!(store.preview_correction(&escape, 1).is_err(), "{clause}");
+            assert!(
+                store.apply_correction(&escape, false, 10, &actor).is_err(),
+                "{clause}"
+            );
+        }
+        assert_eq!(count(&store, "program = 'claude'"), 4);
+
+        // Parentheses inside quoted text are not counted
+        let mut quoted = spec(&[("program", "codex")]);
+        quoted.where_clause = "(machine_id = 'wrong' OR program = ')')".to_string();
+        quoted.since = Some("2026-10-04".to_string());
+        let preview = store.preview_correction(&quoted, 1).unwrap();
+        assert_eq!(preview.rows, 1);
+
+        // A trailing comment ends before the window is added
+        quoted.where_clause = "machine_id = 'wrong' --".to_string();
+        let preview = store.preview_correction(&quoted, 1).unwrap();
+        assert_eq!(preview.rows, 1);
+    }
+
+    #[test]
+    fn test_correction_without_primary_key_uses_rowid() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "INSERT INTO sys_top_processes (machine_id, collected_at, pid, comm) VALUES \
+                 ('wrong', '2026-10-01T10:00:00Z', 1, 'init'), \
+                 ('wrong', '2026-10-01T10:00:00Z', 2, 'sshd')",
+            )
+            .unwrap();
+        let fix = CorrectionSpec {
+            table: "sys_top_processes".to_string(),
+            where_clause: "machine_id = 'wrong'".to_string(),
+            assignments: BTreeMap::from([("machine_id".to_string(), "orko".to_string())]),
+            since: None,
+            until: None,
+        };
+        let actor = ActorContext::system("test");
+        let correction = store.apply_correction(&fix, false, 10, &actor).unwrap();
+        assert_eq!(correction.rows_changed, 2);
+        let undo = store.undo_correction(correction.id, &actor).unwrap();
+        assert_eq!(undo.restored, 2);
+        let wrong: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM sys_top_processes WHERE machine_id = 'wrong'")
+            .unwrap();
+        assert_eq!(wrong, 2);
+    }
+}
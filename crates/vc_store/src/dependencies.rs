@@ -0,0 +1,254 @@
+//! Cross-machine dependencies
+//!
+//! A dependency edge says `machine_id` relies on `depends_on` for storage,
+//! network or a service, e.g. every builder mounting the NFS server. The
+//! edges are directed and must stay acyclic: [`VcStore::add_machine_dependency`]
+//! refuses an edge that would close a loop, so walking downstream from a
+//! failing machine always ends.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// The kinds of dependency an edge can declare
+pub const DEPENDENCY_KINDS: &[&str] = &["storage", "network", "service"];
+
+/// One declared edge: `machine_id` depends on `depends_on`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineDependency {
+    pub machine_id: String,
+    pub depends_on: String,
+    /// One of [`DEPENDENCY_KINDS`]
+    pub kind: String,
+    pub created_at: String,
+}
+
+/// The path from `from` to `to` following the edges in `upstream_of`,
+/// both ends included, if there is one
+fn find_path(
+    upstream_of: &HashMap<String, Vec<String>>,
+    from: &str,
+    to: &str,
+) -> Option<Vec<String>> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![vec![from.to_string()]];
+    while let Some(path) = stack.pop() {
+        let last = path.last().expect("paths are never empty");
+        if last == to {
+            return Some(path);
+        }
+        if !seen.insert(last.clone()) {
+            continue;
+        }
+        for next in upstream_of.get(last).into_iter().flatten() {
+            let mut extended = path.clone();
+            extended.push(next.clone());
+            stack.push(extended);
+        }
+    }
+    None
+}
+
+impl VcStore {
+    /// Declare that `machine_id` depends on `depends_on`, or change the kind
+    /// of an existing declaration.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if the kind is unknown, either
+    /// machine is unknown, or the edge would make a cycle, or
+    /// [`StoreError`] if a query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn add_machine_dependency(
+        &self,
+        machine_id: &str,
+        depends_on: &str,
+        kind: &str,
+    ) -> Result<MachineDependency, StoreError> {
+        if !DEPENDENCY_KINDS.contains(&kind) {
+            return Err(StoreError::QueryError(format!(
+                "unknown dependency kind '{kind}' (expected one of: {})",
+                DEPENDENCY_KINDS.join(", ")
+            )));
+        }
+        if machine_id == depends_on {
+            return Err(StoreError::QueryError(format!(
+                "{machine_id} cannot depend on itself"
+            )));
+        }
+
+        let existing = self.machine_dependencies()?;
+        let conn = self.conn.lock().unwrap();
+        for id in [machine_id, depends_on] {
+            let known: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM machines WHERE machine_id = ?",
+                [id],
+                |row| row.get(0),
+            )?;
+            if known == 0 {
+                return Err(StoreError::QueryError(format!("unknown machine: {id}")));
+            }
+        }
+
+        let mut upstream_of: HashMap<String, Vec<String>> = HashMap::new();
+        for dep in &existing {
+            upstream_of
+                .entry(dep.machine_id.clone())
+                .or_default()
+                .push(dep.depends_on.clone());
+        }
+        if let Some(path) = find_path(&upstream_of, depends_on, machine_id) {
+            return Err(StoreError::QueryError(format!(
+                "{machine_id} -> {depends_on} would make a cycle: {machine_id} -> {}",
+                path.join(" -> ")
+            )));
+        }
+
+        let dependency = MachineDependency {
+            machine_id: machine_id.to_string(),
+            depends_on: depends_on.to_string(),
+            kind: kind.to_string(),
+            created_at: existing
+                .iter()
+                .find(|d| d.machine_id == machine_id && d.depends_on == depends_on)
+                .map_or_else(
+                    || Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+                    |d| d.created_at.clone(),
+                ),
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO machine_dependencies \
+             (machine_id, depends_on, kind, created_at) VALUES (?, ?, ?, ?)",
+            duckdb::params![
+                dependency.machine_id,
+                dependency.depends_on,
+                dependency.kind,
+                dependency.created_at,
+            ],
+        )?;
+        Ok(dependency)
+    }
+
+    /// Drop the declaration that `machine_id` depends on `depends_on`.
+    /// Returns whether there was one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the delete fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn remove_machine_dependency(
+        &self,
+        machine_id: &str,
+        depends_on: &str,
+    ) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute(
+            "DELETE FROM machine_dependencies WHERE machine_id = ? AND depends_on = ?",
+            [machine_id, depends_on],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Every declared dependency, ordered by machine then upstream
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_dependencies(&self) -> Result<Vec<MachineDependency>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT machine_id, depends_on, kind, created_at FROM machine_dependencies \
+             ORDER BY machine_id, depends_on",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MachineDependency {
+                machine_id: row.get(0)?,
+                depends_on: row.get(1)?,
+                kind: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with(machines: &[&str]) -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        for id in machines {
+            store
+                .execute_simple(&format!(
+                    "INSERT INTO machines (machine_id, hostname) VALUES ('{id}', '{id}')"
+                ))
+                .unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_dependencies_add_update_remove() {
+        let store = store_with(&["nfs", "builder-1", "builder-2"]);
+        store
+            .add_machine_dependency("builder-1", "nfs", "storage")
+            .unwrap();
+        let first = store
+            .add_machine_dependency("builder-2", "nfs", "network")
+            .unwrap();
+        let updated = store
+            .add_machine_dependency("builder-2", "nfs", "storage")
+            .unwrap();
+        assert_eq!(updated.created_at, first.created_at);
+
+        let deps = store.machine_dependencies().unwrap();
+        assert_eq!(deps.len(), 2);
+        assert!(
+            deps.iter()
+                .all(|d| d.depends_on == "nfs" && d.kind == "storage")
+        );
+
+        assert!(store.remove_machine_dependency("builder-1", "nfs").unwrap());
+        assert!(!store.remove_machine_dependency("builder-1", "nfs").unwrap());
+        assert_eq!(store.machine_dependencies().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_dependencies_reject_bad_edges() {
+        let store = store_with(&["a", "b", "c"]);
+        store.add_machine_dependency("a", "b", "service").unwrap();
+        store.add_machine_dependency("b", "c", "network").unwrap();
+
+        let err = store
+            .add_machine_dependency("c", "a", "service")
+            .unwrap_err();
+        assert!(
+            matches!(&err, StoreError::QueryError(msg) if msg.contains("c -> a -> b -> c")),
+            "{err:?}"
+        );
+        assert!(store.add_machine_dependency("a", "a", "service").is_err());
+        assert!(store.add_machine_dependency("a", "c", "power").is_err());
+        assert!(
+            store
+                .add_machine_dependency("a", "ghost", "service")
+                .is_err()
+        );
+        // A second path to the same upstream is not a cycle
+        store.add_machine_dependency("a", "c", "storage").unwrap();
+        assert_eq!(store.machine_dependencies().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,140 @@
+//! Export bundles
+//!
+//! Every `vc db export` records the directory it wrote, so the web API can
+//! offer the bundle for download. The recorded directory is also the
+//! boundary for serving: nothing outside a registered `output_dir` is
+//! reachable through `/api/exports`.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// One directory written by `vc db export`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportBundle {
+    pub bundle_id: String,
+    /// Absolute path of the export directory
+    pub output_dir: String,
+    /// `jsonl` or `csv`
+    pub format: String,
+    pub tables_exported: i64,
+    pub total_rows: i64,
+    pub created_at: String,
+}
+
+const COLUMNS: &str = "bundle_id, output_dir, format, tables_exported, total_rows, created_at";
+
+fn map_bundle(row: &duckdb::Row<'_>) -> duckdb::Result<ExportBundle> {
+    Ok(ExportBundle {
+        bundle_id: row.get(0)?,
+        output_dir: row.get(1)?,
+        format: row.get(2)?,
+        tables_exported: row.get(3)?,
+        total_rows: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+impl VcStore {
+    /// Record an export directory under `bundle_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_export_bundle(
+        &self,
+        bundle_id: &str,
+        output_dir: &str,
+        format: &str,
+        tables_exported: usize,
+        total_rows: usize,
+    ) -> Result<ExportBundle, StoreError> {
+        let bundle = ExportBundle {
+            bundle_id: bundle_id.to_string(),
+            output_dir: output_dir.to_string(),
+            format: format.to_string(),
+            tables_exported: i64::try_from(tables_exported).unwrap_or(i64::MAX),
+            total_rows: i64::try_from(total_rows).unwrap_or(i64::MAX),
+            created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!("INSERT INTO export_bundles ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?)"),
+            duckdb::params![
+                bundle.bundle_id,
+                bundle.output_dir,
+                bundle.format,
+                bundle.tables_exported,
+                bundle.total_rows,
+                bundle.created_at,
+            ],
+        )?;
+        Ok(bundle)
+    }
+
+    /// Recorded export bundles, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_export_bundles(&self, limit: usize) -> Result<Vec<ExportBundle>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM export_bundles ORDER BY created_at DESC, bundle_id LIMIT ?"
+        ))?;
+        let rows = stmt.query_map([i64::try_from(limit).unwrap_or(i64::MAX)], map_bundle)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Look up an export bundle
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn export_bundle(&self, bundle_id: &str) -> Result<Option<ExportBundle>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM export_bundles WHERE bundle_id = ?"
+        ))?;
+        let mut rows = stmt.query_map([bundle_id], map_bundle)?;
+        Ok(rows.next().transpose()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_list_export_bundles() {
+        let store = VcStore::open_memory().unwrap();
+        let bundle = store
+            .record_export_bundle("ex-1", "/var/lib/vc/exports/nightly", "jsonl", 12, 3400)
+            .unwrap();
+        store
+            .record_export_bundle("ex-0", "/var/lib/vc/exports/old", "csv", 1, 5)
+            .unwrap();
+
+        let listed = store.list_export_bundles(1).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(store.list_export_bundles(10).unwrap().len(), 2);
+        assert_eq!(
+            store.export_bundle(&bundle.bundle_id).unwrap(),
+            Some(bundle)
+        );
+        assert_eq!(store.export_bundle("ex-missing").unwrap(), None);
+    }
+}
@@ -0,0 +1,351 @@
+//! Declarative fleet applies
+//!
+//! `vc fleet apply` turns a desired-state file into spawn and stop steps,
+//! stored here under one apply id. Steps run in order and each one that
+//! runs becomes a `fleet_commands` row carrying the apply id. A failed step
+//! stays failed, and the steps after it pending, until `vc fleet resume`
+//! runs them again.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// One recorded apply
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FleetApply {
+    pub apply_id: String,
+    /// Path of the applied file
+    pub source: Option<String>,
+    /// The resolved desired state the plan was made from
+    pub desired_json: String,
+    /// `planned` (nothing ran yet), `partial` or `completed`
+    pub status: String,
+    pub created_at: String,
+    pub completed_at: Option<String>,
+}
+
+/// One step of an apply's plan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FleetApplyStep {
+    pub apply_id: String,
+    pub step: i64,
+    pub machine_id: String,
+    pub agent_type: String,
+    /// `spawn` or `stop`
+    pub action: String,
+    pub count: i64,
+    pub config_json: Option<String>,
+    /// `pending`, `completed` or `failed`
+    pub status: String,
+    /// The fleet command that carried the step out
+    pub command_id: Option<String>,
+    pub error: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+const STEP_COLUMNS: &str = "apply_id, step, machine_id, agent_type, action, count, config_json, \
+                            status, command_id, error, completed_at";
+
+const APPLY_COLUMNS: &str = "apply_id, source, desired_json, status, created_at, completed_at";
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+impl VcStore {
+    /// Record an apply and its plan. An empty plan is recorded as completed,
+    /// so the desired state is still there for drift checks.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if an insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn create_fleet_apply(
+        &self,
+        apply_id: &str,
+        source: Option<&str>,
+        desired_json: &str,
+        steps: &[FleetApplyStep],
+    ) -> Result<FleetApply, StoreError> {
+        let created_at = now();
+        let apply = FleetApply {
+            apply_id: apply_id.to_string(),
+            source: source.map(str::to_string),
+            desired_json: desired_json.to_string(),
+            status: if steps.is_empty() {
+                "completed"
+            } else {
+                "planned"
+            }
+            .to_string(),
+            completed_at: steps.is_empty().then(|| created_at.clone()),
+            created_at,
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!("INSERT INTO fleet_applies ({APPLY_COLUMNS}) VALUES (?, ?, ?, ?, ?, ?)"),
+            duckdb::params![
+                apply.apply_id,
+                apply.source,
+                apply.desired_json,
+                apply.status,
+                apply.created_at,
+                apply.completed_at,
+            ],
+        )?;
+        for (index, step) in steps.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO fleet_apply_steps \
+                 (apply_id, step, machine_id, agent_type, action, count, config_json, status) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 'pending')",
+                duckdb::params![
+                    apply_id,
+                    i64::try_from(index).unwrap_or(i64::MAX),
+                    step.machine_id,
+                    step.agent_type,
+                    step.action,
+                    step.count,
+                    step.config_json,
+                ],
+            )?;
+        }
+        Ok(apply)
+    }
+
+    /// Look up an apply
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn fleet_apply(&self, apply_id: &str) -> Result<Option<FleetApply>, StoreError> {
+        Ok(self
+            .query_applies("WHERE apply_id = ?", &[apply_id])?
+            .into_iter()
+            .next())
+    }
+
+    /// The most recent apply, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn latest_fleet_apply(&self) -> Result<Option<FleetApply>, StoreError> {
+        Ok(self
+            .query_applies("ORDER BY created_at DESC LIMIT 1", &[])?
+            .into_iter()
+            .next())
+    }
+
+    /// An apply's steps, in plan order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn fleet_apply_steps(&self, apply_id: &str) -> Result<Vec<FleetApplyStep>, StoreError> {
+        self.query_steps("WHERE apply_id = ? ORDER BY step", &[apply_id])
+    }
+
+    /// Steps of any apply completed at or after `since` (RFC 3339, UTC)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn completed_fleet_apply_steps_since(
+        &self,
+        since: &str,
+    ) -> Result<Vec<FleetApplyStep>, StoreError> {
+        self.query_steps(
+            "WHERE status = 'completed' AND completed_at >= ? ORDER BY completed_at",
+            &[since],
+        )
+    }
+
+    /// Record how a step went: `Ok(command_id)` for the fleet command that
+    /// carried it out, which is tied to the apply, or `Err(message)`. The
+    /// apply's status follows its steps.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if an update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn finish_fleet_apply_step(
+        &self,
+        apply_id: &str,
+        step: i64,
+        outcome: Result<&str, &str>,
+    ) -> Result<(), StoreError> {
+        let finished_at = now();
+        let conn = self.conn.lock().unwrap();
+        match outcome {
+            Ok(command_id) => {
+                conn.execute(
+                    "UPDATE fleet_apply_steps SET status = 'completed', command_id = ?, \
+                     error = NULL, completed_at = ? WHERE apply_id = ? AND step = ?",
+                    duckdb::params![command_id, finished_at, apply_id, step],
+                )?;
+                conn.execute(
+                    "UPDATE fleet_commands SET apply_id = ? WHERE command_id = ?",
+                    [apply_id, command_id],
+                )?;
+            }
+            Err(message) => {
+                conn.execute(
+                    "UPDATE fleet_apply_steps SET status = 'failed', error = ? \
+                     WHERE apply_id = ? AND step = ?",
+                    duckdb::params![message, apply_id, step],
+                )?;
+            }
+        }
+
+        let pending: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM fleet_apply_steps WHERE apply_id = ? AND status <> 'completed'",
+            [apply_id],
+            |row| row.get(0),
+        )?;
+        if pending == 0 {
+            conn.execute(
+                "UPDATE fleet_applies SET status = 'completed', completed_at = ? \
+                 WHERE apply_id = ?",
+                [finished_at.as_str(), apply_id],
+            )?;
+        } else {
+            conn.execute(
+                "UPDATE fleet_applies SET status = 'partial' WHERE apply_id = ?",
+                [apply_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn query_applies(&self, tail: &str, params: &[&str]) -> Result<Vec<FleetApply>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare(&format!("SELECT {APPLY_COLUMNS} FROM fleet_applies {tail}"))?;
+        let rows = stmt.query_map(duckdb::params_from_iter(params), |row| {
+            Ok(FleetApply {
+                apply_id: row.get(0)?,
+                source: row.get(1)?,
+                desired_json: row.get(2)?,
+                status: row.get(3)?,
+                created_at: row.get(4)?,
+                completed_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn query_steps(&self, tail: &str, params: &[&str]) -> Result<Vec<FleetApplyStep>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {STEP_COLUMNS} FROM fleet_apply_steps {tail}"
+        ))?;
+        let rows = stmt.query_map(duckdb::params_from_iter(params), |row| {
+            Ok(FleetApplyStep {
+                apply_id: row.get(0)?,
+                step: row.get(1)?,
+                machine_id: row.get(2)?,
+                agent_type: row.get(3)?,
+                action: row.get(4)?,
+                count: row.get(5)?,
+                config_json: row.get(6)?,
+                status: row.get(7)?,
+                command_id: row.get(8)?,
+                error: row.get(9)?,
+                completed_at: row.get(10)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(machine_id: &str, action: &str, count: i64) -> FleetApplyStep {
+        FleetApplyStep {
+            apply_id: String::new(),
+            step: 0,
+            machine_id: machine_id.to_string(),
+            agent_type: "claude".to_string(),
+            action: action.to_string(),
+            count,
+            config_json: None,
+            status: "pending".to_string(),
+            command_id: None,
+            error: None,
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_fleet_apply_steps_drive_status() {
+        let store = VcStore::open_memory().unwrap();
+        let apply = store
+            .create_fleet_apply(
+                "fa-1",
+                Some("fleet.yaml"),
+                "[]",
+                &[step("orko", "spawn", 2), step("mac", "stop", 1)],
+            )
+            .unwrap();
+        assert_eq!(apply.status, "planned");
+
+        store
+            .record_fleet_command("fc-1", "spawn", "{}", Some("fleet-apply"))
+            .unwrap();
+        store
+            .finish_fleet_apply_step("fa-1", 0, Ok("fc-1"))
+            .unwrap();
+        store
+            .finish_fleet_apply_step("fa-1", 1, Err("machine is in maintenance"))
+            .unwrap();
+        assert_eq!(
+            store.fleet_apply("fa-1").unwrap().unwrap().status,
+            "partial"
+        );
+        let commands = store.list_fleet_commands(Some("spawn"), 10).unwrap();
+        assert_eq!(commands[0]["apply_id"], "fa-1");
+
+        let steps = store.fleet_apply_steps("fa-1").unwrap();
+        assert_eq!(steps[0].status, "completed");
+        assert_eq!(steps[0].command_id.as_deref(), Some("fc-1"));
+        assert_eq!(steps[1].status, "failed");
+        assert_eq!(
+            store
+                .completed_fleet_apply_steps_since("2000-01-01T00:00:00Z")
+                .unwrap()
+                .len(),
+            1
+        );
+
+        store
+            .finish_fleet_apply_step("fa-1", 1, Ok("fc-2"))
+            .unwrap();
+        let apply = store.fleet_apply("fa-1").unwrap().unwrap();
+        assert_eq!(apply.status, "completed");
+        assert!(apply.completed_at.is_some());
+    }
+
+    #[test]
+    fn test_empty_fleet_apply_is_completed_and_latest() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(store.latest_fleet_apply().unwrap().is_none());
+        store
+            .create_fleet_apply("fa-1", None, "[]", &[step("orko", "spawn", 1)])
+            .unwrap();
+        let apply = store.create_fleet_apply("fa-2", None, "[]", &[]).unwrap();
+        assert_eq!(apply.status, "completed");
+        assert_eq!(
+            store.latest_fleet_apply().unwrap().unwrap().apply_id,
+            "fa-2"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! HTTP health check results
+//!
+//! The `http_check` collector appends one `http_check_results` row per
+//! configured check per collection. Readers mostly want the latest result
+//! of each check and how many times in a row it has failed, which is what
+//! separates a blip from a service that is down.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// Latest result of one HTTP check on one machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HttpCheckRecord {
+    pub machine_id: String,
+    pub check_name: String,
+    pub url: String,
+    pub method: String,
+    /// `machine` or `hub`: where the request was sent from
+    pub origin: String,
+    pub ok: bool,
+    pub status_code: Option<i64>,
+    pub latency_ms: Option<f64>,
+    /// Whether the body contained the configured text, when one is set
+    pub body_matched: Option<bool>,
+    pub error: Option<String>,
+    /// Collector failure cause, e.g. `timeout` or `connect_refused`
+    pub error_cause: Option<String>,
+    pub tls_expires_at: Option<String>,
+    pub tls_days_left: Option<i64>,
+    /// Set when the certificate expires within the check's warning window
+    pub tls_warning: Option<String>,
+    pub collected_at: String,
+    /// Failed results in a row, newest first; 0 when the latest passed
+    pub consecutive_failures: i64,
+}
+
+impl VcStore {
+    /// Latest result of every HTTP check, for one machine or the whole
+    /// fleet, ordered by machine and check name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn latest_http_checks(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<HttpCheckRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let filter = if machine_id.is_some() {
+            "WHERE machine_id = ?"
+        } else {
+            ""
+        };
+        let mut stmt = conn.prepare(&format!(
+            "WITH ranked AS ( \
+                 SELECT *, ROW_NUMBER() OVER ( \
+                     PARTITION BY machine_id, check_name ORDER BY collected_at DESC \
+                 ) AS rn \
+                 FROM http_check_results {filter} \
+             ), streaks AS ( \
+                 SELECT machine_id, check_name, \
+                        MIN(CASE WHEN ok <> 0 THEN rn END) AS first_ok, \
+                        MAX(rn) AS results \
+                 FROM ranked GROUP BY machine_id, check_name \
+             ) \
+             SELECT r.machine_id, r.check_name, r.url, r.method, r.origin, r.ok, \
+                    r.status_code, r.latency_ms, r.body_matched, r.error, r.error_cause, \
+                    r.tls_expires_at, r.tls_days_left, r.tls_warning, r.collected_at, \
+                    COALESCE(s.first_ok - 1, s.results) AS consecutive_failures \
+             FROM ranked r \
+             JOIN streaks s ON s.machine_id = r.machine_id AND s.check_name = r.check_name \
+             WHERE r.rn = 1 \
+             ORDER BY r.machine_id, r.check_name"
+        ))?;
+        let map_row = |row: &duckdb::Row<'_>| -> duckdb::Result<HttpCheckRecord> {
+            Ok(HttpCheckRecord {
+                machine_id: row.get(0)?,
+                check_name: row.get(1)?,
+                url: row.get(2)?,
+                method: row.get(3)?,
+                origin: row.get(4)?,
+                ok: row.get::<_, i32>(5)? != 0,
+                status_code: row.get(6)?,
+                latency_ms: row.get(7)?,
+                body_matched: row.get::<_, Option<i32>>(8)?.map(|matched| matched != 0),
+                error: row.get(9)?,
+                error_cause: row.get(10)?,
+                tls_expires_at: row.get(11)?,
+                tls_days_left: row.get(12)?,
+                tls_warning: row.get(13)?,
+                collected_at: row.get(14)?,
+                consecutive_failures: row.get(15)?,
+            })
+        };
+        let rows = match machine_id {
+            Some(id) => stmt.query_map([id], map_row)?,
+            None => stmt.query_map([], map_row)?,
+        };
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(store: &VcStore, machine_id: &str, check: &str, at: &str, ok: bool) {
+        store
+            .insert_json(
+                "http_check_results",
+                &serde_json::json!({
+                    "machine_id": machine_id,
+                    "collected_at": at,
+                    "check_name": check,
+                    "url": "http://localhost:8080/health",
+                    "method": "GET",
+                    "origin": "machine",
+                    "ok": i32::from(ok),
+                    "status_code": if ok { 200 } else { 503 },
+                    "latency_ms": 12.5,
+                }),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_latest_http_checks_count_failure_streaks() {
+        let store = VcStore::open_memory().unwrap();
+        record(&store, "orko", "model", "2026-01-01T00:00:00Z", true);
+        record(&store, "orko", "model", "2026-01-01T00:01:00Z", false);
+        record(&store, "orko", "model", "2026-01-01T00:02:00Z", false);
+        record(&store, "orko", "proxy", "2026-01-01T00:00:00Z", false);
+        record(&store, "orko", "proxy", "2026-01-01T00:01:00Z", true);
+        record(&store, "mac", "never-up", "2026-01-01T00:00:00Z", false);
+
+        let checks = store.latest_http_checks(None).unwrap();
+        let streaks: Vec<_> = checks
+            .iter()
+            .map(|c| {
+                (
+                    c.machine_id.as_str(),
+                    c.check_name.as_str(),
+                    c.ok,
+                    c.consecutive_failures,
+                )
+            })
+            .collect();
+        assert_eq!(
+            streaks,
+            vec![
+                ("mac", "never-up", false, 1),
+                ("orko", "model", false, 2),
+                ("orko", "proxy", true, 0),
+            ]
+        );
+        assert_eq!(checks[1].status_code, Some(503));
+        assert_eq!(checks[1].collected_at, "2026-01-01T00:02:00Z");
+        assert_eq!(store.latest_http_checks(Some("mac")).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,373 @@
+//! Advisory leases for single-writer roles
+//!
+//! A lease is a row in `leases` naming its holder, when the holder last
+//! heartbeated, and when the lease expires if it stops. Taking one is a
+//! single conditional upsert, so two processes racing for a free lease
+//! cannot both win: the row ends up naming one of them, and each reads it
+//! back to learn which. A lease past its expiry belongs to nobody and the
+//! next taker gets it; `takeover` takes it even before then, for a holder
+//! known to be dead.
+//!
+//! A store opened by a lease holder records the claim with
+//! [`VcStore::with_lease`]. Writers that must not interleave between two
+//! daemons (poll decisions and profiling samples) then refuse with
+//! [`StoreError::LeaseNotHeld`] once the lease has moved on. Stores without
+//! a claim, such as one-shot CLI commands, write as before.
+
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreConnectionGuard, StoreError, VcStore};
+
+/// Lease the daemon holds while it runs its poll loop
+pub const DAEMON_LEASE: &str = "daemon";
+
+/// The lease a store's writes are made under
+#[derive(Debug, Clone)]
+pub(crate) struct LeaseClaim {
+    name: String,
+    holder_id: String,
+}
+
+/// One lease row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Lease {
+    pub name: String,
+    pub holder_id: String,
+    pub hostname: Option<String>,
+    pub pid: Option<i64>,
+    pub acquired_at: String,
+    pub heartbeat_at: String,
+    pub expires_at: String,
+}
+
+impl Lease {
+    /// Whether the holder's last heartbeat still covers `now`
+    #[must_use]
+    pub fn is_live(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at > timestamp(now)
+    }
+}
+
+/// Result of [`VcStore::acquire_lease`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeaseOutcome {
+    /// The caller holds the lease
+    Acquired(Lease),
+    /// Someone else holds a live lease
+    Held(Lease),
+}
+
+/// Fixed-width RFC 3339 UTC, which sorts as text in time order
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn expiry(at: DateTime<Utc>, ttl: Duration) -> String {
+    let ttl = chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::days(365));
+    timestamp(at + ttl)
+}
+
+fn read_lease(conn: &StoreConnectionGuard<'_>, name: &str) -> Result<Option<Lease>, StoreError> {
+    let result = conn.query_row(
+        "SELECT name, holder_id, hostname, pid, acquired_at, heartbeat_at, expires_at \
+         FROM leases WHERE name = ?",
+        [name],
+        |row| {
+            Ok(Lease {
+                name: row.get(0)?,
+                holder_id: row.get(1)?,
+                hostname: row.get(2)?,
+                pid: row.get(3)?,
+                acquired_at: row.get(4)?,
+                heartbeat_at: row.get(5)?,
+                expires_at: row.get(6)?,
+            })
+        },
+    );
+    match result {
+        Ok(lease) => Ok(Some(lease)),
+        Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl VcStore {
+    /// Make this store's guarded writes under `holder_id`'s claim on the
+    /// lease `name`. Take the lease with [`Self::acquire_lease`] first.
+    #[must_use]
+    pub fn with_lease(self, name: &str, holder_id: &str) -> Self {
+        let _ = self.conn.shared.lease.set(LeaseClaim {
+            name: name.to_string(),
+            holder_id: holder_id.to_string(),
+        });
+        self
+    }
+
+    /// Take the lease `name` for `holder_id` until `ttl` from now, unless
+    /// another holder's lease is still live. `takeover` expires theirs.
+    /// Taking a lease already held by `holder_id` renews it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the upsert or reading it back fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn acquire_lease(
+        &self,
+        name: &str,
+        holder_id: &str,
+        hostname: Option<&str>,
+        ttl: Duration,
+        takeover: bool,
+    ) -> Result<LeaseOutcome, StoreError> {
+        let now = Utc::now();
+        let now_text = timestamp(now);
+        let expires_at = expiry(now, ttl);
+        let pid = i64::from(std::process::id());
+        // Takes a free or expired lease, keeps our own, and leaves a live
+        // one held by someone else untouched
+        let condition = if takeover {
+            ""
+        } else {
+            " WHERE leases.holder_id = excluded.holder_id \
+             OR leases.expires_at <= excluded.acquired_at"
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "INSERT INTO leases (name, holder_id, hostname, pid, acquired_at, heartbeat_at, \
+                 expires_at) VALUES (?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT (name) DO UPDATE SET holder_id = excluded.holder_id, \
+                 hostname = excluded.hostname, pid = excluded.pid, \
+                 acquired_at = CASE WHEN leases.holder_id = excluded.holder_id \
+                     THEN leases.acquired_at ELSE excluded.acquired_at END, \
+                 heartbeat_at = excluded.heartbeat_at, expires_at = excluded.expires_at\
+                 {condition}"
+            ),
+            duckdb::params![
+                name, holder_id, hostname, pid, now_text, now_text, expires_at
+            ],
+        )?;
+        let lease = read_lease(&conn, name)?
+            .ok_or_else(|| StoreError::QueryError(format!("lease {name} vanished")))?;
+        Ok(if lease.holder_id == holder_id {
+            LeaseOutcome::Acquired(lease)
+        } else {
+            LeaseOutcome::Held(lease)
+        })
+    }
+
+    /// Heartbeat `holder_id`'s lease `name`, pushing its expiry to `ttl` from
+    /// now. Returns `false` if the lease is no longer theirs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn renew_lease(
+        &self,
+        name: &str,
+        holder_id: &str,
+        ttl: Duration,
+    ) -> Result<bool, StoreError> {
+        let now = Utc::now();
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE leases SET heartbeat_at = ?, expires_at = ? \
+             WHERE name = ? AND holder_id = ?",
+            duckdb::params![timestamp(now), expiry(now, ttl), name, holder_id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Give up `holder_id`'s lease `name`, if they still hold it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the delete fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn release_lease(&self, name: &str, holder_id: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM leases WHERE name = ? AND holder_id = ?",
+            [name, holder_id],
+        )?;
+        Ok(())
+    }
+
+    /// The lease `name` as recorded, live or not
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn current_lease(&self, name: &str) -> Result<Option<Lease>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        read_lease(&conn, name)
+    }
+
+    /// Refuse a guarded write when this store writes under a lease claim the
+    /// lease no longer honours
+    pub(crate) fn check_lease(&self, conn: &StoreConnectionGuard<'_>) -> Result<(), StoreError> {
+        let Some(claim) = self.conn.shared.lease.get() else {
+            return Ok(());
+        };
+        match read_lease(conn, &claim.name)? {
+            Some(lease) if lease.holder_id == claim.holder_id && lease.is_live(Utc::now()) => {
+                Ok(())
+            }
+            Some(lease) if lease.holder_id != claim.holder_id => Err(StoreError::LeaseNotHeld(
+                format!("{} lease is held by {}", claim.name, lease.holder_id),
+            )),
+            _ => Err(StoreError::LeaseNotHeld(format!(
+                "{} lease has expired",
+                claim.name
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn held_by(outcome: &LeaseOutcome) -> (&str, bool) {
+        match outcome {
+            LeaseOutcome::Acquired(lease) => (lease.holder_id.as_str(), true),
+            LeaseOutcome::Held(lease) => (lease.holder_id.as_str(), false),
+        }
+    }
+
+    #[test]
+    fn test_live_lease_keeps_out_a_second_holder() {
+        let store = VcStore::open_memory().unwrap();
+        let first = store
+            .acquire_lease(DAEMON_LEASE, "a", Some("orko"), TTL, false)
+            .unwrap();
+        assert_eq!(held_by(&first), ("a", true));
+
+        let second = store
+            .acquire_lease(DAEMON_LEASE, "b", Some("orko"), TTL, false)
+            .unwrap();
+        assert_eq!(held_by(&second), ("a", false));
+
+        // Re-taking our own lease renews it and keeps when it was acquired
+        let again = store
+            .acquire_lease(DAEMON_LEASE, "a", Some("orko"), TTL, false)
+            .unwrap();
+        let (LeaseOutcome::Acquired(again), LeaseOutcome::Acquired(first)) = (again, first) else {
+            panic!("a should still hold the lease");
+        };
+        assert_eq!(again.acquired_at, first.acquired_at);
+
+        assert!(store.renew_lease(DAEMON_LEASE, "a", TTL).unwrap());
+        assert!(!store.renew_lease(DAEMON_LEASE, "b", TTL).unwrap());
+        store.release_lease(DAEMON_LEASE, "b").unwrap();
+        assert!(store.current_lease(DAEMON_LEASE).unwrap().is_some());
+        store.release_lease(DAEMON_LEASE, "a").unwrap();
+        assert!(store.current_lease(DAEMON_LEASE).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crashed_holder_lease_is_taken_cleanly() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .acquire_lease(DAEMON_LEASE, "crashed", Some("orko"), TTL, false)
+            .unwrap();
+        // The holder died; its last heartbeat ran out a minute ago
+        let stale = timestamp(Utc::now() - chrono::Duration::minutes(1));
+        store
+            .execute(
+                "UPDATE leases SET heartbeat_at = ?, expires_at = ? WHERE name = ?",
+                &[stale.as_str(), stale.as_str(), DAEMON_LEASE],
+            )
+            .unwrap();
+        assert!(
+            !store
+                .current_lease(DAEMON_LEASE)
+                .unwrap()
+                .unwrap()
+                .is_live(Utc::now())
+        );
+
+        let outcome = store
+            .acquire_lease(DAEMON_LEASE, "fresh", Some("orko"), TTL, false)
+            .unwrap();
+        let LeaseOutcome::Acquired(lease) = outcome else {
+            panic!("an expired lease should be free: {outcome:?}");
+        };
+        assert_eq!(lease.holder_id, "fresh");
+        assert_eq!(lease.pid, Some(i64::from(std::process::id())));
+        assert!(lease.is_live(Utc::now()));
+        assert!(lease.acquired_at > stale);
+
+        // The crashed holder cannot heartbeat its way back in
+        assert!(!store.renew_lease(DAEMON_LEASE, "crashed", TTL).unwrap());
+    }
+
+    #[test]
+    fn test_takeover_expires_a_live_lease() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .acquire_lease(DAEMON_LEASE, "hung", None, TTL, false)
+            .unwrap();
+        let outcome = store
+            .acquire_lease(DAEMON_LEASE, "new", None, TTL, true)
+            .unwrap();
+        assert_eq!(held_by(&outcome), ("new", true));
+    }
+
+    #[test]
+    fn test_guarded_writes_stop_once_the_lease_moves() {
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_lease(DAEMON_LEASE, "a");
+        store
+            .acquire_lease(DAEMON_LEASE, "a", None, TTL, false)
+            .unwrap();
+        store
+            .insert_poll_decision("orko", "sysmoni", 60, None)
+            .unwrap();
+        store
+            .insert_profile_sample("orko", "prof-1", None, None)
+            .unwrap();
+
+        store
+            .acquire_lease(DAEMON_LEASE, "b", None, TTL, true)
+            .unwrap();
+        assert!(matches!(
+            store.insert_poll_decision("orko", "sysmoni", 30, None),
+            Err(StoreError::LeaseNotHeld(_))
+        ));
+        assert!(matches!(
+            store.insert_profile_sample("orko", "prof-1", None, None),
+            Err(StoreError::LeaseNotHeld(_))
+        ));
+        assert_eq!(store.list_poll_decisions(None, 10).unwrap().len(), 1);
+
+        // A store without a claim is not held to the lease
+        let store = VcStore::open_memory().unwrap();
+        store
+            .acquire_lease(DAEMON_LEASE, "b", None, TTL, false)
+            .unwrap();
+        store
+            .insert_poll_decision("orko", "sysmoni", 60, None)
+            .unwrap();
+    }
+}
@@ -0,0 +1,501 @@
+//! Legal holds
+//!
+//! A hold exempts rows from vacuum: they are neither deleted for their age
+//! nor evicted by a table's size cap, and they do not count against that
+//! cap. A hold covers any of
+//!
+//! - an incident: rows carrying its `incident_id`, plus the alerts and
+//!   sessions its timeline events name in `details_json` (`alert_ids`,
+//!   `alert_id`, `session_ids`, `session_id`);
+//! - a session: rows carrying its `session_id`;
+//! - a machine: its rows, optionally only those inside `from_ts..=to_ts`.
+//!
+//! Holds are resolved per table when vacuum runs, so an alert linked to a
+//! held incident after the hold was created is covered too. Released holds
+//! stay in `legal_holds` for the record and cover nothing.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreConnectionGuard, StoreError, VcStore, escape_sql_literal};
+
+/// Rendering of hold time bounds, comparable with the vacuum cutoffs
+const HOLD_TS_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A recorded legal hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub hold_id: String,
+    pub reason: String,
+    pub incident_id: Option<String>,
+    pub session_id: Option<String>,
+    pub machine_id: Option<String>,
+    /// Start of the machine's held range; `None` is unbounded
+    pub from_ts: Option<String>,
+    /// End of the machine's held range; `None` is unbounded
+    pub to_ts: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    /// `None` while the hold is in force
+    pub released_at: Option<String>,
+    pub released_by: Option<String>,
+}
+
+impl LegalHold {
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.released_at.is_none()
+    }
+}
+
+/// What a new hold covers; at least one of incident, session or machine
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HoldScope {
+    pub incident_id: Option<String>,
+    pub session_id: Option<String>,
+    pub machine_id: Option<String>,
+    /// With `machine_id`: only rows at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// With `machine_id`: only rows at or before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl HoldScope {
+    fn validate(&self) -> Result<(), StoreError> {
+        if self.incident_id.is_none() && self.session_id.is_none() && self.machine_id.is_none() {
+            return Err(StoreError::QueryError(
+                "a legal hold needs an incident, a session or a machine".to_string(),
+            ));
+        }
+        if self.machine_id.is_none() && (self.from.is_some() || self.to.is_some()) {
+            return Err(StoreError::QueryError(
+                "a legal hold's time range applies to a machine; name one".to_string(),
+            ));
+        }
+        if let (Some(from), Some(to)) = (self.from, self.to)
+            && from > to
+        {
+            return Err(StoreError::QueryError(
+                "a legal hold's range ends before it starts".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+const COLUMNS: &str = "hold_id, reason, incident_id, session_id, machine_id, from_ts, to_ts, \
+                       created_by, created_at, released_at, released_by";
+
+fn map_hold(row: &duckdb::Row<'_>) -> duckdb::Result<LegalHold> {
+    Ok(LegalHold {
+        hold_id: row.get(0)?,
+        reason: row.get(1)?,
+        incident_id: row.get(2)?,
+        session_id: row.get(3)?,
+        machine_id: row.get(4)?,
+        from_ts: row.get(5)?,
+        to_ts: row.get(6)?,
+        created_by: row.get(7)?,
+        created_at: row.get(8)?,
+        released_at: row.get(9)?,
+        released_by: row.get(10)?,
+    })
+}
+
+fn quoted_list<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    values
+        .into_iter()
+        .map(|value| format!("'{}'", escape_sql_literal(value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Alert ids and session ids the timelines of `incidents` name
+fn incident_links(
+    conn: &StoreConnectionGuard<'_>,
+    incidents: &[&str],
+) -> Result<(Vec<i64>, Vec<String>), StoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT details_json FROM incident_timeline_events \
+         WHERE incident_id IN ({}) AND details_json IS NOT NULL",
+        quoted_list(incidents.iter().copied())
+    ))?;
+    let details = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut alerts = Vec::new();
+    let mut sessions = Vec::new();
+    for raw in details {
+        let Ok(details) = serde_json::from_str::<serde_json::Value>(&raw) else {
+            continue;
+        };
+        let listed = |one: &str, many: &str| {
+            details[many]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .chain(std::iter::once(&details[one]))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        alerts.extend(
+            listed("alert_id", "alert_ids")
+                .iter()
+                .filter_map(serde_json::Value::as_i64),
+        );
+        sessions.extend(
+            listed("session_id", "session_ids")
+                .iter()
+                .filter_map(|id| id.as_str().map(str::to_string)),
+        );
+    }
+    alerts.sort_unstable();
+    alerts.dedup();
+    sessions.sort();
+    sessions.dedup();
+    Ok((alerts, sessions))
+}
+
+/// SQL predicate true for the rows of `table` that `holds` cover, or `None`
+/// when they cover none of its rows. Never NULL, so `NOT (...)` keeps
+/// every row it does not cover deletable.
+pub(crate) fn held_predicate(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    ts_column: &str,
+    holds: &[LegalHold],
+) -> Result<Option<String>, StoreError> {
+    if holds.is_empty() {
+        return Ok(None);
+    }
+    let has_session = VcStore::has_column(conn, table, "session_id")?;
+    let mut clauses = Vec::new();
+
+    let incidents: Vec<&str> = holds
+        .iter()
+        .filter_map(|hold| hold.incident_id.as_deref())
+        .collect();
+    let mut sessions: Vec<String> = holds
+        .iter()
+        .filter_map(|hold| hold.session_id.clone())
+        .collect();
+    if !incidents.is_empty() {
+        if VcStore::has_column(conn, table, "incident_id")? {
+            clauses.push(format!(
+                "incident_id IN ({})",
+                quoted_list(incidents.iter().copied())
+            ));
+        }
+        if table == "alert_history" || has_session {
+            let (alerts, linked) = incident_links(conn, &incidents)?;
+            if table == "alert_history" && !alerts.is_empty() {
+                let ids: Vec<String> = alerts.iter().map(ToString::to_string).collect();
+                clauses.push(format!("id IN ({})", ids.join(", ")));
+            }
+            sessions.extend(linked);
+        }
+    }
+    if has_session && !sessions.is_empty() {
+        clauses.push(format!(
+            "session_id IN ({})",
+            quoted_list(sessions.iter().map(String::as_str))
+        ));
+    }
+
+    if VcStore::has_column(conn, table, "machine_id")? {
+        let ts = format!("TRY_CAST({ts_column} AS TIMESTAMP)");
+        for hold in holds {
+            let Some(machine) = &hold.machine_id else {
+                continue;
+            };
+            let mut clause = format!("machine_id = '{}'", escape_sql_literal(machine));
+            if let Some(from) = &hold.from_ts {
+                clause.push_str(&format!(
+                    " AND {ts} >= TIMESTAMP '{}'",
+                    escape_sql_literal(from)
+                ));
+            }
+            if let Some(to) = &hold.to_ts {
+                clause.push_str(&format!(
+                    " AND {ts} <= TIMESTAMP '{}'",
+                    escape_sql_literal(to)
+                ));
+            }
+            clauses.push(clause);
+        }
+    }
+
+    if clauses.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "COALESCE(({}), FALSE)",
+        clauses.join(") OR (")
+    )))
+}
+
+impl VcStore {
+    /// Record a legal hold under `hold_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if `scope` covers nothing or has
+    /// an inverted or machine-less time range, and [`StoreError`] if the
+    /// insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn create_legal_hold(
+        &self,
+        hold_id: &str,
+        reason: &str,
+        scope: &HoldScope,
+        created_by: &str,
+    ) -> Result<LegalHold, StoreError> {
+        scope.validate()?;
+        if reason.trim().is_empty() {
+            return Err(StoreError::QueryError(
+                "a legal hold needs a reason".to_string(),
+            ));
+        }
+        let bound = |ts: Option<DateTime<Utc>>| ts.map(|ts| ts.format(HOLD_TS_FORMAT).to_string());
+        let hold = LegalHold {
+            hold_id: hold_id.to_string(),
+            reason: reason.trim().to_string(),
+            incident_id: scope.incident_id.clone(),
+            session_id: scope.session_id.clone(),
+            machine_id: scope.machine_id.clone(),
+            from_ts: bound(scope.from),
+            to_ts: bound(scope.to),
+            created_by: Some(created_by.to_string()),
+            created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            released_at: None,
+            released_by: None,
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "INSERT INTO legal_holds ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ),
+            duckdb::params![
+                hold.hold_id,
+                hold.reason,
+                hold.incident_id,
+                hold.session_id,
+                hold.machine_id,
+                hold.from_ts,
+                hold.to_ts,
+                hold.created_by,
+                hold.created_at,
+                hold.released_at,
+                hold.released_by,
+            ],
+        )?;
+        Ok(hold)
+    }
+
+    /// Legal holds, newest first; released ones only with `include_released`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_legal_holds(&self, include_released: bool) -> Result<Vec<LegalHold>, StoreError> {
+        let filter = if include_released {
+            ""
+        } else {
+            "WHERE released_at IS NULL"
+        };
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM legal_holds {filter} ORDER BY created_at DESC, hold_id"
+        ))?;
+        let rows = stmt.query_map([], map_hold)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Look up a legal hold
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn legal_hold(&self, hold_id: &str) -> Result<Option<LegalHold>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM legal_holds WHERE hold_id = ?"
+        ))?;
+        let mut rows = stmt.query_map([hold_id], map_hold)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Release a hold in force; its rows age out again from the next vacuum.
+    /// Returns `None` if there is no such hold.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if the hold was already released,
+    /// and [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn release_legal_hold(
+        &self,
+        hold_id: &str,
+        released_by: &str,
+    ) -> Result<Option<LegalHold>, StoreError> {
+        let Some(mut hold) = self.legal_hold(hold_id)? else {
+            return Ok(None);
+        };
+        if let Some(at) = &hold.released_at {
+            return Err(StoreError::QueryError(format!(
+                "legal hold {hold_id} was already released at {at}"
+            )));
+        }
+        hold.released_at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+        hold.released_by = Some(released_by.to_string());
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE legal_holds SET released_at = ?, released_by = ? \
+             WHERE hold_id = ? AND released_at IS NULL",
+            duckdb::params![hold.released_at, hold.released_by, hold_id],
+        )?;
+        Ok(Some(hold))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(raw: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(raw)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_create_list_and_release_holds() {
+        let store = VcStore::open_memory().unwrap();
+        let scope = HoldScope {
+            machine_id: Some("orko".to_string()),
+            from: Some(ts("2026-09-01T00:00:00Z")),
+            ..HoldScope::default()
+        };
+        let hold = store
+            .create_legal_hold("hold-1", "billing dispute", &scope, "ops")
+            .unwrap();
+        assert_eq!(hold.from_ts.as_deref(), Some("2026-09-01 00:00:00"));
+        assert_eq!(store.list_legal_holds(false).unwrap(), vec![hold]);
+
+        let released = store.release_legal_hold("hold-1", "ops").unwrap().unwrap();
+        assert!(!released.is_active());
+        assert!(store.list_legal_holds(false).unwrap().is_empty());
+        assert_eq!(store.list_legal_holds(true).unwrap().len(), 1);
+        assert!(store.release_legal_hold("hold-1", "ops").is_err());
+        assert!(store.release_legal_hold("hold-x", "ops").unwrap().is_none());
+
+        let empty = HoldScope::default();
+        assert!(
+            store
+                .create_legal_hold("hold-2", "x", &empty, "ops")
+                .is_err()
+        );
+        let unanchored = HoldScope {
+            incident_id: Some("inc-1".to_string()),
+            to: Some(ts("2026-09-01T00:00:00Z")),
+            ..HoldScope::default()
+        };
+        assert!(
+            store
+                .create_legal_hold("hold-3", "x", &unanchored, "ops")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_vacuum_keeps_held_rows() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO incident_notes (id, incident_id, content, created_at) VALUES \
+                   (1, 'inc-1', 'held', '2020-01-01 00:00:00'), \
+                   (2, 'inc-2', 'not held', '2020-01-01 00:00:00'); \
+                 INSERT INTO incident_timeline_events (id, incident_id, ts, event_type, source, description, details_json) VALUES \
+                   (1, 'inc-1', '2020-01-01 00:00:00', 'note', 'ops', 'x', '{\"session_id\": \"s-2\"}'); \
+                 INSERT INTO agent_sessions (machine_id, session_id, collected_at) VALUES \
+                   ('orko', 's-1', '2020-01-01 00:00:00'), \
+                   ('orko', 's-2', '2020-01-01 00:00:00'), \
+                   ('orko', 's-3', '2020-01-01 00:00:00'), \
+                   ('bender', 's-4', '2020-01-05 00:00:00'), \
+                   ('bender', 's-5', '2020-03-01 00:00:00');",
+            )
+            .unwrap();
+        for table in ["incident_notes", "agent_sessions"] {
+            store.set_retention_policy(table, 30, None, true).unwrap();
+        }
+        let incident = HoldScope {
+            incident_id: Some("inc-1".to_string()),
+            session_id: Some("s-1".to_string()),
+            ..HoldScope::default()
+        };
+        store
+            .create_legal_hold("hold-1", "dispute", &incident, "ops")
+            .unwrap();
+        let machine = HoldScope {
+            machine_id: Some("bender".to_string()),
+            from: Some(ts("2020-01-01T00:00:00Z")),
+            to: Some(ts("2020-01-31T00:00:00Z")),
+            ..HoldScope::default()
+        };
+        store
+            .create_legal_hold("hold-2", "dispute", &machine, "ops")
+            .unwrap();
+
+        let results = store.run_vacuum(true, None).unwrap();
+        let result = |table: &str| {
+            results
+                .iter()
+                .find(|r| r.table_name == table)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(result("agent_sessions").rows_would_delete, 2);
+        assert_eq!(result("agent_sessions").rows_held, 3);
+        assert_eq!(result("incident_notes").rows_would_delete, 1);
+        assert_eq!(result("incident_notes").rows_held, 1);
+
+        store.run_vacuum(false, None).unwrap();
+        let sessions: Vec<String> = store
+            .query_json("SELECT session_id FROM agent_sessions ORDER BY session_id")
+            .unwrap()
+            .iter()
+            .filter_map(|row| row["session_id"].as_str().map(str::to_string))
+            .collect();
+        assert_eq!(sessions, ["s-1", "s-2", "s-4"]);
+        let notes: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM incident_notes WHERE incident_id = 'inc-1'")
+            .unwrap();
+        assert_eq!(notes, 1);
+        let history = store.list_vacuum_history(10).unwrap();
+        assert!(history.iter().any(|entry| entry["rows_held"] == 3));
+
+        // Released, the rows age out like any other
+        store.release_legal_hold("hold-1", "ops").unwrap();
+        store.release_legal_hold("hold-2", "ops").unwrap();
+        store.run_vacuum(false, None).unwrap();
+        let left: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM agent_sessions")
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+}
@@ -12,28 +12,166 @@
 //!
 //! This crate currently provides:
 //! - `DuckDB` connection management (live)
+//! - A [`StoreBackend`] trait over the generic query/append surface, with an
+//!   optional `SQLite` implementation (`sqlite` feature) for constrained nodes
 //! - Schema migrations (`DuckDB`-shaped today; `FrankenSQLite` shape
 //!   tracked in [`migrations`])
 //! - Data ingestion helpers
+//! - Offloading of old transcripts to a directory or S3-compatible bucket
 //! - Query utilities
+//! - Typed conversions from `query_json` rows ([`rows`])
 
 use chrono::{DateTime, Utc};
+#[cfg(feature = "duckdb")]
 use duckdb::Connection;
+#[cfg(feature = "duckdb")]
+use lease::LeaseClaim;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "duckdb")]
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+#[cfg(feature = "duckdb")]
 use std::fmt::Write as _;
+#[cfg(feature = "duckdb")]
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "duckdb")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "duckdb")]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(feature = "duckdb")]
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, Weak};
+use std::time::Duration;
+#[cfg(feature = "duckdb")]
+use std::time::Instant;
+#[cfg(feature = "duckdb")]
+use table_stats::ChangeCounter;
+#[cfg(feature = "duckdb")]
 use tempfile::TempDir;
 use thiserror::Error;
-use tracing::{info, instrument};
-
+#[cfg(feature = "duckdb")]
+use tracing::{info, instrument, warn};
+
+#[cfg(feature = "duckdb")]
+pub mod actor;
+#[cfg(feature = "duckdb")]
+pub mod agents_inventory;
+#[cfg(feature = "duckdb")]
+pub mod alert_resolution;
+#[cfg(feature = "duckdb")]
+pub mod annotations;
+#[cfg(feature = "duckdb")]
+pub mod artifacts;
+#[cfg(feature = "duckdb")]
+pub mod audit;
+pub mod backend;
+pub mod capabilities;
+#[cfg(feature = "duckdb")]
+pub mod config_history;
+#[cfg(feature = "duckdb")]
+pub mod corrections;
+#[cfg(feature = "duckdb")]
+pub mod dependencies;
+#[cfg(feature = "duckdb")]
+pub mod export_bundles;
+#[cfg(feature = "duckdb")]
+pub mod fleet_apply;
+#[cfg(feature = "duckdb")]
+pub mod http_checks;
+#[cfg(feature = "duckdb")]
+pub mod lease;
+#[cfg(feature = "duckdb")]
+pub mod legal_holds;
+#[cfg(feature = "duckdb")]
+pub mod machine_identity;
 pub mod migrations;
+#[cfg(feature = "duckdb")]
+pub mod operation_reports;
+#[cfg(feature = "duckdb")]
+pub mod query_log;
+#[cfg(feature = "duckdb")]
+pub mod query_proposals;
+#[cfg(feature = "duckdb")]
+pub mod replication;
+#[cfg(feature = "duckdb")]
+pub mod rows;
+#[cfg(feature = "duckdb")]
+pub mod sandbox_runs;
 pub mod schema;
+#[cfg(feature = "duckdb")]
+pub mod seed;
+pub mod sql_tokens;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "duckdb")]
+pub mod table_stats;
+#[cfg(feature = "duckdb")]
+pub mod write_limits;
+
+#[cfg(feature = "duckdb")]
+pub use actor::{ActorContext, ActorSource};
+#[cfg(feature = "duckdb")]
+pub use agents_inventory::AgentInventoryRecord;
+#[cfg(feature = "duckdb")]
+pub use alert_resolution::{ResolutionKind, ResolutionSourceCount};
+#[cfg(feature = "duckdb")]
+pub use annotations::{Annotation, AnnotationFilter, AnnotationScope, NewAnnotation};
+#[cfg(feature = "duckdb")]
+pub use artifacts::{
+    ArtifactBackend, ArtifactCheck, ArtifactPointer, ArtifactStats, ArtifactStatus, ArtifactStore,
+    OffloadSummary,
+};
+#[cfg(feature = "duckdb")]
+pub use audit::{AuditDurability, AuditWriter};
+pub use backend::{BackendKind, StoreBackend, open_backend};
+pub use capabilities::Capabilities;
+#[cfg(feature = "duckdb")]
+pub use config_history::ConfigSnapshot;
+#[cfg(feature = "duckdb")]
+pub use corrections::{
+    Correction, CorrectionPreview, CorrectionSample, CorrectionSpec, CorrectionUndo,
+};
+#[cfg(feature = "duckdb")]
+pub use dependencies::{DEPENDENCY_KINDS, MachineDependency};
+#[cfg(feature = "duckdb")]
+pub use export_bundles::ExportBundle;
+#[cfg(feature = "duckdb")]
+pub use fleet_apply::{FleetApply, FleetApplyStep};
+#[cfg(feature = "duckdb")]
+pub use http_checks::HttpCheckRecord;
+#[cfg(feature = "duckdb")]
+pub use lease::{DAEMON_LEASE, Lease, LeaseOutcome};
+#[cfg(feature = "duckdb")]
+pub use legal_holds::{HoldScope, LegalHold};
+#[cfg(feature = "duckdb")]
+pub use machine_identity::{MachineIdentity, MachineMerge, UuidConflict};
+#[cfg(feature = "duckdb")]
+pub use operation_reports::OperationReportRecord;
+#[cfg(feature = "duckdb")]
+pub use query_log::{QueryCaller, QueryLog, SlowQuery};
+#[cfg(feature = "duckdb")]
+pub use query_proposals::QueryProposal;
+#[cfg(feature = "duckdb")]
+pub use replication::{
+    Promotion, ReplicationAck, ReplicationBatch, ReplicationTableStatus, TableAck, TableBatch,
+    WatermarkSide,
+};
+#[cfg(feature = "duckdb")]
+pub use sandbox_runs::SandboxRunRecord;
+#[cfg(feature = "duckdb")]
+pub use seed::{SeedOptions, SeedProfile, SeedReport, seed_store};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+#[cfg(feature = "duckdb")]
+pub use table_stats::{AnalyzeResult, TableStats};
+#[cfg(feature = "duckdb")]
+pub use write_limits::{
+    ColumnClass, OversizePolicy, OversizedValue, OversizedWriteCount, WriteLimits, WriteOrigin,
+};
 
 /// Storage errors
 #[derive(Error, Debug)]
 pub enum StoreError {
+    #[cfg(feature = "duckdb")]
     #[error("Database error: {0}")]
     DatabaseError(#[from] duckdb::Error),
 
@@ -48,56 +186,133 @@ pub enum StoreError {
 
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error("SQLite error: {0}")]
+    SqliteError(#[from] rusqlite::Error),
+
+    #[error("Not supported: {0}")]
+    NotSupported(String),
+
+    #[error("Query cancelled after {0:?}")]
+    Timeout(Duration),
+
+    #[error("Lease not held: {0}")]
+    LeaseNotHeld(String),
+
+    #[error("Artifact store error: {0}")]
+    Artifact(String),
+
+    #[error("Row rejected by write limits: {0}")]
+    RowRejected(String),
+
+    #[error("Schema v{found} is newer than this build supports (v{supported})")]
+    SchemaTooNew { found: u32, supported: u32 },
 }
 
+#[cfg(feature = "duckdb")]
 const DUCKDB_SESSION_PRAGMAS: &str = r"
     PRAGMA threads=4;
     PRAGMA memory_limit='512MB';
 ";
 
+#[cfg(feature = "duckdb")]
 enum ConnectionSource {
     File(PathBuf),
     Temporary { path: PathBuf, _temp_dir: TempDir },
 }
 
+#[cfg(feature = "duckdb")]
 struct StoreConnectionShared {
     source: ConnectionSource,
     gate: Mutex<()>,
+    query_log: OnceLock<QueryLog>,
+    changes: ChangeCounter,
+    lease: OnceLock<LeaseClaim>,
+    artifacts: OnceLock<artifacts::ArtifactStore>,
+    write_limits: OnceLock<WriteLimits>,
 }
 
+/// Every store this process has opened, for [`VcStore::flush_open_stores`]
+#[cfg(feature = "duckdb")]
+static OPEN_STORES: Mutex<Vec<Weak<StoreConnectionShared>>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "duckdb")]
 #[derive(Clone)]
 pub struct StoreConnectionFactory {
     shared: Arc<StoreConnectionShared>,
 }
 
+#[cfg(feature = "duckdb")]
 pub struct StoreConnectionLockResult<'a>(StoreConnectionGuard<'a>);
 
+#[cfg(feature = "duckdb")]
 pub struct StoreConnectionGuard<'a> {
     _gate: MutexGuard<'a, ()>,
     conn: Option<Connection>,
     connection_error: RefCell<Option<duckdb::Error>>,
+    query_log: Option<&'a QueryLog>,
+    changes: &'a ChangeCounter,
+    write_limits: Option<&'a WriteLimits>,
+    /// Prepared statements, timed until the guard is released
+    prepared: RefCell<Vec<(String, Instant)>>,
 }
 
+#[cfg(feature = "duckdb")]
 impl StoreConnectionFactory {
     fn file(path: PathBuf) -> Self {
-        Self {
-            shared: Arc::new(StoreConnectionShared {
-                source: ConnectionSource::File(path),
-                gate: Mutex::new(()),
-            }),
-        }
+        Self::tracked(StoreConnectionShared {
+            source: ConnectionSource::File(path),
+            gate: Mutex::new(()),
+            query_log: OnceLock::new(),
+            changes: ChangeCounter::new(),
+            lease: OnceLock::new(),
+            artifacts: OnceLock::new(),
+            write_limits: OnceLock::new(),
+        })
     }
 
     fn temporary(temp_dir: TempDir, path: PathBuf) -> Self {
-        Self {
-            shared: Arc::new(StoreConnectionShared {
-                source: ConnectionSource::Temporary {
-                    path,
-                    _temp_dir: temp_dir,
-                },
-                gate: Mutex::new(()),
-            }),
+        Self::tracked(StoreConnectionShared {
+            source: ConnectionSource::Temporary {
+                path,
+                _temp_dir: temp_dir,
+            },
+            gate: Mutex::new(()),
+            query_log: OnceLock::new(),
+            changes: ChangeCounter::new(),
+            lease: OnceLock::new(),
+            artifacts: OnceLock::new(),
+            write_limits: OnceLock::new(),
+        })
+    }
+
+    /// Wrap `shared` and remember it in [`OPEN_STORES`]
+    fn tracked(shared: StoreConnectionShared) -> Self {
+        let shared = Arc::new(shared);
+        if let Ok(mut open) = OPEN_STORES.lock() {
+            open.retain(|store| store.strong_count() > 0);
+            open.push(Arc::downgrade(&shared));
         }
+        Self { shared }
+    }
+
+    /// Write what the store buffers in memory: the query log and the table
+    /// change counts. Both are attempted; the first error is returned.
+    fn flush(&self) -> Result<(), StoreError> {
+        let pending = self
+            .shared
+            .query_log
+            .get()
+            .is_some_and(|log| log.pending_len() > 0)
+            || self.shared.changes.pending_len() > 0;
+        if !pending {
+            return Ok(());
+        }
+        let conn = self.lock().unwrap();
+        let log = conn.flush_query_log();
+        let changes = conn.flush_table_changes();
+        log.and(changes).map(|_| ())
     }
 
     fn open_connection(&self) -> Result<Connection, duckdb::Error> {
@@ -121,10 +336,15 @@ impl StoreConnectionFactory {
             _gate: gate,
             conn,
             connection_error: RefCell::new(connection_error),
+            query_log: self.shared.query_log.get(),
+            changes: &self.shared.changes,
+            write_limits: self.shared.write_limits.get(),
+            prepared: RefCell::new(Vec::new()),
         })
     }
 }
 
+#[cfg(feature = "duckdb")]
 impl<'a> StoreConnectionLockResult<'a> {
     #[must_use]
     pub fn unwrap(self) -> StoreConnectionGuard<'a> {
@@ -140,17 +360,22 @@ impl<'a> StoreConnectionLockResult<'a> {
     }
 }
 
+#[cfg(feature = "duckdb")]
 #[allow(clippy::elidable_lifetime_names)]
 impl<'a> StoreConnectionGuard<'a> {
     fn into_result(self) -> Result<Self, StoreError> {
         if self.conn.is_some() {
             Ok(self)
         } else {
-            let error = self.connection_error.into_inner().unwrap_or_else(|| {
-                duckdb::Error::InvalidParameterName(
-                    "database connection could not be opened".to_string(),
-                )
-            });
+            let error = self
+                .connection_error
+                .borrow_mut()
+                .take()
+                .unwrap_or_else(|| {
+                    duckdb::Error::InvalidParameterName(
+                        "database connection could not be opened".to_string(),
+                    )
+                });
             Err(StoreError::DatabaseError(error))
         }
     }
@@ -166,12 +391,60 @@ impl<'a> StoreConnectionGuard<'a> {
             })
     }
 
+    /// The limits writes over this connection are held to
+    fn write_limits(&self) -> WriteLimits {
+        self.write_limits.copied().unwrap_or_default()
+    }
+
+    /// Add a finished query to the store's query log, if it keeps one
+    fn record_query(&self, sql: &str, started: Instant, rows: Option<u64>) {
+        if let Some(log) = self.query_log {
+            log.record(sql, started.elapsed(), rows);
+        }
+    }
+
+    /// Write the query log's buffer over this connection. Returns the number
+    /// of queries written.
+    fn flush_query_log(&self) -> Result<usize, StoreError> {
+        let Some((sql, count)) = self.query_log.and_then(QueryLog::take_flush_batch) else {
+            return Ok(0);
+        };
+        match self.conn.as_ref() {
+            Some(conn) => conn.execute_batch(&sql)?,
+            None => return Err(StoreError::DatabaseError(self.take_connection_error())),
+        }
+        Ok(count)
+    }
+
+    /// Add the buffered change counts to `table_stats` over this connection.
+    /// Returns the number of tables written.
+    fn flush_table_changes(&self) -> Result<usize, StoreError> {
+        let Some((sql, count)) = self.changes.take_flush_batch() else {
+            return Ok(0);
+        };
+        match self.conn.as_ref() {
+            Some(conn) => conn.execute_batch(&sql)?,
+            None => return Err(StoreError::DatabaseError(self.take_connection_error())),
+        }
+        Ok(count)
+    }
+
     pub(crate) fn execute<P>(&self, sql: &str, params: P) -> Result<usize, duckdb::Error>
     where
         P: duckdb::Params,
     {
         if let Some(conn) = self.conn.as_ref() {
-            conn.execute(sql, params)
+            let started = Instant::now();
+            let result = conn.execute(sql, params);
+            let rows = result
+                .as_ref()
+                .ok()
+                .and_then(|&rows| u64::try_from(rows).ok());
+            self.record_query(sql, started, rows);
+            if let Some(rows) = rows {
+                self.changes.note_statement(sql, rows);
+            }
+            result
         } else {
             Err(self.take_connection_error())
         }
@@ -179,7 +452,10 @@ impl<'a> StoreConnectionGuard<'a> {
 
     pub(crate) fn execute_batch(&self, sql: &str) -> Result<(), duckdb::Error> {
         if let Some(conn) = self.conn.as_ref() {
-            conn.execute_batch(sql)
+            let started = Instant::now();
+            let result = conn.execute_batch(sql);
+            self.record_query(sql, started, None);
+            result
         } else {
             Err(self.take_connection_error())
         }
@@ -188,6 +464,20 @@ impl<'a> StoreConnectionGuard<'a> {
     pub(crate) fn prepare<'conn>(
         &'conn self,
         sql: &str,
+    ) -> Result<duckdb::Statement<'conn>, duckdb::Error> {
+        if self.query_log.is_some() {
+            self.prepared
+                .borrow_mut()
+                .push((sql.to_string(), Instant::now()));
+        }
+        self.prepare_untimed(sql)
+    }
+
+    /// Prepare without timing the statement, for callers that record it
+    /// themselves
+    fn prepare_untimed<'conn>(
+        &'conn self,
+        sql: &str,
     ) -> Result<duckdb::Statement<'conn>, duckdb::Error> {
         if let Some(conn) = self.conn.as_ref() {
             conn.prepare(sql)
@@ -196,6 +486,25 @@ impl<'a> StoreConnectionGuard<'a> {
         }
     }
 
+    /// Interrupt whatever this connection is running once `timeout` elapses.
+    /// Dropping the returned sender disarms the watchdog; `fired` records
+    /// whether it went off.
+    pub(crate) fn interrupt_after(
+        &self,
+        timeout: Duration,
+        fired: Arc<AtomicBool>,
+    ) -> Option<mpsc::Sender<()>> {
+        let handle = self.conn.as_ref()?.interrupt_handle();
+        let (disarm, armed) = mpsc::channel::<()>();
+        std::thread::spawn(move || {
+            if armed.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout) {
+                fired.store(true, Ordering::SeqCst);
+                handle.interrupt();
+            }
+        });
+        Some(disarm)
+    }
+
     #[allow(clippy::missing_errors_doc)]
     pub fn query_row<T, P, F>(&self, sql: &str, params: P, f: F) -> Result<T, duckdb::Error>
     where
@@ -203,21 +512,179 @@ impl<'a> StoreConnectionGuard<'a> {
         F: FnOnce(&duckdb::Row<'_>) -> Result<T, duckdb::Error>,
     {
         if let Some(conn) = self.conn.as_ref() {
-            conn.query_row(sql, params, f)
+            let started = Instant::now();
+            let result = conn.query_row(sql, params, f);
+            let rows = match &result {
+                Ok(_) => Some(1),
+                Err(duckdb::Error::QueryReturnedNoRows) => Some(0),
+                Err(_) => None,
+            };
+            self.record_query(sql, started, rows);
+            result
         } else {
             Err(self.take_connection_error())
         }
     }
 }
 
+#[cfg(feature = "duckdb")]
+impl Drop for StoreConnectionGuard<'_> {
+    fn drop(&mut self) {
+        if self.changes.flush_due()
+            && let Err(err) = self.flush_table_changes()
+        {
+            warn!(error = %err, "failed to write table change counts");
+        }
+        let Some(log) = self.query_log else {
+            return;
+        };
+        for (sql, started) in self.prepared.get_mut().drain(..) {
+            log.record(&sql, started.elapsed(), None);
+        }
+        if log.flush_due()
+            && let Err(err) = self.flush_query_log()
+        {
+            warn!(error = %err, "failed to write the query log");
+        }
+    }
+}
+
+/// Whether a batch with this content hash was applied; rows from before
+/// batch outcomes were recorded have no status and were all applied
+#[cfg(feature = "duckdb")]
+fn ingest_applied(conn: &StoreConnectionGuard<'_>, content_hash: &str) -> bool {
+    conn.query_row(
+        "SELECT COUNT(*) FROM node_ingest_log \
+         WHERE content_hash = ? AND COALESCE(status, 'applied') = 'applied'",
+        [content_hash],
+        |row| row.get::<_, i64>(0),
+    )
+    .unwrap_or(0)
+        > 0
+}
+
+#[cfg(feature = "duckdb")]
+fn insert_ingest_log(
+    conn: &StoreConnectionGuard<'_>,
+    batch: &IngestBatch<'_>,
+    status: &str,
+    error: Option<&str>,
+) -> Result<(), StoreError> {
+    let next_id: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(id), 0) + 1 FROM node_ingest_log",
+        [],
+        |row| row.get(0),
+    )?;
+    conn.execute(
+        "INSERT INTO node_ingest_log \
+         (id, bundle_id, batch_id, machine_id, collector, content_hash, row_count, status, error) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        duckdb::params![
+            next_id,
+            batch.bundle_id,
+            batch.batch_id,
+            batch.machine_id,
+            batch.collector,
+            batch.content_hash,
+            i64::try_from(batch.row_count).unwrap_or(i64::MAX),
+            status,
+            error
+        ],
+    )?;
+    Ok(())
+}
+
+/// The body of [`VcStore::apply_ingest_batch`], run inside its transaction
+#[cfg(feature = "duckdb")]
+fn write_ingest_batch(
+    conn: &StoreConnectionGuard<'_>,
+    batch: &IngestBatch<'_>,
+    columns: &std::collections::HashSet<String>,
+) -> Result<AppliedIngestBatch, StoreError> {
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut applied = AppliedIngestBatch::default();
+    if batch.table == schema::tables::AGENTS_INVENTORY {
+        // A node's inventory report is a full picture of the node, reconciled
+        // rather than appended
+        let observed_at = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        applied.rows_inserted =
+            agents_inventory::reconcile(conn, batch.machine_id, &observed_at, batch.rows)?;
+        insert_ingest_log(conn, batch, "applied", None)?;
+        return Ok(applied);
+    }
+    for (index, row) in batch.rows.iter().enumerate() {
+        let serde_json::Value::Object(map) = row else {
+            applied.rows_skipped += 1;
+            continue;
+        };
+        if map.is_empty() || map.keys().any(|key| !columns.contains(key)) {
+            applied.rows_skipped += 1;
+            continue;
+        }
+
+        let origin = WriteOrigin::collector(batch.machine_id, batch.collector);
+        if !write_limits::write_row(conn, "INSERT INTO", batch.table, map, origin)? {
+            applied.rows_skipped += 1;
+            continue;
+        }
+        applied.rows_inserted += 1;
+
+        if let Some(hash) = batch.row_hashes.get(index) {
+            conn.execute(
+                "INSERT OR REPLACE INTO ingest_row_hashes \
+                 (table_name, content_hash, machine_id, seen_at) VALUES (?, ?, ?, ?)",
+                [batch.table, hash.as_str(), batch.machine_id, now.as_str()],
+            )?;
+        }
+    }
+    insert_ingest_log(conn, batch, "applied", None)?;
+    Ok(applied)
+}
+
+/// Run `sql` and return each row as a JSON object via `DuckDB`'s `to_json()`
+#[cfg(feature = "duckdb")]
+fn collect_json_rows(
+    conn: &StoreConnectionGuard<'_>,
+    sql: &str,
+) -> Result<Vec<serde_json::Value>, StoreError> {
+    let started = Instant::now();
+    let result = read_json_rows(conn, sql);
+    let rows = result
+        .as_ref()
+        .ok()
+        .and_then(|rows| u64::try_from(rows.len()).ok());
+    conn.record_query(sql, started, rows);
+    result
+}
+
+#[cfg(feature = "duckdb")]
+fn read_json_rows(
+    conn: &StoreConnectionGuard<'_>,
+    sql: &str,
+) -> Result<Vec<serde_json::Value>, StoreError> {
+    let json_sql = format!("SELECT to_json(_row) FROM ({sql}) AS _row");
+
+    let mut stmt = conn.prepare_untimed(&json_sql)?;
+    let mut rows = stmt.query([])?;
+
+    let mut results = Vec::new();
+    while let Some(row) = rows.next()? {
+        let json_str: String = row.get(0)?;
+        let value: serde_json::Value = serde_json::from_str(&json_str)?;
+        results.push(value);
+    }
+    Ok(results)
+}
+
 /// Audit event categories
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum AuditEventType {
     CollectorRun,
     AutopilotAction,
     UserCommand,
     GuardianAction,
+    AlertEscalation,
 }
 
 impl AuditEventType {
@@ -228,8 +695,16 @@ impl AuditEventType {
             AuditEventType::AutopilotAction => "autopilot_action",
             AuditEventType::UserCommand => "user_command",
             AuditEventType::GuardianAction => "guardian_action",
+            AuditEventType::AlertEscalation => "alert_escalation",
         }
     }
+
+    /// Whether events of this type must always be written individually and
+    /// synchronously (approvals, autopilot actions, user commands)
+    #[must_use]
+    pub fn is_sensitive(&self) -> bool {
+        !matches!(self, AuditEventType::CollectorRun)
+    }
 }
 
 impl std::str::FromStr for AuditEventType {
@@ -241,6 +716,7 @@ impl std::str::FromStr for AuditEventType {
             "autopilot_action" => Ok(AuditEventType::AutopilotAction),
             "user_command" => Ok(AuditEventType::UserCommand),
             "guardian_action" => Ok(AuditEventType::GuardianAction),
+            "alert_escalation" => Ok(AuditEventType::AlertEscalation),
             other => Err(format!("unknown audit event type: {other}")),
         }
     }
@@ -280,17 +756,22 @@ impl std::str::FromStr for AuditResult {
 }
 
 /// Audit event payload
+#[cfg(feature = "duckdb")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
     pub ts: DateTime<Utc>,
     pub event_type: AuditEventType,
     pub actor: String,
+    /// Frontend the actor came through; `None` for events not tied to one
+    #[serde(default)]
+    pub via: Option<QueryCaller>,
     pub machine_id: Option<String>,
     pub action: String,
     pub result: AuditResult,
     pub details: serde_json::Value,
 }
 
+#[cfg(feature = "duckdb")]
 impl AuditEvent {
     pub fn new(
         event_type: AuditEventType,
@@ -303,6 +784,7 @@ impl AuditEvent {
             ts: Utc::now(),
             event_type,
             actor: actor.into(),
+            via: None,
             machine_id: None,
             action: action.into(),
             result,
@@ -322,6 +804,7 @@ impl AuditEvent {
 pub struct AuditEventFilter {
     pub event_type: Option<AuditEventType>,
     pub machine_id: Option<String>,
+    pub actor: Option<String>,
     pub since: Option<DateTime<Utc>>,
     pub limit: usize,
 }
@@ -330,6 +813,7 @@ pub struct AuditEventFilter {
 ///
 /// Implement this for collector runs, guardian actions, autopilot executions,
 /// and any other actionable types to enable uniform audit logging.
+#[cfg(feature = "duckdb")]
 pub trait Auditable {
     fn to_audit_event(&self) -> AuditEvent;
 }
@@ -343,6 +827,181 @@ pub struct RetentionPolicy {
     pub aggregate_table: Option<String>,
     pub enabled: bool,
     pub last_vacuum_at: Option<String>,
+    /// Cap on the table's total text bytes; vacuum deletes oldest rows past it
+    pub max_total_bytes: Option<i64>,
+    /// Cap on the text bytes of one row; enforced at write time
+    pub max_row_bytes: Option<i64>,
+    /// Bytes kept from the start of a value cut by `max_row_bytes`
+    pub keep_head_bytes: i64,
+    /// Bytes kept from the end of a value cut by `max_row_bytes`
+    pub keep_tail_bytes: i64,
+    /// Machines the policy covers (`tag:<tag>` or `machines:<id>,...`);
+    /// `None` covers the whole table
+    pub scope: Option<String>,
+}
+
+impl RetentionPolicy {
+    /// Write-time row limit, if the policy is enabled and sets `max_row_bytes`
+    #[must_use]
+    pub fn row_size_limit(&self) -> Option<RowSizeLimit> {
+        let max_row_bytes = self.max_row_bytes.filter(|_| self.enabled)?;
+        Some(RowSizeLimit {
+            max_row_bytes: usize::try_from(max_row_bytes).unwrap_or(0),
+            keep_head_bytes: usize::try_from(self.keep_head_bytes).unwrap_or(0),
+            keep_tail_bytes: usize::try_from(self.keep_tail_bytes).unwrap_or(0),
+        })
+    }
+}
+
+/// Machines a scoped retention policy covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetentionScope {
+    /// Every machine carrying the tag
+    Tag(String),
+    /// An explicit machine list
+    Machines(Vec<String>),
+}
+
+impl RetentionScope {
+    /// Higher wins when several policies match a machine
+    #[must_use]
+    pub fn specificity(&self) -> u8 {
+        match self {
+            Self::Tag(_) => 1,
+            Self::Machines(_) => 2,
+        }
+    }
+
+    #[must_use]
+    pub fn matches(&self, machine_id: &str, tags: &[String]) -> bool {
+        match self {
+            Self::Tag(tag) => tags.iter().any(|t| t == tag),
+            Self::Machines(machines) => machines.iter().any(|m| m == machine_id),
+        }
+    }
+}
+
+impl std::str::FromStr for RetentionScope {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = value
+            .split_once(':')
+            .ok_or_else(|| format!("scope must be tag:<tag> or machines:<id>,...: {value}"))?;
+        match kind.trim() {
+            "tag" if !rest.trim().is_empty() => Ok(Self::Tag(rest.trim().to_string())),
+            "machines" => {
+                let mut machines: Vec<String> = rest
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|m| !m.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                if machines.is_empty() {
+                    return Err(format!("scope lists no machines: {value}"));
+                }
+                machines.sort();
+                machines.dedup();
+                Ok(Self::Machines(machines))
+            }
+            _ => Err(format!(
+                "scope must be tag:<tag> or machines:<id>,...: {value}"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for RetentionScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tag(tag) => write!(f, "tag:{tag}"),
+            Self::Machines(machines) => write!(f, "machines:{}", machines.join(",")),
+        }
+    }
+}
+
+/// Policy governing `machine_id`: the most specific matching scope, ties going
+/// to the longest retention and then the lowest policy id; the table-wide
+/// policy when no scope matches
+#[must_use]
+pub fn resolve_retention_policy<'a>(
+    policies: &'a [RetentionPolicy],
+    machine_id: &str,
+    tags: &[String],
+) -> Option<&'a RetentionPolicy> {
+    policies
+        .iter()
+        .filter_map(|policy| match &policy.scope {
+            None => Some((0, policy)),
+            Some(scope) => {
+                let scope: RetentionScope = scope.parse().ok()?;
+                scope
+                    .matches(machine_id, tags)
+                    .then(|| (scope.specificity(), policy))
+            }
+        })
+        .max_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then(a.retention_days.cmp(&b.retention_days))
+                .then(b.policy_id.cmp(&a.policy_id))
+        })
+        .map(|(_, policy)| policy)
+}
+
+/// Default bytes kept at each end of a value cut by `max_row_bytes`
+pub const DEFAULT_KEEP_BYTES: i64 = 65_536;
+
+/// Write-time cap on the text stored in one row.
+///
+/// Rows whose string values add up to more than `max_row_bytes` have each
+/// value longer than `keep_head_bytes + keep_tail_bytes` cut down to its head
+/// and tail around a marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowSizeLimit {
+    pub max_row_bytes: usize,
+    pub keep_head_bytes: usize,
+    pub keep_tail_bytes: usize,
+}
+
+impl RowSizeLimit {
+    /// Cut `text` to head + marker + tail, or `None` if it already fits.
+    ///
+    /// Both cuts back off to the nearest whitespace, quote or comma so a
+    /// token is never split; a secret half-kept at the boundary would no
+    /// longer match the redaction rules.
+    #[must_use]
+    pub fn truncate(&self, text: &str) -> Option<String> {
+        if text.len() <= self.keep_head_bytes.saturating_add(self.keep_tail_bytes) {
+            return None;
+        }
+        let is_break = |c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',');
+
+        let mut head_end = self.keep_head_bytes;
+        while !text.is_char_boundary(head_end) {
+            head_end -= 1;
+        }
+        if !text[head_end..].starts_with(is_break) {
+            head_end = text[..head_end].rfind(is_break).unwrap_or(head_end);
+        }
+
+        let mut tail_start = text.len() - self.keep_tail_bytes;
+        while !text.is_char_boundary(tail_start) {
+            tail_start += 1;
+        }
+        if !text[..tail_start].ends_with(is_break) {
+            tail_start = text[tail_start..]
+                .find(is_break)
+                .map_or(tail_start, |i| tail_start + i);
+        }
+
+        let omitted = tail_start - head_end;
+        Some(format!(
+            "{}\n[... {omitted} bytes truncated ...]\n{}",
+            &text[..head_end],
+            &text[tail_start..]
+        ))
+    }
 }
 
 /// Result of a vacuum operation
@@ -352,22 +1011,112 @@ pub struct VacuumResult {
     pub rows_deleted: i64,
     pub rows_would_delete: i64,
     pub rows_aggregated: i64,
+    /// Text bytes freed by deleted rows
+    pub bytes_reclaimed: i64,
+    /// Text bytes the deletable rows hold (reported on dry runs too)
+    pub bytes_would_reclaim: i64,
     pub duration_ms: i64,
     pub dry_run: bool,
     pub error: Option<String>,
+    /// Rows old enough (or over the size cap) to go, kept by legal holds
+    pub rows_held: i64,
+    /// Would-delete counts per policy scope; rows are counted once, under
+    /// the scope that governs their machine
+    pub scopes: Vec<VacuumScopeResult>,
+}
+
+/// Rows one retention scope selects for deletion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacuumScopeResult {
+    /// `table-wide`, the policy's scope, or `size cap` for rows removed only
+    /// by `max_total_bytes`
+    pub scope: String,
+    pub policy_id: String,
+    pub retention_days: Option<i32>,
+    /// Machines governed by a scoped policy; empty for the table-wide one
+    pub machines: Vec<String>,
+    pub rows_would_delete: i64,
+    pub bytes_would_reclaim: i64,
+}
+
+/// Label of the table-wide retention scope
+pub const TABLE_WIDE_SCOPE: &str = "table-wide";
+
+/// Days of history the insert rate in a [`RetentionSimulation`] averages over
+pub const RETENTION_RATE_WINDOW_DAYS: i32 = 30;
+
+/// What a table would lose under a different table-wide retention period,
+/// from [`VcStore::simulate_retention`]. Nothing is deleted or logged.
+///
+/// Byte counts are estimates: the text bytes vacuum reports plus the fixed
+/// width of every other column.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionSimulation {
+    pub table_name: String,
+    pub retention_days: i32,
+    /// Days kept by the table-wide policy today, if there is one
+    pub current_retention_days: Option<i32>,
+    pub horizon_days: i32,
+    pub rows_total: i64,
+    pub bytes_total: i64,
+    /// Rows already older than the simulated retention
+    pub rows_eligible_now: i64,
+    pub bytes_eligible_now: i64,
+    /// Rows that age past the cutoff within `horizon_days`: existing rows,
+    /// plus new inserts at the current rate when the horizon outlasts the
+    /// retention period
+    pub rows_eligible_horizon: i64,
+    pub bytes_eligible_horizon: i64,
+    /// Average rows inserted per day over the last
+    /// [`RETENTION_RATE_WINDOW_DAYS`] days
+    pub daily_insert_rows: f64,
+    pub daily_insert_bytes: f64,
+    /// Rows eligible now, by machine and month
+    pub breakdown: Vec<RetentionSimulationBucket>,
+    /// Open incidents whose time window covers rows eligible now
+    pub open_incidents: Vec<RetentionIncidentLink>,
+}
+
+/// Eligible rows of one machine in one month
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionSimulationBucket {
+    /// `None` for tables without a `machine_id` column, or rows without one
+    pub machine_id: Option<String>,
+    /// `YYYY-MM`
+    pub month: String,
+    pub rows: i64,
+    pub bytes: i64,
+}
+
+/// An open incident with evidence inside a would-delete set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionIncidentLink {
+    pub incident_id: String,
+    pub title: String,
+    pub severity: String,
+    pub status: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    /// Eligible rows inside the incident's window
+    pub rows: i64,
 }
 
 /// Collector health record
+#[cfg(feature = "duckdb")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectorHealth {
     pub machine_id: String,
     pub collector: String,
     pub collected_at: String,
+    #[serde(deserialize_with = "rows::boolish")]
     pub success: bool,
     pub duration_ms: Option<i64>,
     pub rows_inserted: i64,
     pub bytes_parsed: i64,
+    /// Raw error message of a failed run
     pub error_class: Option<String>,
+    /// Classified cause of a failed run (`timeout`, `auth_failed`, ...)
+    pub error_cause: Option<String>,
     pub freshness_seconds: Option<i64>,
     pub payload_hash: Option<String>,
     pub collector_version: Option<String>,
@@ -375,6 +1124,26 @@ pub struct CollectorHealth {
     pub cursor_json: Option<String>,
 }
 
+/// Failed collector runs of one machine with one cause
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FailureCauseCount {
+    pub machine_id: String,
+    pub cause: String,
+    pub failures: i64,
+    /// Most recent failure with this cause
+    pub last_at: String,
+}
+
+/// When an alert in `alert_history` was open
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertSpan {
+    pub alert_id: i64,
+    pub rule_id: String,
+    pub machine_id: Option<String>,
+    pub fired_at: String,
+    pub resolved_at: Option<String>,
+}
+
 /// An alert that fired and is being written to `alert_history`.
 ///
 /// Deliberately a plain record rather than `vc_alert::Alert`: the store stays
@@ -391,6 +1160,228 @@ pub struct FiredAlert {
     pub machine_id: Option<String>,
 }
 
+/// One batch of a node bundle, applied by [`VcStore::apply_ingest_batch`]
+#[derive(Debug, Clone, Copy)]
+pub struct IngestBatch<'a> {
+    pub bundle_id: &'a str,
+    /// Stable within the bundle (`<bundle_id>:<index>`)
+    pub batch_id: &'a str,
+    pub machine_id: &'a str,
+    pub collector: &'a str,
+    /// Hash of the whole batch, the key for batch-level dedup
+    pub content_hash: &'a str,
+    /// Rows the batch declared, including ones that turn out malformed
+    pub row_count: usize,
+    pub table: &'a str,
+    pub rows: &'a [serde_json::Value],
+    /// Dedup hash per row of `rows`; empty when row-level dedup is off
+    pub row_hashes: &'a [String],
+}
+
+/// Rows an applied ingest batch wrote and skipped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedIngestBatch {
+    pub rows_inserted: usize,
+    /// Not an object, naming columns the table lacks, or rejected by the
+    /// write limits
+    pub rows_skipped: usize,
+}
+
+/// What a notification template can say about an alert beyond the alert
+/// itself; `None` where the store has nothing to offer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertNotificationContext {
+    pub hostname: Option<String>,
+    /// Latest `health_summary.overall_score` of the alert's machine
+    pub health_score: Option<f64>,
+    /// Unresolved occurrences of the alert's rule on its machine
+    pub occurrences: i64,
+}
+
+/// Event stream exported to OpenTelemetry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TelemetryStream {
+    /// Rows added to `alert_history`, in id order
+    AlertFired,
+    /// Alerts whose `resolved_at` was set, in resolution order
+    AlertResolved,
+    /// Incidents opened, and their latest status change
+    Incident,
+    /// Failed `collector_health` rows
+    CollectorFailure,
+}
+
+impl TelemetryStream {
+    pub const ALL: [Self; 4] = [
+        Self::AlertFired,
+        Self::AlertResolved,
+        Self::Incident,
+        Self::CollectorFailure,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AlertFired => "alert_fired",
+            Self::AlertResolved => "alert_resolved",
+            Self::Incident => "incident",
+            Self::CollectorFailure => "collector_failure",
+        }
+    }
+
+    /// Events of the stream as uniform columns:
+    /// `ts, id, key, machine_id, severity, alert_type, incident_id, name, body`
+    #[cfg(feature = "duckdb")]
+    fn source_sql(self) -> &'static str {
+        match self {
+            Self::AlertFired => {
+                "SELECT CAST(fired_at AS TEXT) AS ts, id, '' AS key, machine_id, severity, \
+                 rule_id AS alert_type, CAST(NULL AS TEXT) AS incident_id, \
+                 'alert.fired' AS name, title || COALESCE(': ' || message, '') AS body \
+                 FROM alert_history"
+            }
+            Self::AlertResolved => {
+                "SELECT CAST(resolved_at AS TEXT) AS ts, id, '' AS key, machine_id, severity, \
+                 rule_id AS alert_type, CAST(NULL AS TEXT) AS incident_id, \
+                 'alert.resolved' AS name, title AS body \
+                 FROM alert_history WHERE resolved_at IS NOT NULL"
+            }
+            Self::Incident => {
+                "SELECT CAST(started_at AS TEXT) AS ts, 0 AS id, incident_id || ':opened' AS key, \
+                 CAST(NULL AS TEXT) AS machine_id, severity, CAST(NULL AS TEXT) AS alert_type, \
+                 incident_id, 'incident.opened' AS name, title AS body \
+                 FROM incidents \
+                 UNION ALL \
+                 SELECT CAST(updated_at AS TEXT) AS ts, 0 AS id, \
+                 incident_id || ':' || COALESCE(status, 'open') AS key, \
+                 CAST(NULL AS TEXT) AS machine_id, severity, CAST(NULL AS TEXT) AS alert_type, \
+                 incident_id, 'incident.' || COALESCE(status, 'open') AS name, title AS body \
+                 FROM incidents WHERE updated_at IS NOT NULL"
+            }
+            Self::CollectorFailure => {
+                "SELECT CAST(collected_at AS TEXT) AS ts, 0 AS id, \
+                 machine_id || '/' || collector AS key, machine_id, 'warning' AS severity, \
+                 CAST(NULL AS TEXT) AS alert_type, CAST(NULL AS TEXT) AS incident_id, \
+                 'collector.failed' AS name, \
+                 collector || ' failed' || COALESCE(': ' || error_class, '') AS body \
+                 FROM collector_health WHERE success = false"
+            }
+        }
+    }
+
+    /// Predicate selecting events after a cursor, bound as
+    /// `last_id` or `last_ts, last_ts, last_id|last_key`, and the matching order
+    #[cfg(feature = "duckdb")]
+    fn after_cursor(self) -> (&'static str, &'static str) {
+        match self {
+            Self::AlertFired => ("id > ?", "id"),
+            Self::AlertResolved => ("(ts > ? OR (ts = ? AND id > ?))", "ts, id"),
+            Self::Incident | Self::CollectorFailure => {
+                ("(ts > ? OR (ts = ? AND key > ?))", "ts, key")
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for TelemetryStream {
+    type Err = StoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|stream| stream.as_str() == s)
+            .ok_or_else(|| StoreError::QueryError(format!("unknown telemetry stream: {s}")))
+    }
+}
+
+/// Position of the last exported event in a [`TelemetryStream`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelemetryCursor {
+    pub last_id: i64,
+    pub last_ts: Option<String>,
+    pub last_key: Option<String>,
+}
+
+/// One event awaiting export, with the cursor that marks it exported
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryEvent {
+    pub stream: TelemetryStream,
+    /// Event name, e.g. `alert.fired` or `incident.closed`
+    pub name: String,
+    pub ts: String,
+    pub severity: String,
+    pub body: String,
+    pub machine_id: Option<String>,
+    pub alert_type: Option<String>,
+    pub incident_id: Option<String>,
+    pub cursor: TelemetryCursor,
+}
+
+/// Export progress of one stream, as shown by `vc telemetry status`
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryStreamStatus {
+    pub stream: TelemetryStream,
+    /// Events not yet exported; everything when the exporter never ran
+    pub backlog: i64,
+    pub exported_count: i64,
+    pub last_success_at: Option<String>,
+    pub last_attempt_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// Alert delivery health across every channel
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeliveryHealth {
+    pub last_success_at: Option<String>,
+    pub last_attempt_at: Option<String>,
+    /// Failed deliveries after the last success (all of them when nothing
+    /// was ever delivered)
+    pub failures_since_success: i64,
+}
+
+/// Latest result of one service check on one machine.
+///
+/// `kind` is `process` (regex over command lines) or `unit` (systemd unit /
+/// launchd label); `target` is the pattern or unit name that was checked.
+#[cfg(feature = "duckdb")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineServiceStatus {
+    pub machine_id: String,
+    pub service_name: String,
+    pub kind: String,
+    pub target: String,
+    #[serde(deserialize_with = "rows::boolish")]
+    pub required: bool,
+    #[serde(deserialize_with = "rows::boolish")]
+    pub running: bool,
+    pub pid: Option<i64>,
+    pub uptime_secs: Option<i64>,
+    pub detail: Option<String>,
+    pub checked_at: String,
+}
+
+/// Latest resource self-limit check recorded by the daemon.
+///
+/// `level` is the load-shedding step the daemon is on (`normal`,
+/// `transcripts_paused`, `polling_stretched`, `ingest_paused`); the guard
+/// logic that picks it lives with the daemon, the store only keeps the
+/// outcome so other processes can read it.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DaemonResourceState {
+    pub checked_at: String,
+    pub level: String,
+    pub disk_pressure: bool,
+    pub rss_mb: Option<i64>,
+    pub free_disk_mb: Option<i64>,
+    pub max_rss_mb: Option<i64>,
+    pub min_free_disk_mb: Option<i64>,
+    pub reason: Option<String>,
+    /// Subscribers on the daemon's watch socket; `None` when it serves none
+    #[serde(default)]
+    pub watch_subscribers: Option<i64>,
+}
+
 /// Machine baseline profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MachineBaseline {
@@ -457,28 +1448,413 @@ pub struct DriftEvent {
     pub z_score: f64,
     pub severity: DriftSeverity,
     pub evidence_json: Option<serde_json::Value>,
+    /// Value before the drift (the baseline mean for z-score detections)
+    #[serde(default)]
+    pub previous_value: Option<String>,
+    /// Value that drifted
+    #[serde(default)]
+    pub new_value: Option<String>,
+    /// `new - previous` when both are numeric
+    #[serde(default)]
+    pub delta: Option<f64>,
+    /// Baseline the comparison ran against, e.g. `7d@2026-10-16T00:00:00Z`
+    #[serde(default)]
+    pub baseline_ref: Option<String>,
+    /// How the drift was detected, e.g. `z_score`
+    #[serde(default)]
+    pub detection_method: Option<String>,
 }
 
-/// Freshness summary for a machine/collector pair
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FreshnessSummary {
-    pub machine_id: String,
-    pub collector: String,
-    pub last_success_at: Option<String>,
-    pub freshness_seconds: i64,
-    pub success_rate_24h: f64,
-    pub total_runs_24h: i64,
-    pub stale: bool,
+impl DriftEvent {
+    /// Human-readable one-liner, e.g. `kernel_version: 6.1.0 → 6.6.8`
+    #[must_use]
+    pub fn description(&self) -> String {
+        let previous = self
+            .previous_value
+            .clone()
+            .unwrap_or_else(|| format_drift_number(self.baseline_mean));
+        let new = self
+            .new_value
+            .clone()
+            .unwrap_or_else(|| format_drift_number(self.current_value));
+        describe_drift(&self.metric, &previous, &new, self.delta)
+    }
 }
 
-/// Main storage handle
-pub struct VcStore {
-    conn: StoreConnectionFactory,
-    db_path: String,
+/// Render a drift change as `metric: previous → new (+delta)`
+#[must_use]
+pub fn describe_drift(metric: &str, previous: &str, new: &str, delta: Option<f64>) -> String {
+    match delta {
+        Some(delta) => {
+            let sign = if delta >= 0.0 { "+" } else { "" };
+            format!(
+                "{metric}: {previous} → {new} ({sign}{})",
+                format_drift_number(delta)
+            )
+        }
+        None => format!("{metric}: {previous} → {new}"),
+    }
 }
 
-impl VcStore {
-    /// Open or create database at path
+/// Format a drift value with at most two decimals and no trailing zeros
+fn format_drift_number(value: f64) -> String {
+    let text = format!("{value:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Derived description for a `drift_events` row; rows from before the
+/// structured columns fall back to the baseline mean and current value.
+#[cfg(feature = "duckdb")]
+fn drift_row_description(row: &serde_json::Value) -> String {
+    let value_text = |structured: &str, legacy: &str| {
+        row[structured].as_str().map_or_else(
+            || format_drift_number(row[legacy].as_f64().unwrap_or(0.0)),
+            str::to_string,
+        )
+    };
+    describe_drift(
+        row["metric"].as_str().unwrap_or("unknown"),
+        &value_text("previous_value", "baseline_mean"),
+        &value_text("new_value", "current_value"),
+        row["delta"].as_f64(),
+    )
+}
+
+/// One version of a tool observed on a machine, from `tool_version_history`.
+///
+/// `source` is where the version came from: `probe` (`--version` output),
+/// `version_file`, or `bundle` (a vc-node push agent's own version).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolVersion {
+    pub machine_id: String,
+    pub tool_name: String,
+    pub tool_version: String,
+    pub previous_version: Option<String>,
+    pub source: String,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+}
+
+/// One clock skew measurement for a machine.
+///
+/// `skew_ms` is the remote clock minus hub time, so a machine whose clock
+/// runs fast has a positive skew. Probe measurements bracket the remote
+/// reading between two hub timestamps and take the midpoint, so the true
+/// skew lies within `rtt_ms / 2` of `skew_ms`. Ingest measurements compare a
+/// bundle's send time with its arrival and have no `rtt_ms`: delivery delay
+/// only makes the remote look further behind, so they are a lower bound.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSkewSample {
+    pub machine_id: String,
+    pub measured_at: String,
+    pub skew_ms: i64,
+    pub rtt_ms: Option<i64>,
+    pub source: String,
+}
+
+impl ClockSkewSample {
+    /// Smallest skew magnitude the measurement is consistent with; 0 when
+    /// the clocks may well agree.
+    #[must_use]
+    pub fn min_abs_skew_ms(&self) -> i64 {
+        match self.rtt_ms {
+            Some(rtt) => (self.skew_ms.saturating_abs() - rtt / 2).max(0),
+            None => self.skew_ms.max(0),
+        }
+    }
+
+    /// Whether the measurement is good enough to correct timestamps by:
+    /// always for probes, and for ingest only when the remote is ahead.
+    #[must_use]
+    pub fn is_correctable(&self) -> bool {
+        self.rtt_ms.is_some() || self.skew_ms > 0
+    }
+}
+
+/// A machine maintenance window.
+///
+/// While a window is open (`ended_at` is `None`) the machine's status reads
+/// `maintenance`: collectors leave it alone, its health score stays at its
+/// last value and the fleet aggregate leaves it out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub machine_id: String,
+    pub started_at: String,
+    pub reason: Option<String>,
+    /// When the window ends by itself, if it was given an end
+    pub until_at: Option<String>,
+    pub started_by: String,
+    pub ended_at: Option<String>,
+    /// Who ended it; `expiry` when `until_at` passed
+    pub ended_by: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// How long the window has been open as of `now`
+    #[must_use]
+    pub fn age(&self, now: DateTime<Utc>) -> chrono::Duration {
+        DateTime::parse_from_rfc3339(&self.started_at).map_or_else(
+            |_| chrono::Duration::zero(),
+            |started| now - started.with_timezone(&Utc),
+        )
+    }
+}
+
+/// Compare two dotted version strings component by component.
+///
+/// A leading `v` is ignored and each component compares by its numeric
+/// prefix, so `0.10.0 > 0.9.3` and `v1.2 == 1.2.0`. Anything after a `-` or
+/// `+` (pre-release, build metadata) is ignored.
+#[must_use]
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    fn components(version: &str) -> Vec<u64> {
+        let version = version.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        core.split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    }
+
+    let (a, b) = (components(a), components(b));
+    let len = a.len().max(b.len());
+    (0..len)
+        .map(|i| {
+            a.get(i)
+                .copied()
+                .unwrap_or(0)
+                .cmp(&b.get(i).copied().unwrap_or(0))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// What a bulk alert operation does to the alerts it matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkAlertAction {
+    Acknowledge,
+    Resolve,
+}
+
+impl BulkAlertAction {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BulkAlertAction::Acknowledge => "acknowledge",
+            BulkAlertAction::Resolve => "resolve",
+        }
+    }
+
+    /// Alerts the action still applies to: open and, for acknowledge, not
+    /// yet acknowledged
+    #[cfg(feature = "duckdb")]
+    fn pending_clause(self) -> &'static str {
+        match self {
+            BulkAlertAction::Acknowledge => "resolved_at IS NULL AND COALESCE(acknowledged, 0) = 0",
+            BulkAlertAction::Resolve => "resolved_at IS NULL",
+        }
+    }
+}
+
+/// Which alerts a bulk acknowledge/resolve touches. Empty fields match
+/// everything, so an empty filter is every pending alert.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertFilter {
+    /// Only these alert IDs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<i64>,
+    #[serde(rename = "machine", skip_serializing_if = "Option::is_none")]
+    pub machine_id: Option<String>,
+    /// Case-insensitive substring of the rule ID, e.g. `offline`
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub rule_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Only alerts fired before this RFC 3339 timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+}
+
+impl AlertFilter {
+    /// Whether the filter narrows anything down
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[cfg(feature = "duckdb")]
+    fn where_sql(&self, action: BulkAlertAction) -> String {
+        let mut clauses = vec![action.pending_clause().to_string()];
+        if !self.ids.is_empty() {
+            let ids: Vec<String> = self.ids.iter().map(ToString::to_string).collect();
+            clauses.push(format!("id IN ({})", ids.join(", ")));
+        }
+        if let Some(machine) = &self.machine_id {
+            clauses.push(format!("machine_id = '{}'", escape_sql_literal(machine)));
+        }
+        if let Some(rule_type) = &self.rule_type {
+            clauses.push(format!(
+                "LOWER(rule_id) LIKE '%{}%'",
+                escape_sql_literal(&rule_type.to_lowercase())
+            ));
+        }
+        if let Some(severity) = &self.severity {
+            clauses.push(format!(
+                "LOWER(severity) = '{}'",
+                escape_sql_literal(&severity.to_lowercase())
+            ));
+        }
+        if let Some(before) = &self.before {
+            clauses.push(format!("fired_at < '{}'", escape_sql_literal(before)));
+        }
+        format!("WHERE {}", clauses.join(" AND "))
+    }
+}
+
+/// Alerts a bulk operation would touch: the exact count, the count per
+/// machine and the newest few
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertMatch {
+    pub matched: usize,
+    /// Matches per machine ID (`unknown` for alerts without one)
+    #[serde(default)]
+    pub by_machine: BTreeMap<String, usize>,
+    pub sample: Vec<serde_json::Value>,
+}
+
+/// Open, unacknowledged alerts of one rule on one machine, with the
+/// escalation state the group carries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAlertGroup {
+    pub rule_id: String,
+    pub machine_id: Option<String>,
+    /// Title and severity of the oldest open occurrence
+    pub title: String,
+    pub severity: String,
+    /// When the oldest open occurrence fired
+    pub first_fired_at: DateTime<Utc>,
+    /// Occurrences, oldest first
+    pub alert_ids: Vec<i64>,
+    /// Highest level any occurrence reached
+    pub escalation_level: u32,
+    /// Policy that set `escalation_level`
+    pub escalation_policy: Option<String>,
+    pub escalated_at: Option<String>,
+    /// Occurrences below the group's level, e.g. ones that fired after it
+    /// last escalated
+    pub behind_ids: Vec<i64>,
+}
+
+impl OpenAlertGroup {
+    /// `<machine>/<rule>`, the same key `vc watch --enrich` reports
+    #[must_use]
+    pub fn group_key(&self) -> String {
+        format!(
+            "{}/{}",
+            self.machine_id.as_deref().unwrap_or("unknown"),
+            self.rule_id
+        )
+    }
+}
+
+/// An open alert on a machine, as the correlation pass sees it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationCandidate {
+    pub id: i64,
+    pub rule_id: String,
+    pub machine_id: String,
+    pub severity: String,
+    pub fired_at: DateTime<Utc>,
+    /// Group the alert currently belongs to
+    pub correlation_id: Option<String>,
+    /// Whether it is currently that group's root
+    pub correlation_root: bool,
+}
+
+/// An `alert_history` timestamp: RFC 3339, or DuckDB's `YYYY-MM-DD HH:MM:SS`
+/// taken as UTC
+#[cfg(feature = "duckdb")]
+fn parse_alert_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .map(|ts| ts.and_utc())
+        })
+}
+
+/// The single audit event recorded for a bulk alert operation: the filter
+/// and how many alerts it changed, not one event per alert
+#[cfg(feature = "duckdb")]
+#[must_use]
+pub fn bulk_alert_audit_event(
+    action: BulkAlertAction,
+    actor: &ActorContext,
+    filter: &AlertFilter,
+    count: usize,
+) -> AuditEvent {
+    let event = actor.audit_event(
+        AuditEventType::UserCommand,
+        format!("alert_bulk_{}", action.as_str()),
+        AuditResult::Success,
+        serde_json::json!({ "filter": filter, "count": count }),
+    );
+    match &filter.machine_id {
+        Some(machine) => event.with_machine_id(machine.clone()),
+        None => event,
+    }
+}
+
+/// Freshness summary for a machine/collector pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreshnessSummary {
+    pub machine_id: String,
+    pub collector: String,
+    pub last_success_at: Option<String>,
+    pub freshness_seconds: i64,
+    pub success_rate_24h: f64,
+    pub total_runs_24h: i64,
+    pub stale: bool,
+}
+
+/// The annotation a maintenance window opening or closing leaves
+#[cfg(feature = "duckdb")]
+fn maintenance_annotation(machine_id: &str, message: String) -> NewAnnotation {
+    NewAnnotation {
+        kind: annotations::MAINTENANCE_KIND.to_string(),
+        scope: AnnotationScope::Machine(machine_id.to_string()),
+        ts: None,
+        message,
+        source: annotations::INTERNAL_SOURCE.to_string(),
+    }
+}
+
+/// Main storage handle
+#[cfg(feature = "duckdb")]
+pub struct VcStore {
+    conn: StoreConnectionFactory,
+    db_path: String,
+}
+
+#[cfg(feature = "duckdb")]
+impl Drop for VcStore {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            warn!(error = %err, db = %self.db_path, "failed to flush the store on close");
+        }
+    }
+}
+
+#[cfg(feature = "duckdb")]
+impl VcStore {
+    /// Open or create database at path
     ///
     /// # Errors
     ///
@@ -523,11 +1899,83 @@ impl VcStore {
         Ok(store)
     }
 
+    /// Write everything this store buffers in memory (the query log and
+    /// table change counts), so an exit right after does not lose it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a buffer cannot be written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn flush(&self) -> Result<(), StoreError> {
+        self.conn.flush()
+    }
+
+    /// Flush and close the store. Dropping it flushes too, but can only log
+    /// a failure; this returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a buffer cannot be written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn close(self) -> Result<(), StoreError> {
+        self.flush()
+    }
+
+    /// Flush every store still open in this process, including ones held
+    /// by tasks that outlive the caller. For a command about to exit.
+    /// Returns the number of stores flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`StoreError`]; every store is still attempted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an internal database mutex is poisoned.
+    pub fn flush_open_stores() -> Result<usize, StoreError> {
+        let open = match OPEN_STORES.lock() {
+            Ok(open) => open.clone(),
+            Err(_) => Vec::new(),
+        };
+        Self::flush_stores(&open)
+    }
+
+    /// Flush the stores in `open` that are still alive
+    fn flush_stores(open: &[Weak<StoreConnectionShared>]) -> Result<usize, StoreError> {
+        let open: Vec<StoreConnectionFactory> = open
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|shared| StoreConnectionFactory { shared })
+            .collect();
+        let mut first_error = None;
+        for store in &open {
+            if let Err(err) = store.flush() {
+                first_error.get_or_insert(err);
+            }
+        }
+        first_error.map_or(Ok(open.len()), Err)
+    }
+
     /// Run all pending migrations
     fn run_migrations(&self) -> Result<(), StoreError> {
-        let conn = self.conn.lock().unwrap();
-        migrations::run_all(&conn)?;
-        Ok(())
+        backend::migrate(self)
+    }
+
+    /// Newest migration version applied to this store
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the migrations table cannot be read.
+    pub fn schema_version(&self) -> Result<u32, StoreError> {
+        let version: i64 =
+            self.query_scalar("SELECT COALESCE(MAX(version), 0) FROM _migrations")?;
+        Ok(u32::try_from(version).unwrap_or(0))
     }
 
     /// Get access to the underlying connection
@@ -536,6 +1984,27 @@ impl VcStore {
         self.conn.clone()
     }
 
+    /// Record every query this store runs, tagged with `caller`, for
+    /// `vc db slow-queries`. A no-op when `config.enabled` is off, or when
+    /// this store already keeps a query log.
+    #[must_use]
+    pub fn with_query_log(self, config: &vc_config::QueryLogConfig, caller: QueryCaller) -> Self {
+        if config.enabled {
+            let _ = self
+                .conn
+                .shared
+                .query_log
+                .set(QueryLog::new(config, caller));
+        }
+        self
+    }
+
+    /// The query log this store records into, if any
+    #[must_use]
+    pub fn query_log(&self) -> Option<&QueryLog> {
+        self.conn.shared.query_log.get()
+    }
+
     /// Execute a query that returns no results
     ///
     /// # Errors
@@ -581,13 +2050,35 @@ impl VcStore {
         Ok(())
     }
 
+    /// Whether `table` has a column named `column`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the catalog query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn table_has_column(&self, table: &str, column: &str) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM information_schema.columns \
+             WHERE table_name = ? AND column_name = ?",
+            [table, column],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     /// Insert a row into a table from JSON
     /// Note: This extracts key-value pairs from the JSON object
     ///
+    /// Values are held to the store's [`WriteLimits`].
+    ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if `json` is not an object, SQL execution fails, or value
-    /// serialization fails.
+    /// Returns [`StoreError`] if `json` is not an object, the write limits
+    /// reject the row, SQL execution fails, or value serialization fails.
     ///
     /// # Panics
     ///
@@ -595,24 +2086,12 @@ impl VcStore {
     pub fn insert_json(&self, table: &str, json: &serde_json::Value) -> Result<(), StoreError> {
         if let serde_json::Value::Object(map) = json {
             let conn = self.conn.lock().unwrap();
-
-            let columns: Vec<&str> = map.keys().map(String::as_str).collect();
-            let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
-
-            let sql = format!(
-                "INSERT INTO {} ({}) VALUES ({})",
-                table,
-                columns.join(", "),
-                placeholders.join(", ")
-            );
-
-            let mut stmt = conn.prepare(&sql)?;
-
-            let params: Vec<Box<dyn duckdb::ToSql>> = map.values().map(json_value_to_sql).collect();
-
-            let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
-
-            stmt.execute(param_refs.as_slice())?;
+            if !write_limits::write_row(&conn, "INSERT INTO", table, map, WriteOrigin::default())? {
+                return Err(StoreError::RowRejected(format!(
+                    "{table} row holds a value over the limits; see oversized_writes"
+                )));
+            }
+            conn.changes.note(table, 1);
             Ok(())
         } else {
             Err(StoreError::QueryError(
@@ -634,6 +2113,27 @@ impl VcStore {
         &self,
         table: &str,
         rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError> {
+        self.insert_json_batch_from(table, rows, WriteOrigin::default())
+    }
+
+    /// Insert multiple rows from JSON array, attributing values over the
+    /// store's [`WriteLimits`] to `origin`. Rows the limits reject are
+    /// skipped and the rest are still written; the count returned is of
+    /// rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if inserting any row fails; nothing is written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database connection mutex is poisoned.
+    pub fn insert_json_batch_from(
+        &self,
+        table: &str,
+        rows: &[serde_json::Value],
+        origin: WriteOrigin<'_>,
     ) -> Result<usize, StoreError> {
         if rows.is_empty() {
             return Ok(0);
@@ -645,38 +2145,18 @@ impl VcStore {
         let mut count = 0;
         for row in rows {
             if let serde_json::Value::Object(map) = row {
-                let columns: Vec<&str> = map.keys().map(String::as_str).collect();
-                let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
-
-                let sql = format!(
-                    "INSERT INTO {} ({}) VALUES ({})",
-                    table,
-                    columns.join(", "),
-                    placeholders.join(", ")
-                );
-
-                let mut stmt = match conn.prepare(&sql) {
-                    Ok(s) => s,
+                match write_limits::write_row(&conn, "INSERT INTO", table, map, origin) {
+                    Ok(written) => count += usize::from(written),
                     Err(e) => {
                         let _ = conn.execute("ROLLBACK", []);
-                        return Err(e.into());
+                        return Err(e);
                     }
-                };
-
-                let params: Vec<Box<dyn duckdb::ToSql>> =
-                    map.values().map(json_value_to_sql).collect();
-                let param_refs: Vec<&dyn duckdb::ToSql> =
-                    params.iter().map(AsRef::as_ref).collect();
-
-                if let Err(e) = stmt.execute(param_refs.as_slice()) {
-                    let _ = conn.execute("ROLLBACK", []);
-                    return Err(e.into());
                 }
-                count += 1;
             }
         }
 
         conn.execute("COMMIT", [])?;
+        conn.changes.note(table, u64::try_from(count).unwrap_or(0));
         Ok(count)
     }
 
@@ -691,20 +2171,37 @@ impl VcStore {
     /// Panics if the internal database mutex is poisoned.
     pub fn query_json(&self, sql: &str) -> Result<Vec<serde_json::Value>, StoreError> {
         let conn = self.conn.lock().unwrap();
+        collect_json_rows(&conn, sql)
+    }
 
-        // Wrap query to output each row as JSON using DuckDB's to_json()
-        let json_sql = format!("SELECT to_json(_row) FROM ({sql}) AS _row");
-
-        let mut stmt = conn.prepare(&json_sql)?;
-        let mut rows = stmt.query([])?;
+    /// Run a query like [`Self::query_json`], interrupting the engine once
+    /// `timeout` elapses so a runaway query stops consuming resources
+    /// instead of merely being reported late.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::Timeout`] with the elapsed time if the deadline
+    /// cancelled the query, or [`StoreError`] if execution fails otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn query_json_with_timeout(
+        &self,
+        sql: &str,
+        timeout: Duration,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let started = Instant::now();
+        let fired = Arc::new(AtomicBool::new(false));
+        let watchdog = conn.interrupt_after(timeout, Arc::clone(&fired));
 
-        let mut results = Vec::new();
-        while let Some(row) = rows.next()? {
-            let json_str: String = row.get(0)?;
-            let value: serde_json::Value = serde_json::from_str(&json_str)?;
-            results.push(value);
+        let result = collect_json_rows(&conn, sql);
+        drop(watchdog);
+        match result {
+            Err(_) if fired.load(Ordering::SeqCst) => Err(StoreError::Timeout(started.elapsed())),
+            result => result,
         }
-        Ok(results)
     }
 
     /// Query for a single scalar value
@@ -784,7 +2281,8 @@ impl VcStore {
         Ok(())
     }
 
-    /// Insert a single audit event
+    /// Insert a single audit event synchronously. Hot paths should go
+    /// through [`AuditWriter`] instead.
     ///
     /// # Errors
     ///
@@ -807,14 +2305,15 @@ impl VcStore {
 
         conn.execute(
             r"
-            INSERT INTO audit_events (id, ts, event_type, actor, machine_id, action, result, details_json)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO audit_events (id, ts, event_type, actor, actor_via, machine_id, action, result, details_json)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             ",
             duckdb::params![
                 next_id,
                 event.ts.to_rfc3339(),
                 event.event_type.as_str(),
                 event.actor,
+                event.via.map(|via| via.as_str()),
                 event.machine_id,
                 event.action,
                 event.result.as_str(),
@@ -824,27 +2323,89 @@ impl VcStore {
         Ok(())
     }
 
-    /// List audit events with optional filters
+    /// Insert audit events in one transaction
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if query execution fails.
-    pub fn list_audit_events(
-        &self,
-        filter: &AuditEventFilter,
-    ) -> Result<Vec<serde_json::Value>, StoreError> {
-        let mut clauses: Vec<String> = Vec::new();
-
-        if let Some(event_type) = filter.event_type {
-            clauses.push(format!(
-                "event_type = '{}'",
-                escape_sql_literal(event_type.as_str())
-            ));
+    /// Returns [`StoreError`] if serialization or any insert fails; nothing
+    /// is written in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn insert_audit_events(&self, events: &[AuditEvent]) -> Result<(), StoreError> {
+        if events.is_empty() {
+            return Ok(());
         }
 
-        if let Some(machine_id) = &filter.machine_id {
-            clauses.push(format!("machine_id = '{}'", escape_sql_literal(machine_id)));
-        }
+        let conn = self.conn.lock().unwrap();
+        conn.execute("BEGIN TRANSACTION", [])?;
+
+        let result = (|| -> Result<(), StoreError> {
+            let first_id: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(id), 0) + 1 FROM audit_events",
+                [],
+                |row| row.get(0),
+            )?;
+            for (id, event) in (first_id..).zip(events) {
+                conn.execute(
+                    r"
+                    INSERT INTO audit_events (id, ts, event_type, actor, actor_via, machine_id, action, result, details_json)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    ",
+                    duckdb::params![
+                        id,
+                        event.ts.to_rfc3339(),
+                        event.event_type.as_str(),
+                        event.actor,
+                        event.via.map(|via| via.as_str()),
+                        event.machine_id,
+                        event.action,
+                        event.result.as_str(),
+                        serde_json::to_string(&event.details)?
+                    ],
+                )?;
+            }
+            Ok(())
+        })();
+
+        match result {
+            Ok(()) => {
+                conn.execute("COMMIT", [])?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// List audit events with optional filters
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_audit_events(
+        &self,
+        filter: &AuditEventFilter,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let mut clauses: Vec<String> = Vec::new();
+
+        if let Some(event_type) = filter.event_type {
+            clauses.push(format!(
+                "event_type = '{}'",
+                escape_sql_literal(event_type.as_str())
+            ));
+        }
+
+        if let Some(machine_id) = &filter.machine_id {
+            clauses.push(format!("machine_id = '{}'", escape_sql_literal(machine_id)));
+        }
+
+        if let Some(actor) = &filter.actor {
+            clauses.push(format!("actor = '{}'", escape_sql_literal(actor)));
+        }
 
         if let Some(since) = filter.since {
             clauses.push(format!(
@@ -861,7 +2422,7 @@ impl VcStore {
 
         let limit = clamp_audit_limit(filter.limit);
         let sql = format!(
-            "SELECT id, ts, event_type, actor, machine_id, action, result, details_json \
+            "SELECT id, ts, event_type, actor, actor_via, machine_id, action, result, details_json \
              FROM audit_events {where_sql} ORDER BY ts DESC LIMIT {limit}"
         );
 
@@ -875,7 +2436,7 @@ impl VcStore {
     /// Returns [`StoreError`] if query execution fails.
     pub fn get_audit_event(&self, id: i64) -> Result<Option<serde_json::Value>, StoreError> {
         let sql = format!(
-            "SELECT id, ts, event_type, actor, machine_id, action, result, details_json \
+            "SELECT id, ts, event_type, actor, actor_via, machine_id, action, result, details_json \
              FROM audit_events WHERE id = {id}"
         );
         let mut rows = self.query_json(&sql)?;
@@ -897,21 +2458,12 @@ impl VcStore {
     /// Panics if the internal database mutex is poisoned.
     pub fn list_retention_policies(&self) -> Result<Vec<RetentionPolicy>, StoreError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT policy_id, table_name, retention_days, aggregate_table, enabled, last_vacuum_at \
-             FROM retention_policies ORDER BY table_name",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RETENTION_POLICY_COLUMNS} FROM retention_policies \
+             ORDER BY table_name, scope NULLS FIRST"
+        ))?;
 
-        let rows = stmt.query_map([], |row| {
-            Ok(RetentionPolicy {
-                policy_id: row.get(0)?,
-                table_name: row.get(1)?,
-                retention_days: row.get(2)?,
-                aggregate_table: row.get(3)?,
-                enabled: row.get(4)?,
-                last_vacuum_at: row.get(5)?,
-            })
-        })?;
+        let rows = stmt.query_map([], retention_policy_from_row)?;
 
         let mut policies = Vec::new();
         for row in rows {
@@ -920,7 +2472,7 @@ impl VcStore {
         Ok(policies)
     }
 
-    /// Get a single retention policy by table name
+    /// Get the table-wide retention policy of a table
     ///
     /// # Errors
     ///
@@ -934,21 +2486,12 @@ impl VcStore {
         table_name: &str,
     ) -> Result<Option<RetentionPolicy>, StoreError> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT policy_id, table_name, retention_days, aggregate_table, enabled, last_vacuum_at \
-             FROM retention_policies WHERE table_name = ?",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {RETENTION_POLICY_COLUMNS} FROM retention_policies \
+             WHERE table_name = ? AND scope IS NULL"
+        ))?;
 
-        let result = stmt.query_row([table_name], |row| {
-            Ok(RetentionPolicy {
-                policy_id: row.get(0)?,
-                table_name: row.get(1)?,
-                retention_days: row.get(2)?,
-                aggregate_table: row.get(3)?,
-                enabled: row.get(4)?,
-                last_vacuum_at: row.get(5)?,
-            })
-        });
+        let result = stmt.query_row([table_name], retention_policy_from_row);
 
         match result {
             Ok(policy) => Ok(Some(policy)),
@@ -959,6 +2502,9 @@ impl VcStore {
 
     /// Set retention policy for a table (upsert)
     ///
+    /// Size limits set through [`Self::set_retention_size_limits`] survive the
+    /// update.
+    ///
     /// # Errors
     ///
     /// Returns [`StoreError`] if policy upsert fails.
@@ -977,14 +2523,99 @@ impl VcStore {
         let policy_id = format!("retention_{table_name}");
 
         conn.execute(
-            "INSERT OR REPLACE INTO retention_policies (policy_id, table_name, retention_days, aggregate_table, enabled) \
-             VALUES (?, ?, ?, ?, ?)",
+            "INSERT INTO retention_policies (policy_id, table_name, retention_days, aggregate_table, enabled) \
+             VALUES (?, ?, ?, ?, ?) \
+             ON CONFLICT (policy_id) DO UPDATE SET table_name = excluded.table_name, \
+             retention_days = excluded.retention_days, aggregate_table = excluded.aggregate_table, \
+             enabled = excluded.enabled",
             duckdb::params![policy_id, table_name, retention_days, aggregate_table, enabled],
         )?;
 
         Ok(())
     }
 
+    /// Set a retention policy covering only the machines in `scope` (upsert).
+    /// Size limits stay with the table-wide policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if policy upsert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_scoped_retention_policy(
+        &self,
+        table_name: &str,
+        scope: &RetentionScope,
+        retention_days: i32,
+        enabled: bool,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let scope = scope.to_string();
+        let policy_id = format!("retention_{table_name}@{scope}");
+
+        conn.execute(
+            "INSERT INTO retention_policies (policy_id, table_name, retention_days, enabled, scope) \
+             VALUES (?, ?, ?, ?, ?) \
+             ON CONFLICT (policy_id) DO UPDATE SET retention_days = excluded.retention_days, \
+             enabled = excluded.enabled",
+            duckdb::params![policy_id, table_name, retention_days, enabled, scope],
+        )?;
+
+        Ok(())
+    }
+
+    /// Set the size limits of an existing table-wide retention policy.
+    ///
+    /// `None` turns the respective cap off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails or `table_name` has no policy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_retention_size_limits(
+        &self,
+        table_name: &str,
+        max_total_bytes: Option<i64>,
+        max_row_bytes: Option<i64>,
+        keep_head_bytes: i64,
+        keep_tail_bytes: i64,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE retention_policies SET max_total_bytes = ?, max_row_bytes = ?, \
+             keep_head_bytes = ?, keep_tail_bytes = ? WHERE table_name = ? AND scope IS NULL",
+            duckdb::params![
+                max_total_bytes,
+                max_row_bytes,
+                keep_head_bytes,
+                keep_tail_bytes,
+                table_name
+            ],
+        )?;
+        if updated == 0 {
+            return Err(StoreError::QueryError(format!(
+                "No retention policy for table '{table_name}'"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Write-time row size limit for `table_name`, if its policy sets one
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the policy lookup fails.
+    pub fn row_size_limit(&self, table_name: &str) -> Result<Option<RowSizeLimit>, StoreError> {
+        Ok(self
+            .get_retention_policy(table_name)?
+            .and_then(|policy| policy.row_size_limit()))
+    }
+
     /// Run vacuum for all enabled retention policies (or specific table)
     ///
     /// # Errors
@@ -995,10 +2626,8 @@ impl VcStore {
         dry_run: bool,
         specific_table: Option<&str>,
     ) -> Result<Vec<VacuumResult>, StoreError> {
-        let policies = self.list_retention_policies()?;
-        let mut results = Vec::new();
-
-        for policy in policies {
+        let mut by_table: Vec<(String, Vec<RetentionPolicy>)> = Vec::new();
+        for policy in self.list_retention_policies()? {
             // Skip disabled policies
             if !policy.enabled {
                 continue;
@@ -1011,119 +2640,303 @@ impl VcStore {
                 continue;
             }
 
-            let result = self.vacuum_table(&policy, dry_run)?;
-            results.push(result);
+            // Policies arrive ordered by table
+            match by_table.last_mut() {
+                Some((table, policies)) if *table == policy.table_name => policies.push(policy),
+                _ => by_table.push((policy.table_name.clone(), vec![policy])),
+            }
         }
 
+        let machine_tags = if by_table
+            .iter()
+            .any(|(_, policies)| policies.iter().any(|p| p.scope.is_some()))
+        {
+            self.machine_tags()?
+        } else {
+            std::collections::HashMap::new()
+        };
+
+        let holds = self.list_legal_holds(false)?;
+        let mut results = Vec::new();
+        for (table, policies) in &by_table {
+            results.push(self.vacuum_table(table, policies, &machine_tags, &holds, dry_run)?);
+        }
         Ok(results)
     }
 
-    /// Vacuum a single table based on its retention policy
+    /// Tags of every known machine
+    fn machine_tags(&self) -> Result<std::collections::HashMap<String, Vec<String>>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT machine_id, tags FROM machines")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+        let mut tags = std::collections::HashMap::new();
+        for row in rows {
+            let (machine_id, json) = row?;
+            let parsed: Vec<String> = json
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default();
+            tags.insert(machine_id, parsed);
+        }
+        Ok(tags)
+    }
+
+    /// Vacuum one table under its enabled policies.
+    ///
+    /// When the table has a `machine_id` column, each machine's rows follow
+    /// the policy [`resolve_retention_policy`] picks for it; otherwise only
+    /// the table-wide policy applies. Rows older than the governing
+    /// `retention_days` go first; if the remainder still holds more than the
+    /// table-wide `max_total_bytes` of text, the oldest rows go too until it
+    /// fits, however young they are. Rows under a legal hold are kept either
+    /// way and left out of the size total.
     fn vacuum_table(
         &self,
-        policy: &RetentionPolicy,
+        table: &str,
+        policies: &[RetentionPolicy],
+        machine_tags: &std::collections::HashMap<String, Vec<String>>,
+        holds: &[LegalHold],
         dry_run: bool,
     ) -> Result<VacuumResult, StoreError> {
         let conn = self.conn.lock().unwrap();
         let start = std::time::Instant::now();
+        let cutoff = |policy: &RetentionPolicy| {
+            (Utc::now() - chrono::Duration::days(i64::from(policy.retention_days)))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        };
+        let in_list = |machines: &[String]| {
+            machines
+                .iter()
+                .map(|m| format!("'{}'", escape_sql_literal(m)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
 
-        // Calculate cutoff date
-        let cutoff = Utc::now() - chrono::Duration::days(i64::from(policy.retention_days));
-        let cutoff_str = cutoff.format("%Y-%m-%d %H:%M:%S").to_string();
-
-        // Count rows that would be deleted
         // Try common timestamp column names
-        let ts_column = Self::detect_timestamp_column(&conn, &policy.table_name)?;
+        let ts_column = Self::detect_timestamp_column(&conn, table)?;
+        let row_bytes = Self::row_bytes_expr(&conn, table)?;
+        let table_wide = policies.iter().find(|p| p.scope.is_none());
+        let held = legal_holds::held_predicate(&conn, table, &ts_column, holds)?;
+        let unheld = |predicate: &str| match &held {
+            Some(held) => format!("({predicate}) AND NOT {held}"),
+            None => predicate.to_string(),
+        };
 
-        let count_sql = format!(
-            "SELECT COUNT(*) FROM {} WHERE {} < '{}'",
-            policy.table_name, ts_column, cutoff_str
-        );
+        // (scope breakdown, age predicate) per policy that governs any rows
+        let mut groups: Vec<(VacuumScopeResult, String)> = Vec::new();
+        let mut scoped_machines: Vec<String> = Vec::new();
+        if policies.iter().any(|p| p.scope.is_some()) {
+            if Self::has_machine_column(&conn, table)? {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT DISTINCT machine_id FROM {table} WHERE machine_id IS NOT NULL \
+                     ORDER BY machine_id"
+                ))?;
+                let machines = stmt
+                    .query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                for policy in policies.iter().filter(|p| p.scope.is_some()) {
+                    let governed: Vec<String> = machines
+                        .iter()
+                        .filter(|m| {
+                            let tags = machine_tags.get(*m).map_or(&[][..], Vec::as_slice);
+                            resolve_retention_policy(policies, m, tags)
+                                .is_some_and(|p| p.policy_id == policy.policy_id)
+                        })
+                        .cloned()
+                        .collect();
+                    if governed.is_empty() {
+                        continue;
+                    }
+                    let predicate = format!(
+                        "machine_id IN ({}) AND {ts_column} < '{}'",
+                        in_list(&governed),
+                        cutoff(policy)
+                    );
+                    scoped_machines.extend(governed.iter().cloned());
+                    groups.push((
+                        VacuumScopeResult {
+                            scope: policy.scope.clone().unwrap_or_default(),
+                            policy_id: policy.policy_id.clone(),
+                            retention_days: Some(policy.retention_days),
+                            machines: governed,
+                            rows_would_delete: 0,
+                            bytes_would_reclaim: 0,
+                        },
+                        predicate,
+                    ));
+                }
+            } else {
+                tracing::warn!(
+                    table,
+                    "table has no machine_id column; scoped retention policies ignored"
+                );
+            }
+        }
+        if let Some(policy) = table_wide {
+            let mut predicate = format!("{ts_column} < '{}'", cutoff(policy));
+            if !scoped_machines.is_empty() {
+                predicate = format!(
+                    "(machine_id IS NULL OR machine_id NOT IN ({})) AND {predicate}",
+                    in_list(&scoped_machines)
+                );
+            }
+            groups.insert(
+                0,
+                (
+                    VacuumScopeResult {
+                        scope: TABLE_WIDE_SCOPE.to_string(),
+                        policy_id: policy.policy_id.clone(),
+                        retention_days: Some(policy.retention_days),
+                        machines: Vec::new(),
+                        rows_would_delete: 0,
+                        bytes_would_reclaim: 0,
+                    },
+                    predicate,
+                ),
+            );
+        }
+
+        let age_predicate = if groups.is_empty() {
+            "FALSE".to_string()
+        } else {
+            groups
+                .iter()
+                .map(|(_, predicate)| format!("({predicate})"))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        };
+        let mut predicate = age_predicate.clone();
+        if let Some(policy) = table_wide
+            && let Some(max_total_bytes) = policy.max_total_bytes
+        {
+            // Newest timestamp at which the running total, counted from the
+            // newest surviving row backwards, passes the cap.
+            let size_cutoff_sql = format!(
+                "SELECT CAST(MAX({ts_column}) AS VARCHAR) FROM ( \
+                     SELECT {ts_column}, SUM({row_bytes}) OVER ( \
+                         ORDER BY {ts_column} DESC ROWS BETWEEN UNBOUNDED PRECEDING AND CURRENT ROW \
+                     ) AS running_bytes \
+                     FROM {table} WHERE {unheld_rows} \
+                 ) WHERE running_bytes > {max_total_bytes}",
+                unheld_rows = unheld(&format!("NOT ({age_predicate})"))
+            );
+            let size_cutoff: Option<String> = conn
+                .query_row(&size_cutoff_sql, [], |row| row.get(0))
+                .unwrap_or(None);
+            if let Some(size_cutoff) = size_cutoff {
+                let size_predicate =
+                    format!("{ts_column} <= '{}'", escape_sql_literal(&size_cutoff));
+                groups.push((
+                    VacuumScopeResult {
+                        scope: "size cap".to_string(),
+                        policy_id: policy.policy_id.clone(),
+                        retention_days: None,
+                        machines: Vec::new(),
+                        rows_would_delete: 0,
+                        bytes_would_reclaim: 0,
+                    },
+                    format!("({size_predicate}) AND NOT ({age_predicate})"),
+                ));
+                predicate = format!("{predicate} OR {size_predicate}");
+            }
+        }
 
-        let rows_to_delete: i64 = conn
-            .query_row(&count_sql, [], |row| row.get(0))
-            .unwrap_or(0);
+        let count = |predicate: &str| -> (i64, i64) {
+            conn.query_row(
+                &format!(
+                    "SELECT COUNT(*), COALESCE(SUM({row_bytes}), 0) FROM {table} WHERE {predicate}"
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap_or((0, 0))
+        };
+        for (scope, scope_predicate) in &mut groups {
+            (scope.rows_would_delete, scope.bytes_would_reclaim) = count(&unheld(scope_predicate));
+        }
+        let rows_held = held
+            .as_ref()
+            .map_or(0, |held| count(&format!("({predicate}) AND {held}")).0);
+        let predicate = unheld(&predicate);
+        let (rows_to_delete, bytes_to_reclaim) = count(&predicate);
+
+        let mut result = VacuumResult {
+            table_name: table.to_string(),
+            rows_deleted: 0,
+            rows_would_delete: rows_to_delete,
+            rows_aggregated: 0,
+            bytes_reclaimed: 0,
+            bytes_would_reclaim: bytes_to_reclaim,
+            duration_ms: 0,
+            dry_run,
+            error: None,
+            rows_held,
+            scopes: groups.into_iter().map(|(scope, _)| scope).collect(),
+        };
+        // Logged under the table-wide policy when there is one
+        let log_policy_id = table_wide.unwrap_or(&policies[0]).policy_id.as_str();
 
         if dry_run {
             // Log dry-run result
-            Self::log_vacuum_result(
-                &conn,
-                policy,
-                0,
-                0,
-                i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-                true,
-                None,
-            )?;
-
-            return Ok(VacuumResult {
-                table_name: policy.table_name.clone(),
-                rows_deleted: 0,
-                rows_would_delete: rows_to_delete,
-                rows_aggregated: 0,
-                duration_ms: i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-                dry_run: true,
-                error: None,
-            });
+            result.duration_ms = i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX);
+            Self::log_vacuum_result(&conn, log_policy_id, &result)?;
+            return Ok(result);
         }
 
         // Actually delete old rows
-        let delete_sql = format!(
-            "DELETE FROM {} WHERE {} < '{}'",
-            policy.table_name, ts_column, cutoff_str
-        );
+        let delete_sql = format!("DELETE FROM {table} WHERE {predicate}");
 
-        let deleted = match conn.execute(&delete_sql, []) {
-            Ok(n) => i64::try_from(n).unwrap_or(i64::MAX),
+        match conn.execute(&delete_sql, []) {
+            Ok(n) => {
+                result.rows_deleted = i64::try_from(n).unwrap_or(i64::MAX);
+                result.bytes_reclaimed = bytes_to_reclaim;
+            }
             Err(e) => {
-                let error_msg = e.to_string();
-                Self::log_vacuum_result(
-                    &conn,
-                    policy,
-                    0,
-                    0,
-                    i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-                    false,
-                    Some(&error_msg),
-                )?;
-                return Ok(VacuumResult {
-                    table_name: policy.table_name.clone(),
-                    rows_deleted: 0,
-                    rows_would_delete: rows_to_delete,
-                    rows_aggregated: 0,
-                    duration_ms: i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-                    dry_run: false,
-                    error: Some(error_msg),
-                });
+                result.error = Some(e.to_string());
+                result.duration_ms = i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX);
+                Self::log_vacuum_result(&conn, log_policy_id, &result)?;
+                return Ok(result);
             }
-        };
+        }
 
         // Update last_vacuum_at
-        conn.execute(
-            "UPDATE retention_policies SET last_vacuum_at = current_timestamp WHERE policy_id = ?",
-            [&policy.policy_id],
-        )?;
+        for policy in policies {
+            conn.execute(
+                "UPDATE retention_policies SET last_vacuum_at = current_timestamp WHERE policy_id = ?",
+                [&policy.policy_id],
+            )?;
+        }
 
         // Log success
-        Self::log_vacuum_result(
-            &conn,
-            policy,
-            deleted,
-            0,
-            i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-            false,
-            None,
-        )?;
+        result.duration_ms = i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX);
+        Self::log_vacuum_result(&conn, log_policy_id, &result)?;
 
-        Ok(VacuumResult {
-            table_name: policy.table_name.clone(),
-            rows_deleted: deleted,
-            rows_would_delete: rows_to_delete,
-            rows_aggregated: 0,
-            duration_ms: i64::try_from(start.elapsed().as_millis()).unwrap_or(i64::MAX),
-            dry_run: false,
-            error: None,
-        })
+        Ok(result)
+    }
+
+    /// Whether `table_name` has a `machine_id` column
+    fn has_machine_column(
+        conn: &StoreConnectionGuard<'_>,
+        table_name: &str,
+    ) -> Result<bool, StoreError> {
+        Self::has_column(conn, table_name, "machine_id")
+    }
+
+    /// Whether `table_name` has a column named `column`
+    fn has_column(
+        conn: &StoreConnectionGuard<'_>,
+        table_name: &str,
+        column: &str,
+    ) -> Result<bool, StoreError> {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM information_schema.columns \
+             WHERE table_name = ? AND column_name = ?",
+            [table_name, column],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
     }
 
     /// Detect the timestamp column for a table
@@ -1149,76 +2962,333 @@ impl VcStore {
         )))
     }
 
-    /// Log a vacuum operation to `retention_log`
-    fn log_vacuum_result(
+    /// SQL expression for the text bytes of one row of `table_name`
+    fn row_bytes_expr(
         conn: &StoreConnectionGuard<'_>,
-        policy: &RetentionPolicy,
-        rows_deleted: i64,
-        rows_aggregated: i64,
-        duration_ms: i64,
-        dry_run: bool,
-        error_message: Option<&str>,
-    ) -> Result<(), StoreError> {
-        // Get next ID
-        let next_id: i64 = conn.query_row(
-            "SELECT COALESCE(MAX(id), 0) + 1 FROM retention_log",
-            [],
-            |row| row.get(0),
-        )?;
-
-        conn.execute(
-            "INSERT INTO retention_log (id, policy_id, table_name, rows_deleted, rows_aggregated, duration_ms, dry_run, error_message) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-            duckdb::params![next_id, policy.policy_id, policy.table_name, rows_deleted, rows_aggregated, duration_ms, dry_run, error_message],
-        )?;
+        table_name: &str,
+    ) -> Result<String, StoreError> {
+        let safe_table = escape_sql_literal(table_name);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT column_name FROM information_schema.columns \
+             WHERE table_name = '{safe_table}' AND data_type IN ('VARCHAR', 'JSON') \
+             ORDER BY ordinal_position"
+        ))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(0))?;
 
-        Ok(())
+        let mut terms = Vec::new();
+        for column in columns {
+            terms.push(format!(
+                "COALESCE(strlen(CAST(\"{}\" AS VARCHAR)), 0)",
+                escape_sql_identifier(&column?)
+            ));
+        }
+        if terms.is_empty() {
+            return Ok("0".to_string());
+        }
+        Ok(terms.join(" + "))
     }
 
-    /// Get vacuum history
-    ///
-    /// # Errors
-    ///
-    /// Returns [`StoreError`] if query execution fails.
-    pub fn list_vacuum_history(&self, limit: usize) -> Result<Vec<serde_json::Value>, StoreError> {
-        let limit = limit.min(1000);
-        let sql = format!(
-            "SELECT id, ts, policy_id, table_name, rows_deleted, rows_aggregated, duration_ms, dry_run, error_message \
-             FROM retention_log ORDER BY ts DESC LIMIT {limit}"
-        );
-        self.query_json(&sql)
+    /// Bytes taken by the non-text columns of one row of `table_name`
+    fn fixed_row_bytes(
+        conn: &StoreConnectionGuard<'_>,
+        table_name: &str,
+    ) -> Result<i64, StoreError> {
+        let mut stmt = conn.prepare(
+            "SELECT data_type FROM information_schema.columns \
+             WHERE table_name = ? AND data_type NOT IN ('VARCHAR', 'JSON')",
+        )?;
+        let types = stmt.query_map([table_name], |row| row.get::<_, String>(0))?;
+        let mut bytes = 0;
+        for data_type in types {
+            let data_type = data_type?;
+            bytes += match data_type.split('(').next().unwrap_or_default() {
+                "BOOLEAN" | "TINYINT" | "UTINYINT" => 1,
+                "SMALLINT" | "USMALLINT" => 2,
+                "INTEGER" | "UINTEGER" | "FLOAT" | "DATE" => 4,
+                "HUGEINT" | "UHUGEINT" | "UUID" | "INTERVAL" => 16,
+                _ => 8,
+            };
+        }
+        Ok(bytes)
     }
 
-    // =========================================================================
-    // Collector Health Methods
-    // =========================================================================
-
-    /// Record a collector health entry
+    /// Simulate a table-wide retention of `retention_days` on `table_name`:
+    /// what would be deletable right away and over the next `horizon_days`,
+    /// where it sits by machine and month, and which open incidents cover it.
+    ///
+    /// Scoped policies and `max_total_bytes` are ignored; this answers "what
+    /// if every row of the table were kept this long". Incidents carry no
+    /// machine, so any eligible row inside an open incident's window counts
+    /// as its evidence.
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if insert/upsert fails.
+    /// Returns [`StoreError::QueryError`] if the table does not exist or has
+    /// no timestamp column, and [`StoreError`] if a query fails.
     ///
     /// # Panics
     ///
     /// Panics if the internal database mutex is poisoned.
-    pub fn insert_collector_health(&self, health: &CollectorHealth) -> Result<(), StoreError> {
+    pub fn simulate_retention(
+        &self,
+        table_name: &str,
+        retention_days: i32,
+        horizon_days: i32,
+    ) -> Result<RetentionSimulation, StoreError> {
+        let current_retention_days = self
+            .get_retention_policy(table_name)?
+            .map(|policy| policy.retention_days);
+
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO collector_health \
-             (machine_id, collector, collected_at, success, duration_ms, rows_inserted, \
-              bytes_parsed, error_class, freshness_seconds, payload_hash, \
-              collector_version, schema_version, cursor_json) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            duckdb::params![
-                health.machine_id,
-                health.collector,
+        let exists: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM information_schema.tables WHERE table_name = ?",
+            [table_name],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            return Err(StoreError::QueryError(format!(
+                "No such table: '{table_name}'"
+            )));
+        }
+        let table = table_name;
+        let ts = Self::detect_timestamp_column(&conn, table)?;
+        let bytes = format!(
+            "({}) + {}",
+            Self::row_bytes_expr(&conn, table)?,
+            Self::fixed_row_bytes(&conn, table)?
+        );
+        let has_machine = Self::has_machine_column(&conn, table)?;
+
+        let now = Utc::now();
+        let at = |days_ago: i64| {
+            (now - chrono::Duration::days(days_ago))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        };
+        let retention = i64::from(retention_days);
+        let horizon = i64::from(horizon_days.max(0));
+        let eligible_now = format!("{ts} < '{}'", at(retention));
+        // Rows that cross the cutoff before the horizon ends
+        let eligible_horizon = format!(
+            "{ts} >= '{}' AND {ts} < '{}'",
+            at(retention),
+            at(retention - horizon)
+        );
+
+        let totals = |predicate: &str| -> Result<(i64, i64), StoreError> {
+            Ok(conn.query_row(
+                &format!(
+                    "SELECT COUNT(*), CAST(COALESCE(SUM({bytes}), 0) AS BIGINT) \
+                     FROM {table} WHERE {predicate}"
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?)
+        };
+        let (rows_total, bytes_total) = totals("TRUE")?;
+        let (rows_eligible_now, bytes_eligible_now) = totals(&eligible_now)?;
+        let (mut rows_eligible_horizon, mut bytes_eligible_horizon) = totals(&eligible_horizon)?;
+
+        let window = RETENTION_RATE_WINDOW_DAYS;
+        let (daily_insert_rows, daily_insert_bytes): (f64, f64) = conn.query_row(
+            &format!(
+                "SELECT COUNT(*) / {window}.0, COALESCE(SUM({bytes}), 0) / {window}.0 \
+                 FROM {table} WHERE {ts} >= '{}'",
+                at(i64::from(window))
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // Rows inserted during the first (horizon - retention) days age out
+        // before the horizon too
+        if horizon > retention {
+            let (rows, bytes): (i64, i64) = conn.query_row(
+                "SELECT CAST(ROUND(? * ?) AS BIGINT), CAST(ROUND(? * ?) AS BIGINT)",
+                duckdb::params![
+                    daily_insert_rows,
+                    horizon - retention,
+                    daily_insert_bytes,
+                    horizon - retention
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            rows_eligible_horizon += rows;
+            bytes_eligible_horizon += bytes;
+        }
+
+        let machine = if has_machine {
+            "machine_id"
+        } else {
+            "CAST(NULL AS VARCHAR)"
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {machine} AS machine, substr(CAST({ts} AS VARCHAR), 1, 7) AS month, \
+             COUNT(*), CAST(COALESCE(SUM({bytes}), 0) AS BIGINT) \
+             FROM {table} WHERE {eligible_now} \
+             GROUP BY machine, month ORDER BY machine NULLS LAST, month"
+        ))?;
+        let breakdown = stmt
+            .query_map([], |row| {
+                Ok(RetentionSimulationBucket {
+                    machine_id: row.get(0)?,
+                    month: row.get(1)?,
+                    rows: row.get(2)?,
+                    bytes: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT i.incident_id, i.title, i.severity, COALESCE(i.status, 'open'), \
+             CAST(i.started_at AS VARCHAR), CAST(i.ended_at AS VARCHAR), COUNT(*) \
+             FROM incidents i \
+             JOIN (SELECT TRY_CAST({ts} AS TIMESTAMP) AS ts FROM {table} WHERE {eligible_now}) t \
+               ON t.ts >= TRY_CAST(i.started_at AS TIMESTAMP) \
+              AND t.ts <= COALESCE(TRY_CAST(i.ended_at AS TIMESTAMP), \
+                                   CAST(current_timestamp AS TIMESTAMP)) \
+             WHERE COALESCE(i.status, 'open') NOT IN ('closed', 'resolved') \
+             GROUP BY ALL ORDER BY 5"
+        ))?;
+        let open_incidents = stmt
+            .query_map([], |row| {
+                Ok(RetentionIncidentLink {
+                    incident_id: row.get(0)?,
+                    title: row.get(1)?,
+                    severity: row.get(2)?,
+                    status: row.get(3)?,
+                    started_at: row.get(4)?,
+                    ended_at: row.get(5)?,
+                    rows: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RetentionSimulation {
+            table_name: table.to_string(),
+            retention_days,
+            current_retention_days,
+            horizon_days,
+            rows_total,
+            bytes_total,
+            rows_eligible_now,
+            bytes_eligible_now,
+            rows_eligible_horizon,
+            bytes_eligible_horizon,
+            daily_insert_rows,
+            daily_insert_bytes,
+            breakdown,
+            open_incidents,
+        })
+    }
+
+    /// [`Self::simulate_retention`] for every table with a retention policy,
+    /// most bytes reclaimed first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if listing policies or any simulation fails.
+    pub fn simulate_retention_all(
+        &self,
+        retention_days: i32,
+        horizon_days: i32,
+    ) -> Result<Vec<RetentionSimulation>, StoreError> {
+        let mut tables: Vec<String> = self
+            .list_retention_policies()?
+            .into_iter()
+            .map(|policy| policy.table_name)
+            .collect();
+        tables.dedup();
+
+        let mut simulations = tables
+            .iter()
+            .map(|table| self.simulate_retention(table, retention_days, horizon_days))
+            .collect::<Result<Vec<_>, _>>()?;
+        simulations.sort_by(|a, b| {
+            b.bytes_eligible_now
+                .cmp(&a.bytes_eligible_now)
+                .then_with(|| a.table_name.cmp(&b.table_name))
+        });
+        Ok(simulations)
+    }
+
+    /// Log a vacuum operation to `retention_log`
+    fn log_vacuum_result(
+        conn: &StoreConnectionGuard<'_>,
+        policy_id: &str,
+        result: &VacuumResult,
+    ) -> Result<(), StoreError> {
+        // Get next ID
+        let next_id: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) + 1 FROM retention_log",
+            [],
+            |row| row.get(0),
+        )?;
+
+        conn.execute(
+            "INSERT INTO retention_log (id, policy_id, table_name, rows_deleted, rows_aggregated, bytes_reclaimed, duration_ms, dry_run, error_message, rows_held) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                next_id,
+                policy_id,
+                result.table_name,
+                result.rows_deleted,
+                result.rows_aggregated,
+                result.bytes_reclaimed,
+                result.duration_ms,
+                result.dry_run,
+                result.error,
+                result.rows_held
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Get vacuum history
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_vacuum_history(&self, limit: usize) -> Result<Vec<serde_json::Value>, StoreError> {
+        let limit = limit.min(1000);
+        let sql = format!(
+            "SELECT id, ts, policy_id, table_name, rows_deleted, rows_aggregated, bytes_reclaimed, rows_held, duration_ms, dry_run, error_message \
+             FROM retention_log ORDER BY ts DESC LIMIT {limit}"
+        );
+        self.query_json(&sql)
+    }
+
+    // =========================================================================
+    // Collector Health Methods
+    // =========================================================================
+
+    /// Record a collector health entry
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if insert/upsert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn insert_collector_health(&self, health: &CollectorHealth) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO collector_health \
+             (machine_id, collector, collected_at, success, duration_ms, rows_inserted, \
+              bytes_parsed, error_class, error_cause, freshness_seconds, payload_hash, \
+              collector_version, schema_version, cursor_json) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                health.machine_id,
+                health.collector,
                 health.collected_at,
                 health.success,
                 health.duration_ms,
                 health.rows_inserted,
                 health.bytes_parsed,
                 health.error_class,
+                health.error_cause,
                 health.freshness_seconds,
                 health.payload_hash,
                 health.collector_version,
@@ -1229,6 +3299,118 @@ impl VcStore {
         Ok(())
     }
 
+    // =========================================================================
+    // Machine Service Methods
+    // =========================================================================
+
+    /// Replace the recorded service checks for a machine with `services`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the delete or any insert fails; the previous
+    /// rows are kept in that case.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn replace_machine_services(
+        &self,
+        machine_id: &str,
+        services: &[MachineServiceStatus],
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("BEGIN TRANSACTION", [])?;
+
+        let result = (|| {
+            conn.execute(
+                "DELETE FROM machine_services WHERE machine_id = ?",
+                [machine_id],
+            )?;
+            for service in services {
+                conn.execute(
+                    "INSERT INTO machine_services \
+                     (machine_id, service_name, kind, target, required, running, pid, \
+                      uptime_secs, detail, checked_at) \
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    duckdb::params![
+                        machine_id,
+                        service.service_name,
+                        service.kind,
+                        service.target,
+                        i32::from(service.required),
+                        i32::from(service.running),
+                        service.pid,
+                        service.uptime_secs,
+                        service.detail,
+                        service.checked_at,
+                    ],
+                )?;
+            }
+            Ok::<(), duckdb::Error>(())
+        })();
+
+        match result {
+            Ok(()) => {
+                conn.execute("COMMIT", [])?;
+                Ok(())
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Recorded service checks, for one machine or the whole fleet
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_machine_services(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<MachineServiceStatus>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let filter = if machine_id.is_some() {
+            "WHERE machine_id = ?"
+        } else {
+            ""
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT machine_id, service_name, kind, target, required, running, pid, \
+             uptime_secs, detail, checked_at \
+             FROM machine_services {filter} ORDER BY machine_id, service_name"
+        ))?;
+        let map_row = |row: &duckdb::Row<'_>| -> duckdb::Result<MachineServiceStatus> {
+            Ok(MachineServiceStatus {
+                machine_id: row.get(0)?,
+                service_name: row.get(1)?,
+                kind: row.get(2)?,
+                target: row.get(3)?,
+                required: row.get::<_, i32>(4)? != 0,
+                running: row.get::<_, i32>(5)? != 0,
+                pid: row.get(6)?,
+                uptime_secs: row.get(7)?,
+                detail: row.get(8)?,
+                checked_at: row.get(9)?,
+            })
+        };
+        let rows = match machine_id {
+            Some(id) => stmt.query_map([id], map_row)?,
+            None => stmt.query_map([], map_row)?,
+        };
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
     /// Record a fired alert in `alert_history`.
     ///
     /// `alert_history` had no writer at all before this: the table, the rules and
@@ -1244,11 +3426,18 @@ impl VcStore {
     /// Panics if the internal database mutex is poisoned.
     pub fn insert_alert(&self, alert: &FiredAlert) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
+        // DuckDB doesn't auto-increment INTEGER PRIMARY KEY
+        let next_id: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) + 1 FROM alert_history",
+            [],
+            |row| row.get(0),
+        )?;
         conn.execute(
             "INSERT INTO alert_history \
-             (rule_id, fired_at, severity, title, message, context_json, machine_id) \
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+             (id, rule_id, fired_at, severity, title, message, context_json, machine_id) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             duckdb::params![
+                next_id,
                 alert.rule_id,
                 alert.fired_at,
                 alert.severity,
@@ -1261,6 +3450,190 @@ impl VcStore {
         Ok(())
     }
 
+    /// Highest `alert_history` id, or 0 when no alert has fired yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn max_alert_id(&self) -> Result<i64, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let max_id = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM alert_history",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(max_id)
+    }
+
+    /// Alerts fired after `after_id`, oldest first, with their ids.
+    ///
+    /// The daemon uses this as a watermark to hand newly fired alerts to the
+    /// delivery channels exactly once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_alerts_after(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<(i64, FiredAlert)>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let limit = if limit == 0 { 100 } else { limit.min(1000) };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, rule_id, CAST(fired_at AS TEXT), severity, title, message, \
+             context_json, machine_id \
+             FROM alert_history WHERE id > ? ORDER BY id LIMIT {limit}"
+        ))?;
+        let rows = stmt.query_map(duckdb::params![after_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                FiredAlert {
+                    rule_id: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    fired_at: row.get(2)?,
+                    severity: row.get(3)?,
+                    title: row.get(4)?,
+                    message: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                    context_json: row.get(6)?,
+                    machine_id: row.get(7)?,
+                },
+            ))
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Machine context and open occurrence count for a notification about
+    /// an alert of `rule_id` on `machine_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation or execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn alert_notification_context(
+        &self,
+        rule_id: &str,
+        machine_id: Option<&str>,
+    ) -> Result<AlertNotificationContext, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let occurrences: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM alert_history \
+             WHERE rule_id = ? AND machine_id IS NOT DISTINCT FROM ? AND resolved_at IS NULL",
+            duckdb::params![rule_id, machine_id],
+            |row| row.get(0),
+        )?;
+        let Some(machine_id) = machine_id else {
+            return Ok(AlertNotificationContext {
+                occurrences,
+                ..AlertNotificationContext::default()
+            });
+        };
+
+        let hostname = match conn.query_row(
+            "SELECT hostname FROM machines WHERE machine_id = ?",
+            duckdb::params![machine_id],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(hostname) => Some(hostname),
+            Err(duckdb::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let health_score = match conn.query_row(
+            "SELECT overall_score FROM health_summary WHERE machine_id = ? \
+             ORDER BY TRY_CAST(collected_at AS TIMESTAMP) DESC NULLS LAST LIMIT 1",
+            duckdb::params![machine_id],
+            |row| row.get::<_, Option<f64>>(0),
+        ) {
+            Ok(score) => score,
+            Err(duckdb::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(AlertNotificationContext {
+            hostname,
+            health_score,
+            occurrences,
+        })
+    }
+
+    /// When an alert fired and resolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn alert_span(&self, alert_id: i64) -> Result<Option<AlertSpan>, StoreError> {
+        Ok(self
+            .select_alert_spans(&format!("WHERE id = {alert_id}"))?
+            .into_iter()
+            .next())
+    }
+
+    /// Latest alert of `alert_type` fired at or before `fired_before`,
+    /// optionally on one machine. `alert_type` matches a rule id exactly or
+    /// as its prefix before `:` (`collector-stale` matches
+    /// `collector-stale:sysmoni`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn latest_alert_span(
+        &self,
+        alert_type: &str,
+        machine_id: Option<&str>,
+        fired_before: &str,
+    ) -> Result<Option<AlertSpan>, StoreError> {
+        let alert_type = escape_sql_literal(alert_type);
+        let machine_filter = machine_id
+            .map(|id| format!(" AND machine_id = '{}'", escape_sql_literal(id)))
+            .unwrap_or_default();
+        Ok(self
+            .select_alert_spans(&format!(
+                "WHERE (rule_id = '{alert_type}' OR rule_id LIKE '{alert_type}:%') \
+                 AND CAST(fired_at AS TIMESTAMP) <= CAST('{}' AS TIMESTAMP){machine_filter} \
+                 ORDER BY fired_at DESC LIMIT 1",
+                escape_sql_literal(fired_before)
+            ))?
+            .into_iter()
+            .next())
+    }
+
+    fn select_alert_spans(&self, filter: &str) -> Result<Vec<AlertSpan>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, rule_id, machine_id, CAST(fired_at AS TEXT), CAST(resolved_at AS TEXT) \
+             FROM alert_history {filter}"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(AlertSpan {
+                alert_id: row.get(0)?,
+                rule_id: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                machine_id: row.get(2)?,
+                fired_at: row.get(3)?,
+                resolved_at: row.get(4)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
     /// Whether an unresolved alert for `rule_id` is already open.
     ///
     /// Used to keep a persistently unhealthy machine from re-raising the same
@@ -1296,20 +3669,757 @@ impl VcStore {
         Ok(count > 0)
     }
 
-    /// Get freshness summary for all collectors on a machine (or all machines)
+    /// Resolve every open alert raised by `rule_id`.
+    ///
+    /// Used for conditions that clear on their own (e.g. daemon resource
+    /// pressure) so the alert does not linger after recovery.
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    /// Returns [`StoreError`] if the update fails.
     ///
     /// # Panics
     ///
     /// Panics if the internal database mutex is poisoned.
-    pub fn get_freshness_summaries(
-        &self,
-        machine_id: Option<&str>,
-        stale_threshold_secs: i64,
-    ) -> Result<Vec<FreshnessSummary>, StoreError> {
+    pub fn resolve_open_alerts(&self, rule_id: &str) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE alert_history SET resolved_at = ?, resolution_kind = 'condition_cleared' \
+             WHERE rule_id = ? AND resolved_at IS NULL",
+            duckdb::params![
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                rule_id
+            ],
+        )?;
+        Ok(affected)
+    }
+
+    /// Resolve the open alerts `rule_id` raised for one machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn resolve_open_machine_alerts(
+        &self,
+        rule_id: &str,
+        machine_id: &str,
+    ) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE alert_history SET resolved_at = ?, resolution_kind = 'condition_cleared' \
+             WHERE rule_id = ? AND machine_id = ? AND resolved_at IS NULL",
+            duckdb::params![
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                rule_id,
+                machine_id
+            ],
+        )?;
+        Ok(affected)
+    }
+
+    /// Count the alerts a bulk operation would touch, with the newest
+    /// `sample` of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn match_alerts(
+        &self,
+        action: BulkAlertAction,
+        filter: &AlertFilter,
+        sample: usize,
+    ) -> Result<AlertMatch, StoreError> {
+        let where_sql = filter.where_sql(action);
+        let counted = self.query_json(&format!(
+            "SELECT COUNT(*) AS matched FROM alert_history {where_sql}"
+        ))?;
+        let matched = counted
+            .first()
+            .and_then(|row| row["matched"].as_u64())
+            .map_or(0, |n| usize::try_from(n).unwrap_or(usize::MAX));
+        let by_machine = self
+            .query_json(&format!(
+                "SELECT COALESCE(machine_id, 'unknown') AS machine_id, COUNT(*) AS matched \
+                 FROM alert_history {where_sql} GROUP BY 1"
+            ))?
+            .into_iter()
+            .filter_map(|row| {
+                Some((
+                    row["machine_id"].as_str()?.to_string(),
+                    usize::try_from(row["matched"].as_u64()?).unwrap_or(usize::MAX),
+                ))
+            })
+            .collect();
+        let sample = self.query_json(&format!(
+            "SELECT id, rule_id, CAST(fired_at AS TEXT) AS fired_at, severity, title, \
+             machine_id, acknowledged FROM alert_history {where_sql} \
+             ORDER BY fired_at DESC, id DESC LIMIT {}",
+            sample.min(1000)
+        ))?;
+        Ok(AlertMatch {
+            matched,
+            by_machine,
+            sample,
+        })
+    }
+
+    /// Acknowledge or resolve every alert matching `filter` in one update.
+    ///
+    /// Returns how many alerts changed; alerts already acknowledged (or
+    /// resolved) are left alone and not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn bulk_update_alerts(
+        &self,
+        action: BulkAlertAction,
+        filter: &AlertFilter,
+        actor: &ActorContext,
+    ) -> Result<usize, StoreError> {
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        let where_sql = filter.where_sql(action);
+        let conn = self.conn.lock().unwrap();
+        let affected = match action {
+            BulkAlertAction::Acknowledge => conn.execute(
+                &format!(
+                    "UPDATE alert_history SET acknowledged = 1, acknowledged_by = ?, \
+                     acknowledged_at = ? {where_sql}"
+                ),
+                duckdb::params![actor.name, now],
+            )?,
+            BulkAlertAction::Resolve => conn.execute(
+                &format!(
+                    "UPDATE alert_history SET resolved_at = ?, resolution_kind = 'manual', \
+                     resolved_by = ? {where_sql}"
+                ),
+                duckdb::params![now, actor.name],
+            )?,
+        };
+        Ok(affected)
+    }
+
+    /// Open, unacknowledged alerts grouped by rule and machine, oldest group
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn open_alert_groups(&self) -> Result<Vec<OpenAlertGroup>, StoreError> {
+        let rows = self.query_json(
+            "SELECT id, rule_id, machine_id, title, severity, \
+             CAST(fired_at AS TEXT) AS fired_at, \
+             COALESCE(escalation_level, 0) AS escalation_level, \
+             escalation_policy, escalated_at \
+             FROM alert_history \
+             WHERE resolved_at IS NULL AND COALESCE(acknowledged, 0) = 0 \
+             ORDER BY CAST(fired_at AS TIMESTAMP), id",
+        )?;
+
+        let mut groups: Vec<OpenAlertGroup> = Vec::new();
+        let mut levels: Vec<Vec<(i64, u32)>> = Vec::new();
+        for row in &rows {
+            let Some(id) = row["id"].as_i64() else {
+                continue;
+            };
+            let rule_id = row["rule_id"].as_str().unwrap_or("unknown").to_string();
+            let machine_id = row["machine_id"].as_str().map(ToString::to_string);
+            let level = row["escalation_level"]
+                .as_u64()
+                .and_then(|level| u32::try_from(level).ok())
+                .unwrap_or(0);
+
+            let index = if let Some(index) = groups
+                .iter()
+                .position(|g| g.rule_id == rule_id && g.machine_id == machine_id)
+            {
+                index
+            } else {
+                let first_fired_at = row["fired_at"]
+                    .as_str()
+                    .and_then(parse_alert_timestamp)
+                    .unwrap_or_else(Utc::now);
+                groups.push(OpenAlertGroup {
+                    title: row["title"].as_str().unwrap_or(&rule_id).to_string(),
+                    severity: row["severity"].as_str().unwrap_or("warning").to_string(),
+                    rule_id,
+                    machine_id,
+                    first_fired_at,
+                    alert_ids: Vec::new(),
+                    escalation_level: 0,
+                    escalation_policy: None,
+                    escalated_at: None,
+                    behind_ids: Vec::new(),
+                });
+                levels.push(Vec::new());
+                groups.len() - 1
+            };
+
+            let group = &mut groups[index];
+            group.alert_ids.push(id);
+            levels[index].push((id, level));
+            if level > group.escalation_level {
+                group.escalation_level = level;
+                group.escalation_policy =
+                    row["escalation_policy"].as_str().map(ToString::to_string);
+                group.escalated_at = row["escalated_at"].as_str().map(ToString::to_string);
+            }
+        }
+
+        for (group, levels) in groups.iter_mut().zip(levels) {
+            group.behind_ids = levels
+                .into_iter()
+                .filter(|(_, level)| *level < group.escalation_level)
+                .map(|(id, _)| id)
+                .collect();
+        }
+        Ok(groups)
+    }
+
+    /// Raise the given alerts to escalation `level`, optionally changing
+    /// their severity.
+    ///
+    /// Only occurrences still open, unacknowledged and below `level` change,
+    /// so repeating the call for a level already reached does nothing.
+    /// Returns how many alerts changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_alert_escalation(
+        &self,
+        alert_ids: &[i64],
+        level: u32,
+        policy: &str,
+        escalated_at: &str,
+        severity: Option<&str>,
+    ) -> Result<usize, StoreError> {
+        if alert_ids.is_empty() {
+            return Ok(0);
+        }
+        let ids: Vec<String> = alert_ids.iter().map(ToString::to_string).collect();
+        let severity_sql = severity
+            .map(|severity| format!(", severity = '{}'", escape_sql_literal(severity)))
+            .unwrap_or_default();
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            &format!(
+                "UPDATE alert_history SET escalation_level = ?, escalation_policy = ?, \
+                 escalated_at = ?{severity_sql} \
+                 WHERE id IN ({}) AND resolved_at IS NULL \
+                 AND COALESCE(acknowledged, 0) = 0 AND COALESCE(escalation_level, 0) < ?",
+                ids.join(", ")
+            ),
+            duckdb::params![level, policy, escalated_at, level],
+        )?;
+        Ok(affected)
+    }
+
+    /// Open alerts that name a machine, by machine and then firing order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn correlation_candidates(&self) -> Result<Vec<CorrelationCandidate>, StoreError> {
+        let rows = self.query_json(
+            "SELECT id, rule_id, machine_id, severity, CAST(fired_at AS TEXT) AS fired_at, \
+             correlation_id, COALESCE(correlation_root, 0) AS correlation_root \
+             FROM alert_history WHERE resolved_at IS NULL AND machine_id IS NOT NULL \
+             ORDER BY machine_id, CAST(fired_at AS TIMESTAMP), id",
+        )?;
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                Some(CorrelationCandidate {
+                    id: row["id"].as_i64()?,
+                    rule_id: row["rule_id"].as_str().unwrap_or("unknown").to_string(),
+                    machine_id: row["machine_id"].as_str()?.to_string(),
+                    severity: row["severity"].as_str().unwrap_or("warning").to_string(),
+                    fired_at: row["fired_at"].as_str().and_then(parse_alert_timestamp)?,
+                    correlation_id: row["correlation_id"].as_str().map(ToString::to_string),
+                    correlation_root: row["correlation_root"].as_i64() == Some(1)
+                        || row["correlation_root"].as_bool() == Some(true),
+                })
+            })
+            .collect())
+    }
+
+    /// Put the given alerts in `correlation_id` (or in no group, with
+    /// `None`), flagging `root_id` as the root. Returns how many alerts
+    /// changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_alert_correlation(
+        &self,
+        alert_ids: &[i64],
+        correlation_id: Option<&str>,
+        root_id: Option<i64>,
+    ) -> Result<usize, StoreError> {
+        if alert_ids.is_empty() {
+            return Ok(0);
+        }
+        let ids: Vec<String> = alert_ids.iter().map(ToString::to_string).collect();
+        let root_id = root_id.unwrap_or(-1);
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            &format!(
+                "UPDATE alert_history SET correlation_id = ?, \
+                 correlation_root = CASE WHEN id = ? THEN 1 ELSE 0 END \
+                 WHERE id IN ({}) AND (correlation_id IS DISTINCT FROM ? \
+                 OR COALESCE(correlation_root, 0) <> CASE WHEN id = ? THEN 1 ELSE 0 END)",
+                ids.join(", ")
+            ),
+            duckdb::params![correlation_id, root_id, correlation_id, root_id],
+        )?;
+        Ok(affected)
+    }
+
+    /// Every alert in a correlation group, open or not: the root first, then
+    /// in firing order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn correlated_alerts(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        self.query_json(&format!(
+            "SELECT id, rule_id, machine_id, severity, title, \
+             CAST(fired_at AS TEXT) AS fired_at, \
+             COALESCE(correlation_root, 0) = 1 AS root, \
+             COALESCE(acknowledged, 0) = 1 AS acknowledged, \
+             CAST(resolved_at AS TEXT) AS resolved_at \
+             FROM alert_history WHERE correlation_id = '{}' \
+             ORDER BY COALESCE(correlation_root, 0) DESC, CAST(fired_at AS TIMESTAMP), id",
+            escape_sql_literal(correlation_id)
+        ))
+    }
+
+    /// Open, unacknowledged alerts that `root_id` is the probable root of.
+    /// Empty when `root_id` is not currently a correlation root.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn correlated_children(&self, root_id: i64) -> Result<Vec<i64>, StoreError> {
+        let rows = self.query_json(&format!(
+            "SELECT child.id FROM alert_history child \
+             JOIN alert_history root ON child.correlation_id = root.correlation_id \
+             WHERE root.id = {root_id} AND COALESCE(root.correlation_root, 0) = 1 \
+             AND child.id <> root.id AND child.resolved_at IS NULL \
+             AND COALESCE(child.acknowledged, 0) = 0 \
+             ORDER BY child.id"
+        ))?;
+        Ok(rows.iter().filter_map(|row| row["id"].as_i64()).collect())
+    }
+
+    // =========================================================================
+    // Telemetry Export Methods
+    // =========================================================================
+
+    /// Saved export cursor for `stream`, or `None` before its first export.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn telemetry_cursor(
+        &self,
+        stream: TelemetryStream,
+    ) -> Result<Option<TelemetryCursor>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT last_id, last_ts, last_key FROM telemetry_export_state WHERE stream = ?",
+            [stream.as_str()],
+            |row| {
+                Ok(TelemetryCursor {
+                    last_id: row.get(0)?,
+                    last_ts: row.get(1)?,
+                    last_key: row.get(2)?,
+                })
+            },
+        );
+        match result {
+            Ok(cursor) => Ok(Some(cursor)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(StoreError::DatabaseError(e)),
+        }
+    }
+
+    /// Cursor just past the newest event currently in `stream`.
+    ///
+    /// The exporter starts here the first time it runs, so enabling it does
+    /// not replay all history.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn telemetry_head(&self, stream: TelemetryStream) -> Result<TelemetryCursor, StoreError> {
+        let (_, order) = stream.after_cursor();
+        let desc: Vec<String> = order.split(", ").map(|c| format!("{c} DESC")).collect();
+        let sql = format!(
+            "SELECT id, ts, key FROM ({}) AS ev WHERE ts IS NOT NULL ORDER BY {} LIMIT 1",
+            stream.source_sql(),
+            desc.join(", ")
+        );
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(&sql, [], |row| {
+            Ok(TelemetryCursor {
+                last_id: row.get(0)?,
+                last_ts: row.get(1)?,
+                last_key: row.get(2)?,
+            })
+        });
+        match result {
+            Ok(cursor) => Ok(cursor),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(TelemetryCursor::default()),
+            Err(e) => Err(StoreError::DatabaseError(e)),
+        }
+    }
+
+    /// Save the starting cursor for `stream` unless one is already saved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn init_telemetry_cursor(
+        &self,
+        stream: TelemetryStream,
+        cursor: &TelemetryCursor,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO telemetry_export_state (stream, last_id, last_ts, last_key) \
+             VALUES (?, ?, ?, ?) ON CONFLICT (stream) DO NOTHING",
+            duckdb::params![
+                stream.as_str(),
+                cursor.last_id,
+                cursor.last_ts,
+                cursor.last_key
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn bind_cursor(
+        stream: TelemetryStream,
+        cursor: &TelemetryCursor,
+    ) -> Vec<Box<dyn duckdb::ToSql>> {
+        let ts = cursor.last_ts.clone().unwrap_or_default();
+        match stream {
+            TelemetryStream::AlertFired => vec![Box::new(cursor.last_id)],
+            TelemetryStream::AlertResolved => {
+                vec![Box::new(ts.clone()), Box::new(ts), Box::new(cursor.last_id)]
+            }
+            TelemetryStream::Incident | TelemetryStream::CollectorFailure => vec![
+                Box::new(ts.clone()),
+                Box::new(ts),
+                Box::new(cursor.last_key.clone().unwrap_or_default()),
+            ],
+        }
+    }
+
+    /// Events in `stream` after `cursor`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn telemetry_events_after(
+        &self,
+        stream: TelemetryStream,
+        cursor: &TelemetryCursor,
+        limit: usize,
+    ) -> Result<Vec<TelemetryEvent>, StoreError> {
+        let limit = if limit == 0 { 100 } else { limit.min(1000) };
+        let (predicate, order) = stream.after_cursor();
+        let sql = format!(
+            "SELECT ts, id, key, machine_id, severity, alert_type, incident_id, name, body \
+             FROM ({}) AS ev WHERE ts IS NOT NULL AND {predicate} ORDER BY {order} LIMIT {limit}",
+            stream.source_sql()
+        );
+        let params = Self::bind_cursor(stream, cursor);
+        let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(param_refs.as_slice(), |row| {
+            let ts: String = row.get(0)?;
+            let id: i64 = row.get(1)?;
+            let key: String = row.get(2)?;
+            Ok(TelemetryEvent {
+                stream,
+                name: row.get(7)?,
+                severity: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                body: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+                machine_id: row.get(3)?,
+                alert_type: row.get(5)?,
+                incident_id: row.get(6)?,
+                cursor: TelemetryCursor {
+                    last_id: id,
+                    last_ts: Some(ts.clone()),
+                    last_key: Some(key),
+                },
+                ts,
+            })
+        })?;
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(row?);
+        }
+        Ok(events)
+    }
+
+    /// Record a successful export of `count` events ending at `cursor`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the upsert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_telemetry_export(
+        &self,
+        stream: TelemetryStream,
+        cursor: &TelemetryCursor,
+        count: usize,
+    ) -> Result<(), StoreError> {
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO telemetry_export_state \
+             (stream, last_id, last_ts, last_key, exported_count, last_success_at, last_attempt_at, last_error) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, NULL) \
+             ON CONFLICT (stream) DO UPDATE SET \
+                last_id = excluded.last_id, \
+                last_ts = excluded.last_ts, \
+                last_key = excluded.last_key, \
+                exported_count = telemetry_export_state.exported_count + excluded.exported_count, \
+                last_success_at = excluded.last_success_at, \
+                last_attempt_at = excluded.last_attempt_at, \
+                last_error = NULL",
+            duckdb::params![
+                stream.as_str(),
+                cursor.last_id,
+                cursor.last_ts,
+                cursor.last_key,
+                i64::try_from(count).unwrap_or(i64::MAX),
+                now,
+                now,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed export attempt; the cursor stays where it was.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_telemetry_failure(
+        &self,
+        stream: TelemetryStream,
+        error: &str,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE telemetry_export_state SET last_attempt_at = ?, last_error = ? WHERE stream = ?",
+            duckdb::params![Utc::now().to_rfc3339(), error, stream.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Backlog and last export of every stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if any query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn telemetry_status(&self) -> Result<Vec<TelemetryStreamStatus>, StoreError> {
+        let mut statuses = Vec::new();
+        for stream in TelemetryStream::ALL {
+            let cursor = self.telemetry_cursor(stream)?.unwrap_or_default();
+            let (predicate, _) = stream.after_cursor();
+            let sql = format!(
+                "SELECT COUNT(*) FROM ({}) AS ev WHERE ts IS NOT NULL AND {predicate}",
+                stream.source_sql()
+            );
+            let params = Self::bind_cursor(stream, &cursor);
+            let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+
+            let conn = self.conn.lock().unwrap();
+            let backlog: i64 = conn.query_row(&sql, param_refs.as_slice(), |row| row.get(0))?;
+            let state = conn.query_row(
+                "SELECT exported_count, last_success_at, last_attempt_at, last_error \
+                 FROM telemetry_export_state WHERE stream = ?",
+                [stream.as_str()],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            );
+            let (exported_count, last_success_at, last_attempt_at, last_error) = match state {
+                Ok(state) => state,
+                Err(duckdb::Error::QueryReturnedNoRows) => (0, None, None, None),
+                Err(e) => return Err(StoreError::DatabaseError(e)),
+            };
+            statuses.push(TelemetryStreamStatus {
+                stream,
+                backlog,
+                exported_count,
+                last_success_at,
+                last_attempt_at,
+                last_error,
+            });
+        }
+        Ok(statuses)
+    }
+
+    // =========================================================================
+    // Daemon Resource State Methods
+    // =========================================================================
+
+    /// Record the daemon's latest resource self-limit check. Each check is
+    /// also a heartbeat for its UTC day in `daemon_heartbeats`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the upsert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn upsert_daemon_resource_state(
+        &self,
+        state: &DaemonResourceState,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO daemon_resource_state \
+             (id, checked_at, level, disk_pressure, rss_mb, free_disk_mb, \
+              max_rss_mb, min_free_disk_mb, reason, watch_subscribers) \
+             VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                state.checked_at,
+                state.level,
+                state.disk_pressure,
+                state.rss_mb,
+                state.free_disk_mb,
+                state.max_rss_mb,
+                state.min_free_disk_mb,
+                state.reason,
+                state.watch_subscribers,
+            ],
+        )?;
+        let day = DateTime::parse_from_rfc3339(&state.checked_at).map_or_else(
+            |_| state.checked_at.chars().take(10).collect(),
+            |at| at.with_timezone(&Utc).format("%Y-%m-%d").to_string(),
+        );
+        conn.execute(
+            "INSERT INTO daemon_heartbeats (day, first_at, last_at, beats) VALUES (?, ?, ?, 1) \
+             ON CONFLICT (day) DO UPDATE SET last_at = excluded.last_at, \
+             beats = daemon_heartbeats.beats + 1",
+            duckdb::params![day, state.checked_at, state.checked_at],
+        )?;
+        Ok(())
+    }
+
+    /// Fetch the daemon's latest resource self-limit check, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn get_daemon_resource_state(&self) -> Result<Option<DaemonResourceState>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT CAST(checked_at AS TEXT), level, disk_pressure, rss_mb, free_disk_mb, \
+             max_rss_mb, min_free_disk_mb, reason, watch_subscribers \
+             FROM daemon_resource_state WHERE id = 1",
+            [],
+            |row| {
+                Ok(DaemonResourceState {
+                    checked_at: row.get(0)?,
+                    level: row.get(1)?,
+                    disk_pressure: row.get(2)?,
+                    rss_mb: row.get(3)?,
+                    free_disk_mb: row.get(4)?,
+                    max_rss_mb: row.get(5)?,
+                    min_free_disk_mb: row.get(6)?,
+                    reason: row.get(7)?,
+                    watch_subscribers: row.get(8)?,
+                })
+            },
+        );
+        match result {
+            Ok(state) => Ok(Some(state)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get freshness summary for all collectors on a machine (or all machines)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn get_freshness_summaries(
+        &self,
+        machine_id: Option<&str>,
+        stale_threshold_secs: i64,
+    ) -> Result<Vec<FreshnessSummary>, StoreError> {
         let conn = self.conn.lock().unwrap();
 
         let machine_filter = match machine_id {
@@ -1317,229 +4427,823 @@ impl VcStore {
             None => String::new(),
         };
 
-        // For each machine/collector pair, get:
-        // - last successful collection timestamp
-        // - freshness in seconds (now - last success)
-        // - success rate over last 24h
-        // - total runs over last 24h
-        // Cast current_timestamp to TIMESTAMP to match the collected_at column type
-        // (DuckDB's current_timestamp returns TIMESTAMP WITH TIME ZONE)
-        let sql = format!(
-            "SELECT \
-                machine_id, \
-                collector, \
-                CAST(MAX(CASE WHEN success THEN collected_at END) AS TEXT) AS last_success_at, \
-                COALESCE(CAST(EXTRACT(EPOCH FROM (CAST(current_timestamp AS TIMESTAMP) - \
-                    MAX(CASE WHEN success THEN CAST(collected_at AS TIMESTAMP) END))) AS BIGINT), -1) \
-                    AS freshness_seconds, \
-                COALESCE(AVG(CASE WHEN CAST(collected_at AS TIMESTAMP) > \
-                    CAST(current_timestamp AS TIMESTAMP) - INTERVAL '24 hours' \
-                    THEN CASE WHEN success THEN 1.0 ELSE 0.0 END END), 0.0) AS success_rate_24h, \
-                COALESCE(COUNT(CASE WHEN CAST(collected_at AS TIMESTAMP) > \
-                    CAST(current_timestamp AS TIMESTAMP) - INTERVAL '24 hours' \
-                    THEN 1 END), 0) AS total_runs_24h \
-             FROM collector_health \
-             {machine_filter} \
-             GROUP BY machine_id, collector \
-             ORDER BY machine_id, collector"
-        );
+        // For each machine/collector pair, get:
+        // - last successful collection timestamp
+        // - freshness in seconds (now - last success)
+        // - success rate over last 24h
+        // - total runs over last 24h
+        // Cast current_timestamp to TIMESTAMP to match the collected_at column type
+        // (DuckDB's current_timestamp returns TIMESTAMP WITH TIME ZONE)
+        let sql = format!(
+            "SELECT \
+                machine_id, \
+                collector, \
+                CAST(MAX(CASE WHEN success THEN collected_at END) AS TEXT) AS last_success_at, \
+                COALESCE(CAST(EXTRACT(EPOCH FROM (CAST(current_timestamp AS TIMESTAMP) - \
+                    MAX(CASE WHEN success THEN CAST(collected_at AS TIMESTAMP) END))) AS BIGINT), -1) \
+                    AS freshness_seconds, \
+                COALESCE(AVG(CASE WHEN CAST(collected_at AS TIMESTAMP) > \
+                    CAST(current_timestamp AS TIMESTAMP) - INTERVAL '24 hours' \
+                    THEN CASE WHEN success THEN 1.0 ELSE 0.0 END END), 0.0) AS success_rate_24h, \
+                COALESCE(COUNT(CASE WHEN CAST(collected_at AS TIMESTAMP) > \
+                    CAST(current_timestamp AS TIMESTAMP) - INTERVAL '24 hours' \
+                    THEN 1 END), 0) AS total_runs_24h \
+             FROM collector_health \
+             {machine_filter} \
+             GROUP BY machine_id, collector \
+             ORDER BY machine_id, collector"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            let freshness_secs: i64 = row.get(3)?;
+            Ok(FreshnessSummary {
+                machine_id: row.get(0)?,
+                collector: row.get(1)?,
+                last_success_at: row.get(2)?,
+                freshness_seconds: freshness_secs,
+                success_rate_24h: row.get(4)?,
+                total_runs_24h: row.get(5)?,
+                stale: freshness_secs < 0 || freshness_secs > stale_threshold_secs,
+            })
+        })?;
+
+        let mut summaries = Vec::new();
+        for row in rows {
+            summaries.push(row?);
+        }
+        Ok(summaries)
+    }
+
+    /// Most recent failed run of a collector on a machine, as
+    /// `(collected_at, error_class)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn last_collector_error(
+        &self,
+        machine_id: &str,
+        collector: &str,
+    ) -> Result<Option<(String, Option<String>)>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT CAST(collected_at AS TEXT), error_class FROM collector_health \
+             WHERE machine_id = ? AND collector = ? AND success = 0 \
+             ORDER BY collected_at DESC LIMIT 1",
+            duckdb::params![machine_id, collector],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        );
+        match result {
+            Ok(error) => Ok(Some(error)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get recent collector health entries
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_collector_health(
+        &self,
+        machine_id: Option<&str>,
+        collector: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let mut clauses: Vec<String> = Vec::new();
+
+        if let Some(id) = machine_id {
+            clauses.push(format!("machine_id = '{}'", escape_sql_literal(id)));
+        }
+        if let Some(c) = collector {
+            clauses.push(format!("collector = '{}'", escape_sql_literal(c)));
+        }
+
+        let where_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+
+        let limit = limit.min(1000);
+        let sql = format!(
+            "SELECT machine_id, collector, collected_at, success, duration_ms, \
+             rows_inserted, bytes_parsed, error_class, error_cause, freshness_seconds, \
+             payload_hash, collector_version, schema_version \
+             FROM collector_health {where_sql} \
+             ORDER BY collected_at DESC LIMIT {limit}"
+        );
+
+        self.query_json(&sql)
+    }
+
+    /// Failed collector runs since `since`, counted per machine and cause,
+    /// optionally only those of one machine and/or collector.
+    ///
+    /// Runs recorded before causes were classified count as `unclassified`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn collector_failure_causes(
+        &self,
+        machine_id: Option<&str>,
+        collector: Option<&str>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<FailureCauseCount>, StoreError> {
+        let mut filter = String::new();
+        if let Some(id) = machine_id {
+            let _ = write!(filter, " AND machine_id = '{}'", escape_sql_literal(id));
+        }
+        if let Some(c) = collector {
+            let _ = write!(filter, " AND collector = '{}'", escape_sql_literal(c));
+        }
+        let sql = format!(
+            "SELECT machine_id, COALESCE(error_cause, 'unclassified') AS cause, \
+                    COUNT(*) AS failures, MAX(collected_at) AS last_at \
+             FROM collector_health \
+             WHERE success = 0 AND collected_at >= '{}'{filter} \
+             GROUP BY 1, 2 ORDER BY machine_id, failures DESC, cause",
+            since.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+        );
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok(FailureCauseCount {
+                machine_id: row.get(0)?,
+                cause: row.get(1)?,
+                failures: row.get(2)?,
+                last_at: row.get(3)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    // =========================================================================
+    // Machine Baseline Methods
+    // =========================================================================
+
+    /// Upsert a machine baseline
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if baseline serialization or upsert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_machine_baseline(
+        &self,
+        machine_id: &str,
+        baseline_window: &str,
+        metrics_json: &serde_json::Value,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let metrics_str = serde_json::to_string(metrics_json)?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO machine_baselines \
+             (machine_id, baseline_window, computed_at, metrics_json) \
+             VALUES (?, ?, current_timestamp, ?)",
+            duckdb::params![machine_id, baseline_window, metrics_str],
+        )?;
+        Ok(())
+    }
+
+    /// Get a machine baseline
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if baseline query fails with an error other than no rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn get_machine_baseline(
+        &self,
+        machine_id: &str,
+        baseline_window: &str,
+    ) -> Result<Option<MachineBaseline>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(
+            "SELECT machine_id, baseline_window, CAST(computed_at AS TEXT), metrics_json \
+             FROM machine_baselines WHERE machine_id = ? AND baseline_window = ?",
+            duckdb::params![machine_id, baseline_window],
+            |row| {
+                let metrics_str: String = row.get(3)?;
+                Ok(MachineBaseline {
+                    machine_id: row.get(0)?,
+                    baseline_window: row.get(1)?,
+                    computed_at: row.get(2)?,
+                    metrics_json: serde_json::from_str(&metrics_str).unwrap_or_default(),
+                })
+            },
+        );
+
+        match result {
+            Ok(baseline) => Ok(Some(baseline)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// List all baselines for a machine
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_machine_baselines(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let where_clause = match machine_id {
+            Some(id) => format!("WHERE machine_id = '{}'", escape_sql_literal(id)),
+            None => String::new(),
+        };
+        let sql = format!(
+            "SELECT machine_id, baseline_window, computed_at, metrics_json \
+             FROM machine_baselines {where_clause} \
+             ORDER BY machine_id, baseline_window"
+        );
+        self.query_json(&sql)
+    }
+
+    // =========================================================================
+    // Drift Detection Methods
+    // =========================================================================
+
+    /// Record a drift event
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if insert or ID allocation fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn insert_drift_event(&self, event: &DriftEvent) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+
+        let next_id: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) + 1 FROM drift_events",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let evidence_str = event
+            .evidence_json
+            .as_ref()
+            .map(|v| serde_json::to_string(v).unwrap_or_default());
+
+        conn.execute(
+            "INSERT INTO drift_events \
+             (id, machine_id, detected_at, metric, current_value, baseline_mean, \
+              baseline_std, z_score, severity, evidence_json, previous_value, new_value, \
+              delta, baseline_ref, detection_method) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                next_id,
+                event.machine_id,
+                event.detected_at,
+                event.metric,
+                event.current_value,
+                event.baseline_mean,
+                event.baseline_std,
+                event.z_score,
+                event.severity.as_str(),
+                evidence_str,
+                event.previous_value,
+                event.new_value,
+                event.delta,
+                event.baseline_ref,
+                event.detection_method,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Record a tool version observed on a machine.
+    ///
+    /// Repeat observations of the current version only bump `last_seen_at`.
+    /// A different version opens a new history row and records a
+    /// `{tool}_version` drift event (warning for a downgrade, info otherwise),
+    /// returning the version it replaced. The first version seen for a tool
+    /// is not drift.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the lookup, history write or drift insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_tool_version(
+        &self,
+        machine_id: &str,
+        tool_name: &str,
+        version: &str,
+        source: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let now = Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        let current = self.latest_tool_version(machine_id, tool_name)?;
+
+        {
+            let conn = self.conn.lock().unwrap();
+            if let Some(current) = &current
+                && current.tool_version == version
+            {
+                conn.execute(
+                    "UPDATE tool_version_history SET last_seen_at = ? \
+                     WHERE machine_id = ? AND tool_name = ? AND first_seen_at = ?",
+                    duckdb::params![now, machine_id, tool_name, current.first_seen_at],
+                )?;
+                return Ok(None);
+            }
+
+            conn.execute(
+                "INSERT INTO tool_version_history \
+                 (machine_id, tool_name, tool_version, previous_version, source, \
+                  first_seen_at, last_seen_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+                duckdb::params![
+                    machine_id,
+                    tool_name,
+                    version,
+                    current.as_ref().map(|c| c.tool_version.clone()),
+                    source,
+                    now,
+                    now,
+                ],
+            )?;
+        }
+
+        let Some(previous) = current.map(|c| c.tool_version) else {
+            return Ok(None);
+        };
+        let severity = if compare_versions(version, &previous).is_lt() {
+            DriftSeverity::Warning
+        } else {
+            DriftSeverity::Info
+        };
+        self.insert_drift_event(&DriftEvent {
+            machine_id: machine_id.to_string(),
+            detected_at: now,
+            metric: format!("{tool_name}_version"),
+            current_value: 0.0,
+            baseline_mean: 0.0,
+            baseline_std: 0.0,
+            z_score: 0.0,
+            severity,
+            evidence_json: Some(serde_json::json!({ "tool": tool_name, "source": source })),
+            previous_value: Some(previous.clone()),
+            new_value: Some(version.to_string()),
+            delta: None,
+            baseline_ref: None,
+            detection_method: Some("value_change".to_string()),
+        })?;
+        Ok(Some(previous))
+    }
+
+    /// Most recent version recorded for one tool on one machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn latest_tool_version(
+        &self,
+        machine_id: &str,
+        tool_name: &str,
+    ) -> Result<Option<ToolVersion>, StoreError> {
+        Ok(self
+            .tool_version_history(machine_id, Some(tool_name), 1)?
+            .into_iter()
+            .next())
+    }
+
+    /// Current version of every tool, per machine (or for one machine).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_tool_versions(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<ToolVersion>, StoreError> {
+        let filter = match machine_id {
+            Some(id) => format!("WHERE machine_id = '{}'", escape_sql_literal(id)),
+            None => String::new(),
+        };
+        self.select_tool_versions(&format!(
+            "SELECT machine_id, tool_name, tool_version, previous_version, source, \
+             first_seen_at, last_seen_at FROM ( \
+                 SELECT *, ROW_NUMBER() OVER ( \
+                     PARTITION BY machine_id, tool_name ORDER BY first_seen_at DESC \
+                 ) AS rn FROM tool_version_history {filter} \
+             ) WHERE rn = 1 ORDER BY tool_name, machine_id"
+        ))
+    }
+
+    /// Version history for a machine, newest first, optionally for one tool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn tool_version_history(
+        &self,
+        machine_id: &str,
+        tool_name: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ToolVersion>, StoreError> {
+        let tool_filter = tool_name
+            .map(|tool| format!(" AND tool_name = '{}'", escape_sql_literal(tool)))
+            .unwrap_or_default();
+        self.select_tool_versions(&format!(
+            "SELECT machine_id, tool_name, tool_version, previous_version, source, \
+             first_seen_at, last_seen_at FROM tool_version_history \
+             WHERE machine_id = '{}'{tool_filter} \
+             ORDER BY first_seen_at DESC, tool_name LIMIT {}",
+            escape_sql_literal(machine_id),
+            limit.min(1000)
+        ))
+    }
 
-        let mut stmt = conn.prepare(&sql)?;
+    fn select_tool_versions(&self, sql: &str) -> Result<Vec<ToolVersion>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
         let rows = stmt.query_map([], |row| {
-            let freshness_secs: i64 = row.get(3)?;
-            Ok(FreshnessSummary {
+            Ok(ToolVersion {
                 machine_id: row.get(0)?,
-                collector: row.get(1)?,
-                last_success_at: row.get(2)?,
-                freshness_seconds: freshness_secs,
-                success_rate_24h: row.get(4)?,
-                total_runs_24h: row.get(5)?,
-                stale: freshness_secs < 0 || freshness_secs > stale_threshold_secs,
+                tool_name: row.get(1)?,
+                tool_version: row.get(2)?,
+                previous_version: row.get(3)?,
+                source: row.get(4)?,
+                first_seen_at: row.get(5)?,
+                last_seen_at: row.get(6)?,
             })
         })?;
 
-        let mut summaries = Vec::new();
+        let mut results = Vec::new();
         for row in rows {
-            summaries.push(row?);
+            results.push(row?);
         }
-        Ok(summaries)
+        Ok(results)
     }
 
-    /// Get recent collector health entries
+    /// Record a clock skew measurement.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_clock_skew(&self, sample: &ClockSkewSample) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO machine_clock_skew \
+             (machine_id, measured_at, skew_ms, rtt_ms, source) VALUES (?, ?, ?, ?, ?)",
+            duckdb::params![
+                sample.machine_id,
+                sample.measured_at,
+                sample.skew_ms,
+                sample.rtt_ms,
+                sample.source,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Clock skew measurements for a machine, newest first.
     ///
     /// # Errors
     ///
     /// Returns [`StoreError`] if query execution fails.
-    pub fn list_collector_health(
+    pub fn clock_skew_history(
         &self,
-        machine_id: Option<&str>,
-        collector: Option<&str>,
+        machine_id: &str,
         limit: usize,
-    ) -> Result<Vec<serde_json::Value>, StoreError> {
-        let mut clauses: Vec<String> = Vec::new();
-
-        if let Some(id) = machine_id {
-            clauses.push(format!("machine_id = '{}'", escape_sql_literal(id)));
-        }
-        if let Some(c) = collector {
-            clauses.push(format!("collector = '{}'", escape_sql_literal(c)));
-        }
+    ) -> Result<Vec<ClockSkewSample>, StoreError> {
+        self.select_clock_skew(&format!(
+            "SELECT machine_id, measured_at, skew_ms, rtt_ms, source \
+             FROM machine_clock_skew WHERE machine_id = '{}' \
+             ORDER BY measured_at DESC LIMIT {}",
+            escape_sql_literal(machine_id),
+            limit.min(1000)
+        ))
+    }
 
-        let where_sql = if clauses.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", clauses.join(" AND "))
+    /// Latest clock skew measurement of every machine (or of one machine).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_clock_skew(
+        &self,
+        machine_id: Option<&str>,
+    ) -> Result<Vec<ClockSkewSample>, StoreError> {
+        let filter = match machine_id {
+            Some(id) => format!("WHERE machine_id = '{}'", escape_sql_literal(id)),
+            None => String::new(),
         };
+        self.select_clock_skew(&format!(
+            "SELECT machine_id, measured_at, skew_ms, rtt_ms, source FROM ( \
+                 SELECT *, ROW_NUMBER() OVER ( \
+                     PARTITION BY machine_id ORDER BY measured_at DESC \
+                 ) AS rn FROM machine_clock_skew {filter} \
+             ) WHERE rn = 1 ORDER BY machine_id"
+        ))
+    }
 
-        let limit = limit.min(1000);
-        let sql = format!(
-            "SELECT machine_id, collector, collected_at, success, duration_ms, \
-             rows_inserted, bytes_parsed, error_class, freshness_seconds, payload_hash \
-             FROM collector_health {where_sql} \
-             ORDER BY collected_at DESC LIMIT {limit}"
-        );
+    /// Skew to correct a machine's timestamps by: its newest measurement
+    /// that [`ClockSkewSample::is_correctable`], if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn effective_clock_skew(
+        &self,
+        machine_id: &str,
+    ) -> Result<Option<ClockSkewSample>, StoreError> {
+        Ok(self
+            .select_clock_skew(&format!(
+                "SELECT machine_id, measured_at, skew_ms, rtt_ms, source \
+                 FROM machine_clock_skew \
+                 WHERE machine_id = '{}' AND (rtt_ms IS NOT NULL OR skew_ms > 0) \
+                 ORDER BY measured_at DESC LIMIT 1",
+                escape_sql_literal(machine_id)
+            ))?
+            .into_iter()
+            .next())
+    }
+
+    fn select_clock_skew(&self, sql: &str) -> Result<Vec<ClockSkewSample>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ClockSkewSample {
+                machine_id: row.get(0)?,
+                measured_at: row.get(1)?,
+                skew_ms: row.get(2)?,
+                rtt_ms: row.get(3)?,
+                source: row.get(4)?,
+            })
+        })?;
 
-        self.query_json(&sql)
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
     }
 
-    // =========================================================================
-    // Machine Baseline Methods
-    // =========================================================================
-
-    /// Upsert a machine baseline
+    /// Put a machine into maintenance, or update the reason and end of the
+    /// window it is already in.
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if baseline serialization or upsert fails.
+    /// Returns [`StoreError`] if the machine is unknown or a write fails.
     ///
     /// # Panics
     ///
     /// Panics if the internal database mutex is poisoned.
-    pub fn set_machine_baseline(
+    pub fn start_maintenance(
         &self,
         machine_id: &str,
-        baseline_window: &str,
-        metrics_json: &serde_json::Value,
-    ) -> Result<(), StoreError> {
-        let conn = self.conn.lock().unwrap();
-        let metrics_str = serde_json::to_string(metrics_json)?;
-
-        conn.execute(
-            "INSERT OR REPLACE INTO machine_baselines \
-             (machine_id, baseline_window, computed_at, metrics_json) \
-             VALUES (?, ?, current_timestamp, ?)",
-            duckdb::params![machine_id, baseline_window, metrics_str],
+        reason: Option<&str>,
+        until: Option<DateTime<Utc>>,
+        actor: &ActorContext,
+    ) -> Result<MaintenanceWindow, StoreError> {
+        let until_at = until.map(|ts| ts.to_rfc3339_opts(chrono::SecondsFormat::Micros, true));
+        let existing = self.maintenance_window(machine_id)?;
+        {
+            let conn = self.conn.lock().unwrap();
+            let updated = conn.execute(
+                "UPDATE machines SET status = 'maintenance' WHERE machine_id = ?",
+                [machine_id],
+            )?;
+            if updated == 0 {
+                return Err(StoreError::QueryError(format!(
+                    "unknown machine: {machine_id}"
+                )));
+            }
+            match &existing {
+                Some(window) => conn.execute(
+                    "UPDATE machine_maintenance SET reason = ?, until_at = ? \
+                     WHERE machine_id = ? AND started_at = ?",
+                    duckdb::params![reason, until_at, machine_id, window.started_at],
+                )?,
+                None => conn.execute(
+                    "INSERT INTO machine_maintenance \
+                     (machine_id, started_at, reason, until_at, started_by) \
+                     VALUES (?, ?, ?, ?, ?)",
+                    duckdb::params![
+                        machine_id,
+                        Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                        reason,
+                        until_at,
+                        actor.name,
+                    ],
+                )?,
+            };
+            if existing.is_none() {
+                annotations::insert_annotation(
+                    &conn,
+                    &maintenance_annotation(
+                        machine_id,
+                        format!(
+                            "maintenance started: {}",
+                            reason.unwrap_or("no reason given")
+                        ),
+                    ),
+                    Some(&actor.name),
+                )?;
+            }
+        }
+        self.insert_audit_event(
+            &actor
+                .audit_event(
+                    AuditEventType::UserCommand,
+                    if existing.is_some() {
+                        "maintenance_update"
+                    } else {
+                        "maintenance_on"
+                    },
+                    AuditResult::Success,
+                    serde_json::json!({ "reason": reason, "until": until_at }),
+                )
+                .with_machine_id(machine_id),
         )?;
-        Ok(())
+        self.maintenance_window(machine_id)?
+            .ok_or_else(|| StoreError::QueryError("maintenance window not recorded".to_string()))
     }
 
-    /// Get a machine baseline
+    /// Take a machine out of maintenance. Its status reads `unknown` until
+    /// the next collection or probe. Returns the closed window, or `None`
+    /// if the machine was not in maintenance.
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if baseline query fails with an error other than no rows.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal database mutex is poisoned.
-    pub fn get_machine_baseline(
+    /// Returns [`StoreError`] if a write fails.
+    pub fn end_maintenance(
         &self,
         machine_id: &str,
-        baseline_window: &str,
-    ) -> Result<Option<MachineBaseline>, StoreError> {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(
-            "SELECT machine_id, baseline_window, CAST(computed_at AS TEXT), metrics_json \
-             FROM machine_baselines WHERE machine_id = ? AND baseline_window = ?",
-            duckdb::params![machine_id, baseline_window],
-            |row| {
-                let metrics_str: String = row.get(3)?;
-                Ok(MachineBaseline {
-                    machine_id: row.get(0)?,
-                    baseline_window: row.get(1)?,
-                    computed_at: row.get(2)?,
-                    metrics_json: serde_json::from_str(&metrics_str).unwrap_or_default(),
-                })
-            },
-        );
+        actor: &ActorContext,
+    ) -> Result<Option<MaintenanceWindow>, StoreError> {
+        let Some(window) = self.maintenance_window(machine_id)? else {
+            return Ok(None);
+        };
+        self.close_maintenance(&window, actor)?;
+        Ok(self
+            .select_maintenance(&format!(
+                "SELECT machine_id, started_at, reason, until_at, started_by, ended_at, ended_by \
+                 FROM machine_maintenance WHERE machine_id = '{}' AND started_at = '{}'",
+                escape_sql_literal(machine_id),
+                escape_sql_literal(&window.started_at)
+            ))?
+            .into_iter()
+            .next())
+    }
 
-        match result {
-            Ok(baseline) => Ok(Some(baseline)),
-            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+    /// End every maintenance window whose `until_at` is at or before `now`,
+    /// returning the machines taken out of maintenance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query or write fails.
+    pub fn expire_maintenance(&self, now: DateTime<Utc>) -> Result<Vec<String>, StoreError> {
+        let mut expired = Vec::new();
+        for window in self.active_maintenance()? {
+            let due = window
+                .until_at
+                .as_deref()
+                .and_then(|until| DateTime::parse_from_rfc3339(until).ok())
+                .is_some_and(|until| until.with_timezone(&Utc) <= now);
+            if due {
+                self.close_maintenance(&window, &ActorContext::system("expiry"))?;
+                expired.push(window.machine_id);
+            }
         }
+        Ok(expired)
     }
 
-    /// List all baselines for a machine
+    fn close_maintenance(
+        &self,
+        window: &MaintenanceWindow,
+        actor: &ActorContext,
+    ) -> Result<(), StoreError> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE machine_maintenance SET ended_at = ?, ended_by = ? \
+                 WHERE machine_id = ? AND started_at = ?",
+                duckdb::params![
+                    Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                    actor.name,
+                    window.machine_id,
+                    window.started_at,
+                ],
+            )?;
+            conn.execute(
+                "UPDATE machines SET status = 'unknown' \
+                 WHERE machine_id = ? AND status = 'maintenance'",
+                [&window.machine_id],
+            )?;
+            annotations::insert_annotation(
+                &conn,
+                &maintenance_annotation(&window.machine_id, "maintenance ended".to_string()),
+                Some(&actor.name),
+            )?;
+        }
+        self.insert_audit_event(
+            &actor
+                .audit_event(
+                    AuditEventType::UserCommand,
+                    "maintenance_off",
+                    AuditResult::Success,
+                    serde_json::json!({
+                        "started_at": window.started_at,
+                        "reason": window.reason,
+                        "until": window.until_at,
+                    }),
+                )
+                .with_machine_id(window.machine_id.clone()),
+        )
+    }
+
+    /// The open maintenance window of a machine, if it is in maintenance.
     ///
     /// # Errors
     ///
     /// Returns [`StoreError`] if query execution fails.
-    pub fn list_machine_baselines(
+    pub fn maintenance_window(
         &self,
-        machine_id: Option<&str>,
-    ) -> Result<Vec<serde_json::Value>, StoreError> {
-        let where_clause = match machine_id {
-            Some(id) => format!("WHERE machine_id = '{}'", escape_sql_literal(id)),
-            None => String::new(),
-        };
-        let sql = format!(
-            "SELECT machine_id, baseline_window, computed_at, metrics_json \
-             FROM machine_baselines {where_clause} \
-             ORDER BY machine_id, baseline_window"
-        );
-        self.query_json(&sql)
+        machine_id: &str,
+    ) -> Result<Option<MaintenanceWindow>, StoreError> {
+        Ok(self
+            .select_maintenance(&format!(
+                "SELECT machine_id, started_at, reason, until_at, started_by, ended_at, ended_by \
+                 FROM machine_maintenance WHERE machine_id = '{}' AND ended_at IS NULL \
+                 ORDER BY started_at DESC LIMIT 1",
+                escape_sql_literal(machine_id)
+            ))?
+            .into_iter()
+            .next())
     }
 
-    // =========================================================================
-    // Drift Detection Methods
-    // =========================================================================
-
-    /// Record a drift event
+    /// Every open maintenance window, oldest first.
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if insert or ID allocation fails.
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal database mutex is poisoned.
-    pub fn insert_drift_event(&self, event: &DriftEvent) -> Result<(), StoreError> {
-        let conn = self.conn.lock().unwrap();
-
-        let next_id: i64 = conn.query_row(
-            "SELECT COALESCE(MAX(id), 0) + 1 FROM drift_events",
-            [],
-            |row| row.get(0),
-        )?;
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn active_maintenance(&self) -> Result<Vec<MaintenanceWindow>, StoreError> {
+        self.select_maintenance(
+            "SELECT machine_id, started_at, reason, until_at, started_by, ended_at, ended_by \
+             FROM machine_maintenance WHERE ended_at IS NULL ORDER BY started_at",
+        )
+    }
 
-        let evidence_str = event
-            .evidence_json
-            .as_ref()
-            .map(|v| serde_json::to_string(v).unwrap_or_default());
+    fn select_maintenance(&self, sql: &str) -> Result<Vec<MaintenanceWindow>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok(MaintenanceWindow {
+                machine_id: row.get(0)?,
+                started_at: row.get(1)?,
+                reason: row.get(2)?,
+                until_at: row.get(3)?,
+                started_by: row.get(4)?,
+                ended_at: row.get(5)?,
+                ended_by: row.get(6)?,
+            })
+        })?;
 
-        conn.execute(
-            "INSERT INTO drift_events \
-             (id, machine_id, detected_at, metric, current_value, baseline_mean, \
-              baseline_std, z_score, severity, evidence_json) \
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            duckdb::params![
-                next_id,
-                event.machine_id,
-                event.detected_at,
-                event.metric,
-                event.current_value,
-                event.baseline_mean,
-                event.baseline_std,
-                event.z_score,
-                event.severity.as_str(),
-                evidence_str,
-            ],
-        )?;
-        Ok(())
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
     }
 
-    /// List recent drift events
+    /// List recent drift events, optionally only those for one metric/field.
+    ///
+    /// Each row carries a derived `description`; structured value columns
+    /// are null on rows recorded before they existed.
     ///
     /// # Errors
     ///
@@ -1548,6 +5252,7 @@ impl VcStore {
         &self,
         machine_id: Option<&str>,
         severity: Option<&str>,
+        field: Option<&str>,
         limit: usize,
     ) -> Result<Vec<serde_json::Value>, StoreError> {
         let mut clauses: Vec<String> = Vec::new();
@@ -1558,6 +5263,9 @@ impl VcStore {
         if let Some(s) = severity {
             clauses.push(format!("severity = '{}'", escape_sql_literal(s)));
         }
+        if let Some(f) = field {
+            clauses.push(format!("metric = '{}'", escape_sql_literal(f)));
+        }
 
         let where_sql = if clauses.is_empty() {
             String::new()
@@ -1568,12 +5276,18 @@ impl VcStore {
         let limit = limit.min(1000);
         let sql = format!(
             "SELECT id, machine_id, detected_at, metric, current_value, baseline_mean, \
-             baseline_std, z_score, severity, evidence_json \
+             baseline_std, z_score, severity, evidence_json, previous_value, new_value, \
+             delta, baseline_ref, detection_method \
              FROM drift_events {where_sql} \
              ORDER BY detected_at DESC LIMIT {limit}"
         );
 
-        self.query_json(&sql)
+        let mut rows = self.query_json(&sql)?;
+        for row in &mut rows {
+            let description = drift_row_description(row);
+            row["description"] = serde_json::Value::String(description);
+        }
+        Ok(rows)
     }
 
     /// Detect drift by comparing a current value against a machine baseline.
@@ -1630,6 +5344,11 @@ impl VcStore {
                     "computed_at": baseline.computed_at,
                     "threshold": z_threshold,
                 })),
+                previous_value: Some(format_drift_number(mean)),
+                new_value: Some(format_drift_number(current_value)),
+                delta: Some(current_value - mean),
+                baseline_ref: Some(format!("{baseline_window}@{}", baseline.computed_at)),
+                detection_method: Some("z_score".to_string()),
             };
 
             // Persist the drift event
@@ -1678,6 +5397,55 @@ impl VcStore {
         Ok(())
     }
 
+    /// Log a digest delivery covering `alert_ids` in one message
+    ///
+    /// The row's `alert_id` is `digest:<id>,<id>,...` and `alert_count` holds
+    /// how many alerts the digest covered.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if ID allocation or insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn insert_digest_delivery_log(
+        &self,
+        channel_type: &str,
+        alert_ids: &[i64],
+        status: &str,
+        error_message: Option<&str>,
+        duration_ms: Option<i64>,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+
+        let next_id: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(id), 0) + 1 FROM alert_delivery_log",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let ids: Vec<String> = alert_ids.iter().map(ToString::to_string).collect();
+        let alert_id = format!("digest:{}", ids.join(","));
+        let alert_count = i64::try_from(alert_ids.len()).unwrap_or(i64::MAX);
+
+        conn.execute(
+            "INSERT INTO alert_delivery_log \
+             (id, alert_id, channel_type, status, error_message, duration_ms, alert_count) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                next_id,
+                alert_id,
+                channel_type,
+                status,
+                error_message,
+                duration_ms,
+                alert_count
+            ],
+        )?;
+        Ok(())
+    }
+
     /// Update delivery status (e.g., after retry)
     ///
     /// # Errors
@@ -1723,7 +5491,8 @@ impl VcStore {
             (
                 format!(
                     "SELECT id, alert_id, channel_type, CAST(delivered_at AS TEXT) AS delivered_at, \
-                     status, error_message, retry_count, duration_ms \
+                     status, error_message, retry_count, duration_ms, \
+                     COALESCE(alert_count, 1) AS alert_count \
                      FROM alert_delivery_log WHERE alert_id = ? \
                      ORDER BY delivered_at DESC LIMIT {limit}"
                 ),
@@ -1733,7 +5502,8 @@ impl VcStore {
             (
                 format!(
                     "SELECT id, alert_id, channel_type, CAST(delivered_at AS TEXT) AS delivered_at, \
-                     status, error_message, retry_count, duration_ms \
+                     status, error_message, retry_count, duration_ms, \
+                     COALESCE(alert_count, 1) AS alert_count \
                      FROM alert_delivery_log \
                      ORDER BY delivered_at DESC LIMIT {limit}"
                 ),
@@ -1753,6 +5523,7 @@ impl VcStore {
                 "error_message": row.get::<_, Option<String>>(5)?,
                 "retry_count": row.get::<_, i32>(6)?,
                 "duration_ms": row.get::<_, Option<i64>>(7)?,
+                "alert_count": row.get::<_, i64>(8)?,
             }))
         })?;
 
@@ -1763,6 +5534,44 @@ impl VcStore {
         Ok(results)
     }
 
+    /// When an alert was last delivered, and how many deliveries failed since
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn delivery_health(&self) -> Result<DeliveryHealth, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let (last_success_at, last_attempt_at) = conn.query_row(
+            "SELECT CAST(MAX(delivered_at) FILTER (WHERE status = 'success') AS TEXT), \
+                    CAST(MAX(delivered_at) AS TEXT) \
+             FROM alert_delivery_log",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                ))
+            },
+        )?;
+        let failures_since_success = conn.query_row(
+            "SELECT COUNT(*) FROM alert_delivery_log \
+             WHERE status = 'failed' AND id > ( \
+                 SELECT COALESCE(MAX(id), 0) FROM alert_delivery_log \
+                 WHERE status = 'success')",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(DeliveryHealth {
+            last_success_at,
+            last_attempt_at,
+            failures_since_success,
+        })
+    }
+
     /// Get delivery summary stats (total, succeeded, failed per channel)
     ///
     /// # Errors
@@ -1963,39 +5772,24 @@ impl VcStore {
 
         for row in rows {
             if let serde_json::Value::Object(map) = row {
-                let columns: Vec<&str> = map.keys().map(String::as_str).collect();
-                let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
-
-                let sql = format!(
-                    "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+                match write_limits::write_row(
+                    &conn,
+                    "INSERT OR REPLACE INTO",
                     table,
-                    columns.join(", "),
-                    placeholders.join(", ")
-                );
-
-                let mut stmt = match conn.prepare(&sql) {
-                    Ok(s) => s,
+                    map,
+                    WriteOrigin::default(),
+                ) {
+                    Ok(written) => count += usize::from(written),
                     Err(e) => {
                         let _ = conn.execute("ROLLBACK", []);
-                        return Err(e.into());
+                        return Err(e);
                     }
-                };
-
-                let params: Vec<Box<dyn duckdb::ToSql>> =
-                    map.values().map(json_value_to_sql).collect();
-
-                let param_refs: Vec<&dyn duckdb::ToSql> =
-                    params.iter().map(AsRef::as_ref).collect();
-
-                if let Err(e) = stmt.execute(param_refs.as_slice()) {
-                    let _ = conn.execute("ROLLBACK", []);
-                    return Err(e.into());
                 }
-                count += 1;
             }
         }
 
         conn.execute("COMMIT", [])?;
+        conn.changes.note(table, u64::try_from(count).unwrap_or(0));
         Ok(count)
     }
 
@@ -2110,7 +5904,8 @@ impl VcStore {
         Ok(results)
     }
 
-    /// Update incident status
+    /// Update incident status. A change of status is recorded on the
+    /// timeline as a `status_changed` event, which the SLA timers read.
     ///
     /// # Errors
     ///
@@ -2127,6 +5922,15 @@ impl VcStore {
         root_cause: Option<&str>,
     ) -> Result<usize, StoreError> {
         let conn = self.conn.lock().unwrap();
+        let previous: Option<String> = match conn.query_row(
+            "SELECT status FROM incidents WHERE incident_id = ?",
+            [incident_id],
+            |row| row.get(0),
+        ) {
+            Ok(previous) => previous,
+            Err(duckdb::Error::QueryReturnedNoRows) => return Ok(0),
+            Err(e) => return Err(StoreError::DatabaseError(e)),
+        };
 
         let mut set_clauses = vec![
             "status = ?".to_string(),
@@ -2158,9 +5962,67 @@ impl VcStore {
 
         let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
         let affected = conn.execute(&sql, param_refs.as_slice())?;
+
+        if previous.as_deref() != Some(status) {
+            let previous = previous.unwrap_or_else(|| "open".to_string());
+            conn.execute(
+                "INSERT INTO incident_timeline_events (incident_id, ts, event_type, source, description, details_json) \
+                 VALUES (?, current_timestamp, 'status_changed', 'vc', ?, ?)",
+                duckdb::params![
+                    incident_id,
+                    format!("Status changed from {previous} to {status}"),
+                    serde_json::json!({ "from": previous, "to": status }).to_string(),
+                ],
+            )?;
+        }
         Ok(affected)
     }
 
+    /// Change an incident's severity, recording the change on the timeline
+    /// as a `severity_changed` event. Returns the previous severity, or
+    /// `None` if there is no such incident.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the lookup, update or insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_incident_severity(
+        &self,
+        incident_id: &str,
+        severity: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let previous: String = match conn.query_row(
+            "SELECT severity FROM incidents WHERE incident_id = ?",
+            [incident_id],
+            |row| row.get(0),
+        ) {
+            Ok(previous) => previous,
+            Err(duckdb::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(StoreError::DatabaseError(e)),
+        };
+        if previous != severity {
+            conn.execute(
+                "UPDATE incidents SET severity = ?, updated_at = current_timestamp \
+                 WHERE incident_id = ?",
+                duckdb::params![severity, incident_id],
+            )?;
+            conn.execute(
+                "INSERT INTO incident_timeline_events (incident_id, ts, event_type, source, description, details_json) \
+                 VALUES (?, current_timestamp, 'severity_changed', 'vc', ?, ?)",
+                duckdb::params![
+                    incident_id,
+                    format!("Severity changed from {previous} to {severity}"),
+                    serde_json::json!({ "from": previous, "to": severity }).to_string(),
+                ],
+            )?;
+        }
+        Ok(Some(previous))
+    }
+
     /// Add a note to an incident
     ///
     /// # Errors
@@ -2371,14 +6233,22 @@ impl VcStore {
         machine_id: &str,
         solutions: i32,
         patterns: i32,
+        suppressed_duplicates: i32,
         quality_avg: Option<f64>,
     ) -> Result<(), StoreError> {
-        let sql = "INSERT INTO mined_sessions (session_id, machine_id, solutions_extracted, patterns_extracted, quality_avg) \
-                   VALUES (?, ?, ?, ?, ?)";
+        let sql = "INSERT INTO mined_sessions (session_id, machine_id, solutions_extracted, patterns_extracted, suppressed_duplicates, quality_avg) \
+                   VALUES (?, ?, ?, ?, ?, ?)";
         let conn = self.conn.lock().unwrap();
         conn.execute(
             sql,
-            duckdb::params![session_id, machine_id, solutions, patterns, quality_avg],
+            duckdb::params![
+                session_id,
+                machine_id,
+                solutions,
+                patterns,
+                suppressed_duplicates,
+                quality_avg
+            ],
         )?;
         Ok(())
     }
@@ -2420,6 +6290,91 @@ impl VcStore {
         self.query_json(&sql)
     }
 
+    /// A recorded agent session with its transcript (`raw_json`), newest
+    /// collection first when several machines report the same id. An
+    /// offloaded transcript is fetched back from the artifact store.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails, or the transcript is
+    /// offloaded and cannot be fetched.
+    pub fn get_agent_session(
+        &self,
+        session_id: &str,
+    ) -> Result<Option<serde_json::Value>, StoreError> {
+        let sql = format!(
+            "SELECT machine_id, session_id, program, CAST(started_at AS TEXT) AS started_at, \
+             CAST(ended_at AS TEXT) AS ended_at, raw_json \
+             FROM agent_sessions WHERE session_id = '{}' \
+             ORDER BY collected_at DESC LIMIT 1",
+            escape_sql_literal(session_id)
+        );
+        let mut rows = self.query_json(&sql)?;
+        self.fill_offloaded_transcripts(&mut rows)?;
+        Ok(rows.into_iter().next())
+    }
+
+    /// Finished agent sessions due for outcome classification, oldest first,
+    /// with their transcripts (fetched back from the artifact store when
+    /// offloaded). Only unclassified ones unless `reclassify`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails, or an offloaded
+    /// transcript cannot be fetched.
+    pub fn sessions_to_classify(
+        &self,
+        reclassify: bool,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let filter = if reclassify {
+            ""
+        } else {
+            " AND outcome IS NULL"
+        };
+        let sql = format!(
+            "SELECT machine_id, session_id, program, CAST(ended_at AS TEXT) AS ended_at, \
+             turn_count, raw_json \
+             FROM agent_sessions WHERE ended_at IS NOT NULL{filter} \
+             ORDER BY ended_at LIMIT {limit}"
+        );
+        let mut rows = self.query_json(&sql)?;
+        self.fill_offloaded_transcripts(&mut rows)?;
+        Ok(rows)
+    }
+
+    /// Record the classified outcome of an agent session.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_session_outcome(
+        &self,
+        machine_id: &str,
+        session_id: &str,
+        outcome: &str,
+        confidence: f64,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE agent_sessions \
+             SET outcome = ?, outcome_confidence = ?, outcome_classified_at = ? \
+             WHERE machine_id = ? AND session_id = ?",
+            duckdb::params![
+                outcome,
+                confidence,
+                Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+                machine_id,
+                session_id,
+            ],
+        )?;
+        Ok(())
+    }
+
     /// Get mining statistics
     ///
     /// # Errors
@@ -2430,13 +6385,16 @@ impl VcStore {
                    (SELECT COUNT(*) as total_mined, \
                     COALESCE(SUM(solutions_extracted), 0) as total_solutions, \
                     COALESCE(SUM(patterns_extracted), 0) as total_patterns, \
+                    COALESCE(SUM(suppressed_duplicates), 0) as suppressed_duplicates, \
                     COALESCE(AVG(quality_avg), 0) as avg_quality \
                     FROM mined_sessions) AS _row";
         let results = self.query_json(sql)?;
         Ok(results.into_iter().next().unwrap_or(serde_json::json!({})))
     }
 
-    /// Get incident timeline events
+    /// Get incident timeline events, with the annotations made while the
+    /// incident ran attached as `annotation` events (no `id`; the
+    /// annotation's is in `details_json`)
     ///
     /// # Errors
     ///
@@ -2449,11 +6407,21 @@ impl VcStore {
         &self,
         incident_id: &str,
     ) -> Result<Vec<serde_json::Value>, StoreError> {
-        let sql = "SELECT to_json(_row) FROM \
-                   (SELECT * FROM incident_timeline_events WHERE incident_id = ? ORDER BY ts ASC) AS _row";
+        let sql = "SELECT to_json(_row) FROM (\
+                   SELECT * FROM (\
+                   SELECT id, incident_id, ts, event_type, source, description, details_json \
+                   FROM incident_timeline_events WHERE incident_id = ? \
+                   UNION ALL \
+                   SELECT NULL, i.incident_id, a.ts, 'annotation', a.source, \
+                   a.kind || ' on ' || a.scope || ': ' || a.message, \
+                   CAST(json_object('annotation_id', a.id, 'kind', a.kind, 'scope', a.scope) AS TEXT) \
+                   FROM annotations a JOIN incidents i ON i.incident_id = ? \
+                   WHERE replace(a.ts, 'T', ' ') >= replace(i.started_at, 'T', ' ') \
+                   AND (i.ended_at IS NULL OR replace(a.ts, 'T', ' ') <= replace(i.ended_at, 'T', ' '))\
+                   ) ORDER BY replace(ts, 'T', ' ') ASC, id ASC NULLS FIRST) AS _row";
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map([incident_id], |row| {
+        let rows = stmt.query_map([incident_id, incident_id], |row| {
             let json_str: String = row.get(0)?;
             Ok(json_str)
         })?;
@@ -2705,7 +6673,9 @@ impl VcStore {
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if ID allocation or insert fails.
+    /// Returns [`StoreError::LeaseNotHeld`] if this store writes under a
+    /// lease it no longer holds, or [`StoreError`] if ID allocation or insert
+    /// fails.
     ///
     /// # Panics
     ///
@@ -2718,6 +6688,7 @@ impl VcStore {
         reason_json: Option<&str>,
     ) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
+        self.check_lease(&conn)?;
         let next_id: i64 = conn
             .query_row(
                 "SELECT COALESCE(MAX(id), 0) + 1 FROM poll_schedule_decisions",
@@ -2789,7 +6760,9 @@ impl VcStore {
     ///
     /// # Errors
     ///
-    /// Returns [`StoreError`] if ID allocation or insert fails.
+    /// Returns [`StoreError::LeaseNotHeld`] if this store writes under a
+    /// lease it no longer holds, or [`StoreError`] if ID allocation or insert
+    /// fails.
     ///
     /// # Panics
     ///
@@ -2802,6 +6775,7 @@ impl VcStore {
         raw_json: Option<&str>,
     ) -> Result<(), StoreError> {
         let conn = self.conn.lock().unwrap();
+        self.check_lease(&conn)?;
         let next_id: i64 = conn
             .query_row(
                 "SELECT COALESCE(MAX(id), 0) + 1 FROM sys_profile_samples",
@@ -3005,14 +6979,75 @@ impl VcStore {
     /// Panics if the internal database mutex is poisoned.
     pub fn has_ingest_record(&self, content_hash: &str) -> Result<bool, StoreError> {
         let conn = self.conn.lock().unwrap();
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM node_ingest_log WHERE content_hash = ?",
-                [content_hash],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
-        Ok(count > 0)
+        Ok(ingest_applied(&conn, content_hash))
+    }
+
+    /// Apply one bundle batch in a single transaction: its rows, their dedup
+    /// hashes and the `applied` ingest record commit together or not at all.
+    ///
+    /// Rows that are not objects or name columns the table lacks are skipped
+    /// (fail-soft, as for malformed lines); any other insert error fails the
+    /// whole batch. Returns `None` without writing anything when the batch's
+    /// content is already applied, e.g. by a concurrent ingest of the same
+    /// bundle.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a write fails; the batch is rolled back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn apply_ingest_batch(
+        &self,
+        batch: &IngestBatch<'_>,
+    ) -> Result<Option<AppliedIngestBatch>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        if ingest_applied(&conn, batch.content_hash) {
+            return Ok(None);
+        }
+        let columns: std::collections::HashSet<String> = {
+            let mut stmt = conn.prepare(
+                "SELECT column_name FROM information_schema.columns WHERE table_name = ?",
+            )?;
+            stmt.query_map([batch.table], |row| row.get::<_, String>(0))?
+                .collect::<Result<_, _>>()?
+        };
+
+        conn.execute("BEGIN TRANSACTION", [])?;
+        match write_ingest_batch(&conn, batch, &columns) {
+            Ok(applied) => {
+                conn.execute("COMMIT", [])?;
+                conn.changes.note(
+                    batch.table,
+                    u64::try_from(applied.rows_inserted).unwrap_or(0),
+                );
+                Ok(Some(applied))
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// Record that a batch failed, so history shows the bundle as partial
+    /// and a rerun retries it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_ingest_failure(
+        &self,
+        batch: &IngestBatch<'_>,
+        error: &str,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        insert_ingest_log(&conn, batch, "failed", Some(error))
     }
 
     /// Record a successful ingest for future dedup
@@ -3055,6 +7090,101 @@ impl VcStore {
         Ok(())
     }
 
+    /// Which of `hashes` were recorded for `table_name` at or after `since`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn seen_ingest_row_hashes(
+        &self,
+        table_name: &str,
+        hashes: &[String],
+        since: DateTime<Utc>,
+    ) -> Result<std::collections::HashSet<String>, StoreError> {
+        let mut seen = std::collections::HashSet::new();
+        if hashes.is_empty() {
+            return Ok(seen);
+        }
+        let since = since.format("%Y-%m-%d %H:%M:%S").to_string();
+        let conn = self.conn.lock().unwrap();
+        for chunk in hashes.chunks(INGEST_HASH_CHUNK) {
+            let in_list = chunk
+                .iter()
+                .map(|hash| format!("'{}'", escape_sql_literal(hash)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT content_hash FROM ingest_row_hashes \
+                 WHERE table_name = ? AND seen_at >= ? AND content_hash IN ({in_list})"
+            ))?;
+            let rows =
+                stmt.query_map([table_name, since.as_str()], |row| row.get::<_, String>(0))?;
+            for row in rows {
+                seen.insert(row?);
+            }
+        }
+        Ok(seen)
+    }
+
+    /// Record row hashes of an ingest as seen now, refreshing hashes that
+    /// were already present
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_ingest_row_hashes(
+        &self,
+        table_name: &str,
+        machine_id: &str,
+        hashes: &[String],
+    ) -> Result<(), StoreError> {
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let conn = self.conn.lock().unwrap();
+        for hash in hashes {
+            conn.execute(
+                "INSERT OR REPLACE INTO ingest_row_hashes \
+                 (table_name, content_hash, machine_id, seen_at) VALUES (?, ?, ?, ?)",
+                [table_name, hash.as_str(), machine_id, now.as_str()],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Delete (or with `dry_run`, count) row hashes last seen before `before`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the delete or count fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn prune_ingest_row_hashes(
+        &self,
+        before: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<usize, StoreError> {
+        let before = before.format("%Y-%m-%d %H:%M:%S").to_string();
+        let conn = self.conn.lock().unwrap();
+        if dry_run {
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM ingest_row_hashes WHERE seen_at < ?",
+                [&before],
+                |row| row.get(0),
+            )?;
+            return Ok(usize::try_from(count).unwrap_or(0));
+        }
+        Ok(conn.execute("DELETE FROM ingest_row_hashes WHERE seen_at < ?", [&before])?)
+    }
+
     /// List recent ingest records
     ///
     /// # Errors
@@ -3081,6 +7211,91 @@ impl VcStore {
         self.query_json(&sql)
     }
 
+    /// Batches whose latest attempt failed: the ingest work a rerun of their
+    /// bundles still has to do
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    pub fn ingest_backlog(&self) -> Result<i64, StoreError> {
+        self.query_scalar(
+            "SELECT COUNT(*) FROM ( \
+                 SELECT COALESCE(status, 'applied') AS status, \
+                        ROW_NUMBER() OVER ( \
+                            PARTITION BY bundle_id, COALESCE(batch_id, CAST(id AS TEXT)) \
+                            ORDER BY id DESC) AS attempt \
+                 FROM node_ingest_log) \
+             WHERE attempt = 1 AND status = 'failed'",
+        )
+    }
+
+    /// Recent bundles, one row each: batches applied and failed, judged by
+    /// each batch's latest attempt, and `complete` or `partial`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_ingest_bundles(
+        &self,
+        machine_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        let filter = machine_id.map_or_else(String::new, |mid| {
+            format!("WHERE machine_id = '{}'", escape_sql_literal(mid))
+        });
+        self.query_json(&format!(
+            "WITH attempts AS ( \
+                 SELECT bundle_id, machine_id, COALESCE(status, 'applied') AS status, \
+                        error, ingested_at, \
+                        ROW_NUMBER() OVER ( \
+                            PARTITION BY bundle_id, COALESCE(batch_id, CAST(id AS TEXT)) \
+                            ORDER BY id DESC) AS attempt \
+                 FROM node_ingest_log {filter}) \
+             SELECT bundle_id, machine_id, \
+                    CASE WHEN SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END) > 0 \
+                         THEN 'partial' ELSE 'complete' END AS status, \
+                    CAST(SUM(CASE WHEN status = 'applied' THEN 1 ELSE 0 END) AS BIGINT) \
+                        AS batches_applied, \
+                    CAST(SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END) AS BIGINT) \
+                        AS batches_failed, \
+                    MAX(error) AS error, \
+                    MAX(ingested_at) AS last_ingested_at \
+             FROM attempts WHERE attempt = 1 \
+             GROUP BY bundle_id, machine_id \
+             ORDER BY last_ingested_at DESC LIMIT {limit}"
+        ))
+    }
+
+    /// Record a request signature unless it was already seen, pruning those
+    /// signed before `expire_before` (unix seconds) first. Returns `false`
+    /// for a signature already on record: a replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the prune or insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_request_signature(
+        &self,
+        signature: &str,
+        signed_at: i64,
+        expire_before: i64,
+    ) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM request_signatures_seen WHERE signed_at < ?",
+            duckdb::params![expire_before],
+        )?;
+        let inserted = conn.execute(
+            "INSERT INTO request_signatures_seen (signature, signed_at) VALUES (?, ?) \
+             ON CONFLICT (signature) DO NOTHING",
+            duckdb::params![signature, signed_at],
+        )?;
+        Ok(inserted == 1)
+    }
+
     // =========================================================================
     // Data export/backup methods
     // =========================================================================
@@ -3110,6 +7325,35 @@ impl VcStore {
         Ok(tables)
     }
 
+    /// Snapshot of the tables this store has, for composite reads that
+    /// should degrade rather than fail on an older schema
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the catalog query fails.
+    pub fn capabilities(&self) -> Result<Capabilities, StoreError> {
+        Ok(Capabilities::from_tables(self.list_tables()?))
+    }
+
+    /// Whether `table` exists in the main schema
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the catalog query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn has_table(&self, table: &str) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM duckdb_tables() WHERE schema_name = 'main' AND table_name = ?",
+            [table],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
     /// Export a single table as JSONL (one JSON object per line)
     ///
     /// # Errors
@@ -3364,6 +7608,25 @@ impl VcStore {
         Ok(next_id)
     }
 
+    /// Attach provenance (a JSON document saying where the actions were
+    /// extracted from) to a captured resolution.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_resolution_provenance(&self, id: i64, provenance: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE resolutions SET provenance = ? WHERE id = ?",
+            duckdb::params![provenance, id],
+        )?;
+        Ok(())
+    }
+
     /// List captured resolutions with optional filtering.
     ///
     /// # Errors
@@ -3492,11 +7755,19 @@ impl VcStore {
                 source_pattern_json,
             ],
         )?;
+        conn.execute(
+            "INSERT INTO playbook_draft_revisions (draft_id, revision, steps_json, editor) \
+             VALUES (?, 1, ?, 'generator')",
+            [draft_id, steps_json],
+        )?;
         Ok(())
     }
 
     /// List playbook drafts with optional status filtering.
     ///
+    /// Each row carries a `revision_count` so callers can flag drafts that
+    /// have been edited since generation.
+    ///
     /// # Errors
     ///
     /// Returns [`StoreError`] if query execution fails.
@@ -3506,18 +7777,100 @@ impl VcStore {
         limit: usize,
     ) -> Result<Vec<serde_json::Value>, StoreError> {
         let limit = limit.min(1000);
-        let sql = match status {
-            Some(s) => format!(
-                "SELECT * FROM playbook_drafts WHERE status = '{}' \
-                 ORDER BY created_at DESC LIMIT {}",
-                escape_sql_literal(s),
-                limit
-            ),
-            None => format!("SELECT * FROM playbook_drafts ORDER BY created_at DESC LIMIT {limit}"),
+        let where_clause = match status {
+            Some(s) => format!("WHERE d.status = '{}'", escape_sql_literal(s)),
+            None => String::new(),
         };
+        let sql = format!(
+            "SELECT d.*, \
+             (SELECT COUNT(*) FROM playbook_draft_revisions r WHERE r.draft_id = d.draft_id) \
+             AS revision_count \
+             FROM playbook_drafts d {where_clause} ORDER BY d.created_at DESC LIMIT {limit}"
+        );
         self.query_json(&sql)
     }
 
+    /// Append an edited revision to a pending playbook draft.
+    ///
+    /// The draft's `steps_json` and `current_revision` move to the new
+    /// revision; earlier revisions (including the generated original) are
+    /// kept. Any approval must then reference the new revision number.
+    ///
+    /// Returns the new revision number, or `None` if the draft does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if the draft is not pending review,
+    /// or [`StoreError`] if the insert or update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn insert_playbook_draft_revision(
+        &self,
+        draft_id: &str,
+        steps_json: &str,
+        editor: &str,
+        diff_summary: &str,
+        validation_json: Option<&str>,
+    ) -> Result<Option<i64>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let status = conn.query_row(
+            "SELECT status FROM playbook_drafts WHERE draft_id = ?",
+            [draft_id],
+            |row| row.get::<_, String>(0),
+        );
+        let status = match status {
+            Ok(status) => status,
+            Err(duckdb::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(StoreError::DatabaseError(e)),
+        };
+        if status != "pending_review" {
+            return Err(StoreError::QueryError(format!(
+                "Only pending_review drafts can be edited (draft is {status})"
+            )));
+        }
+
+        let next_revision: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(revision), 0) + 1 FROM playbook_draft_revisions WHERE draft_id = ?",
+            [draft_id],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "INSERT INTO playbook_draft_revisions \
+             (draft_id, revision, steps_json, editor, diff_summary, validation_json) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                draft_id,
+                next_revision,
+                steps_json,
+                editor,
+                diff_summary,
+                validation_json,
+            ],
+        )?;
+        conn.execute(
+            "UPDATE playbook_drafts SET steps_json = ?, current_revision = ? WHERE draft_id = ?",
+            duckdb::params![steps_json, next_revision, draft_id],
+        )?;
+        Ok(Some(next_revision))
+    }
+
+    /// List all revisions of a playbook draft, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    pub fn list_playbook_draft_revisions(
+        &self,
+        draft_id: &str,
+    ) -> Result<Vec<serde_json::Value>, StoreError> {
+        self.query_json(&format!(
+            "SELECT * FROM playbook_draft_revisions WHERE draft_id = '{}' ORDER BY revision",
+            escape_sql_literal(draft_id)
+        ))
+    }
+
     /// Fetch a single playbook draft by draft ID.
     ///
     /// # Errors
@@ -3549,7 +7902,11 @@ impl VcStore {
         }
     }
 
-    /// Mark a pending playbook draft as approved.
+    /// Mark a pending playbook draft as approved at a specific revision.
+    ///
+    /// Nothing is updated unless `revision` is the draft's current revision,
+    /// so an edit made after the approver looked at the draft cannot be
+    /// approved by accident.
     ///
     /// # Errors
     ///
@@ -3561,13 +7918,15 @@ impl VcStore {
     pub fn approve_playbook_draft(
         &self,
         draft_id: &str,
-        approver: &str,
+        approver: &ActorContext,
+        revision: i64,
     ) -> Result<usize, StoreError> {
         let conn = self.conn.lock().unwrap();
         let affected = conn.execute(
             "UPDATE playbook_drafts SET status = 'approved', approved_by = ?, \
-             approved_at = current_timestamp WHERE draft_id = ? AND status = 'pending_review'",
-            [approver, draft_id],
+             approved_at = current_timestamp, approved_revision = ? \
+             WHERE draft_id = ? AND status = 'pending_review' AND current_revision = ?",
+            duckdb::params![approver.name, revision, draft_id, revision],
         )?;
         Ok(affected)
     }
@@ -3604,6 +7963,61 @@ impl VcStore {
         Ok(affected)
     }
 
+    /// Fetch a single guardian run by run ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution or JSON decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn get_guardian_run(&self, run_id: i64) -> Result<Option<serde_json::Value>, StoreError> {
+        let sql = "SELECT to_json(_row) FROM \
+                   (SELECT * FROM guardian_runs WHERE id = ?) AS _row";
+        let conn = self.conn.lock().unwrap();
+        let result = conn.query_row(sql, [run_id], |row| {
+            let json_str: String = row.get(0)?;
+            Ok(json_str)
+        });
+
+        match result {
+            Ok(json_str) => {
+                let val: serde_json::Value = serde_json::from_str(&json_str)?;
+                Ok(Some(val))
+            }
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(StoreError::DatabaseError(e)),
+        }
+    }
+
+    /// Approve a guardian run that is waiting in `pending_approval`.
+    ///
+    /// Nothing is updated unless the run is still pending, so a run that was
+    /// already approved, executed or aborted cannot be approved again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if update execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn approve_guardian_run(
+        &self,
+        run_id: i64,
+        approver: &ActorContext,
+    ) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE guardian_runs SET status = 'approved', approved_by = ?, \
+             approved_at = current_timestamp \
+             WHERE id = ? AND status = 'pending_approval'",
+            duckdb::params![approver.name, run_id],
+        )?;
+        Ok(affected)
+    }
+
     /// Activate an approved playbook draft into a live guardian playbook.
     ///
     /// # Errors
@@ -3634,15 +8048,26 @@ impl VcStore {
         let name = draft["name"].as_str().unwrap_or("");
         let description = draft["description"].as_str().unwrap_or("");
         let trigger_json = draft["trigger_json"].as_str().unwrap_or("{}");
-        let steps_json = draft["steps_json"].as_str().unwrap_or("[]");
+        let approved_revision = draft["approved_revision"].as_i64().unwrap_or(1);
 
         let conn = self.conn.lock().unwrap();
+        let steps_json: String = match conn.query_row(
+            "SELECT steps_json FROM playbook_draft_revisions WHERE draft_id = ? AND revision = ?",
+            duckdb::params![draft_id, approved_revision],
+            |row| row.get(0),
+        ) {
+            Ok(steps) => steps,
+            Err(duckdb::Error::QueryReturnedNoRows) => {
+                draft["steps_json"].as_str().unwrap_or("[]").to_string()
+            }
+            Err(e) => return Err(StoreError::DatabaseError(e)),
+        };
         conn.execute(
             "INSERT INTO guardian_playbooks \
              (playbook_id, name, description, trigger_condition, steps, \
               enabled, requires_approval, max_runs_per_hour) \
              VALUES (?, ?, ?, ?, ?, TRUE, TRUE, 3)",
-            duckdb::params![playbook_id, name, description, trigger_json, steps_json],
+            duckdb::params![playbook_id, name, description, trigger_json, &steps_json],
         )?;
 
         // Mark draft as activated
@@ -3654,12 +8079,40 @@ impl VcStore {
         Ok(Some(serde_json::json!({
             "playbook_id": playbook_id,
             "name": name,
+            "revision": approved_revision,
             "status": "activated",
         })))
     }
 }
 
+/// Hashes looked up per `IN (...)` list when checking ingest dedup
+#[cfg(feature = "duckdb")]
+const INGEST_HASH_CHUNK: usize = 500;
+
+#[cfg(feature = "duckdb")]
+const RETENTION_POLICY_COLUMNS: &str = "policy_id, table_name, retention_days, aggregate_table, \
+     enabled, last_vacuum_at, max_total_bytes, max_row_bytes, keep_head_bytes, keep_tail_bytes, scope";
+
+/// Map a row selected with [`RETENTION_POLICY_COLUMNS`]
+#[cfg(feature = "duckdb")]
+fn retention_policy_from_row(row: &duckdb::Row<'_>) -> duckdb::Result<RetentionPolicy> {
+    Ok(RetentionPolicy {
+        policy_id: row.get(0)?,
+        table_name: row.get(1)?,
+        retention_days: row.get(2)?,
+        aggregate_table: row.get(3)?,
+        enabled: row.get(4)?,
+        last_vacuum_at: row.get(5)?,
+        max_total_bytes: row.get(6)?,
+        max_row_bytes: row.get(7)?,
+        keep_head_bytes: row.get::<_, Option<i64>>(8)?.unwrap_or(DEFAULT_KEEP_BYTES),
+        keep_tail_bytes: row.get::<_, Option<i64>>(9)?.unwrap_or(DEFAULT_KEEP_BYTES),
+        scope: row.get(10)?,
+    })
+}
+
 /// Convert JSON value to a SQL parameter
+#[cfg(feature = "duckdb")]
 fn json_value_to_sql(value: &serde_json::Value) -> Box<dyn duckdb::ToSql> {
     match value {
         serde_json::Value::Null => Box::new(None::<String>),
@@ -3692,12 +8145,13 @@ pub fn escape_sql_identifier(value: &str) -> String {
     value.replace('"', "\"\"")
 }
 
+#[cfg(feature = "duckdb")]
 fn clamp_audit_limit(limit: usize) -> usize {
     let limit = if limit == 0 { 100 } else { limit };
     limit.min(10_000)
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "duckdb"))]
 mod tests {
     use super::*;
     use chrono::Duration as ChronoDuration;
@@ -3712,6 +8166,84 @@ mod tests {
         assert_eq!(store.db_path(), ":memory:");
     }
 
+    #[test]
+    fn test_flushed_buffers_survive_exit_without_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        let store = VcStore::open(&path)
+            .unwrap()
+            .with_query_log(&vc_config::QueryLogConfig::default(), QueryCaller::Cli);
+        store
+            .execute(
+                "INSERT INTO machines (machine_id, hostname) VALUES (?, ?)",
+                &["orko", "orko.lan"],
+            )
+            .unwrap();
+        store.flush().unwrap();
+
+        // The command exits straight after: the store is never dropped
+        std::mem::forget(store);
+
+        let reopened = VcStore::open(&path).unwrap();
+        let changes: i64 = reopened
+            .query_scalar(
+                "SELECT changes_since_analyze FROM table_stats WHERE table_name = 'machines'",
+            )
+            .unwrap();
+        assert_eq!(changes, 1);
+        let logged: i64 = reopened
+            .query_scalar(
+                "SELECT COUNT(*) FROM query_log WHERE fingerprint LIKE 'INSERT INTO machines%'",
+            )
+            .unwrap();
+        assert_eq!(logged, 1);
+
+        reopened
+            .execute("DELETE FROM machines WHERE machine_id = ?", &["orko"])
+            .unwrap();
+        reopened.close().unwrap();
+        let reopened = VcStore::open(&path).unwrap();
+        let changes: i64 = reopened
+            .query_scalar(
+                "SELECT changes_since_analyze FROM table_stats WHERE table_name = 'machines'",
+            )
+            .unwrap();
+        assert_eq!(changes, 2);
+    }
+
+    #[test]
+    fn test_flush_open_stores_reaches_stores_the_caller_does_not_hold() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vc.duckdb");
+        let store = VcStore::open(&path).unwrap();
+        let registered = OPEN_STORES
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|open| std::ptr::eq(open.as_ptr(), Arc::as_ptr(&store.conn.shared)));
+        assert!(registered);
+
+        store
+            .execute(
+                "INSERT INTO machines (machine_id, hostname) VALUES (?, ?)",
+                &["orko", "orko.lan"],
+            )
+            .unwrap();
+        // Only this store and one already gone: other tests' stores are
+        // left alone
+        let open = [Arc::downgrade(&store.conn.shared), Weak::new()];
+        assert_eq!(VcStore::flush_stores(&open).unwrap(), 1);
+        std::mem::forget(store);
+
+        let reopened = VcStore::open(&path).unwrap();
+        let changes: i64 = reopened
+            .query_scalar(
+                "SELECT changes_since_analyze FROM table_stats WHERE table_name = 'machines'",
+            )
+            .unwrap();
+        assert_eq!(changes, 1);
+    }
+
     #[test]
     fn test_open_memory_reuses_backing_store_across_connections() {
         let store = VcStore::open_memory().unwrap();
@@ -3877,11 +8409,107 @@ mod tests {
         // No panic = success
     }
 
+    #[test]
+    fn test_schema_version_is_latest_after_open() {
+        let store = VcStore::open_memory().unwrap();
+        assert_eq!(
+            store.schema_version().unwrap(),
+            migrations::latest_version()
+        );
+    }
+
+    #[test]
+    fn test_store_from_newer_build_is_refused() {
+        let store = VcStore::open_memory().unwrap();
+        let ahead = migrations::latest_version() + 1;
+        store
+            .execute_batch(&format!(
+                "INSERT INTO _migrations (version, name) VALUES ({ahead}, 'from_the_future')"
+            ))
+            .unwrap();
+        match store.run_migrations() {
+            Err(StoreError::SchemaTooNew { found, supported }) => {
+                assert_eq!(found, ahead);
+                assert_eq!(supported, migrations::latest_version());
+            }
+            other => panic!("expected SchemaTooNew, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_daemon_resource_state_roundtrip() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(store.get_daemon_resource_state().unwrap().is_none());
+
+        let mut state = DaemonResourceState {
+            checked_at: "2026-01-01T00:00:00Z".to_string(),
+            level: "transcripts_paused".to_string(),
+            disk_pressure: false,
+            rss_mb: Some(600),
+            free_disk_mb: Some(10_000),
+            max_rss_mb: Some(512),
+            min_free_disk_mb: None,
+            reason: Some("rss 600 MiB > 512 MiB".to_string()),
+            watch_subscribers: None,
+        };
+        store.upsert_daemon_resource_state(&state).unwrap();
+
+        // Upserting again replaces the single row
+        state.level = "normal".to_string();
+        state.watch_subscribers = Some(3);
+        store.upsert_daemon_resource_state(&state).unwrap();
+
+        let loaded = store.get_daemon_resource_state().unwrap().unwrap();
+        assert_eq!(loaded.level, "normal");
+        assert_eq!(loaded.watch_subscribers, Some(3));
+        assert_eq!(loaded.rss_mb, Some(600));
+        assert!(loaded.min_free_disk_mb.is_none());
+
+        // ...but each check counts as a heartbeat for its day
+        let beats = store
+            .query_json("SELECT day, beats FROM daemon_heartbeats")
+            .unwrap();
+        assert_eq!(beats.len(), 1);
+        assert_eq!(beats[0]["day"], "2026-01-01");
+        assert_eq!(beats[0]["beats"], 2);
+    }
+
     // Regression: migration 001 created ntm_sessions_snapshot without the
     // columns the NTM collector emits, and migration 006 used CREATE TABLE
     // IF NOT EXISTS so it didn't reconcile them. Migration 027 reconciles
     // the schema. This test exercises an insert with the shape the collector
     // actually emits and fails loudly if the columns are absent.
+    #[test]
+    fn test_session_outcomes_only_classify_finished_sessions() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO agent_sessions (machine_id, session_id, program, ended_at, turn_count) \
+                 VALUES ('m1', 's1', 'claude-code', '2026-10-16T10:00:00Z', 12), \
+                        ('m1', 's2', 'codex', '2026-10-16T09:00:00Z', 1), \
+                        ('m1', 's3', 'codex', NULL, 4);",
+            )
+            .unwrap();
+
+        let due = store.sessions_to_classify(false, 10).unwrap();
+        let ids: Vec<&str> = due
+            .iter()
+            .filter_map(|row| row["session_id"].as_str())
+            .collect();
+        assert_eq!(ids, vec!["s2", "s1"]);
+
+        store
+            .set_session_outcome("m1", "s1", "success", 0.9)
+            .unwrap();
+        assert_eq!(store.sessions_to_classify(false, 10).unwrap().len(), 1);
+        assert_eq!(store.sessions_to_classify(true, 10).unwrap().len(), 2);
+
+        let outcome: String = store
+            .query_scalar("SELECT outcome FROM agent_sessions WHERE session_id = 's1'")
+            .unwrap();
+        assert_eq!(outcome, "success");
+    }
+
     #[test]
     fn test_ntm_sessions_snapshot_accepts_collector_shape() {
         let store = VcStore::open_memory().unwrap();
@@ -4035,6 +8663,7 @@ mod tests {
         let filter = AuditEventFilter {
             event_type: None,
             machine_id: None,
+            actor: None,
             since: None,
             limit: 10,
         };
@@ -4072,6 +8701,7 @@ mod tests {
         let filter = AuditEventFilter {
             event_type: Some(AuditEventType::UserCommand),
             machine_id: None,
+            actor: None,
             since: None,
             limit: 10,
         };
@@ -4083,6 +8713,7 @@ mod tests {
         let filter = AuditEventFilter {
             event_type: None,
             machine_id: Some("alpha".to_string()),
+            actor: None,
             since: Some(since),
             limit: 10,
         };
@@ -4091,6 +8722,38 @@ mod tests {
         assert_eq!(rows[0]["machine_id"], "alpha");
     }
 
+    #[test]
+    fn test_audit_events_filter_by_actor() {
+        let store = VcStore::open_memory().unwrap();
+        let bot = ActorContext::mcp(Some("night-orchestrator"), None);
+        let event = bot.audit_event(
+            AuditEventType::UserCommand,
+            "alert_bulk_ack",
+            AuditResult::Success,
+            serde_json::json!({}),
+        );
+        store.insert_audit_event(&event).unwrap();
+        store
+            .insert_audit_event(&ActorContext::cli(Some("alice")).audit_event(
+                AuditEventType::UserCommand,
+                "config_rollback",
+                AuditResult::Success,
+                serde_json::json!({}),
+            ))
+            .unwrap();
+
+        let rows = store
+            .list_audit_events(&AuditEventFilter {
+                actor: Some("night-orchestrator".to_string()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["action"], "alert_bulk_ack");
+        assert_eq!(rows[0]["actor_via"], "mcp");
+    }
+
     #[test]
     fn test_audit_event_all_types() {
         let store = VcStore::open_memory().unwrap();
@@ -4101,6 +8764,7 @@ mod tests {
             (AuditEventType::AutopilotAction, "autopilot_action"),
             (AuditEventType::UserCommand, "user_command"),
             (AuditEventType::GuardianAction, "guardian_action"),
+            (AuditEventType::AlertEscalation, "alert_escalation"),
         ];
 
         for (event_type, _expected_str) in &types {
@@ -4114,13 +8778,13 @@ mod tests {
             store.insert_audit_event(&event).unwrap();
         }
 
-        // Verify all 4 events were inserted
+        // Verify all 5 events were inserted
         let filter = AuditEventFilter {
             limit: 100,
             ..Default::default()
         };
         let rows = store.list_audit_events(&filter).unwrap();
-        assert_eq!(rows.len(), 4);
+        assert_eq!(rows.len(), 5);
 
         // Verify each type can be filtered individually
         for (event_type, expected_str) in &types {
@@ -4464,6 +9128,37 @@ mod tests {
         assert_eq!(results[2]["id"], 3);
     }
 
+    #[test]
+    fn test_query_json_with_timeout_interrupts_slow_query() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch("CREATE TABLE slow_seed AS SELECT range AS x FROM range(3000);")
+            .unwrap();
+
+        // ~27 billion joined rows: minutes of work if nothing interrupts it
+        let started = Instant::now();
+        let err = store
+            .query_json_with_timeout(
+                "SELECT count(*) AS n FROM slow_seed a, slow_seed b, slow_seed c \
+                 WHERE (a.x * b.x + c.x) % 7 = 3",
+                Duration::from_millis(200),
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, StoreError::Timeout(elapsed) if elapsed >= Duration::from_millis(200))
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // The deadline does not affect queries that finish in time
+        let rows = store
+            .query_json_with_timeout(
+                "SELECT count(*) AS n FROM slow_seed",
+                Duration::from_secs(10),
+            )
+            .unwrap();
+        assert_eq!(rows[0]["n"], 3000);
+    }
+
     // =============================================================================
     // Upsert Tests
     // =============================================================================
@@ -4791,113 +9486,410 @@ mod tests {
 
         // Set initial policy
         store
-            .set_retention_policy("sys_samples", 7, None, true)
+            .set_retention_policy("sys_samples", 7, None, true)
+            .unwrap();
+
+        // Update policy
+        store
+            .set_retention_policy("sys_samples", 30, None, false)
+            .unwrap();
+
+        // Verify update
+        let policy = store.get_retention_policy("sys_samples").unwrap().unwrap();
+        assert_eq!(policy.retention_days, 30);
+        assert!(!policy.enabled);
+    }
+
+    #[test]
+    fn test_vacuum_dry_run_no_policies() {
+        let store = VcStore::open_memory().unwrap();
+
+        // Run vacuum with no policies
+        let results = store.run_vacuum(true, None).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_vacuum_history() {
+        let store = VcStore::open_memory().unwrap();
+
+        // Initially no history
+        let history = store.list_vacuum_history(10).unwrap();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_vacuum_with_data() {
+        let store = VcStore::open_memory().unwrap();
+
+        // Create a test table with a timestamp column
+        store
+            .execute_simple(
+                "CREATE TABLE test_vacuum_data (id INTEGER, collected_at TIMESTAMP, data TEXT)",
+            )
+            .unwrap();
+
+        // Insert some data - some old, some recent
+        store
+            .execute_simple(
+                "INSERT INTO test_vacuum_data VALUES
+                 (1, '2020-01-01 00:00:00', 'old'),
+                 (2, '2020-06-01 00:00:00', 'old'),
+                 (3, current_timestamp, 'new')",
+            )
+            .unwrap();
+
+        // Set a retention policy for 30 days
+        store
+            .set_retention_policy("test_vacuum_data", 30, None, true)
+            .unwrap();
+
+        // Run dry-run vacuum
+        let results = store.run_vacuum(true, Some("test_vacuum_data")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].dry_run);
+        assert_eq!(results[0].rows_deleted, 0); // dry run doesn't delete
+        assert_eq!(results[0].rows_would_delete, 2); // 2 old rows would be deleted
+        assert!(results[0].error.is_none());
+
+        // Verify data still exists (dry run)
+        let count: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM test_vacuum_data")
+            .unwrap();
+        assert_eq!(count, 3);
+
+        // Now run actual vacuum
+        let results = store.run_vacuum(false, Some("test_vacuum_data")).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(!results[0].dry_run);
+        assert_eq!(results[0].rows_deleted, 2);
+        assert!(results[0].error.is_none());
+
+        // Verify only new data remains
+        let count: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM test_vacuum_data")
+            .unwrap();
+        assert_eq!(count, 1);
+
+        // Verify history was logged
+        let history = store.list_vacuum_history(10).unwrap();
+        assert_eq!(history.len(), 2); // dry run + actual run
+    }
+
+    #[test]
+    fn test_simulate_retention_reports_eligible_rows_and_incidents() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "CREATE TABLE test_sim (machine_id TEXT, collected_at TIMESTAMP, data TEXT)",
+            )
+            .unwrap();
+        store
+            .execute_simple(
+                "INSERT INTO test_sim VALUES
+                 ('orko', '2020-01-05 00:00:00', 'aaaa'),
+                 ('orko', '2020-02-05 00:00:00', 'bb'),
+                 ('sydney', '2020-01-10 00:00:00', 'c'),
+                 ('orko', current_timestamp - INTERVAL 10 DAY, 'dddd'),
+                 ('orko', current_timestamp - INTERVAL 1 HOUR, 'e')",
+            )
+            .unwrap();
+        store
+            .set_retention_policy("test_sim", 90, None, true)
+            .unwrap();
+        store
+            .create_incident("inc-old", "January outage", "critical", None)
+            .unwrap();
+        store
+            .execute_simple(
+                "UPDATE incidents SET started_at = '2020-01-01 00:00:00', \
+                 ended_at = '2020-01-31 00:00:00' WHERE incident_id = 'inc-old'",
+            )
+            .unwrap();
+        store
+            .create_incident("inc-closed", "Closed", "info", None)
+            .unwrap();
+        store
+            .execute_simple(
+                "UPDATE incidents SET status = 'closed', started_at = '2020-01-01 00:00:00' \
+                 WHERE incident_id = 'inc-closed'",
+            )
+            .unwrap();
+
+        let sim = store.simulate_retention("test_sim", 30, 30).unwrap();
+        assert_eq!(sim.current_retention_days, Some(90));
+        assert_eq!(sim.rows_total, 5);
+        assert_eq!(sim.rows_eligible_now, 3);
+        // Each row is its text plus an 8-byte timestamp
+        assert_eq!(sim.bytes_eligible_now, (4 + 4) + (4 + 2) + (6 + 1) + 3 * 8);
+        // Both recent rows age out within a 30-day horizon
+        assert_eq!(sim.rows_eligible_horizon, 2);
+        assert_eq!(sim.breakdown.len(), 3);
+        assert_eq!(sim.breakdown[0].machine_id.as_deref(), Some("orko"));
+        assert_eq!(sim.breakdown[0].month, "2020-01");
+        assert_eq!(sim.breakdown[2].machine_id.as_deref(), Some("sydney"));
+
+        // Only the open incident counts, with the two January rows inside it
+        assert_eq!(sim.open_incidents.len(), 1);
+        assert_eq!(sim.open_incidents[0].incident_id, "inc-old");
+        assert_eq!(sim.open_incidents[0].rows, 2);
+
+        // Nothing was deleted or logged
+        assert_eq!(
+            store
+                .query_scalar::<i64>("SELECT COUNT(*) FROM test_sim")
+                .unwrap(),
+            5
+        );
+        assert!(store.list_vacuum_history(10).unwrap().is_empty());
+
+        let all = store.simulate_retention_all(30, 30).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].table_name, "test_sim");
+        assert!(store.simulate_retention("no_such_table", 30, 30).is_err());
+    }
+
+    #[test]
+    fn test_vacuum_disabled_policy() {
+        let store = VcStore::open_memory().unwrap();
+
+        // Create a test table
+        store
+            .execute_simple("CREATE TABLE test_disabled (id INTEGER, ts TIMESTAMP)")
+            .unwrap();
+
+        // Set a disabled retention policy
+        store
+            .set_retention_policy("test_disabled", 7, None, false)
+            .unwrap();
+
+        // Run vacuum - should skip disabled policy
+        let results = store.run_vacuum(true, None).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_retention_size_limits_survive_policy_update() {
+        let store = VcStore::open_memory().unwrap();
+
+        store
+            .set_retention_policy("agent_sessions", 30, None, true)
+            .unwrap();
+        let policy = store
+            .get_retention_policy("agent_sessions")
+            .unwrap()
             .unwrap();
+        assert_eq!(policy.max_total_bytes, None);
+        assert_eq!(policy.keep_head_bytes, DEFAULT_KEEP_BYTES);
+        assert!(store.row_size_limit("agent_sessions").unwrap().is_none());
 
-        // Update policy
         store
-            .set_retention_policy("sys_samples", 30, None, false)
+            .set_retention_size_limits("agent_sessions", Some(1_000_000), Some(4096), 1024, 512)
+            .unwrap();
+        store
+            .set_retention_policy("agent_sessions", 60, None, true)
             .unwrap();
 
-        // Verify update
-        let policy = store.get_retention_policy("sys_samples").unwrap().unwrap();
-        assert_eq!(policy.retention_days, 30);
-        assert!(!policy.enabled);
+        let policy = store
+            .get_retention_policy("agent_sessions")
+            .unwrap()
+            .unwrap();
+        assert_eq!(policy.retention_days, 60);
+        assert_eq!(policy.max_total_bytes, Some(1_000_000));
+        assert_eq!(
+            store.row_size_limit("agent_sessions").unwrap(),
+            Some(RowSizeLimit {
+                max_row_bytes: 4096,
+                keep_head_bytes: 1024,
+                keep_tail_bytes: 512,
+            })
+        );
+
+        // Size limits need an existing policy.
+        assert!(
+            store
+                .set_retention_size_limits("nonexistent", None, None, 1, 1)
+                .is_err()
+        );
     }
 
     #[test]
-    fn test_vacuum_dry_run_no_policies() {
+    fn test_vacuum_enforces_total_byte_cap_oldest_first() {
         let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "CREATE TABLE test_blobs (id INTEGER, collected_at TIMESTAMP, body TEXT)",
+            )
+            .unwrap();
+        // All rows are young; 3 x 100 bytes against a 250 byte cap.
+        store
+            .execute_simple(
+                "INSERT INTO test_blobs VALUES
+                 (1, current_timestamp - INTERVAL 3 HOUR, repeat('a', 100)),
+                 (2, current_timestamp - INTERVAL 2 HOUR, repeat('b', 100)),
+                 (3, current_timestamp - INTERVAL 1 HOUR, repeat('c', 100))",
+            )
+            .unwrap();
+        store
+            .set_retention_policy("test_blobs", 30, None, true)
+            .unwrap();
+        store
+            .set_retention_size_limits("test_blobs", Some(250), None, 0, 0)
+            .unwrap();
 
-        // Run vacuum with no policies
-        let results = store.run_vacuum(true, None).unwrap();
-        assert!(results.is_empty());
-    }
+        let results = store.run_vacuum(true, Some("test_blobs")).unwrap();
+        assert_eq!(results[0].rows_would_delete, 1);
+        assert_eq!(results[0].bytes_would_reclaim, 100);
+        assert_eq!(results[0].bytes_reclaimed, 0);
 
-    #[test]
-    fn test_vacuum_history() {
-        let store = VcStore::open_memory().unwrap();
+        let results = store.run_vacuum(false, Some("test_blobs")).unwrap();
+        assert_eq!(results[0].rows_deleted, 1);
+        assert_eq!(results[0].bytes_reclaimed, 100);
+
+        let oldest: i64 = store
+            .query_scalar("SELECT MIN(id) FROM test_blobs")
+            .unwrap();
+        assert_eq!(oldest, 2);
 
-        // Initially no history
         let history = store.list_vacuum_history(10).unwrap();
-        assert!(history.is_empty());
+        assert!(history.iter().any(|h| h["bytes_reclaimed"] == 100));
     }
 
     #[test]
-    fn test_vacuum_with_data() {
+    fn test_vacuum_applies_most_specific_scope_per_machine() {
         let store = VcStore::open_memory().unwrap();
-
-        // Create a test table with a timestamp column
         store
             .execute_simple(
-                "CREATE TABLE test_vacuum_data (id INTEGER, collected_at TIMESTAMP, data TEXT)",
+                "INSERT INTO machines (machine_id, hostname, tags) VALUES
+                 ('builder-1', 'builder-1', '[\"builder\"]'),
+                 ('builder-2', 'builder-2', '[\"builder\"]'),
+                 ('laptop', 'laptop', '[]')",
             )
             .unwrap();
-
-        // Insert some data - some old, some recent
         store
             .execute_simple(
-                "INSERT INTO test_vacuum_data VALUES
-                 (1, '2020-01-01 00:00:00', 'old'),
-                 (2, '2020-06-01 00:00:00', 'old'),
-                 (3, current_timestamp, 'new')",
+                "CREATE TABLE test_scoped (id INTEGER, machine_id TEXT, collected_at TIMESTAMP)",
             )
             .unwrap();
-
-        // Set a retention policy for 30 days
         store
-            .set_retention_policy("test_vacuum_data", 30, None, true)
+            .execute_simple(
+                "INSERT INTO test_scoped VALUES
+                 (1, 'builder-1', current_timestamp - INTERVAL 10 DAY),
+                 (2, 'builder-2', current_timestamp - INTERVAL 10 DAY),
+                 (3, 'laptop', current_timestamp - INTERVAL 10 DAY),
+                 (4, 'laptop', current_timestamp - INTERVAL 40 DAY)",
+            )
             .unwrap();
-
-        // Run dry-run vacuum
-        let results = store.run_vacuum(true, Some("test_vacuum_data")).unwrap();
-        assert_eq!(results.len(), 1);
-        assert!(results[0].dry_run);
-        assert_eq!(results[0].rows_deleted, 0); // dry run doesn't delete
-        assert_eq!(results[0].rows_would_delete, 2); // 2 old rows would be deleted
-        assert!(results[0].error.is_none());
-
-        // Verify data still exists (dry run)
-        let count: i64 = store
-            .query_scalar("SELECT COUNT(*) FROM test_vacuum_data")
+        store
+            .set_retention_policy("test_scoped", 30, None, true)
+            .unwrap();
+        store
+            .set_scoped_retention_policy("test_scoped", &"tag:builder".parse().unwrap(), 7, true)
+            .unwrap();
+        // builder-2 is pinned longer by an explicit list, which beats the tag.
+        store
+            .set_scoped_retention_policy(
+                "test_scoped",
+                &"machines:builder-2".parse().unwrap(),
+                14,
+                true,
+            )
             .unwrap();
-        assert_eq!(count, 3);
 
-        // Now run actual vacuum
-        let results = store.run_vacuum(false, Some("test_vacuum_data")).unwrap();
+        let policies = store.list_retention_policies().unwrap();
+        assert_eq!(policies.len(), 3);
+        assert!(policies[0].scope.is_none());
+        // The table-wide lookup ignores scoped policies.
+        let policy = store.get_retention_policy("test_scoped").unwrap().unwrap();
+        assert_eq!(policy.retention_days, 30);
+
+        let results = store.run_vacuum(true, Some("test_scoped")).unwrap();
         assert_eq!(results.len(), 1);
-        assert!(!results[0].dry_run);
-        assert_eq!(results[0].rows_deleted, 2);
-        assert!(results[0].error.is_none());
+        assert_eq!(results[0].rows_would_delete, 2);
+        let by_scope: Vec<(&str, i64)> = results[0]
+            .scopes
+            .iter()
+            .map(|s| (s.scope.as_str(), s.rows_would_delete))
+            .collect();
+        assert_eq!(
+            by_scope,
+            vec![
+                (TABLE_WIDE_SCOPE, 1),
+                ("machines:builder-2", 0),
+                ("tag:builder", 1),
+            ]
+        );
 
-        // Verify only new data remains
-        let count: i64 = store
-            .query_scalar("SELECT COUNT(*) FROM test_vacuum_data")
+        store.run_vacuum(false, Some("test_scoped")).unwrap();
+        let survivors: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM test_scoped WHERE id IN (2, 3)")
             .unwrap();
-        assert_eq!(count, 1);
-
-        // Verify history was logged
-        let history = store.list_vacuum_history(10).unwrap();
-        assert_eq!(history.len(), 2); // dry run + actual run
+        assert_eq!(survivors, 2);
+        let total: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM test_scoped")
+            .unwrap();
+        assert_eq!(total, 2);
     }
 
     #[test]
-    fn test_vacuum_disabled_policy() {
-        let store = VcStore::open_memory().unwrap();
+    fn test_resolve_retention_policy_ties_by_longest_retention() {
+        let policy = |id: &str, days: i32, scope: Option<&str>| RetentionPolicy {
+            policy_id: id.to_string(),
+            table_name: "sys_samples".to_string(),
+            retention_days: days,
+            aggregate_table: None,
+            enabled: true,
+            last_vacuum_at: None,
+            max_total_bytes: None,
+            max_row_bytes: None,
+            keep_head_bytes: DEFAULT_KEEP_BYTES,
+            keep_tail_bytes: DEFAULT_KEEP_BYTES,
+            scope: scope.map(str::to_string),
+        };
+        let policies = vec![
+            policy("wide", 30, None),
+            policy("builder", 7, Some("tag:builder")),
+            policy("gpu", 14, Some("tag:gpu")),
+        ];
+        let tags = |t: &[&str]| t.iter().map(|t| (*t).to_string()).collect::<Vec<_>>();
 
-        // Create a test table
-        store
-            .execute_simple("CREATE TABLE test_disabled (id INTEGER, ts TIMESTAMP)")
-            .unwrap();
+        let pick = |machine: &str, t: &[&str]| {
+            resolve_retention_policy(&policies, machine, &tags(t)).map(|p| p.policy_id.as_str())
+        };
+        assert_eq!(pick("laptop", &[]), Some("wide"));
+        assert_eq!(pick("b1", &["builder"]), Some("builder"));
+        assert_eq!(pick("b2", &["builder", "gpu"]), Some("gpu"));
+
+        assert!("machines:".parse::<RetentionScope>().is_err());
+        assert!("host:b1".parse::<RetentionScope>().is_err());
+        assert_eq!(
+            "machines: b2,b1,b1"
+                .parse::<RetentionScope>()
+                .unwrap()
+                .to_string(),
+            "machines:b1,b2"
+        );
+    }
 
-        // Set a disabled retention policy
-        store
-            .set_retention_policy("test_disabled", 7, None, false)
-            .unwrap();
+    #[test]
+    fn test_row_size_limit_truncate_keeps_head_and_tail() {
+        let limit = RowSizeLimit {
+            max_row_bytes: 10,
+            keep_head_bytes: 12,
+            keep_tail_bytes: 12,
+        };
+        assert!(limit.truncate("short text").is_none());
 
-        // Run vacuum - should skip disabled policy
-        let results = store.run_vacuum(true, None).unwrap();
-        assert!(results.is_empty());
+        let text = format!("first words {} last words", "x".repeat(200));
+        let cut = limit.truncate(&text).unwrap();
+        assert!(cut.starts_with("first words"));
+        assert!(cut.ends_with(" last words"));
+        assert!(cut.contains("bytes truncated"));
+        assert!(cut.len() < text.len());
+
+        // Multi-byte text is never cut inside a character.
+        let text = "é".repeat(100);
+        assert!(limit.truncate(&text).is_some());
     }
 
     // =============================================================================
@@ -4917,6 +9909,7 @@ mod tests {
             rows_inserted: 42,
             bytes_parsed: 8192,
             error_class: None,
+            error_cause: None,
             freshness_seconds: Some(120),
             payload_hash: Some("abc123".to_string()),
             collector_version: Some("1.0".to_string()),
@@ -4935,6 +9928,7 @@ mod tests {
         // `success` is `INTEGER NOT NULL` (011_collector_health.sql), so it
         // round-trips through JSON as 1/0, not as a JSON boolean.
         assert_eq!(entries[0]["success"], 1);
+        assert_eq!(entries[0]["collector_version"], "1.0");
     }
 
     #[test]
@@ -4955,6 +9949,7 @@ mod tests {
                 rows_inserted: 10,
                 bytes_parsed: 1024,
                 error_class: None,
+                error_cause: None,
                 freshness_seconds: Some(60),
                 payload_hash: None,
                 collector_version: None,
@@ -4988,6 +9983,7 @@ mod tests {
             rows_inserted: 0,
             bytes_parsed: 0,
             error_class: Some("timeout".to_string()),
+            error_cause: Some("timeout".to_string()),
             freshness_seconds: None,
             payload_hash: None,
             collector_version: None,
@@ -5006,6 +10002,60 @@ mod tests {
         assert_eq!(entries[0]["error_class"], "timeout");
     }
 
+    #[test]
+    fn test_collector_failure_causes_grouped_per_machine() {
+        let store = VcStore::open_memory().unwrap();
+        let run = |machine: &str, at: &str, cause: Option<&str>| CollectorHealth {
+            machine_id: machine.to_string(),
+            collector: "sysmoni".to_string(),
+            collected_at: at.to_string(),
+            success: cause.is_none(),
+            duration_ms: None,
+            rows_inserted: 0,
+            bytes_parsed: 0,
+            error_class: cause.map(|c| format!("raw {c} message")),
+            error_cause: cause.map(ToString::to_string),
+            freshness_seconds: None,
+            payload_hash: None,
+            collector_version: None,
+            schema_version: None,
+            cursor_json: None,
+        };
+        for health in [
+            run("m1", "2026-10-16T09:00:00.000000Z", Some("timeout")),
+            run("m1", "2026-10-16T10:00:00.000000Z", Some("timeout")),
+            run("m1", "2026-10-16T10:05:00.000000Z", Some("auth_failed")),
+            run("m1", "2026-10-16T10:10:00.000000Z", None),
+            run("m2", "2026-10-16T10:00:00.000000Z", Some("unknown")),
+        ] {
+            store.insert_collector_health(&health).unwrap();
+        }
+
+        let since = DateTime::parse_from_rfc3339("2026-10-16T09:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let causes = store.collector_failure_causes(None, None, since).unwrap();
+        let summary: Vec<(&str, &str, i64)> = causes
+            .iter()
+            .map(|c| (c.machine_id.as_str(), c.cause.as_str(), c.failures))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("m1", "auth_failed", 1),
+                ("m1", "timeout", 1),
+                ("m2", "unknown", 1)
+            ]
+        );
+        assert_eq!(
+            store
+                .collector_failure_causes(Some("m2"), Some("sysmoni"), since)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
     #[test]
     fn test_freshness_summaries() {
         let store = VcStore::open_memory().unwrap();
@@ -5020,6 +10070,7 @@ mod tests {
             rows_inserted: 10,
             bytes_parsed: 1024,
             error_class: None,
+            error_cause: None,
             freshness_seconds: Some(5),
             payload_hash: None,
             collector_version: None,
@@ -5054,6 +10105,7 @@ mod tests {
             rows_inserted: 5,
             bytes_parsed: 512,
             error_class: None,
+            error_cause: None,
             freshness_seconds: Some(3600),
             payload_hash: None,
             collector_version: None,
@@ -5152,14 +10204,71 @@ mod tests {
             z_score: 5.0,
             severity: DriftSeverity::Critical,
             evidence_json: Some(serde_json::json!({"baseline_window": "7d"})),
+            previous_value: Some("45".to_string()),
+            new_value: Some("95".to_string()),
+            delta: Some(50.0),
+            baseline_ref: Some("7d@2026-10-01T00:00:00Z".to_string()),
+            detection_method: Some("z_score".to_string()),
         };
 
         store.insert_drift_event(&event).unwrap();
 
-        let events = store.list_drift_events(Some("m1"), None, 100).unwrap();
+        let events = store
+            .list_drift_events(Some("m1"), None, None, 100)
+            .unwrap();
         assert_eq!(events.len(), 1);
         assert_eq!(events[0]["metric"], "cpu_pct");
         assert_eq!(events[0]["severity"], "critical");
+        assert_eq!(events[0]["previous_value"], "45");
+        assert_eq!(events[0]["detection_method"], "z_score");
+        assert_eq!(events[0]["description"], "cpu_pct: 45 → 95 (+50)");
+        assert_eq!(event.description(), "cpu_pct: 45 → 95 (+50)");
+    }
+
+    #[test]
+    fn test_drift_events_filter_by_field_and_legacy_rows() {
+        let store = VcStore::open_memory().unwrap();
+
+        // A row written before the structured columns existed.
+        store
+            .execute_simple(
+                "INSERT INTO drift_events (id, machine_id, metric, current_value, baseline_mean, \
+                 baseline_std, z_score, severity) \
+                 VALUES (1, 'm1', 'mem_pct', 90.0, 40.5, 10.0, 4.95, 'critical')",
+            )
+            .unwrap();
+        store
+            .insert_drift_event(&DriftEvent {
+                machine_id: "m1".to_string(),
+                detected_at: Utc::now().to_rfc3339(),
+                metric: "kernel_version".to_string(),
+                current_value: 0.0,
+                baseline_mean: 0.0,
+                baseline_std: 0.0,
+                z_score: 0.0,
+                severity: DriftSeverity::Warning,
+                evidence_json: None,
+                previous_value: Some("6.1.0".to_string()),
+                new_value: Some("6.6.8".to_string()),
+                delta: None,
+                baseline_ref: None,
+                detection_method: Some("value_change".to_string()),
+            })
+            .unwrap();
+
+        let kernel = store
+            .list_drift_events(None, None, Some("kernel_version"), 100)
+            .unwrap();
+        assert_eq!(kernel.len(), 1);
+        assert_eq!(kernel[0]["description"], "kernel_version: 6.1.0 → 6.6.8");
+
+        let legacy = store
+            .list_drift_events(None, None, Some("mem_pct"), 100)
+            .unwrap();
+        assert_eq!(legacy.len(), 1);
+        assert!(legacy[0]["previous_value"].is_null());
+        assert!(legacy[0]["delta"].is_null());
+        assert_eq!(legacy[0]["description"], "mem_pct: 40.5 → 90");
     }
 
     #[test]
@@ -5182,16 +10291,21 @@ mod tests {
                 z_score: z,
                 severity: sev,
                 evidence_json: None,
+                previous_value: None,
+                new_value: None,
+                delta: None,
+                baseline_ref: None,
+                detection_method: None,
             };
             store.insert_drift_event(&event).unwrap();
         }
 
         let critical = store
-            .list_drift_events(None, Some("critical"), 100)
+            .list_drift_events(None, Some("critical"), None, 100)
             .unwrap();
         assert_eq!(critical.len(), 1);
 
-        let all = store.list_drift_events(None, None, 100).unwrap();
+        let all = store.list_drift_events(None, None, None, 100).unwrap();
         assert_eq!(all.len(), 3);
     }
 
@@ -5211,6 +10325,11 @@ mod tests {
         let event = event.unwrap();
         assert_eq!(event.severity, DriftSeverity::Critical);
         assert!((event.z_score - 5.0).abs() < f64::EPSILON);
+        assert_eq!(event.previous_value.as_deref(), Some("45"));
+        assert_eq!(event.new_value.as_deref(), Some("95"));
+        assert_eq!(event.delta, Some(50.0));
+        assert_eq!(event.detection_method.as_deref(), Some("z_score"));
+        assert!(event.baseline_ref.as_deref().unwrap().starts_with("7d@"));
 
         // Check with value within normal range
         let no_event = store.check_drift("m1", "cpu_pct", 50.0, 3.0, "7d").unwrap();
@@ -5249,31 +10368,267 @@ mod tests {
         });
         store.set_machine_baseline("m1", "7d", &baseline).unwrap();
 
-        // Zero std deviation - avoid division by zero, return None
-        let result = store.check_drift("m1", "cpu_pct", 95.0, 3.0, "7d").unwrap();
-        assert!(result.is_none());
+        // Zero std deviation - avoid division by zero, return None
+        let result = store.check_drift("m1", "cpu_pct", 95.0, 3.0, "7d").unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_drift_severity_from_z_score() {
+        assert_eq!(DriftSeverity::from_z_score(2.0), DriftSeverity::Info);
+        assert_eq!(DriftSeverity::from_z_score(3.0), DriftSeverity::Warning);
+        assert_eq!(DriftSeverity::from_z_score(3.5), DriftSeverity::Warning);
+        assert_eq!(DriftSeverity::from_z_score(4.0), DriftSeverity::Critical);
+        assert_eq!(DriftSeverity::from_z_score(5.0), DriftSeverity::Critical);
+        assert_eq!(DriftSeverity::from_z_score(-4.5), DriftSeverity::Critical);
+    }
+
+    #[test]
+    fn test_drift_severity_roundtrip() {
+        let severities = ["info", "warning", "critical"];
+        for s in severities {
+            let parsed: DriftSeverity = s.parse().unwrap();
+            assert_eq!(parsed.as_str(), s);
+        }
+
+        let err = "invalid".parse::<DriftSeverity>();
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_record_tool_version_history_and_drift() {
+        let store = VcStore::open_memory().unwrap();
+
+        assert_eq!(
+            store
+                .record_tool_version("m1", "sysmoni", "0.4.0", "probe")
+                .unwrap(),
+            None
+        );
+        // Same version again only refreshes last_seen_at.
+        assert_eq!(
+            store
+                .record_tool_version("m1", "sysmoni", "0.4.0", "probe")
+                .unwrap(),
+            None
+        );
+        assert!(
+            store
+                .list_drift_events(Some("m1"), None, None, 10)
+                .unwrap()
+                .is_empty()
+        );
+
+        assert_eq!(
+            store
+                .record_tool_version("m1", "sysmoni", "0.5.1", "probe")
+                .unwrap(),
+            Some("0.4.0".to_string())
+        );
+        store
+            .record_tool_version("m2", "sysmoni", "0.4.0", "probe")
+            .unwrap();
+
+        let history = store
+            .tool_version_history("m1", Some("sysmoni"), 10)
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].tool_version, "0.5.1");
+        assert_eq!(history[0].previous_version.as_deref(), Some("0.4.0"));
+
+        let current = store.list_tool_versions(None).unwrap();
+        assert_eq!(current.len(), 2);
+        assert_eq!(current[0].machine_id, "m1");
+        assert_eq!(current[0].tool_version, "0.5.1");
+
+        let drift = store
+            .list_drift_events(Some("m1"), None, Some("sysmoni_version"), 10)
+            .unwrap();
+        assert_eq!(drift.len(), 1);
+        assert_eq!(drift[0]["severity"], "info");
+        assert_eq!(drift[0]["description"], "sysmoni_version: 0.4.0 → 0.5.1");
+
+        // A downgrade is a warning.
+        store
+            .record_tool_version("m1", "sysmoni", "0.4.0", "probe")
+            .unwrap();
+        let drift = store
+            .list_drift_events(Some("m1"), Some("warning"), Some("sysmoni_version"), 10)
+            .unwrap();
+        assert_eq!(drift.len(), 1);
+    }
+
+    #[test]
+    fn test_clock_skew_history_and_effective_skew() {
+        let store = VcStore::open_memory().unwrap();
+        let sample = |at: &str, skew_ms: i64, rtt_ms: Option<i64>, source: &str| ClockSkewSample {
+            machine_id: "m1".to_string(),
+            measured_at: at.to_string(),
+            skew_ms,
+            rtt_ms,
+            source: source.to_string(),
+        };
+
+        store
+            .record_clock_skew(&sample(
+                "2026-10-16T10:00:00.000000Z",
+                2_400_000,
+                Some(300),
+                "probe",
+            ))
+            .unwrap();
+        // Arrived late: looks behind, but that may just be delivery delay.
+        store
+            .record_clock_skew(&sample(
+                "2026-10-16T10:05:00.000000Z",
+                -5_000,
+                None,
+                "ingest",
+            ))
+            .unwrap();
+
+        let history = store.clock_skew_history("m1", 10).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].source, "ingest");
+        assert_eq!(store.list_clock_skew(None).unwrap()[0].skew_ms, -5_000);
+
+        let effective = store.effective_clock_skew("m1").unwrap().unwrap();
+        assert_eq!(effective.skew_ms, 2_400_000);
+        assert_eq!(effective.min_abs_skew_ms(), 2_399_850);
+        assert!(store.effective_clock_skew("m2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_maintenance_window_lifecycle() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "INSERT INTO machines (machine_id, hostname, status) VALUES \
+                 ('m1', 'host1', 'online'), ('m2', 'host2', 'online')",
+            )
+            .unwrap();
+        let status = |id: &str| -> String {
+            store
+                .query_scalar(&format!(
+                    "SELECT status FROM machines WHERE machine_id = '{id}'"
+                ))
+                .unwrap()
+        };
+
+        assert!(
+            store
+                .start_maintenance("nope", None, None, &ActorContext::system("op"))
+                .is_err()
+        );
+        let window = store
+            .start_maintenance("m1", Some("RAM upgrade"), None, &ActorContext::system("op"))
+            .unwrap();
+        assert_eq!(window.reason.as_deref(), Some("RAM upgrade"));
+        assert_eq!(status("m1"), "maintenance");
+
+        // A second --on updates the open window rather than opening another.
+        let until = Utc::now() - chrono::Duration::minutes(1);
+        let updated = store
+            .start_maintenance(
+                "m1",
+                Some("RAM upgrade, take two"),
+                Some(until),
+                &ActorContext::system("op"),
+            )
+            .unwrap();
+        assert_eq!(updated.started_at, window.started_at);
+        store
+            .start_maintenance("m2", None, None, &ActorContext::system("op"))
+            .unwrap();
+        assert_eq!(store.active_maintenance().unwrap().len(), 2);
+
+        assert_eq!(store.expire_maintenance(Utc::now()).unwrap(), vec!["m1"]);
+        assert_eq!(status("m1"), "unknown");
+        assert!(store.maintenance_window("m1").unwrap().is_none());
+
+        let ended = store
+            .end_maintenance("m2", &ActorContext::system("op"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ended.ended_by.as_deref(), Some("op"));
+        assert!(
+            store
+                .end_maintenance("m2", &ActorContext::system("op"))
+                .unwrap()
+                .is_none()
+        );
+        assert!(store.active_maintenance().unwrap().is_empty());
+
+        let audited: i64 = store
+            .query_scalar(
+                "SELECT COUNT(*) FROM audit_events \
+                 WHERE action IN ('maintenance_on', 'maintenance_off')",
+            )
+            .unwrap();
+        assert_eq!(audited, 4);
     }
 
     #[test]
-    fn test_drift_severity_from_z_score() {
-        assert_eq!(DriftSeverity::from_z_score(2.0), DriftSeverity::Info);
-        assert_eq!(DriftSeverity::from_z_score(3.0), DriftSeverity::Warning);
-        assert_eq!(DriftSeverity::from_z_score(3.5), DriftSeverity::Warning);
-        assert_eq!(DriftSeverity::from_z_score(4.0), DriftSeverity::Critical);
-        assert_eq!(DriftSeverity::from_z_score(5.0), DriftSeverity::Critical);
-        assert_eq!(DriftSeverity::from_z_score(-4.5), DriftSeverity::Critical);
+    fn test_alert_spans_and_resolution_provenance() {
+        let store = VcStore::open_memory().unwrap();
+        let alert = |rule: &str, fired_at: &str| FiredAlert {
+            rule_id: rule.to_string(),
+            fired_at: fired_at.to_string(),
+            severity: "warning".to_string(),
+            title: "t".to_string(),
+            message: "m".to_string(),
+            context_json: None,
+            machine_id: Some("orko".to_string()),
+        };
+        store
+            .insert_alert(&alert("collector-stale:sysmoni", "2026-10-16T09:00:00Z"))
+            .unwrap();
+        store
+            .insert_alert(&alert("collector-stale:caut", "2026-10-16T10:00:00Z"))
+            .unwrap();
+        store
+            .insert_alert(&alert("clock-skew", "2026-10-16T11:00:00Z"))
+            .unwrap();
+
+        let span = store
+            .latest_alert_span("collector-stale", Some("orko"), "2026-10-16T12:00:00Z")
+            .unwrap()
+            .unwrap();
+        assert_eq!(span.rule_id, "collector-stale:caut");
+        assert!(span.resolved_at.is_none());
+        assert_eq!(store.alert_span(span.alert_id).unwrap(), Some(span));
+        let earlier = store
+            .latest_alert_span("collector-stale", None, "2026-10-16T09:30:00Z")
+            .unwrap()
+            .unwrap();
+        assert_eq!(earlier.rule_id, "collector-stale:sysmoni");
+        assert!(
+            store
+                .latest_alert_span("collector", None, "2026-10-16T12:00:00Z")
+                .unwrap()
+                .is_none()
+        );
+
+        let id = store
+            .insert_resolution("collector-stale", "[]", "success", None, None, None)
+            .unwrap();
+        store
+            .set_resolution_provenance(id, r#"{"source":"session"}"#)
+            .unwrap();
+        let rows = store
+            .list_resolutions(Some("collector-stale"), None, 10)
+            .unwrap();
+        assert_eq!(rows[0]["provenance"], r#"{"source":"session"}"#);
     }
 
     #[test]
-    fn test_drift_severity_roundtrip() {
-        let severities = ["info", "warning", "critical"];
-        for s in severities {
-            let parsed: DriftSeverity = s.parse().unwrap();
-            assert_eq!(parsed.as_str(), s);
-        }
+    fn test_compare_versions() {
+        use std::cmp::Ordering;
 
-        let err = "invalid".parse::<DriftSeverity>();
-        assert!(err.is_err());
+        assert_eq!(compare_versions("0.10.0", "0.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.0-beta", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.3", "1.10"), Ordering::Less);
     }
 
     // =========================================================================
@@ -5338,6 +10693,493 @@ mod tests {
         assert_eq!(logs.len(), 3);
     }
 
+    #[test]
+    fn test_digest_delivery_log_records_alert_count() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .insert_delivery_log("7", "webhook", "success", None, Some(50))
+            .unwrap();
+        store
+            .insert_digest_delivery_log("slack", &[3, 4, 5], "success", None, Some(120))
+            .unwrap();
+
+        let logs = store.list_delivery_logs(Some("digest:3,4,5"), 10).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0]["channel_type"], "slack");
+        assert_eq!(logs[0]["alert_count"], 3);
+
+        let direct = store.list_delivery_logs(Some("7"), 10).unwrap();
+        assert_eq!(direct[0]["alert_count"], 1);
+    }
+
+    #[test]
+    fn test_replace_machine_services() {
+        let store = VcStore::open_memory().unwrap();
+        let service = |name: &str, running: bool| MachineServiceStatus {
+            machine_id: "m1".to_string(),
+            service_name: name.to_string(),
+            kind: "process".to_string(),
+            target: name.to_string(),
+            required: true,
+            running,
+            pid: running.then_some(42),
+            uptime_secs: running.then_some(3600),
+            detail: None,
+            checked_at: "2026-10-16T12:00:00Z".to_string(),
+        };
+
+        store
+            .replace_machine_services("m1", &[service("tmux", true), service("vc-node", false)])
+            .unwrap();
+        let listed = store.list_machine_services(Some("m1")).unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0], service("tmux", true));
+        assert!(!listed[1].running);
+
+        // A later check replaces the set, dropping services no longer configured
+        store
+            .replace_machine_services("m1", &[service("tmux", false)])
+            .unwrap();
+        let listed = store.list_machine_services(None).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(!listed[0].running);
+        assert!(store.list_machine_services(Some("m2")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_alerts_after_watermark() {
+        let store = VcStore::open_memory().unwrap();
+        assert_eq!(store.max_alert_id().unwrap(), 0);
+
+        for rule in ["disk", "cpu", "mem"] {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: "2026-10-16 12:00:00".to_string(),
+                    severity: "warning".to_string(),
+                    title: rule.to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: Some("m1".to_string()),
+                })
+                .unwrap();
+        }
+        assert_eq!(store.max_alert_id().unwrap(), 3);
+
+        let newer = store.list_alerts_after(1, 10).unwrap();
+        assert_eq!(newer.len(), 2);
+        assert_eq!(newer[0].0, 2);
+        assert_eq!(newer[0].1.rule_id, "cpu");
+        assert_eq!(newer[1].1.machine_id.as_deref(), Some("m1"));
+        assert!(store.list_alerts_after(3, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_record_request_signature_detects_replays() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(store.record_request_signature("v1=aa", 1_000, 700).unwrap());
+        assert!(!store.record_request_signature("v1=aa", 1_000, 700).unwrap());
+        assert!(store.record_request_signature("v1=bb", 1_100, 800).unwrap());
+
+        // Once outside the window the old signature is pruned
+        assert!(
+            store
+                .record_request_signature("v1=cc", 2_000, 1_700)
+                .unwrap()
+        );
+        assert!(store.record_request_signature("v1=aa", 1_000, 700).unwrap());
+    }
+
+    #[test]
+    fn test_alert_notification_context() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_simple(
+                "INSERT INTO machines (machine_id, hostname) VALUES ('m1', 'm1.lan'); \
+                 INSERT INTO health_summary (machine_id, collected_at, overall_score) VALUES \
+                 ('m1', '2026-10-16 11:00:00', 0.9), ('m1', '2026-10-16 12:00:00', 0.4);",
+            )
+            .unwrap();
+        for _ in 0..2 {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: "disk".to_string(),
+                    fired_at: "2026-10-16 12:00:00".to_string(),
+                    severity: "warning".to_string(),
+                    title: "disk".to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: Some("m1".to_string()),
+                })
+                .unwrap();
+        }
+
+        let context = store
+            .alert_notification_context("disk", Some("m1"))
+            .unwrap();
+        assert_eq!(context.hostname.as_deref(), Some("m1.lan"));
+        assert_eq!(context.health_score, Some(0.4));
+        assert_eq!(context.occurrences, 2);
+
+        let unknown = store
+            .alert_notification_context("disk", Some("m9"))
+            .unwrap();
+        assert_eq!(unknown, AlertNotificationContext::default());
+        let fleet = store.alert_notification_context("disk", None).unwrap();
+        assert_eq!(fleet.occurrences, 0);
+    }
+
+    #[test]
+    fn test_bulk_acknowledge_and_resolve_by_filter() {
+        let store = VcStore::open_memory().unwrap();
+        for (rule, machine, fired_at) in [
+            ("machine-offline", "orko", "2026-10-01T00:00:00Z"),
+            ("machine-offline", "orko", "2026-10-02T00:00:00Z"),
+            ("disk-critical", "orko", "2026-10-02T00:00:00Z"),
+            ("machine-offline", "orko", "2026-10-20T00:00:00Z"),
+            ("machine-offline", "sydney", "2026-10-01T00:00:00Z"),
+        ] {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: fired_at.to_string(),
+                    severity: "warning".to_string(),
+                    title: rule.to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: Some(machine.to_string()),
+                })
+                .unwrap();
+        }
+
+        let filter = AlertFilter {
+            machine_id: Some("orko".to_string()),
+            rule_type: Some("OFFLINE".to_string()),
+            before: Some("2026-10-16T00:00:00Z".to_string()),
+            ..AlertFilter::default()
+        };
+        let preview = store
+            .match_alerts(BulkAlertAction::Acknowledge, &filter, 1)
+            .unwrap();
+        assert_eq!(preview.matched, 2);
+        assert_eq!(preview.sample.len(), 1);
+        assert_eq!(preview.sample[0]["id"], 2);
+        assert_eq!(preview.by_machine.get("orko"), Some(&2));
+        assert_eq!(preview.by_machine.len(), 1);
+
+        let acked = store
+            .bulk_update_alerts(
+                BulkAlertAction::Acknowledge,
+                &filter,
+                &ActorContext::system("ops"),
+            )
+            .unwrap();
+        assert_eq!(acked, 2);
+        // Already acknowledged alerts no longer match an acknowledge...
+        assert_eq!(
+            store
+                .match_alerts(BulkAlertAction::Acknowledge, &filter, 10)
+                .unwrap()
+                .matched,
+            0
+        );
+        // ...but are still open, so resolve picks them up.
+        assert_eq!(
+            store
+                .bulk_update_alerts(
+                    BulkAlertAction::Resolve,
+                    &filter,
+                    &ActorContext::system("ops")
+                )
+                .unwrap(),
+            2
+        );
+
+        let by_id = AlertFilter {
+            ids: vec![3],
+            ..AlertFilter::default()
+        };
+        assert_eq!(
+            store
+                .bulk_update_alerts(
+                    BulkAlertAction::Acknowledge,
+                    &by_id,
+                    &ActorContext::system("ops")
+                )
+                .unwrap(),
+            1
+        );
+        let rows = store
+            .query_json("SELECT acknowledged_by FROM alert_history WHERE id = 3")
+            .unwrap();
+        assert_eq!(rows[0]["acknowledged_by"], "ops");
+
+        let event = bulk_alert_audit_event(
+            BulkAlertAction::Resolve,
+            &ActorContext::system("ops"),
+            &filter,
+            2,
+        );
+        assert_eq!(event.action, "alert_bulk_resolve");
+        assert_eq!(event.details["count"], 2);
+        assert_eq!(event.details["filter"]["type"], "OFFLINE");
+        assert_eq!(event.machine_id.as_deref(), Some("orko"));
+    }
+
+    #[test]
+    fn test_alert_groups_carry_escalation_state() {
+        let store = VcStore::open_memory().unwrap();
+        for (rule, machine, fired_at) in [
+            ("disk-full", "orko", "2026-10-01T00:00:00Z"),
+            ("machine-offline", "orko", "2026-10-01T06:00:00Z"),
+            ("disk-full", "orko", "2026-10-02T00:00:00Z"),
+            ("disk-full", "sydney", "2026-10-03T00:00:00Z"),
+        ] {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: fired_at.to_string(),
+                    severity: "warning".to_string(),
+                    title: rule.to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: Some(machine.to_string()),
+                })
+                .unwrap();
+        }
+
+        let groups = store.open_alert_groups().unwrap();
+        let keys: Vec<String> = groups.iter().map(OpenAlertGroup::group_key).collect();
+        assert_eq!(
+            keys,
+            vec!["orko/disk-full", "orko/machine-offline", "sydney/disk-full"]
+        );
+        assert_eq!(groups[0].alert_ids, vec![1, 3]);
+        assert_eq!(groups[0].escalation_level, 0);
+
+        // Only alert 1 escalates; the group takes its level and alert 3 is
+        // behind it
+        let at = "2026-10-02T06:00:00Z";
+        assert_eq!(
+            store
+                .record_alert_escalation(&[1], 1, "unacked", at, Some("critical"))
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            store
+                .record_alert_escalation(&[1], 1, "unacked", at, None)
+                .unwrap(),
+            0
+        );
+        let group = &store.open_alert_groups().unwrap()[0];
+        assert_eq!(group.escalation_level, 1);
+        assert_eq!(group.escalation_policy.as_deref(), Some("unacked"));
+        assert_eq!(group.escalated_at.as_deref(), Some(at));
+        assert_eq!(group.severity, "critical");
+        assert_eq!(group.behind_ids, vec![3]);
+
+        // Acknowledged alerts leave their group and no longer escalate
+        store
+            .bulk_update_alerts(
+                BulkAlertAction::Acknowledge,
+                &AlertFilter {
+                    ids: vec![3],
+                    ..AlertFilter::default()
+                },
+                &ActorContext::system("ops"),
+            )
+            .unwrap();
+        assert_eq!(
+            store
+                .record_alert_escalation(&[3], 2, "unacked", at, None)
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.open_alert_groups().unwrap()[0].alert_ids, vec![1]);
+    }
+
+    #[test]
+    fn test_alert_correlation_round_trip() {
+        let store = VcStore::open_memory().unwrap();
+        for (rule, machine, severity) in [
+            ("disk-full", Some("orko"), "critical"),
+            ("collector-stale:sysmoni", Some("orko"), "warning"),
+            ("session-stuck", Some("orko"), "warning"),
+            ("fleet-wide", None, "info"),
+        ] {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: "2026-10-01T00:00:00Z".to_string(),
+                    severity: severity.to_string(),
+                    title: rule.to_string(),
+                    message: String::new(),
+                    context_json: None,
+                    machine_id: machine.map(ToString::to_string),
+                })
+                .unwrap();
+        }
+
+        let candidates = store.correlation_candidates().unwrap();
+        assert_eq!(candidates.len(), 3, "alerts without a machine are skipped");
+        assert!(candidates.iter().all(|c| c.correlation_id.is_none()));
+
+        let changed = store
+            .set_alert_correlation(&[1, 2, 3], Some("corr-orko-1"), Some(1))
+            .unwrap();
+        assert_eq!(changed, 3);
+        // Nothing changes on a repeat
+        assert_eq!(
+            store
+                .set_alert_correlation(&[1, 2, 3], Some("corr-orko-1"), Some(1))
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.correlated_children(1).unwrap(), vec![2, 3]);
+        assert!(store.correlated_children(2).unwrap().is_empty());
+
+        // The root moves; only the two alerts whose flag flips change
+        assert_eq!(
+            store
+                .set_alert_correlation(&[1, 2, 3], Some("corr-orko-1"), Some(2))
+                .unwrap(),
+            2
+        );
+        let group = store.correlated_alerts("corr-orko-1").unwrap();
+        assert_eq!(group[0]["id"], 2);
+        assert_eq!(group[0]["root"], true);
+        assert_eq!(group.len(), 3);
+
+        store.set_alert_correlation(&[3], None, None).unwrap();
+        assert_eq!(store.correlated_children(2).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_telemetry_alert_streams_resume_from_cursor() {
+        let store = VcStore::open_memory().unwrap();
+        for rule in ["disk", "cpu"] {
+            store
+                .insert_alert(&FiredAlert {
+                    rule_id: rule.to_string(),
+                    fired_at: "2026-10-16 12:00:00".to_string(),
+                    severity: "critical".to_string(),
+                    title: format!("{rule} alert"),
+                    message: "over threshold".to_string(),
+                    context_json: None,
+                    machine_id: Some("m1".to_string()),
+                })
+                .unwrap();
+        }
+
+        let fired = TelemetryStream::AlertFired;
+        assert!(store.telemetry_cursor(fired).unwrap().is_none());
+        let events = store
+            .telemetry_events_after(fired, &TelemetryCursor::default(), 10)
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].name, "alert.fired");
+        assert_eq!(events[0].alert_type.as_deref(), Some("disk"));
+        assert_eq!(events[0].body, "disk alert: over threshold");
+
+        store
+            .init_telemetry_cursor(fired, &TelemetryCursor::default())
+            .unwrap();
+        store
+            .record_telemetry_export(fired, &events[0].cursor, 1)
+            .unwrap();
+        let cursor = store.telemetry_cursor(fired).unwrap().unwrap();
+        let rest = store.telemetry_events_after(fired, &cursor, 10).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].alert_type.as_deref(), Some("cpu"));
+
+        store
+            .record_telemetry_failure(fired, "connection refused")
+            .unwrap();
+        let status = store.telemetry_status().unwrap();
+        let fired_status = status.iter().find(|s| s.stream == fired).unwrap();
+        assert_eq!(fired_status.backlog, 1);
+        assert_eq!(fired_status.exported_count, 1);
+        assert!(fired_status.last_success_at.is_some());
+        assert_eq!(
+            fired_status.last_error.as_deref(),
+            Some("connection refused")
+        );
+
+        // Resolutions form their own stream, starting at the current head
+        let resolved = TelemetryStream::AlertResolved;
+        assert_eq!(
+            store.telemetry_head(resolved).unwrap(),
+            TelemetryCursor::default()
+        );
+        store.resolve_open_alerts("disk").unwrap();
+        let events = store
+            .telemetry_events_after(resolved, &TelemetryCursor::default(), 10)
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "alert.resolved");
+        let head = store.telemetry_head(resolved).unwrap();
+        assert!(
+            store
+                .telemetry_events_after(resolved, &head, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_telemetry_incident_and_collector_streams() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .create_incident("inc-1", "Disk pressure", "high", None)
+            .unwrap();
+        store
+            .update_incident_status("inc-1", "closed", Some("pruned"), None)
+            .unwrap();
+        let events = store
+            .telemetry_events_after(TelemetryStream::Incident, &TelemetryCursor::default(), 10)
+            .unwrap();
+        let names: Vec<&str> = events.iter().map(|e| e.name.as_str()).collect();
+        assert!(names.contains(&"incident.opened"));
+        assert!(names.contains(&"incident.closed"));
+        assert!(
+            events
+                .iter()
+                .all(|e| e.incident_id.as_deref() == Some("inc-1"))
+        );
+
+        for (collector, success) in [("sysmoni", true), ("ntm", false)] {
+            store
+                .insert_collector_health(&CollectorHealth {
+                    machine_id: "m1".to_string(),
+                    collector: collector.to_string(),
+                    collected_at: "2026-10-16T12:00:00Z".to_string(),
+                    success,
+                    duration_ms: Some(10),
+                    rows_inserted: 0,
+                    bytes_parsed: 0,
+                    error_class: (!success).then(|| "timeout".to_string()),
+                    error_cause: (!success).then(|| "timeout".to_string()),
+                    freshness_seconds: None,
+                    payload_hash: None,
+                    collector_version: None,
+                    schema_version: None,
+                    cursor_json: None,
+                })
+                .unwrap();
+        }
+        let failures = store
+            .telemetry_events_after(
+                TelemetryStream::CollectorFailure,
+                &TelemetryCursor::default(),
+                10,
+            )
+            .unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].body, "ntm failed: timeout");
+        assert_eq!(failures[0].machine_id.as_deref(), Some("m1"));
+    }
+
     #[test]
     fn test_delivery_log_filter_by_alert() {
         let store = VcStore::open_memory().unwrap();
@@ -5475,6 +11317,31 @@ mod tests {
         assert!(summary.is_empty());
     }
 
+    #[test]
+    fn test_delivery_health_counts_failures_since_success() {
+        let store = VcStore::open_memory().unwrap();
+        let health = store.delivery_health().unwrap();
+        assert!(health.last_attempt_at.is_none());
+        assert_eq!(health.failures_since_success, 0);
+
+        store
+            .insert_delivery_log("a1", "slack", "failed", Some("err"), None)
+            .unwrap();
+        store
+            .insert_delivery_log("a2", "slack", "success", None, None)
+            .unwrap();
+        store
+            .insert_delivery_log("a3", "slack", "failed", Some("err"), None)
+            .unwrap();
+        store
+            .insert_delivery_log("a4", "discord", "failed", Some("err"), None)
+            .unwrap();
+
+        let health = store.delivery_health().unwrap();
+        assert!(health.last_success_at.is_some());
+        assert_eq!(health.failures_since_success, 2);
+    }
+
     // =========================================================================
     // Autopilot Decision Tests
     // =========================================================================
@@ -5739,6 +11606,56 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_incident_status_and_severity_changes_go_on_the_timeline() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .create_incident("inc-sla-1", "Disk pressure", "warning", None)
+            .unwrap();
+
+        assert_eq!(
+            store
+                .set_incident_severity("inc-sla-1", "critical")
+                .unwrap()
+                .as_deref(),
+            Some("warning")
+        );
+        assert!(
+            store
+                .set_incident_severity("missing", "critical")
+                .unwrap()
+                .is_none()
+        );
+        store
+            .update_incident_status("inc-sla-1", "mitigated", None, None)
+            .unwrap();
+        // Same status again: nothing new on the timeline
+        store
+            .update_incident_status("inc-sla-1", "mitigated", None, None)
+            .unwrap();
+        assert_eq!(
+            store
+                .update_incident_status("missing", "closed", None, None)
+                .unwrap(),
+            0
+        );
+
+        let timeline = store.get_incident_timeline("inc-sla-1").unwrap();
+        let types: Vec<&str> = timeline
+            .iter()
+            .filter_map(|e| e["event_type"].as_str())
+            .collect();
+        assert_eq!(types, vec!["severity_changed", "status_changed"]);
+        let details: serde_json::Value =
+            serde_json::from_str(timeline[1]["details_json"].as_str().unwrap()).unwrap();
+        assert_eq!(details["from"], "open");
+        assert_eq!(details["to"], "mitigated");
+        assert_eq!(
+            store.get_incident("inc-sla-1").unwrap().unwrap()["severity"],
+            "critical"
+        );
+    }
+
     // =========================================================================
     // Data export/backup tests
     // =========================================================================
@@ -5752,6 +11669,19 @@ mod tests {
         assert!(tables.contains(&"alert_history".to_string()));
     }
 
+    #[test]
+    fn test_has_table_and_capabilities() {
+        let store = VcStore::open_memory().unwrap();
+        assert!(store.has_table("incidents").unwrap());
+        store.execute_batch("DROP TABLE incidents").unwrap();
+        assert!(!store.has_table("incidents").unwrap());
+
+        let caps = store.capabilities().unwrap();
+        assert!(caps.has_table("machines"));
+        assert!(!caps.require(&["incidents"]));
+        assert_eq!(caps.missing(), vec!["incidents"]);
+    }
+
     #[test]
     fn test_export_table_jsonl_empty() {
         let store = VcStore::open_memory().unwrap();
@@ -0,0 +1,711 @@
+//! Machine identity
+//!
+//! A machine's `machine_id` is whatever it was registered as, and it
+//! changes: a host gets re-added under a new name, or its DHCP hostname
+//! moves. Its `machine_uuid` does not. The registry assigns one when the
+//! machine is registered and a probe leaves it in a marker file on the
+//! machine, so the machine can say who it is whatever it is called.
+//!
+//! `machine_identities` keeps every machine id and hostname a UUID has been
+//! seen under, keyed by the UUID, with the host's hardware identifier for
+//! recognizing a machine whose marker file is gone. [`VcStore::merge_machines`]
+//! folds a duplicate registry entry into the one that stays: its rows move
+//! to the kept id in every table and its identities become earlier
+//! identities of the kept UUID.
+
+use std::collections::BTreeMap;
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ActorContext, AuditEventType, AuditResult, StoreConnectionGuard, StoreError, VcStore,
+    escape_sql_identifier,
+};
+
+/// Tables a merge leaves as they are: the registry itself, the identity
+/// history (keyed by UUID, it records the old id on purpose) and the audit
+/// log, which says what happened under which id
+const MERGE_KEEPS: &[&str] = &["machines", "machine_identities", "audit_events"];
+
+/// One machine id and hostname a machine has been seen under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineIdentity {
+    pub machine_uuid: String,
+    pub machine_id: String,
+    pub hostname: String,
+    /// `/etc/machine-id`, `IOPlatformUUID` or the SMBIOS UUID, when read
+    pub hardware_id: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+}
+
+/// A machine UUID seen under a registered machine id other than the one it
+/// is registered to: the same host registered twice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UuidConflict {
+    pub machine_uuid: String,
+    /// The entry the UUID is registered to
+    pub registered_as: String,
+    /// The other entry the machine was probed as
+    pub seen_as: String,
+    pub last_seen_at: String,
+}
+
+/// What [`VcStore::merge_machines`] did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachineMerge {
+    /// The registry entry that was removed
+    pub from: String,
+    /// The registry entry that stays
+    pub into: String,
+    /// UUID the kept entry carries now
+    pub machine_uuid: String,
+    /// Rows moved to the kept id, by table
+    pub moved: BTreeMap<String, usize>,
+    /// Rows of the removed id dropped because the kept id already had a row
+    /// with the same key, by table
+    pub dropped: BTreeMap<String, usize>,
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// The one text value `sql` selects with `param`, if it selects a row
+fn select_text(
+    conn: &StoreConnectionGuard<'_>,
+    sql: &str,
+    param: &str,
+) -> Result<Option<String>, StoreError> {
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query_map([param], |row| row.get::<_, Option<String>>(0))?;
+    Ok(rows.next().transpose()?.flatten())
+}
+
+/// Registered machine (`added_at` or `created_at`, uuid) for `machine_id`
+fn registration(
+    conn: &StoreConnectionGuard<'_>,
+    machine_id: &str,
+) -> Result<Option<(String, Option<String>)>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT CAST(COALESCE(added_at, created_at, '') AS VARCHAR), machine_uuid \
+         FROM machines WHERE machine_id = ?",
+    )?;
+    let mut rows = stmt.query_map([machine_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+    })?;
+    Ok(rows.next().transpose()?)
+}
+
+/// Tables other than [`MERGE_KEEPS`] with a `machine_id` column
+fn tables_with_machine_id(conn: &StoreConnectionGuard<'_>) -> Result<Vec<String>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT DISTINCT c.table_name FROM information_schema.columns c \
+         JOIN duckdb_tables() t ON t.table_name = c.table_name AND t.schema_name = 'main' \
+         WHERE c.column_name = 'machine_id' ORDER BY c.table_name",
+    )?;
+    let tables = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tables
+        .into_iter()
+        .filter(|table| !MERGE_KEEPS.contains(&table.as_str()))
+        .collect())
+}
+
+/// Column sets of `table`'s primary key and unique constraints that
+/// include `column`
+fn keys_with(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    column: &str,
+) -> Result<Vec<Vec<String>>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT constraint_index, unnest(constraint_column_names) FROM duckdb_constraints() \
+         WHERE schema_name = 'main' AND table_name = ? \
+         AND constraint_type IN ('PRIMARY KEY', 'UNIQUE')",
+    )?;
+    let rows = stmt
+        .query_map([table], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut keys: BTreeMap<i64, Vec<String>> = BTreeMap::new();
+    for (index, name) in rows {
+        keys.entry(index).or_default().push(name);
+    }
+    Ok(keys
+        .into_values()
+        .filter(|key| key.iter().any(|name| name == column))
+        .collect())
+}
+
+/// Move `table`'s rows from `from` to `into` in `column`. Rows of `from`
+/// whose key `into` already has are dropped first. Returns (moved, dropped).
+fn move_rows(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    column: &str,
+    from: &str,
+    into: &str,
+) -> Result<(usize, usize), StoreError> {
+    let safe_table = escape_sql_identifier(table);
+    let safe_column = escape_sql_identifier(column);
+    let mut dropped = 0;
+    for key in keys_with(conn, table, column)? {
+        let same_key: Vec<String> = key
+            .iter()
+            .filter(|name| *name != column)
+            .map(|name| {
+                let name = escape_sql_identifier(name);
+                format!("keep.\"{name}\" IS NOT DISTINCT FROM \"{safe_table}\".\"{name}\"")
+            })
+            .chain(std::iter::once(format!("keep.\"{safe_column}\" = ?")))
+            .collect();
+        dropped += conn.execute(
+            &format!(
+                "DELETE FROM \"{safe_table}\" WHERE \"{safe_column}\" = ? AND EXISTS ( \
+                     SELECT 1 FROM \"{safe_table}\" keep WHERE {} \
+                 )",
+                same_key.join(" AND ")
+            ),
+            [from, into],
+        )?;
+    }
+    let moved = conn.execute(
+        &format!("UPDATE \"{safe_table}\" SET \"{safe_column}\" = ? WHERE \"{safe_column}\" = ?"),
+        [into, from],
+    )?;
+    Ok((moved, dropped))
+}
+
+fn merge(
+    conn: &StoreConnectionGuard<'_>,
+    from: &str,
+    into: &str,
+    uuids: [Option<&str>; 2],
+    kept_uuid: &str,
+) -> Result<MachineMerge, StoreError> {
+    let mut merged = MachineMerge {
+        from: from.to_string(),
+        into: into.to_string(),
+        machine_uuid: kept_uuid.to_string(),
+        moved: BTreeMap::new(),
+        dropped: BTreeMap::new(),
+    };
+    let mut tally = |table: &str, (moved, dropped): (usize, usize)| {
+        if moved > 0 {
+            *merged.moved.entry(table.to_string()).or_default() += moved;
+        }
+        if dropped > 0 {
+            *merged.dropped.entry(table.to_string()).or_default() += dropped;
+        }
+    };
+
+    // An edge between the two would become a machine depending on itself
+    tally(
+        "machine_dependencies",
+        (
+            0,
+            conn.execute(
+                "DELETE FROM machine_dependencies \
+                 WHERE (machine_id = ? AND depends_on = ?) OR (machine_id = ? AND depends_on = ?)",
+                [from, into, into, from],
+            )?,
+        ),
+    );
+    for table in tables_with_machine_id(conn)? {
+        tally(&table, move_rows(conn, &table, "machine_id", from, into)?);
+    }
+    tally(
+        "machine_dependencies",
+        move_rows(conn, "machine_dependencies", "depends_on", from, into)?,
+    );
+    tally(
+        "annotations",
+        move_rows(
+            conn,
+            "annotations",
+            "scope",
+            &format!("machine:{from}"),
+            &format!("machine:{into}"),
+        )?,
+    );
+
+    // Both entries' identities become identities of the kept UUID
+    let seen_at = now();
+    for uuid in uuids.into_iter().flatten() {
+        if uuid != kept_uuid {
+            move_rows(conn, "machine_identities", "machine_uuid", uuid, kept_uuid)?;
+        }
+    }
+    conn.execute(
+        "INSERT INTO machine_identities \
+         (machine_uuid, machine_id, hostname, first_seen_at, last_seen_at) \
+         SELECT ?, machine_id, hostname, CAST(COALESCE(added_at, created_at, ?) AS VARCHAR), ? \
+         FROM machines WHERE machine_id = ? \
+         ON CONFLICT (machine_uuid, machine_id, hostname) DO NOTHING",
+        [kept_uuid, seen_at.as_str(), seen_at.as_str(), from],
+    )?;
+    conn.execute("DELETE FROM machines WHERE machine_id = ?", [from])?;
+    conn.execute(
+        "UPDATE machines SET machine_uuid = ? WHERE machine_id = ?",
+        [kept_uuid, into],
+    )?;
+    Ok(merged)
+}
+
+impl VcStore {
+    /// UUID of a registered machine; `None` for an unknown machine or one
+    /// registered before UUIDs were assigned
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_uuid(&self, machine_id: &str) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        select_text(
+            &conn,
+            "SELECT machine_uuid FROM machines WHERE machine_id = ?",
+            machine_id,
+        )
+    }
+
+    /// Machine id registered with `machine_uuid`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_with_uuid(&self, machine_uuid: &str) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        select_text(
+            &conn,
+            "SELECT machine_id FROM machines WHERE machine_uuid = ? ORDER BY machine_id LIMIT 1",
+            machine_uuid,
+        )
+    }
+
+    /// UUID last seen with `hardware_id`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_uuid_for_hardware(
+        &self,
+        hardware_id: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        select_text(
+            &conn,
+            "SELECT machine_uuid FROM machine_identities WHERE hardware_id = ? \
+             ORDER BY last_seen_at DESC LIMIT 1",
+            hardware_id,
+        )
+    }
+
+    /// Set the UUID of a registered machine
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn set_machine_uuid(&self, machine_id: &str, machine_uuid: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE machines SET machine_uuid = ? WHERE machine_id = ?",
+            [machine_uuid, machine_id],
+        )?;
+        Ok(())
+    }
+
+    /// Note that `machine_uuid` was seen as `machine_id` calling itself
+    /// `hostname`. Returns the hostname it last reported under that id when
+    /// this one differs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_machine_identity(
+        &self,
+        machine_uuid: &str,
+        machine_id: &str,
+        hostname: &str,
+        hardware_id: Option<&str>,
+    ) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let previous = {
+            let mut stmt = conn.prepare(
+                "SELECT hostname FROM machine_identities \
+                 WHERE machine_uuid = ? AND machine_id = ? \
+                 ORDER BY last_seen_at DESC LIMIT 1",
+            )?;
+            let mut rows =
+                stmt.query_map([machine_uuid, machine_id], |row| row.get::<_, String>(0))?;
+            rows.next().transpose()?
+        };
+        let seen_at = now();
+        conn.execute(
+            "INSERT INTO machine_identities \
+             (machine_uuid, machine_id, hostname, hardware_id, first_seen_at, last_seen_at) \
+             VALUES (?, ?, ?, ?, ?, ?) \
+             ON CONFLICT (machine_uuid, machine_id, hostname) DO UPDATE SET \
+                 last_seen_at = excluded.last_seen_at, \
+                 hardware_id = COALESCE(excluded.hardware_id, machine_identities.hardware_id)",
+            duckdb::params![
+                machine_uuid,
+                machine_id,
+                hostname,
+                hardware_id,
+                seen_at,
+                seen_at
+            ],
+        )?;
+        Ok(previous.filter(|previous| previous != hostname))
+    }
+
+    /// Every machine id and hostname `machine_uuid` was seen under, most
+    /// recently seen first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_identities(
+        &self,
+        machine_uuid: &str,
+    ) -> Result<Vec<MachineIdentity>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT machine_uuid, machine_id, hostname, hardware_id, first_seen_at, last_seen_at \
+             FROM machine_identities WHERE machine_uuid = ? \
+             ORDER BY last_seen_at DESC, machine_id, hostname",
+        )?;
+        let rows = stmt.query_map([machine_uuid], |row| {
+            Ok(MachineIdentity {
+                machine_uuid: row.get(0)?,
+                machine_id: row.get(1)?,
+                hostname: row.get(2)?,
+                hardware_id: row.get(3)?,
+                first_seen_at: row.get(4)?,
+                last_seen_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Fold the registry entry `from` into `into`: every table's rows of
+    /// `from` move to `into` (rows whose key `into` already has are
+    /// dropped), `from` leaves the registry, and `into` keeps the UUID of
+    /// the older of the two registrations, under which both entries'
+    /// identities are kept. The audit log keeps the old id.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if the ids are the same or either
+    /// is not registered, or [`StoreError`] if a write fails; the merge is
+    /// rolled back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn merge_machines(
+        &self,
+        from: &str,
+        into: &str,
+        actor: &ActorContext,
+    ) -> Result<MachineMerge, StoreError> {
+        if from == into {
+            return Err(StoreError::QueryError(format!(
+                "cannot merge {from} into itself"
+            )));
+        }
+        let merged = {
+            let conn = self.conn.lock().unwrap();
+            let (Some(old), Some(new)) = (registration(&conn, from)?, registration(&conn, into)?)
+            else {
+                let unknown = if registration(&conn, from)?.is_none() {
+                    from
+                } else {
+                    into
+                };
+                return Err(StoreError::QueryError(format!(
+                    "unknown machine: {unknown}"
+                )));
+            };
+            // Registration times are RFC 3339 or naive UTC, both sortable
+            let (older, newer) = if old.0 <= new.0 {
+                (&old, &new)
+            } else {
+                (&new, &old)
+            };
+            let kept_uuid = older.1.clone().or_else(|| newer.1.clone()).ok_or_else(|| {
+                StoreError::QueryError(format!(
+                    "neither {from} nor {into} has a machine UUID yet; load the config first"
+                ))
+            })?;
+
+            conn.execute("BEGIN TRANSACTION", [])?;
+            let uuids = [old.1.as_deref(), new.1.as_deref()];
+            match merge(&conn, from, into, uuids, &kept_uuid) {
+                Ok(merged) => {
+                    conn.execute("COMMIT", [])?;
+                    merged
+                }
+                Err(e) => {
+                    let _ = conn.execute("ROLLBACK", []);
+                    return Err(e);
+                }
+            }
+        };
+        self.insert_audit_event(
+            &actor
+                .audit_event(
+                    AuditEventType::UserCommand,
+                    "machine_merge",
+                    AuditResult::Success,
+                    serde_json::json!({
+                        "from": merged.from,
+                        "machine_uuid": merged.machine_uuid,
+                        "moved": merged.moved,
+                        "dropped": merged.dropped,
+                    }),
+                )
+                .with_machine_id(into),
+        )?;
+        Ok(merged)
+    }
+
+    /// Machine UUIDs seen under a registered id other than their own, most
+    /// recently seen first. Merging `registered_as` into `seen_as` resolves
+    /// one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn machine_uuid_conflicts(&self) -> Result<Vec<UuidConflict>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT i.machine_uuid, owner.machine_id, i.machine_id, MAX(i.last_seen_at) \
+             FROM machine_identities i \
+             JOIN machines owner ON owner.machine_uuid = i.machine_uuid \
+             JOIN machines seen ON seen.machine_id = i.machine_id \
+             WHERE owner.machine_id <> i.machine_id \
+             GROUP BY i.machine_uuid, owner.machine_id, i.machine_id \
+             ORDER BY MAX(i.last_seen_at) DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(UuidConflict {
+                machine_uuid: row.get(0)?,
+                registered_as: row.get(1)?,
+                seen_as: row.get(2)?,
+                last_seen_at: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with(machines: &[(&str, &str, &str)]) -> VcStore {
+        let store = VcStore::open_memory().unwrap();
+        for (id, uuid, added_at) in machines {
+            store
+                .execute_simple(&format!(
+                    "INSERT INTO machines (machine_id, hostname, machine_uuid, added_at) \
+                     VALUES ('{id}', '{id}.lan', '{uuid}', '{added_at}')"
+                ))
+                .unwrap();
+        }
+        store
+    }
+
+    fn count(store: &VcStore, sql: &str) -> i64 {
+        store.query_json(sql).unwrap()[0]["n"].as_i64().unwrap()
+    }
+
+    #[test]
+    fn test_identity_records_hostname_changes() {
+        let store = store_with(&[("orko", "u-1", "2026-01-01T00:00:00Z")]);
+        let first = store
+            .record_machine_identity("u-1", "orko", "orko.lan", Some("hw-1"))
+            .unwrap();
+        assert!(first.is_none());
+        let same = store
+            .record_machine_identity("u-1", "orko", "orko.lan", None)
+            .unwrap();
+        assert!(same.is_none());
+        let moved = store
+            .record_machine_identity("u-1", "orko", "dhcp-17.lan", None)
+            .unwrap();
+        assert_eq!(moved.as_deref(), Some("orko.lan"));
+
+        let identities = store.machine_identities("u-1").unwrap();
+        assert_eq!(identities.len(), 2);
+        assert_eq!(identities[0].hostname, "dhcp-17.lan");
+        // The hardware id recorded earlier is not wiped by a probe without one
+        assert_eq!(identities[1].hardware_id.as_deref(), Some("hw-1"));
+        assert_eq!(
+            store.machine_uuid_for_hardware("hw-1").unwrap().as_deref(),
+            Some("u-1")
+        );
+        assert_eq!(
+            store.machine_with_uuid("u-1").unwrap().as_deref(),
+            Some("orko")
+        );
+    }
+
+    #[test]
+    fn test_merge_moves_history_and_keeps_the_older_uuid() {
+        let store = store_with(&[
+            ("orko", "u-old", "2026-01-01T00:00:00Z"),
+            ("orko-2", "u-new", "2026-06-01T00:00:00Z"),
+            ("nfs", "u-nfs", "2026-01-01T00:00:00Z"),
+        ]);
+        store
+            .record_machine_identity("u-old", "orko", "orko.lan", None)
+            .unwrap();
+        for (id, at) in [
+            ("orko", "2026-02-01"),
+            ("orko", "2026-03-01"),
+            ("orko-2", "2026-07-01"),
+        ] {
+            store
+                .execute_simple(&format!(
+                    "INSERT INTO sys_fallback_samples (machine_id, collected_at) \
+                     VALUES ('{id}', '{at}')"
+                ))
+                .unwrap();
+        }
+        // Keyed per machine: the kept id's row wins
+        for id in ["orko", "orko-2"] {
+            store
+                .execute_simple(&format!(
+                    "INSERT INTO collector_status (machine_id, collector_name, status) \
+                     VALUES ('{id}', 'sysmoni', '{id}')"
+                ))
+                .unwrap();
+        }
+        store
+            .add_machine_dependency("orko", "nfs", "storage")
+            .unwrap();
+        store
+            .add_machine_dependency("orko-2", "orko", "network")
+            .unwrap();
+
+        let merged = store
+            .merge_machines("orko", "orko-2", &ActorContext::system("test"))
+            .unwrap();
+        assert_eq!(merged.machine_uuid, "u-old");
+        assert_eq!(merged.moved["sys_fallback_samples"], 2);
+        assert_eq!(merged.dropped["collector_status"], 1);
+
+        assert_eq!(
+            count(
+                &store,
+                "SELECT COUNT(*) AS n FROM sys_fallback_samples WHERE machine_id = 'orko-2'"
+            ),
+            3
+        );
+        assert_eq!(
+            count(
+                &store,
+                "SELECT COUNT(*) AS n FROM machines WHERE machine_id = 'orko'"
+            ),
+            0
+        );
+        let status = store
+            .query_json("SELECT status FROM collector_status WHERE machine_id = 'orko-2'")
+            .unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0]["status"], "orko-2");
+        let deps = store.machine_dependencies().unwrap();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(
+            (deps[0].machine_id.as_str(), deps[0].depends_on.as_str()),
+            ("orko-2", "nfs")
+        );
+
+        assert_eq!(
+            store.machine_uuid("orko-2").unwrap().as_deref(),
+            Some("u-old")
+        );
+        let ids: Vec<_> = store
+            .machine_identities("u-old")
+            .unwrap()
+            .into_iter()
+            .map(|identity| identity.machine_id)
+            .collect();
+        assert!(ids.contains(&"orko".to_string()), "{ids:?}");
+        assert!(store.machine_uuid_conflicts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_rejects_unknown_and_same_ids() {
+        let store = store_with(&[("orko", "u-1", "2026-01-01T00:00:00Z")]);
+        let actor = ActorContext::system("test");
+        assert!(store.merge_machines("orko", "orko", &actor).is_err());
+        let err = store.merge_machines("ghost", "orko", &actor).unwrap_err();
+        assert!(err.to_string().contains("ghost"), "{err}");
+        assert_eq!(store.machine_uuid("orko").unwrap().as_deref(), Some("u-1"));
+    }
+
+    #[test]
+    fn test_uuid_seen_under_another_id_is_a_conflict() {
+        let store = store_with(&[
+            ("orko", "u-1", "2026-01-01T00:00:00Z"),
+            ("orko-new", "u-2", "2026-06-01T00:00:00Z"),
+        ]);
+        store
+            .record_machine_identity("u-1", "orko", "orko.lan", None)
+            .unwrap();
+        assert!(store.machine_uuid_conflicts().unwrap().is_empty());
+
+        // orko-new's probe found orko's marker
+        store
+            .record_machine_identity("u-1", "orko-new", "orko.lan", None)
+            .unwrap();
+        let conflicts = store.machine_uuid_conflicts().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].registered_as, "orko");
+        assert_eq!(conflicts[0].seen_as, "orko-new");
+
+        store
+            .merge_machines("orko", "orko-new", &ActorContext::system("test"))
+            .unwrap();
+        assert!(store.machine_uuid_conflicts().unwrap().is_empty());
+        assert_eq!(
+            store.machine_uuid("orko-new").unwrap().as_deref(),
+            Some("u-1")
+        );
+    }
+}
@@ -67,7 +67,8 @@
 
 #![allow(clippy::doc_markdown)]
 
-use crate::{StoreConnectionGuard, StoreError};
+use crate::StoreError;
+use crate::backend::{BackendKind, StoreBackend};
 use tracing::{debug, info};
 
 /// Migration definition
@@ -219,16 +220,289 @@ const MIGRATIONS: &[Migration] = &[
         name: "widen_byte_columns_to_bigint",
         sql: include_str!("migrations/028_widen_byte_columns_to_bigint.sql"),
     },
+    Migration {
+        version: 29,
+        name: "playbook_draft_revisions",
+        sql: include_str!("migrations/029_playbook_draft_revisions.sql"),
+    },
+    Migration {
+        version: 30,
+        name: "daemon_resource_state",
+        sql: include_str!("migrations/030_daemon_resource_state.sql"),
+    },
+    Migration {
+        version: 31,
+        name: "alert_delivery_digest",
+        sql: include_str!("migrations/031_alert_delivery_digest.sql"),
+    },
+    Migration {
+        version: 32,
+        name: "machine_services",
+        sql: include_str!("migrations/032_machine_services.sql"),
+    },
+    Migration {
+        version: 33,
+        name: "knowledge_relations",
+        sql: include_str!("migrations/033_knowledge_relations.sql"),
+    },
+    Migration {
+        version: 34,
+        name: "account_status",
+        sql: include_str!("migrations/034_account_status.sql"),
+    },
+    Migration {
+        version: 35,
+        name: "size_aware_retention",
+        sql: include_str!("migrations/035_size_aware_retention.sql"),
+    },
+    Migration {
+        version: 36,
+        name: "drift_event_fields",
+        sql: include_str!("migrations/036_drift_event_fields.sql"),
+    },
+    Migration {
+        version: 37,
+        name: "knowledge_embeddings",
+        sql: include_str!("migrations/037_knowledge_embeddings.sql"),
+    },
+    Migration {
+        version: 38,
+        name: "telemetry_export_state",
+        sql: include_str!("migrations/038_telemetry_export_state.sql"),
+    },
+    Migration {
+        version: 39,
+        name: "replication",
+        sql: include_str!("migrations/039_replication.sql"),
+    },
+    Migration {
+        version: 40,
+        name: "retention_scopes",
+        sql: include_str!("migrations/040_retention_scopes.sql"),
+    },
+    Migration {
+        version: 41,
+        name: "guardian_run_approval",
+        sql: include_str!("migrations/041_guardian_run_approval.sql"),
+    },
+    Migration {
+        version: 42,
+        name: "ingest_row_hashes",
+        sql: include_str!("migrations/042_ingest_row_hashes.sql"),
+    },
+    Migration {
+        version: 43,
+        name: "tool_version_history",
+        sql: include_str!("migrations/043_tool_version_history.sql"),
+    },
+    Migration {
+        version: 44,
+        name: "daemon_watch_subscribers",
+        sql: include_str!("migrations/044_daemon_watch_subscribers.sql"),
+    },
+    Migration {
+        version: 45,
+        name: "clock_skew",
+        sql: include_str!("migrations/045_clock_skew.sql"),
+    },
+    Migration {
+        version: 46,
+        name: "resolution_provenance",
+        sql: include_str!("migrations/046_resolution_provenance.sql"),
+    },
+    Migration {
+        version: 47,
+        name: "query_log",
+        sql: include_str!("migrations/047_query_log.sql"),
+    },
+    Migration {
+        version: 48,
+        name: "machine_maintenance",
+        sql: include_str!("migrations/048_machine_maintenance.sql"),
+    },
+    Migration {
+        version: 49,
+        name: "collector_error_cause",
+        sql: include_str!("migrations/049_collector_error_cause.sql"),
+    },
+    Migration {
+        version: 50,
+        name: "session_outcomes",
+        sql: include_str!("migrations/050_session_outcomes.sql"),
+    },
+    Migration {
+        version: 51,
+        name: "machine_config_state",
+        sql: include_str!("migrations/051_machine_config_state.sql"),
+    },
+    Migration {
+        version: 52,
+        name: "table_stats",
+        sql: include_str!("migrations/052_table_stats.sql"),
+    },
+    Migration {
+        version: 53,
+        name: "playbook_library",
+        sql: include_str!("migrations/053_playbook_library.sql"),
+    },
+    Migration {
+        version: 54,
+        name: "leases",
+        sql: include_str!("migrations/054_leases.sql"),
+    },
+    Migration {
+        version: 55,
+        name: "alert_escalation",
+        sql: include_str!("migrations/055_alert_escalation.sql"),
+    },
+    Migration {
+        version: 56,
+        name: "alert_correlation",
+        sql: include_str!("migrations/056_alert_correlation.sql"),
+    },
+    Migration {
+        version: 57,
+        name: "config_history",
+        sql: include_str!("migrations/057_config_history.sql"),
+    },
+    Migration {
+        version: 58,
+        name: "machine_dependencies",
+        sql: include_str!("migrations/058_machine_dependencies.sql"),
+    },
+    Migration {
+        version: 59,
+        name: "fallback_cpu_pct",
+        sql: include_str!("migrations/059_fallback_cpu_pct.sql"),
+    },
+    Migration {
+        version: 60,
+        name: "fleet_applies",
+        sql: include_str!("migrations/060_fleet_applies.sql"),
+    },
+    Migration {
+        version: 61,
+        name: "http_checks",
+        sql: include_str!("migrations/061_http_checks.sql"),
+    },
+    Migration {
+        version: 62,
+        name: "knowledge_review",
+        sql: include_str!("migrations/062_knowledge_review.sql"),
+    },
+    Migration {
+        version: 63,
+        name: "artifact_pointers",
+        sql: include_str!("migrations/063_artifact_pointers.sql"),
+    },
+    Migration {
+        version: 64,
+        name: "ingest_batch_outcomes",
+        sql: include_str!("migrations/064_ingest_batch_outcomes.sql"),
+    },
+    Migration {
+        version: 65,
+        name: "request_signatures",
+        sql: include_str!("migrations/065_request_signatures.sql"),
+    },
+    Migration {
+        version: 66,
+        name: "audit_actor_via",
+        sql: include_str!("migrations/066_audit_actor_via.sql"),
+    },
+    Migration {
+        version: 67,
+        name: "query_proposals",
+        sql: include_str!("migrations/067_query_proposals.sql"),
+    },
+    Migration {
+        version: 68,
+        name: "health_scoring_version",
+        sql: include_str!("migrations/068_health_scoring_version.sql"),
+    },
+    Migration {
+        version: 69,
+        name: "agents_inventory",
+        sql: include_str!("migrations/069_agents_inventory.sql"),
+    },
+    Migration {
+        version: 70,
+        name: "export_bundles",
+        sql: include_str!("migrations/070_export_bundles.sql"),
+    },
+    Migration {
+        version: 71,
+        name: "oversized_writes",
+        sql: include_str!("migrations/071_oversized_writes.sql"),
+    },
+    Migration {
+        version: 72,
+        name: "legal_holds",
+        sql: include_str!("migrations/072_legal_holds.sql"),
+    },
+    Migration {
+        version: 73,
+        name: "guardian_sandbox_runs",
+        sql: include_str!("migrations/073_guardian_sandbox_runs.sql"),
+    },
+    Migration {
+        version: 74,
+        name: "disk_forecasts",
+        sql: include_str!("migrations/074_disk_forecasts.sql"),
+    },
+    Migration {
+        version: 75,
+        name: "alert_resolution",
+        sql: include_str!("migrations/075_alert_resolution.sql"),
+    },
+    Migration {
+        version: 76,
+        name: "daemon_heartbeats",
+        sql: include_str!("migrations/076_daemon_heartbeats.sql"),
+    },
+    Migration {
+        version: 77,
+        name: "operation_reports",
+        sql: include_str!("migrations/077_operation_reports.sql"),
+    },
+    Migration {
+        version: 78,
+        name: "annotations",
+        sql: include_str!("migrations/078_annotations.sql"),
+    },
+    Migration {
+        version: 79,
+        name: "machine_identity",
+        sql: include_str!("migrations/079_machine_identity.sql"),
+    },
+    Migration {
+        version: 80,
+        name: "data_corrections",
+        sql: include_str!("migrations/080_data_corrections.sql"),
+    },
 ];
 
+/// Version of the newest migration this build knows about
+#[must_use]
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Migrations that only make sense on `DuckDB`. They are still recorded as
+/// applied on other backends so the version sequence stays contiguous.
+///
+/// 028 widens INT32 byte columns; `SQLite` integers are already 64-bit and it
+/// has no `ALTER COLUMN ... TYPE`.
+const DUCKDB_ONLY: &[u32] = &[28];
+
 /// Run all pending migrations
 ///
 /// # Errors
 ///
 /// Returns [`StoreError`] if migration bookkeeping or any migration SQL fails.
-pub(crate) fn run_all(conn: &StoreConnectionGuard<'_>) -> Result<(), StoreError> {
+pub(crate) fn run_all(backend: &dyn StoreBackend) -> Result<(), StoreError> {
     // Create migrations table if not exists
-    conn.execute_batch(
+    backend.execute_batch(
         r"
         CREATE TABLE IF NOT EXISTS _migrations (
             version INTEGER PRIMARY KEY,
@@ -239,16 +513,24 @@ pub(crate) fn run_all(conn: &StoreConnectionGuard<'_>) -> Result<(), StoreError>
     )?;
 
     // Get current version
-    let current_version: i64 = conn
-        .query_row(
-            "SELECT COALESCE(MAX(version), 0) FROM _migrations",
-            [],
-            |row| row.get(0),
-        )
+    let current_version: i64 = backend
+        .query_json("SELECT COALESCE(MAX(version), 0) AS version FROM _migrations")
+        .ok()
+        .and_then(|rows| rows.first().and_then(|row| row["version"].as_i64()))
         .unwrap_or(0);
 
     info!(current_version = current_version, "Checking migrations");
 
+    // A store written by a newer vc has tables this build does not know
+    // about; refuse it rather than write to it half-understood
+    let supported = latest_version();
+    if current_version > i64::from(supported) {
+        return Err(StoreError::SchemaTooNew {
+            found: u32::try_from(current_version).unwrap_or(u32::MAX),
+            supported,
+        });
+    }
+
     // Apply pending migrations
     for migration in MIGRATIONS {
         if i64::from(migration.version) > current_version {
@@ -258,16 +540,18 @@ pub(crate) fn run_all(conn: &StoreConnectionGuard<'_>) -> Result<(), StoreError>
                 "Applying migration"
             );
 
-            conn.execute_batch(migration.sql).map_err(|e| {
-                StoreError::MigrationError(format!(
-                    "Failed to apply migration {}: {}",
-                    migration.name, e
-                ))
-            })?;
+            if backend.kind() == BackendKind::DuckDb || !DUCKDB_ONLY.contains(&migration.version) {
+                backend.execute_batch(migration.sql).map_err(|e| {
+                    StoreError::MigrationError(format!(
+                        "Failed to apply migration {}: {}",
+                        migration.name, e
+                    ))
+                })?;
+            }
 
-            conn.execute(
+            backend.execute(
                 "INSERT INTO _migrations (version, name) VALUES (?, ?)",
-                [&migration.version.to_string(), &migration.name.to_string()],
+                &[&migration.version.to_string(), migration.name],
             )?;
 
             debug!(version = migration.version, "Migration applied");
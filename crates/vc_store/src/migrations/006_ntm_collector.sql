@@ -7,7 +7,7 @@ CREATE TABLE IF NOT EXISTS ntm_sessions_snapshot (
     machine_id TEXT NOT NULL,
     collected_at TEXT NOT NULL,
     session_name TEXT NOT NULL,
-    exists INTEGER DEFAULT 1,
+    "exists" INTEGER DEFAULT 1,
     attached INTEGER DEFAULT 0,
     windows INTEGER DEFAULT 0,
     panes INTEGER DEFAULT 0,
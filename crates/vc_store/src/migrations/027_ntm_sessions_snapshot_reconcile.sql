@@ -15,7 +15,7 @@
 -- that backfills existing rows. The pre-existing 001 columns (work_dir,
 -- git_branch, agent_counts_json, panes_json) are left in place so historical
 -- rows remain readable and any out-of-tree consumer is not broken.
-ALTER TABLE ntm_sessions_snapshot ADD COLUMN exists INTEGER DEFAULT 1;
+ALTER TABLE ntm_sessions_snapshot ADD COLUMN "exists" INTEGER DEFAULT 1;
 ALTER TABLE ntm_sessions_snapshot ADD COLUMN attached INTEGER DEFAULT 0;
 ALTER TABLE ntm_sessions_snapshot ADD COLUMN windows INTEGER DEFAULT 0;
 ALTER TABLE ntm_sessions_snapshot ADD COLUMN panes INTEGER DEFAULT 0;
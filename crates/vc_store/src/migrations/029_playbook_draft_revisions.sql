@@ -0,0 +1,22 @@
+-- Playbook draft revisions: operator edits to auto-generated drafts
+--
+-- Revision 1 is always the generated steps. Each edit appends a new revision
+-- and moves `current_revision` forward; approval pins `approved_revision` so
+-- activation uses exactly the steps the approver saw.
+CREATE TABLE IF NOT EXISTS playbook_draft_revisions (
+    draft_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    steps_json TEXT NOT NULL,
+    editor TEXT NOT NULL,
+    diff_summary TEXT,
+    validation_json TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (draft_id, revision)
+);
+
+ALTER TABLE playbook_drafts ADD COLUMN current_revision INTEGER DEFAULT 1;
+ALTER TABLE playbook_drafts ADD COLUMN approved_revision INTEGER;
+
+-- Backfill revision 1 for drafts generated before revisions existed
+INSERT INTO playbook_draft_revisions (draft_id, revision, steps_json, editor, created_at)
+SELECT draft_id, 1, steps_json, 'generator', created_at FROM playbook_drafts;
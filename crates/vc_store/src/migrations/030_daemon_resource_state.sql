@@ -0,0 +1,16 @@
+-- Daemon resource guard state
+--
+-- Single-row table (id = 1) holding the most recent self-limit check made by
+-- the daemon. Other processes (`vc ingest`, `vc daemon check`, robot health)
+-- read it to learn whether the daemon is shedding load.
+CREATE TABLE IF NOT EXISTS daemon_resource_state (
+    id INTEGER PRIMARY KEY,
+    checked_at TEXT NOT NULL,
+    level TEXT NOT NULL,
+    disk_pressure BOOLEAN NOT NULL DEFAULT FALSE,
+    rss_mb BIGINT,
+    free_disk_mb BIGINT,
+    max_rss_mb BIGINT,
+    min_free_disk_mb BIGINT,
+    reason TEXT
+);
@@ -0,0 +1,6 @@
+-- Migration 031: Digest deliveries in the alert delivery log
+-- Created: 2026-10-16
+-- Purpose: A digest delivery covers many alerts in one message; record how many.
+-- Direct deliveries keep alert_count = 1.
+
+ALTER TABLE alert_delivery_log ADD COLUMN alert_count INTEGER DEFAULT 1;
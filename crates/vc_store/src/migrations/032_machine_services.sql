@@ -0,0 +1,20 @@
+-- Migration 032: Service supervision status per machine
+-- Created: 2026-10-16
+-- Purpose: Latest result of each configured service check ([[services]] in
+-- config), written on `vc machines probe` and on every collection tick.
+-- A machine's rows are replaced wholesale on each check so services removed
+-- from config stop being reported.
+
+CREATE TABLE IF NOT EXISTS machine_services (
+    machine_id TEXT NOT NULL,
+    service_name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    required INTEGER NOT NULL DEFAULT 1,
+    running INTEGER NOT NULL DEFAULT 0,
+    pid BIGINT,
+    uptime_secs BIGINT,
+    detail TEXT,
+    checked_at TEXT NOT NULL,
+    PRIMARY KEY (machine_id, service_name)
+);
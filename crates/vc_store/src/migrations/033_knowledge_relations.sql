@@ -0,0 +1,23 @@
+-- Migration 033: Knowledge entry relations and alert-type tags
+-- Created: 2026-10-16
+-- Purpose: Tie knowledge entries together (a solution derived from a
+-- pattern, a prompt superseding an older one) and index entries by the alert
+-- types they address, so alert handling can look knowledge up directly
+-- instead of through keyword search.
+
+CREATE TABLE IF NOT EXISTS knowledge_relations (
+    source_id INTEGER NOT NULL,
+    target_id INTEGER NOT NULL,
+    relation TEXT NOT NULL CHECK(relation IN ('relates_to', 'supersedes', 'derived_from')),
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (source_id, target_id, relation)
+);
+
+CREATE TABLE IF NOT EXISTS knowledge_alert_types (
+    entry_id INTEGER NOT NULL,
+    alert_type TEXT NOT NULL,
+    PRIMARY KEY (entry_id, alert_type)
+);
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_relations_target ON knowledge_relations(target_id);
+CREATE INDEX IF NOT EXISTS idx_knowledge_alert_type ON knowledge_alert_types(alert_type);
@@ -0,0 +1,26 @@
+-- Migration 034: Account quota and rate-limit state from provider CLIs
+-- Created: 2026-10-16
+-- Purpose: History of remaining quota, reset time and active identity per
+-- provider account, written by the `accounts` collector from the commands
+-- configured under [accounts]. Every run keeps the raw command output next
+-- to the parsed fields; when nothing could be extracted a single row with
+-- `parse_error` set is written instead, so format changes in a provider CLI
+-- show up as data rather than silently empty tables.
+
+CREATE TABLE IF NOT EXISTS account_status (
+    machine_id TEXT NOT NULL,
+    collected_at TEXT NOT NULL,
+    source_command TEXT NOT NULL,
+    provider TEXT,
+    account_id TEXT,
+    remaining_pct REAL,
+    used_pct REAL,
+    resets_at TEXT,
+    is_active INTEGER,
+    parse_error TEXT,
+    raw_output TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_status_ts ON account_status(collected_at);
+CREATE INDEX IF NOT EXISTS idx_account_status_account
+    ON account_status(provider, account_id, collected_at);
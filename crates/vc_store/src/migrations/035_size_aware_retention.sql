@@ -0,0 +1,14 @@
+-- Migration 035: Size-aware retention limits
+-- Created: 2026-10-16
+-- Purpose: Row-count retention lets one huge transcript live as long as a
+-- tiny row. max_row_bytes caps the text written per row (oversized values are
+-- cut to keep_head_bytes + keep_tail_bytes around a marker at write time);
+-- max_total_bytes caps a table's text volume, vacuum deleting oldest rows
+-- until it fits. NULL leaves the respective limit off.
+
+ALTER TABLE retention_policies ADD COLUMN max_total_bytes BIGINT;
+ALTER TABLE retention_policies ADD COLUMN max_row_bytes BIGINT;
+ALTER TABLE retention_policies ADD COLUMN keep_head_bytes BIGINT DEFAULT 65536;
+ALTER TABLE retention_policies ADD COLUMN keep_tail_bytes BIGINT DEFAULT 65536;
+
+ALTER TABLE retention_log ADD COLUMN bytes_reclaimed BIGINT DEFAULT 0;
@@ -0,0 +1,16 @@
+-- Migration 036: Structured before/after values on drift events
+-- Created: 2026-10-16
+-- Purpose: Record what drifted as data rather than prose: previous and new
+-- value (TEXT so versions like "6.1.0" fit as well as numbers), the numeric
+-- delta where there is one, the baseline the comparison ran against and the
+-- detection method. The human-readable description is derived from these on
+-- read. Rows written before this migration keep NULLs here.
+
+ALTER TABLE drift_events ADD COLUMN previous_value TEXT;
+ALTER TABLE drift_events ADD COLUMN new_value TEXT;
+ALTER TABLE drift_events ADD COLUMN delta REAL;
+ALTER TABLE drift_events ADD COLUMN baseline_ref TEXT;
+ALTER TABLE drift_events ADD COLUMN detection_method TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_drift_events_metric
+    ON drift_events(metric);
@@ -0,0 +1,18 @@
+-- Migration 037: Knowledge entry embeddings
+-- Created: 2026-10-16
+-- Purpose: Store one embedding vector per knowledge entry for semantic
+-- search. Only written when vc_knowledge is built with the `embeddings`
+-- feature and an embedding backend is configured. The model name is kept so
+-- vectors from a different model are treated as missing and re-embedded by
+-- `vc knowledge reindex`.
+
+CREATE TABLE IF NOT EXISTS knowledge_embeddings (
+    entry_id INTEGER PRIMARY KEY,
+    model TEXT NOT NULL,
+    dims INTEGER NOT NULL,
+    vector TEXT NOT NULL,              -- JSON array of floats
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_knowledge_embeddings_model
+    ON knowledge_embeddings(model);
@@ -0,0 +1,19 @@
+-- Migration 038: OpenTelemetry export state
+-- Created: 2026-10-16
+-- Purpose: Track, per exported event stream (alert firings, alert
+-- resolutions, incident lifecycle, collector failures), the position of the
+-- last event the OTLP exporter delivered. The cursor only advances after the
+-- collector accepts a batch, so a daemon restart resumes where it stopped.
+-- Id-ordered streams use last_id; time-ordered streams use last_ts with
+-- last_id or last_key breaking ties.
+
+CREATE TABLE IF NOT EXISTS telemetry_export_state (
+    stream TEXT PRIMARY KEY,
+    last_id BIGINT NOT NULL DEFAULT 0,
+    last_ts TEXT,
+    last_key TEXT,
+    exported_count BIGINT NOT NULL DEFAULT 0,
+    last_success_at TEXT,
+    last_attempt_at TEXT,
+    last_error TEXT
+);
@@ -0,0 +1,26 @@
+-- Migration 039: Warm standby replication
+-- Created: 2026-10-16
+-- Purpose: Track how far each table has been replicated. On a primary the
+-- 'shipped' rows hold the last timestamp the standby acknowledged; on a
+-- standby the 'applied' rows hold the last timestamp applied, plus the
+-- primary's head and backlog as reported with the latest batch. Promotions of
+-- a standby to primary are kept so the role survives restarts.
+
+CREATE TABLE IF NOT EXISTS replication_watermarks (
+    side TEXT NOT NULL,
+    table_name TEXT NOT NULL,
+    ts_column TEXT NOT NULL,
+    watermark TEXT,
+    source_head TEXT,
+    rows_pending BIGINT NOT NULL DEFAULT 0,
+    rows_total BIGINT NOT NULL DEFAULT 0,
+    updated_at TEXT,
+    last_error TEXT,
+    PRIMARY KEY (side, table_name)
+);
+
+CREATE TABLE IF NOT EXISTS replication_promotions (
+    id INTEGER PRIMARY KEY,
+    promoted_at TEXT NOT NULL,
+    promoted_by TEXT NOT NULL
+);
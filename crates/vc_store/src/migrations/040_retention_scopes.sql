@@ -0,0 +1,9 @@
+-- Migration 040: Machine-scoped retention policies
+-- Created: 2026-10-16
+-- Purpose: Let a retention policy cover only some machines, either every
+-- machine carrying a tag ('tag:builder') or an explicit list
+-- ('machines:orko,bender'). NULL keeps the policy table-wide. Vacuum applies
+-- the most specific matching policy per machine_id, falling back to the
+-- table-wide one.
+
+ALTER TABLE retention_policies ADD COLUMN scope TEXT;
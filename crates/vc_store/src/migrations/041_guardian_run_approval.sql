@@ -0,0 +1,8 @@
+-- Migration 041: Guardian run approvals
+-- Created: 2026-10-16
+-- Purpose: Record who approved a guardian run that was waiting in
+-- 'pending_approval', mirroring the approved_by/approved_at columns that
+-- playbook drafts already carry.
+
+ALTER TABLE guardian_runs ADD COLUMN approved_by TEXT;
+ALTER TABLE guardian_runs ADD COLUMN approved_at TEXT;
@@ -0,0 +1,16 @@
+-- Migration 042: Content-level ingest dedup
+-- Created: 2026-10-16
+-- Purpose: Remember a hash of every row ingested from vc-node bundles so a
+-- resend under a fresh bundle ID is skipped row by row. Only hashes inside
+-- the configured lookback are consulted; `vc vacuum` prunes older ones.
+
+CREATE TABLE IF NOT EXISTS ingest_row_hashes (
+    table_name TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    machine_id TEXT NOT NULL,
+    seen_at TEXT NOT NULL,
+    PRIMARY KEY (table_name, content_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingest_row_hashes_seen_at
+    ON ingest_row_hashes(seen_at);
@@ -0,0 +1,21 @@
+-- Migration 043: Tool and collector version history
+-- Created: 2026-10-16
+-- Purpose: Keep every version a tool or agent has reported per machine, not
+-- just the latest one in machine_tools. A row spans the period a version was
+-- observed: first_seen_at is when it appeared, last_seen_at is bumped on each
+-- repeat observation, and previous_version records what it replaced. `source`
+-- says where the version came from (`probe`, `version_file`, `bundle`).
+
+CREATE TABLE IF NOT EXISTS tool_version_history (
+    machine_id TEXT NOT NULL,
+    tool_name TEXT NOT NULL,
+    tool_version TEXT NOT NULL,
+    previous_version TEXT,
+    source TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    PRIMARY KEY (machine_id, tool_name, first_seen_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tool_version_history_tool
+    ON tool_version_history(tool_name, tool_version);
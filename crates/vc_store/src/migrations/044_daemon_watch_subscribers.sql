@@ -0,0 +1,7 @@
+-- Migration 044: Watch socket subscribers in daemon state
+-- Created: 2026-10-16
+-- Purpose: Record how many local agents are subscribed to the daemon's watch
+-- socket alongside its resource state, so `vc daemon check` shows it. NULL
+-- when the daemon is not serving a watch socket.
+
+ALTER TABLE daemon_resource_state ADD COLUMN watch_subscribers BIGINT;
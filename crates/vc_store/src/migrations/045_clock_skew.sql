@@ -0,0 +1,28 @@
+-- Migration 045: Clock skew history
+-- Created: 2026-10-16
+-- Purpose: Keep every clock skew measurement taken for a machine. skew_ms is
+-- the remote clock minus hub time (positive = remote ahead). rtt_ms is the
+-- round trip of the probe command the measurement was bracketed by; it is
+-- NULL for measurements taken on ingest, where the remote's send time is
+-- only compared against arrival. `source` is `probe` or `ingest`.
+--
+-- Rows ingested with skew normalization enabled carry the offset that was
+-- subtracted from their timestamps in clock_skew_ms, so the original remote
+-- time stays recoverable.
+
+CREATE TABLE IF NOT EXISTS machine_clock_skew (
+    machine_id TEXT NOT NULL,
+    measured_at TEXT NOT NULL,
+    skew_ms BIGINT NOT NULL,
+    rtt_ms BIGINT,
+    source TEXT NOT NULL,
+    PRIMARY KEY (machine_id, measured_at, source)
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_clock_skew_measured
+    ON machine_clock_skew(measured_at);
+
+ALTER TABLE sys_samples ADD COLUMN clock_skew_ms BIGINT;
+ALTER TABLE ntm_sessions_snapshot ADD COLUMN clock_skew_ms BIGINT;
+ALTER TABLE afsc_status_snapshot ADD COLUMN clock_skew_ms BIGINT;
+ALTER TABLE cloud_bench_raw ADD COLUMN clock_skew_ms BIGINT;
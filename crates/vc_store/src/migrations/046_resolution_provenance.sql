@@ -0,0 +1,8 @@
+-- Migration 046: Resolution provenance
+-- Created: 2026-10-16
+-- Purpose: Record where an assisted capture took its actions from (agent
+-- session or shell history, the machine, the time window and how many
+-- commands were extracted, kept and redacted) as a JSON document. NULL for
+-- resolutions whose actions were entered by hand.
+
+ALTER TABLE resolutions ADD COLUMN provenance TEXT;
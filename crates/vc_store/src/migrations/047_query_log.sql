@@ -0,0 +1,18 @@
+-- Migration 047: Query log
+-- Created: 2026-10-16
+-- Purpose: Ring buffer of the queries the store ran, for `vc db slow-queries`.
+-- `fingerprint` is the SQL with literals replaced by `?`, so one row shape
+-- aggregates across parameter values. `caller` is the frontend that ran it
+-- (cli, web, mcp or daemon). row_count is NULL when it is not known (the
+-- query failed, or its rows were consumed by typed store code). Flushes trim
+-- the table to the newest `query_log.max_rows` rows.
+
+CREATE TABLE IF NOT EXISTS query_log (
+    recorded_at TEXT NOT NULL,
+    caller TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    duration_us BIGINT NOT NULL,
+    row_count BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_query_log_recorded ON query_log(recorded_at);
@@ -0,0 +1,21 @@
+-- Migration 048: Machine maintenance windows
+-- Created: 2026-10-16
+-- Purpose: Record when a machine was put into maintenance, why, until when
+-- and by whom, and when and by whom it came out (`expiry` when its until_at
+-- passed). A window is open while ended_at is NULL; the machine's
+-- machines.status reads 'maintenance' for as long as it is, which is what
+-- collectors, health scoring and the fleet aggregate key off.
+
+CREATE TABLE IF NOT EXISTS machine_maintenance (
+    machine_id TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    reason TEXT,
+    until_at TEXT,
+    started_by TEXT NOT NULL,
+    ended_at TEXT,
+    ended_by TEXT,
+    PRIMARY KEY (machine_id, started_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_maintenance_open
+    ON machine_maintenance(ended_at);
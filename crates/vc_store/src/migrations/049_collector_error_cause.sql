@@ -0,0 +1,12 @@
+-- Migration 049: Collector failure causes
+-- Created: 2026-10-16
+-- Purpose: Store the classified cause of each failed collector run
+-- (connect_refused, timeout, auth_failed, tool_missing, parse_error,
+-- remote_error, unknown) next to the raw message in error_class, so
+-- failures can be counted by cause. Runs recorded before this migration
+-- have no cause.
+
+ALTER TABLE collector_health ADD COLUMN error_cause TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_collector_health_cause
+    ON collector_health(error_cause);
@@ -0,0 +1,14 @@
+-- Migration 050: Session outcomes
+-- Created: 2026-10-16
+-- Purpose: Store the classified outcome of each finished agent session
+-- (success, partial, failed, abandoned) with the classifier's confidence
+-- and when it ran, so success rates can be aggregated per agent type and
+-- repo. Sessions stay unclassified until the analyzer reaches them;
+-- `vc sessions classify --backfill` reclassifies the rest.
+
+ALTER TABLE agent_sessions ADD COLUMN outcome TEXT;
+ALTER TABLE agent_sessions ADD COLUMN outcome_confidence REAL;
+ALTER TABLE agent_sessions ADD COLUMN outcome_classified_at TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_agent_sessions_outcome
+    ON agent_sessions(outcome);
@@ -0,0 +1,11 @@
+-- Migration 051: Machine config state
+-- Created: 2026-10-16
+-- Purpose: Let config reloads merge into the machine registry instead of
+-- overwriting it. config_snapshot holds the runtime-owned values vc.toml
+-- last set for a machine (enabled, tags), so a reload only changes them
+-- when the config itself changed. config_absent marks machines that came
+-- from the config and have since been removed from it; they are kept, not
+-- deleted.
+
+ALTER TABLE machines ADD COLUMN config_snapshot TEXT;
+ALTER TABLE machines ADD COLUMN config_absent INTEGER DEFAULT 0;
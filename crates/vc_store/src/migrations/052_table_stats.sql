@@ -0,0 +1,23 @@
+-- Migration 052: Table statistics
+-- Created: 2026-10-16
+-- Purpose: Decide when a table needs ANALYZE. table_stats counts rows
+-- written to each table since its last ANALYZE (buffered by each store and
+-- added here on flush) and what the table held when it was analyzed.
+-- query_plans keeps a hash of the EXPLAIN output of logged SELECT shapes, so
+-- `vc db slow-queries` can show which plans an ANALYZE changed.
+
+CREATE TABLE IF NOT EXISTS table_stats (
+    table_name TEXT PRIMARY KEY,
+    changes_since_analyze BIGINT NOT NULL DEFAULT 0,
+    rows_at_analyze BIGINT,
+    last_analyzed_at TEXT,
+    last_analyze_ms DOUBLE
+);
+
+CREATE TABLE IF NOT EXISTS query_plans (
+    fingerprint TEXT PRIMARY KEY,
+    plan_hash TEXT NOT NULL,
+    captured_at TEXT NOT NULL,
+    changed_at TEXT,
+    changed_by_table TEXT
+);
@@ -0,0 +1,15 @@
+-- Migration 053: Playbook library
+-- Created: 2026-10-16
+-- Purpose: Playbooks imported from library files (`vc guardian import`).
+-- rollback_steps, required_tools and parameters carry the parts of the file
+-- format live playbooks did not have. source_hash is the SHA-256 of the file
+-- as imported and content_hash that of the playbook as stored, so
+-- `vc guardian export` can tell when the live version diverged from the file.
+
+ALTER TABLE guardian_playbooks ADD COLUMN rollback_steps TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN required_tools TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN parameters TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN source_path TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN source_hash TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN content_hash TEXT;
+ALTER TABLE guardian_playbooks ADD COLUMN imported_at TEXT;
@@ -0,0 +1,16 @@
+-- Migration 054: Advisory leases
+-- Created: 2026-10-16
+-- Purpose: Keep one writer per role. A daemon holds the 'daemon' lease while
+-- it runs its poll loop and renews the heartbeat every cycle; a lease whose
+-- expires_at has passed belongs to nobody. Timestamps are fixed-width
+-- RFC 3339 UTC, so comparing them as text compares them in time.
+
+CREATE TABLE IF NOT EXISTS leases (
+    name TEXT PRIMARY KEY,
+    holder_id TEXT NOT NULL,
+    hostname TEXT,
+    pid BIGINT,
+    acquired_at TEXT NOT NULL,
+    heartbeat_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
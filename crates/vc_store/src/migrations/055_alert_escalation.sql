@@ -0,0 +1,11 @@
+-- Migration 055: Alert escalation state
+-- Created: 2026-10-16
+-- Purpose: Escalation policies ([[alerts.escalation]]) act on an alert group
+-- (one rule on one machine) that stays open and unacknowledged. The group's
+-- level, the policy that set it and when it last rose are stamped on every
+-- open occurrence in the group, so a new occurrence joins at the group's
+-- level instead of escalating again from zero.
+
+ALTER TABLE alert_history ADD COLUMN escalation_level INTEGER DEFAULT 0;
+ALTER TABLE alert_history ADD COLUMN escalated_at TEXT;
+ALTER TABLE alert_history ADD COLUMN escalation_policy TEXT;
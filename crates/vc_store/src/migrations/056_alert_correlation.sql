@@ -0,0 +1,9 @@
+-- Migration 056: Alert correlation
+-- Created: 2026-10-16
+-- Purpose: Open alerts on one machine that fire close together are grouped
+-- under a correlation id, with the probable root cause flagged. The daemon
+-- recomputes the groups every cycle, so a later, more severe alert can take
+-- over as root; resolved alerts keep the group they last belonged to.
+
+ALTER TABLE alert_history ADD COLUMN correlation_id TEXT;
+ALTER TABLE alert_history ADD COLUMN correlation_root INTEGER DEFAULT 0;
@@ -0,0 +1,18 @@
+-- Migration 057: Config change history
+-- Created: 2026-10-16
+-- Purpose: Keep a snapshot of the config file every time one with new
+-- content is loaded, so edits to vc.toml leave a record that can be listed,
+-- diffed and rolled back. The same content may appear more than once, e.g.
+-- after a rollback; the newest rows are kept up to global.config_history_keep.
+
+CREATE TABLE IF NOT EXISTS config_history (
+    content_hash TEXT NOT NULL,
+    config_path TEXT NOT NULL,
+    content TEXT NOT NULL,
+    file_mtime TEXT,
+    loaded_by TEXT NOT NULL,
+    recorded_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_config_history_recorded
+    ON config_history(recorded_at);
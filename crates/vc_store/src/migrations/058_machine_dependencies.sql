@@ -0,0 +1,17 @@
+-- Migration 058: Cross-machine dependencies
+-- Created: 2026-10-16
+-- Purpose: Record that one machine depends on another (a builder mounting
+-- the NFS server, a worker behind a gateway), so alerts on the downstream
+-- machine can be traced to a failing upstream one instead of ranked on
+-- their own. Edges are directed and the graph is kept acyclic by the store.
+
+CREATE TABLE IF NOT EXISTS machine_dependencies (
+    machine_id TEXT NOT NULL,
+    depends_on TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (machine_id, depends_on)
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_dependencies_upstream
+    ON machine_dependencies(depends_on);
@@ -0,0 +1,7 @@
+-- Migration 059: CPU utilisation on fallback samples
+-- Created: 2026-10-16
+-- Purpose: Windows has no load averages, so the fallback probe records CPU
+-- utilisation from Get-Counter (or typeperf) instead. Linux and macOS rows
+-- leave it NULL.
+
+ALTER TABLE sys_fallback_samples ADD COLUMN cpu_pct REAL;
@@ -0,0 +1,32 @@
+-- Migration 060: Declarative fleet applies
+-- Created: 2026-10-16
+-- Purpose: `vc fleet apply` diffs a desired-state file against the agents
+-- the collectors see and records the resulting plan under one apply_id.
+-- Each step becomes a fleet command when executed; steps left pending or
+-- failed are picked up again by `vc fleet resume`.
+
+CREATE TABLE IF NOT EXISTS fleet_applies (
+    apply_id TEXT PRIMARY KEY,
+    source TEXT,                        -- path of the applied file
+    desired_json TEXT NOT NULL,         -- [{machine_id, agent_type, count, config}]
+    status TEXT NOT NULL,               -- planned, partial, completed
+    created_at TEXT NOT NULL,
+    completed_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS fleet_apply_steps (
+    apply_id TEXT NOT NULL,
+    step INTEGER NOT NULL,
+    machine_id TEXT NOT NULL,
+    agent_type TEXT NOT NULL,
+    action TEXT NOT NULL,               -- spawn, stop
+    count INTEGER NOT NULL,
+    config_json TEXT,
+    status TEXT NOT NULL DEFAULT 'pending',  -- pending, completed, failed
+    command_id TEXT,
+    error TEXT,
+    completed_at TEXT,
+    PRIMARY KEY (apply_id, step)
+);
+
+ALTER TABLE fleet_commands ADD COLUMN apply_id TEXT;
@@ -0,0 +1,28 @@
+-- Migration 061: HTTP health checks
+-- Created: 2026-10-16
+-- Purpose: Results of the `http_check` collector, one row per configured
+-- check per collection: status, latency, whether the body matched, and the
+-- certificate expiry of https endpoints. `error_cause` uses the collector
+-- failure taxonomy. A certificate close to expiry is recorded in
+-- `tls_warning` while `ok` stays 1.
+
+CREATE TABLE IF NOT EXISTS http_check_results (
+    machine_id TEXT NOT NULL,
+    collected_at TEXT NOT NULL,
+    check_name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    method TEXT NOT NULL,
+    origin TEXT NOT NULL,               -- machine or hub
+    ok INTEGER NOT NULL,
+    status_code INTEGER,
+    latency_ms REAL,
+    body_matched INTEGER,
+    error TEXT,
+    error_cause TEXT,
+    tls_expires_at TEXT,
+    tls_days_left INTEGER,
+    tls_warning TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_http_check_results_check
+    ON http_check_results(machine_id, check_name, collected_at);
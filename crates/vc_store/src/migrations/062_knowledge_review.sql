@@ -0,0 +1,11 @@
+-- Migration 062: Knowledge review
+-- Created: 2026-10-16
+-- Purpose: Track when a knowledge entry was last viewed so the review queue
+-- can rank entries nobody reads, and let entries be archived: hidden from
+-- default search but kept, so mining can recognise them and stop
+-- re-creating them. Mined sessions count the entries that were suppressed
+-- for that reason.
+
+ALTER TABLE knowledge_entries ADD COLUMN last_viewed_at TEXT;
+ALTER TABLE knowledge_entries ADD COLUMN archived_at TEXT;
+ALTER TABLE mined_sessions ADD COLUMN suppressed_duplicates INTEGER DEFAULT 0;
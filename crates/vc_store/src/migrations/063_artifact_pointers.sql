@@ -0,0 +1,17 @@
+-- Migration 063: Offloaded artifact pointers
+-- Created: 2026-10-16
+-- Purpose: Transcripts older than [storage.artifacts] offload_after_days move
+-- out of agent_sessions.raw_json into a directory or S3-compatible bucket.
+-- Each leaves a pointer here: where the body went, its size and SHA-256, so
+-- readers can fetch it back and `vc db artifacts verify` can check it.
+
+CREATE TABLE IF NOT EXISTS artifact_pointers (
+    kind TEXT NOT NULL,          -- transcript
+    machine_id TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    location TEXT NOT NULL,      -- file:///... or s3://bucket/key
+    size_bytes BIGINT NOT NULL,
+    sha256 TEXT NOT NULL,
+    offloaded_at TEXT NOT NULL,
+    PRIMARY KEY (kind, machine_id, session_id)
+);
@@ -0,0 +1,11 @@
+-- Migration 064: Ingest batch outcomes
+-- Created: 2026-10-16
+-- Purpose: Each bundle batch is applied in its own transaction. Record which
+-- batch of which bundle a log row is about and whether it was applied or
+-- failed (with the error), so a partial ingest shows up in `vc node history`
+-- and re-running the bundle retries only the failed batches. Rows written
+-- before this migration were all successful ingests.
+
+ALTER TABLE node_ingest_log ADD COLUMN batch_id TEXT;
+ALTER TABLE node_ingest_log ADD COLUMN status TEXT DEFAULT 'applied';
+ALTER TABLE node_ingest_log ADD COLUMN error TEXT;
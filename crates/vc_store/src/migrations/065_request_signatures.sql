@@ -0,0 +1,16 @@
+-- Migration 065: Seen request signatures
+-- Created: 2026-10-16
+-- Purpose: Signed pushes to the web server (`[web.signing]`) are rejected
+-- when their signature was already seen inside the timestamp window. The web
+-- server keeps recent signatures in memory; this table is the fallback that
+-- survives restarts and cache eviction. Rows older than the window are pruned
+-- as new ones arrive.
+
+CREATE TABLE IF NOT EXISTS request_signatures_seen (
+    signature TEXT PRIMARY KEY,
+    signed_at BIGINT NOT NULL,
+    seen_at TIMESTAMP DEFAULT current_timestamp
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_signatures_signed_at
+    ON request_signatures_seen(signed_at);
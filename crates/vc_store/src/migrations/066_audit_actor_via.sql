@@ -0,0 +1,10 @@
+-- Migration 066: Audit actor frontend
+-- Created: 2026-10-16
+-- Purpose: Audit events name the consumer that performed them (`--actor`,
+-- an MCP client's `clientInfo`, the web token name, or the OS user). Record
+-- which frontend the actor came through, and index actors for
+-- `vc audit list --actor`. Older rows have no frontend recorded.
+
+ALTER TABLE audit_events ADD COLUMN actor_via TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor ON audit_events(actor);
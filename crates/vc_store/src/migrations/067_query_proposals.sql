@@ -0,0 +1,29 @@
+-- Migration 067: Query proposals
+-- Created: 2026-10-16
+-- Purpose: Agents propose raw read queries over MCP (`vc_propose_query`);
+-- an operator approves or denies each one. Approval runs the query under the
+-- agent statement timeout and keeps its result here for the agent to fetch
+-- with `vc_check_query`. Pending proposals expire after
+-- `[query_approval] ttl_secs`.
+
+CREATE TABLE IF NOT EXISTS query_proposals (
+    id BIGINT PRIMARY KEY,
+    sql TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,      -- as recorded in query_log
+    justification TEXT NOT NULL,
+    proposed_by TEXT NOT NULL,
+    proposed_via TEXT,              -- cli, web, mcp, daemon
+    status TEXT NOT NULL DEFAULT 'pending', -- pending, approved, executed, failed, denied, expired
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    decided_by TEXT,
+    decided_at TEXT,
+    decision_reason TEXT,
+    row_count BIGINT,
+    duration_ms DOUBLE,
+    result_json TEXT,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_query_proposals_status
+    ON query_proposals(status, expires_at);
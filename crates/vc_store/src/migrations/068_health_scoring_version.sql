@@ -0,0 +1,7 @@
+-- Migration 068: Health scoring version
+-- Created: 2026-10-16
+-- Purpose: Record which version of the health scoring logic produced each
+-- summary, so score jumps caused by an upgrade are not read as the fleet
+-- getting worse. Rows written before versioning count as version 1.
+
+ALTER TABLE health_summary ADD COLUMN scoring_version INTEGER DEFAULT 1;
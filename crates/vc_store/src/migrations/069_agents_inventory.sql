@@ -0,0 +1,29 @@
+-- Migration 069: Agent inventory
+-- Created: 2026-10-16
+-- Purpose: Agents running on each machine, as found by the `agent_inventory`
+-- collector in tmux panes and the process table. One row per agent for as
+-- long as it keeps being seen: `last_seen_at` moves forward every cycle and
+-- an agent missing from a later cycle gets `ended_at`. `last_activity_at` is
+-- the tmux window's last output, NULL for agents outside tmux.
+
+CREATE TABLE IF NOT EXISTS agents_inventory (
+    machine_id TEXT NOT NULL,
+    agent_key TEXT NOT NULL,            -- tmux:<pane_id>:<pid> or pid:<pid>
+    agent_type TEXT NOT NULL,
+    source TEXT NOT NULL,               -- tmux or process
+    session_name TEXT,
+    pane TEXT,                          -- window.pane index
+    pane_id TEXT,
+    pid INTEGER,
+    working_dir TEXT,
+    command TEXT,
+    started_at TEXT,
+    last_activity_at TEXT,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    ended_at TEXT,
+    PRIMARY KEY (machine_id, agent_key, first_seen_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_agents_inventory_active
+    ON agents_inventory(machine_id, ended_at);
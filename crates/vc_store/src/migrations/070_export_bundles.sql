@@ -0,0 +1,17 @@
+-- Migration 070: Export bundles
+-- Created: 2026-10-16
+-- Purpose: Directories written by `vc db export`, so the web API can list
+-- them and serve their files. Only files inside a registered `output_dir`
+-- are ever served.
+
+CREATE TABLE IF NOT EXISTS export_bundles (
+    bundle_id TEXT PRIMARY KEY,         -- ex-<8 hex>
+    output_dir TEXT NOT NULL,           -- absolute path
+    format TEXT NOT NULL,               -- jsonl or csv
+    tables_exported INTEGER NOT NULL,
+    total_rows BIGINT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_export_bundles_created
+    ON export_bundles(created_at);
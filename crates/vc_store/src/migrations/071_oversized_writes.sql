@@ -0,0 +1,21 @@
+-- Migration 071: Oversized writes
+-- Created: 2026-10-16
+-- Purpose: Values the store's write limits truncated or whose rows they
+-- rejected, one row per value, for `/metrics`, `vc health collectors
+-- --oversized` and the `oversized-writes` alert rule. `collector` and
+-- `machine_id` are NULL for writes that did not come from a collection.
+
+CREATE TABLE IF NOT EXISTS oversized_writes (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    column_class TEXT NOT NULL,          -- text or json
+    action TEXT NOT NULL,                -- truncated or rejected
+    bytes BIGINT NOT NULL,
+    limit_bytes BIGINT NOT NULL,
+    collector TEXT,
+    machine_id TEXT,
+    occurred_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversized_writes_occurred
+    ON oversized_writes(occurred_at);
@@ -0,0 +1,23 @@
+-- Migration 072: Legal holds
+-- Created: 2026-10-16
+-- Purpose: Rows that vacuum must keep whatever their age or the table's
+-- size cap. A hold covers an incident (its own rows plus the alerts and
+-- sessions its timeline links), a session, and/or one machine's rows in a
+-- time range. Released holds are kept for the record. `retention_log`
+-- gains the number of rows each run kept because of holds.
+
+CREATE TABLE IF NOT EXISTS legal_holds (
+    hold_id TEXT PRIMARY KEY,           -- hold-<8 hex>
+    reason TEXT NOT NULL,
+    incident_id TEXT,
+    session_id TEXT,
+    machine_id TEXT,
+    from_ts TEXT,                       -- with machine_id; NULL = unbounded
+    to_ts TEXT,
+    created_by TEXT,
+    created_at TEXT NOT NULL,
+    released_at TEXT,
+    released_by TEXT
+);
+
+ALTER TABLE retention_log ADD COLUMN rows_held BIGINT DEFAULT 0;
@@ -0,0 +1,23 @@
+-- Migration 073: Guardian sandbox runs
+-- Created: 2026-10-16
+-- Purpose: Dry reports of `vc guardian run --sandbox`: a playbook walked
+-- against one machine's recorded state, with each step's command line and
+-- predicted effect. A run made for a draft revision can be attached to
+-- that draft's approval.
+
+CREATE TABLE IF NOT EXISTS guardian_sandbox_runs (
+    sandbox_id TEXT PRIMARY KEY,        -- sbx-<8 hex>
+    playbook_id TEXT NOT NULL,
+    draft_id TEXT,                      -- set when the playbook is a draft
+    draft_revision INTEGER,
+    machine_id TEXT NOT NULL,
+    as_of TEXT NOT NULL,                -- instant the recorded state is from
+    report_json TEXT NOT NULL,
+    created_by TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_guardian_sandbox_runs_playbook
+    ON guardian_sandbox_runs(playbook_id, created_at);
+
+ALTER TABLE playbook_drafts ADD COLUMN approved_sandbox_run TEXT;
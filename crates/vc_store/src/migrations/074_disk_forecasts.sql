@@ -0,0 +1,19 @@
+-- Migration 074: Disk forecasts
+-- Created: 2026-10-16
+-- Purpose: The latest forecast per machine and mount, written by the daemon
+-- each tick for the `disk-forecast-full` alert rule. `days_until_full` is
+-- NULL when usage is flat, falling or too irregular to project.
+
+CREATE TABLE IF NOT EXISTS disk_forecasts (
+    machine_id TEXT NOT NULL,
+    mount TEXT NOT NULL,
+    total_bytes BIGINT NOT NULL,
+    used_bytes BIGINT NOT NULL,
+    growth_bytes_per_day DOUBLE,
+    days_until_full DOUBLE,
+    full_at TEXT,
+    confidence DOUBLE NOT NULL,
+    trend TEXT NOT NULL,                 -- filling, flat, shrinking, cyclic, insufficient_data
+    computed_at TEXT NOT NULL,
+    PRIMARY KEY (machine_id, mount)
+);
@@ -0,0 +1,12 @@
+-- Migration 075: Alert resolution attribution
+-- Created: 2026-10-16
+-- Purpose: What resolved each alert: its condition clearing, a guardian
+-- run, or a person (`resolved_by`). `guardian_run_id` is the run that
+-- claimed the alert, set before it resolves. Alerts resolved before this
+-- migration are attributed to `unknown`.
+
+ALTER TABLE alert_history ADD COLUMN resolution_kind TEXT;
+ALTER TABLE alert_history ADD COLUMN resolved_by TEXT;
+ALTER TABLE alert_history ADD COLUMN guardian_run_id BIGINT;
+
+UPDATE alert_history SET resolution_kind = 'unknown' WHERE resolved_at IS NOT NULL;
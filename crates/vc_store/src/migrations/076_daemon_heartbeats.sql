@@ -0,0 +1,12 @@
+-- Migration 076: Daemon heartbeat days
+-- Created: 2026-10-16
+-- Purpose: One row per UTC day the daemon ran a cycle, so history can tell
+-- a quiet day (zero alerts) from a day nothing was watching (no data).
+-- `daemon_resource_state` only keeps the latest check.
+
+CREATE TABLE IF NOT EXISTS daemon_heartbeats (
+    day TEXT PRIMARY KEY,
+    first_at TEXT NOT NULL,
+    last_at TEXT NOT NULL,
+    beats BIGINT NOT NULL DEFAULT 1
+);
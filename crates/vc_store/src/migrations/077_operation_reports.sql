@@ -0,0 +1,20 @@
+-- Migration 077: Saved multi-machine operation reports
+-- Created: 2026-10-16
+-- Purpose: `--save-report` keeps the per-target outcomes of a command that
+-- acted on several machines (`vc collect`, `vc fleet apply`, bulk alert
+-- ack/resolve) under an operation ID, for `vc ops show <id>`. The report is
+-- stored as the JSON the command printed.
+
+CREATE TABLE IF NOT EXISTS operation_reports (
+    operation_id TEXT PRIMARY KEY,
+    command TEXT NOT NULL,
+    worst_outcome TEXT,
+    targets BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0,
+    report_json TEXT NOT NULL,
+    created_by TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_operation_reports_created
+    ON operation_reports(created_at);
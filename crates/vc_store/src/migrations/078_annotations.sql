@@ -0,0 +1,21 @@
+-- Migration 078: Annotations
+-- Created: 2026-10-16
+-- Purpose: Deploys and other outside events, recorded by `vc annotate` or
+-- `POST /api/annotations` (and internally by maintenance windows and config
+-- changes), so they show next to the alerts they explain: in the timeline,
+-- the digest, incident timelines and the web charts. Rows are append-only;
+-- `scope` is `fleet`, `machine:<id>` or `tag:<tag>`.
+
+CREATE TABLE IF NOT EXISTS annotations (
+    id BIGINT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    scope TEXT NOT NULL DEFAULT 'fleet',
+    ts TEXT NOT NULL,
+    message TEXT NOT NULL,
+    source TEXT NOT NULL,
+    created_by TEXT,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_annotations_ts ON annotations(ts);
+CREATE INDEX IF NOT EXISTS idx_annotations_kind ON annotations(kind, ts);
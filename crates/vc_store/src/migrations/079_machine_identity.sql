@@ -0,0 +1,24 @@
+-- Migration 079: Machine identity
+-- Created: 2026-10-16
+-- Purpose: Give every machine a stable UUID next to its human-chosen
+-- machine_id, so a host renamed or re-added under a new id keeps one
+-- history instead of two. The registry assigns machine_uuid at
+-- registration and a probe leaves it in a marker file on the machine.
+-- machine_identities keeps every machine id and hostname a UUID was seen
+-- under, keyed by the UUID, with the host's own hardware identifier for
+-- recognizing a machine whose marker file is gone.
+
+ALTER TABLE machines ADD COLUMN machine_uuid TEXT;
+
+CREATE TABLE IF NOT EXISTS machine_identities (
+    machine_uuid TEXT NOT NULL,
+    machine_id TEXT NOT NULL,
+    hostname TEXT NOT NULL,
+    hardware_id TEXT,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    PRIMARY KEY (machine_uuid, machine_id, hostname)
+);
+
+CREATE INDEX IF NOT EXISTS idx_machine_identities_hardware
+    ON machine_identities(hardware_id);
@@ -0,0 +1,30 @@
+-- Migration 080: Data corrections
+-- Created: 2026-10-16
+-- Purpose: `vc db correct` rewrites columns of historical rows a collector
+-- got wrong. Each correction records its full specification, and every row
+-- it changed keeps the original values of the corrected columns along with
+-- how to find the row again (its primary key after the correction, or its
+-- rowid in a table without one), so `vc db correct --undo <id>` can put the
+-- rows back.
+
+CREATE TABLE IF NOT EXISTS data_corrections (
+    id BIGINT PRIMARY KEY,
+    table_name TEXT NOT NULL,
+    where_clause TEXT NOT NULL,
+    assignments_json TEXT NOT NULL,
+    since TEXT,
+    until TEXT,
+    rows_changed BIGINT NOT NULL DEFAULT 0,
+    corrected_by TEXT NOT NULL,
+    corrected_at TEXT NOT NULL,
+    undone_by TEXT,
+    undone_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS data_correction_rows (
+    correction_id BIGINT NOT NULL,
+    row_index BIGINT NOT NULL,
+    locator_json TEXT NOT NULL,
+    original_json TEXT NOT NULL,
+    PRIMARY KEY (correction_id, row_index)
+);
@@ -0,0 +1,158 @@
+//! Saved operation reports
+//!
+//! A command that acts on several machines can keep its report with
+//! `--save-report`: the per-target outcomes under an operation ID, for
+//! `vc ops show` later. The report's shape belongs to `vc_cli`; the store
+//! keeps it as JSON next to the columns reports are listed by.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// One saved report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationReportRecord {
+    pub operation_id: String,
+    /// Command that produced it, e.g. `vc collect`
+    pub command: String,
+    /// Worst outcome over the targets; `None` when there were none
+    pub worst_outcome: Option<String>,
+    pub targets: i64,
+    pub failed: i64,
+    pub report: serde_json::Value,
+    pub created_by: Option<String>,
+    pub created_at: String,
+}
+
+const COLUMNS: &str =
+    "operation_id, command, worst_outcome, targets, failed, report_json, created_by, created_at";
+
+fn map_report(row: &duckdb::Row<'_>) -> duckdb::Result<OperationReportRecord> {
+    let report: String = row.get(5)?;
+    Ok(OperationReportRecord {
+        operation_id: row.get(0)?,
+        command: row.get(1)?,
+        worst_outcome: row.get(2)?,
+        targets: row.get(3)?,
+        failed: row.get(4)?,
+        report: serde_json::from_str(&report).unwrap_or(serde_json::Value::Null),
+        created_by: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+impl VcStore {
+    /// Save an operation report
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails, e.g. on a reused ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_operation_report(
+        &self,
+        report: &OperationReportRecord,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!("INSERT INTO operation_reports ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"),
+            duckdb::params![
+                report.operation_id,
+                report.command,
+                report.worst_outcome,
+                report.targets,
+                report.failed,
+                report.report.to_string(),
+                report.created_by,
+                report.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Look up a saved operation report
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn operation_report(
+        &self,
+        operation_id: &str,
+    ) -> Result<Option<OperationReportRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM operation_reports WHERE operation_id = ?"
+        ))?;
+        let mut rows = stmt.query_map([operation_id], map_report)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Saved operation reports, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_operation_reports(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<OperationReportRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM operation_reports \
+             ORDER BY created_at DESC, operation_id LIMIT ?"
+        ))?;
+        let rows = stmt.query_map([i64::try_from(limit).unwrap_or(i64::MAX)], map_report)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(operation_id: &str, failed: i64, created_at: &str) -> OperationReportRecord {
+        OperationReportRecord {
+            operation_id: operation_id.to_string(),
+            command: "vc collect".to_string(),
+            worst_outcome: Some(if failed > 0 { "failed" } else { "success" }.to_string()),
+            targets: 3,
+            failed,
+            report: serde_json::json!({"targets": [{"target": "orko", "outcome": "success"}]}),
+            created_by: Some("alice".to_string()),
+            created_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_show_and_list_operation_reports() {
+        let store = VcStore::open_memory().unwrap();
+        let first = report("op-1", 1, "2026-10-16T12:00:00Z");
+        store.record_operation_report(&first).unwrap();
+        store
+            .record_operation_report(&report("op-2", 0, "2026-10-16T13:00:00Z"))
+            .unwrap();
+
+        assert_eq!(store.operation_report("op-1").unwrap(), Some(first.clone()));
+        assert_eq!(store.operation_report("op-missing").unwrap(), None);
+        let ids: Vec<String> = store
+            .list_operation_reports(10)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.operation_id)
+            .collect();
+        assert_eq!(ids, vec!["op-2", "op-1"]);
+
+        // IDs are not reused
+        assert!(store.record_operation_report(&first).is_err());
+    }
+}
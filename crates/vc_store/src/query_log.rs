@@ -0,0 +1,407 @@
+//! Query observability
+//!
+//! Every statement the store runs is fingerprinted (literals stripped),
+//! tagged with the frontend that ran it and timed. Records go into an
+//! in-memory buffer that is written to the `query_log` table in one batch
+//! while the connection is held anyway, so recording costs a string scan and
+//! a vector push per query. `vc db slow-queries` aggregates the table by
+//! fingerprint, marking shapes whose plan an `ANALYZE` changed (see
+//! [`crate::table_stats`]).
+//!
+//! Timing is taken around `execute`/`query_row` calls and JSON queries.
+//! Statements prepared by typed store methods are timed from preparation
+//! until the connection is released, which covers reading their rows; their
+//! row count is not known.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use vc_config::QueryLogConfig;
+
+use crate::{StoreError, VcStore, escape_sql_literal};
+
+/// Frontend a store was opened by, recorded with each of its queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryCaller {
+    Cli,
+    Web,
+    Mcp,
+    Daemon,
+}
+
+impl QueryCaller {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueryCaller::Cli => "cli",
+            QueryCaller::Web => "web",
+            QueryCaller::Mcp => "mcp",
+            QueryCaller::Daemon => "daemon",
+        }
+    }
+}
+
+impl std::str::FromStr for QueryCaller {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "cli" => Ok(QueryCaller::Cli),
+            "web" => Ok(QueryCaller::Web),
+            "mcp" => Ok(QueryCaller::Mcp),
+            "daemon" => Ok(QueryCaller::Daemon),
+            other => Err(format!("unknown query caller: {other}")),
+        }
+    }
+}
+
+/// A query shape's share of store load, from [`VcStore::slow_queries`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlowQuery {
+    pub fingerprint: String,
+    /// Frontends that ran it
+    pub callers: Vec<String>,
+    pub calls: i64,
+    pub total_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// Rows returned or changed, over the calls whose count is known
+    pub rows: Option<i64>,
+    /// When an `ANALYZE` last changed this shape's plan
+    pub plan_changed_at: Option<String>,
+    /// Table whose `ANALYZE` changed it
+    pub plan_changed_by: Option<String>,
+}
+
+#[derive(Debug)]
+struct QueryRecord {
+    recorded_at: DateTime<Utc>,
+    fingerprint: String,
+    duration: Duration,
+    rows: Option<u64>,
+}
+
+#[derive(Debug)]
+struct Buffer {
+    pending: Vec<QueryRecord>,
+    last_flush: Instant,
+}
+
+/// In-memory buffer of one store's recorded queries
+#[derive(Debug)]
+pub struct QueryLog {
+    caller: QueryCaller,
+    max_rows: usize,
+    buffer_size: usize,
+    flush_interval: Duration,
+    buffer: Mutex<Buffer>,
+}
+
+impl QueryLog {
+    /// Build a log from the `[query_log]` config section
+    #[must_use]
+    pub fn new(config: &QueryLogConfig, caller: QueryCaller) -> Self {
+        Self {
+            caller,
+            max_rows: config.max_rows.max(1),
+            buffer_size: config.buffer_size.max(1),
+            flush_interval: Duration::from_secs(config.flush_interval_secs),
+            buffer: Mutex::new(Buffer {
+                pending: Vec::new(),
+                last_flush: Instant::now(),
+            }),
+        }
+    }
+
+    #[must_use]
+    pub fn caller(&self) -> QueryCaller {
+        self.caller
+    }
+
+    /// Queries recorded but not yet written
+    #[must_use]
+    pub fn pending_len(&self) -> usize {
+        self.buffer.lock().map_or(0, |buffer| buffer.pending.len())
+    }
+
+    pub(crate) fn record(&self, sql: &str, duration: Duration, rows: Option<u64>) {
+        let record = QueryRecord {
+            recorded_at: Utc::now(),
+            fingerprint: fingerprint(sql),
+            duration,
+            rows,
+        };
+        if let Ok(mut buffer) = self.buffer.lock() {
+            buffer.pending.push(record);
+        }
+    }
+
+    /// Whether the buffer is full or has waited out the flush interval
+    pub(crate) fn flush_due(&self) -> bool {
+        self.buffer.lock().is_ok_and(|buffer| {
+            buffer.pending.len() >= self.buffer_size
+                || (!buffer.pending.is_empty()
+                    && buffer.last_flush.elapsed() >= self.flush_interval)
+        })
+    }
+
+    /// Take everything pending as one batch: the insert, then a trim of the
+    /// table to the newest `max_rows` rows. `None` when nothing is pending.
+    pub(crate) fn take_flush_batch(&self) -> Option<(String, usize)> {
+        let mut buffer = self.buffer.lock().ok()?;
+        buffer.last_flush = Instant::now();
+        if buffer.pending.is_empty() {
+            return None;
+        }
+        let records = std::mem::take(&mut buffer.pending);
+        drop(buffer);
+
+        let values: Vec<String> = records
+            .iter()
+            .map(|record| {
+                format!(
+                    "('{}', '{}', '{}', {}, {})",
+                    record
+                        .recorded_at
+                        .to_rfc3339_opts(SecondsFormat::Micros, true),
+                    self.caller.as_str(),
+                    escape_sql_literal(&record.fingerprint),
+                    i64::try_from(record.duration.as_micros()).unwrap_or(i64::MAX),
+                    record
+                        .rows
+                        .map_or_else(|| "NULL".to_string(), |rows| rows.to_string()),
+                )
+            })
+            .collect();
+        let sql = format!(
+            "INSERT INTO query_log (recorded_at, caller, fingerprint, duration_us, row_count) \
+             VALUES {};\n\
+             DELETE FROM query_log WHERE recorded_at < (\
+             SELECT recorded_at FROM query_log ORDER BY recorded_at DESC LIMIT 1 OFFSET {});",
+            values.join(", "),
+            self.max_rows - 1
+        );
+        Some((sql, records.len()))
+    }
+}
+
+/// Normalize a statement so calls that differ only in their values
+/// aggregate together: string and numeric literals become `?`, runs of
+/// whitespace one space, and lists of literals (`IN (?, ?, ?)`, multi-row
+/// `VALUES`) a single `?`.
+#[must_use]
+pub fn fingerprint(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                // '' inside a literal is an escaped quote
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                out.push('?');
+            }
+            c if c.is_ascii_digit()
+                && !out
+                    .chars()
+                    .last()
+                    .is_some_and(|prev| prev.is_alphanumeric() || prev == '_') =>
+            {
+                while chars
+                    .peek()
+                    .is_some_and(|next| next.is_ascii_alphanumeric() || *next == '.')
+                {
+                    chars.next();
+                }
+                out.push('?');
+            }
+            c if c.is_whitespace() => {
+                if !out.is_empty() && !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    let mut fingerprint = out.trim_end().to_string();
+    for list in ["?, ?", "?,?", "(?), (?)", "(?),(?)"] {
+        let single = if list.starts_with('(') { "(?)" } else { "?" };
+        while fingerprint.contains(list) {
+            fingerprint = fingerprint.replace(list, single);
+        }
+    }
+    fingerprint
+}
+
+impl VcStore {
+    /// Write queries recorded by this store that are still buffered.
+    /// Returns the number written; 0 when query logging is off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the batch write fails; the batch is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn flush_query_log(&self) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.flush_query_log()
+    }
+
+    /// Recorded query shapes ordered by total time spent in them, optionally
+    /// only counting calls recorded at or after `since` (RFC 3339).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if flushing this store's buffer or the query
+    /// fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn slow_queries(
+        &self,
+        since: Option<&str>,
+        top: usize,
+    ) -> Result<Vec<SlowQuery>, StoreError> {
+        self.flush_query_log()?;
+
+        let filter = since
+            .map(|since| format!("WHERE q.recorded_at >= '{}'", escape_sql_literal(since)))
+            .unwrap_or_default();
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT q.fingerprint, string_agg(DISTINCT q.caller, ','), COUNT(*), \
+             CAST(SUM(q.duration_us) AS BIGINT), MAX(q.duration_us), \
+             CAST(SUM(q.row_count) AS BIGINT), MAX(p.changed_at), MAX(p.changed_by_table) \
+             FROM query_log q LEFT JOIN query_plans p ON p.fingerprint = q.fingerprint \
+             {filter} \
+             GROUP BY q.fingerprint \
+             ORDER BY SUM(q.duration_us) DESC, q.fingerprint \
+             LIMIT {top}"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            let callers: String = row.get(1)?;
+            let calls: i64 = row.get(2)?;
+            let total_us: i64 = row.get(3)?;
+            let max_us: i64 = row.get(4)?;
+            let mut callers: Vec<String> = callers.split(',').map(ToString::to_string).collect();
+            callers.sort();
+            Ok(SlowQuery {
+                fingerprint: row.get(0)?,
+                callers,
+                calls,
+                total_ms: total_us as f64 / 1000.0,
+                avg_ms: total_us as f64 / 1000.0 / calls.max(1) as f64,
+                max_ms: max_us as f64 / 1000.0,
+                rows: row.get(5)?,
+                plan_changed_at: row.get(6)?,
+                plan_changed_by: row.get(7)?,
+            })
+        })?;
+
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_strips_literals() {
+        assert_eq!(
+            fingerprint(
+                "SELECT *\n  FROM sys_samples WHERE machine_id = 'it''s'   AND cpu > 90.5 LIMIT 10"
+            ),
+            "SELECT * FROM sys_samples WHERE machine_id = ? AND cpu > ? LIMIT ?"
+        );
+        assert_eq!(
+            fingerprint("SELECT col1, t2.x FROM t2 WHERE id IN (1, 2, 3)"),
+            "SELECT col1, t2.x FROM t2 WHERE id IN (?)"
+        );
+        assert_eq!(
+            fingerprint("INSERT INTO t VALUES ('a'), ('b'), ('c')"),
+            fingerprint("INSERT INTO t VALUES ('z')")
+        );
+    }
+
+    #[test]
+    fn test_query_log_records_and_aggregates() {
+        let config = QueryLogConfig {
+            max_rows: 3,
+            buffer_size: 100,
+            ..QueryLogConfig::default()
+        };
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_query_log(&config, QueryCaller::Web);
+
+        for id in 0..2 {
+            store
+                .query_json(&format!(
+                    "SELECT * FROM machines WHERE machine_id = 'm{id}'"
+                ))
+                .unwrap();
+        }
+        store.query_scalar::<i64>("SELECT 42").unwrap();
+        assert_eq!(store.query_log().unwrap().pending_len(), 3);
+
+        let slow = store.slow_queries(None, 20).unwrap();
+        let machines = slow
+            .iter()
+            .find(|q| q.fingerprint == "SELECT * FROM machines WHERE machine_id = ?")
+            .unwrap();
+        assert_eq!(machines.calls, 2);
+        assert_eq!(machines.callers, vec!["web"]);
+        assert_eq!(machines.rows, Some(0));
+        assert!(machines.max_ms <= machines.total_ms);
+
+        // The ring keeps the newest max_rows entries
+        for _ in 0..5 {
+            store.query_scalar::<i64>("SELECT 1").unwrap();
+        }
+        store.flush_query_log().unwrap();
+        let logged: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM query_log")
+            .unwrap();
+        assert_eq!(logged, 3);
+
+        let future = store
+            .slow_queries(Some("2999-01-01T00:00:00Z"), 20)
+            .unwrap();
+        assert!(future.is_empty());
+    }
+
+    #[test]
+    fn test_query_log_off_records_nothing() {
+        let config = QueryLogConfig {
+            enabled: false,
+            ..QueryLogConfig::default()
+        };
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_query_log(&config, QueryCaller::Cli);
+        store.query_scalar::<i64>("SELECT 1").unwrap();
+        assert!(store.query_log().is_none());
+        assert_eq!(store.flush_query_log().unwrap(), 0);
+        assert!(store.slow_queries(None, 20).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+//! Raw queries proposed by agents, waiting for an operator
+//!
+//! An agent that wants a query no template covers proposes it with a
+//! justification. An operator approves or denies it; approval runs the query
+//! then and there and keeps the result on the proposal, where the agent
+//! picks it up. A proposal nobody decides on expires.
+//!
+//! Status moves `pending` → `approved` → `executed` or `failed`, or from
+//! `pending` to `denied` or `expired`. Each move is audited. Running the
+//! query is the caller's job (it owns the guardrails); this module only
+//! records what happened.
+
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::query_log::fingerprint;
+use crate::{ActorContext, AuditEventType, AuditResult, StoreError, VcStore};
+
+/// One proposed query and, once decided, its outcome
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryProposal {
+    pub id: i64,
+    pub sql: String,
+    /// The query's shape as recorded in `query_log`
+    pub fingerprint: String,
+    pub justification: String,
+    pub proposed_by: String,
+    /// Frontend the proposal came through
+    pub proposed_via: Option<String>,
+    /// `pending`, `approved`, `executed`, `failed`, `denied` or `expired`
+    pub status: String,
+    pub created_at: String,
+    pub expires_at: String,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+    /// Why it was denied, when the operator said
+    pub decision_reason: Option<String>,
+    pub row_count: Option<i64>,
+    pub duration_ms: Option<f64>,
+    /// Result rows of an executed proposal
+    pub result: Option<serde_json::Value>,
+    /// Why an approved proposal failed to run
+    pub error: Option<String>,
+}
+
+impl QueryProposal {
+    /// Whether an operator can still approve or deny it
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.status == "pending"
+    }
+}
+
+const PROPOSAL_COLUMNS: &str = "id, sql, fingerprint, justification, proposed_by, proposed_via, \
+                                status, created_at, expires_at, decided_by, decided_at, \
+                                decision_reason, row_count, duration_ms, result_json, error";
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+impl VcStore {
+    /// Record a proposed query as pending until `ttl` from now. The SQL is
+    /// stored as given; validating it is the caller's job.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if an insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn propose_query(
+        &self,
+        sql: &str,
+        justification: &str,
+        proposer: &ActorContext,
+        ttl: std::time::Duration,
+    ) -> Result<QueryProposal, StoreError> {
+        let now = Utc::now();
+        let ttl = Duration::from_std(ttl).unwrap_or(Duration::MAX);
+        let expires_at = now
+            .checked_add_signed(ttl)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let id = {
+            let conn = self.conn.lock().unwrap();
+            let id: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(id), 0) + 1 FROM query_proposals",
+                [],
+                |row| row.get(0),
+            )?;
+            conn.execute(
+                "INSERT INTO query_proposals \
+                 (id, sql, fingerprint, justification, proposed_by, proposed_via, status, \
+                  created_at, expires_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, 'pending', ?, ?)",
+                duckdb::params![
+                    id,
+                    sql,
+                    fingerprint(sql),
+                    justification,
+                    proposer.name,
+                    proposer.via.as_str(),
+                    timestamp(now),
+                    timestamp(expires_at),
+                ],
+            )?;
+            id
+        };
+        self.audit_proposal(proposer, "query_proposed", id, AuditResult::Success)?;
+        self.query_proposal(id)?
+            .ok_or_else(|| StoreError::QueryError(format!("query proposal {id} not recorded")))
+    }
+
+    /// Look up a proposal, expiring it first if its time is up
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query fails or a stored result is not JSON.
+    pub fn query_proposal(&self, id: i64) -> Result<Option<QueryProposal>, StoreError> {
+        self.expire_query_proposals()?;
+        Ok(self
+            .query_proposals_where("WHERE id = ?", &[&id])?
+            .into_iter()
+            .next())
+    }
+
+    /// Proposals, newest first, optionally only those with `status`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query fails or a stored result is not JSON.
+    pub fn list_query_proposals(
+        &self,
+        status: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<QueryProposal>, StoreError> {
+        self.expire_query_proposals()?;
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        match status {
+            Some(status) => self.query_proposals_where(
+                "WHERE status = ? ORDER BY id DESC LIMIT ?",
+                &[&status, &limit],
+            ),
+            None => self.query_proposals_where("ORDER BY id DESC LIMIT ?", &[&limit]),
+        }
+    }
+
+    /// Mark pending proposals past their expiry as `expired`, auditing each.
+    /// Returns how many expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update or an audit insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn expire_query_proposals(&self) -> Result<usize, StoreError> {
+        let now = timestamp(Utc::now());
+        let expired: Vec<i64> = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "UPDATE query_proposals SET status = 'expired', decided_at = ? \
+                 WHERE status = 'pending' AND expires_at <= ? RETURNING id",
+            )?;
+            let rows = stmt.query_map(duckdb::params![now, now], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        let expiry = ActorContext::system("expiry");
+        for id in &expired {
+            self.audit_proposal(&expiry, "query_expired", *id, AuditResult::Success)?;
+        }
+        Ok(expired.len())
+    }
+
+    /// Approve (`approve`) or deny a pending proposal. Returns the updated
+    /// proposal, or `None` when it is not pending (already decided or
+    /// expired) so it cannot be decided twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update or audit insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn decide_query_proposal(
+        &self,
+        id: i64,
+        approve: bool,
+        reason: Option<&str>,
+        operator: &ActorContext,
+    ) -> Result<Option<QueryProposal>, StoreError> {
+        self.expire_query_proposals()?;
+        let status = if approve { "approved" } else { "denied" };
+        let affected = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE query_proposals SET status = ?, decided_by = ?, decided_at = ?, \
+                 decision_reason = ? \
+                 WHERE id = ? AND status = 'pending'",
+                duckdb::params![status, operator.name, timestamp(Utc::now()), reason, id],
+            )?
+        };
+        if affected == 0 {
+            return Ok(None);
+        }
+        let action = if approve {
+            "query_approved"
+        } else {
+            "query_denied"
+        };
+        self.audit_proposal(operator, action, id, AuditResult::Success)?;
+        self.query_proposal(id)
+    }
+
+    /// Record the outcome of running an approved proposal: its rows, or the
+    /// error that stopped it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update or audit insert fails, or the
+    /// proposal is not approved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_query_proposal_result(
+        &self,
+        id: i64,
+        outcome: Result<&[serde_json::Value], &str>,
+        duration_ms: f64,
+        operator: &ActorContext,
+    ) -> Result<QueryProposal, StoreError> {
+        let (status, rows, result_json, error) = match outcome {
+            Ok(rows) => (
+                "executed",
+                Some(i64::try_from(rows.len()).unwrap_or(i64::MAX)),
+                Some(serde_json::to_string(rows)?),
+                None,
+            ),
+            Err(error) => ("failed", None, None, Some(error)),
+        };
+        let affected = {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "UPDATE query_proposals SET status = ?, row_count = ?, duration_ms = ?, \
+                 result_json = ?, error = ? \
+                 WHERE id = ? AND status = 'approved'",
+                duckdb::params![status, rows, duration_ms, result_json, error, id],
+            )?
+        };
+        if affected == 0 {
+            return Err(StoreError::QueryError(format!(
+                "query proposal {id} is not approved"
+            )));
+        }
+        let result = if error.is_some() {
+            AuditResult::Failure
+        } else {
+            AuditResult::Success
+        };
+        self.audit_proposal(operator, &format!("query_{status}"), id, result)?;
+        self.query_proposal(id)?
+            .ok_or_else(|| StoreError::QueryError(format!("query proposal {id} not found")))
+    }
+
+    fn audit_proposal(
+        &self,
+        actor: &ActorContext,
+        action: &str,
+        id: i64,
+        result: AuditResult,
+    ) -> Result<(), StoreError> {
+        self.insert_audit_event(&actor.audit_event(
+            AuditEventType::UserCommand,
+            action,
+            result,
+            serde_json::json!({ "proposal_id": id }),
+        ))
+    }
+
+    fn query_proposals_where(
+        &self,
+        tail: &str,
+        params: &[&dyn duckdb::ToSql],
+    ) -> Result<Vec<QueryProposal>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {PROPOSAL_COLUMNS} FROM query_proposals {tail}"
+        ))?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                QueryProposal {
+                    id: row.get(0)?,
+                    sql: row.get(1)?,
+                    fingerprint: row.get(2)?,
+                    justification: row.get(3)?,
+                    proposed_by: row.get(4)?,
+                    proposed_via: row.get(5)?,
+                    status: row.get(6)?,
+                    created_at: row.get(7)?,
+                    expires_at: row.get(8)?,
+                    decided_by: row.get(9)?,
+                    decided_at: row.get(10)?,
+                    decision_reason: row.get(11)?,
+                    row_count: row.get(12)?,
+                    duration_ms: row.get(13)?,
+                    result: None,
+                    error: row.get(15)?,
+                },
+                row.get::<_, Option<String>>(14)?,
+            ))
+        })?;
+        rows.map(|row| -> Result<QueryProposal, StoreError> {
+            let (mut proposal, result_json) = row?;
+            proposal.result = result_json
+                .map(|json| serde_json::from_str(&json))
+                .transpose()?;
+            Ok(proposal)
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuditEventFilter;
+
+    const HOUR: std::time::Duration = std::time::Duration::from_secs(3600);
+
+    #[test]
+    fn test_proposal_lifecycle_is_audited() {
+        let store = VcStore::open_memory().unwrap();
+        let agent = ActorContext::mcp(None, Some("night-agent"));
+        let operator = ActorContext::cli(Some("alice"));
+
+        let proposal = store
+            .propose_query("SELECT 42 AS answer", "need the answer", &agent, HOUR)
+            .unwrap();
+        assert!(proposal.is_pending());
+        assert_eq!(proposal.proposed_by, "night-agent");
+        assert_eq!(proposal.proposed_via.as_deref(), Some("mcp"));
+        assert_eq!(
+            store.list_query_proposals(Some("pending"), 10).unwrap(),
+            vec![proposal.clone()]
+        );
+
+        let approved = store
+            .decide_query_proposal(proposal.id, true, None, &operator)
+            .unwrap()
+            .unwrap();
+        assert_eq!(approved.status, "approved");
+        assert_eq!(approved.decided_by.as_deref(), Some("alice"));
+        // A decided proposal cannot be decided again
+        assert!(
+            store
+                .decide_query_proposal(proposal.id, false, None, &operator)
+                .unwrap()
+                .is_none()
+        );
+
+        let rows = vec![serde_json::json!({ "answer": 42 })];
+        let executed = store
+            .record_query_proposal_result(proposal.id, Ok(&rows), 1.5, &operator)
+            .unwrap();
+        assert_eq!(executed.status, "executed");
+        assert_eq!(executed.row_count, Some(1));
+        assert_eq!(executed.result, Some(serde_json::json!(rows)));
+
+        let actions: Vec<String> = store
+            .list_audit_events(&AuditEventFilter {
+                limit: 10,
+                ..AuditEventFilter::default()
+            })
+            .unwrap()
+            .into_iter()
+            .map(|event| event.action)
+            .collect();
+        for action in ["query_proposed", "query_approved", "query_executed"] {
+            assert!(
+                actions.iter().any(|a| a == action),
+                "{action} in {actions:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pending_proposals_expire() {
+        let store = VcStore::open_memory().unwrap();
+        let agent = ActorContext::mcp(Some("agent"), None);
+        let stale = store
+            .propose_query("SELECT 1", "quick check", &agent, std::time::Duration::ZERO)
+            .unwrap();
+        let fresh = store
+            .propose_query("SELECT 2", "another", &agent, HOUR)
+            .unwrap();
+
+        assert_eq!(
+            store.query_proposal(stale.id).unwrap().unwrap().status,
+            "expired"
+        );
+        assert!(
+            store
+                .query_proposal(fresh.id)
+                .unwrap()
+                .unwrap()
+                .is_pending()
+        );
+        assert!(
+            store
+                .decide_query_proposal(stale.id, true, None, &ActorContext::cli(Some("bob")))
+                .unwrap()
+                .is_none()
+        );
+
+        let denied = store
+            .decide_query_proposal(fresh.id, false, Some("too broad"), &ActorContext::cli(None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(denied.status, "denied");
+        assert_eq!(denied.decision_reason.as_deref(), Some("too broad"));
+    }
+}
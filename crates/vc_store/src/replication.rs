@@ -0,0 +1,873 @@
+//! Warm standby replication
+//!
+//! A primary ships rows to a standby table by table. Each table's watermark
+//! is the timestamp column `vc db export --since` filters on: a
+//! [`TableBatch`] carries every row whose timestamp falls in
+//! `(after, through]`, and the standby replaces that whole range with the
+//! batch. Re-sending a batch therefore leaves the standby unchanged.
+//!
+//! Conflicts are last-write-wins in the primary's favour: the standby drops
+//! its rows in the batch's range, and any row sharing a primary key with an
+//! incoming row, before inserting the primary's copies. Replication is
+//! one-way, so the primary is always the later writer.
+//!
+//! Rows are shipped once, when their timestamp passes the watermark; later
+//! in-place updates on the primary are not re-sent, rows with a NULL
+//! timestamp are never sent, and tables without a timestamp column are not
+//! replicated.
+
+use chrono::Utc;
+use duckdb::Connection;
+use serde::{Deserialize, Serialize};
+use vc_config::ReplicationMode;
+
+use crate::{
+    ActorContext, AuditEventType, AuditResult, StoreError, VcStore, escape_sql_identifier,
+    escape_sql_literal, json_value_to_sql,
+};
+
+/// Tables holding this node's own replication, export, query-log and
+/// planner state
+const LOCAL_TABLES: &[&str] = &[
+    "replication_watermarks",
+    "replication_promotions",
+    "telemetry_export_state",
+    "query_log",
+    "table_stats",
+    "query_plans",
+    "leases",
+];
+
+/// Which end of replication a watermark describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatermarkSide {
+    /// Last timestamp the standby acknowledged, kept on the primary
+    Shipped,
+    /// Last timestamp applied, kept on the standby
+    Applied,
+}
+
+impl WatermarkSide {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Shipped => "shipped",
+            Self::Applied => "applied",
+        }
+    }
+
+    /// Side whose watermarks describe a node running as `mode`
+    #[must_use]
+    pub fn for_mode(mode: ReplicationMode) -> Self {
+        match mode {
+            ReplicationMode::Primary => Self::Shipped,
+            ReplicationMode::Standby => Self::Applied,
+        }
+    }
+}
+
+/// Rows of one table with timestamps in `(after, through]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableBatch {
+    pub table: String,
+    pub ts_column: String,
+    /// Exclusive lower bound; `None` when the table has never been shipped
+    pub after: Option<String>,
+    /// Inclusive upper bound
+    pub through: String,
+    pub rows: Vec<serde_json::Value>,
+    /// Newest timestamp in the table on the primary
+    pub source_head: Option<String>,
+    /// Rows on the primary newer than `through`
+    pub rows_pending: i64,
+}
+
+/// One shipment from a primary
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationBatch {
+    /// Name of the shipping host
+    pub source: String,
+    pub tables: Vec<TableBatch>,
+}
+
+impl ReplicationBatch {
+    #[must_use]
+    pub fn row_count(&self) -> usize {
+        self.tables.iter().map(|t| t.rows.len()).sum()
+    }
+
+    /// Whether any table has rows beyond this batch
+    #[must_use]
+    pub fn has_more(&self) -> bool {
+        self.tables.iter().any(|t| t.rows_pending > 0)
+    }
+}
+
+/// Standby's answer for one table of a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableAck {
+    pub table: String,
+    /// Standby's applied watermark after this batch; the primary resumes
+    /// shipping from here
+    pub watermark: Option<String>,
+    pub rows_applied: usize,
+    pub error: Option<String>,
+}
+
+/// Standby's answer to a [`ReplicationBatch`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplicationAck {
+    pub tables: Vec<TableAck>,
+}
+
+/// Replication progress of one table
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplicationTableStatus {
+    pub table: String,
+    pub ts_column: String,
+    pub watermark: Option<String>,
+    /// Newest timestamp on the primary (live on a primary, as last reported
+    /// on a standby)
+    pub source_head: Option<String>,
+    /// Rows on the primary newer than the watermark
+    pub rows_pending: i64,
+    /// Rows shipped or applied so far
+    pub rows_total: i64,
+    /// Seconds between the watermark and `source_head`
+    pub lag_secs: Option<i64>,
+    pub updated_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+/// A standby promoted to primary with `vc db promote`
+#[derive(Debug, Clone, Serialize)]
+pub struct Promotion {
+    pub promoted_at: String,
+    pub promoted_by: String,
+}
+
+impl VcStore {
+    /// Tables that replicate, with their watermark column. `only` limits the
+    /// set when non-empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the catalog query fails.
+    pub fn replicated_tables(&self, only: &[String]) -> Result<Vec<(String, String)>, StoreError> {
+        let mut tables = Vec::new();
+        for table in self.list_tables()? {
+            if LOCAL_TABLES.contains(&table.as_str())
+                || (!only.is_empty() && !only.contains(&table))
+            {
+                continue;
+            }
+            if let Some(column) = self.guess_timestamp_column(&table) {
+                tables.push((table, column));
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Watermark recorded for `table`, if it has replicated before
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn replication_watermark(
+        &self,
+        side: WatermarkSide,
+        table: &str,
+    ) -> Result<Option<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row(
+            "SELECT watermark FROM replication_watermarks WHERE side = ? AND table_name = ?",
+            duckdb::params![side.as_str(), table],
+            |row| row.get::<_, Option<String>>(0),
+        ) {
+            Ok(watermark) => Ok(watermark),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Next shipment: up to about `limit` rows per table past each table's
+    /// shipped watermark. Tables with nothing new are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if reading any table fails.
+    pub fn build_replication_batch(
+        &self,
+        source: &str,
+        only: &[String],
+        limit: usize,
+    ) -> Result<ReplicationBatch, StoreError> {
+        let mut batch = ReplicationBatch {
+            source: source.to_string(),
+            tables: Vec::new(),
+        };
+        for (table, column) in self.replicated_tables(only)? {
+            let after = self.replication_watermark(WatermarkSide::Shipped, &table)?;
+            if let Some(table_batch) =
+                self.table_batch(&table, &column, after.as_deref(), limit.max(1))?
+            {
+                batch.tables.push(table_batch);
+            }
+        }
+        Ok(batch)
+    }
+
+    fn table_batch(
+        &self,
+        table: &str,
+        ts_column: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Option<TableBatch>, StoreError> {
+        let safe_table = escape_sql_identifier(table);
+        let column = escape_sql_identifier(ts_column);
+        let newer = after.map_or_else(
+            || format!("\"{column}\" IS NOT NULL"),
+            |after| format!("\"{column}\" > '{}'", escape_sql_literal(after)),
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let (head, pending): (Option<String>, i64) = conn.query_row(
+            &format!(
+                "SELECT CAST(MAX(\"{column}\") AS VARCHAR), COUNT(*) FROM \"{safe_table}\" WHERE {newer}"
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let Some(head) = head else {
+            return Ok(None);
+        };
+        // Cut at the limit-th timestamp but keep every row sharing it, so
+        // rows with equal timestamps never straddle two batches
+        let through: String = if usize::try_from(pending).unwrap_or(usize::MAX) <= limit {
+            head.clone()
+        } else {
+            conn.query_row(
+                &format!(
+                    "SELECT CAST(\"{column}\" AS VARCHAR) FROM \"{safe_table}\" WHERE {newer} \
+                     ORDER BY \"{column}\" LIMIT 1 OFFSET {}",
+                    limit - 1
+                ),
+                [],
+                |row| row.get(0),
+            )?
+        };
+        drop(conn);
+
+        let rows = self.query_json(&format!(
+            "SELECT * FROM \"{safe_table}\" WHERE {newer} AND \"{column}\" <= '{}' \
+             ORDER BY \"{column}\"",
+            escape_sql_literal(&through)
+        ))?;
+        let shipped = i64::try_from(rows.len()).unwrap_or(i64::MAX);
+        Ok(Some(TableBatch {
+            table: table.to_string(),
+            ts_column: ts_column.to_string(),
+            after: after.map(str::to_string),
+            through,
+            rows,
+            source_head: Some(head),
+            rows_pending: (pending - shipped).max(0),
+        }))
+    }
+
+    /// Apply a shipment on the standby. Each table is applied in its own
+    /// transaction; a table that fails is reported in the ack with the
+    /// watermark the primary should resume from.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] only if a failed table's watermark cannot be
+    /// read back.
+    pub fn apply_replication_batch(
+        &self,
+        batch: &ReplicationBatch,
+    ) -> Result<ReplicationAck, StoreError> {
+        let mut ack = ReplicationAck::default();
+        for table_batch in &batch.tables {
+            let table_ack = match self.apply_table_batch(table_batch) {
+                Ok((watermark, rows_applied)) => TableAck {
+                    table: table_batch.table.clone(),
+                    watermark: Some(watermark),
+                    rows_applied,
+                    error: None,
+                },
+                Err(e) => {
+                    tracing::warn!(
+                        table = %table_batch.table,
+                        source = %batch.source,
+                        error = %e,
+                        "replicated batch rejected"
+                    );
+                    TableAck {
+                        table: table_batch.table.clone(),
+                        watermark: self
+                            .replication_watermark(WatermarkSide::Applied, &table_batch.table)?,
+                        rows_applied: 0,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            ack.tables.push(table_ack);
+        }
+        Ok(ack)
+    }
+
+    /// Replace the batch's range with its rows; returns the new watermark and
+    /// the number of rows written
+    fn apply_table_batch(&self, batch: &TableBatch) -> Result<(String, usize), StoreError> {
+        if LOCAL_TABLES.contains(&batch.table.as_str()) || !self.has_table(&batch.table)? {
+            return Err(StoreError::QueryError(format!(
+                "table {} is not replicated here",
+                batch.table
+            )));
+        }
+        let applied = self.replication_watermark(WatermarkSide::Applied, &batch.table)?;
+        // A range starting past what we hold would leave a gap
+        if let Some(after) = &batch.after
+            && applied
+                .as_deref()
+                .is_none_or(|applied| applied < after.as_str())
+        {
+            return Err(StoreError::QueryError(format!(
+                "batch starts after {after} but only {} has been applied",
+                applied.as_deref().unwrap_or("nothing")
+            )));
+        }
+        let watermark = match applied {
+            Some(applied) if applied > batch.through => applied,
+            _ => batch.through.clone(),
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let key = primary_key_columns(&conn, &batch.table)?;
+        conn.execute("BEGIN TRANSACTION", [])?;
+        let result = replace_range(&conn, batch, &key).and_then(|rows| {
+            upsert_watermark(
+                &conn,
+                WatermarkSide::Applied,
+                batch,
+                Some(&watermark),
+                rows,
+                None,
+            )?;
+            Ok(rows)
+        });
+        match result {
+            Ok(rows) => {
+                conn.execute("COMMIT", [])?;
+                Ok((watermark, rows))
+            }
+            Err(e) => {
+                let _ = conn.execute("ROLLBACK", []);
+                Err(e)
+            }
+        }
+    }
+
+    /// Record the standby's answer to a shipment. The standby's watermark is
+    /// authoritative, so a rejected table is re-shipped from where the
+    /// standby actually is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if an update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_replication_ack(
+        &self,
+        batch: &ReplicationBatch,
+        ack: &ReplicationAck,
+    ) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        for sent in &batch.tables {
+            let Some(result) = ack.tables.iter().find(|a| a.table == sent.table) else {
+                continue;
+            };
+            upsert_watermark(
+                &conn,
+                WatermarkSide::Shipped,
+                sent,
+                result.watermark.as_deref(),
+                result.rows_applied,
+                result.error.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Record a shipment that never reached the standby; watermarks stay put.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if an update fails.
+    pub fn record_replication_failure(
+        &self,
+        batch: &ReplicationBatch,
+        error: &str,
+    ) -> Result<(), StoreError> {
+        for sent in &batch.tables {
+            let watermark = self.replication_watermark(WatermarkSide::Shipped, &sent.table)?;
+            let conn = self.conn.lock().unwrap();
+            upsert_watermark(
+                &conn,
+                WatermarkSide::Shipped,
+                sent,
+                watermark.as_deref(),
+                0,
+                Some(error),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Per-table replication progress. On the shipped side, head and backlog
+    /// are read live from each replicated table (limited to `only` when
+    /// non-empty); on the applied side they are as the primary last
+    /// reported them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if a query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn replication_status(
+        &self,
+        side: WatermarkSide,
+        only: &[String],
+    ) -> Result<Vec<ReplicationTableStatus>, StoreError> {
+        let mut statuses = {
+            let conn = self.conn.lock().unwrap();
+            let mut stmt = conn.prepare(
+                "SELECT table_name, ts_column, watermark, source_head, rows_pending, \
+                        rows_total, updated_at, last_error \
+                 FROM replication_watermarks WHERE side = ? ORDER BY table_name",
+            )?;
+            let rows = stmt.query_map([side.as_str()], |row| {
+                Ok(ReplicationTableStatus {
+                    table: row.get(0)?,
+                    ts_column: row.get(1)?,
+                    watermark: row.get(2)?,
+                    source_head: row.get(3)?,
+                    rows_pending: row.get(4)?,
+                    rows_total: row.get(5)?,
+                    lag_secs: None,
+                    updated_at: row.get(6)?,
+                    last_error: row.get(7)?,
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        if side == WatermarkSide::Shipped {
+            for (table, ts_column) in self.replicated_tables(only)? {
+                if !statuses.iter().any(|s| s.table == table) {
+                    statuses.push(ReplicationTableStatus {
+                        table,
+                        ts_column,
+                        watermark: None,
+                        source_head: None,
+                        rows_pending: 0,
+                        rows_total: 0,
+                        lag_secs: None,
+                        updated_at: None,
+                        last_error: None,
+                    });
+                }
+            }
+            statuses.sort_by(|a, b| a.table.cmp(&b.table));
+        }
+
+        let conn = self.conn.lock().unwrap();
+        for status in &mut statuses {
+            if side == WatermarkSide::Shipped {
+                let safe_table = escape_sql_identifier(&status.table);
+                let column = escape_sql_identifier(&status.ts_column);
+                let (head, pending) = conn.query_row(
+                    &format!(
+                        "SELECT CAST(MAX(\"{column}\") AS VARCHAR), \
+                                COUNT(*) FILTER (WHERE ? IS NULL OR \"{column}\" > ?) \
+                         FROM \"{safe_table}\""
+                    ),
+                    duckdb::params![status.watermark, status.watermark],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                status.source_head = head;
+                status.rows_pending = pending;
+            }
+            status.lag_secs = conn.query_row(
+                "SELECT date_diff('second', TRY_CAST(? AS TIMESTAMP), TRY_CAST(? AS TIMESTAMP))",
+                duckdb::params![status.watermark, status.source_head],
+                |row| row.get(0),
+            )?;
+        }
+        Ok(statuses)
+    }
+
+    /// Most recent promotion of this store to primary
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn last_promotion(&self) -> Result<Option<Promotion>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        match conn.query_row(
+            "SELECT promoted_at, promoted_by FROM replication_promotions ORDER BY id DESC LIMIT 1",
+            [],
+            |row| {
+                Ok(Promotion {
+                    promoted_at: row.get(0)?,
+                    promoted_by: row.get(1)?,
+                })
+            },
+        ) {
+            Ok(promotion) => Ok(Some(promotion)),
+            Err(duckdb::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Role this store plays: a configured standby becomes primary once it
+    /// has been promoted
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the promotion lookup fails.
+    pub fn replication_role(
+        &self,
+        configured: ReplicationMode,
+    ) -> Result<ReplicationMode, StoreError> {
+        if configured == ReplicationMode::Standby && self.last_promotion()?.is_none() {
+            Ok(ReplicationMode::Standby)
+        } else {
+            Ok(ReplicationMode::Primary)
+        }
+    }
+
+    /// Promote this standby to primary and record it in the audit log along
+    /// with the watermarks it had applied
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the promotion or its audit event cannot be
+    /// written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn promote_to_primary(&self, actor: &ActorContext) -> Result<Promotion, StoreError> {
+        let applied: serde_json::Map<String, serde_json::Value> = self
+            .replication_status(WatermarkSide::Applied, &[])?
+            .into_iter()
+            .map(|s| (s.table, serde_json::json!(s.watermark)))
+            .collect();
+        let promotion = Promotion {
+            promoted_at: Utc::now().to_rfc3339(),
+            promoted_by: actor.name.clone(),
+        };
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "INSERT INTO replication_promotions (id, promoted_at, promoted_by) \
+                 SELECT COALESCE(MAX(id), 0) + 1, ?, ? FROM replication_promotions",
+                duckdb::params![promotion.promoted_at, promotion.promoted_by],
+            )?;
+        }
+        self.insert_audit_event(&actor.audit_event(
+            AuditEventType::UserCommand,
+            "db_promote",
+            AuditResult::Success,
+            serde_json::json!({ "applied_watermarks": applied }),
+        ))?;
+        Ok(promotion)
+    }
+}
+
+fn primary_key_columns(conn: &Connection, table: &str) -> Result<Vec<String>, StoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT unnest(constraint_column_names) FROM duckdb_constraints() \
+         WHERE schema_name = 'main' AND table_name = ? AND constraint_type = 'PRIMARY KEY'",
+    )?;
+    let rows = stmt.query_map([table], |row| row.get::<_, String>(0))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Delete the batch's range and rows it overwrites by key, then insert it
+fn replace_range(
+    conn: &Connection,
+    batch: &TableBatch,
+    key: &[String],
+) -> Result<usize, StoreError> {
+    let safe_table = escape_sql_identifier(&batch.table);
+    let column = escape_sql_identifier(&batch.ts_column);
+    match &batch.after {
+        Some(after) => conn.execute(
+            &format!("DELETE FROM \"{safe_table}\" WHERE \"{column}\" > ? AND \"{column}\" <= ?"),
+            duckdb::params![after, batch.through],
+        )?,
+        None => conn.execute(
+            &format!("DELETE FROM \"{safe_table}\" WHERE \"{column}\" <= ?"),
+            [&batch.through],
+        )?,
+    };
+
+    for row in &batch.rows {
+        let serde_json::Value::Object(map) = row else {
+            return Err(StoreError::QueryError(format!(
+                "replicated row for {} is not an object",
+                batch.table
+            )));
+        };
+
+        // Last write wins: the primary's row replaces ours with the same key
+        let key_values: Option<Vec<&serde_json::Value>> =
+            key.iter().map(|column| map.get(column)).collect();
+        if let Some(key_values) = key_values.filter(|values| !values.is_empty()) {
+            let condition: Vec<String> = key
+                .iter()
+                .map(|column| format!("\"{}\" = ?", escape_sql_identifier(column)))
+                .collect();
+            let params: Vec<Box<dyn duckdb::ToSql>> =
+                key_values.into_iter().map(json_value_to_sql).collect();
+            let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+            conn.execute(
+                &format!(
+                    "DELETE FROM \"{safe_table}\" WHERE {}",
+                    condition.join(" AND ")
+                ),
+                param_refs.as_slice(),
+            )?;
+        }
+
+        let columns: Vec<String> = map
+            .keys()
+            .map(|column| format!("\"{}\"", escape_sql_identifier(column)))
+            .collect();
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let params: Vec<Box<dyn duckdb::ToSql>> = map.values().map(json_value_to_sql).collect();
+        let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+        conn.execute(
+            &format!(
+                "INSERT INTO \"{safe_table}\" ({}) VALUES ({placeholders})",
+                columns.join(", ")
+            ),
+            param_refs.as_slice(),
+        )?;
+    }
+    Ok(batch.rows.len())
+}
+
+fn upsert_watermark(
+    conn: &Connection,
+    side: WatermarkSide,
+    batch: &TableBatch,
+    watermark: Option<&str>,
+    rows_added: usize,
+    error: Option<&str>,
+) -> Result<(), StoreError> {
+    conn.execute(
+        "INSERT INTO replication_watermarks \
+         (side, table_name, ts_column, watermark, source_head, rows_pending, rows_total, updated_at, last_error) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT (side, table_name) DO UPDATE SET \
+            ts_column = excluded.ts_column, \
+            watermark = excluded.watermark, \
+            source_head = excluded.source_head, \
+            rows_pending = excluded.rows_pending, \
+            rows_total = replication_watermarks.rows_total + excluded.rows_total, \
+            updated_at = excluded.updated_at, \
+            last_error = excluded.last_error",
+        duckdb::params![
+            side.as_str(),
+            batch.table,
+            batch.ts_column,
+            watermark,
+            batch.source_head,
+            batch.rows_pending,
+            i64::try_from(rows_added).unwrap_or(i64::MAX),
+            Utc::now().to_rfc3339(),
+            error,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_sample(store: &VcStore, machine: &str, ts: &str, cpu: f64) {
+        store
+            .execute(
+                "INSERT INTO sys_samples (machine_id, collected_at, cpu_total) VALUES (?, ?, ?)",
+                &[machine, ts, &cpu.to_string()],
+            )
+            .unwrap();
+    }
+
+    fn samples(store: &VcStore) -> Vec<(String, String, f64)> {
+        let conn = store.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT machine_id, collected_at, cpu_total FROM sys_samples \
+                 ORDER BY collected_at, machine_id",
+            )
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    fn ship(primary: &VcStore, standby: &VcStore, limit: usize) -> ReplicationBatch {
+        let only = vec!["sys_samples".to_string()];
+        let batch = primary
+            .build_replication_batch("primary", &only, limit)
+            .unwrap();
+        let ack = standby.apply_replication_batch(&batch).unwrap();
+        primary.record_replication_ack(&batch, &ack).unwrap();
+        batch
+    }
+
+    #[test]
+    fn test_replication_ships_in_watermark_order() {
+        let primary = VcStore::open_memory().unwrap();
+        let standby = VcStore::open_memory().unwrap();
+        insert_sample(&primary, "m1", "2026-10-16 10:00:00", 1.0);
+        insert_sample(&primary, "m2", "2026-10-16 10:00:00", 2.0);
+        insert_sample(&primary, "m1", "2026-10-16 10:01:00", 3.0);
+
+        // Both rows at 10:00 travel together even though the limit is one
+        let first = ship(&primary, &standby, 1);
+        assert_eq!(first.row_count(), 2);
+        assert!(first.has_more());
+        assert_eq!(samples(&standby).len(), 2);
+
+        let second = ship(&primary, &standby, 1);
+        assert_eq!(
+            second.tables[0].after.as_deref(),
+            Some("2026-10-16 10:00:00")
+        );
+        assert!(!second.has_more());
+        assert_eq!(samples(&standby), samples(&primary));
+
+        let empty = ship(&primary, &standby, 1);
+        assert_eq!(empty.row_count(), 0);
+
+        let shipped = primary
+            .replication_status(WatermarkSide::Shipped, &["sys_samples".to_string()])
+            .unwrap();
+        assert_eq!(shipped[0].watermark.as_deref(), Some("2026-10-16 10:01:00"));
+        assert_eq!(shipped[0].rows_pending, 0);
+        assert_eq!(shipped[0].lag_secs, Some(0));
+
+        let applied = standby
+            .replication_status(WatermarkSide::Applied, &[])
+            .unwrap();
+        assert_eq!(applied[0].rows_total, 3);
+    }
+
+    #[test]
+    fn test_replication_apply_is_idempotent() {
+        let primary = VcStore::open_memory().unwrap();
+        let standby = VcStore::open_memory().unwrap();
+        insert_sample(&primary, "m1", "2026-10-16 10:00:00", 1.0);
+        insert_sample(&primary, "m1", "2026-10-16 10:01:00", 2.0);
+
+        let batch = primary
+            .build_replication_batch("primary", &["sys_samples".to_string()], 100)
+            .unwrap();
+        standby.apply_replication_batch(&batch).unwrap();
+        let again = standby.apply_replication_batch(&batch).unwrap();
+        assert!(again.tables[0].error.is_none());
+        assert_eq!(samples(&standby), samples(&primary));
+    }
+
+    #[test]
+    fn test_replication_conflicts_are_last_write_wins() {
+        let primary = VcStore::open_memory().unwrap();
+        let standby = VcStore::open_memory().unwrap();
+        insert_sample(&primary, "m1", "2026-10-16 10:00:00", 1.0);
+        // The standby wrote its own copy of the same key and a stray row in
+        // the shipped range; the primary's version replaces both
+        insert_sample(&standby, "m1", "2026-10-16 10:00:00", 99.0);
+        insert_sample(&standby, "m9", "2026-10-16 09:00:00", 5.0);
+
+        ship(&primary, &standby, 100);
+        assert_eq!(
+            samples(&standby),
+            vec![("m1".to_string(), "2026-10-16 10:00:00".to_string(), 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_replication_gap_rewinds_primary() {
+        let primary = VcStore::open_memory().unwrap();
+        let standby = VcStore::open_memory().unwrap();
+        insert_sample(&primary, "m1", "2026-10-16 10:00:00", 1.0);
+        ship(&primary, &standby, 100);
+
+        // A fresh standby cannot take a batch that assumes earlier rows
+        let fresh = VcStore::open_memory().unwrap();
+        insert_sample(&primary, "m1", "2026-10-16 10:01:00", 2.0);
+        let batch = ship(&primary, &fresh, 100);
+        assert_eq!(batch.row_count(), 1);
+        assert!(samples(&fresh).is_empty());
+        assert_eq!(
+            primary
+                .replication_watermark(WatermarkSide::Shipped, "sys_samples")
+                .unwrap(),
+            None
+        );
+
+        // The primary re-ships from the start
+        ship(&primary, &fresh, 100);
+        assert_eq!(samples(&fresh), samples(&primary));
+    }
+
+    #[test]
+    fn test_promotion_flips_role_and_audits() {
+        let store = VcStore::open_memory().unwrap();
+        assert_eq!(
+            store.replication_role(ReplicationMode::Standby).unwrap(),
+            ReplicationMode::Standby
+        );
+        assert_eq!(
+            store.replication_role(ReplicationMode::Primary).unwrap(),
+            ReplicationMode::Primary
+        );
+
+        let promotion = store
+            .promote_to_primary(&ActorContext::system("ops"))
+            .unwrap();
+        assert_eq!(promotion.promoted_by, "ops");
+        assert_eq!(
+            store.replication_role(ReplicationMode::Standby).unwrap(),
+            ReplicationMode::Primary
+        );
+
+        let audited: i64 = store
+            .query_scalar(
+                "SELECT COUNT(*) FROM audit_events WHERE action = 'db_promote' AND actor = 'ops'",
+            )
+            .unwrap();
+        assert_eq!(audited, 1);
+    }
+}
@@ -0,0 +1,106 @@
+//! Typed rows from [`VcStore::query_json`]
+//!
+//! Store types that mirror a table row convert from the JSON object
+//! `query_json` returns for that row with [`TryFrom`], so code running its
+//! own `SELECT` does not pick fields out by hand. Columns the type does not
+//! know are ignored; booleans kept as `INTEGER` columns read as booleans.
+
+use serde::{Deserialize, Deserializer};
+
+#[cfg(doc)]
+use crate::VcStore;
+use crate::{
+    ClockSkewSample, CollectorHealth, MachineServiceStatus, MaintenanceWindow, ToolVersion,
+};
+
+macro_rules! impl_try_from_row {
+    ($($row:ty),+ $(,)?) => {
+        $(
+            impl TryFrom<serde_json::Value> for $row {
+                type Error = serde_json::Error;
+
+                fn try_from(row: serde_json::Value) -> Result<Self, Self::Error> {
+                    serde_json::from_value(row)
+                }
+            }
+        )+
+    };
+}
+
+impl_try_from_row!(
+    ClockSkewSample,
+    CollectorHealth,
+    MachineServiceStatus,
+    MaintenanceWindow,
+    ToolVersion,
+);
+
+/// A boolean stored as `0`/`1` or as a real boolean
+pub(crate) fn boolish<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Boolish {
+        Bool(bool),
+        Int(i64),
+    }
+    Ok(match Boolish::deserialize(deserializer)? {
+        Boolish::Bool(value) => value,
+        Boolish::Int(value) => value != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorContext, VcStore};
+
+    #[test]
+    fn test_rows_convert_to_store_types() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO collector_health \
+                   (machine_id, collector, collected_at, success, rows_inserted, bytes_parsed, \
+                    error_cause) \
+                 VALUES ('m1', 'sysmoni', '2026-10-16T00:00:00Z', 0, 0, 0, 'timeout'); \
+                 INSERT INTO machine_services \
+                   (machine_id, service_name, kind, target, required, running, checked_at) \
+                 VALUES ('m1', 'nginx', 'unit', 'nginx.service', 1, 0, '2026-10-16T00:00:00Z');",
+            )
+            .unwrap();
+        store
+            .start_maintenance(
+                "m1",
+                Some("disk swap"),
+                None,
+                &ActorContext::cli(Some("op")),
+            )
+            .unwrap();
+
+        let row = store
+            .query_json("SELECT * FROM collector_health")
+            .unwrap()
+            .remove(0);
+        let health = CollectorHealth::try_from(row).unwrap();
+        assert!(!health.success);
+        assert_eq!(health.error_cause.as_deref(), Some("timeout"));
+
+        let row = store
+            .query_json("SELECT * FROM machine_services")
+            .unwrap()
+            .remove(0);
+        let service = MachineServiceStatus::try_from(row).unwrap();
+        assert!(service.required);
+        assert!(!service.running);
+
+        let row = store
+            .query_json("SELECT * FROM machine_maintenance")
+            .unwrap()
+            .remove(0);
+        let window = MaintenanceWindow::try_from(row).unwrap();
+        assert_eq!(window.reason.as_deref(), Some("disk swap"));
+        assert!(window.ended_at.is_none());
+
+        assert!(ToolVersion::try_from(serde_json::json!({ "machine_id": "m1" })).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! Guardian sandbox runs
+//!
+//! `vc guardian run --sandbox` keeps each dry report it produces, so a
+//! reviewer can look at it later and an approver can attach the one made
+//! for the revision they approve. The report itself is built by
+//! `vc_guardian::sandbox`; the store keeps it as JSON next to the columns
+//! runs are looked up by.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{StoreError, VcStore};
+
+/// One stored sandbox run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SandboxRunRecord {
+    pub sandbox_id: String,
+    pub playbook_id: String,
+    /// Set when the playbook walked was a draft
+    pub draft_id: Option<String>,
+    pub draft_revision: Option<i64>,
+    pub machine_id: String,
+    /// Instant the recorded state is from
+    pub as_of: String,
+    pub report: serde_json::Value,
+    pub created_by: Option<String>,
+    pub created_at: String,
+}
+
+const COLUMNS: &str = "sandbox_id, playbook_id, draft_id, draft_revision, machine_id, as_of, \
+                       report_json, created_by, created_at";
+
+fn map_run(row: &duckdb::Row<'_>) -> duckdb::Result<SandboxRunRecord> {
+    let report: String = row.get(6)?;
+    Ok(SandboxRunRecord {
+        sandbox_id: row.get(0)?,
+        playbook_id: row.get(1)?,
+        draft_id: row.get(2)?,
+        draft_revision: row.get(3)?,
+        machine_id: row.get(4)?,
+        as_of: row.get(5)?,
+        report: serde_json::from_str(&report).unwrap_or(serde_json::Value::Null),
+        created_by: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+impl VcStore {
+    /// Store a sandbox run
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the insert fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn record_sandbox_run(&self, run: &SandboxRunRecord) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            &format!(
+                "INSERT INTO guardian_sandbox_runs ({COLUMNS}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            ),
+            duckdb::params![
+                run.sandbox_id,
+                run.playbook_id,
+                run.draft_id,
+                run.draft_revision,
+                run.machine_id,
+                run.as_of,
+                run.report.to_string(),
+                run.created_by,
+                run.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Look up a sandbox run
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the query fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn sandbox_run(&self, sandbox_id: &str) -> Result<Option<SandboxRunRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM guardian_sandbox_runs WHERE sandbox_id = ?"
+        ))?;
+        let mut rows = stmt.query_map([sandbox_id], map_run)?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Sandbox runs of a playbook or draft, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query preparation, execution, or row decoding fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn list_sandbox_runs(
+        &self,
+        playbook_id: &str,
+        limit: usize,
+    ) -> Result<Vec<SandboxRunRecord>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM guardian_sandbox_runs \
+             WHERE playbook_id = ? OR draft_id = ? \
+             ORDER BY created_at DESC, sandbox_id LIMIT ?"
+        ))?;
+        let rows = stmt.query_map(
+            duckdb::params![
+                playbook_id,
+                playbook_id,
+                i64::try_from(limit).unwrap_or(i64::MAX)
+            ],
+            map_run,
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Attach a sandbox run to an approved draft's approval. Returns `false`
+    /// if the draft is not approved.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the update fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn attach_sandbox_run_to_draft(
+        &self,
+        draft_id: &str,
+        sandbox_id: &str,
+    ) -> Result<bool, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(
+            "UPDATE playbook_drafts SET approved_sandbox_run = ? \
+             WHERE draft_id = ? AND status = 'approved'",
+            duckdb::params![sandbox_id, draft_id],
+        )?;
+        Ok(affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(sandbox_id: &str, draft_id: Option<&str>, created_at: &str) -> SandboxRunRecord {
+        SandboxRunRecord {
+            sandbox_id: sandbox_id.to_string(),
+            playbook_id: draft_id.unwrap_or("tmp-cleanup").to_string(),
+            draft_id: draft_id.map(ToString::to_string),
+            draft_revision: draft_id.map(|_| 2),
+            machine_id: "orko".to_string(),
+            as_of: "2026-10-16T12:00:00Z".to_string(),
+            report: serde_json::json!({"steps": [{"index": 1}]}),
+            created_by: Some("alice".to_string()),
+            created_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_list_and_attach_sandbox_runs() {
+        let store = VcStore::open_memory().unwrap();
+        let first = run("sbx-1", Some("draft-1"), "2026-10-16T12:00:00Z");
+        store.record_sandbox_run(&first).unwrap();
+        store
+            .record_sandbox_run(&run("sbx-2", Some("draft-1"), "2026-10-16T13:00:00Z"))
+            .unwrap();
+        store
+            .record_sandbox_run(&run("sbx-3", None, "2026-10-16T14:00:00Z"))
+            .unwrap();
+
+        assert_eq!(store.sandbox_run("sbx-1").unwrap(), Some(first));
+        assert_eq!(store.sandbox_run("sbx-missing").unwrap(), None);
+        let ids: Vec<String> = store
+            .list_sandbox_runs("draft-1", 10)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.sandbox_id)
+            .collect();
+        assert_eq!(ids, vec!["sbx-2", "sbx-1"]);
+
+        store
+            .execute_batch(
+                "INSERT INTO playbook_drafts (draft_id, name, alert_type, trigger_json, steps_json) \
+                 VALUES ('draft-1', 'Cleanup', 'disk-full', '{}', '[]')",
+            )
+            .unwrap();
+        // Only an approved draft takes an attachment
+        assert!(
+            !store
+                .attach_sandbox_run_to_draft("draft-1", "sbx-2")
+                .unwrap()
+        );
+        store
+            .execute_batch("UPDATE playbook_drafts SET status = 'approved'")
+            .unwrap();
+        assert!(
+            store
+                .attach_sandbox_run_to_draft("draft-1", "sbx-2")
+                .unwrap()
+        );
+        let draft = store.get_playbook_draft("draft-1").unwrap().unwrap();
+        assert_eq!(draft["approved_sandbox_run"], "sbx-2");
+    }
+}
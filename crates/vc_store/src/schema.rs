@@ -14,6 +14,7 @@ pub mod tables {
     pub const REPO_STATUS_SNAPSHOTS: &str = "repo_status_snapshots";
     pub const ACCOUNT_USAGE_SNAPSHOTS: &str = "account_usage_snapshots";
     pub const ACCOUNT_PROFILE_SNAPSHOTS: &str = "account_profile_snapshots";
+    pub const ACCOUNT_STATUS: &str = "account_status";
     pub const AGENT_SESSIONS: &str = "agent_sessions";
     pub const MAIL_MESSAGES: &str = "mail_messages";
     pub const MAIL_FILE_RESERVATIONS: &str = "mail_file_reservations";
@@ -60,6 +61,8 @@ pub mod tables {
     pub const PT_PROCESSES: &str = "pt_processes";
     pub const PT_SNAPSHOTS: &str = "pt_snapshots";
     pub const GH_REPO_ISSUE_PR_SNAPSHOT: &str = "gh_repo_issue_pr_snapshot";
+    pub const HTTP_CHECK_RESULTS: &str = "http_check_results";
+    pub const AGENTS_INVENTORY: &str = "agents_inventory";
 }
 
 /// Common column names
@@ -0,0 +1,643 @@
+//! Synthetic fleet data for demos, integration tests and load testing
+//!
+//! [`seed_store`] fills a store with a deterministic fleet: machines with
+//! varied tags and statuses, `sys_samples` following a daily load curve,
+//! alerts across severities, a couple of incidents with timelines, agent
+//! sessions with transcripts and collector health with some failures. The
+//! same options always produce the same rows, so tests can assert on them.
+//!
+//! Everything is written through the store's normal write helpers, so the
+//! schema's constraints are exercised exactly as collected data would be.
+//! Samples are generated and written in batches, which keeps the `load`
+//! profile's millions of rows out of memory.
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, SecondsFormat, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{CollectorHealth, FiredAlert, StoreError, VcStore};
+
+/// Rows written per `sys_samples` batch
+const SAMPLE_BATCH: usize = 10_000;
+
+/// Tables a seeded store writes to; any rows in them make a store non-empty
+pub const SEEDED_TABLES: &[&str] = &[
+    "machines",
+    "sys_samples",
+    "alert_history",
+    "incidents",
+    "agent_sessions",
+    "collector_health",
+];
+
+const ROLES: &[&str] = &["build", "gpu", "web", "db", "agents"];
+const REGIONS: &[&str] = &["us-east", "us-west", "eu-central"];
+const COLLECTORS: &[&str] = &["sysmoni", "cass", "caut", "ntm"];
+const FAILURE_CAUSES: &[&str] = &["timeout", "auth_failed", "parse_error", "connect_refused"];
+const PROGRAMS: &[(&str, &str)] = &[
+    ("claude-code", "claude-sonnet"),
+    ("codex", "gpt-5-codex"),
+    ("gemini", "gemini-2.5-pro"),
+];
+const ALERT_RULES: &[(&str, &str)] = &[
+    ("cpu_high", "CPU above 90% for 5 minutes"),
+    ("memory_pressure", "Available memory below 10%"),
+    ("disk_full", "Root filesystem above 95%"),
+    (
+        "collector_stale",
+        "Collector has not reported in 30 minutes",
+    ),
+    ("rate_limited", "Agent account hit its rate limit"),
+];
+const COMMANDS: &[&str] = &[
+    "cargo test --workspace",
+    "git status",
+    "df -h /",
+    "sudo systemctl restart vc-node",
+    "cargo build --release",
+    "rg TODO src/",
+];
+
+/// What a seeded store is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedProfile {
+    /// Five-minute samples and plenty of alerts and sessions to click through
+    #[default]
+    Demo,
+    /// Half-hourly samples and short transcripts; small and fast for tests
+    Test,
+    /// Two-second samples: millions of rows for performance testing
+    Load,
+}
+
+impl SeedProfile {
+    fn sample_interval(self) -> Duration {
+        match self {
+            SeedProfile::Demo => Duration::minutes(5),
+            SeedProfile::Test => Duration::minutes(30),
+            SeedProfile::Load => Duration::seconds(2),
+        }
+    }
+
+    fn sessions_per_day(self) -> u64 {
+        match self {
+            SeedProfile::Demo | SeedProfile::Load => 4,
+            SeedProfile::Test => 1,
+        }
+    }
+
+    fn alerts_per_day(self) -> u64 {
+        match self {
+            SeedProfile::Demo | SeedProfile::Load => 3,
+            SeedProfile::Test => 1,
+        }
+    }
+
+    fn transcript_turns(self) -> u64 {
+        match self {
+            SeedProfile::Demo | SeedProfile::Load => 12,
+            SeedProfile::Test => 3,
+        }
+    }
+}
+
+impl std::str::FromStr for SeedProfile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "demo" => Ok(Self::Demo),
+            "test" => Ok(Self::Test),
+            "load" => Ok(Self::Load),
+            other => Err(format!("profile must be demo, test or load: {other}")),
+        }
+    }
+}
+
+/// Shape of the generated fleet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedOptions {
+    pub profile: SeedProfile,
+    pub machines: usize,
+    /// Days of history, ending at `until`
+    pub days: u32,
+    /// Same seed and `until`, same rows
+    pub seed: u64,
+    /// Seed a store that already holds data
+    pub force: bool,
+    /// End of the generated history
+    pub until: DateTime<Utc>,
+}
+
+impl Default for SeedOptions {
+    /// Five machines, a week of history ending at the current hour
+    fn default() -> Self {
+        let now = Utc::now();
+        Self {
+            profile: SeedProfile::Demo,
+            machines: 5,
+            days: 7,
+            seed: 42,
+            force: false,
+            until: now.duration_trunc(Duration::hours(1)).unwrap_or(now),
+        }
+    }
+}
+
+/// Rows written by [`seed_store`], per table
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedReport {
+    pub profile: SeedProfile,
+    pub seed: u64,
+    pub machines: usize,
+    pub sys_samples: usize,
+    pub alerts: usize,
+    pub incidents: usize,
+    pub incident_events: usize,
+    pub sessions: usize,
+    pub collector_health: usize,
+}
+
+/// splitmix64: tiny, fast and identical on every platform
+#[derive(Debug, Clone)]
+struct SeedRng(u64);
+
+impl SeedRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`
+    #[allow(clippy::cast_precision_loss)]
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `[0, n)`
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[usize::try_from(self.below(items.len() as u64)).unwrap_or(0)]
+    }
+
+    /// Whether an event with probability `p` happens
+    fn chance(&mut self, p: f64) -> bool {
+        self.unit() < p
+    }
+}
+
+/// One generated machine and the parameters its samples are drawn from
+struct SeedMachine {
+    id: String,
+    /// Mean CPU percent at the daily peak
+    peak_cpu: f64,
+    cores: i64,
+    mem_total: i64,
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// How busy a fleet is at `at`: near 0 overnight, 1 mid-afternoon, damped
+/// at weekends
+fn daily_load(at: DateTime<Utc>) -> f64 {
+    let hour = f64::from(at.hour()) + f64::from(at.minute()) / 60.0;
+    let curve = (((hour - 8.0) / 24.0) * std::f64::consts::TAU)
+        .sin()
+        .max(0.0);
+    if at.weekday().number_from_monday() >= 6 {
+        curve * 0.4
+    } else {
+        curve
+    }
+}
+
+/// Rows in the tables a seed writes to
+///
+/// # Errors
+///
+/// Returns [`StoreError`] if a row count query fails.
+pub fn seeded_rows(store: &VcStore) -> Result<i64, StoreError> {
+    SEEDED_TABLES
+        .iter()
+        .try_fold(0, |total, table| Ok(total + store.table_row_count(table)?))
+}
+
+/// Fill `store` with a synthetic fleet.
+///
+/// # Errors
+///
+/// Returns [`StoreError::QueryError`] if the store already holds data and
+/// `options.force` is unset, or [`StoreError`] if a write fails.
+pub fn seed_store(store: &VcStore, options: &SeedOptions) -> Result<SeedReport, StoreError> {
+    let existing = seeded_rows(store)?;
+    if existing > 0 && !options.force {
+        return Err(StoreError::QueryError(format!(
+            "store is not empty ({existing} rows in {}); refusing to seed without force",
+            SEEDED_TABLES.join(", ")
+        )));
+    }
+
+    let mut rng = SeedRng(options.seed);
+    let start = options.until - Duration::days(i64::from(options.days));
+    let mut report = SeedReport {
+        profile: options.profile,
+        seed: options.seed,
+        ..SeedReport::default()
+    };
+
+    let machines = seed_machines(store, options, &mut rng)?;
+    report.machines = machines.len();
+    for machine in &machines {
+        report.sys_samples += seed_samples(store, options, machine, start, &mut rng)?;
+        report.collector_health += seed_collector_health(store, options, machine, start, &mut rng)?;
+        report.sessions += seed_sessions(store, options, machine, start, &mut rng)?;
+        report.alerts += seed_alerts(store, options, machine, start, &mut rng)?;
+    }
+    let (incidents, events) = seed_incidents(store, options, &machines, &mut rng)?;
+    report.incidents = incidents;
+    report.incident_events = events;
+    Ok(report)
+}
+
+fn seed_machines(
+    store: &VcStore,
+    options: &SeedOptions,
+    rng: &mut SeedRng,
+) -> Result<Vec<SeedMachine>, StoreError> {
+    let mut machines = Vec::with_capacity(options.machines);
+    let mut rows = Vec::with_capacity(options.machines);
+    for index in 0..options.machines {
+        let role = ROLES[index % ROLES.len()];
+        let region = rng.pick(REGIONS);
+        let env = if rng.chance(0.7) { "prod" } else { "staging" };
+        // Mostly online, with one offline and one never-probed machine in
+        // any fleet of five or more.
+        let status = match index % 5 {
+            3 => "offline",
+            4 => "unknown",
+            _ => "online",
+        };
+        let id = format!("{role}-{:02}", index + 1);
+        let cores = *rng.pick(&[4_i64, 8, 16, 32, 64]);
+        let mem_total = cores * 4 * 1024 * 1024 * 1024;
+        rows.push(json!({
+            "machine_id": id,
+            "hostname": format!("{id}.{region}.fleet.test"),
+            "display_name": id,
+            "ssh_host": format!("{id}.{region}.fleet.test"),
+            "ssh_user": "vc",
+            "is_local": index == 0,
+            "os_type": if role == "agents" { "darwin" } else { "linux" },
+            "arch": if role == "agents" { "aarch64" } else { "x86_64" },
+            "status": status,
+            "tags": [role, region, env],
+            "enabled": true,
+            "last_seen_at": timestamp(options.until),
+        }));
+        machines.push(SeedMachine {
+            id,
+            peak_cpu: 30.0 + rng.unit() * 55.0,
+            cores,
+            mem_total,
+        });
+    }
+    store.upsert_json("machines", &rows, &["machine_id"])?;
+    Ok(machines)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn seed_samples(
+    store: &VcStore,
+    options: &SeedOptions,
+    machine: &SeedMachine,
+    start: DateTime<Utc>,
+    rng: &mut SeedRng,
+) -> Result<usize, StoreError> {
+    let interval = options.profile.sample_interval();
+    let mut written = 0;
+    let mut batch = Vec::with_capacity(SAMPLE_BATCH);
+    let mut at = start;
+    while at < options.until {
+        let load = daily_load(at);
+        let noise = rng.unit() * 10.0 - 5.0;
+        // The odd spike, so threshold alerts and charts have something to show
+        let spike = if rng.chance(0.002) { 40.0 } else { 0.0 };
+        let cpu = (5.0 + machine.peak_cpu * load + noise + spike).clamp(0.0, 100.0);
+        let load1 = cpu / 100.0 * machine.cores as f64;
+        let mem_used = (machine.mem_total as f64 * (0.3 + 0.5 * load + rng.unit() * 0.05)) as i64;
+        batch.push(json!({
+            "machine_id": machine.id,
+            "collected_at": timestamp(at),
+            "cpu_total": cpu,
+            "load1": load1,
+            "load5": load1 * 0.9,
+            "load15": load1 * 0.8,
+            "mem_used_bytes": mem_used,
+            "mem_total_bytes": machine.mem_total,
+            "mem_available_bytes": machine.mem_total - mem_used,
+            "swap_used_bytes": 0,
+            "swap_total_bytes": machine.mem_total / 4,
+            "disk_read_mbps": rng.unit() * 40.0 * load,
+            "disk_write_mbps": rng.unit() * 25.0 * load,
+            "net_rx_mbps": rng.unit() * 80.0 * load,
+            "net_tx_mbps": rng.unit() * 30.0 * load,
+            "core_count": machine.cores,
+        }));
+        if batch.len() == SAMPLE_BATCH {
+            written += store.upsert_json("sys_samples", &batch, &["machine_id", "collected_at"])?;
+            batch.clear();
+        }
+        at += interval;
+    }
+    written += store.upsert_json("sys_samples", &batch, &["machine_id", "collected_at"])?;
+    Ok(written)
+}
+
+fn seed_collector_health(
+    store: &VcStore,
+    options: &SeedOptions,
+    machine: &SeedMachine,
+    start: DateTime<Utc>,
+    rng: &mut SeedRng,
+) -> Result<usize, StoreError> {
+    let mut written = 0;
+    let mut at = start;
+    while at < options.until {
+        for collector in COLLECTORS {
+            let success = !rng.chance(0.05);
+            let cause = (!success).then(|| (*rng.pick(FAILURE_CAUSES)).to_string());
+            let duration = 50 + i64::try_from(rng.below(2_000)).unwrap_or(0);
+            store.insert_collector_health(&CollectorHealth {
+                machine_id: machine.id.clone(),
+                collector: (*collector).to_string(),
+                collected_at: timestamp(at),
+                success,
+                duration_ms: Some(duration),
+                rows_inserted: if success {
+                    1 + i64::try_from(rng.below(200)).unwrap_or(0)
+                } else {
+                    0
+                },
+                bytes_parsed: if success { 4_096 } else { 0 },
+                error_class: cause.as_ref().map(|c| format!("{collector} failed: {c}")),
+                error_cause: cause,
+                freshness_seconds: Some(if success { 60 } else { 3_600 }),
+                payload_hash: None,
+                collector_version: Some("seed".to_string()),
+                schema_version: Some("1".to_string()),
+                cursor_json: None,
+            })?;
+            written += 1;
+        }
+        at += Duration::hours(1);
+    }
+    Ok(written)
+}
+
+fn seed_sessions(
+    store: &VcStore,
+    options: &SeedOptions,
+    machine: &SeedMachine,
+    start: DateTime<Utc>,
+    rng: &mut SeedRng,
+) -> Result<usize, StoreError> {
+    let count = u64::from(options.days) * options.profile.sessions_per_day();
+    let span = (options.until - start).num_seconds().max(1).unsigned_abs();
+    let mut rows = Vec::new();
+    for index in 0..count {
+        let (program, model) = *rng.pick(PROGRAMS);
+        let started = start + Duration::seconds(i64::try_from(rng.below(span)).unwrap_or(0));
+        let turns = options.profile.transcript_turns();
+        let mut transcript = Vec::new();
+        let mut at = started;
+        for turn in 0..turns {
+            at += Duration::seconds(30 + i64::try_from(rng.below(240)).unwrap_or(0));
+            if turn % 2 == 0 {
+                transcript.push(json!({
+                    "type": "user",
+                    "timestamp": timestamp(at),
+                    "content": format!("Step {}: keep going on the build fix", turn / 2 + 1),
+                }));
+            } else {
+                transcript.push(json!({
+                    "type": "assistant",
+                    "timestamp": timestamp(at),
+                    "content": [{
+                        "type": "tool_use",
+                        "name": "Bash",
+                        "input": { "command": rng.pick(COMMANDS) },
+                    }],
+                }));
+            }
+        }
+        let tokens = 2_000 + rng.below(150_000);
+        #[allow(clippy::cast_precision_loss)]
+        let cost = tokens as f64 * 0.000_006;
+        rows.push(json!({
+            "machine_id": machine.id,
+            "collected_at": timestamp(at),
+            "session_id": format!("seed-{}-{index:04}", machine.id),
+            "program": program,
+            "model": model,
+            "repo_path": format!("/home/vc/src/{}", rng.pick(&["cockpit", "api", "infra"])),
+            "started_at": timestamp(started),
+            "ended_at": (at < options.until).then(|| timestamp(at)),
+            "turn_count": turns,
+            "token_count": tokens,
+            "cost_estimate": cost,
+            "raw_json": serde_json::to_string(&transcript)?,
+        }));
+    }
+    store.upsert_json("agent_sessions", &rows, &["machine_id", "session_id"])
+}
+
+fn seed_alerts(
+    store: &VcStore,
+    options: &SeedOptions,
+    machine: &SeedMachine,
+    start: DateTime<Utc>,
+    rng: &mut SeedRng,
+) -> Result<usize, StoreError> {
+    let count = u64::from(options.days) * options.profile.alerts_per_day();
+    let span = (options.until - start).num_seconds().max(1).unsigned_abs();
+    for _ in 0..count {
+        let (rule_id, title) = *rng.pick(ALERT_RULES);
+        let roll = rng.unit();
+        let severity = if roll < 0.6 {
+            "info"
+        } else if roll < 0.9 {
+            "warning"
+        } else {
+            "critical"
+        };
+        let fired_at = start + Duration::seconds(i64::try_from(rng.below(span)).unwrap_or(0));
+        store.insert_alert(&FiredAlert {
+            rule_id: rule_id.to_string(),
+            fired_at: timestamp(fired_at),
+            severity: severity.to_string(),
+            title: title.to_string(),
+            message: format!("{title} on {}", machine.id),
+            context_json: Some(json!({ "seeded": true }).to_string()),
+            machine_id: Some(machine.id.clone()),
+        })?;
+    }
+    Ok(usize::try_from(count).unwrap_or(usize::MAX))
+}
+
+/// A couple of incidents with timelines; existing ones (a forced reseed)
+/// are left alone
+fn seed_incidents(
+    store: &VcStore,
+    options: &SeedOptions,
+    machines: &[SeedMachine],
+    rng: &mut SeedRng,
+) -> Result<(usize, usize), StoreError> {
+    const INCIDENTS: &[(&str, &str, &[(&str, &str)])] = &[
+        (
+            "Build fleet saturated",
+            "critical",
+            &[
+                ("alert_linked", "cpu_high fired on {machine}"),
+                ("acknowledged", "Acknowledged by on-call"),
+                ("mitigated", "Paused the nightly rebuild"),
+            ],
+        ),
+        (
+            "Collector auth failures",
+            "warning",
+            &[
+                ("alert_linked", "collector_stale fired on {machine}"),
+                ("note", "SSH key rotated without updating the config"),
+            ],
+        ),
+    ];
+
+    let (mut incidents, mut events) = (0, 0);
+    for (index, (title, severity, timeline)) in INCIDENTS.iter().enumerate() {
+        let Some(machine) =
+            machines.get(usize::try_from(rng.below(machines.len() as u64)).unwrap_or(0))
+        else {
+            break;
+        };
+        let incident_id = format!("seed-{}-inc-{}", options.seed, index + 1);
+        if store.get_incident(&incident_id)?.is_some() {
+            continue;
+        }
+        store.create_incident(
+            &incident_id,
+            title,
+            severity,
+            Some(&format!("Seeded incident on {}", machine.id)),
+        )?;
+        incidents += 1;
+        for (event_type, description) in *timeline {
+            store.add_incident_timeline_event(
+                &incident_id,
+                event_type,
+                "seed",
+                &description.replace("{machine}", &machine.id),
+                None,
+            )?;
+            events += 1;
+        }
+    }
+    Ok((incidents, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_options() -> SeedOptions {
+        SeedOptions {
+            profile: SeedProfile::Test,
+            machines: 3,
+            days: 1,
+            seed: 7,
+            force: false,
+            until: DateTime::parse_from_rfc3339("2026-10-14T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    fn fingerprint(store: &VcStore) -> Vec<serde_json::Value> {
+        store
+            .query_json(
+                "SELECT machine_id, collected_at, cpu_total, mem_used_bytes FROM sys_samples \
+                 ORDER BY machine_id, collected_at",
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let a = VcStore::open_memory().unwrap();
+        let b = VcStore::open_memory().unwrap();
+        let report = seed_store(&a, &test_options()).unwrap();
+        assert_eq!(seed_store(&b, &test_options()).unwrap(), report);
+        assert_eq!(fingerprint(&a), fingerprint(&b));
+
+        // Three machines, half-hourly samples over a day
+        assert_eq!(report.machines, 3);
+        assert_eq!(report.sys_samples, 3 * 48);
+        assert_eq!(report.collector_health, 3 * 24 * COLLECTORS.len());
+        assert_eq!(report.incidents, 2);
+
+        let other = VcStore::open_memory().unwrap();
+        let options = SeedOptions {
+            seed: 8,
+            ..test_options()
+        };
+        seed_store(&other, &options).unwrap();
+        assert_ne!(fingerprint(&a), fingerprint(&other));
+    }
+
+    #[test]
+    fn test_seed_refuses_non_empty_store_without_force() {
+        let store = VcStore::open_memory().unwrap();
+        seed_store(&store, &test_options()).unwrap();
+        let err = seed_store(&store, &test_options()).unwrap_err();
+        assert!(err.to_string().contains("not empty"), "{err}");
+
+        let forced = SeedOptions {
+            force: true,
+            ..test_options()
+        };
+        let report = seed_store(&store, &forced).unwrap();
+        // Samples and sessions are replaced in place; incidents are kept
+        assert_eq!(report.incidents, 0);
+        assert_eq!(
+            store.table_row_count("sys_samples").unwrap(),
+            i64::try_from(report.sys_samples).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_samples_follow_the_daily_curve() {
+        let store = VcStore::open_memory().unwrap();
+        seed_store(&store, &test_options()).unwrap();
+        let avg = |hour: &str| -> f64 {
+            store
+                .query_scalar(&format!(
+                    "SELECT AVG(cpu_total) FROM sys_samples \
+                     WHERE substr(collected_at, 12, 2) = '{hour}'"
+                ))
+                .unwrap()
+        };
+        assert!(avg("14") > avg("03") + 10.0);
+        let failures: i64 = store
+            .query_scalar("SELECT COUNT(*) FROM collector_health WHERE success = 0")
+            .unwrap();
+        assert!(failures > 0);
+    }
+}
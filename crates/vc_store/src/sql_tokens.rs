@@ -0,0 +1,209 @@
+//! SQL tokenizer
+//!
+//! Splits SQL into words, quoted text, numbers and symbols the way `DuckDB`
+//! reads it, so checks on a query or a filter see string literals, quoted
+//! identifiers and comments as `DuckDB` does and cannot be fooled by keywords
+//! or parentheses hidden in them. The query guardrails in `vc_query` and the
+//! where clauses of [`crate::corrections`] are checked on these tokens.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Keyword or bare identifier, uppercased
+    Word,
+    /// `"..."` or backtick identifier
+    QuotedIdent,
+    /// `'...'`, `E'...'`, `$$...$$` or `$tag$...$tag$` string literal
+    Str,
+    Number,
+    Symbol(char),
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    /// Byte offset in the query
+    pub offset: usize,
+}
+
+impl Token {
+    /// Whether this is the keyword or bare identifier `word` (uppercase)
+    #[must_use]
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text == word
+    }
+}
+
+/// A literal or comment that is never closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unterminated {
+    /// `comment` or `quoted text`
+    pub construct: &'static str,
+    /// Byte offset where it starts
+    pub offset: usize,
+}
+
+/// Split SQL into tokens, dropping whitespace and comments.
+///
+/// Comments separate tokens, so `SEL/**/ECT` is two words rather than
+/// `SELECT`. Unterminated literals and comments are rejected outright since
+/// there is no telling where they were meant to end.
+///
+/// # Errors
+///
+/// Returns [`Unterminated`] for a literal or comment that is never closed.
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, Unterminated> {
+    let unterminated = |offset: usize, construct: &'static str| Unterminated { construct, offset };
+    // Byte offset just past the first `close` at or after `from`
+    let find_close =
+        |from: usize, close: &str| sql[from..].find(close).map(|i| from + i + close.len());
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < sql.len() {
+        let rest = &sql[i..];
+        let c = rest.chars().next().unwrap_or(' ');
+        if c.is_whitespace() {
+            i += c.len_utf8();
+        } else if rest.starts_with("--") {
+            i = sql[i..].find('\n').map_or(sql.len(), |n| i + n + 1);
+        } else if rest.starts_with("/*") {
+            i = find_close(i + 2, "*/").ok_or_else(|| unterminated(i, "comment"))?;
+        } else if c == '\'' || c == '"' || c == '`' {
+            // Quotes inside are doubled: 'it''s'
+            let mut j = i + 1;
+            loop {
+                let close = sql[j..]
+                    .find(c)
+                    .ok_or_else(|| unterminated(i, "quoted text"))?;
+                j += close + 1;
+                if !sql[j..].starts_with(c) {
+                    break;
+                }
+                j += 1;
+            }
+            tokens.push(Token {
+                kind: if c == '\'' {
+                    TokenKind::Str
+                } else {
+                    TokenKind::QuotedIdent
+                },
+                text: sql[i..j].to_string(),
+                offset: i,
+            });
+            i = j;
+        } else if (c == 'e' || c == 'E') && rest[1..].starts_with('\'') {
+            // Escape string: a backslash escapes the next character too
+            let mut j = i + 2;
+            loop {
+                let close = sql[j..]
+                    .find(['\'', '\\'])
+                    .ok_or_else(|| unterminated(i, "quoted text"))?;
+                j += close + 1;
+                if sql[j - 1..].starts_with('\\') {
+                    j += sql[j..].chars().next().map_or(0, char::len_utf8);
+                } else if sql[j..].starts_with('\'') {
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token {
+                kind: TokenKind::Str,
+                text: sql[i..j].to_string(),
+                offset: i,
+            });
+            i = j;
+        } else if let Some(delimiter) = dollar_quote(rest) {
+            let j = find_close(i + delimiter.len(), delimiter)
+                .ok_or_else(|| unterminated(i, "quoted text"))?;
+            tokens.push(Token {
+                kind: TokenKind::Str,
+                text: sql[i..j].to_string(),
+                offset: i,
+            });
+            i = j;
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '_' || ch == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token {
+                kind: TokenKind::Word,
+                text: rest[..len].to_uppercase(),
+                offset: i,
+            });
+            i += len;
+        } else if c.is_ascii_digit() {
+            let len = rest
+                .find(|ch: char| !(ch.is_alphanumeric() || ch == '.' || ch == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token {
+                kind: TokenKind::Number,
+                text: rest[..len].to_string(),
+                offset: i,
+            });
+            i += len;
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Symbol(c),
+                text: c.to_string(),
+                offset: i,
+            });
+            i += c.len_utf8();
+        }
+    }
+    Ok(tokens)
+}
+
+/// The opening `$$` or `$tag$` of a dollar-quoted string at the start of
+/// `rest`; `$1` is a parameter, not a quote
+fn dollar_quote(rest: &str) -> Option<&str> {
+    let tag = rest.strip_prefix('$')?;
+    let len = tag.find('$')?;
+    let name = &tag[..len];
+    let is_tag = name.is_empty()
+        || (name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    is_tag.then(|| &rest[..len + 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<TokenKind> {
+        tokenize(sql).unwrap().iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn test_quoted_text_hides_symbols() {
+        for sql in [
+            "'a '' ) b'",
+            "$$ ' ) $$",
+            "$q$ $$ ) $q$",
+            "E'\\' ) '",
+            "\"a \"\" ) b\"",
+        ] {
+            let tokens = tokenize(sql).unwrap();
+            assert_eq!(tokens.len(), 1, "{sql}");
+            assert_eq!(tokens[0].text, sql);
+        }
+        assert_eq!(
+            kinds("x = $1 -- )\n/* ( */"),
+            vec![
+                TokenKind::Word,
+                TokenKind::Symbol('='),
+                TokenKind::Symbol('$'),
+                TokenKind::Number,
+            ]
+        );
+        assert_eq!(
+            tokenize("$a$ ) ").unwrap_err(),
+            Unterminated {
+                construct: "quoted text",
+                offset: 0,
+            }
+        );
+        assert_eq!(tokenize("1 /* (").unwrap_err().construct, "comment");
+    }
+}
@@ -0,0 +1,196 @@
+//! `SQLite` fallback backend for constrained nodes
+//!
+//! Built only with the `sqlite` feature. Runs the same migrations as
+//! `DuckDB` (they are already written in the `SQLite`-compatible dialect, see
+//! [`crate::migrations`]) and implements the generic [`StoreBackend`]
+//! surface; `DuckDB`-only domain methods stay on [`crate::VcStore`].
+
+use crate::backend::{BackendKind, StoreBackend};
+use crate::{StoreError, escape_sql_identifier, escape_sql_literal};
+use rusqlite::Connection;
+use rusqlite::types::ValueRef;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{info, instrument};
+
+/// `SQLite`-backed store
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+    db_path: String,
+}
+
+impl SqliteStore {
+    /// Open or create a `SQLite` database at path and run migrations
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if directory creation, database opening, or
+    /// migration execution fails.
+    #[instrument]
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        info!(path = %path.display(), "Opening SQLite database");
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path)?;
+        // WAL keeps readers off the writer's back on slow flash storage. The
+        // pragma answers with the resulting mode, so it has to be queried.
+        conn.query_row("PRAGMA journal_mode=WAL", [], |_| Ok(()))?;
+
+        let store = Self {
+            conn: Mutex::new(conn),
+            db_path: path.to_string_lossy().to_string(),
+        };
+        crate::backend::migrate(&store)?;
+        Ok(store)
+    }
+}
+
+impl StoreBackend for SqliteStore {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Sqlite
+    }
+
+    fn db_path(&self) -> &str {
+        &self.db_path
+    }
+
+    fn execute(&self, sql: &str, params: &[&str]) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let affected = conn.execute(sql, rusqlite::params_from_iter(params.iter()))?;
+        Ok(affected)
+    }
+
+    fn execute_batch(&self, sql: &str) -> Result<(), StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(sql)?;
+        Ok(())
+    }
+
+    fn query_json(&self, sql: &str) -> Result<Vec<serde_json::Value>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let columns: Vec<String> = stmt
+            .column_names()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+
+        let mut rows = stmt.query([])?;
+        let mut results = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut object = serde_json::Map::with_capacity(columns.len());
+            for (index, column) in columns.iter().enumerate() {
+                object.insert(column.clone(), value_ref_to_json(row.get_ref(index)?));
+            }
+            results.push(serde_json::Value::Object(object));
+        }
+        Ok(results)
+    }
+
+    fn insert_json_batch(
+        &self,
+        table: &str,
+        rows: &[serde_json::Value],
+    ) -> Result<usize, StoreError> {
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut count = 0;
+        for row in rows {
+            if let serde_json::Value::Object(map) = row {
+                let columns: Vec<&str> = map.keys().map(String::as_str).collect();
+                let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
+
+                let sql = format!(
+                    "INSERT INTO {} ({}) VALUES ({})",
+                    table,
+                    columns.join(", "),
+                    placeholders.join(", ")
+                );
+
+                let params: Vec<Box<dyn rusqlite::ToSql>> =
+                    map.values().map(json_value_to_sql).collect();
+                // Dropping `tx` on the error path rolls the batch back
+                tx.execute(&sql, rusqlite::params_from_iter(params.iter()))?;
+                count += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(count)
+    }
+
+    fn list_tables(&self) -> Result<Vec<String>, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' \
+             AND name NOT LIKE '\\_%' ESCAPE '\\' \
+             AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut tables = Vec::new();
+        for row in rows {
+            tables.push(row?);
+        }
+        Ok(tables)
+    }
+
+    fn table_row_count(&self, table: &str) -> Result<i64, StoreError> {
+        let safe_table = escape_sql_identifier(table);
+        let conn = self.conn.lock().unwrap();
+        let count = conn.query_row(
+            &format!("SELECT COUNT(*) FROM \"{safe_table}\""),
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    fn native_backup(&self, dest: &Path) -> Result<(), StoreError> {
+        let dest = escape_sql_literal(&dest.to_string_lossy());
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(&format!("VACUUM INTO '{dest}'"))?;
+        Ok(())
+    }
+}
+
+fn value_ref_to_json(value: ValueRef<'_>) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(number) => serde_json::json!(number),
+        ValueRef::Real(number) => serde_json::json!(number),
+        ValueRef::Text(bytes) => serde_json::Value::String(String::from_utf8_lossy(bytes).into()),
+        ValueRef::Blob(bytes) => {
+            serde_json::Value::Array(bytes.iter().copied().map(serde_json::Value::from).collect())
+        }
+    }
+}
+
+fn json_value_to_sql(value: &serde_json::Value) -> Box<dyn rusqlite::ToSql> {
+    match value {
+        serde_json::Value::Null => Box::new(None::<String>),
+        serde_json::Value::Bool(b) => Box::new(*b),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Box::new(i)
+            } else if let Some(f) = n.as_f64() {
+                Box::new(f)
+            } else {
+                Box::new(n.to_string())
+            }
+        }
+        serde_json::Value::String(s) => Box::new(s.clone()),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+            Box::new(serde_json::to_string(value).unwrap_or_default())
+        }
+    }
+}
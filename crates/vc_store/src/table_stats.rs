@@ -0,0 +1,490 @@
+//! Table statistics and `ANALYZE`
+//!
+//! Each store counts the rows its writes touch, per table, in an in-memory
+//! buffer that is added to the `table_stats` table while the connection is
+//! held anyway, at most every [`FLUSH_INTERVAL`] (and when the store is
+//! dropped). Writes made through `execute` are attributed by parsing the
+//! statement's target table; JSON inserts and upserts name theirs. Batches
+//! run with `execute_batch` report no row count and are not counted.
+//!
+//! [`VcStore::analyze_table`] runs `ANALYZE` on one table at a time, holding
+//! the connection only for that table's statement, so writers queued behind
+//! it wait for one table rather than the whole database. Around it, the
+//! plans of logged `SELECT` shapes that read the table are captured, and
+//! shapes whose plan changed are marked in `query_plans` for
+//! `vc db slow-queries`.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::query_log::fingerprint;
+use crate::{StoreConnectionGuard, StoreError, VcStore, escape_sql_identifier, escape_sql_literal};
+
+/// Longest counted changes wait in the buffer
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Logged query shapes whose plans are compared around one `ANALYZE`
+const PLAN_SAMPLE: usize = 50;
+
+#[derive(Debug)]
+struct ChangeBuffer {
+    pending: HashMap<String, u64>,
+    last_flush: Instant,
+}
+
+/// Rows changed per table by one store, not yet written to `table_stats`
+#[derive(Debug)]
+pub(crate) struct ChangeCounter {
+    buffer: Mutex<ChangeBuffer>,
+}
+
+impl ChangeCounter {
+    pub(crate) fn new() -> Self {
+        Self {
+            buffer: Mutex::new(ChangeBuffer {
+                pending: HashMap::new(),
+                last_flush: Instant::now(),
+            }),
+        }
+    }
+
+    /// Count `rows` changed in `table`
+    pub(crate) fn note(&self, table: &str, rows: u64) {
+        if rows == 0 {
+            return;
+        }
+        if let Ok(mut buffer) = self.buffer.lock() {
+            *buffer.pending.entry(table.to_lowercase()).or_default() += rows;
+        }
+    }
+
+    /// Count `rows` changed by `sql`, if it is an INSERT, UPDATE or DELETE
+    pub(crate) fn note_statement(&self, sql: &str, rows: u64) {
+        if let Some(table) = dml_target(sql) {
+            self.note(&table, rows);
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn pending_len(&self) -> usize {
+        self.buffer.lock().map_or(0, |buffer| buffer.pending.len())
+    }
+
+    /// Whether changes are pending and have waited out the flush interval
+    pub(crate) fn flush_due(&self) -> bool {
+        self.buffer.lock().is_ok_and(|buffer| {
+            !buffer.pending.is_empty() && buffer.last_flush.elapsed() >= FLUSH_INTERVAL
+        })
+    }
+
+    /// Take everything pending as one statement adding to `table_stats`.
+    /// `None` when nothing is pending.
+    pub(crate) fn take_flush_batch(&self) -> Option<(String, usize)> {
+        let mut buffer = self.buffer.lock().ok()?;
+        buffer.last_flush = Instant::now();
+        if buffer.pending.is_empty() {
+            return None;
+        }
+        let pending = std::mem::take(&mut buffer.pending);
+        drop(buffer);
+
+        let values: Vec<String> = pending
+            .iter()
+            .map(|(table, rows)| format!("('{}', {rows})", escape_sql_literal(table)))
+            .collect();
+        let sql = format!(
+            "INSERT INTO table_stats (table_name, changes_since_analyze) VALUES {} \
+             ON CONFLICT (table_name) DO UPDATE SET changes_since_analyze = \
+             table_stats.changes_since_analyze + excluded.changes_since_analyze",
+            values.join(", ")
+        );
+        Some((sql, pending.len()))
+    }
+}
+
+/// Table an INSERT, UPDATE or DELETE statement writes to, lowercased and
+/// without a schema prefix
+fn dml_target(sql: &str) -> Option<String> {
+    let mut words = sql.split_whitespace();
+    let target = match words.next()?.to_ascii_uppercase().as_str() {
+        "INSERT" => {
+            words.find(|word| word.eq_ignore_ascii_case("INTO"))?;
+            words.next()?
+        }
+        "UPDATE" => words.next()?,
+        "DELETE" => {
+            words
+                .next()
+                .filter(|word| word.eq_ignore_ascii_case("FROM"))?;
+            words.next()?
+        }
+        _ => return None,
+    };
+    let name = target.split('(').next()?;
+    let name = name.rsplit('.').next()?.trim_matches('"');
+    (!name.is_empty()).then(|| name.to_lowercase())
+}
+
+/// Whether `fingerprint` mentions `table` as a whole word
+fn mentions_table(fingerprint: &str, table: &str) -> bool {
+    fingerprint
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word.eq_ignore_ascii_case(table))
+}
+
+/// Hash of a query shape's plan with its cardinality estimates stripped, so
+/// only a change of operators or join order counts. `None` when the shape
+/// cannot be explained (unbound parameters it cannot take as NULL, or a
+/// statement that is not a query).
+fn plan_hash(conn: &StoreConnectionGuard<'_>, shape: &str) -> Option<String> {
+    let mut stmt = conn.prepare_untimed(&format!("EXPLAIN {shape}")).ok()?;
+    let params = vec![duckdb::types::Null; stmt.parameter_count()];
+    let mut rows = stmt.query(duckdb::params_from_iter(params)).ok()?;
+    let mut hasher = DefaultHasher::new();
+    while let Some(row) = rows.next().ok()? {
+        let plan: String = row.get(1).ok()?;
+        fingerprint(&plan).hash(&mut hasher);
+    }
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+/// One table's change counts, from [`VcStore::table_stats`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableStats {
+    pub table_name: String,
+    /// Rows the table holds now (the engine's estimate)
+    pub row_count: i64,
+    pub changes_since_analyze: i64,
+    pub rows_at_analyze: Option<i64>,
+    pub last_analyzed_at: Option<String>,
+    pub last_analyze_ms: Option<f64>,
+    /// Changes over the rows at the last `ANALYZE` (or now, if never analyzed)
+    pub change_ratio: f64,
+}
+
+impl TableStats {
+    /// Whether enough changed since the last `ANALYZE` to run it again
+    #[must_use]
+    pub fn is_due(&self, change_ratio: f64, min_changes: u64) -> bool {
+        u64::try_from(self.changes_since_analyze).unwrap_or(0) >= min_changes.max(1)
+            && self.change_ratio >= change_ratio
+    }
+}
+
+/// Outcome of one [`VcStore::analyze_table`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzeResult {
+    pub table_name: String,
+    pub duration_ms: f64,
+    pub row_count: i64,
+    /// Changes counted since the previous `ANALYZE`, now reset
+    pub changes_reset: i64,
+    /// Logged query shapes reading the table whose plan changed
+    pub plans_changed: Vec<String>,
+}
+
+impl VcStore {
+    /// Add this store's buffered change counts to `table_stats`. Returns the
+    /// number of tables written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if the write fails; the counts are dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn flush_table_changes(&self) -> Result<usize, StoreError> {
+        let conn = self.conn.lock().unwrap();
+        conn.flush_table_changes()
+    }
+
+    /// Change counts for every table, most changed (relative to size) first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if flushing this store's counts or the query
+    /// fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        self.flush_table_changes()?;
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.table_name, t.estimated_size, \
+             COALESCE(s.changes_since_analyze, 0), s.rows_at_analyze, \
+             s.last_analyzed_at, s.last_analyze_ms \
+             FROM duckdb_tables() t \
+             LEFT JOIN table_stats s ON s.table_name = lower(t.table_name) \
+             WHERE NOT t.internal AND t.schema_name = 'main'",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let row_count: i64 = row.get(1)?;
+            let changes: i64 = row.get(2)?;
+            let rows_at_analyze: Option<i64> = row.get(3)?;
+            let base = rows_at_analyze.unwrap_or(row_count).max(1);
+            Ok(TableStats {
+                table_name: row.get(0)?,
+                row_count,
+                changes_since_analyze: changes,
+                rows_at_analyze,
+                last_analyzed_at: row.get(4)?,
+                last_analyze_ms: row.get(5)?,
+                change_ratio: changes as f64 / base as f64,
+            })
+        })?;
+
+        let mut stats = Vec::new();
+        for row in rows {
+            stats.push(row?);
+        }
+        stats.sort_by(|a, b| {
+            b.change_ratio
+                .total_cmp(&a.change_ratio)
+                .then_with(|| a.table_name.cmp(&b.table_name))
+        });
+        Ok(stats)
+    }
+
+    /// Tables whose changes since their last `ANALYZE` reach `change_ratio`
+    /// of their rows and at least `min_changes`, most changed first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if reading the statistics fails.
+    pub fn tables_due_for_analyze(
+        &self,
+        change_ratio: f64,
+        min_changes: u64,
+    ) -> Result<Vec<TableStats>, StoreError> {
+        let mut stats = self.table_stats()?;
+        stats.retain(|table| table.is_due(change_ratio, min_changes));
+        Ok(stats)
+    }
+
+    /// Run `ANALYZE` on one table, reset its change count, and record which
+    /// logged query shapes reading it got a different plan.
+    ///
+    /// The connection is taken separately for the plans before, the
+    /// `ANALYZE` itself, and the plans and bookkeeping after, so concurrent
+    /// writers are never held up by more than one of those steps.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError::QueryError`] if there is no such table, or
+    /// [`StoreError`] if `ANALYZE` or the bookkeeping fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn analyze_table(&self, table: &str) -> Result<AnalyzeResult, StoreError> {
+        let stats = self
+            .table_stats()?
+            .into_iter()
+            .find(|stats| stats.table_name.eq_ignore_ascii_case(table))
+            .ok_or_else(|| StoreError::QueryError(format!("no table named {table}")))?;
+        self.flush_query_log()?;
+
+        let shapes: Vec<String> = self
+            .query_json(
+                "SELECT fingerprint FROM query_log \
+                 WHERE fingerprint LIKE 'SELECT %' OR fingerprint LIKE 'WITH %' \
+                 GROUP BY fingerprint ORDER BY SUM(duration_us) DESC",
+            )?
+            .iter()
+            .filter_map(|row| row["fingerprint"].as_str())
+            .filter(|shape| mentions_table(shape, &stats.table_name))
+            .take(PLAN_SAMPLE)
+            .map(ToString::to_string)
+            .collect();
+
+        let before: HashMap<&str, String> = {
+            let conn = self.conn.lock().unwrap();
+            shapes
+                .iter()
+                .filter_map(|shape| Some((shape.as_str(), plan_hash(&conn, shape)?)))
+                .collect()
+        };
+
+        let duration = {
+            let conn = self.conn.lock().unwrap();
+            let started = Instant::now();
+            conn.execute_batch(&format!(
+                "ANALYZE \"{}\"",
+                escape_sql_identifier(&stats.table_name)
+            ))?;
+            started.elapsed()
+        };
+        let duration_ms = duration.as_secs_f64() * 1000.0;
+
+        let conn = self.conn.lock().unwrap();
+        let now = Utc::now().to_rfc3339();
+        let table_literal = escape_sql_literal(&stats.table_name.to_lowercase());
+        let mut plans_changed = Vec::new();
+        let mut sql = format!(
+            "INSERT INTO table_stats (table_name, changes_since_analyze, rows_at_analyze, \
+             last_analyzed_at, last_analyze_ms) \
+             VALUES ('{table_literal}', 0, {}, '{now}', {duration_ms}) \
+             ON CONFLICT (table_name) DO UPDATE SET changes_since_analyze = 0, \
+             rows_at_analyze = excluded.rows_at_analyze, \
+             last_analyzed_at = excluded.last_analyzed_at, \
+             last_analyze_ms = excluded.last_analyze_ms;\n",
+            stats.row_count
+        );
+        for shape in &shapes {
+            let Some(after) = plan_hash(&conn, shape) else {
+                continue;
+            };
+            let changed = before
+                .get(shape.as_str())
+                .is_some_and(|before| *before != after);
+            let shape_literal = escape_sql_literal(shape);
+            if changed {
+                plans_changed.push(shape.clone());
+                sql.push_str(&format!(
+                    "INSERT INTO query_plans (fingerprint, plan_hash, captured_at, changed_at, \
+                     changed_by_table) VALUES ('{shape_literal}', '{after}', '{now}', '{now}', \
+                     '{table_literal}') \
+                     ON CONFLICT (fingerprint) DO UPDATE SET plan_hash = excluded.plan_hash, \
+                     captured_at = excluded.captured_at, changed_at = excluded.changed_at, \
+                     changed_by_table = excluded.changed_by_table;\n"
+                ));
+            } else {
+                sql.push_str(&format!(
+                    "INSERT INTO query_plans (fingerprint, plan_hash, captured_at) \
+                     VALUES ('{shape_literal}', '{after}', '{now}') \
+                     ON CONFLICT (fingerprint) DO UPDATE SET plan_hash = excluded.plan_hash, \
+                     captured_at = excluded.captured_at;\n"
+                ));
+            }
+        }
+        conn.execute_batch(&sql)?;
+
+        Ok(AnalyzeResult {
+            table_name: stats.table_name,
+            duration_ms,
+            row_count: stats.row_count,
+            changes_reset: stats.changes_since_analyze,
+            plans_changed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QueryCaller;
+    use vc_config::QueryLogConfig;
+
+    #[test]
+    fn test_dml_target() {
+        assert_eq!(
+            dml_target("INSERT INTO sys_samples (machine_id) VALUES (?)").as_deref(),
+            Some("sys_samples")
+        );
+        assert_eq!(
+            dml_target("insert or replace into main.\"Machines\"(id) values (1)").as_deref(),
+            Some("machines")
+        );
+        assert_eq!(
+            dml_target("UPDATE alert_history SET resolved_at = ?").as_deref(),
+            Some("alert_history")
+        );
+        assert_eq!(
+            dml_target("DELETE FROM query_log WHERE 1 = 1").as_deref(),
+            Some("query_log")
+        );
+        assert_eq!(dml_target("SELECT * FROM machines"), None);
+        assert_eq!(dml_target("DELETE machines"), None);
+        assert!(mentions_table(
+            "SELECT * FROM sys_samples s WHERE s.x = ?",
+            "sys_samples"
+        ));
+        assert!(!mentions_table(
+            "SELECT * FROM sys_samples_v2",
+            "sys_samples"
+        ));
+    }
+
+    #[test]
+    fn test_writes_are_counted_until_analyze() {
+        let store = VcStore::open_memory().unwrap();
+        for id in 0..3 {
+            store
+                .insert_json(
+                    "machines",
+                    &serde_json::json!({"machine_id": format!("m{id}"), "hostname": "h"}),
+                )
+                .unwrap();
+        }
+        store
+            .execute_simple("UPDATE machines SET hostname = 'x'")
+            .unwrap();
+
+        let stats = store.table_stats().unwrap();
+        let machines = stats
+            .iter()
+            .find(|table| table.table_name == "machines")
+            .unwrap();
+        assert_eq!(machines.changes_since_analyze, 6);
+        assert_eq!(machines.last_analyzed_at, None);
+        assert!(machines.is_due(0.5, 5));
+        assert!(!machines.is_due(0.5, 10));
+
+        let due = store.tables_due_for_analyze(0.5, 5).unwrap();
+        assert_eq!(due.len(), 1);
+
+        let result = store.analyze_table("MACHINES").unwrap();
+        assert_eq!(result.table_name, "machines");
+        assert_eq!(result.changes_reset, 6);
+        assert!(result.duration_ms >= 0.0);
+
+        let machines = store
+            .table_stats()
+            .unwrap()
+            .into_iter()
+            .find(|table| table.table_name == "machines")
+            .unwrap();
+        assert_eq!(machines.changes_since_analyze, 0);
+        assert!(machines.last_analyzed_at.is_some());
+        assert!(store.tables_due_for_analyze(0.5, 5).unwrap().is_empty());
+
+        assert!(matches!(
+            store.analyze_table("no_such_table"),
+            Err(StoreError::QueryError(_))
+        ));
+    }
+
+    #[test]
+    fn test_analyze_captures_plans_of_logged_shapes() {
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_query_log(&QueryLogConfig::default(), QueryCaller::Cli);
+        store
+            .query_json("SELECT * FROM machines WHERE machine_id = 'orko'")
+            .unwrap();
+
+        store.analyze_table("machines").unwrap();
+        let plans: i64 = store
+            .query_scalar(
+                "SELECT COUNT(*) FROM query_plans \
+                 WHERE fingerprint = 'SELECT * FROM machines WHERE machine_id = ?'",
+            )
+            .unwrap();
+        assert_eq!(plans, 1);
+
+        let slow = store.slow_queries(None, 20).unwrap();
+        assert!(slow.iter().all(|query| query.plan_changed_at.is_none()));
+    }
+}
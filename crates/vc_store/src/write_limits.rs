@@ -0,0 +1,492 @@
+//! Write-time size limits
+//!
+//! One oversized value, say a collector bug writing a 300 MB blob into a
+//! metadata column, slows every later scan of its table. So every JSON
+//! write path checks each value's length before binding it: strings against
+//! the text limit, objects and arrays against the JSON limit once
+//! serialized, which the write does anyway. Nothing is parsed.
+//!
+//! An oversized value is truncated behind a marker or its row is rejected,
+//! per [`OversizePolicy`]. A rejected row never fails its batch; the rest
+//! is written. Every occurrence is recorded in `oversized_writes`.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+pub use vc_config::OversizePolicy;
+
+use crate::{StoreConnectionGuard, StoreError, VcStore, escape_sql_literal, json_value_to_sql};
+
+/// Bytes of the original JSON kept in the marker replacing an oversized
+/// JSON value
+const JSON_HEAD_BYTES: usize = 512;
+
+/// Largest values the store writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteLimits {
+    pub max_text_bytes: usize,
+    pub max_json_bytes: usize,
+    pub policy: OversizePolicy,
+}
+
+impl WriteLimits {
+    /// The `[write_limits]` defaults
+    pub const DEFAULT: Self = Self {
+        max_text_bytes: 4 * 1024 * 1024,
+        max_json_bytes: 16 * 1024 * 1024,
+        policy: OversizePolicy::Truncate,
+    };
+
+    /// No limits, for `enabled = false`
+    pub const UNLIMITED: Self = Self {
+        max_text_bytes: usize::MAX,
+        max_json_bytes: usize::MAX,
+        policy: OversizePolicy::Truncate,
+    };
+
+    #[must_use]
+    pub fn from_config(config: &vc_config::WriteLimitsConfig) -> Self {
+        if config.enabled {
+            Self {
+                max_text_bytes: config.max_text_bytes,
+                max_json_bytes: config.max_json_bytes,
+                policy: config.policy,
+            }
+        } else {
+            Self::UNLIMITED
+        }
+    }
+}
+
+impl Default for WriteLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Which limit a value is held to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnClass {
+    /// String values
+    Text,
+    /// Objects and arrays, stored as JSON text
+    Json,
+}
+
+impl ColumnClass {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// One value over its limit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OversizedValue {
+    pub column: String,
+    pub class: ColumnClass,
+    pub bytes: usize,
+    pub limit: usize,
+}
+
+/// Where a write came from, for attributing oversized values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOrigin<'a> {
+    pub machine_id: Option<&'a str>,
+    pub collector: Option<&'a str>,
+}
+
+impl<'a> WriteOrigin<'a> {
+    /// A collector's rows for one machine
+    #[must_use]
+    pub fn collector(machine_id: &'a str, collector: &'a str) -> Self {
+        Self {
+            machine_id: Some(machine_id),
+            collector: Some(collector),
+        }
+    }
+}
+
+/// Oversized values per table, collector and action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OversizedWriteCount {
+    pub table_name: String,
+    /// `None` for writes that did not come from a collection
+    pub collector: Option<String>,
+    /// `truncated` or `rejected`
+    pub action: String,
+    pub occurrences: i64,
+    /// Size of the largest value
+    pub max_bytes: i64,
+    pub last_at: String,
+}
+
+/// Cut `text` to fit `limit`: its head, then a marker with the original size
+fn truncate_text(text: &str, limit: usize) -> String {
+    let marker = format!(
+        "\n[... value of {} bytes truncated by vc write limits ...]",
+        text.len()
+    );
+    let mut head = limit.saturating_sub(marker.len()).min(text.len());
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    format!("{}{marker}", &text[..head])
+}
+
+/// A small JSON object standing in for an oversized JSON value, so JSON
+/// columns still hold valid JSON
+fn json_marker(json: &str) -> String {
+    let mut head = JSON_HEAD_BYTES.min(json.len());
+    while !json.is_char_boundary(head) {
+        head -= 1;
+    }
+    serde_json::json!({
+        "vc_truncated": true,
+        "original_bytes": json.len(),
+        "head": &json[..head],
+    })
+    .to_string()
+}
+
+/// Bind a row's values, applying `limits`. Values over a limit are pushed
+/// to `oversized`; under [`OversizePolicy::Reject`] any of them rejects
+/// the row and `None` is returned.
+fn guard_row(
+    map: &serde_json::Map<String, serde_json::Value>,
+    limits: &WriteLimits,
+    oversized: &mut Vec<OversizedValue>,
+) -> Option<Vec<Box<dyn duckdb::ToSql>>> {
+    let mut params: Vec<Box<dyn duckdb::ToSql>> = Vec::with_capacity(map.len());
+    for (column, value) in map {
+        let mut note = |class, bytes, limit| {
+            oversized.push(OversizedValue {
+                column: column.clone(),
+                class,
+                bytes,
+                limit,
+            });
+        };
+        match value {
+            serde_json::Value::String(s) if s.len() > limits.max_text_bytes => {
+                note(ColumnClass::Text, s.len(), limits.max_text_bytes);
+                params.push(Box::new(truncate_text(s, limits.max_text_bytes)));
+            }
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                let json = serde_json::to_string(value).unwrap_or_default();
+                if json.len() > limits.max_json_bytes {
+                    note(ColumnClass::Json, json.len(), limits.max_json_bytes);
+                    params.push(Box::new(json_marker(&json)));
+                } else {
+                    params.push(Box::new(json));
+                }
+            }
+            other => params.push(json_value_to_sql(other)),
+        }
+    }
+    let rejected = limits.policy == OversizePolicy::Reject && !oversized.is_empty();
+    (!rejected).then_some(params)
+}
+
+fn record_oversized(
+    conn: &StoreConnectionGuard<'_>,
+    table: &str,
+    origin: WriteOrigin<'_>,
+    oversized: &[OversizedValue],
+    action: &str,
+) -> Result<(), StoreError> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true);
+    for value in oversized {
+        warn!(
+            table,
+            column = %value.column,
+            class = value.class.as_str(),
+            bytes = value.bytes,
+            limit = value.limit,
+            collector = origin.collector.unwrap_or("-"),
+            action,
+            "oversized write"
+        );
+        conn.execute(
+            "INSERT INTO oversized_writes (table_name, column_name, column_class, action, \
+             bytes, limit_bytes, collector, machine_id, occurred_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            duckdb::params![
+                table,
+                value.column,
+                value.class.as_str(),
+                action,
+                i64::try_from(value.bytes).unwrap_or(i64::MAX),
+                i64::try_from(value.limit).unwrap_or(i64::MAX),
+                origin.collector,
+                origin.machine_id,
+                now,
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Write one JSON object into `table` with `insert` (`INSERT INTO` or
+/// `INSERT OR REPLACE INTO`) under the store's write limits. Returns
+/// `false` when the limits rejected the row.
+pub(crate) fn write_row(
+    conn: &StoreConnectionGuard<'_>,
+    insert: &str,
+    table: &str,
+    map: &serde_json::Map<String, serde_json::Value>,
+    origin: WriteOrigin<'_>,
+) -> Result<bool, StoreError> {
+    let mut oversized = Vec::new();
+    let Some(params) = guard_row(map, &conn.write_limits(), &mut oversized) else {
+        record_oversized(conn, table, origin, &oversized, "rejected")?;
+        return Ok(false);
+    };
+
+    let columns: Vec<&str> = map.keys().map(String::as_str).collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "{insert} {table} ({}) VALUES ({placeholders})",
+        columns.join(", ")
+    ))?;
+    let param_refs: Vec<&dyn duckdb::ToSql> = params.iter().map(AsRef::as_ref).collect();
+    stmt.execute(param_refs.as_slice())?;
+    if !oversized.is_empty() {
+        record_oversized(conn, table, origin, &oversized, "truncated")?;
+    }
+    Ok(true)
+}
+
+impl VcStore {
+    /// Hold writes to `[write_limits]` instead of the defaults. Applies to
+    /// every handle on this store; only the first call takes effect.
+    #[must_use]
+    pub fn with_write_limits(self, config: &vc_config::WriteLimitsConfig) -> Self {
+        let _ = self
+            .conn
+            .shared
+            .write_limits
+            .set(WriteLimits::from_config(config));
+        self
+    }
+
+    /// The limits writes through this store are held to
+    #[must_use]
+    pub fn write_limits(&self) -> WriteLimits {
+        self.conn
+            .shared
+            .write_limits
+            .get()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Oversized values per table, collector and action, optionally only
+    /// those of one machine or collector, or since a time. Largest counts
+    /// first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StoreError`] if query execution fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal database mutex is poisoned.
+    pub fn oversized_write_counts(
+        &self,
+        machine_id: Option<&str>,
+        collector: Option<&str>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<OversizedWriteCount>, StoreError> {
+        let mut filters = Vec::new();
+        if let Some(id) = machine_id {
+            filters.push(format!("machine_id = '{}'", escape_sql_literal(id)));
+        }
+        if let Some(c) = collector {
+            filters.push(format!("collector = '{}'", escape_sql_literal(c)));
+        }
+        if let Some(since) = since {
+            filters.push(format!(
+                "occurred_at >= '{}'",
+                since.to_rfc3339_opts(SecondsFormat::Micros, true)
+            ));
+        }
+        let filter = if filters.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", filters.join(" AND "))
+        };
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT table_name, collector, action, COUNT(*), MAX(bytes), MAX(occurred_at) \
+             FROM oversized_writes {filter} \
+             GROUP BY 1, 2, 3 ORDER BY 4 DESC, 1, 2, 3"
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok(OversizedWriteCount {
+                table_name: row.get(0)?,
+                collector: row.get(1)?,
+                action: row.get(2)?,
+                occurrences: row.get(3)?,
+                max_bytes: row.get(4)?,
+                last_at: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tight(policy: OversizePolicy) -> vc_config::WriteLimitsConfig {
+        vc_config::WriteLimitsConfig {
+            enabled: true,
+            max_text_bytes: 64,
+            max_json_bytes: 600,
+            policy,
+        }
+    }
+
+    fn row(minute: u32, raw_output: &str) -> serde_json::Value {
+        serde_json::json!({
+            "machine_id": "orko",
+            "collected_at": format!("2026-10-16T12:{minute:02}:00Z"),
+            "load1": 0.5,
+            "raw_output": raw_output,
+        })
+    }
+
+    fn stored_outputs(store: &VcStore) -> Vec<String> {
+        store
+            .query_json("SELECT raw_output FROM sys_fallback_samples ORDER BY collected_at")
+            .unwrap()
+            .into_iter()
+            .filter_map(|r| r["raw_output"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_truncate_text_fits_limit_on_char_boundary() {
+        let text = "é".repeat(100);
+        let cut = truncate_text(&text, 120);
+        assert!(cut.len() <= 120);
+        assert!(cut.contains("value of 200 bytes truncated"));
+        assert!(cut.starts_with('é'));
+
+        let marker: serde_json::Value =
+            serde_json::from_str(&json_marker(&"x".repeat(10_000))).unwrap();
+        assert_eq!(marker["vc_truncated"], true);
+        assert_eq!(marker["original_bytes"], 10_000);
+        assert_eq!(marker["head"].as_str().unwrap().len(), JSON_HEAD_BYTES);
+    }
+
+    #[test]
+    fn test_guard_row_truncates_text_and_json() {
+        let limits = WriteLimits::from_config(&tight(OversizePolicy::Truncate));
+        let value = serde_json::json!({
+            "short": "ok",
+            "long": "y".repeat(500),
+            "nested": {"blob": "z".repeat(1000)},
+            "n": 3,
+        });
+        let mut oversized = Vec::new();
+        let params = guard_row(value.as_object().unwrap(), &limits, &mut oversized);
+        assert_eq!(params.map(|p| p.len()), Some(4));
+        let columns: Vec<(&str, ColumnClass)> = oversized
+            .iter()
+            .map(|v| (v.column.as_str(), v.class))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![("long", ColumnClass::Text), ("nested", ColumnClass::Json)]
+        );
+
+        let limits = WriteLimits::from_config(&tight(OversizePolicy::Reject));
+        let mut oversized = Vec::new();
+        assert!(guard_row(value.as_object().unwrap(), &limits, &mut oversized).is_none());
+        assert_eq!(oversized.len(), 2);
+        assert!(
+            guard_row(
+                serde_json::json!({"short": "ok"}).as_object().unwrap(),
+                &limits,
+                &mut Vec::new()
+            )
+            .is_some()
+        );
+    }
+
+    #[test]
+    fn test_batch_truncates_oversized_values_and_counts_them() {
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_write_limits(&tight(OversizePolicy::Truncate));
+        let written = store
+            .insert_json_batch_from(
+                "sys_fallback_samples",
+                &[row(0, "fine"), row(1, &"x".repeat(1000))],
+                WriteOrigin::collector("orko", "sysmoni"),
+            )
+            .unwrap();
+        assert_eq!(written, 2);
+        let outputs = stored_outputs(&store);
+        assert_eq!(outputs[0], "fine");
+        assert!(outputs[1].len() <= 64);
+        assert!(outputs[1].contains("1000 bytes truncated"));
+
+        let counts = store.oversized_write_counts(None, None, None).unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].table_name, "sys_fallback_samples");
+        assert_eq!(counts[0].collector.as_deref(), Some("sysmoni"));
+        assert_eq!(counts[0].action, "truncated");
+        assert_eq!(counts[0].max_bytes, 1000);
+    }
+
+    #[test]
+    fn test_batch_rejects_oversized_rows_and_commits_the_rest() {
+        let store = VcStore::open_memory()
+            .unwrap()
+            .with_write_limits(&tight(OversizePolicy::Reject));
+        let written = store
+            .insert_json_batch(
+                "sys_fallback_samples",
+                &[row(0, "a"), row(1, &"x".repeat(1000)), row(2, "b")],
+            )
+            .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(stored_outputs(&store), vec!["a", "b"]);
+        let counts = store.oversized_write_counts(None, None, None).unwrap();
+        assert_eq!(counts[0].action, "rejected");
+        assert_eq!(counts[0].collector, None);
+        assert!(
+            store
+                .oversized_write_counts(None, Some("sysmoni"), None)
+                .unwrap()
+                .is_empty()
+        );
+
+        // A lone rejected row is an error
+        assert!(matches!(
+            store.insert_json("sys_fallback_samples", &row(3, &"x".repeat(1000))),
+            Err(StoreError::RowRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_disabled_limits_write_everything() {
+        let mut config = tight(OversizePolicy::Reject);
+        config.enabled = false;
+        let store = VcStore::open_memory().unwrap().with_write_limits(&config);
+        assert_eq!(store.write_limits(), WriteLimits::UNLIMITED);
+        store
+            .insert_json("sys_fallback_samples", &row(0, &"x".repeat(1000)))
+            .unwrap();
+        assert_eq!(stored_outputs(&store)[0].len(), 1000);
+    }
+}
@@ -170,29 +170,12 @@ pub struct CollectionEvent {
 
 /// Format relative time
 fn format_relative_time(ts: DateTime<Utc>) -> String {
-    let now = Utc::now();
-    let diff = now.signed_duration_since(ts);
-
-    if diff.num_seconds() < 60 {
-        "just now".to_string()
-    } else if diff.num_minutes() < 60 {
-        format!("{}m ago", diff.num_minutes())
-    } else if diff.num_hours() < 24 {
-        format!("{}h ago", diff.num_hours())
-    } else {
-        format!("{}d ago", diff.num_days())
-    }
+    vc_query::timefmt::relative(ts, Utc::now())
 }
 
 /// Format uptime duration
 fn format_uptime(secs: i64) -> String {
-    if secs < 3600 {
-        format!("{}m", secs / 60)
-    } else if secs < 86400 {
-        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
-    } else {
-        format!("{}d {}h", secs / 86400, (secs % 86400) / 3600)
-    }
+    vc_query::timefmt::duration_secs(u64::try_from(secs).unwrap_or(0))
 }
 
 /// Render the machines screen using `ftui` widgets.
@@ -829,7 +812,7 @@ mod tests {
     #[test]
     fn test_format_uptime() {
         assert_eq!(format_uptime(300), "5m");
-        assert_eq!(format_uptime(7200), "2h 0m");
+        assert_eq!(format_uptime(7200), "2h");
         assert_eq!(format_uptime(90000), "1d 1h");
     }
 
@@ -462,6 +462,7 @@ fn enabled_collectors(config: &VcConfig) -> usize {
         config.collectors.bv_br,
         config.collectors.afsc,
         config.collectors.cloud_benchmarker,
+        config.collectors.accounts,
     ]
     .into_iter()
     .filter(|enabled| *enabled)
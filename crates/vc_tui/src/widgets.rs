@@ -127,42 +127,11 @@ pub fn format_bytes(bytes: u64) -> String {
     }
 }
 
-/// Format a duration to a human-readable string.
+/// Format a duration to a human-readable string, the same way the CLI and
+/// digests do.
 #[must_use]
 pub fn format_duration(secs: u64) -> String {
-    if secs == 0 {
-        return "just now".to_string();
-    }
-
-    let days = secs / 86_400;
-    if days > 0 {
-        return format!("{days}d");
-    }
-
-    let hours = secs / 3_600;
-    let minutes = (secs % 3_600) / 60;
-    let seconds = secs % 60;
-
-    if hours > 0 {
-        let mut parts = vec![format!("{hours}h")];
-        if minutes > 0 {
-            parts.push(format!("{minutes}m"));
-        }
-        if seconds > 0 {
-            parts.push(format!("{seconds}s"));
-        }
-        return parts.join(" ");
-    }
-
-    if minutes > 0 {
-        if seconds > 0 {
-            format!("{minutes}m {seconds}s")
-        } else {
-            format!("{minutes}m")
-        }
-    } else {
-        format!("{seconds}s")
-    }
+    vc_query::timefmt::duration_secs(secs)
 }
 
 fn packed(color: ftui::Color) -> PackedRgba {
@@ -220,15 +189,16 @@ mod tests {
 
     #[test]
     fn test_format_duration() {
-        assert_eq!(format_duration(0), "just now");
+        assert_eq!(format_duration(0), "0s");
         assert_eq!(format_duration(30), "30s");
         assert_eq!(format_duration(59), "59s");
         assert_eq!(format_duration(60), "1m");
         assert_eq!(format_duration(61), "1m 1s");
         assert_eq!(format_duration(3_600), "1h");
-        assert_eq!(format_duration(3_661), "1h 1m 1s");
+        assert_eq!(format_duration(3_661), "1h 1m");
         assert_eq!(format_duration(86_400), "1d");
         assert_eq!(format_duration(172_800), "2d");
+        assert_eq!(format_duration(187_200), "2d 4h");
     }
 
     #[test]
@@ -0,0 +1,636 @@
+//! `vc_types` - Wire types of the Vibe Cockpit web API
+//!
+//! Every body `vc_web` serves under `/api` is one of these types, and
+//! `vc_client` reads them back into the same types, so the server and its
+//! clients cannot drift apart. The crate depends on nothing but serde,
+//! chrono and sha2 (for [`signing`]), so a client does not pull in the store.
+//!
+//! Rows read straight out of a store table (machines, alerts, incidents)
+//! travel as JSON objects, the same as `vc --format json` prints them.
+//!
+//! Types an embedder builds itself are `#[non_exhaustive]`, so a field added
+//! here is not a breaking change: start from `Default` or the type's
+//! constructor and set what you need.
+
+pub mod signing;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ============================================================================
+// Health & overview
+// ============================================================================
+
+/// `GET /api/health`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    pub version: String,
+    pub uptime_secs: u64,
+}
+
+/// Fleet overview summary (`GET /api/overview`)
+///
+/// Outside this crate, start from [`FleetOverview::default`] (an empty,
+/// fully healthy fleet) and assign the fields you need.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct FleetOverview {
+    pub total_machines: usize,
+    pub online_machines: usize,
+    pub offline_machines: usize,
+    pub total_agents: usize,
+    pub active_agents: usize,
+    /// Machines in maintenance; they count toward neither online nor
+    /// offline and are left out of the fleet health score
+    #[serde(default)]
+    pub maintenance_machines: usize,
+    pub fleet_health_score: f64,
+    pub worst_machine: Option<String>,
+    pub active_alerts: usize,
+    pub pending_approvals: usize,
+    /// Tables this store lacks; the counts backed by them read as zero
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_capabilities: Vec<String>,
+    /// Machines other machines depend on that have an active critical alert
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upstream_failures: Vec<UpstreamFailure>,
+    /// The same numbers per machine tag, tags in name order and
+    /// [`UNTAGGED_GROUP`] last. A machine with several tags counts in each.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_tag: Vec<TagBreakdown>,
+}
+
+impl Default for FleetOverview {
+    fn default() -> Self {
+        Self {
+            total_machines: 0,
+            online_machines: 0,
+            offline_machines: 0,
+            total_agents: 0,
+            active_agents: 0,
+            maintenance_machines: 0,
+            fleet_health_score: 1.0,
+            worst_machine: None,
+            active_alerts: 0,
+            pending_approvals: 0,
+            missing_capabilities: Vec::new(),
+            upstream_failures: Vec::new(),
+            by_tag: Vec::new(),
+        }
+    }
+}
+
+impl FleetOverview {
+    /// "1 upstream failure affecting 6 machines", when there is one. A
+    /// machine downstream of two failures is counted once.
+    #[must_use]
+    pub fn upstream_headline(&self) -> Option<String> {
+        if self.upstream_failures.is_empty() {
+            return None;
+        }
+        let mut affected: Vec<&str> = self
+            .upstream_failures
+            .iter()
+            .flat_map(|failure| failure.affected_machines.iter().map(String::as_str))
+            .collect();
+        affected.sort_unstable();
+        affected.dedup();
+        let failures = self.upstream_failures.len();
+        Some(format!(
+            "{failures} upstream failure{} affecting {} machine{}",
+            if failures == 1 { "" } else { "s" },
+            affected.len(),
+            if affected.len() == 1 { "" } else { "s" },
+        ))
+    }
+}
+
+/// Group of [`FleetOverview::by_tag`] holding the machines without tags
+pub const UNTAGGED_GROUP: &str = "untagged";
+
+/// Fleet numbers for the machines carrying one tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TagBreakdown {
+    /// The tag, or [`UNTAGGED_GROUP`]
+    pub tag: String,
+    pub total_machines: usize,
+    pub online_machines: usize,
+    pub offline_machines: usize,
+    pub maintenance_machines: usize,
+    /// Mean health score of its machines outside maintenance, 1.0 without
+    /// health data
+    pub health_score: f64,
+    /// Unresolved alerts on its machines
+    pub active_alerts: usize,
+    pub worst_machine: Option<String>,
+}
+
+/// A machine with an active critical alert that others depend on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpstreamFailure {
+    pub machine_id: String,
+    /// Its unresolved critical alerts
+    pub alert_ids: Vec<i64>,
+    /// Machines depending on it, directly or through another machine
+    pub affected_machines: Vec<String>,
+}
+
+/// The headline numbers of [`FleetOverview`] (`GET /api/fleet`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetSummary {
+    pub total_machines: usize,
+    pub online_machines: usize,
+    pub offline_machines: usize,
+    pub maintenance_machines: usize,
+    pub fleet_health: f64,
+    pub active_alerts: usize,
+    pub pending_approvals: usize,
+    /// [`FleetOverview::by_tag`], sent for `?group_by=tag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_tag: Vec<TagBreakdown>,
+}
+
+impl From<&FleetOverview> for FleetSummary {
+    fn from(overview: &FleetOverview) -> Self {
+        Self {
+            total_machines: overview.total_machines,
+            online_machines: overview.online_machines,
+            offline_machines: overview.offline_machines,
+            maintenance_machines: overview.maintenance_machines,
+            fleet_health: overview.fleet_health_score,
+            active_alerts: overview.active_alerts,
+            pending_approvals: overview.pending_approvals,
+            by_tag: Vec::new(),
+        }
+    }
+}
+
+// ============================================================================
+// Machine health
+// ============================================================================
+
+/// Health score for a machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct HealthScore {
+    pub machine_id: String,
+    pub overall_score: f64,
+    pub factors: Vec<HealthFactor>,
+    pub worst_factor: Option<String>,
+}
+
+impl HealthScore {
+    /// A score without factors
+    #[must_use]
+    pub fn new(machine_id: impl Into<String>, overall_score: f64) -> Self {
+        Self {
+            machine_id: machine_id.into(),
+            overall_score,
+            factors: Vec::new(),
+            worst_factor: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_factors(mut self, factors: Vec<HealthFactor>) -> Self {
+        self.factors = factors;
+        self
+    }
+
+    #[must_use]
+    pub fn with_worst_factor(mut self, factor_id: impl Into<String>) -> Self {
+        self.worst_factor = Some(factor_id.into());
+        self
+    }
+}
+
+/// Individual health factor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthFactor {
+    pub factor_id: String,
+    pub name: String,
+    pub score: f64,
+    pub weight: f64,
+    pub severity: Severity,
+    pub details: String,
+    /// Average score over the trailing baseline window, `None` when the
+    /// machine does not have enough history yet
+    #[serde(default)]
+    pub baseline_score: Option<f64>,
+    /// Direction of `score` relative to `baseline_score`
+    #[serde(default)]
+    pub trend: Option<HealthTrend>,
+}
+
+impl HealthFactor {
+    /// Change from the baseline (positive = healthier), if a baseline exists
+    #[must_use]
+    pub fn delta(&self) -> Option<f64> {
+        self.baseline_score.map(|baseline| self.score - baseline)
+    }
+}
+
+/// Score change (on the 0-1 scale) below which a factor counts as stable
+pub const TREND_THRESHOLD: f64 = 0.05;
+
+/// Direction a health score moved relative to its baseline
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthTrend {
+    Improving,
+    Stable,
+    Degrading,
+}
+
+impl HealthTrend {
+    /// Classify a score delta, treating moves under [`TREND_THRESHOLD`] as noise
+    #[must_use]
+    pub fn from_delta(delta: f64) -> Self {
+        if delta >= TREND_THRESHOLD {
+            HealthTrend::Improving
+        } else if delta <= -TREND_THRESHOLD {
+            HealthTrend::Degrading
+        } else {
+            HealthTrend::Stable
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HealthTrend::Improving => "improving",
+            HealthTrend::Stable => "stable",
+            HealthTrend::Degrading => "degrading",
+        }
+    }
+}
+
+/// Severity levels
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Healthy,
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Healthy => "healthy",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "healthy" => Ok(Severity::Healthy),
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!("unknown severity: {other}")),
+        }
+    }
+}
+
+// ============================================================================
+// Lists
+// ============================================================================
+
+/// `GET /api/machines`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachinesPage {
+    /// `machines` rows
+    pub machines: Vec<serde_json::Value>,
+    /// Machines in the inventory, across all pages
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// `GET /api/alerts`: the newest alerts first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertsPage {
+    /// `alert_history` rows
+    pub alerts: Vec<serde_json::Value>,
+    pub limit: usize,
+}
+
+/// `GET /api/incidents`: the newest incidents first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentsPage {
+    /// `incidents` rows
+    pub incidents: Vec<serde_json::Value>,
+    pub limit: usize,
+}
+
+/// `GET /api/incidents/{id}`, as `vc incident show` prints it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentDetail {
+    pub incident: serde_json::Value,
+    pub notes: Vec<serde_json::Value>,
+    pub timeline: Vec<serde_json::Value>,
+}
+
+// ============================================================================
+// Query templates
+// ============================================================================
+
+/// `GET /api/query/templates`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateCatalog {
+    pub templates: Vec<TemplateInfo>,
+}
+
+/// A query template as listed by `vc query templates`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<TemplateParamInfo>,
+    /// Runnable by tokens below operator
+    pub agent_safe: bool,
+}
+
+/// A template parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateParamInfo {
+    pub name: String,
+    pub description: String,
+    pub default: Option<String>,
+}
+
+/// Request body for `POST /api/query/template`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateQueryRequest {
+    /// Template name, as listed by `GET /api/query/templates`
+    pub name: String,
+    /// Parameter values (strings, numbers, booleans or null)
+    #[serde(default)]
+    pub params: HashMap<String, serde_json::Value>,
+}
+
+/// Response of `POST /api/query/template`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateQueryResult {
+    pub template: String,
+    pub row_count: usize,
+    /// More rows matched than the guardrail row limit
+    pub truncated: bool,
+    pub rows: Vec<serde_json::Value>,
+    pub timing: QueryTiming,
+}
+
+/// Milliseconds spent on a template query
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct QueryTiming {
+    pub query_ms: f64,
+    pub total_ms: f64,
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Body of every error response
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiErrorBody {
+    /// Error message, or a code such as `unauthorized` or `rate_limited`
+    pub error: String,
+    /// HTTP status, repeated for clients that only see the body
+    pub status: u16,
+    /// Why a request was refused (401 and 403)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Human-readable detail for a coded error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Seconds to wait before trying again (429)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+    /// The row's state when a write lost a race (409)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+}
+
+impl ApiErrorBody {
+    #[must_use]
+    pub fn new(error: impl Into<String>, status: u16) -> Self {
+        Self {
+            error: error.into(),
+            status,
+            ..Self::default()
+        }
+    }
+}
+
+// ============================================================================
+// Robot envelopes
+// ============================================================================
+
+/// Standard envelope for all robot mode output
+///
+/// Every robot command returns data wrapped in this envelope,
+/// providing consistent metadata for agent consumption.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RobotEnvelope<T: Serialize> {
+    /// Schema version identifier (e.g., "vc.robot.health.v1")
+    pub schema_version: String,
+
+    /// When this output was generated
+    pub generated_at: DateTime<Utc>,
+
+    /// The actual data payload
+    pub data: T,
+
+    /// Data staleness by source (seconds since last collection)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub staleness: HashMap<String, u64>,
+
+    /// Warnings about data quality or collection issues
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// Tables this store lacks; the output they back is omitted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_capabilities: Vec<String>,
+}
+
+impl<T: Serialize> RobotEnvelope<T> {
+    /// Create a new envelope with the given schema and data
+    pub fn new(schema_version: impl Into<String>, data: T) -> Self {
+        Self {
+            schema_version: schema_version.into(),
+            generated_at: Utc::now(),
+            data,
+            staleness: HashMap::new(),
+            warnings: Vec::new(),
+            missing_capabilities: Vec::new(),
+        }
+    }
+
+    /// Add staleness information
+    #[must_use]
+    pub fn with_staleness(mut self, staleness: HashMap<String, u64>) -> Self {
+        self.staleness = staleness;
+        self
+    }
+
+    /// Add a single staleness entry
+    #[must_use]
+    pub fn add_staleness(mut self, source: impl Into<String>, seconds: u64) -> Self {
+        self.staleness.insert(source.into(), seconds);
+        self
+    }
+
+    /// Add warnings
+    #[must_use]
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Add a single warning
+    #[must_use]
+    pub fn add_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
+    /// Add missing tables, keeping the list sorted and unique
+    #[must_use]
+    pub fn add_missing_capabilities(mut self, missing: impl IntoIterator<Item = String>) -> Self {
+        self.missing_capabilities.extend(missing);
+        self.missing_capabilities.sort();
+        self.missing_capabilities.dedup();
+        self
+    }
+
+    /// Serialize to pretty JSON string
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .unwrap_or_else(|e| format!(r#"{{"error": "serialization failed: {e}"}}"#))
+    }
+
+    /// Serialize to compact JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self)
+            .unwrap_or_else(|e| format!(r#"{{"error": "serialization failed: {e}"}}"#))
+    }
+}
+
+/// A robot envelope by the name used in `vc robot <name>` and
+/// `GET /api/robot/<name>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RobotView {
+    Health,
+    Status,
+    Triage,
+    Machines,
+    Accounts,
+    Repos,
+    Oracle,
+}
+
+impl RobotView {
+    /// Every view, in `vc robot --help` order
+    pub const ALL: [Self; 7] = [
+        Self::Health,
+        Self::Status,
+        Self::Triage,
+        Self::Machines,
+        Self::Accounts,
+        Self::Repos,
+        Self::Oracle,
+    ];
+
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Health => "health",
+            Self::Status => "status",
+            Self::Triage => "triage",
+            Self::Machines => "machines",
+            Self::Accounts => "accounts",
+            Self::Repos => "repos",
+            Self::Oracle => "oracle",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.name() == name)
+    }
+
+    /// How long a client may reuse this envelope before asking again.
+    ///
+    /// There is no server-side cache behind these: every request rebuilds the
+    /// envelope from the store, so the TTL only bounds how stale a client's
+    /// copy gets. Fleet views are kept well inside one collection poll;
+    /// forecasts move with the usage snapshots, and the inventory and repo
+    /// status change rarely.
+    #[must_use]
+    pub const fn cache_ttl_secs(self) -> u64 {
+        match self {
+            Self::Health | Self::Status | Self::Triage => 30,
+            Self::Accounts | Self::Oracle => 60,
+            Self::Machines | Self::Repos => 300,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_body_omits_unset_fields() {
+        let body = ApiErrorBody::new("not found", 404);
+        assert_eq!(
+            serde_json::to_value(&body).unwrap(),
+            serde_json::json!({ "error": "not found", "status": 404 })
+        );
+
+        let parsed: ApiErrorBody =
+            serde_json::from_str(r#"{"error":"rate_limited","retry_after_secs":12,"status":429}"#)
+                .unwrap();
+        assert_eq!(parsed.retry_after_secs, Some(12));
+    }
+
+    #[test]
+    fn test_upstream_headline_counts_each_machine_once() {
+        let failure = |machine_id: &str, affected: &[&str]| UpstreamFailure {
+            machine_id: machine_id.to_string(),
+            alert_ids: vec![1],
+            affected_machines: affected.iter().map(ToString::to_string).collect(),
+        };
+        let mut overview: FleetOverview = serde_json::from_value(serde_json::json!({
+            "total_machines": 8, "online_machines": 8, "offline_machines": 0,
+            "total_agents": 0, "active_agents": 0, "fleet_health_score": 1.0,
+            "worst_machine": null, "active_alerts": 1, "pending_approvals": 0,
+        }))
+        .unwrap();
+        assert_eq!(overview.upstream_headline(), None);
+
+        overview.upstream_failures = vec![failure("nfs", &["b1", "b2", "b3", "b4", "b5", "b6"])];
+        assert_eq!(
+            overview.upstream_headline().as_deref(),
+            Some("1 upstream failure affecting 6 machines")
+        );
+        overview.upstream_failures.push(failure("gw", &["b1"]));
+        assert_eq!(
+            overview.upstream_headline().as_deref(),
+            Some("2 upstream failures affecting 6 machines")
+        );
+    }
+}
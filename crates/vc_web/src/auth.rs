@@ -4,6 +4,9 @@
 //! - `read`: Read-only access to all API endpoints
 //! - `operator`: Read + write for operational actions (ack alerts, run collectors)
 //! - `admin`: Full access including token management and configuration
+//!
+//! With `[web.oidc]` on, a request without a bearer token may instead carry
+//! a dashboard session cookie; see [`crate::oidc`].
 
 use axum::{
     http::{HeaderMap, StatusCode},
@@ -155,6 +158,17 @@ impl AuthResult {
         }
     }
 
+    /// A dashboard login session, named by its OIDC subject
+    #[must_use]
+    pub fn session(subject: &str, role: Role) -> Self {
+        Self {
+            authenticated: true,
+            token_name: Some(subject.to_string()),
+            role: Some(role),
+            reason: "session_valid".to_string(),
+        }
+    }
+
     #[must_use]
     pub fn local_bypass() -> Self {
         Self {
@@ -239,31 +253,36 @@ use axum::{
 };
 use std::net::SocketAddr;
 
+/// Header naming the consumer behind a token (e.g. one orchestrator of
+/// several sharing it), recorded on its writes and counted apart for rate
+/// limits
+pub const ACTOR_HEADER: &str = "x-vc-actor";
+
 /// Auth state to pass through layers
 #[derive(Clone)]
 pub struct AuthState {
     pub config: Arc<AuthConfig>,
+    /// OIDC login sessions, when `[web.oidc]` is on
+    pub sessions: Option<Arc<crate::oidc::SessionStore>>,
 }
 
 /// Create a 401 Unauthorized response
 #[must_use]
 pub fn unauthorized_response(reason: &str) -> Response {
-    let body = serde_json::json!({
-        "error": "unauthorized",
-        "reason": reason,
-        "status": 401
-    });
+    let body = vc_types::ApiErrorBody {
+        reason: Some(reason.to_string()),
+        ..vc_types::ApiErrorBody::new("unauthorized", 401)
+    };
     (StatusCode::UNAUTHORIZED, Json(body)).into_response()
 }
 
 /// Create a 403 Forbidden response
 #[must_use]
 pub fn forbidden_response(reason: &str) -> Response {
-    let body = serde_json::json!({
-        "error": "forbidden",
-        "reason": reason,
-        "status": 403
-    });
+    let body = vc_types::ApiErrorBody {
+        reason: Some(reason.to_string()),
+        ..vc_types::ApiErrorBody::new("forbidden", 403)
+    };
     (StatusCode::FORBIDDEN, Json(body)).into_response()
 }
 
@@ -278,14 +297,39 @@ pub async fn auth_middleware(
         .get::<ConnectInfo<SocketAddr>>()
         .map_or_else(|| "unknown".to_string(), |info| info.ip().to_string());
 
-    let result = authenticate(&state.config, request.headers(), &client_ip);
+    // A bearer token decides on its own; without one, a live session counts
+    let session = if extract_bearer_token(request.headers()).is_none() {
+        state
+            .sessions
+            .as_deref()
+            .and_then(|sessions| sessions.get(crate::oidc::session_cookie(request.headers())?))
+    } else {
+        None
+    };
+    let result = match &session {
+        Some(session) => AuthResult::session(&session.subject, session.role),
+        None => authenticate(&state.config, request.headers(), &client_ip),
+    };
 
     if !result.authenticated {
         return unauthorized_response(&result.reason);
     }
 
-    // Insert AuthResult into request extensions for subsequent use
+    let actor = match &session {
+        Some(session) => vc_store::ActorContext::web_session(&session.subject),
+        None => vc_store::ActorContext::web(
+            request
+                .headers()
+                .get(ACTOR_HEADER)
+                .and_then(|value| value.to_str().ok()),
+            result.token_name.as_deref(),
+        ),
+    };
+
+    // Insert AuthResult and the caller's actor into request extensions for
+    // subsequent use
     request.extensions_mut().insert(result);
+    request.extensions_mut().insert(actor);
     next.run(request).await
 }
 
@@ -0,0 +1,728 @@
+//! Server-sent event fan-out for `GET /api/events`.
+//!
+//! One [`EventPoller`] turns new alerts and health changes in the store into
+//! [`StreamEvent`]s, and the [`EventHub`] fans each out to every connected
+//! client. A client's filter (machines, minimum severity) is applied before
+//! queueing, so events it does not want never take up room in its queue.
+//!
+//! Each client has a bounded queue. When it is full (a dashboard on a flaky
+//! network), room is made in this order:
+//!
+//! 1. a queued heartbeat is dropped, or the incoming one if none is queued
+//! 2. a health change for a machine with a newer one queued (or incoming) is
+//!    dropped, keeping the latest
+//! 3. the oldest event is dropped, and the client is sent a `dropped` event
+//!    with the count before its next event
+//!
+//! Connected clients, delivered events and dropped events by reason are
+//! exported on `/metrics`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::response::sse::Event;
+use serde::Deserialize;
+use tokio::sync::Notify;
+use vc_query::{QueryBuilder, Severity};
+use vc_store::VcStore;
+
+/// What a [`StreamEvent`] reports; sent as the SSE `event:` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Heartbeat,
+    Alert,
+    HealthChange,
+    /// How many events a client lost to a full queue
+    Dropped,
+}
+
+impl EventKind {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Heartbeat => "heartbeat",
+            Self::Alert => "alert",
+            Self::HealthChange => "health_change",
+            Self::Dropped => "dropped",
+        }
+    }
+}
+
+/// One event for `GET /api/events` clients
+#[derive(Debug, Clone)]
+pub struct StreamEvent {
+    pub kind: EventKind,
+    /// Machine the event is about
+    pub machine: Option<String>,
+    /// Events without one pass every severity filter
+    pub severity: Option<Severity>,
+    /// The SSE `data:` payload
+    pub data: serde_json::Value,
+}
+
+impl StreamEvent {
+    #[must_use]
+    pub fn heartbeat(uptime_secs: u64) -> Self {
+        Self {
+            kind: EventKind::Heartbeat,
+            machine: None,
+            severity: None,
+            data: serde_json::json!({ "uptime_secs": uptime_secs }),
+        }
+    }
+
+    /// An `alert_history` row (`id`, `machine_id`, `severity`, `title`,
+    /// `fired_at`)
+    #[must_use]
+    pub fn alert(row: &serde_json::Value) -> Self {
+        let severity = row["severity"]
+            .as_str()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(Severity::Info);
+        Self {
+            kind: EventKind::Alert,
+            machine: row["machine_id"].as_str().map(str::to_string),
+            severity: Some(severity),
+            data: serde_json::json!({
+                "id": row["id"],
+                "machine_id": row["machine_id"],
+                "severity": severity.as_str(),
+                "title": row["title"],
+                "fired_at": row["fired_at"],
+            }),
+        }
+    }
+
+    /// A machine's health score moved from `old_score` to `new_score`
+    #[must_use]
+    pub fn health_change(machine: &str, old_score: f64, new_score: f64) -> Self {
+        let severity = if new_score < 0.5 {
+            Severity::Critical
+        } else if new_score < 0.8 {
+            Severity::Warning
+        } else {
+            Severity::Info
+        };
+        Self {
+            kind: EventKind::HealthChange,
+            machine: Some(machine.to_string()),
+            severity: Some(severity),
+            data: serde_json::json!({
+                "machine_id": machine,
+                "old_score": old_score,
+                "new_score": new_score,
+            }),
+        }
+    }
+
+    fn dropped(count: u64) -> Self {
+        Self {
+            kind: EventKind::Dropped,
+            machine: None,
+            severity: None,
+            data: serde_json::json!({ "count": count }),
+        }
+    }
+
+    /// The SSE frame for this event
+    #[must_use]
+    pub fn to_sse(&self) -> Event {
+        Event::default()
+            .event(self.kind.as_str())
+            .data(self.data.to_string())
+    }
+}
+
+/// Query parameters of `GET /api/events`
+#[derive(Debug, Default, Deserialize)]
+pub struct EventStreamParams {
+    /// Comma-separated machine IDs
+    pub machine: Option<String>,
+    /// Minimum severity: info, warning or critical
+    pub severity: Option<String>,
+}
+
+/// Which events one client receives. Events without a machine or severity
+/// (heartbeats) pass the matching check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    pub machines: Option<Vec<String>>,
+    pub min_severity: Option<Severity>,
+}
+
+impl EventFilter {
+    /// The filter `params` describe
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the severity when it is not one.
+    pub fn from_params(params: &EventStreamParams) -> Result<Self, String> {
+        let machines = params.machine.as_deref().map(|list| {
+            list.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        });
+        let min_severity = params
+            .severity
+            .as_deref()
+            .map(str::parse::<Severity>)
+            .transpose()?;
+        Ok(Self {
+            machines: machines.filter(|ids| !ids.is_empty()),
+            min_severity,
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, event: &StreamEvent) -> bool {
+        let machine_ok = match (&self.machines, &event.machine) {
+            (Some(ids), Some(machine)) => ids.contains(machine),
+            _ => true,
+        };
+        let severity_ok = match (self.min_severity, event.severity) {
+            (Some(min), Some(severity)) => severity >= min,
+            _ => true,
+        };
+        machine_ok && severity_ok
+    }
+}
+
+/// Why an event was dropped from a full client queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    Heartbeat,
+    /// An older health change for the same machine
+    Coalesced,
+    /// The oldest event, reported to the client
+    Overflow,
+}
+
+impl DropReason {
+    pub const ALL: [Self; 3] = [Self::Heartbeat, Self::Coalesced, Self::Overflow];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Heartbeat => "heartbeat",
+            Self::Coalesced => "coalesced",
+            Self::Overflow => "overflow",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ClientQueue {
+    events: VecDeque<StreamEvent>,
+    /// Overflow drops not yet reported to the client
+    dropped: u64,
+}
+
+impl ClientQueue {
+    /// Queue `event`, making room first when `capacity` is reached
+    fn push(&mut self, event: StreamEvent, capacity: usize) -> Option<DropReason> {
+        if self.events.len() < capacity {
+            self.events.push_back(event);
+            return None;
+        }
+        let reason = if let Some(i) = self
+            .events
+            .iter()
+            .position(|queued| queued.kind == EventKind::Heartbeat)
+        {
+            self.events.remove(i);
+            DropReason::Heartbeat
+        } else if event.kind == EventKind::Heartbeat {
+            return Some(DropReason::Heartbeat);
+        } else if let Some(i) = self.superseded_health_change(&event) {
+            self.events.remove(i);
+            DropReason::Coalesced
+        } else {
+            self.events.pop_front();
+            self.dropped += 1;
+            DropReason::Overflow
+        };
+        self.events.push_back(event);
+        Some(reason)
+    }
+
+    /// The oldest queued health change for a machine that has a later one
+    /// queued or `incoming`
+    fn superseded_health_change(&self, incoming: &StreamEvent) -> Option<usize> {
+        let is_health = |event: &StreamEvent| event.kind == EventKind::HealthChange;
+        self.events.iter().enumerate().position(|(i, queued)| {
+            is_health(queued)
+                && self
+                    .events
+                    .iter()
+                    .skip(i + 1)
+                    .chain(std::iter::once(incoming))
+                    .any(|later| is_health(later) && later.machine == queued.machine)
+        })
+    }
+
+    /// The next event to send; a pending drop count goes first
+    fn pop(&mut self) -> Option<StreamEvent> {
+        if self.dropped > 0 {
+            return Some(StreamEvent::dropped(std::mem::take(&mut self.dropped)));
+        }
+        self.events.pop_front()
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    id: u64,
+    filter: EventFilter,
+    queue: Mutex<ClientQueue>,
+    ready: Notify,
+}
+
+/// Counters behind the `vc_web_events_*` metrics
+#[derive(Debug, Default)]
+pub struct EventMetrics {
+    clients: AtomicUsize,
+    delivered: AtomicU64,
+    dropped: [AtomicU64; 3],
+}
+
+impl EventMetrics {
+    /// Clients currently connected
+    #[must_use]
+    pub fn clients(&self) -> usize {
+        self.clients.load(Ordering::Relaxed)
+    }
+
+    /// Events handed to clients since start
+    #[must_use]
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Events dropped from full queues for `reason` since start
+    #[must_use]
+    pub fn dropped(&self, reason: DropReason) -> u64 {
+        self.dropped[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// Prometheus text lines
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "# HELP vc_web_event_clients Clients connected to /api/events".to_string(),
+            "# TYPE vc_web_event_clients gauge".to_string(),
+            format!("vc_web_event_clients {}", self.clients()),
+            "# HELP vc_web_events_delivered_total Events sent to /api/events clients".to_string(),
+            "# TYPE vc_web_events_delivered_total counter".to_string(),
+            format!("vc_web_events_delivered_total {}", self.delivered()),
+            "# HELP vc_web_events_dropped_total Events dropped from full client queues".to_string(),
+            "# TYPE vc_web_events_dropped_total counter".to_string(),
+        ];
+        lines.extend(DropReason::ALL.iter().map(|reason| {
+            format!(
+                "vc_web_events_dropped_total{{reason=\"{}\"}} {}",
+                reason.as_str(),
+                self.dropped(*reason)
+            )
+        }));
+        lines
+    }
+}
+
+/// Fans stream events out to every connected client
+#[derive(Debug)]
+pub struct EventHub {
+    queue_size: usize,
+    clients: Mutex<Vec<Arc<Client>>>,
+    next_id: AtomicU64,
+    closed: AtomicBool,
+    metrics: EventMetrics,
+}
+
+impl EventHub {
+    /// A hub queueing up to `queue_size` events per client
+    #[must_use]
+    pub fn new(queue_size: usize) -> Self {
+        Self {
+            queue_size: queue_size.max(1),
+            clients: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+            closed: AtomicBool::new(false),
+            metrics: EventMetrics::default(),
+        }
+    }
+
+    /// Connect a client receiving the events `filter` passes
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client list mutex is poisoned.
+    #[must_use]
+    pub fn subscribe(self: &Arc<Self>, filter: EventFilter) -> Subscription {
+        let client = Arc::new(Client {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            filter,
+            queue: Mutex::new(ClientQueue::default()),
+            ready: Notify::new(),
+        });
+        self.clients.lock().unwrap().push(Arc::clone(&client));
+        self.metrics.clients.fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(client = client.id, filter = ?client.filter, "event client connected");
+        Subscription {
+            hub: Arc::clone(self),
+            client,
+        }
+    }
+
+    /// Queue `event` for every client whose filter it passes. Returns how
+    /// many it was offered to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a client mutex is poisoned.
+    pub fn publish(&self, event: &StreamEvent) -> usize {
+        let clients = self.clients.lock().unwrap();
+        let mut offered = 0;
+        for client in clients.iter().filter(|client| client.filter.matches(event)) {
+            let dropped = client
+                .queue
+                .lock()
+                .unwrap()
+                .push(event.clone(), self.queue_size);
+            if let Some(reason) = dropped {
+                self.metrics.dropped[reason as usize].fetch_add(1, Ordering::Relaxed);
+            }
+            client.ready.notify_one();
+            offered += 1;
+        }
+        offered
+    }
+
+    /// End every client's stream and stop the poller
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client list mutex is poisoned.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        for client in self.clients.lock().unwrap().iter() {
+            client.ready.notify_one();
+        }
+    }
+
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    #[must_use]
+    pub fn metrics(&self) -> &EventMetrics {
+        &self.metrics
+    }
+}
+
+/// One connected client; dropping it disconnects the client
+#[derive(Debug)]
+pub struct Subscription {
+    hub: Arc<EventHub>,
+    client: Arc<Client>,
+}
+
+impl Subscription {
+    /// The next event, waiting for one; `None` once the hub is closed
+    pub async fn next(&self) -> Option<StreamEvent> {
+        loop {
+            if let Some(event) = self.try_next() {
+                return Some(event);
+            }
+            if self.hub.is_closed() {
+                return None;
+            }
+            self.client.ready.notified().await;
+        }
+    }
+
+    /// The next event if one is queued
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client mutex is poisoned.
+    #[must_use]
+    pub fn try_next(&self) -> Option<StreamEvent> {
+        let event = self.client.queue.lock().unwrap().pop()?;
+        self.hub.metrics.delivered.fetch_add(1, Ordering::Relaxed);
+        Some(event)
+    }
+
+    /// Events waiting in this client's queue
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client mutex is poisoned.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.client.queue.lock().unwrap().events.len()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Ok(mut clients) = self.hub.clients.lock() {
+            clients.retain(|client| client.id != self.client.id);
+        }
+        self.hub.metrics.clients.fetch_sub(1, Ordering::Relaxed);
+        tracing::debug!(client = self.client.id, "event client disconnected");
+    }
+}
+
+/// Turns what changed in the store since the previous poll into events
+#[derive(Debug, Default)]
+pub struct EventPoller {
+    /// Highest `alert_history.id` seen; `None` before the first poll
+    last_alert_id: Option<i64>,
+    /// Latest health score per machine as of the previous poll
+    scores: HashMap<String, f64>,
+}
+
+impl EventPoller {
+    /// New alerts, oldest first, then machines whose latest health score
+    /// changed. The first poll only notes where the store stands, so a
+    /// restart does not replay history.
+    pub fn poll(&mut self, store: &VcStore) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+
+        match self.last_alert_id {
+            None => {
+                self.last_alert_id = Some(
+                    store
+                        .query_scalar("SELECT COALESCE(MAX(id), 0) FROM alert_history")
+                        .unwrap_or(0),
+                );
+            }
+            Some(last) => {
+                let sql = format!(
+                    "SELECT id, machine_id, severity, title, CAST(fired_at AS TEXT) AS fired_at \
+                     FROM alert_history WHERE id > {last} ORDER BY id"
+                );
+                match store.query_json(&sql) {
+                    Ok(rows) => {
+                        for row in &rows {
+                            if let Some(id) = row["id"].as_i64() {
+                                self.last_alert_id = Some(id.max(last));
+                            }
+                            events.push(StreamEvent::alert(row));
+                        }
+                    }
+                    Err(e) => tracing::debug!(error = %e, "event poll: alerts unavailable"),
+                }
+            }
+        }
+
+        match QueryBuilder::new(store).list_health_summaries() {
+            Ok(rows) => {
+                for row in &rows {
+                    let (Some(machine), Some(score)) =
+                        (row["machine_id"].as_str(), row["overall_score"].as_f64())
+                    else {
+                        continue;
+                    };
+                    let previous = self.scores.insert(machine.to_string(), score);
+                    if let Some(old) = previous.filter(|old| (old - score).abs() > f64::EPSILON) {
+                        events.push(StreamEvent::health_change(machine, old, score));
+                    }
+                }
+            }
+            Err(e) => tracing::debug!(error = %e, "event poll: health scores unavailable"),
+        }
+        events
+    }
+}
+
+/// Poll `store` every `interval` and publish what changed to `hub`, with a
+/// heartbeat each time, until the hub is closed
+pub async fn run_poller(store: &VcStore, hub: &EventHub, interval: Duration, started: Instant) {
+    let mut poller = EventPoller::default();
+    let mut ticker = tokio::time::interval(interval);
+    while !hub.is_closed() {
+        ticker.tick().await;
+        for event in poller.poll(store) {
+            hub.publish(&event);
+        }
+        hub.publish(&StreamEvent::heartbeat(started.elapsed().as_secs()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(id: i64, machine: &str, severity: &str) -> StreamEvent {
+        StreamEvent::alert(&serde_json::json!({
+            "id": id,
+            "machine_id": machine,
+            "severity": severity,
+            "title": "t",
+        }))
+    }
+
+    fn kinds(subscription: &Subscription) -> Vec<(EventKind, Option<String>)> {
+        std::iter::from_fn(|| subscription.try_next())
+            .map(|event| (event.kind, event.machine))
+            .collect()
+    }
+
+    #[test]
+    fn test_full_queue_drops_heartbeats_then_coalesces_then_oldest() {
+        let hub = Arc::new(EventHub::new(3));
+        let client = hub.subscribe(EventFilter::default());
+
+        hub.publish(&StreamEvent::heartbeat(1));
+        hub.publish(&StreamEvent::health_change("m1", 0.9, 0.7));
+        hub.publish(&alert(1, "m2", "warning"));
+        // Full: the heartbeat goes first
+        hub.publish(&StreamEvent::health_change("m1", 0.7, 0.4));
+        assert_eq!(hub.metrics().dropped(DropReason::Heartbeat), 1);
+        // Full, no heartbeat: an incoming heartbeat is dropped itself
+        hub.publish(&StreamEvent::heartbeat(2));
+        assert_eq!(hub.metrics().dropped(DropReason::Heartbeat), 2);
+        // Then the older m1 health change makes room
+        hub.publish(&alert(2, "m3", "critical"));
+        assert_eq!(hub.metrics().dropped(DropReason::Coalesced), 1);
+        // Finally the oldest event, reported to the client
+        hub.publish(&alert(3, "m4", "info"));
+        assert_eq!(hub.metrics().dropped(DropReason::Overflow), 1);
+        assert_eq!(client.queued(), 3);
+
+        let received: Vec<StreamEvent> = std::iter::from_fn(|| client.try_next()).collect();
+        assert_eq!(received[0].kind, EventKind::Dropped);
+        assert_eq!(received[0].data["count"], 1);
+        let rest: Vec<Option<&str>> = received[1..]
+            .iter()
+            .map(|event| event.machine.as_deref())
+            .collect();
+        // The m2 alert was the oldest; m1 kept its latest health change
+        assert_eq!(rest, [Some("m1"), Some("m3"), Some("m4")]);
+        assert_eq!(received[1].data["new_score"], 0.4);
+        assert_eq!(hub.metrics().delivered(), 4);
+    }
+
+    #[test]
+    fn test_filtered_events_never_take_queue_space() {
+        let hub = Arc::new(EventHub::new(2));
+        let filter = EventFilter::from_params(&EventStreamParams {
+            machine: Some("m1, m2".to_string()),
+            severity: Some("warning".to_string()),
+        })
+        .unwrap();
+        let client = hub.subscribe(filter);
+
+        for id in 0..100 {
+            hub.publish(&alert(id, "m3", "critical"));
+            hub.publish(&alert(id, "m1", "info"));
+        }
+        assert_eq!(client.queued(), 0);
+        hub.publish(&alert(1, "m1", "critical"));
+        hub.publish(&StreamEvent::heartbeat(1));
+        assert_eq!(
+            kinds(&client),
+            [
+                (EventKind::Alert, Some("m1".to_string())),
+                (EventKind::Heartbeat, None)
+            ]
+        );
+        assert_eq!(hub.metrics().dropped(DropReason::Overflow), 0);
+
+        assert!(
+            EventFilter::from_params(&EventStreamParams {
+                machine: None,
+                severity: Some("loud".to_string()),
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_slow_clients_stay_bounded_and_do_not_slow_fast_ones() {
+        const QUEUE: usize = 32;
+        const EVENTS: i64 = 3_000;
+        let hub = Arc::new(EventHub::new(QUEUE));
+        let slow: Vec<Subscription> = (0..50)
+            .map(|_| hub.subscribe(EventFilter::default()))
+            .collect();
+        let fast = hub.subscribe(EventFilter::default());
+        assert_eq!(hub.metrics().clients(), 51);
+
+        let mut fast_received = Vec::new();
+        for id in 0..EVENTS {
+            let machine = format!("m{}", id % 10);
+            match id % 3 {
+                0 => hub.publish(&StreamEvent::heartbeat(0)),
+                1 => hub.publish(&StreamEvent::health_change(&machine, 0.9, 0.5)),
+                _ => hub.publish(&alert(id, &machine, "warning")),
+            };
+            // The fast client keeps up; the slow ones never read
+            fast_received.extend(std::iter::from_fn(|| fast.try_next()));
+            for client in &slow {
+                assert!(client.queued() <= QUEUE);
+            }
+        }
+
+        assert_eq!(fast_received.len(), usize::try_from(EVENTS).unwrap());
+        assert!(
+            fast_received
+                .iter()
+                .all(|event| event.kind != EventKind::Dropped)
+        );
+        for client in &slow {
+            assert_eq!(client.queued(), QUEUE);
+        }
+        let metrics = hub.metrics();
+        assert!(metrics.dropped(DropReason::Heartbeat) > 0);
+        assert!(metrics.dropped(DropReason::Coalesced) > 0);
+        assert!(metrics.dropped(DropReason::Overflow) > 0);
+
+        // A slow client that reads again learns how much it missed
+        let first = slow[0].try_next().unwrap();
+        assert_eq!(first.kind, EventKind::Dropped);
+        assert!(first.data["count"].as_u64().unwrap() > 0);
+
+        drop(slow);
+        assert_eq!(hub.metrics().clients(), 1);
+    }
+
+    #[test]
+    fn test_poller_reports_new_alerts_and_health_changes() {
+        let store = VcStore::open_memory().unwrap();
+        store
+            .execute_batch(
+                "INSERT INTO alert_history (id, fired_at, severity, title, machine_id)
+                 VALUES (1, '2026-01-01 00:00:00', 'warning', 'old', 'm1');
+                 INSERT INTO health_summary (machine_id, collected_at, overall_score)
+                 VALUES ('m1', '2026-01-01 00:00:00', 0.9);",
+            )
+            .unwrap();
+        let mut poller = EventPoller::default();
+        // History is not replayed
+        assert!(poller.poll(&store).is_empty());
+
+        store
+            .execute_batch(
+                "INSERT INTO alert_history (id, fired_at, severity, title, machine_id)
+                 VALUES (2, '2026-01-01 00:05:00', 'critical', 'new', 'm1');
+                 INSERT INTO health_summary (machine_id, collected_at, overall_score)
+                 VALUES ('m1', '2026-01-01 00:05:00', 0.4);",
+            )
+            .unwrap();
+        let events = poller.poll(&store);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::Alert);
+        assert_eq!(events[0].data["title"], "new");
+        assert_eq!(events[0].severity, Some(Severity::Critical));
+        assert_eq!(events[1].kind, EventKind::HealthChange);
+        assert_eq!(events[1].data["old_score"], 0.9);
+        assert!(poller.poll(&store).is_empty());
+    }
+}
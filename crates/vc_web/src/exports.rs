@@ -0,0 +1,497 @@
+//! Export bundle downloads for `/api/exports`.
+//!
+//! Bundles are the directories `vc db export` recorded in the store. Only
+//! plain file names directly inside a recorded directory are served: a name
+//! with a path separator or a leading dot is refused outright, and the
+//! resolved path (after following symlinks) must still sit in that
+//! directory.
+//!
+//! Files are served with a strong `ETag` built from size and modification
+//! time, and honor a single `Range: bytes=` range so an interrupted
+//! download can resume. `If-Range` with a stale `ETag` gets the whole file.
+//! A whole bundle can also be fetched as one uncompressed tar stream.
+
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use futures::{Stream, StreamExt, TryStreamExt, future, stream};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use vc_store::ExportBundle;
+
+/// Bytes read from disk per body chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Tar block size; headers and file data are padded to it
+const TAR_BLOCK: u64 = 512;
+
+/// Block padding and the two-block end-of-archive marker
+static ZEROS: [u8; 1024] = [0; 1024];
+
+/// One file in an export bundle
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportFile {
+    pub name: String,
+    pub size: u64,
+    /// Strong validator for `If-Range`
+    pub etag: String,
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    mtime_secs: u64,
+}
+
+/// A recorded bundle with the files currently in its directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportBundleListing {
+    #[serde(flatten)]
+    pub bundle: ExportBundle,
+    /// False once the directory is gone or unreadable
+    pub available: bool,
+    pub files: Vec<ExportFile>,
+}
+
+impl ExportBundleListing {
+    #[must_use]
+    pub fn new(bundle: ExportBundle) -> Self {
+        let files = bundle_files(Path::new(&bundle.output_dir));
+        Self {
+            available: files.is_some(),
+            files: files.unwrap_or_default(),
+            bundle,
+        }
+    }
+}
+
+/// Resolve `name` inside `root`, or `None` if it is not a plain file name,
+/// does not exist, is not a regular file, or resolves outside `root`
+#[must_use]
+pub fn resolve_file(root: &Path, name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', '\0']) {
+        return None;
+    }
+    let root = root.canonicalize().ok()?;
+    let path = root.join(name).canonicalize().ok()?;
+    (path.parent() == Some(root.as_path()) && path.is_file()).then_some(path)
+}
+
+/// Describe `name` in `root`, confined as in [`resolve_file`]
+#[must_use]
+pub fn export_file(root: &Path, name: &str) -> Option<ExportFile> {
+    let path = resolve_file(root, name)?;
+    let metadata = path.metadata().ok()?;
+    let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Some(ExportFile {
+        name: name.to_string(),
+        size: metadata.len(),
+        etag: format!(
+            "\"{:x}-{:x}-{:x}\"",
+            metadata.len(),
+            since_epoch.as_secs(),
+            since_epoch.subsec_nanos()
+        ),
+        path,
+        mtime_secs: since_epoch.as_secs(),
+    })
+}
+
+/// Files directly inside an export directory, by name, or `None` if the
+/// directory cannot be read
+#[must_use]
+pub fn bundle_files(root: &Path) -> Option<Vec<ExportFile>> {
+    let mut files: Vec<ExportFile> = std::fs::read_dir(root)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| export_file(root, entry.file_name().to_str()?))
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Some(files)
+}
+
+/// Inclusive byte range of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Always false: a satisfiable range holds at least one byte
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// What a `Range` header asks of a file of a given size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// No usable range: send the whole file
+    Full,
+    Partial(ByteRange),
+    /// Malformed, or starts past the end: 416
+    Unsatisfiable,
+}
+
+/// Interpret a `Range` header against a file of `len` bytes.
+///
+/// Supports one `bytes=` range: `first-last`, `first-` and the suffix form
+/// `-count`. `last` past the end is clamped. Other units and multi-range
+/// requests are ignored and get the whole file.
+#[must_use]
+pub fn parse_range(value: &str, len: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return RangeRequest::Unsatisfiable;
+    };
+    let parse = |s: &str| {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            None
+        } else {
+            s.parse::<u64>().ok()
+        }
+    };
+    let range = match (first.is_empty(), last.is_empty()) {
+        // `-count`: the final `count` bytes
+        (true, false) => parse(last)
+            .filter(|count| *count > 0 && len > 0)
+            .map(|count| ByteRange {
+                start: len.saturating_sub(count),
+                end: len - 1,
+            }),
+        // `first-`: from `first` to the end
+        (false, true) => parse(first)
+            .filter(|start| *start < len)
+            .map(|start| ByteRange {
+                start,
+                end: len - 1,
+            }),
+        (false, false) => match (parse(first), parse(last)) {
+            (Some(start), Some(end)) if start <= end && start < len => Some(ByteRange {
+                start,
+                end: end.min(len - 1),
+            }),
+            _ => None,
+        },
+        (true, true) => None,
+    };
+    range.map_or(RangeRequest::Unsatisfiable, RangeRequest::Partial)
+}
+
+fn content_type(name: &str) -> &'static str {
+    match Path::new(name).extension().and_then(|ext| ext.to_str()) {
+        Some("jsonl") => "application/x-ndjson",
+        Some("json") => "application/json",
+        Some("csv") => "text/csv; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+fn attachment(name: &str) -> HeaderValue {
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    HeaderValue::from_str(&format!("attachment; filename=\"{safe}\""))
+        .unwrap_or_else(|_| HeaderValue::from_static("attachment"))
+}
+
+fn header_value(value: &str) -> HeaderValue {
+    HeaderValue::from_str(value).unwrap_or_else(|_| HeaderValue::from_static(""))
+}
+
+/// `len` bytes of `path` from `start`, in chunks. A file that shrinks
+/// while being read ends the body with an error instead of short data.
+fn file_chunks(
+    path: PathBuf,
+    start: u64,
+    len: u64,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+    stream::once(async move {
+        let mut file = tokio::fs::File::open(&path).await?;
+        file.seek(SeekFrom::Start(start)).await?;
+        Ok::<_, io::Error>(stream::try_unfold(
+            (file, len),
+            |(mut file, remaining)| async move {
+                if remaining == 0 {
+                    return Ok(None);
+                }
+                let mut buf = vec![
+                    0;
+                    usize::try_from(remaining)
+                        .unwrap_or(CHUNK_SIZE)
+                        .min(CHUNK_SIZE)
+                ];
+                let read = file.read(&mut buf).await?;
+                if read == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "export file shrank while being served",
+                    ));
+                }
+                buf.truncate(read);
+                let read = u64::try_from(read).unwrap_or(remaining);
+                Ok(Some((
+                    Bytes::from(buf),
+                    (file, remaining.saturating_sub(read)),
+                )))
+            },
+        ))
+    })
+    .try_flatten()
+}
+
+/// Serve one bundle file, honoring `Range` and `If-Range`
+#[must_use]
+pub fn file_response(file: &ExportFile, headers: &HeaderMap) -> Response {
+    let range_header = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok());
+    // A stale validator means the client's partial copy is of another file
+    let if_range_matches = headers
+        .get(header::IF_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .is_none_or(|tag| tag == file.etag);
+    let request = match range_header {
+        Some(value) if if_range_matches => parse_range(value, file.size),
+        _ => RangeRequest::Full,
+    };
+
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(header::ETAG, header_value(&file.etag));
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    match request {
+        RangeRequest::Unsatisfiable => {
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!("bytes */{}", file.size)),
+            );
+            (StatusCode::RANGE_NOT_SATISFIABLE, response_headers).into_response()
+        }
+        RangeRequest::Full => {
+            response_headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type(&file.name)),
+            );
+            response_headers.insert(header::CONTENT_DISPOSITION, attachment(&file.name));
+            response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(file.size));
+            let body = Body::from_stream(file_chunks(file.path.clone(), 0, file.size));
+            (StatusCode::OK, response_headers, body).into_response()
+        }
+        RangeRequest::Partial(range) => {
+            response_headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static(content_type(&file.name)),
+            );
+            response_headers.insert(header::CONTENT_DISPOSITION, attachment(&file.name));
+            response_headers.insert(header::CONTENT_LENGTH, HeaderValue::from(range.len()));
+            response_headers.insert(
+                header::CONTENT_RANGE,
+                header_value(&format!(
+                    "bytes {}-{}/{}",
+                    range.start, range.end, file.size
+                )),
+            );
+            let body = Body::from_stream(file_chunks(file.path.clone(), range.start, range.len()));
+            (StatusCode::PARTIAL_CONTENT, response_headers, body).into_response()
+        }
+    }
+}
+
+fn tar_padding(size: u64) -> usize {
+    usize::try_from((TAR_BLOCK - size % TAR_BLOCK) % TAR_BLOCK).unwrap_or(0)
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{value:0digits$o}");
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+/// ustar header for a regular file, or `None` if the name or size does
+/// not fit the format
+fn tar_header(name: &str, size: u64, mtime_secs: u64) -> Option<[u8; 512]> {
+    // 11 octal digits: sizes up to 8 GiB
+    if name.len() > 100 || size >= 1 << 33 {
+        return None;
+    }
+    let mut header = [0u8; 512];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], size);
+    write_octal(&mut header[136..148], mtime_secs.min(0o777_7777_7777));
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // The checksum is computed with its own field as spaces
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|b| u64::from(*b)).sum();
+    write_octal(&mut header[148..155], checksum);
+    header[155] = b' ';
+    Some(header)
+}
+
+/// Stream a bundle's files as an uncompressed tar under a `<bundle_id>/`
+/// directory. Files whose name or size ustar cannot hold are left out.
+#[must_use]
+pub fn tar_response(bundle_id: &str, files: Vec<ExportFile>) -> Response {
+    let mut total: u64 = 2 * TAR_BLOCK;
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+        let Some(header) = tar_header(
+            &format!("{bundle_id}/{}", file.name),
+            file.size,
+            file.mtime_secs,
+        ) else {
+            tracing::warn!(bundle = bundle_id, file = %file.name, "left out of tar stream");
+            continue;
+        };
+        let padding = tar_padding(file.size);
+        total += TAR_BLOCK + file.size + u64::try_from(padding).unwrap_or(0);
+        entries.push((header, file, padding));
+    }
+
+    let body = stream::iter(entries)
+        .flat_map(|(header, file, padding)| {
+            stream::once(future::ready(Ok(Bytes::copy_from_slice(&header))))
+                .chain(file_chunks(file.path, 0, file.size))
+                .chain(stream::once(future::ready(Ok(Bytes::from_static(
+                    &ZEROS[..padding],
+                )))))
+        })
+        .chain(stream::once(future::ready(Ok(Bytes::from_static(&ZEROS)))));
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/x-tar"),
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(total));
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        attachment(&format!("{bundle_id}.tar")),
+    );
+    (StatusCode::OK, headers, Body::from_stream(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(start: u64, end: u64) -> RangeRequest {
+        RangeRequest::Partial(ByteRange { start, end })
+    }
+
+    #[test]
+    fn test_parse_range_forms() {
+        assert_eq!(parse_range("bytes=0-99", 1000), partial(0, 99));
+        assert_eq!(parse_range("bytes=900-", 1000), partial(900, 999));
+        // Suffix ranges take the final bytes, all of them if longer
+        assert_eq!(parse_range("bytes=-100", 1000), partial(900, 999));
+        assert_eq!(parse_range("bytes=-5000", 1000), partial(0, 999));
+        // The last position is clamped to the file
+        assert_eq!(parse_range("bytes=990-2000", 1000), partial(990, 999));
+    }
+
+    #[test]
+    fn test_parse_range_unsatisfiable() {
+        for value in [
+            "bytes=1000-",
+            "bytes=1000-1001",
+            "bytes=-0",
+            "bytes=500-100",
+            "bytes=abc-",
+            "bytes=-",
+            "bytes=5",
+            "bytes=+5-10",
+        ] {
+            assert_eq!(
+                parse_range(value, 1000),
+                RangeRequest::Unsatisfiable,
+                "{value}"
+            );
+        }
+        assert_eq!(parse_range("bytes=-10", 0), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn test_parse_range_ignored() {
+        assert_eq!(parse_range("items=0-5", 1000), RangeRequest::Full);
+        assert_eq!(parse_range("bytes=0-5,10-20", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_resolve_file_confined_to_root() {
+        let outer = tempfile::tempdir().unwrap();
+        let root = outer.path().join("bundle");
+        std::fs::create_dir(&root).unwrap();
+        std::fs::write(root.join("alerts.jsonl"), "{}\n").unwrap();
+        std::fs::write(outer.path().join("secret.txt"), "nope").unwrap();
+        std::fs::create_dir(root.join("nested")).unwrap();
+
+        assert!(resolve_file(&root, "alerts.jsonl").is_some());
+        for name in [
+            "",
+            "..",
+            "../secret.txt",
+            "nested",
+            "nested/x",
+            ".hidden",
+            "..\\secret.txt",
+        ] {
+            assert!(resolve_file(&root, name).is_none(), "{name}");
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outer.path().join("secret.txt"), root.join("link.txt"))
+                .unwrap();
+            assert!(resolve_file(&root, "link.txt").is_none());
+            let names: Vec<String> = bundle_files(&root)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.name)
+                .collect();
+            assert_eq!(names, vec!["alerts.jsonl"]);
+        }
+    }
+
+    #[test]
+    fn test_tar_header_checksum_and_fields() {
+        let header = tar_header("ex-1/manifest.json", 1234, 1_700_000_000).unwrap();
+        assert_eq!(&header[..18], b"ex-1/manifest.json");
+        assert_eq!(&header[124..136], b"00000002322\0");
+        assert_eq!(&header[257..262], b"ustar");
+        let stored = std::str::from_utf8(&header[148..154]).unwrap();
+        let mut blank = header;
+        blank[148..156].fill(b' ');
+        let sum: u64 = blank.iter().map(|b| u64::from(*b)).sum();
+        assert_eq!(u64::from_str_radix(stored, 8).unwrap(), sum);
+        assert!(tar_header(&"x".repeat(101), 1, 0).is_none());
+        assert_eq!(tar_padding(1234), 302);
+        assert_eq!(tar_padding(1024), 0);
+    }
+}
@@ -5,23 +5,43 @@
 //! - JSON API endpoints
 //! - Static file serving for dashboard
 //! - WebSocket support for real-time updates
-//! - Token-based authentication with RBAC
+//! - Server-sent alert and health events with per-client backpressure
+//! - Token-based authentication with RBAC, and OIDC login for people
+//! - Agent-safe query templates with per-caller rate limiting
+//! - Operator approval of raw queries agents proposed over MCP
+//! - Replication intake for a warm standby
+//! - Export bundle downloads with `Range` resume and whole-bundle tar streams
+//! - Robot envelopes (`GET /api/robot/<name>`), shared with `vc robot`
+//!
+//! Response bodies are the [`vc_types`] structs, which `vc_client` reads
+//! back.
 
 pub mod auth;
+pub mod events;
+pub mod exports;
+pub mod oidc;
+pub mod rate_limit;
+pub mod signature;
 
 use axum::{
     Router,
     extract::ws::{Message, WebSocket, WebSocketUpgrade},
-    extract::{Path, Query, State},
-    http::{HeaderValue, StatusCode},
-    response::{IntoResponse, Json, Response},
-    routing::get,
+    extract::{ConnectInfo, DefaultBodyLimit, Extension, Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{
+        IntoResponse, Json, Response,
+        sse::{Event, Sse},
+    },
+    routing::{get, post},
 };
 use futures::future::{self, Either};
 use serde::{Deserialize, Serialize};
-use std::future::Future;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
 use std::path::Path as FsPath;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::net::TcpListener;
@@ -29,9 +49,19 @@ use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tower_http::services::ServeDir;
 use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
-use vc_config::WebConfig;
-use vc_query::{FleetOverview, QueryBuilder};
-use vc_store::{VcStore, escape_sql_literal};
+use vc_config::{ReplicationMode, VcConfig, WebConfig};
+use vc_query::{
+    ComponentStatus, ComponentsReport, FleetOverview, GuardrailConfig, QueryBuilder,
+    QueryValidator, ValidationError,
+};
+use vc_robot::toon::ToToon;
+use vc_robot::{RobotEnvelope, RobotError, RobotView};
+use vc_store::{ActorContext, ActorSource, ReplicationBatch, VcStore, escape_sql_literal};
+use vc_types::{
+    AlertsPage, ApiErrorBody, FleetSummary, IncidentDetail, IncidentsPage, MachinesPage,
+    QueryTiming, TemplateCatalog, TemplateQueryResult,
+};
+pub use vc_types::{HealthResponse, TemplateQueryRequest};
 
 /// Web server errors
 #[derive(Error, Debug)]
@@ -42,6 +72,9 @@ pub enum WebError {
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
     #[error("Query error: {0}")]
     QueryError(#[from] vc_query::QueryError),
 
@@ -49,21 +82,39 @@ pub enum WebError {
     StoreError(#[from] vc_store::StoreError),
 }
 
+impl From<RobotError> for WebError {
+    fn from(err: RobotError) -> Self {
+        match err {
+            RobotError::StoreError(e) => WebError::StoreError(e),
+            RobotError::QueryError(e) => WebError::QueryError(e),
+            RobotError::RegistryError(e) => WebError::ServerError(e.to_string()),
+        }
+    }
+}
+
+impl From<ValidationError> for WebError {
+    fn from(err: ValidationError) -> Self {
+        match err {
+            ValidationError::UnknownTemplate { .. } => WebError::NotFound(err.to_string()),
+            _ => WebError::BadRequest(err.to_string()),
+        }
+    }
+}
+
 impl IntoResponse for WebError {
     fn into_response(self) -> Response {
         let (status, message) = match &self {
             WebError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
+            WebError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            WebError::QueryError(e @ vc_query::QueryError::Timeout { .. }) => {
+                (StatusCode::GATEWAY_TIMEOUT, e.to_string())
+            }
             WebError::QueryError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             WebError::StoreError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             WebError::ServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
         };
 
-        let body = serde_json::json!({
-            "error": message,
-            "status": status.as_u16()
-        });
-
-        (status, Json(body)).into_response()
+        (status, Json(ApiErrorBody::new(message, status.as_u16()))).into_response()
     }
 }
 
@@ -75,17 +126,38 @@ pub struct AppState {
     pub start_time: Instant,
     /// Auth config
     pub auth_config: Arc<auth::AuthConfig>,
+    /// Query templates and guardrails for `/api/query/*`
+    pub query_validator: QueryValidator,
+    /// Per-caller limit for `POST /api/query/template`
+    pub query_limiter: rate_limit::RateLimiter,
+    /// Configured replication role; only an unpromoted standby accepts
+    /// `POST /api/replication/batch`
+    pub replication_mode: ReplicationMode,
+    /// `[collectors.min_versions]`, for the outdated-tool actions in
+    /// `GET /api/robot/triage`
+    pub min_versions: HashMap<String, String>,
+    /// `[web.signing]`: when set, inbound pushes must carry a valid
+    /// signature
+    pub signature_verifier: Option<Arc<signature::SignatureVerifier>>,
+    /// Fan-out behind `GET /api/events`
+    pub events: Arc<events::EventHub>,
+    /// `[web.oidc]`: when set, people log in at `/auth/login`
+    pub oidc: Option<Arc<oidc::OidcLogin>>,
+    /// Config `GET /api/health/components` judges components against
+    pub health_config: Arc<VcConfig>,
+    /// Last component report and when it was computed, reused for
+    /// `health_components.cache_ttl_secs`
+    pub component_health: Mutex<Option<(Instant, ComponentsReport)>>,
+    /// `[fleet_run]`: which commands non-admin callers may run through
+    /// `POST /api/fleet/run`, and the output cap
+    pub fleet_run: vc_config::FleetRunConfig,
 }
 
 impl AppState {
     /// Create new app state with the given store
     #[must_use]
     pub fn new(store: VcStore) -> Self {
-        Self {
-            store,
-            start_time: Instant::now(),
-            auth_config: Arc::new(auth::AuthConfig::default()),
-        }
+        Self::new_with_auth(store, Arc::new(auth::AuthConfig::default()))
     }
 
     /// Create new app state with the given store and auth config
@@ -95,6 +167,20 @@ impl AppState {
             store,
             start_time: Instant::now(),
             auth_config,
+            query_validator: QueryValidator::new(GuardrailConfig::default()),
+            query_limiter: rate_limit::RateLimiter::new(
+                WebConfig::default().query_rate_limit_per_min,
+            ),
+            replication_mode: ReplicationMode::Primary,
+            min_versions: HashMap::new(),
+            signature_verifier: None,
+            events: Arc::new(events::EventHub::new(
+                vc_config::EventStreamConfig::default().queue_size,
+            )),
+            oidc: None,
+            health_config: Arc::new(VcConfig::default()),
+            component_health: Mutex::new(None),
+            fleet_run: vc_config::FleetRunConfig::default(),
         }
     }
 
@@ -116,20 +202,84 @@ pub struct WebServer {
 impl WebServer {
     #[must_use]
     pub fn new(store: VcStore, config: WebConfig) -> Self {
-        Self {
-            state: Arc::new(AppState::new(store)), // NOTE: Real app would need a way to pass auth_config
-            config,
-        }
+        // NOTE: Real app would need a way to pass auth_config
+        Self::new_with_auth(store, config, auth::AuthConfig::default())
     }
 
     #[must_use]
     pub fn new_with_auth(store: VcStore, config: WebConfig, auth_config: auth::AuthConfig) -> Self {
+        let mut state = AppState::new_with_auth(store, Arc::new(auth_config));
+        state.query_limiter = rate_limit::RateLimiter::new(config.query_rate_limit_per_min);
+        state.events = Arc::new(events::EventHub::new(config.events.queue_size));
         Self {
-            state: Arc::new(AppState::new_with_auth(store, Arc::new(auth_config))),
+            state: Arc::new(state),
             config,
         }
     }
 
+    /// Set the configured replication role
+    #[must_use]
+    pub fn with_replication_mode(mut self, mode: ReplicationMode) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.replication_mode = mode;
+        }
+        self
+    }
+
+    /// Set the minimum tool versions triage compares against
+    #[must_use]
+    pub fn with_min_versions(mut self, min_versions: HashMap<String, String>) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.min_versions = min_versions;
+        }
+        self
+    }
+
+    /// Require signed requests on inbound push endpoints
+    #[must_use]
+    pub fn with_request_signing(mut self, verifier: signature::SignatureVerifier) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.signature_verifier = Some(Arc::new(verifier));
+        }
+        self
+    }
+
+    /// Let people log in through an OIDC provider
+    #[must_use]
+    pub fn with_oidc(mut self, login: oidc::OidcLogin) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.oidc = Some(Arc::new(login));
+        }
+        self
+    }
+
+    /// Judge `/api/health/components` against the full config
+    #[must_use]
+    pub fn with_health_config(mut self, config: VcConfig) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.health_config = Arc::new(config);
+        }
+        self
+    }
+
+    /// Set the guardrails on `POST /api/fleet/run`
+    #[must_use]
+    pub fn with_fleet_run(mut self, fleet_run: vc_config::FleetRunConfig) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.fleet_run = fleet_run;
+        }
+        self
+    }
+
+    /// Set the guardrails on queries run through the API
+    #[must_use]
+    pub fn with_query_guardrails(mut self, guardrails: GuardrailConfig) -> Self {
+        if let Some(state) = Arc::get_mut(&mut self.state) {
+            state.query_validator = QueryValidator::new(guardrails);
+        }
+        self
+    }
+
     pub fn router(&self) -> Router {
         let mut router = create_router(self.state.clone());
         if let Some(cors) = build_cors_layer(&self.config) {
@@ -149,6 +299,9 @@ impl WebServer {
 
     /// Run the web server until the provided shutdown future resolves.
     ///
+    /// The store is polled for `GET /api/events` alongside; shutdown ends
+    /// every event stream so the server can drain.
+    ///
     /// # Errors
     ///
     /// Returns an error if binding the TCP listener fails or if serving fails.
@@ -161,14 +314,29 @@ impl WebServer {
             .await
             .map_err(|err| WebError::ServerError(err.to_string()))?;
         tracing::info!(%addr, "Starting vc_web server");
-        axum::serve(
+        let hub = Arc::clone(&self.state.events);
+        let serve = axum::serve(
             listener,
             self.router()
                 .into_make_service_with_connect_info::<std::net::SocketAddr>(),
         )
-        .with_graceful_shutdown(shutdown)
-        .await
-        .map_err(|err| WebError::ServerError(err.to_string()))?;
+        .with_graceful_shutdown(async move {
+            shutdown.await;
+            hub.close();
+        })
+        .into_future();
+        let poll = events::run_poller(
+            &self.state.store,
+            &self.state.events,
+            Duration::from_secs(self.config.events.poll_interval_secs),
+            self.state.start_time,
+        );
+        // The poller stops once shutdown closes the hub
+        let served = match future::select(Box::pin(serve), Box::pin(poll)).await {
+            Either::Left((served, _)) => served,
+            Either::Right(((), serve)) => serve.await,
+        };
+        served.map_err(|err| WebError::ServerError(err.to_string()))?;
         Ok(())
     }
 }
@@ -209,14 +377,6 @@ async fn shutdown_signal() {
     }
 }
 
-/// Health check response
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HealthResponse {
-    pub status: String,
-    pub version: String,
-    pub uptime_secs: u64,
-}
-
 /// Maximum allowed limit for pagination to prevent `DoS`.
 const MAX_PAGINATION_LIMIT: usize = 1000;
 /// Maximum allowed offset for pagination
@@ -304,21 +464,41 @@ fn resolve_static_dir() -> Option<String> {
 pub fn create_router(state: Arc<AppState>) -> Router {
     let auth_state = auth::AuthState {
         config: state.auth_config.clone(),
+        sessions: state.oidc.as_ref().map(|login| login.sessions()),
     };
+    // Pushes from scripts and other hosts: HMAC-signed when `[web.signing]`
+    // is on, on top of the bearer token
+    let signed =
+        axum::middleware::from_fn_with_state(state.clone(), signature::signature_middleware);
 
     let api_router = Router::new()
         // Health and overview
         .route("/health", get(health_handler))
+        .route("/health/components", get(health_components_handler))
         .route("/overview", get(overview_handler))
         .route("/fleet", get(fleet_handler))
+        .route("/fleet/run", post(fleet_run_handler))
+        .route("/events", get(events_handler))
         // Machines
         .route("/machines", get(machines_handler))
         .route("/machines/{id}", get(machine_by_id_handler))
         .route("/machines/{id}/health", get(machine_health_handler))
         .route("/machines/{id}/collectors", get(machine_collectors_handler))
+        .route("/machines/{id}/diff", get(machine_diff_handler))
+        // Timeline
+        .route("/timeline", get(timeline_handler))
+        .route("/burndown", get(burndown_handler))
+        .route(
+            "/annotations",
+            get(annotations_handler).merge(post(annotation_create_handler).layer(signed.clone())),
+        )
         // Alerts
         .route("/alerts", get(alerts_handler))
         .route("/alerts/rules", get(alert_rules_handler))
+        .route("/alerts/bulk-ack", post(alerts_bulk_ack_handler))
+        // Incidents
+        .route("/incidents", get(incidents_handler))
+        .route("/incidents/{id}", get(incident_by_id_handler))
         // Accounts
         .route("/accounts", get(accounts_handler))
         // Sessions
@@ -327,13 +507,58 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/guardian/playbooks", get(guardian_playbooks_handler))
         .route("/guardian/runs", get(guardian_runs_handler))
         .route("/guardian/pending", get(guardian_pending_handler))
+        .route(
+            "/guardian/runs/{id}/approve",
+            post(guardian_run_approve_handler),
+        )
+        .route(
+            "/guardian/drafts/{id}/approve",
+            post(guardian_draft_approve_handler),
+        )
+        .route(
+            "/guardian/drafts/{id}/reject",
+            post(guardian_draft_reject_handler),
+        )
+        // Query templates
+        .route("/query/templates", get(query_templates_handler))
+        .route("/query/template", post(query_template_handler))
+        .route("/query/proposals", get(query_proposals_handler))
+        .route(
+            "/query/proposals/{id}/approve",
+            post(query_proposal_approve_handler),
+        )
+        .route(
+            "/query/proposals/{id}/deny",
+            post(query_proposal_deny_handler),
+        )
+        // Robot envelopes
+        .route("/robot/{name}", get(robot_handler))
+        // Export bundles
+        .route("/exports", get(exports_handler))
+        .route("/exports/{id}/archive.tar", get(export_tar_handler))
+        .route("/exports/{id}/{file}", get(export_file_handler))
+        // Replication
+        .route(
+            "/replication/batch",
+            post(replication_batch_handler)
+                .layer(signed)
+                .layer(DefaultBodyLimit::max(REPLICATION_BODY_LIMIT)),
+        )
         .layer(axum::middleware::from_fn_with_state(
             auth_state,
             auth::auth_middleware,
         ));
 
+    let auth_router = Router::new()
+        .route("/login", get(oidc::login_handler))
+        .route("/callback", get(oidc::callback_handler))
+        .route("/logout", post(oidc::logout_handler))
+        .route("/session", get(oidc::session_handler));
+
     let router = Router::new()
         .nest("/api", api_router)
+        // OIDC login
+        .nest("/auth", auth_router)
         // Prometheus metrics
         .route("/metrics", get(metrics_handler))
         // WebSocket
@@ -362,6 +587,34 @@ async fn health_handler(State(state): State<Arc<AppState>>) -> Json<HealthRespon
     })
 }
 
+/// Per-component health for uptime monitors: 200 while every component is
+/// ok or degraded, 503 once the overall status is failed. Reports are
+/// cached for `health_components.cache_ttl_secs`.
+async fn health_components_handler(State(state): State<Arc<AppState>>) -> Response {
+    let ttl = Duration::from_secs(state.health_config.health_components.cache_ttl_secs);
+    let report = {
+        let mut cached = state.component_health.lock().unwrap();
+        match cached.as_ref() {
+            Some((at, report)) if at.elapsed() < ttl => report.clone(),
+            _ => {
+                let report = ComponentsReport::collect(
+                    Ok(&state.store),
+                    &state.health_config,
+                    chrono::Utc::now(),
+                );
+                *cached = Some((Instant::now(), report.clone()));
+                report
+            }
+        }
+    };
+    let status = if report.status == ComponentStatus::Failed {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(report)).into_response()
+}
+
 /// Fleet overview endpoint - returns `FleetOverview` from `vc_query`.
 async fn overview_handler(
     State(state): State<Arc<AppState>>,
@@ -371,20 +624,158 @@ async fn overview_handler(
     Ok(Json(overview))
 }
 
-/// Fleet handler (alias for overview, returns JSON object)
+/// Query parameters for the fleet summary
+#[derive(Debug, Deserialize)]
+pub struct FleetParams {
+    /// `tag` adds the per-tag breakdown
+    pub group_by: Option<String>,
+}
+
+/// Fleet handler (the headline numbers of the overview)
 async fn fleet_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, WebError> {
+    Query(params): Query<FleetParams>,
+) -> Result<Json<FleetSummary>, WebError> {
     let builder = QueryBuilder::new(&state.store);
     let overview = builder.fleet_overview()?;
+    let mut summary = FleetSummary::from(&overview);
+    match params.group_by.as_deref() {
+        None => {}
+        Some("tag") => summary.by_tag = overview.by_tag,
+        Some(other) => {
+            return Err(WebError::BadRequest(format!(
+                "unknown group_by: {other} (expected tag)"
+            )));
+        }
+    }
+    Ok(Json(summary))
+}
+
+/// Body of `POST /api/fleet/run`, the options of `vc fleet run`
+#[derive(Debug, Deserialize)]
+struct FleetRunRequest {
+    command: String,
+    /// all, tag:<tag> or <id>,...
+    #[serde(default = "default_fleet_run_machines")]
+    machines: String,
+    #[serde(default = "default_fleet_run_concurrency")]
+    concurrency: usize,
+    #[serde(default = "default_fleet_run_timeout")]
+    timeout_secs: u64,
+    #[serde(default)]
+    fail_fast: bool,
+    /// Required above `fleet_run.confirm_above` machines, like `--yes`
+    #[serde(default)]
+    confirm: bool,
+}
+
+fn default_fleet_run_machines() -> String {
+    "all".to_string()
+}
+
+const fn default_fleet_run_concurrency() -> usize {
+    8
+}
+
+const fn default_fleet_run_timeout() -> u64 {
+    15
+}
+
+/// Run one command on every selected machine and return each output.
+/// Operators may only run commands `[fleet_run]` allows; admins may run any.
+async fn fleet_run_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Json(request): Json<FleetRunRequest>,
+) -> Result<Response, WebError> {
+    let admin = caller
+        .as_ref()
+        .is_some_and(|Extension(result)| auth::authorize(result, auth::Role::Admin));
+    let Some(actor) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response("fleet_run_requires_operator"));
+    };
+    if let Err(why) = state.fleet_run.check_command(&request.command, admin) {
+        let body = ApiErrorBody {
+            reason: Some("fleet_run_command_not_allowed".to_string()),
+            message: Some(why),
+            ..ApiErrorBody::new("forbidden", 403)
+        };
+        return Ok((StatusCode::FORBIDDEN, Json(body)).into_response());
+    }
+    let selector: vc_collect::fleet_run::MachineSelector =
+        request.machines.parse().map_err(WebError::BadRequest)?;
+    let machines = vc_collect::machine::load_machines(&state.store)
+        .map_err(|e| WebError::ServerError(e.to_string()))?;
+    let mut machines = selector.resolve(machines).map_err(WebError::BadRequest)?;
+    let in_maintenance: Vec<String> = state
+        .store
+        .active_maintenance()?
+        .into_iter()
+        .map(|window| window.machine_id)
+        .filter(|id| machines.iter().any(|m| &m.machine_id == id))
+        .collect();
+    machines.retain(|m| !in_maintenance.contains(&m.machine_id));
+    if machines.is_empty() {
+        return Err(WebError::BadRequest(format!(
+            "no enabled machines outside maintenance match {selector}"
+        )));
+    }
+    if machines.len() > state.fleet_run.confirm_above && !request.confirm {
+        return Err(WebError::BadRequest(format!(
+            "{} machines match; set \"confirm\": true to run on all of them",
+            machines.len()
+        )));
+    }
+
+    let cx = asupersync::Cx::current()
+        .ok_or_else(|| WebError::ServerError("no Asupersync context".to_string()))?;
+    let options = vc_collect::fleet_run::FleetRunOptions {
+        timeout: Duration::from_secs(request.timeout_secs),
+        concurrency: request.concurrency,
+        fail_fast: request.fail_fast,
+        max_output_bytes: state.fleet_run.max_output_bytes,
+    };
+    let (command_id, outputs) = vc_collect::fleet_run::run_recorded(
+        &cx,
+        &state.store,
+        &request.command,
+        &selector,
+        machines,
+        &options,
+        &vc_collect::redact::RedactionEngine::new(),
+        &actor,
+    )
+    .await?;
+    let failed = outputs
+        .iter()
+        .filter(|o| !o.succeeded() && !o.skipped)
+        .count();
+    info!(command_id = %command_id, failed, actor = %actor, "fleet command run");
     Ok(Json(serde_json::json!({
-        "total_machines": overview.total_machines,
-        "online_machines": overview.online_machines,
-        "offline_machines": overview.offline_machines,
-        "fleet_health": overview.fleet_health_score,
-        "active_alerts": overview.active_alerts,
-        "pending_approvals": overview.pending_approvals
-    })))
+        "command_id": command_id,
+        "command": request.command,
+        "machines": selector.to_string(),
+        "in_maintenance": in_maintenance,
+        "failed": failed,
+        "outputs": outputs,
+    }))
+    .into_response())
+}
+
+/// Alerts, health changes and heartbeats as server-sent events, limited to
+/// `?machine=a,b` and `?severity=<minimum>`
+async fn events_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<events::EventStreamParams>,
+) -> Result<Sse<impl futures::Stream<Item = Result<Event, Infallible>>>, WebError> {
+    let filter = events::EventFilter::from_params(&params).map_err(WebError::BadRequest)?;
+    let subscription = state.events.subscribe(filter);
+    let stream = futures::stream::unfold(subscription, |subscription| async move {
+        let event = subscription.next().await?;
+        Some((Ok(event.to_sse()), subscription))
+    });
+    Ok(Sse::new(stream))
 }
 
 // =============================================================================
@@ -395,7 +786,7 @@ async fn fleet_handler(
 async fn machines_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<serde_json::Value>, WebError> {
+) -> Result<Json<MachinesPage>, WebError> {
     let builder = QueryBuilder::new(&state.store);
     let machines = builder.machines()?;
 
@@ -405,12 +796,12 @@ async fn machines_handler(
     let offset = params.bounded_offset();
     let paginated: Vec<_> = machines.into_iter().skip(offset).take(limit).collect();
 
-    Ok(Json(serde_json::json!({
-        "machines": paginated,
-        "total": total,
-        "limit": limit,
-        "offset": offset
-    })))
+    Ok(Json(MachinesPage {
+        machines: paginated,
+        total,
+        limit,
+        offset,
+    }))
 }
 
 /// Get machine by ID
@@ -465,6 +856,236 @@ async fn machine_collectors_handler(
     })))
 }
 
+/// Query parameters for an environment diff: `from`/`to` for one machine
+/// over time, or `against` for another machine as of `to`
+#[derive(Debug, Deserialize)]
+pub struct EnvDiffParams {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub against: Option<String>,
+}
+
+/// Field-level environment diff for a machine (same as `vc machines diff`)
+async fn machine_diff_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<EnvDiffParams>,
+) -> Result<Json<vc_query::EnvDiff>, WebError> {
+    let parse = |raw: &str| {
+        vc_query::timefmt::parse_timestamp(raw)
+            .ok_or_else(|| WebError::BadRequest(format!("invalid timestamp: {raw}")))
+    };
+    let to = params
+        .to
+        .as_deref()
+        .map_or_else(|| Ok(chrono::Utc::now()), parse)?;
+    let from = params.from.as_deref().map(parse).transpose()?;
+
+    let builder = QueryBuilder::new(&state.store);
+    let diff = match (&params.against, from) {
+        (Some(other), from) => builder.environment_diff(
+            &id,
+            to,
+            other,
+            to,
+            from.unwrap_or(to - chrono::Duration::days(7)),
+        ),
+        (None, Some(from)) if from < to => builder.environment_diff(&id, from, &id, to, from),
+        (None, _) => {
+            return Err(WebError::BadRequest(
+                "set from (before to) or against".to_string(),
+            ));
+        }
+    }
+    .map_err(|e| match e {
+        vc_query::QueryError::InvalidQuery(msg) => WebError::NotFound(msg),
+        other => other.into(),
+    })?;
+    Ok(Json(diff))
+}
+
+// =============================================================================
+// Timeline Endpoint
+// =============================================================================
+
+/// Query parameters for the fleet timeline (same as `vc timeline`)
+#[derive(Debug, Deserialize)]
+pub struct TimelineParams {
+    /// Window start: an age such as `24h` or a timestamp (default 24h)
+    pub since: Option<String>,
+    pub machine: Option<String>,
+    /// Comma-separated kinds (default all)
+    pub kinds: Option<String>,
+    pub limit: Option<usize>,
+    /// Cursor from the previous page's `next`
+    pub after: Option<String>,
+}
+
+/// One page of alerts, incidents, fleet commands, audit and drift events
+/// and annotations, oldest first
+async fn timeline_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<TimelineParams>,
+) -> Result<Json<vc_query::TimelinePage>, WebError> {
+    let since = params.since.as_deref().unwrap_or("24h");
+    let since = vc_query::timefmt::parse_since(since, chrono::Utc::now())
+        .ok_or_else(|| WebError::BadRequest(format!("invalid since: {since}")))?;
+    let kinds = params
+        .kinds
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|kind| !kind.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<Vec<vc_query::TimelineKind>, String>>()
+        .map_err(WebError::BadRequest)?;
+    let filter = vc_query::TimelineFilter {
+        since,
+        machine: params.machine,
+        kinds,
+        limit: params
+            .limit
+            .unwrap_or(vc_query::timeline::DEFAULT_TIMELINE_LIMIT),
+        after: params.after,
+    };
+    let page = QueryBuilder::new(&state.store)
+        .timeline(&filter)
+        .map_err(|e| match e {
+            vc_query::QueryError::InvalidQuery(msg) => WebError::BadRequest(msg),
+            other => other.into(),
+        })?;
+    Ok(Json(page))
+}
+
+/// Burndown query parameters
+#[derive(Debug, Deserialize)]
+pub struct BurndownParams {
+    /// Days to cover, ending today (default 14)
+    pub days: Option<u32>,
+}
+
+/// Alerts fired and resolved by severity, and incidents opened and closed,
+/// per UTC day; days nothing was watching have null counts
+async fn burndown_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<BurndownParams>,
+) -> Result<Json<vc_query::Burndown>, WebError> {
+    let burndown = QueryBuilder::new(&state.store)
+        .burndown(params.days.unwrap_or(14))
+        .map_err(|e| match e {
+            vc_query::QueryError::InvalidQuery(msg) => WebError::BadRequest(msg),
+            other => other.into(),
+        })?;
+    Ok(Json(burndown))
+}
+
+// =============================================================================
+// Annotations Endpoints
+// =============================================================================
+
+/// Query parameters for the annotation list (same as `vc annotations list`)
+#[derive(Debug, Deserialize)]
+pub struct AnnotationParams {
+    /// Comma-separated kinds (default all)
+    pub kind: Option<String>,
+    /// Window start: an age such as `7d` or a timestamp (default 7d)
+    pub since: Option<String>,
+    /// Window end: an age or a timestamp (default now)
+    pub until: Option<String>,
+    /// Only those that apply to this machine
+    pub machine: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Annotations in a window, newest first
+async fn annotations_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AnnotationParams>,
+) -> Result<Json<Vec<vc_store::Annotation>>, WebError> {
+    let now = chrono::Utc::now();
+    let window = |raw: &str| {
+        vc_query::timefmt::parse_since(raw, now)
+            .ok_or_else(|| WebError::BadRequest(format!("invalid window bound: {raw}")))
+    };
+    let filter = vc_store::AnnotationFilter {
+        kinds: params
+            .kind
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(str::to_string)
+            .collect(),
+        since: Some(window(params.since.as_deref().unwrap_or("7d"))?),
+        until: params.until.as_deref().map(window).transpose()?,
+        machine: params.machine,
+        limit: params
+            .limit
+            .unwrap_or(vc_store::annotations::DEFAULT_ANNOTATION_LIMIT),
+    };
+    Ok(Json(state.store.list_annotations(&filter)?))
+}
+
+fn default_annotation_source() -> String {
+    "api".to_string()
+}
+
+/// Body of `POST /api/annotations`
+#[derive(Debug, Deserialize)]
+struct AnnotationRequest {
+    /// e.g. `deploy`
+    kind: String,
+    /// `fleet` (default), `machine:<id>` or `tag:<tag>`
+    #[serde(default)]
+    scope: Option<vc_store::AnnotationScope>,
+    message: String,
+    /// When it happened (default now)
+    #[serde(default)]
+    ts: Option<String>,
+    /// What reported it, e.g. `ci` (default `api`)
+    #[serde(default = "default_annotation_source")]
+    source: String,
+}
+
+/// Record an annotation, e.g. from a CI or deploy pipeline
+async fn annotation_create_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Json(request): Json<AnnotationRequest>,
+) -> Result<Response, WebError> {
+    let Some(actor) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response("annotation_requires_operator"));
+    };
+    let ts = request
+        .ts
+        .as_deref()
+        .map(|raw| {
+            vc_query::timefmt::parse_timestamp(raw)
+                .ok_or_else(|| WebError::BadRequest(format!("invalid ts: {raw}")))
+        })
+        .transpose()?;
+    let annotation = state
+        .store
+        .record_annotation(
+            &vc_store::NewAnnotation {
+                kind: request.kind,
+                scope: request.scope.unwrap_or(vc_store::AnnotationScope::Fleet),
+                ts,
+                message: request.message,
+                source: request.source,
+            },
+            &actor,
+        )
+        .map_err(|e| match e {
+            vc_store::StoreError::QueryError(msg) => WebError::BadRequest(msg),
+            other => other.into(),
+        })?;
+    info!(id = annotation.id, kind = %annotation.kind, actor = %actor, "annotation recorded");
+    Ok((StatusCode::CREATED, Json(annotation)).into_response())
+}
+
 // =============================================================================
 // Alerts Endpoints
 // =============================================================================
@@ -473,15 +1094,81 @@ async fn machine_collectors_handler(
 async fn alerts_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<PaginationParams>,
-) -> Result<Json<serde_json::Value>, WebError> {
+) -> Result<Json<AlertsPage>, WebError> {
     let limit = params.bounded_limit();
     let builder = QueryBuilder::new(&state.store);
     let alerts = builder.recent_alerts(limit)?;
 
+    Ok(Json(AlertsPage { alerts, limit }))
+}
+
+/// Alerts a dry-run bulk acknowledge returns as a sample
+const BULK_ACK_SAMPLE: usize = 10;
+
+/// Body of `POST /api/alerts/bulk-ack`: the same filter as
+/// `vc alert ack --all` (`machine`, `before`, `type`, `severity`, `ids`)
+#[derive(Debug, Deserialize)]
+struct BulkAckRequest {
+    #[serde(flatten)]
+    filter: vc_store::AlertFilter,
+    /// Required when the filter is empty, to acknowledge every open alert
+    #[serde(default)]
+    all: bool,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// Acknowledge every open alert matching a filter in one update
+async fn alerts_bulk_ack_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Json(request): Json<BulkAckRequest>,
+) -> Result<Response, WebError> {
+    let Some(actor) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response("alert_ack_requires_operator"));
+    };
+    let mut filter = request.filter;
+    if filter.is_empty() && !request.all {
+        return Err(WebError::BadRequest(
+            "empty filter; set \"all\": true to acknowledge every open alert".to_string(),
+        ));
+    }
+    if let Some(before) = &filter.before {
+        let ts = vc_query::timefmt::parse_timestamp(before)
+            .ok_or_else(|| WebError::BadRequest(format!("invalid before timestamp: {before}")))?;
+        filter.before = Some(ts.to_rfc3339_opts(chrono::SecondsFormat::Micros, true));
+    }
+
+    let action = vc_store::BulkAlertAction::Acknowledge;
+    let preview = state.store.match_alerts(action, &filter, BULK_ACK_SAMPLE)?;
+    if request.dry_run {
+        return Ok(Json(serde_json::json!({
+            "dry_run": true,
+            "filter": filter,
+            "matched": preview.matched,
+            "sample": preview.sample,
+        }))
+        .into_response());
+    }
+
+    let acknowledged = state.store.bulk_update_alerts(action, &filter, &actor)?;
+    state
+        .store
+        .insert_audit_event(&vc_store::bulk_alert_audit_event(
+            action,
+            &actor,
+            &filter,
+            acknowledged,
+        ))?;
+    info!(acknowledged, actor = %actor, "alerts bulk-acknowledged");
     Ok(Json(serde_json::json!({
-        "alerts": alerts,
-        "limit": limit
-    })))
+        "dry_run": false,
+        "filter": filter,
+        "matched": preview.matched,
+        "acknowledged": acknowledged,
+    }))
+    .into_response())
 }
 
 /// Alert rules endpoint
@@ -501,6 +1188,47 @@ async fn alert_rules_handler(
     })))
 }
 
+// =============================================================================
+// Incidents Endpoints
+// =============================================================================
+
+/// Query parameters for the incident list (same as `vc incident list`)
+#[derive(Debug, Deserialize)]
+pub struct IncidentParams {
+    /// open, mitigated or closed
+    pub status: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+/// Incidents list endpoint, newest first
+async fn incidents_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<IncidentParams>,
+) -> Result<Json<IncidentsPage>, WebError> {
+    let limit = params.limit.clamp(1, MAX_PAGINATION_LIMIT);
+    let incidents = state
+        .store
+        .list_incidents(params.status.as_deref(), limit)?;
+    Ok(Json(IncidentsPage { incidents, limit }))
+}
+
+/// One incident with its notes and timeline (same as `vc incident show`)
+async fn incident_by_id_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<IncidentDetail>, WebError> {
+    let incident = state
+        .store
+        .get_incident(&id)?
+        .ok_or_else(|| WebError::NotFound(format!("Incident not found: {id}")))?;
+    Ok(Json(IncidentDetail {
+        incident,
+        notes: state.store.get_incident_notes(&id)?,
+        timeline: state.store.get_incident_timeline(&id)?,
+    }))
+}
+
 // =============================================================================
 // Accounts Endpoints
 // =============================================================================
@@ -531,7 +1259,9 @@ async fn sessions_handler(
     let sql = format!(
         "SELECT * FROM agent_sessions ORDER BY collected_at DESC LIMIT {limit} OFFSET {offset}"
     );
-    let sessions = state.store.query_json(&sql)?;
+    let mut sessions = state.store.query_json(&sql)?;
+    // Offloaded transcripts come back from the artifact store
+    state.store.fill_offloaded_transcripts(&mut sessions)?;
 
     Ok(Json(serde_json::json!({
         "sessions": sessions,
@@ -575,68 +1305,698 @@ async fn guardian_runs_handler(
     })))
 }
 
-/// Guardian pending approvals endpoint
+/// Guardian pending approvals endpoint.
+///
+/// `pending` lists runs awaiting approval and `drafts` lists playbook drafts
+/// in `pending_review`, each with an `age_secs` field. `total` matches the
+/// overview's `pending_approvals`.
 async fn guardian_pending_handler(
     State(state): State<Arc<AppState>>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<serde_json::Value>, WebError> {
     let limit = params.bounded_limit();
     let offset = params.bounded_offset();
-    let sql = format!(
-        "SELECT * FROM guardian_runs WHERE status = 'pending_approval' ORDER BY started_at DESC LIMIT {limit} OFFSET {offset}"
-    );
-    let pending = state.store.query_json(&sql)?;
+    let pending = QueryBuilder::new(&state.store).pending_approvals(limit, offset)?;
 
     Ok(Json(serde_json::json!({
-        "pending": pending,
+        "total": pending.total(),
+        "pending": pending.runs,
+        "drafts": pending.drafts,
+        "total_runs": pending.total_runs,
+        "total_drafts": pending.total_drafts,
         "limit": limit,
         "offset": offset
     })))
 }
 
-// =============================================================================
-// Prometheus Metrics Endpoint
-// =============================================================================
+/// Request body for `POST /api/guardian/drafts/{id}/approve`
+#[derive(Debug, Deserialize)]
+pub struct ApproveDraftRequest {
+    /// Revision the approver reviewed; must still be the draft's current one
+    pub revision: i64,
+}
 
-/// Serve Prometheus-format metrics
-#[allow(clippy::too_many_lines)]
-async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let mut lines = Vec::new();
+/// Request body for `POST /api/guardian/drafts/{id}/reject`
+#[derive(Debug, Default, Deserialize)]
+pub struct RejectDraftRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
 
-    // -- Collector freshness --
-    let collectors = state
-        .store
-        .query_json(
-            "SELECT machine_id, collector, \
-             EXTRACT(EPOCH FROM current_timestamp) - EXTRACT(EPOCH FROM CAST(collected_at AS TIMESTAMP)) AS freshness_secs \
-             FROM collector_health \
-             WHERE collected_at = (SELECT MAX(ch2.collected_at) FROM collector_health ch2 \
-                WHERE ch2.machine_id = collector_health.machine_id \
-                AND ch2.collector = collector_health.collector)",
-        )
-        .unwrap_or_default();
+/// Actor recorded as the approver (or actor of any other write), or `None`
+/// if the caller is below operator.
+///
+/// The actor is the one [`auth::auth_middleware`] resolved: the `X-VC-Actor`
+/// header, else the token name, else `local` for local-bypass callers.
+fn operator_actor(
+    caller: Option<&Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+) -> Option<ActorContext> {
+    let Extension(result) = caller?;
+    if !auth::authorize(result, auth::Role::Operator) {
+        return None;
+    }
+    Some(actor.map_or_else(
+        || ActorContext::web(None, result.token_name.as_deref()),
+        |Extension(actor)| actor,
+    ))
+}
 
-    if !collectors.is_empty() {
-        lines.push(
-            "# HELP vc_collector_freshness_seconds Seconds since last collector check".to_string(),
+/// Create a 409 Conflict response carrying the row's current state
+fn approval_conflict_response(error: &str, message: &str, current: serde_json::Value) -> Response {
+    let body = ApiErrorBody {
+        message: Some(message.to_string()),
+        current: Some(current),
+        ..ApiErrorBody::new(error, 409)
+    };
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
+
+/// Record a guardian approval decision in the audit log
+fn audit_guardian_decision(
+    store: &VcStore,
+    approver: &ActorContext,
+    action: &str,
+    details: serde_json::Value,
+) -> Result<(), WebError> {
+    store.insert_audit_event(&approver.audit_event(
+        vc_store::AuditEventType::GuardianAction,
+        action,
+        vc_store::AuditResult::Success,
+        details,
+    ))?;
+    Ok(())
+}
+
+/// Approve a guardian run waiting in `pending_approval`
+async fn guardian_run_approve_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Path(run_id): Path<i64>,
+) -> Result<Response, WebError> {
+    let Some(approver) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response(
+            "guardian_approval_requires_operator",
+        ));
+    };
+    let not_found = || WebError::NotFound(format!("Guardian run {run_id} not found"));
+    let current = state
+        .store
+        .get_guardian_run(run_id)?
+        .ok_or_else(not_found)?;
+
+    if state.store.approve_guardian_run(run_id, &approver)? == 0 {
+        let current = state.store.get_guardian_run(run_id)?.unwrap_or(current);
+        let message = format!(
+            "run {run_id} is {} and can no longer be approved",
+            current["status"].as_str().unwrap_or("unknown")
         );
-        lines.push("# TYPE vc_collector_freshness_seconds gauge".to_string());
-        for c in &collectors {
-            let machine = c["machine_id"].as_str().unwrap_or("unknown");
-            let collector = c["collector"].as_str().unwrap_or("unknown");
-            let secs = c["freshness_secs"].as_f64().unwrap_or(0.0);
-            lines.push(format!(
-                "vc_collector_freshness_seconds{{machine=\"{machine}\",collector=\"{collector}\"}} {secs:.1}"
-            ));
-        }
+        return Ok(approval_conflict_response(
+            "run_not_pending",
+            &message,
+            current,
+        ));
     }
 
-    // -- Collector success total --
-    let success_counts = state
+    audit_guardian_decision(
+        &state.store,
+        &approver,
+        "approve_run",
+        serde_json::json!({ "run_id": run_id }),
+    )?;
+    let run = state
         .store
-        .query_json(
-            "SELECT machine_id, collector, \
-             COUNT(*) FILTER (WHERE success = true) AS success_count \
+        .get_guardian_run(run_id)?
+        .ok_or_else(not_found)?;
+    info!(run_id, approver = %approver, "guardian run approved");
+    Ok(Json(serde_json::json!({ "run": run })).into_response())
+}
+
+/// Approve a playbook draft at the revision the approver reviewed
+async fn guardian_draft_approve_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Path(draft_id): Path<String>,
+    Json(request): Json<ApproveDraftRequest>,
+) -> Result<Response, WebError> {
+    let Some(approver) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response(
+            "guardian_approval_requires_operator",
+        ));
+    };
+    let not_found = || WebError::NotFound(format!("Playbook draft {draft_id} not found"));
+    let current = state
+        .store
+        .get_playbook_draft(&draft_id)?
+        .ok_or_else(not_found)?;
+
+    let revision = request.revision;
+    if state
+        .store
+        .approve_playbook_draft(&draft_id, &approver, revision)?
+        == 0
+    {
+        let current = state
+            .store
+            .get_playbook_draft(&draft_id)?
+            .unwrap_or(current);
+        let message = if current["status"] == "pending_review" {
+            format!(
+                "revision {revision} is not the current revision ({})",
+                current["current_revision"]
+            )
+        } else {
+            format!(
+                "draft is {} and can no longer be approved",
+                current["status"].as_str().unwrap_or("unknown")
+            )
+        };
+        return Ok(approval_conflict_response(
+            "draft_not_pending",
+            &message,
+            current,
+        ));
+    }
+
+    audit_guardian_decision(
+        &state.store,
+        &approver,
+        "approve_draft",
+        serde_json::json!({ "draft_id": draft_id, "revision": revision }),
+    )?;
+    let draft = state
+        .store
+        .get_playbook_draft(&draft_id)?
+        .ok_or_else(not_found)?;
+    info!(draft_id = %draft_id, approver = %approver, "playbook draft approved");
+    Ok(Json(serde_json::json!({ "draft": draft })).into_response())
+}
+
+/// Reject a playbook draft in `pending_review`
+async fn guardian_draft_reject_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Path(draft_id): Path<String>,
+    request: Option<Json<RejectDraftRequest>>,
+) -> Result<Response, WebError> {
+    let Some(approver) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response(
+            "guardian_approval_requires_operator",
+        ));
+    };
+    let reason = request.and_then(|Json(request)| request.reason);
+    let not_found = || WebError::NotFound(format!("Playbook draft {draft_id} not found"));
+    let current = state
+        .store
+        .get_playbook_draft(&draft_id)?
+        .ok_or_else(not_found)?;
+
+    if state
+        .store
+        .reject_playbook_draft(&draft_id, reason.as_deref())?
+        == 0
+    {
+        let current = state
+            .store
+            .get_playbook_draft(&draft_id)?
+            .unwrap_or(current);
+        let message = format!(
+            "draft is {} and can no longer be rejected",
+            current["status"].as_str().unwrap_or("unknown")
+        );
+        return Ok(approval_conflict_response(
+            "draft_not_pending",
+            &message,
+            current,
+        ));
+    }
+
+    audit_guardian_decision(
+        &state.store,
+        &approver,
+        "reject_draft",
+        serde_json::json!({ "draft_id": draft_id, "reason": reason }),
+    )?;
+    let draft = state
+        .store
+        .get_playbook_draft(&draft_id)?
+        .ok_or_else(not_found)?;
+    info!(draft_id = %draft_id, approver = %approver, "playbook draft rejected");
+    Ok(Json(serde_json::json!({ "draft": draft })).into_response())
+}
+
+// =============================================================================
+// Query Template Endpoints
+// =============================================================================
+
+/// List query templates (mirrors `vc query templates`)
+async fn query_templates_handler(State(state): State<Arc<AppState>>) -> Json<TemplateCatalog> {
+    Json(TemplateCatalog {
+        templates: state.query_validator.template_catalog(),
+    })
+}
+
+/// Run a query template.
+///
+/// Tokens below operator may only run templates marked `agent_safe`. The
+/// expanded SQL must pass the read-only check and results are capped at the
+/// guardrail row limit.
+async fn query_template_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    client: Option<Extension<ConnectInfo<SocketAddr>>>,
+    Json(request): Json<TemplateQueryRequest>,
+) -> Result<Response, WebError> {
+    let started = Instant::now();
+    let caller = caller.map(|Extension(result)| result);
+
+    let key = rate_limit_key(
+        caller.as_ref(),
+        actor.as_ref().map(|Extension(actor)| actor),
+        client.map(|Extension(ConnectInfo(addr))| addr.ip()),
+    );
+    if let Err(retry_after) = state.query_limiter.check(&key) {
+        return Ok(rate_limited_response(retry_after));
+    }
+
+    let validator = &state.query_validator;
+    let template = validator.templates().get(&request.name).ok_or_else(|| {
+        WebError::from(ValidationError::UnknownTemplate {
+            name: request.name.clone(),
+        })
+    })?;
+    let privileged = caller
+        .as_ref()
+        .is_some_and(|result| auth::authorize(result, auth::Role::Operator));
+    if !template.agent_safe && !privileged {
+        return Ok(auth::forbidden_response("template_not_agent_safe"));
+    }
+
+    let params: HashMap<String, String> = request
+        .params
+        .into_iter()
+        .map(|(name, value)| {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Null => "NULL".to_string(),
+                other => other.to_string(),
+            };
+            (name, value)
+        })
+        .collect();
+    let sql = validator.expand_template(&request.name, &params)?;
+    validator.validate_readonly(&sql)?;
+
+    let origin = if privileged {
+        vc_query::QueryOrigin::Operator
+    } else {
+        vc_query::QueryOrigin::Agent
+    };
+    let query_started = Instant::now();
+    let mut rows = validator.execute(&state.store, &sql, origin)?;
+    let query_ms = query_started.elapsed().as_secs_f64() * 1000.0;
+
+    let max_rows = validator.config().max_rows;
+    let truncated = rows.len() > max_rows;
+    rows.truncate(max_rows);
+
+    Ok(Json(TemplateQueryResult {
+        template: request.name,
+        row_count: rows.len(),
+        truncated,
+        rows,
+        timing: QueryTiming {
+            query_ms,
+            total_ms: started.elapsed().as_secs_f64() * 1000.0,
+        },
+    })
+    .into_response())
+}
+
+/// Query parameters for `GET /api/query/proposals`
+#[derive(Debug, Default, Deserialize)]
+pub struct ProposalListParams {
+    /// Only proposals with this status (default `pending`; `all` for every one)
+    pub status: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Request body for `POST /api/query/proposals/{id}/deny`
+#[derive(Debug, Default, Deserialize)]
+pub struct DenyProposalRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Raw queries proposed by agents (operator role: they carry SQL and rows
+/// no template vetted)
+async fn query_proposals_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Query(params): Query<ProposalListParams>,
+) -> Result<Response, WebError> {
+    if operator_actor(caller.as_ref(), actor).is_none() {
+        return Ok(auth::forbidden_response("query_approval_requires_operator"));
+    }
+    let status = match params.status.as_deref() {
+        Some("all") => None,
+        Some(status) => Some(status),
+        None => Some("pending"),
+    };
+    let limit = params.limit.unwrap_or(100).min(1_000);
+    let proposals = state.store.list_query_proposals(status, limit)?;
+    Ok(Json(serde_json::json!({
+        "count": proposals.len(),
+        "proposals": proposals,
+    }))
+    .into_response())
+}
+
+/// The 404 or 409 for a proposal that could not be decided
+fn proposal_not_pending(store: &VcStore, id: i64) -> Result<Response, WebError> {
+    let current = store
+        .query_proposal(id)?
+        .ok_or_else(|| WebError::NotFound(format!("Query proposal {id} not found")))?;
+    let message = format!(
+        "proposal {id} is {} and can no longer be decided",
+        current.status
+    );
+    Ok(approval_conflict_response(
+        "proposal_not_pending",
+        &message,
+        serde_json::to_value(current).unwrap_or_default(),
+    ))
+}
+
+/// Approve a proposed query and run it under the agent deadline
+async fn query_proposal_approve_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Path(id): Path<i64>,
+) -> Result<Response, WebError> {
+    let Some(approver) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response("query_approval_requires_operator"));
+    };
+    match state
+        .query_validator
+        .approve_proposal(&state.store, id, &approver)?
+    {
+        Some(proposal) => {
+            info!(id, approver = %approver, status = %proposal.status, "query proposal approved");
+            Ok(Json(serde_json::json!({ "proposal": proposal })).into_response())
+        }
+        None => proposal_not_pending(&state.store, id),
+    }
+}
+
+/// Deny a proposed query
+async fn query_proposal_deny_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    actor: Option<Extension<ActorContext>>,
+    Path(id): Path<i64>,
+    request: Option<Json<DenyProposalRequest>>,
+) -> Result<Response, WebError> {
+    let Some(approver) = operator_actor(caller.as_ref(), actor) else {
+        return Ok(auth::forbidden_response("query_approval_requires_operator"));
+    };
+    let reason = request.and_then(|Json(request)| request.reason);
+    match state
+        .store
+        .decide_query_proposal(id, false, reason.as_deref(), &approver)?
+    {
+        Some(proposal) => Ok(Json(serde_json::json!({ "proposal": proposal })).into_response()),
+        None => proposal_not_pending(&state.store, id),
+    }
+}
+
+// =============================================================================
+// Robot Endpoints
+// =============================================================================
+
+/// Media type for TOON bodies
+const TOON_CONTENT_TYPE: &str = "text/x-toon";
+
+#[derive(Debug, Default, Deserialize)]
+struct RobotParams {
+    /// `json` (default) or `toon`; overrides the `Accept` header
+    format: Option<String>,
+    /// Keep only the best-ranked triage actions
+    max_items: Option<usize>,
+}
+
+/// `vc robot <name>` over HTTP (read role). JSON bodies are the full
+/// envelope. TOON carries only the payload, as on the CLI, so the schema
+/// version and staleness map ride along in `X-VC-Schema` and
+/// `X-VC-Staleness`.
+async fn robot_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(params): Query<RobotParams>,
+    headers: HeaderMap,
+) -> Result<Response, WebError> {
+    let view =
+        RobotView::parse(&name).ok_or_else(|| WebError::NotFound(format!("Robot view {name}")))?;
+    let toon = match params.format.as_deref() {
+        Some("toon") => true,
+        Some("json") => false,
+        Some(other) => {
+            return Err(WebError::BadRequest(format!(
+                "format must be json or toon, got {other}"
+            )));
+        }
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains(TOON_CONTENT_TYPE)),
+    };
+
+    let store = &state.store;
+    Ok(match view {
+        RobotView::Health => robot_response(view, vc_robot::robot_health(store)?, toon),
+        RobotView::Status => robot_response(view, vc_robot::robot_status(store)?, toon),
+        RobotView::Triage => robot_response(
+            view,
+            vc_robot::robot_triage(store, &state.min_versions, params.max_items)?,
+            toon,
+        ),
+        RobotView::Machines => robot_response(view, vc_robot::robot_machines(store)?, toon),
+        RobotView::Accounts => robot_response(view, vc_robot::robot_accounts(store)?, toon),
+        RobotView::Repos => robot_response(view, vc_robot::robot_repos(store)?, toon),
+        RobotView::Oracle => robot_response(view, vc_robot::robot_oracle(store)?, toon),
+    })
+}
+
+fn robot_response<T: Serialize + ToToon>(
+    view: RobotView,
+    envelope: RobotEnvelope<T>,
+    toon: bool,
+) -> Response {
+    let cache = [
+        (
+            header::CACHE_CONTROL,
+            format!("private, max-age={}", view.cache_ttl_secs()),
+        ),
+        (header::VARY, header::ACCEPT.to_string()),
+    ];
+    if !toon {
+        return (cache, Json(envelope)).into_response();
+    }
+
+    let staleness = serde_json::to_string(&envelope.staleness).unwrap_or_default();
+    (
+        cache,
+        [
+            (header::CONTENT_TYPE, TOON_CONTENT_TYPE.to_string()),
+            (
+                header::HeaderName::from_static("x-vc-schema"),
+                envelope.schema_version.clone(),
+            ),
+            (header::HeaderName::from_static("x-vc-staleness"), staleness),
+        ],
+        envelope.data.to_toon(),
+    )
+        .into_response()
+}
+
+// =============================================================================
+// Export Bundle Endpoints
+// =============================================================================
+
+/// Export bundles recorded by `vc db export`, newest first, each with the
+/// files now in its directory
+async fn exports_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<PaginationParams>,
+) -> Result<Json<serde_json::Value>, WebError> {
+    let limit = params.bounded_limit();
+    let bundles: Vec<exports::ExportBundleListing> = state
+        .store
+        .list_export_bundles(limit)?
+        .into_iter()
+        .map(exports::ExportBundleListing::new)
+        .collect();
+
+    Ok(Json(serde_json::json!({
+        "bundles": bundles,
+        "limit": limit
+    })))
+}
+
+fn registered_bundle(state: &AppState, id: &str) -> Result<vc_store::ExportBundle, WebError> {
+    state
+        .store
+        .export_bundle(id)?
+        .ok_or_else(|| WebError::NotFound(format!("Export {id}")))
+}
+
+/// One file of a bundle, resumable with `Range`. Only plain names directly
+/// in the bundle's recorded directory are served.
+async fn export_file_handler(
+    State(state): State<Arc<AppState>>,
+    Path((id, name)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<Response, WebError> {
+    let bundle = registered_bundle(&state, &id)?;
+    let file = exports::export_file(FsPath::new(&bundle.output_dir), &name)
+        .ok_or_else(|| WebError::NotFound(format!("File {name} in export {id}")))?;
+    Ok(exports::file_response(&file, &headers))
+}
+
+/// The whole bundle as one tar stream
+async fn export_tar_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Response, WebError> {
+    let bundle = registered_bundle(&state, &id)?;
+    let files = exports::bundle_files(FsPath::new(&bundle.output_dir))
+        .ok_or_else(|| WebError::NotFound(format!("Directory of export {id}")))?;
+    Ok(exports::tar_response(&bundle.bundle_id, files))
+}
+
+// =============================================================================
+// Replication Endpoint
+// =============================================================================
+
+/// Largest accepted replication batch; rows carry raw collector JSON
+const REPLICATION_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Apply a batch shipped by the primary (operator role). A primary, or a
+/// promoted standby, answers 409 so two primaries can never feed each other.
+async fn replication_batch_handler(
+    State(state): State<Arc<AppState>>,
+    caller: Option<Extension<auth::AuthResult>>,
+    Json(batch): Json<ReplicationBatch>,
+) -> Result<Response, WebError> {
+    let authorized = caller
+        .as_ref()
+        .is_some_and(|Extension(result)| auth::authorize(result, auth::Role::Operator));
+    if !authorized {
+        return Ok(auth::forbidden_response("replication_requires_operator"));
+    }
+
+    if state.store.replication_role(state.replication_mode)? != ReplicationMode::Standby {
+        let body = ApiErrorBody {
+            message: Some(
+                "this cockpit is a primary and does not accept replicated writes".to_string(),
+            ),
+            ..ApiErrorBody::new("not_standby", 409)
+        };
+        return Ok((StatusCode::CONFLICT, Json(body)).into_response());
+    }
+
+    let ack = state.store.apply_replication_batch(&batch)?;
+    info!(
+        source = %batch.source,
+        tables = batch.tables.len(),
+        rows = batch.row_count(),
+        "applied replication batch"
+    );
+    Ok(Json(ack).into_response())
+}
+
+/// Rate-limit bucket of a request: the token name, or the client IP for
+/// callers without one. An actor named with `X-VC-Actor` gets its own bucket
+/// under that, so agents sharing a token are counted apart.
+fn rate_limit_key(
+    caller: Option<&auth::AuthResult>,
+    actor: Option<&ActorContext>,
+    client: Option<std::net::IpAddr>,
+) -> String {
+    let base = caller
+        .and_then(|result| result.token_name.clone())
+        .or_else(|| client.map(|ip| ip.to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+    match actor {
+        Some(actor) if actor.source == ActorSource::Explicit => format!("{base}/{}", actor.name),
+        _ => base,
+    }
+}
+
+/// Create a 429 Too Many Requests response
+fn rate_limited_response(retry_after: Duration) -> Response {
+    let secs = retry_after.as_secs().max(1);
+    let body = ApiErrorBody {
+        retry_after_secs: Some(secs),
+        ..ApiErrorBody::new("rate_limited", 429)
+    };
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, secs.to_string())],
+        Json(body),
+    )
+        .into_response()
+}
+
+// =============================================================================
+// Prometheus Metrics Endpoint
+// =============================================================================
+
+/// Serve Prometheus-format metrics
+#[allow(clippy::too_many_lines)]
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let mut lines = Vec::new();
+
+    // -- Collector freshness --
+    let collectors = state
+        .store
+        .query_json(
+            "SELECT machine_id, collector, \
+             EXTRACT(EPOCH FROM current_timestamp) - EXTRACT(EPOCH FROM CAST(collected_at AS TIMESTAMP)) AS freshness_secs \
+             FROM collector_health \
+             WHERE collected_at = (SELECT MAX(ch2.collected_at) FROM collector_health ch2 \
+                WHERE ch2.machine_id = collector_health.machine_id \
+                AND ch2.collector = collector_health.collector)",
+        )
+        .unwrap_or_default();
+
+    if !collectors.is_empty() {
+        lines.push(
+            "# HELP vc_collector_freshness_seconds Seconds since last collector check".to_string(),
+        );
+        lines.push("# TYPE vc_collector_freshness_seconds gauge".to_string());
+        for c in &collectors {
+            let machine = c["machine_id"].as_str().unwrap_or("unknown");
+            let collector = c["collector"].as_str().unwrap_or("unknown");
+            let secs = c["freshness_secs"].as_f64().unwrap_or(0.0);
+            lines.push(format!(
+                "vc_collector_freshness_seconds{{machine=\"{machine}\",collector=\"{collector}\"}} {secs:.1}"
+            ));
+        }
+    }
+
+    // -- Collector success total --
+    let success_counts = state
+        .store
+        .query_json(
+            "SELECT machine_id, collector, \
+             COUNT(*) FILTER (WHERE success = true) AS success_count \
              FROM collector_health GROUP BY machine_id, collector",
         )
         .unwrap_or_default();
@@ -710,12 +2070,18 @@ async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoRespons
     lines.push("# TYPE vc_machines_total gauge".to_string());
     lines.push(format!("vc_machines_total {machine_count}"));
 
+    // -- Oversized writes --
+    lines.extend(oversized_write_metrics(&state.store));
+
     // -- Uptime --
     let uptime_secs = state.start_time.elapsed().as_secs_f64();
     lines.push("# HELP vc_uptime_seconds Server uptime in seconds".to_string());
     lines.push("# TYPE vc_uptime_seconds counter".to_string());
     lines.push(format!("vc_uptime_seconds {uptime_secs:.1}"));
 
+    // -- Event stream --
+    lines.extend(state.events.metrics().lines());
+
     // Return as text/plain (Prometheus text format)
     let body = lines.join("\n") + "\n";
     (
@@ -764,9 +2130,31 @@ pub fn generate_metrics_text(store: &VcStore) -> String {
     lines.push("# TYPE vc_machines_total gauge".to_string());
     lines.push(format!("vc_machines_total {machine_count}"));
 
+    lines.extend(oversized_write_metrics(store));
+
     lines.join("\n") + "\n"
 }
 
+/// `vc_oversized_writes_total` lines, one per table, collector and action.
+/// Writes that did not come from a collection carry `collector="-"`.
+fn oversized_write_metrics(store: &VcStore) -> Vec<String> {
+    let counts = store
+        .oversized_write_counts(None, None, None)
+        .unwrap_or_default();
+    let mut lines = vec![
+        "# HELP vc_oversized_writes_total Values over the store write limits".to_string(),
+        "# TYPE vc_oversized_writes_total counter".to_string(),
+    ];
+    for c in &counts {
+        let collector = c.collector.as_deref().unwrap_or("-");
+        lines.push(format!(
+            "vc_oversized_writes_total{{table=\"{}\",collector=\"{collector}\",action=\"{}\"}} {}",
+            c.table_name, c.action, c.occurrences
+        ));
+    }
+    lines
+}
+
 // =============================================================================
 // WebSocket Endpoint
 // =============================================================================
@@ -1004,6 +2392,125 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_health_components_endpoint() {
+        run_tokio(async {
+            let state = test_state();
+            let get = |app: Router| async move {
+                let request = Request::builder()
+                    .uri("/api/health/components")
+                    .body(Body::empty())
+                    .unwrap();
+                let response = app.oneshot(request).await.unwrap();
+                let status = response.status();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                (status, json)
+            };
+
+            // A fresh store holds no collector runs and no daemon heartbeat
+            let (status, json) = get(create_router(state.clone())).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(json["status"], "degraded");
+            assert_eq!(json["components"][0]["component"], "store");
+            assert!(json["components"][0]["detail"].is_i64());
+
+            // Failing notifications are not seen until the cached report expires
+            state
+                .store
+                .insert_delivery_log("a1", "slack", "failed", Some("err"), None)
+                .unwrap();
+            let (status, _) = get(create_router(state.clone())).await;
+            assert_eq!(status, StatusCode::OK);
+
+            state.component_health.lock().unwrap().take();
+            let (status, json) = get(create_router(state)).await;
+            assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(json["status"], "failed");
+        });
+    }
+
+    #[test]
+    fn test_fleet_run_guardrails() {
+        run_tokio(async {
+            let token = |name: &str, role| auth::ApiToken {
+                name: name.to_string(),
+                token: format!("tok-{name}"),
+                role,
+                allowed_ips: vec![],
+                enabled: true,
+            };
+            let auth_config = auth::AuthConfig {
+                enabled: true,
+                tokens: vec![
+                    token("reader", auth::Role::Read),
+                    token("operator", auth::Role::Operator),
+                    token("admin", auth::Role::Admin),
+                ],
+                local_bypass: false,
+            };
+            let mut state =
+                AppState::new_with_auth(VcStore::open_memory().unwrap(), Arc::new(auth_config));
+            state.fleet_run.allow = vec!["uname( -[a-z]+)*".to_string()];
+            let state = Arc::new(state);
+            let run = |token: &str, body: serde_json::Value| {
+                let request = Request::builder()
+                    .method("POST")
+                    .uri("/api/fleet/run")
+                    .header("authorization", format!("Bearer {token}"))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap();
+                create_router(state.clone()).oneshot(request)
+            };
+
+            let response = run("tok-reader", serde_json::json!({"command": "uname -r"}))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                json_body(response).await["reason"],
+                "fleet_run_requires_operator"
+            );
+
+            let response = run("tok-operator", serde_json::json!({"command": "rm -rf /"}))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                json_body(response).await["reason"],
+                "fleet_run_command_not_allowed"
+            );
+
+            // The allow pattern must match the whole command
+            for chained in ["uname -r; rm -rf ~", "uname -r $(rm -rf ~)"] {
+                let response = run("tok-operator", serde_json::json!({"command": chained}))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::FORBIDDEN);
+                assert_eq!(
+                    json_body(response).await["reason"],
+                    "fleet_run_command_not_allowed"
+                );
+            }
+
+            // Allowed, but no machine is registered
+            let response = run("tok-operator", serde_json::json!({"command": "uname -r"}))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            // Admins are not held to the patterns
+            let response = run(
+                "tok-admin",
+                serde_json::json!({"command": "rm -rf /", "machines": "nope"}),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        });
+    }
+
     #[test]
     fn test_overview_endpoint() {
         run_tokio(async {
@@ -1082,6 +2589,42 @@ mod tests {
             assert!(json.get("fleet_health").is_some());
             assert!(json.get("active_alerts").is_some());
             assert!(json.get("pending_approvals").is_some());
+            assert!(json.get("by_tag").is_none());
+        });
+    }
+
+    #[test]
+    fn test_fleet_endpoint_group_by_tag() {
+        run_tokio(async {
+            let state = test_state();
+            state
+                .store
+                .execute_batch(
+                    r#"INSERT INTO machines (machine_id, hostname, status, tags) VALUES
+                        ('b1', 'b1', 'online', '["builder"]'),
+                        ('l1', 'l1', 'offline', NULL);"#,
+                )
+                .unwrap();
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/api/fleet?group_by=tag")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: FleetSummary = serde_json::from_slice(&body).unwrap();
+            let tags: Vec<&str> = json.by_tag.iter().map(|g| g.tag.as_str()).collect();
+            assert_eq!(tags, ["builder", "untagged"]);
+            assert_eq!(json.by_tag[1].offline_machines, 1);
+
+            let request = Request::builder()
+                .uri("/api/fleet?group_by=machine")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
         });
     }
 
@@ -1325,18 +2868,136 @@ mod tests {
     }
 
     #[test]
-    fn test_machine_collectors_endpoint() {
+    fn test_timeline_endpoint() {
         run_tokio(async {
             let state = test_state();
             state
                 .store
-                .insert_json(
-                    "collector_health",
-                    &serde_json::json!({
-                        "machine_id": "machine-1",
-                        "collector": "sysmoni",
-                        "collected_at": "2026-01-01T00:00:00",
-                        "success": true
+                .execute_batch(&format!(
+                    "INSERT INTO alert_history (id, fired_at, severity, title, machine_id) \
+                     VALUES (1, '{}', 'critical', 'Disk full', 'orko')",
+                    chrono::Utc::now().to_rfc3339()
+                ))
+                .unwrap();
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/api/timeline?since=1h&kinds=alerts,drift")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["events"][0]["kind"], "alerts");
+            assert_eq!(json["events"][0]["summary"], "Disk full");
+            assert!(json["next"].is_null());
+
+            let request = Request::builder()
+                .uri("/api/timeline?kinds=deploys")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        });
+    }
+
+    #[test]
+    fn test_burndown_endpoint() {
+        run_tokio(async {
+            let state = test_state();
+            state
+                .store
+                .execute_batch(&format!(
+                    "INSERT INTO alert_history (id, fired_at, severity, title, machine_id) \
+                     VALUES (1, '{}', 'high', 'Disk full', 'orko')",
+                    chrono::Utc::now().to_rfc3339()
+                ))
+                .unwrap();
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/api/burndown?days=3")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["days"].as_array().unwrap().len(), 3);
+            // Nothing was watching the first two days
+            assert!(json["days"][0]["alerts_fired"].is_null());
+            assert_eq!(json["days"][2]["alerts_fired"]["critical"], 1);
+            assert_eq!(json["totals"]["days_unobserved"], 2);
+
+            let request = Request::builder()
+                .uri("/api/burndown?days=0")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        });
+    }
+
+    #[test]
+    fn test_machine_diff_endpoint() {
+        run_tokio(async {
+            let state = test_state();
+            for (machine, arch) in [("orko", "x86_64"), ("bender", "aarch64")] {
+                state
+                    .store
+                    .insert_json(
+                        "machines",
+                        &serde_json::json!({
+                            "machine_id": machine,
+                            "hostname": machine,
+                            "arch": arch
+                        }),
+                    )
+                    .unwrap();
+            }
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/api/machines/orko/diff?against=bender")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["changes"][0]["field"], "arch");
+            assert_eq!(json["changes"][0]["after"], "aarch64");
+
+            let request = Request::builder()
+                .uri("/api/machines/orko/diff")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let request = Request::builder()
+                .uri("/api/machines/ghost/diff?against=orko")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        });
+    }
+
+    #[test]
+    fn test_machine_collectors_endpoint() {
+        run_tokio(async {
+            let state = test_state();
+            state
+                .store
+                .insert_json(
+                    "collector_health",
+                    &serde_json::json!({
+                        "machine_id": "machine-1",
+                        "collector": "sysmoni",
+                        "collected_at": "2026-01-01T00:00:00",
+                        "success": true
                     }),
                 )
                 .unwrap();
@@ -2265,87 +3926,1411 @@ mod tests {
     }
 
     // =============================================================================
-    // Prometheus metrics tests
+    // Query template tests
     // =============================================================================
 
+    /// Auth-enabled state with a read and an operator token and one
+    /// template that is not agent-safe
+    fn query_state(rate_limit: u32) -> Arc<AppState> {
+        let token = |name: &str, role| auth::ApiToken {
+            name: name.to_string(),
+            token: format!("tok-{name}"),
+            role,
+            allowed_ips: vec![],
+            enabled: true,
+        };
+        let auth_config = auth::AuthConfig {
+            enabled: true,
+            tokens: vec![
+                token("reader", auth::Role::Read),
+                token("operator", auth::Role::Operator),
+            ],
+            local_bypass: false,
+        };
+        let mut state =
+            AppState::new_with_auth(VcStore::open_memory().unwrap(), Arc::new(auth_config));
+        state
+            .query_validator
+            .register_template(vc_query::QueryTemplate {
+                name: "audit_dump".to_string(),
+                description: "Raw audit events".to_string(),
+                sql: "SELECT * FROM audit_events".to_string(),
+                params: vec![],
+                agent_safe: false,
+            });
+        state.query_limiter = rate_limit::RateLimiter::new(rate_limit);
+        Arc::new(state)
+    }
+
+    fn template_request(token: &str, body: &serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/api/query/template")
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
     #[test]
-    fn test_metrics_endpoint() {
+    fn test_query_template_read_token_refused_unsafe_template() {
         run_tokio(async {
-            let state = Arc::new(AppState::new_memory().unwrap());
+            let app = create_router(query_state(0));
+            let body = serde_json::json!({"name": "audit_dump"});
+
+            let response = app
+                .clone()
+                .oneshot(template_request("tok-reader", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            assert_eq!(
+                json_body(response).await["reason"],
+                "template_not_agent_safe"
+            );
+
+            let response = app
+                .oneshot(template_request("tok-operator", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["template"], "audit_dump");
+            assert!(json["rows"].is_array());
+            assert!(json["timing"]["query_ms"].is_number());
+        });
+    }
+
+    #[test]
+    fn test_query_template_read_token_runs_agent_safe_template() {
+        run_tokio(async {
+            let state = query_state(0);
+            state
+                .store
+                .execute_batch(
+                    "INSERT INTO machines (machine_id, hostname) VALUES ('orko', 'orko'), \
+                     ('ghost', 'ghost')",
+                )
+                .unwrap();
             let app = create_router(state);
+            let body = serde_json::json!({
+                "name": "machine_status",
+                "params": {"machine_id": "orko", "limit": 10}
+            });
 
-            let request = Request::builder()
-                .uri("/metrics")
-                .body(Body::empty())
+            let response = app
+                .oneshot(template_request("tok-reader", &body))
+                .await
                 .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["row_count"], 1);
+            assert_eq!(json["rows"][0]["machine_id"], "orko");
+            assert_eq!(json["truncated"], false);
+        });
+    }
 
-            let response = app.oneshot(request).await.unwrap();
+    #[test]
+    fn test_query_template_errors_and_rate_limit() {
+        run_tokio(async {
+            let app = create_router(query_state(2));
+
+            let unknown = serde_json::json!({"name": "nope"});
+            let response = app
+                .clone()
+                .oneshot(template_request("tok-reader", &unknown))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let bad = serde_json::json!({"name": "machine_status", "params": {"limit": "ten"}});
+            let response = app
+                .clone()
+                .oneshot(template_request("tok-reader", &bad))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let response = app
+                .clone()
+                .oneshot(template_request("tok-reader", &unknown))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert!(response.headers().contains_key("retry-after"));
+
+            // The operator token has its own budget.
+            let response = app
+                .oneshot(template_request("tok-operator", &unknown))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        });
+    }
+
+    fn replication_request(token: &str, body: &serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/api/replication/batch")
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replication_batch_only_on_standby() {
+        run_tokio(async {
+            let body = serde_json::json!({
+                "source": "hub",
+                "tables": [{
+                    "table": "sys_samples",
+                    "ts_column": "collected_at",
+                    "after": null,
+                    "through": "2026-10-16 10:00:00",
+                    "rows": [{"machine_id": "m1", "collected_at": "2026-10-16 10:00:00", "cpu_total": 12.5}],
+                    "source_head": "2026-10-16 10:00:00",
+                    "rows_pending": 0
+                }]
+            });
+
+            let primary = create_router(query_state(0));
+            let response = primary
+                .oneshot(replication_request("tok-operator", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+
+            let mut state = Arc::try_unwrap(query_state(0)).ok().unwrap();
+            state.replication_mode = ReplicationMode::Standby;
+            let state = Arc::new(state);
+            let standby = create_router(state.clone());
+
+            let response = standby
+                .clone()
+                .oneshot(replication_request("tok-reader", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let response = standby
+                .oneshot(replication_request("tok-operator", &body))
+                .await
+                .unwrap();
             assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["tables"][0]["rows_applied"], 1);
+            assert_eq!(json["tables"][0]["watermark"], "2026-10-16 10:00:00");
+            assert_eq!(
+                state
+                    .store
+                    .query_scalar::<i64>("SELECT COUNT(*) FROM sys_samples")
+                    .unwrap(),
+                1
+            );
+        });
+    }
 
-            let body = response.into_body().collect().await.unwrap().to_bytes();
-            let text = String::from_utf8(body.to_vec()).unwrap();
+    #[test]
+    fn test_replication_batch_requires_signature_when_enabled() {
+        run_tokio(async {
+            let body = serde_json::json!({"source": "hub", "tables": []});
+            let bytes = body.to_string();
+
+            let mut state = Arc::try_unwrap(query_state(0)).ok().unwrap();
+            state.replication_mode = ReplicationMode::Standby;
+            state.signature_verifier = Some(Arc::new(signature::SignatureVerifier::new(
+                b"s3cret".to_vec(),
+                300,
+                16,
+            )));
+            let app = create_router(Arc::new(state));
+
+            let signed = |secret: &[u8], ts: i64| {
+                let mut request = replication_request("tok-operator", &body);
+                let headers = request.headers_mut();
+                headers.insert(signature::TIMESTAMP_HEADER, ts.to_string().parse().unwrap());
+                headers.insert(
+                    signature::SIGNATURE_HEADER,
+                    signature::sign(secret, ts, bytes.as_bytes())
+                        .parse()
+                        .unwrap(),
+                );
+                request
+            };
+            let now = chrono::Utc::now().timestamp();
+
+            let cases = [
+                (
+                    replication_request("tok-operator", &body),
+                    "missing_signature",
+                ),
+                (signed(b"wrong", now), "bad_signature"),
+                (signed(b"s3cret", now - 3600), "stale_timestamp"),
+            ];
+            for (request, reason) in cases {
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+                assert_eq!(json_body(response).await["reason"], reason);
+            }
 
-            // Should contain Prometheus format headers
-            assert!(text.contains("# HELP"));
-            assert!(text.contains("# TYPE"));
-            assert!(text.contains("vc_alerts_open_total"));
-            assert!(text.contains("vc_machines_total"));
-            assert!(text.contains("vc_uptime_seconds"));
+            let response = app.clone().oneshot(signed(b"s3cret", now)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = app.oneshot(signed(b"s3cret", now)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(json_body(response).await["reason"], "replayed");
         });
     }
 
+    fn guardian_request(uri: &str, token: &str, body: &serde_json::Value) -> Request<Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn seed_guardian_approvals(store: &VcStore) {
+        store
+            .insert_json(
+                "guardian_runs",
+                &serde_json::json!({
+                    "id": 7,
+                    "playbook_id": "rate-limit-switch",
+                    "started_at": "2026-01-28T12:00:00Z",
+                    "status": "pending_approval"
+                }),
+            )
+            .unwrap();
+        for draft_id in ["d1", "d2"] {
+            store
+                .insert_json(
+                    "playbook_drafts",
+                    &serde_json::json!({
+                        "draft_id": draft_id,
+                        "name": "Clear disk",
+                        "alert_type": "disk_full",
+                        "trigger_json": "{}",
+                        "steps_json": "[]",
+                        "created_at": "2026-01-28 12:00:00"
+                    }),
+                )
+                .unwrap();
+        }
+    }
+
     #[test]
-    fn test_metrics_content_type() {
+    fn test_guardian_pending_lists_runs_and_drafts() {
         run_tokio(async {
-            let state = Arc::new(AppState::new_memory().unwrap());
+            let state = query_state(0);
+            seed_guardian_approvals(&state.store);
             let app = create_router(state);
 
             let request = Request::builder()
-                .uri("/metrics")
+                .uri("/api/guardian/pending")
+                .header("authorization", "Bearer tok-reader")
                 .body(Body::empty())
                 .unwrap();
+            let json = json_body(app.clone().oneshot(request).await.unwrap()).await;
+            assert_eq!(json["pending"].as_array().unwrap().len(), 1);
+            assert_eq!(json["drafts"].as_array().unwrap().len(), 2);
+            assert!(json["pending"][0]["age_secs"].as_i64().unwrap() > 0);
+            assert_eq!(json["total"], 3);
 
-            let response = app.oneshot(request).await.unwrap();
-            let content_type = response
-                .headers()
-                .get("content-type")
-                .unwrap()
-                .to_str()
+            let request = Request::builder()
+                .uri("/api/overview")
+                .header("authorization", "Bearer tok-reader")
+                .body(Body::empty())
                 .unwrap();
-            assert!(content_type.contains("text/plain"));
+            let overview = json_body(app.oneshot(request).await.unwrap()).await;
+            assert_eq!(overview["pending_approvals"], json["total"]);
         });
     }
 
     #[test]
-    fn test_metrics_empty_db_defaults() {
+    fn test_guardian_run_approve_requires_operator_and_conflicts() {
         run_tokio(async {
-            let state = Arc::new(AppState::new_memory().unwrap());
-            let app = create_router(state);
+            let state = query_state(0);
+            seed_guardian_approvals(&state.store);
+            let app = create_router(state.clone());
+            let uri = "/api/guardian/runs/7/approve";
+            let empty = serde_json::json!({});
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-reader", &empty))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-            let request = Request::builder()
-                .uri("/metrics")
-                .body(Body::empty())
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-operator", &empty))
+                .await
                 .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["run"]["status"], "approved");
+            assert_eq!(json["run"]["approved_by"], "operator");
 
-            let response = app.oneshot(request).await.unwrap();
-            let body = response.into_body().collect().await.unwrap().to_bytes();
-            let text = String::from_utf8(body.to_vec()).unwrap();
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-operator", &empty))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            let json = json_body(response).await;
+            assert_eq!(json["current"]["status"], "approved");
+
+            let response = app
+                .oneshot(guardian_request(
+                    "/api/guardian/runs/99/approve",
+                    "tok-operator",
+                    &empty,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
-            // Empty DB should show zero alerts
-            assert!(text.contains("vc_alerts_open_total{severity=\"info\"} 0"));
-            assert!(text.contains("vc_alerts_open_total{severity=\"warning\"} 0"));
-            assert!(text.contains("vc_alerts_open_total{severity=\"critical\"} 0"));
-            assert!(text.contains("vc_machines_total 0"));
+            let audited = state
+                .store
+                .query_json(
+                    "SELECT actor FROM audit_events WHERE action = 'approve_run' AND event_type = 'guardian_action'",
+                )
+                .unwrap();
+            assert_eq!(audited.len(), 1);
+            assert_eq!(audited[0]["actor"], "operator");
         });
     }
 
     #[test]
-    fn test_generate_metrics_text() {
-        let store = VcStore::open_memory().unwrap();
-        let text = generate_metrics_text(&store);
+    fn test_query_proposals_approve_and_deny() {
+        run_tokio(async {
+            let state = query_state(0);
+            let agent = ActorContext::mcp(Some("agent"), None);
+            let hour = Duration::from_secs(3600);
+            let first = state
+                .store
+                .propose_query("SELECT 5 AS n", "check", &agent, hour)
+                .unwrap();
+            let second = state
+                .store
+                .propose_query("SELECT 6 AS n", "check again", &agent, hour)
+                .unwrap();
+            let app = create_router(state);
+            let empty = serde_json::json!({});
+
+            let list = |token: &str| {
+                Request::builder()
+                    .uri("/api/query/proposals")
+                    .header("authorization", format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .unwrap()
+            };
+            let response = app.clone().oneshot(list("tok-reader")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let json = json_body(app.clone().oneshot(list("tok-operator")).await.unwrap()).await;
+            assert_eq!(json["count"], 2);
+
+            let approve = format!("/api/query/proposals/{}/approve", first.id);
+            let response = app
+                .clone()
+                .oneshot(guardian_request(&approve, "tok-reader", &empty))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let response = app
+                .clone()
+                .oneshot(guardian_request(&approve, "tok-operator", &empty))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["proposal"]["status"], "executed");
+            assert_eq!(json["proposal"]["decided_by"], "operator");
+            assert_eq!(json["proposal"]["result"][0]["n"], 5);
+
+            let deny = format!("/api/query/proposals/{}/deny", second.id);
+            let reason = serde_json::json!({ "reason": "not needed" });
+            let response = app
+                .clone()
+                .oneshot(guardian_request(&deny, "tok-operator", &reason))
+                .await
+                .unwrap();
+            let json = json_body(response).await;
+            assert_eq!(json["proposal"]["status"], "denied");
+            assert_eq!(json["proposal"]["decision_reason"], "not needed");
 
-        assert!(text.contains("# HELP vc_alerts_open_total"));
-        assert!(text.contains("# TYPE vc_alerts_open_total gauge"));
-        assert!(text.contains("vc_machines_total 0"));
+            let response = app
+                .clone()
+                .oneshot(guardian_request(&approve, "tok-operator", &empty))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            let response = app
+                .oneshot(guardian_request(
+                    "/api/query/proposals/99/deny",
+                    "tok-operator",
+                    &empty,
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        });
+    }
+
+    #[test]
+    fn test_annotation_post_requires_signature_when_enabled() {
+        run_tokio(async {
+            let body = serde_json::json!({"kind": "deploy", "message": "release 1.42"});
+            let mut state = Arc::try_unwrap(query_state(0)).ok().unwrap();
+            state.signature_verifier = Some(Arc::new(signature::SignatureVerifier::new(
+                b"s3cret".to_vec(),
+                300,
+                16,
+            )));
+            let app = create_router(Arc::new(state));
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request("/api/annotations", "tok-operator", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(json_body(response).await["reason"], "missing_signature");
+
+            let mut request = guardian_request("/api/annotations", "tok-operator", &body);
+            let now = chrono::Utc::now().timestamp();
+            let headers = request.headers_mut();
+            headers.insert(
+                signature::TIMESTAMP_HEADER,
+                now.to_string().parse().unwrap(),
+            );
+            headers.insert(
+                signature::SIGNATURE_HEADER,
+                signature::sign(b"s3cret", now, body.to_string().as_bytes())
+                    .parse()
+                    .unwrap(),
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+
+            // Reading them stays a plain bearer-token GET
+            let response = app
+                .oneshot(
+                    Request::builder()
+                        .uri("/api/annotations")
+                        .header("authorization", "Bearer tok-reader")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        });
+    }
+
+    #[test]
+    fn test_annotations_create_and_list() {
+        run_tokio(async {
+            let state = query_state(0);
+            let app = create_router(state.clone());
+            let body = serde_json::json!({
+                "kind": "deploy",
+                "scope": "machine:orko",
+                "message": "release 1.42",
+                "source": "ci"
+            });
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request("/api/annotations", "tok-reader", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(
+                    "/api/annotations",
+                    "tok-operator",
+                    &serde_json::json!({"kind": "Deploy!", "message": "x"}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request("/api/annotations", "tok-operator", &body))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            let created = json_body(response).await;
+            assert_eq!(created["scope"], "machine:orko");
+            assert_eq!(created["created_by"], "operator");
+
+            let request = Request::builder()
+                .uri("/api/annotations?kind=deploy&since=1h&machine=orko")
+                .header("authorization", "Bearer tok-reader")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let listed = json_body(response).await;
+            assert_eq!(listed.as_array().unwrap().len(), 1);
+            assert_eq!(listed[0]["message"], "release 1.42");
+
+            let request = Request::builder()
+                .uri("/api/annotations?kind=maintenance")
+                .header("authorization", "Bearer tok-reader")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert!(json_body(response).await.as_array().unwrap().is_empty());
+
+            let audited = state
+                .store
+                .query_json("SELECT actor FROM audit_events WHERE action = 'annotation_add'")
+                .unwrap();
+            assert_eq!(audited.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_oidc_session_cookie_stands_in_for_a_token() {
+        run_tokio(async {
+            let config = vc_config::OidcConfig {
+                enabled: true,
+                issuer: Some("https://sso.example.com".to_string()),
+                client_id: Some("vibe-cockpit".to_string()),
+                client_secret: Some("s3cret".to_string()),
+                redirect_url: Some("https://cockpit.example.com/auth/callback".to_string()),
+                ..vc_config::OidcConfig::default()
+            };
+            let login = oidc::OidcLogin::new(&config, "s3cret".to_string(), None).unwrap();
+            let cookie = login.sessions().create(oidc::Session {
+                subject: "00u1a2b3c4".to_string(),
+                display_name: Some("alice@example.com".to_string()),
+                role: auth::Role::Operator,
+                expires_at: chrono::Utc::now() + chrono::Duration::hours(1),
+            });
+            let mut state = Arc::into_inner(query_state(0)).unwrap();
+            state.oidc = Some(Arc::new(login));
+            let state = Arc::new(state);
+            let app = create_router(state.clone());
+            let with_cookie = |method: &str, uri: &str, cookie: &str, body: Body| {
+                Request::builder()
+                    .method(method)
+                    .uri(uri)
+                    .header("cookie", format!("{}={cookie}", oidc::SESSION_COOKIE))
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .unwrap()
+            };
+
+            let response = app
+                .clone()
+                .oneshot(with_cookie("GET", "/api/alerts", &cookie, Body::empty()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let response = app
+                .clone()
+                .oneshot(with_cookie(
+                    "GET",
+                    "/api/alerts",
+                    "forged.cookie",
+                    Body::empty(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            // Writes in the session are recorded under the OIDC subject
+            let response = app
+                .clone()
+                .oneshot(with_cookie(
+                    "POST",
+                    "/api/annotations",
+                    &cookie,
+                    Body::from(r#"{"kind": "deploy", "message": "release 1.42"}"#),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+            assert_eq!(json_body(response).await["created_by"], "00u1a2b3c4");
+
+            let response = app
+                .clone()
+                .oneshot(with_cookie("GET", "/auth/session", &cookie, Body::empty()))
+                .await
+                .unwrap();
+            let info = json_body(response).await;
+            assert_eq!(info["oidc"], true);
+            assert_eq!(info["session"]["role"], "operator");
+
+            let response = app
+                .clone()
+                .oneshot(with_cookie("POST", "/auth/logout", &cookie, Body::empty()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+            let cleared = response.headers()["set-cookie"].to_str().unwrap();
+            assert!(cleared.contains("Max-Age=0"), "{cleared}");
+
+            let response = app
+                .oneshot(with_cookie("GET", "/api/alerts", &cookie, Body::empty()))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let actions = state
+                .store
+                .query_json("SELECT actor, action FROM audit_events ORDER BY id")
+                .unwrap();
+            assert!(
+                actions
+                    .iter()
+                    .any(|row| row["actor"] == "00u1a2b3c4" && row["action"] == "oidc_logout")
+            );
+        });
+    }
+
+    #[test]
+    fn test_alerts_bulk_ack_by_filter() {
+        run_tokio(async {
+            let state = query_state(0);
+            for (rule, machine, fired_at) in [
+                ("machine-offline", "orko", "2026-10-01T00:00:00Z"),
+                ("machine-offline", "orko", "2026-10-02T00:00:00Z"),
+                ("disk-critical", "orko", "2026-10-02T00:00:00Z"),
+                ("machine-offline", "sydney", "2026-10-01T00:00:00Z"),
+            ] {
+                state
+                    .store
+                    .insert_alert(&vc_store::FiredAlert {
+                        rule_id: rule.to_string(),
+                        fired_at: fired_at.to_string(),
+                        severity: "warning".to_string(),
+                        title: rule.to_string(),
+                        message: String::new(),
+                        context_json: None,
+                        machine_id: Some(machine.to_string()),
+                    })
+                    .unwrap();
+            }
+            let app = create_router(state.clone());
+            let uri = "/api/alerts/bulk-ack";
+            let filter = serde_json::json!({
+                "machine": "orko",
+                "type": "offline",
+                "before": "2026-10-16",
+                "dry_run": true
+            });
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-reader", &filter))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(
+                    uri,
+                    "tok-operator",
+                    &serde_json::json!({}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            // `before` must be a timestamp; a bare date is the CLI's shorthand.
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-operator", &filter))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let mut filter = filter;
+            filter["before"] = serde_json::json!("2026-10-16T00:00:00Z");
+            let response = app
+                .clone()
+                .oneshot(guardian_request(uri, "tok-operator", &filter))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["matched"], 2);
+            assert_eq!(json["sample"].as_array().unwrap().len(), 2);
+
+            filter["dry_run"] = serde_json::json!(false);
+            let response = app
+                .oneshot(guardian_request(uri, "tok-operator", &filter))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["acknowledged"], 2);
+
+            let audited = state
+                .store
+                .query_json(
+                    "SELECT actor FROM audit_events WHERE action = 'alert_bulk_acknowledge'",
+                )
+                .unwrap();
+            assert_eq!(audited.len(), 1);
+            assert_eq!(audited[0]["actor"], "operator");
+        });
+    }
+
+    #[test]
+    fn test_actor_header_names_writes_and_rate_limit_buckets() {
+        run_tokio(async {
+            let state = query_state(1);
+            let app = create_router(state.clone());
+            let as_actor = |actor: &str, mut request: Request<Body>| {
+                request
+                    .headers_mut()
+                    .insert(auth::ACTOR_HEADER, actor.parse().unwrap());
+                request
+            };
+
+            // Two agents sharing a token each get their own budget.
+            let unknown = serde_json::json!({"name": "nope"});
+            for actor in ["night-orchestrator", "day-orchestrator"] {
+                let request = as_actor(actor, template_request("tok-reader", &unknown));
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            }
+            let request = as_actor(
+                "night-orchestrator",
+                template_request("tok-reader", &unknown),
+            );
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+            let request = as_actor(
+                "night-orchestrator",
+                guardian_request(
+                    "/api/alerts/bulk-ack",
+                    "tok-operator",
+                    &serde_json::json!({"all": true}),
+                ),
+            );
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let audited = state
+                .store
+                .query_json(
+                    "SELECT actor, actor_via FROM audit_events \
+                     WHERE action = 'alert_bulk_acknowledge'",
+                )
+                .unwrap();
+            assert_eq!(audited[0]["actor"], "night-orchestrator");
+            assert_eq!(audited[0]["actor_via"], "web");
+        });
+    }
+
+    #[test]
+    fn test_guardian_draft_approve_and_reject_conflicts() {
+        run_tokio(async {
+            let state = query_state(0);
+            seed_guardian_approvals(&state.store);
+            let app = create_router(state.clone());
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(
+                    "/api/guardian/drafts/d1/reject",
+                    "tok-operator",
+                    &serde_json::json!({"reason": "too broad"}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(
+                    "/api/guardian/drafts/d1/approve",
+                    "tok-operator",
+                    &serde_json::json!({"revision": 1}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+            let json = json_body(response).await;
+            assert_eq!(json["current"]["status"], "rejected");
+
+            let response = app
+                .clone()
+                .oneshot(guardian_request(
+                    "/api/guardian/drafts/d2/approve",
+                    "tok-operator",
+                    &serde_json::json!({"revision": 2}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CONFLICT);
+
+            let response = app
+                .oneshot(guardian_request(
+                    "/api/guardian/drafts/d2/approve",
+                    "tok-operator",
+                    &serde_json::json!({"revision": 1}),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["draft"]["status"], "approved");
+            assert_eq!(json["draft"]["approved_by"], "operator");
+
+            let audited = state
+                .store
+                .query_scalar::<i64>(
+                    "SELECT COUNT(*) FROM audit_events WHERE action IN ('approve_draft', 'reject_draft')",
+                )
+                .unwrap();
+            assert_eq!(audited, 2);
+        });
+    }
+
+    #[test]
+    fn test_query_templates_lists_catalog() {
+        run_tokio(async {
+            let app = create_router(query_state(0));
+            let request = Request::builder()
+                .uri("/api/query/templates")
+                .header("authorization", "Bearer tok-reader")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            let templates = json["templates"].as_array().unwrap();
+            assert!(
+                templates
+                    .iter()
+                    .any(|t| t["name"] == "audit_dump" && t["agent_safe"] == false)
+            );
+            assert!(templates.iter().any(|t| t["name"] == "machine_status"));
+        });
+    }
+
+    // =============================================================================
+    // Robot endpoint tests
+    // =============================================================================
+
+    fn robot_request(uri: &str, accept: Option<&str>) -> Request<Body> {
+        let mut request = Request::builder()
+            .uri(uri)
+            .header("authorization", "Bearer tok-reader");
+        if let Some(accept) = accept {
+            request = request.header("accept", accept);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[test]
+    fn test_robot_json_matches_cli_envelope() {
+        run_tokio(async {
+            let state = query_state(0);
+            let app = create_router(state.clone());
+
+            for view in RobotView::ALL {
+                let uri = format!("/api/robot/{}", view.name());
+                let response = app
+                    .clone()
+                    .oneshot(robot_request(&uri, None))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK, "{uri}");
+                assert_eq!(
+                    response.headers()[header::CACHE_CONTROL],
+                    format!("private, max-age={}", view.cache_ttl_secs()).as_str()
+                );
+                let json = json_body(response).await;
+                let schema = json["schema_version"].as_str().unwrap_or_default();
+                assert!(
+                    schema.starts_with(&format!("vc.robot.{}.v", view.name())),
+                    "{uri}: {schema}"
+                );
+            }
+
+            let response = app
+                .oneshot(robot_request("/api/robot/accounts", None))
+                .await
+                .unwrap();
+            let json = json_body(response).await;
+            let cli = vc_robot::robot_accounts(&state.store).unwrap();
+            assert_eq!(json["data"], serde_json::to_value(&cli.data).unwrap());
+            assert_eq!(
+                json["warnings"],
+                serde_json::to_value(&cli.warnings).unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn test_robot_toon_by_accept_or_format() {
+        run_tokio(async {
+            let app = create_router(query_state(0));
+
+            for (uri, accept) in [
+                ("/api/robot/health", Some("text/x-toon")),
+                ("/api/robot/health?format=toon", None),
+                ("/api/robot/health?format=toon", Some("application/json")),
+            ] {
+                let response = app
+                    .clone()
+                    .oneshot(robot_request(uri, accept))
+                    .await
+                    .unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(response.headers()[header::CONTENT_TYPE], "text/x-toon");
+                assert_eq!(response.headers()["x-vc-schema"], "vc.robot.health.v1");
+                assert!(response.headers().contains_key("x-vc-staleness"));
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                assert!(body.starts_with(b"TOON1"));
+            }
+
+            let response = app
+                .clone()
+                .oneshot(robot_request(
+                    "/api/robot/health?format=json",
+                    Some("text/x-toon"),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(
+                json_body(response).await["schema_version"],
+                "vc.robot.health.v1"
+            );
+
+            let response = app
+                .clone()
+                .oneshot(robot_request("/api/robot/health?format=yaml", None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+            let response = app
+                .oneshot(robot_request("/api/robot/schema", None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        });
+    }
+
+    #[test]
+    fn test_robot_triage_honors_max_items() {
+        run_tokio(async {
+            let state = query_state(0);
+            state
+                .store
+                .execute_batch(
+                    "INSERT INTO alert_history (id, rule_id, fired_at, severity, title) VALUES \
+                     (1, 'disk-critical', '2026-01-28 12:00:00', 'critical', 'Disk full'), \
+                     (2, 'load-high', '2026-01-28 12:05:00', 'warning', 'Load high')",
+                )
+                .unwrap();
+            let app = create_router(state.clone());
+
+            let response = app
+                .oneshot(robot_request("/api/robot/triage?max_items=1", None))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            assert_eq!(json["data"]["actions"].as_array().unwrap().len(), 1);
+            assert_eq!(json["data"]["truncated"], 1);
+            let cli = vc_robot::robot_triage(&state.store, &state.min_versions, Some(1)).unwrap();
+            assert_eq!(
+                json["data"]["actions"][0]["id"],
+                cli.data.actions[0].id.as_str()
+            );
+        });
+    }
+
+    // =============================================================================
+    // Prometheus metrics tests
+    // =============================================================================
+
+    #[test]
+    fn test_metrics_endpoint() {
+        run_tokio(async {
+            let state = Arc::new(AppState::new_memory().unwrap());
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let text = String::from_utf8(body.to_vec()).unwrap();
+
+            // Should contain Prometheus format headers
+            assert!(text.contains("# HELP"));
+            assert!(text.contains("# TYPE"));
+            assert!(text.contains("vc_alerts_open_total"));
+            assert!(text.contains("vc_machines_total"));
+            assert!(text.contains("vc_uptime_seconds"));
+        });
+    }
+
+    #[test]
+    fn test_metrics_content_type() {
+        run_tokio(async {
+            let state = Arc::new(AppState::new_memory().unwrap());
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let content_type = response
+                .headers()
+                .get("content-type")
+                .unwrap()
+                .to_str()
+                .unwrap();
+            assert!(content_type.contains("text/plain"));
+        });
+    }
+
+    #[test]
+    fn test_events_stream_delivers_published_events() {
+        run_tokio(async {
+            let state = test_state();
+            let app = create_router(Arc::clone(&state));
+
+            let request = Request::builder()
+                .uri("/api/events?machine=m1&severity=warning")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert!(
+                response.headers()["content-type"]
+                    .to_str()
+                    .unwrap()
+                    .starts_with("text/event-stream")
+            );
+            assert_eq!(state.events.metrics().clients(), 1);
+
+            let alert = |machine: &str, severity: &str| {
+                events::StreamEvent::alert(&serde_json::json!({
+                    "id": 1, "machine_id": machine, "severity": severity,
+                }))
+            };
+            assert_eq!(state.events.publish(&alert("m2", "critical")), 0);
+            assert_eq!(state.events.publish(&alert("m1", "info")), 0);
+            assert_eq!(state.events.publish(&alert("m1", "critical")), 1);
+
+            let mut body = response.into_body();
+            let frame = body.frame().await.unwrap().unwrap().into_data().unwrap();
+            let text = String::from_utf8(frame.to_vec()).unwrap();
+            assert!(text.contains("event: alert"));
+            assert!(text.contains("\"machine_id\":\"m1\""));
+
+            drop(body);
+            assert_eq!(state.events.metrics().clients(), 0);
+        });
+    }
+
+    #[test]
+    fn test_events_stream_rejects_unknown_severity() {
+        run_tokio(async {
+            let app = create_router(test_state());
+            let request = Request::builder()
+                .uri("/api/events?severity=loud")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        });
+    }
+
+    #[test]
+    fn test_metrics_include_event_stream() {
+        run_tokio(async {
+            let app = create_router(test_state());
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let text = String::from_utf8(body.to_vec()).unwrap();
+            assert!(text.contains("vc_web_event_clients 0"));
+            assert!(text.contains("vc_web_events_dropped_total{reason=\"overflow\"} 0"));
+        });
+    }
+
+    #[test]
+    fn test_metrics_empty_db_defaults() {
+        run_tokio(async {
+            let state = Arc::new(AppState::new_memory().unwrap());
+            let app = create_router(state);
+
+            let request = Request::builder()
+                .uri("/metrics")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let text = String::from_utf8(body.to_vec()).unwrap();
+
+            // Empty DB should show zero alerts
+            assert!(text.contains("vc_alerts_open_total{severity=\"info\"} 0"));
+            assert!(text.contains("vc_alerts_open_total{severity=\"warning\"} 0"));
+            assert!(text.contains("vc_alerts_open_total{severity=\"critical\"} 0"));
+            assert!(text.contains("vc_machines_total 0"));
+        });
+    }
+
+    #[test]
+    fn test_generate_metrics_text() {
+        let store = VcStore::open_memory().unwrap();
+        let text = generate_metrics_text(&store);
+
+        assert!(text.contains("# HELP vc_alerts_open_total"));
+        assert!(text.contains("# TYPE vc_alerts_open_total gauge"));
+        assert!(text.contains("vc_machines_total 0"));
+        assert!(text.contains("# TYPE vc_oversized_writes_total counter"));
+    }
+
+    #[test]
+    fn test_metrics_count_oversized_writes() {
+        let store =
+            VcStore::open_memory()
+                .unwrap()
+                .with_write_limits(&vc_config::WriteLimitsConfig {
+                    max_text_bytes: 4096,
+                    ..vc_config::WriteLimitsConfig::default()
+                });
+        store
+            .insert_json(
+                "sys_fallback_samples",
+                &serde_json::json!({
+                    "machine_id": "orko",
+                    "collected_at": "2026-10-16T12:00:00Z",
+                    "raw_output": "x".repeat(10_000),
+                }),
+            )
+            .unwrap();
+
+        let text = generate_metrics_text(&store);
+        assert!(text.contains(
+            "vc_oversized_writes_total{table=\"sys_fallback_samples\",collector=\"-\",action=\"truncated\"} 1"
+        ));
+    }
+
+    // =============================================================================
+    // Export bundle tests
+    // =============================================================================
+
+    /// Auth-enabled state with one recorded bundle holding `manifest.json`
+    /// and `alerts.jsonl`, and a file beside the bundle that must stay out
+    /// of reach
+    fn export_state() -> (Arc<AppState>, tempfile::TempDir) {
+        let outer = tempfile::tempdir().unwrap();
+        let dir = outer.path().join("nightly");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("manifest.json"), r#"{"export_version":"1.0"}"#).unwrap();
+        std::fs::write(dir.join("alerts.jsonl"), "0123456789abcdefghij").unwrap();
+        std::fs::write(outer.path().join("vc.toml"), "secret").unwrap();
+        let state = query_state(0);
+        state
+            .store
+            .record_export_bundle("ex-1", dir.to_str().unwrap(), "jsonl", 2, 1)
+            .unwrap();
+        (state, outer)
+    }
+
+    fn export_request(uri: &str, headers: &[(&str, &str)]) -> Request<Body> {
+        let mut builder = Request::builder()
+            .uri(uri)
+            .header("authorization", "Bearer tok-reader");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    async fn body_bytes(response: Response) -> Vec<u8> {
+        response
+            .into_body()
+            .collect()
+            .await
+            .unwrap()
+            .to_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_exports_list_requires_auth_and_lists_files() {
+        run_tokio(async {
+            let (state, _dir) = export_state();
+            let app = create_router(state);
+
+            let anonymous = Request::builder()
+                .uri("/api/exports")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(anonymous).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+            let response = app
+                .oneshot(export_request("/api/exports", &[]))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let json = json_body(response).await;
+            let bundle = &json["bundles"][0];
+            assert_eq!(bundle["bundle_id"], "ex-1");
+            assert_eq!(bundle["available"], true);
+            assert_eq!(bundle["files"][0]["name"], "alerts.jsonl");
+            assert_eq!(bundle["files"][0]["size"], 20);
+            assert_eq!(bundle["files"][1]["name"], "manifest.json");
+        });
+    }
+
+    #[test]
+    fn test_export_file_full_and_ranges() {
+        run_tokio(async {
+            let (state, _dir) = export_state();
+            let app = create_router(state);
+            let uri = "/api/exports/ex-1/alerts.jsonl";
+
+            let response = app.clone().oneshot(export_request(uri, &[])).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["accept-ranges"], "bytes");
+            assert_eq!(response.headers()["content-length"], "20");
+            let etag = response.headers()["etag"].to_str().unwrap().to_string();
+            assert_eq!(body_bytes(response).await, b"0123456789abcdefghij");
+
+            // Resume after the first 15 bytes
+            let response = app
+                .clone()
+                .oneshot(export_request(
+                    uri,
+                    &[("range", "bytes=15-"), ("if-range", etag.as_str())],
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(response.headers()["content-range"], "bytes 15-19/20");
+            assert_eq!(response.headers()["content-length"], "5");
+            assert_eq!(body_bytes(response).await, b"fghij");
+
+            // Suffix range, and an end past the file clamped
+            let response = app
+                .clone()
+                .oneshot(export_request(uri, &[("range", "bytes=-3")]))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(response.headers()["content-range"], "bytes 17-19/20");
+            assert_eq!(body_bytes(response).await, b"hij");
+            let response = app
+                .clone()
+                .oneshot(export_request(uri, &[("range", "bytes=8-99")]))
+                .await
+                .unwrap();
+            assert_eq!(response.headers()["content-range"], "bytes 8-19/20");
+
+            // A stale validator gets the whole file
+            let response = app
+                .clone()
+                .oneshot(export_request(
+                    uri,
+                    &[("range", "bytes=15-"), ("if-range", "\"stale\"")],
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(body_bytes(response).await.len(), 20);
+
+            for range in ["bytes=20-", "bytes=9-3", "bytes=-0", "bytes=x-y"] {
+                let response = app
+                    .clone()
+                    .oneshot(export_request(uri, &[("range", range)]))
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    response.status(),
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    "{range}"
+                );
+                assert_eq!(response.headers()["content-range"], "bytes */20");
+            }
+        });
+    }
+
+    #[test]
+    fn test_export_file_confined_to_bundle_dir() {
+        run_tokio(async {
+            let (state, _dir) = export_state();
+            let app = create_router(state);
+            for uri in [
+                "/api/exports/ex-1/..%2Fvc.toml",
+                "/api/exports/ex-1/..",
+                "/api/exports/ex-1/%2E%2E%2Fvc.toml",
+                "/api/exports/ex-1/missing.jsonl",
+                "/api/exports/ex-2/alerts.jsonl",
+            ] {
+                let response = app.clone().oneshot(export_request(uri, &[])).await.unwrap();
+                assert_eq!(response.status(), StatusCode::NOT_FOUND, "{uri}");
+            }
+        });
+    }
+
+    #[test]
+    fn test_export_tar_streams_whole_bundle() {
+        run_tokio(async {
+            let (state, _dir) = export_state();
+            let app = create_router(state);
+            let response = app
+                .oneshot(export_request("/api/exports/ex-1/archive.tar", &[]))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["content-type"], "application/x-tar");
+            let length: usize = response.headers()["content-length"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            let body = body_bytes(response).await;
+            // Two files of one block each after their headers, then the end marker
+            assert_eq!(body.len(), length);
+            assert_eq!(length, 4 * 512 + 1024);
+            assert_eq!(&body[..17], b"ex-1/alerts.jsonl");
+            assert_eq!(&body[512..532], b"0123456789abcdefghij");
+            assert_eq!(&body[1024..1042], b"ex-1/manifest.json");
+            assert!(body[2048..].iter().all(|b| *b == 0));
+        });
     }
 }
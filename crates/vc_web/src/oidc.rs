@@ -0,0 +1,928 @@
+//! OpenID Connect login for people using the dashboard.
+//!
+//! - `GET /auth/login` redirects to the provider's authorization endpoint
+//!   with a fresh `state`, `nonce` and PKCE challenge
+//! - `GET /auth/callback` trades the code for an ID token, maps the user's
+//!   groups through `[web.oidc.role_map]` to a [`Role`] and sets the session
+//!   cookie (`HttpOnly`, `SameSite=Lax`, signed with the session key)
+//! - `POST /auth/logout` ends the session and clears the cookie
+//! - `GET /auth/session` says whether OIDC is on and who is logged in
+//!
+//! [`crate::auth::auth_middleware`] accepts a live session wherever a bearer
+//! token is accepted; a request carrying a token is judged by the token
+//! alone. Everything done in a session is recorded under the ID token's
+//! subject.
+//!
+//! The ID token comes straight from the token endpoint over TLS, so the
+//! provider is trusted from that connection (OIDC Core 3.1.3.7) rather than
+//! from a JWKS signature; its issuer, audience, expiry and nonce are still
+//! checked. Sessions and logins in flight live in memory.
+
+use crate::auth::{self, Role};
+use crate::signature::{constant_time_eq, hmac_sha256};
+use crate::{AppState, WebError};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Json, Redirect, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use vc_store::{ActorContext, AuditEventType, AuditResult};
+
+/// Cookie carrying the session ID and its signature
+pub const SESSION_COOKIE: &str = "vc_session";
+
+/// How long a login may take from `/auth/login` to the callback
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Logins in flight kept at most; the oldest is dropped past this
+const MAX_PENDING_LOGINS: usize = 1024;
+
+/// Tolerated difference between our clock and the provider's
+const CLOCK_SKEW_SECS: i64 = 60;
+
+/// Longest session honoured, whatever `session_ttl_secs` says
+const MAX_SESSION_TTL_SECS: u64 = 30 * 86_400;
+
+// ============================================================================
+// Sessions
+// ============================================================================
+
+/// A logged-in dashboard user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Session {
+    /// The ID token's `sub`; the actor on everything done in the session
+    pub subject: String,
+    /// `preferred_username`, `email` or `name`, for display
+    pub display_name: Option<String>,
+    pub role: Role,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Live sessions, keyed by a random ID the cookie carries with its HMAC
+pub struct SessionStore {
+    key: Vec<u8>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    /// Sessions whose cookies are signed with `key`
+    #[must_use]
+    pub fn new(key: Vec<u8>) -> Self {
+        Self {
+            key,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start a session; returns the cookie value
+    ///
+    /// # Panics
+    ///
+    /// Panics if the session mutex is poisoned.
+    pub fn create(&self, session: Session) -> String {
+        let id = random_token();
+        let value = format!("{id}.{}", self.sign(&id));
+        let now = Utc::now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires_at > now);
+        sessions.insert(id, session);
+        value
+    }
+
+    /// The live session a cookie value names; `None` when the signature is
+    /// wrong or the session is unknown or expired
+    ///
+    /// # Panics
+    ///
+    /// Panics if the session mutex is poisoned.
+    #[must_use]
+    pub fn get(&self, cookie: &str) -> Option<Session> {
+        let id = self.verify(cookie)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(id)?;
+        if session.expires_at <= Utc::now() {
+            sessions.remove(id);
+            return None;
+        }
+        Some(session.clone())
+    }
+
+    /// End the session a cookie value names
+    ///
+    /// # Panics
+    ///
+    /// Panics if the session mutex is poisoned.
+    pub fn remove(&self, cookie: &str) -> Option<Session> {
+        let id = self.verify(cookie)?;
+        self.sessions.lock().unwrap().remove(id)
+    }
+
+    fn sign(&self, id: &str) -> String {
+        hex(&hmac_sha256(&self.key, &[id.as_bytes()]))
+    }
+
+    fn verify<'a>(&self, cookie: &'a str) -> Option<&'a str> {
+        let (id, signature) = cookie.split_once('.')?;
+        constant_time_eq(self.sign(id).as_bytes(), signature.as_bytes()).then_some(id)
+    }
+}
+
+/// The session cookie's value in a request, if any
+#[must_use]
+pub fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+}
+
+// ============================================================================
+// Login flow
+// ============================================================================
+
+/// The endpoints named by the issuer's discovery document
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+/// A login between `/auth/login` and the callback, keyed by its `state`
+struct PendingLogin {
+    nonce: String,
+    verifier: String,
+    return_to: String,
+    started: Instant,
+}
+
+/// The ID token claims a login uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdClaims {
+    pub subject: String,
+    pub display_name: Option<String>,
+    /// The configured groups claim, as a list
+    pub groups: Vec<String>,
+}
+
+/// `[web.oidc]` with its secret resolved, and the sessions it logs into
+pub struct OidcLogin {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    scopes: Vec<String>,
+    groups_claim: String,
+    role_map: BTreeMap<String, Role>,
+    default_role: Option<Role>,
+    session_ttl: chrono::Duration,
+    secure_cookie: bool,
+    sessions: Arc<SessionStore>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+    provider: Mutex<Option<ProviderMetadata>>,
+    http: reqwest::Client,
+}
+
+impl OidcLogin {
+    /// Login for `config`, with the client secret already resolved; cookies
+    /// are signed with `session_key`, or a random key when `None`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`WebError::ServerError`] naming the first of
+    /// [`vc_config::OidcConfig::problems`].
+    pub fn new(
+        config: &vc_config::OidcConfig,
+        client_secret: String,
+        session_key: Option<Vec<u8>>,
+    ) -> Result<Self, WebError> {
+        if let Some((key, message)) = config.problems().into_iter().next() {
+            return Err(WebError::ServerError(format!("{key}: {message}")));
+        }
+        let setting = |value: &Option<String>| value.clone().unwrap_or_default();
+        Ok(Self {
+            issuer: setting(&config.issuer),
+            client_id: setting(&config.client_id),
+            client_secret,
+            redirect_url: setting(&config.redirect_url),
+            scopes: config.scopes.clone(),
+            groups_claim: config.groups_claim.clone(),
+            role_map: config
+                .role_map
+                .iter()
+                .filter_map(|(group, role)| Some((group.clone(), Role::parse(role)?)))
+                .collect(),
+            default_role: config.default_role.as_deref().and_then(Role::parse),
+            session_ttl: chrono::Duration::seconds(
+                i64::try_from(config.session_ttl_secs.min(MAX_SESSION_TTL_SECS))
+                    .unwrap_or_default(),
+            ),
+            secure_cookie: config.secure_cookie,
+            sessions: Arc::new(SessionStore::new(
+                session_key.unwrap_or_else(|| random_token().into_bytes()),
+            )),
+            pending: Mutex::new(HashMap::new()),
+            provider: Mutex::new(None),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// The sessions logins create, for the auth middleware
+    #[must_use]
+    pub fn sessions(&self) -> Arc<SessionStore> {
+        Arc::clone(&self.sessions)
+    }
+
+    /// The provider's endpoints, discovered on first use
+    async fn provider(&self) -> Result<ProviderMetadata, String> {
+        let cached = self.provider.lock().unwrap().clone();
+        if let Some(provider) = cached {
+            return Ok(provider);
+        }
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| format!("OIDC discovery at {url} failed: {e}"))?
+            .json()
+            .await
+            .map_err(|e| format!("OIDC discovery at {url} returned no usable document: {e}"))?;
+        if !same_issuer(&metadata.issuer, &self.issuer) {
+            return Err(format!(
+                "OIDC provider calls itself {}, not {}",
+                metadata.issuer, self.issuer
+            ));
+        }
+        *self.provider.lock().unwrap() = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Where to send the browser to log in. The login's nonce and PKCE
+    /// verifier are kept under its `state` until the callback.
+    fn authorization_url(&self, authorization_endpoint: &str, return_to: String) -> String {
+        let state = random_token();
+        let nonce = random_token();
+        let verifier = random_token();
+        let challenge = base64url_encode(&Sha256::digest(verifier.as_bytes()));
+        let scope = std::iter::once("openid")
+            .chain(
+                self.scopes
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|s| *s != "openid"),
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+        let query = form_encode(&[
+            ("response_type", "code"),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &self.redirect_url),
+            ("scope", &scope),
+            ("state", &state),
+            ("nonce", &nonce),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+        ]);
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| login.started.elapsed() < LOGIN_TIMEOUT);
+        if pending.len() >= MAX_PENDING_LOGINS
+            && let Some(oldest) = pending
+                .iter()
+                .min_by_key(|(_, login)| login.started)
+                .map(|(state, _)| state.clone())
+        {
+            pending.remove(&oldest);
+        }
+        pending.insert(
+            state,
+            PendingLogin {
+                nonce,
+                verifier,
+                return_to,
+                started: Instant::now(),
+            },
+        );
+        let separator = if authorization_endpoint.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        format!("{authorization_endpoint}{separator}{query}")
+    }
+
+    /// The login a callback's `state` belongs to, once; `None` when unknown
+    /// or too old
+    fn take_pending(&self, state: &str) -> Option<PendingLogin> {
+        let login = self.pending.lock().unwrap().remove(state)?;
+        (login.started.elapsed() < LOGIN_TIMEOUT).then_some(login)
+    }
+
+    /// Trade an authorization code for the ID token
+    async fn exchange_code(
+        &self,
+        token_endpoint: &str,
+        code: &str,
+        verifier: &str,
+    ) -> Result<String, String> {
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            id_token: Option<String>,
+        }
+
+        let body = form_encode(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_url),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("code_verifier", verifier),
+        ]);
+        let response = self
+            .http
+            .post(token_endpoint)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .header(reqwest::header::ACCEPT, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("OIDC token endpoint unreachable: {e}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail: String = response
+                .text()
+                .await
+                .unwrap_or_default()
+                .chars()
+                .take(200)
+                .collect();
+            return Err(format!("OIDC token endpoint answered {status}: {detail}"));
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("OIDC token response is not JSON: {e}"))?;
+        tokens
+            .id_token
+            .ok_or_else(|| "OIDC token response has no id_token".to_string())
+    }
+
+    /// Check an ID token from the token endpoint: issuer, audience, expiry
+    /// and the login's nonce
+    fn verify_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+        now: DateTime<Utc>,
+    ) -> Result<IdClaims, String> {
+        let claims: serde_json::Value = id_token
+            .split('.')
+            .nth(1)
+            .and_then(base64url_decode)
+            .and_then(|payload| serde_json::from_slice(&payload).ok())
+            .ok_or("ID token is not a JWT")?;
+
+        let issuer = claims["iss"].as_str().unwrap_or_default();
+        if !same_issuer(issuer, &self.issuer) {
+            return Err(format!("ID token issuer '{issuer}' is not {}", self.issuer));
+        }
+        let for_us = match &claims["aud"] {
+            serde_json::Value::String(aud) => *aud == self.client_id,
+            serde_json::Value::Array(auds) => auds
+                .iter()
+                .any(|aud| aud.as_str() == Some(self.client_id.as_str())),
+            _ => false,
+        };
+        if !for_us {
+            return Err("ID token is for another client".to_string());
+        }
+        let expires = claims["exp"].as_i64().ok_or("ID token has no exp")?;
+        if expires + CLOCK_SKEW_SECS < now.timestamp() {
+            return Err("ID token has expired".to_string());
+        }
+        if claims["nonce"].as_str() != Some(nonce) {
+            return Err("ID token nonce does not match the login".to_string());
+        }
+        let subject = claims["sub"]
+            .as_str()
+            .filter(|sub| !sub.is_empty())
+            .ok_or("ID token has no sub")?;
+
+        let groups = match &claims[self.groups_claim.as_str()] {
+            serde_json::Value::Array(groups) => groups
+                .iter()
+                .filter_map(|group| group.as_str().map(str::to_string))
+                .collect(),
+            serde_json::Value::String(groups) => groups
+                .split([',', ' '])
+                .filter(|group| !group.is_empty())
+                .map(str::to_string)
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(IdClaims {
+            subject: subject.to_string(),
+            display_name: ["preferred_username", "email", "name"]
+                .iter()
+                .find_map(|claim| claims[*claim].as_str())
+                .map(str::to_string),
+            groups,
+        })
+    }
+
+    /// The highest role among the user's mapped groups, else the default
+    /// role; `None` refuses the login
+    fn role_for(&self, groups: &[String]) -> Option<Role> {
+        groups
+            .iter()
+            .filter_map(|group| self.role_map.get(group))
+            .copied()
+            .max()
+            .or(self.default_role)
+    }
+
+    fn set_cookie(&self, value: &str) -> String {
+        let max_age = self.session_ttl.num_seconds().max(0);
+        self.cookie(value, max_age)
+    }
+
+    fn clear_cookie(&self) -> String {
+        self.cookie("", 0)
+    }
+
+    fn cookie(&self, value: &str, max_age: i64) -> String {
+        let secure = if self.secure_cookie { "; Secure" } else { "" };
+        format!(
+            "{SESSION_COOKIE}={value}; Path=/; HttpOnly; SameSite=Lax; Max-Age={max_age}{secure}"
+        )
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Query parameters of `GET /auth/login`
+#[derive(Debug, Deserialize)]
+pub struct LoginParams {
+    /// Dashboard path to land on afterwards (default `/`)
+    pub return_to: Option<String>,
+}
+
+/// Query parameters the provider sends back to `GET /auth/callback`
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// `GET /auth/session`
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    /// Whether `[web.oidc]` login is on
+    pub oidc: bool,
+    /// The caller's session, if logged in
+    pub session: Option<Session>,
+}
+
+fn configured(state: &AppState) -> Result<&OidcLogin, WebError> {
+    state
+        .oidc
+        .as_deref()
+        .ok_or_else(|| WebError::NotFound("OIDC login is not configured".to_string()))
+}
+
+/// Redirect to the provider's login page
+pub(crate) async fn login_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<LoginParams>,
+) -> Result<Response, WebError> {
+    let login = configured(&state)?;
+    let provider = login.provider().await.map_err(|e| {
+        warn!(error = %e, "OIDC login unavailable");
+        WebError::ServerError(e)
+    })?;
+    let url = login.authorization_url(
+        &provider.authorization_endpoint,
+        local_path(params.return_to.as_deref()),
+    );
+    Ok(Redirect::to(&url).into_response())
+}
+
+/// Finish a login: check the ID token, map the role and set the cookie
+pub(crate) async fn callback_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CallbackParams>,
+) -> Result<Response, WebError> {
+    let login = configured(&state)?;
+    if let Some(error) = params.error {
+        return Err(WebError::BadRequest(format!(
+            "the OIDC provider refused the login: {error}{}",
+            params
+                .error_description
+                .map(|d| format!(" ({d})"))
+                .unwrap_or_default()
+        )));
+    }
+    let (Some(code), Some(login_state)) = (params.code, params.state) else {
+        return Err(WebError::BadRequest(
+            "the callback needs code and state".to_string(),
+        ));
+    };
+    let Some(pending) = login.take_pending(&login_state) else {
+        return Err(WebError::BadRequest(
+            "unknown or expired login; start again at /auth/login".to_string(),
+        ));
+    };
+    let provider = login.provider().await.map_err(WebError::ServerError)?;
+    let id_token = login
+        .exchange_code(&provider.token_endpoint, &code, &pending.verifier)
+        .await
+        .map_err(|e| {
+            warn!(error = %e, "OIDC code exchange failed");
+            WebError::ServerError(e)
+        })?;
+    let claims = login
+        .verify_id_token(&id_token, &pending.nonce, Utc::now())
+        .map_err(|e| {
+            warn!(error = %e, "OIDC ID token rejected");
+            WebError::BadRequest(e)
+        })?;
+
+    let actor = ActorContext::web_session(&claims.subject);
+    let Some(role) = login.role_for(&claims.groups) else {
+        audit(
+            &state,
+            &actor,
+            "oidc_login",
+            AuditResult::Failure,
+            serde_json::json!({"reason": "no_mapped_role", "groups": claims.groups}),
+        );
+        return Ok(auth::forbidden_response("no_mapped_role"));
+    };
+    let session = Session {
+        subject: claims.subject,
+        display_name: claims.display_name,
+        role,
+        expires_at: Utc::now() + login.session_ttl,
+    };
+    audit(
+        &state,
+        &actor,
+        "oidc_login",
+        AuditResult::Success,
+        serde_json::json!({
+            "display_name": session.display_name,
+            "role": role.as_str(),
+            "expires_at": session.expires_at,
+        }),
+    );
+    info!(subject = %actor, role = role.as_str(), "OIDC login");
+    let cookie = login.sessions.create(session);
+    Ok((
+        [(header::SET_COOKIE, login.set_cookie(&cookie))],
+        Redirect::to(&pending.return_to),
+    )
+        .into_response())
+}
+
+/// End the caller's session and clear the cookie
+pub(crate) async fn logout_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Response, WebError> {
+    let login = configured(&state)?;
+    if let Some(session) = session_cookie(&headers).and_then(|cookie| login.sessions.remove(cookie))
+    {
+        audit(
+            &state,
+            &ActorContext::web_session(&session.subject),
+            "oidc_logout",
+            AuditResult::Success,
+            serde_json::json!({}),
+        );
+    }
+    Ok((
+        StatusCode::NO_CONTENT,
+        [(header::SET_COOKIE, login.clear_cookie())],
+    )
+        .into_response())
+}
+
+/// Whether OIDC is on and the caller's session, if any
+pub(crate) async fn session_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Json<SessionInfo> {
+    let login = state.oidc.as_deref();
+    Json(SessionInfo {
+        oidc: login.is_some(),
+        session: login
+            .zip(session_cookie(&headers))
+            .and_then(|(login, cookie)| login.sessions.get(cookie)),
+    })
+}
+
+fn audit(
+    state: &AppState,
+    actor: &ActorContext,
+    action: &str,
+    result: AuditResult,
+    details: serde_json::Value,
+) {
+    let event = actor.audit_event(AuditEventType::UserCommand, action, result, details);
+    if let Err(e) = state.store.insert_audit_event(&event) {
+        warn!(error = %e, action, "failed to audit OIDC login");
+    }
+}
+
+// ============================================================================
+// Encoding helpers
+// ============================================================================
+
+/// 256 random bits as hex
+fn random_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Issuers compare equal up to a trailing slash
+fn same_issuer(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+/// A dashboard path to return to; anything that could leave the site
+/// becomes `/`
+fn local_path(raw: Option<&str>) -> String {
+    match raw {
+        Some(path) if path.starts_with('/') && !path.starts_with("//") && !path.contains('\\') => {
+            path.to_string()
+        }
+        _ => "/".to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+/// `application/x-www-form-urlencoded`, also used for the login query
+fn form_encode(pairs: &[(&str, &str)]) -> String {
+    let encode = |text: &str| {
+        let mut out = String::with_capacity(text.len());
+        for byte in text.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                out.push(char::from(byte));
+            } else {
+                let _ = write!(out, "%{byte:02X}");
+            }
+        }
+        out
+    };
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", encode(key), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Unpadded base64url, as PKCE and JWTs use
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (u32::from(*b) << (16 - 8 * i)));
+        for i in 0..=chunk.len() {
+            out.push(char::from(BASE64URL[(n >> (18 - 6 * i)) as usize & 63]));
+        }
+    }
+    out
+}
+
+/// Decode unpadded (or padded) base64url; `None` on any other character
+fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes() {
+        let value = BASE64URL.iter().position(|b| *b == c)?;
+        bits = (bits << 6) | u32::try_from(value).ok()?;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push(u8::try_from((bits >> count) & 0xff).ok()?);
+        }
+    }
+    Some(out)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn config() -> vc_config::OidcConfig {
+        vc_config::OidcConfig {
+            enabled: true,
+            issuer: Some("https://sso.example.com/realms/ops".to_string()),
+            client_id: Some("vibe-cockpit".to_string()),
+            client_secret: Some("secret://env/VC_OIDC_CLIENT_SECRET".to_string()),
+            redirect_url: Some("https://cockpit.example.com/auth/callback".to_string()),
+            role_map: [("sre", "admin"), ("oncall", "operator")]
+                .into_iter()
+                .map(|(group, role)| (group.to_string(), role.to_string()))
+                .collect(),
+            ..vc_config::OidcConfig::default()
+        }
+    }
+
+    fn login() -> OidcLogin {
+        OidcLogin::new(&config(), "s3cret".to_string(), Some(b"key".to_vec())).unwrap()
+    }
+
+    fn id_token(claims: &serde_json::Value) -> String {
+        format!(
+            "{}.{}.sig",
+            base64url_encode(br#"{"alg":"RS256"}"#),
+            base64url_encode(claims.to_string().as_bytes())
+        )
+    }
+
+    fn session(role: Role, ttl_secs: i64) -> Session {
+        Session {
+            subject: "00u1a2b3c4".to_string(),
+            display_name: Some("alice@example.com".to_string()),
+            role,
+            expires_at: Utc::now() + chrono::Duration::seconds(ttl_secs),
+        }
+    }
+
+    #[test]
+    fn test_session_cookie_is_signed_and_expires() {
+        let store = SessionStore::new(b"key".to_vec());
+        let cookie = store.create(session(Role::Operator, 60));
+        assert_eq!(store.get(&cookie).unwrap().role, Role::Operator);
+
+        let (id, _) = cookie.split_once('.').unwrap();
+        assert!(store.get(&format!("{id}.{}", "0".repeat(64))).is_none());
+        assert!(store.get(id).is_none());
+        assert!(SessionStore::new(b"other".to_vec()).get(&cookie).is_none());
+
+        let expired = store.create(session(Role::Admin, -1));
+        assert!(store.get(&expired).is_none());
+
+        assert!(store.remove(&cookie).is_some());
+        assert!(store.get(&cookie).is_none());
+    }
+
+    #[test]
+    fn test_session_cookie_is_read_from_the_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; vc_session=abc.def; other=1"),
+        );
+        assert_eq!(session_cookie(&headers), Some("abc.def"));
+        assert_eq!(session_cookie(&HeaderMap::new()), None);
+
+        let set = login().set_cookie("abc.def");
+        assert!(set.starts_with("vc_session=abc.def; Path=/; HttpOnly; SameSite=Lax"));
+        assert!(set.contains("Max-Age=28800"));
+        assert!(set.ends_with("; Secure"));
+        assert!(login().clear_cookie().contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn test_authorization_url_carries_state_nonce_and_pkce() {
+        let login = login();
+        let url =
+            login.authorization_url("https://sso.example.com/auth", local_path(Some("/alerts")));
+        assert!(url.starts_with("https://sso.example.com/auth?response_type=code"));
+        assert!(url.contains("client_id=vibe-cockpit"));
+        assert!(url.contains("redirect_uri=https%3A%2F%2Fcockpit.example.com%2Fauth%2Fcallback"));
+        assert!(url.contains("scope=openid%20profile%20email"));
+        assert!(url.contains("code_challenge_method=S256"));
+
+        let state = url
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("state="))
+            .unwrap();
+        let pending = login.take_pending(state).unwrap();
+        assert_eq!(pending.return_to, "/alerts");
+        assert!(url.contains(&format!("nonce={}", pending.nonce)));
+        let challenge = base64url_encode(&Sha256::digest(pending.verifier.as_bytes()));
+        assert!(url.contains(&format!("code_challenge={challenge}")));
+
+        // A state is good for one callback
+        assert!(login.take_pending(state).is_none());
+        assert_eq!(local_path(Some("//evil.example.com")), "/");
+        assert_eq!(local_path(Some("https://evil.example.com")), "/");
+    }
+
+    #[test]
+    fn test_id_token_checks_issuer_audience_expiry_and_nonce() {
+        let login = login();
+        let now = Utc::now();
+        let good = serde_json::json!({
+            "iss": "https://sso.example.com/realms/ops/",
+            "aud": ["vibe-cockpit", "other"],
+            "exp": now.timestamp() + 300,
+            "nonce": "n-1",
+            "sub": "00u1a2b3c4",
+            "email": "alice@example.com",
+            "groups": ["oncall", "staff"],
+        });
+        let claims = login.verify_id_token(&id_token(&good), "n-1", now).unwrap();
+        assert_eq!(claims.subject, "00u1a2b3c4");
+        assert_eq!(claims.display_name.as_deref(), Some("alice@example.com"));
+        assert_eq!(claims.groups, vec!["oncall", "staff"]);
+
+        for (claim, value) in [
+            ("iss", serde_json::json!("https://evil.example.com")),
+            ("aud", serde_json::json!("someone-else")),
+            ("exp", serde_json::json!(now.timestamp() - 3600)),
+            ("nonce", serde_json::json!("n-2")),
+            ("sub", serde_json::json!("")),
+        ] {
+            let mut bad = good.clone();
+            bad[claim] = value;
+            assert!(
+                login.verify_id_token(&id_token(&bad), "n-1", now).is_err(),
+                "{claim}"
+            );
+        }
+        assert!(login.verify_id_token("not-a-jwt", "n-1", now).is_err());
+    }
+
+    #[test]
+    fn test_groups_map_to_the_highest_role() {
+        let groups = |list: &[&str]| list.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let mut login = login();
+        assert_eq!(login.role_for(&groups(&["oncall"])), Some(Role::Operator));
+        assert_eq!(
+            login.role_for(&groups(&["oncall", "sre"])),
+            Some(Role::Admin)
+        );
+        assert_eq!(login.role_for(&groups(&["staff"])), None);
+
+        login.default_role = Some(Role::Read);
+        assert_eq!(login.role_for(&groups(&["staff"])), Some(Role::Read));
+    }
+
+    #[test]
+    fn test_config_problems_refuse_the_login() {
+        let mut config = config();
+        config.issuer = Some("http://sso.example.com".to_string());
+        let err = OidcLogin::new(&config, String::new(), None).err().unwrap();
+        assert!(err.to_string().contains("web.oidc.issuer"), "{err}");
+    }
+
+    #[test]
+    fn test_encoding_helpers() {
+        assert_eq!(form_encode(&[("a b", "x/y&z")]), "a%20b=x%2Fy%26z");
+        for text in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+            let encoded = base64url_encode(text.as_bytes());
+            assert!(!encoded.contains('='));
+            assert_eq!(base64url_decode(&encoded).unwrap(), text.as_bytes());
+        }
+        assert_eq!(base64url_encode(b"\xfb\xff"), "-_8");
+        assert!(base64url_decode("a+b").is_none());
+    }
+}
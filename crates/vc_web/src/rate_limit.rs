@@ -0,0 +1,92 @@
+//! Per-caller request rate limiting for `vc_web`.
+//!
+//! A fixed one-minute window per key (token name, or client IP for
+//! unauthenticated callers, plus the actor when one is named). Coarse, but
+//! enough to keep an agent loop from hammering expensive endpoints.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const WINDOW: Duration = Duration::from_mins(1);
+
+/// Fixed-window request counter keyed by caller
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_minute: u32,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Allow `per_minute` requests per caller per minute; 0 disables the limit
+    #[must_use]
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `key`.
+    ///
+    /// Returns `Err(retry_after)` once the caller's budget for the current
+    /// window is spent.
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until the window resets when the request is refused.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal mutex is poisoned.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if self.per_minute == 0 {
+            return Ok(());
+        }
+        let mut windows = self.windows.lock().unwrap();
+        let (started, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*started) >= WINDOW {
+            *started = now;
+            *count = 0;
+        }
+        if *count >= self.per_minute {
+            return Err(WINDOW.saturating_sub(now.duration_since(*started)));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_per_key_and_window_reset() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+
+        assert!(limiter.check_at("agent", start).is_ok());
+        assert!(limiter.check_at("agent", start).is_ok());
+        let retry = limiter.check_at("agent", start).unwrap_err();
+        assert!(retry <= WINDOW);
+
+        // Other callers have their own budget.
+        assert!(limiter.check_at("other", start).is_ok());
+
+        // A new window restores the budget.
+        assert!(limiter.check_at("agent", start + WINDOW).is_ok());
+    }
+
+    #[test]
+    fn test_zero_disables_limit() {
+        let limiter = RateLimiter::new(0);
+        for _ in 0..100 {
+            assert!(limiter.check("agent").is_ok());
+        }
+    }
+}
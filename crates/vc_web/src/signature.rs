@@ -0,0 +1,296 @@
+//! HMAC request signing for inbound pushes.
+//!
+//! A signed request carries two headers:
+//! - `X-VC-Timestamp`: unix seconds when the client signed it
+//! - `X-VC-Signature`: `v1=<hex HMAC-SHA256 of "{timestamp}.{body}">`
+//!
+//! [`signature_middleware`] rejects a request with a 401 whose `reason` says
+//! why: `missing_signature`, `bad_signature`, `stale_timestamp` (outside
+//! `[web.signing] max_skew_secs` either way) or `replayed` (a signature
+//! already accepted). Accepted signatures are remembered in a small
+//! in-memory LRU; on a miss the store's `request_signatures_seen` table is
+//! consulted, so replays are caught across restarts too.
+
+use crate::auth::unauthorized_response;
+use crate::{AppState, REPLICATION_BODY_LIMIT};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+use vc_store::VcStore;
+
+// The scheme itself lives in `vc_types` so a signing client does not need
+// the server
+pub use vc_types::signing::{SIGNATURE_HEADER, TIMESTAMP_HEADER, sign};
+pub(crate) use vc_types::signing::{constant_time_eq, hmac_sha256};
+
+/// Why a signed request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// No timestamp or signature header
+    MissingSignature,
+    /// Signature does not match the body and timestamp
+    BadSignature,
+    /// Timestamp outside the allowed skew
+    StaleTimestamp,
+    /// Signature already accepted once
+    Replayed,
+}
+
+impl Rejection {
+    /// The `reason` reported in the 401 body
+    #[must_use]
+    pub fn reason(self) -> &'static str {
+        match self {
+            Rejection::MissingSignature => "missing_signature",
+            Rejection::BadSignature => "bad_signature",
+            Rejection::StaleTimestamp => "stale_timestamp",
+            Rejection::Replayed => "replayed",
+        }
+    }
+}
+
+/// Bounded set of recently accepted signatures, oldest evicted first
+#[derive(Debug)]
+struct ReplayCache {
+    capacity: usize,
+    seen: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl ReplayCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn contains(&self, signature: &str) -> bool {
+        self.seen.contains(signature)
+    }
+
+    fn insert(&mut self, signature: &str) {
+        if !self.seen.insert(signature.to_string()) {
+            return;
+        }
+        self.order.push_back(signature.to_string());
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Checks request signatures against a shared secret
+#[derive(Debug)]
+pub struct SignatureVerifier {
+    secret: Vec<u8>,
+    max_skew_secs: i64,
+    cache: Mutex<ReplayCache>,
+}
+
+impl SignatureVerifier {
+    #[must_use]
+    pub fn new(secret: impl Into<Vec<u8>>, max_skew_secs: u64, replay_cache_size: usize) -> Self {
+        Self {
+            secret: secret.into(),
+            max_skew_secs: i64::try_from(max_skew_secs).unwrap_or(i64::MAX),
+            cache: Mutex::new(ReplayCache::new(replay_cache_size)),
+        }
+    }
+
+    /// Verify `body` against the signing headers as of `now` (unix seconds).
+    ///
+    /// Checks run in order: signature, timestamp window, then replay. The
+    /// store, when given, backs the in-memory cache; a store error is logged
+    /// and the request let through rather than refusing every push.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`Rejection`] for the first check that fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the replay cache mutex is poisoned.
+    pub fn verify(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+        now: i64,
+        store: Option<&VcStore>,
+    ) -> Result<(), Rejection> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let (Some(timestamp), Some(signature)) =
+            (header(TIMESTAMP_HEADER), header(SIGNATURE_HEADER))
+        else {
+            return Err(Rejection::MissingSignature);
+        };
+        let timestamp: i64 = timestamp
+            .trim()
+            .parse()
+            .map_err(|_| Rejection::BadSignature)?;
+        let signature = signature.trim();
+
+        let expected = sign(&self.secret, timestamp, body);
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err(Rejection::BadSignature);
+        }
+
+        if now.abs_diff(timestamp) > self.max_skew_secs.unsigned_abs() {
+            return Err(Rejection::StaleTimestamp);
+        }
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.contains(signature) {
+            return Err(Rejection::Replayed);
+        }
+        if let Some(store) = store {
+            match store.record_request_signature(
+                signature,
+                timestamp,
+                now.saturating_sub(self.max_skew_secs),
+            ) {
+                Ok(true) => {}
+                Ok(false) => {
+                    cache.insert(signature);
+                    return Err(Rejection::Replayed);
+                }
+                Err(err) => warn!(error = %err, "failed to record request signature"),
+            }
+        }
+        cache.insert(signature);
+        Ok(())
+    }
+}
+
+/// Axum middleware verifying signed requests when `[web.signing]` is on
+pub async fn signature_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(verifier) = state.signature_verifier.clone() else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, REPLICATION_BODY_LIMIT).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let now = chrono::Utc::now().timestamp();
+    if let Err(rejection) = verifier.verify(&parts.headers, &bytes, now, Some(&state.store)) {
+        warn!(
+            path = %parts.uri.path(),
+            reason = rejection.reason(),
+            "rejected signed request"
+        );
+        return unauthorized_response(rejection.reason());
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes)))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const SECRET: &[u8] = b"shared-secret";
+    const NOW: i64 = 1_760_000_000;
+
+    fn signed(timestamp: i64, body: &[u8]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            TIMESTAMP_HEADER,
+            HeaderValue::from_str(&timestamp.to_string()).unwrap(),
+        );
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&sign(SECRET, timestamp, body)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_valid_and_bad_signatures() {
+        let verifier = SignatureVerifier::new(SECRET, 300, 16);
+        let body = br#"{"rows":[]}"#;
+
+        assert_eq!(verifier.verify(&signed(NOW, body), body, NOW, None), Ok(()));
+        assert_eq!(
+            verifier.verify(&signed(NOW + 1, body), b"tampered", NOW, None),
+            Err(Rejection::BadSignature)
+        );
+        assert_eq!(
+            SignatureVerifier::new(b"other".to_vec(), 300, 16).verify(
+                &signed(NOW, body),
+                body,
+                NOW,
+                None
+            ),
+            Err(Rejection::BadSignature)
+        );
+        assert_eq!(
+            verifier.verify(&HeaderMap::new(), body, NOW, None),
+            Err(Rejection::MissingSignature)
+        );
+    }
+
+    #[test]
+    fn test_clock_skew_within_and_beyond_window() {
+        let verifier = SignatureVerifier::new(SECRET, 300, 16);
+        let body = b"{}";
+
+        // A client running ahead or behind is fine up to the window's edge.
+        for skew in [-300, -120, 120, 300] {
+            let ts = NOW + skew;
+            assert_eq!(verifier.verify(&signed(ts, body), body, NOW, None), Ok(()));
+        }
+        for skew in [-301, -3600, 301, 3600] {
+            let ts = NOW + skew;
+            assert_eq!(
+                verifier.verify(&signed(ts, body), body, NOW, None),
+                Err(Rejection::StaleTimestamp),
+                "skew {skew}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_replays_rejected_from_memory_and_store() {
+        let store = VcStore::open_memory().unwrap();
+        let body = b"{}";
+        let headers = signed(NOW, body);
+
+        let verifier = SignatureVerifier::new(SECRET, 300, 1);
+        assert_eq!(verifier.verify(&headers, body, NOW, Some(&store)), Ok(()));
+        assert_eq!(
+            verifier.verify(&headers, body, NOW + 5, Some(&store)),
+            Err(Rejection::Replayed)
+        );
+
+        // Evicted from the one-entry cache, still caught by the store.
+        let other = signed(NOW + 1, body);
+        assert_eq!(verifier.verify(&other, body, NOW, Some(&store)), Ok(()));
+        assert_eq!(
+            verifier.verify(&headers, body, NOW, Some(&store)),
+            Err(Rejection::Replayed)
+        );
+
+        // A restarted server has an empty cache but the same store.
+        let restarted = SignatureVerifier::new(SECRET, 300, 16);
+        assert_eq!(
+            restarted.verify(&other, body, NOW, Some(&store)),
+            Err(Rejection::Replayed)
+        );
+    }
+}
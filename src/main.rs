@@ -64,6 +64,14 @@ fn main() -> Result<()> {
         anyhow::bail!("CLI execution was cancelled before completion");
     };
     tracing::debug!("CLI future completed inside runtime bridge");
+    // Errors with their own exit code (a report produced with failed
+    // sections) must not collapse into anyhow's generic 1
+    if let Err(err) = &cli_result
+        && err.exit_code() != 1
+    {
+        eprintln!("Error: {err}");
+        std::process::exit(err.exit_code());
+    }
     cli_result?;
     tracing::info!("CLI execution completed successfully");
 
@@ -47,7 +47,7 @@ run_vc_or_skip robot triage 2>&1 || true
 triage_output="$VC_LAST_OUTPUT"
 set -x
 assert_json_valid "$triage_output" "Triage output should be valid JSON"
-assert_json_field "$triage_output" ".schema_version" "vc.robot.triage.v1" "Schema version should match"
+assert_json_field "$triage_output" ".schema_version" "vc.robot.triage.v4" "Schema version should match"
 
 # Test 5: Verify config parsing with test config
 test_info "Test 5: Checking config parsing"
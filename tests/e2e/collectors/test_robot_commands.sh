@@ -64,7 +64,7 @@ assert_json_valid "$triage_output" "Triage output should be valid JSON"
 
 # Test 6: Triage JSON has required schema fields
 test_info "Test 6: Validating triage schema"
-assert_json_field "$triage_output" ".schema_version" "vc.robot.triage.v1" "Schema version should match"
+assert_json_field "$triage_output" ".schema_version" "vc.robot.triage.v4" "Schema version should match"
 
 # Test 7: Triage JSON has data section with expected structure
 test_info "Test 7: Checking triage data structure"
@@ -0,0 +1,62 @@
+//! The minimal `vc` (`--no-default-features`) keeps the web server, MCP
+//! server and TUI out of its dependency graph.
+
+use std::collections::BTreeSet;
+use std::process::Command;
+
+/// Crates the optional features exist to leave out
+const HEAVY_CRATES: [&str; 7] = [
+    "axum",
+    "tower-http",
+    "ftui",
+    "vc_web",
+    "vc_mcp",
+    "vc_tui",
+    "vc_guardian",
+];
+
+/// Names of the packages `vc` depends on (normal edges only) with `args`
+fn dependency_names(args: &[&str]) -> BTreeSet<String> {
+    let output = Command::new(env!("CARGO"))
+        .args(["tree", "-p", "vc", "-e", "normal", "--prefix", "none"])
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("run cargo tree");
+    assert!(
+        output.status.success(),
+        "cargo tree failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn test_minimal_build_leaves_out_web_mcp_and_tui() {
+    let minimal = dependency_names(&["--no-default-features"]);
+    assert!(minimal.contains("vc_cli"), "{minimal:?}");
+    assert!(minimal.contains("vc_query"), "{minimal:?}");
+    for name in HEAVY_CRATES {
+        assert!(
+            !minimal.contains(name),
+            "{name} is in the minimal dependency graph"
+        );
+    }
+
+    // One feature brings back only its own part
+    let web = dependency_names(&["--no-default-features", "--features", "web"]);
+    assert!(web.contains("axum") && web.contains("vc_web"), "{web:?}");
+    assert!(!web.contains("ftui") && !web.contains("vc_mcp"), "{web:?}");
+}
+
+#[test]
+fn test_default_build_has_every_part() {
+    let full = dependency_names(&[]);
+    for name in HEAVY_CRATES {
+        assert!(full.contains(name), "{name} missing from the default build");
+    }
+}
@@ -190,17 +190,17 @@ test_robot_triage_output() {
         local schema_version
         schema_version=$(echo "$output" | jq -r '.schema_version')
 
-        if [[ "$schema_version" != "vc.robot.triage.v1" ]]; then
+        if [[ "$schema_version" != "vc.robot.triage.v4" ]]; then
             fail "Robot triage has wrong schema_version: $schema_version"
             return
         fi
 
         # Check data structure
-        local has_recommendations has_suggested_commands
-        has_recommendations=$(echo "$output" | jq '.data | has("recommendations")')
-        has_suggested_commands=$(echo "$output" | jq '.data | has("suggested_commands")')
+        local has_actions has_truncated
+        has_actions=$(echo "$output" | jq '.data | has("actions")')
+        has_truncated=$(echo "$output" | jq '.data | has("truncated")')
 
-        if [[ "$has_recommendations" != "true" ]] || [[ "$has_suggested_commands" != "true" ]]; then
+        if [[ "$has_actions" != "true" ]] || [[ "$has_truncated" != "true" ]]; then
             fail "Robot triage data missing required fields"
             return
         fi
@@ -0,0 +1,103 @@
+//! Construction paths for code that embeds the vc crates.
+//!
+//! Integration tests compile as their own crate, so `#[non_exhaustive]`
+//! applies here just as it does downstream. Everything below goes through
+//! constructors, builders, `Default` and `TryFrom`, never struct literals:
+//! adding a field upstream must keep this file compiling unchanged.
+
+use serde_json::json;
+use vc_cli::watch::{WatchEvent, WatchEventType, WatchFilter, WatchSeverity};
+use vc_collect::machine::{Machine, MachineStatus};
+use vc_knowledge::SearchOptions;
+use vc_query::{FleetOverview, HealthScore};
+use vc_store::{CollectorHealth, MachineServiceStatus};
+
+#[test]
+fn test_machine_builder_and_row_conversion() {
+    let machine = Machine::builder("m1", "orko.local")
+        .with_ssh_host("orko.local")
+        .with_ssh_user("ubuntu")
+        .with_tags(vec!["gpu".to_string()])
+        .build();
+    assert_eq!(machine.machine_id, "m1");
+    assert_eq!(machine.ssh_port, 22);
+    assert!(machine.enabled);
+    assert_eq!(machine.status, MachineStatus::Unknown);
+    assert!(machine.ssh_config().is_some());
+
+    let row = json!({
+        "machine_id": "m2",
+        "hostname": "sydney",
+        "is_local": 1,
+        "enabled": 0,
+        "status": "online",
+        "tags": "[\"gpu\", \"prod\"]",
+    });
+    let machine = Machine::try_from(row).unwrap();
+    assert!(machine.is_local);
+    assert!(!machine.enabled);
+    assert_eq!(machine.tags, vec!["gpu", "prod"]);
+}
+
+#[test]
+fn test_query_types_construct_without_literals() {
+    let score = HealthScore::new("m1", 0.4).with_worst_factor("sys_disk");
+    assert_eq!(score.worst_factor.as_deref(), Some("sys_disk"));
+    assert!(score.factors.is_empty());
+
+    let mut overview = FleetOverview::default();
+    overview.total_machines = 3;
+    overview.online_machines = 3;
+    assert!((overview.fleet_health_score - 1.0).abs() < f64::EPSILON);
+    assert!(overview.upstream_headline().is_none());
+}
+
+#[test]
+fn test_watch_filter_and_search_options_builders() {
+    let filter = WatchFilter::builder()
+        .with_event_types([WatchEventType::Alert])
+        .with_machines(["m1"])
+        .with_min_severity(WatchSeverity::High)
+        .build();
+    assert!(filter.matches(&WatchEvent::alert(
+        "m1",
+        WatchSeverity::Critical,
+        "a1",
+        "disk"
+    )));
+    assert!(!filter.matches(&WatchEvent::alert(
+        "m2",
+        WatchSeverity::Critical,
+        "a2",
+        "disk"
+    )));
+
+    let options = SearchOptions::new().with_min_score(0.5).with_limit(5);
+    assert_eq!(options.min_score, Some(0.5));
+    assert_eq!(options.limit, 5);
+}
+
+#[test]
+fn test_store_rows_convert_from_query_json() {
+    let health = CollectorHealth::try_from(json!({
+        "machine_id": "m1",
+        "collector": "sysmoni",
+        "collected_at": "2026-10-16T00:00:00Z",
+        "success": 1,
+        "duration_ms": 120,
+        "rows_inserted": 4,
+        "bytes_parsed": 512,
+        "error_class": null,
+        "error_cause": null,
+        "freshness_seconds": 30,
+        "payload_hash": null,
+        "collector_version": null,
+        "schema_version": null,
+        "cursor_json": null,
+    }))
+    .unwrap();
+    assert!(health.success);
+
+    let missing_columns = json!({ "machine_id": "m1", "service_name": "nginx" });
+    assert!(MachineServiceStatus::try_from(missing_columns).is_err());
+}
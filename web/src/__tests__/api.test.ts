@@ -1,4 +1,4 @@
-import { api } from "@/lib/api";
+import { api, loginUrl } from "@/lib/api";
 
 // Mock global fetch
 const mockFetch = jest.fn();
@@ -84,4 +84,85 @@ describe("api client", () => {
     expect(result.alerts).toHaveLength(1);
     expect(result.alerts[0].severity).toBe("critical");
   });
+
+  it("fetches the burndown with unwatched days as null", async () => {
+    mockFetch.mockResolvedValueOnce({
+      ok: true,
+      json: () =>
+        Promise.resolve({
+          window_days: 2,
+          generated_at: "2026-10-16T00:00:00Z",
+          days: [
+            {
+              day: "2026-10-15",
+              observed: false,
+              alerts_fired: null,
+              alerts_resolved: null,
+              incidents_opened: null,
+              incidents_closed: null,
+            },
+            {
+              day: "2026-10-16",
+              observed: true,
+              alerts_fired: { critical: 1, warning: 0, info: 2 },
+              alerts_resolved: { critical: 0, warning: 0, info: 0 },
+              incidents_opened: 0,
+              incidents_closed: 0,
+            },
+          ],
+        }),
+    });
+    const result = await api.burndown(2);
+    expect(mockFetch).toHaveBeenCalledWith("/api/burndown?days=2");
+    expect(result.days[0].alerts_fired).toBeNull();
+    expect(result.days[1].alerts_fired?.info).toBe(2);
+  });
+
+  it("fetches annotations filtered by kind", async () => {
+    mockFetch.mockResolvedValueOnce({
+      ok: true,
+      json: () =>
+        Promise.resolve([
+          {
+            id: 1,
+            kind: "deploy",
+            scope: "machine:orko",
+            ts: "2026-10-16T12:00:00.000000Z",
+            message: "release 1.42",
+            source: "ci",
+            created_by: "ci-bot",
+            created_at: "2026-10-16T12:00:01.000000Z",
+          },
+        ]),
+    });
+    const result = await api.annotations("14d", ["deploy", "maintenance"]);
+    expect(mockFetch).toHaveBeenCalledWith(
+      "/api/annotations?since=14d&kind=deploy,maintenance"
+    );
+    expect(result[0].scope).toBe("machine:orko");
+  });
+
+  it("reads the login session and logs out", async () => {
+    mockFetch.mockResolvedValueOnce({
+      ok: true,
+      json: () =>
+        Promise.resolve({
+          oidc: true,
+          session: {
+            subject: "00u1a2b3c4",
+            display_name: "alice@example.com",
+            role: "operator",
+            expires_at: "2026-10-16T20:00:00Z",
+          },
+        }),
+    });
+    const auth = await api.session();
+    expect(mockFetch).toHaveBeenCalledWith("/auth/session");
+    expect(auth.session?.role).toBe("operator");
+
+    mockFetch.mockResolvedValueOnce({ ok: true, status: 204 });
+    await api.logout();
+    expect(mockFetch).toHaveBeenCalledWith("/auth/logout", { method: "POST" });
+    expect(loginUrl("/alerts")).toBe("/auth/login?return_to=%2Falerts");
+  });
 });
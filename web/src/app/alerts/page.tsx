@@ -1,11 +1,14 @@
 "use client";
 
 import { useEffect, useState } from "react";
+import { BurndownChart } from "@/components/BurndownChart";
 import { StatusBadge } from "@/components/StatusBadge";
-import { api, Alert } from "@/lib/api";
+import { api, Alert, Annotation, Burndown } from "@/lib/api";
 
 export default function AlertsPage() {
   const [alerts, setAlerts] = useState<Alert[]>([]);
+  const [burndown, setBurndown] = useState<Burndown | null>(null);
+  const [annotations, setAnnotations] = useState<Annotation[]>([]);
   const [error, setError] = useState<string | null>(null);
 
   useEffect(() => {
@@ -13,12 +16,23 @@ export default function AlertsPage() {
       .alerts()
       .then((d) => setAlerts(d.alerts))
       .catch((e) => setError(e.message));
+    api
+      .burndown()
+      .then(setBurndown)
+      .catch(() => setBurndown(null));
+    api
+      .annotations("14d")
+      .then(setAnnotations)
+      .catch(() => setAnnotations([]));
   }, []);
 
   return (
     <div>
       <h1>Alerts</h1>
       {error && <p style={{ color: "#ef4444" }}>{error}</p>}
+      {burndown && (
+        <BurndownChart burndown={burndown} annotations={annotations} />
+      )}
       <table
         style={{
           width: "100%",
@@ -1,28 +1,123 @@
 "use client";
 
-import { useEffect, useState } from "react";
+import { useCallback, useEffect, useState } from "react";
 import { StatusBadge } from "@/components/StatusBadge";
-import { api, GuardianPlaybook, GuardianRun } from "@/lib/api";
+import {
+  api,
+  GuardianPlaybook,
+  GuardianRun,
+  PendingApprovals,
+} from "@/lib/api";
+
+function formatAge(secs: number | null): string {
+  if (secs === null) return "-";
+  if (secs < 3600) return `${Math.floor(secs / 60)}m`;
+  if (secs < 86400) return `${Math.floor(secs / 3600)}h`;
+  return `${Math.floor(secs / 86400)}d`;
+}
+
+const buttonStyle = {
+  padding: "2px 8px",
+  marginRight: "6px",
+  fontSize: "12px",
+  border: "1px solid #374151",
+  borderRadius: "4px",
+  background: "#1f2937",
+  color: "#e5e7eb",
+  cursor: "pointer",
+};
 
 export default function GuardianPage() {
   const [playbooks, setPlaybooks] = useState<GuardianPlaybook[]>([]);
   const [runs, setRuns] = useState<GuardianRun[]>([]);
+  const [pending, setPending] = useState<PendingApprovals | null>(null);
   const [error, setError] = useState<string | null>(null);
 
-  useEffect(() => {
-    Promise.all([api.guardianPlaybooks(), api.guardianRuns()])
-      .then(([pb, r]) => {
+  const load = useCallback(() => {
+    Promise.all([
+      api.guardianPlaybooks(),
+      api.guardianRuns(),
+      api.guardianPending(),
+    ])
+      .then(([pb, r, p]) => {
         setPlaybooks(pb.playbooks);
         setRuns(r.runs);
+        setPending(p);
       })
       .catch((e) => setError(e.message));
   }, []);
 
+  useEffect(load, [load]);
+
+  // Conflicts (already approved, rejected or revised elsewhere) surface the
+  // server's message and reload so the list shows the current state.
+  const act = (action: Promise<unknown>) => {
+    setError(null);
+    action.catch((e) => setError(e.message)).finally(load);
+  };
+
   return (
     <div>
       <h1>Guardian</h1>
       {error && <p style={{ color: "#ef4444" }}>{error}</p>}
 
+      <h2 style={{ fontSize: "16px", marginTop: "24px" }}>
+        Pending Approvals ({pending?.total ?? 0})
+      </h2>
+      <table
+        style={{ width: "100%", borderCollapse: "collapse", fontSize: "13px" }}
+      >
+        <thead>
+          <tr style={{ borderBottom: "1px solid #374151", textAlign: "left" }}>
+            <th style={{ padding: "8px" }}>Kind</th>
+            <th style={{ padding: "8px" }}>ID</th>
+            <th style={{ padding: "8px" }}>Playbook</th>
+            <th style={{ padding: "8px" }}>Age</th>
+            <th style={{ padding: "8px" }}></th>
+          </tr>
+        </thead>
+        <tbody>
+          {pending?.pending.map((r) => (
+            <tr key={`run-${r.id}`} style={{ borderBottom: "1px solid #1f2937" }}>
+              <td style={{ padding: "8px" }}>run</td>
+              <td style={{ padding: "8px", fontWeight: 600 }}>{r.id}</td>
+              <td style={{ padding: "8px" }}>{r.playbook_id}</td>
+              <td style={{ padding: "8px", color: "#9ca3af" }}>
+                {formatAge(r.age_secs)}
+              </td>
+              <td style={{ padding: "8px" }}>
+                <button style={buttonStyle} onClick={() => act(api.approveRun(r.id))}>
+                  Approve
+                </button>
+              </td>
+            </tr>
+          ))}
+          {pending?.drafts.map((d) => (
+            <tr key={`draft-${d.draft_id}`} style={{ borderBottom: "1px solid #1f2937" }}>
+              <td style={{ padding: "8px" }}>draft</td>
+              <td style={{ padding: "8px", fontWeight: 600 }}>{d.draft_id}</td>
+              <td style={{ padding: "8px" }}>
+                {d.name} (rev {d.current_revision})
+              </td>
+              <td style={{ padding: "8px", color: "#9ca3af" }}>
+                {formatAge(d.age_secs)}
+              </td>
+              <td style={{ padding: "8px" }}>
+                <button
+                  style={buttonStyle}
+                  onClick={() => act(api.approveDraft(d.draft_id, d.current_revision))}
+                >
+                  Approve
+                </button>
+                <button style={buttonStyle} onClick={() => act(api.rejectDraft(d.draft_id))}>
+                  Reject
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+
       <h2 style={{ fontSize: "16px", marginTop: "24px" }}>Playbooks</h2>
       <table
         style={{ width: "100%", borderCollapse: "collapse", fontSize: "13px" }}
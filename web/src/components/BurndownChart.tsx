@@ -0,0 +1,119 @@
+"use client";
+
+import { Annotation, Burndown, SeverityCounts } from "@/lib/api";
+
+interface BurndownChartProps {
+  burndown: Burndown;
+  /** Marked under the day they fall on */
+  annotations?: Annotation[];
+}
+
+const BAR_HEIGHT = 80;
+
+function total(counts: SeverityCounts | null): number {
+  return counts ? counts.critical + counts.warning + counts.info : 0;
+}
+
+/**
+ * Fired and resolved alerts per day; days nothing was watching are hatched,
+ * and days with annotations (deploys, maintenance...) get a marker below
+ */
+export function BurndownChart({
+  burndown,
+  annotations = [],
+}: BurndownChartProps) {
+  const { days, totals } = burndown;
+  const peak = Math.max(
+    1,
+    ...days.map((d) => Math.max(total(d.alerts_fired), total(d.alerts_resolved)))
+  );
+  const height = (n: number) => `${Math.round((n / peak) * BAR_HEIGHT)}px`;
+  const net = total(totals.alerts_fired) - total(totals.alerts_resolved);
+
+  return (
+    <div style={{ marginBottom: "24px" }}>
+      <div style={{ fontSize: "13px", color: "#9ca3af", marginBottom: "8px" }}>
+        Last {burndown.window_days} days: {total(totals.alerts_fired)} fired,{" "}
+        {total(totals.alerts_resolved)} resolved (net {net > 0 ? "+" : ""}
+        {net}); incidents {totals.incidents_opened} opened,{" "}
+        {totals.incidents_closed} closed
+      </div>
+      <div
+        style={{
+          display: "flex",
+          alignItems: "flex-end",
+          gap: "4px",
+          height: `${BAR_HEIGHT}px`,
+        }}
+      >
+        {days.map((d) =>
+          d.observed ? (
+            <div
+              key={d.day}
+              title={`${d.day}: ${total(d.alerts_fired)} fired (${d.alerts_fired?.critical ?? 0} critical), ${total(d.alerts_resolved)} resolved`}
+              style={{ display: "flex", alignItems: "flex-end", gap: "1px", flex: 1 }}
+            >
+              <div
+                style={{
+                  flex: 1,
+                  height: height(total(d.alerts_fired)),
+                  background: "#ea580c",
+                }}
+              />
+              <div
+                style={{
+                  flex: 1,
+                  height: height(total(d.alerts_resolved)),
+                  background: "#16a34a",
+                }}
+              />
+            </div>
+          ) : (
+            <div
+              key={d.day}
+              title={`${d.day}: no data`}
+              style={{
+                flex: 1,
+                height: "100%",
+                background:
+                  "repeating-linear-gradient(45deg, #1f2937 0 4px, transparent 4px 8px)",
+              }}
+            />
+          )
+        )}
+      </div>
+      {annotations.length > 0 && (
+        <div style={{ display: "flex", gap: "4px", marginTop: "2px" }}>
+          {days.map((d) => {
+            const marked = annotations.filter((a) => a.ts.startsWith(d.day));
+            return (
+              <div
+                key={d.day}
+                title={marked
+                  .map(
+                    (a) =>
+                      `${a.ts.slice(11, 16)} ${a.kind} on ${a.scope}: ${a.message}`
+                  )
+                  .join("\n")}
+                style={{
+                  flex: 1,
+                  textAlign: "center",
+                  fontSize: "10px",
+                  color: "#60a5fa",
+                }}
+              >
+                {marked.length > 0 ? "▲" : ""}
+              </div>
+            );
+          })}
+        </div>
+      )}
+      <div style={{ fontSize: "11px", color: "#6b7280", marginTop: "4px" }}>
+        <span style={{ color: "#ea580c" }}>■</span> fired{" "}
+        <span style={{ color: "#16a34a" }}>■</span> resolved ·{" "}
+        <span style={{ color: "#60a5fa" }}>▲</span> annotated · hatched days
+        had nothing watching
+      </div>
+    </div>
+  );
+}
@@ -2,6 +2,8 @@
 
 import Link from "next/link";
 import { usePathname } from "next/navigation";
+import { useEffect, useState } from "react";
+import { api, AuthSession, loginUrl } from "@/lib/api";
 
 const NAV_ITEMS = [
   { href: "/", label: "Overview" },
@@ -16,6 +18,21 @@ const NAV_ITEMS = [
 
 export function NavBar() {
   const pathname = usePathname();
+  const [auth, setAuth] = useState<AuthSession | null>(null);
+
+  useEffect(() => {
+    api
+      .session()
+      .then(setAuth)
+      .catch(() => setAuth(null));
+  }, []);
+
+  const logout = () =>
+    api
+      .logout()
+      .then(() => setAuth({ oidc: true, session: null }))
+      .catch(() => undefined);
+
   return (
     <nav
       style={{
@@ -57,6 +74,36 @@ export function NavBar() {
           </Link>
         );
       })}
+      {auth?.oidc && (
+        <span
+          style={{ marginLeft: "auto", fontSize: "13px", lineHeight: "32px" }}
+        >
+          {auth.session ? (
+            <>
+              <span style={{ color: "#9ca3af" }}>
+                {auth.session.display_name ?? auth.session.subject} (
+                {auth.session.role})
+              </span>{" "}
+              <button
+                onClick={logout}
+                style={{
+                  background: "none",
+                  border: "none",
+                  color: "#60a5fa",
+                  cursor: "pointer",
+                  font: "inherit",
+                }}
+              >
+                Sign out
+              </button>
+            </>
+          ) : (
+            <a href={loginUrl(pathname)} style={{ color: "#60a5fa" }}>
+              Sign in
+            </a>
+          )}
+        </span>
+      )}
     </nav>
   );
 }
@@ -20,10 +20,25 @@ export interface Machine {
   enabled?: boolean;
 }
 
+export type HealthTrend = "improving" | "stable" | "degrading";
+
+export interface HealthFactor {
+  factor_id: string;
+  name: string;
+  score: number;
+  weight: number;
+  severity: string;
+  details: string;
+  /** Trailing 7-day average; null when there is not enough history */
+  baseline_score: number | null;
+  trend: HealthTrend | null;
+}
+
 export interface HealthScore {
   machine_id: string;
-  score: number;
-  factors: Record<string, number>;
+  overall_score: number;
+  factors: HealthFactor[];
+  worst_factor: string | null;
 }
 
 export interface Alert {
@@ -35,6 +50,25 @@ export interface Alert {
   created_at: string;
 }
 
+/** Filter for bulk alert operations; omitted fields match everything */
+export interface AlertFilter {
+  ids?: number[];
+  machine?: string;
+  /** Substring of the rule ID, e.g. "offline" */
+  type?: string;
+  severity?: string;
+  /** RFC 3339; only alerts fired before this */
+  before?: string;
+}
+
+export interface BulkAckResult {
+  dry_run: boolean;
+  filter: AlertFilter;
+  matched: number;
+  acknowledged?: number;
+  sample?: Record<string, unknown>[];
+}
+
 export interface GuardianPlaybook {
   id: string;
   name: string;
@@ -50,6 +84,82 @@ export interface GuardianRun {
   completed_at?: string;
 }
 
+export interface PendingRun extends GuardianRun {
+  age_secs: number | null;
+}
+
+export interface PendingDraft {
+  draft_id: string;
+  name: string;
+  alert_type: string;
+  confidence: number;
+  current_revision: number;
+  created_at: string;
+  age_secs: number | null;
+}
+
+export interface PendingApprovals {
+  pending: PendingRun[];
+  drafts: PendingDraft[];
+  total: number;
+  total_runs: number;
+  total_drafts: number;
+}
+
+export interface SeverityCounts {
+  critical: number;
+  warning: number;
+  info: number;
+}
+
+/** One UTC day; counts are null when nothing was watching that day */
+export interface BurndownDay {
+  day: string;
+  observed: boolean;
+  alerts_fired: SeverityCounts | null;
+  alerts_resolved: SeverityCounts | null;
+  incidents_opened: number | null;
+  incidents_closed: number | null;
+}
+
+export interface Burndown {
+  window_days: number;
+  generated_at: string;
+  days: BurndownDay[];
+  totals: {
+    alerts_fired: SeverityCounts;
+    alerts_resolved: SeverityCounts;
+    incidents_opened: number;
+    incidents_closed: number;
+    days_observed: number;
+    days_unobserved: number;
+  };
+}
+
+/** A deploy, maintenance window, config change or other marked event */
+export interface Annotation {
+  id: number;
+  kind: string;
+  /** `fleet`, `machine:<id>` or `tag:<tag>` */
+  scope: string;
+  ts: string;
+  message: string;
+  source: string;
+  created_by: string | null;
+  created_at: string;
+}
+
+/** `GET /auth/session`: whether OIDC login is on and who is logged in */
+export interface AuthSession {
+  oidc: boolean;
+  session: {
+    subject: string;
+    display_name: string | null;
+    role: "read" | "operator" | "admin";
+    expires_at: string;
+  } | null;
+}
+
 export interface HealthResponse {
   status: string;
   version: string;
@@ -64,8 +174,35 @@ async function fetchJson<T>(path: string): Promise<T> {
   return res.json();
 }
 
+async function postJson<T>(path: string, body: unknown = {}): Promise<T> {
+  const res = await fetch(`${API_BASE}${path}`, {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: JSON.stringify(body),
+  });
+  if (!res.ok) {
+    const detail = await res.json().catch(() => null);
+    throw new Error(
+      detail?.message ?? `API error: ${res.status} ${res.statusText}`
+    );
+  }
+  return res.json();
+}
+
+/** Where the browser goes to log in through the OIDC provider */
+export function loginUrl(returnTo = "/"): string {
+  return `${API_BASE}/auth/login?return_to=${encodeURIComponent(returnTo)}`;
+}
+
 export const api = {
   health: () => fetchJson<HealthResponse>("/api/health"),
+  session: () => fetchJson<AuthSession>("/auth/session"),
+  logout: async () => {
+    const res = await fetch(`${API_BASE}/auth/logout`, { method: "POST" });
+    if (!res.ok) {
+      throw new Error(`API error: ${res.status} ${res.statusText}`);
+    }
+  },
   overview: () => fetchJson<FleetOverview>("/api/overview"),
   fleet: () => fetchJson<FleetOverview>("/api/fleet"),
   machines: (limit = 50, offset = 0) =>
@@ -77,8 +214,25 @@ export const api = {
     fetchJson<HealthScore>(`/api/machines/${id}/health`),
   alerts: (limit = 50) =>
     fetchJson<{ alerts: Alert[]; limit: number }>(`/api/alerts?limit=${limit}`),
+  burndown: (days = 14) =>
+    fetchJson<Burndown>(`/api/burndown?days=${days}`),
+  annotations: (since = "7d", kinds: string[] = []) =>
+    fetchJson<Annotation[]>(
+      `/api/annotations?since=${since}` +
+        (kinds.length ? `&kind=${kinds.join(",")}` : "")
+    ),
+  bulkAckAlerts: (filter: AlertFilter & { all?: boolean; dry_run?: boolean }) =>
+    postJson<BulkAckResult>("/api/alerts/bulk-ack", filter),
   guardianPlaybooks: () =>
     fetchJson<{ playbooks: GuardianPlaybook[] }>("/api/guardian/playbooks"),
   guardianRuns: () =>
     fetchJson<{ runs: GuardianRun[] }>("/api/guardian/runs"),
+  guardianPending: () =>
+    fetchJson<PendingApprovals>("/api/guardian/pending"),
+  approveRun: (id: string) =>
+    postJson<{ run: GuardianRun }>(`/api/guardian/runs/${id}/approve`),
+  approveDraft: (id: string, revision: number) =>
+    postJson(`/api/guardian/drafts/${id}/approve`, { revision }),
+  rejectDraft: (id: string, reason?: string) =>
+    postJson(`/api/guardian/drafts/${id}/reject`, { reason }),
 };